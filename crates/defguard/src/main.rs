@@ -8,18 +8,21 @@ use defguard_common::{
     VERSION,
     config::{Command, DefGuardConfig, SERVER_CONFIG},
     db::{
-        init_db,
+        connect_pool, db_connect_options, init_db,
         models::{Settings, settings::initialize_current_settings},
+        register_pool, with_statement_limits,
     },
 };
 use defguard_core::{
+    announcements::run_announcement_scheduler,
     auth::failed_login::FailedLoginMap,
-    db::{AppEvent, GatewayEvent, User},
+    db::{AppEvent, GatewayEvent, User, models::background_job::BackgroundJob},
     enterprise::{
         activity_log_stream::activity_log_stream_manager::run_activity_log_stream_manager,
         license::{License, run_periodic_license_check, set_cached_license},
         limits::update_counts,
     },
+    event_outbox::run_event_outbox_publisher,
     events::{ApiEvent, BidiStreamEvent, GrpcEvent, InternalEvent},
     gateway_config,
     grpc::{
@@ -27,15 +30,27 @@ use defguard_core::{
         gateway::{client_state::ClientMap, map::GatewayMap},
         run_grpc_bidi_stream, run_grpc_server,
     },
-    init_dev_env, init_vpn_location, run_web_server,
+    init_dev_env, init_reporting_role, init_vpn_location,
+    instance_export::{run_export, run_import},
+    ip_allowlist::run_ip_allowlist_publisher,
+    itsm::run_itsm_connectors,
+    load_test::run_load_test,
+    migration_preflight::check_migrations,
+    notification_digest::run_notification_digest,
+    notifications::run_smtp_failover_notifier,
+    run_web_server,
+    security_summary::run_security_summary_mailer,
     utility_thread::run_utility_thread,
     version::IncompatibleComponents,
     wireguard_peer_disconnect::run_periodic_peer_disconnect,
+    wireguard_session_reconciliation::run_periodic_session_reconciliation,
+    wireguard_stats_maintenance::run_stats_maintenance,
+    wireguard_stats_partitions::run_stats_partition_manager,
     wireguard_stats_purge::run_periodic_stats_purge,
 };
 use defguard_event_logger::{message::EventLoggerMessage, run_event_logger};
 use defguard_event_router::{RouterReceiverSet, run_event_router};
-use defguard_mail::{Mail, run_mail_handler};
+use defguard_mail::{Mail, failover::FailoverEvent, run_mail_handler};
 use secrecy::ExposeSecret;
 use tokio::sync::{broadcast, mpsc::unbounded_channel};
 
@@ -61,12 +76,38 @@ async fn main() -> Result<(), anyhow::Error> {
     info!("Starting ... version v{VERSION}");
     debug!("Using config: {config:?}");
 
+    // check migrations before they are applied by `init_db`
+    let check_only = matches!(config.cmd, Some(Command::CheckMigrations));
+    if check_only || config.migration_preflight {
+        let report = check_migrations(&db_connect_options(
+            &config.database_host,
+            config.database_port,
+            &config.database_name,
+            &config.database_user,
+            config.database_password.expose_secret(),
+        ))
+        .await?;
+        if check_only {
+            print!("{report}");
+            if report.is_ok() {
+                return Ok(());
+            }
+            anyhow::bail!("Database migration check failed");
+        }
+        if !report.is_ok() {
+            error!("Database migration preflight check failed:\n{report}");
+            anyhow::bail!("Database migration preflight check failed");
+        }
+        info!("Database migration preflight check passed");
+    }
+
     let pool = init_db(
         &config.database_host,
         config.database_port,
         &config.database_name,
         &config.database_user,
         config.database_password.expose_secret(),
+        config.database_max_connections,
     )
     .await;
 
@@ -84,12 +125,73 @@ async fn main() -> Result<(), anyhow::Error> {
                 let config = gateway_config(&pool, args).await?;
                 println!("{config:#?}");
             }
+            Command::InitReportingRole(args) => {
+                init_reporting_role(&pool, args).await?;
+                println!("Reporting role {} is ready", args.name);
+            }
+            Command::LoadTest(args) => {
+                run_load_test(&pool, args, &config.grpc_url).await?;
+            }
+            Command::Export(args) => {
+                run_export(&pool, args).await?;
+            }
+            Command::Import(args) => {
+                let report = run_import(&pool, args).await?;
+                print!("{report}");
+                if !report.is_ok() {
+                    anyhow::bail!("Import failed");
+                }
+            }
+            // handled before migrations are applied
+            Command::CheckMigrations => {}
         }
 
         // return early
         return Ok(());
     }
 
+    // separate pools, so that stats bursts and background tasks can't starve API requests;
+    // migrations are done, so replace the initial pool with one limiting statement execution time
+    let connect_options = db_connect_options(
+        &config.database_host,
+        config.database_port,
+        &config.database_name,
+        &config.database_user,
+        config.database_password.expose_secret(),
+    );
+    let slow_statement_threshold = *config.database_slow_statement_threshold;
+    pool.close().await;
+    let pool = connect_pool(
+        with_statement_limits(
+            connect_options.clone(),
+            *config.database_statement_timeout,
+            slow_statement_threshold,
+        ),
+        config.database_max_connections,
+    )
+    .await;
+    let stats_pool = connect_pool(
+        with_statement_limits(
+            connect_options.clone(),
+            *config.database_stats_statement_timeout,
+            slow_statement_threshold,
+        ),
+        config.database_stats_max_connections,
+    )
+    .await;
+    let background_pool = connect_pool(
+        with_statement_limits(
+            connect_options,
+            *config.database_background_statement_timeout,
+            slow_statement_threshold,
+        ),
+        config.database_background_max_connections,
+    )
+    .await;
+    register_pool("api", &pool);
+    register_pool("stats", &stats_pool);
+    register_pool("background", &background_pool);
+
     if config.openid_signing_key.is_some() {
         info!("Using RSA OpenID signing key");
     } else {
@@ -110,6 +212,7 @@ async fn main() -> Result<(), anyhow::Error> {
     let (webhook_tx, webhook_rx) = unbounded_channel::<AppEvent>();
     let (wireguard_tx, _wireguard_rx) = broadcast::channel::<GatewayEvent>(256);
     let (mail_tx, mail_rx) = unbounded_channel::<Mail>();
+    let (failover_tx, failover_rx) = unbounded_channel::<FailoverEvent>();
     let (event_logger_tx, event_logger_rx) = unbounded_channel::<EventLoggerMessage>();
 
     let worker_state = Arc::new(Mutex::new(WorkerState::new(webhook_tx.clone())));
@@ -126,6 +229,12 @@ async fn main() -> Result<(), anyhow::Error> {
     // initialize global settings struct
     initialize_current_settings(&pool).await?;
 
+    // background jobs don't survive restarts
+    let interrupted_jobs = BackgroundJob::fail_interrupted(&pool).await?;
+    if interrupted_jobs > 0 {
+        warn!("Marked {interrupted_jobs} background jobs interrupted by restart as failed");
+    }
+
     // read grpc TLS cert and key
     let grpc_cert = config
         .grpc_cert
@@ -162,6 +271,7 @@ async fn main() -> Result<(), anyhow::Error> {
             pool.clone(),
             wireguard_tx.clone(),
             mail_tx.clone(),
+            webhook_tx.clone(),
             bidi_event_tx,
             Arc::clone(&incompatible_components),
         ), if config.proxy_url.is_some() => error!("Proxy gRPC stream returned early: {res:?}"),
@@ -169,18 +279,19 @@ async fn main() -> Result<(), anyhow::Error> {
             Arc::clone(&worker_state),
             pool.clone(),
             Arc::clone(&gateway_state),
-            client_state,
+            Arc::clone(&client_state),
             wireguard_tx.clone(),
             mail_tx.clone(),
             grpc_cert,
             grpc_key,
             failed_logins.clone(),
-            grpc_event_tx,
+            grpc_event_tx.clone(),
             Arc::clone(&incompatible_components),
+            stats_pool.clone(),
         ) => error!("gRPC server returned early: {res:?}"),
         res = run_web_server(
             worker_state,
-            gateway_state,
+            Arc::clone(&gateway_state),
             webhook_tx,
             webhook_rx,
             wireguard_tx.clone(),
@@ -189,22 +300,49 @@ async fn main() -> Result<(), anyhow::Error> {
             failed_logins,
             api_event_tx,
             incompatible_components,
+            activity_log_messages_tx.clone(),
         ) => error!("Web server returned early: {res:?}"),
-        res = run_mail_handler(mail_rx) => error!("Mail handler returned early: {res:?}"),
+        res = run_mail_handler(mail_rx, pool.clone(), failover_tx) => error!("Mail handler returned early: {res:?}"),
+        res = run_smtp_failover_notifier(background_pool.clone(), mail_tx.clone(), failover_rx) =>
+            error!("SMTP failover notifier returned early: {res:?}"),
+        res = run_notification_digest(mail_tx.clone()) =>
+            error!("Notification digest returned early: {res:?}"),
+        res = run_announcement_scheduler(background_pool.clone(), mail_tx.clone()) =>
+            error!("Announcement scheduler returned early: {res:?}"),
+        res = run_security_summary_mailer(background_pool.clone(), mail_tx.clone()) =>
+            error!("Security summary mailer returned early: {res:?}"),
+        res = run_itsm_connectors(background_pool.clone(), gateway_state) =>
+            error!("ITSM connectors task returned early: {res:?}"),
+        res = run_ip_allowlist_publisher(background_pool.clone(), wireguard_tx.subscribe()),
+            if config.ip_allowlist_push_url.is_some() =>
+            error!("IP allow-list publisher returned early: {res:?}"),
         res = run_periodic_peer_disconnect(
-            pool.clone(),
+            background_pool.clone(),
             wireguard_tx.clone(),
             internal_event_tx.clone()
         ) => error!("Periodic peer disconnect task returned early: {res:?}"),
+        res = run_periodic_session_reconciliation(
+            background_pool.clone(),
+            client_state,
+            grpc_event_tx
+        ) => error!("Periodic session reconciliation task returned early: {res:?}"),
         res = run_periodic_stats_purge(
-            pool.clone(),
+            stats_pool.clone(),
             config.stats_purge_frequency.into(),
             config.stats_purge_threshold.into()
-        ), if !config.disable_stats_purge =>
+        ), if !config.disable_stats_purge && config.stats_maintenance_window.is_none() =>
             error!("Periodic stats purge task returned early: {res:?}"),
-        res = run_periodic_license_check(&pool) =>
+        res = run_stats_partition_manager(stats_pool.clone(), config.clone()) =>
+            error!("Stats partition manager returned early: {res:?}"),
+        res = run_stats_maintenance(stats_pool.clone(), config.clone()),
+            if config.stats_maintenance_window.is_some() =>
+            error!("Stats maintenance task returned early: {res:?}"),
+        res = run_event_outbox_publisher(background_pool.clone(), config.clone()),
+            if config.event_outbox_enabled() =>
+            error!("Domain event publisher returned early: {res:?}"),
+        res = run_periodic_license_check(&background_pool) =>
             error!("Periodic license check task returned early: {res:?}"),
-        res = run_utility_thread(&pool, wireguard_tx.clone()) =>
+        res = run_utility_thread(&background_pool, wireguard_tx.clone()) =>
             error!("Utility thread returned early: {res:?}"),
         res = run_event_router(
             RouterReceiverSet::new(
@@ -218,10 +356,13 @@ async fn main() -> Result<(), anyhow::Error> {
             mail_tx,
             activity_log_stream_reload_notify.clone()
         ) => error!("Event router returned early: {res:?}"),
-        res = run_event_logger(pool.clone(), event_logger_rx, activity_log_messages_tx.clone()) =>
-            error!("Activity log event logger returned early: {res:?}"),
+        res = run_event_logger(
+            background_pool.clone(),
+            event_logger_rx,
+            activity_log_messages_tx.clone()
+        ) => error!("Activity log event logger returned early: {res:?}"),
         res = run_activity_log_stream_manager(
-            pool.clone(),
+            background_pool.clone(),
             activity_log_stream_reload_notify.clone(),
             activity_log_messages_rx
         ) => error!("Activity log stream manager returned early: {res:?}"),
@@ -1,6 +1,7 @@
-use std::{net::IpAddr, sync::OnceLock};
+use std::{fmt, net::IpAddr, path::PathBuf, str::FromStr, sync::OnceLock};
 
-use clap::{Args, Parser, Subcommand};
+use chrono::{NaiveTime, TimeDelta};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use humantime::Duration;
 use ipnetwork::IpNetwork;
 use openidconnect::{JsonWebKeyId, core::CoreRsaPrivateSigningKey};
@@ -12,7 +13,7 @@ use rsa::{
     traits::PublicKeyParts,
 };
 use secrecy::{ExposeSecret, SecretString};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 pub static SERVER_CONFIG: OnceLock<DefGuardConfig> = OnceLock::new();
 
@@ -59,6 +60,57 @@ pub struct DefGuardConfig {
     #[serde(skip_serializing)]
     pub database_password: SecretString,
 
+    // maximum number of DB connections used by the web API and gRPC services
+    #[arg(long, env = "DEFGUARD_DB_MAX_CONNECTIONS", default_value_t = 10)]
+    pub database_max_connections: u32,
+
+    // maximum number of DB connections used for writing and purging peer stats,
+    // kept separate so stats bursts can't starve API requests
+    #[arg(long, env = "DEFGUARD_DB_STATS_MAX_CONNECTIONS", default_value_t = 4)]
+    pub database_stats_max_connections: u32,
+
+    // maximum number of DB connections used by background tasks
+    #[arg(
+        long,
+        env = "DEFGUARD_DB_BACKGROUND_MAX_CONNECTIONS",
+        default_value_t = 4
+    )]
+    pub database_background_max_connections: u32,
+
+    // maximum execution time of a single SQL statement issued by the web API and gRPC services,
+    // so that a pathological query can't hold a connection indefinitely; 0 disables the limit
+    #[arg(long, env = "DEFGUARD_DB_STATEMENT_TIMEOUT", default_value = "30s")]
+    #[serde(skip_serializing)]
+    pub database_statement_timeout: Duration,
+
+    // maximum execution time of a single SQL statement writing or purging peer stats
+    #[arg(
+        long,
+        env = "DEFGUARD_DB_STATS_STATEMENT_TIMEOUT",
+        default_value = "5m"
+    )]
+    #[serde(skip_serializing)]
+    pub database_stats_statement_timeout: Duration,
+
+    // maximum execution time of a single SQL statement issued by background tasks
+    #[arg(
+        long,
+        env = "DEFGUARD_DB_BACKGROUND_STATEMENT_TIMEOUT",
+        default_value = "5m"
+    )]
+    #[serde(skip_serializing)]
+    pub database_background_statement_timeout: Duration,
+
+    // SQL statements running longer than this are logged as warnings, along with the request
+    // or task which issued them
+    #[arg(
+        long,
+        env = "DEFGUARD_DB_SLOW_STATEMENT_THRESHOLD",
+        default_value = "1s"
+    )]
+    #[serde(skip_serializing)]
+    pub database_slow_statement_threshold: Duration,
+
     #[arg(long, env = "DEFGUARD_HTTP_PORT", default_value_t = 8000)]
     pub http_port: u16,
 
@@ -95,6 +147,14 @@ pub struct DefGuardConfig {
     #[arg(long, env = "DEFGUARD_DISABLE_STATS_PURGE")]
     pub disable_stats_purge: bool,
 
+    // expose GraphQL API under /api/v1/graphql
+    #[arg(long, env = "DEFGUARD_GRAPHQL_ENABLED")]
+    pub graphql_enabled: bool,
+
+    // periodically apply recommended keepalive intervals to VPN locations
+    #[arg(long, env = "DEFGUARD_KEEPALIVE_AUTO_TUNING")]
+    pub keepalive_auto_tuning: bool,
+
     #[arg(long, env = "DEFGUARD_STATS_PURGE_FREQUENCY", default_value = "24h")]
     #[serde(skip_serializing)]
     pub stats_purge_frequency: Duration,
@@ -103,6 +163,51 @@ pub struct DefGuardConfig {
     #[serde(skip_serializing)]
     pub stats_purge_threshold: Duration,
 
+    // daily UTC window (e.g. `02:00-04:00`) in which stats tables are purged, vacuumed
+    // and analyzed; if not set, stats are purged in the background at any time
+    #[arg(long, env = "DEFGUARD_STATS_MAINTENANCE_WINDOW")]
+    pub stats_maintenance_window: Option<MaintenanceWindow>,
+
+    // number of peer stats ingestion workers; locations are distributed among them
+    #[arg(long, env = "DEFGUARD_STATS_INGEST_SHARDS", default_value_t = 4)]
+    pub stats_ingest_shards: usize,
+
+    // maximum number of peer stats records written to the database in a single batch
+    #[arg(long, env = "DEFGUARD_STATS_INGEST_BATCH_SIZE", default_value_t = 1000)]
+    pub stats_ingest_batch_size: usize,
+
+    // maximum time peer stats are buffered before being written to the database
+    #[arg(
+        long,
+        env = "DEFGUARD_STATS_INGEST_FLUSH_INTERVAL",
+        default_value = "500ms"
+    )]
+    #[serde(skip_serializing)]
+    pub stats_ingest_flush_interval: Duration,
+
+    // number of peer stats records which can wait for ingestion in each worker queue;
+    // further updates are dropped until the queue drains
+    #[arg(
+        long,
+        env = "DEFGUARD_STATS_INGEST_QUEUE_SIZE",
+        default_value_t = 50_000
+    )]
+    pub stats_ingest_queue_size: usize,
+
+    // maximum number of SMTP sessions open at the same time
+    #[arg(
+        long,
+        env = "DEFGUARD_MAIL_MAX_CONCURRENT_SESSIONS",
+        default_value_t = 4
+    )]
+    pub mail_max_concurrent_sessions: usize,
+
+    // minimum delay between consecutive mails sent to the same recipient domain,
+    // used to avoid hitting mail provider rate limits during mass mailing
+    #[arg(long, env = "DEFGUARD_MAIL_DOMAIN_SEND_INTERVAL", default_value = "1s")]
+    #[serde(skip_serializing)]
+    pub mail_domain_send_interval: Duration,
+
     #[arg(long, env = "DEFGUARD_ENROLLMENT_URL", value_parser = Url::parse, default_value = "http://localhost:8080")]
     pub enrollment_url: Url,
 
@@ -118,6 +223,15 @@ pub struct DefGuardConfig {
     #[serde(skip_serializing)]
     pub session_timeout: Duration,
 
+    // lifetime of refresh tokens issued by the OpenID provider; each use rotates the token
+    #[arg(
+        long,
+        env = "DEFGUARD_OAUTH2_REFRESH_TOKEN_TIMEOUT",
+        default_value = "30d"
+    )]
+    #[serde(skip_serializing)]
+    pub oauth2_refresh_token_timeout: Duration,
+
     #[arg(
         long,
         env = "DEFGUARD_PASSWORD_RESET_TOKEN_TIMEOUT",
@@ -142,6 +256,11 @@ pub struct DefGuardConfig {
     #[serde(skip_serializing)]
     pub password_reset_session_timeout: Duration,
 
+    // time after which devices awaiting approval in locations requiring it are rejected
+    #[arg(long, env = "DEFGUARD_DEVICE_APPROVAL_TIMEOUT", default_value = "72h")]
+    #[serde(skip_serializing)]
+    pub device_approval_timeout: Duration,
+
     #[arg(long, env = "DEFGUARD_COOKIE_DOMAIN")]
     pub cookie_domain: Option<String>,
 
@@ -156,6 +275,67 @@ pub struct DefGuardConfig {
     #[arg(long, env = "DEFGUARD_PROXY_GRPC_CA")]
     pub proxy_grpc_ca: Option<String>,
 
+    // publish domain events to NATS JetStream; a stream covering `<event_outbox_topic>.>`
+    // subjects has to exist, requires the `outbox-nats` feature
+    #[arg(long, env = "DEFGUARD_EVENT_OUTBOX_NATS_URL")]
+    pub event_outbox_nats_url: Option<String>,
+
+    // comma-separated list of Kafka brokers to publish domain events to, requires the
+    // `outbox-kafka` feature
+    #[arg(long, env = "DEFGUARD_EVENT_OUTBOX_KAFKA_BROKERS")]
+    pub event_outbox_kafka_brokers: Option<String>,
+
+    // Kafka topic or prefix of NATS subjects for domain events
+    #[arg(
+        long,
+        env = "DEFGUARD_EVENT_OUTBOX_TOPIC",
+        default_value = "defguard.events"
+    )]
+    pub event_outbox_topic: String,
+
+    // URL the IP allow-list is pushed to whenever it changes
+    #[arg(long, env = "DEFGUARD_IP_ALLOWLIST_PUSH_URL")]
+    pub ip_allowlist_push_url: Option<Url>,
+
+    // format of the pushed IP allow-list
+    #[arg(
+        long,
+        env = "DEFGUARD_IP_ALLOWLIST_PUSH_FORMAT",
+        value_enum,
+        default_value_t = IpAllowlistFormat::Json
+    )]
+    pub ip_allowlist_push_format: IpAllowlistFormat,
+
+    // bearer token sent along with the pushed IP allow-list, or PAN-OS API key for `panos` format
+    #[arg(long, env = "DEFGUARD_IP_ALLOWLIST_PUSH_TOKEN")]
+    #[serde(skip_serializing)]
+    pub ip_allowlist_push_token: Option<SecretString>,
+
+    // domain served by a DNS server reporting queries to Core, used by clients for DNS leak tests
+    #[arg(long, env = "DEFGUARD_DNS_CANARY_DOMAIN")]
+    pub dns_canary_domain: Option<String>,
+
+    // bearer token the canary domain DNS server authenticates with when reporting queries
+    #[arg(long, env = "DEFGUARD_DNS_CANARY_TOKEN")]
+    #[serde(skip_serializing)]
+    pub dns_canary_token: Option<SecretString>,
+
+    // CSV file mapping IP address ranges to country codes (`start,end,country` rows, like in
+    // DB-IP Lite country database, or DB-IP Lite city database rows), used by geofencing policies
+    // of locations and to show approximate location in new device login emails
+    #[arg(long, env = "DEFGUARD_GEOIP_DATABASE")]
+    pub geoip_database: Option<String>,
+
+    // public URL mail images are served from, e.g. a CDN; images are loaded from defguard.net
+    // if not set
+    #[arg(long, env = "DEFGUARD_MAIL_ASSET_URL")]
+    pub mail_asset_url: Option<Url>,
+
+    // check migrations and database schema before applying migrations on startup,
+    // and refuse to start if problems are found
+    #[arg(long, env = "DEFGUARD_MIGRATION_PREFLIGHT")]
+    pub migration_preflight: bool,
+
     #[command(subcommand)]
     #[serde(skip_serializing)]
     pub cmd: Option<Command>,
@@ -179,6 +359,82 @@ pub struct DefGuardConfig {
     pub grpc_bind_address: Option<IpAddr>,
 }
 
+/// Format of IP allow-list exported for third-party firewalls.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum IpAllowlistFormat {
+    /// Generic JSON document
+    #[default]
+    Json,
+    /// PAN-OS User-ID API message tagging addresses for dynamic address groups
+    Panos,
+    /// `ipset restore` script
+    Ipset,
+}
+
+/// Daily time window in UTC, written as `HH:MM-HH:MM`. Windows ending before they start span
+/// midnight.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct MaintenanceWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl MaintenanceWindow {
+    /// Length of the window.
+    #[must_use]
+    pub fn length(&self) -> TimeDelta {
+        let length = self.end - self.start;
+        if length < TimeDelta::zero() {
+            length + TimeDelta::days(1)
+        } else {
+            length
+        }
+    }
+
+    #[must_use]
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            self.start <= time || time < self.end
+        }
+    }
+}
+
+impl fmt::Display for MaintenanceWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        )
+    }
+}
+
+impl FromStr for MaintenanceWindow {
+    type Err = String;
+
+    fn from_str(window: &str) -> Result<Self, Self::Err> {
+        let parse = |time: &str| {
+            NaiveTime::parse_from_str(time.trim(), "%H:%M")
+                .map_err(|err| format!("Invalid time {time}: {err}"))
+        };
+        let Some((start, end)) = window.split_once('-') else {
+            return Err(format!(
+                "Invalid window {window}, expected format is HH:MM-HH:MM"
+            ));
+        };
+        let (start, end) = (parse(start)?, parse(end)?);
+        if start == end {
+            return Err(format!("Window {window} is empty"));
+        }
+
+        Ok(Self { start, end })
+    }
+}
+
 #[derive(Clone, Debug, Subcommand)]
 pub enum Command {
     #[command(
@@ -191,6 +447,26 @@ pub enum Command {
     InitVpnLocation(InitVpnLocationArgs),
     #[command(about = "Output the gateway gRPC configuration payload for a VPN location by ID.")]
     GatewayConfig(GatewayConfigArgs),
+    #[command(
+        about = "Create or update a read-only database role with access to reporting views for BI tools."
+    )]
+    InitReportingRole(InitReportingRoleArgs),
+    #[command(
+        about = "Check pending database migrations and schema drift without applying migrations."
+    )]
+    CheckMigrations,
+    #[command(
+        about = "Development only. Create synthetic devices and stream their peer stats to a running core instance."
+    )]
+    LoadTest(LoadTestArgs),
+    #[command(
+        about = "Export users, groups, devices, VPN locations and settings to a file, e.g. to move the deployment to another database."
+    )]
+    Export(ExportArgs),
+    #[command(
+        about = "Import users, groups, devices, VPN locations and settings exported from another instance and verify the result."
+    )]
+    Import(ImportArgs),
 }
 
 #[derive(Args, Debug, Clone)]
@@ -217,6 +493,65 @@ pub struct GatewayConfigArgs {
     pub location_id: i64,
 }
 
+#[derive(Args, Debug, Clone)]
+pub struct InitReportingRoleArgs {
+    #[arg(long, default_value = "defguard_reporting")]
+    pub name: String,
+    #[arg(long, env = "DEFGUARD_REPORTING_PASSWORD")]
+    pub password: String,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct LoadTestArgs {
+    /// Number of synthetic VPN locations
+    #[arg(long, default_value_t = 1)]
+    pub locations: u32,
+    /// Number of synthetic devices, distributed evenly across locations
+    #[arg(long, default_value_t = 100)]
+    pub devices: u32,
+    /// Peer stats updates sent per second across all locations
+    #[arg(long, default_value_t = 100)]
+    pub rate: u32,
+    /// Stop after this many seconds; runs until interrupted if not set
+    #[arg(long)]
+    pub duration: Option<u64>,
+    /// Remove synthetic locations and devices along with their stats and exit
+    #[arg(long)]
+    pub cleanup: bool,
+}
+
+/// How an imported object is handled if the same object already exists.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ConflictPolicy {
+    /// Keep the existing object
+    #[default]
+    Skip,
+    /// Replace the existing object with the imported one
+    Overwrite,
+    /// Abort the import
+    Fail,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ExportArgs {
+    /// File the export is written to; contains password hashes and private keys
+    #[arg(long)]
+    pub output: PathBuf,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ImportArgs {
+    /// File created by the `export` command
+    #[arg(long)]
+    pub input: PathBuf,
+    /// How objects which already exist are handled
+    #[arg(long, value_enum, default_value_t)]
+    pub on_conflict: ConflictPolicy,
+    /// Report what would be imported without changing the database
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
 impl DefGuardConfig {
     #[must_use]
     pub fn new() -> Self {
@@ -296,6 +631,12 @@ impl DefGuardConfig {
         }
     }
 
+    /// Domain events are stored in the outbox only if a message broker is configured.
+    #[must_use]
+    pub fn event_outbox_enabled(&self) -> bool {
+        self.event_outbox_nats_url.is_some() || self.event_outbox_kafka_brokers.is_some()
+    }
+
     /// Returns configured URL with "auth/callback" appended to the path.
     #[must_use]
     pub fn callback_url(&self) -> Url {
@@ -391,6 +732,28 @@ mod tests {
         assert_eq!(config.cookie_domain, Some("example.com".to_string()));
     }
 
+    #[test]
+    fn test_maintenance_window() {
+        let window: MaintenanceWindow = "02:00-04:30".parse().unwrap();
+        assert!(!window.contains(NaiveTime::from_hms_opt(1, 59, 0).unwrap()));
+        assert!(window.contains(NaiveTime::from_hms_opt(2, 0, 0).unwrap()));
+        assert!(window.contains(NaiveTime::from_hms_opt(4, 29, 59).unwrap()));
+        assert!(!window.contains(NaiveTime::from_hms_opt(4, 30, 0).unwrap()));
+
+        // spanning midnight
+        let window: MaintenanceWindow = "23:00 - 01:00".parse().unwrap();
+        assert!(window.contains(NaiveTime::from_hms_opt(23, 30, 0).unwrap()));
+        assert!(window.contains(NaiveTime::from_hms_opt(0, 30, 0).unwrap()));
+        assert!(!window.contains(NaiveTime::from_hms_opt(12, 0, 0).unwrap()));
+
+        assert_eq!(window.to_string(), "23:00-01:00");
+        assert_eq!(window.length(), TimeDelta::hours(2));
+
+        assert!("02:00".parse::<MaintenanceWindow>().is_err());
+        assert!("02:00-25:00".parse::<MaintenanceWindow>().is_err());
+        assert!("02:00-02:00".parse::<MaintenanceWindow>().is_err());
+    }
+
     #[test]
     fn test_callback_url() {
         unsafe {
@@ -1,6 +1,12 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use log::LevelFilter;
 use serde::{Deserialize, Serialize};
 use sqlx::{
-    PgPool,
+    ConnectOptions, PgPool,
     postgres::{PgConnectOptions, PgPoolOptions},
 };
 use tracing::info;
@@ -16,18 +22,50 @@ pub type Id = i64;
 // reference: https://docs.rs/sqlx/latest/sqlx/attr.test.html#automatic-migrations-requires-migrate-feature
 pub static MIGRATOR: sqlx::migrate::Migrator = sqlx::migrate!("../../migrations");
 
-/// Initializes and migrates postgres database. Returns DB pool object.
-pub async fn init_db(host: &str, port: u16, name: &str, user: &str, password: &str) -> PgPool {
-    info!("Initializing DB pool");
-    let opts = PgConnectOptions::new()
+/// Returns postgres connection options.
+#[must_use]
+pub fn db_connect_options(
+    host: &str,
+    port: u16,
+    name: &str,
+    user: &str,
+    password: &str,
+) -> PgConnectOptions {
+    PgConnectOptions::new()
         .host(host)
         .port(port)
         .username(user)
         .password(password)
-        .database(name);
-    let pool = PgPool::connect_with(opts)
-        .await
-        .expect("Database connection failed");
+        .database(name)
+}
+
+/// Limits execution time of SQL statements on connections made with given options
+/// and logs statements exceeding `slow_statement_threshold` as warnings.
+///
+/// Warnings are emitted within the current tracing span, so they identify the request
+/// or task which issued the statement.
+#[must_use]
+pub fn with_statement_limits(
+    options: PgConnectOptions,
+    statement_timeout: Duration,
+    slow_statement_threshold: Duration,
+) -> PgConnectOptions {
+    options
+        .options([("statement_timeout", statement_timeout.as_millis())])
+        .log_slow_statements(LevelFilter::Warn, slow_statement_threshold)
+}
+
+/// Initializes and migrates postgres database. Returns DB pool object.
+pub async fn init_db(
+    host: &str,
+    port: u16,
+    name: &str,
+    user: &str,
+    password: &str,
+    max_connections: u32,
+) -> PgPool {
+    let opts = db_connect_options(host, port, name, user, password);
+    let pool = connect_pool(opts, max_connections).await;
     MIGRATOR
         .run(&pool)
         .await
@@ -35,6 +73,71 @@ pub async fn init_db(host: &str, port: u16, name: &str, user: &str, password: &s
     pool
 }
 
+/// Connects DB pool of at most `max_connections` connections, without running migrations.
+pub async fn connect_pool(options: PgConnectOptions, max_connections: u32) -> PgPool {
+    info!("Initializing DB pool with up to {max_connections} connections");
+    PgPoolOptions::new()
+        .max_connections(max_connections)
+        .connect_with(options)
+        .await
+        .expect("Database connection failed")
+}
+
+// pools used by separate subsystems, see `register_pool`
+static POOLS: Mutex<Vec<(&str, PgPool)>> = Mutex::new(Vec::new());
+
+/// Registers a pool under a given name so its usage is reported by [`pool_stats`].
+/// Registering another pool with the same name replaces the previous one.
+pub fn register_pool(name: &'static str, pool: &PgPool) {
+    let mut pools = POOLS.lock().expect("Failed to acquire lock on DB pools");
+    pools.retain(|(pool_name, _)| *pool_name != name);
+    pools.push((name, pool.clone()));
+}
+
+/// Usage of a single DB pool.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PoolStats {
+    pub name: String,
+    pub max_connections: u32,
+    /// Open connections, both idle and in use
+    pub size: u32,
+    pub idle: u32,
+    pub in_use: u32,
+    /// Time it took to acquire a connection when collecting stats, in milliseconds;
+    /// `None` if no connection could be acquired
+    pub acquire_ms: Option<u64>,
+}
+
+/// Collect usage of all registered pools.
+pub async fn pool_stats() -> Vec<PoolStats> {
+    let pools = POOLS
+        .lock()
+        .expect("Failed to acquire lock on DB pools")
+        .clone();
+    let mut stats = Vec::with_capacity(pools.len());
+    for (name, pool) in pools {
+        // read usage before acquiring a connection for the wait time measurement
+        let size = pool.size();
+        let idle = pool.num_idle() as u32;
+        let started = Instant::now();
+        let acquire_ms = pool
+            .acquire()
+            .await
+            .ok()
+            .map(|_| started.elapsed().as_millis() as u64);
+        stats.push(PoolStats {
+            name: name.to_string(),
+            max_connections: pool.options().get_max_connections(),
+            size,
+            idle,
+            in_use: size.saturating_sub(idle),
+            acquire_ms,
+        });
+    }
+
+    stats
+}
+
 // Helper function to instantiate pool manually as a workaround for issues with `sqlx::test` macro
 // reference: https://github.com/launchbadge/sqlx/issues/2567#issuecomment-2009849261
 pub async fn setup_pool(options: PgConnectOptions) -> PgPool {
@@ -45,3 +148,36 @@ pub async fn setup_pool(options: PgConnectOptions) -> PgPool {
         .expect("Cannot run database migrations.");
     pool
 }
+
+#[cfg(test)]
+mod test {
+    use sqlx::query;
+
+    use super::*;
+
+    #[sqlx::test]
+    async fn test_statement_timeout(_: PgPoolOptions, options: PgConnectOptions) {
+        let options = with_statement_limits(
+            options,
+            Duration::from_millis(100),
+            Duration::from_millis(50),
+        );
+        let pool = connect_pool(options, 1).await;
+
+        // slow statement is logged, but completes
+        query("SELECT pg_sleep(0.06)").execute(&pool).await.unwrap();
+
+        let err = query("SELECT pg_sleep(5)")
+            .execute(&pool)
+            .await
+            .unwrap_err();
+        // query_canceled
+        assert_eq!(
+            err.as_database_error().unwrap().code().as_deref(),
+            Some("57014")
+        );
+
+        // connection is still usable
+        query("SELECT 1").execute(&pool).await.unwrap();
+    }
+}
@@ -0,0 +1,190 @@
+use std::collections::{BTreeMap, HashMap};
+
+use model_derive::Model;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgExecutor, PgPool, query_as};
+use tracing::debug;
+use utoipa::ToSchema;
+
+use crate::{
+    db::{Id, NoId, models::settings::get_settings},
+    global_value,
+};
+
+/// Translations by template and language, mapping section names to their text.
+type Translations = BTreeMap<(String, String), HashMap<String, String>>;
+
+global_value!(
+    MAIL_CONTEXT,
+    Translations,
+    BTreeMap::new(),
+    set_mail_context,
+    get_mail_context
+);
+
+/// Language of built-in mail texts, used if neither the user nor the instance have
+/// a mail language set.
+pub const DEFAULT_MAIL_LANGUAGE: &str = "en";
+
+/// Translation of a mail template section, which replaces its built-in English text.
+#[derive(Clone, Debug, Deserialize, Model, PartialEq, Serialize, ToSchema)]
+#[table(mail_context)]
+pub struct MailContext<I = NoId> {
+    pub id: I,
+    pub template: String,
+    pub section: String,
+    pub language_tag: String,
+    pub text: String,
+}
+
+impl MailContext {
+    #[must_use]
+    pub fn new(template: String, section: String, language_tag: String, text: String) -> Self {
+        Self {
+            id: NoId,
+            template,
+            section,
+            language_tag,
+            text,
+        }
+    }
+}
+
+impl MailContext<Id> {
+    pub async fn find<'e, E>(
+        executor: E,
+        template: &str,
+        section: &str,
+        language_tag: &str,
+    ) -> Result<Option<Self>, sqlx::Error>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, template, section, language_tag, text FROM mail_context \
+            WHERE template = $1 AND section = $2 AND language_tag = $3",
+            template,
+            section,
+            language_tag
+        )
+        .fetch_optional(executor)
+        .await
+    }
+}
+
+/// Load mail template translations from the DB into the global `MAIL_CONTEXT` map.
+///
+/// Has to be called again after translations are modified.
+pub async fn initialize_mail_context(pool: &PgPool) -> Result<(), sqlx::Error> {
+    debug!("Initializing mail template translations");
+    let mut translations = Translations::new();
+    for context in MailContext::all(pool).await? {
+        translations
+            .entry((context.template, context.language_tag))
+            .or_default()
+            .insert(context.section, context.text);
+    }
+    set_mail_context(translations);
+    Ok(())
+}
+
+/// Language tags like `en`, `pl` or `pt-BR`.
+#[must_use]
+pub fn is_valid_language_tag(tag: &str) -> bool {
+    let mut subtags = tag.split('-');
+    let primary = subtags.next().unwrap_or_default();
+    (2..=3).contains(&primary.len())
+        && primary.chars().all(|c| c.is_ascii_lowercase())
+        && subtags.all(|subtag| {
+            (2..=8).contains(&subtag.len()) && subtag.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+}
+
+/// Languages mails to a user are looked up in, in order of preference: the user's language,
+/// instance default and the language of built-in texts.
+#[must_use]
+pub fn mail_languages(user_language: Option<&str>) -> Vec<String> {
+    let instance_language = get_settings()
+        .as_ref()
+        .map(|settings| settings.default_mail_language.clone());
+    let mut languages = Vec::new();
+    for language in [
+        user_language.map(ToString::to_string),
+        instance_language,
+        Some(DEFAULT_MAIL_LANGUAGE.to_string()),
+    ]
+    .into_iter()
+    .flatten()
+    {
+        if !language.is_empty() && !languages.contains(&language) {
+            languages.push(language);
+        }
+    }
+    languages
+}
+
+/// Translated text of a template section in the first of `languages` it's available in.
+#[must_use]
+pub fn translated_section(template: &str, section: &str, languages: &[String]) -> Option<String> {
+    let translations = get_mail_context();
+    languages.iter().find_map(|language| {
+        translations
+            .get(&(template.to_string(), language.clone()))
+            .and_then(|sections| sections.get(section))
+            .cloned()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_language_tag() {
+        assert!(is_valid_language_tag("en"));
+        assert!(is_valid_language_tag("pl"));
+        assert!(is_valid_language_tag("pt-BR"));
+        assert!(is_valid_language_tag("zh-Hant-TW"));
+        assert!(!is_valid_language_tag(""));
+        assert!(!is_valid_language_tag("EN"));
+        assert!(!is_valid_language_tag("english"));
+        assert!(!is_valid_language_tag("pt-"));
+        assert!(!is_valid_language_tag("pt_BR"));
+    }
+
+    #[test]
+    fn test_translated_section() {
+        set_mail_context(BTreeMap::from([
+            (
+                ("mail_desktop_start".to_string(), "pl".to_string()),
+                HashMap::from([("intro".to_string(), "Cześć".to_string())]),
+            ),
+            (
+                ("mail_desktop_start".to_string(), "de".to_string()),
+                HashMap::from([
+                    ("intro".to_string(), "Hallo".to_string()),
+                    ("button".to_string(), "Konfigurieren".to_string()),
+                ]),
+            ),
+        ]));
+        let languages = vec!["pl".to_string(), "de".to_string(), "en".to_string()];
+        assert_eq!(
+            translated_section("mail_desktop_start", "intro", &languages).as_deref(),
+            Some("Cześć")
+        );
+        // falls back to the next language
+        assert_eq!(
+            translated_section("mail_desktop_start", "button", &languages).as_deref(),
+            Some("Konfigurieren")
+        );
+        assert_eq!(
+            translated_section("mail_desktop_start", "token", &languages),
+            None
+        );
+        assert_eq!(
+            translated_section("mail_enrollment_start", "intro", &languages),
+            None
+        );
+    }
+}
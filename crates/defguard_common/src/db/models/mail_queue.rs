@@ -0,0 +1,101 @@
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use model_derive::Model;
+use sqlx::{PgExecutor, query, query_as};
+
+use crate::db::{Id, NoId, models::MailCategory};
+
+/// Mail waiting for another delivery attempt after a transient SMTP failure.
+#[derive(Clone, Debug, Model)]
+#[table(mail_queue)]
+pub struct QueuedMail<I = NoId> {
+    pub id: I,
+    pub recipient: String,
+    pub subject: String,
+    pub content: String,
+    #[model(enum)]
+    pub category: MailCategory,
+    /// Number of failed delivery attempts
+    pub attempts: i32,
+    /// `None` once delivery is given up
+    pub next_attempt_at: Option<NaiveDateTime>,
+    pub last_error: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl QueuedMail {
+    /// Mail which failed to be delivered for the first time.
+    #[must_use]
+    pub fn new(
+        recipient: String,
+        subject: String,
+        content: String,
+        category: MailCategory,
+        error: String,
+        next_attempt_at: NaiveDateTime,
+    ) -> Self {
+        Self {
+            id: NoId,
+            recipient,
+            subject,
+            content,
+            category,
+            attempts: 1,
+            next_attempt_at: Some(next_attempt_at),
+            last_error: error,
+            created_at: Utc::now().naive_utc(),
+        }
+    }
+}
+
+impl QueuedMail<Id> {
+    /// All queued mails, the most recent first.
+    pub async fn list<'e, E>(executor: E) -> Result<Vec<Self>, sqlx::Error>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, recipient, subject, content, category \"category: MailCategory\", \
+            attempts, next_attempt_at, last_error, created_at FROM mail_queue \
+            ORDER BY created_at DESC, id DESC"
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    /// Claims up to `limit` mails due for delivery by postponing their next attempt by `lease`,
+    /// so they aren't picked up again while being sent.
+    pub async fn claim_due<'e, E>(
+        executor: E,
+        limit: i64,
+        lease: TimeDelta,
+    ) -> Result<Vec<Self>, sqlx::Error>
+    where
+        E: PgExecutor<'e>,
+    {
+        let now = Utc::now().naive_utc();
+        query_as!(
+            Self,
+            "UPDATE mail_queue SET next_attempt_at = $1 WHERE id IN ( \
+                SELECT id FROM mail_queue WHERE next_attempt_at <= $2 \
+                ORDER BY next_attempt_at LIMIT $3 FOR UPDATE SKIP LOCKED) \
+            RETURNING id, recipient, subject, content, category \"category: MailCategory\", \
+            attempts, next_attempt_at, last_error, created_at",
+            now + lease,
+            now,
+            limit
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    /// Removes all queued mails, returns their number.
+    pub async fn delete_all<'e, E>(executor: E) -> Result<u64, sqlx::Error>
+    where
+        E: PgExecutor<'e>,
+    {
+        let result = query!("DELETE FROM mail_queue").execute(executor).await?;
+
+        Ok(result.rows_affected())
+    }
+}
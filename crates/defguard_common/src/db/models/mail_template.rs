@@ -0,0 +1,76 @@
+use std::collections::BTreeMap;
+
+use model_derive::Model;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgExecutor, PgPool, query_as};
+use tracing::debug;
+use utoipa::ToSchema;
+
+use crate::{
+    db::{Id, NoId},
+    global_value,
+};
+
+global_value!(
+    MAIL_TEMPLATES,
+    BTreeMap<String, String>,
+    BTreeMap::new(),
+    set_mail_templates,
+    get_mail_templates
+);
+
+/// Admin override of a built-in mail template.
+#[derive(Clone, Debug, Deserialize, Model, PartialEq, Serialize, ToSchema)]
+#[table(mail_template)]
+pub struct MailTemplate<I = NoId> {
+    pub id: I,
+    /// Name of the built-in template, e.g. `mail_enrollment_start`
+    pub name: String,
+    pub content: String,
+}
+
+impl MailTemplate {
+    #[must_use]
+    pub fn new(name: String, content: String) -> Self {
+        Self {
+            id: NoId,
+            name,
+            content,
+        }
+    }
+}
+
+impl MailTemplate<Id> {
+    pub async fn find_by_name<'e, E>(executor: E, name: &str) -> Result<Option<Self>, sqlx::Error>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, name, content FROM mail_template WHERE name = $1",
+            name
+        )
+        .fetch_optional(executor)
+        .await
+    }
+}
+
+/// Load mail template overrides from the DB into the global `MAIL_TEMPLATES` map.
+///
+/// Has to be called again after overrides are modified.
+pub async fn initialize_mail_templates(pool: &PgPool) -> Result<(), sqlx::Error> {
+    debug!("Initializing mail template overrides");
+    let templates = MailTemplate::all(pool)
+        .await?
+        .into_iter()
+        .map(|template| (template.name, template.content))
+        .collect();
+    set_mail_templates(templates);
+    Ok(())
+}
+
+/// Override of a built-in mail template, if there is one.
+#[must_use]
+pub fn mail_template_override(name: &str) -> Option<String> {
+    get_mail_templates().get(name).cloned()
+}
@@ -0,0 +1,163 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use model_derive::Model;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgExecutor, PgPool, query_as};
+use tracing::debug;
+use utoipa::ToSchema;
+
+use crate::{
+    db::{Id, NoId},
+    global_value,
+};
+
+global_value!(
+    MAIL_VARIABLES,
+    BTreeMap<String, String>,
+    BTreeMap::new(),
+    set_mail_variables,
+    get_mail_variables
+);
+
+/// Name of the template context object holding instance variables.
+pub const MAIL_VARIABLES_CONTEXT_KEY: &str = "instance";
+
+/// Instance-level variable (e.g. company name or support URL) which can be referenced
+/// in admin-editable mail templates as `{{ instance.<name> }}`.
+#[derive(Clone, Debug, Deserialize, Model, PartialEq, Serialize, ToSchema)]
+#[table(mail_variable)]
+pub struct MailVariable<I = NoId> {
+    pub id: I,
+    pub name: String,
+    pub value: String,
+}
+
+impl MailVariable {
+    #[must_use]
+    pub fn new(name: String, value: String) -> Self {
+        Self {
+            id: NoId,
+            name,
+            value,
+        }
+    }
+}
+
+impl MailVariable<Id> {
+    pub async fn find_by_name<'e, E>(executor: E, name: &str) -> Result<Option<Self>, sqlx::Error>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, name, value FROM mail_variable WHERE name = $1",
+            name
+        )
+        .fetch_optional(executor)
+        .await
+    }
+}
+
+/// Load mail variables from the DB into the global `MAIL_VARIABLES` map.
+///
+/// Has to be called again after variables are modified.
+pub async fn initialize_mail_variables(pool: &PgPool) -> Result<(), sqlx::Error> {
+    debug!("Initializing global mail variables");
+    let variables = MailVariable::all(pool)
+        .await?
+        .into_iter()
+        .map(|variable| (variable.name, variable.value))
+        .collect();
+    set_mail_variables(variables);
+    Ok(())
+}
+
+/// Variable names are used as template identifiers, so only lowercase letters, digits and
+/// underscores are allowed.
+#[must_use]
+pub fn is_valid_mail_variable_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Names of instance variables referenced as `instance.<name>` inside template tags.
+#[must_use]
+pub fn referenced_mail_variables(template: &str) -> BTreeSet<&str> {
+    let prefix = format!("{MAIL_VARIABLES_CONTEXT_KEY}.");
+    let mut names = BTreeSet::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{").into_iter().chain(rest.find("{%")).min() {
+        let tag = &rest[start + 2..];
+        let end = tag
+            .find("}}")
+            .into_iter()
+            .chain(tag.find("%}"))
+            .min()
+            .unwrap_or(tag.len());
+        let tag = &tag[..end];
+        for (index, _) in tag.match_indices(&prefix) {
+            // skip identifiers which only end with the prefix, like `my_instance.name`
+            if tag[..index]
+                .chars()
+                .next_back()
+                .is_some_and(|c| c.is_alphanumeric() || c == '_')
+            {
+                continue;
+            }
+            let name = &tag[index + prefix.len()..];
+            let length = name
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(name.len());
+            if length > 0 {
+                names.insert(&name[..length]);
+            }
+        }
+        rest = &rest[start + 2 + end..];
+    }
+    names
+}
+
+/// Return the first variable referenced in a template which isn't defined.
+#[must_use]
+pub fn unknown_mail_variable(template: &str) -> Option<String> {
+    let variables = get_mail_variables();
+    referenced_mail_variables(template)
+        .into_iter()
+        .find(|name| !variables.contains_key(*name))
+        .map(ToString::to_string)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_referenced_mail_variables() {
+        let template = "Contact {{ instance.company_name }} at {{instance.support_url|safe}}.\n\
+            {% if instance.helpdesk_phone %}Call {{ instance.helpdesk_phone }}{% endif %}\n\
+            For instance.ignored {{ my_instance.ignored }} {{ first_name }}";
+        assert_eq!(
+            referenced_mail_variables(template),
+            BTreeSet::from(["company_name", "helpdesk_phone", "support_url"])
+        );
+
+        // unterminated tag
+        assert_eq!(
+            referenced_mail_variables("{{ instance.name"),
+            BTreeSet::from(["name"])
+        );
+        assert!(referenced_mail_variables("no tags").is_empty());
+    }
+
+    #[test]
+    fn test_mail_variable_name() {
+        assert!(is_valid_mail_variable_name("support_url"));
+        assert!(is_valid_mail_variable_name("_phone2"));
+        assert!(!is_valid_mail_variable_name(""));
+        assert!(!is_valid_mail_variable_name("2fa_url"));
+        assert!(!is_valid_mail_variable_name("Support"));
+        assert!(!is_valid_mail_variable_name("support-url"));
+    }
+}
@@ -3,7 +3,12 @@ pub mod authentication_key;
 pub mod biometric_auth;
 pub mod device_login;
 pub mod error;
+pub mod mail_context;
+pub mod mail_queue;
+pub mod mail_template;
+pub mod mail_variable;
 pub mod settings;
+pub mod smtp_profile;
 pub mod user;
 
 pub use auth_code::AuthCode;
@@ -11,5 +16,9 @@ pub use authentication_key::{AuthenticationKey, AuthenticationKeyType};
 pub use biometric_auth::{BiometricAuth, BiometricChallenge};
 pub use device_login::DeviceLoginEvent;
 pub use error::ModelError;
+pub use mail_context::MailContext;
+pub use mail_template::MailTemplate;
+pub use mail_variable::MailVariable;
 pub use settings::{Settings, SettingsEssentials};
+pub use smtp_profile::{MailCategory, SmtpProfile};
 pub use user::MFAMethod;
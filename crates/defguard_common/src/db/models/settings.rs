@@ -1,4 +1,4 @@
-use std::{collections::HashMap, fmt};
+use std::{collections::HashMap, fmt, time::Duration};
 
 use serde::{Deserialize, Serialize};
 use sqlx::{PgExecutor, PgPool, Type, query, query_as};
@@ -7,11 +7,21 @@ use thiserror::Error;
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
-use crate::{global_value, secret::SecretStringWrapper};
+use crate::{
+    config::server_config,
+    db::models::{
+        mail_context::{initialize_mail_context, is_valid_language_tag},
+        mail_template::initialize_mail_templates,
+        mail_variable::{initialize_mail_variables, unknown_mail_variable},
+        smtp_profile::initialize_smtp_profiles,
+    },
+    global_value,
+    secret::SecretStringWrapper,
+};
 
 global_value!(SETTINGS, Option<Settings>, None, set_settings, get_settings);
 
-/// Initializes global `SETTINGS` struct at program startup
+/// Initializes global `SETTINGS` struct and mail variables at program startup
 pub async fn initialize_current_settings(pool: &PgPool) -> Result<(), sqlx::Error> {
     debug!("Initializing global settings struct");
     if let Some(settings) = Settings::get(pool).await? {
@@ -22,6 +32,10 @@ pub async fn initialize_current_settings(pool: &PgPool) -> Result<(), sqlx::Erro
         );
         set_settings(Some(Settings::default()));
     }
+    initialize_mail_variables(pool).await?;
+    initialize_mail_context(pool).await?;
+    initialize_mail_templates(pool).await?;
+    initialize_smtp_profiles(pool).await?;
     Ok(())
 }
 
@@ -40,6 +54,24 @@ pub async fn update_current_settings<'e, E: sqlx::PgExecutor<'e>>(
 pub enum SettingsValidationError {
     #[error("Cannot enable gateway disconnect notifications. SMTP is not configured")]
     CannotEnableGatewayNotifications,
+    #[error("Cannot enable self-registration. SMTP is not configured")]
+    CannotEnableSelfRegistration,
+    #[error("Invalid email address: {0}")]
+    InvalidEmailAddress(String),
+    #[error("Sender name can't contain control characters")]
+    InvalidSenderName,
+    #[error("Template references unknown mail variable: {0}")]
+    UnknownMailVariable(String),
+    #[error("Password reset link lifetime has to be positive")]
+    InvalidPasswordResetLifetime,
+    #[error("Password reset link has to be usable at least once")]
+    InvalidPasswordResetMaxUses,
+    #[error("Notification digest window can't be negative")]
+    InvalidNotificationDigestWindow,
+    #[error("Invalid mail HTTP API URL: {0}")]
+    InvalidMailHttpUrl(String),
+    #[error("Invalid mail language: {0}")]
+    InvalidMailLanguage(String),
 }
 
 #[derive(Clone, Deserialize, Serialize, PartialEq, Eq, Type, Debug, Default)]
@@ -51,6 +83,30 @@ pub enum SmtpEncryption {
     ImplicitTls,
 }
 
+/// SMTP authentication mechanism.
+#[derive(Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Type, Debug, Default)]
+#[sqlx(type_name = "smtp_auth_method", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpAuthMethod {
+    /// Username and password (PLAIN/LOGIN).
+    #[default]
+    Password,
+    /// XOAUTH2 with access token obtained through OAuth2 client credentials grant.
+    OAuth2,
+}
+
+/// Backend used to deliver mails.
+#[derive(Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Type, Debug, Default)]
+#[sqlx(type_name = "mail_backend", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum MailBackend {
+    /// SMTP server configured with `smtp_*` settings.
+    #[default]
+    Smtp,
+    /// HTTP API of a mail delivery service, which receives rendered mails as JSON.
+    Http,
+}
+
 #[derive(Clone, Deserialize, Serialize, PartialEq, Eq, Type, Debug, Default, Copy)]
 #[sqlx(type_name = "openid_username_handling", rename_all = "snake_case")]
 pub enum OpenidUsernameHandling {
@@ -99,12 +155,34 @@ pub struct Settings {
     pub smtp_user: Option<String>,
     pub smtp_password: Option<SecretStringWrapper>,
     pub smtp_sender: Option<String>,
+    pub smtp_auth_method: SmtpAuthMethod,
+    pub smtp_oauth2_token_url: Option<String>,
+    pub smtp_oauth2_client_id: Option<String>,
+    pub smtp_oauth2_client_secret: Option<SecretStringWrapper>,
+    pub smtp_oauth2_scope: Option<String>,
+    // Display name of the sender
+    pub smtp_sender_name: Option<String>,
+    pub smtp_reply_to: Option<String>,
+    // Senders of specific mail categories, `smtp_sender` is used if not set
+    pub smtp_security_sender: Option<String>,
+    pub smtp_announcement_sender: Option<String>,
+    // HTTP mail backend, sender settings are shared with SMTP
+    pub mail_backend: MailBackend,
+    pub mail_http_url: Option<String>,
+    // Sent as a bearer token
+    pub mail_http_token: Option<SecretStringWrapper>,
+    // Language of mails to users who haven't set their own
+    pub default_mail_language: String,
     // Enrollment
     pub enrollment_vpn_step_optional: bool,
     pub enrollment_welcome_message: Option<String>,
     pub enrollment_welcome_email: Option<String>,
     pub enrollment_welcome_email_subject: Option<String>,
     pub enrollment_use_welcome_message_as_email: bool,
+    // Self-registration
+    pub self_registration_enabled: bool,
+    // Email domains allowed to request an account
+    pub self_registration_domains: Vec<String>,
     // Instance UUID needed for desktop client
     #[serde(skip)]
     pub uuid: Uuid,
@@ -143,6 +221,19 @@ pub struct Settings {
     pub gateway_disconnect_notifications_enabled: bool,
     pub gateway_disconnect_notifications_inactivity_threshold: i32,
     pub gateway_disconnect_notifications_reconnect_notification_enabled: bool,
+    // Weekly security summary sent to admin users
+    pub security_summary_enabled: bool,
+    // Connectivity notifications are batched into a digest mail over this many minutes;
+    // 0 sends them immediately
+    pub notification_digest_window: i32,
+    // Personal data redacted in responses to auditors
+    pub auditor_redact_emails: bool,
+    pub auditor_redact_ip_addresses: bool,
+    // Password reset
+    // Lifetime of password reset links in minutes, server configuration is used if not set
+    pub password_reset_token_lifetime: Option<i32>,
+    // Number of times a password reset link can be used to start a reset session
+    pub password_reset_max_uses: i32,
 }
 
 // Implement manually to avoid exposing the license key.
@@ -163,6 +254,19 @@ impl fmt::Debug for Settings {
             .field("smtp_user", &self.smtp_user)
             .field("smtp_password", &self.smtp_password)
             .field("smtp_sender", &self.smtp_sender)
+            .field("smtp_auth_method", &self.smtp_auth_method)
+            .field("smtp_oauth2_token_url", &self.smtp_oauth2_token_url)
+            .field("smtp_oauth2_client_id", &self.smtp_oauth2_client_id)
+            .field("smtp_oauth2_client_secret", &self.smtp_oauth2_client_secret)
+            .field("smtp_oauth2_scope", &self.smtp_oauth2_scope)
+            .field("smtp_sender_name", &self.smtp_sender_name)
+            .field("smtp_reply_to", &self.smtp_reply_to)
+            .field("smtp_security_sender", &self.smtp_security_sender)
+            .field("smtp_announcement_sender", &self.smtp_announcement_sender)
+            .field("mail_backend", &self.mail_backend)
+            .field("mail_http_url", &self.mail_http_url)
+            .field("mail_http_token", &self.mail_http_token)
+            .field("default_mail_language", &self.default_mail_language)
             .field(
                 "enrollment_vpn_step_optional",
                 &self.enrollment_vpn_step_optional,
@@ -180,6 +284,8 @@ impl fmt::Debug for Settings {
                 "enrollment_use_welcome_message_as_email",
                 &self.enrollment_use_welcome_message_as_email,
             )
+            .field("self_registration_enabled", &self.self_registration_enabled)
+            .field("self_registration_domains", &self.self_registration_domains)
             .field("uuid", &self.uuid)
             .field("ldap_url", &self.ldap_url)
             .field("ldap_bind_username", &self.ldap_bind_username)
@@ -220,6 +326,21 @@ impl fmt::Debug for Settings {
                 "gateway_disconnect_notifications_reconnect_notification_enabled",
                 &self.gateway_disconnect_notifications_reconnect_notification_enabled,
             )
+            .field("security_summary_enabled", &self.security_summary_enabled)
+            .field(
+                "notification_digest_window",
+                &self.notification_digest_window,
+            )
+            .field("auditor_redact_emails", &self.auditor_redact_emails)
+            .field(
+                "auditor_redact_ip_addresses",
+                &self.auditor_redact_ip_addresses,
+            )
+            .field(
+                "password_reset_token_lifetime",
+                &self.password_reset_token_lifetime,
+            )
+            .field("password_reset_max_uses", &self.password_reset_max_uses)
             .finish_non_exhaustive()
     }
 }
@@ -249,7 +370,16 @@ impl Settings {
             ldap_enabled, ldap_sync_enabled, ldap_is_authoritative, \
             ldap_sync_interval, ldap_user_auxiliary_obj_classes, ldap_uses_ad, \
             ldap_user_rdn_attr, ldap_sync_groups, \
-            openid_username_handling \"openid_username_handling: OpenidUsernameHandling\" \
+            openid_username_handling \"openid_username_handling: OpenidUsernameHandling\", \
+            smtp_auth_method \"smtp_auth_method: SmtpAuthMethod\", smtp_oauth2_token_url, \
+            smtp_oauth2_client_id, \
+            smtp_oauth2_client_secret \"smtp_oauth2_client_secret?: SecretStringWrapper\", \
+            smtp_oauth2_scope, self_registration_enabled, self_registration_domains, \
+            smtp_sender_name, smtp_reply_to, smtp_security_sender, smtp_announcement_sender, \
+            security_summary_enabled, password_reset_token_lifetime, password_reset_max_uses, \
+            mail_backend \"mail_backend: MailBackend\", mail_http_url, \
+            mail_http_token \"mail_http_token?: SecretStringWrapper\", default_mail_language, \
+            notification_digest_window, auditor_redact_emails, auditor_redact_ip_addresses \
             FROM \"settings\" WHERE id = 1",
         )
         .fetch_optional(executor)
@@ -269,6 +399,68 @@ impl Settings {
             warn!("Cannot enable gateway disconnect notifications. SMTP is not configured.");
             return Err(SettingsValidationError::CannotEnableGatewayNotifications);
         }
+        // Self-registration requires SMTP to verify email addresses.
+        if self.self_registration_enabled && !self.smtp_configured() {
+            warn!("Cannot enable self-registration. SMTP is not configured.");
+            return Err(SettingsValidationError::CannotEnableSelfRegistration);
+        }
+        for address in [
+            &self.smtp_sender,
+            &self.smtp_reply_to,
+            &self.smtp_security_sender,
+            &self.smtp_announcement_sender,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if !address.is_empty() && !is_valid_email_address(address) {
+                return Err(SettingsValidationError::InvalidEmailAddress(
+                    address.clone(),
+                ));
+            }
+        }
+        if self
+            .smtp_sender_name
+            .as_ref()
+            .is_some_and(|name| name.chars().any(char::is_control))
+        {
+            return Err(SettingsValidationError::InvalidSenderName);
+        }
+        if self
+            .password_reset_token_lifetime
+            .is_some_and(|lifetime| lifetime < 1)
+        {
+            return Err(SettingsValidationError::InvalidPasswordResetLifetime);
+        }
+        if self.password_reset_max_uses < 1 {
+            return Err(SettingsValidationError::InvalidPasswordResetMaxUses);
+        }
+        if self.notification_digest_window < 0 {
+            return Err(SettingsValidationError::InvalidNotificationDigestWindow);
+        }
+        if let Some(url) = self.mail_http_url.as_ref().filter(|url| !url.is_empty()) {
+            if !reqwest::Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https")) {
+                return Err(SettingsValidationError::InvalidMailHttpUrl(url.clone()));
+            }
+        }
+        if !self.default_mail_language.is_empty()
+            && !is_valid_language_tag(&self.default_mail_language)
+        {
+            return Err(SettingsValidationError::InvalidMailLanguage(
+                self.default_mail_language.clone(),
+            ));
+        }
+        for template in [
+            &self.enrollment_welcome_message,
+            &self.enrollment_welcome_email,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if let Some(name) = unknown_mail_variable(template) {
+                return Err(SettingsValidationError::UnknownMailVariable(name));
+            }
+        }
 
         Ok(())
     }
@@ -326,7 +518,28 @@ impl Settings {
             ldap_uses_ad = $45, \
             ldap_user_rdn_attr = $46, \
             ldap_sync_groups = $47, \
-            openid_username_handling = $48 \
+            openid_username_handling = $48, \
+            smtp_auth_method = $49, \
+            smtp_oauth2_token_url = $50, \
+            smtp_oauth2_client_id = $51, \
+            smtp_oauth2_client_secret = $52, \
+            smtp_oauth2_scope = $53, \
+            self_registration_enabled = $54, \
+            self_registration_domains = $55, \
+            smtp_sender_name = $56, \
+            smtp_reply_to = $57, \
+            smtp_security_sender = $58, \
+            smtp_announcement_sender = $59, \
+            security_summary_enabled = $60, \
+            password_reset_token_lifetime = $61, \
+            password_reset_max_uses = $62, \
+            mail_backend = $63, \
+            mail_http_url = $64, \
+            mail_http_token = $65, \
+            default_mail_language = $66, \
+            notification_digest_window = $67, \
+            auditor_redact_emails = $68, \
+            auditor_redact_ip_addresses = $69 \
             WHERE id = 1",
             self.openid_enabled,
             self.wireguard_enabled,
@@ -376,6 +589,27 @@ impl Settings {
             self.ldap_user_rdn_attr,
             &self.ldap_sync_groups as &Vec<String>,
             &self.openid_username_handling as &OpenidUsernameHandling,
+            &self.smtp_auth_method as &SmtpAuthMethod,
+            self.smtp_oauth2_token_url,
+            self.smtp_oauth2_client_id,
+            &self.smtp_oauth2_client_secret as &Option<SecretStringWrapper>,
+            self.smtp_oauth2_scope,
+            self.self_registration_enabled,
+            &self.self_registration_domains as &Vec<String>,
+            self.smtp_sender_name,
+            self.smtp_reply_to,
+            self.smtp_security_sender,
+            self.smtp_announcement_sender,
+            self.security_summary_enabled,
+            self.password_reset_token_lifetime,
+            self.password_reset_max_uses,
+            &self.mail_backend as &MailBackend,
+            self.mail_http_url,
+            &self.mail_http_token as &Option<SecretStringWrapper>,
+            self.default_mail_language,
+            self.notification_digest_window,
+            self.auditor_redact_emails,
+            self.auditor_redact_ip_addresses,
         )
         .execute(executor)
         .await?;
@@ -415,17 +649,37 @@ impl Settings {
         Ok(())
     }
 
-    /// Check if all required SMTP options are configured.
-    /// User & password can be empty for no-auth servers.
+    /// Check if all required options of the selected mail backend are configured.
+    /// SMTP user & password can be empty for no-auth servers.
     ///
     /// Meant to be used to check if sending emails is enabled in current instance.
     #[must_use]
     pub fn smtp_configured(&self) -> bool {
-        self.smtp_server.is_some()
-            && self.smtp_port.is_some()
-            && self.smtp_sender.is_some()
-            && self.smtp_server != Some(String::new())
-            && self.smtp_sender != Some(String::new())
+        if self.smtp_sender.as_ref().is_none_or(String::is_empty) {
+            return false;
+        }
+        match self.mail_backend {
+            MailBackend::Smtp => {
+                self.smtp_server
+                    .as_ref()
+                    .is_some_and(|server| !server.is_empty())
+                    && self.smtp_port.is_some()
+            }
+            MailBackend::Http => self
+                .mail_http_url
+                .as_ref()
+                .is_some_and(|url| !url.is_empty()),
+        }
+    }
+
+    /// Lifetime of password reset links, `password_reset_token_timeout` from the server
+    /// configuration is used if it's not set.
+    #[must_use]
+    pub fn password_reset_token_timeout(&self) -> Duration {
+        self.password_reset_token_lifetime.map_or_else(
+            || *server_config().password_reset_token_timeout,
+            |minutes| Duration::from_secs(60 * u64::try_from(minutes).unwrap_or(0)),
+        )
     }
 
     #[must_use]
@@ -436,6 +690,17 @@ impl Settings {
     }
 }
 
+/// Basic check of an email address: non-empty local part and domain, without whitespace.
+#[must_use]
+pub fn is_valid_email_address(address: &str) -> bool {
+    address.split_once('@').is_some_and(|(user, domain)| {
+        !(user.is_empty()
+            || domain.is_empty()
+            || domain.contains('@')
+            || address.chars().any(char::is_whitespace))
+    })
+}
+
 #[derive(Serialize)]
 pub struct SettingsEssentials {
     pub instance_name: String,
@@ -546,6 +811,98 @@ mod test {
         assert!(settings.smtp_configured());
     }
 
+    #[test]
+    fn test_mail_http_config() {
+        let mut settings = Settings {
+            mail_backend: MailBackend::Http,
+            smtp_server: Some("localhost".into()),
+            smtp_port: Some(587),
+            smtp_sender: Some("no-reply@defguard.net".into()),
+            password_reset_max_uses: 1,
+            ..Default::default()
+        };
+        // SMTP server isn't used by HTTP backend
+        assert!(!settings.smtp_configured());
+
+        settings.mail_http_url = Some("https://api.mail.example.com/v3/send".into());
+        assert!(settings.smtp_configured());
+        assert!(settings.validate().is_ok());
+
+        settings.smtp_sender = None;
+        assert!(!settings.smtp_configured());
+
+        settings.mail_http_url = Some("ftp://mail.example.com".into());
+        assert!(matches!(
+            settings.validate(),
+            Err(SettingsValidationError::InvalidMailHttpUrl(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_smtp_senders() {
+        let mut settings = Settings {
+            smtp_sender: Some("no-reply@defguard.net".into()),
+            smtp_sender_name: Some("Defguard".into()),
+            smtp_reply_to: Some(String::new()),
+            smtp_security_sender: Some("security@defguard.net".into()),
+            password_reset_max_uses: 1,
+            ..Default::default()
+        };
+        assert!(settings.validate().is_ok());
+
+        settings.smtp_reply_to = Some("support".into());
+        assert!(matches!(
+            settings.validate(),
+            Err(SettingsValidationError::InvalidEmailAddress(_))
+        ));
+        settings.smtp_reply_to = None;
+
+        settings.smtp_announcement_sender = Some("news @defguard.net".into());
+        assert!(matches!(
+            settings.validate(),
+            Err(SettingsValidationError::InvalidEmailAddress(_))
+        ));
+        settings.smtp_announcement_sender = None;
+
+        settings.smtp_sender_name = Some("Defguard\r\nBcc: everyone@defguard.net".into());
+        assert!(matches!(
+            settings.validate(),
+            Err(SettingsValidationError::InvalidSenderName)
+        ));
+    }
+
+    #[test]
+    fn test_validate_password_reset() {
+        let mut settings = Settings {
+            password_reset_token_lifetime: Some(30),
+            password_reset_max_uses: 3,
+            ..Default::default()
+        };
+        assert!(settings.validate().is_ok());
+        assert_eq!(
+            settings.password_reset_token_timeout(),
+            Duration::from_secs(30 * 60)
+        );
+
+        settings.password_reset_token_lifetime = Some(0);
+        assert!(matches!(
+            settings.validate(),
+            Err(SettingsValidationError::InvalidPasswordResetLifetime)
+        ));
+        settings.password_reset_token_lifetime = Some(-5);
+        assert!(matches!(
+            settings.validate(),
+            Err(SettingsValidationError::InvalidPasswordResetLifetime)
+        ));
+        settings.password_reset_token_lifetime = None;
+
+        settings.password_reset_max_uses = 0;
+        assert!(matches!(
+            settings.validate(),
+            Err(SettingsValidationError::InvalidPasswordResetMaxUses)
+        ));
+    }
+
     #[test]
     fn dg25_32_test_dont_expose_license_key() {
         let key = "0000000000000000";
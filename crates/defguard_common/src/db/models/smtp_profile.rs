@@ -0,0 +1,207 @@
+use std::collections::BTreeMap;
+
+use model_derive::Model;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgExecutor, PgPool, Type, query, query_as};
+use tracing::debug;
+use utoipa::ToSchema;
+
+use crate::{
+    db::{Id, NoId, models::settings::SmtpEncryption},
+    global_value,
+    secret::SecretStringWrapper as SecretString,
+};
+
+global_value!(
+    SMTP_PROFILES,
+    BTreeMap<MailCategory, SmtpProfile<Id>>,
+    BTreeMap::new(),
+    set_smtp_profiles,
+    get_smtp_profiles
+);
+
+// separate module, as `global_value!` can only be used once in a module
+mod failover {
+    use super::SmtpProfile;
+    use crate::{db::Id, global_value};
+
+    global_value!(
+        FAILOVER_SMTP_PROFILE,
+        Option<SmtpProfile<Id>>,
+        None,
+        set_failover_smtp_profile,
+        get_failover_smtp_profile
+    );
+}
+
+pub use failover::{get_failover_smtp_profile, set_failover_smtp_profile};
+
+/// Category of a mail, determines its sender and SMTP profile used to send it.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    Eq,
+    Hash,
+    Ord,
+    PartialEq,
+    PartialOrd,
+    Serialize,
+    ToSchema,
+    Type,
+)]
+#[sqlx(type_name = "mail_category", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum MailCategory {
+    /// Account and administrative mails, sent from the default sender.
+    #[default]
+    General,
+    /// MFA codes, password resets and other security-related mails.
+    Security,
+    /// Announcements broadcast to users.
+    Announcement,
+    /// Notifications sent to admins, e.g. gateway disconnection alerts.
+    Alert,
+    /// Enrollment and desktop client activation mails sent to users.
+    Enrollment,
+}
+
+/// SMTP configuration used instead of the one from settings for mail categories bound to it.
+/// Only password authentication is supported.
+#[derive(Clone, Debug, Model)]
+#[table(smtp_profile)]
+pub struct SmtpProfile<I = NoId> {
+    pub id: I,
+    pub name: String,
+    pub server: String,
+    pub port: i32,
+    #[model(enum)]
+    pub encryption: SmtpEncryption,
+    pub username: Option<String>,
+    #[model(secret)]
+    pub password: Option<SecretString>,
+    pub sender: String,
+    pub sender_name: Option<String>,
+    pub reply_to: Option<String>,
+    /// Used in place of the SMTP server from settings while it keeps failing
+    pub failover: bool,
+}
+
+impl SmtpProfile<Id> {
+    pub async fn find_by_name<'e, E>(executor: E, name: &str) -> Result<Option<Self>, sqlx::Error>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, name, server, port, encryption \"encryption: SmtpEncryption\", username, \
+            password \"password?: SecretString\", sender, sender_name, reply_to, failover \
+            FROM smtp_profile WHERE name = $1",
+            name
+        )
+        .fetch_optional(executor)
+        .await
+    }
+
+    /// Mail categories bound to this profile.
+    pub async fn categories<'e, E>(&self, executor: E) -> Result<Vec<MailCategory>, sqlx::Error>
+    where
+        E: PgExecutor<'e>,
+    {
+        let rows = query!(
+            "SELECT category \"category: MailCategory\" FROM smtp_profile_category \
+            WHERE profile_id = $1 ORDER BY category",
+            self.id
+        )
+        .fetch_all(executor)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| row.category).collect())
+    }
+
+    /// Bind given mail categories to this profile, replacing its previous bindings. Categories
+    /// bound to other profiles are moved to this one.
+    pub async fn set_categories(
+        &self,
+        conn: &mut PgConnection,
+        categories: &[MailCategory],
+    ) -> Result<(), sqlx::Error> {
+        query!(
+            "DELETE FROM smtp_profile_category WHERE profile_id = $1",
+            self.id
+        )
+        .execute(&mut *conn)
+        .await?;
+        for category in categories {
+            query!(
+                "INSERT INTO smtp_profile_category (category, profile_id) VALUES ($1, $2) \
+                ON CONFLICT (category) DO UPDATE SET profile_id = EXCLUDED.profile_id",
+                category as &MailCategory,
+                self.id
+            )
+            .execute(&mut *conn)
+            .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Make sure no profile is used for failover, so another one can be.
+pub async fn clear_failover_smtp_profile<'e, E>(executor: E) -> Result<(), sqlx::Error>
+where
+    E: PgExecutor<'e>,
+{
+    query!("UPDATE smtp_profile SET failover = false WHERE failover")
+        .execute(executor)
+        .await?;
+
+    Ok(())
+}
+
+/// Mail categories bound to SMTP profiles, along with profile IDs.
+pub async fn smtp_profile_categories<'e, E>(
+    executor: E,
+) -> Result<Vec<(MailCategory, Id)>, sqlx::Error>
+where
+    E: PgExecutor<'e>,
+{
+    let rows = query!(
+        "SELECT category \"category: MailCategory\", profile_id FROM smtp_profile_category \
+        ORDER BY category"
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| (row.category, row.profile_id))
+        .collect())
+}
+
+/// Load SMTP profiles bound to mail categories from the DB into the global `SMTP_PROFILES` map,
+/// along with the failover profile.
+///
+/// Has to be called again after profiles or their bindings are modified.
+pub async fn initialize_smtp_profiles(pool: &PgPool) -> Result<(), sqlx::Error> {
+    debug!("Initializing global SMTP profiles");
+    let profiles: BTreeMap<Id, SmtpProfile<Id>> = SmtpProfile::all(pool)
+        .await?
+        .into_iter()
+        .map(|profile| (profile.id, profile))
+        .collect();
+    set_failover_smtp_profile(profiles.values().find(|profile| profile.failover).cloned());
+    let bound = smtp_profile_categories(pool)
+        .await?
+        .into_iter()
+        .filter_map(|(category, profile_id)| {
+            profiles
+                .get(&profile_id)
+                .map(|profile| (category, profile.clone()))
+        })
+        .collect();
+    set_smtp_profiles(bound);
+    Ok(())
+}
@@ -0,0 +1,156 @@
+use std::time::Duration;
+
+use defguard_common::db::Id;
+use defguard_mail::{
+    Mail, MailCategory,
+    templates::{self, TemplateError, insert_mail_variables, safe_tera},
+};
+use sqlx::PgPool;
+use tera::Context;
+use tokio::{
+    sync::mpsc::{UnboundedSender, unbounded_channel},
+    time::sleep,
+};
+
+use crate::{
+    db::models::announcement::{Announcement, AnnouncementDelivery, AnnouncementRecipient},
+    server_config,
+};
+
+// How long to sleep between loop iterations
+const ANNOUNCEMENT_LOOP_SLEEP: Duration = Duration::from_secs(30);
+
+/// Rendered announcement subject and mail content.
+pub(crate) struct RenderedAnnouncement {
+    pub subject: String,
+    pub content: String,
+}
+
+/// Render announcement subject and body as templates for a given recipient.
+///
+/// Available template variables:
+/// - first_name
+/// - last_name
+/// - username
+/// - email
+/// - defguard_url
+/// - instance (mail variables)
+pub(crate) fn render_announcement(
+    subject: &str,
+    body: &str,
+    recipient: &AnnouncementRecipient,
+) -> Result<RenderedAnnouncement, TemplateError> {
+    let mut tera = safe_tera();
+    tera.add_raw_template("announcement_subject", subject)?;
+    tera.add_raw_template("announcement_body", body)?;
+
+    let mut context = Context::new();
+    context.insert("first_name", &recipient.first_name);
+    context.insert("last_name", &recipient.last_name);
+    context.insert("username", &recipient.username);
+    context.insert("email", &recipient.email);
+    context.insert("defguard_url", &server_config().url);
+    insert_mail_variables(&mut context);
+
+    let content = tera.render("announcement_body", &context)?;
+    Ok(RenderedAnnouncement {
+        subject: tera.render("announcement_subject", &context)?,
+        content: templates::announcement_mail(&content)?,
+    })
+}
+
+/// Send announcement to all users matching its segment filters.
+///
+/// Delivery status is recorded for each recipient once the mail handler reports the result.
+/// Announcements which have already been sent are skipped.
+pub async fn send_announcement(
+    pool: &PgPool,
+    mail_tx: &UnboundedSender<Mail>,
+    mut announcement: Announcement<Id>,
+) -> Result<(), sqlx::Error> {
+    if !announcement.claim(pool).await? {
+        debug!("Announcement {} has already been sent", announcement.id);
+        return Ok(());
+    }
+
+    let recipients = announcement.recipients(pool).await?;
+    info!(
+        "Sending announcement {} \"{}\" to {} recipients",
+        announcement.id,
+        announcement.subject,
+        recipients.len()
+    );
+    for recipient in recipients {
+        let mut delivery =
+            AnnouncementDelivery::new(announcement.id, recipient.id, recipient.email.clone())
+                .save(pool)
+                .await?;
+        let rendered =
+            match render_announcement(&announcement.subject, &announcement.body, &recipient) {
+                Ok(rendered) => rendered,
+                Err(err) => {
+                    warn!(
+                        "Failed to render announcement {} for user {}: {err}",
+                        announcement.id, recipient.username
+                    );
+                    delivery.set_result(pool, Some(err.to_string())).await?;
+                    continue;
+                }
+            };
+
+        let (tx, mut rx) = unbounded_channel();
+        let mail = Mail {
+            to: recipient.email,
+            subject: rendered.subject,
+            content: rendered.content,
+            attachments: Vec::new(),
+            category: MailCategory::Announcement,
+            result_tx: Some(tx),
+        };
+        if let Err(err) = mail_tx.send(mail) {
+            error!(
+                "Failed to send announcement {} to user {}: {err}",
+                announcement.id, recipient.username
+            );
+            delivery.set_result(pool, Some(err.to_string())).await?;
+            continue;
+        }
+
+        // record delivery result in the background, as mails are throttled by the mail handler
+        let pool = pool.clone();
+        tokio::spawn(async move {
+            let error = match rx.recv().await {
+                Some(Ok(_)) => None,
+                Some(Err(err)) => Some(err.to_string()),
+                None => Some("Mail has not been sent".to_string()),
+            };
+            if let Err(err) = delivery.set_result(&pool, error).await {
+                error!(
+                    "Failed to record announcement delivery {}: {err}",
+                    delivery.id
+                );
+            }
+        });
+    }
+
+    Ok(())
+}
+
+/// Periodically sends scheduled announcements which are due.
+#[instrument(skip_all)]
+pub async fn run_announcement_scheduler(
+    pool: PgPool,
+    mail_tx: UnboundedSender<Mail>,
+) -> Result<(), sqlx::Error> {
+    info!("Starting announcement scheduler");
+
+    loop {
+        debug!("Checking for scheduled announcements");
+        for announcement in Announcement::all_due(&pool).await? {
+            send_announcement(&pool, &mail_tx, announcement).await?;
+        }
+
+        // wait till next iteration
+        sleep(ANNOUNCEMENT_LOOP_SLEEP).await;
+    }
+}
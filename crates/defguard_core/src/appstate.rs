@@ -1,24 +1,31 @@
-use std::sync::{Arc, Mutex, RwLock};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, RwLock},
+    time::Duration,
+};
 
 use axum::extract::FromRef;
 use axum_extra::extract::cookie::Key;
-use defguard_common::config::server_config;
+use chrono::Utc;
+use defguard_common::{config::server_config, db::Id};
 use defguard_mail::Mail;
-use reqwest::Client;
+use reqwest::{Client, StatusCode, header::CONTENT_TYPE};
 use secrecy::ExposeSecret;
 use serde_json::json;
 use sqlx::PgPool;
 use tokio::{
     sync::{
         broadcast::Sender,
-        mpsc::{UnboundedReceiver, UnboundedSender},
+        mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel},
     },
     task::spawn,
+    time::sleep,
 };
 use webauthn_rs::prelude::*;
 
 use crate::{
     auth::failed_login::FailedLoginMap,
+    background_jobs::JobRunner,
     db::{AppEvent, GatewayEvent, WebHook},
     error::WebError,
     events::ApiEvent,
@@ -27,6 +34,17 @@ use crate::{
 };
 
 const X_DEFGUARD_EVENT: &str = "x-defguard-event";
+const X_DEFGUARD_TIMESTAMP: &str = "x-defguard-timestamp";
+const X_DEFGUARD_SIGNATURE: &str = "x-defguard-signature";
+const WEBHOOK_MAX_ATTEMPTS: u32 = 5;
+const WEBHOOK_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Webhook request waiting to be sent.
+struct WebhookDelivery {
+    webhook: WebHook<Id>,
+    event: &'static str,
+    body: String,
+}
 
 #[derive(Clone)]
 pub struct AppState {
@@ -39,6 +57,7 @@ pub struct AppState {
     key: Key,
     pub event_tx: UnboundedSender<ApiEvent>,
     pub incompatible_components: Arc<RwLock<IncompatibleComponents>>,
+    pub jobs: JobRunner,
 }
 
 impl AppState {
@@ -53,6 +72,9 @@ impl AppState {
     /// Handle webhook events
     async fn handle_triggers(pool: PgPool, mut rx: UnboundedReceiver<AppEvent>) {
         let reqwest_client = Client::builder().user_agent("reqwest").build().unwrap();
+        // requests are queued per webhook, so each webhook receives events in order,
+        // even if some requests have to be retried
+        let mut queues: HashMap<Id, UnboundedSender<WebhookDelivery>> = HashMap::new();
         while let Some(msg) = rx.recv().await {
             debug!("WebHook triggered");
             debug!("Retrieving webhooks");
@@ -65,21 +87,27 @@ impl AppState {
                         (json!({ "username": username }), "user_deleted")
                     }
                     AppEvent::HWKeyProvision(data) => (json!(data), "user_keys"),
+                    AppEvent::EnrollmentCompleted(data) => (json!(data), "enrollment_completed"),
                 };
                 for webhook in webhooks {
-                    match reqwest_client
-                        .post(&webhook.url)
-                        .bearer_auth(&webhook.token)
-                        .header(X_DEFGUARD_EVENT, event)
-                        .json(&payload)
-                        .send()
-                        .await
-                    {
-                        Ok(res) => {
-                            info!("Trigger sent to {}, status {}", webhook.url, res.status());
+                    match webhook.payload(event, &payload) {
+                        Ok(body) => {
+                            let queue = queues.entry(webhook.id).or_insert_with(|| {
+                                let (tx, rx) = unbounded_channel();
+                                spawn(Self::send_queued_triggers(reqwest_client.clone(), rx));
+                                tx
+                            });
+                            let url = webhook.url.clone();
+                            if let Err(err) = queue.send(WebhookDelivery {
+                                webhook,
+                                event,
+                                body,
+                            }) {
+                                error!("Failed to queue trigger for {url}: {err}");
+                            }
                         }
                         Err(err) => {
-                            error!("Error sending trigger to {}: {err}", webhook.url);
+                            error!("Failed to render payload for {}: {err}", webhook.url);
                         }
                     }
                 }
@@ -87,6 +115,69 @@ impl AppState {
         }
     }
 
+    /// Send queued requests of a single webhook one after another.
+    async fn send_queued_triggers(client: Client, mut rx: UnboundedReceiver<WebhookDelivery>) {
+        while let Some(delivery) = rx.recv().await {
+            Self::send_trigger(&client, delivery).await;
+        }
+    }
+
+    /// Send signed webhook request, retrying with exponential backoff on connection errors
+    /// and server errors.
+    async fn send_trigger(client: &Client, delivery: WebhookDelivery) {
+        let WebhookDelivery {
+            webhook,
+            event,
+            body,
+        } = delivery;
+        let mut delay = WEBHOOK_RETRY_DELAY;
+        for attempt in 1..=WEBHOOK_MAX_ATTEMPTS {
+            let timestamp = Utc::now().timestamp();
+            match client
+                .post(&webhook.url)
+                .bearer_auth(&webhook.token)
+                .header(X_DEFGUARD_EVENT, event)
+                .header(X_DEFGUARD_TIMESTAMP, timestamp.to_string())
+                .header(X_DEFGUARD_SIGNATURE, webhook.signature(timestamp, &body))
+                .header(CONTENT_TYPE, "application/json")
+                .body(body.clone())
+                .send()
+                .await
+            {
+                Ok(res)
+                    if !res.status().is_server_error()
+                        && res.status() != StatusCode::TOO_MANY_REQUESTS =>
+                {
+                    info!("Trigger sent to {}, status {}", webhook.url, res.status());
+                    return;
+                }
+                Ok(res) => {
+                    warn!(
+                        "Trigger sent to {} failed with status {}, attempt \
+                        {attempt}/{WEBHOOK_MAX_ATTEMPTS}",
+                        webhook.url,
+                        res.status()
+                    );
+                }
+                Err(err) => {
+                    warn!(
+                        "Error sending trigger to {}, attempt {attempt}/{WEBHOOK_MAX_ATTEMPTS}: \
+                        {err}",
+                        webhook.url
+                    );
+                }
+            }
+            if attempt < WEBHOOK_MAX_ATTEMPTS {
+                sleep(delay).await;
+                delay *= 2;
+            }
+        }
+        error!(
+            "Giving up sending trigger {event} to {} after {WEBHOOK_MAX_ATTEMPTS} attempts",
+            webhook.url
+        );
+    }
+
     /// Sends given `GatewayEvent` to be handled by gateway GRPC server.
     /// Convenience wrapper around [`send_wireguard_event`]
     pub fn send_wireguard_event(&self, event: GatewayEvent) {
@@ -135,6 +226,7 @@ impl AppState {
         );
 
         let key = Key::from(config.secret_key.expose_secret().as_bytes());
+        let jobs = JobRunner::new(pool.clone());
 
         Self {
             pool,
@@ -146,6 +238,7 @@ impl AppState {
             key,
             event_tx,
             incompatible_components,
+            jobs,
         }
     }
 }
@@ -206,6 +206,8 @@ macro_rules! role {
 }
 
 role!(AdminRole, Permission::IsAdmin);
+role!(HelpdeskRole, Permission::IsHelpdesk Permission::IsAdmin);
+role!(AuditorRole, Permission::IsAuditor Permission::IsAdmin);
 
 #[derive(Debug)]
 pub(crate) struct UserClaims {
@@ -277,6 +279,19 @@ impl UserClaims {
             sub: user.username.clone(),
         }
     }
+
+    /// Removes standard claim `claim` and returns its value, e.g. to emit it under another name.
+    pub fn take(&mut self, claim: &str) -> Option<String> {
+        match claim {
+            "email" => self.email.take(),
+            "family_name" => self.family_name.take(),
+            "given_name" => self.given_name.take(),
+            "name" => self.name.take(),
+            "phone_number" => self.phone_number.take(),
+            "preferred_username" => self.preferred_username.take(),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,199 @@
+//! This module implements a framework for long-running operations. Instead of blocking an API
+//! request, handlers spawn a background job and return its id immediately. Clients poll job
+//! status and progress through `/api/v1/jobs/{id}` and may cancel unfinished jobs.
+//!
+//! Job state is stored in the database, so it survives Core restarts; jobs interrupted by a
+//! restart are marked as failed on startup.
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use defguard_common::db::Id;
+use serde_json::Value;
+use sqlx::{Error as SqlxError, PgPool};
+use tokio::spawn;
+use tokio_util::sync::CancellationToken;
+
+use crate::db::models::background_job::{BackgroundJob, BackgroundJobStatus};
+
+/// Handle passed to a running job, used to report its progress.
+#[derive(Clone)]
+pub struct JobContext {
+    pub id: Id,
+    pool: PgPool,
+}
+
+impl JobContext {
+    /// Update number of processed items and total number of items, if known.
+    pub async fn set_progress(&self, progress: i32, total: Option<i32>) {
+        if let Err(err) = BackgroundJob::set_progress(&self.pool, self.id, progress, total).await {
+            warn!(
+                "Failed to update progress of background job {}: {err}",
+                self.id
+            );
+        }
+    }
+}
+
+/// Spawns background jobs and keeps track of running ones, so they can be cancelled.
+#[derive(Clone)]
+pub struct JobRunner {
+    pool: PgPool,
+    running: Arc<Mutex<HashMap<Id, CancellationToken>>>,
+}
+
+impl JobRunner {
+    #[must_use]
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            running: Arc::default(),
+        }
+    }
+
+    /// Create a job of a given kind and run `operation` in background.
+    /// The value returned by `operation` is stored as the job result.
+    pub async fn spawn<F, Fut>(
+        &self,
+        kind: &str,
+        created_by: Option<Id>,
+        operation: F,
+    ) -> Result<BackgroundJob, SqlxError>
+    where
+        F: FnOnce(JobContext) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<Value, anyhow::Error>> + Send + 'static,
+    {
+        let job = BackgroundJob::create(&self.pool, kind, created_by).await?;
+        let token = CancellationToken::new();
+        self.running
+            .lock()
+            .expect("Failed to lock running jobs")
+            .insert(job.id, token.clone());
+
+        let context = JobContext {
+            id: job.id,
+            pool: self.pool.clone(),
+        };
+        let running = Arc::clone(&self.running);
+        let pool = self.pool.clone();
+        let id = job.id;
+        let kind = job.kind.clone();
+        spawn(async move {
+            if let Err(err) = BackgroundJob::start(&pool, id).await {
+                error!("Failed to start background job {id} ({kind}): {err}");
+            }
+            debug!("Started background job {id} ({kind})");
+            // the operation is dropped at its next await point once the job is cancelled
+            let (status, message, result) = tokio::select! {
+                () = token.cancelled() => (BackgroundJobStatus::Cancelled, None, None),
+                outcome = operation(context) => match outcome {
+                    Ok(result) => (BackgroundJobStatus::Succeeded, None, Some(result)),
+                    Err(err) => {
+                        error!("Background job {id} ({kind}) failed: {err}");
+                        (BackgroundJobStatus::Failed, Some(err.to_string()), None)
+                    }
+                },
+            };
+            running
+                .lock()
+                .expect("Failed to lock running jobs")
+                .remove(&id);
+            if let Err(err) = BackgroundJob::finish(&pool, id, status, message, result).await {
+                error!("Failed to store result of background job {id} ({kind}): {err}");
+            }
+            info!("Background job {id} ({kind}) finished with status {status:?}");
+        });
+        info!("Spawned background job {} ({})", job.id, job.kind);
+
+        Ok(job)
+    }
+
+    /// Request cancellation of a running job. Returns `false` if the job isn't running.
+    pub fn cancel(&self, id: Id) -> bool {
+        let running = self.running.lock().expect("Failed to lock running jobs");
+        if let Some(token) = running.get(&id) {
+            token.cancel();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use defguard_common::db::setup_pool;
+    use serde_json::json;
+    use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+    use tokio::time::sleep;
+
+    use super::*;
+
+    async fn wait_for_finish(pool: &PgPool, id: Id) -> BackgroundJob {
+        loop {
+            let job = BackgroundJob::find_by_id(pool, id).await.unwrap().unwrap();
+            if job.is_finished() {
+                return job;
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_background_jobs(_: PgPoolOptions, options: PgConnectOptions) {
+        let pool = setup_pool(options).await;
+        let runner = JobRunner::new(pool.clone());
+
+        // successful job with progress
+        let job = runner
+            .spawn("test", None, |context| async move {
+                context.set_progress(2, Some(2)).await;
+                Ok(json!({"processed": 2}))
+            })
+            .await
+            .unwrap();
+        assert_eq!(job.status, BackgroundJobStatus::Pending);
+        let job = wait_for_finish(&pool, job.id).await;
+        assert_eq!(job.status, BackgroundJobStatus::Succeeded);
+        assert_eq!(job.progress, 2);
+        assert_eq!(job.total, Some(2));
+        assert_eq!(job.result, Some(json!({"processed": 2})));
+        assert!(job.started_at.is_some());
+        assert!(!runner.cancel(job.id));
+
+        // failed job
+        let job = runner
+            .spawn("test", None, |_| async { Err(anyhow::anyhow!("broken")) })
+            .await
+            .unwrap();
+        let job = wait_for_finish(&pool, job.id).await;
+        assert_eq!(job.status, BackgroundJobStatus::Failed);
+        assert_eq!(job.message.as_deref(), Some("broken"));
+
+        // cancelled job
+        let job = runner
+            .spawn("test", None, |_| async {
+                sleep(Duration::from_secs(60)).await;
+                Ok(Value::Null)
+            })
+            .await
+            .unwrap();
+        assert!(runner.cancel(job.id));
+        let job = wait_for_finish(&pool, job.id).await;
+        assert_eq!(job.status, BackgroundJobStatus::Cancelled);
+
+        // interrupted jobs
+        let job = BackgroundJob::create(&pool, "test", None).await.unwrap();
+        assert_eq!(BackgroundJob::fail_interrupted(&pool).await.unwrap(), 1);
+        let job = BackgroundJob::find_by_id(&pool, job.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(job.status, BackgroundJobStatus::Failed);
+    }
+}
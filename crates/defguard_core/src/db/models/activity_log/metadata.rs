@@ -2,15 +2,21 @@ use chrono::NaiveDateTime;
 use defguard_common::db::{
     Id,
     models::{
-        AuthenticationKey, AuthenticationKeyType, MFAMethod, Settings,
-        settings::{LdapSyncStatus, OpenidUsernameHandling, SmtpEncryption},
+        AuthenticationKey, AuthenticationKeyType, MFAMethod, MailContext, MailTemplate,
+        MailVariable, Settings,
+        settings::{
+            LdapSyncStatus, MailBackend, OpenidUsernameHandling, SmtpAuthMethod, SmtpEncryption,
+        },
     },
 };
 
 use crate::{
     db::{
         Device, Group, User, WebAuthn, WebHook, WireguardNetwork,
-        models::oauth2client::OAuth2Client,
+        models::{
+            announcement::Announcement, itsm::ItsmConnector, oauth2client::OAuth2Client,
+            route::Route, service_account::ServiceAccountInfo,
+        },
     },
     enterprise::db::models::{
         activity_log_stream::{ActivityLogStream, ActivityLogStreamType},
@@ -84,6 +90,13 @@ pub struct DeviceMetadata {
     pub device: Device<Id>,
 }
 
+#[derive(Serialize)]
+pub struct DeviceQuarantinedMetadata {
+    pub owner: UserNoSecrets,
+    pub device: Device<Id>,
+    pub reason: Option<String>,
+}
+
 #[derive(Serialize)]
 pub struct DeviceModifiedMetadata {
     pub owner: UserNoSecrets,
@@ -183,6 +196,7 @@ pub struct VpnClientMfaMetadata {
     pub location: WireguardNetwork<Id>,
     pub device: Device<Id>,
     pub method: ClientMFAMethod,
+    pub remembered: bool,
 }
 
 #[derive(Serialize)]
@@ -214,6 +228,12 @@ pub struct VpnLocationModifiedMetadata {
     pub after: WireguardNetwork<Id>,
 }
 
+#[derive(Serialize)]
+pub struct VpnLocationFirewallRolledBackMetadata {
+    pub location: WireguardNetwork<Id>,
+    pub version: i64,
+}
+
 #[derive(Serialize)]
 pub struct ApiTokenMetadata {
     pub owner: UserNoSecrets,
@@ -356,12 +376,26 @@ pub struct SettingsNoSecrets {
     pub smtp_encryption: SmtpEncryption,
     pub smtp_user: Option<String>,
     pub smtp_sender: Option<String>,
+    pub smtp_auth_method: SmtpAuthMethod,
+    pub smtp_oauth2_token_url: Option<String>,
+    pub smtp_oauth2_client_id: Option<String>,
+    pub smtp_oauth2_scope: Option<String>,
+    pub smtp_sender_name: Option<String>,
+    pub smtp_reply_to: Option<String>,
+    pub smtp_security_sender: Option<String>,
+    pub smtp_announcement_sender: Option<String>,
+    pub mail_backend: MailBackend,
+    pub mail_http_url: Option<String>,
+    pub default_mail_language: String,
     // Enrollment
     pub enrollment_vpn_step_optional: bool,
     pub enrollment_welcome_message: Option<String>,
     pub enrollment_welcome_email: Option<String>,
     pub enrollment_welcome_email_subject: Option<String>,
     pub enrollment_use_welcome_message_as_email: bool,
+    // Self-registration
+    pub self_registration_enabled: bool,
+    pub self_registration_domains: Vec<String>,
     // LDAP
     pub ldap_url: Option<String>,
     pub ldap_bind_username: Option<String>,
@@ -396,6 +430,12 @@ pub struct SettingsNoSecrets {
     pub gateway_disconnect_notifications_enabled: bool,
     pub gateway_disconnect_notifications_inactivity_threshold: i32,
     pub gateway_disconnect_notifications_reconnect_notification_enabled: bool,
+    pub security_summary_enabled: bool,
+    pub notification_digest_window: i32,
+    pub auditor_redact_emails: bool,
+    pub auditor_redact_ip_addresses: bool,
+    pub password_reset_token_lifetime: Option<i32>,
+    pub password_reset_max_uses: i32,
 }
 
 impl From<Settings> for SettingsNoSecrets {
@@ -414,11 +454,24 @@ impl From<Settings> for SettingsNoSecrets {
             smtp_encryption: value.smtp_encryption,
             smtp_user: value.smtp_user,
             smtp_sender: value.smtp_sender,
+            smtp_auth_method: value.smtp_auth_method,
+            smtp_oauth2_token_url: value.smtp_oauth2_token_url,
+            smtp_oauth2_client_id: value.smtp_oauth2_client_id,
+            smtp_oauth2_scope: value.smtp_oauth2_scope,
+            smtp_sender_name: value.smtp_sender_name,
+            smtp_reply_to: value.smtp_reply_to,
+            smtp_security_sender: value.smtp_security_sender,
+            smtp_announcement_sender: value.smtp_announcement_sender,
+            mail_backend: value.mail_backend,
+            mail_http_url: value.mail_http_url,
+            default_mail_language: value.default_mail_language,
             enrollment_vpn_step_optional: value.enrollment_vpn_step_optional,
             enrollment_welcome_message: value.enrollment_welcome_message,
             enrollment_welcome_email: value.enrollment_welcome_email,
             enrollment_welcome_email_subject: value.enrollment_welcome_email_subject,
             enrollment_use_welcome_message_as_email: value.enrollment_use_welcome_message_as_email,
+            self_registration_enabled: value.self_registration_enabled,
+            self_registration_domains: value.self_registration_domains,
             ldap_url: value.ldap_url,
             ldap_bind_username: value.ldap_bind_username,
             ldap_group_search_base: value.ldap_group_search_base,
@@ -449,6 +502,12 @@ impl From<Settings> for SettingsNoSecrets {
                 .gateway_disconnect_notifications_inactivity_threshold,
             gateway_disconnect_notifications_reconnect_notification_enabled: value
                 .gateway_disconnect_notifications_reconnect_notification_enabled,
+            security_summary_enabled: value.security_summary_enabled,
+            notification_digest_window: value.notification_digest_window,
+            auditor_redact_emails: value.auditor_redact_emails,
+            auditor_redact_ip_addresses: value.auditor_redact_ip_addresses,
+            password_reset_token_lifetime: value.password_reset_token_lifetime,
+            password_reset_max_uses: value.password_reset_max_uses,
         }
     }
 }
@@ -499,6 +558,65 @@ pub struct WebHookStateChangedMetadata {
     pub enabled: bool,
 }
 
+#[derive(Serialize)]
+pub struct AnnouncementMetadata {
+    pub announcement: Announcement<Id>,
+}
+
+#[derive(Serialize)]
+pub struct ServiceAccountMetadata {
+    pub service_account: ServiceAccountInfo,
+}
+
+#[derive(Serialize)]
+pub struct ServiceAccountModifiedMetadata {
+    pub before: ServiceAccountInfo,
+    pub after: ServiceAccountInfo,
+}
+
+#[derive(Serialize)]
+pub struct ItsmConnectorMetadata {
+    pub connector: ItsmConnector<Id>,
+}
+
+#[derive(Serialize)]
+pub struct ItsmConnectorModifiedMetadata {
+    pub before: ItsmConnector<Id>,
+    pub after: ItsmConnector<Id>,
+}
+
+#[derive(Serialize)]
+pub struct MailVariableMetadata {
+    pub variable: MailVariable<Id>,
+}
+
+#[derive(Serialize)]
+pub struct MailVariableModifiedMetadata {
+    pub before: MailVariable<Id>,
+    pub after: MailVariable<Id>,
+}
+
+#[derive(Serialize)]
+pub struct MailContextMetadata {
+    pub translation: MailContext<Id>,
+}
+
+#[derive(Serialize)]
+pub struct MailTemplateMetadata {
+    pub template: MailTemplate<Id>,
+}
+
+#[derive(Serialize)]
+pub struct RouteMetadata {
+    pub route: Route<Id>,
+}
+
+#[derive(Serialize)]
+pub struct RouteModifiedMetadata {
+    pub before: Route<Id>,
+    pub after: Route<Id>,
+}
+
 #[derive(Serialize)]
 pub struct AuthenticationKeyMetadata {
     pub key: AuthenticationKeyNoSecrets,
@@ -42,16 +42,24 @@ pub enum EventType {
     MfaSecurityKeyRemoved,
     // user management
     UserAdded,
+    UserSelfRegistered,
     UserRemoved,
     UserModified,
     UserGroupsModified,
     PasswordChanged,
     PasswordChangedByAdmin,
     PasswordReset,
+    HelpdeskPasswordResetRequested,
+    HelpdeskPasswordResetConfirmed,
     // device management
     DeviceAdded,
     DeviceRemoved,
     DeviceModified,
+    DeviceDisconnected,
+    DeviceQuarantined,
+    DeviceReleasedFromQuarantine,
+    DeviceApproved,
+    DeviceRejected,
     NetworkDeviceAdded,
     NetworkDeviceRemoved,
     NetworkDeviceModified,
@@ -72,6 +80,7 @@ pub enum EventType {
     VpnLocationAdded,
     VpnLocationRemoved,
     VpnLocationModified,
+    VpnLocationFirewallRolledBack,
     // VPN client events
     VpnClientConnected,
     VpnClientDisconnected,
@@ -80,6 +89,9 @@ pub enum EventType {
     VpnClientMfaFailed,
     // Enrollment events
     EnrollmentTokenAdded,
+    EnrollmentTokenResent,
+    EnrollmentTokenExtended,
+    EnrollmentTokenRevoked,
     EnrollmentStarted,
     EnrollmentDeviceAdded,
     EnrollmentCompleted,
@@ -107,6 +119,31 @@ pub enum EventType {
     WebHookModified,
     WebHookRemoved,
     WebHookStateChanged,
+    // Announcements
+    AnnouncementCreated,
+    AnnouncementRemoved,
+    // Service accounts management
+    ServiceAccountAdded,
+    ServiceAccountModified,
+    ServiceAccountRemoved,
+    // ITSM connectors management
+    ItsmConnectorAdded,
+    ItsmConnectorModified,
+    ItsmConnectorRemoved,
+    // Mail variables management
+    MailVariableAdded,
+    MailVariableModified,
+    MailVariableRemoved,
+    // Mail template translations management
+    MailContextModified,
+    MailContextRemoved,
+    // Mail template overrides management
+    MailTemplateModified,
+    MailTemplateRestored,
+    // Routes management
+    RouteAdded,
+    RouteModified,
+    RouteRemoved,
     // Authentication key management
     AuthenticationKeyAdded,
     AuthenticationKeyRemoved,
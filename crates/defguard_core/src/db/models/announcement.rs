@@ -0,0 +1,203 @@
+use chrono::{NaiveDateTime, Utc};
+use defguard_common::db::{Id, NoId};
+use model_derive::Model;
+use sqlx::{Error as SqlxError, PgExecutor, query, query_as};
+use utoipa::ToSchema;
+
+/// Announcement email sent to a segment of users.
+///
+/// Recipients are selected when the announcement is sent: users must belong to one of
+/// `group_ids` and have a device in one of `location_ids` (empty lists don't filter),
+/// and match `user_active` if set.
+#[derive(Clone, Debug, Deserialize, Model, PartialEq, Serialize, ToSchema)]
+#[table(announcement)]
+pub struct Announcement<I = NoId> {
+    pub id: I,
+    pub subject: String,
+    /// Markdown content, rendered as a template for each recipient
+    pub body: String,
+    #[model(ref)]
+    pub group_ids: Vec<Id>,
+    #[model(ref)]
+    pub location_ids: Vec<Id>,
+    pub user_active: Option<bool>,
+    pub scheduled_at: NaiveDateTime,
+    pub sent_at: Option<NaiveDateTime>,
+    pub created_by: Option<Id>,
+    pub created_at: NaiveDateTime,
+}
+
+/// User matching announcement segment filters.
+#[derive(Debug)]
+pub struct AnnouncementRecipient {
+    pub id: Id,
+    pub username: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: String,
+}
+
+impl Announcement {
+    #[must_use]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        subject: String,
+        body: String,
+        group_ids: Vec<Id>,
+        location_ids: Vec<Id>,
+        user_active: Option<bool>,
+        scheduled_at: Option<NaiveDateTime>,
+        created_by: Id,
+    ) -> Self {
+        let now = Utc::now().naive_utc();
+        Self {
+            id: NoId,
+            subject,
+            body,
+            group_ids,
+            location_ids,
+            user_active,
+            scheduled_at: scheduled_at.unwrap_or(now),
+            sent_at: None,
+            created_by: Some(created_by),
+            created_at: now,
+        }
+    }
+}
+
+impl Announcement<Id> {
+    /// Announcements which are due to be sent.
+    pub(crate) async fn all_due<'e, E>(executor: E) -> Result<Vec<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, subject, body, group_ids, location_ids, user_active, scheduled_at, \
+            sent_at, created_by, created_at FROM announcement \
+            WHERE sent_at IS NULL AND scheduled_at <= $1 ORDER BY scheduled_at",
+            Utc::now().naive_utc()
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    /// Users matching segment filters of this announcement.
+    pub async fn recipients<'e, E>(
+        &self,
+        executor: E,
+    ) -> Result<Vec<AnnouncementRecipient>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            AnnouncementRecipient,
+            "SELECT u.id, u.username, u.first_name, u.last_name, u.email FROM \"user\" u \
+            WHERE ($1::bool IS NULL OR u.is_active = $1) \
+            AND (cardinality($2::bigint[]) = 0 OR EXISTS (\
+                SELECT 1 FROM group_user gu WHERE gu.user_id = u.id AND gu.group_id = ANY($2))) \
+            AND (cardinality($3::bigint[]) = 0 OR EXISTS (\
+                SELECT 1 FROM device d \
+                JOIN wireguard_network_device wnd ON wnd.device_id = d.id \
+                WHERE d.user_id = u.id AND wnd.wireguard_network_id = ANY($3))) \
+            ORDER BY u.id",
+            self.user_active,
+            &self.group_ids,
+            &self.location_ids
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    /// Marks announcement as sent. Returns `false` if it has already been claimed for sending,
+    /// so it's never sent twice.
+    pub(crate) async fn claim<'e, E>(&mut self, executor: E) -> Result<bool, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        let sent_at = Utc::now().naive_utc();
+        let result = query!(
+            "UPDATE announcement SET sent_at = $2 WHERE id = $1 AND sent_at IS NULL",
+            self.id,
+            sent_at
+        )
+        .execute(executor)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+        self.sent_at = Some(sent_at);
+
+        Ok(true)
+    }
+}
+
+/// Delivery status of an announcement to a single recipient.
+#[derive(Clone, Debug, Deserialize, Model, Serialize, ToSchema)]
+#[table(announcement_delivery)]
+pub struct AnnouncementDelivery<I = NoId> {
+    pub id: I,
+    pub announcement_id: Id,
+    pub user_id: Option<Id>,
+    pub email: String,
+    pub sent_at: Option<NaiveDateTime>,
+    pub error: Option<String>,
+}
+
+impl AnnouncementDelivery {
+    #[must_use]
+    pub fn new(announcement_id: Id, user_id: Id, email: String) -> Self {
+        Self {
+            id: NoId,
+            announcement_id,
+            user_id: Some(user_id),
+            email,
+            sent_at: None,
+            error: None,
+        }
+    }
+}
+
+impl AnnouncementDelivery<Id> {
+    pub async fn all_for_announcement<'e, E>(
+        executor: E,
+        announcement_id: Id,
+    ) -> Result<Vec<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, announcement_id, user_id, email, sent_at, error \
+            FROM announcement_delivery WHERE announcement_id = $1 ORDER BY id",
+            announcement_id
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    /// Records delivery result reported by the mail handler.
+    pub(crate) async fn set_result<'e, E>(
+        &mut self,
+        executor: E,
+        error: Option<String>,
+    ) -> Result<(), SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        if error.is_none() {
+            self.sent_at = Some(Utc::now().naive_utc());
+        }
+        self.error = error;
+        query!(
+            "UPDATE announcement_delivery SET sent_at = $2, error = $3 WHERE id = $1",
+            self.id,
+            self.sent_at,
+            self.error
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+}
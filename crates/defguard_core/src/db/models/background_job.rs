@@ -0,0 +1,183 @@
+use chrono::{NaiveDateTime, Utc};
+use defguard_common::db::Id;
+use serde_json::Value;
+use sqlx::{Error as SqlxError, PgExecutor, Type, query, query_as};
+use utoipa::ToSchema;
+
+/// State of a background job.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "background_job_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum BackgroundJobStatus {
+    /// Created, but not started yet.
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+    /// Cancelled by a user before it finished.
+    Cancelled,
+}
+
+/// Long-running operation executed in background. Clients poll its status and progress
+/// until it's finished.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct BackgroundJob {
+    pub id: Id,
+    /// Type of the operation, e.g. `directory_sync`.
+    pub kind: String,
+    pub status: BackgroundJobStatus,
+    /// Number of processed items.
+    pub progress: i32,
+    /// Total number of items to process, if known.
+    pub total: Option<i32>,
+    /// Error message of failed jobs.
+    pub message: Option<String>,
+    /// Result of succeeded jobs.
+    pub result: Option<Value>,
+    pub created_by: Option<Id>,
+    pub created_at: NaiveDateTime,
+    pub started_at: Option<NaiveDateTime>,
+    pub finished_at: Option<NaiveDateTime>,
+}
+
+impl BackgroundJob {
+    pub async fn create<'e, E>(
+        executor: E,
+        kind: &str,
+        created_by: Option<Id>,
+    ) -> Result<Self, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "INSERT INTO background_job (kind, created_by) VALUES ($1, $2) \
+            RETURNING id, kind, status \"status: BackgroundJobStatus\", progress, total, message, \
+            result, created_by, created_at, started_at, finished_at",
+            kind,
+            created_by
+        )
+        .fetch_one(executor)
+        .await
+    }
+
+    pub async fn find_by_id<'e, E>(executor: E, id: Id) -> Result<Option<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, kind, status \"status: BackgroundJobStatus\", progress, total, message, \
+            result, created_by, created_at, started_at, finished_at \
+            FROM background_job WHERE id = $1",
+            id
+        )
+        .fetch_optional(executor)
+        .await
+    }
+
+    /// Most recently created jobs.
+    pub async fn recent<'e, E>(executor: E, limit: i64) -> Result<Vec<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, kind, status \"status: BackgroundJobStatus\", progress, total, message, \
+            result, created_by, created_at, started_at, finished_at \
+            FROM background_job ORDER BY created_at DESC, id DESC LIMIT $1",
+            limit
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self.status,
+            BackgroundJobStatus::Succeeded
+                | BackgroundJobStatus::Failed
+                | BackgroundJobStatus::Cancelled
+        )
+    }
+
+    pub(crate) async fn start<'e, E>(executor: E, id: Id) -> Result<(), SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query!(
+            "UPDATE background_job SET status = 'running'::background_job_status, started_at = $2 \
+            WHERE id = $1 AND status = 'pending'::background_job_status",
+            id,
+            Utc::now().naive_utc()
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn set_progress<'e, E>(
+        executor: E,
+        id: Id,
+        progress: i32,
+        total: Option<i32>,
+    ) -> Result<(), SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query!(
+            "UPDATE background_job SET progress = $2, total = $3 WHERE id = $1",
+            id,
+            progress,
+            total
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn finish<'e, E>(
+        executor: E,
+        id: Id,
+        status: BackgroundJobStatus,
+        message: Option<String>,
+        result: Option<Value>,
+    ) -> Result<(), SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query!(
+            "UPDATE background_job SET status = $2, message = $3, result = $4, finished_at = $5 \
+            WHERE id = $1",
+            id,
+            status as BackgroundJobStatus,
+            message,
+            result,
+            Utc::now().naive_utc()
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Marks jobs which didn't finish before Core was stopped as failed.
+    pub async fn fail_interrupted<'e, E>(executor: E) -> Result<u64, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        let result = query!(
+            "UPDATE background_job SET status = 'failed'::background_job_status, \
+            message = 'Interrupted by Core restart', finished_at = $1 \
+            WHERE status IN ('pending'::background_job_status, 'running'::background_job_status)",
+            Utc::now().naive_utc()
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -30,7 +30,10 @@ use crate::{
     KEY_LENGTH,
     db::{
         User,
-        models::wireguard::{ServiceLocationMode, get_allowed_ips_for_device},
+        models::wireguard::{
+            DeviceNameUniqueness, GatewayDistributionPolicy, IpAllocationStrategy,
+            ServiceLocationMode,
+        },
     },
     enterprise::db::models::enterprise_settings::EnterpriseSettings,
 };
@@ -306,6 +309,23 @@ impl WireguardNetworkDevice {
         }
     }
 
+    /// Same as `new`, but generates a preshared key if the location uses static preshared keys.
+    #[must_use]
+    pub(crate) fn for_location<I>(
+        location: &WireguardNetwork<Id>,
+        device_id: Id,
+        wireguard_ips: I,
+    ) -> Self
+    where
+        I: Into<Vec<IpAddr>>,
+    {
+        let mut network_device = Self::new(location.id, device_id, wireguard_ips);
+        if location.static_preshared_keys() {
+            network_device.preshared_key = Some(WireguardNetwork::genkey().public);
+        }
+        network_device
+    }
+
     #[must_use]
     pub(crate) fn ips_as_network(&self) -> Vec<IpNetwork> {
         self.wireguard_ips
@@ -324,7 +344,8 @@ impl WireguardNetworkDevice {
             preshared_key) \
             VALUES ($1, $2, $3, $4, $5, $6) \
             ON CONFLICT ON CONSTRAINT device_network \
-            DO UPDATE SET wireguard_ips = $3, is_authorized = $4",
+            DO UPDATE SET wireguard_ips = $3, is_authorized = $4, \
+            preshared_key = COALESCE($6, wireguard_network_device.preshared_key)",
             self.device_id,
             self.wireguard_network_id,
             &self.ips_as_network(),
@@ -510,7 +531,16 @@ impl WireguardNetworkDevice {
             "SELECT id, name, address, port, pubkey, prvkey, endpoint, dns, allowed_ips, \
             connected_at, keepalive_interval, peer_disconnect_threshold, \
             acl_enabled, acl_default_allow, location_mfa_mode \"location_mfa_mode: LocationMfaMode\", \
-            service_location_mode \"service_location_mode: ServiceLocationMode\" \
+            service_location_mode \"service_location_mode: ServiceLocationMode\", \
+            min_desktop_client_version, min_mobile_client_version, device_approval_required, \
+            gateway_distribution_policy \"gateway_distribution_policy: GatewayDistributionPolicy\", \
+            gateway_peer_sharding, \
+            ip_allocation_strategy \"ip_allocation_strategy: IpAllocationStrategy\", \
+            client_traffic_policy \"client_traffic_policy: _\", \
+            mfa_session_lifetime_hours, mfa_remember_device_hours, \
+            preshared_keys_enabled, preshared_key_rotation_days, preshared_keys_rotated_at, \
+            device_name_pattern, device_name_prefix, \
+            device_name_uniqueness \"device_name_uniqueness: DeviceNameUniqueness\" \
             FROM wireguard_network WHERE id = $1",
             self.wireguard_network_id
         )
@@ -574,7 +604,7 @@ impl Device<Id> {
     pub(crate) fn create_config(
         location: &WireguardNetwork<Id>,
         wireguard_network_device: &WireguardNetworkDevice,
-        enterprise_settings: &EnterpriseSettings,
+        allowed_ips: &[IpNetwork],
     ) -> String {
         let dns = match &location.dns {
             Some(dns) => {
@@ -587,11 +617,16 @@ impl Device<Id> {
             None => String::new(),
         };
 
-        let location_allowed_ips = get_allowed_ips_for_device(enterprise_settings, location);
-        let allowed_ips = if location_allowed_ips.is_empty() {
+        let allowed_ips = if allowed_ips.is_empty() {
             String::new()
         } else {
-            format!("AllowedIPs = {}\n", location_allowed_ips.as_csv())
+            format!("AllowedIPs = {}\n", allowed_ips.as_csv())
+        };
+
+        // MFA session preshared keys are delivered to clients after MFA
+        let preshared_key = match &wireguard_network_device.preshared_key {
+            Some(key) if location.static_preshared_keys() => format!("PresharedKey = {key}\n"),
+            _ => String::new(),
         };
 
         format!(
@@ -602,6 +637,7 @@ impl Device<Id> {
             \n\
             [Peer]\n\
             PublicKey = {}\n\
+            {preshared_key}\
             {allowed_ips}\
             Endpoint = {}:{}\n\
             PersistentKeepalive = 300",
@@ -703,8 +739,10 @@ impl Device<Id> {
             is_authorized: wireguard_network_device.is_authorized,
         };
 
-        let config = Self::create_config(location, &wireguard_network_device, enterprise_settings);
-        let allowed_ips = get_allowed_ips_for_device(enterprise_settings, location);
+        let allowed_ips = location
+            .device_allowed_ips(&mut *transaction, self, enterprise_settings)
+            .await?;
+        let config = Self::create_config(location, &wireguard_network_device, &allowed_ips);
         let device_config = DeviceConfig {
             network_id: location.id,
             network_name: location.name.clone(),
@@ -739,8 +777,10 @@ impl Device<Id> {
             is_authorized: wireguard_network_device.is_authorized,
         };
 
-        let config = Self::create_config(location, &wireguard_network_device, enterprise_settings);
-        let allowed_ips = get_allowed_ips_for_device(enterprise_settings, location);
+        let allowed_ips = location
+            .device_allowed_ips(&mut *transaction, self, enterprise_settings)
+            .await?;
+        let config = Self::create_config(location, &wireguard_network_device, &allowed_ips);
         let device_config = DeviceConfig {
             network_id: location.id,
             network_name: location.name.clone(),
@@ -763,10 +803,31 @@ impl Device<Id> {
         &self,
         transaction: &mut PgConnection,
     ) -> Result<(Vec<DeviceNetworkInfo>, Vec<DeviceConfig>), DeviceError> {
-        info!("Adding device {} to all existing networks", self.name);
-        let locations = WireguardNetwork::all(&mut *transaction).await?;
+        self.add_to_networks(transaction, None).await
+    }
 
-        let enterprise_settings = EnterpriseSettings::get(&mut *transaction).await?;
+    /// Add device to given networks, or all existing networks if `location_ids` is `None`.
+    pub async fn add_to_networks(
+        &self,
+        transaction: &mut PgConnection,
+        location_ids: Option<&[Id]>,
+    ) -> Result<(Vec<DeviceNetworkInfo>, Vec<DeviceConfig>), DeviceError> {
+        let mut locations = WireguardNetwork::all(&mut *transaction).await?;
+        if let Some(location_ids) = location_ids {
+            info!(
+                "Adding device {} to networks with IDs {location_ids:?}",
+                self.name
+            );
+            locations.retain(|location| location_ids.contains(&location.id));
+        } else {
+            info!("Adding device {} to all existing networks", self.name);
+        }
+
+        let enterprise_settings = if self.device_type == DeviceType::Network {
+            EnterpriseSettings::get(&mut *transaction).await?
+        } else {
+            EnterpriseSettings::get_for_user(&mut *transaction, self.user_id).await?
+        };
 
         let mut configs = Vec::new();
         let mut network_info = Vec::new();
@@ -805,9 +866,11 @@ impl Device<Id> {
                 };
                 network_info.push(device_network_info);
 
+                let allowed_ips = location
+                    .device_allowed_ips(&mut *transaction, self, &enterprise_settings)
+                    .await?;
                 let config =
-                    Self::create_config(&location, &wireguard_network_device, &enterprise_settings);
-                let allowed_ips = get_allowed_ips_for_device(&enterprise_settings, &location);
+                    Self::create_config(&location, &wireguard_network_device, &allowed_ips);
                 configs.push(DeviceConfig {
                     network_id: location.id,
                     network_name: location.name,
@@ -829,7 +892,7 @@ impl Device<Id> {
     /// Assign the next available IP address in each subnet of the network to this device.
     ///
     /// For every CIDR block in `network.address`, this function:
-    /// 1. Iterates through the block's IPs in order.
+    /// 1. Iterates through the block's IPs in the order given by the location IP allocation strategy.
     /// 2. Skips any IP that:
     ///    - Fails the `can_assign_ips` validation (out of range, reserved, or already in use by another device), or
     ///    - Appears in the optional `reserved_ips`.
@@ -880,7 +943,10 @@ impl Device<Id> {
                 continue;
             }
             let mut picked = None;
-            for ip in address {
+            for ip in network
+                .ip_allocation_strategy
+                .candidate_ips(address, self.user_id)
+            {
                 if network
                     .can_assign_ips(transaction, &[ip], Some(self.id))
                     .await
@@ -911,7 +977,7 @@ impl Device<Id> {
 
         // Create relation record
         let wireguard_network_device =
-            WireguardNetworkDevice::new(network.id, self.id, ips.clone());
+            WireguardNetworkDevice::for_location(network, self.id, ips.clone());
         wireguard_network_device.insert(&mut *transaction).await?;
 
         info!(
@@ -944,7 +1010,7 @@ impl Device<Id> {
             })?;
 
         // insert relation record
-        let wireguard_network_device = WireguardNetworkDevice::new(network.id, self.id, ips);
+        let wireguard_network_device = WireguardNetworkDevice::for_location(network, self.id, ips);
         wireguard_network_device.insert(&mut *transaction).await?;
         info!(
             "Assigned IPs: {ips:?} for device: {} in network {}",
@@ -967,7 +1033,16 @@ impl Device<Id> {
             "SELECT id, name, address, port, pubkey, prvkey, endpoint, dns, allowed_ips, \
             connected_at,  keepalive_interval, peer_disconnect_threshold, \
             acl_enabled, acl_default_allow, location_mfa_mode \"location_mfa_mode: LocationMfaMode\", \
-            service_location_mode \"service_location_mode: ServiceLocationMode\" \
+            service_location_mode \"service_location_mode: ServiceLocationMode\", \
+            min_desktop_client_version, min_mobile_client_version, device_approval_required, \
+            gateway_distribution_policy \"gateway_distribution_policy: GatewayDistributionPolicy\", \
+            gateway_peer_sharding, \
+            ip_allocation_strategy \"ip_allocation_strategy: IpAllocationStrategy\", \
+            client_traffic_policy \"client_traffic_policy: _\", \
+            mfa_session_lifetime_hours, mfa_remember_device_hours, \
+            preshared_keys_enabled, preshared_key_rotation_days, preshared_keys_rotated_at, \
+            device_name_pattern, device_name_prefix, \
+            device_name_uniqueness \"device_name_uniqueness: DeviceNameUniqueness\" \
             FROM wireguard_network WHERE id IN \
             (SELECT wireguard_network_id FROM wireguard_network_device WHERE device_id = $1 ORDER BY id LIMIT 1)",
             self.id
@@ -976,6 +1051,39 @@ impl Device<Id> {
         .await
     }
 
+    /// Fetch all networks the device is assigned to.
+    pub async fn find_networks<'e, E>(
+        &self,
+        executor: E,
+    ) -> Result<Vec<WireguardNetwork<Id>>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            WireguardNetwork,
+            "SELECT n.id, n.name, n.address, n.port, n.pubkey, n.prvkey, n.endpoint, n.dns, \
+            n.allowed_ips, n.connected_at, n.keepalive_interval, n.peer_disconnect_threshold, \
+            n.acl_enabled, n.acl_default_allow, \
+            n.location_mfa_mode \"location_mfa_mode: LocationMfaMode\", \
+            n.service_location_mode \"service_location_mode: ServiceLocationMode\", \
+            n.min_desktop_client_version, n.min_mobile_client_version, n.device_approval_required, \
+            n.gateway_distribution_policy \"gateway_distribution_policy: GatewayDistributionPolicy\", \
+            n.gateway_peer_sharding, \
+            n.ip_allocation_strategy \"ip_allocation_strategy: IpAllocationStrategy\", \
+            n.client_traffic_policy \"client_traffic_policy: _\", \
+            n.mfa_session_lifetime_hours, n.mfa_remember_device_hours, \
+            n.preshared_keys_enabled, n.preshared_key_rotation_days, n.preshared_keys_rotated_at, \
+            n.device_name_pattern, n.device_name_prefix, \
+            n.device_name_uniqueness \"device_name_uniqueness: DeviceNameUniqueness\" \
+            FROM wireguard_network n \
+            JOIN wireguard_network_device wnd ON wnd.wireguard_network_id = n.id \
+            WHERE wnd.device_id = $1 ORDER BY n.id",
+            self.id
+        )
+        .fetch_all(executor)
+        .await
+    }
+
     pub fn validate_pubkey(pubkey: &str) -> Result<(), String> {
         if let Ok(key) = BASE64_STANDARD.decode(pubkey) {
             if key.len() == KEY_LENGTH {
@@ -1029,7 +1137,7 @@ impl Device<Id> {
             "SELECT id, username, password_hash, last_name, first_name, email, \
             phone, mfa_enabled, totp_enabled, email_mfa_enabled, \
             totp_secret, email_mfa_secret, mfa_method \"mfa_method: _\", recovery_codes, is_active, openid_sub, \
-            from_ldap, ldap_pass_randomized, ldap_rdn, ldap_user_path, enrollment_pending \
+            from_ldap, ldap_pass_randomized, ldap_rdn, ldap_user_path, enrollment_pending, mail_language \
             FROM \"user\" WHERE id = $1",
             self.user_id
         ).fetch_one(executor).await
@@ -1038,7 +1146,7 @@ impl Device<Id> {
 
 #[cfg(test)]
 mod test {
-    use std::str::FromStr;
+    use std::{collections::HashSet, str::FromStr};
 
     use claims::{assert_err, assert_ok};
     use defguard_common::db::setup_pool;
@@ -1080,7 +1188,7 @@ mod test {
                         info!("Created device: {}", device.name);
                         debug!("For user: {}", device.user_id);
                         let wireguard_network_device =
-                            WireguardNetworkDevice::new(network.id, device.id, [ip]);
+                            WireguardNetworkDevice::for_location(network, device.id, [ip]);
                         wireguard_network_device.insert(pool).await?;
                         info!(
                             "Assigned IP: {ip} for device: {name} in network: {}",
@@ -1123,6 +1231,97 @@ mod test {
         assert!(device.is_err());
     }
 
+    #[sqlx::test]
+    async fn test_assign_device_ip_strategy(_: PgPoolOptions, options: PgConnectOptions) {
+        let pool = setup_pool(options).await;
+
+        let user = User::new(
+            "testuser",
+            Some("hunter2"),
+            "Tester",
+            "Test",
+            "test@test.com",
+            None,
+        )
+        .save(&pool)
+        .await
+        .unwrap();
+        let mut conn = pool.acquire().await.unwrap();
+
+        for (index, strategy) in [
+            IpAllocationStrategy::Sequential,
+            IpAllocationStrategy::Random,
+            IpAllocationStrategy::StickyByUser,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let mut network = WireguardNetwork {
+                name: format!("network{index}"),
+                ip_allocation_strategy: strategy,
+                ..Default::default()
+            };
+            network.try_set_address("10.1.1.1/28").unwrap();
+            let network = network.save(&pool).await.unwrap();
+            let address = network.address[0];
+
+            // every free address is eventually assigned, regardless of the strategy
+            let mut ips = Vec::new();
+            for device_index in 0..13 {
+                let device = Device::new(
+                    format!("dev{index}-{device_index}"),
+                    format!("key{index}-{device_index}"),
+                    user.id,
+                    DeviceType::User,
+                    None,
+                    true,
+                )
+                .save(&pool)
+                .await
+                .unwrap();
+                let network_device = device
+                    .assign_next_network_ip(&mut conn, &network, None, None)
+                    .await
+                    .unwrap();
+                ips.push(network_device.wireguard_ips[0]);
+            }
+            let unique: HashSet<IpAddr> = ips.iter().copied().collect();
+            assert_eq!(unique.len(), 13);
+            assert!(!unique.contains(&address.ip()));
+            assert!(!unique.contains(&address.network()));
+            assert!(!unique.contains(&address.broadcast()));
+
+            // deterministic strategies assign addresses in candidate order, skipping taken ones
+            if strategy != IpAllocationStrategy::Random {
+                let expected: Vec<IpAddr> = strategy
+                    .candidate_ips(&address, user.id)
+                    .filter(|ip| {
+                        ![address.ip(), address.network(), address.broadcast()].contains(ip)
+                    })
+                    .collect();
+                assert_eq!(ips, expected);
+            }
+
+            let device = Device::new(
+                format!("dev{index}-full"),
+                format!("key{index}-full"),
+                user.id,
+                DeviceType::User,
+                None,
+                true,
+            )
+            .save(&pool)
+            .await
+            .unwrap();
+            assert!(
+                device
+                    .assign_next_network_ip(&mut conn, &network, None, None)
+                    .await
+                    .is_err()
+            );
+        }
+    }
+
     #[test]
     fn test_pubkey_validation() {
         let invalid_test_key = "invalid_key";
@@ -1254,4 +1453,58 @@ mod test {
         assert_eq!(devices.len(), 1);
         assert_eq!(devices[0].device_id, device.id);
     }
+
+    #[sqlx::test]
+    async fn test_find_networks(_: PgPoolOptions, options: PgConnectOptions) {
+        let pool = setup_pool(options).await;
+
+        let user = User::new(
+            "testuser",
+            Some("hunter2"),
+            "Tester",
+            "Test",
+            "test@test.com",
+            None,
+        )
+        .save(&pool)
+        .await
+        .unwrap();
+        let mut networks = Vec::new();
+        for i in 1..=3 {
+            let mut network = WireguardNetwork::<NoId> {
+                name: format!("network{i}"),
+                ..Default::default()
+            };
+            network.try_set_address(&format!("10.1.{i}.1/24")).unwrap();
+            networks.push(network.save(&pool).await.unwrap());
+        }
+        let device = Device::new(
+            "device".into(),
+            "key".into(),
+            user.id,
+            DeviceType::User,
+            None,
+            true,
+        )
+        .save(&pool)
+        .await
+        .unwrap();
+        assert!(device.find_networks(&pool).await.unwrap().is_empty());
+
+        // only networks the device is assigned to are returned
+        for network in [&networks[2], &networks[0]] {
+            WireguardNetworkDevice::for_location(network, device.id, [network.address[0].ip()])
+                .insert(&pool)
+                .await
+                .unwrap();
+        }
+        let network_ids: Vec<Id> = device
+            .find_networks(&pool)
+            .await
+            .unwrap()
+            .iter()
+            .map(|network| network.id)
+            .collect();
+        assert_eq!(network_ids, [networks[0].id, networks[2].id]);
+    }
 }
@@ -0,0 +1,142 @@
+use chrono::{NaiveDateTime, Utc};
+use defguard_common::db::{Id, NoId};
+use model_derive::Model;
+use sqlx::{Error as SqlxError, PgExecutor, Type, query, query_as};
+use utoipa::ToSchema;
+
+/// State of a device approval request.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "device_approval_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum DeviceApprovalStatus {
+    /// Waiting for an administrator decision.
+    #[default]
+    Pending,
+    Approved,
+    Rejected,
+    /// Not resolved before `expires_at`, treated as rejected.
+    Expired,
+}
+
+/// Request to add a device created through enrollment to a location which requires
+/// device approval.
+///
+/// Devices with unapproved requests are not considered allowed in a given location,
+/// so they're not assigned an IP address and not pushed to its gateways.
+#[derive(Clone, Debug, Deserialize, Model, PartialEq, Serialize, ToSchema)]
+#[table(device_approval_request)]
+pub struct DeviceApprovalRequest<I = NoId> {
+    pub id: I,
+    pub device_id: Id,
+    pub location_id: Id,
+    #[model(enum)]
+    pub status: DeviceApprovalStatus,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub resolved_at: Option<NaiveDateTime>,
+    pub resolved_by: Option<Id>,
+}
+
+/// Pending device approval request with device, owner and location details.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct PendingDeviceApproval {
+    pub id: Id,
+    pub device_id: Id,
+    pub device_name: String,
+    pub wireguard_pubkey: String,
+    pub user_id: Id,
+    pub username: String,
+    pub location_id: Id,
+    pub location_name: String,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+}
+
+impl DeviceApprovalRequest {
+    #[must_use]
+    pub fn new(device_id: Id, location_id: Id, expires_at: NaiveDateTime) -> Self {
+        Self {
+            id: NoId,
+            device_id,
+            location_id,
+            status: DeviceApprovalStatus::Pending,
+            created_at: Utc::now().naive_utc(),
+            expires_at,
+            resolved_at: None,
+            resolved_by: None,
+        }
+    }
+}
+
+impl DeviceApprovalRequest<Id> {
+    /// Resolves a pending request. Returns `false` if the request has already been resolved.
+    pub(crate) async fn resolve<'e, E>(
+        &mut self,
+        executor: E,
+        status: DeviceApprovalStatus,
+        resolved_by: Option<Id>,
+    ) -> Result<bool, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        let resolved_at = Utc::now().naive_utc();
+        let result = query!(
+            "UPDATE device_approval_request SET status = $2, resolved_at = $3, resolved_by = $4 \
+            WHERE id = $1 AND status = 'pending'::device_approval_status",
+            self.id,
+            status as DeviceApprovalStatus,
+            resolved_at,
+            resolved_by
+        )
+        .execute(executor)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+        self.status = status;
+        self.resolved_at = Some(resolved_at);
+        self.resolved_by = resolved_by;
+
+        Ok(true)
+    }
+
+    /// Marks all pending requests past their expiry time as expired.
+    pub(crate) async fn expire_all_due<'e, E>(executor: E) -> Result<Vec<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        let now = Utc::now().naive_utc();
+        query_as!(
+            Self,
+            "UPDATE device_approval_request SET status = 'expired'::device_approval_status, \
+            resolved_at = $1 \
+            WHERE status = 'pending'::device_approval_status AND expires_at <= $1 \
+            RETURNING id, device_id, location_id, status \"status: DeviceApprovalStatus\", \
+            created_at, expires_at, resolved_at, resolved_by",
+            now
+        )
+        .fetch_all(executor)
+        .await
+    }
+}
+
+impl PendingDeviceApproval {
+    pub async fn all<'e, E>(executor: E) -> Result<Vec<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT r.id, r.device_id, d.name device_name, d.wireguard_pubkey, d.user_id, \
+            u.username, r.location_id, n.name location_name, r.created_at, r.expires_at \
+            FROM device_approval_request r \
+            JOIN device d ON d.id = r.device_id \
+            JOIN \"user\" u ON u.id = d.user_id \
+            JOIN wireguard_network n ON n.id = r.location_id \
+            WHERE r.status = 'pending'::device_approval_status \
+            ORDER BY r.created_at, r.id"
+        )
+        .fetch_all(executor)
+        .await
+    }
+}
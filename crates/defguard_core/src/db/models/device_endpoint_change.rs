@@ -0,0 +1,118 @@
+use std::{collections::HashMap, net::IpAddr};
+
+use chrono::NaiveDateTime;
+use defguard_common::db::{Id, NoId};
+use ipnetwork::IpNetwork;
+use model_derive::Model;
+use sqlx::{Error as SqlxError, PgExecutor, query, query_as};
+use utoipa::ToSchema;
+
+/// Change of the address a device connects to a location from. Only changes are stored, so
+/// the history stays small regardless of how often peer stats are collected.
+#[derive(Clone, Debug, Deserialize, Model, PartialEq, Serialize, ToSchema)]
+#[table(device_endpoint_change)]
+pub struct DeviceEndpointChange<I = NoId> {
+    pub id: I,
+    pub device_id: Id,
+    pub location_id: Id,
+    #[schema(value_type = String)]
+    pub ip: IpNetwork,
+    pub changed_at: NaiveDateTime,
+}
+
+impl DeviceEndpointChange {
+    #[must_use]
+    pub fn new(device_id: Id, location_id: Id, ip: IpAddr, changed_at: NaiveDateTime) -> Self {
+        Self {
+            id: NoId,
+            device_id,
+            location_id,
+            ip: ip.into(),
+            changed_at,
+        }
+    }
+
+    /// Insert multiple changes using a single multi-row `INSERT`.
+    pub(crate) async fn save_many<'e, E>(executor: E, changes: &[Self]) -> Result<u64, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        let mut device_ids = Vec::with_capacity(changes.len());
+        let mut location_ids = Vec::with_capacity(changes.len());
+        let mut ips = Vec::with_capacity(changes.len());
+        let mut changed_at = Vec::with_capacity(changes.len());
+        for change in changes {
+            device_ids.push(change.device_id);
+            location_ids.push(change.location_id);
+            ips.push(change.ip);
+            changed_at.push(change.changed_at);
+        }
+
+        let result = query!(
+            "INSERT INTO device_endpoint_change (device_id, location_id, ip, changed_at) \
+            SELECT * FROM UNNEST($1::bigint[], $2::bigint[], $3::inet[], $4::timestamp[])",
+            &device_ids,
+            &location_ids,
+            &ips,
+            &changed_at,
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+impl DeviceEndpointChange<Id> {
+    /// Latest known addresses of given peers, keyed by device and location IDs.
+    pub(crate) async fn latest_ips<'e, E>(
+        executor: E,
+        peers: &[(Id, Id)],
+    ) -> Result<HashMap<(Id, Id), IpAddr>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        let (device_ids, location_ids): (Vec<Id>, Vec<Id>) = peers.iter().copied().unzip();
+        let changes = query_as!(
+            Self,
+            "SELECT DISTINCT ON (device_id, location_id) id, device_id, location_id, ip, changed_at \
+            FROM device_endpoint_change \
+            WHERE (device_id, location_id) IN (SELECT * FROM UNNEST($1::bigint[], $2::bigint[])) \
+            ORDER BY device_id, location_id, changed_at DESC, id DESC",
+            &device_ids,
+            &location_ids,
+        )
+        .fetch_all(executor)
+        .await?;
+
+        Ok(changes
+            .into_iter()
+            .map(|change| ((change.device_id, change.location_id), change.ip.ip()))
+            .collect())
+    }
+
+    /// Address changes of a device since a given time, including the addresses in use at that
+    /// time; the most recent first.
+    pub async fn for_device_since<'e, E>(
+        executor: E,
+        device_id: Id,
+        since: NaiveDateTime,
+    ) -> Result<Vec<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, device_id, location_id, ip, changed_at FROM device_endpoint_change \
+            WHERE device_id = $1 AND (changed_at >= $2 OR id IN ( \
+                SELECT DISTINCT ON (location_id) id FROM device_endpoint_change \
+                WHERE device_id = $1 AND changed_at < $2 \
+                ORDER BY location_id, changed_at DESC, id DESC)) \
+            ORDER BY changed_at DESC, id DESC",
+            device_id,
+            since,
+        )
+        .fetch_all(executor)
+        .await
+    }
+}
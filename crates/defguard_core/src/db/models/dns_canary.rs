@@ -0,0 +1,100 @@
+use std::net::IpAddr;
+
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use defguard_common::{
+    db::{Id, NoId},
+    random::gen_alphanumeric,
+};
+use ipnetwork::IpNetwork;
+use model_derive::Model;
+use sqlx::{Error as SqlxError, PgExecutor, query, query_as};
+
+/// How long a client has to resolve a canary hostname and verify the result.
+pub const DNS_CANARY_TIMEOUT: TimeDelta = TimeDelta::minutes(10);
+
+/// Unique hostname resolved by a device during a DNS leak test. Addresses of resolvers which
+/// queried the hostname are reported by the DNS server authoritative for the canary domain.
+#[derive(Clone, Debug, Model)]
+#[table(dns_canary)]
+pub struct DnsCanary<I = NoId> {
+    pub id: I,
+    pub hostname: String,
+    pub device_id: Id,
+    pub network_id: Id,
+    #[model(ref)]
+    pub resolvers: Vec<IpNetwork>,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+}
+
+impl DnsCanary {
+    #[must_use]
+    pub fn new(domain: &str, device_id: Id, network_id: Id) -> Self {
+        let now = Utc::now().naive_utc();
+        Self {
+            id: NoId,
+            // DNS names are case-insensitive, resolvers may change the case of queried names
+            hostname: format!("{}.{domain}", gen_alphanumeric(24).to_lowercase()),
+            device_id,
+            network_id,
+            resolvers: Vec::new(),
+            created_at: now,
+            expires_at: now + DNS_CANARY_TIMEOUT,
+        }
+    }
+}
+
+impl DnsCanary<Id> {
+    /// Record a query for `hostname` made by `resolver`. Returns `false` if there is no such
+    /// canary or it has expired.
+    pub(crate) async fn add_resolver<'e, E>(
+        executor: E,
+        hostname: &str,
+        resolver: IpAddr,
+    ) -> Result<bool, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        // resolvers usually query the same name more than once, store each of them only once
+        let result = query!(
+            "UPDATE dns_canary SET resolvers = CASE WHEN $2 = ANY(resolvers) THEN resolvers \
+            ELSE array_append(resolvers, $2) END WHERE hostname = $1 AND expires_at > NOW()",
+            hostname,
+            IpNetwork::from(resolver),
+        )
+        .execute(executor)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Find a canary of a device which hasn't expired yet.
+    pub(crate) async fn find_for_device<'e, E>(
+        executor: E,
+        id: Id,
+        device_id: Id,
+    ) -> Result<Option<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, hostname, device_id, network_id, resolvers \"resolvers: _\", created_at, \
+            expires_at FROM dns_canary WHERE id = $1 AND device_id = $2 AND expires_at > NOW()",
+            id,
+            device_id
+        )
+        .fetch_optional(executor)
+        .await
+    }
+
+    /// Remove expired canaries.
+    pub(crate) async fn delete_expired<'e, E>(executor: E) -> Result<u64, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        let result = query!("DELETE FROM dns_canary WHERE expires_at <= NOW()")
+            .execute(executor)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
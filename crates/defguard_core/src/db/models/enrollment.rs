@@ -6,9 +6,10 @@ use defguard_common::{
     random::gen_alphanumeric,
 };
 use defguard_mail::{
-    Mail,
-    templates::{self, TemplateError, safe_tera},
+    Mail, MailCategory,
+    templates::{self, TemplateError, insert_mail_variables, safe_tera},
 };
+use defguard_proto::proxy::DeviceInfo;
 use reqwest::Url;
 use sqlx::{Error as SqlxError, PgConnection, PgExecutor, PgPool, query, query_as};
 use tera::Context;
@@ -21,9 +22,6 @@ use super::User;
 pub static ENROLLMENT_TOKEN_TYPE: &str = "ENROLLMENT";
 pub static PASSWORD_RESET_TOKEN_TYPE: &str = "PASSWORD_RESET";
 
-static ENROLLMENT_START_MAIL_SUBJECT: &str = "Defguard user enrollment";
-static DESKTOP_START_MAIL_SUBJECT: &str = "Defguard desktop client configuration";
-
 #[derive(Error, Debug)]
 pub enum TokenError {
     #[error(transparent)]
@@ -91,6 +89,13 @@ pub struct Token {
     pub used_at: Option<NaiveDateTime>,
     pub token_type: Option<String>,
     pub device_id: Option<Id>,
+    // number of sessions started with this token
+    pub use_count: i32,
+    // locations devices can be added to, all locations available to the user if not set
+    pub location_ids: Option<Vec<Id>>,
+    // client which started the last session
+    pub used_ip: Option<String>,
+    pub used_user_agent: Option<String>,
 }
 
 impl Token {
@@ -113,6 +118,10 @@ impl Token {
             used_at: None,
             token_type,
             device_id: None,
+            use_count: 0,
+            location_ids: None,
+            used_ip: None,
+            used_user_agent: None,
         }
     }
 
@@ -121,8 +130,9 @@ impl Token {
         E: PgExecutor<'e>,
     {
         query!(
-            "INSERT INTO token (id, user_id, admin_id, email, created_at, expires_at, used_at, token_type, device_id) \
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+            "INSERT INTO token (id, user_id, admin_id, email, created_at, expires_at, used_at, token_type, device_id, use_count, location_ids, \
+            used_ip, used_user_agent) \
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)",
             self.id,
             self.user_id,
             self.admin_id,
@@ -131,7 +141,11 @@ impl Token {
             self.expires_at,
             self.used_at,
             self.token_type,
-            self.device_id
+            self.device_id,
+            self.use_count,
+            self.location_ids.as_deref(),
+            self.used_ip,
+            self.used_user_agent
         )
         .execute(executor)
         .await?;
@@ -150,6 +164,33 @@ impl Token {
         self.used_at.is_some()
     }
 
+    // check if devices enrolled with this token can be added to a location
+    #[must_use]
+    pub fn allows_location(&self, location_id: Id) -> bool {
+        self.location_ids
+            .as_ref()
+            .is_none_or(|location_ids| location_ids.contains(&location_id))
+    }
+
+    /// Limit locations devices enrolled with a token can be added to.
+    pub async fn restrict_locations<'e, E>(
+        executor: E,
+        id: &str,
+        location_ids: &[Id],
+    ) -> Result<(), TokenError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query!(
+            "UPDATE token SET location_ids = $2 WHERE id = $1",
+            id,
+            location_ids
+        )
+        .execute(executor)
+        .await?;
+        Ok(())
+    }
+
     // check if enrollment session is still valid
     // after using the token user has 10 minutes to complete enrollment
     #[must_use]
@@ -164,11 +205,15 @@ impl Token {
 
     // check if token can be used to start an enrollment session
     // and set timestamp if token is valid
+    // a new session can be started after the previous one expired, up to `max_uses` times
+    // client starting the session is recorded for auditing
     // returns session deadline
     pub async fn start_session(
         &mut self,
         transaction: &mut PgConnection,
         session_timeout_seconds: u64,
+        max_uses: i32,
+        client: Option<&DeviceInfo>,
     ) -> Result<NaiveDateTime, TokenError> {
         // check if token can be used
         debug!("Creating a new session.");
@@ -182,17 +227,28 @@ impl Token {
                 debug!("Session already exists yet it is still valid.");
                 Ok(used_at + TimeDelta::seconds(session_timeout_seconds as i64))
             }
-            // session expired
-            Some(_) => {
+            // session expired and token can't be used again
+            Some(_) if self.use_count >= max_uses => {
                 debug!("Session has expired.");
                 Err(TokenError::TokenUsed)
             }
-            // session not yet started
-            None => {
+            // session not yet started, or previous one expired
+            _ => {
                 let now = Utc::now().naive_utc();
-                query!("UPDATE token SET used_at = $1 WHERE id = $2", now, self.id)
-                    .execute(transaction)
-                    .await?;
+                self.use_count += 1;
+                self.used_ip = client.map(|client| client.ip_address.clone());
+                self.used_user_agent = client.and_then(|client| client.user_agent.clone());
+                query!(
+                    "UPDATE token SET used_at = $1, use_count = $2, used_ip = $3, \
+                    used_user_agent = $4 WHERE id = $5",
+                    now,
+                    self.use_count,
+                    self.used_ip,
+                    self.used_user_agent,
+                    self.id
+                )
+                .execute(transaction)
+                .await?;
                 self.used_at = Some(now);
 
                 debug!("Generate a new session successfully.");
@@ -204,7 +260,8 @@ impl Token {
     pub async fn find_by_id(pool: &PgPool, id: &str) -> Result<Self, TokenError> {
         if let Some(enrollment) = query_as!(
             Self,
-            "SELECT id, user_id, admin_id, email, created_at, expires_at, used_at, token_type, device_id \
+            "SELECT id, user_id, admin_id, email, created_at, expires_at, used_at, token_type, device_id, use_count, \
+            location_ids, used_ip, used_user_agent \
             FROM token WHERE id = $1",
             id
         )
@@ -222,7 +279,8 @@ impl Token {
     pub async fn fetch_all(pool: &PgPool) -> Result<Vec<Self>, TokenError> {
         let tokens = query_as!(
             Self,
-            "SELECT id, user_id, admin_id, email, created_at, expires_at, used_at, token_type, device_id \
+            "SELECT id, user_id, admin_id, email, created_at, expires_at, used_at, token_type, device_id, use_count, \
+            location_ids, used_ip, used_user_agent \
             FROM token",
         )
         .fetch_all(pool)
@@ -230,6 +288,59 @@ impl Token {
         Ok(tokens)
     }
 
+    /// Enrollment and desktop configuration tokens of a user, the most recent first.
+    pub async fn fetch_user_enrollment_tokens<'e, E>(
+        executor: E,
+        user_id: Id,
+    ) -> Result<Vec<Self>, TokenError>
+    where
+        E: PgExecutor<'e>,
+    {
+        let tokens = query_as!(
+            Self,
+            "SELECT id, user_id, admin_id, email, created_at, expires_at, used_at, token_type, device_id, use_count, \
+            location_ids, used_ip, used_user_agent \
+            FROM token WHERE user_id = $1 AND token_type = 'ENROLLMENT' \
+            ORDER BY created_at DESC",
+            user_id
+        )
+        .fetch_all(executor)
+        .await?;
+        Ok(tokens)
+    }
+
+    /// Moves token expiration to `now + token_timeout_seconds`.
+    pub async fn extend<'e, E>(
+        &mut self,
+        executor: E,
+        token_timeout_seconds: u64,
+    ) -> Result<(), TokenError>
+    where
+        E: PgExecutor<'e>,
+    {
+        let expires_at =
+            (Utc::now() + TimeDelta::seconds(token_timeout_seconds as i64)).naive_utc();
+        query!(
+            "UPDATE token SET expires_at = $2 WHERE id = $1",
+            self.id,
+            expires_at
+        )
+        .execute(executor)
+        .await?;
+        self.expires_at = expires_at;
+        Ok(())
+    }
+
+    pub async fn delete<'e, E>(self, executor: E) -> Result<(), TokenError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query!("DELETE FROM token WHERE id = $1", self.id)
+            .execute(executor)
+            .await?;
+        Ok(())
+    }
+
     pub async fn fetch_user<'e, E>(&self, executor: E) -> Result<User<Id>, TokenError>
     where
         E: PgExecutor<'e>,
@@ -308,6 +419,138 @@ impl Token {
         Ok(())
     }
 
+    /// Invalidate password reset tokens of a user once the password has been reset.
+    /// Returns `false` if this token has already been invalidated, e.g. by a concurrent request.
+    pub async fn invalidate_password_reset_tokens(
+        &self,
+        transaction: &mut PgConnection,
+    ) -> Result<bool, TokenError> {
+        debug!(
+            "Invalidating password reset tokens for user {}",
+            self.user_id
+        );
+        let result = query!("DELETE FROM token WHERE id = $1", self.id)
+            .execute(&mut *transaction)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+        query!(
+            "DELETE FROM token WHERE user_id = $1 AND token_type = 'PASSWORD_RESET'",
+            self.user_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        Ok(true)
+    }
+
+    /// Sends mail with a link to start enrollment with this token.
+    pub(crate) async fn send_enrollment_start_mail(
+        &self,
+        transaction: &mut PgConnection,
+        email: &str,
+        enrollment_service_url: Url,
+        mail_tx: &UnboundedSender<Mail>,
+    ) -> Result<(), TokenError> {
+        debug!(
+            "Sending an enrollment mail for user {} to {email}.",
+            self.user_id
+        );
+        let base_message_context = self.get_welcome_message_context(&mut *transaction).await?;
+        let language = self.fetch_user(&mut *transaction).await?.mail_language;
+        let mail = Mail {
+            to: email.to_string(),
+            subject: templates::localized_subject(
+                templates::ENROLLMENT_START_TEMPLATE,
+                language.as_deref(),
+            )
+            .map_err(|err| TokenError::NotificationError(err.to_string()))?,
+            content: templates::enrollment_start_mail(
+                base_message_context,
+                enrollment_service_url,
+                &self.id,
+                language.as_deref(),
+            )
+            .map_err(|err| {
+                debug!(
+                    "Cannot send an email to the user {} due to the error {err}.",
+                    self.user_id
+                );
+                TokenError::NotificationError(err.to_string())
+            })?,
+            attachments: Vec::new(),
+            category: MailCategory::Enrollment,
+            result_tx: None,
+        };
+        match mail_tx.send(mail) {
+            Ok(()) => {
+                info!(
+                    "Sent enrollment start mail for user {} to {email}",
+                    self.user_id
+                );
+                Ok(())
+            }
+            Err(err) => {
+                error!("Error sending mail: {err}");
+                Err(TokenError::NotificationError(err.to_string()))
+            }
+        }
+    }
+
+    /// Sends mail with a link to configure the desktop client with this token.
+    pub(crate) async fn send_desktop_start_mail(
+        &self,
+        transaction: &mut PgConnection,
+        email: &str,
+        enrollment_service_url: &Url,
+        mail_tx: &UnboundedSender<Mail>,
+    ) -> Result<(), TokenError> {
+        debug!(
+            "Sending a desktop configuration mail for user {} to {email}",
+            self.user_id
+        );
+        let base_message_context = self.get_welcome_message_context(&mut *transaction).await?;
+        let language = self.fetch_user(&mut *transaction).await?.mail_language;
+        let mail = Mail {
+            to: email.to_string(),
+            subject: templates::localized_subject(
+                templates::DESKTOP_START_TEMPLATE,
+                language.as_deref(),
+            )
+            .map_err(|err| TokenError::NotificationError(err.to_string()))?,
+            content: templates::desktop_start_mail(
+                base_message_context,
+                enrollment_service_url,
+                &self.id,
+                language.as_deref(),
+            )
+            .map_err(|err| {
+                debug!(
+                    "Cannot send an email to the user {} due to the error {err}.",
+                    self.user_id
+                );
+                TokenError::NotificationError(err.to_string())
+            })?,
+            attachments: Vec::new(),
+            category: MailCategory::Enrollment,
+            result_tx: None,
+        };
+        match mail_tx.send(mail) {
+            Ok(()) => {
+                info!(
+                    "Sent desktop configuration start mail for user {} to {email}",
+                    self.user_id
+                );
+            }
+            Err(err) => {
+                error!("Error sending mail: {err}");
+            }
+        }
+
+        Ok(())
+    }
+
     /// Prepare context for rendering welcome messages
     /// Available tags include:
     /// - first_name
@@ -337,6 +580,7 @@ impl Token {
         context.insert("username", &user.username);
         context.insert("defguard_url", &server_config().url);
         context.insert("defguard_version", &VERSION);
+        insert_mail_variables(&mut context);
 
         if let Some(admin) = admin {
             context.insert("admin_first_name", &admin.first_name);
@@ -454,44 +698,14 @@ impl User<Id> {
 
         if send_user_notification {
             if let Some(email) = email {
-                debug!(
-                    "Sending an enrollment mail for user {} to {email}.",
-                    self.username
-                );
-                let base_message_context = enrollment
-                    .get_welcome_message_context(&mut *transaction)
-                    .await?;
-                let mail = Mail {
-                    to: email.clone(),
-                    subject: ENROLLMENT_START_MAIL_SUBJECT.to_string(),
-                    content: templates::enrollment_start_mail(
-                        base_message_context,
+                enrollment
+                    .send_enrollment_start_mail(
+                        &mut *transaction,
+                        &email,
                         enrollment_service_url,
-                        &enrollment.id,
+                        &mail_tx,
                     )
-                    .map_err(|err| {
-                        debug!(
-                            "Cannot send an email to the user {} due to the error {}.",
-                            self.username,
-                            err.to_string()
-                        );
-                        TokenError::NotificationError(err.to_string())
-                    })?,
-                    attachments: Vec::new(),
-                    result_tx: None,
-                };
-                match mail_tx.send(mail) {
-                    Ok(()) => {
-                        info!(
-                            "Sent enrollment start mail for user {} to {email}",
-                            self.username
-                        );
-                    }
-                    Err(err) => {
-                        error!("Error sending mail: {err}");
-                        return Err(TokenError::NotificationError(err.to_string()));
-                    }
-                }
+                    .await?;
             }
         }
         info!(
@@ -563,43 +777,14 @@ impl User<Id> {
 
         if send_user_notification {
             if let Some(email) = email {
-                debug!(
-                    "Sending a desktop configuration mail for user {} to {email}",
-                    self.username
-                );
-                let base_message_context = desktop_configuration
-                    .get_welcome_message_context(&mut *transaction)
-                    .await?;
-                let mail = Mail {
-                    to: email.clone(),
-                    subject: DESKTOP_START_MAIL_SUBJECT.to_string(),
-                    content: templates::desktop_start_mail(
-                        base_message_context,
+                desktop_configuration
+                    .send_desktop_start_mail(
+                        &mut *transaction,
+                        &email,
                         &enrollment_service_url,
-                        &desktop_configuration.id,
+                        &mail_tx,
                     )
-                    .map_err(|err| {
-                        debug!(
-                            "Cannot send an email to the user {} due to the error {}.",
-                            self.username,
-                            err.to_string()
-                        );
-                        TokenError::NotificationError(err.to_string())
-                    })?,
-                    attachments: Vec::new(),
-                    result_tx: None,
-                };
-                match mail_tx.send(mail) {
-                    Ok(()) => {
-                        info!(
-                            "Sent desktop configuration start mail for user {} to {email}",
-                            self.username
-                        );
-                    }
-                    Err(err) => {
-                        error!("Error sending mail: {err}");
-                    }
-                }
+                    .await?;
             }
         }
         info!(
@@ -0,0 +1,225 @@
+use chrono::NaiveDateTime;
+use defguard_common::db::Id;
+use serde_json::Value;
+use sqlx::{Error as SqlxError, PgConnection, PgExecutor, query, query_as, types::Json};
+
+use super::{device::Device, user::User, wireguard::WireguardNetwork};
+
+/// Normalized domain event published to external data platforms through the outbox.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DomainEvent {
+    UserCreated {
+        user_id: Id,
+        username: String,
+    },
+    DeviceAdded {
+        device_id: Id,
+        device_name: String,
+        device_type: String,
+        user_id: Id,
+        username: String,
+    },
+    SessionStarted {
+        device_id: Id,
+        device_name: String,
+        location_id: Id,
+        location_name: String,
+        user_id: Id,
+        username: String,
+    },
+    SessionEnded {
+        device_id: Id,
+        device_name: String,
+        location_id: Id,
+        location_name: String,
+        user_id: Id,
+        username: String,
+    },
+    GatewayConnected {
+        location_id: Id,
+        location_name: String,
+        hostname: String,
+    },
+    GatewayDisconnected {
+        location_id: Id,
+        location_name: String,
+        hostname: String,
+    },
+}
+
+impl DomainEvent {
+    #[must_use]
+    pub fn user_created(user: &User<Id>) -> Self {
+        Self::UserCreated {
+            user_id: user.id,
+            username: user.username.clone(),
+        }
+    }
+
+    #[must_use]
+    pub fn device_added(device: &Device<Id>, username: &str) -> Self {
+        Self::DeviceAdded {
+            device_id: device.id,
+            device_name: device.name.clone(),
+            device_type: device.device_type.to_string(),
+            user_id: device.user_id,
+            username: username.into(),
+        }
+    }
+
+    #[must_use]
+    pub fn session_started(
+        device: &Device<Id>,
+        location: &WireguardNetwork<Id>,
+        username: &str,
+    ) -> Self {
+        Self::SessionStarted {
+            device_id: device.id,
+            device_name: device.name.clone(),
+            location_id: location.id,
+            location_name: location.name.clone(),
+            user_id: device.user_id,
+            username: username.into(),
+        }
+    }
+
+    #[must_use]
+    pub fn session_ended(
+        device: &Device<Id>,
+        location: &WireguardNetwork<Id>,
+        username: &str,
+    ) -> Self {
+        Self::SessionEnded {
+            device_id: device.id,
+            device_name: device.name.clone(),
+            location_id: location.id,
+            location_name: location.name.clone(),
+            user_id: device.user_id,
+            username: username.into(),
+        }
+    }
+
+    /// Name of the event, same as the `type` field of serialized event.
+    #[must_use]
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            Self::UserCreated { .. } => "user_created",
+            Self::DeviceAdded { .. } => "device_added",
+            Self::SessionStarted { .. } => "session_started",
+            Self::SessionEnded { .. } => "session_ended",
+            Self::GatewayConnected { .. } => "gateway_connected",
+            Self::GatewayDisconnected { .. } => "gateway_disconnected",
+        }
+    }
+
+    /// Store event in the outbox. Use the same transaction as the change causing the event.
+    pub async fn enqueue<'e, E>(&self, executor: E) -> Result<(), SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query!(
+            "INSERT INTO event_outbox (event_type, payload) VALUES ($1, $2)",
+            self.event_type(),
+            Json(self) as _
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Domain event waiting in the outbox to be published.
+#[derive(Debug, Serialize)]
+pub struct OutboxEvent {
+    pub id: Id,
+    pub event_type: String,
+    pub payload: Value,
+    pub created_at: NaiveDateTime,
+}
+
+impl OutboxEvent {
+    /// Oldest events waiting to be published. Events are locked until the transaction ends,
+    /// so they are not published twice by concurrent publishers.
+    pub async fn pending(
+        transaction: &mut PgConnection,
+        limit: i64,
+    ) -> Result<Vec<Self>, SqlxError> {
+        query_as!(
+            Self,
+            "SELECT id, event_type, payload, created_at FROM event_outbox \
+            ORDER BY id LIMIT $1 FOR UPDATE SKIP LOCKED",
+            limit
+        )
+        .fetch_all(transaction)
+        .await
+    }
+
+    /// Remove published events from the outbox.
+    pub async fn remove<'e, E>(executor: E, ids: &[Id]) -> Result<(), SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query!("DELETE FROM event_outbox WHERE id = ANY($1)", ids)
+            .execute(executor)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use defguard_common::db::setup_pool;
+    use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+    use super::*;
+
+    #[sqlx::test]
+    async fn test_outbox(_: PgPoolOptions, options: PgConnectOptions) {
+        let pool = setup_pool(options).await;
+
+        let connected = DomainEvent::GatewayConnected {
+            location_id: 1,
+            location_name: "office".into(),
+            hostname: "gateway-1".into(),
+        };
+        let disconnected = DomainEvent::GatewayDisconnected {
+            location_id: 1,
+            location_name: "office".into(),
+            hostname: "gateway-1".into(),
+        };
+        connected.enqueue(&pool).await.unwrap();
+        disconnected.enqueue(&pool).await.unwrap();
+
+        let mut transaction = pool.begin().await.unwrap();
+        let events = OutboxEvent::pending(&mut transaction, 10).await.unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event_type, "gateway_connected");
+        assert_eq!(events[0].payload["type"], "gateway_connected");
+        assert_eq!(
+            serde_json::from_value::<DomainEvent>(events[1].payload.clone()).unwrap(),
+            disconnected
+        );
+
+        // events locked by another publisher are skipped
+        let mut other_transaction = pool.begin().await.unwrap();
+        assert!(
+            OutboxEvent::pending(&mut other_transaction, 10)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        other_transaction.rollback().await.unwrap();
+
+        OutboxEvent::remove(&mut *transaction, &[events[0].id])
+            .await
+            .unwrap();
+        transaction.commit().await.unwrap();
+        let mut transaction = pool.begin().await.unwrap();
+        let events = OutboxEvent::pending(&mut transaction, 10).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_type, "gateway_disconnected");
+    }
+}
@@ -0,0 +1,218 @@
+use chrono::NaiveDateTime;
+use defguard_common::db::{Id, NoId};
+use model_derive::Model;
+use sqlx::{Error as SqlxError, FromRow, PgExecutor, query, query_as, query_scalar};
+use utoipa::ToSchema;
+
+use super::wireguard::{GatewayDistributionPolicy, WireguardNetwork};
+
+/// Public endpoint and weight of a gateway serving a location.
+///
+/// Gateways are identified by their hostname, the same way as in the gateway state map.
+#[derive(Clone, Debug, Deserialize, Model, PartialEq, Serialize, ToSchema)]
+#[table(location_gateway)]
+pub struct LocationGateway<I = NoId> {
+    pub id: I,
+    pub location_id: Id,
+    pub hostname: String,
+    /// Public address of the gateway, location endpoint is used if not set.
+    pub endpoint: Option<String>,
+    /// Share of devices in weighted distribution.
+    pub weight: i32,
+    /// Group whose members are assigned to this gateway by group distribution.
+    pub group_id: Option<Id>,
+}
+
+impl LocationGateway {
+    #[must_use]
+    pub fn new(
+        location_id: Id,
+        hostname: String,
+        endpoint: Option<String>,
+        weight: i32,
+        group_id: Option<Id>,
+    ) -> Self {
+        Self {
+            id: NoId,
+            location_id,
+            hostname,
+            endpoint,
+            weight,
+            group_id,
+        }
+    }
+}
+
+impl LocationGateway<Id> {
+    pub(crate) async fn all_for_location<'e, E>(
+        executor: E,
+        location_id: Id,
+    ) -> Result<Vec<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, location_id, hostname, endpoint, weight, group_id FROM location_gateway \
+            WHERE location_id = $1 ORDER BY hostname",
+            location_id
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    pub(crate) async fn delete_for_location<'e, E>(
+        executor: E,
+        location_id: Id,
+    ) -> Result<(), SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query!(
+            "DELETE FROM location_gateway WHERE location_id = $1",
+            location_id
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Gateway a device should connect to in a location served by multiple gateways.
+#[derive(Clone, Debug, Deserialize, FromRow, PartialEq, Serialize, ToSchema)]
+pub struct DeviceGatewayAssignment {
+    pub device_id: Id,
+    pub location_id: Id,
+    pub hostname: String,
+    pub assigned_at: NaiveDateTime,
+}
+
+impl DeviceGatewayAssignment {
+    pub(crate) async fn all_for_location<'e, E>(
+        executor: E,
+        location_id: Id,
+    ) -> Result<Vec<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT device_id, location_id, hostname, assigned_at FROM device_gateway_assignment \
+            WHERE location_id = $1 ORDER BY device_id",
+            location_id
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    /// Returns hostname of the gateway a device with a given public key is assigned to.
+    pub(crate) async fn hostname_for_pubkey<'e, E>(
+        executor: E,
+        location_id: Id,
+        pubkey: &str,
+    ) -> Result<Option<String>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_scalar!(
+            "SELECT a.hostname FROM device_gateway_assignment a \
+            JOIN device d ON d.id = a.device_id \
+            WHERE a.location_id = $1 AND d.wireguard_pubkey = $2",
+            location_id,
+            pubkey
+        )
+        .fetch_optional(executor)
+        .await
+    }
+
+    /// Returns public keys of devices assigned to a gateway.
+    pub(crate) async fn pubkeys_for_gateway<'e, E>(
+        executor: E,
+        location_id: Id,
+        hostname: &str,
+    ) -> Result<Vec<String>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_scalar!(
+            "SELECT d.wireguard_pubkey FROM device_gateway_assignment a \
+            JOIN device d ON d.id = a.device_id \
+            WHERE a.location_id = $1 AND a.hostname = $2",
+            location_id,
+            hostname
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    /// Assign device to a gateway, replacing previous assignment.
+    pub(crate) async fn assign<'e, E>(
+        executor: E,
+        device_id: Id,
+        location_id: Id,
+        hostname: &str,
+    ) -> Result<(), SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query!(
+            "INSERT INTO device_gateway_assignment (device_id, location_id, hostname) \
+            VALUES ($1, $2, $3) ON CONFLICT (device_id, location_id) \
+            DO UPDATE SET hostname = EXCLUDED.hostname, assigned_at = CURRENT_TIMESTAMP",
+            device_id,
+            location_id,
+            hostname
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn unassign_many<'e, E>(
+        executor: E,
+        location_id: Id,
+        device_ids: &[Id],
+    ) -> Result<(), SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query!(
+            "DELETE FROM device_gateway_assignment \
+            WHERE location_id = $1 AND device_id = ANY($2)",
+            location_id,
+            device_ids
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Returns endpoint a device should connect to in a given location:
+    /// the endpoint of its assigned gateway or the location endpoint.
+    pub(crate) async fn device_endpoint<'e, E>(
+        executor: E,
+        device_id: Id,
+        location: &WireguardNetwork<Id>,
+    ) -> Result<String, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        if location.gateway_distribution_policy == GatewayDistributionPolicy::None {
+            return Ok(location.endpoint.clone());
+        }
+        let endpoint = query_scalar!(
+            "SELECT g.endpoint \"endpoint!\" FROM device_gateway_assignment a \
+            JOIN location_gateway g ON g.location_id = a.location_id AND g.hostname = a.hostname \
+            WHERE a.device_id = $1 AND a.location_id = $2 AND g.endpoint IS NOT NULL",
+            device_id,
+            location.id
+        )
+        .fetch_optional(executor)
+        .await?;
+
+        Ok(endpoint.unwrap_or_else(|| location.endpoint.clone()))
+    }
+}
@@ -0,0 +1,98 @@
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use defguard_common::db::Id;
+use sqlx::{Error as SqlxError, PgConnection, PgExecutor, query, query_as, query_scalar};
+
+/// Number of days journal entries are kept for.
+pub const GATEWAY_JOURNAL_RETENTION_DAYS: i64 = 30;
+
+/// Entry of gateway configuration journal.
+///
+/// Each location has its own, monotonically increasing configuration epoch. `event` is the kind
+/// of `GatewayEvent` the entry was created for and `payload` holds an encoded gateway `Update`,
+/// without private and preshared keys.
+/// Entries without payload mark a gap in the journal (changes which could not be recorded).
+#[derive(Debug)]
+pub struct GatewayJournalEntry {
+    pub id: Id,
+    pub location_id: Id,
+    pub epoch: i64,
+    pub event: String,
+    pub payload: Option<Vec<u8>>,
+    pub created_at: NaiveDateTime,
+}
+
+impl GatewayJournalEntry {
+    /// Store a new entry with the next epoch for a given location and return this epoch.
+    /// Entries older than [`GATEWAY_JOURNAL_RETENTION_DAYS`] are removed.
+    pub(crate) async fn record(
+        conn: &mut PgConnection,
+        location_id: Id,
+        event: &str,
+        payload: Option<&[u8]>,
+    ) -> Result<i64, SqlxError> {
+        let epoch = query_scalar!(
+            "INSERT INTO gateway_config_journal (location_id, epoch, event, payload) \
+            SELECT $1, COALESCE(MAX(epoch), 0) + 1, $2, $3 \
+            FROM gateway_config_journal WHERE location_id = $1 \
+            RETURNING epoch",
+            location_id,
+            event,
+            payload
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+
+        // keep the latest entry, so epochs continue to grow
+        query!(
+            "DELETE FROM gateway_config_journal \
+            WHERE location_id = $1 AND created_at < $2 AND epoch < $3",
+            location_id,
+            Utc::now().naive_utc() - TimeDelta::days(GATEWAY_JOURNAL_RETENTION_DAYS),
+            epoch
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        Ok(epoch)
+    }
+
+    /// Remove all entries of a given location.
+    pub(crate) async fn delete_location<'e, E>(
+        executor: E,
+        location_id: Id,
+    ) -> Result<(), SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query!(
+            "DELETE FROM gateway_config_journal WHERE location_id = $1",
+            location_id
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Fetch entries of a given location created in a given time period, ordered by epoch.
+    pub(crate) async fn all_between<'e, E>(
+        executor: E,
+        location_id: Id,
+        from: NaiveDateTime,
+        until: NaiveDateTime,
+    ) -> Result<Vec<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, location_id, epoch, event, payload, created_at FROM gateway_config_journal \
+            WHERE location_id = $1 AND created_at >= $2 AND created_at <= $3 ORDER BY epoch",
+            location_id,
+            from,
+            until
+        )
+        .fetch_all(executor)
+        .await
+    }
+}
@@ -0,0 +1,66 @@
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use defguard_common::{
+    db::{Id, NoId},
+    random::gen_alphanumeric,
+};
+use model_derive::Model;
+use sqlx::{Error as SqlxError, PgExecutor, query_scalar};
+
+/// How long a gateway setup link can be used for.
+pub const GATEWAY_SETUP_LINK_TIMEOUT: TimeDelta = TimeDelta::hours(1);
+
+/// One-time link for downloading gateway setup bundle (token, gRPC URL, CA certificate and
+/// deployment snippets), so admins don't have to copy secrets manually.
+#[derive(Clone, Debug, Model)]
+#[table(gateway_setup_link)]
+pub struct GatewaySetupLink<I = NoId> {
+    pub id: I,
+    pub token: String,
+    pub network_id: Id,
+    pub created_by: Option<Id>,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+}
+
+impl GatewaySetupLink {
+    #[must_use]
+    pub fn new(network_id: Id, created_by: Id) -> Self {
+        let now = Utc::now().naive_utc();
+        Self {
+            id: NoId,
+            token: gen_alphanumeric(32),
+            network_id,
+            created_by: Some(created_by),
+            created_at: now,
+            expires_at: now + GATEWAY_SETUP_LINK_TIMEOUT,
+        }
+    }
+}
+
+impl GatewaySetupLink<Id> {
+    /// Remove a link and return its network ID, if the link exists and hasn't expired.
+    /// Removing the link in the same statement makes sure it can be used only once.
+    pub(crate) async fn consume<'e, E>(executor: E, token: &str) -> Result<Option<Id>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_scalar!(
+            "DELETE FROM gateway_setup_link WHERE token = $1 AND expires_at > NOW() \
+            RETURNING network_id",
+            token
+        )
+        .fetch_optional(executor)
+        .await
+    }
+
+    /// Remove expired links.
+    pub(crate) async fn delete_expired<'e, E>(executor: E) -> Result<u64, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        let result = sqlx::query!("DELETE FROM gateway_setup_link WHERE expires_at <= NOW()")
+            .execute(executor)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
@@ -5,17 +5,27 @@ use model_derive::Model;
 use sqlx::{Error as SqlxError, FromRow, PgConnection, PgExecutor, query, query_as, query_scalar};
 use utoipa::ToSchema;
 
-use crate::db::{User, WireguardNetwork};
+use crate::{
+    db::{User, WireguardNetwork},
+    enterprise::db::models::enterprise_settings::ClientTrafficPolicy,
+};
 
 #[derive(Debug)]
 pub enum Permission {
     IsAdmin,
+    /// Allows initiating password resets of non-admin users.
+    IsHelpdesk,
+    /// Allows read-only access to activity log, statistics and configuration with personal data
+    /// redacted.
+    IsAuditor,
 }
 
 impl fmt::Display for Permission {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::IsAdmin => write!(f, "is_admin"),
+            Self::IsHelpdesk => write!(f, "is_helpdesk"),
+            Self::IsAuditor => write!(f, "is_auditor"),
         }
     }
 }
@@ -25,6 +35,9 @@ pub struct Group<I = NoId> {
     pub(crate) id: I,
     pub name: String,
     pub is_admin: bool,
+    /// Overrides instance-wide client traffic policy for group members.
+    #[model(enum)]
+    pub client_traffic_policy: Option<ClientTrafficPolicy>,
 }
 
 #[cfg(test)]
@@ -34,6 +47,7 @@ impl Default for Group {
             id: NoId,
             name: Default::default(),
             is_admin: Default::default(),
+            client_traffic_policy: None,
         }
     }
 }
@@ -45,6 +59,7 @@ impl Group {
             id: NoId,
             name: name.into(),
             is_admin: false,
+            client_traffic_policy: None,
         }
     }
 }
@@ -56,7 +71,9 @@ impl Group<Id> {
     {
         query_as!(
             Self,
-            "SELECT id, name, is_admin FROM \"group\" WHERE name = $1",
+            "SELECT id, name, is_admin, \
+            client_traffic_policy \"client_traffic_policy: _\" \
+            FROM \"group\" WHERE name = $1",
             name
         )
         .fetch_optional(executor)
@@ -85,7 +102,7 @@ impl Group<Id> {
             "SELECT \"user\".id, username, password_hash, last_name, first_name, email, \
             phone, mfa_enabled, totp_enabled, totp_secret, email_mfa_enabled, email_mfa_secret, \
             mfa_method \"mfa_method: _\", recovery_codes, is_active, openid_sub, \
-            from_ldap, ldap_pass_randomized, ldap_rdn, ldap_user_path, enrollment_pending \
+            from_ldap, ldap_pass_randomized, ldap_rdn, ldap_user_path, enrollment_pending, mail_language \
             FROM \"user\" \
             JOIN group_user ON \"user\".id = group_user.user_id \
             WHERE group_user.group_id = $1",
@@ -119,7 +136,8 @@ impl Group<Id> {
         E: PgExecutor<'e>,
     {
         let query = format!(
-            "SELECT id, name, is_admin FROM \"group\" WHERE {permission} = TRUE ORDER BY id"
+            "SELECT id, name, is_admin, client_traffic_policy FROM \"group\" \
+            WHERE {permission} = TRUE ORDER BY id"
         );
         query_as(&query).fetch_all(executor).await
     }
@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHash, PasswordVerifier, errors::Error as HashError},
+};
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use defguard_common::{
+    db::{Id, NoId},
+    random::gen_alphanumeric,
+};
+use model_derive::Model;
+use sqlx::{Error as SqlxError, PgExecutor, query, query_as};
+use utoipa::ToSchema;
+
+use super::user::{User, hash_password};
+
+/// Reset is invalidated after this many failed confirmation attempts.
+pub const MAX_FAILED_ATTEMPTS: i32 = 5;
+const CODE_LENGTH: usize = 8;
+
+/// Password reset initiated by helpdesk. The temporary password becomes active only after the
+/// user confirms the reset with a code sent to their email. Resets are kept as an audit trail.
+#[derive(Clone, Debug, Model)]
+#[table(helpdesk_password_reset)]
+pub struct HelpdeskPasswordReset<I = NoId> {
+    pub id: I,
+    pub user_id: Id,
+    pub requested_by: Option<Id>,
+    pub requested_by_username: String,
+    // temporary password, cleared once the reset is confirmed or invalidated
+    pub password_hash: Option<String>,
+    // verification code sent to the user
+    pub code: String,
+    pub failed_attempts: i32,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub confirmed_at: Option<NaiveDateTime>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HelpdeskPasswordResetStatus {
+    /// Awaiting confirmation by the user
+    Pending,
+    Confirmed,
+    Expired,
+    /// Replaced by another reset or blocked after too many failed confirmation attempts
+    Invalidated,
+}
+
+impl HelpdeskPasswordReset {
+    pub fn new(
+        user_id: Id,
+        requested_by: &User<Id>,
+        password: &str,
+        timeout: Duration,
+    ) -> Result<Self, HashError> {
+        let now = Utc::now();
+        Ok(Self {
+            id: NoId,
+            user_id,
+            requested_by: Some(requested_by.id),
+            requested_by_username: requested_by.username.clone(),
+            password_hash: Some(hash_password(password)?),
+            code: gen_alphanumeric(CODE_LENGTH),
+            failed_attempts: 0,
+            created_at: now.naive_utc(),
+            expires_at: (now + TimeDelta::from_std(timeout).unwrap_or_default()).naive_utc(),
+            confirmed_at: None,
+        })
+    }
+}
+
+impl HelpdeskPasswordReset<Id> {
+    #[must_use]
+    pub fn status(&self) -> HelpdeskPasswordResetStatus {
+        if self.confirmed_at.is_some() {
+            HelpdeskPasswordResetStatus::Confirmed
+        } else if self.password_hash.is_none() {
+            HelpdeskPasswordResetStatus::Invalidated
+        } else if self.expires_at < Utc::now().naive_utc() {
+            HelpdeskPasswordResetStatus::Expired
+        } else {
+            HelpdeskPasswordResetStatus::Pending
+        }
+    }
+
+    /// Checks verification code and temporary password given by the user.
+    #[must_use]
+    pub fn verify(&self, code: &str, password: &str) -> bool {
+        let Some(hash) = &self.password_hash else {
+            return false;
+        };
+        let Ok(parsed_hash) = PasswordHash::new(hash) else {
+            return false;
+        };
+        self.code == code
+            && Argon2::default()
+                .verify_password(password.as_bytes(), &parsed_hash)
+                .is_ok()
+    }
+
+    /// All resets of a user, the most recent first.
+    pub async fn all_for_user<'e, E>(executor: E, user_id: Id) -> Result<Vec<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, user_id, requested_by, requested_by_username, password_hash, code, \
+            failed_attempts, created_at, expires_at, confirmed_at FROM helpdesk_password_reset \
+            WHERE user_id = $1 ORDER BY created_at DESC, id DESC",
+            user_id
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    /// The most recent reset of a user awaiting confirmation.
+    pub async fn find_pending<'e, E>(executor: E, user_id: Id) -> Result<Option<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, user_id, requested_by, requested_by_username, password_hash, code, \
+            failed_attempts, created_at, expires_at, confirmed_at FROM helpdesk_password_reset \
+            WHERE user_id = $1 AND password_hash IS NOT NULL AND confirmed_at IS NULL \
+            AND expires_at > now() ORDER BY created_at DESC, id DESC LIMIT 1",
+            user_id
+        )
+        .fetch_optional(executor)
+        .await
+    }
+
+    /// Invalidates resets of a user which haven't been confirmed, e.g. when a new reset is
+    /// initiated.
+    pub async fn invalidate_unconfirmed<'e, E>(executor: E, user_id: Id) -> Result<(), SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query!(
+            "UPDATE helpdesk_password_reset SET password_hash = NULL \
+            WHERE user_id = $1 AND password_hash IS NOT NULL AND confirmed_at IS NULL",
+            user_id
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,239 @@
+use chrono::{NaiveDateTime, Utc};
+use defguard_common::db::{Id, NoId};
+use model_derive::Model;
+use serde_json::{Map, Value};
+use sqlx::{Error as SqlxError, FromRow, PgExecutor, Type, query_as};
+use strum_macros::{Display, EnumString};
+use tera::{Context, Tera};
+use utoipa::ToSchema;
+
+/// Default template of ticket summary.
+pub const DEFAULT_SUMMARY_TEMPLATE: &str = "{{ title }}";
+/// Default template of ticket description.
+pub const DEFAULT_DESCRIPTION_TEMPLATE: &str = "{{ details }}";
+
+#[derive(Clone, Debug, Deserialize, Display, EnumString, PartialEq, Serialize, ToSchema, Type)]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ItsmConnectorType {
+    #[strum(serialize = "service_now")]
+    ServiceNow,
+    #[strum(serialize = "jira")]
+    Jira,
+}
+
+/// Connector to an ITSM system, which opens tickets for configured kinds of incidents.
+///
+/// Ticket summary, description and additional `fields` are Tera templates rendered with
+/// incident data.
+#[derive(Clone, Debug, Deserialize, FromRow, Model, PartialEq, Serialize, ToSchema)]
+#[table(itsm_connector)]
+pub struct ItsmConnector<I = NoId> {
+    pub id: I,
+    pub name: String,
+    #[model(enum)]
+    pub connector_type: ItsmConnectorType,
+    /// Base URL of ServiceNow instance or Jira site
+    pub url: String,
+    pub username: String,
+    /// ServiceNow password or Jira API token
+    #[serde(skip_serializing)]
+    pub secret: String,
+    /// Jira project key, or ServiceNow assignment group
+    pub project: Option<String>,
+    /// Jira issue type (`Task` by default), or ServiceNow table (`incident` by default)
+    pub ticket_type: Option<String>,
+    pub enabled: bool,
+    pub on_gateway_down: bool,
+    /// Minutes a gateway has to be disconnected for, before a ticket is opened
+    pub gateway_down_threshold: i32,
+    pub on_license_expired: bool,
+    pub on_certificate_expiring: bool,
+    /// Days before certificate expiration when a ticket is opened
+    pub certificate_expiration_threshold: i32,
+    pub summary_template: String,
+    pub description_template: String,
+    /// Additional ticket fields, mapping field names to templates of their values
+    #[schema(value_type = Object)]
+    pub fields: Value,
+}
+
+/// Ticket contents rendered from connector templates.
+#[derive(Debug, PartialEq)]
+pub struct RenderedTicket {
+    pub summary: String,
+    pub description: String,
+    pub fields: Map<String, Value>,
+}
+
+impl<I> ItsmConnector<I> {
+    fn field_templates(&self) -> Result<Vec<(&String, &str)>, tera::Error> {
+        let Value::Object(fields) = &self.fields else {
+            return Err(tera::Error::msg("fields have to be an object"));
+        };
+        fields
+            .iter()
+            .map(|(name, template)| {
+                template
+                    .as_str()
+                    .map(|template| (name, template))
+                    .ok_or_else(|| tera::Error::msg(format!("field {name} is not a template")))
+            })
+            .collect()
+    }
+
+    /// Check if all templates can be parsed.
+    pub fn validate_templates(&self) -> Result<(), tera::Error> {
+        let mut tera = Tera::default();
+        tera.add_raw_template("summary", &self.summary_template)?;
+        tera.add_raw_template("description", &self.description_template)?;
+        for (name, template) in self.field_templates()? {
+            tera.add_raw_template(name, template)?;
+        }
+        Ok(())
+    }
+
+    /// Render ticket contents from incident data.
+    pub(crate) fn render(&self, data: &Value) -> Result<RenderedTicket, tera::Error> {
+        let context = Context::from_value(data.clone())?;
+        let mut fields = Map::new();
+        for (name, template) in self.field_templates()? {
+            let value = Tera::one_off(template, &context, false)?;
+            fields.insert(name.clone(), Value::String(value));
+        }
+
+        Ok(RenderedTicket {
+            summary: Tera::one_off(&self.summary_template, &context, false)?,
+            description: Tera::one_off(&self.description_template, &context, false)?,
+            fields,
+        })
+    }
+}
+
+impl ItsmConnector<Id> {
+    /// Fetch all enabled connectors.
+    pub(crate) async fn all_enabled<'e, E>(executor: E) -> Result<Vec<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, name, connector_type \"connector_type: ItsmConnectorType\", url, \
+            username, secret, project, ticket_type, enabled, on_gateway_down, \
+            gateway_down_threshold, on_license_expired, on_certificate_expiring, \
+            certificate_expiration_threshold, summary_template, description_template, fields \
+            FROM itsm_connector WHERE enabled ORDER BY id"
+        )
+        .fetch_all(executor)
+        .await
+    }
+}
+
+/// Ticket opened for an ongoing incident.
+///
+/// It is removed once the incident is over, so a new ticket is opened if it happens again.
+#[derive(Clone, Debug, Deserialize, Model, PartialEq, Serialize)]
+#[table(itsm_ticket)]
+pub struct ItsmTicket<I = NoId> {
+    pub id: I,
+    pub connector_id: Id,
+    /// Key identifying the incident, e.g. `gateway_down:1:gateway-1`
+    pub incident: String,
+    /// Ticket number or key in the ITSM system
+    pub ticket: String,
+    pub opened_at: NaiveDateTime,
+}
+
+impl ItsmTicket {
+    #[must_use]
+    pub fn new(connector_id: Id, incident: String, ticket: String) -> Self {
+        Self {
+            id: NoId,
+            connector_id,
+            incident,
+            ticket,
+            opened_at: Utc::now().naive_utc(),
+        }
+    }
+}
+
+impl ItsmTicket<Id> {
+    pub(crate) async fn all_for_connector<'e, E>(
+        executor: E,
+        connector_id: Id,
+    ) -> Result<Vec<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, connector_id, incident, ticket, opened_at FROM itsm_ticket \
+            WHERE connector_id = $1 ORDER BY id",
+            connector_id
+        )
+        .fetch_all(executor)
+        .await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    fn connector(fields: Value) -> ItsmConnector {
+        ItsmConnector {
+            id: NoId,
+            name: "Service desk".into(),
+            connector_type: ItsmConnectorType::Jira,
+            url: "https://example.atlassian.net".into(),
+            username: "ops@example.com".into(),
+            secret: "token".into(),
+            project: Some("OPS".into()),
+            ticket_type: None,
+            enabled: true,
+            on_gateway_down: true,
+            gateway_down_threshold: 15,
+            on_license_expired: false,
+            on_certificate_expiring: false,
+            certificate_expiration_threshold: 14,
+            summary_template: "[{{ event }}] {{ title }}".into(),
+            description_template: DEFAULT_DESCRIPTION_TEMPLATE.into(),
+            fields,
+        }
+    }
+
+    #[test]
+    fn test_itsm_connector_render() {
+        let connector = connector(json!({"labels": "{{ location }}"}));
+        assert!(connector.validate_templates().is_ok());
+        let ticket = connector
+            .render(&json!({
+                "event": "gateway_down",
+                "title": "Gateway gw-1 is down",
+                "details": "Gateway gw-1 has been disconnected for 20 minutes.",
+                "location": "Office",
+            }))
+            .unwrap();
+        assert_eq!(ticket.summary, "[gateway_down] Gateway gw-1 is down");
+        assert_eq!(
+            ticket.description,
+            "Gateway gw-1 has been disconnected for 20 minutes."
+        );
+        assert_eq!(ticket.fields["labels"], "Office");
+
+        // invalid templates
+        assert!(
+            connector(json!({"labels": "{{ location"}))
+                .validate_templates()
+                .is_err()
+        );
+        assert!(
+            connector(json!({"labels": 1}))
+                .validate_templates()
+                .is_err()
+        );
+        assert!(connector(json!([])).validate_templates().is_err());
+    }
+}
@@ -0,0 +1,97 @@
+use defguard_common::db::Id;
+use sqlx::{Error as SqlxError, PgExecutor, query, query_as};
+use utoipa::ToSchema;
+
+/// Countries desktop client MFA authorizations of a location are allowed or blocked from,
+/// identified by ISO 3166-1 alpha-2 codes of client public addresses.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct LocationGeofence {
+    pub location_id: Id,
+    /// Only these countries are allowed if not empty
+    pub allowed_countries: Vec<String>,
+    pub blocked_countries: Vec<String>,
+}
+
+impl LocationGeofence {
+    /// Geofence of a location; without any restrictions if it's not configured.
+    pub async fn for_location<'e, E>(executor: E, location_id: Id) -> Result<Self, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        let geofence = query_as!(
+            Self,
+            "SELECT location_id, allowed_countries, blocked_countries FROM location_geofence \
+            WHERE location_id = $1",
+            location_id
+        )
+        .fetch_optional(executor)
+        .await?;
+
+        Ok(geofence.unwrap_or_else(|| Self {
+            location_id,
+            ..Default::default()
+        }))
+    }
+
+    pub async fn save<'e, E>(&self, executor: E) -> Result<(), SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query!(
+            "INSERT INTO location_geofence (location_id, allowed_countries, blocked_countries) \
+            VALUES ($1, $2, $3) ON CONFLICT (location_id) DO UPDATE \
+            SET allowed_countries = EXCLUDED.allowed_countries, \
+            blocked_countries = EXCLUDED.blocked_countries",
+            self.location_id,
+            &self.allowed_countries,
+            &self.blocked_countries,
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn is_restricted(&self) -> bool {
+        !(self.allowed_countries.is_empty() && self.blocked_countries.is_empty())
+    }
+
+    /// Checks if authorization from a given country is allowed. Unknown country is allowed only
+    /// if there's no list of allowed countries.
+    #[must_use]
+    pub fn allows(&self, country: Option<&str>) -> bool {
+        match country {
+            Some(country) => {
+                !self.blocked_countries.iter().any(|code| code == country)
+                    && (self.allowed_countries.is_empty()
+                        || self.allowed_countries.iter().any(|code| code == country))
+            }
+            None => self.allowed_countries.is_empty(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_geofence_allows() {
+        let mut geofence = LocationGeofence::default();
+        assert!(!geofence.is_restricted());
+        assert!(geofence.allows(Some("PL")));
+        assert!(geofence.allows(None));
+
+        geofence.blocked_countries = vec!["RU".into(), "KP".into()];
+        assert!(geofence.is_restricted());
+        assert!(geofence.allows(Some("PL")));
+        assert!(!geofence.allows(Some("KP")));
+        assert!(geofence.allows(None));
+
+        geofence.allowed_countries = vec!["PL".into(), "DE".into()];
+        assert!(geofence.allows(Some("DE")));
+        assert!(!geofence.allows(Some("US")));
+        assert!(!geofence.allows(None));
+    }
+}
@@ -0,0 +1,115 @@
+use chrono::{NaiveDateTime, Utc};
+use defguard_common::db::{Id, NoId};
+use model_derive::Model;
+use sqlx::{Error as SqlxError, PgExecutor, query, query_as, query_scalar};
+use utoipa::ToSchema;
+
+/// Version of the banner shown before logging in, e.g. a legal notice of a government system.
+/// Users have to acknowledge each version once before logging in.
+#[derive(Clone, Debug, Deserialize, Model, PartialEq, Serialize, ToSchema)]
+#[table(login_banner)]
+pub struct LoginBanner<I = NoId> {
+    pub id: I,
+    pub content: String,
+    /// Only the latest active version is shown
+    pub active: bool,
+    pub created_at: NaiveDateTime,
+}
+
+/// User who acknowledged a version of the login banner.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct LoginBannerAcknowledgment {
+    pub username: String,
+    pub acknowledged_at: NaiveDateTime,
+}
+
+impl LoginBanner {
+    #[must_use]
+    pub fn new(content: String) -> Self {
+        Self {
+            id: NoId,
+            content,
+            active: true,
+            created_at: Utc::now().naive_utc(),
+        }
+    }
+}
+
+impl LoginBanner<Id> {
+    /// Current version of the banner, `None` if there's no active banner.
+    pub async fn current<'e, E>(executor: E) -> Result<Option<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, content, active, created_at FROM login_banner \
+            WHERE active ORDER BY id DESC LIMIT 1"
+        )
+        .fetch_optional(executor)
+        .await
+    }
+
+    /// Deactivate all versions, so no banner is shown.
+    pub(crate) async fn deactivate_all<'e, E>(executor: E) -> Result<(), SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query!("UPDATE login_banner SET active = false WHERE active")
+            .execute(executor)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn is_acknowledged<'e, E>(&self, executor: E, user_id: Id) -> Result<bool, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_scalar!(
+            "SELECT EXISTS (SELECT 1 FROM login_banner_acknowledgment \
+            WHERE user_id = $1 AND banner_id = $2) \"exists!\"",
+            user_id,
+            self.id
+        )
+        .fetch_one(executor)
+        .await
+    }
+
+    /// Record acknowledgment of this version by a user, unless it's already recorded.
+    pub async fn acknowledge<'e, E>(&self, executor: E, user_id: Id) -> Result<(), SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query!(
+            "INSERT INTO login_banner_acknowledgment (user_id, banner_id, acknowledged_at) \
+            VALUES ($1, $2, $3) ON CONFLICT DO NOTHING",
+            user_id,
+            self.id,
+            Utc::now().naive_utc()
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Users who acknowledged this version, the most recent first.
+    pub async fn acknowledgments<'e, E>(
+        &self,
+        executor: E,
+    ) -> Result<Vec<LoginBannerAcknowledgment>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            LoginBannerAcknowledgment,
+            "SELECT u.username, a.acknowledged_at FROM login_banner_acknowledgment a \
+            JOIN \"user\" u ON u.id = a.user_id WHERE a.banner_id = $1 \
+            ORDER BY a.acknowledged_at DESC, u.username",
+            self.id
+        )
+        .fetch_all(executor)
+        .await
+    }
+}
@@ -0,0 +1,79 @@
+use chrono::NaiveDateTime;
+use defguard_common::db::{Id, NoId};
+use ipnetwork::IpNetwork;
+use model_derive::Model;
+use sqlx::{Error as SqlxError, PgExecutor, Type, query_as, query_scalar};
+use utoipa::ToSchema;
+
+/// Where a login attempt was made.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, ToSchema, Type)]
+#[sqlx(type_name = "login_source", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum LoginSource {
+    /// Web UI login, either with password or with an MFA method.
+    Web,
+    /// Desktop client MFA when connecting to a location, relayed by the proxy.
+    ClientMfa,
+}
+
+/// Successful or failed login attempt of a user.
+#[derive(Clone, Debug, Deserialize, Model, PartialEq, Serialize, ToSchema)]
+#[table(login_history)]
+pub struct LoginRecord<I = NoId> {
+    pub id: I,
+    pub user_id: Id,
+    pub timestamp: NaiveDateTime,
+    #[model(enum)]
+    pub source: LoginSource,
+    /// Password or the MFA method used
+    pub method: String,
+    pub success: bool,
+    #[schema(value_type = String)]
+    pub ip: IpNetwork,
+    /// Browser user agent for web logins, device name for client MFA
+    pub device: String,
+    /// Location connected to with client MFA
+    pub location: Option<String>,
+    /// Proxy which relayed client MFA
+    pub proxy: Option<String>,
+    /// Reason of a failure
+    pub message: Option<String>,
+}
+
+impl LoginRecord<Id> {
+    /// Page of login history of a user, the most recent first.
+    pub async fn page_for_user<'e, E>(
+        executor: E,
+        user_id: Id,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, user_id, timestamp, source \"source: _\", method, success, ip, device, \
+            location, proxy, message FROM login_history WHERE user_id = $1 \
+            ORDER BY timestamp DESC, id DESC LIMIT $2 OFFSET $3",
+            user_id,
+            limit,
+            offset
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    /// Number of login attempts of a user.
+    pub async fn count_for_user<'e, E>(executor: E, user_id: Id) -> Result<i64, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_scalar!(
+            "SELECT count(*) \"count!\" FROM login_history WHERE user_id = $1",
+            user_id
+        )
+        .fetch_one(executor)
+        .await
+    }
+}
@@ -0,0 +1,64 @@
+use chrono::{NaiveDateTime, Utc};
+use defguard_common::db::{Id, NoId};
+use model_derive::Model;
+use sqlx::{Error as SqlxError, PgExecutor, query_as};
+use utoipa::ToSchema;
+
+/// Scheduled downtime of location gateways. Times are in UTC.
+#[derive(Clone, Debug, Deserialize, Model, PartialEq, Serialize, ToSchema)]
+#[table(maintenance_window)]
+pub struct MaintenanceWindow<I = NoId> {
+    pub id: I,
+    pub location_id: Id,
+    pub starts_at: NaiveDateTime,
+    pub ends_at: NaiveDateTime,
+    pub description: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl MaintenanceWindow {
+    #[must_use]
+    pub fn new(
+        location_id: Id,
+        starts_at: NaiveDateTime,
+        ends_at: NaiveDateTime,
+        description: String,
+    ) -> Self {
+        Self {
+            id: NoId,
+            location_id,
+            starts_at,
+            ends_at,
+            description,
+            created_at: Utc::now().naive_utc(),
+        }
+    }
+}
+
+impl MaintenanceWindow<Id> {
+    /// Maintenance windows of a location which haven't ended yet, the earliest first.
+    pub async fn upcoming_for_location<'e, E>(
+        executor: E,
+        location_id: Id,
+    ) -> Result<Vec<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, location_id, starts_at, ends_at, description, created_at \
+            FROM maintenance_window WHERE location_id = $1 AND ends_at > $2 \
+            ORDER BY starts_at",
+            location_id,
+            Utc::now().naive_utc()
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    /// Calendar event UID, unique across Defguard instances.
+    #[must_use]
+    pub fn calendar_uid(&self, host: &str) -> String {
+        format!("maintenance-window-{}@{host}", self.id)
+    }
+}
@@ -0,0 +1,153 @@
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use defguard_common::db::Id;
+use sqlx::{Error as SqlxError, PgExecutor, query, query_scalar};
+
+use super::{device::Device, wireguard::WireguardNetwork};
+
+/// Device which completed MFA for a location. Remembered devices can connect again without
+/// repeating MFA, as long as the location allows it and the device keeps its public key.
+#[derive(Clone, Debug)]
+pub struct MfaRememberedDevice {
+    pub wireguard_pubkey: String,
+    pub location_id: Id,
+    pub device_id: Id,
+    pub remembered_at: NaiveDateTime,
+}
+
+impl MfaRememberedDevice {
+    #[must_use]
+    pub fn new(device: &Device<Id>, location: &WireguardNetwork<Id>) -> Self {
+        Self {
+            wireguard_pubkey: device.wireguard_pubkey.clone(),
+            location_id: location.id,
+            device_id: device.id,
+            remembered_at: Utc::now().naive_utc(),
+        }
+    }
+
+    /// Store device, replacing previous entry for the same public key and location.
+    pub async fn save<'e, E>(&self, executor: E) -> Result<(), SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query!(
+            "INSERT INTO mfa_remembered_device \
+            (wireguard_pubkey, location_id, device_id, remembered_at) \
+            VALUES ($1, $2, $3, $4) \
+            ON CONFLICT (wireguard_pubkey, location_id) DO UPDATE \
+            SET device_id = EXCLUDED.device_id, remembered_at = EXCLUDED.remembered_at",
+            self.wireguard_pubkey,
+            self.location_id,
+            self.device_id,
+            self.remembered_at
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Check if device with a given public key is still remembered for a location.
+    pub async fn is_remembered<'e, E>(
+        executor: E,
+        pubkey: &str,
+        location: &WireguardNetwork<Id>,
+    ) -> Result<bool, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        if location.mfa_remember_device_hours <= 0 {
+            return Ok(false);
+        }
+        let remembered_since =
+            Utc::now().naive_utc() - TimeDelta::hours(location.mfa_remember_device_hours.into());
+
+        query_scalar!(
+            "SELECT EXISTS (SELECT 1 FROM mfa_remembered_device \
+            WHERE wireguard_pubkey = $1 AND location_id = $2 AND remembered_at > $3) \"exists!\"",
+            pubkey,
+            location.id,
+            remembered_since
+        )
+        .fetch_one(executor)
+        .await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use defguard_common::db::setup_pool;
+    use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+    use super::*;
+    use crate::db::{User, models::device::DeviceType};
+
+    #[sqlx::test]
+    async fn test_remembered_device(_: PgPoolOptions, options: PgConnectOptions) {
+        let pool = setup_pool(options).await;
+
+        let mut network = WireguardNetwork::default();
+        network.mfa_remember_device_hours = 8;
+        let mut network = network.save(&pool).await.unwrap();
+        let user = User::new(
+            "hpotter",
+            Some("pass123"),
+            "Potter",
+            "Harry",
+            "h.potter@hogwart.edu.uk",
+            None,
+        )
+        .save(&pool)
+        .await
+        .unwrap();
+        let device = Device::new(
+            "dev1".into(),
+            "key1".into(),
+            user.id,
+            DeviceType::User,
+            None,
+            true,
+        )
+        .save(&pool)
+        .await
+        .unwrap();
+
+        assert!(
+            !MfaRememberedDevice::is_remembered(&pool, "key1", &network)
+                .await
+                .unwrap()
+        );
+
+        let mut remembered = MfaRememberedDevice::new(&device, &network);
+        remembered.save(&pool).await.unwrap();
+        assert!(
+            MfaRememberedDevice::is_remembered(&pool, "key1", &network)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !MfaRememberedDevice::is_remembered(&pool, "key2", &network)
+                .await
+                .unwrap()
+        );
+
+        // remembered too long ago
+        remembered.remembered_at -= TimeDelta::hours(9);
+        remembered.save(&pool).await.unwrap();
+        assert!(
+            !MfaRememberedDevice::is_remembered(&pool, "key1", &network)
+                .await
+                .unwrap()
+        );
+
+        // location doesn't remember devices anymore
+        remembered.remembered_at = Utc::now().naive_utc();
+        remembered.save(&pool).await.unwrap();
+        network.mfa_remember_device_hours = 0;
+        assert!(
+            !MfaRememberedDevice::is_remembered(&pool, "key1", &network)
+                .await
+                .unwrap()
+        );
+    }
+}
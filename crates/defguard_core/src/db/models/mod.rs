@@ -1,11 +1,31 @@
 pub mod activity_log;
+pub mod announcement;
+pub mod background_job;
 pub mod device;
+pub mod device_approval;
+pub mod device_endpoint_change;
+pub mod dns_canary;
 pub mod enrollment;
+pub mod event_outbox;
+pub mod gateway_distribution;
+pub mod gateway_journal;
+pub mod gateway_setup_link;
 pub mod group;
+pub mod helpdesk_password_reset;
+pub mod itsm;
+pub mod location_geofence;
+pub mod login_banner;
+pub mod login_history;
+pub mod maintenance_window;
+pub mod mfa_remembered_device;
+pub mod notification;
 pub mod oauth2authorizedapp;
 pub mod oauth2client;
 pub mod oauth2token;
 pub mod polling_token;
+pub mod route;
+pub mod self_registration;
+pub mod service_account;
 pub mod session;
 pub mod user;
 pub mod webauthn;
@@ -14,24 +34,31 @@ pub mod wireguard;
 pub mod wireguard_peer_stats;
 pub mod yubikey;
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use defguard_common::db::{
     Id,
     models::{BiometricAuth, MFAMethod},
 };
+use serde_json::{Map, Value};
 use sqlx::{Error as SqlxError, PgConnection, PgPool, query_as};
 use utoipa::ToSchema;
 
 use self::{device::UserDevice, user::User};
 use super::Group;
 
-#[derive(Deserialize, Serialize)]
+#[derive(Default, Deserialize, Serialize)]
 pub struct NewOpenIDClient {
     pub name: String,
     pub redirect_uri: Vec<String>,
     pub scope: Vec<String>,
     pub enabled: bool,
+    #[serde(default)]
+    pub claim_mapping: HashMap<String, String>,
+    #[serde(default)]
+    pub group_filter: Vec<String>,
+    #[serde(default)]
+    pub static_claims: Map<String, Value>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
@@ -67,6 +94,9 @@ pub struct UserInfo {
     pub enrolled: bool,
     pub is_admin: bool,
     pub ldap_pass_requires_change: bool,
+    /// Language of mails sent to the user, instance default is used if not set
+    #[serde(default)]
+    pub mail_language: Option<String>,
 }
 
 #[derive(Debug, Default)]
@@ -104,6 +134,7 @@ impl UserInfo {
             enrolled: user.is_enrolled(),
             is_admin: user.is_admin(pool).await?,
             ldap_pass_requires_change: user.ldap_pass_randomized,
+            mail_language: user.mail_language.clone(),
         })
     }
 
@@ -173,6 +204,7 @@ impl UserInfo {
     pub fn into_user_safe_fields(self, user: &mut User<Id>) -> Result<(), SqlxError> {
         user.phone = self.phone;
         user.mfa_method = self.mfa_method;
+        user.mail_language = self.mail_language;
 
         Ok(())
     }
@@ -184,6 +216,7 @@ impl UserInfo {
         user.last_name = self.last_name;
         user.first_name = self.first_name;
         user.email = self.email;
+        user.mail_language = self.mail_language;
 
         Ok(())
     }
@@ -0,0 +1,174 @@
+use chrono::{NaiveDateTime, Utc};
+use defguard_common::db::{Id, NoId};
+use model_derive::Model;
+use sqlx::{Error as SqlxError, PgExecutor, Type, query, query_as};
+use utoipa::ToSchema;
+
+/// Category of alerts sent to admins.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize, ToSchema, Type)]
+#[sqlx(type_name = "notification_category", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationCategory {
+    /// Device is awaiting approval.
+    DeviceApproval,
+    /// Self-registered user is awaiting activation.
+    SelfRegistration,
+    GatewayDisconnected,
+    GatewayReconnected,
+    /// Mails are sent through the failover SMTP profile, or the primary server recovered.
+    SmtpFailover,
+    /// Connection to the proxy has been lost or restored.
+    ProxyConnectivity,
+}
+
+/// Channel through which alerts are delivered.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize, ToSchema, Type)]
+#[sqlx(type_name = "notification_channel", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationChannel {
+    Email,
+    /// Stored as [`Notification`] and listed in the web UI.
+    InApp,
+    /// Posted as JSON to rule webhook URL.
+    Webhook,
+}
+
+/// Alert category an admin receives through a channel. Admins without any rules receive all
+/// alerts by email.
+#[derive(Clone, Debug, Deserialize, Model, PartialEq, Serialize, ToSchema)]
+#[table(notification_rule)]
+pub struct NotificationRule<I = NoId> {
+    pub id: I,
+    pub user_id: Id,
+    #[model(enum)]
+    pub category: NotificationCategory,
+    #[model(enum)]
+    pub channel: NotificationChannel,
+    /// Required for the webhook channel
+    pub webhook_url: Option<String>,
+}
+
+impl NotificationRule {
+    #[must_use]
+    pub fn new(
+        user_id: Id,
+        category: NotificationCategory,
+        channel: NotificationChannel,
+        webhook_url: Option<String>,
+    ) -> Self {
+        Self {
+            id: NoId,
+            user_id,
+            category,
+            channel,
+            webhook_url,
+        }
+    }
+}
+
+impl NotificationRule<Id> {
+    /// Rules of a user, ordered by category and channel.
+    pub async fn all_for_user<'e, E>(executor: E, user_id: Id) -> Result<Vec<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, user_id, category \"category: _\", channel \"channel: _\", webhook_url \
+            FROM notification_rule WHERE user_id = $1 ORDER BY category, channel",
+            user_id
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    /// Remove all rules of a user.
+    pub(crate) async fn delete_for_user<'e, E>(executor: E, user_id: Id) -> Result<(), SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query!("DELETE FROM notification_rule WHERE user_id = $1", user_id)
+            .execute(executor)
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Alert delivered to an admin through the in-app channel.
+#[derive(Clone, Debug, Deserialize, Model, Serialize, ToSchema)]
+#[table(notification)]
+pub struct Notification<I = NoId> {
+    pub id: I,
+    pub user_id: Id,
+    #[model(enum)]
+    pub category: NotificationCategory,
+    pub subject: String,
+    pub message: String,
+    pub created_at: NaiveDateTime,
+    pub read_at: Option<NaiveDateTime>,
+}
+
+impl Notification {
+    #[must_use]
+    pub fn new(
+        user_id: Id,
+        category: NotificationCategory,
+        subject: String,
+        message: String,
+    ) -> Self {
+        Self {
+            id: NoId,
+            user_id,
+            category,
+            subject,
+            message,
+            created_at: Utc::now().naive_utc(),
+            read_at: None,
+        }
+    }
+}
+
+impl Notification<Id> {
+    /// Notifications of a user, the most recent first.
+    pub async fn all_for_user<'e, E>(
+        executor: E,
+        user_id: Id,
+        unread_only: bool,
+    ) -> Result<Vec<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, user_id, category \"category: _\", subject, message, created_at, read_at \
+            FROM notification WHERE user_id = $1 AND (NOT $2 OR read_at IS NULL) \
+            ORDER BY created_at DESC, id DESC",
+            user_id,
+            unread_only
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    /// Mark notification of a user as read. Returns `false` if there is no such notification.
+    pub(crate) async fn mark_read<'e, E>(
+        executor: E,
+        id: Id,
+        user_id: Id,
+    ) -> Result<bool, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        let result = query!(
+            "UPDATE notification SET read_at = COALESCE(read_at, $3) WHERE id = $1 AND user_id = $2",
+            id,
+            user_id,
+            Utc::now().naive_utc()
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -1,9 +1,12 @@
+use std::collections::HashMap;
+
 use defguard_common::{
     db::{Id, NoId},
     random::gen_alphanumeric,
 };
 use model_derive::Model;
-use sqlx::{Error as SqlxError, PgExecutor, PgPool, query_as};
+use serde_json::{Map, Value};
+use sqlx::{Error as SqlxError, PgExecutor, PgPool, query_as, types::Json};
 
 use super::NewOpenIDClient;
 use crate::db::OAuth2Token;
@@ -20,6 +23,70 @@ pub struct OAuth2Client<I = NoId> {
     // informational
     pub name: String,
     pub enabled: bool,
+    /// Names under which user claims are emitted, e.g. `preferred_username` as `uid`.
+    #[model(ref)]
+    pub claim_mapping: Json<HashMap<String, String>>,
+    /// Groups included in the `groups` claim; all groups of a user if empty.
+    #[model(ref)]
+    pub group_filter: Vec<String>,
+    /// Claims with fixed values emitted for every user.
+    #[model(ref)]
+    pub static_claims: Json<Map<String, Value>>,
+}
+
+/// User claims which can be emitted under a different name.
+const MAPPABLE_CLAIMS: [&str; 7] = [
+    "email",
+    "family_name",
+    "given_name",
+    "groups",
+    "name",
+    "phone_number",
+    "preferred_username",
+];
+
+/// Claims set by the provider itself, which can't be configured.
+const RESERVED_CLAIMS: [&str; 12] = [
+    "acr",
+    "amr",
+    "at_hash",
+    "aud",
+    "auth_time",
+    "azp",
+    "c_hash",
+    "exp",
+    "iat",
+    "iss",
+    "nonce",
+    "sub",
+];
+
+impl NewOpenIDClient {
+    /// Makes sure configured claims don't clash with each other or with claims set by the
+    /// provider.
+    pub(crate) fn validate_claims(&self) -> Result<(), String> {
+        let mut names = Vec::new();
+        for (claim, name) in &self.claim_mapping {
+            if !MAPPABLE_CLAIMS.contains(&claim.as_str()) {
+                return Err(format!("claim {claim} can't be mapped"));
+            }
+            if name.is_empty() {
+                return Err(format!("missing name for claim {claim}"));
+            }
+            names.push(name.as_str());
+        }
+        names.extend(self.static_claims.keys().map(String::as_str));
+        for (index, name) in names.iter().enumerate() {
+            if RESERVED_CLAIMS.contains(name) || MAPPABLE_CLAIMS.contains(name) {
+                return Err(format!("claim name {name} is reserved"));
+            }
+            if names[..index].contains(name) {
+                return Err(format!("claim name {name} is used more than once"));
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl OAuth2Client {
@@ -35,6 +102,9 @@ impl OAuth2Client {
             scope,
             name,
             enabled: true,
+            claim_mapping: Json(HashMap::new()),
+            group_filter: Vec::new(),
+            static_claims: Json(Map::new()),
         }
     }
 
@@ -50,6 +120,9 @@ impl OAuth2Client {
             scope: new.scope,
             name: new.name,
             enabled: new.enabled,
+            claim_mapping: Json(new.claim_mapping),
+            group_filter: new.group_filter,
+            static_claims: Json(new.static_claims),
         }
     }
 }
@@ -65,7 +138,8 @@ impl OAuth2Client<Id> {
     {
         query_as!(
             Self,
-            "SELECT id, client_id, client_secret, redirect_uri, scope, name, enabled \
+            "SELECT id, client_id, client_secret, redirect_uri, scope, name, enabled, \
+            claim_mapping \"claim_mapping: _\", group_filter, static_claims \"static_claims: _\" \
             FROM oauth2client WHERE client_id = $1",
             client_id
         )
@@ -94,7 +168,8 @@ impl OAuth2Client<Id> {
     ) -> Result<Option<Self>, SqlxError> {
         query_as!(
             Self,
-            "SELECT id, client_id, client_secret, redirect_uri, scope, name, enabled \
+            "SELECT id, client_id, client_secret, redirect_uri, scope, name, enabled, \
+            claim_mapping \"claim_mapping: _\", group_filter, static_claims \"static_claims: _\" \
             FROM oauth2client WHERE client_id = $1 AND client_secret = $2 AND enabled",
             client_id,
             client_secret
@@ -109,7 +184,9 @@ impl OAuth2Client<Id> {
     ) -> Result<Option<Self>, SqlxError> {
         query_as!(
             Self,
-            "SELECT c.id, c.client_id, c.client_secret, c.redirect_uri, c.scope, c.name, c.enabled \
+            "SELECT c.id, c.client_id, c.client_secret, c.redirect_uri, c.scope, c.name, c.enabled, \
+            c.claim_mapping \"claim_mapping: _\", c.group_filter, \
+            c.static_claims \"static_claims: _\" \
             FROM oauth2client c \
             JOIN oauth2authorizedapp a ON a.oauth2client_id = c.id \
             JOIN oauth2token t ON t.oauth2authorizedapp_id = a.id \
@@ -170,6 +247,9 @@ mod tests {
             scope: Vec::new(),
             name: String::new(),
             enabled: true,
+            claim_mapping: Json(HashMap::new()),
+            group_filter: Vec::new(),
+            static_claims: Json(Map::new()),
         };
         assert!(oauth2client.contains_redirect_url("http://safe.net"));
         assert!(oauth2client.contains_redirect_url("http://localhost"));
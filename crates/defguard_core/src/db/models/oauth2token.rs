@@ -1,6 +1,19 @@
 use chrono::{TimeDelta, Utc};
 use defguard_common::{config::server_config, db::Id, random::gen_alphanumeric};
-use sqlx::{Error as SqlxError, PgPool, query, query_as};
+use sqlx::{Error as SqlxError, PgExecutor, PgPool, Type, query, query_as};
+
+/// Why a refresh token can't be used anymore.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, Type)]
+#[sqlx(type_name = "oauth2_revocation_reason", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum OAuth2RevocationReason {
+    /// Replaced by a new refresh token.
+    Rotated,
+    /// Revoked by an administrator.
+    Admin,
+    /// Revoked because a rotated refresh token of the same app was used again.
+    Replay,
+}
 
 pub struct OAuth2Token {
     pub oauth2authorizedapp_id: Id,
@@ -9,61 +22,106 @@ pub struct OAuth2Token {
     pub redirect_uri: String,
     pub scope: String,
     pub expires_in: i64,
+    pub refresh_expires_in: i64,
+}
+
+/// Timestamps at which a new access token and refresh token expire.
+fn expirations() -> (i64, i64) {
+    let config = server_config();
+    let now = Utc::now();
+    let access = now + TimeDelta::seconds(config.session_timeout.as_secs() as i64);
+    let refresh = now + TimeDelta::seconds(config.oauth2_refresh_token_timeout.as_secs() as i64);
+    (access.timestamp(), refresh.timestamp())
 }
 
 impl OAuth2Token {
     #[must_use]
     pub fn new(oauth2authorizedapp_id: Id, redirect_uri: String, scope: String) -> Self {
-        let timeout = server_config().session_timeout;
-        let expiration = Utc::now() + TimeDelta::seconds(timeout.as_secs() as i64);
+        let (expires_in, refresh_expires_in) = expirations();
         Self {
             oauth2authorizedapp_id,
             access_token: gen_alphanumeric(24),
             refresh_token: gen_alphanumeric(24),
             redirect_uri,
             scope,
-            expires_in: expiration.timestamp(),
+            expires_in,
+            refresh_expires_in,
         }
     }
 
-    /// Generate new access token, scratching the old one. Changes are reflected in the database.
-    pub async fn refresh_and_save(&mut self, pool: &PgPool) -> Result<(), SqlxError> {
-        let timeout = server_config().session_timeout;
+    /// Generate new access and refresh tokens, scratching the old ones. The old refresh token is
+    /// added to revoked tokens, so its reuse can be detected. Changes are reflected in the
+    /// database. Returns `false` if the token has been refreshed concurrently.
+    pub async fn refresh_and_save(&mut self, pool: &PgPool) -> Result<bool, SqlxError> {
         let new_access_token = gen_alphanumeric(24);
         let new_refresh_token = gen_alphanumeric(24);
-        let expiration = Utc::now() + TimeDelta::seconds(timeout.as_secs() as i64);
-        self.expires_in = expiration.timestamp();
+        let (expires_in, refresh_expires_in) = expirations();
 
-        query!(
-            "UPDATE oauth2token SET access_token = $2, refresh_token = $3, expires_in = $4 \
-            WHERE access_token = $1",
-            self.access_token,
+        let mut transaction = pool.begin().await?;
+        let result = query!(
+            "UPDATE oauth2token SET access_token = $2, refresh_token = $3, expires_in = $4, \
+            refresh_expires_in = $5 WHERE refresh_token = $1",
+            self.refresh_token,
             new_access_token,
             new_refresh_token,
-            self.expires_in,
+            expires_in,
+            refresh_expires_in,
         )
-        .execute(pool)
+        .execute(&mut *transaction)
         .await?;
-        Ok(())
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+        // expired tokens are rejected anyway, no need to keep them
+        query!(
+            "DELETE FROM oauth2revokedtoken WHERE expires_in < $1",
+            Utc::now().timestamp()
+        )
+        .execute(&mut *transaction)
+        .await?;
+        query!(
+            "INSERT INTO oauth2revokedtoken (refresh_token, oauth2authorizedapp_id, reason, expires_in) \
+            VALUES ($1, $2, $3, $4)",
+            self.refresh_token,
+            self.oauth2authorizedapp_id,
+            OAuth2RevocationReason::Rotated as OAuth2RevocationReason,
+            self.refresh_expires_in,
+        )
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
+
+        self.access_token = new_access_token;
+        self.refresh_token = new_refresh_token;
+        self.expires_in = expires_in;
+        self.refresh_expires_in = refresh_expires_in;
+        Ok(true)
     }
 
-    /// Check if token has expired.
+    /// Check if access token has expired.
     #[must_use]
     pub fn is_expired(&self) -> bool {
         self.expires_in < Utc::now().timestamp()
     }
 
+    /// Check if refresh token has expired.
+    #[must_use]
+    pub fn is_refresh_expired(&self) -> bool {
+        self.refresh_expires_in < Utc::now().timestamp()
+    }
+
     /// Store data in the database.
     pub async fn save(&self, pool: &PgPool) -> Result<(), SqlxError> {
         query!(
-            "INSERT INTO oauth2token (oauth2authorizedapp_id, access_token, refresh_token, redirect_uri, scope, expires_in) \
-            VALUES ($1, $2, $3, $4, $5, $6)",
+            "INSERT INTO oauth2token (oauth2authorizedapp_id, access_token, refresh_token, redirect_uri, scope, expires_in, refresh_expires_in) \
+            VALUES ($1, $2, $3, $4, $5, $6, $7)",
             self.oauth2authorizedapp_id,
             self.access_token,
             self.refresh_token,
             self.redirect_uri,
             self.scope,
-            self.expires_in)
+            self.expires_in,
+            self.refresh_expires_in)
             .execute(pool)
             .await?;
         Ok(())
@@ -88,7 +146,8 @@ impl OAuth2Token {
     ) -> Result<Option<Self>, SqlxError> {
         match query_as!(
             Self,
-            "SELECT oauth2authorizedapp_id, access_token, refresh_token, redirect_uri, scope, expires_in \
+            "SELECT oauth2authorizedapp_id, access_token, refresh_token, redirect_uri, scope, expires_in, \
+            refresh_expires_in \
             FROM oauth2token WHERE access_token = $1",
             access_token
         )
@@ -96,9 +155,12 @@ impl OAuth2Token {
         .await
         {
             Ok(Some(token)) => {
-                if token.is_expired() {
+                if token.is_refresh_expired() {
                     token.delete(pool).await?;
                     Ok(None)
+                } else if token.is_expired() {
+                    // keep the token, so it can be refreshed
+                    Ok(None)
                 } else {
                     Ok(Some(token))
                 }
@@ -115,7 +177,8 @@ impl OAuth2Token {
     ) -> Result<Option<Self>, SqlxError> {
         match query_as!(
             Self,
-            "SELECT oauth2authorizedapp_id, access_token, refresh_token, redirect_uri, scope, expires_in \
+            "SELECT oauth2authorizedapp_id, access_token, refresh_token, redirect_uri, scope, expires_in, \
+            refresh_expires_in \
             FROM oauth2token WHERE refresh_token = $1",
             refresh_token
         )
@@ -123,7 +186,7 @@ impl OAuth2Token {
         .await
         {
             Ok(Some(token)) => {
-                if token.is_expired() {
+                if token.is_refresh_expired() {
                     token.delete(pool).await?;
                     Ok(None)
                 } else {
@@ -142,7 +205,8 @@ impl OAuth2Token {
     ) -> Result<Option<Self>, SqlxError> {
         match query_as!(
             Self,
-            "SELECT oauth2authorizedapp_id, access_token, refresh_token, redirect_uri, scope, expires_in \
+            "SELECT oauth2authorizedapp_id, access_token, refresh_token, redirect_uri, scope, expires_in, \
+            refresh_expires_in \
             FROM oauth2token WHERE oauth2authorizedapp_id = $1",
             oauth2authorizedapp_id,
         )
@@ -150,7 +214,7 @@ impl OAuth2Token {
         .await
         {
             Ok(Some(token)) => {
-                if token.is_expired() {
+                if token.is_refresh_expired() {
                     token.delete(pool).await?;
                     Ok(None)
                 } else {
@@ -161,4 +225,104 @@ impl OAuth2Token {
             Err(err) => Err(err),
         }
     }
+
+    /// Revoke tokens of an authorized app, e.g. when reuse of its rotated refresh token has been
+    /// detected.
+    pub async fn revoke_for_authorized_app<'e, E>(
+        executor: E,
+        oauth2authorizedapp_id: Id,
+        reason: OAuth2RevocationReason,
+    ) -> Result<u64, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        let result = query!(
+            "WITH revoked AS (DELETE FROM oauth2token WHERE oauth2authorizedapp_id = $1 \
+            RETURNING refresh_token, oauth2authorizedapp_id, refresh_expires_in) \
+            INSERT INTO oauth2revokedtoken (refresh_token, oauth2authorizedapp_id, reason, expires_in) \
+            SELECT refresh_token, oauth2authorizedapp_id, $2, refresh_expires_in FROM revoked \
+            ON CONFLICT (refresh_token) DO NOTHING",
+            oauth2authorizedapp_id,
+            reason as OAuth2RevocationReason,
+        )
+        .execute(executor)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Revoke tokens issued to an OAuth2 client; only the token with `token_id` if given.
+    pub async fn revoke_for_client<'e, E>(
+        executor: E,
+        oauth2client_id: Id,
+        token_id: Option<Id>,
+    ) -> Result<u64, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        let result = query!(
+            "WITH revoked AS (DELETE FROM oauth2token t USING oauth2authorizedapp a \
+            WHERE t.oauth2authorizedapp_id = a.id AND a.oauth2client_id = $1 \
+            AND ($2::bigint IS NULL OR t.id = $2) \
+            RETURNING t.refresh_token, t.oauth2authorizedapp_id, t.refresh_expires_in) \
+            INSERT INTO oauth2revokedtoken (refresh_token, oauth2authorizedapp_id, reason, expires_in) \
+            SELECT refresh_token, oauth2authorizedapp_id, $3, refresh_expires_in FROM revoked \
+            ON CONFLICT (refresh_token) DO NOTHING",
+            oauth2client_id,
+            token_id,
+            OAuth2RevocationReason::Admin as OAuth2RevocationReason,
+        )
+        .execute(executor)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Find revoked refresh token. Returns ID of its authorized app and revocation reason.
+    pub async fn find_revoked<'e, E>(
+        executor: E,
+        refresh_token: &str,
+    ) -> Result<Option<(Id, OAuth2RevocationReason)>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        let revoked = query!(
+            "SELECT oauth2authorizedapp_id, reason \"reason: OAuth2RevocationReason\" \
+            FROM oauth2revokedtoken WHERE refresh_token = $1",
+            refresh_token
+        )
+        .fetch_optional(executor)
+        .await?;
+        Ok(revoked.map(|revoked| (revoked.oauth2authorizedapp_id, revoked.reason)))
+    }
+}
+
+/// Active token, as listed for administrators. Doesn't contain any secrets.
+#[derive(Debug, Serialize)]
+pub struct OAuth2TokenInfo {
+    pub id: Id,
+    pub username: String,
+    pub scope: String,
+    pub expires_in: i64,
+    pub refresh_expires_in: i64,
+}
+
+impl OAuth2TokenInfo {
+    pub async fn all_for_client<'e, E>(
+        executor: E,
+        oauth2client_id: Id,
+    ) -> Result<Vec<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT t.id, u.username, t.scope, t.expires_in, t.refresh_expires_in \
+            FROM oauth2token t \
+            JOIN oauth2authorizedapp a ON a.id = t.oauth2authorizedapp_id \
+            JOIN \"user\" u ON u.id = a.user_id \
+            WHERE a.oauth2client_id = $1 ORDER BY u.username, t.id",
+            oauth2client_id
+        )
+        .fetch_all(executor)
+        .await
+    }
 }
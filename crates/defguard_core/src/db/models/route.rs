@@ -0,0 +1,144 @@
+use defguard_common::db::{Id, NoId};
+use ipnetwork::IpNetwork;
+use model_derive::Model;
+use sqlx::{Error as SqlxError, PgConnection, PgExecutor, query, query_as, query_scalar};
+use utoipa::ToSchema;
+
+/// Named list of networks routed through locations.
+///
+/// Addresses of routes attached to a location are added to AllowedIPs of device configs for this
+/// location. Routes attached to groups only apply to devices of their members.
+#[derive(Clone, Debug, Deserialize, Model, PartialEq, Serialize, ToSchema)]
+#[table(route)]
+pub struct Route<I = NoId> {
+    pub id: I,
+    pub name: String,
+    pub description: Option<String>,
+    #[model(ref)]
+    #[schema(value_type = Vec<String>)]
+    pub addresses: Vec<IpNetwork>,
+}
+
+impl Route {
+    #[must_use]
+    pub fn new(name: String, description: Option<String>, addresses: Vec<IpNetwork>) -> Self {
+        Self {
+            id: NoId,
+            name,
+            description,
+            addresses,
+        }
+    }
+}
+
+impl Route<Id> {
+    pub async fn find_by_name<'e, E>(executor: E, name: &str) -> Result<Option<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, name, description, addresses \"addresses: Vec<IpNetwork>\" \
+            FROM route WHERE name = $1",
+            name
+        )
+        .fetch_optional(executor)
+        .await
+    }
+
+    /// IDs of locations this route is attached to.
+    pub async fn location_ids<'e, E>(&self, executor: E) -> Result<Vec<Id>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_scalar!(
+            "SELECT location_id FROM route_location WHERE route_id = $1 ORDER BY location_id",
+            self.id
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    /// IDs of groups this route is restricted to.
+    pub async fn group_ids<'e, E>(&self, executor: E) -> Result<Vec<Id>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_scalar!(
+            "SELECT group_id FROM route_group WHERE route_id = $1 ORDER BY group_id",
+            self.id
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    /// Replace locations this route is attached to.
+    pub(crate) async fn set_locations(
+        &self,
+        transaction: &mut PgConnection,
+        location_ids: &[Id],
+    ) -> Result<(), SqlxError> {
+        query!("DELETE FROM route_location WHERE route_id = $1", self.id)
+            .execute(&mut *transaction)
+            .await?;
+        query!(
+            "INSERT INTO route_location (route_id, location_id) \
+            SELECT DISTINCT $1::bigint, UNNEST($2::bigint[])",
+            self.id,
+            location_ids
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Replace groups this route is restricted to.
+    pub(crate) async fn set_groups(
+        &self,
+        transaction: &mut PgConnection,
+        group_ids: &[Id],
+    ) -> Result<(), SqlxError> {
+        query!("DELETE FROM route_group WHERE route_id = $1", self.id)
+            .execute(&mut *transaction)
+            .await?;
+        query!(
+            "INSERT INTO route_group (route_id, group_id) \
+            SELECT DISTINCT $1::bigint, UNNEST($2::bigint[])",
+            self.id,
+            group_ids
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Addresses of routes attached to a given location, which apply to a given user.
+    /// Without a user, only routes not restricted to any group are included.
+    pub(crate) async fn addresses_for_location<'e, E>(
+        executor: E,
+        location_id: Id,
+        user_id: Option<Id>,
+    ) -> Result<Vec<IpNetwork>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        let routes = query_scalar!(
+            "SELECT r.addresses \"addresses: Vec<IpNetwork>\" FROM route r \
+            JOIN route_location rl ON rl.route_id = r.id \
+            WHERE rl.location_id = $1 \
+            AND (NOT EXISTS (SELECT 1 FROM route_group rg WHERE rg.route_id = r.id) \
+                OR EXISTS (SELECT 1 FROM route_group rg \
+                    JOIN group_user gu ON gu.group_id = rg.group_id \
+                    WHERE rg.route_id = r.id AND gu.user_id = $2)) \
+            ORDER BY r.id",
+            location_id,
+            user_id
+        )
+        .fetch_all(executor)
+        .await?;
+
+        Ok(routes.into_iter().flatten().collect())
+    }
+}
@@ -0,0 +1,94 @@
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use defguard_common::{
+    db::{Id, NoId},
+    random::gen_alphanumeric,
+};
+use model_derive::Model;
+use sqlx::{Error as SqlxError, PgExecutor, query, query_as};
+
+/// Account request submitted through public self-registration, awaiting email verification.
+#[derive(Clone, Debug, Model)]
+#[table(self_registration_request)]
+pub struct SelfRegistrationRequest<I = NoId> {
+    pub id: I,
+    pub username: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: String,
+    pub phone: Option<String>,
+    // email verification token
+    pub token: String,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+}
+
+impl SelfRegistrationRequest {
+    #[must_use]
+    pub fn new(
+        username: String,
+        first_name: String,
+        last_name: String,
+        email: String,
+        phone: Option<String>,
+        token_timeout_seconds: u64,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            id: NoId,
+            username,
+            first_name,
+            last_name,
+            email,
+            phone,
+            token: gen_alphanumeric(32),
+            created_at: now.naive_utc(),
+            expires_at: (now + TimeDelta::seconds(token_timeout_seconds as i64)).naive_utc(),
+        }
+    }
+}
+
+impl SelfRegistrationRequest<Id> {
+    #[must_use]
+    pub fn is_expired(&self) -> bool {
+        self.expires_at < Utc::now().naive_utc()
+    }
+
+    pub(crate) async fn find_by_token<'e, E>(
+        executor: E,
+        token: &str,
+    ) -> Result<Option<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, username, first_name, last_name, email, phone, token, created_at, \
+            expires_at FROM self_registration_request WHERE token = $1",
+            token
+        )
+        .fetch_optional(executor)
+        .await
+    }
+
+    /// Removes expired requests and previous requests for the same username or email, so that
+    /// only the latest verification token remains valid.
+    pub(crate) async fn delete_stale<'e, E>(
+        executor: E,
+        username: &str,
+        email: &str,
+    ) -> Result<(), SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query!(
+            "DELETE FROM self_registration_request \
+            WHERE expires_at < now() OR username = $1 OR email ILIKE $2",
+            username,
+            email
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+}
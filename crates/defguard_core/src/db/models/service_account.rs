@@ -0,0 +1,110 @@
+use chrono::{NaiveDateTime, Utc};
+use defguard_common::db::{Id, NoId};
+use model_derive::Model;
+use sqlx::{Error as SqlxError, PgExecutor, query_as, query_scalar};
+use utoipa::ToSchema;
+
+/// Non-human account used by automation.
+///
+/// Each service account is backed by a [`User`](super::user::User) without credentials, so it
+/// can own network devices and API tokens. Service accounts don't count towards the licensed
+/// user limit, are skipped by directory synchronization and can't configure MFA.
+#[derive(Clone, Debug, Deserialize, Model, PartialEq, Serialize, ToSchema)]
+#[table(service_account)]
+pub struct ServiceAccount<I = NoId> {
+    pub id: I,
+    pub user_id: Id,
+    pub description: Option<String>,
+    pub created_by: Option<Id>,
+    pub created_at: NaiveDateTime,
+}
+
+/// Service account with details of its backing user.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct ServiceAccountInfo {
+    pub id: Id,
+    pub user_id: Id,
+    /// Username of the backing user
+    pub name: String,
+    pub description: Option<String>,
+    pub is_active: bool,
+    pub network_devices: i64,
+    pub created_at: NaiveDateTime,
+}
+
+impl ServiceAccount {
+    #[must_use]
+    pub fn new(user_id: Id, description: Option<String>, created_by: Id) -> Self {
+        Self {
+            id: NoId,
+            user_id,
+            description,
+            created_by: Some(created_by),
+            created_at: Utc::now().naive_utc(),
+        }
+    }
+}
+
+impl ServiceAccount<Id> {
+    /// Check if a given user backs a service account.
+    pub(crate) async fn is_service_account<'e, E>(
+        executor: E,
+        user_id: Id,
+    ) -> Result<bool, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_scalar!(
+            "SELECT EXISTS (SELECT 1 FROM service_account WHERE user_id = $1) \"exists!\"",
+            user_id
+        )
+        .fetch_one(executor)
+        .await
+    }
+
+    pub(crate) async fn network_device_count<'e, E>(&self, executor: E) -> Result<i64, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_scalar!(
+            "SELECT count(*) \"count!\" FROM device \
+            WHERE user_id = $1 AND device_type = 'network'::device_type",
+            self.user_id
+        )
+        .fetch_one(executor)
+        .await
+    }
+}
+
+impl ServiceAccountInfo {
+    pub(crate) async fn all<'e, E>(executor: E) -> Result<Vec<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT sa.id, sa.user_id, u.username name, sa.description, u.is_active, \
+            (SELECT count(*) FROM device d WHERE d.user_id = u.id \
+            AND d.device_type = 'network'::device_type) \"network_devices!\", sa.created_at \
+            FROM service_account sa JOIN \"user\" u ON u.id = sa.user_id ORDER BY u.username"
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    pub(crate) async fn find_by_id<'e, E>(executor: E, id: Id) -> Result<Option<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT sa.id, sa.user_id, u.username name, sa.description, u.is_active, \
+            (SELECT count(*) FROM device d WHERE d.user_id = u.id \
+            AND d.device_type = 'network'::device_type) \"network_devices!\", sa.created_at \
+            FROM service_account sa JOIN \"user\" u ON u.id = sa.user_id WHERE sa.id = $1",
+            id
+        )
+        .fetch_optional(executor)
+        .await
+    }
+}
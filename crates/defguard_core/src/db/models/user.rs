@@ -100,6 +100,8 @@ pub struct User<I = NoId> {
     /// Uninitialized clients should then guide the user through enrollment process.
     /// Related issue: https://github.com/DefGuard/client/issues/647.
     pub enrollment_pending: bool,
+    /// Language of mails sent to the user, instance default is used if not set.
+    pub mail_language: Option<String>,
 }
 
 // TODO: Refactor the user struct to use SecretStringWrapper instead of this
@@ -127,6 +129,7 @@ impl<I: std::fmt::Debug> fmt::Debug for User<I> {
             mfa_method,
             recovery_codes,
             enrollment_pending,
+            mail_language,
         } = self;
 
         f.debug_struct("User")
@@ -154,11 +157,12 @@ impl<I: std::fmt::Debug> fmt::Debug for User<I> {
             .field("totp_secret", &"***")
             .field("email_mfa_secret", &"***")
             .field("enrollment_pending", enrollment_pending)
+            .field("mail_language", mail_language)
             .finish()
     }
 }
 
-fn hash_password(password: &str) -> Result<String, HashError> {
+pub(crate) fn hash_password(password: &str) -> Result<String, HashError> {
     let salt = SaltString::generate(&mut OsRng);
     Ok(Argon2::default()
         .hash_password(password.as_bytes(), &salt)?
@@ -208,6 +212,7 @@ impl User {
             ldap_rdn: Some(username.clone()),
             ldap_user_path: None,
             enrollment_pending: false,
+            mail_language: None,
         }
     }
 }
@@ -687,7 +692,7 @@ impl User<Id> {
             phone, mfa_enabled, totp_enabled, totp_secret, \
             email_mfa_enabled, email_mfa_secret, \
             mfa_method \"mfa_method: _\", recovery_codes, is_active, openid_sub, \
-            from_ldap, ldap_pass_randomized, ldap_rdn, ldap_user_path, enrollment_pending \
+            from_ldap, ldap_pass_randomized, ldap_rdn, ldap_user_path, enrollment_pending, mail_language \
             FROM \"user\" \
             INNER JOIN \"group_user\" ON \"user\".id = \"group_user\".user_id \
             INNER JOIN \"group\" ON \"group_user\".group_id = \"group\".id \
@@ -838,7 +843,7 @@ impl User<Id> {
             "SELECT id, username, password_hash, last_name, first_name, email, phone, mfa_enabled, \
             totp_enabled, email_mfa_enabled, totp_secret, email_mfa_secret, \
             mfa_method \"mfa_method: _\", recovery_codes, is_active, openid_sub, \
-            from_ldap, ldap_pass_randomized, ldap_rdn, ldap_user_path, enrollment_pending \
+            from_ldap, ldap_pass_randomized, ldap_rdn, ldap_user_path, enrollment_pending, mail_language \
             FROM \"user\" WHERE username = $1",
             username
         )
@@ -858,7 +863,7 @@ impl User<Id> {
             "SELECT id, username, password_hash, last_name, first_name, email, phone, mfa_enabled, \
             totp_enabled, email_mfa_enabled, totp_secret, email_mfa_secret, \
             mfa_method \"mfa_method: _\", recovery_codes, is_active, openid_sub, from_ldap, \
-            ldap_pass_randomized, ldap_rdn, ldap_user_path, enrollment_pending \
+            ldap_pass_randomized, ldap_rdn, ldap_user_path, enrollment_pending, mail_language \
             FROM \"user\" WHERE email ILIKE $1",
             email
         )
@@ -913,7 +918,7 @@ impl User<Id> {
             "SELECT id, username, password_hash, last_name, first_name, email, phone, \
             mfa_enabled, totp_enabled, email_mfa_enabled, totp_secret, email_mfa_secret, \
             mfa_method \"mfa_method: _\", recovery_codes, is_active, openid_sub, \
-            from_ldap, ldap_pass_randomized, ldap_rdn, ldap_user_path, enrollment_pending \
+            from_ldap, ldap_pass_randomized, ldap_rdn, ldap_user_path, enrollment_pending, mail_language \
             FROM \"user\" WHERE openid_sub = $1",
             sub
         )
@@ -940,7 +945,9 @@ impl User<Id> {
     {
         query_as!(
             Group,
-            "SELECT id, name, is_admin FROM \"group\" JOIN group_user ON \"group\".id = group_user.group_id \
+            "SELECT id, name, is_admin, \
+            client_traffic_policy \"client_traffic_policy: _\" \
+            FROM \"group\" JOIN group_user ON \"group\".id = group_user.group_id \
             WHERE group_user.user_id = $1",
             self.id
         )
@@ -1144,7 +1151,7 @@ impl User<Id> {
             u.phone, u.mfa_enabled, u.totp_enabled, u.email_mfa_enabled, \
             u.totp_secret, u.email_mfa_secret, u.mfa_method \"mfa_method: _\", u.recovery_codes, \
             u.is_active, u.openid_sub, from_ldap, ldap_pass_randomized, ldap_rdn, ldap_user_path, \
-            enrollment_pending \
+            enrollment_pending, mail_language \
             FROM \"user\" u \
             JOIN \"device\" d ON u.id = d.user_id \
             WHERE d.id = $1",
@@ -1154,7 +1161,7 @@ impl User<Id> {
         .await
     }
 
-    /// Find users which emails are NOT in `user_emails`.
+    /// Find users which emails are NOT in `user_emails`, skipping service accounts.
     pub(crate) async fn exclude<'e, E>(
         executor: E,
         user_emails: &[&str],
@@ -1168,7 +1175,8 @@ impl User<Id> {
             mfa_enabled, totp_enabled, email_mfa_enabled, totp_secret, email_mfa_secret, \
             mfa_method, recovery_codes, is_active, openid_sub, from_ldap, ldap_pass_randomized, \
             ldap_rdn, ldap_user_path, enrollment_pending \
-            FROM \"user\" WHERE email NOT IN (SELECT * FROM UNNEST($1::TEXT[]))",
+            FROM \"user\" WHERE email NOT IN (SELECT * FROM UNNEST($1::TEXT[])) \
+            AND id NOT IN (SELECT user_id FROM service_account)",
         )
         .bind(user_emails)
         .fetch_all(executor)
@@ -1185,6 +1193,20 @@ impl User<Id> {
             .await
     }
 
+    /// Check if user is a member of an auditor group.
+    pub(crate) async fn is_auditor<'e, E>(&self, executor: E) -> Result<bool, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_scalar!(
+            "SELECT EXISTS (SELECT 1 FROM group_user gu LEFT JOIN \"group\" g ON gu.group_id = g.id \
+            WHERE is_auditor = true AND user_id = $1) \"bool!\"",
+            self.id
+        )
+        .fetch_one(executor)
+        .await
+    }
+
     /// Find all users that are admins and are active.
     pub(crate) async fn find_admins<'e, E>(executor: E) -> Result<Vec<Self>, SqlxError>
     where
@@ -1196,7 +1218,7 @@ impl User<Id> {
             SELECT u.id, u.username, u.password_hash, u.last_name, u.first_name, u.email, \
             u.phone, u.mfa_enabled, u.totp_enabled, u.email_mfa_enabled, \
             u.totp_secret, u.email_mfa_secret, u.mfa_method \"mfa_method: _\", u.recovery_codes, u.is_active, u.openid_sub, \
-            from_ldap, ldap_pass_randomized, ldap_rdn, ldap_user_path, enrollment_pending \
+            from_ldap, ldap_pass_randomized, ldap_rdn, ldap_user_path, enrollment_pending, mail_language \
             FROM \"user\" u \
             WHERE EXISTS (SELECT 1 FROM group_user gu LEFT JOIN \"group\" g ON gu.group_id = g.id \
             WHERE is_admin = true AND user_id = u.id) AND u.is_active = true"
@@ -1243,6 +1265,7 @@ impl Distribution<User<Id>> for Standard {
             ldap_rdn: None,
             ldap_user_path: None,
             enrollment_pending: false,
+            mail_language: None,
         }
     }
 }
@@ -1284,6 +1307,7 @@ impl Distribution<User<NoId>> for Standard {
             ldap_rdn: None,
             ldap_user_path: None,
             enrollment_pending: false,
+            mail_language: None,
         }
     }
 }
@@ -1297,6 +1321,7 @@ mod test {
     use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
 
     use super::*;
+    use crate::db::models::service_account::ServiceAccount;
 
     #[sqlx::test]
     async fn test_mfa_code(_: PgPoolOptions, options: PgConnectOptions) {
@@ -1589,6 +1614,22 @@ mod test {
         .save(&pool)
         .await
         .unwrap();
+        // service accounts are never excluded
+        let bot = User::new(
+            "deploy-bot",
+            None,
+            "",
+            "deploy-bot",
+            "deploy-bot@service-accounts.invalid",
+            None,
+        )
+        .save(&pool)
+        .await
+        .unwrap();
+        ServiceAccount::new(bot.id, None, albus.id)
+            .save(&pool)
+            .await
+            .unwrap();
 
         let user_emails = vec![user1.email.as_str(), albus.email.as_str()];
         let users = User::exclude(&pool, &user_emails).await.unwrap();
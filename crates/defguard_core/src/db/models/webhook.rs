@@ -1,6 +1,12 @@
+use std::{fmt::Write, net::IpAddr};
+
 use defguard_common::db::{Id, NoId};
+use hmac::{Hmac, Mac};
 use model_derive::Model;
+use serde_json::Value;
+use sha2::Sha256;
 use sqlx::{Error as SqlxError, FromRow, PgPool, query_as};
+use tera::{Context, Tera};
 
 use super::UserInfo;
 
@@ -11,6 +17,7 @@ pub enum AppEvent {
     UserModified(UserInfo),
     UserDeleted(String),
     HWKeyProvision(HWKeyUserData),
+    EnrollmentCompleted(EnrollmentCompletedData),
 }
 
 /// User data send on HWKeyProvision AppEvent
@@ -23,6 +30,27 @@ pub struct HWKeyUserData {
     pub serial: String,
 }
 
+/// Device data send on EnrollmentCompleted AppEvent
+#[derive(Debug, Serialize)]
+pub struct EnrollmentCompletedData {
+    pub username: String,
+    pub email: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub device_id: Id,
+    pub device_name: String,
+    pub pubkey: String,
+    pub locations: Vec<EnrollmentLocationData>,
+}
+
+/// Location the enrolled device was added to
+#[derive(Debug, Serialize)]
+pub struct EnrollmentLocationData {
+    pub id: Id,
+    pub name: String,
+    pub assigned_ips: Vec<IpAddr>,
+}
+
 impl AppEvent {
     // Debug name
     #[must_use]
@@ -32,6 +60,7 @@ impl AppEvent {
             Self::UserModified(_) => "user modified",
             Self::UserDeleted(_) => "user deleted",
             Self::HWKeyProvision(_) => "hwkey provisioned",
+            Self::EnrollmentCompleted(_) => "enrollment completed",
         }
     }
 
@@ -43,6 +72,7 @@ impl AppEvent {
             Self::UserModified(_) => "on_user_modified",
             Self::UserDeleted(_) => "on_user_deleted",
             Self::HWKeyProvision(_) => "on_hwkey_provision",
+            Self::EnrollmentCompleted(_) => "on_enrollment_completed",
         }
     }
 }
@@ -58,6 +88,48 @@ pub struct WebHook<I = NoId> {
     pub on_user_deleted: bool,
     pub on_user_modified: bool,
     pub on_hwkey_provision: bool,
+    pub on_enrollment_completed: bool,
+    /// Tera template rendering the JSON payload. Event data is available as template variables.
+    pub payload_template: Option<String>,
+}
+
+impl<I> WebHook<I> {
+    /// Check if the payload template can be parsed.
+    pub fn validate_payload_template(&self) -> Result<(), tera::Error> {
+        if let Some(template) = &self.payload_template {
+            Tera::default().add_raw_template("payload", template)?;
+        }
+        Ok(())
+    }
+
+    /// Build the request body from event data, using the payload template if it is set.
+    pub(crate) fn payload(&self, event: &str, data: &Value) -> Result<String, tera::Error> {
+        let Some(template) = &self.payload_template else {
+            return Ok(data.to_string());
+        };
+        let mut context = Context::from_value(data.clone()).unwrap_or_default();
+        context.insert("event", event);
+        let payload = Tera::one_off(template, &context, false)?;
+        // make sure the template produced valid JSON
+        serde_json::from_str::<Value>(&payload).map_err(tera::Error::json)?;
+        Ok(payload)
+    }
+
+    /// HMAC-SHA256 signature of `<timestamp>.<body>` keyed with webhook token,
+    /// in `sha256=<hex digest>` format.
+    #[must_use]
+    pub(crate) fn signature(&self, timestamp: i64, body: &str) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.token.as_bytes())
+            .expect("HMAC can take key of any size");
+        mac.update(timestamp.to_string().as_bytes());
+        mac.update(b".");
+        mac.update(body.as_bytes());
+        let mut signature = String::from("sha256=");
+        for byte in mac.finalize().into_bytes() {
+            let _ = write!(signature, "{byte:02x}");
+        }
+        signature
+    }
 }
 
 impl WebHook<Id> {
@@ -66,8 +138,8 @@ impl WebHook<Id> {
         let column_name = trigger.column_name();
         let query = format!(
             "SELECT id, url, description, token, enabled, on_user_created, \
-            on_user_deleted, on_user_modified, on_hwkey_provision FROM webhook \
-            WHERE enabled AND {column_name}"
+            on_user_deleted, on_user_modified, on_hwkey_provision, on_enrollment_completed, \
+            payload_template FROM webhook WHERE enabled AND {column_name}"
         );
         query_as(&query).fetch_all(pool).await
     }
@@ -77,10 +149,87 @@ impl WebHook<Id> {
         query_as!(
             Self,
             "SELECT id, url, description, token, enabled, on_user_created, \
-            on_user_deleted, on_user_modified, on_hwkey_provision FROM webhook WHERE url = $1",
+            on_user_deleted, on_user_modified, on_hwkey_provision, on_enrollment_completed, \
+            payload_template FROM webhook WHERE url = $1",
             url
         )
         .fetch_optional(pool)
         .await
     }
 }
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    fn webhook(payload_template: Option<&str>) -> WebHook {
+        WebHook {
+            id: NoId,
+            url: "http://localhost:3000/cmdb".into(),
+            description: "CMDB".into(),
+            token: "secret".into(),
+            enabled: true,
+            on_user_created: false,
+            on_user_deleted: false,
+            on_user_modified: false,
+            on_hwkey_provision: false,
+            on_enrollment_completed: true,
+            payload_template: payload_template.map(Into::into),
+        }
+    }
+
+    #[test]
+    fn test_webhook_payload() {
+        let data = json!({
+            "username": "hpotter",
+            "pubkey": "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=",
+            "locations": [{"id": 1, "name": "office", "assigned_ips": ["10.1.1.2"]}],
+        });
+
+        // default payload
+        let payload = webhook(None)
+            .payload("enrollment_completed", &data)
+            .unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&payload).unwrap(), data);
+
+        let template = r#"{
+            "type": {{ event | json_encode() }},
+            "ci": {"owner": {{ username | json_encode() }}, "key": {{ pubkey | json_encode() }}},
+            "ip": {{ locations.0.assigned_ips.0 | json_encode() }}
+        }"#;
+        let payload = webhook(Some(template))
+            .payload("enrollment_completed", &data)
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&payload).unwrap(),
+            json!({
+                "type": "enrollment_completed",
+                "ci": {
+                    "owner": "hpotter",
+                    "key": "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=",
+                },
+                "ip": "10.1.1.2",
+            })
+        );
+
+        // template must produce valid JSON
+        let payload =
+            webhook(Some(r#"{"owner": {{ username }}}"#)).payload("enrollment_completed", &data);
+        assert!(payload.is_err());
+        assert!(
+            webhook(Some("{{ username"))
+                .validate_payload_template()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_webhook_signature() {
+        assert_eq!(
+            webhook(None).signature(1_700_000_000, r#"{"username":"hpotter"}"#),
+            "sha256=0039d06e3fa23b89c3cc28933ee9263283015ecd9e16bd215058a0b0b2e13cae"
+        );
+    }
+}
@@ -21,10 +21,10 @@ use defguard_proto::{
 };
 use ipnetwork::{IpNetwork, IpNetworkError, NetworkSize};
 use model_derive::Model;
-use rand::rngs::OsRng;
+use rand::{Rng, rngs::OsRng};
 use sqlx::{
     Error as SqlxError, FromRow, PgConnection, PgExecutor, PgPool, Type,
-    postgres::types::PgInterval, query_as, query_scalar,
+    postgres::types::PgInterval, query, query_as, query_scalar,
 };
 use thiserror::Error;
 use tokio::sync::broadcast::Sender;
@@ -36,6 +36,7 @@ use super::{
     device::{
         Device, DeviceError, DeviceInfo, DeviceNetworkInfo, DeviceType, WireguardNetworkDevice,
     },
+    route::Route,
     user::User,
     wireguard_peer_stats::WireguardPeerStats,
 };
@@ -43,10 +44,10 @@ use crate::{
     enterprise::{
         db::models::enterprise_settings::{ClientTrafficPolicy, EnterpriseSettings},
         firewall::FirewallError,
-        is_enterprise_license_active,
+        is_business_license_active, is_enterprise_license_active,
     },
     grpc::gateway::{send_multiple_wireguard_events, state::GatewayState},
-    wg_config::ImportedDevice,
+    wg_config::{ExportedPeer, ImportedDevice},
 };
 
 pub const DEFAULT_KEEPALIVE_INTERVAL: i32 = 25;
@@ -61,6 +62,32 @@ pub struct MappedDevice {
     pub wireguard_ips: Vec<IpAddr>,
 }
 
+/// Outcome of importing a single peer from a WireGuard config.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case", tag = "status")]
+pub enum PeerImportStatus {
+    /// Peer matched an existing device by public key and imported IPs were assigned to it.
+    Matched { device_id: Id },
+    /// New device with imported IPs was created for a user matched by email annotation.
+    Created { device_id: Id, user_id: Id },
+    /// Device exists (or was created) but is not allowed in the network.
+    NotAllowed { device_id: Id },
+    /// Peer could not be matched and has to be mapped to a user manually.
+    Unmapped,
+}
+
+/// Single entry of a network migration report.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct PeerImportReport {
+    pub name: String,
+    pub wireguard_pubkey: String,
+    #[schema(value_type = Vec<String>)]
+    pub wireguard_ips: Vec<IpAddr>,
+    pub email: Option<String>,
+    #[serde(flatten)]
+    pub status: PeerImportStatus,
+}
+
 pub const WIREGUARD_MAX_HANDSHAKE: TimeDelta = TimeDelta::minutes(8);
 pub const PEER_STATS_LIMIT: i64 = 6 * 60;
 
@@ -165,6 +192,110 @@ impl From<ServiceLocationMode> for ProtoServiceLocationMode {
     }
 }
 
+/// How devices are distributed between multiple gateways serving a location.
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize, ToSchema, Type,
+)]
+#[sqlx(type_name = "gateway_distribution_policy", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum GatewayDistributionPolicy {
+    /// Devices connect to the location endpoint.
+    #[default]
+    None,
+    /// Devices are spread evenly between connected gateways.
+    RoundRobin,
+    /// Devices are spread between connected gateways proportionally to gateway weights.
+    Weighted,
+    /// Devices are assigned to gateways by hash of their ID, so the assignment doesn't depend
+    /// on gateway load.
+    Hash,
+    /// Devices are assigned to gateways serving groups of their owners.
+    Group,
+}
+
+/// Order in which free addresses are picked when assigning an address to a device.
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize, ToSchema, Type,
+)]
+#[sqlx(type_name = "ip_allocation_strategy", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum IpAllocationStrategy {
+    /// The lowest free address is assigned.
+    #[default]
+    Sequential,
+    /// A random free address is assigned, which makes the address space harder to scan.
+    Random,
+    /// Addresses are assigned starting from an offset derived from the device owner,
+    /// so devices of the same user are grouped together.
+    StickyByUser,
+}
+
+/// Number of random addresses tried before scanning the subnet for a free one.
+const RANDOM_ALLOCATION_ATTEMPTS: usize = 32;
+
+impl IpAllocationStrategy {
+    /// Returns addresses of a subnet in the order they should be tried when assigning an address
+    /// to a device owned by a given user.
+    ///
+    /// Every address of the subnet is eventually returned, so a free address is found as long as
+    /// there is one. Network, broadcast and gateway addresses are not filtered out.
+    pub(crate) fn candidate_ips(
+        self,
+        network: &IpNetwork,
+        user_id: Id,
+    ) -> impl Iterator<Item = IpAddr> + Send + use<> {
+        let size = match network.size() {
+            NetworkSize::V4(size) => u128::from(size),
+            NetworkSize::V6(size) => size,
+        };
+        let (random, start) = match self {
+            Self::Sequential => (Vec::new(), 0),
+            Self::Random => {
+                let mut rng = rand::thread_rng();
+                let random = (0..RANDOM_ALLOCATION_ATTEMPTS)
+                    .map(|_| rng.gen_range(0..size))
+                    .collect();
+                (random, rng.gen_range(0..size))
+            }
+            Self::StickyByUser => {
+                let digest = sha256::digest(user_id.to_string());
+                let hash = u128::from_str_radix(&digest[..32], 16).unwrap_or_default();
+                (Vec::new(), hash % size)
+            }
+        };
+        let network = *network;
+        // Try random addresses first, then scan the whole subnet from the start offset, wrapping
+        // around, so collisions are resolved by picking the next free address.
+        random
+            .into_iter()
+            .chain(start..size)
+            .chain(0..start)
+            .map(move |offset| match network {
+                IpNetwork::V4(network) => IpAddr::V4(Ipv4Addr::from(
+                    u32::from(network.network())
+                        .wrapping_add(u32::try_from(offset).unwrap_or_default()),
+                )),
+                IpNetwork::V6(network) => IpAddr::V6(Ipv6Addr::from(
+                    u128::from(network.network()).wrapping_add(offset),
+                )),
+            })
+    }
+}
+
+/// Scope in which names of devices added to a location have to be unique.
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize, ToSchema, Type,
+)]
+#[sqlx(type_name = "device_name_uniqueness", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceNameUniqueness {
+    /// A user can't have two devices with the same name.
+    #[default]
+    User,
+    /// No two devices in the location can have the same name, regardless of their owners.
+    Location,
+}
+
 /// Stores configuration required to setup a WireGuard network
 #[derive(Clone, Deserialize, Eq, Hash, Model, PartialEq, Serialize, ToSchema)]
 #[table(wireguard_network)]
@@ -192,6 +323,41 @@ pub struct WireguardNetwork<I = NoId> {
     pub location_mfa_mode: LocationMfaMode,
     #[model(enum)]
     pub service_location_mode: ServiceLocationMode,
+    /// Minimum version of desktop clients allowed to connect to this location.
+    pub min_desktop_client_version: Option<String>,
+    /// Minimum version of mobile clients allowed to connect to this location.
+    pub min_mobile_client_version: Option<String>,
+    /// Devices created through enrollment need to be approved by an admin before they're
+    /// added to this location.
+    pub device_approval_required: bool,
+    /// Assignment of devices to gateways, if the location is served by multiple gateways.
+    #[model(enum)]
+    pub gateway_distribution_policy: GatewayDistributionPolicy,
+    /// Each gateway holds only peers of devices assigned to it, instead of all location peers.
+    pub gateway_peer_sharding: bool,
+    /// How addresses are picked for new devices.
+    #[model(enum)]
+    pub ip_allocation_strategy: IpAllocationStrategy,
+    /// Overrides instance-wide and group client traffic policy for this location.
+    #[model(enum)]
+    pub client_traffic_policy: Option<ClientTrafficPolicy>,
+    /// Devices need to repeat MFA after this many hours, even if active; 0 means no limit.
+    pub mfa_session_lifetime_hours: i32,
+    /// Devices which completed MFA don't need to repeat it for this many hours; 0 disables.
+    pub mfa_remember_device_hours: i32,
+    /// Peers get static preshared keys; ignored if MFA is enabled, since MFA sessions
+    /// already use their own preshared keys.
+    pub preshared_keys_enabled: bool,
+    /// Static preshared keys are rotated after this many days; 0 disables rotation.
+    pub preshared_key_rotation_days: i32,
+    pub preshared_keys_rotated_at: Option<NaiveDateTime>,
+    /// Names of devices added to this location have to match this regular expression.
+    pub device_name_pattern: Option<String>,
+    /// Names of devices added to this location have to start with this prefix; may contain
+    /// `{username}`, `{first_name}`, `{last_name}` and `{os}` placeholders.
+    pub device_name_prefix: Option<String>,
+    #[model(enum)]
+    pub device_name_uniqueness: DeviceNameUniqueness,
 }
 
 pub struct WireguardKey {
@@ -230,6 +396,31 @@ impl fmt::Debug for WireguardNetwork<Id> {
             .field("peer_disconnect_threshold", &self.peer_disconnect_threshold)
             .field("location_mfa_mode", &self.location_mfa_mode)
             .field("service_location_mode", &self.service_location_mode)
+            .field(
+                "min_desktop_client_version",
+                &self.min_desktop_client_version,
+            )
+            .field("min_mobile_client_version", &self.min_mobile_client_version)
+            .field("device_approval_required", &self.device_approval_required)
+            .field(
+                "gateway_distribution_policy",
+                &self.gateway_distribution_policy,
+            )
+            .field("gateway_peer_sharding", &self.gateway_peer_sharding)
+            .field("ip_allocation_strategy", &self.ip_allocation_strategy)
+            .field(
+                "mfa_session_lifetime_hours",
+                &self.mfa_session_lifetime_hours,
+            )
+            .field("mfa_remember_device_hours", &self.mfa_remember_device_hours)
+            .field("preshared_keys_enabled", &self.preshared_keys_enabled)
+            .field(
+                "preshared_key_rotation_days",
+                &self.preshared_key_rotation_days,
+            )
+            .field("device_name_pattern", &self.device_name_pattern)
+            .field("device_name_prefix", &self.device_name_prefix)
+            .field("device_name_uniqueness", &self.device_name_uniqueness)
             .finish()
     }
 }
@@ -254,6 +445,21 @@ impl Default for WireguardNetwork<Id> {
             acl_enabled: false,
             location_mfa_mode: LocationMfaMode::default(),
             service_location_mode: ServiceLocationMode::default(),
+            min_desktop_client_version: None,
+            min_mobile_client_version: None,
+            device_approval_required: false,
+            gateway_distribution_policy: GatewayDistributionPolicy::default(),
+            gateway_peer_sharding: false,
+            ip_allocation_strategy: IpAllocationStrategy::default(),
+            client_traffic_policy: None,
+            mfa_session_lifetime_hours: 0,
+            mfa_remember_device_hours: 0,
+            preshared_keys_enabled: false,
+            preshared_key_rotation_days: 0,
+            preshared_keys_rotated_at: None,
+            device_name_pattern: None,
+            device_name_prefix: None,
+            device_name_uniqueness: DeviceNameUniqueness::default(),
         }
     }
 }
@@ -336,6 +542,21 @@ impl WireguardNetwork {
             acl_default_allow,
             location_mfa_mode,
             service_location_mode,
+            min_desktop_client_version: None,
+            min_mobile_client_version: None,
+            device_approval_required: false,
+            gateway_distribution_policy: GatewayDistributionPolicy::default(),
+            gateway_peer_sharding: false,
+            ip_allocation_strategy: IpAllocationStrategy::default(),
+            client_traffic_policy: None,
+            mfa_session_lifetime_hours: 0,
+            mfa_remember_device_hours: 0,
+            preshared_keys_enabled: false,
+            preshared_key_rotation_days: 0,
+            preshared_keys_rotated_at: None,
+            device_name_pattern: None,
+            device_name_prefix: None,
+            device_name_uniqueness: DeviceNameUniqueness::default(),
         }
     }
 
@@ -367,7 +588,16 @@ impl WireguardNetwork<Id> {
             "SELECT id, name, address, port, pubkey, prvkey, endpoint, dns, allowed_ips, \
             connected_at, keepalive_interval, peer_disconnect_threshold, \
             acl_enabled, acl_default_allow, location_mfa_mode \"location_mfa_mode: LocationMfaMode\", \
-            service_location_mode \"service_location_mode: ServiceLocationMode\" \
+            service_location_mode \"service_location_mode: ServiceLocationMode\", \
+            min_desktop_client_version, min_mobile_client_version, device_approval_required, \
+            gateway_distribution_policy \"gateway_distribution_policy: GatewayDistributionPolicy\", \
+            gateway_peer_sharding, \
+            ip_allocation_strategy \"ip_allocation_strategy: IpAllocationStrategy\", \
+            client_traffic_policy \"client_traffic_policy: _\", \
+            mfa_session_lifetime_hours, mfa_remember_device_hours, \
+            preshared_keys_enabled, preshared_key_rotation_days, preshared_keys_rotated_at, \
+            device_name_pattern, device_name_prefix, \
+            device_name_uniqueness \"device_name_uniqueness: DeviceNameUniqueness\" \
             FROM wireguard_network WHERE name = $1",
             name
         )
@@ -447,6 +677,7 @@ impl WireguardNetwork<Id> {
 
     /// Get a list of all devices belonging to users in allowed groups.
     /// Admin users should always be allowed to access a network.
+    /// Devices awaiting approval for this network or rejected are not allowed.
     /// Note: Doesn't check if the devices are really in the network.
     pub(crate) async fn get_allowed_devices(
         &self,
@@ -467,8 +698,10 @@ impl WireguardNetwork<Id> {
                 WHERE g.\"name\" IN (SELECT * FROM UNNEST($1::text[])) \
                 AND u.is_active = true \
                 AND d.device_type = 'user'::device_type \
+                AND NOT EXISTS (SELECT 1 FROM device_approval_request r \
+                WHERE r.device_id = d.id AND r.location_id = $2 AND r.status != 'approved'::device_approval_status) \
                 ORDER BY d.id ASC",
-                &allowed_groups
+                &allowed_groups, self.id
             )
                 .fetch_all(&mut *transaction)
                 .await?
@@ -483,7 +716,10 @@ impl WireguardNetwork<Id> {
                     JOIN \"user\" u ON d.user_id = u.id \
                     WHERE u.is_active = true \
                     AND d.device_type = 'user'::device_type \
-                    ORDER BY d.id ASC"
+                    AND NOT EXISTS (SELECT 1 FROM device_approval_request r \
+                    WHERE r.device_id = d.id AND r.location_id = $1 AND r.status != 'approved'::device_approval_status) \
+                    ORDER BY d.id ASC",
+                    self.id
                 )
                 .fetch_all(&mut *transaction)
                 .await?
@@ -494,8 +730,9 @@ impl WireguardNetwork<Id> {
 
     /// Get a list of devices belonging to a user which are also in the network's allowed groups.
     /// Admin users should always be allowed to access a network.
+    /// Devices awaiting approval for this network or rejected are not allowed.
     /// Note: Doesn't check if the devices are really in the network.
-    async fn get_allowed_devices_for_user(
+    pub(crate) async fn get_allowed_devices_for_user(
         &self,
         transaction: &mut PgConnection,
         user_id: Id,
@@ -516,8 +753,10 @@ impl WireguardNetwork<Id> {
                 AND u.is_active = true \
                 AND d.device_type = 'user'::device_type \
                 AND d.user_id = $2 \
+                AND NOT EXISTS (SELECT 1 FROM device_approval_request r \
+                WHERE r.device_id = d.id AND r.location_id = $3 AND r.status != 'approved'::device_approval_status) \
                 ORDER BY d.id ASC",
-                &allowed_groups, user_id
+                &allowed_groups, user_id, self.id
             )
                 .fetch_all(&mut *transaction)
                 .await?
@@ -533,7 +772,9 @@ impl WireguardNetwork<Id> {
                     WHERE u.is_active = true \
                     AND d.device_type = 'user'::device_type \
                     AND d.user_id = $1 \
-                    ORDER BY d.id ASC", user_id
+                    AND NOT EXISTS (SELECT 1 FROM device_approval_request r \
+                    WHERE r.device_id = d.id AND r.location_id = $2 AND r.status != 'approved'::device_approval_status) \
+                    ORDER BY d.id ASC", user_id, self.id
                 )
                 .fetch_all(&mut *transaction)
                 .await?
@@ -797,8 +1038,8 @@ impl WireguardNetwork<Id> {
                                 existing_device.wireguard_pubkey,
                                 imported_device.wireguard_ips.as_csv()
                             );
-                            let wireguard_network_device = WireguardNetworkDevice::new(
-                                self.id,
+                            let wireguard_network_device = WireguardNetworkDevice::for_location(
+                                self,
                                 existing_device.id,
                                 imported_device.wireguard_ips,
                             );
@@ -882,8 +1123,8 @@ impl WireguardNetwork<Id> {
             let mut network_info = Vec::new();
             match &allowed_groups {
                 None => {
-                    let wireguard_network_device = WireguardNetworkDevice::new(
-                        self.id,
+                    let wireguard_network_device = WireguardNetworkDevice::for_location(
+                        self,
                         device.id,
                         mapped_device.wireguard_ips.clone(),
                     );
@@ -899,8 +1140,8 @@ impl WireguardNetwork<Id> {
                     // check if user belongs to an allowed group
                     if allowed.iter().any(|group| groups.contains(group)) {
                         // assign specified IP in imported network
-                        let wireguard_network_device = WireguardNetworkDevice::new(
-                            self.id,
+                        let wireguard_network_device = WireguardNetworkDevice::for_location(
+                            self,
                             device.id,
                             mapped_device.wireguard_ips.clone(),
                         );
@@ -933,6 +1174,146 @@ impl WireguardNetwork<Id> {
         Ok(events)
     }
 
+    /// Import all peers found in a WireGuard config in one go.
+    /// Peers are matched with existing devices by public key first. Remaining peers with an email
+    /// annotation get a new device created for the matching user, using imported IPs.
+    /// Return a per-peer report and a list of WireGuard events to be sent out.
+    pub(crate) async fn migrate_imported_devices(
+        &self,
+        transaction: &mut PgConnection,
+        imported_devices: Vec<ImportedDevice>,
+    ) -> Result<(Vec<PeerImportReport>, Vec<GatewayEvent>), WireguardNetworkError> {
+        info!(
+            "Migrating {} imported peers for network {self}",
+            imported_devices.len()
+        );
+        let (devices_to_map, mut events) = self
+            .handle_imported_devices(&mut *transaction, imported_devices.clone())
+            .await?;
+
+        // match remaining peers with users by email annotation
+        let mut mapped_devices = Vec::new();
+        for imported_device in &devices_to_map {
+            let Some(email) = &imported_device.email else {
+                continue;
+            };
+            match User::find_by_email(&mut *transaction, email).await? {
+                Some(user) => mapped_devices.push(MappedDevice {
+                    user_id: user.id,
+                    name: imported_device.name.clone(),
+                    wireguard_pubkey: imported_device.wireguard_pubkey.clone(),
+                    wireguard_ips: imported_device.wireguard_ips.clone(),
+                }),
+                None => warn!(
+                    "No user with email {email} found for imported peer {}",
+                    imported_device.wireguard_pubkey
+                ),
+            }
+        }
+        events.extend(
+            self.handle_mapped_devices(&mut *transaction, mapped_devices)
+                .await?,
+        );
+
+        // build the report
+        let mut report = Vec::with_capacity(imported_devices.len());
+        for imported_device in imported_devices {
+            let status =
+                match Device::find_by_pubkey(&mut *transaction, &imported_device.wireguard_pubkey)
+                    .await?
+                {
+                    Some(device) => {
+                        let is_allowed =
+                            WireguardNetworkDevice::find(&mut *transaction, device.id, self.id)
+                                .await?
+                                .is_some();
+                        let created = devices_to_map
+                            .iter()
+                            .any(|dev| dev.wireguard_pubkey == device.wireguard_pubkey);
+                        match (is_allowed, created) {
+                            (false, _) => PeerImportStatus::NotAllowed {
+                                device_id: device.id,
+                            },
+                            (true, false) => PeerImportStatus::Matched {
+                                device_id: device.id,
+                            },
+                            (true, true) => PeerImportStatus::Created {
+                                device_id: device.id,
+                                user_id: device.user_id,
+                            },
+                        }
+                    }
+                    None => PeerImportStatus::Unmapped,
+                };
+            report.push(PeerImportReport {
+                name: imported_device.name,
+                wireguard_pubkey: imported_device.wireguard_pubkey,
+                wireguard_ips: imported_device.wireguard_ips,
+                email: imported_device.email,
+                status,
+            });
+        }
+
+        Ok((report, events))
+    }
+
+    /// AllowedIPs for a device in this location, including routes attached to this location.
+    pub(crate) async fn device_allowed_ips<'e, E>(
+        &self,
+        executor: E,
+        device: &Device<Id>,
+        enterprise_settings: &EnterpriseSettings,
+    ) -> Result<Vec<IpNetwork>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        // groups of the owner don't apply to network devices
+        let user_id = (device.device_type == DeviceType::User).then_some(device.user_id);
+        let routes = Route::addresses_for_location(executor, self.id, user_id).await?;
+
+        Ok(get_allowed_ips_for_device(
+            enterprise_settings,
+            self,
+            &routes,
+        ))
+    }
+
+    /// Fetch peers to be included in exported wg-quick server config.
+    /// Uses the same criteria as peers sent to gateways.
+    pub(crate) async fn export_peers<'e, E>(
+        &self,
+        executor: E,
+    ) -> Result<Vec<ExportedPeer>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        let mut peers = query_as!(
+            ExportedPeer,
+            "SELECT d.id device_id, d.name, d.wireguard_pubkey, u.email, \
+                wnd.wireguard_ips \"wireguard_ips: Vec<IpAddr>\", wnd.preshared_key \
+            FROM wireguard_network_device wnd \
+            JOIN device d ON wnd.device_id = d.id \
+            JOIN \"user\" u ON d.user_id = u.id \
+            WHERE wireguard_network_id = $1 AND (is_authorized = true OR NOT $2) \
+            AND d.configured = true \
+            AND u.is_active = true \
+            ORDER BY d.id ASC",
+            self.id,
+            self.mfa_enabled()
+        )
+        .fetch_all(executor)
+        .await?;
+
+        // preshared keys are only used with MFA or static preshared keys, same as in gateway config
+        if !self.mfa_enabled() && !self.static_preshared_keys() {
+            for peer in &mut peers {
+                peer.preshared_key = None;
+            }
+        }
+
+        Ok(peers)
+    }
+
     /// Finds when the device connected based on handshake timestamps.
     async fn connected_at(
         &self,
@@ -1299,6 +1680,71 @@ impl WireguardNetwork<Id> {
         }
     }
 
+    /// Peers of this location use static preshared keys. MFA locations generate a preshared key
+    /// for each session instead.
+    #[must_use]
+    pub fn static_preshared_keys(&self) -> bool {
+        self.preshared_keys_enabled && !self.mfa_enabled()
+    }
+
+    /// Static preshared keys are due for rotation.
+    #[must_use]
+    pub(crate) fn preshared_keys_rotation_due(&self) -> bool {
+        if !self.static_preshared_keys() || self.preshared_key_rotation_days <= 0 {
+            return false;
+        }
+        self.preshared_keys_rotated_at.is_none_or(|rotated_at| {
+            rotated_at + TimeDelta::days(self.preshared_key_rotation_days.into())
+                <= Utc::now().naive_utc()
+        })
+    }
+
+    /// Generate static preshared keys for location peers. If `rotate` is false, only peers
+    /// without a preshared key get one.
+    pub(crate) async fn generate_preshared_keys(
+        &self,
+        conn: &mut PgConnection,
+        rotate: bool,
+    ) -> Result<(), SqlxError> {
+        let device_ids = query_scalar!(
+            "SELECT device_id FROM wireguard_network_device \
+            WHERE wireguard_network_id = $1 AND ($2 OR preshared_key IS NULL)",
+            self.id,
+            rotate
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+        for device_id in device_ids {
+            query!(
+                "UPDATE wireguard_network_device SET preshared_key = $3 \
+                WHERE wireguard_network_id = $1 AND device_id = $2",
+                self.id,
+                device_id,
+                Self::genkey().public
+            )
+            .execute(&mut *conn)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove static preshared keys of location peers.
+    pub(crate) async fn clear_preshared_keys<'e, E>(&self, executor: E) -> Result<(), SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query!(
+            "UPDATE wireguard_network_device SET preshared_key = NULL \
+            WHERE wireguard_network_id = $1",
+            self.id
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
     // fetch all locations using external MFA
     pub(crate) async fn all_using_external_mfa<'e, E>(
         executor: E,
@@ -1311,7 +1757,16 @@ impl WireguardNetwork<Id> {
             "SELECT id, name, address, port, pubkey, prvkey, endpoint, dns, allowed_ips, \
             connected_at, keepalive_interval, peer_disconnect_threshold, acl_enabled, \
             acl_default_allow, location_mfa_mode \"location_mfa_mode: LocationMfaMode\", \
-            service_location_mode \"service_location_mode: ServiceLocationMode\" \
+            service_location_mode \"service_location_mode: ServiceLocationMode\", \
+            min_desktop_client_version, min_mobile_client_version, device_approval_required, \
+            gateway_distribution_policy \"gateway_distribution_policy: GatewayDistributionPolicy\", \
+            gateway_peer_sharding, \
+            ip_allocation_strategy \"ip_allocation_strategy: IpAllocationStrategy\", \
+            client_traffic_policy \"client_traffic_policy: _\", \
+            mfa_session_lifetime_hours, mfa_remember_device_hours, \
+            preshared_keys_enabled, preshared_key_rotation_days, preshared_keys_rotated_at, \
+            device_name_pattern, device_name_prefix, \
+            device_name_uniqueness \"device_name_uniqueness: DeviceNameUniqueness\" \
             FROM wireguard_network WHERE location_mfa_mode = 'external'::location_mfa_mode",
         )
         .fetch_all(executor)
@@ -1364,6 +1819,21 @@ impl Default for WireguardNetwork {
             acl_default_allow: false,
             location_mfa_mode: LocationMfaMode::default(),
             service_location_mode: ServiceLocationMode::default(),
+            min_desktop_client_version: None,
+            min_mobile_client_version: None,
+            device_approval_required: false,
+            gateway_distribution_policy: GatewayDistributionPolicy::default(),
+            gateway_peer_sharding: false,
+            ip_allocation_strategy: IpAllocationStrategy::default(),
+            client_traffic_policy: None,
+            mfa_session_lifetime_hours: 0,
+            mfa_remember_device_hours: 0,
+            preshared_keys_enabled: false,
+            preshared_key_rotation_days: 0,
+            preshared_keys_rotated_at: None,
+            device_name_pattern: None,
+            device_name_prefix: None,
+            device_name_uniqueness: DeviceNameUniqueness::default(),
         }
     }
 }
@@ -1496,13 +1966,57 @@ pub(crate) async fn networks_stats(
     })
 }
 
+/// Traffic of a single location, used to find the busiest locations.
+#[derive(Deserialize, Serialize)]
+pub struct LocationTrafficStats {
+    pub id: Id,
+    pub name: String,
+    pub active_devices: i64,
+    pub upload: i64,
+    pub download: i64,
+}
+
+/// Locations with the highest traffic since `from`.
+pub(crate) async fn top_locations_by_traffic(
+    conn: &PgPool,
+    from: &NaiveDateTime,
+    limit: i64,
+) -> Result<Vec<LocationTrafficStats>, SqlxError> {
+    let current_activity_from = (Utc::now() - WIREGUARD_MAX_HANDSHAKE).naive_utc();
+    query_as!(
+        LocationTrafficStats,
+        "SELECT n.id, n.name, \
+                COUNT(DISTINCT s.device_id) FILTER (WHERE s.latest_handshake >= $2) \"active_devices!\", \
+                CAST(COALESCE(SUM(s.upload), 0) AS bigint) \"upload!\", \
+                CAST(COALESCE(SUM(s.download), 0) AS bigint) \"download!\" \
+            FROM wireguard_network n \
+            JOIN wireguard_peer_stats_view s ON s.network = n.id \
+            WHERE s.collected_at >= $1 \
+            GROUP BY n.id, n.name \
+            ORDER BY COALESCE(SUM(s.upload), 0) + COALESCE(SUM(s.download), 0) DESC \
+            LIMIT $3",
+        from,
+        current_activity_from,
+        limit
+    )
+    .fetch_all(conn)
+    .await
+}
+
 // If `force_all_traffic` setting is enabled we override the allowed_ips
-// to also enforce this on legacy clients.
+// to also enforce this on legacy clients. Location client traffic policy takes precedence
+// over the one from enterprise settings. Otherwise, addresses of routes applying to the device
+// are appended to location allowed_ips.
 pub fn get_allowed_ips_for_device(
     enterprise_settings: &EnterpriseSettings,
     location: &WireguardNetwork<Id>,
+    routes: &[IpNetwork],
 ) -> Vec<IpNetwork> {
-    if enterprise_settings.client_traffic_policy == ClientTrafficPolicy::ForceAllTraffic {
+    let client_traffic_policy = match location.client_traffic_policy {
+        Some(policy) if is_business_license_active() => policy,
+        _ => enterprise_settings.client_traffic_policy,
+    };
+    if client_traffic_policy == ClientTrafficPolicy::ForceAllTraffic {
         vec![
             IpNetwork::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0)
                 .expect("Failed to parse UNSPECIFIED IPv4 constant"),
@@ -1510,13 +2024,19 @@ pub fn get_allowed_ips_for_device(
                 .expect("Failed to parse UNSPECIFIED IPv6 constant"),
         ]
     } else {
-        location.allowed_ips.clone()
+        let mut allowed_ips = location.allowed_ips.clone();
+        for address in routes {
+            if !allowed_ips.contains(address) {
+                allowed_ips.push(*address);
+            }
+        }
+        allowed_ips
     }
 }
 
 #[cfg(test)]
 mod test {
-    use std::str::FromStr;
+    use std::{collections::HashSet, str::FromStr};
 
     use chrono::{SubsecRound, TimeDelta, Utc};
     use defguard_common::db::setup_pool;
@@ -1572,6 +2092,7 @@ mod test {
                 download: (samples - i) * 20,
                 latest_handshake: now - TimeDelta::minutes(handshake_minutes),
                 allowed_ips: Some("10.1.1.0/24".into()),
+                gateway: None,
             }
             .save(&pool)
             .await
@@ -1634,6 +2155,7 @@ mod test {
                 download: (samples - i) * 20,
                 latest_handshake: now - TimeDelta::minutes(i), // handshake every minute
                 allowed_ips: Some("10.1.1.0/24".into()),
+                gateway: None,
             }
             .save(&pool)
             .await
@@ -2510,4 +3032,152 @@ mod test {
         let normal_counts = Counts::new(0, 0, 0, 0);
         set_counts(normal_counts);
     }
+
+    #[test]
+    fn test_ip_allocation_sequential() {
+        let network = IpNetwork::from_str("10.1.1.1/29").unwrap();
+        let ips: Vec<IpAddr> = IpAllocationStrategy::Sequential
+            .candidate_ips(&network, 1)
+            .collect();
+        assert_eq!(ips, network.iter().collect::<Vec<_>>());
+
+        let network = IpNetwork::from_str("fc00::1/120").unwrap();
+        let mut ips = IpAllocationStrategy::Sequential.candidate_ips(&network, 1);
+        assert_eq!(ips.next(), Some(IpAddr::from_str("fc00::").unwrap()));
+        assert_eq!(ips.next(), Some(IpAddr::from_str("fc00::1").unwrap()));
+        assert_eq!(ips.count(), 254);
+    }
+
+    #[test]
+    fn test_ip_allocation_random() {
+        let network = IpNetwork::from_str("10.1.0.1/16").unwrap();
+        let ips: Vec<IpAddr> = IpAllocationStrategy::Random
+            .candidate_ips(&network, 1)
+            .collect();
+        // random attempts followed by a scan of the whole subnet
+        assert_eq!(ips.len(), RANDOM_ALLOCATION_ATTEMPTS + 65536);
+        assert!(ips.iter().all(|ip| network.contains(*ip)));
+        let scanned: HashSet<IpAddr> = ips[RANDOM_ALLOCATION_ATTEMPTS..].iter().copied().collect();
+        assert_eq!(scanned.len(), 65536);
+
+        // different addresses are tried first on consecutive allocations
+        let other: Vec<IpAddr> = IpAllocationStrategy::Random
+            .candidate_ips(&network, 1)
+            .take(RANDOM_ALLOCATION_ATTEMPTS)
+            .collect();
+        assert_ne!(ips[..RANDOM_ALLOCATION_ATTEMPTS], other);
+
+        let network = IpNetwork::from_str("fc00::1/64").unwrap();
+        assert!(
+            IpAllocationStrategy::Random
+                .candidate_ips(&network, 1)
+                .take(100)
+                .all(|ip| network.contains(ip))
+        );
+    }
+
+    #[test]
+    fn test_ip_allocation_sticky_by_user() {
+        let network = IpNetwork::from_str("10.1.0.1/16").unwrap();
+        let first: Vec<IpAddr> = IpAllocationStrategy::StickyByUser
+            .candidate_ips(&network, 1)
+            .collect();
+        let second: Vec<IpAddr> = IpAllocationStrategy::StickyByUser
+            .candidate_ips(&network, 1)
+            .collect();
+        assert_eq!(first, second);
+        assert_eq!(first.iter().copied().collect::<HashSet<_>>().len(), 65536);
+
+        // collisions are resolved by trying following addresses, wrapping around the subnet
+        for pair in first.windows(2) {
+            let (IpAddr::V4(ip), IpAddr::V4(next)) = (pair[0], pair[1]) else {
+                panic!("unexpected address family");
+            };
+            if ip == Ipv4Addr::new(10, 1, 255, 255) {
+                assert_eq!(next, Ipv4Addr::new(10, 1, 0, 0));
+            } else {
+                assert_eq!(u32::from(next), u32::from(ip) + 1);
+            }
+        }
+
+        let other: Vec<IpAddr> = IpAllocationStrategy::StickyByUser
+            .candidate_ips(&network, 2)
+            .collect();
+        assert_ne!(first[0], other[0]);
+    }
+
+    #[sqlx::test]
+    async fn test_static_preshared_keys(_: PgPoolOptions, options: PgConnectOptions) {
+        let pool = setup_pool(options).await;
+        let mut network = WireguardNetwork::default();
+        network.try_set_address("10.1.1.1/29").unwrap();
+        network.preshared_keys_enabled = true;
+        let mut network = network.save(&pool).await.unwrap();
+
+        let user = User::new(
+            "testuser",
+            Some("hunter2"),
+            "Tester",
+            "Test",
+            "test@test.com",
+            None,
+        )
+        .save(&pool)
+        .await
+        .unwrap();
+        let device = Device::new(
+            "device".into(),
+            "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=".into(),
+            user.id,
+            DeviceType::User,
+            None,
+            true,
+        )
+        .save(&pool)
+        .await
+        .unwrap();
+
+        // new peers get a preshared key
+        let mut conn = pool.acquire().await.unwrap();
+        let network_device = network
+            .add_device_to_network(&mut conn, &device, None)
+            .await
+            .unwrap();
+        let preshared_key = network_device.preshared_key.unwrap();
+        let peers = network.get_peers(&mut *conn).await.unwrap();
+        assert_eq!(peers[0].preshared_key.as_ref(), Some(&preshared_key));
+
+        // existing keys are only replaced on rotation
+        network
+            .generate_preshared_keys(&mut conn, false)
+            .await
+            .unwrap();
+        let peers = network.get_peers(&mut *conn).await.unwrap();
+        assert_eq!(peers[0].preshared_key.as_ref(), Some(&preshared_key));
+        network
+            .generate_preshared_keys(&mut conn, true)
+            .await
+            .unwrap();
+        let peers = network.get_peers(&mut *conn).await.unwrap();
+        assert_ne!(peers[0].preshared_key.as_ref(), Some(&preshared_key));
+
+        assert!(!network.preshared_keys_rotation_due());
+        network.preshared_key_rotation_days = 7;
+        assert!(network.preshared_keys_rotation_due());
+        network.preshared_keys_rotated_at = Some(Utc::now().naive_utc() - TimeDelta::days(6));
+        assert!(!network.preshared_keys_rotation_due());
+        network.preshared_keys_rotated_at = Some(Utc::now().naive_utc() - TimeDelta::days(7));
+        assert!(network.preshared_keys_rotation_due());
+
+        // MFA locations use their own preshared keys
+        network.location_mfa_mode = LocationMfaMode::Internal;
+        assert!(!network.static_preshared_keys());
+        assert!(!network.preshared_keys_rotation_due());
+
+        network.location_mfa_mode = LocationMfaMode::Disabled;
+        network.clear_preshared_keys(&mut *conn).await.unwrap();
+        network.preshared_keys_enabled = false;
+        let peers = network.get_peers(&mut *conn).await.unwrap();
+        assert_eq!(peers[0].preshared_key, None);
+    }
 }
@@ -0,0 +1,66 @@
+//! Approval-gated device enrollment.
+//!
+//! Locations with `device_approval_required` don't receive devices created through enrollment
+//! right away. Instead a [`DeviceApprovalRequest`] is created for each such location and the
+//! device is excluded from allowed devices of that location until an administrator approves it.
+//! Requests which are not resolved in time expire and are treated as rejected.
+
+use chrono::NaiveDateTime;
+use defguard_common::db::Id;
+use sqlx::{PgConnection, PgPool};
+
+use crate::db::{
+    Device, WireguardNetwork,
+    models::{device_approval::DeviceApprovalRequest, wireguard::WireguardNetworkError},
+};
+
+/// Creates approval requests for a new device in all locations which require device approval
+/// and in which the device would otherwise be allowed.
+///
+/// Must be called before the device is added to locations, as pending requests exclude it from
+/// allowed devices. Returns locations awaiting approval.
+pub(crate) async fn request_device_approvals(
+    conn: &mut PgConnection,
+    device: &Device<Id>,
+    expires_at: NaiveDateTime,
+    location_ids: Option<&[Id]>,
+) -> Result<Vec<WireguardNetwork<Id>>, WireguardNetworkError> {
+    let mut locations = Vec::new();
+    for location in WireguardNetwork::all(&mut *conn).await? {
+        if !location.device_approval_required {
+            continue;
+        }
+        if location_ids.is_some_and(|location_ids| !location_ids.contains(&location.id)) {
+            continue;
+        }
+        let allowed = location
+            .get_allowed_devices_for_user(&mut *conn, device.user_id)
+            .await?
+            .iter()
+            .any(|allowed_device| allowed_device.id == device.id);
+        if !allowed {
+            continue;
+        }
+
+        DeviceApprovalRequest::new(device.id, location.id, expires_at)
+            .save(&mut *conn)
+            .await?;
+        info!("Device {device} awaits approval in location {location}");
+        locations.push(location);
+    }
+
+    Ok(locations)
+}
+
+/// Expires device approval requests which have not been resolved in time.
+pub async fn expire_device_approvals(pool: &PgPool) -> Result<(), sqlx::Error> {
+    let expired = DeviceApprovalRequest::expire_all_due(pool).await?;
+    for request in expired {
+        info!(
+            "Approval request {} of device {} in location {} has expired",
+            request.id, request.device_id, request.location_id
+        );
+    }
+
+    Ok(())
+}
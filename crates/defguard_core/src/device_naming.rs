@@ -0,0 +1,251 @@
+//! Device naming policies of locations.
+//!
+//! Locations can require names of devices added to them to match a regular expression, to start
+//! with a prefix rendered from a template like `{username}-{os}` and to be unique among all
+//! devices of the location instead of only among devices of the owner. Policies are enforced
+//! when user devices are created, both through the API and enrollment.
+
+use defguard_common::db::{Id, models::ModelError};
+use regex::Regex;
+use sqlx::{PgConnection, query_scalar};
+use thiserror::Error;
+use tonic::Status;
+
+use crate::db::{User, WireguardNetwork, models::wireguard::DeviceNameUniqueness};
+
+/// Placeholders available in device name prefix templates.
+const PREFIX_PLACEHOLDERS: [&str; 4] = ["username", "first_name", "last_name", "os"];
+
+/// Used in place of `{os}` if the operating system of a device isn't known.
+const UNKNOWN_OS: &str = "unknown";
+
+#[derive(Debug, Error)]
+pub enum DeviceNameError {
+    #[error("Invalid device name pattern {0}")]
+    InvalidPattern(String),
+    #[error("Unknown placeholder {{{0}}} in device name prefix")]
+    UnknownPlaceholder(String),
+    #[error("Device name {name} doesn't match naming pattern of location {location}")]
+    PatternMismatch { name: String, location: String },
+    #[error("Device name {name} has to start with \"{prefix}\" in location {location}")]
+    MissingPrefix {
+        name: String,
+        prefix: String,
+        location: String,
+    },
+    #[error("Device {0} already exists")]
+    DuplicateForUser(String),
+    #[error("Device {name} already exists in location {location}")]
+    DuplicateInLocation { name: String, location: String },
+    #[error(transparent)]
+    DbError(#[from] sqlx::Error),
+    #[error(transparent)]
+    ModelError(#[from] ModelError),
+}
+
+impl From<DeviceNameError> for Status {
+    fn from(error: DeviceNameError) -> Self {
+        match error {
+            DeviceNameError::DbError(_) | DeviceNameError::ModelError(_) => {
+                error!("Failed to validate device name: {error}");
+                Status::internal("unexpected error")
+            }
+            _ => Status::invalid_argument(error.to_string()),
+        }
+    }
+}
+
+/// Device names have to match the whole pattern, not only a part of it.
+fn compile_pattern(pattern: &str) -> Result<Regex, DeviceNameError> {
+    Regex::new(&format!("^(?:{pattern})$"))
+        .map_err(|_| DeviceNameError::InvalidPattern(pattern.to_string()))
+}
+
+/// Lowercase name part with whitespace replaced by dashes, e.g. "Mac OS X" becomes "mac-os-x".
+fn name_part(value: &str) -> String {
+    value
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase()
+}
+
+/// Replaces `{placeholder}`s of a prefix template using `value`. Braces which don't form
+/// a placeholder are kept as they are.
+fn render_prefix<F>(template: &str, value: F) -> Result<String, DeviceNameError>
+where
+    F: Fn(&str) -> String,
+{
+    let mut prefix = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        prefix.push_str(&rest[..start]);
+        let tag = &rest[start + 1..];
+        let Some(end) = tag.find('}') else {
+            prefix.push_str(&rest[start..]);
+            return Ok(prefix);
+        };
+        let placeholder = &tag[..end];
+        if !PREFIX_PLACEHOLDERS.contains(&placeholder) {
+            return Err(DeviceNameError::UnknownPlaceholder(placeholder.to_string()));
+        }
+        prefix.push_str(&value(placeholder));
+        rest = &tag[end + 1..];
+    }
+    prefix.push_str(rest);
+
+    Ok(prefix)
+}
+
+/// Validates naming policy settings of a location.
+pub(crate) fn validate_naming_policy(
+    pattern: Option<&str>,
+    prefix: Option<&str>,
+) -> Result<(), DeviceNameError> {
+    if let Some(pattern) = pattern {
+        compile_pattern(pattern)?;
+    }
+    if let Some(prefix) = prefix {
+        render_prefix(prefix, |_| String::new())?;
+    }
+
+    Ok(())
+}
+
+/// Makes sure a new user device can be named `name` in all locations it's going to be added to,
+/// i.e. locations the user has access to, limited to `location_ids` if given.
+///
+/// `os` is the operating system family of the device, if known.
+pub(crate) async fn validate_device_name(
+    conn: &mut PgConnection,
+    name: &str,
+    user: &User<Id>,
+    os: Option<&str>,
+    location_ids: Option<&[Id]>,
+) -> Result<(), DeviceNameError> {
+    let exists = query_scalar!(
+        "SELECT EXISTS (SELECT 1 FROM device WHERE user_id = $1 AND name = $2 \
+        AND device_type = 'user'::device_type) \"exists!\"",
+        user.id,
+        name
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    if exists {
+        return Err(DeviceNameError::DuplicateForUser(name.to_string()));
+    }
+
+    let user_groups = user.member_of_names(&mut *conn).await?;
+    for location in WireguardNetwork::all(&mut *conn).await? {
+        if location_ids.is_some_and(|location_ids| !location_ids.contains(&location.id)) {
+            continue;
+        }
+        let allowed = location
+            .get_allowed_groups(&mut *conn)
+            .await?
+            .is_none_or(|groups| groups.iter().any(|group| user_groups.contains(group)));
+        if !allowed {
+            continue;
+        }
+        validate_location_device_name(&mut *conn, &location, name, user, os).await?;
+    }
+
+    Ok(())
+}
+
+async fn validate_location_device_name(
+    conn: &mut PgConnection,
+    location: &WireguardNetwork<Id>,
+    name: &str,
+    user: &User<Id>,
+    os: Option<&str>,
+) -> Result<(), DeviceNameError> {
+    if let Some(pattern) = &location.device_name_pattern {
+        if !compile_pattern(pattern)?.is_match(name) {
+            return Err(DeviceNameError::PatternMismatch {
+                name: name.to_string(),
+                location: location.name.clone(),
+            });
+        }
+    }
+    if let Some(template) = &location.device_name_prefix {
+        let prefix = render_prefix(template, |placeholder| match placeholder {
+            "username" => user.username.clone(),
+            "first_name" => name_part(&user.first_name),
+            "last_name" => name_part(&user.last_name),
+            _ => name_part(os.unwrap_or(UNKNOWN_OS)),
+        })?;
+        if !name.starts_with(&prefix) {
+            return Err(DeviceNameError::MissingPrefix {
+                name: name.to_string(),
+                prefix,
+                location: location.name.clone(),
+            });
+        }
+    }
+    if location.device_name_uniqueness == DeviceNameUniqueness::Location {
+        let exists = query_scalar!(
+            "SELECT EXISTS (SELECT 1 FROM device d \
+            JOIN wireguard_network_device wnd ON wnd.device_id = d.id \
+            WHERE wnd.wireguard_network_id = $1 AND d.name = $2) \"exists!\"",
+            location.id,
+            name
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+        if exists {
+            return Err(DeviceNameError::DuplicateInLocation {
+                name: name.to_string(),
+                location: location.name.clone(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_render_prefix() {
+        let value = |placeholder: &str| match placeholder {
+            "username" => "hpotter".to_string(),
+            "os" => name_part("Mac OS X"),
+            _ => String::new(),
+        };
+        assert_eq!(
+            render_prefix("{username}-{os}-", value).unwrap(),
+            "hpotter-mac-os-x-"
+        );
+        assert_eq!(render_prefix("corp-", value).unwrap(), "corp-");
+        assert_eq!(render_prefix("{username}-{", value).unwrap(), "hpotter-{");
+        assert!(matches!(
+            render_prefix("{hostname}", value),
+            Err(DeviceNameError::UnknownPlaceholder(placeholder)) if placeholder == "hostname"
+        ));
+    }
+
+    #[test]
+    fn test_validate_naming_policy() {
+        assert!(validate_naming_policy(Some("[a-z0-9-]+"), Some("{username}-")).is_ok());
+        assert!(validate_naming_policy(None, None).is_ok());
+        assert!(matches!(
+            validate_naming_policy(Some("[a-z"), None),
+            Err(DeviceNameError::InvalidPattern(_))
+        ));
+        assert!(matches!(
+            validate_naming_policy(None, Some("{user}")),
+            Err(DeviceNameError::UnknownPlaceholder(_))
+        ));
+    }
+
+    #[test]
+    fn test_pattern_matches_whole_name() {
+        let pattern = compile_pattern("laptop|phone").unwrap();
+        assert!(pattern.is_match("laptop"));
+        assert!(!pattern.is_match("my-laptop"));
+        assert!(!pattern.is_match("phone-2"));
+    }
+}
@@ -0,0 +1,344 @@
+//! Printable enrollment sheets.
+//!
+//! Enrollment sheets are single page PDF documents containing everything a user needs to
+//! enroll without receiving an email: enrollment service URL, enrollment token and instructions.
+//! They're meant to be printed and handed out, e.g. during classes or onboarding events.
+
+use std::io::{Cursor, Write};
+
+use chrono::NaiveDateTime;
+use reqwest::Url;
+use zip::{ZipWriter, result::ZipError, write::SimpleFileOptions};
+
+// A4 page size in points
+const PAGE_WIDTH: u32 = 595;
+const PAGE_HEIGHT: u32 = 842;
+const MARGIN: u32 = 50;
+// Maximum number of characters in a line, fits the page width for used font sizes.
+const LINE_CHARS: usize = 80;
+const DATETIME_FORMAT: &str = "%A, %B %d, %Y at %H:%M UTC";
+const DOWNLOAD_URL: &str = "https://defguard.net/download/";
+
+/// Fonts available in generated documents.
+#[derive(Clone, Copy)]
+enum Font {
+    Regular,
+    Bold,
+    Monospace,
+}
+
+impl Font {
+    fn resource_name(self) -> &'static str {
+        match self {
+            Self::Regular => "F1",
+            Self::Bold => "F2",
+            Self::Monospace => "F3",
+        }
+    }
+}
+
+/// Enrollment data of a single user.
+pub struct EnrollmentSheet {
+    pub instance_name: String,
+    pub username: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub enrollment_url: Url,
+    pub token: String,
+    pub expires_at: NaiveDateTime,
+}
+
+impl EnrollmentSheet {
+    #[must_use]
+    pub fn file_name(&self) -> String {
+        format!("enrollment_{}.pdf", self.username)
+    }
+
+    /// Renders the sheet as a PDF document.
+    #[must_use]
+    pub fn to_pdf(&self) -> Vec<u8> {
+        let mut link_url = self.enrollment_url.clone();
+        link_url.query_pairs_mut().append_pair("token", &self.token);
+
+        let mut page = Page::default();
+        page.text(
+            Font::Bold,
+            20,
+            &format!("{} enrollment", self.instance_name),
+        );
+        page.space(8);
+        page.text(
+            Font::Regular,
+            12,
+            &format!(
+                "Account: {} {} ({})",
+                self.first_name, self.last_name, self.username
+            ),
+        );
+        page.text(
+            Font::Regular,
+            12,
+            &format!("Token expires: {}", self.expires_at.format(DATETIME_FORMAT)),
+        );
+        page.space(16);
+
+        page.text(Font::Bold, 14, "1. Enrollment by desktop client");
+        page.space(4);
+        page.text(
+            Font::Regular,
+            11,
+            &format!("Download the official defguard desktop client: {DOWNLOAD_URL}"),
+        );
+        page.text(
+            Font::Regular,
+            11,
+            "After installation, add a new instance by entering:",
+        );
+        page.text(Font::Regular, 11, "Instance URL:");
+        page.text(Font::Monospace, 11, self.enrollment_url.as_str());
+        page.text(Font::Regular, 11, "Enrollment token:");
+        page.text(Font::Monospace, 11, &self.token);
+        page.space(16);
+
+        page.text(Font::Bold, 14, "2. Enrollment via web browser");
+        page.space(4);
+        page.text(
+            Font::Regular,
+            11,
+            "Open the following address in your browser to set your password and configure \
+            a WireGuard device:",
+        );
+        page.text(Font::Monospace, 11, link_url.as_str());
+        page.space(16);
+
+        page.text(
+            Font::Regular,
+            10,
+            "Keep this sheet private, the enrollment token grants access to your account \
+            until the enrollment is completed.",
+        );
+
+        page.into_pdf()
+    }
+}
+
+/// Packs enrollment sheets of multiple users into a ZIP archive.
+pub fn enrollment_sheets_zip(sheets: &[EnrollmentSheet]) -> Result<Vec<u8>, ZipError> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    for sheet in sheets {
+        zip.start_file(sheet.file_name(), SimpleFileOptions::default())?;
+        zip.write_all(&sheet.to_pdf())?;
+    }
+
+    Ok(zip.finish()?.into_inner())
+}
+
+/// Content of a single PDF page, laid out top to bottom.
+struct Page {
+    content: Vec<u8>,
+    // vertical position of the next line
+    y: u32,
+}
+
+impl Default for Page {
+    fn default() -> Self {
+        Self {
+            content: Vec::new(),
+            y: PAGE_HEIGHT - MARGIN,
+        }
+    }
+}
+
+impl Page {
+    fn space(&mut self, height: u32) {
+        self.y = self.y.saturating_sub(height);
+    }
+
+    /// Appends text, wrapping it into multiple lines if needed.
+    fn text(&mut self, font: Font, size: u32, text: &str) {
+        for line in wrap(text, LINE_CHARS * 11 / size as usize) {
+            self.y = self.y.saturating_sub(size + size / 2);
+            self.content.extend_from_slice(
+                format!(
+                    "BT /{} {size} Tf {MARGIN} {} Td (",
+                    font.resource_name(),
+                    self.y
+                )
+                .as_bytes(),
+            );
+            self.content.extend(encode_pdf_string(&line));
+            self.content.extend_from_slice(b") Tj ET\n");
+        }
+    }
+
+    fn into_pdf(self) -> Vec<u8> {
+        let mut stream = format!("<< /Length {} >>\nstream\n", self.content.len()).into_bytes();
+        stream.extend(self.content);
+        stream.extend_from_slice(b"\nendstream");
+
+        let objects: [Vec<u8>; 7] = [
+            b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+            b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec(),
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PAGE_WIDTH} {PAGE_HEIGHT}] \
+                /Resources << /Font << /F1 4 0 R /F2 5 0 R /F3 6 0 R >> >> /Contents 7 0 R >>"
+            )
+            .into_bytes(),
+            font_object("Helvetica"),
+            font_object("Helvetica-Bold"),
+            font_object("Courier"),
+            stream,
+        ];
+
+        let mut pdf = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (index, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend_from_slice(format!("{} 0 obj\n", index + 1).as_bytes());
+            pdf.extend_from_slice(object);
+            pdf.extend_from_slice(b"\nendobj\n");
+        }
+        let xref_offset = pdf.len();
+        pdf.extend_from_slice(
+            format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes(),
+        );
+        for offset in offsets {
+            pdf.extend_from_slice(format!("{offset:010} 00000 n \n").as_bytes());
+        }
+        pdf.extend_from_slice(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref_offset}\n%%EOF\n",
+                objects.len() + 1
+            )
+            .as_bytes(),
+        );
+
+        pdf
+    }
+}
+
+fn font_object(base_font: &str) -> Vec<u8> {
+    format!("<< /Type /Font /Subtype /Type1 /BaseFont /{base_font} /Encoding /WinAnsiEncoding >>")
+        .into_bytes()
+}
+
+/// Encodes text as PDF literal string content. Characters outside of Latin-1 are replaced,
+/// as standard fonts don't support them.
+fn encode_pdf_string(text: &str) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(text.len());
+    for char in text.chars() {
+        match char {
+            '(' | ')' | '\\' => {
+                encoded.push(b'\\');
+                encoded.push(char as u8);
+            }
+            ' '..='~' | '\u{a0}'..='\u{ff}' => encoded.push(char as u8),
+            _ => encoded.push(b'?'),
+        }
+    }
+
+    encoded
+}
+
+/// Splits text into lines of at most `width` characters, breaking long words if needed.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        if !line.is_empty() && line.chars().count() + 1 + word.len() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        while word.len() > width {
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            lines.push(word.drain(..width).collect());
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.extend(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Read;
+
+    use chrono::Utc;
+    use zip::ZipArchive;
+
+    use super::*;
+
+    fn sheet(username: &str) -> EnrollmentSheet {
+        EnrollmentSheet {
+            instance_name: "Defguard".into(),
+            username: username.into(),
+            first_name: "Harry".into(),
+            last_name: "Potter (Gryffindor)".into(),
+            enrollment_url: Url::parse("https://enroll.example.com").unwrap(),
+            token: "4TqvCmZYyHkSNmMpjBbiXuxETvg3MaW6".into(),
+            expires_at: Utc::now().naive_utc(),
+        }
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("ab cd ef", 5), vec!["ab cd", "ef"]);
+        assert_eq!(wrap("abcdefgh ij", 3), vec!["abc", "def", "gh", "ij"]);
+        assert!(wrap("", 5).is_empty());
+    }
+
+    #[test]
+    fn test_encode_pdf_string() {
+        assert_eq!(encode_pdf_string("a(b)\\"), b"a\\(b\\)\\\\");
+        assert_eq!(encode_pdf_string("Łódź"), b"?\xf3d?");
+    }
+
+    #[test]
+    fn test_enrollment_sheet_pdf() {
+        let pdf = sheet("hpotter").to_pdf();
+        assert!(pdf.starts_with(b"%PDF-1.4\n"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains("(4TqvCmZYyHkSNmMpjBbiXuxETvg3MaW6) Tj"));
+        assert!(text.contains("Potter \\(Gryffindor\\)"));
+        assert!(
+            text.contains("https://enroll.example.com/?token=4TqvCmZYyHkSNmMpjBbiXuxETvg3MaW6")
+        );
+
+        // cross-reference table points to object headers
+        let startxref = text.rfind("startxref\n").unwrap();
+        let xref_offset: usize = text[startxref + 10..]
+            .lines()
+            .next()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(text[xref_offset..].starts_with("xref\n0 8\n"));
+        for (index, entry) in text[xref_offset..].lines().skip(3).take(7).enumerate() {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(text[offset..].starts_with(&format!("{} 0 obj\n", index + 1)));
+        }
+    }
+
+    #[test]
+    fn test_enrollment_sheets_zip() {
+        let archive = enrollment_sheets_zip(&[sheet("hpotter"), sheet("rweasley")]).unwrap();
+        let mut archive = ZipArchive::new(Cursor::new(archive)).unwrap();
+        assert_eq!(archive.len(), 2);
+        let mut pdf = Vec::new();
+        archive
+            .by_name("enrollment_rweasley.pdf")
+            .unwrap()
+            .read_to_end(&mut pdf)
+            .unwrap();
+        assert_eq!(pdf, sheet("rweasley").to_pdf());
+    }
+}
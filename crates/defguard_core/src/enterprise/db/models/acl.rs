@@ -20,7 +20,10 @@ use crate::{
     appstate::AppState,
     db::{
         Device, GatewayEvent, Group, User, WireguardNetwork,
-        models::wireguard::{LocationMfaMode, ServiceLocationMode},
+        models::wireguard::{
+            DeviceNameUniqueness, GatewayDistributionPolicy, IpAllocationStrategy, LocationMfaMode,
+            ServiceLocationMode,
+        },
     },
     enterprise::{
         firewall::FirewallError,
@@ -586,6 +589,22 @@ pub fn parse_ports(ports: &str) -> Result<Vec<PortRange>, AclError> {
     Ok(result)
 }
 
+/// Formats destination string the same way [`AclRuleInfo::format_destination`] does, so it can be
+/// compared with destination of a stored rule.
+pub(crate) fn normalize_destination(destination: &str) -> Result<String, AclError> {
+    let parsed = parse_destination(destination)?;
+    let mut formatted = format_destination(&parsed.addrs);
+    for (start, end) in parsed.ranges {
+        formatted.push_str(&format!("{start}-{end}, "));
+    }
+    Ok(formatted.trim_end_matches(", ").to_string())
+}
+
+/// Formats ports string the same way [`AclRuleInfo::format_ports`] does.
+pub(crate) fn normalize_ports(ports: &str) -> Result<String, AclError> {
+    Ok(format_ports(&parse_ports(ports)?))
+}
+
 /// Maps [`sqlx::Error`] to [`AclError`] while checking for [`ErrorKind::ForeignKeyViolation`].
 fn map_relation_error(err: SqlxError, class: &str, id: Id) -> AclError {
     if let SqlxError::Database(dberror) = &err {
@@ -913,6 +932,46 @@ impl AclRule<Id> {
         Ok(())
     }
 
+    /// Returns rules which apply only to a given location, without pending modifications of
+    /// other rules.
+    pub(crate) async fn all_for_single_location<'e, E>(
+        executor: E,
+        location_id: Id,
+    ) -> Result<Vec<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            AclRule,
+            "SELECT id, parent_id, state AS \"state: RuleState\", name, allow_all_users, \
+            deny_all_users, allow_all_network_devices, deny_all_network_devices, \
+            all_networks, destination, ports, protocols, enabled, expires \
+            FROM aclrule r \
+            WHERE parent_id IS NULL AND NOT all_networks \
+            AND EXISTS (SELECT 1 FROM aclrulenetwork WHERE rule_id = r.id AND network_id = $1) \
+            AND NOT EXISTS (SELECT 1 FROM aclrulenetwork WHERE rule_id = r.id AND network_id != $1) \
+            ORDER BY id",
+            location_id,
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    /// Returns IDs of rules with pending changes among given rules and their modifications.
+    pub(crate) async fn pending_ids<'e, E>(executor: E, ids: &[Id]) -> Result<Vec<Id>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_scalar!(
+            "SELECT id FROM aclrule \
+            WHERE state IN ('new'::aclrule_state, 'modified'::aclrule_state, 'deleted'::aclrule_state) \
+            AND (id = ANY($1) OR parent_id = ANY($1)) ORDER BY id",
+            ids,
+        )
+        .fetch_all(executor)
+        .await
+    }
+
     /// Returns all [`WireguardNetwork`]s the rule applies to
     pub(crate) async fn get_networks<'e, E>(
         &self,
@@ -929,7 +988,16 @@ impl AclRule<Id> {
                 "SELECT n.id, name, address, port, pubkey, prvkey, endpoint, dns, allowed_ips, \
                 connected_at, keepalive_interval, peer_disconnect_threshold, \
                 acl_enabled, acl_default_allow, location_mfa_mode \"location_mfa_mode: LocationMfaMode\", \
-                service_location_mode \"service_location_mode: ServiceLocationMode\" \
+                service_location_mode \"service_location_mode: ServiceLocationMode\", \
+                min_desktop_client_version, min_mobile_client_version, device_approval_required, \
+                gateway_distribution_policy \"gateway_distribution_policy: GatewayDistributionPolicy\", \
+                gateway_peer_sharding, \
+                ip_allocation_strategy \"ip_allocation_strategy: IpAllocationStrategy\", \
+                client_traffic_policy \"client_traffic_policy: _\", \
+                mfa_session_lifetime_hours, mfa_remember_device_hours, \
+                preshared_keys_enabled, preshared_key_rotation_days, preshared_keys_rotated_at, \
+                device_name_pattern, device_name_prefix, \
+                device_name_uniqueness \"device_name_uniqueness: DeviceNameUniqueness\" \
                 FROM aclrulenetwork r \
                 JOIN wireguard_network n \
                 ON n.id = r.network_id \
@@ -992,7 +1060,7 @@ impl AclRule<Id> {
             "SELECT u.id, username, password_hash, last_name, first_name, email, phone, \
             mfa_enabled, totp_enabled, totp_secret, email_mfa_enabled, email_mfa_secret, \
             mfa_method \"mfa_method: _\", recovery_codes, is_active, openid_sub, from_ldap, \
-            ldap_pass_randomized, ldap_rdn, ldap_user_path, enrollment_pending \
+            ldap_pass_randomized, ldap_rdn, ldap_user_path, enrollment_pending, mail_language \
             FROM aclruleuser r \
             JOIN \"user\" u \
             ON u.id = r.user_id \
@@ -1018,7 +1086,7 @@ impl AclRule<Id> {
             "SELECT u.id, username, password_hash, last_name, first_name, email, phone, \
             mfa_enabled, totp_enabled, totp_secret, email_mfa_enabled, email_mfa_secret, \
             mfa_method \"mfa_method: _\", recovery_codes, is_active, openid_sub, from_ldap, \
-            ldap_pass_randomized, ldap_rdn, ldap_user_path, enrollment_pending \
+            ldap_pass_randomized, ldap_rdn, ldap_user_path, enrollment_pending, mail_language \
             FROM aclruleuser r \
             JOIN \"user\" u \
             ON u.id = r.user_id \
@@ -1042,7 +1110,8 @@ impl AclRule<Id> {
     {
         query_as!(
             Group,
-            "SELECT g.id, name, is_admin \
+            "SELECT g.id, name, is_admin, \
+            client_traffic_policy \"client_traffic_policy: _\" \
             FROM aclrulegroup r \
             JOIN \"group\" g \
             ON g.id = r.group_id \
@@ -1199,7 +1268,7 @@ impl AclRuleInfo<Id> {
                 phone, mfa_enabled, totp_enabled, totp_secret, \
                 email_mfa_enabled, email_mfa_secret, \
                 mfa_method \"mfa_method: _\", recovery_codes, is_active, openid_sub, from_ldap, \
-                ldap_pass_randomized, ldap_rdn, ldap_user_path, enrollment_pending \
+                ldap_pass_randomized, ldap_rdn, ldap_user_path, enrollment_pending, mail_language \
                 FROM \"user\" \
                 WHERE is_active = true"
             )
@@ -1221,7 +1290,7 @@ impl AclRuleInfo<Id> {
             "SELECT id, username, password_hash, last_name, first_name, email, phone, mfa_enabled, \
             totp_enabled, totp_secret, email_mfa_enabled, email_mfa_secret, \
             mfa_method \"mfa_method: _\", recovery_codes, is_active, openid_sub, \
-            from_ldap, ldap_pass_randomized, ldap_rdn, ldap_user_path, enrollment_pending \
+            from_ldap, ldap_pass_randomized, ldap_rdn, ldap_user_path, enrollment_pending, mail_language \
             FROM \"user\" u \
             JOIN group_user gu ON u.id=gu.user_id \
             WHERE u.is_active=true AND gu.group_id=ANY($1)",
@@ -1260,7 +1329,7 @@ impl AclRuleInfo<Id> {
                 phone, mfa_enabled, totp_enabled, totp_secret, \
                 email_mfa_enabled, email_mfa_secret, \
                 mfa_method \"mfa_method: _\", recovery_codes, is_active, openid_sub, from_ldap, \
-                ldap_pass_randomized, ldap_rdn, ldap_user_path, enrollment_pending \
+                ldap_pass_randomized, ldap_rdn, ldap_user_path, enrollment_pending, mail_language \
                 FROM \"user\" \
                 WHERE is_active = true"
             )
@@ -1283,7 +1352,7 @@ impl AclRuleInfo<Id> {
                 phone, mfa_enabled, totp_enabled, totp_secret, \
                 email_mfa_enabled, email_mfa_secret, \
                 mfa_method \"mfa_method: _\", recovery_codes, is_active, openid_sub, \
-                from_ldap, ldap_pass_randomized, ldap_rdn, ldap_user_path, enrollment_pending \
+                from_ldap, ldap_pass_randomized, ldap_rdn, ldap_user_path, enrollment_pending, mail_language \
                 FROM \"user\" u \
             JOIN group_user gu ON u.id=gu.user_id \
                 WHERE u.is_active=true AND gu.group_id=ANY($1)",
@@ -705,3 +705,19 @@ async fn test_all_denied_users(_: PgPoolOptions, options: PgConnectOptions) {
     assert!(denied_users.iter().any(|u| u.id == user_3.id));
     assert!(!denied_users.iter().any(|u| u.id == user_4.id));
 }
+
+#[test]
+fn test_normalize_destination_and_ports() {
+    assert_eq!(
+        normalize_destination("10.1.1.10-10.1.1.20,10.0.0.1/32,  10.2.0.0/16").unwrap(),
+        "10.0.0.1, 10.2.0.0/16, 10.1.1.10-10.1.1.20"
+    );
+    assert_eq!(normalize_destination("").unwrap(), "");
+    assert!(normalize_destination("10.0.0").is_err());
+
+    assert_eq!(
+        normalize_ports("22,8000-9000, 80-80").unwrap(),
+        "22, 8000-9000, 80"
+    );
+    assert!(normalize_ports("22-23-24").is_err());
+}
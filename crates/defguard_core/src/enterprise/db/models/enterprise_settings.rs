@@ -1,5 +1,7 @@
+use defguard_common::db::Id;
 use sqlx::{PgExecutor, Type, query, query_as};
 use struct_patch::Patch;
+use utoipa::ToSchema;
 
 use crate::enterprise::is_business_license_active;
 
@@ -51,6 +53,36 @@ impl EnterpriseSettings {
         }
     }
 
+    /// Same as [`EnterpriseSettings::get`], but client traffic policy is overridden by groups
+    /// of a given user. If groups override the policy differently, the first one in order:
+    /// [`ClientTrafficPolicy::ForceAllTraffic`], [`ClientTrafficPolicy::DisableAllTraffic`],
+    /// [`ClientTrafficPolicy::None`] is used.
+    pub async fn get_for_user<'e, E>(executor: E, user_id: Id) -> Result<Self, sqlx::Error>
+    where
+        E: PgExecutor<'e>,
+    {
+        if is_business_license_active() {
+            let settings = query_as!(
+                Self,
+                "SELECT admin_device_management, \
+                COALESCE(( \
+                    SELECT g.client_traffic_policy FROM \"group\" g \
+                    JOIN group_user gu ON gu.group_id = g.id \
+                    WHERE gu.user_id = $1 AND g.client_traffic_policy IS NOT NULL \
+                    ORDER BY g.client_traffic_policy DESC LIMIT 1 \
+                ), client_traffic_policy) \"client_traffic_policy!: ClientTrafficPolicy\", \
+                only_client_activation \
+                FROM \"enterprisesettings\" WHERE id = 1",
+                user_id
+            )
+            .fetch_optional(executor)
+            .await?;
+            Ok(settings.expect("EnterpriseSettings not found"))
+        } else {
+            Ok(EnterpriseSettings::default())
+        }
+    }
+
     pub(crate) async fn save<'e, E>(&self, executor: E) -> Result<(), sqlx::Error>
     where
         E: PgExecutor<'e>,
@@ -73,7 +105,10 @@ impl EnterpriseSettings {
 }
 
 /// Describes allowed traffic options for clients connecting to the instance.
-#[derive(Clone, Deserialize, Serialize, PartialEq, Eq, Type, Debug, Default, Copy)]
+/// Variants are ordered from the least to the most restrictive one.
+#[derive(
+    Clone, Deserialize, Serialize, PartialEq, Eq, Type, Debug, Default, Copy, Hash, ToSchema,
+)]
 #[sqlx(type_name = "client_traffic_policy", rename_all = "snake_case")]
 #[serde(rename_all = "snake_case")]
 pub enum ClientTrafficPolicy {
@@ -0,0 +1,147 @@
+use chrono::NaiveDateTime;
+use defguard_common::db::Id;
+use defguard_proto::enterprise::firewall::FirewallConfig;
+use prost::{DecodeError, Message};
+use sqlx::{Error as SqlxError, PgConnection, PgExecutor, query, query_as};
+
+/// Number of firewall configuration versions kept for each location.
+pub const FIREWALL_CONFIG_VERSIONS_KEPT: i64 = 100;
+
+/// Firewall configuration sent to gateways of a location.
+///
+/// Each location has its own, monotonically increasing version number. `config` holds an encoded
+/// [`FirewallConfig`]. `rollback_of` is set for versions restored from an older one.
+#[derive(Debug)]
+pub struct FirewallConfigVersion {
+    pub id: Id,
+    pub location_id: Id,
+    pub version: i64,
+    pub config: Vec<u8>,
+    pub rollback_of: Option<i64>,
+    pub created_at: NaiveDateTime,
+}
+
+impl FirewallConfigVersion {
+    /// Store a firewall configuration with the next version for a given location and return this
+    /// version. Returns `None` if the configuration doesn't differ from the latest version.
+    /// Only the latest [`FIREWALL_CONFIG_VERSIONS_KEPT`] versions are kept.
+    pub(crate) async fn record(
+        conn: &mut PgConnection,
+        location_id: Id,
+        config: &FirewallConfig,
+        rollback_of: Option<i64>,
+    ) -> Result<Option<i64>, SqlxError> {
+        let config = config.encode_to_vec();
+        if let Some(latest) = Self::latest(&mut *conn, location_id).await? {
+            if latest.config == config {
+                return Ok(None);
+            }
+        }
+
+        let version = query!(
+            "INSERT INTO firewall_config_version (location_id, version, config, rollback_of) \
+            SELECT $1, COALESCE(MAX(version), 0) + 1, $2, $3 \
+            FROM firewall_config_version WHERE location_id = $1 \
+            RETURNING version",
+            location_id,
+            config,
+            rollback_of
+        )
+        .fetch_one(&mut *conn)
+        .await?
+        .version;
+
+        query!(
+            "DELETE FROM firewall_config_version WHERE location_id = $1 AND version <= $2",
+            location_id,
+            version - FIREWALL_CONFIG_VERSIONS_KEPT
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        Ok(Some(version))
+    }
+
+    /// Fetch the latest version of a given location.
+    pub(crate) async fn latest<'e, E>(
+        executor: E,
+        location_id: Id,
+    ) -> Result<Option<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, location_id, version, config, rollback_of, created_at \
+            FROM firewall_config_version WHERE location_id = $1 \
+            ORDER BY version DESC LIMIT 1",
+            location_id
+        )
+        .fetch_optional(executor)
+        .await
+    }
+
+    /// Fetch a given version of a location.
+    pub(crate) async fn find_by_version<'e, E>(
+        executor: E,
+        location_id: Id,
+        version: i64,
+    ) -> Result<Option<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, location_id, version, config, rollback_of, created_at \
+            FROM firewall_config_version WHERE location_id = $1 AND version = $2",
+            location_id,
+            version
+        )
+        .fetch_optional(executor)
+        .await
+    }
+
+    /// Fetch the version preceding a given one, if it's still kept.
+    pub(crate) async fn find_previous<'e, E>(
+        executor: E,
+        location_id: Id,
+        version: i64,
+    ) -> Result<Option<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, location_id, version, config, rollback_of, created_at \
+            FROM firewall_config_version WHERE location_id = $1 AND version < $2 \
+            ORDER BY version DESC LIMIT 1",
+            location_id,
+            version
+        )
+        .fetch_optional(executor)
+        .await
+    }
+
+    /// Fetch all versions of a given location, newest first.
+    pub(crate) async fn all_for_location<'e, E>(
+        executor: E,
+        location_id: Id,
+    ) -> Result<Vec<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, location_id, version, config, rollback_of, created_at \
+            FROM firewall_config_version WHERE location_id = $1 ORDER BY version DESC",
+            location_id
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    /// Decode stored firewall configuration.
+    pub(crate) fn decode(&self) -> Result<FirewallConfig, DecodeError> {
+        FirewallConfig::decode(self.config.as_slice())
+    }
+}
@@ -2,5 +2,7 @@ pub mod acl;
 pub mod activity_log_stream;
 pub mod api_tokens;
 pub mod enterprise_settings;
+pub mod firewall_config_version;
 pub mod openid_provider;
+pub mod quarantine;
 pub mod snat;
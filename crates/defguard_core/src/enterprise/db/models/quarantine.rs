@@ -0,0 +1,134 @@
+use std::net::IpAddr;
+
+use chrono::NaiveDateTime;
+use defguard_common::db::{Id, NoId};
+use ipnetwork::IpNetwork;
+use model_derive::Model;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgExecutor, Type, query_as, query_scalar};
+use utoipa::ToSchema;
+
+/// Subsystem which placed a device in quarantine.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "quarantine_source", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum QuarantineSource {
+    /// Quarantine set by an administrator through the API.
+    #[default]
+    Manual,
+    /// Device failed a posture check.
+    Posture,
+    /// Anomalous traffic has been detected for the device.
+    Anomaly,
+}
+
+/// Marks a device as quarantined.
+///
+/// A quarantined device keeps its peers on all gateways, but its firewall policy is replaced
+/// with a restricted rule set allowing traffic only to [`QuarantineRemediationHost`]s
+/// configured for a given location.
+#[derive(Clone, Debug, Deserialize, Model, Serialize, ToSchema, PartialEq)]
+#[table(device_quarantine)]
+pub struct DeviceQuarantine<I = NoId> {
+    pub id: I,
+    pub device_id: Id,
+    #[model(enum)]
+    pub source: QuarantineSource,
+    pub reason: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl DeviceQuarantine {
+    #[must_use]
+    pub fn new(device_id: Id, source: QuarantineSource, reason: Option<String>) -> Self {
+        Self {
+            id: NoId,
+            device_id,
+            source,
+            reason,
+            created_at: chrono::Utc::now().naive_utc(),
+        }
+    }
+}
+
+impl DeviceQuarantine<Id> {
+    pub async fn find_by_device_id<'e, E>(
+        executor: E,
+        device_id: Id,
+    ) -> Result<Option<Self>, sqlx::Error>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, device_id, source \"source: QuarantineSource\", reason, created_at \
+            FROM device_quarantine WHERE device_id = $1",
+            device_id
+        )
+        .fetch_optional(executor)
+        .await
+    }
+
+    /// Fetches WireGuard IPs of all quarantined devices in a given location.
+    pub(crate) async fn device_ips_for_location<'e, E>(
+        executor: E,
+        location_id: Id,
+    ) -> Result<Vec<IpAddr>, sqlx::Error>
+    where
+        E: PgExecutor<'e>,
+    {
+        let ips = query_scalar!(
+            "SELECT wireguard_ips \"wireguard_ips: Vec<IpAddr>\" \
+            FROM wireguard_network_device wnd \
+            JOIN device_quarantine dq ON dq.device_id = wnd.device_id \
+            WHERE wnd.wireguard_network_id = $1",
+            location_id
+        )
+        .fetch_all(executor)
+        .await?;
+
+        Ok(ips.into_iter().flatten().collect())
+    }
+}
+
+/// Host or subnet which quarantined devices can still reach in a given location.
+#[derive(Clone, Debug, Deserialize, Model, Serialize, ToSchema, PartialEq)]
+#[table(quarantine_remediation_host)]
+pub struct QuarantineRemediationHost<I = NoId> {
+    pub id: I,
+    pub location_id: Id,
+    #[schema(value_type = String)]
+    pub address: IpNetwork,
+    pub description: Option<String>,
+}
+
+impl QuarantineRemediationHost {
+    #[must_use]
+    pub fn new(location_id: Id, address: IpNetwork, description: Option<String>) -> Self {
+        Self {
+            id: NoId,
+            location_id,
+            address,
+            description,
+        }
+    }
+}
+
+impl QuarantineRemediationHost<Id> {
+    pub async fn all_for_location<'e, E>(
+        executor: E,
+        location_id: Id,
+    ) -> Result<Vec<Self>, sqlx::Error>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT id, location_id, address, description \
+            FROM quarantine_remediation_host WHERE location_id = $1 ORDER BY id",
+            location_id
+        )
+        .fetch_all(executor)
+        .await
+    }
+}
@@ -19,11 +19,18 @@ use super::{
 use crate::{
     db::{Device, User, WireguardNetwork},
     enterprise::{
-        db::models::{acl::AliasKind, snat::UserSnatBinding},
+        db::models::{
+            acl::AliasKind,
+            quarantine::{DeviceQuarantine, QuarantineRemediationHost},
+            snat::UserSnatBinding,
+        },
         is_business_license_active,
     },
 };
 
+/// Identifier used for firewall rules which are not generated from ACLs, but from device quarantine.
+const QUARANTINE_RULE_ID: Id = 0;
+
 #[derive(Debug, thiserror::Error)]
 pub enum FirewallError {
     #[error("Database error")]
@@ -274,6 +281,73 @@ pub async fn generate_firewall_rules_from_acls(
     Ok(allow_rules.into_iter().chain(deny_rules).collect())
 }
 
+/// Generates rules restricting traffic of quarantined devices in a given location.
+///
+/// For each IP version an ALLOW rule lets quarantined devices reach remediation hosts
+/// and a DENY rule blocks all of their remaining traffic. These rules have to be placed
+/// before any rules generated from ACLs so that quarantine takes precedence.
+async fn generate_quarantine_rules(
+    location: &WireguardNetwork<Id>,
+    conn: &mut PgConnection,
+) -> Result<Vec<FirewallRule>, SqlxError> {
+    let quarantined_ips =
+        DeviceQuarantine::device_ips_for_location(&mut *conn, location.id).await?;
+    if quarantined_ips.is_empty() {
+        return Ok(Vec::new());
+    }
+    debug!(
+        "Generating quarantine firewall rules for {} device IPs in location {location}",
+        quarantined_ips.len()
+    );
+
+    let remediation_hosts: Vec<IpNetwork> =
+        QuarantineRemediationHost::all_for_location(&mut *conn, location.id)
+            .await?
+            .into_iter()
+            .map(|host| host.address)
+            .collect();
+    let (remediation_addrs_v4, remediation_addrs_v6) =
+        process_destination_addrs(&remediation_hosts, Vec::new());
+    let (quarantined_ips_v4, quarantined_ips_v6) =
+        quarantined_ips.into_iter().partition(IpAddr::is_ipv4);
+
+    let mut rules = Vec::new();
+    for (ip_version, source_ips, remediation_addrs) in [
+        (IpVersion::Ipv4, quarantined_ips_v4, remediation_addrs_v4),
+        (IpVersion::Ipv6, quarantined_ips_v6, remediation_addrs_v6),
+    ] {
+        let source_addrs = get_source_addrs(source_ips, Vec::new(), ip_version);
+        if source_addrs.is_empty() {
+            continue;
+        }
+        let ip_version = i32::from(ip_version);
+        if !remediation_addrs.is_empty() {
+            rules.push(FirewallRule {
+                id: QUARANTINE_RULE_ID,
+                source_addrs: source_addrs.clone(),
+                destination_addrs: remediation_addrs,
+                destination_ports: Vec::new(),
+                protocols: Vec::new(),
+                verdict: i32::from(FirewallPolicy::Allow),
+                comment: Some("Quarantine - remediation hosts ALLOW".into()),
+                ip_version,
+            });
+        }
+        rules.push(FirewallRule {
+            id: QUARANTINE_RULE_ID,
+            source_addrs,
+            destination_addrs: Vec::new(),
+            destination_ports: Vec::new(),
+            protocols: Vec::new(),
+            verdict: i32::from(FirewallPolicy::Deny),
+            comment: Some("Quarantine DENY".into()),
+            ip_version,
+        });
+    }
+
+    Ok(rules)
+}
+
 /// Creates ALLOW and DENY rules for given set of source, destination
 /// addresses, ports and protocols. The DENY rule should block all
 /// remaining traffic to the destination from sources other than specified.
@@ -885,12 +959,27 @@ impl WireguardNetwork<Id> {
             return Ok(None);
         }
 
+        // quarantine is enforced regardless of ACL configuration
+        let quarantine_rules = generate_quarantine_rules(self, &mut *conn).await?;
+
         // check if ACLs are enabled
         if !self.acl_enabled {
-            debug!(
-                "ACL rules are disabled for location {self}, skipping generating firewall config"
+            if quarantine_rules.is_empty() {
+                debug!(
+                    "ACL rules are disabled for location {self}, skipping generating firewall \
+                    config"
+                );
+                return Ok(None);
+            }
+            info!(
+                "ACL rules are disabled for location {self}, generating firewall config with \
+                quarantine rules only"
             );
-            return Ok(None);
+            return Ok(Some(FirewallConfig {
+                default_policy: FirewallPolicy::Allow.into(),
+                rules: quarantine_rules,
+                snat_bindings: Vec::new(),
+            }));
         }
 
         info!("Generating firewall config for location {self}");
@@ -902,12 +991,12 @@ impl WireguardNetwork<Id> {
         } else {
             FirewallPolicy::Deny
         };
-        let firewall_rules =
+        let acl_rules =
             generate_firewall_rules_from_acls(self.id, location_acls, &mut *conn).await?;
         let snat_bindings = generate_user_snat_bindings_for_location(self.id, &mut *conn).await?;
         let firewall_config = FirewallConfig {
             default_policy: default_policy.into(),
-            rules: firewall_rules,
+            rules: quarantine_rules.into_iter().chain(acl_rules).collect(),
             snat_bindings,
         };
 
@@ -24,10 +24,13 @@ use crate::{
         models::device::{DeviceType, WireguardNetworkDevice},
     },
     enterprise::{
-        db::models::acl::{
-            AclAlias, AclAliasDestinationRange, AclRule, AclRuleAlias, AclRuleDestinationRange,
-            AclRuleDevice, AclRuleGroup, AclRuleInfo, AclRuleNetwork, AclRuleUser, AliasKind,
-            PortRange, RuleState,
+        db::models::{
+            acl::{
+                AclAlias, AclAliasDestinationRange, AclRule, AclRuleAlias, AclRuleDestinationRange,
+                AclRuleDevice, AclRuleGroup, AclRuleInfo, AclRuleNetwork, AclRuleUser, AliasKind,
+                PortRange, RuleState,
+            },
+            quarantine::{DeviceQuarantine, QuarantineRemediationHost, QuarantineSource},
         },
         firewall::{get_source_addrs, get_source_network_devices},
     },
@@ -5332,3 +5335,77 @@ async fn test_empty_manual_destination_only_acl(_: PgPoolOptions, options: PgCon
     assert!(deny_rule_ipv6.source_addrs.is_empty());
     assert!(deny_rule_ipv6.destination_addrs.is_empty());
 }
+
+#[sqlx::test]
+async fn test_quarantine_rules(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let mut location = create_location_with_addresses(
+        &pool,
+        vec![IpNetwork::new(IpAddr::V4(Ipv4Addr::new(10, 0, 80, 1)), 24).unwrap()],
+        Some(false),
+    )
+    .await;
+    let (_, quarantined_device) = create_user_device_assigned(&pool, &location).await;
+    let (_, _other_device) = create_user_device_assigned(&pool, &location).await;
+    let quarantined_ip = WireguardNetworkDevice::find(&pool, quarantined_device.id, location.id)
+        .await
+        .unwrap()
+        .unwrap()
+        .wireguard_ips[0];
+
+    let quarantine = DeviceQuarantine::new(quarantined_device.id, QuarantineSource::Manual, None)
+        .save(&pool)
+        .await
+        .unwrap();
+    QuarantineRemediationHost::new(location.id, "10.1.1.0/24".parse().unwrap(), None)
+        .save(&pool)
+        .await
+        .unwrap();
+
+    // quarantine rules are placed before ACL rules
+    let mut conn = pool.acquire().await.unwrap();
+    let config = location
+        .try_get_firewall_config(&mut conn)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(config.default_policy, i32::from(FirewallPolicy::Deny));
+    assert_eq!(config.rules.len(), 2);
+    let expected_source = vec![IpAddress {
+        address: Some(Address::Ip(quarantined_ip.to_string())),
+    }];
+    let allow_rule = &config.rules[0];
+    assert_eq!(allow_rule.verdict, i32::from(FirewallPolicy::Allow));
+    assert_eq!(allow_rule.source_addrs, expected_source);
+    assert_eq!(
+        allow_rule.destination_addrs,
+        vec![IpAddress {
+            address: Some(Address::IpSubnet("10.1.1.0/24".to_string())),
+        }]
+    );
+    let deny_rule = &config.rules[1];
+    assert_eq!(deny_rule.verdict, i32::from(FirewallPolicy::Deny));
+    assert_eq!(deny_rule.source_addrs, expected_source);
+    assert!(deny_rule.destination_addrs.is_empty());
+
+    // quarantine is enforced even with ACLs disabled
+    location.acl_enabled = false;
+    location.save(&pool).await.unwrap();
+    let config = location
+        .try_get_firewall_config(&mut conn)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(config.default_policy, i32::from(FirewallPolicy::Allow));
+    assert_eq!(config.rules.len(), 2);
+
+    // releasing the device disables the firewall again
+    quarantine.delete(&pool).await.unwrap();
+    assert!(
+        location
+            .try_get_firewall_config(&mut conn)
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
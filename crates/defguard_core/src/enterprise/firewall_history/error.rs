@@ -0,0 +1,26 @@
+use prost::DecodeError;
+use thiserror::Error;
+
+use crate::error::WebError;
+
+#[derive(Debug, Error)]
+pub enum FirewallHistoryError {
+    #[error("Firewall configuration version {0} not found")]
+    VersionNotFound(i64),
+    #[error("Stored firewall configuration is invalid")]
+    InvalidConfig(#[from] DecodeError),
+    #[error("Database error")]
+    DbError(#[from] sqlx::Error),
+}
+
+impl From<FirewallHistoryError> for WebError {
+    fn from(value: FirewallHistoryError) -> Self {
+        match value {
+            FirewallHistoryError::VersionNotFound(_) => WebError::ObjectNotFound(value.to_string()),
+            FirewallHistoryError::InvalidConfig(err) => WebError::Serialization(format!(
+                "Failed to decode stored firewall configuration: {err}"
+            )),
+            FirewallHistoryError::DbError(err) => WebError::DbError(err.to_string()),
+        }
+    }
+}
@@ -0,0 +1,257 @@
+use axum::extract::{Path, Query, State};
+use chrono::NaiveDateTime;
+use defguard_common::db::Id;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa::ToSchema;
+
+use super::{FirewallConfigDiff, FirewallConfigVersionInfo, error::FirewallHistoryError, rollback};
+use crate::{
+    appstate::AppState,
+    auth::{AdminRole, SessionInfo},
+    db::WireguardNetwork,
+    enterprise::{
+        db::models::firewall_config_version::FirewallConfigVersion, handlers::LicenseInfo,
+    },
+    error::WebError,
+    events::{ApiEvent, ApiEventType, ApiRequestContext},
+    handlers::{ApiResponse, ApiResult},
+};
+
+/// Summary of a firewall configuration version.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FirewallConfigVersionSummary {
+    pub version: i64,
+    /// Version the configuration was restored from.
+    pub rollback_of: Option<i64>,
+    pub created_at: NaiveDateTime,
+    pub default_policy: String,
+    pub rules: usize,
+    pub snat_bindings: usize,
+}
+
+/// Firewall configuration version with changes against another version.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FirewallConfigVersionDetails {
+    #[serde(flatten)]
+    pub info: FirewallConfigVersionInfo,
+    /// Version the changes are relative to, `None` for the oldest kept version.
+    pub compared_to: Option<i64>,
+    pub diff: Option<FirewallConfigDiff>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompareQuery {
+    compare: Option<i64>,
+}
+
+async fn find_location(
+    appstate: &AppState,
+    location_id: Id,
+) -> Result<WireguardNetwork<Id>, WebError> {
+    WireguardNetwork::find_by_id(&appstate.pool, location_id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Location {location_id} not found")))
+}
+
+/// List firewall configuration versions of a location
+///
+/// Each firewall configuration sent to location gateways is stored as a new version.
+///
+/// # Returns
+/// - `Vec<FirewallConfigVersionSummary>` object, newest first
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/network/{location_id}/firewall/version",
+    tag = "Firewall",
+    params(
+        ("location_id" = Id, Path, description = "WireGuard location ID")
+    ),
+    responses(
+        (status = 200, description = "List of firewall configuration versions", body = Vec<FirewallConfigVersionSummary>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - location does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn list_firewall_versions(
+    _license: LicenseInfo,
+    _admin_role: AdminRole,
+    session: SessionInfo,
+    Path(location_id): Path<Id>,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let location = find_location(&appstate, location_id).await?;
+    debug!(
+        "User {} listing firewall configuration versions of location {location}",
+        session.user.username
+    );
+
+    let versions = FirewallConfigVersion::all_for_location(&appstate.pool, location.id).await?;
+    let mut summaries = Vec::with_capacity(versions.len());
+    for version in versions {
+        let config = version.decode().map_err(FirewallHistoryError::from)?;
+        summaries.push(FirewallConfigVersionSummary {
+            version: version.version,
+            rollback_of: version.rollback_of,
+            created_at: version.created_at,
+            default_policy: config.default_policy().as_str_name().to_lowercase(),
+            rules: config.rules.len(),
+            snat_bindings: config.snat_bindings.len(),
+        });
+    }
+
+    Ok(ApiResponse {
+        json: json!(summaries),
+        status: StatusCode::OK,
+    })
+}
+
+/// Get a firewall configuration version of a location
+///
+/// Returns rules of the version and changes against the preceding version
+/// or the version given in `compare` parameter.
+///
+/// # Returns
+/// - `FirewallConfigVersionDetails` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/network/{location_id}/firewall/version/{version}",
+    tag = "Firewall",
+    params(
+        ("location_id" = Id, Path, description = "WireGuard location ID"),
+        ("version" = i64, Path, description = "Firewall configuration version"),
+        ("compare" = Option<i64>, Query, description = "Version to compare with, defaults to the preceding one")
+    ),
+    responses(
+        (status = 200, description = "Firewall configuration version", body = FirewallConfigVersionDetails),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - location or version does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn get_firewall_version(
+    _license: LicenseInfo,
+    _admin_role: AdminRole,
+    session: SessionInfo,
+    Path((location_id, version)): Path<(Id, i64)>,
+    Query(query): Query<CompareQuery>,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let location = find_location(&appstate, location_id).await?;
+    debug!(
+        "User {} displaying firewall configuration version {version} of location {location}",
+        session.user.username
+    );
+
+    let current = FirewallConfigVersion::find_by_version(&appstate.pool, location.id, version)
+        .await?
+        .ok_or(FirewallHistoryError::VersionNotFound(version))?;
+    let previous = match query.compare {
+        Some(compare) => Some(
+            FirewallConfigVersion::find_by_version(&appstate.pool, location.id, compare)
+                .await?
+                .ok_or(FirewallHistoryError::VersionNotFound(compare))?,
+        ),
+        None => FirewallConfigVersion::find_previous(&appstate.pool, location.id, version).await?,
+    };
+
+    let config = current.decode().map_err(FirewallHistoryError::from)?;
+    let diff = match &previous {
+        Some(previous) => Some(FirewallConfigDiff::new(
+            &previous.decode().map_err(FirewallHistoryError::from)?,
+            &config,
+        )),
+        None => None,
+    };
+    let details = FirewallConfigVersionDetails {
+        info: FirewallConfigVersionInfo::new(&current, &config),
+        compared_to: previous.map(|previous| previous.version),
+        diff,
+    };
+
+    Ok(ApiResponse {
+        json: json!(details),
+        status: StatusCode::OK,
+    })
+}
+
+/// Roll back firewall configuration of a location
+///
+/// Sends a previous firewall configuration version to location gateways and stores it as the
+/// latest version. The configuration is replaced once firewall configuration of the location
+/// is generated again, e.g. after applying ACL changes.
+///
+/// # Returns
+/// - `FirewallConfigVersionInfo` object of the latest version
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    post,
+    path = "/api/v1/network/{location_id}/firewall/version/{version}/rollback",
+    tag = "Firewall",
+    params(
+        ("location_id" = Id, Path, description = "WireGuard location ID"),
+        ("version" = i64, Path, description = "Firewall configuration version to restore")
+    ),
+    responses(
+        (status = 200, description = "Firewall configuration rolled back", body = FirewallConfigVersionInfo),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - location or version does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn rollback_firewall_version(
+    _license: LicenseInfo,
+    _admin_role: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    Path((location_id, version)): Path<(Id, i64)>,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let location = find_location(&appstate, location_id).await?;
+    debug!(
+        "User {} rolling back firewall configuration of location {location} to version {version}",
+        session.user.username
+    );
+
+    let mut transaction = appstate.pool.begin().await?;
+    let (latest, event) = rollback(&mut transaction, location.id, version).await?;
+    transaction.commit().await?;
+    let config = latest.decode().map_err(FirewallHistoryError::from)?;
+    appstate.send_wireguard_event(event);
+    info!(
+        "User {} rolled back firewall configuration of location {location} to version {version}",
+        session.user.username
+    );
+
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::VpnLocationFirewallRolledBack { location, version }),
+    })?;
+
+    Ok(ApiResponse {
+        json: json!(FirewallConfigVersionInfo::new(&latest, &config)),
+        status: StatusCode::OK,
+    })
+}
@@ -0,0 +1,301 @@
+//! Firewall configuration history.
+//!
+//! Every firewall configuration sent to gateways of a location is stored as a new version by the
+//! gateway configuration journal. Administrators can review differences between versions and roll
+//! back to a previous one, e.g. when an ACL change locked them out. A rolled back configuration
+//! stays on gateways until firewall configuration of the location is generated again, e.g. after
+//! applying ACL changes.
+
+pub mod error;
+pub mod handlers;
+
+use chrono::NaiveDateTime;
+use defguard_common::db::Id;
+use defguard_proto::enterprise::firewall::{
+    FirewallConfig, FirewallRule, IpAddress, IpVersion, Port, SnatBinding, ip_address::Address,
+    port::Port as PortInner,
+};
+use serde::Serialize;
+use sqlx::PgConnection;
+use utoipa::ToSchema;
+
+use self::error::FirewallHistoryError;
+use crate::{
+    db::GatewayEvent, enterprise::db::models::firewall_config_version::FirewallConfigVersion,
+};
+
+/// Firewall configuration version in a readable form.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FirewallConfigVersionInfo {
+    pub version: i64,
+    /// Version the configuration was restored from.
+    pub rollback_of: Option<i64>,
+    pub created_at: NaiveDateTime,
+    pub default_policy: String,
+    pub rules: Vec<String>,
+    pub snat_bindings: Vec<String>,
+}
+
+impl FirewallConfigVersionInfo {
+    pub(crate) fn new(version: &FirewallConfigVersion, config: &FirewallConfig) -> Self {
+        Self {
+            version: version.version,
+            rollback_of: version.rollback_of,
+            created_at: version.created_at,
+            default_policy: config.default_policy().as_str_name().to_lowercase(),
+            rules: config.rules.iter().map(describe_rule).collect(),
+            snat_bindings: config
+                .snat_bindings
+                .iter()
+                .map(describe_snat_binding)
+                .collect(),
+        }
+    }
+}
+
+/// Changes between two firewall configuration versions.
+#[derive(Debug, Default, PartialEq, Serialize, ToSchema)]
+pub struct FirewallConfigDiff {
+    /// Previous default policy, if it has changed.
+    pub previous_default_policy: Option<String>,
+    pub added_rules: Vec<String>,
+    pub removed_rules: Vec<String>,
+    pub added_snat_bindings: Vec<String>,
+    pub removed_snat_bindings: Vec<String>,
+}
+
+impl FirewallConfigDiff {
+    #[must_use]
+    pub fn new(previous: &FirewallConfig, current: &FirewallConfig) -> Self {
+        let previous_default_policy = (previous.default_policy != current.default_policy)
+            .then(|| previous.default_policy().as_str_name().to_lowercase());
+        let (added_rules, removed_rules) = diff(
+            previous.rules.iter().map(describe_rule),
+            current.rules.iter().map(describe_rule),
+        );
+        let (added_snat_bindings, removed_snat_bindings) = diff(
+            previous.snat_bindings.iter().map(describe_snat_binding),
+            current.snat_bindings.iter().map(describe_snat_binding),
+        );
+        Self {
+            previous_default_policy,
+            added_rules,
+            removed_rules,
+            added_snat_bindings,
+            removed_snat_bindings,
+        }
+    }
+}
+
+/// Returns entries added to and removed from `previous`. Duplicate entries are counted
+/// separately, since rule order and multiplicity are preserved on gateways.
+fn diff(
+    previous: impl Iterator<Item = String>,
+    current: impl Iterator<Item = String>,
+) -> (Vec<String>, Vec<String>) {
+    let mut removed: Vec<String> = previous.collect();
+    let mut added = Vec::new();
+    for entry in current {
+        match removed.iter().position(|previous| *previous == entry) {
+            Some(index) => {
+                removed.remove(index);
+            }
+            None => added.push(entry),
+        }
+    }
+    (added, removed)
+}
+
+fn describe_addrs(addrs: &[IpAddress]) -> String {
+    if addrs.is_empty() {
+        return "any".into();
+    }
+    addrs
+        .iter()
+        .filter_map(|addr| match addr.address.as_ref()? {
+            Address::Ip(ip) | Address::IpSubnet(ip) => Some(ip.clone()),
+            Address::IpRange(range) => Some(format!("{}-{}", range.start, range.end)),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn describe_ports(ports: &[Port]) -> String {
+    if ports.is_empty() {
+        return "any".into();
+    }
+    ports
+        .iter()
+        .filter_map(|port| match port.port.as_ref()? {
+            PortInner::SinglePort(port) => Some(port.to_string()),
+            PortInner::PortRange(range) => Some(format!("{}-{}", range.start, range.end)),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Describes a firewall rule, e.g. `allow tcp ipv4 from 10.0.0.2 to 192.168.1.0/24 port 22
+/// (ACL 1 - SSH)`. Rule IDs are omitted, since they don't affect traffic.
+#[must_use]
+pub fn describe_rule(rule: &FirewallRule) -> String {
+    let protocols = if rule.protocols.is_empty() {
+        "any".into()
+    } else {
+        rule.protocols()
+            .map(|protocol| protocol.as_str_name().to_lowercase())
+            .collect::<Vec<_>>()
+            .join("/")
+    };
+    let ip_version = match rule.ip_version() {
+        IpVersion::Ipv4 => "ipv4",
+        IpVersion::Ipv6 => "ipv6",
+    };
+    let mut description = format!(
+        "{} {protocols} {ip_version} from {} to {} port {}",
+        rule.verdict().as_str_name().to_lowercase(),
+        describe_addrs(&rule.source_addrs),
+        describe_addrs(&rule.destination_addrs),
+        describe_ports(&rule.destination_ports),
+    );
+    if let Some(comment) = &rule.comment {
+        description.push_str(&format!(" ({comment})"));
+    }
+    description
+}
+
+/// Describes a SNAT binding, e.g. `10.0.0.2 -> 203.0.113.1`.
+#[must_use]
+pub fn describe_snat_binding(binding: &SnatBinding) -> String {
+    let mut description = format!(
+        "{} -> {}",
+        describe_addrs(&binding.source_addrs),
+        binding.public_ip
+    );
+    if let Some(comment) = &binding.comment {
+        description.push_str(&format!(" ({comment})"));
+    }
+    description
+}
+
+/// Records a firewall configuration restored from `version` as the latest version of a location
+/// and returns the latest version along with the gateway event pushing it.
+pub(crate) async fn rollback(
+    conn: &mut PgConnection,
+    location_id: Id,
+    version: i64,
+) -> Result<(FirewallConfigVersion, GatewayEvent), FirewallHistoryError> {
+    let target = FirewallConfigVersion::find_by_version(&mut *conn, location_id, version)
+        .await?
+        .ok_or(FirewallHistoryError::VersionNotFound(version))?;
+    let config = target.decode()?;
+    // configuration identical to the latest version isn't recorded again
+    FirewallConfigVersion::record(&mut *conn, location_id, &config, Some(version)).await?;
+    let latest = FirewallConfigVersion::latest(&mut *conn, location_id)
+        .await?
+        .ok_or(FirewallHistoryError::VersionNotFound(version))?;
+    Ok((
+        latest,
+        GatewayEvent::FirewallConfigChanged(location_id, config),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use defguard_proto::enterprise::firewall::{FirewallPolicy, IpRange, PortRange, Protocol};
+
+    use super::*;
+
+    fn ip(ip: &str) -> IpAddress {
+        IpAddress {
+            address: Some(Address::Ip(ip.into())),
+        }
+    }
+
+    fn rule(destination: &str, verdict: FirewallPolicy) -> FirewallRule {
+        FirewallRule {
+            id: 1,
+            source_addrs: vec![ip("10.0.0.2")],
+            destination_addrs: vec![IpAddress {
+                address: Some(Address::IpSubnet(destination.into())),
+            }],
+            destination_ports: vec![
+                Port {
+                    port: Some(PortInner::SinglePort(22)),
+                },
+                Port {
+                    port: Some(PortInner::PortRange(PortRange {
+                        start: 8000,
+                        end: 8080,
+                    })),
+                },
+            ],
+            protocols: vec![Protocol::Tcp.into(), Protocol::Udp.into()],
+            verdict: verdict.into(),
+            comment: Some("ACL 1 - SSH".into()),
+            ip_version: IpVersion::Ipv4.into(),
+        }
+    }
+
+    #[test]
+    fn test_describe_rule() {
+        assert_eq!(
+            describe_rule(&rule("192.168.1.0/24", FirewallPolicy::Allow)),
+            "allow tcp/udp ipv4 from 10.0.0.2 to 192.168.1.0/24 port 22, 8000-8080 (ACL 1 - SSH)"
+        );
+        let any = FirewallRule {
+            source_addrs: vec![IpAddress {
+                address: Some(Address::IpRange(IpRange {
+                    start: "fc00::2".into(),
+                    end: "fc00::5".into(),
+                })),
+            }],
+            verdict: FirewallPolicy::Deny.into(),
+            ip_version: IpVersion::Ipv6.into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            describe_rule(&any),
+            "deny any ipv6 from fc00::2-fc00::5 to any port any"
+        );
+    }
+
+    #[test]
+    fn test_config_diff() {
+        let previous = FirewallConfig {
+            default_policy: FirewallPolicy::Allow.into(),
+            rules: vec![
+                rule("192.168.1.0/24", FirewallPolicy::Allow),
+                rule("192.168.1.0/24", FirewallPolicy::Deny),
+            ],
+            snat_bindings: vec![SnatBinding {
+                id: 1,
+                source_addrs: vec![ip("10.0.0.2")],
+                public_ip: "203.0.113.1".into(),
+                comment: None,
+            }],
+        };
+        let current = FirewallConfig {
+            default_policy: FirewallPolicy::Deny.into(),
+            rules: vec![
+                rule("192.168.2.0/24", FirewallPolicy::Allow),
+                rule("192.168.1.0/24", FirewallPolicy::Deny),
+            ],
+            snat_bindings: previous.snat_bindings.clone(),
+        };
+
+        assert_eq!(
+            FirewallConfigDiff::new(&previous, &current),
+            FirewallConfigDiff {
+                previous_default_policy: Some("allow".into()),
+                added_rules: vec![describe_rule(&current.rules[0])],
+                removed_rules: vec![describe_rule(&previous.rules[0])],
+                added_snat_bindings: Vec::new(),
+                removed_snat_bindings: Vec::new(),
+            }
+        );
+        assert_eq!(
+            FirewallConfigDiff::new(&current, &current),
+            FirewallConfigDiff::default()
+        );
+    }
+}
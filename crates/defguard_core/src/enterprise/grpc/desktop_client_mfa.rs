@@ -53,6 +53,7 @@ impl ClientMfaServer {
             user,
             openid_auth_completed,
             biometric_challenge: _,
+            remembered,
         } = session;
 
         if openid_auth_completed {
@@ -148,6 +149,7 @@ impl ClientMfaServer {
                 user: user.clone(),
                 openid_auth_completed: true,
                 biometric_challenge: None,
+                remembered,
             },
         );
 
@@ -6,7 +6,7 @@ use tonic::Status;
 use crate::{
     db::{Device, User, models::polling_token::PollingToken},
     enterprise::is_business_license_active,
-    grpc::utils::build_device_config_response,
+    grpc::{client_version::check_min_client_version, utils::build_device_config_response},
 };
 
 pub struct PollingServer {
@@ -85,6 +85,16 @@ impl PollingServer {
             return Err(Status::permission_denied("user inactive"));
         }
 
+        // Reject clients older than required by any of the device locations
+        let locations = device.find_networks(&self.pool).await.map_err(|err| {
+            error!(
+                "Failed to retrieve locations for device id {}: {err}",
+                device.id
+            );
+            Status::internal("failed to retrieve locations")
+        })?;
+        check_min_client_version(&locations, device_info.as_ref())?;
+
         // Build and return polling info.
         let device_config =
             build_device_config_response(&self.pool, device, None, device_info).await?;
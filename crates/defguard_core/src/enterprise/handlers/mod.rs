@@ -47,10 +47,10 @@ where
     }
 }
 
-/// Gets full information about enterprise status.
-pub async fn check_enterprise_info(_admin: AdminRole, _session: SessionInfo) -> ApiResult {
+/// Summary of the current license, `None` if there is no license.
+pub(crate) fn license_info() -> Option<serde_json::Value> {
     let license = get_cached_license();
-    let license_info = license.as_ref().map(|license| {
+    license.as_ref().map(|license| {
         let counts = get_counts();
         serde_json::json!(
             {
@@ -61,11 +61,15 @@ pub async fn check_enterprise_info(_admin: AdminRole, _session: SessionInfo) ->
                 "tier": license.tier
             }
         )
-    });
+    })
+}
+
+/// Gets full information about enterprise status.
+pub async fn check_enterprise_info(_admin: AdminRole, _session: SessionInfo) -> ApiResult {
     Ok(ApiResponse {
         json: serde_json::json!(
             {
-                "license_info": license_info,
+                "license_info": license_info(),
             }
         ),
         status: StatusCode::OK,
@@ -8,7 +8,7 @@ use defguard_common::db::models::{
     settings::{OpenidUsernameHandling, update_current_settings},
 };
 use rsa::{RsaPrivateKey, pkcs8::DecodePrivateKey};
-use serde_json::json;
+use serde_json::{Value, json};
 
 use super::LicenseInfo;
 use crate::{
@@ -16,8 +16,10 @@ use crate::{
     auth::{AdminRole, SessionInfo},
     db::{WireguardNetwork, models::wireguard::LocationMfaMode},
     enterprise::{
-        db::models::openid_provider::OpenIdProvider, directory_sync::test_directory_sync_connection,
+        db::models::openid_provider::OpenIdProvider,
+        directory_sync::{do_directory_sync, test_directory_sync_connection},
     },
+    error::WebError,
     events::{ApiEvent, ApiEventType, ApiRequestContext},
     handlers::{ApiResponse, ApiResult},
 };
@@ -348,3 +350,39 @@ pub async fn test_dirsync_connection(
         status: StatusCode::OK,
     })
 }
+
+/// Start directory sync in background. Returns a background job to poll.
+pub async fn run_directory_sync(
+    _license: LicenseInfo,
+    _admin: AdminRole,
+    session: SessionInfo,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let provider = OpenIdProvider::get_current(&appstate.pool).await?;
+    if !provider.is_some_and(|provider| provider.directory_sync_enabled) {
+        return Err(WebError::BadRequest("Directory sync is not enabled".into()));
+    }
+
+    let pool = appstate.pool.clone();
+    let wireguard_tx = appstate.wireguard_tx.clone();
+    let job = appstate
+        .jobs
+        .spawn(
+            "directory_sync",
+            Some(session.user.id),
+            move |_| async move {
+                do_directory_sync(&pool, &wireguard_tx).await?;
+                Ok(Value::Null)
+            },
+        )
+        .await?;
+    info!(
+        "User {} started directory sync as background job {}",
+        session.user.username, job.id
+    );
+
+    Ok(ApiResponse {
+        json: json!(job),
+        status: StatusCode::ACCEPTED,
+    })
+}
@@ -24,12 +24,14 @@ pub struct Counts {
 global_value!(COUNTS, Counts, Counts::default(), set_counts, get_counts);
 
 /// Update the counts of users, devices, and wireguard networks stored in the memory.
+/// Service accounts are not counted as users.
 // TODO: Use it with database triggers when they are implemented
 pub async fn update_counts<'e, E: sqlx::PgExecutor<'e>>(executor: E) -> Result<(), SqlxError> {
     debug!("Updating device, user, and wireguard network counts.");
     let result = query!(
         "SELECT \
-        (SELECT count(*) FROM \"user\") \"users!\", \
+        (SELECT count(*) FROM \"user\" \
+        WHERE id NOT IN (SELECT user_id FROM service_account)) \"users!\", \
         (SELECT count(*) FROM device WHERE device_type = 'user') \"user_devices!\", \
         (SELECT count(*) FROM device WHERE device_type = 'network') \"network_devices!\",
         (SELECT count(*) FROM wireguard_network) \"wireguard_networks!\"
@@ -179,6 +181,65 @@ impl Counts {
             }
         }
     }
+
+    /// Current object counts along with corresponding limits. Unlimited licenses have no limits.
+    pub(crate) fn limit_usage(&self, license: Option<&License>) -> Vec<LimitUsage> {
+        let usage = |name, current, limit| LimitUsage {
+            name,
+            current,
+            limit,
+        };
+        let Some(license) = license else {
+            return vec![
+                usage("Users", self.user, DEFAULT_USERS_LIMIT),
+                usage("User devices", self.user_device, DEFAULT_DEVICES_LIMIT),
+                usage("Locations", self.location, DEFAULT_LOCATIONS_LIMIT),
+                usage(
+                    "Network devices",
+                    self.network_device,
+                    DEFAULT_NETWORK_DEVICES_LIMIT,
+                ),
+            ];
+        };
+        let Some(limits) = &license.limits else {
+            return Vec::new();
+        };
+        let mut result = vec![
+            usage("Users", self.user, limits.users),
+            usage("Locations", self.location, limits.locations),
+        ];
+        match limits.network_devices {
+            Some(network_devices) => {
+                result.push(usage("User devices", self.user_device, limits.devices));
+                result.push(usage(
+                    "Network devices",
+                    self.network_device,
+                    network_devices,
+                ));
+            }
+            None => result.push(usage(
+                "Devices",
+                self.user_device + self.network_device,
+                limits.devices,
+            )),
+        }
+        result
+    }
+}
+
+// Usage of a single license limit.
+#[derive(Debug, PartialEq)]
+pub(crate) struct LimitUsage {
+    pub name: &'static str,
+    pub current: u32,
+    pub limit: u32,
+}
+
+impl LimitUsage {
+    /// Returns true if usage reached a given percentage of the limit.
+    pub(crate) fn reached(&self, percent: u32) -> bool {
+        u64::from(self.current) * 100 >= u64::from(self.limit) * u64::from(percent)
+    }
 }
 
 // Granular exceeded limits info for the AppInfo endpoint.
@@ -582,4 +643,56 @@ mod test {
         assert!(!exceeded.network_device);
         assert!(!exceeded.any());
     }
+
+    #[test]
+    fn test_limit_usage() {
+        let counts = Counts {
+            user: 4,
+            user_device: 15,
+            location: 1,
+            network_device: 6,
+        };
+
+        // free tier
+        let usage = counts.limit_usage(None);
+        assert_eq!(usage.len(), 4);
+        assert!(usage[0].reached(80));
+        assert!(!usage[0].reached(90));
+
+        // old license without network devices limit
+        let license = License::new(
+            "test".to_string(),
+            true,
+            None,
+            Some(LicenseLimits {
+                users: 10,
+                devices: 20,
+                locations: 5,
+                network_devices: None,
+            }),
+            None,
+            LicenseTier::Business,
+        );
+        let usage = counts.limit_usage(Some(&license));
+        assert_eq!(
+            usage[2],
+            LimitUsage {
+                name: "Devices",
+                current: 21,
+                limit: 20,
+            }
+        );
+        assert!(usage[2].reached(100));
+
+        // unlimited license
+        let license = License::new(
+            "test".to_string(),
+            true,
+            None,
+            None,
+            None,
+            LicenseTier::Business,
+        );
+        assert!(counts.limit_usage(Some(&license)).is_empty());
+    }
 }
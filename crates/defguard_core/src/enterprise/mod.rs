@@ -2,11 +2,13 @@ pub mod activity_log_stream;
 pub mod db;
 pub mod directory_sync;
 pub mod firewall;
+pub mod firewall_history;
 pub mod grpc;
 pub mod handlers;
 pub mod ldap;
 pub mod license;
 pub mod limits;
+pub mod quarantine;
 pub mod snat;
 mod utils;
 
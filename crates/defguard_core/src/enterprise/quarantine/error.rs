@@ -0,0 +1,47 @@
+use thiserror::Error;
+
+use crate::{enterprise::firewall::FirewallError, error::WebError};
+
+#[derive(Debug, Error)]
+pub enum QuarantineError {
+    #[error("Device is already quarantined")]
+    AlreadyQuarantined,
+    #[error("Device is not quarantined")]
+    NotQuarantined,
+    #[error("Remediation host not found")]
+    RemediationHostNotFound,
+    #[error("Remediation host already exists")]
+    RemediationHostAlreadyExists,
+    #[error("Database error")]
+    DbError { source: sqlx::Error },
+    #[error(transparent)]
+    FirewallError(#[from] FirewallError),
+}
+
+impl From<sqlx::Error> for QuarantineError {
+    fn from(value: sqlx::Error) -> Self {
+        match value {
+            sqlx::Error::Database(ref err) => match err.constraint() {
+                Some("device_quarantine_device_id_key") => Self::AlreadyQuarantined,
+                Some("location_address") => Self::RemediationHostAlreadyExists,
+                _ => Self::DbError { source: value },
+            },
+            _ => Self::DbError { source: value },
+        }
+    }
+}
+
+impl From<QuarantineError> for WebError {
+    fn from(value: QuarantineError) -> Self {
+        match value {
+            QuarantineError::AlreadyQuarantined | QuarantineError::RemediationHostAlreadyExists => {
+                WebError::ObjectAlreadyExists(value.to_string())
+            }
+            QuarantineError::NotQuarantined | QuarantineError::RemediationHostNotFound => {
+                WebError::ObjectNotFound(value.to_string())
+            }
+            QuarantineError::DbError { source } => WebError::DbError(source.to_string()),
+            QuarantineError::FirewallError(err) => WebError::FirewallError(err),
+        }
+    }
+}
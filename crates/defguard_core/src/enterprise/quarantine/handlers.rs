@@ -0,0 +1,358 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use defguard_common::db::Id;
+use ipnetwork::IpNetwork;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa::ToSchema;
+
+use super::{error::QuarantineError, firewall_update_event, quarantine_device, release_device};
+use crate::{
+    appstate::AppState,
+    auth::{AdminRole, SessionInfo},
+    db::{Device, WireguardNetwork},
+    enterprise::{
+        db::models::quarantine::{DeviceQuarantine, QuarantineRemediationHost, QuarantineSource},
+        handlers::LicenseInfo,
+    },
+    error::WebError,
+    events::{ApiEvent, ApiEventType, ApiRequestContext},
+    handlers::{ApiResponse, ApiResult},
+};
+
+/// List all quarantined devices
+///
+/// # Returns
+/// - `Vec<DeviceQuarantine>` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/quarantine",
+    tag = "Quarantine",
+    responses(
+        (status = 200, description = "List of quarantined devices", body = Vec<DeviceQuarantine>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn list_quarantined_devices(
+    _license: LicenseInfo,
+    _admin_role: AdminRole,
+    session: SessionInfo,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    debug!("User {} listing quarantined devices", session.user.username);
+
+    let quarantines = DeviceQuarantine::all(&appstate.pool).await?;
+
+    Ok(ApiResponse {
+        json: json!(quarantines),
+        status: StatusCode::OK,
+    })
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct QuarantineDevice {
+    /// Reason for placing the device in quarantine
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Place a device in quarantine
+///
+/// Quarantined device keeps its configuration on gateways, but its traffic is restricted
+/// to remediation hosts configured for each location.
+///
+/// # Returns
+/// - `DeviceQuarantine` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    post,
+    path = "/api/v1/device/{device_id}/quarantine",
+    tag = "Quarantine",
+    params(
+        ("device_id" = Id, Path, description = "Device ID")
+    ),
+    request_body = QuarantineDevice,
+    responses(
+        (status = 201, description = "Device has been quarantined", body = DeviceQuarantine),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - device does not exist"),
+        (status = 409, description = "Conflict - device is already quarantined"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn create_device_quarantine(
+    _license: LicenseInfo,
+    _admin_role: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    Path(device_id): Path<Id>,
+    State(appstate): State<AppState>,
+    Json(data): Json<QuarantineDevice>,
+) -> ApiResult {
+    let device = Device::find_by_id(&appstate.pool, device_id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Device {device_id} not found")))?;
+    debug!(
+        "User {} placing device {device} in quarantine",
+        session.user.username
+    );
+
+    let quarantine = quarantine_device(
+        &appstate.pool,
+        &appstate.wireguard_tx,
+        &device,
+        QuarantineSource::Manual,
+        data.reason,
+    )
+    .await?;
+
+    let owner = device.get_owner(&appstate.pool).await?;
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::DeviceQuarantined {
+            owner,
+            device,
+            reason: quarantine.reason.clone(),
+        }),
+    })?;
+
+    Ok(ApiResponse {
+        json: json!(quarantine),
+        status: StatusCode::CREATED,
+    })
+}
+
+/// Release a device from quarantine
+///
+/// # Returns
+/// - empty JSON
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    delete,
+    path = "/api/v1/device/{device_id}/quarantine",
+    tag = "Quarantine",
+    params(
+        ("device_id" = Id, Path, description = "Device ID")
+    ),
+    responses(
+        (status = 200, description = "Device has been released from quarantine"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - device does not exist or is not quarantined"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn delete_device_quarantine(
+    _license: LicenseInfo,
+    _admin_role: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    Path(device_id): Path<Id>,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let device = Device::find_by_id(&appstate.pool, device_id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Device {device_id} not found")))?;
+    debug!(
+        "User {} releasing device {device} from quarantine",
+        session.user.username
+    );
+
+    release_device(&appstate.pool, &appstate.wireguard_tx, &device).await?;
+
+    let owner = device.get_owner(&appstate.pool).await?;
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::DeviceReleasedFromQuarantine { owner, device }),
+    })?;
+
+    Ok(ApiResponse::default())
+}
+
+/// List remediation hosts reachable by quarantined devices in a WireGuard location
+///
+/// # Returns
+/// - `Vec<QuarantineRemediationHost>` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/network/{location_id}/quarantine",
+    tag = "Quarantine",
+    params(
+        ("location_id" = Id, Path, description = "WireGuard location ID")
+    ),
+    responses(
+        (status = 200, description = "List of remediation hosts", body = Vec<QuarantineRemediationHost>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - location does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn list_remediation_hosts(
+    _license: LicenseInfo,
+    _admin_role: AdminRole,
+    Path(location_id): Path<Id>,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let location = WireguardNetwork::find_by_id(&appstate.pool, location_id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Location {location_id} not found")))?;
+
+    let hosts = QuarantineRemediationHost::all_for_location(&appstate.pool, location.id).await?;
+
+    Ok(ApiResponse {
+        json: json!(hosts),
+        status: StatusCode::OK,
+    })
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct NewRemediationHost {
+    /// Address or subnet reachable by quarantined devices
+    #[schema(value_type = String)]
+    pub address: IpNetwork,
+    pub description: Option<String>,
+}
+
+/// Add a remediation host reachable by quarantined devices in a WireGuard location
+///
+/// # Returns
+/// - `QuarantineRemediationHost` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    post,
+    path = "/api/v1/network/{location_id}/quarantine",
+    tag = "Quarantine",
+    params(
+        ("location_id" = Id, Path, description = "WireGuard location ID")
+    ),
+    request_body = NewRemediationHost,
+    responses(
+        (status = 201, description = "Remediation host added", body = QuarantineRemediationHost),
+        (status = 400, description = "Bad request - Invalid input data"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - location does not exist"),
+        (status = 409, description = "Conflict - Remediation host already exists"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn create_remediation_host(
+    _license: LicenseInfo,
+    _admin_role: AdminRole,
+    session: SessionInfo,
+    Path(location_id): Path<Id>,
+    State(appstate): State<AppState>,
+    Json(data): Json<NewRemediationHost>,
+) -> ApiResult {
+    let location = WireguardNetwork::find_by_id(&appstate.pool, location_id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Location {location_id} not found")))?;
+    debug!(
+        "User {} adding quarantine remediation host {} in location {location}",
+        session.user.username, data.address
+    );
+
+    let host = QuarantineRemediationHost::new(location.id, data.address, data.description)
+        .save(&appstate.pool)
+        .await
+        .map_err(QuarantineError::from)?;
+
+    let mut conn = appstate.pool.acquire().await?;
+    if let Some(event) = firewall_update_event(&mut conn, &location).await? {
+        appstate.send_wireguard_event(event);
+    }
+
+    Ok(ApiResponse {
+        json: json!(host),
+        status: StatusCode::CREATED,
+    })
+}
+
+/// Remove a remediation host from a WireGuard location
+///
+/// # Returns
+/// - empty JSON
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    delete,
+    path = "/api/v1/network/{location_id}/quarantine/{host_id}",
+    tag = "Quarantine",
+    params(
+        ("location_id" = Id, Path, description = "WireGuard location ID"),
+        ("host_id" = Id, Path, description = "Remediation host ID")
+    ),
+    responses(
+        (status = 200, description = "Remediation host removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - remediation host does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn delete_remediation_host(
+    _license: LicenseInfo,
+    _admin_role: AdminRole,
+    session: SessionInfo,
+    Path((location_id, host_id)): Path<(Id, Id)>,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let location = WireguardNetwork::find_by_id(&appstate.pool, location_id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Location {location_id} not found")))?;
+    let host = QuarantineRemediationHost::find_by_id(&appstate.pool, host_id)
+        .await?
+        .filter(|host| host.location_id == location.id)
+        .ok_or(QuarantineError::RemediationHostNotFound)?;
+    debug!(
+        "User {} removing quarantine remediation host {} from location {location}",
+        session.user.username, host.address
+    );
+
+    host.delete(&appstate.pool).await?;
+
+    let mut conn = appstate.pool.acquire().await?;
+    if let Some(event) = firewall_update_event(&mut conn, &location).await? {
+        appstate.send_wireguard_event(event);
+    }
+
+    Ok(ApiResponse::default())
+}
@@ -0,0 +1,114 @@
+//! Device quarantine.
+//!
+//! Quarantined devices stay connected to all of their locations, but the firewall on each
+//! gateway only lets them reach remediation hosts configured for a given location.
+//! Quarantine can be set by administrators through the API or automatically by other
+//! subsystems (e.g. posture checks or anomaly detection) using [`quarantine_device`].
+
+pub mod error;
+pub mod handlers;
+
+use defguard_common::db::Id;
+use sqlx::{PgConnection, PgPool};
+use tokio::sync::broadcast::Sender;
+
+use self::error::QuarantineError;
+use crate::{
+    db::{Device, GatewayEvent, WireguardNetwork, models::device::WireguardNetworkDevice},
+    enterprise::{
+        db::models::quarantine::{DeviceQuarantine, QuarantineSource},
+        is_business_license_active,
+    },
+    grpc::gateway::send_wireguard_event,
+};
+
+/// Places a device in quarantine and pushes restricted firewall rules to all locations
+/// the device belongs to.
+pub async fn quarantine_device(
+    pool: &PgPool,
+    wireguard_tx: &Sender<GatewayEvent>,
+    device: &Device<Id>,
+    source: QuarantineSource,
+    reason: Option<String>,
+) -> Result<DeviceQuarantine<Id>, QuarantineError> {
+    let mut transaction = pool.begin().await?;
+    let quarantine = DeviceQuarantine::new(device.id, source, reason)
+        .save(&mut *transaction)
+        .await?;
+    let events = firewall_update_events(&mut transaction, device.id).await?;
+    transaction.commit().await?;
+
+    info!(
+        "Device {device} has been quarantined ({:?}): {:?}",
+        quarantine.source, quarantine.reason
+    );
+    for event in events {
+        send_wireguard_event(event, wireguard_tx);
+    }
+
+    Ok(quarantine)
+}
+
+/// Releases a device from quarantine and restores its regular firewall rules.
+pub async fn release_device(
+    pool: &PgPool,
+    wireguard_tx: &Sender<GatewayEvent>,
+    device: &Device<Id>,
+) -> Result<DeviceQuarantine<Id>, QuarantineError> {
+    let mut transaction = pool.begin().await?;
+    let quarantine = DeviceQuarantine::find_by_device_id(&mut *transaction, device.id)
+        .await?
+        .ok_or(QuarantineError::NotQuarantined)?;
+    quarantine.clone().delete(&mut *transaction).await?;
+    let events = firewall_update_events(&mut transaction, device.id).await?;
+    transaction.commit().await?;
+
+    info!("Device {device} has been released from quarantine");
+    for event in events {
+        send_wireguard_event(event, wireguard_tx);
+    }
+
+    Ok(quarantine)
+}
+
+/// Regenerates firewall configuration for all locations a device belongs to.
+async fn firewall_update_events(
+    conn: &mut PgConnection,
+    device_id: Id,
+) -> Result<Vec<GatewayEvent>, QuarantineError> {
+    let network_devices = WireguardNetworkDevice::find_by_device(&mut *conn, device_id)
+        .await?
+        .unwrap_or_default();
+
+    let mut events = Vec::new();
+    for network_device in network_devices {
+        let Some(location) =
+            WireguardNetwork::find_by_id(&mut *conn, network_device.wireguard_network_id).await?
+        else {
+            continue;
+        };
+        events.extend(firewall_update_event(&mut *conn, &location).await?);
+    }
+
+    Ok(events)
+}
+
+/// Prepares firewall configuration update for a location affected by a quarantine change.
+pub(crate) async fn firewall_update_event(
+    conn: &mut PgConnection,
+    location: &WireguardNetwork<Id>,
+) -> Result<Option<GatewayEvent>, QuarantineError> {
+    if let Some(firewall_config) = location.try_get_firewall_config(&mut *conn).await? {
+        debug!("Sending firewall config update for location {location} after quarantine change");
+        Ok(Some(GatewayEvent::FirewallConfigChanged(
+            location.id,
+            firewall_config,
+        )))
+    } else if !location.acl_enabled && is_business_license_active() {
+        // no quarantined devices remain and ACLs are disabled
+        debug!("Disabling firewall for location {location} after quarantine change");
+        Ok(Some(GatewayEvent::FirewallDisabled(location.id)))
+    } else {
+        Ok(None)
+    }
+}
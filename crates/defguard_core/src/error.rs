@@ -9,11 +9,13 @@ use utoipa::ToSchema;
 use crate::{
     auth::failed_login::FailedLoginError,
     db::models::{device::DeviceError, enrollment::TokenError, wireguard::WireguardNetworkError},
+    device_naming::DeviceNameError,
     enterprise::{
         activity_log_stream::error::ActivityLogStreamError, db::models::acl::AclError,
         firewall::FirewallError, ldap::error::LdapError, license::LicenseError,
     },
     events::ApiEvent,
+    gateway_deployment::GatewayDeploymentError,
     grpc::gateway::map::GatewayMapError,
 };
 
@@ -138,6 +140,30 @@ impl From<GatewayMapError> for WebError {
     }
 }
 
+impl From<DeviceNameError> for WebError {
+    fn from(error: DeviceNameError) -> Self {
+        match error {
+            DeviceNameError::DbError(_) => Self::DbError(error.to_string()),
+            DeviceNameError::ModelError(_) => Self::ModelError(error.to_string()),
+            _ => Self::BadRequest(error.to_string()),
+        }
+    }
+}
+
+impl From<GatewayDeploymentError> for WebError {
+    fn from(error: GatewayDeploymentError) -> Self {
+        match error {
+            GatewayDeploymentError::InvalidVersion(_) | GatewayDeploymentError::InvalidName(_) => {
+                Self::BadRequest(error.to_string())
+            }
+            GatewayDeploymentError::Template(err) => {
+                error!("Failed to render gateway deployment template: {err}");
+                Self::Http(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+        }
+    }
+}
+
 impl From<WireguardNetworkError> for WebError {
     fn from(error: WireguardNetworkError) -> Self {
         match error {
@@ -182,9 +208,16 @@ impl From<TokenError> for WebError {
 impl From<SettingsValidationError> for WebError {
     fn from(err: SettingsValidationError) -> Self {
         match err {
-            SettingsValidationError::CannotEnableGatewayNotifications => {
-                Self::BadRequest(err.to_string())
-            }
+            SettingsValidationError::CannotEnableGatewayNotifications
+            | SettingsValidationError::CannotEnableSelfRegistration
+            | SettingsValidationError::InvalidEmailAddress(_)
+            | SettingsValidationError::InvalidSenderName
+            | SettingsValidationError::UnknownMailVariable(_)
+            | SettingsValidationError::InvalidPasswordResetLifetime
+            | SettingsValidationError::InvalidPasswordResetMaxUses
+            | SettingsValidationError::InvalidNotificationDigestWindow
+            | SettingsValidationError::InvalidMailHttpUrl(_)
+            | SettingsValidationError::InvalidMailLanguage(_) => Self::BadRequest(err.to_string()),
         }
     }
 }
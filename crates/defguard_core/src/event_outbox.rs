@@ -0,0 +1,202 @@
+//! This module publishes domain events stored in the transactional outbox to a message broker
+//! (NATS JetStream or Kafka). Events are removed from the outbox only after the broker
+//! acknowledged them, so they are delivered at least once; the outbox event ID is sent as
+//! a message ID (NATS) or key (Kafka) to let consumers deduplicate them.
+//!
+//! Support for each broker is enabled with the `outbox-nats` and `outbox-kafka` features.
+
+#[cfg(any(feature = "outbox-kafka", feature = "outbox-nats"))]
+use std::time::Duration;
+
+#[cfg(feature = "outbox-nats")]
+use async_nats::{
+    HeaderMap,
+    header::NATS_MESSAGE_ID,
+    jetstream::{self, Context as JetStreamContext},
+};
+use defguard_common::config::DefGuardConfig;
+#[cfg(feature = "outbox-kafka")]
+use rdkafka::{
+    ClientConfig,
+    error::KafkaError,
+    producer::{FutureProducer, FutureRecord},
+};
+use sqlx::{Error as SqlxError, PgPool};
+use thiserror::Error;
+#[cfg(any(feature = "outbox-kafka", feature = "outbox-nats"))]
+use tokio::time::sleep;
+
+#[cfg(any(feature = "outbox-kafka", feature = "outbox-nats"))]
+use crate::db::models::event_outbox::OutboxEvent;
+
+// How long to sleep when there are no events to publish or the broker is unavailable
+#[cfg(any(feature = "outbox-kafka", feature = "outbox-nats"))]
+const OUTBOX_LOOP_SLEEP: Duration = Duration::from_secs(5);
+// Maximum number of events published in one iteration
+#[cfg(any(feature = "outbox-kafka", feature = "outbox-nats"))]
+const OUTBOX_BATCH_SIZE: i64 = 100;
+// How long Kafka producer may keep a message in its queue
+#[cfg(feature = "outbox-kafka")]
+const KAFKA_QUEUE_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Error)]
+pub enum EventOutboxError {
+    #[error(transparent)]
+    DbError(#[from] SqlxError),
+    #[cfg(feature = "outbox-nats")]
+    #[error("NATS error: {0}")]
+    NatsError(String),
+    #[cfg(feature = "outbox-kafka")]
+    #[error(transparent)]
+    KafkaError(#[from] KafkaError),
+    #[error("Failed to serialize event: {0}")]
+    SerializationError(#[from] serde_json::Error),
+    #[error("Publishing events to {0} isn't enabled in this build")]
+    UnsupportedBroker(&'static str),
+}
+
+#[cfg(any(feature = "outbox-kafka", feature = "outbox-nats"))]
+enum Publisher {
+    #[cfg(feature = "outbox-nats")]
+    Nats(JetStreamContext),
+    #[cfg(feature = "outbox-kafka")]
+    Kafka(FutureProducer),
+}
+
+#[cfg(any(feature = "outbox-kafka", feature = "outbox-nats"))]
+impl Publisher {
+    async fn connect(config: &DefGuardConfig) -> Result<Option<Self>, EventOutboxError> {
+        #[cfg(not(feature = "outbox-nats"))]
+        if config.event_outbox_nats_url.is_some() {
+            return Err(EventOutboxError::UnsupportedBroker("NATS"));
+        }
+        #[cfg(feature = "outbox-nats")]
+        if let Some(url) = &config.event_outbox_nats_url {
+            info!("Connecting to NATS server at {url}");
+            let client = async_nats::connect(url)
+                .await
+                .map_err(|err| EventOutboxError::NatsError(err.to_string()))?;
+            return Ok(Some(Self::Nats(jetstream::new(client))));
+        }
+        #[cfg(not(feature = "outbox-kafka"))]
+        if config.event_outbox_kafka_brokers.is_some() {
+            return Err(EventOutboxError::UnsupportedBroker("Kafka"));
+        }
+        #[cfg(feature = "outbox-kafka")]
+        if let Some(brokers) = &config.event_outbox_kafka_brokers {
+            info!("Connecting to Kafka brokers {brokers}");
+            let producer: FutureProducer = ClientConfig::new()
+                .set("bootstrap.servers", brokers)
+                .set("enable.idempotence", "true")
+                .create()?;
+            return Ok(Some(Self::Kafka(producer)));
+        }
+
+        Ok(None)
+    }
+
+    async fn publish(&self, topic: &str, event: &OutboxEvent) -> Result<(), EventOutboxError> {
+        let payload = serde_json::to_vec(event)?;
+        match self {
+            #[cfg(feature = "outbox-nats")]
+            Self::Nats(context) => {
+                let mut headers = HeaderMap::new();
+                headers.insert(NATS_MESSAGE_ID, event.id.to_string());
+                context
+                    .publish_with_headers(
+                        format!("{topic}.{}", event.event_type),
+                        headers,
+                        payload.into(),
+                    )
+                    .await
+                    .map_err(|err| EventOutboxError::NatsError(err.to_string()))?
+                    .await
+                    .map_err(|err| EventOutboxError::NatsError(err.to_string()))?;
+            }
+            #[cfg(feature = "outbox-kafka")]
+            Self::Kafka(producer) => {
+                let key = event.id.to_string();
+                producer
+                    .send(
+                        FutureRecord::to(topic).key(&key).payload(&payload),
+                        KAFKA_QUEUE_TIMEOUT,
+                    )
+                    .await
+                    .map_err(|(err, _)| err)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Publish a batch of pending events and remove published ones from the outbox.
+/// Returns the number of published events.
+#[cfg(any(feature = "outbox-kafka", feature = "outbox-nats"))]
+async fn publish_pending(
+    pool: &PgPool,
+    publisher: &Publisher,
+    topic: &str,
+) -> Result<usize, EventOutboxError> {
+    let mut transaction = pool.begin().await?;
+    let events = OutboxEvent::pending(&mut transaction, OUTBOX_BATCH_SIZE).await?;
+    let mut published = Vec::with_capacity(events.len());
+    for event in &events {
+        if let Err(err) = publisher.publish(topic, event).await {
+            warn!(
+                "Failed to publish event {} ({}), will retry: {err}",
+                event.id, event.event_type
+            );
+            break;
+        }
+        published.push(event.id);
+    }
+    OutboxEvent::remove(&mut *transaction, &published).await?;
+    transaction.commit().await?;
+    if !published.is_empty() {
+        debug!("Published {} events from the outbox", published.len());
+    }
+
+    Ok(published.len())
+}
+
+/// Run domain event publisher
+///
+/// Publishes events stored in the outbox to the configured message broker. Should only be run
+/// if a message broker is configured, see [`DefGuardConfig::event_outbox_enabled`].
+#[cfg(any(feature = "outbox-kafka", feature = "outbox-nats"))]
+#[instrument(skip_all)]
+pub async fn run_event_outbox_publisher(
+    pool: PgPool,
+    config: DefGuardConfig,
+) -> Result<(), EventOutboxError> {
+    let Some(publisher) = Publisher::connect(&config).await? else {
+        debug!("No message broker configured, domain events won't be published");
+        return Ok(());
+    };
+    info!("Starting domain event publisher");
+
+    loop {
+        let published = publish_pending(&pool, &publisher, &config.event_outbox_topic).await?;
+        // publish remaining events right away unless the batch wasn't full
+        if published < OUTBOX_BATCH_SIZE as usize {
+            sleep(OUTBOX_LOOP_SLEEP).await;
+        }
+    }
+}
+
+/// Fails if a message broker is configured, as support for none of them is enabled in this build.
+#[cfg(not(any(feature = "outbox-kafka", feature = "outbox-nats")))]
+pub async fn run_event_outbox_publisher(
+    _pool: PgPool,
+    config: DefGuardConfig,
+) -> Result<(), EventOutboxError> {
+    if config.event_outbox_nats_url.is_some() {
+        return Err(EventOutboxError::UnsupportedBroker("NATS"));
+    }
+    if config.event_outbox_kafka_brokers.is_some() {
+        return Err(EventOutboxError::UnsupportedBroker("Kafka"));
+    }
+
+    Ok(())
+}
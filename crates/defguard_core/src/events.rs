@@ -3,14 +3,17 @@ use std::net::IpAddr;
 use chrono::{NaiveDateTime, Utc};
 use defguard_common::db::{
     Id,
-    models::{AuthenticationKey, MFAMethod, Settings},
+    models::{AuthenticationKey, MFAMethod, MailContext, MailTemplate, MailVariable, Settings},
 };
 use defguard_proto::proxy::MfaMethod;
 
 use crate::{
     db::{
         Device, Group, User, WebAuthn, WebHook, WireguardNetwork,
-        models::oauth2client::OAuth2Client,
+        models::{
+            announcement::Announcement, itsm::ItsmConnector, oauth2client::OAuth2Client,
+            route::Route, service_account::ServiceAccountInfo,
+        },
     },
     enterprise::db::models::{
         activity_log_stream::ActivityLogStream, api_tokens::ApiToken,
@@ -105,6 +108,12 @@ pub enum ApiEventType {
     PasswordReset {
         user: User<Id>,
     },
+    HelpdeskPasswordResetRequested {
+        user: User<Id>,
+    },
+    HelpdeskPasswordResetConfirmed {
+        user: User<Id>,
+    },
     MfaDisabled,
     UserMfaDisabled {
         user: User<Id>,
@@ -122,6 +131,9 @@ pub enum ApiEventType {
     UserAdded {
         user: User<Id>,
     },
+    UserSelfRegistered {
+        user: User<Id>,
+    },
     UserRemoved {
         user: User<Id>,
     },
@@ -147,6 +159,30 @@ pub enum ApiEventType {
         before: Device<Id>,
         after: Device<Id>,
     },
+    DeviceDisconnected {
+        owner: User<Id>,
+        device: Device<Id>,
+        location: WireguardNetwork<Id>,
+    },
+    DeviceQuarantined {
+        owner: User<Id>,
+        device: Device<Id>,
+        reason: Option<String>,
+    },
+    DeviceReleasedFromQuarantine {
+        owner: User<Id>,
+        device: Device<Id>,
+    },
+    DeviceApproved {
+        owner: User<Id>,
+        device: Device<Id>,
+        location: WireguardNetwork<Id>,
+    },
+    DeviceRejected {
+        owner: User<Id>,
+        device: Device<Id>,
+        location: WireguardNetwork<Id>,
+    },
     NetworkDeviceAdded {
         device: Device<Id>,
         location: WireguardNetwork<Id>,
@@ -180,6 +216,10 @@ pub enum ApiEventType {
         before: WireguardNetwork<Id>,
         after: WireguardNetwork<Id>,
     },
+    VpnLocationFirewallRolledBack {
+        location: WireguardNetwork<Id>,
+        version: i64,
+    },
     ApiTokenAdded {
         owner: User<Id>,
         token: ApiToken<Id>,
@@ -264,6 +304,64 @@ pub enum ApiEventType {
         webhook: WebHook<Id>,
         enabled: bool,
     },
+    AnnouncementCreated {
+        announcement: Announcement<Id>,
+    },
+    AnnouncementRemoved {
+        announcement: Announcement<Id>,
+    },
+    ServiceAccountAdded {
+        service_account: ServiceAccountInfo,
+    },
+    ServiceAccountModified {
+        before: ServiceAccountInfo,
+        after: ServiceAccountInfo,
+    },
+    ServiceAccountRemoved {
+        service_account: ServiceAccountInfo,
+    },
+    ItsmConnectorAdded {
+        connector: ItsmConnector<Id>,
+    },
+    ItsmConnectorModified {
+        before: ItsmConnector<Id>,
+        after: ItsmConnector<Id>,
+    },
+    ItsmConnectorRemoved {
+        connector: ItsmConnector<Id>,
+    },
+    MailVariableAdded {
+        variable: MailVariable<Id>,
+    },
+    MailVariableModified {
+        before: MailVariable<Id>,
+        after: MailVariable<Id>,
+    },
+    MailVariableRemoved {
+        variable: MailVariable<Id>,
+    },
+    MailContextModified {
+        translation: MailContext<Id>,
+    },
+    MailContextRemoved {
+        translation: MailContext<Id>,
+    },
+    MailTemplateModified {
+        template: MailTemplate<Id>,
+    },
+    MailTemplateRestored {
+        template: MailTemplate<Id>,
+    },
+    RouteAdded {
+        route: Route<Id>,
+    },
+    RouteModified {
+        before: Route<Id>,
+        after: Route<Id>,
+    },
+    RouteRemoved {
+        route: Route<Id>,
+    },
     AuthenticationKeyAdded {
         key: AuthenticationKey<Id>,
     },
@@ -278,6 +376,15 @@ pub enum ApiEventType {
     EnrollmentTokenAdded {
         user: User<Id>,
     },
+    EnrollmentTokenResent {
+        user: User<Id>,
+    },
+    EnrollmentTokenExtended {
+        user: User<Id>,
+    },
+    EnrollmentTokenRevoked {
+        user: User<Id>,
+    },
     ClientConfigurationTokenAdded {
         user: User<Id>,
     },
@@ -392,6 +499,8 @@ pub enum DesktopClientMfaEvent {
         device: Device<Id>,
         location: WireguardNetwork<Id>,
         method: ClientMFAMethod,
+        /// MFA was skipped, because the device is remembered for this location.
+        remembered: bool,
     },
     Failed {
         device: Device<Id>,
@@ -0,0 +1,223 @@
+//! Deployment artifacts for gateways, rendered from templates: docker compose service,
+//! systemd unit or env file.
+
+use semver::Version;
+use tera::{Context, Tera};
+use thiserror::Error;
+use utoipa::ToSchema;
+
+static DOCKER_COMPOSE: &str = include_str!("../templates/gateway/docker_compose.tera");
+static SYSTEMD: &str = include_str!("../templates/gateway/systemd.tera");
+static ENV: &str = include_str!("../templates/gateway/env.tera");
+
+const GATEWAY_IMAGE: &str = "ghcr.io/defguard/gateway";
+/// Path of gRPC CA certificate on the gateway host or inside the gateway container.
+pub const GATEWAY_CA_PATH: &str = "/etc/defguard/ca.pem";
+/// Path of env file read by gateway systemd unit.
+const GATEWAY_ENV_FILE: &str = "/etc/defguard/gateway.env";
+const LATEST_VERSION: &str = "latest";
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DeploymentFormat {
+    DockerCompose,
+    Systemd,
+    Env,
+}
+
+impl DeploymentFormat {
+    fn template(self) -> (&'static str, &'static str) {
+        match self {
+            Self::DockerCompose => ("docker_compose", DOCKER_COMPOSE),
+            Self::Systemd => ("systemd", SYSTEMD),
+            Self::Env => ("env", ENV),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum GatewayDeploymentError {
+    #[error("Invalid gateway version {0}, expected \"latest\" or a version number")]
+    InvalidVersion(String),
+    #[error(
+        "Invalid gateway name {0}, only letters, digits, dots, dashes and underscores are allowed"
+    )]
+    InvalidName(String),
+    #[error(transparent)]
+    Template(#[from] tera::Error),
+}
+
+/// Values available in gateway deployment templates.
+#[derive(Debug, Serialize)]
+pub struct GatewayDeployment {
+    /// Location name
+    pub location: String,
+    /// Optional gateway name, reported by the gateway
+    pub name: Option<String>,
+    pub token: String,
+    pub grpc_url: String,
+    /// Where the gRPC CA certificate is expected, if TLS is configured
+    pub ca_cert_path: Option<&'static str>,
+    /// Gateway version to pin, or "latest"
+    pub version: String,
+}
+
+impl GatewayDeployment {
+    pub fn new(
+        location: String,
+        name: Option<String>,
+        token: String,
+        grpc_url: String,
+        tls: bool,
+        version: Option<String>,
+    ) -> Result<Self, GatewayDeploymentError> {
+        if let Some(name) = &name {
+            if name.is_empty()
+                || !name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+            {
+                return Err(GatewayDeploymentError::InvalidName(name.clone()));
+            }
+        }
+        let version = match version {
+            Some(version) if version != LATEST_VERSION => {
+                let version = version.strip_prefix('v').unwrap_or(&version);
+                Version::parse(version)
+                    .map_err(|_| GatewayDeploymentError::InvalidVersion(version.to_string()))?
+                    .to_string()
+            }
+            _ => LATEST_VERSION.to_string(),
+        };
+
+        Ok(Self {
+            location,
+            name,
+            token,
+            grpc_url,
+            ca_cert_path: tls.then_some(GATEWAY_CA_PATH),
+            version,
+        })
+    }
+
+    pub fn render(&self, format: DeploymentFormat) -> Result<String, GatewayDeploymentError> {
+        let (name, template) = format.template();
+        let mut tera = Tera::default();
+        tera.add_raw_template(name, template)?;
+        let mut context = Context::from_serialize(self)?;
+        context.insert("image", GATEWAY_IMAGE);
+        context.insert("env_file", GATEWAY_ENV_FILE);
+
+        Ok(tera.render(name, &context)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn deployment(name: Option<&str>, tls: bool, version: Option<&str>) -> GatewayDeployment {
+        GatewayDeployment::new(
+            "office".into(),
+            name.map(ToString::to_string),
+            "TOKEN".into(),
+            "https://defguard.example.com:50055/".into(),
+            tls,
+            version.map(ToString::to_string),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_render_env() {
+        assert_eq!(
+            deployment(None, false, None)
+                .render(DeploymentFormat::Env)
+                .unwrap(),
+            "# Defguard gateway for location office\n\
+            DEFGUARD_TOKEN=TOKEN\n\
+            DEFGUARD_GRPC_URL=https://defguard.example.com:50055/\n"
+        );
+        assert_eq!(
+            deployment(Some("gw-1"), true, Some("v1.5.0"))
+                .render(DeploymentFormat::Env)
+                .unwrap(),
+            "# Defguard gateway for location office, version 1.5.0\n\
+            DEFGUARD_TOKEN=TOKEN\n\
+            DEFGUARD_GRPC_URL=https://defguard.example.com:50055/\n\
+            DEFGUARD_GRPC_CA=/etc/defguard/ca.pem\n\
+            DEFGUARD_GATEWAY_NAME=gw-1\n"
+        );
+    }
+
+    #[test]
+    fn test_render_docker_compose() {
+        assert_eq!(
+            deployment(None, false, None)
+                .render(DeploymentFormat::DockerCompose)
+                .unwrap(),
+            "# Defguard gateway for location office\n\
+            services:\n  \
+            gateway:\n    \
+            image: ghcr.io/defguard/gateway:latest\n    \
+            restart: unless-stopped\n    \
+            network_mode: host\n    \
+            cap_add:\n      \
+            - NET_ADMIN\n    \
+            environment:\n      \
+            DEFGUARD_TOKEN: TOKEN\n      \
+            DEFGUARD_GRPC_URL: https://defguard.example.com:50055/\n"
+        );
+        let rendered = deployment(Some("gw-1"), true, Some("1.5.0"))
+            .render(DeploymentFormat::DockerCompose)
+            .unwrap();
+        assert!(rendered.contains("image: ghcr.io/defguard/gateway:1.5.0\n"));
+        assert!(rendered.ends_with(
+            "DEFGUARD_GRPC_CA: /etc/defguard/ca.pem\n      \
+            DEFGUARD_GATEWAY_NAME: gw-1\n    \
+            volumes:\n      \
+            - ./ca.pem:/etc/defguard/ca.pem:ro\n"
+        ));
+    }
+
+    #[test]
+    fn test_render_systemd() {
+        let rendered = deployment(Some("gw-1"), false, Some("1.5.0"))
+            .render(DeploymentFormat::Systemd)
+            .unwrap();
+        assert!(rendered.starts_with(
+            "# Defguard gateway for location office, requires defguard-gateway 1.5.0\n"
+        ));
+        assert!(rendered.contains("Description=Defguard VPN gateway gw-1\n"));
+        assert!(rendered.contains("EnvironmentFile=/etc/defguard/gateway.env\n"));
+        // token is kept in the env file
+        assert!(!rendered.contains("TOKEN"));
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        let new = |name: Option<&str>, version: Option<&str>| {
+            GatewayDeployment::new(
+                "office".into(),
+                name.map(ToString::to_string),
+                "TOKEN".into(),
+                "http://localhost:50055/".into(),
+                false,
+                version.map(ToString::to_string),
+            )
+        };
+        assert!(matches!(
+            new(Some("gw 1\nEvil: true"), None),
+            Err(GatewayDeploymentError::InvalidName(_))
+        ));
+        assert!(matches!(
+            new(Some(""), None),
+            Err(GatewayDeploymentError::InvalidName(_))
+        ));
+        assert!(matches!(
+            new(None, Some("1.5")),
+            Err(GatewayDeploymentError::InvalidVersion(_))
+        ));
+        assert!(new(None, Some("latest")).is_ok());
+    }
+}
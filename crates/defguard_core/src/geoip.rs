@@ -0,0 +1,189 @@
+//! Country lookup of client addresses, used by geofencing policies of locations.
+//!
+//! Countries are looked up in a CSV database configured with `DEFGUARD_GEOIP_DATABASE`, holding
+//! `start,end,country` rows with inclusive address ranges and ISO 3166-1 alpha-2 country codes,
+//! like the freely available DB-IP Lite country database. City databases with
+//! `start,end,continent,country,region,city,...` rows, like DB-IP Lite city database, are supported
+//! as well, which makes cities available too. The database is loaded on first use.
+
+use std::{fs, net::IpAddr, sync::OnceLock};
+
+use defguard_common::config::server_config;
+
+/// Addresses from `start` to `end` (inclusive) assigned to a country and, optionally, a city.
+#[derive(Debug)]
+struct CountryRange {
+    start: IpAddr,
+    end: IpAddr,
+    country: String,
+    city: Option<String>,
+}
+
+/// Address ranges sorted by their first address.
+#[derive(Debug)]
+pub struct GeoIpDatabase {
+    ranges: Vec<CountryRange>,
+}
+
+/// Split a CSV line into fields, which may be quoted and contain commas then.
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    for c in line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+impl GeoIpDatabase {
+    /// Parses CSV database content. Lines which can't be parsed, e.g. headers, are skipped.
+    #[must_use]
+    pub fn parse(content: &str) -> Self {
+        let mut ranges: Vec<CountryRange> = content
+            .lines()
+            .filter_map(|line| {
+                let fields = csv_fields(line);
+                let fields: Vec<&str> = fields.iter().map(|field| field.trim()).collect();
+                let start: IpAddr = fields.first()?.parse().ok()?;
+                let end: IpAddr = fields.get(1)?.parse().ok()?;
+                // city databases have continent before country and region before city
+                let (country, city) = if fields.len() >= 6 {
+                    (fields[3], Some(fields[5]).filter(|city| !city.is_empty()))
+                } else {
+                    (*fields.get(2)?, None)
+                };
+                if start.is_ipv4() != end.is_ipv4() || start > end || !is_country_code(country) {
+                    return None;
+                }
+                Some(CountryRange {
+                    start,
+                    end,
+                    country: country.to_ascii_uppercase(),
+                    city: city.map(ToString::to_string),
+                })
+            })
+            .collect();
+        ranges.sort_by(|a, b| a.start.cmp(&b.start));
+        Self { ranges }
+    }
+
+    fn range(&self, ip: IpAddr) -> Option<&CountryRange> {
+        let ip = ip.to_canonical();
+        let index = self.ranges.partition_point(|range| range.start <= ip);
+        let range = self.ranges.get(index.checked_sub(1)?)?;
+        (ip <= range.end).then_some(range)
+    }
+
+    /// Country code of a given address, `None` if it's not in the database.
+    #[must_use]
+    pub fn country(&self, ip: IpAddr) -> Option<&str> {
+        self.range(ip).map(|range| range.country.as_str())
+    }
+
+    /// City of a given address, `None` if it's not in the database or it has no cities.
+    #[must_use]
+    pub fn city(&self, ip: IpAddr) -> Option<&str> {
+        self.range(ip)?.city.as_deref()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+}
+
+/// Checks if a string is an ISO 3166-1 alpha-2 country code, regardless of its case.
+#[must_use]
+pub fn is_country_code(code: &str) -> bool {
+    code.len() == 2 && code.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+static GEOIP_DATABASE: OnceLock<Option<GeoIpDatabase>> = OnceLock::new();
+
+/// Configured GeoIP database, `None` if it's not configured or can't be read.
+pub fn geoip_database() -> Option<&'static GeoIpDatabase> {
+    GEOIP_DATABASE
+        .get_or_init(|| {
+            let path = server_config().geoip_database.as_ref()?;
+            match fs::read_to_string(path) {
+                Ok(content) => {
+                    let database = GeoIpDatabase::parse(&content);
+                    info!(
+                        "Loaded {} address ranges from GeoIP database {path}",
+                        database.len()
+                    );
+                    Some(database)
+                }
+                Err(err) => {
+                    error!("Failed to read GeoIP database {path}: {err}");
+                    None
+                }
+            }
+        })
+        .as_ref()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_country_lookup() {
+        let database = GeoIpDatabase::parse(
+            "start,end,country\n\
+            1.0.0.0,1.0.0.255,AU\n\
+            \"1.0.4.0\",\"1.0.7.255\",\"au\"\n\
+            2.16.0.0,2.16.0.255,de\n\
+            1.0.1.0,1.0.3.255,CN\n\
+            2001:200::,2001:200:ffff:ffff:ffff:ffff:ffff:ffff,JP\n\
+            3.0.0.0,2.0.0.0,US\n\
+            invalid,line,XX\n",
+        );
+        assert_eq!(database.len(), 5);
+
+        let country = |ip: &str| database.country(ip.parse().unwrap());
+        assert_eq!(country("1.0.0.0"), Some("AU"));
+        assert_eq!(country("1.0.2.3"), Some("CN"));
+        assert_eq!(country("1.0.7.255"), Some("AU"));
+        assert_eq!(country("1.0.8.0"), None);
+        assert_eq!(country("2.16.0.10"), Some("DE"));
+        assert_eq!(country("::ffff:2.16.0.10"), Some("DE"));
+        assert_eq!(country("0.255.255.255"), None);
+        assert_eq!(country("2001:200::1"), Some("JP"));
+        assert_eq!(country("2001:201::1"), None);
+        assert_eq!(country("2.255.0.0"), None);
+    }
+
+    #[test]
+    fn test_city_lookup() {
+        let database = GeoIpDatabase::parse(
+            "1.0.0.0,1.0.0.255,OC,AU,Queensland,South Brisbane,-27.4767,153.017\n\
+            \"2.16.0.0\",\"2.16.0.255\",\"EU\",\"DE\",\"Hesse\",\"Frankfurt, am Main\"\n\
+            2.17.0.0,2.17.0.255,EU,PL,,,52.2,21.0\n",
+        );
+        assert_eq!(database.len(), 3);
+
+        let location = |ip: &str| {
+            let ip = ip.parse().unwrap();
+            (database.country(ip), database.city(ip))
+        };
+        assert_eq!(location("1.0.0.1"), (Some("AU"), Some("South Brisbane")));
+        assert_eq!(
+            location("2.16.0.1"),
+            (Some("DE"), Some("Frankfurt, am Main"))
+        );
+        assert_eq!(location("2.17.0.1"), (Some("PL"), None));
+        assert_eq!(location("3.0.0.1"), (None, None));
+    }
+}
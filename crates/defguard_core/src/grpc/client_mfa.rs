@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, net::IpAddr};
 
 use chrono::Utc;
 use defguard_common::{
@@ -27,12 +27,15 @@ use crate::{
         Device, GatewayEvent, User, UserInfo, WireguardNetwork,
         models::{
             device::{DeviceInfo, DeviceNetworkInfo, WireguardNetworkDevice},
+            location_geofence::LocationGeofence,
+            mfa_remembered_device::MfaRememberedDevice,
             wireguard::LocationMfaMode,
         },
     },
     enterprise::{db::models::openid_provider::OpenIdProvider, is_business_license_active},
     events::{BidiRequestContext, BidiStreamEvent, BidiStreamEventType, DesktopClientMfaEvent},
-    grpc::utils::parse_client_ip_agent,
+    geoip::geoip_database,
+    grpc::{client_version::check_min_client_version, utils::parse_client_ip_agent},
     handlers::mail::send_email_mfa_code_email,
 };
 
@@ -58,6 +61,8 @@ pub(crate) struct ClientLoginSession {
     pub(crate) user: User<Id>,
     pub(crate) openid_auth_completed: bool,
     pub(crate) biometric_challenge: Option<BiometricChallenge>,
+    /// Device is remembered for this location, so MFA doesn't have to be repeated.
+    pub(crate) remembered: bool,
 }
 
 pub(crate) struct ClientMfaServer {
@@ -129,6 +134,7 @@ impl ClientMfaServer {
     pub async fn start_client_mfa_login(
         &mut self,
         request: ClientMfaStartRequest,
+        info: Option<proxy::DeviceInfo>,
     ) -> Result<ClientMfaStartResponse, Status> {
         debug!("Starting desktop client login: {request:?}");
         // fetch location
@@ -145,6 +151,9 @@ impl ClientMfaServer {
             return Err(Status::invalid_argument("MFA not enabled for location"));
         }
 
+        // reject clients older than required by the location
+        check_min_client_version([&location], info.as_ref())?;
+
         // fetch device
         let Ok(Some(device)) = Device::find_by_pubkey(&self.pool, &request.pubkey).await else {
             error!("Failed to find device with pubkey {}", request.pubkey);
@@ -172,6 +181,14 @@ impl ClientMfaServer {
             Status::internal("unexpected error")
         })?;
 
+        // devices remembered for this location can skip MFA
+        let remembered = MfaRememberedDevice::is_remembered(&self.pool, &request.pubkey, &location)
+            .await
+            .map_err(|err| {
+                error!("Failed to check if device {device} is remembered: {err}");
+                Status::internal("unexpected error")
+            })?;
+
         // extract user selected method from request
         let selected_method = MfaMethod::try_from(request.method).map_err(|err| {
             error!("Invalid MFA method selected ({}): {err}", request.method);
@@ -249,14 +266,16 @@ impl ClientMfaServer {
                         "selected MFA method not available",
                     ));
                 }
-                // send email code
-                send_email_mfa_code_email(&user, &self.mail_tx, None).map_err(|err| {
-                    error!(
-                        "Failed to send email MFA code for user {}: {err}",
-                        user.username
-                    );
-                    Status::internal("unexpected error")
-                })?;
+                // send email code, unless it won't be needed
+                if !remembered {
+                    send_email_mfa_code_email(&user, &self.mail_tx, None).map_err(|err| {
+                        error!(
+                            "Failed to send email MFA code for user {}: {err}",
+                            user.username
+                        );
+                        Status::internal("unexpected error")
+                    })?;
+                }
             }
             MfaMethod::Oidc => {
                 if !is_business_license_active() {
@@ -323,6 +342,7 @@ impl ClientMfaServer {
                 user,
                 openid_auth_completed: false,
                 biometric_challenge,
+                remembered,
             },
         );
 
@@ -332,6 +352,32 @@ impl ClientMfaServer {
         })
     }
 
+    /// Returns country code of a client address, or `unknown` if it can't be determined,
+    /// if authorization from it is not allowed by the location geofence.
+    async fn geofence_denial(
+        pool: &PgPool,
+        location: &WireguardNetwork<Id>,
+        ip: IpAddr,
+    ) -> Result<Option<String>, Status> {
+        let geofence = LocationGeofence::for_location(pool, location.id)
+            .await
+            .map_err(|err| {
+                error!("Failed to fetch geofence of location {location}: {err}");
+                Status::internal("unexpected error")
+            })?;
+        if !geofence.is_restricted() {
+            return Ok(None);
+        }
+        let Some(database) = geoip_database() else {
+            warn!("Location {location} has a geofence, but GeoIP database is not available");
+            return Ok((!geofence.allows(None)).then(|| "unknown".into()));
+        };
+        let country = database.country(ip);
+        debug!("Client address {ip} is located in country {country:?}");
+
+        Ok((!geofence.allows(country)).then(|| country.unwrap_or("unknown").into()))
+    }
+
     /// Checks if given user is allowed to access a location
     async fn validate_location_access(
         pool: &PgPool,
@@ -392,6 +438,7 @@ impl ClientMfaServer {
             user,
             openid_auth_completed,
             biometric_challenge,
+            remembered,
         } = session;
 
         // Prepare event context
@@ -403,8 +450,36 @@ impl ClientMfaServer {
             format!("{} (ID {})", device.name, device.id),
         );
 
+        // geofence is checked against the public address the client connects to the proxy from
+        if let Some(country) = Self::geofence_denial(&self.pool, location, ip).await? {
+            info!(
+                "User {user} tried to authorize device {device} in location {location} from \
+                country {country}, which is not allowed by the location geofence"
+            );
+            self.emit_event(BidiStreamEvent {
+                context,
+                event: BidiStreamEventType::DesktopClientMfa(Box::new(
+                    DesktopClientMfaEvent::Failed {
+                        location: location.clone(),
+                        device: device.clone(),
+                        method: *method,
+                        message: format!("Authorization from country {country} not allowed"),
+                    },
+                )),
+            })?;
+            return Err(Status::permission_denied(format!(
+                "geofence: authorization from country {country} not allowed"
+            )));
+        }
+
         // validate code
         match method {
+            _ if *remembered => {
+                info!(
+                    "Device {device} is remembered for location {location}, skipping MFA \
+                    verification"
+                );
+            }
             MfaMethod::MobileApprove => {
                 let challenge = biometric_challenge.as_ref().ok_or_else(|| {
                     error!("Challenge not found in MFA session.");
@@ -611,6 +686,17 @@ impl ClientMfaServer {
                 Status::internal("unexpected error")
             })?;
 
+        // remember device, so it doesn't have to repeat MFA for a while
+        if !remembered && location.mfa_remember_device_hours > 0 {
+            MfaRememberedDevice::new(device, location)
+                .save(&mut *transaction)
+                .await
+                .map_err(|err| {
+                    error!("Failed to remember device {device} for location {location}: {err}");
+                    Status::internal("unexpected error")
+                })?;
+        }
+
         // send gateway event
         debug!("Sending `peer_create` message to gateway");
         let device_info = DeviceInfo {
@@ -641,6 +727,7 @@ impl ClientMfaServer {
                     location: location.clone(),
                     device: device.clone(),
                     method: *method,
+                    remembered: *remembered,
                 },
             )),
         })?;
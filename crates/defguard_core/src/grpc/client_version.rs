@@ -1,7 +1,18 @@
 use base64::{Engine, prelude::BASE64_STANDARD};
+use defguard_common::db::Id;
 use defguard_proto::proxy::{ClientPlatformInfo, DeviceInfo};
 use prost::Message;
 use semver::Version;
+use serde::Serialize;
+use tonic::Status;
+
+use crate::db::WireguardNetwork;
+
+/// Page from which Defguard clients can be downloaded.
+pub(crate) const CLIENT_DOWNLOAD_URL: &str = "https://defguard.net/download/";
+
+/// Operating system families reported by mobile clients.
+const MOBILE_OS_FAMILIES: [&str; 2] = ["android", "ios"];
 
 pub(crate) fn parse_client_version_platform(
     info: Option<&DeviceInfo>,
@@ -102,6 +113,84 @@ impl ClientFeature {
     }
 }
 
+/// Response returned to clients older than the minimum version required by a location.
+///
+/// It's sent to the proxy serialized as JSON in the message of a `FAILED_PRECONDITION` error,
+/// so clients can distinguish it from other errors and point users to the download page.
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct ClientUpdateRequired {
+    error: &'static str,
+    minimum_version: String,
+    current_version: Option<String>,
+    download_url: &'static str,
+}
+
+impl From<ClientUpdateRequired> for Status {
+    fn from(value: ClientUpdateRequired) -> Self {
+        match serde_json::to_string(&value) {
+            Ok(message) => Status::failed_precondition(message),
+            Err(err) => {
+                error!("Failed to serialize client update required response: {err}");
+                Status::failed_precondition("client update required")
+            }
+        }
+    }
+}
+
+/// Verifies that the client meets minimum client versions configured for given locations.
+///
+/// Mobile and desktop clients are distinguished by the reported OS family. Clients which
+/// don't report their version are treated as outdated if any minimum version is configured.
+pub(crate) fn check_min_client_version<'a, I>(
+    locations: I,
+    info: Option<&DeviceInfo>,
+) -> Result<(), ClientUpdateRequired>
+where
+    I: IntoIterator<Item = &'a WireguardNetwork<Id>>,
+{
+    let (version, platform) = parse_client_version_platform(info);
+    let is_mobile = platform.as_ref().is_some_and(|platform| {
+        MOBILE_OS_FAMILIES
+            .iter()
+            .any(|family| platform.os_family.eq_ignore_ascii_case(family))
+    });
+
+    // find the highest version required by any of the locations
+    let minimum_version = locations
+        .into_iter()
+        .filter_map(|location| {
+            let min_version = if is_mobile {
+                location.min_mobile_client_version.as_ref()
+            } else {
+                location.min_desktop_client_version.as_ref()
+            }?;
+            Version::parse(min_version)
+                .inspect_err(|err| {
+                    error!("Invalid minimum client version {min_version} for location {location}: {err}");
+                })
+                .ok()
+        })
+        .max();
+
+    let Some(minimum_version) = minimum_version else {
+        return Ok(());
+    };
+    if version
+        .as_ref()
+        .is_some_and(|version| version >= &minimum_version)
+    {
+        return Ok(());
+    }
+
+    info!("Client version {version:?} does not meet minimum required version {minimum_version}");
+    Err(ClientUpdateRequired {
+        error: "client_update_required",
+        minimum_version: minimum_version.to_string(),
+        current_version: version.map(|version| version.to_string()),
+        download_url: CLIENT_DOWNLOAD_URL,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -383,4 +472,58 @@ mod tests {
             "ServiceLocations should not be supported with pre-release version below minimum"
         );
     }
+
+    #[test]
+    fn test_check_min_client_version() {
+        let location_1 = WireguardNetwork {
+            min_desktop_client_version: Some("1.5.0".into()),
+            ..Default::default()
+        };
+        let location_2 = WireguardNetwork {
+            min_desktop_client_version: Some("1.6.0".into()),
+            min_mobile_client_version: Some("1.2.0".into()),
+            ..Default::default()
+        };
+        let location_3 = WireguardNetwork::default();
+        let locations = [location_1, location_2, location_3];
+        let linux = ClientPlatformInfo {
+            os_family: "linux".to_string(),
+            ..Default::default()
+        };
+        let ios = ClientPlatformInfo {
+            os_family: "ios".to_string(),
+            ..Default::default()
+        };
+
+        // no requirements
+        let info = create_device_info(Some("1.0.0".to_string()), Some(linux.clone()));
+        assert!(check_min_client_version(&locations[2..], Some(&info)).is_ok());
+
+        // the highest requirement applies
+        let info = create_device_info(Some("1.5.5".to_string()), Some(linux.clone()));
+        assert!(check_min_client_version(&locations[..1], Some(&info)).is_ok());
+        assert_eq!(
+            check_min_client_version(&locations, Some(&info)),
+            Err(ClientUpdateRequired {
+                error: "client_update_required",
+                minimum_version: "1.6.0".into(),
+                current_version: Some("1.5.5".into()),
+                download_url: CLIENT_DOWNLOAD_URL,
+            })
+        );
+        let info = create_device_info(Some("1.6.0".to_string()), Some(linux));
+        assert!(check_min_client_version(&locations, Some(&info)).is_ok());
+
+        // mobile clients have separate requirements
+        let info = create_device_info(Some("1.2.0".to_string()), Some(ios.clone()));
+        assert!(check_min_client_version(&locations, Some(&info)).is_ok());
+        let info = create_device_info(Some("1.1.0".to_string()), Some(ios));
+        assert!(check_min_client_version(&locations, Some(&info)).is_err());
+
+        // clients without version info are treated as outdated
+        let info = create_device_info(None, None);
+        assert!(check_min_client_version(&locations, Some(&info)).is_err());
+        assert!(check_min_client_version(&locations, None).is_err());
+        assert!(check_min_client_version(&locations[2..], None).is_ok());
+    }
 }
@@ -1,5 +1,6 @@
 use std::collections::HashSet;
 
+use chrono::Utc;
 use defguard_common::{
     csv::AsCsv,
     db::{
@@ -8,7 +9,7 @@ use defguard_common::{
     },
 };
 use defguard_mail::{
-    Mail,
+    Mail, MailCategory,
     templates::{self, TemplateLocation},
 };
 use defguard_proto::proxy::{
@@ -29,14 +30,17 @@ use tonic::Status;
 use super::InstanceInfo;
 use crate::{
     db::{
-        Device, GatewayEvent, User, WireguardNetwork,
+        AppEvent, Device, GatewayEvent, User, WireguardNetwork,
         models::{
             device::{DeviceConfig, DeviceInfo, DeviceType},
             enrollment::{ENROLLMENT_TOKEN_TYPE, Token, TokenError},
             polling_token::PollingToken,
+            webhook::{EnrollmentCompletedData, EnrollmentLocationData},
             wireguard::{LocationMfaMode, ServiceLocationMode},
         },
     },
+    device_approval::request_device_approvals,
+    device_naming::validate_device_name,
     enterprise::{
         db::models::{enterprise_settings::EnterpriseSettings, openid_provider::OpenIdProvider},
         ldap::utils::ldap_add_user,
@@ -44,16 +48,17 @@ use crate::{
     },
     events::{BidiRequestContext, BidiStreamEvent, BidiStreamEventType, EnrollmentEvent},
     grpc::{
-        client_version::ClientFeature,
+        client_version::{ClientFeature, parse_client_version_platform},
         utils::{build_device_config_response, new_polling_token, parse_client_ip_agent},
     },
     handlers::{
         mail::{
-            send_email_mfa_activation_email, send_mfa_configured_email, send_new_device_added_email,
+            send_device_approval_request_email, send_email_mfa_activation_email,
+            send_mfa_configured_email, send_new_device_added_email,
         },
         user::check_password_strength,
     },
-    headers::get_device_info,
+    headers::{get_device_info, get_user_agent_os},
     is_valid_phone_number, server_config,
 };
 
@@ -61,6 +66,7 @@ pub(super) struct EnrollmentServer {
     pool: PgPool,
     wireguard_tx: Sender<GatewayEvent>,
     mail_tx: UnboundedSender<Mail>,
+    webhook_tx: UnboundedSender<AppEvent>,
     bidi_event_tx: UnboundedSender<BidiStreamEvent>,
 }
 
@@ -70,12 +76,14 @@ impl EnrollmentServer {
         pool: PgPool,
         wireguard_tx: Sender<GatewayEvent>,
         mail_tx: UnboundedSender<Mail>,
+        webhook_tx: UnboundedSender<AppEvent>,
         bidi_event_tx: UnboundedSender<BidiStreamEvent>,
     ) -> Self {
         Self {
             pool,
             wireguard_tx,
             mail_tx,
+            webhook_tx,
             bidi_event_tx,
         }
     }
@@ -181,6 +189,8 @@ impl EnrollmentServer {
                 .start_session(
                     &mut transaction,
                     server_config().enrollment_session_timeout.as_secs(),
+                    1,
+                    info.as_ref(),
                 )
                 .await?;
             info!(
@@ -199,13 +209,12 @@ impl EnrollmentServer {
                 "Retrieving enterprise settings for enrollment of user {}({:?}).",
                 user.username, user.id
             );
-            let enterprise_settings =
-                EnterpriseSettings::get(&mut *transaction)
-                    .await
-                    .map_err(|err| {
-                        error!("Failed to get enterprise settings: {err}");
-                        Status::internal("unexpected error")
-                    })?;
+            let enterprise_settings = EnterpriseSettings::get_for_user(&mut *transaction, user.id)
+                .await
+                .map_err(|err| {
+                    error!("Failed to get enterprise settings: {err}");
+                    Status::internal("unexpected error")
+                })?;
             debug!("Enterprise settings: {enterprise_settings:?}");
 
             let vpn_setup_optional = settings.enrollment_vpn_step_optional;
@@ -510,7 +519,9 @@ impl EnrollmentServer {
             "Fetching enterprise settings for device creation process for user {}({:?})",
             user.username, user.id,
         );
-        let enterprise_settings = EnterpriseSettings::get(&self.pool).await.map_err(|err| {
+        let enterprise_settings = EnterpriseSettings::get_for_user(&self.pool, user.id)
+            .await
+            .map_err(|err| {
             error!(
             "Failed to fetch enterprise settings for device creation process for user {}({:?}): \
             {err}",
@@ -598,7 +609,10 @@ impl EnrollmentServer {
             Status::internal("unexpected error")
         })?;
 
-        let (device, network_info, configs) = if let Some(device_id) = enrollment_token.device_id {
+        let approval_expires_at = Utc::now().naive_utc() + *server_config().device_approval_timeout;
+        let (device, network_info, configs, approval_locations) = if let Some(device_id) =
+            enrollment_token.device_id
+        {
             debug!(
                 "A device with ID {device_id} is attached to a received enrollment token, trying \
                 to finish its configuration instead of creating a new one."
@@ -661,6 +675,17 @@ impl EnrollmentServer {
                 );
                 return Err(Status::not_found("network not found"));
             };
+            if !enrollment_token.allows_location(network.id) {
+                error!(
+                    "Network device {} added by user {}({:?}) is assigned to location {network}, \
+                    which the enrollment token doesn't allow. Aborting partial device \
+                    configuration process.",
+                    device.name, user.username, user.id
+                );
+                return Err(Status::permission_denied(
+                    "enrollment token is not valid for device location",
+                ));
+            }
             // We popped the last network, there should be 0 left.
             if !networks.is_empty() {
                 warn!(
@@ -681,7 +706,7 @@ impl EnrollmentServer {
                     Status::internal("unexpected error")
                 })?;
 
-            (device, vec![network_info], vec![configs])
+            (device, vec![network_info], vec![configs], Vec::new())
         } else {
             debug!(
                 "Creating new device for user {}({:?}): {}.",
@@ -700,6 +725,31 @@ impl EnrollmentServer {
                     "Cannot add a new device with no name. You may be trying to add a new user device as a network device. Defguard CLI supports only network devices.",
                 ));
             }
+            // prefer operating system reported by Defguard clients over the user agent
+            let os = parse_client_version_platform(req_device_info.as_ref())
+                .1
+                .map(|platform| platform.os_family)
+                .or_else(|| {
+                    req_device_info
+                        .as_ref()
+                        .and_then(|info| info.user_agent.as_deref())
+                        .and_then(get_user_agent_os)
+                });
+            validate_device_name(
+                &mut transaction,
+                &device.name,
+                &user,
+                os.as_deref(),
+                enrollment_token.location_ids.as_deref(),
+            )
+            .await
+            .map_err(|err| {
+                warn!(
+                    "User {}({:?}) can't add device {}: {err}",
+                    user.username, user.id, device.name
+                );
+                Status::from(err)
+            })?;
             let device = device.save(&mut *transaction).await.map_err(|err| {
                 error!(
                     "Failed to save device {}, pubkey {} for user {}({:?}): {err}",
@@ -709,12 +759,28 @@ impl EnrollmentServer {
             })?;
             info!("New device created using a token: {device:?}.");
             let _ = update_counts(&self.pool).await;
+            // locations requiring approval won't be configured until the device is approved
+            let approval_locations = request_device_approvals(
+                &mut transaction,
+                &device,
+                approval_expires_at,
+                enrollment_token.location_ids.as_deref(),
+            )
+            .await
+            .map_err(|err| {
+                error!(
+                    "Failed to request approval of device {} for user {}({:?}): {err}",
+                    device.name, user.username, user.id
+                );
+                Status::internal("unexpected error")
+            })?;
             debug!(
-                "Adding device {} to all existing user networks for user {}({:?}).",
+                "Adding device {} to existing user networks allowed by the enrollment token for \
+                user {}({:?}).",
                 device.wireguard_pubkey, user.username, user.id,
             );
             let (network_info, configs) = device
-                .add_to_all_networks(&mut transaction)
+                .add_to_networks(&mut transaction, enrollment_token.location_ids.as_deref())
                 .await
                 .map_err(|err| {
                     error!(
@@ -724,10 +790,10 @@ impl EnrollmentServer {
                     Status::internal("unexpected error")
                 })?;
             info!(
-                "Added device {} to all existing user networks for user {}({:?})",
+                "Added device {} to existing user networks for user {}({:?})",
                 device.wireguard_pubkey, user.username, user.id
             );
-            (device, network_info, configs)
+            (device, network_info, configs, approval_locations)
         };
 
         // get all locations affected by device being added
@@ -814,6 +880,34 @@ impl EnrollmentServer {
             Status::internal("unexpected error")
         })?;
 
+        // notify external systems (e.g. CMDB) through webhooks
+        let enrollment_data = EnrollmentCompletedData {
+            username: user.username.clone(),
+            email: user.email.clone(),
+            first_name: user.first_name.clone(),
+            last_name: user.last_name.clone(),
+            device_id: device.id,
+            device_name: device.name.clone(),
+            pubkey: device.wireguard_pubkey.clone(),
+            locations: configs
+                .iter()
+                .map(|config| EnrollmentLocationData {
+                    id: config.network_id,
+                    name: config.network_name.clone(),
+                    assigned_ips: config.address.clone(),
+                })
+                .collect(),
+        };
+        if let Err(err) = self
+            .webhook_tx
+            .send(AppEvent::EnrollmentCompleted(enrollment_data))
+        {
+            error!(
+                "Failed to trigger enrollment completed webhooks for device {}: {err}",
+                device.name
+            );
+        }
+
         // Don't send them service locations if they don't support it
         let configs = configs
             .into_iter()
@@ -847,6 +941,28 @@ impl EnrollmentServer {
         )
         .map_err(|_| Status::internal("error rendering email template"))?;
 
+        if !approval_locations.is_empty() {
+            let locations: Vec<String> = approval_locations
+                .into_iter()
+                .map(|location| location.name)
+                .collect();
+            if let Err(err) = send_device_approval_request_email(
+                &device,
+                &user.username,
+                &locations,
+                approval_expires_at,
+                &self.mail_tx,
+                &self.pool,
+            )
+            .await
+            {
+                error!(
+                    "Failed to notify admins about device {} awaiting approval: {err}",
+                    device.name
+                );
+            }
+        }
+
         info!("Device {} remote configuration done.", device.name);
 
         let openid_provider = OpenIdProvider::get_current(&self.pool)
@@ -891,7 +1007,7 @@ impl EnrollmentServer {
         device_info: Option<defguard_proto::proxy::DeviceInfo>,
     ) -> Result<DeviceConfigResponse, Status> {
         debug!("Getting network info for device: {:?}", request.pubkey);
-        let token = self.validate_session(request.token.as_ref()).await?;
+        let enrollment_token = self.validate_session(request.token.as_ref()).await?;
 
         Device::validate_pubkey(&request.pubkey).map_err(|_| {
             error!("Invalid pubkey {}", &request.pubkey);
@@ -904,7 +1020,7 @@ impl EnrollmentServer {
         };
 
         // check if device owner matches used enrollment token
-        if device.user_id != token.user_id {
+        if device.user_id != enrollment_token.user_id {
             error!(
                 "Enrollment token does not match device with pubkey {}",
                 request.pubkey
@@ -915,7 +1031,14 @@ impl EnrollmentServer {
         }
 
         let token = new_polling_token(&self.pool, &device).await?;
-        build_device_config_response(&self.pool, device, Some(token), device_info).await
+        let mut response =
+            build_device_config_response(&self.pool, device, Some(token), device_info).await?;
+        // only return configs of locations allowed by the enrollment token
+        response
+            .configs
+            .retain(|config| enrollment_token.allows_location(config.network_id));
+
+        Ok(response)
     }
 
     // TODO: Add events
@@ -1139,6 +1262,7 @@ impl Token {
                 .get_welcome_email_content(&mut *transaction, ip_address, device_info)
                 .await?,
             attachments: Vec::new(),
+            category: MailCategory::Enrollment,
             result_tx: None,
         };
         match mail_tx.send(mail) {
@@ -1175,6 +1299,7 @@ impl Token {
                 device_info,
             )?,
             attachments: Vec::new(),
+            category: MailCategory::Alert,
             result_tx: None,
         };
         match mail_tx.send(mail) {
@@ -32,6 +32,8 @@ pub struct ClientState {
     pub device: Device<Id>,
     pub user_id: Id,
     pub username: String,
+    // hostname of the gateway through which the client is connected
+    pub gateway_hostname: String,
     // current IP & port from which the client is connecting
     pub endpoint: SocketAddr,
     pub latest_handshake: NaiveDateTime,
@@ -48,6 +50,7 @@ impl ClientState {
     pub fn new(
         device: Device<Id>,
         user: &User<Id>,
+        gateway_hostname: &str,
         endpoint: SocketAddr,
         latest_handshake: NaiveDateTime,
         total_upload: i64,
@@ -58,6 +61,7 @@ impl ClientState {
             device,
             user_id: user.id,
             username: user.username.clone(),
+            gateway_hostname: gateway_hostname.into(),
             endpoint,
             latest_handshake,
             latest_update,
@@ -66,9 +70,18 @@ impl ClientState {
         }
     }
 
+    /// Checks if stats reported by a gateway are older than the current client state.
+    /// This happens when a client moves to another gateway and the previous one
+    /// keeps reporting the last known peer state.
+    #[must_use]
+    pub fn is_stale_report(&self, gateway_hostname: &str, latest_handshake: NaiveDateTime) -> bool {
+        self.gateway_hostname != gateway_hostname && latest_handshake < self.latest_handshake
+    }
+
     pub fn update_client_state(
         &mut self,
         current_device: Device<Id>,
+        gateway_hostname: &str,
         current_endpoint: SocketAddr,
         latest_handshake: NaiveDateTime,
         upload: i64,
@@ -76,6 +89,13 @@ impl ClientState {
     ) {
         self.latest_update = Utc::now().naive_utc();
         self.device = current_device;
+        if self.gateway_hostname != gateway_hostname {
+            info!(
+                "VPN client {} moved from gateway {} to {gateway_hostname}",
+                self.device.name, self.gateway_hostname
+            );
+            self.gateway_hostname = gateway_hostname.into();
+        }
         self.endpoint = current_endpoint;
         self.latest_handshake = latest_handshake;
         self.total_upload = upload;
@@ -142,6 +162,7 @@ impl ClientMap {
         let client_state = ClientState::new(
             device.clone(),
             user,
+            gateway_hostname,
             endpoint,
             stats.latest_handshake,
             stats.upload,
@@ -199,6 +220,21 @@ impl ClientMap {
         Ok(disconnected_clients)
     }
 
+    /// Returns IDs of all locations with connected VPN clients.
+    #[must_use]
+    pub fn location_ids(&self) -> Vec<Id> {
+        self.0
+            .iter()
+            .filter(|(_, location_map)| !location_map.is_empty())
+            .map(|(location_id, _)| *location_id)
+            .collect()
+    }
+
+    /// Removes all clients for a given location, e.g. after the location has been deleted.
+    pub fn remove_location(&mut self, location_id: Id) {
+        self.0.remove(&location_id);
+    }
+
     #[must_use]
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
@@ -0,0 +1,526 @@
+//! Distribution of devices between multiple gateways serving one location.
+//!
+//! By default all gateways of a location hold all its peers, the assignment only decides which
+//! gateway endpoint is handed out to a device. Assignments are kept stable: devices are moved
+//! only when their gateway disconnects, new devices and devices from disconnected gateways go to
+//! the gateway chosen by the distribution policy.
+//!
+//! Locations with peer sharding split peers between configured gateways instead, so each gateway
+//! holds only peers of devices assigned to it. Assignments don't depend on connected gateways
+//! then, as devices can only use the gateway holding their peer.
+
+use std::collections::{HashMap, HashSet};
+
+use defguard_common::db::Id;
+use defguard_proto::gateway::Peer;
+use sqlx::{PgPool, query};
+use tokio::runtime::Handle;
+
+use crate::db::{
+    WireguardNetwork,
+    models::{
+        gateway_distribution::{DeviceGatewayAssignment, LocationGateway},
+        wireguard::GatewayDistributionPolicy,
+    },
+};
+
+/// Gateway taking part in distribution.
+#[derive(Debug)]
+pub(crate) struct GatewayShare {
+    pub hostname: String,
+    pub weight: u32,
+    pub group_id: Option<Id>,
+}
+
+/// Device taking part in distribution.
+#[derive(Debug)]
+pub(crate) struct DistributedDevice {
+    pub id: Id,
+    /// Groups of the device owner.
+    pub group_ids: Vec<Id>,
+}
+
+/// Computes gateway assignment of all location devices.
+///
+/// `gateways` have to be sorted by hostname to get deterministic results.
+/// Returns an empty map if distribution is disabled. If there are no gateways, current
+/// assignments are kept as there's nowhere to move devices to. Unless `reassign` is set,
+/// devices keep their current gateway as long as it's available.
+pub(crate) fn distribute(
+    policy: GatewayDistributionPolicy,
+    gateways: &[GatewayShare],
+    devices: &[DistributedDevice],
+    current: &HashMap<Id, String>,
+    reassign: bool,
+) -> HashMap<Id, String> {
+    if policy == GatewayDistributionPolicy::None {
+        return HashMap::new();
+    }
+    if gateways.is_empty() {
+        return devices
+            .iter()
+            .filter_map(|device| {
+                current
+                    .get(&device.id)
+                    .map(|hostname| (device.id, hostname.clone()))
+            })
+            .collect();
+    }
+
+    let mut loads: Vec<u64> = vec![0; gateways.len()];
+    let mut assignment = HashMap::with_capacity(devices.len());
+    let mut unassigned = Vec::new();
+    for device in devices {
+        let index = if reassign {
+            None
+        } else {
+            current.get(&device.id).and_then(|hostname| {
+                gateways
+                    .iter()
+                    .position(|gateway| &gateway.hostname == hostname)
+            })
+        };
+        match index {
+            Some(index) => {
+                loads[index] += 1;
+                assignment.insert(device.id, gateways[index].hostname.clone());
+            }
+            None => unassigned.push(device),
+        }
+    }
+
+    let all: Vec<usize> = (0..gateways.len()).collect();
+    for device in unassigned {
+        let index = match policy {
+            GatewayDistributionPolicy::Hash => rendezvous(gateways, device.id),
+            GatewayDistributionPolicy::Group => {
+                // prefer gateways serving device owner groups, then gateways without a group
+                let mut candidates: Vec<usize> = all
+                    .iter()
+                    .copied()
+                    .filter(|index| {
+                        gateways[*index]
+                            .group_id
+                            .is_some_and(|group_id| device.group_ids.contains(&group_id))
+                    })
+                    .collect();
+                if candidates.is_empty() {
+                    candidates = all
+                        .iter()
+                        .copied()
+                        .filter(|index| gateways[*index].group_id.is_none())
+                        .collect();
+                }
+                if candidates.is_empty() {
+                    candidates.clone_from(&all);
+                }
+                least_loaded(gateways, &loads, &candidates, true)
+            }
+            GatewayDistributionPolicy::Weighted => least_loaded(gateways, &loads, &all, true),
+            _ => least_loaded(gateways, &loads, &all, false),
+        };
+        loads[index] += 1;
+        assignment.insert(device.id, gateways[index].hostname.clone());
+    }
+
+    assignment
+}
+
+/// Picks the candidate gateway with the lowest (load + 1) / weight ratio.
+fn least_loaded(
+    gateways: &[GatewayShare],
+    loads: &[u64],
+    candidates: &[usize],
+    weighted: bool,
+) -> usize {
+    let weight = |index: usize| {
+        if weighted {
+            u64::from(gateways[index].weight.max(1))
+        } else {
+            1
+        }
+    };
+    let mut best = candidates[0];
+    for &index in &candidates[1..] {
+        if (loads[index] + 1) * weight(best) < (loads[best] + 1) * weight(index) {
+            best = index;
+        }
+    }
+    best
+}
+
+/// Picks gateway with the highest hash of its hostname and device ID. Adding or removing
+/// a gateway moves only devices assigned to it.
+fn rendezvous(gateways: &[GatewayShare], device_id: Id) -> usize {
+    gateways
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, gateway)| sha256::digest(format!("{}/{device_id}", gateway.hostname)))
+        .map_or(0, |(index, _)| index)
+}
+
+/// Updates device gateway assignment of a location according to its distribution policy.
+///
+/// Connected gateways take part in distribution, unless the location uses peer sharding,
+/// in which case all configured gateways do.
+pub(crate) async fn rebalance_location(
+    pool: &PgPool,
+    location_id: Id,
+    connected_hostnames: &[String],
+    reassign: bool,
+) -> Result<(), sqlx::Error> {
+    let Some(location) = WireguardNetwork::find_by_id(pool, location_id).await? else {
+        return Ok(());
+    };
+    let configured = LocationGateway::all_for_location(pool, location_id).await?;
+    let gateways: Vec<GatewayShare> = if location.gateway_peer_sharding {
+        configured
+            .into_iter()
+            .map(|gateway| GatewayShare {
+                hostname: gateway.hostname,
+                weight: gateway.weight.max(1) as u32,
+                group_id: gateway.group_id,
+            })
+            .collect()
+    } else {
+        connected_hostnames
+            .iter()
+            .map(|hostname| {
+                let gateway = configured
+                    .iter()
+                    .find(|gateway| &gateway.hostname == hostname);
+                GatewayShare {
+                    hostname: hostname.clone(),
+                    weight: gateway.map_or(1, |gateway| gateway.weight.max(1) as u32),
+                    group_id: gateway.and_then(|gateway| gateway.group_id),
+                }
+            })
+            .collect()
+    };
+    let devices: Vec<DistributedDevice> = query!(
+        "SELECT wnd.device_id, ARRAY(SELECT group_id FROM group_user gu WHERE gu.user_id = d.user_id) \
+        \"group_ids!\" FROM wireguard_network_device wnd JOIN device d ON d.id = wnd.device_id \
+        WHERE wnd.wireguard_network_id = $1 ORDER BY wnd.device_id",
+        location_id
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|row| DistributedDevice {
+        id: row.device_id,
+        group_ids: row.group_ids,
+    })
+    .collect();
+    let current: HashMap<Id, String> = DeviceGatewayAssignment::all_for_location(pool, location_id)
+        .await?
+        .into_iter()
+        .map(|assignment| (assignment.device_id, assignment.hostname))
+        .collect();
+
+    let assignment = distribute(
+        location.gateway_distribution_policy,
+        &gateways,
+        &devices,
+        &current,
+        reassign,
+    );
+
+    let mut transaction = pool.begin().await?;
+    let removed: Vec<Id> = current
+        .keys()
+        .filter(|device_id| !assignment.contains_key(device_id))
+        .copied()
+        .collect();
+    if !removed.is_empty() {
+        DeviceGatewayAssignment::unassign_many(&mut *transaction, location_id, &removed).await?;
+    }
+    let mut moved = 0;
+    for (device_id, hostname) in &assignment {
+        if current.get(device_id) != Some(hostname) {
+            DeviceGatewayAssignment::assign(&mut *transaction, *device_id, location_id, hostname)
+                .await?;
+            moved += 1;
+        }
+    }
+    transaction.commit().await?;
+
+    if moved > 0 || !removed.is_empty() {
+        info!(
+            "Rebalanced gateways of location {location}: {moved} devices assigned, {} unassigned",
+            removed.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// Runs [`rebalance_location`] in the background, e.g. when a gateway connects or disconnects.
+pub(crate) fn spawn_rebalance(pool: PgPool, location_id: Id, connected_hostnames: Vec<String>) {
+    // may be called from `Drop` implementations, outside of the runtime during shutdown
+    let Ok(runtime) = Handle::try_current() else {
+        warn!("Skipping gateway rebalance of location {location_id}, runtime is not available");
+        return;
+    };
+    runtime.spawn(async move {
+        if let Err(err) = rebalance_location(&pool, location_id, &connected_hostnames, false).await
+        {
+            error!("Failed to rebalance gateway assignment of location {location_id}: {err}");
+        }
+    });
+}
+
+/// Returns hostname of the gateway holding a peer in a location with peer sharding.
+/// Devices added since the last rebalance are assigned first.
+pub(crate) async fn peer_gateway(
+    pool: &PgPool,
+    location_id: Id,
+    pubkey: &str,
+) -> Result<Option<String>, sqlx::Error> {
+    if let Some(hostname) =
+        DeviceGatewayAssignment::hostname_for_pubkey(pool, location_id, pubkey).await?
+    {
+        return Ok(Some(hostname));
+    }
+    rebalance_location(pool, location_id, &[], false).await?;
+    DeviceGatewayAssignment::hostname_for_pubkey(pool, location_id, pubkey).await
+}
+
+/// Filters location peers down to the ones held by a given gateway,
+/// if the location uses peer sharding.
+pub(crate) async fn shard_peers(
+    pool: &PgPool,
+    location: &WireguardNetwork<Id>,
+    hostname: &str,
+    peers: Vec<Peer>,
+) -> Result<Vec<Peer>, sqlx::Error> {
+    if !location.gateway_peer_sharding {
+        return Ok(peers);
+    }
+    rebalance_location(pool, location.id, &[], false).await?;
+    let pubkeys: HashSet<String> =
+        DeviceGatewayAssignment::pubkeys_for_gateway(pool, location.id, hostname)
+            .await?
+            .into_iter()
+            .collect();
+    let total = peers.len();
+    let peers: Vec<Peer> = peers
+        .into_iter()
+        .filter(|peer| pubkeys.contains(&peer.pubkey))
+        .collect();
+    debug!(
+        "Gateway {hostname} holds {} of {total} peers of location {location}",
+        peers.len()
+    );
+
+    Ok(peers)
+}
+
+/// Returns endpoint a device should connect to in a given location.
+/// In locations with peer sharding, devices added since the last rebalance are assigned first.
+pub(crate) async fn device_endpoint(
+    pool: &PgPool,
+    device_id: Id,
+    location: &WireguardNetwork<Id>,
+) -> Result<String, sqlx::Error> {
+    if location.gateway_peer_sharding {
+        rebalance_location(pool, location.id, &[], false).await?;
+    }
+    DeviceGatewayAssignment::device_endpoint(pool, device_id, location).await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn gateways(weights: &[(&str, u32)]) -> Vec<GatewayShare> {
+        weights
+            .iter()
+            .map(|(hostname, weight)| GatewayShare {
+                hostname: (*hostname).to_string(),
+                weight: *weight,
+                group_id: None,
+            })
+            .collect()
+    }
+
+    fn devices(ids: impl IntoIterator<Item = Id>) -> Vec<DistributedDevice> {
+        ids.into_iter()
+            .map(|id| DistributedDevice {
+                id,
+                group_ids: Vec::new(),
+            })
+            .collect()
+    }
+
+    fn count(assignment: &HashMap<Id, String>, hostname: &str) -> usize {
+        assignment.values().filter(|h| *h == hostname).count()
+    }
+
+    #[test]
+    fn test_distribute_disabled() {
+        let current = HashMap::from([(1, "gw1".to_string())]);
+        let assignment = distribute(
+            GatewayDistributionPolicy::None,
+            &gateways(&[("gw1", 1)]),
+            &devices([1, 2]),
+            &current,
+            false,
+        );
+        assert!(assignment.is_empty());
+    }
+
+    #[test]
+    fn test_distribute_round_robin() {
+        let assignment = distribute(
+            GatewayDistributionPolicy::RoundRobin,
+            &gateways(&[("gw1", 1), ("gw2", 5), ("gw3", 1)]),
+            &devices(1..=6),
+            &HashMap::new(),
+            false,
+        );
+        assert_eq!(assignment.len(), 6);
+        assert_eq!(count(&assignment, "gw1"), 2);
+        assert_eq!(count(&assignment, "gw2"), 2);
+        assert_eq!(count(&assignment, "gw3"), 2);
+    }
+
+    #[test]
+    fn test_distribute_weighted() {
+        let assignment = distribute(
+            GatewayDistributionPolicy::Weighted,
+            &gateways(&[("gw1", 1), ("gw2", 3)]),
+            &devices(1..=8),
+            &HashMap::new(),
+            false,
+        );
+        assert_eq!(count(&assignment, "gw1"), 2);
+        assert_eq!(count(&assignment, "gw2"), 6);
+    }
+
+    #[test]
+    fn test_distribute_rebalance_on_disconnect() {
+        let current: HashMap<Id, String> = HashMap::from([
+            (1, "gw1".into()),
+            (2, "gw2".into()),
+            (3, "gw3".into()),
+            (4, "gw1".into()),
+            (5, "gw2".into()),
+            (6, "gw3".into()),
+        ]);
+        // gw3 disconnected
+        let assignment = distribute(
+            GatewayDistributionPolicy::RoundRobin,
+            &gateways(&[("gw1", 1), ("gw2", 1)]),
+            &devices(1..=7),
+            &current,
+            false,
+        );
+        // devices of connected gateways stay in place
+        for device_id in [1, 2, 4, 5] {
+            assert_eq!(assignment[&device_id], current[&device_id]);
+        }
+        assert_eq!(count(&assignment, "gw3"), 0);
+        assert_eq!(assignment.len(), 7);
+        assert!(count(&assignment, "gw1").abs_diff(count(&assignment, "gw2")) <= 1);
+
+        // no gateway connected, assignment of existing devices is kept
+        let assignment = distribute(
+            GatewayDistributionPolicy::RoundRobin,
+            &[],
+            &devices([1, 2, 7]),
+            &current,
+            false,
+        );
+        assert_eq!(assignment.len(), 2);
+        assert_eq!(assignment[&1], "gw1");
+    }
+
+    #[test]
+    fn test_distribute_hash() {
+        let three = gateways(&[("gw1", 1), ("gw2", 1), ("gw3", 1)]);
+        let assignment = distribute(
+            GatewayDistributionPolicy::Hash,
+            &three,
+            &devices(1..=300),
+            &HashMap::new(),
+            false,
+        );
+        // deterministic and reasonably even
+        assert_eq!(
+            assignment,
+            distribute(
+                GatewayDistributionPolicy::Hash,
+                &three,
+                &devices(1..=300),
+                &HashMap::new(),
+                true,
+            )
+        );
+        for hostname in ["gw1", "gw2", "gw3"] {
+            assert!(count(&assignment, hostname) > 50);
+        }
+
+        // removing a gateway moves only its devices
+        let reassigned = distribute(
+            GatewayDistributionPolicy::Hash,
+            &gateways(&[("gw1", 1), ("gw2", 1)]),
+            &devices(1..=300),
+            &assignment,
+            true,
+        );
+        for (device_id, hostname) in &assignment {
+            if hostname != "gw3" {
+                assert_eq!(&reassigned[device_id], hostname);
+            }
+        }
+    }
+
+    #[test]
+    fn test_distribute_group() {
+        let mut gateways = gateways(&[("gw1", 1), ("gw2", 1), ("gw3", 1)]);
+        gateways[0].group_id = Some(10);
+        gateways[1].group_id = Some(20);
+        let devices = vec![
+            DistributedDevice {
+                id: 1,
+                group_ids: vec![20],
+            },
+            DistributedDevice {
+                id: 2,
+                group_ids: vec![10, 30],
+            },
+            DistributedDevice {
+                id: 3,
+                group_ids: vec![30],
+            },
+            DistributedDevice {
+                id: 4,
+                group_ids: Vec::new(),
+            },
+        ];
+        let current = HashMap::from([(1, "gw3".to_string())]);
+
+        let assignment = distribute(
+            GatewayDistributionPolicy::Group,
+            &gateways,
+            &devices,
+            &current,
+            false,
+        );
+        // current assignment is kept
+        assert_eq!(assignment[&1], "gw3");
+        assert_eq!(assignment[&2], "gw1");
+        // devices without matching groups use gateways without a group
+        assert_eq!(assignment[&3], "gw3");
+        assert_eq!(assignment[&4], "gw3");
+
+        let assignment = distribute(
+            GatewayDistributionPolicy::Group,
+            &gateways,
+            &devices,
+            &current,
+            true,
+        );
+        assert_eq!(assignment[&1], "gw2");
+    }
+}
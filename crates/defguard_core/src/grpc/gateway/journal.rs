@@ -0,0 +1,342 @@
+//! Gateway configuration journal.
+//!
+//! All [`GatewayEvent`]s are converted here into location-specific gateway [`Update`]s. Each update
+//! is stored in the journal with the next configuration epoch of its location, so it's possible
+//! to review what was pushed to gateways of a location.
+use std::collections::HashMap;
+
+use defguard_common::db::Id;
+use defguard_proto::{
+    enterprise::firewall::FirewallConfig,
+    gateway::{Peer, Update, update},
+};
+use prost::Message;
+use serde::Serialize;
+use sqlx::{Error as SqlxError, PgPool};
+use tokio::sync::broadcast::{Receiver, error::RecvError};
+use utoipa::ToSchema;
+
+use super::{network_update, peer};
+use crate::{
+    db::{
+        GatewayEvent, WireguardNetwork,
+        models::{device::DeviceInfo, gateway_journal::GatewayJournalEntry},
+    },
+    enterprise::db::models::firewall_config_version::FirewallConfigVersion,
+};
+
+// `UpdateType` values
+const CREATE: i32 = 0;
+const MODIFY: i32 = 1;
+const DELETE: i32 = 2;
+
+/// Event name of journal entries marking a gap.
+const GAP_EVENT: &str = "gap";
+
+pub(crate) struct GatewayJournal {
+    pool: PgPool,
+    events_rx: Receiver<GatewayEvent>,
+    // locations are cached to avoid fetching them for every peer update
+    locations: HashMap<Id, WireguardNetwork<Id>>,
+}
+
+impl GatewayJournal {
+    #[must_use]
+    pub(crate) fn new(pool: PgPool, events_rx: Receiver<GatewayEvent>) -> Self {
+        Self {
+            pool,
+            events_rx,
+            locations: HashMap::new(),
+        }
+    }
+
+    /// Process gateway events until the events channel is closed.
+    pub(crate) async fn run(mut self) {
+        info!("Starting gateway configuration journal");
+        loop {
+            match self.events_rx.recv().await {
+                Ok(event) => self.handle_event(event).await,
+                Err(RecvError::Lagged(count)) => {
+                    error!("Gateway configuration journal skipped {count} events");
+                    self.record_gap().await;
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+        info!("Gateway configuration journal stopped");
+    }
+
+    async fn handle_event(&mut self, event: GatewayEvent) {
+        debug!("Journaling gateway event: {event:?}");
+        let event_name = event_name(&event);
+        match event {
+            GatewayEvent::NetworkCreated(location_id, location) => {
+                let update = network_update(&location, Vec::new(), None, CREATE);
+                self.locations.insert(location_id, location);
+                self.journal(location_id, event_name, update).await;
+            }
+            GatewayEvent::NetworkModified(location_id, location, peers, firewall_config) => {
+                if let Some(firewall_config) = &firewall_config {
+                    self.record_firewall_config(location_id, firewall_config)
+                        .await;
+                }
+                let update = network_update(&location, peers, firewall_config, MODIFY);
+                self.locations.insert(location_id, location);
+                self.journal(location_id, event_name, update).await;
+            }
+            GatewayEvent::NetworkDeleted(location_id, _) => {
+                self.locations.remove(&location_id);
+                // journal isn't kept for deleted locations
+                match GatewayJournalEntry::delete_location(&self.pool, location_id).await {
+                    Ok(()) => debug!("Removed journal of deleted location {location_id}"),
+                    Err(err) => {
+                        error!("Failed to remove journal of deleted location {location_id}: {err}");
+                    }
+                }
+            }
+            GatewayEvent::DeviceCreated(device) => {
+                self.journal_peer(device, event_name, CREATE).await;
+            }
+            GatewayEvent::DeviceModified(device) => {
+                self.journal_peer(device, event_name, MODIFY).await;
+            }
+            GatewayEvent::DeviceDeleted(device) => {
+                for network_info in &device.network_info {
+                    let update = Update {
+                        update_type: DELETE,
+                        update: Some(update::Update::Peer(Peer {
+                            pubkey: device.device.wireguard_pubkey.clone(),
+                            allowed_ips: Vec::new(),
+                            preshared_key: None,
+                            keepalive_interval: None,
+                        })),
+                    };
+                    self.journal(network_info.network_id, event_name, update)
+                        .await;
+                }
+            }
+            GatewayEvent::FirewallConfigChanged(location_id, firewall_config) => {
+                self.record_firewall_config(location_id, &firewall_config)
+                    .await;
+                let update = Update {
+                    update_type: MODIFY,
+                    update: Some(update::Update::FirewallConfig(firewall_config)),
+                };
+                self.journal(location_id, event_name, update).await;
+            }
+            GatewayEvent::FirewallDisabled(location_id) => {
+                let update = Update {
+                    update_type: DELETE,
+                    update: Some(update::Update::DisableFirewall(())),
+                };
+                self.journal(location_id, event_name, update).await;
+            }
+        }
+    }
+
+    /// Journal peer updates in all locations a device belongs to.
+    async fn journal_peer(&mut self, device: DeviceInfo, event_name: &str, update_type: i32) {
+        for network_info in &device.network_info {
+            let location = match self.location(network_info.network_id).await {
+                Ok(Some(location)) => location,
+                Ok(None) => {
+                    debug!(
+                        "Location {} of device {} not found, skipping peer update",
+                        network_info.network_id, device.device.name
+                    );
+                    continue;
+                }
+                Err(err) => {
+                    error!(
+                        "Failed to fetch location {}: {err}",
+                        network_info.network_id
+                    );
+                    continue;
+                }
+            };
+            if location.mfa_enabled() && !network_info.is_authorized {
+                debug!(
+                    "WireGuard device {} is not authorized to connect to MFA enabled location {}",
+                    device.device.name, location.name
+                );
+                continue;
+            }
+            let update = Update {
+                update_type,
+                update: Some(update::Update::Peer(peer(
+                    &device.device.wireguard_pubkey,
+                    network_info,
+                    location.keepalive_interval,
+                ))),
+            };
+            self.journal(network_info.network_id, event_name, update)
+                .await;
+        }
+    }
+
+    /// Get location from cache or fetch it from the database.
+    async fn location(
+        &mut self,
+        location_id: Id,
+    ) -> Result<Option<&WireguardNetwork<Id>>, SqlxError> {
+        if !self.locations.contains_key(&location_id) {
+            match WireguardNetwork::find_by_id(&self.pool, location_id).await? {
+                Some(location) => {
+                    self.locations.insert(location_id, location);
+                }
+                None => return Ok(None),
+            }
+        }
+        Ok(self.locations.get(&location_id))
+    }
+
+    /// Store firewall configuration as a new version, so it can be reviewed and rolled back to.
+    async fn record_firewall_config(&self, location_id: Id, firewall_config: &FirewallConfig) {
+        let result = async {
+            let mut transaction = self.pool.begin().await?;
+            let version =
+                FirewallConfigVersion::record(&mut transaction, location_id, firewall_config, None)
+                    .await?;
+            transaction.commit().await?;
+            Ok::<_, SqlxError>(version)
+        }
+        .await;
+        match result {
+            Ok(Some(version)) => debug!(
+                "Recorded firewall configuration version {version} of location {location_id}"
+            ),
+            Ok(None) => debug!("Firewall configuration of location {location_id} is unchanged"),
+            Err(err) => error!(
+                "Failed to record firewall configuration version of location {location_id}: {err}"
+            ),
+        }
+    }
+
+    /// Store update in the journal, without private and preshared keys.
+    async fn journal(&self, location_id: Id, event_name: &str, mut update: Update) {
+        strip_keys(&mut update);
+        if let Err(err) = self
+            .record(location_id, event_name, Some(&update.encode_to_vec()))
+            .await
+        {
+            error!("Failed to journal update for location {location_id}: {err}");
+        }
+    }
+
+    /// Record a gap in journals of all locations, since it's unknown which ones were affected.
+    async fn record_gap(&mut self) {
+        // cached locations may be outdated after skipping events
+        self.locations.clear();
+        let locations = match WireguardNetwork::all(&self.pool).await {
+            Ok(locations) => locations,
+            Err(err) => {
+                error!("Failed to fetch locations: {err}");
+                return;
+            }
+        };
+        for location in locations {
+            if let Err(err) = self.record(location.id, GAP_EVENT, None).await {
+                error!("Failed to journal gap for location {location}: {err}");
+            }
+        }
+    }
+
+    async fn record(
+        &self,
+        location_id: Id,
+        event_name: &str,
+        payload: Option<&[u8]>,
+    ) -> Result<(), SqlxError> {
+        let mut transaction = self.pool.begin().await?;
+        let epoch =
+            GatewayJournalEntry::record(&mut transaction, location_id, event_name, payload).await?;
+        transaction.commit().await?;
+        debug!("Journaled {event_name} update of location {location_id} with epoch {epoch}");
+        Ok(())
+    }
+}
+
+/// Remove private and preshared keys, which must not be stored in the journal.
+fn strip_keys(update: &mut Update) {
+    match &mut update.update {
+        Some(update::Update::Network(config)) => {
+            config.prvkey.clear();
+            for peer in &mut config.peers {
+                peer.preshared_key = None;
+            }
+        }
+        Some(update::Update::Peer(peer)) => peer.preshared_key = None,
+        _ => (),
+    }
+}
+
+/// Summary of a journaled update.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UpdateSummary {
+    pub update_type: String,
+    pub kind: String,
+    // location name for network updates
+    pub name: Option<String>,
+    // peer public key for peer updates
+    pub pubkey: Option<String>,
+    // location addresses or peer allowed IPs
+    pub addresses: Vec<String>,
+    pub peers: Option<usize>,
+    pub firewall_rules: Option<usize>,
+}
+
+impl UpdateSummary {
+    /// Decode journal entry payload. Returns `None` for gaps and malformed payloads.
+    pub(crate) fn from_payload(payload: Option<&[u8]>) -> Option<Self> {
+        let update = Update::decode(payload?).ok()?;
+        let update_type = match update.update_type {
+            CREATE => "create",
+            MODIFY => "modify",
+            DELETE => "delete",
+            _ => "unknown",
+        };
+        let mut summary = Self {
+            update_type: update_type.into(),
+            kind: String::new(),
+            name: None,
+            pubkey: None,
+            addresses: Vec::new(),
+            peers: None,
+            firewall_rules: None,
+        };
+        match update.update? {
+            update::Update::Network(config) => {
+                summary.kind = "network".into();
+                summary.name = Some(config.name);
+                summary.addresses = config.addresses;
+                summary.peers = Some(config.peers.len());
+                summary.firewall_rules = config.firewall_config.map(|config| config.rules.len());
+            }
+            update::Update::Peer(peer) => {
+                summary.kind = "peer".into();
+                summary.pubkey = Some(peer.pubkey);
+                summary.addresses = peer.allowed_ips;
+            }
+            update::Update::FirewallConfig(config) => {
+                summary.kind = "firewall".into();
+                summary.firewall_rules = Some(config.rules.len());
+            }
+            update::Update::DisableFirewall(()) => summary.kind = "firewall_disabled".into(),
+        }
+        Some(summary)
+    }
+}
+
+/// Name of a gateway event stored in the journal.
+fn event_name(event: &GatewayEvent) -> &'static str {
+    match event {
+        GatewayEvent::NetworkCreated(..) => "network_created",
+        GatewayEvent::NetworkModified(..) => "network_modified",
+        GatewayEvent::NetworkDeleted(..) => "network_deleted",
+        GatewayEvent::DeviceCreated(_) => "device_created",
+        GatewayEvent::DeviceModified(_) => "device_modified",
+        GatewayEvent::DeviceDeleted(_) => "device_deleted",
+        GatewayEvent::FirewallConfigChanged(..) => "firewall_config_changed",
+        GatewayEvent::FirewallDisabled(_) => "firewall_disabled",
+    }
+}
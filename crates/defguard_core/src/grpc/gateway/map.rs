@@ -126,6 +126,7 @@ impl GatewayMap {
                 if is_reconnecting {
                     state.handle_reconnect_notification(pool);
                 }
+                state.enqueue_state_change_event(pool);
                 debug!(
                     "Gateway {hostname} found in gateway map, current state: {:?}",
                     state
@@ -156,6 +157,8 @@ impl GatewayMap {
                 state.connected = false;
                 state.disconnected_at = Some(Utc::now().naive_utc());
                 state.handle_disconnect_notification(pool);
+                state.record_disconnect(pool);
+                state.enqueue_state_change_event(pool);
                 debug!("Gateway {hostname} found in gateway map, current state: {state:?}");
                 info!("Gateway {hostname} disconnected in network {network_id}");
                 return Ok(());
@@ -177,6 +180,21 @@ impl GatewayMap {
         }
     }
 
+    /// Return sorted hostnames of connected gateways in a given network.
+    #[must_use]
+    pub(crate) fn connected_hostnames(&self, network_id: Id) -> Vec<String> {
+        let mut hostnames: Vec<String> = match self.0.get(&network_id) {
+            Some(network_gateway_map) => network_gateway_map
+                .values()
+                .filter(|gateway| gateway.connected)
+                .map(|gateway| gateway.hostname.clone())
+                .collect(),
+            None => Vec::new(),
+        };
+        hostnames.sort();
+        hostnames
+    }
+
     /// Return a list of all statuses of all gateways for a given network.
     #[must_use]
     pub fn get_network_gateway_status(&self, network_id: Id) -> Vec<GatewayState> {
@@ -1,4 +1,5 @@
 use std::{
+    collections::HashMap,
     net::{IpAddr, SocketAddr},
     pin::Pin,
     sync::{Arc, Mutex},
@@ -12,7 +13,7 @@ use defguard_mail::Mail;
 use defguard_proto::{
     enterprise::firewall::FirewallConfig,
     gateway::{
-        Configuration, ConfigurationRequest, Peer, PeerStats, StatsUpdate, Update,
+        Configuration, ConfigurationRequest, Peer, PeerStats, StatsUpdate, Update, UpdateType,
         gateway_service_server, stats_update, update,
     },
 };
@@ -31,16 +32,26 @@ use tokio::{
 use tokio_stream::Stream;
 use tonic::{Code, Request, Response, Status, metadata::MetadataMap};
 
-use self::map::GatewayMap;
+use self::{
+    distribution::{peer_gateway, rebalance_location, shard_peers, spawn_rebalance},
+    map::GatewayMap,
+};
 use crate::{
     db::{
         Device, GatewayEvent, User,
-        models::{wireguard::WireguardNetwork, wireguard_peer_stats::WireguardPeerStats},
+        models::{
+            device::{DeviceInfo, DeviceNetworkInfo},
+            wireguard::{GatewayDistributionPolicy, WireguardNetwork},
+            wireguard_peer_stats::WireguardPeerStats,
+        },
     },
     events::{GrpcEvent, GrpcRequestContext},
+    wireguard_stats_ingest::StatsIngestHandle,
 };
 
 pub mod client_state;
+pub(crate) mod distribution;
+pub(crate) mod journal;
 pub mod map;
 pub(crate) mod state;
 
@@ -88,6 +99,9 @@ pub struct GatewayServer {
     wireguard_tx: Sender<GatewayEvent>,
     mail_tx: UnboundedSender<Mail>,
     grpc_event_tx: UnboundedSender<GrpcEvent>,
+    stats_ingest: StatsIngestHandle,
+    // gateway events subscriptions made when sending configuration, by network ID and hostname
+    pending_events: Mutex<HashMap<(Id, String), BroadcastReceiver<GatewayEvent>>>,
 }
 
 impl WireguardNetwork<Id> {
@@ -112,12 +126,7 @@ impl WireguardNetwork<Id> {
         }
 
         let rows = query!(
-            "SELECT d.wireguard_pubkey pubkey, preshared_key, \
-                -- TODO possible to not use ARRAY-unnest here?
-                ARRAY(
-                    SELECT host(ip)
-                    FROM unnest(wnd.wireguard_ips) AS ip
-                ) \"allowed_ips!: Vec<String>\" \
+            "SELECT d.wireguard_pubkey pubkey, preshared_key, wnd.allowed_ips \
             FROM wireguard_network_device wnd \
             JOIN device d ON wnd.device_id = d.id \
             JOIN \"user\" u ON d.user_id = u.id \
@@ -138,10 +147,10 @@ impl WireguardNetwork<Id> {
             .map(|row| Peer {
                 pubkey: row.pubkey,
                 allowed_ips: row.allowed_ips,
-                // Don't send preshared key if MFA is not enabled, it can't be used and may
-                // cause issues with clients connecting if they expect no preshared key
-                // e.g. when you disable MFA on a location
-                preshared_key: if self.mfa_enabled() {
+                // Don't send preshared key if neither MFA nor static preshared keys are enabled,
+                // it can't be used and may cause issues with clients connecting if they expect
+                // no preshared key e.g. when you disable MFA on a location
+                preshared_key: if self.mfa_enabled() || self.static_preshared_keys() {
                     row.preshared_key
                 } else {
                     None
@@ -172,6 +181,7 @@ impl GatewayServer {
         wireguard_tx: Sender<GatewayEvent>,
         mail_tx: UnboundedSender<Mail>,
         grpc_event_tx: UnboundedSender<GrpcEvent>,
+        stats_ingest: StatsIngestHandle,
     ) -> Self {
         Self {
             pool,
@@ -180,6 +190,8 @@ impl GatewayServer {
             wireguard_tx,
             mail_tx,
             grpc_event_tx,
+            stats_ingest,
+            pending_events: Mutex::new(HashMap::new()),
         }
     }
 
@@ -332,8 +344,41 @@ pub(crate) fn gen_config(
     }
 }
 
+pub(crate) fn network_update(
+    network: &WireguardNetwork<Id>,
+    peers: Vec<Peer>,
+    firewall_config: Option<FirewallConfig>,
+    update_type: i32,
+) -> Update {
+    Update {
+        update_type,
+        update: Some(update::Update::Network(gen_config(
+            network,
+            peers,
+            firewall_config,
+        ))),
+    }
+}
+
+pub(crate) fn peer(
+    pubkey: &str,
+    network_info: &DeviceNetworkInfo,
+    keepalive_interval: i32,
+) -> Peer {
+    Peer {
+        pubkey: pubkey.to_string(),
+        allowed_ips: network_info
+            .device_wireguard_ips
+            .iter()
+            .map(IpAddr::to_string)
+            .collect(),
+        preshared_key: network_info.preshared_key.clone(),
+        keepalive_interval: Some(keepalive_interval as u32),
+    }
+}
+
 impl WireguardPeerStats {
-    fn from_peer_stats(stats: PeerStats, network_id: Id, device_id: Id) -> Self {
+    fn from_peer_stats(stats: PeerStats, network_id: Id, device_id: Id, gateway: &str) -> Self {
         let endpoint = match stats.endpoint {
             endpoint if endpoint.is_empty() => None,
             _ => Some(stats.endpoint),
@@ -350,17 +395,19 @@ impl WireguardPeerStats {
                 .unwrap_or_default()
                 .naive_utc(),
             allowed_ips: Some(stats.allowed_ips),
+            gateway: Some(gateway.to_string()),
         }
     }
 }
 
-/// Helper struct for handling gateway events
+/// Helper struct for handling gateway updates
 struct GatewayUpdatesHandler {
     network_id: Id,
     network: WireguardNetwork<Id>,
     gateway_hostname: String,
     events_rx: BroadcastReceiver<GatewayEvent>,
     tx: mpsc::Sender<Result<Update, Status>>,
+    pool: PgPool,
 }
 
 impl GatewayUpdatesHandler {
@@ -370,6 +417,7 @@ impl GatewayUpdatesHandler {
         gateway_hostname: String,
         events_rx: BroadcastReceiver<GatewayEvent>,
         tx: mpsc::Sender<Result<Update, Status>>,
+        pool: PgPool,
     ) -> Self {
         Self {
             network_id,
@@ -377,6 +425,7 @@ impl GatewayUpdatesHandler {
             gateway_hostname,
             events_rx,
             tx,
+            pool,
         }
     }
 
@@ -389,138 +438,12 @@ impl GatewayUpdatesHandler {
             "Starting update stream to gateway: {}, network {}",
             self.gateway_hostname, self.network
         );
-        while let Ok(update) = self.events_rx.recv().await {
-            debug!("Received WireGuard update: {update:?}");
-            let result = match update {
-                GatewayEvent::NetworkCreated(network_id, network) => {
-                    if network_id == self.network_id {
-                        self.send_network_update(&network, Vec::new(), None, 0)
-                            .await
-                    } else {
-                        Ok(())
-                    }
-                }
-                GatewayEvent::NetworkModified(
-                    network_id,
-                    network,
-                    peers,
-                    maybe_firewall_config,
-                ) => {
-                    if network_id == self.network_id {
-                        let result = self
-                            .send_network_update(&network, peers, maybe_firewall_config, 1)
-                            .await;
-                        // update stored network data
-                        self.network = network;
-                        result
-                    } else {
-                        Ok(())
-                    }
-                }
-                GatewayEvent::NetworkDeleted(network_id, network_name) => {
-                    if network_id == self.network_id {
-                        self.send_network_delete(&network_name).await
-                    } else {
-                        Ok(())
-                    }
-                }
-                GatewayEvent::DeviceCreated(device) => {
-                    // check if a peer has to be added in the current network
-                    match device
-                        .network_info
-                        .iter()
-                        .find(|info| info.network_id == self.network_id)
-                    {
-                        Some(network_info) => {
-                            if self.network.mfa_enabled() && !network_info.is_authorized {
-                                debug!(
-                                    "Created WireGuard device {} is not authorized to connect to MFA enabled location {}",
-                                    device.device.name, self.network.name
-                                );
-                                continue;
-                            }
-                            self.send_peer_update(
-                                Peer {
-                                    pubkey: device.device.wireguard_pubkey,
-                                    allowed_ips: network_info
-                                        .device_wireguard_ips
-                                        .iter()
-                                        .map(IpAddr::to_string)
-                                        .collect(),
-                                    preshared_key: network_info.preshared_key.clone(),
-                                    keepalive_interval: Some(
-                                        self.network.keepalive_interval as u32,
-                                    ),
-                                },
-                                0,
-                            )
-                            .await
-                        }
-                        None => Ok(()),
-                    }
-                }
-                GatewayEvent::DeviceModified(device) => {
-                    // check if a peer has to be updated in the current network
-                    match device
-                        .network_info
-                        .iter()
-                        .find(|info| info.network_id == self.network_id)
-                    {
-                        Some(network_info) => {
-                            if self.network.mfa_enabled() && !network_info.is_authorized {
-                                debug!(
-                                    "Modified WireGuard device {} is not authorized to connect to MFA enabled location {}",
-                                    device.device.name, self.network.name
-                                );
-                                continue;
-                            }
-                            self.send_peer_update(
-                                Peer {
-                                    pubkey: device.device.wireguard_pubkey,
-                                    allowed_ips: network_info
-                                        .device_wireguard_ips
-                                        .iter()
-                                        .map(IpAddr::to_string)
-                                        .collect(),
-                                    preshared_key: network_info.preshared_key.clone(),
-                                    keepalive_interval: Some(
-                                        self.network.keepalive_interval as u32,
-                                    ),
-                                },
-                                1,
-                            )
-                            .await
-                        }
-                        None => Ok(()),
-                    }
-                }
-                GatewayEvent::DeviceDeleted(device) => {
-                    // check if a peer has to be updated in the current network
-                    match device
-                        .network_info
-                        .iter()
-                        .find(|info| info.network_id == self.network_id)
-                    {
-                        Some(_) => self.send_peer_delete(&device.device.wireguard_pubkey).await,
-                        None => Ok(()),
-                    }
-                }
-                GatewayEvent::FirewallConfigChanged(location_id, firewall_config) => {
-                    if location_id == self.network_id {
-                        self.send_firewall_update(firewall_config).await
-                    } else {
-                        Ok(())
-                    }
-                }
-                GatewayEvent::FirewallDisabled(location_id) => {
-                    if location_id == self.network_id {
-                        self.send_firewall_disable().await
-                    } else {
-                        Ok(())
-                    }
-                }
+        while let Ok(event) = self.events_rx.recv().await {
+            debug!("Received WireGuard update: {event:?}");
+            let Some(update) = self.event_update(event) else {
+                continue;
             };
-            if result.is_err() {
+            if self.send_update(update).await.is_err() {
                 error!(
                     "Closing update steam to gateway: {}, network {}",
                     self.gateway_hostname, self.network
@@ -530,170 +453,165 @@ impl GatewayUpdatesHandler {
         }
     }
 
-    /// Sends updated network configuration
-    async fn send_network_update(
-        &self,
-        network: &WireguardNetwork<Id>,
-        peers: Vec<Peer>,
-        firewall_config: Option<FirewallConfig>,
-        update_type: i32,
-    ) -> Result<(), Status> {
-        debug!("Sending network update for network {network}");
-        if let Err(err) = self
-            .tx
-            .send(Ok(Update {
-                update_type,
-                update: Some(update::Update::Network(Configuration {
-                    name: network.name.clone(),
-                    prvkey: network.prvkey.clone(),
-                    addresses: network.address.iter().map(ToString::to_string).collect(),
-                    port: network.port as u32,
+    /// Convert gateway event to an update of the network being serviced.
+    ///
+    /// Returns `None` if the event isn't relevant for the network.
+    fn event_update(&mut self, event: GatewayEvent) -> Option<Update> {
+        match event {
+            GatewayEvent::NetworkCreated(network_id, network) if network_id == self.network_id => {
+                Some(network_update(
+                    &network,
+                    Vec::new(),
+                    None,
+                    UpdateType::Create.into(),
+                ))
+            }
+            GatewayEvent::NetworkModified(network_id, network, peers, maybe_firewall_config)
+                if network_id == self.network_id =>
+            {
+                let update = network_update(
+                    &network,
                     peers,
-                    firewall_config,
-                })),
-            }))
-            .await
-        {
-            let msg = format!(
-                "Failed to send network update, network {network}, update type: {update_type} ({}), error: {err}",
-                if update_type == 0 { "CREATE" } else { "MODIFY" },
-            );
-            error!(msg);
-            return Err(Status::new(Code::Internal, msg));
+                    maybe_firewall_config,
+                    UpdateType::Modify.into(),
+                );
+                // update stored network data
+                self.network = network;
+                Some(update)
+            }
+            GatewayEvent::NetworkDeleted(network_id, network_name)
+                if network_id == self.network_id =>
+            {
+                Some(Update {
+                    update_type: UpdateType::Delete.into(),
+                    update: Some(update::Update::Network(Configuration {
+                        name: network_name,
+                        ..Default::default()
+                    })),
+                })
+            }
+            GatewayEvent::DeviceCreated(device) => {
+                self.peer_update(&device, UpdateType::Create.into())
+            }
+            GatewayEvent::DeviceModified(device) => {
+                self.peer_update(&device, UpdateType::Modify.into())
+            }
+            GatewayEvent::DeviceDeleted(device) => {
+                // check if a peer has to be removed from the current network
+                device
+                    .network_info
+                    .iter()
+                    .any(|info| info.network_id == self.network_id)
+                    .then(|| Update {
+                        update_type: UpdateType::Delete.into(),
+                        update: Some(update::Update::Peer(Peer {
+                            pubkey: device.device.wireguard_pubkey,
+                            allowed_ips: Vec::new(),
+                            preshared_key: None,
+                            keepalive_interval: None,
+                        })),
+                    })
+            }
+            GatewayEvent::FirewallConfigChanged(location_id, firewall_config)
+                if location_id == self.network_id =>
+            {
+                Some(Update {
+                    update_type: UpdateType::Modify.into(),
+                    update: Some(update::Update::FirewallConfig(firewall_config)),
+                })
+            }
+            GatewayEvent::FirewallDisabled(location_id) if location_id == self.network_id => {
+                Some(Update {
+                    update_type: UpdateType::Delete.into(),
+                    update: Some(update::Update::DisableFirewall(())),
+                })
+            }
+            _ => None,
         }
-        debug!("Network update sent for network {network}");
-        Ok(())
     }
 
-    /// Sends delete network command to gateway
-    async fn send_network_delete(&self, network_name: &str) -> Result<(), Status> {
-        debug!(
-            "Sending network delete command for network {}",
-            self.network
-        );
-        if let Err(err) = self
-            .tx
-            .send(Ok(Update {
-                update_type: 2,
-                update: Some(update::Update::Network(Configuration {
-                    name: network_name.to_string(),
-                    prvkey: String::new(),
-                    addresses: Vec::new(),
-                    port: 0,
-                    peers: Vec::new(),
-                    firewall_config: None,
-                })),
-            }))
-            .await
-        {
-            let msg = format!(
-                "Failed to send network update, network {}, update type: 2 (DELETE), error: {err}",
-                self.network,
+    /// Peer update of a device, if it belongs to the current network.
+    fn peer_update(&self, device: &DeviceInfo, update_type: i32) -> Option<Update> {
+        let network_info = device
+            .network_info
+            .iter()
+            .find(|info| info.network_id == self.network_id)?;
+        if self.network.mfa_enabled() && !network_info.is_authorized {
+            debug!(
+                "WireGuard device {} is not authorized to connect to MFA enabled location {}",
+                device.device.name, self.network.name
             );
-            error!(msg);
-            return Err(Status::new(Code::Internal, msg));
+            return None;
         }
-        debug!("Network delete command sent for network {}", self.network);
-        Ok(())
+        Some(Update {
+            update_type,
+            update: Some(update::Update::Peer(peer(
+                &device.device.wireguard_pubkey,
+                network_info,
+                self.network.keepalive_interval,
+            ))),
+        })
     }
 
-    /// Send update peer command to gateway
-    async fn send_peer_update(&self, peer: Peer, update_type: i32) -> Result<(), Status> {
-        debug!("Sending peer update for network {}", self.network);
-        if let Err(err) = self
-            .tx
-            .send(Ok(Update {
-                update_type,
-                update: Some(update::Update::Peer(peer)),
-            }))
-            .await
-        {
-            let msg = format!(
-                "Failed to send peer update for network {}, update type: {update_type} ({}), error: {err}",
-                self.network,
-                if update_type == 0 { "CREATE" } else { "MODIFY" },
-            );
-            error!(msg);
-            return Err(Status::new(Code::Internal, msg));
+    /// Limit update to peers held by the gateway, if the location uses peer sharding.
+    ///
+    /// Returns `None` if the update concerns a peer held by another gateway.
+    async fn shard_update(&mut self, mut update: Update) -> Result<Option<Update>, SqlxError> {
+        if update.update_type() == UpdateType::Delete {
+            return Ok(Some(update));
         }
-        debug!("Peer update sent for network {}", self.network);
-        Ok(())
-    }
-
-    /// Send delete peer command to gateway
-    async fn send_peer_delete(&self, peer_pubkey: &str) -> Result<(), Status> {
-        debug!("Sending peer delete for network {}", self.network);
-        if let Err(err) = self
-            .tx
-            .send(Ok(Update {
-                update_type: 2,
-                update: Some(update::Update::Peer(Peer {
-                    pubkey: peer_pubkey.into(),
-                    allowed_ips: Vec::new(),
-                    preshared_key: None,
-                    keepalive_interval: None,
-                })),
-            }))
-            .await
-        {
-            let msg = format!(
-                "Failed to send peer update for network {}, peer {peer_pubkey}, update type: 2 (DELETE), error: {err}",
-                self.network,
-            );
-            error!(msg);
-            return Err(Status::new(Code::Internal, msg));
+        match &mut update.update {
+            Some(update::Update::Network(configuration)) => {
+                // peer sharding may have changed along with location configuration
+                if let Some(network) =
+                    WireguardNetwork::find_by_id(&self.pool, self.network_id).await?
+                {
+                    self.network = network;
+                }
+                let peers = std::mem::take(&mut configuration.peers);
+                configuration.peers =
+                    shard_peers(&self.pool, &self.network, &self.gateway_hostname, peers).await?;
+            }
+            Some(update::Update::Peer(peer)) if self.network.gateway_peer_sharding => {
+                let hostname = peer_gateway(&self.pool, self.network_id, &peer.pubkey).await?;
+                if hostname.as_deref() != Some(&self.gateway_hostname) {
+                    debug!(
+                        "Skipping update of peer {} held by gateway {hostname:?}, network {}",
+                        peer.pubkey, self.network
+                    );
+                    return Ok(None);
+                }
+            }
+            _ => (),
         }
-        debug!("Peer delete command sent for network {}", self.network);
-        Ok(())
-    }
 
-    /// Send firewall config update command to gateway
-    async fn send_firewall_update(&self, firewall_config: FirewallConfig) -> Result<(), Status> {
-        debug!(
-            "Sending firewall config update for network {} with config {firewall_config:?}",
-            self.network
-        );
-        if let Err(err) = self
-            .tx
-            .send(Ok(Update {
-                update_type: 1,
-                update: Some(update::Update::FirewallConfig(firewall_config)),
-            }))
-            .await
-        {
-            let msg = format!(
-                "Failed to send firewall config update for network {}, error: {err}",
-                self.network,
-            );
-            error!(msg);
-            return Err(Status::new(Code::Internal, msg));
-        }
-        debug!("Firewall config update sent for network {}", self.network);
-        Ok(())
+        Ok(Some(update))
     }
 
-    /// Send firewall disable command to gateway
-    async fn send_firewall_disable(&self) -> Result<(), Status> {
-        debug!(
-            "Sending firewall disable command for network {}",
-            self.network
-        );
-        if let Err(err) = self
-            .tx
-            .send(Ok(Update {
-                update_type: 2,
-                update: Some(update::Update::DisableFirewall(())),
-            }))
-            .await
-        {
+    /// Send update to gateway
+    async fn send_update(&mut self, update: Update) -> Result<(), Status> {
+        let update = match self.shard_update(update).await {
+            Ok(Some(update)) => update,
+            Ok(None) => return Ok(()),
+            Err(err) => {
+                let msg = format!(
+                    "Failed to determine peers of gateway {}, network {}: {err}",
+                    self.gateway_hostname, self.network
+                );
+                error!(msg);
+                return Err(Status::new(Code::Internal, msg));
+            }
+        };
+        debug!("Sending update for network {}: {update:?}", self.network);
+        if let Err(err) = self.tx.send(Ok(update)).await {
             let msg = format!(
-                "Failed to send firewall disable command for network {}, error: {err}",
+                "Failed to send update for network {}, error: {err}",
                 self.network,
             );
             error!(msg);
             return Err(Status::new(Code::Internal, msg));
         }
-        debug!("Firewall disable command sent for network {}", self.network);
+        debug!("Update sent for network {}", self.network);
         Ok(())
     }
 }
@@ -705,6 +623,7 @@ pub struct GatewayUpdatesStream {
     gateway_hostname: String,
     gateway_state: Arc<Mutex<GatewayMap>>,
     pool: PgPool,
+    gateway_distribution_policy: GatewayDistributionPolicy,
 }
 
 impl GatewayUpdatesStream {
@@ -716,6 +635,7 @@ impl GatewayUpdatesStream {
         gateway_hostname: String,
         gateway_state: Arc<Mutex<GatewayMap>>,
         pool: PgPool,
+        gateway_distribution_policy: GatewayDistributionPolicy,
     ) -> Self {
         Self {
             task_handle,
@@ -724,6 +644,7 @@ impl GatewayUpdatesStream {
             gateway_hostname,
             gateway_state,
             pool,
+            gateway_distribution_policy,
         }
     }
 }
@@ -743,11 +664,17 @@ impl Drop for GatewayUpdatesStream {
         self.task_handle.abort();
         // update gateway state
         // TODO: possibly use a oneshot channel instead
-        self.gateway_state
-            .lock()
-            .unwrap()
-            .disconnect_gateway(self.network_id, self.gateway_hostname.clone(), &self.pool)
-            .expect("Unable to disconnect gateway.");
+        let connected_hostnames = {
+            let mut gateway_state = self.gateway_state.lock().unwrap();
+            gateway_state
+                .disconnect_gateway(self.network_id, self.gateway_hostname.clone(), &self.pool)
+                .expect("Unable to disconnect gateway.");
+            gateway_state.connected_hostnames(self.network_id)
+        };
+        // move devices assigned to the disconnected gateway
+        if self.gateway_distribution_policy != GatewayDistributionPolicy::None {
+            spawn_rebalance(self.pool.clone(), self.network_id, connected_hostnames);
+        }
     }
 }
 
@@ -805,6 +732,18 @@ impl gateway_service_server::GatewayService for GatewayServer {
                             device,
                         })?;
                     };
+
+                    // assign gateways to devices added since last rebalance
+                    if location.gateway_distribution_policy != GatewayDistributionPolicy::None {
+                        let connected_hostnames =
+                            self.gateway_state.lock().unwrap().connected_hostnames(network_id);
+                        if let Err(err) =
+                            rebalance_location(&self.pool, network_id, &connected_hostnames, false)
+                                .await
+                        {
+                            error!("Failed to rebalance gateway assignment of location {network_id}: {err}");
+                        }
+                    }
                     continue;
                 }
             };
@@ -837,7 +776,8 @@ impl gateway_service_server::GatewayService for GatewayServer {
             let location = self.fetch_location_from_db(network_id).await?;
 
             // convert stats to DB storage format
-            let stats = WireguardPeerStats::from_peer_stats(peer_stats, network_id, device_id);
+            let stats =
+                WireguardPeerStats::from_peer_stats(peer_stats, network_id, device_id, &hostname);
 
             // only perform client state update if stats include an endpoint IP
             // otherwise a peer was added to the gateway interface
@@ -860,14 +800,22 @@ impl gateway_service_server::GatewayService for GatewayServer {
                     // update connected clients map
                     match client_map.get_vpn_client(network_id, &public_key) {
                         Some(client_state) => {
-                            // update connected client state
-                            client_state.update_client_state(
-                                device,
-                                socket_addr,
-                                stats.latest_handshake,
-                                stats.upload,
-                                stats.download,
-                            );
+                            if client_state.is_stale_report(&hostname, stats.latest_handshake) {
+                                debug!(
+                                    "Ignoring stale stats of VPN client {public_key} reported by \
+                                    gateway {hostname}"
+                                );
+                            } else {
+                                // update connected client state
+                                client_state.update_client_state(
+                                    device,
+                                    &hostname,
+                                    socket_addr,
+                                    stats.latest_handshake,
+                                    stats.upload,
+                                    stats.download,
+                                );
+                            }
                         }
                         None => {
                             // don't mark inactive peers as connected
@@ -917,19 +865,8 @@ impl gateway_service_server::GatewayService for GatewayServer {
                 }
             }
 
-            // Save stats to db
-            let stats = match stats.save(&self.pool).await {
-                Ok(stats) => stats,
-                Err(err) => {
-                    error!("Saving WireGuard peer stats to db failed: {err}");
-                    return Err(Status::new(
-                        Code::Internal,
-                        format!("Saving WireGuard peer stats to db failed: {err}"),
-                    ));
-                }
-            };
-            info!("Saved WireGuard peer stats to db.");
-            debug!("WireGuard peer stats: {stats:?}");
+            // queue stats to be saved to db in batches
+            self.stats_ingest.submit(stats);
         }
 
         Ok(Response::new(()))
@@ -975,18 +912,27 @@ impl gateway_service_server::GatewayService for GatewayServer {
 
         debug!("Sending configuration to gateway client, network {network}.");
 
+        let request = request.into_inner();
+        // subscribe before building configuration, events emitted in the meantime
+        // will be sent through the updates stream
+        let events_rx = self.wireguard_tx.subscribe();
+
         // store connected gateway in memory
         {
             let mut state = self.gateway_state.lock().unwrap();
             state.add_gateway(
                 network_id,
                 &network.name,
-                hostname,
-                request.into_inner().name,
+                hostname.clone(),
+                request.name,
                 self.mail_tx.clone(),
                 version,
             );
         }
+        self.pending_events
+            .lock()
+            .unwrap()
+            .insert((network_id, hostname.clone()), events_rx);
 
         network.connected_at = Some(Utc::now().naive_utc());
         if let Err(err) = network.save(&mut *conn).await {
@@ -1000,6 +946,15 @@ impl gateway_service_server::GatewayService for GatewayServer {
                 format!("Failed to retrieve peers from the database for network: {network_id}"),
             )
         })?;
+        let peers = shard_peers(&self.pool, &network, &hostname, peers)
+            .await
+            .map_err(|err| {
+                error!("Failed to determine peers of gateway {hostname}, network {network}: {err}");
+                Status::new(
+                    Code::Internal,
+                    format!("Failed to determine peers of gateway for network: {network_id}"),
+                )
+            })?;
         let maybe_firewall_config =
             network
                 .try_get_firewall_config(&mut conn)
@@ -1052,23 +1007,54 @@ impl gateway_service_server::GatewayService for GatewayServer {
         info!("New client connected to updates stream: {hostname}, network {network}",);
 
         let (tx, rx) = mpsc::channel(4);
-        let events_rx = self.wireguard_tx.subscribe();
-        let mut state = self.gateway_state.lock().unwrap();
-        state
-            .connect_gateway(network_id, &hostname, &self.pool)
-            .map_err(|err| {
-                error!("Failed to connect gateway on network {network_id}: {err}");
-                Status::new(
-                    Code::Internal,
-                    format!("Failed to connect gateway on network {network_id}"),
-                )
-            })?;
+        // configuration sent to the gateway is a snapshot taken after subscribing to events,
+        // so events emitted since then are sent through the updates stream
+        let Some(events_rx) = self
+            .pending_events
+            .lock()
+            .unwrap()
+            .remove(&(network_id, hostname.clone()))
+        else {
+            warn!(
+                "Gateway {hostname} connected to updates stream of network {network} without \
+                fetching configuration first"
+            );
+            return Err(Status::new(
+                Code::FailedPrecondition,
+                format!("Configuration of network {network_id} has to be fetched first"),
+            ));
+        };
+
+        let connected_hostnames = {
+            let mut gateway_state = self.gateway_state.lock().unwrap();
+            gateway_state
+                .connect_gateway(network_id, &hostname, &self.pool)
+                .map_err(|err| {
+                    error!("Failed to connect gateway on network {network_id}: {err}");
+                    Status::new(
+                        Code::Internal,
+                        format!("Failed to connect gateway on network {network_id}"),
+                    )
+                })?;
+            gateway_state.connected_hostnames(network_id)
+        };
+        let gateway_distribution_policy = network.gateway_distribution_policy;
+        if gateway_distribution_policy != GatewayDistributionPolicy::None {
+            spawn_rebalance(self.pool.clone(), network_id, connected_hostnames);
+        }
 
         // clone here before moving into a closure
         let gateway_hostname = hostname.clone();
+        let pool = self.pool.clone();
         let handle = tokio::spawn(async move {
-            let mut update_handler =
-                GatewayUpdatesHandler::new(network_id, network, gateway_hostname, events_rx, tx);
+            let mut update_handler = GatewayUpdatesHandler::new(
+                network_id,
+                network,
+                gateway_hostname,
+                events_rx,
+                tx,
+                pool,
+            );
             update_handler.run().await;
         });
 
@@ -1079,6 +1065,96 @@ impl gateway_service_server::GatewayService for GatewayServer {
             hostname,
             Arc::clone(&self.gateway_state),
             self.pool.clone(),
+            gateway_distribution_policy,
         )))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use defguard_common::db::setup_pool;
+    use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+    use tokio::sync::broadcast;
+
+    use super::*;
+    use crate::db::models::device::{DeviceType, WireguardNetworkDevice};
+
+    #[sqlx::test]
+    async fn test_updates_for_serviced_network(_: PgPoolOptions, options: PgConnectOptions) {
+        let pool = setup_pool(options).await;
+        let mut network = WireguardNetwork::<Id>::default();
+        network.id = 1;
+        let (events_tx, events_rx) = broadcast::channel(16);
+        let (tx, mut rx) = mpsc::channel(16);
+
+        // events of other networks are skipped
+        events_tx.send(GatewayEvent::FirewallDisabled(2)).unwrap();
+        events_tx.send(GatewayEvent::FirewallDisabled(1)).unwrap();
+        events_tx
+            .send(GatewayEvent::NetworkDeleted(2, "other".into()))
+            .unwrap();
+        events_tx
+            .send(GatewayEvent::NetworkDeleted(1, "network".into()))
+            .unwrap();
+        drop(events_tx);
+
+        let mut handler =
+            GatewayUpdatesHandler::new(network.id, network, "gateway".into(), events_rx, tx, pool);
+        handler.run().await;
+        drop(handler);
+
+        let mut updates = Vec::new();
+        while let Some(update) = rx.recv().await {
+            updates.push(update.unwrap().update.unwrap());
+        }
+        assert_eq!(updates.len(), 2);
+        assert_eq!(updates[0], update::Update::DisableFirewall(()));
+        assert!(matches!(&updates[1], update::Update::Network(config) if config.name == "network"));
+    }
+
+    #[sqlx::test]
+    async fn test_peer_allowed_ips(_: PgPoolOptions, options: PgConnectOptions) {
+        let pool = setup_pool(options).await;
+        let mut network = WireguardNetwork::default();
+        network.try_set_address("10.1.1.1/24,fd00::1/64").unwrap();
+        let network = network.save(&pool).await.unwrap();
+        let user = User::new(
+            "testuser",
+            Some("hunter2"),
+            "Tester",
+            "Test",
+            "test@test.com",
+            None,
+        )
+        .save(&pool)
+        .await
+        .unwrap();
+        let device = Device::new(
+            "device".into(),
+            "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=".into(),
+            user.id,
+            DeviceType::User,
+            None,
+            true,
+        )
+        .save(&pool)
+        .await
+        .unwrap();
+        let mut network_device = WireguardNetworkDevice::new(
+            network.id,
+            device.id,
+            vec!["10.1.1.2".parse().unwrap(), "fd00::2".parse().unwrap()],
+        );
+        network_device.insert(&pool).await.unwrap();
+
+        let peers = network.get_peers(&pool).await.unwrap();
+        assert_eq!(peers.len(), 1);
+        assert_eq!(peers[0].allowed_ips, ["10.1.1.2", "fd00::2"]);
+
+        // allowed IPs follow changes of device addresses
+        network_device.wireguard_ips = vec!["10.1.1.3".parse().unwrap()];
+        network_device.update(&pool).await.unwrap();
+        let peers = network.get_peers(&pool).await.unwrap();
+        assert_eq!(peers[0].allowed_ips, ["10.1.1.3"]);
+    }
+}
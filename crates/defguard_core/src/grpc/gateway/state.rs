@@ -1,7 +1,10 @@
 use std::time::Duration;
 
 use chrono::NaiveDateTime;
-use defguard_common::db::{Id, models::Settings};
+use defguard_common::{
+    config::server_config,
+    db::{Id, models::Settings},
+};
 use defguard_mail::Mail;
 use defguard_version::{DefguardComponent, tracing::VersionInfo};
 use semver::Version;
@@ -13,8 +16,10 @@ use utoipa::ToSchema;
 use uuid::Uuid;
 
 use crate::{
+    db::models::event_outbox::DomainEvent,
     grpc::MIN_GATEWAY_VERSION,
     handlers::mail::{send_gateway_disconnected_email, send_gateway_reconnected_email},
+    security_summary::record_gateway_disconnect,
 };
 
 #[derive(Clone, Debug, Serialize, ToSchema)]
@@ -60,6 +65,48 @@ impl GatewayState {
         }
     }
 
+    /// Store gateway connection state change in the event outbox.
+    pub(super) fn enqueue_state_change_event(&self, pool: &PgPool) {
+        if !server_config().event_outbox_enabled() {
+            return;
+        }
+        let (location_id, location_name, hostname) = (
+            self.network_id,
+            self.network_name.clone(),
+            self.hostname.clone(),
+        );
+        let event = if self.connected {
+            DomainEvent::GatewayConnected {
+                location_id,
+                location_name,
+                hostname,
+            }
+        } else {
+            DomainEvent::GatewayDisconnected {
+                location_id,
+                location_name,
+                hostname,
+            }
+        };
+        let pool = pool.clone();
+        tokio::spawn(async move {
+            if let Err(err) = event.enqueue(&pool).await {
+                error!("Failed to store gateway state change event in the outbox: {err}");
+            }
+        });
+    }
+
+    /// Store gateway disconnect, so unstable gateways can be reported in security summary.
+    pub(super) fn record_disconnect(&self, pool: &PgPool) {
+        let (location_id, hostname) = (self.network_id, self.hostname.clone());
+        let pool = pool.clone();
+        tokio::spawn(async move {
+            if let Err(err) = record_gateway_disconnect(&pool, location_id, &hostname).await {
+                error!("Failed to store disconnect of gateway {hostname}: {err}");
+            }
+        });
+    }
+
     /// Checks if gateway disconnect notification should be sent.
     pub(super) fn handle_disconnect_notification(&mut self, pool: &PgPool) {
         debug!("Checking if gateway disconnect notification needs to be sent");
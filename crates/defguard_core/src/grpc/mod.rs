@@ -63,9 +63,11 @@ use crate::{
         ldap::utils::ldap_update_user_state,
     },
     events::{BidiStreamEvent, GrpcEvent},
-    grpc::gateway::{client_state::ClientMap, map::GatewayMap},
+    grpc::gateway::{client_state::ClientMap, journal::GatewayJournal, map::GatewayMap},
+    handlers::mail::send_proxy_connectivity_email,
     server_config,
     version::{IncompatibleComponents, IncompatibleProxyData, is_proxy_version_supported},
+    wireguard_stats_ingest::StatsIngestor,
 };
 
 static VERSION_ZERO: Version = Version::new(0, 0, 0);
@@ -300,7 +302,7 @@ async fn handle_proxy_message_loop(
                     Some(core_request::Payload::ClientMfaStart(request)) => {
                         match context
                             .client_mfa_server
-                            .start_client_mfa_login(request)
+                            .start_client_mfa_login(request, received.device_info)
                             .await
                         {
                             Ok(response_payload) => {
@@ -547,12 +549,53 @@ async fn handle_proxy_message_loop(
     Ok(())
 }
 
+/// Respond to requests received from proxy in `resp_stream` until the stream is closed.
+/// Each request is answered with a single response with the same ID, sent to `tx`.
+/// Used by tests to talk to core over an in-memory stream instead of a proxy connection.
+pub async fn run_proxy_message_loop(
+    pool: PgPool,
+    wireguard_tx: Sender<GatewayEvent>,
+    mail_tx: UnboundedSender<Mail>,
+    webhook_tx: UnboundedSender<AppEvent>,
+    bidi_event_tx: UnboundedSender<BidiStreamEvent>,
+    tx: UnboundedSender<CoreResponse>,
+    resp_stream: &mut Streaming<CoreRequest>,
+    endpoint_uri: &Uri,
+) -> Result<(), anyhow::Error> {
+    let mut enrollment_server = EnrollmentServer::new(
+        pool.clone(),
+        wireguard_tx.clone(),
+        mail_tx.clone(),
+        webhook_tx,
+        bidi_event_tx.clone(),
+    );
+    let mut password_reset_server =
+        PasswordResetServer::new(pool.clone(), mail_tx.clone(), bidi_event_tx.clone());
+    let mut client_mfa_server =
+        ClientMfaServer::new(pool.clone(), mail_tx, wireguard_tx.clone(), bidi_event_tx);
+    let mut polling_server = PollingServer::new(pool.clone());
+
+    handle_proxy_message_loop(ProxyMessageLoopContext {
+        pool,
+        tx,
+        wireguard_tx,
+        resp_stream,
+        enrollment_server: &mut enrollment_server,
+        password_reset_server: &mut password_reset_server,
+        client_mfa_server: &mut client_mfa_server,
+        polling_server: &mut polling_server,
+        endpoint_uri,
+    })
+    .await
+}
+
 /// Bi-directional gRPC stream for communication with Defguard Proxy.
 #[instrument(skip_all)]
 pub async fn run_grpc_bidi_stream(
     pool: PgPool,
     wireguard_tx: Sender<GatewayEvent>,
     mail_tx: UnboundedSender<Mail>,
+    webhook_tx: UnboundedSender<AppEvent>,
     bidi_event_tx: UnboundedSender<BidiStreamEvent>,
     incompatible_components: Arc<RwLock<IncompatibleComponents>>,
 ) -> Result<(), anyhow::Error> {
@@ -563,12 +606,17 @@ pub async fn run_grpc_bidi_stream(
         pool.clone(),
         wireguard_tx.clone(),
         mail_tx.clone(),
+        webhook_tx,
         bidi_event_tx.clone(),
     );
     let mut password_reset_server =
         PasswordResetServer::new(pool.clone(), mail_tx.clone(), bidi_event_tx.clone());
-    let mut client_mfa_server =
-        ClientMfaServer::new(pool.clone(), mail_tx, wireguard_tx.clone(), bidi_event_tx);
+    let mut client_mfa_server = ClientMfaServer::new(
+        pool.clone(),
+        mail_tx.clone(),
+        wireguard_tx.clone(),
+        bidi_event_tx,
+    );
     let mut polling_server = PollingServer::new(pool.clone());
 
     let endpoint = Endpoint::from_shared(config.proxy_url.as_deref().unwrap())?;
@@ -584,6 +632,8 @@ pub async fn run_grpc_bidi_stream(
         endpoint.tls_config(ClientTlsConfig::new().with_enabled_roots())?
     };
 
+    // admins are alerted about lost connections only, not about failed connection attempts
+    let mut connection_lost = false;
     loop {
         debug!("Connecting to proxy at {}", endpoint.uri());
         let interceptor = ClientVersionInterceptor::new(Version::parse(VERSION)?);
@@ -638,6 +688,10 @@ pub async fn run_grpc_bidi_stream(
         IncompatibleComponents::remove_proxy(&incompatible_components);
 
         info!("Connected to proxy at {}", endpoint.uri());
+        if connection_lost {
+            connection_lost = false;
+            notify_proxy_connectivity(endpoint.uri(), true, &mail_tx, &pool);
+        }
         let mut resp_stream = response.into_inner();
         handle_proxy_message_loop(ProxyMessageLoopContext {
             pool: pool.clone(),
@@ -651,9 +705,28 @@ pub async fn run_grpc_bidi_stream(
             endpoint_uri: endpoint.uri(),
         })
         .await?;
+        connection_lost = true;
+        notify_proxy_connectivity(endpoint.uri(), false, &mail_tx, &pool);
     }
 }
 
+/// Alert admins about lost or restored connection to the proxy in the background.
+fn notify_proxy_connectivity(
+    uri: &Uri,
+    connected: bool,
+    mail_tx: &UnboundedSender<Mail>,
+    pool: &PgPool,
+) {
+    let (proxy_url, mail_tx, pool) = (uri.to_string(), mail_tx.clone(), pool.clone());
+    tokio::spawn(async move {
+        if let Err(err) =
+            send_proxy_connectivity_email(&proxy_url, connected, &mail_tx, &pool).await
+        {
+            error!("Failed to send proxy connectivity notification: {err}");
+        }
+    });
+}
+
 /// Runs gRPC server with core services.
 #[instrument(skip_all)]
 pub async fn run_grpc_server(
@@ -668,6 +741,7 @@ pub async fn run_grpc_server(
     failed_logins: Arc<Mutex<FailedLoginMap>>,
     grpc_event_tx: UnboundedSender<GrpcEvent>,
     incompatible_components: Arc<RwLock<IncompatibleComponents>>,
+    stats_pool: PgPool,
 ) -> Result<(), anyhow::Error> {
     // Build gRPC services
     let server = if let (Some(cert), Some(key)) = (grpc_cert, grpc_key) {
@@ -688,6 +762,7 @@ pub async fn run_grpc_server(
         failed_logins,
         grpc_event_tx,
         incompatible_components,
+        stats_pool,
     )
     .await?;
 
@@ -715,6 +790,7 @@ pub async fn build_grpc_service_router(
     failed_logins: Arc<Mutex<FailedLoginMap>>,
     grpc_event_tx: UnboundedSender<GrpcEvent>,
     incompatible_components: Arc<RwLock<IncompatibleComponents>>,
+    stats_pool: PgPool,
 ) -> Result<Router, anyhow::Error> {
     let auth_service = AuthServiceServer::new(AuthServer::new(pool.clone(), failed_logins));
 
@@ -737,6 +813,20 @@ pub async fn build_grpc_service_router(
     let router = {
         use crate::version::GatewayVersionInterceptor;
 
+        // journal updates pushed to gateways
+        tokio::spawn(GatewayJournal::new(pool.clone(), wireguard_tx.subscribe()).run());
+
+        // write peer stats reported by gateways in batches
+        let config = server_config();
+        let (stats_ingestor, stats_ingest) = StatsIngestor::new(
+            stats_pool,
+            config.stats_ingest_shards,
+            config.stats_ingest_batch_size,
+            *config.stats_ingest_flush_interval,
+            config.stats_ingest_queue_size,
+        );
+        tokio::spawn(stats_ingestor.run());
+
         let gateway_service = GatewayServiceServer::new(GatewayServer::new(
             pool,
             gateway_state,
@@ -744,6 +834,7 @@ pub async fn build_grpc_service_router(
             wireguard_tx,
             mail_tx,
             grpc_event_tx,
+            stats_ingest,
         ));
 
         let own_version = Version::parse(VERSION)?;
@@ -1,3 +1,4 @@
+use defguard_common::db::models::Settings;
 use defguard_mail::Mail;
 use defguard_proto::proxy::{
     DeviceInfo, PasswordResetInitializeRequest, PasswordResetRequest, PasswordResetStartRequest,
@@ -10,7 +11,7 @@ use tonic::Status;
 use crate::{
     db::{
         User,
-        models::enrollment::{PASSWORD_RESET_TOKEN_TYPE, Token},
+        models::enrollment::{PASSWORD_RESET_TOKEN_TYPE, Token, TokenError},
     },
     enterprise::ldap::utils::ldap_change_password,
     events::{BidiRequestContext, BidiStreamEvent, BidiStreamEventType, PasswordResetEvent},
@@ -67,7 +68,7 @@ impl PasswordResetServer {
             return Err(Status::permission_denied("invalid token"));
         }
 
-        if enrollment.is_session_valid(server_config().enrollment_session_timeout.as_secs()) {
+        if enrollment.is_session_valid(server_config().password_reset_session_timeout.as_secs()) {
             info!("Password reset session validated: {enrollment:?}.",);
             Ok(enrollment)
         } else {
@@ -97,6 +98,7 @@ impl PasswordResetServer {
         req_device_info: Option<DeviceInfo>,
     ) -> Result<(), Status> {
         let config = server_config();
+        let settings = Settings::get_current_settings();
         debug!("Starting password reset request");
 
         let ip_address;
@@ -145,7 +147,7 @@ impl PasswordResetServer {
             user.id,
             None,
             Some(email.clone()),
-            config.password_reset_token_timeout.as_secs(),
+            settings.password_reset_token_timeout().as_secs(),
             Some(PASSWORD_RESET_TOKEN_TYPE.to_string()),
         );
         enrollment.save(&mut *transaction).await?;
@@ -158,7 +160,8 @@ impl PasswordResetServer {
             &user,
             &self.mail_tx,
             config.enrollment_url.clone(),
-            &enrollment.id,
+            &enrollment,
+            settings.password_reset_max_uses,
             Some(&ip_address),
             Some(&device_info),
         )?;
@@ -218,6 +221,8 @@ impl PasswordResetServer {
             .start_session(
                 &mut transaction,
                 server_config().password_reset_session_timeout.as_secs(),
+                Settings::get_current_settings().password_reset_max_uses,
+                info.as_ref(),
             )
             .await?;
 
@@ -286,6 +291,18 @@ impl PasswordResetServer {
             Status::internal("unexpected error")
         })?;
 
+        // password reset link can't be used again once the password has been changed
+        if !enrollment
+            .invalidate_password_reset_tokens(&mut transaction)
+            .await?
+        {
+            error!(
+                "Password reset token for user {} has already been used",
+                user.username
+            );
+            return Err(TokenError::TokenUsed.into());
+        }
+
         // update user
         user.set_password(&request.password);
         user.save(&mut *transaction).await.map_err(|err| {
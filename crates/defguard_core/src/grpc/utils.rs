@@ -18,15 +18,13 @@ use crate::{
         models::{
             device::{DeviceType, WireguardNetworkDevice},
             polling_token::PollingToken,
-            wireguard::{
-                LocationMfaMode, ServiceLocationMode, WireguardNetwork, get_allowed_ips_for_device,
-            },
+            wireguard::{LocationMfaMode, ServiceLocationMode, WireguardNetwork},
         },
     },
     enterprise::db::models::{
         enterprise_settings::EnterpriseSettings, openid_provider::OpenIdProvider,
     },
-    grpc::client_version::ClientFeature,
+    grpc::{client_version::ClientFeature, gateway::distribution::device_endpoint},
 };
 
 // Create a new token for configuration polling.
@@ -71,6 +69,23 @@ pub(crate) async fn new_polling_token(
     Ok(new_token.token)
 }
 
+/// Endpoint of the gateway assigned to a device in a location served by multiple gateways.
+async fn assigned_endpoint(
+    pool: &PgPool,
+    device: &Device<Id>,
+    location: &WireguardNetwork<Id>,
+) -> Result<String, Status> {
+    device_endpoint(pool, device.id, location)
+        .await
+        .map_err(|err| {
+            error!(
+                "Failed to fetch gateway assignment of device {} in location {}: {err}",
+                device.name, location.name
+            );
+            Status::internal(format!("unexpected error: {err}"))
+        })
+}
+
 pub(crate) async fn build_device_config_response(
     pool: &PgPool,
     device: Device<Id>,
@@ -89,7 +104,13 @@ pub(crate) async fn build_device_config_response(
         Status::internal(format!("unexpected error: {err}"))
     })?;
 
-    let enterprise_settings = EnterpriseSettings::get(pool).await.map_err(|err| {
+    // groups of the owner don't apply to network devices
+    let enterprise_settings = if device.device_type == DeviceType::Network {
+        EnterpriseSettings::get(pool).await
+    } else {
+        EnterpriseSettings::get_for_user(pool, device.user_id).await
+    }
+    .map_err(|err| {
         error!("Failed to get enterprise settings: {err}");
         Status::internal(format!("unexpected error: {err}"))
     })?;
@@ -116,7 +137,7 @@ pub(crate) async fn build_device_config_response(
                 Status::internal(format!("unexpected error: {err}"))
             })?;
         if let Some(wireguard_network_device) = wireguard_network_device {
-            let location = wireguard_network_device
+            let mut location = wireguard_network_device
                 .network(pool)
                 .await
                 .map_err(|err| {
@@ -126,6 +147,7 @@ pub(crate) async fn build_device_config_response(
                     );
                     Status::internal(format!("unexpected error: {err}"))
                 })?;
+            location.endpoint = assigned_endpoint(pool, &device, &location).await?;
 
             if location.service_location_mode != ServiceLocationMode::Disabled {
                 error!(
@@ -139,20 +161,29 @@ pub(crate) async fn build_device_config_response(
 
             // DEPRECATED(1.5): superseeded by location_mfa_mode
             let mfa_enabled = location.location_mfa_mode == LocationMfaMode::Internal;
-            let allowed_ips = get_allowed_ips_for_device(&enterprise_settings, &location).as_csv();
+            let allowed_ips = location
+                .device_allowed_ips(pool, &device, &enterprise_settings)
+                .await
+                .map_err(|err| {
+                    error!(
+                        "Failed to fetch allowed IPs for device {} in location {}: {err}",
+                        device.name, location.name
+                    );
+                    Status::internal(format!("unexpected error: {err}"))
+                })?;
             let config =
                 ProtoDeviceConfig {
                     config: Device::create_config(
                         &location,
                         &wireguard_network_device,
-                        &enterprise_settings,
+                        &allowed_ips,
                     ),
                     network_id: location.id,
                     network_name: location.name,
                     assigned_ip: wireguard_network_device.wireguard_ips.as_csv(),
                     endpoint: format!("{}:{}", location.endpoint, location.port),
                     pubkey: location.pubkey,
-                    allowed_ips,
+                    allowed_ips: allowed_ips.as_csv(),
                     dns: location.dns,
                     keepalive_interval: location.keepalive_interval,
                     #[allow(deprecated)]
@@ -206,20 +237,31 @@ pub(crate) async fn build_device_config_response(
             }
             // DEPRECATED(1.5): superseeded by location_mfa_mode
             let mfa_enabled = location.location_mfa_mode == LocationMfaMode::Internal;
-            let allowed_ips = get_allowed_ips_for_device(&enterprise_settings, &location).as_csv();
             if let Some(wireguard_network_device) = wireguard_network_device {
+                let mut location = location;
+                location.endpoint = assigned_endpoint(pool, &device, &location).await?;
+                let allowed_ips = location
+                    .device_allowed_ips(pool, &device, &enterprise_settings)
+                    .await
+                    .map_err(|err| {
+                        error!(
+                            "Failed to fetch allowed IPs for device {} in location {}: {err}",
+                            device.name, location.name
+                        );
+                        Status::internal(format!("unexpected error: {err}"))
+                    })?;
                 let config = ProtoDeviceConfig {
                     config: Device::create_config(
                         &location,
                         &wireguard_network_device,
-                        &enterprise_settings,
+                        &allowed_ips,
                     ),
                     network_id: location.id,
                     network_name: location.name,
                     assigned_ip: wireguard_network_device.wireguard_ips.as_csv(),
                     endpoint: format!("{}:{}", location.endpoint, location.port),
                     pubkey: location.pubkey,
-                    allowed_ips,
+                    allowed_ips: allowed_ips.as_csv(),
                     dns: location.dns,
                     keepalive_interval: location.keepalive_interval,
                     #[allow(deprecated)]
@@ -1,17 +1,41 @@
-use std::fmt::{self, Display, Formatter};
+use std::{
+    convert::Infallible,
+    fmt::{self, Display, Formatter},
+};
 
-use axum::extract::State;
+use axum::{
+    Extension,
+    extract::State,
+    response::sse::{Event, KeepAlive, Sse},
+};
 use axum_extra::extract::Query;
+use bytes::Bytes;
 use chrono::{DateTime, NaiveDateTime, Utc};
 use defguard_common::db::Id;
 use ipnetwork::IpNetwork;
+use serde_json::{Value, json};
 use sqlx::{FromRow, Postgres, QueryBuilder, Type};
+use tokio::{
+    select, spawn,
+    sync::{
+        broadcast::{Sender, error::RecvError},
+        mpsc,
+    },
+};
+use tokio_stream::wrappers::ReceiverStream;
 
 use super::{
     DEFAULT_API_PAGE_SIZE,
-    pagination::{PaginatedApiResponse, PaginatedApiResult, PaginationMeta, PaginationParams},
+    pagination::{
+        PaginatedApiResponse, PaginatedApiResult, PaginationParams, get_pagination_metadata,
+    },
+    redaction::Redaction,
+};
+use crate::{
+    appstate::AppState,
+    auth::{AdminRole, SessionInfo},
+    db::models::activity_log::ActivityLogModule,
 };
-use crate::{appstate::AppState, auth::SessionInfo, db::models::activity_log::ActivityLogModule};
 
 #[derive(Debug, Deserialize, Default)]
 pub struct FilterParams {
@@ -128,11 +152,12 @@ pub struct ApiActivityLogEvent {
 /// Returns a paginated list of `ApiActivityLogEvent` objects or `WebError` if error occurs.
 pub async fn get_activity_log_events(
     session_info: SessionInfo,
+    redaction: Redaction,
     State(appstate): State<AppState>,
     pagination: Query<PaginationParams>,
     filters: Query<FilterParams>,
     sorting: Query<SortParams>,
-) -> PaginatedApiResult<ApiActivityLogEvent> {
+) -> PaginatedApiResult<Value> {
     debug!("Fetching activity log with filters {filters:?} and pagination {pagination:?}");
     // start with base SELECT query
     // dummy WHERE filter is use to enable composable filtering
@@ -140,8 +165,8 @@ pub async fn get_activity_log_events(
         "SELECT id, timestamp, user_id, username, location, ip, event, module, device, description FROM activity_log_event WHERE 1=1 ",
     );
 
-    // filter events for non-admin users to show only their own events
-    if !session_info.is_admin {
+    // filter events for users other than admins and auditors to show only their own events
+    if !session_info.is_admin && !session_info.user.is_auditor(&appstate.pool).await? {
         query_builder
             .push(" AND username = ")
             .push_bind(session_info.user.username)
@@ -178,10 +203,17 @@ pub async fn get_activity_log_events(
 
     let pagination = get_pagination_metadata(pagination.page, total_items as u32);
 
-    Ok(PaginatedApiResponse {
-        data: events,
-        pagination,
-    })
+    // personal data is redacted for auditors
+    let data = events
+        .iter()
+        .map(|event| {
+            let mut json = json!(event);
+            redaction.apply(&mut json);
+            json
+        })
+        .collect();
+
+    Ok(PaginatedApiResponse { data, pagination })
 }
 
 /// Adds optional filtering statements to SQL query based on request query params
@@ -259,20 +291,97 @@ fn apply_sorting(query_builder: &mut QueryBuilder<Postgres>, sorting: &SortParam
         .push(sorting.sort_order.to_string());
 }
 
-/// Prepares pagination metadata that's part of the response
-fn get_pagination_metadata(current_page: u32, total_items: u32) -> PaginationMeta {
-    let total_pages = (total_items).div_ceil(DEFAULT_API_PAGE_SIZE);
-    let next_page = if current_page < total_pages {
-        Some(current_page + 1)
-    } else {
-        None
-    };
-
-    PaginationMeta {
-        current_page,
-        page_size: DEFAULT_API_PAGE_SIZE,
-        total_items,
-        total_pages,
-        next_page,
+/// Size of the per-client buffer of events waiting to be sent over live tail connection.
+const LIVE_TAIL_BUFFER: usize = 256;
+
+#[derive(Debug, Deserialize, Default)]
+pub struct LiveTailParams {
+    #[serde(default)]
+    pub username: Vec<String>,
+    #[serde(default)]
+    pub location: Vec<String>,
+    #[serde(default)]
+    pub event: Vec<String>,
+}
+
+/// Activity log event fields used by live tail filters.
+#[derive(Deserialize)]
+struct LiveTailEvent {
+    username: String,
+    location: Option<String>,
+    event: String,
+}
+
+impl LiveTailParams {
+    fn matches(&self, event: &LiveTailEvent) -> bool {
+        (self.username.is_empty() || self.username.contains(&event.username))
+            && (self.event.is_empty() || self.event.contains(&event.event))
+            && (self.location.is_empty()
+                || event
+                    .location
+                    .as_ref()
+                    .is_some_and(|location| self.location.contains(location)))
     }
 }
+
+/// Live tail of activity log events
+///
+/// Streams activity log events as Server-Sent Events as soon as they are stored. Each message
+/// contains a single JSON-serialized event. Events can be filtered by following query parameters,
+/// each of which can be repeated:
+/// - username
+/// - event
+/// - location
+///
+/// If the client can't keep up with incoming events, some are dropped and a `lagged` event is sent
+/// with the number of skipped event batches.
+pub async fn activity_log_live_tail(
+    _admin: AdminRole,
+    Extension(activity_log_tx): Extension<Sender<Bytes>>,
+    filters: Query<LiveTailParams>,
+) -> Sse<ReceiverStream<Result<Event, Infallible>>> {
+    debug!("Starting activity log live tail with filters {filters:?}");
+    let Query(filters) = filters;
+    let mut activity_log_rx = activity_log_tx.subscribe();
+    let (tx, rx) = mpsc::channel(LIVE_TAIL_BUFFER);
+
+    spawn(async move {
+        loop {
+            let messages = select! {
+                () = tx.closed() => break,
+                messages = activity_log_rx.recv() => messages,
+            };
+            let messages = match messages {
+                Ok(messages) => messages,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Activity log live tail lagged, skipped {skipped} event batches");
+                    let event = Event::default().event("lagged").data(skipped.to_string());
+                    if tx.send(Ok(event)).await.is_err() {
+                        break;
+                    }
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+            // messages contain newline-separated serialized events
+            let Ok(messages) = std::str::from_utf8(&messages) else {
+                error!("Received activity log events which aren't valid UTF-8");
+                continue;
+            };
+            for message in messages.lines() {
+                match serde_json::from_str::<LiveTailEvent>(message) {
+                    Ok(event) if filters.matches(&event) => {
+                        if tx.send(Ok(Event::default().data(message))).await.is_err() {
+                            break;
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(err) => error!("Failed to parse activity log event: {err}"),
+                }
+            }
+        }
+        debug!("Activity log live tail finished");
+    });
+
+    Sse::new(ReceiverStream::new(rx)).keep_alive(KeepAlive::default())
+}
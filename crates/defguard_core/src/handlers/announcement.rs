@@ -0,0 +1,296 @@
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+};
+use chrono::{NaiveDateTime, Utc};
+use defguard_common::db::Id;
+use serde_json::json;
+use utoipa::ToSchema;
+
+use super::{ApiResponse, ApiResult};
+use crate::{
+    announcements::{render_announcement, send_announcement},
+    appstate::AppState,
+    auth::{AdminRole, SessionInfo},
+    db::models::announcement::{Announcement, AnnouncementDelivery, AnnouncementRecipient},
+    error::WebError,
+    events::{ApiEvent, ApiEventType, ApiRequestContext},
+};
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct NewAnnouncement {
+    pub subject: String,
+    /// Markdown content; `first_name`, `last_name`, `username`, `email`, `defguard_url`
+    /// and `instance.<name>` mail variables are available
+    pub body: String,
+    /// Send only to members of these groups
+    #[serde(default)]
+    pub group_ids: Vec<Id>,
+    /// Send only to users with devices in these locations
+    #[serde(default)]
+    pub location_ids: Vec<Id>,
+    /// Send only to active (`true`) or disabled (`false`) users
+    #[serde(default)]
+    pub user_active: Option<bool>,
+    /// Send at a given time (UTC) instead of immediately
+    #[serde(default)]
+    pub scheduled_at: Option<NaiveDateTime>,
+}
+
+/// Summary of announcement delivery.
+#[derive(Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct AnnouncementDeliveryReport {
+    pub total: usize,
+    pub sent: usize,
+    pub failed: usize,
+    pub pending: usize,
+}
+
+impl From<&[AnnouncementDelivery<Id>]> for AnnouncementDeliveryReport {
+    fn from(deliveries: &[AnnouncementDelivery<Id>]) -> Self {
+        let mut report = Self {
+            total: deliveries.len(),
+            ..Default::default()
+        };
+        for delivery in deliveries {
+            if delivery.error.is_some() {
+                report.failed += 1;
+            } else if delivery.sent_at.is_some() {
+                report.sent += 1;
+            } else {
+                report.pending += 1;
+            }
+        }
+        report
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AnnouncementDetails {
+    pub announcement: Announcement<Id>,
+    pub report: AnnouncementDeliveryReport,
+    pub deliveries: Vec<AnnouncementDelivery<Id>>,
+}
+
+/// List all announcements
+///
+/// # Returns
+/// - `Vec<Announcement>` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/announcement",
+    tag = "announcement",
+    responses(
+        (status = 200, description = "List of announcements", body = Vec<Announcement>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn list_announcements(_admin: AdminRole, State(appstate): State<AppState>) -> ApiResult {
+    let announcements = Announcement::all(&appstate.pool).await?;
+
+    Ok(ApiResponse {
+        json: json!(announcements),
+        status: StatusCode::OK,
+    })
+}
+
+/// Get announcement with its delivery report
+///
+/// # Returns
+/// - `AnnouncementDetails` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/announcement/{id}",
+    tag = "announcement",
+    params(
+        ("id" = Id, Path, description = "Announcement ID")
+    ),
+    responses(
+        (status = 200, description = "Announcement with delivery report", body = AnnouncementDetails),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - announcement does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn get_announcement(
+    _admin: AdminRole,
+    Path(id): Path<Id>,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let announcement = Announcement::find_by_id(&appstate.pool, id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Announcement {id} not found")))?;
+    let deliveries = AnnouncementDelivery::all_for_announcement(&appstate.pool, id).await?;
+
+    Ok(ApiResponse {
+        json: json!(AnnouncementDetails {
+            announcement,
+            report: deliveries.as_slice().into(),
+            deliveries,
+        }),
+        status: StatusCode::OK,
+    })
+}
+
+/// Create an announcement email for a segment of users
+///
+/// Announcement is sent immediately, unless `scheduled_at` is set in the future.
+///
+/// # Returns
+/// - `Announcement` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    post,
+    path = "/api/v1/announcement",
+    tag = "announcement",
+    request_body = NewAnnouncement,
+    responses(
+        (status = 201, description = "Announcement created", body = Announcement),
+        (status = 400, description = "Bad request - invalid subject or body template"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn create_announcement(
+    _admin: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    State(appstate): State<AppState>,
+    Json(data): Json<NewAnnouncement>,
+) -> ApiResult {
+    debug!(
+        "User {} creating announcement \"{}\"",
+        session.user.username, data.subject
+    );
+
+    // make sure templates can be rendered before sending them to all recipients
+    let sample_recipient = AnnouncementRecipient {
+        id: session.user.id,
+        username: session.user.username.clone(),
+        first_name: session.user.first_name.clone(),
+        last_name: session.user.last_name.clone(),
+        email: session.user.email.clone(),
+    };
+    if let Err(err) = render_announcement(&data.subject, &data.body, &sample_recipient) {
+        return Err(WebError::BadRequest(format!(
+            "Invalid announcement template: {err}"
+        )));
+    }
+
+    let announcement = Announcement::new(
+        data.subject,
+        data.body,
+        data.group_ids,
+        data.location_ids,
+        data.user_active,
+        data.scheduled_at,
+        session.user.id,
+    )
+    .save(&appstate.pool)
+    .await?;
+    info!(
+        "User {} created announcement {} scheduled at {}",
+        session.user.username, announcement.id, announcement.scheduled_at
+    );
+
+    // announcements scheduled in the future are sent by the announcement scheduler
+    if announcement.scheduled_at <= Utc::now().naive_utc() {
+        let (pool, mail_tx) = (appstate.pool.clone(), appstate.mail_tx.clone());
+        let pending = announcement.clone();
+        tokio::spawn(async move {
+            if let Err(err) = send_announcement(&pool, &mail_tx, pending).await {
+                error!("Failed to send announcement: {err}");
+            }
+        });
+    }
+
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::AnnouncementCreated {
+            announcement: announcement.clone(),
+        }),
+    })?;
+
+    Ok(ApiResponse {
+        json: json!(announcement),
+        status: StatusCode::CREATED,
+    })
+}
+
+/// Remove an announcement
+///
+/// Removing a scheduled announcement cancels it. Removing a sent announcement removes
+/// its delivery report.
+///
+/// # Returns
+/// - empty JSON
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    delete,
+    path = "/api/v1/announcement/{id}",
+    tag = "announcement",
+    params(
+        ("id" = Id, Path, description = "Announcement ID")
+    ),
+    responses(
+        (status = 200, description = "Announcement removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - announcement does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn delete_announcement(
+    _admin: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    Path(id): Path<Id>,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let announcement = Announcement::find_by_id(&appstate.pool, id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Announcement {id} not found")))?;
+    debug!(
+        "User {} removing announcement {id} \"{}\"",
+        session.user.username, announcement.subject
+    );
+
+    announcement.clone().delete(&appstate.pool).await?;
+    info!(
+        "User {} removed announcement {id} \"{}\"",
+        session.user.username, announcement.subject
+    );
+
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::AnnouncementRemoved { announcement }),
+    })?;
+
+    Ok(ApiResponse::default())
+}
@@ -0,0 +1,109 @@
+//! REST API versioning.
+//!
+//! Every endpoint is available under `/api/v1`. Requests to `/api/v2` are handled by a
+//! compatibility shim which forwards them to the corresponding `/api/v1` route, marking the
+//! request with [`ApiVersion::V2`]. Handlers which introduce breaking response changes use the
+//! [`ApiVersion`] extractor to decide which representation to return, so only endpoints that
+//! actually changed need version-specific code. Old representations can be marked deprecated
+//! with [`deprecation_headers`] middleware, which informs API clients about the successor.
+
+use std::convert::Infallible;
+
+use axum::{
+    Router,
+    extract::{FromRequestParts, Request, State},
+    http::{HeaderName, HeaderValue, Uri, request::Parts},
+    middleware::Next,
+    response::Response,
+};
+use tower::{ServiceExt, util::MapRequest};
+
+pub(crate) const API_V1_PREFIX: &str = "/api/v1";
+pub(crate) const API_V2_PREFIX: &str = "/api/v2";
+
+const DEPRECATION_HEADER: HeaderName = HeaderName::from_static("deprecation");
+const SUNSET_HEADER: HeaderName = HeaderName::from_static("sunset");
+const LINK_HEADER: HeaderName = HeaderName::from_static("link");
+
+/// REST API version requested by a client.
+#[derive(Clone, Copy, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
+pub enum ApiVersion {
+    #[default]
+    V1,
+    V2,
+}
+
+impl<S> FromRequestParts<S> for ApiVersion
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        // requests without version set by the compatibility shim were sent to `/api/v1`
+        Ok(parts.extensions.get::<Self>().copied().unwrap_or_default())
+    }
+}
+
+/// Deprecation details of an endpoint representation.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Deprecation {
+    /// Last API version serving the deprecated representation.
+    pub version: ApiVersion,
+    /// Unix timestamp of the deprecation.
+    pub since: i64,
+    /// Date after which the representation may be removed, formatted as HTTP date.
+    pub sunset: Option<&'static str>,
+    /// Path of the endpoint replacing the deprecated one.
+    pub successor: Option<&'static str>,
+}
+
+/// Middleware adding `Deprecation`, `Sunset` and `Link` headers (RFC 9745, RFC 8594) to
+/// responses for requests made with a deprecated API version.
+pub(crate) async fn deprecation_headers(
+    State(deprecation): State<Deprecation>,
+    version: ApiVersion,
+    request: Request,
+    next: Next,
+) -> Response {
+    let mut response = next.run(request).await;
+    if version > deprecation.version {
+        return response;
+    }
+
+    let headers = response.headers_mut();
+    if let Ok(value) = HeaderValue::from_str(&format!("@{}", deprecation.since)) {
+        headers.insert(DEPRECATION_HEADER, value);
+    }
+    if let Some(sunset) = deprecation.sunset {
+        headers.insert(SUNSET_HEADER, HeaderValue::from_static(sunset));
+    }
+    if let Some(value) = deprecation.successor.and_then(|successor| {
+        HeaderValue::from_str(&format!("<{successor}>; rel=\"successor-version\"")).ok()
+    }) {
+        headers.insert(LINK_HEADER, value);
+    }
+
+    response
+}
+
+/// Rewrite a request nested under `/api/v2` to the corresponding `/api/v1` route.
+fn forward_to_v1(mut request: Request) -> Request {
+    let path_and_query = request
+        .uri()
+        .path_and_query()
+        .map_or("/", |path_and_query| path_and_query.as_str());
+    let v1_uri = format!("{API_V1_PREFIX}{path_and_query}");
+    match Uri::try_from(&v1_uri) {
+        Ok(uri) => *request.uri_mut() = uri,
+        Err(err) => error!("Failed to forward request to {v1_uri}: {err}"),
+    }
+    request.extensions_mut().insert(ApiVersion::V2);
+
+    request
+}
+
+/// Compatibility shim serving `/api/v2` endpoints with `/api/v1` handlers.
+pub(crate) fn compatibility_shim(v1: Router) -> MapRequest<Router, fn(Request) -> Request> {
+    v1.map_request(forward_to_v1 as fn(Request) -> Request)
+}
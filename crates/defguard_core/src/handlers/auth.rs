@@ -36,7 +36,10 @@ use crate::{
         SessionInfo,
         failed_login::{check_failed_logins, log_failed_login_attempt},
     },
-    db::{MFAInfo, Session, SessionState, User, UserInfo, WebAuthn},
+    db::{
+        MFAInfo, Session, SessionState, User, UserInfo, WebAuthn,
+        models::{login_banner::LoginBanner, service_account::ServiceAccount},
+    },
     enterprise::ldap::utils::login_through_ldap,
     error::WebError,
     events::{ApiEvent, ApiEventType, ApiRequestContext},
@@ -223,6 +226,27 @@ pub(crate) async fn authenticate(
         return Err(WebError::Authentication);
     }
 
+    // current version of the login banner has to be acknowledged once
+    if let Some(banner) = LoginBanner::current(&appstate.pool).await? {
+        if !banner.is_acknowledged(&appstate.pool, user.id).await? {
+            if data.banner_id != Some(banner.id) {
+                info!(
+                    "Failed to authenticate user {username_or_email}: login banner version {} \
+                    not acknowledged",
+                    banner.id
+                );
+                return Err(WebError::Forbidden(
+                    "Login banner must be acknowledged".into(),
+                ));
+            }
+            banner.acknowledge(&appstate.pool, user.id).await?;
+            info!(
+                "User {} acknowledged login banner version {}",
+                user.username, banner.id
+            );
+        }
+    }
+
     let (session, user_info, mfa_info) = create_session(
         &appstate.pool,
         &appstate.mail_tx,
@@ -327,6 +351,20 @@ pub async fn logout(
     Ok((cookies, ApiResponse::default()))
 }
 
+/// Service accounts have no credentials, so they can't configure MFA.
+async fn ensure_mfa_allowed(pool: &PgPool, user: &User<Id>) -> Result<(), WebError> {
+    if ServiceAccount::is_service_account(pool, user.id).await? {
+        debug!(
+            "Rejected MFA configuration for service account {}",
+            user.username
+        );
+        return Err(WebError::Forbidden(
+            "MFA is not available for service accounts".into(),
+        ));
+    }
+    Ok(())
+}
+
 /// Enable MFA
 pub async fn mfa_enable(
     cookies: CookieJar,
@@ -335,6 +373,7 @@ pub async fn mfa_enable(
     State(appstate): State<AppState>,
 ) -> Result<(CookieJar, ApiResponse), WebError> {
     let mut user = session_info.user;
+    ensure_mfa_allowed(&appstate.pool, &user).await?;
     debug!("Enabling MFA for user {}", user.username);
     user.enable_mfa(&appstate.pool).await?;
     if user.mfa_enabled {
@@ -393,6 +432,7 @@ pub async fn webauthn_init(
     State(appstate): State<AppState>,
 ) -> ApiResult {
     let user = session_info.user;
+    ensure_mfa_allowed(&appstate.pool, &user).await?;
     info!(
         "Initializing WebAuthn registration for user {}",
         user.username
@@ -620,6 +660,7 @@ pub async fn webauthn_end(
 /// Generate new TOTP secret
 pub async fn totp_secret(session: SessionInfo, State(appstate): State<AppState>) -> ApiResult {
     let mut user = session.user;
+    ensure_mfa_allowed(&appstate.pool, &user).await?;
     debug!("Generating new TOTP secret for user {}", user.username);
 
     let secret = user.new_totp_secret(&appstate.pool).await?;
@@ -788,6 +829,7 @@ pub async fn email_mfa_init(session: SessionInfo, State(appstate): State<AppStat
 
     // generate TOTP secret
     let mut user = session.user;
+    ensure_mfa_allowed(&appstate.pool, &user).await?;
     debug!("Generating new email MFA secret for user {}", user.username);
     user.new_email_secret(&appstate.pool).await?;
     info!("Generated new email MFA secret for user {}", user.username);
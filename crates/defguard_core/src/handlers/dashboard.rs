@@ -0,0 +1,87 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use axum::{Extension, extract::State, http::StatusCode};
+use chrono::{TimeDelta, Utc};
+use defguard_common::db::Id;
+use serde_json::{Value, json};
+use sqlx::query_as;
+
+use super::{ApiResponse, ApiResult, activity_log::ApiActivityLogEvent};
+use crate::{
+    appstate::AppState,
+    auth::{AdminRole, SessionInfo},
+    db::models::wireguard::{
+        DateTimeAggregation, LocationTrafficStats, WireguardNetworkStats, networks_stats,
+        top_locations_by_traffic,
+    },
+    enterprise::handlers::license_info,
+    grpc::gateway::{map::GatewayMap, state::GatewayState},
+    version::IncompatibleComponents,
+};
+
+/// Number of most recent activity log events included in the dashboard.
+const DASHBOARD_RECENT_EVENTS: i64 = 10;
+/// Number of locations with the highest traffic included in the dashboard.
+const DASHBOARD_TOP_LOCATIONS: i64 = 5;
+
+#[derive(Serialize)]
+pub(crate) struct DashboardSummary {
+    /// Activity and transfer of all locations over the last 24 hours
+    stats: WireguardNetworkStats,
+    top_locations: Vec<LocationTrafficStats>,
+    gateways: HashMap<Id, Vec<GatewayState>>,
+    incompatible_components: IncompatibleComponents,
+    recent_events: Vec<ApiActivityLogEvent>,
+    license_info: Option<Value>,
+}
+
+/// Aggregated overview for the admin dashboard
+///
+/// Combines statistics of all locations from the last 24 hours, the busiest locations, gateway
+/// status, components with incompatible versions, most recent activity log events and license
+/// status, so the dashboard can be rendered with a single request.
+pub(crate) async fn dashboard(
+    _admin: AdminRole,
+    session: SessionInfo,
+    State(appstate): State<AppState>,
+    Extension(gateway_state): Extension<Arc<Mutex<GatewayMap>>>,
+) -> ApiResult {
+    debug!("User {} is fetching dashboard", session.user.username);
+    let from = (Utc::now() - TimeDelta::hours(24)).naive_utc();
+    let stats = networks_stats(&appstate.pool, &from, &DateTimeAggregation::Hour).await?;
+    let top_locations =
+        top_locations_by_traffic(&appstate.pool, &from, DASHBOARD_TOP_LOCATIONS).await?;
+    let recent_events = query_as::<_, ApiActivityLogEvent>(
+        "SELECT id, timestamp, user_id, username, location, ip, event, module, device, description \
+        FROM activity_log_event ORDER BY timestamp DESC LIMIT $1",
+    )
+    .bind(DASHBOARD_RECENT_EVENTS)
+    .fetch_all(&appstate.pool)
+    .await?;
+
+    let gateways = gateway_state
+        .lock()
+        .expect("Failed to acquire gateway state lock")
+        .as_flattened();
+    IncompatibleComponents::remove_expired(&appstate.incompatible_components);
+    let incompatible_components = appstate
+        .incompatible_components
+        .read()
+        .expect("Failed to lock appstate.incompatible_components")
+        .clone();
+
+    let summary = DashboardSummary {
+        stats,
+        top_locations,
+        gateways,
+        incompatible_components,
+        recent_events,
+        license_info: license_info(),
+    };
+    debug!("Displayed dashboard for user {}", session.user.username);
+
+    Ok(ApiResponse::new(json!(summary), StatusCode::OK))
+}
@@ -0,0 +1,262 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+};
+use defguard_common::db::Id;
+use serde_json::json;
+use sqlx::PgConnection;
+
+use super::{ApiResponse, ApiResult};
+use crate::{
+    appstate::AppState,
+    auth::{AdminRole, SessionInfo},
+    db::{
+        Device, GatewayEvent, User, WireguardNetwork,
+        models::{
+            device::{DeviceInfo, DeviceNetworkInfo},
+            device_approval::{DeviceApprovalRequest, DeviceApprovalStatus, PendingDeviceApproval},
+        },
+    },
+    error::WebError,
+    events::{ApiEvent, ApiEventType, ApiRequestContext},
+};
+
+/// Fetches a pending request with related device, its owner and location, and resolves it.
+async fn resolve_request(
+    conn: &mut PgConnection,
+    id: Id,
+    status: DeviceApprovalStatus,
+    resolved_by: Id,
+) -> Result<
+    (
+        DeviceApprovalRequest<Id>,
+        Device<Id>,
+        User<Id>,
+        WireguardNetwork<Id>,
+    ),
+    WebError,
+> {
+    let mut request = DeviceApprovalRequest::find_by_id(&mut *conn, id)
+        .await?
+        .ok_or_else(|| {
+            WebError::ObjectNotFound(format!("Device approval request {id} not found"))
+        })?;
+    if !request
+        .resolve(&mut *conn, status, Some(resolved_by))
+        .await?
+    {
+        return Err(WebError::BadRequest(format!(
+            "Device approval request {id} has already been resolved"
+        )));
+    }
+    let device = Device::find_by_id(&mut *conn, request.device_id)
+        .await?
+        .ok_or_else(|| {
+            WebError::ObjectNotFound(format!("Device {} not found", request.device_id))
+        })?;
+    let owner = device.get_owner(&mut *conn).await?;
+    let location = WireguardNetwork::find_by_id(&mut *conn, request.location_id)
+        .await?
+        .ok_or_else(|| {
+            WebError::ObjectNotFound(format!("Location {} not found", request.location_id))
+        })?;
+
+    Ok((request, device, owner, location))
+}
+
+/// List devices awaiting approval
+///
+/// # Returns
+/// - `Vec<PendingDeviceApproval>` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/device_approval",
+    tag = "device",
+    responses(
+        (status = 200, description = "List of devices awaiting approval", body = Vec<PendingDeviceApproval>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn list_pending_device_approvals(
+    _admin: AdminRole,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let pending = PendingDeviceApproval::all(&appstate.pool).await?;
+
+    Ok(ApiResponse {
+        json: json!(pending),
+        status: StatusCode::OK,
+    })
+}
+
+/// Approve a device in a location
+///
+/// Assigns the device an IP address in the location and pushes it to location gateways.
+///
+/// # Returns
+/// - `DeviceApprovalRequest` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    post,
+    path = "/api/v1/device_approval/{id}/approve",
+    tag = "device",
+    params(
+        ("id" = Id, Path, description = "Device approval request ID")
+    ),
+    responses(
+        (status = 200, description = "Device approved", body = DeviceApprovalRequest),
+        (status = 400, description = "Bad request - request has already been resolved"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - request does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn approve_device(
+    _admin: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    Path(id): Path<Id>,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    debug!(
+        "User {} approving device approval request {id}",
+        session.user.username
+    );
+    let mut transaction = appstate.pool.begin().await?;
+    let (request, device, owner, location) = resolve_request(
+        &mut transaction,
+        id,
+        DeviceApprovalStatus::Approved,
+        session.user.id,
+    )
+    .await?;
+
+    let network_device = location
+        .add_device_to_network(&mut transaction, &device, None)
+        .await
+        .map_err(|err| {
+            error!("Failed to add approved device {device} to location {location}: {err}");
+            WebError::BadRequest(format!(
+                "Device {} can't be added to location {}",
+                device.name, location.name
+            ))
+        })?;
+    let firewall_config = location.try_get_firewall_config(&mut transaction).await?;
+    transaction.commit().await?;
+
+    if let Some(firewall_config) = firewall_config {
+        appstate.send_wireguard_event(GatewayEvent::FirewallConfigChanged(
+            location.id,
+            firewall_config,
+        ));
+    }
+    appstate.send_wireguard_event(GatewayEvent::DeviceCreated(DeviceInfo {
+        device: device.clone(),
+        network_info: vec![DeviceNetworkInfo {
+            network_id: location.id,
+            device_wireguard_ips: network_device.wireguard_ips,
+            preshared_key: network_device.preshared_key,
+            is_authorized: network_device.is_authorized,
+        }],
+    }));
+    info!(
+        "User {} approved device {device} of user {} in location {location}",
+        session.user.username, owner.username
+    );
+
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::DeviceApproved {
+            owner,
+            device,
+            location,
+        }),
+    })?;
+
+    Ok(ApiResponse {
+        json: json!(request),
+        status: StatusCode::OK,
+    })
+}
+
+/// Reject a device in a location
+///
+/// Rejected device won't be added to the location.
+///
+/// # Returns
+/// - `DeviceApprovalRequest` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    post,
+    path = "/api/v1/device_approval/{id}/reject",
+    tag = "device",
+    params(
+        ("id" = Id, Path, description = "Device approval request ID")
+    ),
+    responses(
+        (status = 200, description = "Device rejected", body = DeviceApprovalRequest),
+        (status = 400, description = "Bad request - request has already been resolved"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - request does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn reject_device(
+    _admin: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    Path(id): Path<Id>,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    debug!(
+        "User {} rejecting device approval request {id}",
+        session.user.username
+    );
+    let mut transaction = appstate.pool.begin().await?;
+    let (request, device, owner, location) = resolve_request(
+        &mut transaction,
+        id,
+        DeviceApprovalStatus::Rejected,
+        session.user.id,
+    )
+    .await?;
+    transaction.commit().await?;
+    info!(
+        "User {} rejected device {device} of user {} in location {location}",
+        session.user.username, owner.username
+    );
+
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::DeviceRejected {
+            owner,
+            device,
+            location,
+        }),
+    })?;
+
+    Ok(ApiResponse {
+        json: json!(request),
+        status: StatusCode::OK,
+    })
+}
@@ -0,0 +1,298 @@
+use std::net::IpAddr;
+
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+};
+use axum_extra::{
+    TypedHeader,
+    headers::{Authorization, authorization::Bearer},
+};
+use chrono::NaiveDateTime;
+use defguard_common::{config::server_config, db::Id};
+use secrecy::ExposeSecret;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+
+use super::{ApiResponse, ApiResult};
+use crate::{
+    appstate::AppState,
+    db::{
+        WireguardNetwork,
+        models::{
+            device::WireguardNetworkDevice, dns_canary::DnsCanary, polling_token::PollingToken,
+        },
+    },
+    error::WebError,
+};
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct DnsCanaryRequest {
+    /// Polling token of the device running the test
+    pub token: String,
+    /// Location the device is connected to
+    pub network_id: Id,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct DnsCanaryInfo {
+    pub id: Id,
+    /// Hostname the client should resolve through its system resolver
+    pub hostname: String,
+    pub expires_at: NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct DnsCanaryQuery {
+    /// Queried hostname
+    pub hostname: String,
+    /// Address of the resolver which made the query
+    #[schema(value_type = String)]
+    pub resolver: IpAddr,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct DnsLeakVerifyRequest {
+    /// Polling token of the device running the test
+    pub token: String,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DnsLeakStatus {
+    /// Canary hostname hasn't been queried yet.
+    Pending,
+    /// Canary hostname has been queried only by resolvers of the location.
+    NoLeak,
+    /// Canary hostname has been queried by resolvers outside of the location.
+    Leak,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct DnsLeakResult {
+    pub status: DnsLeakStatus,
+    /// All resolvers which queried the canary hostname
+    #[schema(value_type = Vec<String>)]
+    pub resolvers: Vec<IpAddr>,
+    /// Resolvers which don't belong to the location
+    #[schema(value_type = Vec<String>)]
+    pub leaking_resolvers: Vec<IpAddr>,
+}
+
+/// Checks if `resolver` belongs to a location: it's one of location DNS servers, its gateway
+/// endpoint or an address from location networks.
+fn is_location_resolver(location: &WireguardNetwork<Id>, resolver: IpAddr) -> bool {
+    let dns_servers = location.dns.as_deref().unwrap_or_default().split(',');
+    location
+        .address
+        .iter()
+        .any(|network| network.contains(resolver))
+        || location.endpoint.parse::<IpAddr>() == Ok(resolver)
+        || dns_servers
+            .filter_map(|server| server.trim().parse::<IpAddr>().ok())
+            .any(|server| server == resolver)
+}
+
+async fn find_polling_token(
+    appstate: &AppState,
+    token: &str,
+) -> Result<PollingToken<Id>, WebError> {
+    PollingToken::find(&appstate.pool, token)
+        .await?
+        .ok_or_else(|| WebError::Authorization("Invalid token".into()))
+}
+
+/// Create DNS canary
+///
+/// Public endpoint used by desktop clients, authorized by device polling token. Returns a unique
+/// hostname in the canary domain, which the client should resolve through its system resolver
+/// while connected to the location, and then verify the result.
+///
+/// # Returns
+/// - `DnsCanaryInfo` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    post,
+    path = "/api/v1/dns_leak/canary",
+    tag = "dns_leak",
+    request_body = DnsCanaryRequest,
+    responses(
+        (status = 201, description = "DNS canary created", body = DnsCanaryInfo),
+        (status = 401, description = "Unauthorized - invalid polling token"),
+        (status = 404, description = "Not found - DNS leak tests aren't configured or device doesn't belong to the location"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_dns_canary(
+    State(appstate): State<AppState>,
+    Json(data): Json<DnsCanaryRequest>,
+) -> ApiResult {
+    let Some(domain) = server_config().dns_canary_domain.as_deref() else {
+        return Err(WebError::ObjectNotFound(
+            "DNS leak tests aren't configured".into(),
+        ));
+    };
+    let token = find_polling_token(&appstate, &data.token).await?;
+    if WireguardNetworkDevice::find(&appstate.pool, token.device_id, data.network_id)
+        .await?
+        .is_none()
+    {
+        return Err(WebError::ObjectNotFound(format!(
+            "Device {} doesn't belong to location {}",
+            token.device_id, data.network_id
+        )));
+    }
+    DnsCanary::delete_expired(&appstate.pool).await?;
+    let canary = DnsCanary::new(
+        domain.trim_end_matches('.'),
+        token.device_id,
+        data.network_id,
+    )
+    .save(&appstate.pool)
+    .await?;
+    debug!(
+        "Created DNS canary {} for device {} in location {}",
+        canary.hostname, canary.device_id, canary.network_id
+    );
+
+    Ok(ApiResponse {
+        json: json!(DnsCanaryInfo {
+            id: canary.id,
+            hostname: canary.hostname,
+            expires_at: canary.expires_at,
+        }),
+        status: StatusCode::CREATED,
+    })
+}
+
+/// Compare digests of tokens instead of tokens themselves, so that the comparison time doesn't
+/// reveal how much of the token was guessed.
+fn tokens_match(expected: &str, token: &str) -> bool {
+    Sha256::digest(expected.as_bytes()) == Sha256::digest(token.as_bytes())
+}
+
+/// Report DNS canary query
+///
+/// Used by the DNS server authoritative for the canary domain to report queries for canary
+/// hostnames. Authorized by the bearer token configured with `DEFGUARD_DNS_CANARY_TOKEN`.
+///
+/// # Returns
+/// - empty JSON object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    post,
+    path = "/api/v1/dns_leak/query",
+    tag = "dns_leak",
+    request_body = DnsCanaryQuery,
+    responses(
+        (status = 200, description = "Query recorded"),
+        (status = 401, description = "Unauthorized - invalid token"),
+        (status = 404, description = "Not found - canary doesn't exist or has expired"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer" = [])
+    )
+)]
+pub async fn report_dns_canary_query(
+    TypedHeader(authorization): TypedHeader<Authorization<Bearer>>,
+    State(appstate): State<AppState>,
+    Json(data): Json<DnsCanaryQuery>,
+) -> ApiResult {
+    let authorized = server_config()
+        .dns_canary_token
+        .as_ref()
+        .is_some_and(|token| tokens_match(token.expose_secret(), authorization.token()));
+    if !authorized {
+        warn!("Rejected DNS canary query report with invalid token");
+        return Err(WebError::Authorization("Invalid token".into()));
+    }
+    let hostname = data.hostname.trim_end_matches('.').to_lowercase();
+    if !DnsCanary::add_resolver(&appstate.pool, &hostname, data.resolver).await? {
+        return Err(WebError::ObjectNotFound(format!(
+            "DNS canary {hostname} not found"
+        )));
+    }
+    debug!("DNS canary {hostname} queried by {}", data.resolver);
+
+    Ok(ApiResponse {
+        json: json!({}),
+        status: StatusCode::OK,
+    })
+}
+
+/// Verify DNS canary
+///
+/// Public endpoint used by desktop clients, authorized by device polling token. Checks which
+/// resolvers queried the canary hostname. Resolution is considered to go through the VPN if
+/// all resolvers are location DNS servers, its gateway endpoint or belong to location networks.
+///
+/// # Returns
+/// - `DnsLeakResult` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    post,
+    path = "/api/v1/dns_leak/canary/{id}/verify",
+    tag = "dns_leak",
+    params(
+        ("id" = Id, Path, description = "DNS canary ID")
+    ),
+    request_body = DnsLeakVerifyRequest,
+    responses(
+        (status = 200, description = "DNS leak test result", body = DnsLeakResult),
+        (status = 401, description = "Unauthorized - invalid polling token"),
+        (status = 404, description = "Not found - canary doesn't exist or has expired"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn verify_dns_canary(
+    State(appstate): State<AppState>,
+    Path(id): Path<Id>,
+    Json(data): Json<DnsLeakVerifyRequest>,
+) -> ApiResult {
+    let token = find_polling_token(&appstate, &data.token).await?;
+    let canary = DnsCanary::find_for_device(&appstate.pool, id, token.device_id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("DNS canary {id} not found")))?;
+    let location = WireguardNetwork::find_by_id(&appstate.pool, canary.network_id)
+        .await?
+        .ok_or_else(|| {
+            WebError::ObjectNotFound(format!("Location {} not found", canary.network_id))
+        })?;
+
+    let resolvers: Vec<IpAddr> = canary
+        .resolvers
+        .iter()
+        .map(|network| network.ip())
+        .collect();
+    let leaking_resolvers: Vec<IpAddr> = resolvers
+        .iter()
+        .copied()
+        .filter(|resolver| !is_location_resolver(&location, *resolver))
+        .collect();
+    let status = if resolvers.is_empty() {
+        DnsLeakStatus::Pending
+    } else if leaking_resolvers.is_empty() {
+        DnsLeakStatus::NoLeak
+    } else {
+        warn!(
+            "DNS leak detected for device {} in location {location}, resolvers outside of the \
+            location: {leaking_resolvers:?}",
+            canary.device_id
+        );
+        DnsLeakStatus::Leak
+    };
+
+    Ok(ApiResponse {
+        json: json!(DnsLeakResult {
+            status,
+            resolvers,
+            leaking_resolvers,
+        }),
+        status: StatusCode::OK,
+    })
+}
@@ -0,0 +1,249 @@
+use axum::{
+    extract::{Json, Path, State},
+    http::{
+        StatusCode,
+        header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+    },
+    response::{IntoResponse, Response},
+};
+use chrono::{TimeDelta, Utc};
+use defguard_common::db::{Id, models::Settings};
+use humantime::parse_duration;
+use sqlx::PgConnection;
+use utoipa::ToSchema;
+
+use crate::{
+    appstate::AppState,
+    auth::{AdminRole, SessionInfo},
+    db::User,
+    enrollment_sheet::{EnrollmentSheet, enrollment_sheets_zip},
+    error::WebError,
+    events::{ApiEvent, ApiEventType, ApiRequestContext},
+    server_config,
+};
+
+#[derive(Deserialize, ToSchema)]
+pub struct EnrollmentSheetRequest {
+    pub token_expiration_time: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct EnrollmentSheetsRequest {
+    pub usernames: Vec<String>,
+    pub token_expiration_time: Option<String>,
+}
+
+fn token_expiration_seconds(token_expiration_time: Option<&str>) -> Result<u64, WebError> {
+    match token_expiration_time {
+        Some(time) => Ok(parse_duration(time)
+            .map_err(|err| {
+                error!("Failed to parse token expiration time {time}: {err}");
+                WebError::BadRequest("Failed to parse token expiration time".to_owned())
+            })?
+            .as_secs()),
+        None => Ok(server_config().enrollment_token_timeout.as_secs()),
+    }
+}
+
+/// Creates a new enrollment token for given user and prepares the sheet.
+async fn prepare_sheet(
+    transaction: &mut PgConnection,
+    admin: &User<Id>,
+    username: &str,
+    token_timeout_seconds: u64,
+    appstate: &AppState,
+) -> Result<(EnrollmentSheet, User<Id>), WebError> {
+    let Some(mut user) = User::find_by_username(&mut *transaction, username).await? else {
+        error!("User {username} couldn't be found, enrollment sheet not created");
+        return Err(WebError::ObjectNotFound(format!(
+            "user {username} not found"
+        )));
+    };
+    let config = server_config();
+    let token = user
+        .start_enrollment(
+            &mut *transaction,
+            admin,
+            None,
+            token_timeout_seconds,
+            config.enrollment_url.clone(),
+            false,
+            appstate.mail_tx.clone(),
+        )
+        .await?;
+    let sheet = EnrollmentSheet {
+        instance_name: Settings::get_current_settings().instance_name,
+        username: user.username.clone(),
+        first_name: user.first_name.clone(),
+        last_name: user.last_name.clone(),
+        enrollment_url: config.enrollment_url.clone(),
+        token,
+        expires_at: Utc::now().naive_utc()
+            + TimeDelta::seconds(token_timeout_seconds.try_into().unwrap_or(i64::MAX)),
+    };
+
+    Ok((sheet, user))
+}
+
+fn attachment(content_type: &'static str, file_name: &str, content: Vec<u8>) -> Response {
+    (
+        StatusCode::CREATED,
+        [
+            (CONTENT_TYPE, content_type.to_string()),
+            (
+                CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{file_name}\""),
+            ),
+        ],
+        content,
+    )
+        .into_response()
+}
+
+/// Generate printable enrollment sheet
+///
+/// Starts a new enrollment for the user and returns a PDF document with enrollment URL, token,
+/// instructions and token expiration date. Previous unused enrollment tokens are invalidated.
+///
+/// # Returns
+/// - PDF document
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    post,
+    path = "/api/v1/user/{username}/enrollment_sheet",
+    request_body = EnrollmentSheetRequest,
+    params(
+        ("username" = String, description = "name of a user"),
+    ),
+    responses(
+        (status = 201, description = "Enrollment sheet PDF document.", content_type = "application/pdf"),
+        (status = 400, description = "Bad request, invalid token expiration time or user already enrolled."),
+        (status = 401, description = "Unauthorized to start enrollment."),
+        (status = 403, description = "You don't have permission to start enrollment."),
+        (status = 404, description = "Provided user does not exist."),
+        (status = 500, description = "Unable to generate enrollment sheet.")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn enrollment_sheet(
+    _role: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    State(appstate): State<AppState>,
+    Path(username): Path<String>,
+    Json(data): Json<EnrollmentSheetRequest>,
+) -> Result<Response, WebError> {
+    debug!(
+        "User {} generating enrollment sheet for user {username}",
+        session.user.username
+    );
+    let token_timeout_seconds = token_expiration_seconds(data.token_expiration_time.as_deref())?;
+    let mut transaction = appstate.pool.begin().await?;
+    let (sheet, user) = prepare_sheet(
+        &mut transaction,
+        &session.user,
+        &username,
+        token_timeout_seconds,
+        &appstate,
+    )
+    .await?;
+    transaction.commit().await?;
+    info!(
+        "User {} generated enrollment sheet for user {username}",
+        session.user.username
+    );
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::EnrollmentTokenAdded { user }),
+    })?;
+
+    Ok(attachment(
+        "application/pdf",
+        &sheet.file_name(),
+        sheet.to_pdf(),
+    ))
+}
+
+/// Generate printable enrollment sheets for multiple users
+///
+/// Starts a new enrollment for each of given users and returns a ZIP archive with one PDF
+/// enrollment sheet per user. No tokens are created if enrollment can't be started for any user.
+///
+/// # Returns
+/// - ZIP archive with PDF documents
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    post,
+    path = "/api/v1/user/enrollment_sheets",
+    request_body = EnrollmentSheetsRequest,
+    responses(
+        (status = 201, description = "ZIP archive with enrollment sheets.", content_type = "application/zip"),
+        (status = 400, description = "Bad request, invalid token expiration time or user already enrolled."),
+        (status = 401, description = "Unauthorized to start enrollment."),
+        (status = 403, description = "You don't have permission to start enrollment."),
+        (status = 404, description = "One of provided users does not exist."),
+        (status = 500, description = "Unable to generate enrollment sheets.")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn enrollment_sheets(
+    _role: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    State(appstate): State<AppState>,
+    Json(data): Json<EnrollmentSheetsRequest>,
+) -> Result<Response, WebError> {
+    debug!(
+        "User {} generating enrollment sheets for users {:?}",
+        session.user.username, data.usernames
+    );
+    if data.usernames.is_empty() {
+        return Err(WebError::BadRequest("No users provided".into()));
+    }
+    let token_timeout_seconds = token_expiration_seconds(data.token_expiration_time.as_deref())?;
+    let mut transaction = appstate.pool.begin().await?;
+    let mut sheets = Vec::with_capacity(data.usernames.len());
+    let mut users = Vec::with_capacity(data.usernames.len());
+    for username in &data.usernames {
+        let (sheet, user) = prepare_sheet(
+            &mut transaction,
+            &session.user,
+            username,
+            token_timeout_seconds,
+            &appstate,
+        )
+        .await?;
+        sheets.push(sheet);
+        users.push(user);
+    }
+    let archive = enrollment_sheets_zip(&sheets).map_err(|err| {
+        error!("Failed to create enrollment sheets archive: {err}");
+        WebError::Http(StatusCode::INTERNAL_SERVER_ERROR)
+    })?;
+    transaction.commit().await?;
+    info!(
+        "User {} generated enrollment sheets for {} users",
+        session.user.username,
+        users.len()
+    );
+    for user in users {
+        appstate.emit_event(ApiEvent {
+            context: context.clone(),
+            event: Box::new(ApiEventType::EnrollmentTokenAdded { user }),
+        })?;
+    }
+
+    Ok(attachment(
+        "application/zip",
+        "enrollment_sheets.zip",
+        archive,
+    ))
+}
@@ -0,0 +1,345 @@
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+};
+use chrono::NaiveDateTime;
+use defguard_common::db::Id;
+use humantime::parse_duration;
+use serde_json::json;
+use utoipa::ToSchema;
+
+use super::{ApiResponse, ApiResult};
+use crate::{
+    appstate::AppState,
+    auth::{AdminRole, SessionInfo},
+    db::{
+        User,
+        models::enrollment::{ENROLLMENT_TOKEN_TYPE, Token, TokenError},
+    },
+    error::WebError,
+    events::{ApiEvent, ApiEventType, ApiRequestContext},
+    server_config,
+};
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EnrollmentTokenStatus {
+    /// Token awaits use
+    Pending,
+    /// Session started with the token is still in progress
+    Active,
+    Used,
+    Expired,
+}
+
+/// Enrollment or desktop configuration token along with the client which used it.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct EnrollmentTokenInfo {
+    pub token: String,
+    pub status: EnrollmentTokenStatus,
+    pub admin_id: Option<Id>,
+    pub email: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub used_at: Option<NaiveDateTime>,
+    pub use_count: i32,
+    pub used_ip: Option<String>,
+    pub used_user_agent: Option<String>,
+    pub device_id: Option<Id>,
+    pub location_ids: Option<Vec<Id>>,
+}
+
+impl From<Token> for EnrollmentTokenInfo {
+    fn from(token: Token) -> Self {
+        let status = if token.is_session_valid(server_config().enrollment_session_timeout.as_secs())
+        {
+            EnrollmentTokenStatus::Active
+        } else if token.is_used() {
+            EnrollmentTokenStatus::Used
+        } else if token.is_expired() {
+            EnrollmentTokenStatus::Expired
+        } else {
+            EnrollmentTokenStatus::Pending
+        };
+        Self {
+            token: token.id,
+            status,
+            admin_id: token.admin_id,
+            email: token.email,
+            created_at: token.created_at,
+            expires_at: token.expires_at,
+            used_at: token.used_at,
+            use_count: token.use_count,
+            used_ip: token.used_ip,
+            used_user_agent: token.used_user_agent,
+            device_id: token.device_id,
+            location_ids: token.location_ids,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ExtendEnrollmentToken {
+    /// New validity period counted from now, e.g. "24h"
+    pub token_expiration_time: String,
+}
+
+async fn find_user(appstate: &AppState, username: &str) -> Result<User<Id>, WebError> {
+    User::find_by_username(&appstate.pool, username)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("User {username} not found")))
+}
+
+/// Finds an enrollment token which belongs to the user.
+async fn find_token(appstate: &AppState, user: &User<Id>, token: &str) -> Result<Token, WebError> {
+    match Token::find_by_id(&appstate.pool, token).await {
+        Ok(token)
+            if token.user_id == user.id
+                && token.token_type.as_deref() == Some(ENROLLMENT_TOKEN_TYPE) =>
+        {
+            Ok(token)
+        }
+        Ok(_) | Err(TokenError::NotFound) => Err(WebError::ObjectNotFound(format!(
+            "Enrollment token not found for user {}",
+            user.username
+        ))),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// List enrollment tokens of a user
+///
+/// Lists enrollment and desktop configuration tokens of a user along with their expiration and
+/// the client which used them.
+///
+/// # Returns
+/// - `Vec<EnrollmentTokenInfo>` object, the most recent first
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/user/{username}/enrollment_token",
+    tag = "user",
+    params(
+        ("username" = String, description = "Name of a user"),
+    ),
+    responses(
+        (status = 200, description = "List of enrollment tokens", body = Vec<EnrollmentTokenInfo>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin role required"),
+        (status = 404, description = "Not found - user does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn list_enrollment_tokens(
+    _role: AdminRole,
+    State(appstate): State<AppState>,
+    Path(username): Path<String>,
+) -> ApiResult {
+    let user = find_user(&appstate, &username).await?;
+    let tokens: Vec<EnrollmentTokenInfo> =
+        Token::fetch_user_enrollment_tokens(&appstate.pool, user.id)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+
+    Ok(ApiResponse {
+        json: json!(tokens),
+        status: StatusCode::OK,
+    })
+}
+
+/// Resend enrollment token email
+///
+/// Sends the enrollment or desktop configuration email again, to the address the token was
+/// issued for or to the user's email.
+///
+/// # Returns
+/// - empty JSON
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    post,
+    path = "/api/v1/user/{username}/enrollment_token/{token}/resend",
+    tag = "user",
+    params(
+        ("username" = String, description = "Name of a user"),
+        ("token" = String, description = "Enrollment token"),
+    ),
+    responses(
+        (status = 200, description = "Email has been queued"),
+        (status = 400, description = "Bad request - token has been used or has expired"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin role required"),
+        (status = 404, description = "Not found - user or token does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn resend_enrollment_token(
+    _role: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    State(appstate): State<AppState>,
+    Path((username, token)): Path<(String, String)>,
+) -> ApiResult {
+    let user = find_user(&appstate, &username).await?;
+    let token = find_token(&appstate, &user, &token).await?;
+    if token.is_used() {
+        return Err(WebError::BadRequest(
+            "Enrollment token has already been used".into(),
+        ));
+    }
+    if token.is_expired() {
+        return Err(WebError::BadRequest("Enrollment token has expired".into()));
+    }
+
+    let email = token.email.clone().unwrap_or_else(|| user.email.clone());
+    let enrollment_url = server_config().enrollment_url.clone();
+    let mut transaction = appstate.pool.begin().await?;
+    // enrolled users get desktop configuration tokens
+    if user.has_password() {
+        token
+            .send_desktop_start_mail(&mut transaction, &email, &enrollment_url, &appstate.mail_tx)
+            .await?;
+    } else {
+        token
+            .send_enrollment_start_mail(&mut transaction, &email, enrollment_url, &appstate.mail_tx)
+            .await?;
+    }
+    transaction.commit().await?;
+    info!(
+        "User {} resent enrollment token email of user {username} to {email}",
+        session.user.username
+    );
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::EnrollmentTokenResent { user }),
+    })?;
+
+    Ok(ApiResponse::default())
+}
+
+/// Extend enrollment token
+///
+/// Sets a new expiration time of an unused enrollment token, counted from now.
+///
+/// # Returns
+/// - `EnrollmentTokenInfo` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    put,
+    path = "/api/v1/user/{username}/enrollment_token/{token}",
+    tag = "user",
+    params(
+        ("username" = String, description = "Name of a user"),
+        ("token" = String, description = "Enrollment token"),
+    ),
+    request_body = ExtendEnrollmentToken,
+    responses(
+        (status = 200, description = "Enrollment token has been extended", body = EnrollmentTokenInfo),
+        (status = 400, description = "Bad request - token has been used or invalid expiration time"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin role required"),
+        (status = 404, description = "Not found - user or token does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn extend_enrollment_token(
+    _role: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    State(appstate): State<AppState>,
+    Path((username, token)): Path<(String, String)>,
+    Json(data): Json<ExtendEnrollmentToken>,
+) -> ApiResult {
+    let user = find_user(&appstate, &username).await?;
+    let mut token = find_token(&appstate, &user, &token).await?;
+    if token.is_used() {
+        return Err(WebError::BadRequest(
+            "Enrollment token has already been used".into(),
+        ));
+    }
+    let timeout = parse_duration(&data.token_expiration_time).map_err(|err| {
+        error!(
+            "Failed to parse token expiration time {}: {err}",
+            data.token_expiration_time
+        );
+        WebError::BadRequest("Failed to parse token expiration time".to_owned())
+    })?;
+    token.extend(&appstate.pool, timeout.as_secs()).await?;
+    info!(
+        "User {} extended enrollment token of user {username} until {}",
+        session.user.username, token.expires_at
+    );
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::EnrollmentTokenExtended { user }),
+    })?;
+
+    Ok(ApiResponse {
+        json: json!(EnrollmentTokenInfo::from(token)),
+        status: StatusCode::OK,
+    })
+}
+
+/// Revoke enrollment token
+///
+/// # Returns
+/// - empty JSON
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    delete,
+    path = "/api/v1/user/{username}/enrollment_token/{token}",
+    tag = "user",
+    params(
+        ("username" = String, description = "Name of a user"),
+        ("token" = String, description = "Enrollment token"),
+    ),
+    responses(
+        (status = 200, description = "Enrollment token has been revoked"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - admin role required"),
+        (status = 404, description = "Not found - user or token does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn revoke_enrollment_token(
+    _role: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    State(appstate): State<AppState>,
+    Path((username, token)): Path<(String, String)>,
+) -> ApiResult {
+    let user = find_user(&appstate, &username).await?;
+    let token = find_token(&appstate, &user, &token).await?;
+    token.delete(&appstate.pool).await?;
+    info!(
+        "User {} revoked enrollment token of user {username}",
+        session.user.username
+    );
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::EnrollmentTokenRevoked { user }),
+    })?;
+
+    Ok(ApiResponse::default())
+}
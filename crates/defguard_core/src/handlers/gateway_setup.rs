@@ -0,0 +1,258 @@
+use std::fs::read_to_string;
+
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::{StatusCode, header::CONTENT_TYPE},
+    response::{IntoResponse, Response},
+};
+use chrono::NaiveDateTime;
+use defguard_common::{config::server_config, db::Id};
+use serde_json::json;
+use utoipa::ToSchema;
+
+use super::{ApiResponse, ApiResult};
+use crate::{
+    appstate::AppState,
+    auth::{AdminRole, SessionInfo},
+    db::{WireguardNetwork, models::gateway_setup_link::GatewaySetupLink},
+    error::WebError,
+    gateway_deployment::{DeploymentFormat, GatewayDeployment},
+};
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct GatewaySetupLinkInfo {
+    /// Public URL of the setup bundle; works only once
+    pub url: String,
+    pub expires_at: NaiveDateTime,
+}
+
+/// Everything needed to run a gateway for a location.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct GatewaySetupBundle {
+    pub network_id: Id,
+    pub network_name: String,
+    pub token: String,
+    pub grpc_url: String,
+    /// Certificate chain of the gRPC server, if TLS is configured
+    pub ca_cert: Option<String>,
+    /// Gateway configuration as environment variables
+    pub env: String,
+    /// Ready-to-run docker compose service
+    pub docker_compose: String,
+}
+
+impl GatewaySetupBundle {
+    fn new(network: &WireguardNetwork<Id>, token: String) -> Result<Self, WebError> {
+        let config = server_config();
+        let ca_cert = config
+            .grpc_cert
+            .as_ref()
+            .and_then(|path| read_to_string(path).ok());
+        let deployment = GatewayDeployment::new(
+            network.name.clone(),
+            None,
+            token,
+            config.grpc_url.to_string(),
+            ca_cert.is_some(),
+            None,
+        )?;
+
+        Ok(Self {
+            network_id: network.id,
+            network_name: network.name.clone(),
+            env: deployment.render(DeploymentFormat::Env)?,
+            docker_compose: deployment.render(DeploymentFormat::DockerCompose)?,
+            token: deployment.token,
+            grpc_url: deployment.grpc_url,
+            ca_cert,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GatewayDeploymentQuery {
+    format: DeploymentFormat,
+    name: Option<String>,
+    version: Option<String>,
+}
+
+/// Create gateway setup link
+///
+/// Creates a one-time link to download the gateway setup bundle for a location: gateway token,
+/// gRPC URL, CA certificate and ready-to-run configuration snippets. Link expires after first
+/// use or after an hour.
+///
+/// # Returns
+/// - `GatewaySetupLinkInfo` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    post,
+    path = "/api/v1/network/{network_id}/gateway_setup_link",
+    tag = "gateway_setup",
+    params(
+        ("network_id" = Id, Path, description = "Location ID")
+    ),
+    responses(
+        (status = 201, description = "Gateway setup link created", body = GatewaySetupLinkInfo),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - location does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn create_gateway_setup_link(
+    _admin: AdminRole,
+    session: SessionInfo,
+    State(appstate): State<AppState>,
+    Path(network_id): Path<Id>,
+) -> ApiResult {
+    let network = WireguardNetwork::find_by_id(&appstate.pool, network_id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Network {network_id} not found")))?;
+    debug!(
+        "User {} creating gateway setup link for location {network}",
+        session.user.username
+    );
+    GatewaySetupLink::delete_expired(&appstate.pool).await?;
+    let link = GatewaySetupLink::new(network.id, session.user.id)
+        .save(&appstate.pool)
+        .await?;
+    let url = server_config()
+        .url
+        .join(&format!("api/v1/gateway_setup/{}", link.token))
+        .map_err(|err| {
+            error!("Failed to prepare gateway setup URL: {err}");
+            WebError::Http(StatusCode::INTERNAL_SERVER_ERROR)
+        })?;
+    info!(
+        "User {} created gateway setup link for location {network}, valid until {}",
+        session.user.username, link.expires_at
+    );
+
+    Ok(ApiResponse {
+        json: json!(GatewaySetupLinkInfo {
+            url: url.to_string(),
+            expires_at: link.expires_at,
+        }),
+        status: StatusCode::CREATED,
+    })
+}
+
+/// Download gateway setup bundle
+///
+/// Public endpoint, authorized by the link token. Each link can be used only once.
+///
+/// # Returns
+/// - `GatewaySetupBundle` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/gateway_setup/{token}",
+    tag = "gateway_setup",
+    params(
+        ("token" = String, Path, description = "Gateway setup link token")
+    ),
+    responses(
+        (status = 200, description = "Gateway setup bundle", body = GatewaySetupBundle),
+        (status = 404, description = "Not found - link doesn't exist, expired or was already used"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn download_gateway_setup(
+    State(appstate): State<AppState>,
+    Path(token): Path<String>,
+) -> ApiResult {
+    let mut transaction = appstate.pool.begin().await?;
+    let Some(network_id) = GatewaySetupLink::consume(&mut *transaction, &token).await? else {
+        warn!("Attempted to use invalid, expired or already used gateway setup link");
+        return Err(WebError::ObjectNotFound(
+            "Gateway setup link not found".into(),
+        ));
+    };
+    let network = WireguardNetwork::find_by_id(&mut *transaction, network_id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Network {network_id} not found")))?;
+    let gateway_token = network.generate_gateway_token()?;
+    transaction.commit().await?;
+    info!("Gateway setup bundle for location {network} downloaded");
+
+    Ok(ApiResponse {
+        json: json!(GatewaySetupBundle::new(&network, gateway_token)?),
+        status: StatusCode::OK,
+    })
+}
+
+/// Render gateway deployment
+///
+/// Renders a deployment artifact for a gateway of a location with a freshly generated gateway
+/// token: docker compose service, systemd unit or env file. Systemd unit reads configuration,
+/// including the token, from an env file, which can be rendered in the `env` format.
+///
+/// # Returns
+/// - deployment artifact in requested format
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/network/{network_id}/gateway_deployment",
+    tag = "gateway_setup",
+    params(
+        ("network_id" = Id, Path, description = "Location ID"),
+        ("format" = DeploymentFormat, Query, description = "Artifact format: `docker_compose`, `systemd` or `env`"),
+        ("name" = Option<String>, Query, description = "Gateway name; letters, digits, dots, dashes and underscores"),
+        ("version" = Option<String>, Query, description = "Gateway version to pin, `latest` by default")
+    ),
+    responses(
+        (status = 200, description = "Gateway deployment artifact", body = String),
+        (status = 400, description = "Bad request - invalid gateway name or version"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - location does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn gateway_deployment(
+    _admin: AdminRole,
+    session: SessionInfo,
+    State(appstate): State<AppState>,
+    Path(network_id): Path<Id>,
+    Query(query): Query<GatewayDeploymentQuery>,
+) -> Result<Response, WebError> {
+    let network = WireguardNetwork::find_by_id(&appstate.pool, network_id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Network {network_id} not found")))?;
+    debug!(
+        "User {} rendering gateway deployment for location {network} with {query:?}",
+        session.user.username
+    );
+    let config = server_config();
+    let deployment = GatewayDeployment::new(
+        network.name.clone(),
+        query.name,
+        network.generate_gateway_token()?,
+        config.grpc_url.to_string(),
+        config.grpc_cert.is_some(),
+        query.version,
+    )?;
+    let content = deployment.render(query.format)?;
+    let content_type = match query.format {
+        DeploymentFormat::DockerCompose => "application/yaml",
+        DeploymentFormat::Systemd | DeploymentFormat::Env => "text/plain",
+    };
+    info!(
+        "User {} rendered gateway deployment for location {network}",
+        session.user.username
+    );
+
+    Ok(([(CONTENT_TYPE, content_type)], content).into_response())
+}
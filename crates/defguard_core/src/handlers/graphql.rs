@@ -0,0 +1,426 @@
+use std::sync::LazyLock;
+
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, Error, Object, Request, Result, Schema, SimpleObject,
+};
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+};
+use chrono::{DateTime, NaiveDateTime, TimeDelta, Utc};
+use defguard_common::db::Id;
+use serde_json::json;
+use sqlx::{Error as SqlxError, PgPool};
+
+use super::{ApiResponse, ApiResult};
+use crate::{
+    appstate::AppState,
+    auth::SessionInfo,
+    db::{
+        Device, User, WireguardNetwork,
+        models::{
+            device::WireguardNetworkDevice,
+            wireguard::{DateTimeAggregation, WireguardNetworkStats, networks_stats},
+        },
+    },
+};
+
+/// Maximum depth of GraphQL queries, so nested relations can't be expanded endlessly.
+const GRAPHQL_MAX_DEPTH: usize = 10;
+
+type DefguardSchema = Schema<Query, EmptyMutation, EmptySubscription>;
+
+static SCHEMA: LazyLock<DefguardSchema> = LazyLock::new(|| {
+    Schema::build(Query, EmptyMutation, EmptySubscription)
+        .limit_depth(GRAPHQL_MAX_DEPTH)
+        .finish()
+});
+
+/// User executing a GraphQL query.
+struct QueryUser {
+    id: Id,
+    is_admin: bool,
+}
+
+fn db_error(err: SqlxError) -> Error {
+    error!("GraphQL query failed: {err}");
+    Error::new("Internal server error")
+}
+
+fn query_user<'a>(ctx: &Context<'a>) -> Result<&'a QueryUser> {
+    ctx.data::<QueryUser>()
+}
+
+/// Guard for fields only available to admins.
+fn admin_only(ctx: &Context<'_>) -> Result<()> {
+    if query_user(ctx)?.is_admin {
+        Ok(())
+    } else {
+        Err(Error::new("Admin role required"))
+    }
+}
+
+/// Check if the current user is an admin or owns a given object.
+fn ensure_admin_or_owner(ctx: &Context<'_>, user_id: Id) -> Result<()> {
+    let user = query_user(ctx)?;
+    if user.is_admin || user.id == user_id {
+        Ok(())
+    } else {
+        Err(Error::new("Access denied"))
+    }
+}
+
+/// Statistics since `from`, or from the last hour.
+fn stats_since(from: Option<DateTime<Utc>>) -> Result<(NaiveDateTime, DateTimeAggregation)> {
+    let now = Utc::now();
+    let from = from.unwrap_or(now - TimeDelta::hours(1));
+    let aggregation = match now - from {
+        duration if duration < TimeDelta::zero() => {
+            return Err(Error::new("`from` can't be in the future"));
+        }
+        duration if duration >= TimeDelta::hours(6) => DateTimeAggregation::Hour,
+        _ => DateTimeAggregation::Minute,
+    };
+
+    Ok((from.naive_utc(), aggregation))
+}
+
+pub(crate) struct Query;
+
+#[Object]
+impl Query {
+    /// Currently logged in user.
+    async fn me(&self, ctx: &Context<'_>) -> Result<GraphQlUser> {
+        let pool = ctx.data::<PgPool>()?;
+        let user = User::find_by_id(pool, query_user(ctx)?.id)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| Error::new("User not found"))?;
+
+        Ok(GraphQlUser(user))
+    }
+
+    /// All users.
+    #[graphql(guard = "admin_only")]
+    async fn users(&self, ctx: &Context<'_>) -> Result<Vec<GraphQlUser>> {
+        let pool = ctx.data::<PgPool>()?;
+        let users = User::all(pool).await.map_err(db_error)?;
+
+        Ok(users.into_iter().map(GraphQlUser).collect())
+    }
+
+    /// User with a given username. Non-admin users can only query themselves.
+    async fn user(&self, ctx: &Context<'_>, username: String) -> Result<Option<GraphQlUser>> {
+        let pool = ctx.data::<PgPool>()?;
+        let Some(user) = User::find_by_username(pool, &username)
+            .await
+            .map_err(db_error)?
+        else {
+            return Ok(None);
+        };
+        ensure_admin_or_owner(ctx, user.id)?;
+
+        Ok(Some(GraphQlUser(user)))
+    }
+
+    /// All locations.
+    #[graphql(guard = "admin_only")]
+    async fn locations(&self, ctx: &Context<'_>) -> Result<Vec<Location>> {
+        let pool = ctx.data::<PgPool>()?;
+        let locations = WireguardNetwork::all(pool).await.map_err(db_error)?;
+
+        Ok(locations.into_iter().map(Location).collect())
+    }
+
+    /// Location with a given ID.
+    #[graphql(guard = "admin_only")]
+    async fn location(&self, ctx: &Context<'_>, id: Id) -> Result<Option<Location>> {
+        let pool = ctx.data::<PgPool>()?;
+        let location = WireguardNetwork::find_by_id(pool, id)
+            .await
+            .map_err(db_error)?;
+
+        Ok(location.map(Location))
+    }
+
+    /// Statistics of all locations since `from`, defaults to the last hour.
+    #[graphql(guard = "admin_only")]
+    async fn stats(&self, ctx: &Context<'_>, from: Option<DateTime<Utc>>) -> Result<Stats> {
+        let pool = ctx.data::<PgPool>()?;
+        let (from, aggregation) = stats_since(from)?;
+        let stats = networks_stats(pool, &from, &aggregation)
+            .await
+            .map_err(db_error)?;
+
+        Ok(stats.into())
+    }
+}
+
+pub(crate) struct GraphQlUser(User<Id>);
+
+#[Object(name = "User")]
+impl GraphQlUser {
+    async fn id(&self) -> Id {
+        self.0.id
+    }
+
+    async fn username(&self) -> &str {
+        &self.0.username
+    }
+
+    async fn first_name(&self) -> &str {
+        &self.0.first_name
+    }
+
+    async fn last_name(&self) -> &str {
+        &self.0.last_name
+    }
+
+    /// Only available to admins and the user itself.
+    async fn email(&self, ctx: &Context<'_>) -> Result<&str> {
+        ensure_admin_or_owner(ctx, self.0.id)?;
+        Ok(&self.0.email)
+    }
+
+    /// Only available to admins and the user itself.
+    async fn phone(&self, ctx: &Context<'_>) -> Result<Option<&str>> {
+        ensure_admin_or_owner(ctx, self.0.id)?;
+        Ok(self.0.phone.as_deref())
+    }
+
+    async fn is_active(&self) -> bool {
+        self.0.is_active
+    }
+
+    async fn mfa_enabled(&self) -> bool {
+        self.0.mfa_enabled
+    }
+
+    /// Names of groups the user is a member of.
+    async fn groups(&self, ctx: &Context<'_>) -> Result<Vec<String>> {
+        let pool = ctx.data::<PgPool>()?;
+        self.0.member_of_names(pool).await.map_err(db_error)
+    }
+
+    /// Only available to admins and the user itself.
+    async fn devices(&self, ctx: &Context<'_>) -> Result<Vec<GraphQlDevice>> {
+        ensure_admin_or_owner(ctx, self.0.id)?;
+        let pool = ctx.data::<PgPool>()?;
+        let devices = Device::all_for_username(pool, &self.0.username)
+            .await
+            .map_err(db_error)?;
+
+        Ok(devices.into_iter().map(GraphQlDevice).collect())
+    }
+}
+
+pub(crate) struct GraphQlDevice(Device<Id>);
+
+#[Object(name = "Device")]
+impl GraphQlDevice {
+    async fn id(&self) -> Id {
+        self.0.id
+    }
+
+    async fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    async fn description(&self) -> Option<&str> {
+        self.0.description.as_deref()
+    }
+
+    async fn wireguard_pubkey(&self) -> &str {
+        &self.0.wireguard_pubkey
+    }
+
+    async fn device_type(&self) -> String {
+        self.0.device_type.to_string()
+    }
+
+    async fn created(&self) -> NaiveDateTime {
+        self.0.created
+    }
+
+    async fn configured(&self) -> bool {
+        self.0.configured
+    }
+
+    /// Locations this device is configured for, with their sessions.
+    async fn locations(&self, ctx: &Context<'_>) -> Result<Vec<DeviceLocation>> {
+        let pool = ctx.data::<PgPool>()?;
+        let network_devices = WireguardNetworkDevice::find_by_device(pool, self.0.id)
+            .await
+            .map_err(db_error)?
+            .unwrap_or_default();
+
+        Ok(network_devices.into_iter().map(DeviceLocation).collect())
+    }
+}
+
+/// Device configured in a location, along with its session state.
+pub(crate) struct DeviceLocation(WireguardNetworkDevice);
+
+#[Object]
+impl DeviceLocation {
+    async fn device_id(&self) -> Id {
+        self.0.device_id
+    }
+
+    async fn location_id(&self) -> Id {
+        self.0.wireguard_network_id
+    }
+
+    /// Name of the location.
+    async fn location_name(&self, ctx: &Context<'_>) -> Result<Option<String>> {
+        let pool = ctx.data::<PgPool>()?;
+        let location = WireguardNetwork::find_by_id(pool, self.0.wireguard_network_id)
+            .await
+            .map_err(db_error)?;
+
+        Ok(location.map(|location| location.name))
+    }
+
+    async fn wireguard_ips(&self) -> Vec<String> {
+        self.0
+            .wireguard_ips
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    /// Whether the device has an active session, e.g. completed MFA for this location.
+    async fn authorized(&self) -> bool {
+        self.0.is_authorized
+    }
+
+    async fn authorized_at(&self) -> Option<NaiveDateTime> {
+        self.0.authorized_at
+    }
+}
+
+pub(crate) struct Location(WireguardNetwork<Id>);
+
+#[Object]
+impl Location {
+    async fn id(&self) -> Id {
+        self.0.id
+    }
+
+    async fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    async fn address(&self) -> Vec<String> {
+        self.0.address.iter().map(ToString::to_string).collect()
+    }
+
+    async fn endpoint(&self) -> &str {
+        &self.0.endpoint
+    }
+
+    async fn port(&self) -> i32 {
+        self.0.port
+    }
+
+    async fn allowed_ips(&self) -> Vec<String> {
+        self.0.allowed_ips.iter().map(ToString::to_string).collect()
+    }
+
+    async fn location_mfa_mode(&self) -> String {
+        self.0.location_mfa_mode.to_string()
+    }
+
+    async fn connected_at(&self) -> Option<NaiveDateTime> {
+        self.0.connected_at
+    }
+
+    /// Devices configured for this location, with their sessions.
+    #[graphql(guard = "admin_only")]
+    async fn devices(&self, ctx: &Context<'_>) -> Result<Vec<DeviceLocation>> {
+        let pool = ctx.data::<PgPool>()?;
+        let network_devices = WireguardNetworkDevice::all_for_network(pool, self.0.id)
+            .await
+            .map_err(db_error)?;
+
+        Ok(network_devices.into_iter().map(DeviceLocation).collect())
+    }
+
+    /// Statistics since `from`, defaults to the last hour.
+    #[graphql(guard = "admin_only")]
+    async fn stats(&self, ctx: &Context<'_>, from: Option<DateTime<Utc>>) -> Result<Stats> {
+        let pool = ctx.data::<PgPool>()?;
+        let (from, aggregation) = stats_since(from)?;
+        let stats = self
+            .0
+            .network_stats(pool, &from, &aggregation)
+            .await
+            .map_err(db_error)?;
+
+        Ok(stats.into())
+    }
+}
+
+#[derive(SimpleObject)]
+pub(crate) struct Stats {
+    current_active_users: i64,
+    current_active_user_devices: i64,
+    current_active_network_devices: i64,
+    active_users: i64,
+    active_user_devices: i64,
+    active_network_devices: i64,
+    upload: i64,
+    download: i64,
+    transfer_series: Vec<TransferStats>,
+}
+
+#[derive(SimpleObject)]
+pub(crate) struct TransferStats {
+    collected_at: Option<NaiveDateTime>,
+    upload: Option<i64>,
+    download: Option<i64>,
+}
+
+impl From<WireguardNetworkStats> for Stats {
+    fn from(stats: WireguardNetworkStats) -> Self {
+        Self {
+            current_active_users: stats.current_active_users,
+            current_active_user_devices: stats.current_active_user_devices,
+            current_active_network_devices: stats.current_active_network_devices,
+            active_users: stats.active_users,
+            active_user_devices: stats.active_user_devices,
+            active_network_devices: stats.active_network_devices,
+            upload: stats.upload,
+            download: stats.download,
+            transfer_series: stats
+                .transfer_series
+                .into_iter()
+                .map(|row| TransferStats {
+                    collected_at: row.collected_at,
+                    upload: row.upload,
+                    download: row.download,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Execute a GraphQL query
+///
+/// Available only if enabled with `DEFGUARD_GRAPHQL_ENABLED`. Exposes users, devices, locations,
+/// device sessions and statistics; fields are authorized like corresponding REST endpoints, so
+/// non-admin users can only query themselves and their devices.
+pub(crate) async fn graphql(
+    session: SessionInfo,
+    State(appstate): State<AppState>,
+    Json(request): Json<Request>,
+) -> ApiResult {
+    debug!("User {} is executing GraphQL query", session.user.username);
+    let request = request.data(appstate.pool).data(QueryUser {
+        id: session.user.id,
+        is_admin: session.is_admin,
+    });
+    let response = SCHEMA.execute(request).await;
+
+    Ok(ApiResponse::new(json!(response), StatusCode::OK))
+}
@@ -77,7 +77,7 @@ pub(crate) async fn bulk_assign_to_groups(
         "SELECT id, username, password_hash, last_name, first_name, email, \
             phone, mfa_enabled, totp_enabled, email_mfa_enabled, \
             totp_secret, email_mfa_secret, mfa_method \"mfa_method: _\", recovery_codes, is_active, openid_sub, \
-            from_ldap, ldap_pass_randomized, ldap_rdn, ldap_user_path, enrollment_pending \
+            from_ldap, ldap_pass_randomized, ldap_rdn, ldap_user_path, enrollment_pending, mail_language \
             FROM \"user\" WHERE id = ANY($1)",
         &data.users
     )
@@ -86,7 +86,8 @@ pub(crate) async fn bulk_assign_to_groups(
 
     let groups = query_as!(
         Group,
-        "SELECT * FROM \"group\" WHERE name = ANY($1)",
+        "SELECT id, name, is_admin, client_traffic_policy \"client_traffic_policy: _\" \
+        FROM \"group\" WHERE name = ANY($1)",
         &data.groups
     )
     .fetch_all(&appstate.pool)
@@ -182,7 +183,7 @@ pub(crate) async fn list_groups_info(
         "SELECT g.id, g.name, \
         COALESCE(ARRAY_AGG(DISTINCT u.username) FILTER (WHERE u.username IS NOT NULL), '{}') \"members!\", \
         COALESCE(ARRAY_AGG(DISTINCT wn.name) FILTER (WHERE wn.name IS NOT NULL), '{}') \"vpn_locations!\", \
-        is_admin \
+        is_admin, is_helpdesk, is_auditor, g.client_traffic_policy \"client_traffic_policy: _\" \
         FROM \"group\" g \
         LEFT JOIN \"group_user\" gu ON gu.group_id = g.id \
         LEFT JOIN \"user\" u ON u.id = gu.user_id \
@@ -255,7 +256,9 @@ pub(crate) async fn list_groups(
                 "name": "name",
                 "members": ["user"],
                 "vpn_locations": ["location"],
-                "is_admin": false
+                "is_admin": false,
+                "is_helpdesk": false,
+                "is_auditor": false
             }
         )),
         (status = 401, description = "Unauthorized to retrieve a group.", body = ApiResponse, example = json!({"msg": "Session is required"})),
@@ -280,6 +283,12 @@ pub(crate) async fn get_group(
         let is_admin = group
             .has_permission(&appstate.pool, Permission::IsAdmin)
             .await?;
+        let is_helpdesk = group
+            .has_permission(&appstate.pool, Permission::IsHelpdesk)
+            .await?;
+        let is_auditor = group
+            .has_permission(&appstate.pool, Permission::IsAuditor)
+            .await?;
         info!("Retrieved group {name}");
         Ok(ApiResponse {
             json: json!(GroupInfo::new(
@@ -287,7 +296,10 @@ pub(crate) async fn get_group(
                 name,
                 members,
                 vpn_locations,
-                is_admin
+                is_admin,
+                is_helpdesk,
+                is_auditor,
+                group.client_traffic_policy
             )),
             status: StatusCode::OK,
         })
@@ -303,6 +315,9 @@ pub(crate) async fn get_group(
 /// Create group based on `EditGroupInfo` object.
 ///
 /// You can also choose whether group should grant admin privileges by changing `is_admin` parameter.
+/// Members of groups with `is_helpdesk` parameter set can initiate password resets of non-admin
+/// users. Members of groups with `is_auditor` parameter set have read-only access to activity log,
+/// statistics and configuration, with personal data redacted.
 ///
 /// # Returns
 /// - `EditGroupInfo` object
@@ -341,10 +356,26 @@ pub(crate) async fn create_group(
     let mut transaction = appstate.pool.begin().await?;
 
     // FIXME: conflicts must not return internal server error (500).
-    let group = Group::new(&group_info.name).save(&appstate.pool).await?;
+    let mut group = Group::new(&group_info.name);
+    group.client_traffic_policy = group_info.client_traffic_policy;
+    let group = group.save(&appstate.pool).await?;
     group
         .set_permission(&mut *transaction, Permission::IsAdmin, group_info.is_admin)
         .await?;
+    group
+        .set_permission(
+            &mut *transaction,
+            Permission::IsHelpdesk,
+            group_info.is_helpdesk,
+        )
+        .await?;
+    group
+        .set_permission(
+            &mut *transaction,
+            Permission::IsAuditor,
+            group_info.is_auditor,
+        )
+        .await?;
 
     let mut members = Vec::new();
     for member_username in &group_info.members {
@@ -396,6 +427,7 @@ pub(crate) async fn create_group(
 /// Rename group and change members basing on `EditGroupInfo` object.
 ///
 ///  You can also change `is_admin` parameter if you want to grant admin privileges to group members.
+/// Similarly, `is_helpdesk` and `is_auditor` parameters grant helpdesk and auditor privileges.
 ///
 /// # Returns
 /// - empty JSON
@@ -437,10 +469,13 @@ pub(crate) async fn modify_group(
     let mut remove_from_ldap_groups: HashMap<&User<Id>, HashSet<&str>> = HashMap::new();
     let mut transaction = appstate.pool.begin().await?;
 
-    // Rename only when needed.
+    // Save only when needed.
     //
-    if group.name != group_info.name {
+    if group.name != group_info.name
+        || group.client_traffic_policy != group_info.client_traffic_policy
+    {
         group.name.clone_from(&group_info.name);
+        group.client_traffic_policy = group_info.client_traffic_policy;
         group.save(&mut *transaction).await?;
     }
 
@@ -464,6 +499,20 @@ pub(crate) async fn modify_group(
     group
         .set_permission(&mut *transaction, Permission::IsAdmin, group_info.is_admin)
         .await?;
+    group
+        .set_permission(
+            &mut *transaction,
+            Permission::IsHelpdesk,
+            group_info.is_helpdesk,
+        )
+        .await?;
+    group
+        .set_permission(
+            &mut *transaction,
+            Permission::IsAuditor,
+            group_info.is_auditor,
+        )
+        .await?;
 
     // Modify group members.
     let mut current_members = group.members(&mut *transaction).await?;
@@ -0,0 +1,298 @@
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+};
+use axum_client_ip::InsecureClientIp;
+use axum_extra::{TypedHeader, headers::UserAgent};
+use chrono::{NaiveDateTime, Utc};
+use defguard_common::db::{Id, models::Settings};
+use serde_json::json;
+use utoipa::ToSchema;
+
+use super::{
+    ApiResponse, ApiResult, mail::send_helpdesk_password_reset_email, user::check_password_strength,
+};
+use crate::{
+    appstate::AppState,
+    auth::{HelpdeskRole, SessionInfo},
+    db::{
+        User,
+        models::helpdesk_password_reset::{
+            HelpdeskPasswordReset, HelpdeskPasswordResetStatus, MAX_FAILED_ATTEMPTS,
+        },
+    },
+    enterprise::ldap::utils::ldap_change_password,
+    error::WebError,
+    events::{ApiEvent, ApiEventType, ApiRequestContext},
+};
+
+#[derive(Deserialize, ToSchema)]
+pub struct HelpdeskPasswordResetData {
+    /// Temporary password, activated once the user confirms the reset
+    pub password: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct HelpdeskPasswordResetConfirmation {
+    pub username: String,
+    /// Verification code sent to the user's email
+    pub code: String,
+    /// Temporary password received from helpdesk
+    pub password: String,
+}
+
+/// Helpdesk password reset without its temporary password and verification code.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct HelpdeskPasswordResetInfo {
+    pub id: Id,
+    pub requested_by: String,
+    pub status: HelpdeskPasswordResetStatus,
+    pub failed_attempts: i32,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub confirmed_at: Option<NaiveDateTime>,
+}
+
+impl From<HelpdeskPasswordReset<Id>> for HelpdeskPasswordResetInfo {
+    fn from(reset: HelpdeskPasswordReset<Id>) -> Self {
+        Self {
+            id: reset.id,
+            status: reset.status(),
+            requested_by: reset.requested_by_username,
+            failed_attempts: reset.failed_attempts,
+            created_at: reset.created_at,
+            expires_at: reset.expires_at,
+            confirmed_at: reset.confirmed_at,
+        }
+    }
+}
+
+/// Finds a user whose password can be reset by the session user. Helpdesk users can't reset
+/// passwords of admins.
+async fn find_resettable_user(
+    appstate: &AppState,
+    session: &SessionInfo,
+    username: &str,
+) -> Result<User<Id>, WebError> {
+    if session.user.username == username {
+        return Err(WebError::BadRequest(
+            "Can't reset own password through helpdesk".into(),
+        ));
+    }
+    let user = User::find_by_username(&appstate.pool, username)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("User {username} not found")))?;
+    if !session.is_admin && user.is_admin(&appstate.pool).await? {
+        return Err(WebError::Forbidden(
+            "Helpdesk can't reset passwords of admin users".into(),
+        ));
+    }
+
+    Ok(user)
+}
+
+/// Initiate helpdesk password reset
+///
+/// Sets a temporary password for a user, which becomes active only after the user confirms the
+/// reset with a verification code sent to their email. Previous unconfirmed resets of the user are
+/// invalidated. Available to members of admin and helpdesk groups; helpdesk can't reset passwords
+/// of admin users.
+///
+/// # Returns
+/// - `HelpdeskPasswordResetInfo` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    post,
+    path = "/api/v1/user/{username}/helpdesk_password_reset",
+    tag = "user",
+    params(
+        ("username" = String, description = "Name of a user"),
+    ),
+    request_body = HelpdeskPasswordResetData,
+    responses(
+        (status = 201, description = "Verification code has been sent to the user", body = HelpdeskPasswordResetInfo),
+        (status = 400, description = "Bad request - password does not satisfy requirements, user is disabled or own account"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - helpdesk or admin role required"),
+        (status = 404, description = "Not found - user does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn request_helpdesk_password_reset(
+    _role: HelpdeskRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    State(appstate): State<AppState>,
+    Path(username): Path<String>,
+    Json(data): Json<HelpdeskPasswordResetData>,
+) -> ApiResult {
+    debug!(
+        "User {} initiating helpdesk password reset for user {username}",
+        session.user.username
+    );
+    let user = find_resettable_user(&appstate, &session, &username).await?;
+    if !user.is_active {
+        return Err(WebError::BadRequest(format!("User {username} is disabled")));
+    }
+    if let Err(err) = check_password_strength(&data.password) {
+        debug!("Temporary password for user {username} not strong enough: {err}");
+        return Err(WebError::BadRequest(
+            "Password does not satisfy requirements".into(),
+        ));
+    }
+
+    let settings = Settings::get_current_settings();
+    let reset = HelpdeskPasswordReset::new(
+        user.id,
+        &session.user,
+        &data.password,
+        settings.password_reset_token_timeout(),
+    )
+    .map_err(|err| WebError::Serialization(format!("Failed to hash password: {err}")))?;
+    let mut transaction = appstate.pool.begin().await?;
+    HelpdeskPasswordReset::invalidate_unconfirmed(&mut *transaction, user.id).await?;
+    let reset = reset.save(&mut *transaction).await?;
+    transaction.commit().await?;
+    send_helpdesk_password_reset_email(&user, &reset, &appstate.mail_tx)?;
+    info!(
+        "User {} initiated helpdesk password reset for user {username}",
+        session.user.username
+    );
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::HelpdeskPasswordResetRequested { user }),
+    })?;
+
+    Ok(ApiResponse {
+        json: json!(HelpdeskPasswordResetInfo::from(reset)),
+        status: StatusCode::CREATED,
+    })
+}
+
+/// List helpdesk password resets of a user
+///
+/// # Returns
+/// - `Vec<HelpdeskPasswordResetInfo>` object, the most recent first
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/user/{username}/helpdesk_password_reset",
+    tag = "user",
+    params(
+        ("username" = String, description = "Name of a user"),
+    ),
+    responses(
+        (status = 200, description = "List of helpdesk password resets", body = Vec<HelpdeskPasswordResetInfo>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - helpdesk or admin role required"),
+        (status = 404, description = "Not found - user does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn list_helpdesk_password_resets(
+    _role: HelpdeskRole,
+    State(appstate): State<AppState>,
+    Path(username): Path<String>,
+) -> ApiResult {
+    let user = User::find_by_username(&appstate.pool, &username)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("User {username} not found")))?;
+    let resets: Vec<HelpdeskPasswordResetInfo> =
+        HelpdeskPasswordReset::all_for_user(&appstate.pool, user.id)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+
+    Ok(ApiResponse {
+        json: json!(resets),
+        status: StatusCode::OK,
+    })
+}
+
+/// Confirm helpdesk password reset
+///
+/// Public endpoint used by the user to activate the temporary password set by helpdesk. The reset
+/// is invalidated after too many failed attempts.
+///
+/// # Returns
+/// - empty JSON
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    post,
+    path = "/api/v1/helpdesk_password_reset/confirm",
+    tag = "user",
+    request_body = HelpdeskPasswordResetConfirmation,
+    responses(
+        (status = 200, description = "Temporary password has been activated"),
+        (status = 400, description = "Bad request - invalid code or password, or no pending reset"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn confirm_helpdesk_password_reset(
+    user_agent: TypedHeader<UserAgent>,
+    InsecureClientIp(insecure_ip): InsecureClientIp,
+    State(appstate): State<AppState>,
+    Json(data): Json<HelpdeskPasswordResetConfirmation>,
+) -> ApiResult {
+    debug!(
+        "Confirming helpdesk password reset for user {}",
+        data.username
+    );
+    // Don't reveal whether the user or a pending reset exists.
+    let invalid = || WebError::BadRequest("Invalid verification code or password".into());
+    let Some(mut user) = User::find_by_username(&appstate.pool, &data.username).await? else {
+        return Err(invalid());
+    };
+    let Some(mut reset) = HelpdeskPasswordReset::find_pending(&appstate.pool, user.id).await?
+    else {
+        return Err(invalid());
+    };
+
+    if !reset.verify(&data.code, &data.password) {
+        reset.failed_attempts += 1;
+        if reset.failed_attempts >= MAX_FAILED_ATTEMPTS {
+            warn!(
+                "Invalidating helpdesk password reset for user {} after {} failed attempts",
+                user.username, reset.failed_attempts
+            );
+            reset.password_hash = None;
+        }
+        reset.save(&appstate.pool).await?;
+        return Err(invalid());
+    }
+
+    let mut transaction = appstate.pool.begin().await?;
+    user.password_hash = reset.password_hash.take();
+    user.save(&mut *transaction).await?;
+    reset.confirmed_at = Some(Utc::now().naive_utc());
+    reset.save(&mut *transaction).await?;
+    transaction.commit().await?;
+    ldap_change_password(&mut user, &data.password, &appstate.pool).await;
+    info!(
+        "User {} confirmed helpdesk password reset initiated by {}",
+        user.username, reset.requested_by_username
+    );
+    appstate.emit_event(ApiEvent {
+        context: ApiRequestContext::new(
+            user.id,
+            user.username.clone(),
+            insecure_ip,
+            user_agent.to_string(),
+        ),
+        event: Box::new(ApiEventType::HelpdeskPasswordResetConfirmed { user }),
+    })?;
+
+    Ok(ApiResponse::default())
+}
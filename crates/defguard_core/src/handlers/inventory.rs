@@ -0,0 +1,129 @@
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use serde_json::json;
+
+use super::ApiResponse;
+use crate::{
+    appstate::AppState,
+    auth::AdminRole,
+    error::WebError,
+    inventory::{
+        DEVICE_COLUMNS, ExportFormat, RowRenderer, USER_COLUMNS, export_devices, export_users,
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct InventoryExportQuery {
+    #[serde(default)]
+    format: ExportFormat,
+    columns: Option<String>,
+}
+
+pub(super) fn attachment(format: ExportFormat, name: &str, body: Body) -> Response {
+    let file_name = format!(
+        "{name}-{}.{}",
+        Utc::now().format("%Y%m%d"),
+        format.extension()
+    );
+    (
+        [
+            (CONTENT_TYPE, format.content_type().to_string()),
+            (
+                CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{file_name}\""),
+            ),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// Export user inventory
+///
+/// Streams all users with their groups, number of devices and the latest handshake of any of
+/// their devices. Available columns: `id`, `username`, `first_name`, `last_name`, `email`,
+/// `phone`, `is_active`, `enrollment_pending`, `mfa_enabled`, `mfa_method`, `from_ldap`,
+/// `groups`, `devices`, `last_handshake`.
+///
+/// # Returns
+/// - CSV or NDJSON document
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/inventory/users",
+    tag = "inventory",
+    params(
+        ("format" = Option<String>, Query, description = "Export format: `csv` (default) or `ndjson`"),
+        ("columns" = Option<String>, Query, description = "Comma-separated list of columns, all columns by default")
+    ),
+    responses(
+        (status = 200, description = "User inventory as CSV or NDJSON.", content_type = "text/csv"),
+        (status = 400, description = "Unknown column.", body = ApiResponse, example = json!({"msg": "Unknown column password"})),
+        (status = 401, description = "Unauthorized to export inventory.", body = ApiResponse, example = json!({"msg": "Session is required"})),
+        (status = 403, description = "You don't have permission to export inventory.", body = ApiResponse, example = json!({"msg": "access denied"}))
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub(crate) async fn export_user_inventory(
+    _role: AdminRole,
+    State(appstate): State<AppState>,
+    Query(query): Query<InventoryExportQuery>,
+) -> Result<Response, WebError> {
+    debug!("Exporting user inventory with {query:?}");
+    let renderer = RowRenderer::new(query.format, query.columns.as_deref(), USER_COLUMNS)
+        .map_err(WebError::BadRequest)?;
+    let rows = export_users(appstate.pool.clone(), renderer);
+
+    Ok(attachment(query.format, "users", Body::from_stream(rows)))
+}
+
+/// Export device inventory
+///
+/// Streams all devices with their owners, addresses assigned in each location and the latest
+/// handshake. Available columns: `id`, `name`, `owner`, `device_type`, `wireguard_pubkey`,
+/// `description`, `created`, `configured`, `addresses`, `last_handshake`.
+///
+/// # Returns
+/// - CSV or NDJSON document
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/inventory/devices",
+    tag = "inventory",
+    params(
+        ("format" = Option<String>, Query, description = "Export format: `csv` (default) or `ndjson`"),
+        ("columns" = Option<String>, Query, description = "Comma-separated list of columns, all columns by default")
+    ),
+    responses(
+        (status = 200, description = "Device inventory as CSV or NDJSON.", content_type = "text/csv"),
+        (status = 400, description = "Unknown column.", body = ApiResponse, example = json!({"msg": "Unknown column preshared_key"})),
+        (status = 401, description = "Unauthorized to export inventory.", body = ApiResponse, example = json!({"msg": "Session is required"})),
+        (status = 403, description = "You don't have permission to export inventory.", body = ApiResponse, example = json!({"msg": "access denied"}))
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub(crate) async fn export_device_inventory(
+    _role: AdminRole,
+    State(appstate): State<AppState>,
+    Query(query): Query<InventoryExportQuery>,
+) -> Result<Response, WebError> {
+    debug!("Exporting device inventory with {query:?}");
+    let renderer = RowRenderer::new(query.format, query.columns.as_deref(), DEVICE_COLUMNS)
+        .map_err(WebError::BadRequest)?;
+    let rows = export_devices(appstate.pool.clone(), renderer);
+
+    Ok(attachment(query.format, "devices", Body::from_stream(rows)))
+}
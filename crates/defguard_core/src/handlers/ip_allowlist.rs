@@ -0,0 +1,82 @@
+use axum::{
+    extract::{Query, State},
+    http::header::CONTENT_TYPE,
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use defguard_common::{config::IpAllowlistFormat, db::Id};
+use serde_json::json;
+
+use super::ApiResponse;
+use crate::{
+    appstate::AppState, auth::AdminRole, db::WireguardNetwork, error::WebError,
+    ip_allowlist::IpAllowlist,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct IpAllowlistQuery {
+    #[serde(default)]
+    format: IpAllowlistFormat,
+    location: Option<Id>,
+}
+
+/// Export IP allow-list
+///
+/// Returns mapping of users and groups to VPN addresses of their configured devices, for use
+/// in third-party firewalls. Available formats are generic JSON, PAN-OS User-ID API message
+/// tagging addresses with `defguard-user-<username>` and `defguard-group-<group>` tags for use
+/// in dynamic address groups, and `ipset restore` script.
+///
+/// # Returns
+/// - IP allow-list in requested format
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/ip_allowlist",
+    tag = "ip_allowlist",
+    params(
+        ("format" = Option<String>, Query, description = "Format of allow-list: `json` (default), `panos` or `ipset`"),
+        ("location" = Option<i64>, Query, description = "Limit allow-list to addresses in a location")
+    ),
+    responses(
+        (status = 200, description = "IP allow-list", body = IpAllowlist),
+        (status = 401, description = "Unauthorized to export IP allow-list.", body = ApiResponse, example = json!({"msg": "Session is required"})),
+        (status = 403, description = "You don't have permission to export IP allow-list.", body = ApiResponse, example = json!({"msg": "access denied"})),
+        (status = 404, description = "Location not found.", body = ApiResponse, example = json!({"msg": "location not found"})),
+        (status = 500, description = "Unable to export IP allow-list.", body = ApiResponse, example = json!({"msg": "Internal server error"}))
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub(crate) async fn export_ip_allowlist(
+    _role: AdminRole,
+    State(appstate): State<AppState>,
+    Query(query): Query<IpAllowlistQuery>,
+) -> Result<Response, WebError> {
+    debug!("Exporting IP allow-list with {query:?}");
+    if let Some(location_id) = query.location {
+        if WireguardNetwork::find_by_id(&appstate.pool, location_id)
+            .await?
+            .is_none()
+        {
+            return Err(WebError::ObjectNotFound(format!(
+                "location {location_id} not found"
+            )));
+        }
+    }
+    let allowlist = IpAllowlist::current(&appstate.pool, query.location).await?;
+    let content_type = match query.format {
+        IpAllowlistFormat::Json => "application/json",
+        IpAllowlistFormat::Panos => "application/xml",
+        IpAllowlistFormat::Ipset => "text/plain",
+    };
+
+    Ok((
+        [(CONTENT_TYPE, content_type)],
+        allowlist.render(query.format, Utc::now().naive_utc()),
+    )
+        .into_response())
+}
@@ -0,0 +1,419 @@
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+};
+use defguard_common::db::{Id, NoId};
+use reqwest::Url;
+use serde_json::{Value, json};
+use utoipa::ToSchema;
+
+use super::{ApiResponse, ApiResult};
+use crate::{
+    appstate::AppState,
+    auth::{AdminRole, SessionInfo},
+    db::models::itsm::{
+        DEFAULT_DESCRIPTION_TEMPLATE, DEFAULT_SUMMARY_TEMPLATE, ItsmConnector, ItsmConnectorType,
+    },
+    error::WebError,
+    events::{ApiEvent, ApiEventType, ApiRequestContext},
+};
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct ItsmConnectorData {
+    pub name: String,
+    pub connector_type: ItsmConnectorType,
+    /// Base URL of ServiceNow instance or Jira site
+    pub url: String,
+    pub username: String,
+    /// ServiceNow password or Jira API token. Required when creating a connector, current one is
+    /// kept if omitted when modifying it.
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Jira project key (required for Jira), or ServiceNow assignment group
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Jira issue type, or ServiceNow table
+    #[serde(default)]
+    pub ticket_type: Option<String>,
+    pub enabled: bool,
+    #[serde(default)]
+    pub on_gateway_down: bool,
+    /// Minutes a gateway has to be disconnected for, before a ticket is opened
+    #[serde(default = "default_gateway_down_threshold")]
+    pub gateway_down_threshold: i32,
+    #[serde(default)]
+    pub on_license_expired: bool,
+    #[serde(default)]
+    pub on_certificate_expiring: bool,
+    /// Days before certificate expiration when a ticket is opened
+    #[serde(default = "default_certificate_expiration_threshold")]
+    pub certificate_expiration_threshold: i32,
+    #[serde(default = "default_summary_template")]
+    pub summary_template: String,
+    #[serde(default = "default_description_template")]
+    pub description_template: String,
+    /// Additional ticket fields, mapping field names to templates of their values
+    #[serde(default = "default_fields")]
+    #[schema(value_type = Object)]
+    pub fields: Value,
+}
+
+fn default_gateway_down_threshold() -> i32 {
+    15
+}
+
+fn default_certificate_expiration_threshold() -> i32 {
+    14
+}
+
+fn default_summary_template() -> String {
+    DEFAULT_SUMMARY_TEMPLATE.into()
+}
+
+fn default_description_template() -> String {
+    DEFAULT_DESCRIPTION_TEMPLATE.into()
+}
+
+fn default_fields() -> Value {
+    json!({})
+}
+
+impl From<ItsmConnectorData> for ItsmConnector {
+    fn from(data: ItsmConnectorData) -> Self {
+        Self {
+            id: NoId,
+            name: data.name,
+            connector_type: data.connector_type,
+            url: data.url,
+            username: data.username,
+            secret: data.secret.unwrap_or_default(),
+            project: data.project,
+            ticket_type: data.ticket_type,
+            enabled: data.enabled,
+            on_gateway_down: data.on_gateway_down,
+            gateway_down_threshold: data.gateway_down_threshold,
+            on_license_expired: data.on_license_expired,
+            on_certificate_expiring: data.on_certificate_expiring,
+            certificate_expiration_threshold: data.certificate_expiration_threshold,
+            summary_template: data.summary_template,
+            description_template: data.description_template,
+            fields: data.fields,
+        }
+    }
+}
+
+impl ItsmConnectorData {
+    /// Apply data to an existing connector, keeping its current secret if none was provided.
+    fn apply(self, connector: &mut ItsmConnector<Id>) {
+        connector.name = self.name;
+        connector.connector_type = self.connector_type;
+        connector.url = self.url;
+        connector.username = self.username;
+        if let Some(secret) = self.secret {
+            connector.secret = secret;
+        }
+        connector.project = self.project;
+        connector.ticket_type = self.ticket_type;
+        connector.enabled = self.enabled;
+        connector.on_gateway_down = self.on_gateway_down;
+        connector.gateway_down_threshold = self.gateway_down_threshold;
+        connector.on_license_expired = self.on_license_expired;
+        connector.on_certificate_expiring = self.on_certificate_expiring;
+        connector.certificate_expiration_threshold = self.certificate_expiration_threshold;
+        connector.summary_template = self.summary_template;
+        connector.description_template = self.description_template;
+        connector.fields = self.fields;
+    }
+}
+
+fn validate_connector<I>(connector: &ItsmConnector<I>) -> Result<(), WebError> {
+    match Url::parse(&connector.url) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+        _ => {
+            return Err(WebError::BadRequest(format!(
+                "Invalid ITSM connector URL {}",
+                connector.url
+            )));
+        }
+    }
+    if connector.connector_type == ItsmConnectorType::Jira && connector.project.is_none() {
+        return Err(WebError::BadRequest(
+            "Jira project key is required".to_string(),
+        ));
+    }
+    if connector.gateway_down_threshold < 1 || connector.certificate_expiration_threshold < 1 {
+        return Err(WebError::BadRequest(
+            "ITSM connector thresholds have to be positive".to_string(),
+        ));
+    }
+    if let Err(err) = connector.validate_templates() {
+        debug!(
+            "Invalid template of ITSM connector {}: {err}",
+            connector.name
+        );
+        return Err(WebError::BadRequest(format!(
+            "Invalid template of ITSM connector {}",
+            connector.name
+        )));
+    }
+
+    Ok(())
+}
+
+async fn find_connector(appstate: &AppState, id: Id) -> Result<ItsmConnector<Id>, WebError> {
+    ItsmConnector::find_by_id(&appstate.pool, id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("ITSM connector {id} not found")))
+}
+
+/// List all ITSM connectors
+///
+/// Secrets of connectors are never returned.
+///
+/// # Returns
+/// - `Vec<ItsmConnector>` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/itsm_connector",
+    tag = "itsm_connector",
+    responses(
+        (status = 200, description = "List of ITSM connectors", body = Vec<ItsmConnector>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn list_itsm_connectors(
+    _admin: AdminRole,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let connectors = ItsmConnector::all(&appstate.pool).await?;
+
+    Ok(ApiResponse {
+        json: json!(connectors),
+        status: StatusCode::OK,
+    })
+}
+
+/// Get ITSM connector
+///
+/// # Returns
+/// - `ItsmConnector` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/itsm_connector/{id}",
+    tag = "itsm_connector",
+    params(
+        ("id" = Id, Path, description = "ITSM connector ID")
+    ),
+    responses(
+        (status = 200, description = "ITSM connector", body = ItsmConnector),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - ITSM connector does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn get_itsm_connector(
+    _admin: AdminRole,
+    Path(id): Path<Id>,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let connector = find_connector(&appstate, id).await?;
+
+    Ok(ApiResponse {
+        json: json!(connector),
+        status: StatusCode::OK,
+    })
+}
+
+/// Create ITSM connector
+///
+/// Connector opens tickets in ServiceNow or Jira for enabled kinds of incidents. Ticket summary,
+/// description and additional fields are Tera templates rendered with incident data.
+///
+/// # Returns
+/// - `ItsmConnector` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    post,
+    path = "/api/v1/itsm_connector",
+    tag = "itsm_connector",
+    request_body = ItsmConnectorData,
+    responses(
+        (status = 201, description = "ITSM connector created", body = ItsmConnector),
+        (status = 400, description = "Bad request - invalid connector configuration"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn create_itsm_connector(
+    _admin: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    State(appstate): State<AppState>,
+    Json(data): Json<ItsmConnectorData>,
+) -> ApiResult {
+    debug!(
+        "User {} creating ITSM connector {}",
+        session.user.username, data.name
+    );
+    if data.secret.is_none() {
+        return Err(WebError::BadRequest(
+            "ITSM connector secret is required".to_string(),
+        ));
+    }
+    let connector: ItsmConnector = data.into();
+    validate_connector(&connector)?;
+    let connector = connector.save(&appstate.pool).await?;
+    info!(
+        "User {} created ITSM connector {}",
+        session.user.username, connector.name
+    );
+
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::ItsmConnectorAdded {
+            connector: connector.clone(),
+        }),
+    })?;
+
+    Ok(ApiResponse {
+        json: json!(connector),
+        status: StatusCode::CREATED,
+    })
+}
+
+/// Modify ITSM connector
+///
+/// # Returns
+/// - `ItsmConnector` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    put,
+    path = "/api/v1/itsm_connector/{id}",
+    tag = "itsm_connector",
+    params(
+        ("id" = Id, Path, description = "ITSM connector ID")
+    ),
+    request_body = ItsmConnectorData,
+    responses(
+        (status = 200, description = "ITSM connector modified", body = ItsmConnector),
+        (status = 400, description = "Bad request - invalid connector configuration"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - ITSM connector does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn modify_itsm_connector(
+    _admin: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    Path(id): Path<Id>,
+    State(appstate): State<AppState>,
+    Json(data): Json<ItsmConnectorData>,
+) -> ApiResult {
+    let mut connector = find_connector(&appstate, id).await?;
+    debug!(
+        "User {} modifying ITSM connector {}",
+        session.user.username, connector.name
+    );
+    let before = connector.clone();
+    data.apply(&mut connector);
+    validate_connector(&connector)?;
+    connector.save(&appstate.pool).await?;
+    info!(
+        "User {} modified ITSM connector {}",
+        session.user.username, connector.name
+    );
+
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::ItsmConnectorModified {
+            before,
+            after: connector.clone(),
+        }),
+    })?;
+
+    Ok(ApiResponse {
+        json: json!(connector),
+        status: StatusCode::OK,
+    })
+}
+
+/// Remove ITSM connector
+///
+/// Tickets already opened by the connector are left intact in the ITSM system.
+///
+/// # Returns
+/// - empty JSON
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    delete,
+    path = "/api/v1/itsm_connector/{id}",
+    tag = "itsm_connector",
+    params(
+        ("id" = Id, Path, description = "ITSM connector ID")
+    ),
+    responses(
+        (status = 200, description = "ITSM connector removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - ITSM connector does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn delete_itsm_connector(
+    _admin: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    Path(id): Path<Id>,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let connector = find_connector(&appstate, id).await?;
+    debug!(
+        "User {} removing ITSM connector {}",
+        session.user.username, connector.name
+    );
+    connector.clone().delete(&appstate.pool).await?;
+    info!(
+        "User {} removed ITSM connector {}",
+        session.user.username, connector.name
+    );
+
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::ItsmConnectorRemoved { connector }),
+    })?;
+
+    Ok(ApiResponse::default())
+}
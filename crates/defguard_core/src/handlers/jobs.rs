@@ -0,0 +1,140 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+};
+use defguard_common::db::Id;
+use serde_json::json;
+
+use super::{ApiResponse, ApiResult};
+use crate::{
+    appstate::AppState,
+    auth::{AdminRole, SessionInfo},
+    db::models::background_job::BackgroundJob,
+    error::WebError,
+};
+
+// Number of jobs returned by the job list
+const RECENT_JOBS_LIMIT: i64 = 100;
+
+/// List recent background jobs
+///
+/// # Returns
+/// - `Vec<BackgroundJob>` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/jobs",
+    tag = "jobs",
+    responses(
+        (status = 200, description = "List of recent background jobs", body = Vec<BackgroundJob>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn list_jobs(_admin: AdminRole, State(appstate): State<AppState>) -> ApiResult {
+    let jobs = BackgroundJob::recent(&appstate.pool, RECENT_JOBS_LIMIT).await?;
+
+    Ok(ApiResponse {
+        json: json!(jobs),
+        status: StatusCode::OK,
+    })
+}
+
+/// Get background job status
+///
+/// Long-running operations return a job which should be polled until it's finished.
+///
+/// # Returns
+/// - `BackgroundJob` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/jobs/{id}",
+    tag = "jobs",
+    params(
+        ("id" = Id, Path, description = "Background job ID")
+    ),
+    responses(
+        (status = 200, description = "Background job", body = BackgroundJob),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - job does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn get_job(
+    _admin: AdminRole,
+    State(appstate): State<AppState>,
+    Path(id): Path<Id>,
+) -> ApiResult {
+    let job = BackgroundJob::find_by_id(&appstate.pool, id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Background job {id} not found")))?;
+
+    Ok(ApiResponse {
+        json: json!(job),
+        status: StatusCode::OK,
+    })
+}
+
+/// Cancel background job
+///
+/// # Returns
+/// - empty JSON
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    post,
+    path = "/api/v1/jobs/{id}/cancel",
+    tag = "jobs",
+    params(
+        ("id" = Id, Path, description = "Background job ID")
+    ),
+    responses(
+        (status = 202, description = "Cancellation requested"),
+        (status = 400, description = "Bad request - job has already finished"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - job does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn cancel_job(
+    _admin: AdminRole,
+    session: SessionInfo,
+    State(appstate): State<AppState>,
+    Path(id): Path<Id>,
+) -> ApiResult {
+    let job = BackgroundJob::find_by_id(&appstate.pool, id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Background job {id} not found")))?;
+    if !appstate.jobs.cancel(job.id) {
+        return Err(WebError::BadRequest(format!(
+            "Background job {id} has already finished"
+        )));
+    }
+    info!(
+        "User {} cancelled background job {id} ({})",
+        session.user.username, job.kind
+    );
+
+    Ok(ApiResponse {
+        json: json!({}),
+        status: StatusCode::ACCEPTED,
+    })
+}
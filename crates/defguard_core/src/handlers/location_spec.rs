@@ -0,0 +1,97 @@
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    Extension,
+    extract::{Json, Query, State},
+    http::StatusCode,
+};
+use serde_json::json;
+
+use super::{ApiResponse, ApiResult};
+use crate::{
+    appstate::AppState,
+    auth::{AdminRole, SessionInfo},
+    enterprise::limits::update_counts,
+    events::{ApiEvent, ApiEventType, ApiRequestContext},
+    grpc::gateway::map::GatewayMap,
+    location_spec::{LocationApplyStatus, LocationSpec, apply_location_spec},
+};
+
+#[derive(Debug, Deserialize)]
+pub struct ApplyLocationQuery {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Apply declarative location spec
+///
+/// Converges a location, identified by name, to the desired state of its network settings,
+/// allowed groups, gateways and ACL rules. The location is created if it doesn't exist.
+/// Applying the same spec again makes no changes, so it can be called repeatedly by a
+/// reconciler, e.g. a Kubernetes operator. Gateways and ACL rules are left untouched if omitted;
+/// only ACL rules applying exclusively to the location are managed. Managing ACL rules requires
+/// an enterprise license.
+///
+/// With `dry_run` set, changes are only computed and reported.
+///
+/// # Returns
+/// - `LocationApplyStatus` object with changes and status conditions
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    put,
+    path = "/api/v1/network/apply",
+    params(
+        ("dry_run" = Option<bool>, Query, description = "Only report changes, without applying them")
+    ),
+    request_body = LocationSpec,
+    responses(
+        (status = 200, description = "Location spec has been applied.", body = LocationApplyStatus),
+        (status = 400, description = "Invalid spec.", body = ApiResponse, example = json!({"msg": "group admins not found"})),
+        (status = 401, description = "Unauthorized to apply location spec.", body = ApiResponse, example = json!({"msg": "Session is required"})),
+        (status = 403, description = "You don't have permission to apply location spec.", body = ApiResponse, example = json!({"msg": "access denied"})),
+        (status = 500, description = "Unable to apply location spec.", body = ApiResponse, example = json!({"msg": "Internal server error"}))
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub(crate) async fn apply_location(
+    _role: AdminRole,
+    State(appstate): State<AppState>,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    Query(query): Query<ApplyLocationQuery>,
+    Extension(gateway_state): Extension<Arc<Mutex<GatewayMap>>>,
+    Json(spec): Json<LocationSpec>,
+) -> ApiResult {
+    let name = spec.network.name.clone();
+    debug!(
+        "User {} applying spec of location {name} (dry run: {})",
+        session.user.username, query.dry_run
+    );
+    let (status, event) =
+        apply_location_spec(&appstate, &gateway_state, spec, query.dry_run).await?;
+    if let Some(event) = event {
+        let created = matches!(event, ApiEventType::VpnLocationAdded { .. });
+        appstate.emit_event(ApiEvent {
+            context,
+            event: Box::new(event),
+        })?;
+        if created {
+            update_counts(&appstate.pool).await?;
+        }
+    }
+    info!(
+        "User {} applied spec of location {name} with {} changes (dry run: {})",
+        session.user.username,
+        status.changes.len(),
+        query.dry_run
+    );
+
+    Ok(ApiResponse {
+        json: json!(status),
+        status: StatusCode::OK,
+    })
+}
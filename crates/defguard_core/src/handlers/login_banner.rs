@@ -0,0 +1,187 @@
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+};
+use defguard_common::db::Id;
+use serde_json::json;
+use utoipa::ToSchema;
+
+use super::{ApiResponse, ApiResult};
+use crate::{
+    appstate::AppState,
+    auth::{AdminRole, SessionInfo},
+    db::models::login_banner::{LoginBanner, LoginBannerAcknowledgment},
+    error::WebError,
+};
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct LoginBannerData {
+    pub content: String,
+}
+
+/// Get current login banner
+///
+/// Available without logging in, so the banner can be shown on the login page. Its ID has to be
+/// sent along with credentials by users who haven't acknowledged this version yet.
+///
+/// # Returns
+/// - `LoginBanner` object or `null` if there's no banner
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/login_banner",
+    tag = "login_banner",
+    responses(
+        (status = 200, description = "Current login banner", body = Option<LoginBanner>),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn get_login_banner(State(appstate): State<AppState>) -> ApiResult {
+    let banner = LoginBanner::current(&appstate.pool).await?;
+
+    Ok(ApiResponse {
+        json: json!(banner),
+        status: StatusCode::OK,
+    })
+}
+
+/// Set login banner
+///
+/// Changing the content creates a new version of the banner, which all users have to
+/// acknowledge again. Setting the same content keeps the current version.
+///
+/// # Returns
+/// - `LoginBanner` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    put,
+    path = "/api/v1/login_banner",
+    tag = "login_banner",
+    request_body = LoginBannerData,
+    responses(
+        (status = 200, description = "Login banner set", body = LoginBanner),
+        (status = 400, description = "Bad request - empty content"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn set_login_banner(
+    _admin: AdminRole,
+    session: SessionInfo,
+    State(appstate): State<AppState>,
+    Json(data): Json<LoginBannerData>,
+) -> ApiResult {
+    debug!("User {} setting login banner", session.user.username);
+    if data.content.trim().is_empty() {
+        return Err(WebError::BadRequest(
+            "Login banner content can't be empty".into(),
+        ));
+    }
+
+    let mut transaction = appstate.pool.begin().await?;
+    let banner = match LoginBanner::current(&mut *transaction).await? {
+        Some(current) if current.content == data.content => current,
+        _ => {
+            LoginBanner::deactivate_all(&mut *transaction).await?;
+            LoginBanner::new(data.content)
+                .save(&mut *transaction)
+                .await?
+        }
+    };
+    transaction.commit().await?;
+    info!(
+        "User {} set login banner version {}",
+        session.user.username, banner.id
+    );
+
+    Ok(ApiResponse {
+        json: json!(banner),
+        status: StatusCode::OK,
+    })
+}
+
+/// Remove login banner
+///
+/// Users can log in without acknowledging any banner. Acknowledgments of previous versions are
+/// kept.
+///
+/// # Returns
+/// - empty JSON
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    delete,
+    path = "/api/v1/login_banner",
+    tag = "login_banner",
+    responses(
+        (status = 200, description = "Login banner removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn delete_login_banner(
+    _admin: AdminRole,
+    session: SessionInfo,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    LoginBanner::deactivate_all(&appstate.pool).await?;
+    info!("User {} removed login banner", session.user.username);
+
+    Ok(ApiResponse {
+        json: json!({}),
+        status: StatusCode::OK,
+    })
+}
+
+/// List acknowledgments of a login banner version
+///
+/// # Returns
+/// - `Vec<LoginBannerAcknowledgment>` object, the most recent first
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/login_banner/{id}/acknowledgment",
+    tag = "login_banner",
+    params(
+        ("id" = Id, Path, description = "Login banner version ID")
+    ),
+    responses(
+        (status = 200, description = "Users who acknowledged the banner version", body = Vec<LoginBannerAcknowledgment>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - banner version does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn list_login_banner_acknowledgments(
+    _admin: AdminRole,
+    Path(id): Path<Id>,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let banner = LoginBanner::find_by_id(&appstate.pool, id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Login banner {id} not found")))?;
+    let acknowledgments = banner.acknowledgments(&appstate.pool).await?;
+
+    Ok(ApiResponse {
+        json: json!(acknowledgments),
+        status: StatusCode::OK,
+    })
+}
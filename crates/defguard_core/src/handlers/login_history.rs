@@ -0,0 +1,137 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    response::Response,
+};
+use defguard_common::db::Id;
+use serde_json::json;
+
+use super::{
+    ApiResponse, DEFAULT_API_PAGE_SIZE,
+    inventory::attachment,
+    pagination::{
+        PaginatedApiResponse, PaginatedApiResult, PaginationParams, get_pagination_metadata,
+    },
+    user_for_admin_or_self,
+};
+use crate::{
+    appstate::AppState,
+    auth::SessionInfo,
+    db::models::login_history::LoginRecord,
+    error::WebError,
+    inventory::{ExportFormat, LOGIN_HISTORY_COLUMNS, RowRenderer, export_login_history},
+};
+
+#[derive(Debug, Deserialize)]
+pub struct LoginHistoryExportQuery {
+    #[serde(default)]
+    format: ExportFormat,
+    columns: Option<String>,
+}
+
+/// List login history of a user
+///
+/// Returns a page of successful and failed web logins and desktop client MFA attempts of a user,
+/// the most recent first. Users can view their own history, admins can view history of any user.
+///
+/// # Returns
+/// - paginated list of `LoginRecord` objects
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/user/{username}/login_history",
+    params(
+        ("username" = String, Path, description = "Name of a user"),
+        ("page" = Option<u32>, Query, description = "Page number, starting from 1")
+    ),
+    responses(
+        (status = 200, description = "Page of login history.", body = [LoginRecord]),
+        (status = 401, description = "Unauthorized to view login history.", body = ApiResponse, example = json!({"msg": "Session is required"})),
+        (status = 403, description = "You don't have permission to view login history of this user.", body = ApiResponse, example = json!({"msg": "requires privileged access"})),
+        (status = 404, description = "User not found.", body = ApiResponse, example = json!({"msg": "user username not found"}))
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub(crate) async fn list_login_history(
+    session: SessionInfo,
+    State(appstate): State<AppState>,
+    Path(username): Path<String>,
+    Query(pagination): Query<PaginationParams>,
+) -> PaginatedApiResult<LoginRecord<Id>> {
+    let user = user_for_admin_or_self(&appstate.pool, &session, &username).await?;
+    debug!(
+        "Listing login history of user {username}, page {}",
+        pagination.page
+    );
+    let offset = pagination.page.saturating_sub(1) * DEFAULT_API_PAGE_SIZE;
+    let records = LoginRecord::page_for_user(
+        &appstate.pool,
+        user.id,
+        i64::from(DEFAULT_API_PAGE_SIZE),
+        i64::from(offset),
+    )
+    .await?;
+    let total_items = LoginRecord::count_for_user(&appstate.pool, user.id).await?;
+
+    Ok(PaginatedApiResponse {
+        data: records,
+        pagination: get_pagination_metadata(pagination.page, total_items as u32),
+    })
+}
+
+/// Export login history of a user
+///
+/// Streams complete login history of a user, the most recent first. Users can export their own
+/// history, admins can export history of any user. Available columns: `id`, `timestamp`,
+/// `source`, `method`, `success`, `ip`, `device`, `location`, `proxy`, `message`.
+///
+/// # Returns
+/// - CSV or NDJSON document
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/user/{username}/login_history/export",
+    params(
+        ("username" = String, Path, description = "Name of a user"),
+        ("format" = Option<String>, Query, description = "Export format: `csv` (default) or `ndjson`"),
+        ("columns" = Option<String>, Query, description = "Comma-separated list of columns, all columns by default")
+    ),
+    responses(
+        (status = 200, description = "Login history as CSV or NDJSON.", content_type = "text/csv"),
+        (status = 400, description = "Unknown column.", body = ApiResponse, example = json!({"msg": "Unknown column password"})),
+        (status = 401, description = "Unauthorized to export login history.", body = ApiResponse, example = json!({"msg": "Session is required"})),
+        (status = 403, description = "You don't have permission to export login history of this user.", body = ApiResponse, example = json!({"msg": "requires privileged access"})),
+        (status = 404, description = "User not found.", body = ApiResponse, example = json!({"msg": "user username not found"}))
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub(crate) async fn export_user_login_history(
+    session: SessionInfo,
+    State(appstate): State<AppState>,
+    Path(username): Path<String>,
+    Query(query): Query<LoginHistoryExportQuery>,
+) -> Result<Response, WebError> {
+    let user = user_for_admin_or_self(&appstate.pool, &session, &username).await?;
+    debug!("Exporting login history of user {username} with {query:?}");
+    let renderer = RowRenderer::new(
+        query.format,
+        query.columns.as_deref(),
+        LOGIN_HISTORY_COLUMNS,
+    )
+    .map_err(WebError::BadRequest)?;
+    let rows = export_login_history(appstate.pool.clone(), user.id, renderer);
+
+    Ok(attachment(
+        query.format,
+        &format!("login-history-{username}"),
+        Body::from_stream(rows),
+    ))
+}
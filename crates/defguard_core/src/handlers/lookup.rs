@@ -0,0 +1,344 @@
+use std::net::IpAddr;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use chrono::{DateTime, NaiveDateTime, TimeDelta, Utc};
+use defguard_common::db::Id;
+use ipnetwork::IpNetwork;
+use serde_json::json;
+use sqlx::{Error as SqlxError, PgExecutor, postgres::types::PgInterval, query};
+use utoipa::ToSchema;
+
+use super::{ApiResponse, ApiResult};
+use crate::{
+    appstate::AppState,
+    auth::AdminRole,
+    db::models::{device::DeviceType, wireguard::WIREGUARD_MAX_HANDSHAKE},
+    error::WebError,
+};
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct IpLookupDevice {
+    pub id: Id,
+    pub name: String,
+    pub wireguard_pubkey: String,
+    pub device_type: DeviceType,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct IpLookupUser {
+    pub id: Id,
+    pub username: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct IpLookupLocation {
+    pub id: Id,
+    pub name: String,
+}
+
+/// VPN session based on the latest stats reported by a gateway.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct IpLookupSession {
+    /// Hostname of the gateway the device is connected through.
+    pub gateway: Option<String>,
+    /// Public address the device connects from.
+    pub endpoint: Option<String>,
+    pub latest_handshake: NaiveDateTime,
+    pub upload: i64,
+    pub download: i64,
+}
+
+/// Device using a VPN address in a location.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct IpLookupResult {
+    pub device: IpLookupDevice,
+    /// Owner of the device, or the admin who added it for network devices.
+    pub user: IpLookupUser,
+    pub location: IpLookupLocation,
+    /// Current session, if the device is connected.
+    pub session: Option<IpLookupSession>,
+}
+
+impl IpLookupResult {
+    /// Finds devices with a given VPN address, along with their owners, locations and sessions.
+    ///
+    /// An address may be used in more than one location if location subnets overlap.
+    async fn find_by_ip<'e, E>(executor: E, ip: IpAddr) -> Result<Vec<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        let rows = query!(
+            "SELECT d.id device_id, d.name device_name, d.wireguard_pubkey, \
+            d.device_type \"device_type: DeviceType\", \
+            u.id user_id, u.username, u.first_name, u.last_name, u.email, \
+            n.id location_id, n.name location_name, \
+            s.gateway \"gateway?\", s.endpoint \"endpoint?\", \
+            s.latest_handshake \"latest_handshake?\", s.upload \"upload?\", s.download \"download?\" \
+            FROM wireguard_network_device wnd \
+            JOIN device d ON d.id = wnd.device_id \
+            JOIN \"user\" u ON u.id = d.user_id \
+            JOIN wireguard_network n ON n.id = wnd.wireguard_network_id \
+            LEFT JOIN LATERAL ( \
+                SELECT gateway, endpoint, latest_handshake, upload, download \
+                FROM wireguard_peer_stats \
+                WHERE device_id = d.id AND network = n.id \
+                ORDER BY collected_at DESC LIMIT 1 \
+            ) s ON true \
+            WHERE wnd.wireguard_ips @> ARRAY[$1::inet] \
+            ORDER BY n.id",
+            IpNetwork::from(ip)
+        )
+        .fetch_all(executor)
+        .await?;
+
+        let active_since = (Utc::now() - WIREGUARD_MAX_HANDSHAKE).naive_utc();
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let session = row
+                    .latest_handshake
+                    .filter(|latest_handshake| *latest_handshake >= active_since)
+                    .map(|latest_handshake| IpLookupSession {
+                        gateway: row.gateway,
+                        endpoint: row.endpoint,
+                        latest_handshake,
+                        upload: row.upload.unwrap_or_default(),
+                        download: row.download.unwrap_or_default(),
+                    });
+                Self {
+                    device: IpLookupDevice {
+                        id: row.device_id,
+                        name: row.device_name,
+                        wireguard_pubkey: row.wireguard_pubkey,
+                        device_type: row.device_type,
+                    },
+                    user: IpLookupUser {
+                        id: row.user_id,
+                        username: row.username,
+                        first_name: row.first_name,
+                        last_name: row.last_name,
+                        email: row.email,
+                    },
+                    location: IpLookupLocation {
+                        id: row.location_id,
+                        name: row.location_name,
+                    },
+                    session,
+                }
+            })
+            .collect())
+    }
+}
+
+/// Look up a device by VPN address
+///
+/// Resolves a VPN address to the device using it, its owner, location and current session.
+/// Intended for correlating firewall or proxy logs with identities.
+///
+/// # Returns
+/// - List of `IpLookupResult` objects, one for each location using the address
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/lookup/ip/{addr}",
+    tag = "device",
+    params(
+        ("addr" = String, description = "VPN address of a device")
+    ),
+    responses(
+        (status = 200, description = "Devices using the address", body = Vec<IpLookupResult>),
+        (status = 400, description = "Invalid address", body = ApiResponse, example = json!({})),
+        (status = 401, description = "Unauthorized to look up devices.", body = ApiResponse, example = json!({"msg": "Session is required"})),
+        (status = 403, description = "You don't have permission to look up devices.", body = ApiResponse, example = json!({"msg": "access denied"})),
+        (status = 404, description = "No device uses the address.", body = ApiResponse, example = json!({"msg": "No device with address 10.1.1.2"})),
+        (status = 500, description = "Unable to look up devices.", body = ApiResponse, example = json!({"msg": "Internal server error"}))
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub(crate) async fn lookup_ip(
+    _role: AdminRole,
+    State(appstate): State<AppState>,
+    Path(addr): Path<IpAddr>,
+) -> ApiResult {
+    debug!("Looking up devices with VPN address {addr}");
+    let results = IpLookupResult::find_by_ip(&appstate.pool, addr).await?;
+    if results.is_empty() {
+        return Err(WebError::ObjectNotFound(format!(
+            "No device with address {addr}"
+        )));
+    }
+    debug!("Found {} devices with VPN address {addr}", results.len());
+
+    Ok(ApiResponse {
+        json: json!(results),
+        status: StatusCode::OK,
+    })
+}
+
+/// Device connected from a public address during a time range.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EndpointLookupResult {
+    pub device: IpLookupDevice,
+    /// Owner of the device, or the admin who added it for network devices.
+    pub user: IpLookupUser,
+    pub location: IpLookupLocation,
+    /// Time of the first stats report with the address in the time range.
+    pub first_seen: NaiveDateTime,
+    /// Time of the last stats report with the address in the time range.
+    pub last_seen: NaiveDateTime,
+    /// Endpoints, including ports, the device connected from.
+    pub endpoints: Vec<String>,
+    /// Hostnames of gateways which reported the device.
+    pub gateways: Vec<String>,
+}
+
+impl EndpointLookupResult {
+    /// Finds devices which were connected from a given public address in a time range,
+    /// based on stats reported by gateways. Reports of inactive peers are skipped.
+    async fn find_by_endpoint<'e, E>(
+        executor: E,
+        ip: IpAddr,
+        from: NaiveDateTime,
+        until: NaiveDateTime,
+    ) -> Result<Vec<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        // Endpoints are stored with ports, e.g. "1.2.3.4:51820" or "[2001:db8::1]:51820".
+        // Match them with a prefix range which can use the endpoint index.
+        let host = match ip {
+            IpAddr::V4(ip) => ip.to_string(),
+            IpAddr::V6(ip) => format!("[{ip}]"),
+        };
+        let rows = query!(
+            "SELECT d.id device_id, d.name device_name, d.wireguard_pubkey, \
+            d.device_type \"device_type: DeviceType\", \
+            u.id user_id, u.username, u.first_name, u.last_name, u.email, \
+            n.id location_id, n.name location_name, \
+            MIN(s.collected_at) \"first_seen!\", MAX(s.collected_at) \"last_seen!\", \
+            ARRAY_AGG(DISTINCT s.endpoint) \"endpoints!: Vec<String>\", \
+            ARRAY_REMOVE(ARRAY_AGG(DISTINCT s.gateway), NULL) \"gateways!: Vec<String>\" \
+            FROM wireguard_peer_stats s \
+            JOIN device d ON d.id = s.device_id \
+            JOIN \"user\" u ON u.id = d.user_id \
+            JOIN wireguard_network n ON n.id = s.network \
+            WHERE s.endpoint ~>=~ $1 AND s.endpoint ~<~ $2 \
+            AND s.collected_at >= $3 AND s.collected_at <= $4 \
+            AND s.latest_handshake >= s.collected_at - $5::interval \
+            GROUP BY d.id, u.id, n.id \
+            ORDER BY MIN(s.collected_at), d.id, n.id",
+            format!("{host}:"),
+            format!("{host};"),
+            from,
+            until,
+            PgInterval::try_from(WIREGUARD_MAX_HANDSHAKE).unwrap()
+        )
+        .fetch_all(executor)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| Self {
+                device: IpLookupDevice {
+                    id: row.device_id,
+                    name: row.device_name,
+                    wireguard_pubkey: row.wireguard_pubkey,
+                    device_type: row.device_type,
+                },
+                user: IpLookupUser {
+                    id: row.user_id,
+                    username: row.username,
+                    first_name: row.first_name,
+                    last_name: row.last_name,
+                    email: row.email,
+                },
+                location: IpLookupLocation {
+                    id: row.location_id,
+                    name: row.location_name,
+                },
+                first_seen: row.first_seen,
+                last_seen: row.last_seen,
+                endpoints: row.endpoints,
+                gateways: row.gateways,
+            })
+            .collect())
+    }
+}
+
+#[derive(Deserialize)]
+pub struct EndpointLookupQuery {
+    from: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+}
+
+/// Look up devices by public endpoint address
+///
+/// Finds devices, their owners and locations which were connected from a public address
+/// during a time range, e.g. to handle an abuse report.
+///
+/// # Returns
+/// - List of `EndpointLookupResult` objects, one for each device and location
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/lookup/endpoint/{addr}",
+    tag = "device",
+    params(
+        ("addr" = String, description = "Public address devices connected from"),
+        ("from" = Option<String>, Query, description = "Start of time range in RFC 3339 format, defaults to an hour before its end"),
+        ("until" = Option<String>, Query, description = "End of time range in RFC 3339 format, defaults to now")
+    ),
+    responses(
+        (status = 200, description = "Devices connected from the address", body = Vec<EndpointLookupResult>),
+        (status = 400, description = "Invalid address or time range", body = ApiResponse, example = json!({})),
+        (status = 401, description = "Unauthorized to look up devices.", body = ApiResponse, example = json!({"msg": "Session is required"})),
+        (status = 403, description = "You don't have permission to look up devices.", body = ApiResponse, example = json!({"msg": "access denied"})),
+        (status = 500, description = "Unable to look up devices.", body = ApiResponse, example = json!({"msg": "Internal server error"}))
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub(crate) async fn lookup_endpoint(
+    _role: AdminRole,
+    State(appstate): State<AppState>,
+    Path(addr): Path<IpAddr>,
+    Query(query): Query<EndpointLookupQuery>,
+) -> ApiResult {
+    let until = query.until.unwrap_or_else(Utc::now);
+    let from = query.from.unwrap_or(until - TimeDelta::hours(1));
+    if from > until {
+        return Err(WebError::BadRequest(
+            "Start of time range is after its end".into(),
+        ));
+    }
+    debug!("Looking up devices connected from {addr} between {from} and {until}");
+    let results = EndpointLookupResult::find_by_endpoint(
+        &appstate.pool,
+        addr,
+        from.naive_utc(),
+        until.naive_utc(),
+    )
+    .await?;
+    debug!(
+        "Found {} devices connected from {addr} between {from} and {until}",
+        results.len()
+    );
+
+    Ok(ApiResponse {
+        json: json!(results),
+        status: StatusCode::OK,
+    })
+}
@@ -5,14 +5,20 @@ use axum::{
     http::StatusCode,
 };
 use chrono::{NaiveDateTime, Utc};
-use defguard_common::db::{Id, models::MFAMethod};
+use defguard_common::db::{
+    Id,
+    models::{MFAMethod, Settings},
+};
 use defguard_mail::{
-    Attachment, Mail,
-    templates::{self, SessionContext, TemplateError, TemplateLocation, support_data_mail},
+    Attachment, AttachmentType, Mail, MailCategory,
+    calendar::{CalendarEvent, CalendarMethod},
+    templates::{
+        self, SessionContext, TemplateError, TemplateLocation, UserContext, support_data_mail,
+    },
 };
-use lettre::message::header::ContentType;
 use reqwest::Url;
 use serde_json::json;
+use tera::Context;
 use tokio::{
     fs::read_to_string,
     sync::mpsc::{UnboundedSender, unbounded_channel},
@@ -23,8 +29,18 @@ use crate::{
     PgPool,
     appstate::AppState,
     auth::{AdminRole, SessionInfo},
-    db::{User, models::enrollment::TokenError},
+    db::{
+        Device, User,
+        models::{
+            enrollment::{Token, TokenError},
+            helpdesk_password_reset::HelpdeskPasswordReset,
+            maintenance_window::MaintenanceWindow,
+            notification::NotificationCategory,
+            self_registration::SelfRegistrationRequest,
+        },
+    },
     error::WebError,
+    notifications::{AdminNotification, notify_admins},
     server_config,
     support::dump_config,
 };
@@ -35,15 +51,25 @@ static SUPPORT_EMAIL_SUBJECT: &str = "Defguard support data";
 
 static NEW_DEVICE_ADDED_EMAIL_SUBJECT: &str = "Defguard: new device added to your account";
 static NEW_DEVICE_LOGIN_EMAIL_SUBJECT: &str = "Defguard: new device logged in to your account";
+static DEVICE_DISCONNECTED_EMAIL_SUBJECT: &str = "Defguard: your device has been disconnected";
+static DEVICE_APPROVAL_REQUEST_EMAIL_SUBJECT: &str = "Defguard: new device awaiting approval";
+
+static SELF_REGISTRATION_VERIFICATION_EMAIL_SUBJECT: &str = "Defguard: verify your email address";
+static SELF_REGISTRATION_ENROLLMENT_EMAIL_SUBJECT: &str = "Defguard user enrollment";
+static SELF_REGISTRATION_ADMIN_NOTIFICATION_EMAIL_SUBJECT: &str =
+    "Defguard: new user awaiting activation";
+
+static HELPDESK_PASSWORD_RESET_EMAIL_SUBJECT: &str = "Defguard: confirm your password reset";
 
 static EMAIL_MFA_ACTIVATION_EMAIL_SUBJECT: &str = "Your Multi-Factor Authentication Activation";
 static EMAIL_MFA_CODE_EMAIL_SUBJECT: &str = "Your Multi-Factor Authentication Code for Login";
 
 static GATEWAY_DISCONNECTED: &str = "Defguard: Gateway disconnected";
 static GATEWAY_RECONNECTED: &str = "Defguard: Gateway reconnected";
-
-pub static EMAIL_PASSWORD_RESET_START_SUBJECT: &str = "Defguard: Password reset";
-pub static EMAIL_PASSWORD_RESET_SUCCESS_SUBJECT: &str = "Defguard: Password reset success";
+static PROXY_DISCONNECTED: &str = "Defguard: Proxy disconnected";
+static PROXY_RECONNECTED: &str = "Defguard: Proxy reconnected";
+static MAINTENANCE_WINDOW_SUBJECT: &str = "Defguard: maintenance scheduled";
+static MAINTENANCE_WINDOW_CANCELLED_SUBJECT: &str = "Defguard: maintenance cancelled";
 
 #[derive(Clone, Deserialize)]
 pub struct TestMail {
@@ -78,6 +104,7 @@ pub async fn test_mail(
         subject: TEST_MAIL_SUBJECT.to_string(),
         content: templates::test_mail(Some(&session.session.into()))?,
         attachments: Vec::new(),
+        category: MailCategory::General,
         result_tx: Some(tx),
     };
     let (to, subject) = (mail.to.clone(), mail.subject.clone());
@@ -130,23 +157,23 @@ pub async fn send_support_data(
     let config = dump_config(&appstate.pool).await;
     let config =
         serde_json::to_string_pretty(&config).unwrap_or("Json formatting error".to_string());
-    let config = Attachment {
-        filename: format!("defguard-support-data-{}.json", Utc::now()),
-        content: config.into(),
-        content_type: ContentType::TEXT_PLAIN,
-    };
-    let logs = read_logs().await;
-    let logs = Attachment {
-        filename: format!("defguard-logs-{}.txt", Utc::now()),
-        content: logs.into(),
-        content_type: ContentType::TEXT_PLAIN,
-    };
+    let config = Attachment::new(
+        format!("defguard-support-data-{}.json", Utc::now()),
+        config,
+        AttachmentType::Json,
+    );
+    let logs = Attachment::new(
+        format!("defguard-logs-{}.txt", Utc::now()),
+        read_logs().await,
+        AttachmentType::Text,
+    );
     let (tx, mut rx) = unbounded_channel();
     let mail = Mail {
         to: SUPPORT_EMAIL_ADDRESS.to_string(),
         subject: SUPPORT_EMAIL_SUBJECT.to_string(),
         content: support_data_mail()?,
         attachments: vec![config, logs],
+        category: MailCategory::General,
         result_tx: Some(tx),
     };
     let (to, subject) = (mail.to.clone(), mail.subject.clone());
@@ -195,6 +222,7 @@ pub fn send_new_device_added_email(
             device_info,
         )?,
         attachments: Vec::new(),
+        category: MailCategory::Security,
         result_tx: None,
     };
 
@@ -212,6 +240,195 @@ pub fn send_new_device_added_email(
     }
 }
 
+pub fn send_device_disconnected_email(
+    device_name: &str,
+    locations: &[String],
+    user_email: &str,
+    mail_tx: &UnboundedSender<Mail>,
+) -> Result<(), TemplateError> {
+    debug!("Sending device disconnected mail to {user_email}");
+
+    let mail = Mail {
+        to: user_email.to_string(),
+        subject: DEVICE_DISCONNECTED_EMAIL_SUBJECT.to_string(),
+        content: templates::device_disconnected_mail(device_name, locations)?,
+        attachments: Vec::new(),
+        category: MailCategory::General,
+        result_tx: None,
+    };
+
+    let to = mail.to.clone();
+
+    match mail_tx.send(mail) {
+        Ok(()) => {
+            info!("Sent device disconnected notification to {to}");
+            Ok(())
+        }
+        Err(err) => {
+            error!("Sending device disconnected notification to {to} failed with error:\n{err}");
+            Ok(())
+        }
+    }
+}
+
+/// Notifies all admin users about a device awaiting approval.
+pub async fn send_device_approval_request_email(
+    device: &Device<Id>,
+    username: &str,
+    locations: &[String],
+    expires_at: NaiveDateTime,
+    mail_tx: &UnboundedSender<Mail>,
+    pool: &PgPool,
+) -> Result<(), WebError> {
+    let content = templates::device_approval_request_mail(
+        &device.name,
+        &device.wireguard_pubkey,
+        username,
+        locations,
+        expires_at,
+    )?;
+    let notification = AdminNotification {
+        category: NotificationCategory::DeviceApproval,
+        subject: DEVICE_APPROVAL_REQUEST_EMAIL_SUBJECT.to_string(),
+        content,
+        message: format!(
+            "Device {} of user {username} is awaiting approval to join locations: {}. \
+            The request expires at {expires_at}.",
+            device.name,
+            locations.join(", ")
+        ),
+    };
+    notify_admins(pool, mail_tx, &notification).await?;
+    Ok(())
+}
+
+pub fn send_self_registration_verification_email(
+    request: &SelfRegistrationRequest<Id>,
+    mail_tx: &UnboundedSender<Mail>,
+) -> Result<(), WebError> {
+    debug!(
+        "Sending self-registration verification mail to {}",
+        request.email
+    );
+    let mail = Mail {
+        to: request.email.clone(),
+        subject: SELF_REGISTRATION_VERIFICATION_EMAIL_SUBJECT.to_string(),
+        content: templates::self_registration_verification_mail(&request.username, &request.token)?,
+        attachments: Vec::new(),
+        category: MailCategory::Enrollment,
+        result_tx: None,
+    };
+    let to = mail.to.clone();
+
+    match mail_tx.send(mail) {
+        Ok(()) => {
+            info!("Sent self-registration verification mail to {to}");
+            Ok(())
+        }
+        Err(err) => {
+            error!("Sending self-registration verification mail to {to} failed with error:\n{err}");
+            Err(WebError::Http(StatusCode::INTERNAL_SERVER_ERROR))
+        }
+    }
+}
+
+/// Sends verification code of a password reset initiated by helpdesk to the user.
+pub fn send_helpdesk_password_reset_email(
+    user: &User<Id>,
+    reset: &HelpdeskPasswordReset<Id>,
+    mail_tx: &UnboundedSender<Mail>,
+) -> Result<(), WebError> {
+    debug!("Sending helpdesk password reset mail to {}", user.email);
+    let mail = Mail {
+        to: user.email.clone(),
+        subject: HELPDESK_PASSWORD_RESET_EMAIL_SUBJECT.to_string(),
+        content: templates::helpdesk_password_reset_mail(
+            &user.username,
+            &reset.requested_by_username,
+            &reset.code,
+            reset.expires_at,
+        )?,
+        attachments: Vec::new(),
+        category: MailCategory::Security,
+        result_tx: None,
+    };
+    let to = mail.to.clone();
+
+    match mail_tx.send(mail) {
+        Ok(()) => {
+            info!("Sent helpdesk password reset mail to {to}");
+            Ok(())
+        }
+        Err(err) => {
+            error!("Sending helpdesk password reset mail to {to} failed with error:\n{err}");
+            Err(WebError::Http(StatusCode::INTERNAL_SERVER_ERROR))
+        }
+    }
+}
+
+/// Sends enrollment token to a user who verified their email through self-registration.
+pub fn send_self_registration_enrollment_email(
+    user: &User<Id>,
+    token: &str,
+    mail_tx: &UnboundedSender<Mail>,
+) -> Result<(), WebError> {
+    debug!(
+        "Sending self-registration enrollment mail to {}",
+        user.email
+    );
+    let mail = Mail {
+        to: user.email.clone(),
+        subject: SELF_REGISTRATION_ENROLLMENT_EMAIL_SUBJECT.to_string(),
+        content: templates::enrollment_start_mail(
+            Context::new(),
+            server_config().enrollment_url.clone(),
+            token,
+            user.mail_language.as_deref(),
+        )?,
+        attachments: Vec::new(),
+        category: MailCategory::Enrollment,
+        result_tx: None,
+    };
+    let to = mail.to.clone();
+
+    match mail_tx.send(mail) {
+        Ok(()) => {
+            info!("Sent self-registration enrollment mail to {to}");
+            Ok(())
+        }
+        Err(err) => {
+            error!("Sending self-registration enrollment mail to {to} failed with error:\n{err}");
+            Err(WebError::Http(StatusCode::INTERNAL_SERVER_ERROR))
+        }
+    }
+}
+
+pub async fn send_self_registration_admin_notification(
+    user: &User<Id>,
+    mail_tx: &UnboundedSender<Mail>,
+    pool: &PgPool,
+) -> Result<(), WebError> {
+    let content = templates::self_registration_admin_notification(
+        &UserContext {
+            last_name: user.last_name.clone(),
+            first_name: user.first_name.clone(),
+        },
+        &user.username,
+        &user.email,
+    )?;
+    let notification = AdminNotification {
+        category: NotificationCategory::SelfRegistration,
+        subject: SELF_REGISTRATION_ADMIN_NOTIFICATION_EMAIL_SUBJECT.to_string(),
+        content,
+        message: format!(
+            "User {} ({}) registered and is awaiting activation.",
+            user.username, user.email
+        ),
+    };
+    notify_admins(pool, mail_tx, &notification).await?;
+    Ok(())
+}
+
 pub async fn send_gateway_disconnected_email(
     gateway_name: Option<String>,
     network_name: String,
@@ -219,34 +436,20 @@ pub async fn send_gateway_disconnected_email(
     mail_tx: &UnboundedSender<Mail>,
     pool: &PgPool,
 ) -> Result<(), WebError> {
-    debug!("Sending gateway disconnected mail to all admin users");
-    let admin_users = User::find_admins(pool).await?;
     let gateway_name = gateway_name.unwrap_or_default();
-    for user in admin_users {
-        let mail = Mail {
-            to: user.email,
-            subject: GATEWAY_DISCONNECTED.to_string(),
-            content: templates::gateway_disconnected_mail(
-                &gateway_name,
-                gateway_adress,
-                &network_name,
-            )?,
-            attachments: Vec::new(),
-            result_tx: None,
-        };
-        let to = mail.to.clone();
-
-        match mail_tx.send(mail) {
-            Ok(()) => {
-                info!("Sent gateway disconnected notification to {to}");
-            }
-            Err(err) => {
-                error!(
-                    "Sending gateway disconnected notification to {to} failed with error:\n{err}"
-                );
-            }
-        }
-    }
+    let notification = AdminNotification {
+        category: NotificationCategory::GatewayDisconnected,
+        subject: GATEWAY_DISCONNECTED.to_string(),
+        content: templates::gateway_disconnected_mail(
+            &gateway_name,
+            gateway_adress,
+            &network_name,
+        )?,
+        message: format!(
+            "Gateway {gateway_name} ({gateway_adress}) in location {network_name} disconnected."
+        ),
+    };
+    notify_admins(pool, mail_tx, &notification).await?;
     Ok(())
 }
 
@@ -257,34 +460,109 @@ pub async fn send_gateway_reconnected_email(
     mail_tx: &UnboundedSender<Mail>,
     pool: &PgPool,
 ) -> Result<(), WebError> {
-    debug!("Sending gateway reconnect mail to all admin users");
-    let admin_users = User::find_admins(pool).await?;
     let gateway_name = gateway_name.unwrap_or_default();
-    for user in admin_users {
+    let notification = AdminNotification {
+        category: NotificationCategory::GatewayReconnected,
+        subject: GATEWAY_RECONNECTED.to_string(),
+        content: templates::gateway_reconnected_mail(&gateway_name, gateway_adress, &network_name)?,
+        message: format!(
+            "Gateway {gateway_name} ({gateway_adress}) in location {network_name} reconnected."
+        ),
+    };
+    notify_admins(pool, mail_tx, &notification).await?;
+    Ok(())
+}
+
+/// Alert admins that connection to the proxy has been lost, or restored if `connected`.
+pub async fn send_proxy_connectivity_email(
+    proxy_url: &str,
+    connected: bool,
+    mail_tx: &UnboundedSender<Mail>,
+    pool: &PgPool,
+) -> Result<(), WebError> {
+    let (subject, message) = if connected {
+        (
+            PROXY_RECONNECTED,
+            format!("Connection to the proxy at {proxy_url} has been restored."),
+        )
+    } else {
+        (
+            PROXY_DISCONNECTED,
+            format!("Connection to the proxy at {proxy_url} has been lost."),
+        )
+    };
+    let notification = AdminNotification {
+        category: NotificationCategory::ProxyConnectivity,
+        subject: subject.to_string(),
+        content: templates::proxy_connectivity_mail(proxy_url, connected)?,
+        message,
+    };
+    notify_admins(pool, mail_tx, &notification).await?;
+    Ok(())
+}
+
+/// Send calendar invites to a maintenance window of a location, or their cancellations, to all
+/// admins.
+pub async fn send_maintenance_window_invites(
+    window: &MaintenanceWindow<Id>,
+    location_name: &str,
+    method: CalendarMethod,
+    mail_tx: &UnboundedSender<Mail>,
+    pool: &PgPool,
+) -> Result<(), WebError> {
+    let cancelled = method == CalendarMethod::Cancel;
+    let subject = if cancelled {
+        MAINTENANCE_WINDOW_CANCELLED_SUBJECT
+    } else {
+        MAINTENANCE_WINDOW_SUBJECT
+    };
+    let content = templates::maintenance_window_mail(
+        location_name,
+        window.starts_at,
+        window.ends_at,
+        &window.description,
+        cancelled,
+    )?;
+    let host = server_config()
+        .url
+        .host_str()
+        .unwrap_or("defguard")
+        .to_string();
+    let organizer = Settings::get_current_settings()
+        .smtp_sender
+        .filter(|address| !address.is_empty());
+    for admin in User::find_admins(pool).await? {
+        let event = CalendarEvent {
+            uid: window.calendar_uid(&host),
+            // cancellation supersedes the invite
+            sequence: u32::from(cancelled),
+            start: window.starts_at,
+            end: window.ends_at,
+            summary: format!("Maintenance of {location_name}"),
+            description: window.description.clone(),
+            organizer: organizer.clone(),
+            attendee: admin.email.clone(),
+        };
         let mail = Mail {
-            to: user.email,
-            subject: GATEWAY_RECONNECTED.to_string(),
-            content: templates::gateway_reconnected_mail(
-                &gateway_name,
-                gateway_adress,
-                &network_name,
-            )?,
-            attachments: Vec::new(),
+            to: admin.email.clone(),
+            subject: format!("{subject}: {location_name}"),
+            content: content.clone(),
+            attachments: vec![event.attachment(method)],
+            category: MailCategory::Alert,
             result_tx: None,
         };
-        let to = mail.to.clone();
-
         match mail_tx.send(mail) {
-            Ok(()) => {
-                info!("Sent gateway reconnected notification to {to}");
-            }
-            Err(err) => {
-                error!(
-                    "Sending gateway reconnected notification to {to} failed with error:\n{err}"
-                );
-            }
+            Ok(()) => info!(
+                "Sent maintenance window {} invite to {}",
+                window.id, admin.email
+            ),
+            Err(err) => error!(
+                "Sending maintenance window {} invite to {} failed with error:\n{err}",
+                window.id, admin.email
+            ),
         }
     }
+
     Ok(())
 }
 
@@ -293,14 +571,18 @@ pub async fn send_new_device_login_email(
     mail_tx: &UnboundedSender<Mail>,
     session: &SessionContext,
     created: NaiveDateTime,
+    device_name: &str,
+    country: Option<&str>,
+    city: Option<&str>,
 ) -> Result<(), TemplateError> {
     debug!("User {user_email} new device login mail to {SUPPORT_EMAIL_ADDRESS}");
 
     let mail = Mail {
         to: user_email.to_string(),
         subject: NEW_DEVICE_LOGIN_EMAIL_SUBJECT.to_string(),
-        content: templates::new_device_login_mail(session, created)?,
+        content: templates::new_device_login_mail(session, created, device_name, country, city)?,
         attachments: Vec::new(),
+        category: MailCategory::Security,
         result_tx: None,
     };
 
@@ -333,6 +615,7 @@ pub async fn send_new_device_ocid_login_email(
         subject,
         content: templates::new_device_ocid_login_mail(session, &oauth2client_name)?,
         attachments: Vec::new(),
+        category: MailCategory::Security,
         result_tx: None,
     };
 
@@ -365,6 +648,7 @@ pub fn send_mfa_configured_email(
         subject,
         content: templates::mfa_configured_mail(session, mfa_method)?,
         attachments: Vec::new(),
+        category: MailCategory::Security,
         result_tx: None,
     };
 
@@ -400,6 +684,7 @@ pub fn send_email_mfa_activation_email(
         subject: EMAIL_MFA_ACTIVATION_EMAIL_SUBJECT.into(),
         content: templates::email_mfa_activation_mail(&user.clone().into(), &code, session)?,
         attachments: Vec::new(),
+        category: MailCategory::Security,
         result_tx: None,
     };
 
@@ -435,6 +720,7 @@ pub fn send_email_mfa_code_email(
         subject: EMAIL_MFA_CODE_EMAIL_SUBJECT.into(),
         content: templates::email_mfa_code_mail(&user.clone().into(), &code, session)?,
         attachments: Vec::new(),
+        category: MailCategory::Security,
         result_tx: None,
     };
 
@@ -456,7 +742,8 @@ pub fn send_password_reset_email(
     user: &User<Id>,
     mail_tx: &UnboundedSender<Mail>,
     service_url: Url,
-    token: &str,
+    token: &Token,
+    max_uses: i32,
     ip_address: Option<&str>,
     device_info: Option<&str>,
 ) -> Result<(), TokenError> {
@@ -464,9 +751,21 @@ pub fn send_password_reset_email(
 
     let mail = Mail {
         to: user.email.clone(),
-        subject: EMAIL_PASSWORD_RESET_START_SUBJECT.into(),
-        content: templates::email_password_reset_mail(service_url, token, ip_address, device_info)?,
+        subject: templates::localized_subject(
+            templates::PASSWORD_RESET_START_TEMPLATE,
+            user.mail_language.as_deref(),
+        )?,
+        content: templates::email_password_reset_mail(
+            service_url,
+            &token.id,
+            token.expires_at,
+            max_uses,
+            ip_address,
+            device_info,
+            user.mail_language.as_deref(),
+        )?,
         attachments: Vec::new(),
+        category: MailCategory::Security,
         result_tx: None,
     };
 
@@ -494,9 +793,17 @@ pub fn send_password_reset_success_email(
 
     let mail = Mail {
         to: user.email.clone(),
-        subject: EMAIL_PASSWORD_RESET_SUCCESS_SUBJECT.into(),
-        content: templates::email_password_reset_success_mail(ip_address, device_info)?,
+        subject: templates::localized_subject(
+            templates::PASSWORD_RESET_SUCCESS_TEMPLATE,
+            user.mail_language.as_deref(),
+        )?,
+        content: templates::email_password_reset_success_mail(
+            ip_address,
+            device_info,
+            user.mail_language.as_deref(),
+        )?,
         attachments: Vec::new(),
+        category: MailCategory::Security,
         result_tx: None,
     };
 
@@ -0,0 +1,265 @@
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+};
+use defguard_common::db::{
+    Id,
+    models::{
+        MailContext,
+        mail_context::{initialize_mail_context, is_valid_language_tag},
+    },
+};
+use defguard_mail::templates::{LOCALIZED_TEMPLATES, safe_tera, template_sections};
+use serde_json::json;
+use utoipa::ToSchema;
+
+use super::{ApiResponse, ApiResult};
+use crate::{
+    appstate::AppState,
+    auth::{AdminRole, SessionInfo},
+    error::WebError,
+    events::{ApiEvent, ApiEventType, ApiRequestContext},
+};
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct MailContextData {
+    pub template: String,
+    pub section: String,
+    /// Language tag, e.g. "pl" or "pt-BR"
+    pub language_tag: String,
+    /// Replaces the built-in English text; may reference the same template context
+    pub text: String,
+}
+
+/// Section of a localized mail template along with its built-in English text.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TemplateSection {
+    pub section: &'static str,
+    pub text: &'static str,
+}
+
+/// Mail template which can be translated.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LocalizedTemplate {
+    pub template: &'static str,
+    pub sections: Vec<TemplateSection>,
+}
+
+/// Make sure the section exists, language tag is valid and the text is a valid template.
+fn validate(data: &MailContextData) -> Result<(), WebError> {
+    let known_section = template_sections(&data.template)
+        .unwrap_or_default()
+        .iter()
+        .any(|(section, _)| *section == data.section);
+    if !known_section {
+        return Err(WebError::BadRequest(format!(
+            "Unknown section {} of mail template {}",
+            data.section, data.template
+        )));
+    }
+    if !is_valid_language_tag(&data.language_tag) {
+        return Err(WebError::BadRequest(format!(
+            "Invalid language tag {}",
+            data.language_tag
+        )));
+    }
+    safe_tera()
+        .add_raw_template("translation", &data.text)
+        .map_err(|err| WebError::BadRequest(format!("Invalid translation template: {err}")))?;
+
+    Ok(())
+}
+
+/// List localized mail templates
+///
+/// # Returns
+/// - `Vec<LocalizedTemplate>` object with sections which can be translated
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/settings/mail_context/template",
+    tag = "mail_context",
+    responses(
+        (status = 200, description = "List of localized mail templates", body = Vec<LocalizedTemplate>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn list_localized_templates(_admin: AdminRole) -> ApiResult {
+    let templates: Vec<LocalizedTemplate> = LOCALIZED_TEMPLATES
+        .iter()
+        .map(|&(template, sections)| LocalizedTemplate {
+            template,
+            sections: sections
+                .iter()
+                .map(|&(section, text)| TemplateSection { section, text })
+                .collect(),
+        })
+        .collect();
+
+    Ok(ApiResponse {
+        json: json!(templates),
+        status: StatusCode::OK,
+    })
+}
+
+/// List mail template translations
+///
+/// # Returns
+/// - `Vec<MailContext>` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/settings/mail_context",
+    tag = "mail_context",
+    responses(
+        (status = 200, description = "List of mail template translations", body = Vec<MailContext>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn list_mail_context(_admin: AdminRole, State(appstate): State<AppState>) -> ApiResult {
+    let translations = MailContext::all(&appstate.pool).await?;
+
+    Ok(ApiResponse {
+        json: json!(translations),
+        status: StatusCode::OK,
+    })
+}
+
+/// Set mail template translation
+///
+/// Creates or replaces translation of a template section to the given language. Mails are sent
+/// in the recipient's language, falling back to the instance default and English.
+///
+/// # Returns
+/// - `MailContext` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    put,
+    path = "/api/v1/settings/mail_context",
+    tag = "mail_context",
+    request_body = MailContextData,
+    responses(
+        (status = 200, description = "Translation set", body = MailContext),
+        (status = 400, description = "Bad request - unknown section, invalid language tag or template"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn set_mail_context(
+    _admin: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    State(appstate): State<AppState>,
+    Json(data): Json<MailContextData>,
+) -> ApiResult {
+    debug!(
+        "User {} setting {} translation of {} section in {} mail template",
+        session.user.username, data.language_tag, data.section, data.template
+    );
+    validate(&data)?;
+    let translation = match MailContext::find(
+        &appstate.pool,
+        &data.template,
+        &data.section,
+        &data.language_tag,
+    )
+    .await?
+    {
+        Some(mut translation) => {
+            translation.text = data.text;
+            translation.save(&appstate.pool).await?;
+            translation
+        }
+        None => {
+            MailContext::new(data.template, data.section, data.language_tag, data.text)
+                .save(&appstate.pool)
+                .await?
+        }
+    };
+    initialize_mail_context(&appstate.pool).await?;
+    info!(
+        "User {} set {} translation of {} section in {} mail template",
+        session.user.username, translation.language_tag, translation.section, translation.template
+    );
+
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::MailContextModified {
+            translation: translation.clone(),
+        }),
+    })?;
+
+    Ok(ApiResponse {
+        json: json!(translation),
+        status: StatusCode::OK,
+    })
+}
+
+/// Remove mail template translation
+///
+/// # Returns
+/// - empty JSON
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    delete,
+    path = "/api/v1/settings/mail_context/{id}",
+    tag = "mail_context",
+    params(
+        ("id" = Id, Path, description = "Translation ID")
+    ),
+    responses(
+        (status = 200, description = "Translation removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - translation does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn delete_mail_context(
+    _admin: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    Path(id): Path<Id>,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let translation = MailContext::find_by_id(&appstate.pool, id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Translation {id} not found")))?;
+    translation.clone().delete(&appstate.pool).await?;
+    initialize_mail_context(&appstate.pool).await?;
+    info!(
+        "User {} removed {} translation of {} section in {} mail template",
+        session.user.username, translation.language_tag, translation.section, translation.template
+    );
+
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::MailContextRemoved { translation }),
+    })?;
+
+    Ok(ApiResponse::default())
+}
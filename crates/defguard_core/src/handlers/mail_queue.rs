@@ -0,0 +1,166 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+};
+use chrono::NaiveDateTime;
+use defguard_common::db::{
+    Id,
+    models::{MailCategory, mail_queue::QueuedMail},
+};
+use serde_json::json;
+use utoipa::ToSchema;
+
+use super::{ApiResponse, ApiResult};
+use crate::{
+    appstate::AppState,
+    auth::{AdminRole, SessionInfo},
+    error::WebError,
+};
+
+/// Queued mail without its content.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct QueuedMailInfo {
+    pub id: Id,
+    pub recipient: String,
+    pub subject: String,
+    pub category: MailCategory,
+    /// Number of failed delivery attempts
+    pub attempts: i32,
+    /// Empty once delivery has been given up
+    pub next_attempt_at: Option<NaiveDateTime>,
+    pub last_error: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<QueuedMail<Id>> for QueuedMailInfo {
+    fn from(mail: QueuedMail<Id>) -> Self {
+        Self {
+            id: mail.id,
+            recipient: mail.recipient,
+            subject: mail.subject,
+            category: mail.category,
+            attempts: mail.attempts,
+            next_attempt_at: mail.next_attempt_at,
+            last_error: mail.last_error,
+            created_at: mail.created_at,
+        }
+    }
+}
+
+/// List queued mails
+///
+/// Mails which failed to be delivered because of transient SMTP failures are retried with
+/// exponential backoff. Mails which have been given up on stay in the queue until removed.
+///
+/// # Returns
+/// - `Vec<QueuedMailInfo>` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/mail_queue",
+    tag = "mail_queue",
+    responses(
+        (status = 200, description = "List of queued mails", body = Vec<QueuedMailInfo>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn list_queued_mails(_admin: AdminRole, State(appstate): State<AppState>) -> ApiResult {
+    let mails: Vec<QueuedMailInfo> = QueuedMail::list(&appstate.pool)
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect();
+
+    Ok(ApiResponse {
+        json: json!(mails),
+        status: StatusCode::OK,
+    })
+}
+
+/// Clear mail queue
+///
+/// Removes all queued mails, so they won't be retried anymore.
+///
+/// # Returns
+/// - empty JSON
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    delete,
+    path = "/api/v1/mail_queue",
+    tag = "mail_queue",
+    responses(
+        (status = 200, description = "Mail queue cleared"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn clear_mail_queue(
+    _admin: AdminRole,
+    session: SessionInfo,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let count = QueuedMail::delete_all(&appstate.pool).await?;
+    info!(
+        "User {} cleared mail queue, removed {count} mails",
+        session.user.username
+    );
+
+    Ok(ApiResponse::default())
+}
+
+/// Remove queued mail
+///
+/// # Returns
+/// - empty JSON
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    delete,
+    path = "/api/v1/mail_queue/{id}",
+    tag = "mail_queue",
+    params(
+        ("id" = Id, Path, description = "Queued mail ID")
+    ),
+    responses(
+        (status = 200, description = "Queued mail removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - queued mail does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn delete_queued_mail(
+    _admin: AdminRole,
+    session: SessionInfo,
+    Path(id): Path<Id>,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let mail = QueuedMail::find_by_id(&appstate.pool, id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Queued mail {id} not found")))?;
+    let (recipient, subject) = (mail.recipient.clone(), mail.subject.clone());
+    mail.delete(&appstate.pool).await?;
+    info!(
+        "User {} removed queued mail to: {recipient}, subject: {subject}",
+        session.user.username
+    );
+
+    Ok(ApiResponse::default())
+}
@@ -0,0 +1,344 @@
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+};
+use defguard_common::db::models::{MailTemplate, mail_template::initialize_mail_templates};
+use defguard_mail::templates::{
+    BUILTIN_TEMPLATES, builtin_template, localized_subject, preview_template, render_mail,
+    sample_mail, template_sections,
+};
+use serde_json::{Value, json};
+use utoipa::ToSchema;
+
+use super::{ApiResponse, ApiResult};
+use crate::{
+    appstate::AppState,
+    auth::{AdminRole, SessionInfo},
+    error::WebError,
+    events::{ApiEvent, ApiEventType, ApiRequestContext},
+};
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct MailTemplateData {
+    /// Tera template replacing the built-in one
+    pub content: String,
+}
+
+/// Mail template along with its built-in source.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MailTemplateInfo {
+    pub name: String,
+    /// Whether the built-in template is overridden
+    pub overridden: bool,
+    /// Template used to render mails
+    pub content: String,
+    pub builtin: &'static str,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct MailPreviewRequest {
+    pub template: String,
+    /// Template context; sample data is used if not set
+    #[schema(value_type = Option<Object>)]
+    pub context: Option<Value>,
+    /// Language of localized templates; the instance default is used if not set
+    pub language: Option<String>,
+}
+
+/// Rendered mail which hasn't been sent.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MailPreview {
+    /// Subject of localized templates
+    pub subject: Option<String>,
+    pub content: String,
+}
+
+fn find_builtin(name: &str) -> Result<&'static str, WebError> {
+    builtin_template(name)
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Mail template {name} not found")))
+}
+
+/// List mail templates
+///
+/// # Returns
+/// - `Vec<MailTemplateInfo>` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/settings/mail_templates",
+    tag = "mail_template",
+    responses(
+        (status = 200, description = "List of mail templates", body = Vec<MailTemplateInfo>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn list_mail_templates(_admin: AdminRole, State(appstate): State<AppState>) -> ApiResult {
+    let overrides = MailTemplate::all(&appstate.pool).await?;
+    let templates: Vec<MailTemplateInfo> = BUILTIN_TEMPLATES
+        .iter()
+        .map(|&(name, builtin)| {
+            let custom = overrides.iter().find(|template| template.name == name);
+            MailTemplateInfo {
+                name: name.to_string(),
+                overridden: custom.is_some(),
+                content: custom
+                    .map_or_else(|| builtin.to_string(), |template| template.content.clone()),
+                builtin,
+            }
+        })
+        .collect();
+
+    Ok(ApiResponse {
+        json: json!(templates),
+        status: StatusCode::OK,
+    })
+}
+
+/// Get mail template
+///
+/// # Returns
+/// - `MailTemplateInfo` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/settings/mail_templates/{name}",
+    tag = "mail_template",
+    params(
+        ("name" = String, Path, description = "Mail template name")
+    ),
+    responses(
+        (status = 200, description = "Mail template", body = MailTemplateInfo),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - mail template does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn get_mail_template(
+    _admin: AdminRole,
+    Path(name): Path<String>,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let builtin = find_builtin(&name)?;
+    let custom = MailTemplate::find_by_name(&appstate.pool, &name).await?;
+    let template = MailTemplateInfo {
+        overridden: custom.is_some(),
+        content: custom.map_or_else(|| builtin.to_string(), |template| template.content),
+        name,
+        builtin,
+    };
+
+    Ok(ApiResponse {
+        json: json!(template),
+        status: StatusCode::OK,
+    })
+}
+
+/// Override mail template
+///
+/// The template is rendered with sample data first and it's only saved if that succeeds.
+/// If the override fails to render later on, the built-in template is used instead.
+///
+/// # Returns
+/// - `MailTemplateInfo` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    put,
+    path = "/api/v1/settings/mail_templates/{name}",
+    tag = "mail_template",
+    params(
+        ("name" = String, Path, description = "Mail template name")
+    ),
+    request_body = MailTemplateData,
+    responses(
+        (status = 200, description = "Mail template overridden", body = MailTemplateInfo),
+        (status = 400, description = "Bad request - template doesn't render"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - mail template does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn set_mail_template(
+    _admin: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    Path(name): Path<String>,
+    State(appstate): State<AppState>,
+    Json(data): Json<MailTemplateData>,
+) -> ApiResult {
+    let builtin = find_builtin(&name)?;
+    debug!(
+        "User {} overriding mail template {name}",
+        session.user.username
+    );
+    if let Err(err) = preview_template(&name, &data.content) {
+        debug!("Override of mail template {name} failed to render: {err}");
+        return Err(WebError::BadRequest(format!(
+            "Mail template {name} failed to render: {err}"
+        )));
+    }
+    let template = match MailTemplate::find_by_name(&appstate.pool, &name).await? {
+        Some(mut template) => {
+            template.content = data.content;
+            template.save(&appstate.pool).await?;
+            template
+        }
+        None => {
+            MailTemplate::new(name.clone(), data.content)
+                .save(&appstate.pool)
+                .await?
+        }
+    };
+    initialize_mail_templates(&appstate.pool).await?;
+    info!(
+        "User {} overrode mail template {name}",
+        session.user.username
+    );
+
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::MailTemplateModified {
+            template: template.clone(),
+        }),
+    })?;
+
+    Ok(ApiResponse {
+        json: json!(MailTemplateInfo {
+            name,
+            overridden: true,
+            content: template.content,
+            builtin,
+        }),
+        status: StatusCode::OK,
+    })
+}
+
+/// Restore built-in mail template
+///
+/// Removes the override of a mail template.
+///
+/// # Returns
+/// - empty JSON
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    delete,
+    path = "/api/v1/settings/mail_templates/{name}",
+    tag = "mail_template",
+    params(
+        ("name" = String, Path, description = "Mail template name")
+    ),
+    responses(
+        (status = 200, description = "Built-in mail template restored"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - mail template is not overridden"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn restore_mail_template(
+    _admin: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    Path(name): Path<String>,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let template = MailTemplate::find_by_name(&appstate.pool, &name)
+        .await?
+        .ok_or_else(|| {
+            WebError::ObjectNotFound(format!("Mail template {name} is not overridden"))
+        })?;
+    template.clone().delete(&appstate.pool).await?;
+    initialize_mail_templates(&appstate.pool).await?;
+    info!(
+        "User {} restored built-in mail template {name}",
+        session.user.username
+    );
+
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::MailTemplateRestored { template }),
+    })?;
+
+    Ok(ApiResponse::default())
+}
+
+/// Preview mail
+///
+/// Renders a mail template, including its override, with supplied context or sample data.
+/// The mail isn't sent, so templates can be verified without a configured mail backend.
+///
+/// # Returns
+/// - `MailPreview` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    post,
+    path = "/api/v1/mail/preview",
+    tag = "mail_template",
+    request_body = MailPreviewRequest,
+    responses(
+        (status = 200, description = "Rendered mail", body = MailPreview),
+        (status = 400, description = "Bad request - template doesn't render with supplied context"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - mail template does not exist"),
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn preview_mail(
+    _admin: AdminRole,
+    session: SessionInfo,
+    Json(data): Json<MailPreviewRequest>,
+) -> ApiResult {
+    find_builtin(&data.template)?;
+    debug!(
+        "User {} previewing {} mail",
+        session.user.username, data.template
+    );
+    let language = data.language.as_deref();
+    let content = match data.context {
+        Some(context) => render_mail(&data.template, context, language),
+        None => sample_mail(&data.template, language),
+    }
+    .map_err(|err| {
+        debug!("Preview of {} mail failed to render: {err}", data.template);
+        WebError::BadRequest(format!(
+            "Mail template {} failed to render: {err}",
+            data.template
+        ))
+    })?;
+    let subject = template_sections(&data.template)
+        .map(|_| localized_subject(&data.template, language))
+        .transpose()?;
+
+    Ok(ApiResponse {
+        json: json!(MailPreview { subject, content }),
+        status: StatusCode::OK,
+    })
+}
@@ -0,0 +1,286 @@
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+};
+use defguard_common::db::{
+    Id,
+    models::{
+        MailVariable, Settings,
+        mail_variable::{
+            initialize_mail_variables, is_valid_mail_variable_name, referenced_mail_variables,
+        },
+    },
+};
+use serde_json::json;
+use utoipa::ToSchema;
+
+use super::{ApiResponse, ApiResult};
+use crate::{
+    appstate::AppState,
+    auth::{AdminRole, SessionInfo},
+    error::WebError,
+    events::{ApiEvent, ApiEventType, ApiRequestContext},
+};
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct MailVariableData {
+    /// Referenced in templates as `instance.<name>`; lowercase letters, digits and underscores
+    pub name: String,
+    pub value: String,
+}
+
+/// Make sure variable name is valid and not used by another variable.
+async fn validate_name(appstate: &AppState, name: &str, id: Option<Id>) -> Result<(), WebError> {
+    if !is_valid_mail_variable_name(name) {
+        return Err(WebError::BadRequest(format!(
+            "Invalid mail variable name {name}"
+        )));
+    }
+    if let Some(existing) = MailVariable::find_by_name(&appstate.pool, name).await? {
+        if Some(existing.id) != id {
+            return Err(WebError::BadRequest(format!(
+                "Mail variable {name} already exists"
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Variables referenced in enrollment welcome templates can't be removed or renamed,
+/// as the templates couldn't be rendered anymore.
+fn ensure_unreferenced(name: &str) -> Result<(), WebError> {
+    let settings = Settings::get_current_settings();
+    let referenced = [
+        &settings.enrollment_welcome_message,
+        &settings.enrollment_welcome_email,
+    ]
+    .into_iter()
+    .flatten()
+    .any(|template| referenced_mail_variables(template).contains(name));
+    if referenced {
+        return Err(WebError::BadRequest(format!(
+            "Mail variable {name} is used in enrollment welcome templates"
+        )));
+    }
+
+    Ok(())
+}
+
+async fn find_variable(appstate: &AppState, id: Id) -> Result<MailVariable<Id>, WebError> {
+    MailVariable::find_by_id(&appstate.pool, id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Mail variable {id} not found")))
+}
+
+/// List all mail variables
+///
+/// # Returns
+/// - `Vec<MailVariable>` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/settings/mail_variable",
+    tag = "mail_variable",
+    responses(
+        (status = 200, description = "List of mail variables", body = Vec<MailVariable>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn list_mail_variables(_admin: AdminRole, State(appstate): State<AppState>) -> ApiResult {
+    let variables = MailVariable::all(&appstate.pool).await?;
+
+    Ok(ApiResponse {
+        json: json!(variables),
+        status: StatusCode::OK,
+    })
+}
+
+/// Create mail variable
+///
+/// Variable becomes available in enrollment welcome templates and announcements
+/// as `{{ instance.<name> }}`.
+///
+/// # Returns
+/// - `MailVariable` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    post,
+    path = "/api/v1/settings/mail_variable",
+    tag = "mail_variable",
+    request_body = MailVariableData,
+    responses(
+        (status = 201, description = "Mail variable created", body = MailVariable),
+        (status = 400, description = "Bad request - invalid or duplicate variable name"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn create_mail_variable(
+    _admin: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    State(appstate): State<AppState>,
+    Json(data): Json<MailVariableData>,
+) -> ApiResult {
+    debug!(
+        "User {} creating mail variable {}",
+        session.user.username, data.name
+    );
+    validate_name(&appstate, &data.name, None).await?;
+    let variable = MailVariable::new(data.name, data.value)
+        .save(&appstate.pool)
+        .await?;
+    initialize_mail_variables(&appstate.pool).await?;
+    info!(
+        "User {} created mail variable {}",
+        session.user.username, variable.name
+    );
+
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::MailVariableAdded {
+            variable: variable.clone(),
+        }),
+    })?;
+
+    Ok(ApiResponse {
+        json: json!(variable),
+        status: StatusCode::CREATED,
+    })
+}
+
+/// Modify mail variable
+///
+/// # Returns
+/// - `MailVariable` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    put,
+    path = "/api/v1/settings/mail_variable/{id}",
+    tag = "mail_variable",
+    params(
+        ("id" = Id, Path, description = "Mail variable ID")
+    ),
+    request_body = MailVariableData,
+    responses(
+        (status = 200, description = "Mail variable modified", body = MailVariable),
+        (status = 400, description = "Bad request - invalid name or variable is in use"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - mail variable does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn modify_mail_variable(
+    _admin: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    Path(id): Path<Id>,
+    State(appstate): State<AppState>,
+    Json(data): Json<MailVariableData>,
+) -> ApiResult {
+    let mut variable = find_variable(&appstate, id).await?;
+    debug!(
+        "User {} modifying mail variable {}",
+        session.user.username, variable.name
+    );
+    if data.name != variable.name {
+        ensure_unreferenced(&variable.name)?;
+        validate_name(&appstate, &data.name, Some(id)).await?;
+    }
+    let before = variable.clone();
+    variable.name = data.name;
+    variable.value = data.value;
+    variable.save(&appstate.pool).await?;
+    initialize_mail_variables(&appstate.pool).await?;
+    info!(
+        "User {} modified mail variable {}",
+        session.user.username, variable.name
+    );
+
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::MailVariableModified {
+            before,
+            after: variable.clone(),
+        }),
+    })?;
+
+    Ok(ApiResponse {
+        json: json!(variable),
+        status: StatusCode::OK,
+    })
+}
+
+/// Remove mail variable
+///
+/// # Returns
+/// - empty JSON
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    delete,
+    path = "/api/v1/settings/mail_variable/{id}",
+    tag = "mail_variable",
+    params(
+        ("id" = Id, Path, description = "Mail variable ID")
+    ),
+    responses(
+        (status = 200, description = "Mail variable removed"),
+        (status = 400, description = "Bad request - variable is in use"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - mail variable does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn delete_mail_variable(
+    _admin: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    Path(id): Path<Id>,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let variable = find_variable(&appstate, id).await?;
+    debug!(
+        "User {} removing mail variable {}",
+        session.user.username, variable.name
+    );
+    ensure_unreferenced(&variable.name)?;
+    variable.clone().delete(&appstate.pool).await?;
+    initialize_mail_variables(&appstate.pool).await?;
+    info!(
+        "User {} removed mail variable {}",
+        session.user.username, variable.name
+    );
+
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::MailVariableRemoved { variable }),
+    })?;
+
+    Ok(ApiResponse::default())
+}
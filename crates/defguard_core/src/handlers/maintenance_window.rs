@@ -0,0 +1,204 @@
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+};
+use chrono::{NaiveDateTime, Utc};
+use defguard_common::db::Id;
+use defguard_mail::calendar::CalendarMethod;
+use serde_json::json;
+use sqlx::PgPool;
+use utoipa::ToSchema;
+
+use super::{ApiResponse, ApiResult, mail::send_maintenance_window_invites};
+use crate::{
+    appstate::AppState,
+    auth::{AdminRole, SessionInfo},
+    db::{WireguardNetwork, models::maintenance_window::MaintenanceWindow},
+    error::WebError,
+};
+
+/// Maintenance window times are in UTC.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct MaintenanceWindowData {
+    pub starts_at: NaiveDateTime,
+    pub ends_at: NaiveDateTime,
+    #[serde(default)]
+    pub description: String,
+}
+
+async fn find_location(pool: &PgPool, id: Id) -> Result<WireguardNetwork<Id>, WebError> {
+    WireguardNetwork::find_by_id(pool, id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Location {id} not found")))
+}
+
+/// List upcoming maintenance windows of a location
+///
+/// # Returns
+/// - `Vec<MaintenanceWindow>` object, the earliest first
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/network/{location_id}/maintenance",
+    tag = "maintenance_window",
+    params(
+        ("location_id" = Id, description = "ID of location")
+    ),
+    responses(
+        (status = 200, description = "Maintenance windows which haven't ended yet", body = Vec<MaintenanceWindow>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - location does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn list_maintenance_windows(
+    _admin: AdminRole,
+    Path(location_id): Path<Id>,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let location = find_location(&appstate.pool, location_id).await?;
+    let windows = MaintenanceWindow::upcoming_for_location(&appstate.pool, location.id).await?;
+
+    Ok(ApiResponse {
+        json: json!(windows),
+        status: StatusCode::OK,
+    })
+}
+
+/// Schedule maintenance window of a location
+///
+/// Announces gateway downtime to admins with calendar invites.
+///
+/// # Returns
+/// - `MaintenanceWindow` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    post,
+    path = "/api/v1/network/{location_id}/maintenance",
+    tag = "maintenance_window",
+    params(
+        ("location_id" = Id, description = "ID of location")
+    ),
+    request_body = MaintenanceWindowData,
+    responses(
+        (status = 201, description = "Maintenance window scheduled", body = MaintenanceWindow),
+        (status = 400, description = "Bad request - window ends before it starts or in the past"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - location does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn create_maintenance_window(
+    _admin: AdminRole,
+    session: SessionInfo,
+    Path(location_id): Path<Id>,
+    State(appstate): State<AppState>,
+    Json(data): Json<MaintenanceWindowData>,
+) -> ApiResult {
+    debug!(
+        "User {} scheduling maintenance window of location {location_id}",
+        session.user.username
+    );
+    let location = find_location(&appstate.pool, location_id).await?;
+    if data.ends_at <= data.starts_at {
+        return Err(WebError::BadRequest(
+            "Maintenance window has to end after it starts".into(),
+        ));
+    }
+    if data.ends_at <= Utc::now().naive_utc() {
+        return Err(WebError::BadRequest(
+            "Maintenance window can't end in the past".into(),
+        ));
+    }
+    let window =
+        MaintenanceWindow::new(location.id, data.starts_at, data.ends_at, data.description)
+            .save(&appstate.pool)
+            .await?;
+    info!(
+        "User {} scheduled maintenance window {} of location {}",
+        session.user.username, window.id, location.name
+    );
+    send_maintenance_window_invites(
+        &window,
+        &location.name,
+        CalendarMethod::Request,
+        &appstate.mail_tx,
+        &appstate.pool,
+    )
+    .await?;
+
+    Ok(ApiResponse {
+        json: json!(window),
+        status: StatusCode::CREATED,
+    })
+}
+
+/// Cancel maintenance window
+///
+/// Admins are sent cancellations of calendar invites unless the window has already ended.
+///
+/// # Returns
+/// - empty JSON
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    delete,
+    path = "/api/v1/network/{location_id}/maintenance/{id}",
+    tag = "maintenance_window",
+    params(
+        ("location_id" = Id, description = "ID of location"),
+        ("id" = Id, description = "ID of maintenance window")
+    ),
+    responses(
+        (status = 200, description = "Maintenance window cancelled"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - maintenance window does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn delete_maintenance_window(
+    _admin: AdminRole,
+    session: SessionInfo,
+    Path((location_id, id)): Path<(Id, Id)>,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let location = find_location(&appstate.pool, location_id).await?;
+    let window = MaintenanceWindow::find_by_id(&appstate.pool, id)
+        .await?
+        .filter(|window| window.location_id == location.id)
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Maintenance window {id} not found")))?;
+    window.clone().delete(&appstate.pool).await?;
+    info!(
+        "User {} cancelled maintenance window {id} of location {}",
+        session.user.username, location.name
+    );
+    if window.ends_at > Utc::now().naive_utc() {
+        send_maintenance_window_invites(
+            &window,
+            &location.name,
+            CalendarMethod::Cancel,
+            &appstate.mail_tx,
+            &appstate.pool,
+        )
+        .await?;
+    }
+
+    Ok(ApiResponse::default())
+}
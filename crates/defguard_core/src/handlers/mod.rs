@@ -16,24 +16,57 @@ use crate::{
     appstate::AppState,
     auth::SessionInfo,
     db::{Device, User, UserInfo, WebHook},
-    enterprise::{db::models::acl::AclError, license::LicenseError},
+    enterprise::{
+        db::models::{acl::AclError, enterprise_settings::ClientTrafficPolicy},
+        license::LicenseError,
+    },
     error::WebError,
     events::ApiRequestContext,
 };
 
 pub(crate) mod activity_log;
+pub(crate) mod announcement;
+pub(crate) mod api_version;
 pub(crate) mod app_info;
 pub(crate) mod auth;
+pub(crate) mod dashboard;
+pub(crate) mod device_approval;
+pub(crate) mod dns_leak;
+pub(crate) mod enrollment_sheet;
+pub(crate) mod enrollment_token;
 pub(crate) mod forward_auth;
+pub(crate) mod gateway_setup;
+pub(crate) mod graphql;
 pub(crate) mod group;
+pub(crate) mod helpdesk_password_reset;
+pub(crate) mod inventory;
+pub(crate) mod ip_allowlist;
+pub(crate) mod itsm;
+pub(crate) mod jobs;
+pub(crate) mod location_spec;
+pub(crate) mod login_banner;
+pub(crate) mod login_history;
+pub(crate) mod lookup;
 pub(crate) mod mail;
+pub(crate) mod mail_context;
+pub(crate) mod mail_queue;
+pub(crate) mod mail_template;
+pub(crate) mod mail_variable;
+pub(crate) mod maintenance_window;
 pub mod network_devices;
+pub(crate) mod notification;
 pub(crate) mod openid_clients;
 pub mod openid_flow;
 pub(crate) mod pagination;
+pub(crate) mod redaction;
+pub(crate) mod route;
+pub(crate) mod self_registration;
+pub(crate) mod service_account;
 pub(crate) mod settings;
+pub(crate) mod smtp_profile;
 pub(crate) mod ssh_authorized_keys;
 pub(crate) mod support;
+pub(crate) mod troubleshoot;
 pub(crate) mod updates;
 pub(crate) mod user;
 pub(crate) mod webhooks;
@@ -225,6 +258,9 @@ pub type ApiResult = Result<ApiResponse, WebError>;
 pub struct Auth {
     username: String,
     password: String,
+    /// Version of the login banner acknowledged by the user
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    banner_id: Option<Id>,
 }
 
 impl Auth {
@@ -233,8 +269,16 @@ impl Auth {
         Self {
             username: username.into(),
             password: password.into(),
+            banner_id: None,
         }
     }
+
+    /// Acknowledge a version of the login banner.
+    #[must_use]
+    pub fn with_banner(mut self, banner_id: Id) -> Self {
+        self.banner_id = Some(banner_id);
+        self
+    }
 }
 
 #[derive(Deserialize, Serialize)]
@@ -270,6 +314,12 @@ pub struct GroupInfo {
     pub members: Vec<String>,
     pub vpn_locations: Vec<String>,
     pub is_admin: bool,
+    /// Members can initiate password resets of non-admin users.
+    pub is_helpdesk: bool,
+    /// Members have read-only access with personal data redacted.
+    pub is_auditor: bool,
+    /// Overrides instance-wide client traffic policy for group members.
+    pub client_traffic_policy: Option<ClientTrafficPolicy>,
 }
 
 impl GroupInfo {
@@ -280,6 +330,9 @@ impl GroupInfo {
         members: Vec<String>,
         vpn_locations: Vec<String>,
         is_admin: bool,
+        is_helpdesk: bool,
+        is_auditor: bool,
+        client_traffic_policy: Option<ClientTrafficPolicy>,
     ) -> Self {
         Self {
             id,
@@ -287,6 +340,9 @@ impl GroupInfo {
             members,
             vpn_locations,
             is_admin,
+            is_helpdesk,
+            is_auditor,
+            client_traffic_policy,
         }
     }
 }
@@ -297,6 +353,15 @@ pub struct EditGroupInfo {
     pub name: String,
     pub members: Vec<String>,
     pub is_admin: bool,
+    /// Members can initiate password resets of non-admin users.
+    #[serde(default)]
+    pub is_helpdesk: bool,
+    /// Members have read-only access with personal data redacted.
+    #[serde(default)]
+    pub is_auditor: bool,
+    /// Overrides instance-wide client traffic policy for group members.
+    #[serde(default)]
+    pub client_traffic_policy: Option<ClientTrafficPolicy>,
 }
 
 impl EditGroupInfo {
@@ -306,6 +371,9 @@ impl EditGroupInfo {
             name: name.into(),
             members,
             is_admin,
+            is_helpdesk: false,
+            is_auditor: false,
+            client_traffic_policy: None,
         }
     }
 }
@@ -331,6 +399,9 @@ pub struct StartEnrollmentRequest {
     pub send_enrollment_notification: bool,
     pub email: Option<String>,
     pub token_expiration_time: Option<String>,
+    /// Locations devices enrolled with the token can be added to; all locations available
+    /// to the user if not set
+    pub location_ids: Option<Vec<Id>>,
 }
 
 #[derive(Deserialize, Serialize, ToSchema)]
@@ -377,6 +448,10 @@ pub struct WebHookData {
     pub on_user_deleted: bool,
     pub on_user_modified: bool,
     pub on_hwkey_provision: bool,
+    #[serde(default)]
+    pub on_enrollment_completed: bool,
+    #[serde(default)]
+    pub payload_template: Option<String>,
 }
 
 impl From<WebHookData> for WebHook {
@@ -391,6 +466,8 @@ impl From<WebHookData> for WebHook {
             on_user_deleted: data.on_user_deleted,
             on_user_modified: data.on_user_modified,
             on_hwkey_provision: data.on_hwkey_provision,
+            on_enrollment_completed: data.on_enrollment_completed,
+            payload_template: data.payload_template,
         }
     }
 }
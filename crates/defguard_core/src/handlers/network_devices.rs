@@ -133,6 +133,9 @@ pub async fn download_network_device_config(
             "No IP address found for device: {}({})",
             device.name, device.id
         )))?;
+    let allowed_ips = location
+        .device_allowed_ips(&appstate.pool, &device, &enterprise_settings)
+        .await?;
     debug!(
         "Created a WireGuard config for network device {device_id} in location {}.",
         location.name
@@ -140,7 +143,7 @@ pub async fn download_network_device_config(
     Ok(Device::create_config(
         &location,
         &network_device,
-        &enterprise_settings,
+        &allowed_ips,
     ))
 }
 
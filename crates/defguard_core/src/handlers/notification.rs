@@ -0,0 +1,242 @@
+use std::collections::HashSet;
+
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+};
+use defguard_common::db::Id;
+use reqwest::Url;
+use serde_json::json;
+use utoipa::{IntoParams, ToSchema};
+
+use super::{ApiResponse, ApiResult};
+use crate::{
+    appstate::AppState,
+    auth::{AdminRole, SessionInfo},
+    db::models::notification::{
+        Notification, NotificationCategory, NotificationChannel, NotificationRule,
+    },
+    error::WebError,
+};
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct NotificationRuleData {
+    pub category: NotificationCategory,
+    pub channel: NotificationChannel,
+    /// Required for the webhook channel, not allowed for other channels
+    pub webhook_url: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[serde(default)]
+pub struct NotificationQuery {
+    /// List only notifications which haven't been read yet
+    unread: bool,
+}
+
+fn validate_rules(rules: &[NotificationRuleData]) -> Result<(), WebError> {
+    let mut seen = HashSet::new();
+    for rule in rules {
+        if !seen.insert((rule.category, rule.channel)) {
+            return Err(WebError::BadRequest(format!(
+                "Duplicate notification rule for {:?} through {:?}",
+                rule.category, rule.channel
+            )));
+        }
+        match (rule.channel, &rule.webhook_url) {
+            (NotificationChannel::Webhook, Some(url)) => {
+                let valid =
+                    Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https"));
+                if !valid {
+                    return Err(WebError::BadRequest(format!("Invalid webhook URL {url}")));
+                }
+            }
+            (NotificationChannel::Webhook, None) => {
+                return Err(WebError::BadRequest(
+                    "Webhook URL is required for the webhook channel".into(),
+                ));
+            }
+            (_, Some(_)) => {
+                return Err(WebError::BadRequest(
+                    "Webhook URL is only allowed for the webhook channel".into(),
+                ));
+            }
+            (_, None) => (),
+        }
+    }
+
+    Ok(())
+}
+
+/// List notification rules of the current admin
+///
+/// Admins without any rules receive all alerts by email.
+///
+/// # Returns
+/// - `Vec<NotificationRule>` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/notification/rules",
+    tag = "notification",
+    responses(
+        (status = 200, description = "Notification rules of the current admin", body = Vec<NotificationRule>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn list_notification_rules(
+    _admin: AdminRole,
+    session: SessionInfo,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let rules = NotificationRule::all_for_user(&appstate.pool, session.user.id).await?;
+
+    Ok(ApiResponse {
+        json: json!(rules),
+        status: StatusCode::OK,
+    })
+}
+
+/// Replace notification rules of the current admin
+///
+/// Each rule delivers alerts of a category through a channel. Categories without rules are
+/// not delivered at all, unless there are no rules, in which case all alerts are sent by email.
+///
+/// # Returns
+/// - `Vec<NotificationRule>` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    put,
+    path = "/api/v1/notification/rules",
+    tag = "notification",
+    request_body = Vec<NotificationRuleData>,
+    responses(
+        (status = 200, description = "Notification rules replaced", body = Vec<NotificationRule>),
+        (status = 400, description = "Bad request - duplicate rule or invalid webhook URL"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn set_notification_rules(
+    _admin: AdminRole,
+    session: SessionInfo,
+    State(appstate): State<AppState>,
+    Json(data): Json<Vec<NotificationRuleData>>,
+) -> ApiResult {
+    let user = &session.user;
+    debug!("User {} setting notification rules", user.username);
+    validate_rules(&data)?;
+
+    let mut transaction = appstate.pool.begin().await?;
+    NotificationRule::delete_for_user(&mut *transaction, user.id).await?;
+    for rule in data {
+        NotificationRule::new(user.id, rule.category, rule.channel, rule.webhook_url)
+            .save(&mut *transaction)
+            .await?;
+    }
+    let rules = NotificationRule::all_for_user(&mut *transaction, user.id).await?;
+    transaction.commit().await?;
+    info!(
+        "User {} set {} notification rules",
+        user.username,
+        rules.len()
+    );
+
+    Ok(ApiResponse {
+        json: json!(rules),
+        status: StatusCode::OK,
+    })
+}
+
+/// List in-app notifications of the current admin
+///
+/// # Returns
+/// - `Vec<Notification>` object, the most recent first
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/notification",
+    tag = "notification",
+    params(NotificationQuery),
+    responses(
+        (status = 200, description = "In-app notifications of the current admin", body = Vec<Notification>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn list_notifications(
+    _admin: AdminRole,
+    session: SessionInfo,
+    State(appstate): State<AppState>,
+    Query(query): Query<NotificationQuery>,
+) -> ApiResult {
+    let notifications =
+        Notification::all_for_user(&appstate.pool, session.user.id, query.unread).await?;
+
+    Ok(ApiResponse {
+        json: json!(notifications),
+        status: StatusCode::OK,
+    })
+}
+
+/// Mark in-app notification as read
+///
+/// # Returns
+/// - empty JSON
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    post,
+    path = "/api/v1/notification/{id}/read",
+    tag = "notification",
+    params(
+        ("id" = Id, Path, description = "Notification ID")
+    ),
+    responses(
+        (status = 200, description = "Notification marked as read"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - notification does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn mark_notification_read(
+    _admin: AdminRole,
+    session: SessionInfo,
+    Path(id): Path<Id>,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    if !Notification::mark_read(&appstate.pool, id, session.user.id).await? {
+        return Err(WebError::ObjectNotFound(format!(
+            "Notification {id} not found"
+        )));
+    }
+
+    Ok(ApiResponse {
+        json: json!({}),
+        status: StatusCode::OK,
+    })
+}
@@ -2,15 +2,20 @@ use axum::{
     extract::{Json, Path, State},
     http::StatusCode,
 };
+use defguard_common::db::Id;
 use serde_json::json;
 
 use super::{ApiResponse, ApiResult, webhooks::ChangeStateData};
 use crate::{
     appstate::AppState,
     auth::{AdminRole, SessionInfo},
-    db::models::{
-        NewOpenIDClient,
-        oauth2client::{OAuth2Client, OAuth2ClientSafe},
+    db::{
+        OAuth2Token,
+        models::{
+            NewOpenIDClient,
+            oauth2client::{OAuth2Client, OAuth2ClientSafe},
+            oauth2token::OAuth2TokenInfo,
+        },
     },
     events::{ApiEvent, ApiEventType, ApiRequestContext},
 };
@@ -36,6 +41,16 @@ pub async fn add_openid_client(
             status: StatusCode::BAD_REQUEST,
         });
     }
+    if let Err(msg) = data.validate_claims() {
+        warn!(
+            "User {} attempted to create openid client with invalid claims: {msg}",
+            session.user.username
+        );
+        return Ok(ApiResponse {
+            json: json!({"msg": msg}),
+            status: StatusCode::BAD_REQUEST,
+        });
+    }
     let client = OAuth2Client::from_new(data).save(&appstate.pool).await?;
     info!(
         "User {} added OpenID client {}",
@@ -109,6 +124,16 @@ pub async fn change_openid_client(
             status: StatusCode::BAD_REQUEST,
         });
     }
+    if let Err(msg) = data.validate_claims() {
+        warn!(
+            "User {} attempted to edit openid client with invalid claims: {msg}",
+            session.user.username
+        );
+        return Ok(ApiResponse {
+            json: json!({"msg": msg}),
+            status: StatusCode::BAD_REQUEST,
+        });
+    }
     let mut transaction = appstate.pool.begin().await?;
     let status = match OAuth2Client::find_by_client_id(&mut *transaction, &client_id).await? {
         Some(mut client) => {
@@ -118,6 +143,9 @@ pub async fn change_openid_client(
             client.redirect_uri = data.redirect_uri;
             client.enabled = data.enabled;
             client.scope = data.scope;
+            client.claim_mapping.0 = data.claim_mapping;
+            client.group_filter = data.group_filter;
+            client.static_claims.0 = data.static_claims;
             client.save(&mut *transaction).await?;
             if before.scope != client.scope {
                 client.clear_authorizations(&mut *transaction).await?;
@@ -212,3 +240,75 @@ pub async fn delete_openid_client(
         status,
     })
 }
+
+/// List active tokens issued to an OpenID client.
+pub async fn list_openid_client_tokens(
+    _admin: AdminRole,
+    State(appstate): State<AppState>,
+    Path(client_id): Path<String>,
+) -> ApiResult {
+    let Some(client) = OAuth2Client::find_by_client_id(&appstate.pool, &client_id).await? else {
+        return Ok(ApiResponse {
+            json: json!({}),
+            status: StatusCode::NOT_FOUND,
+        });
+    };
+    let tokens = OAuth2TokenInfo::all_for_client(&appstate.pool, client.id).await?;
+    Ok(ApiResponse {
+        json: json!(tokens),
+        status: StatusCode::OK,
+    })
+}
+
+/// Revoke all tokens issued to an OpenID client. Users have to log in to the client again.
+pub async fn revoke_openid_client_tokens(
+    _admin: AdminRole,
+    session: SessionInfo,
+    State(appstate): State<AppState>,
+    Path(client_id): Path<String>,
+) -> ApiResult {
+    revoke_tokens(&appstate, &session, &client_id, None).await
+}
+
+/// Revoke a single token issued to an OpenID client.
+pub async fn revoke_openid_client_token(
+    _admin: AdminRole,
+    session: SessionInfo,
+    State(appstate): State<AppState>,
+    Path((client_id, token_id)): Path<(String, Id)>,
+) -> ApiResult {
+    revoke_tokens(&appstate, &session, &client_id, Some(token_id)).await
+}
+
+async fn revoke_tokens(
+    appstate: &AppState,
+    session: &SessionInfo,
+    client_id: &str,
+    token_id: Option<Id>,
+) -> ApiResult {
+    debug!(
+        "User {} revoking tokens of OpenID client {client_id}",
+        session.user.username
+    );
+    let Some(client) = OAuth2Client::find_by_client_id(&appstate.pool, client_id).await? else {
+        return Ok(ApiResponse {
+            json: json!({}),
+            status: StatusCode::NOT_FOUND,
+        });
+    };
+    let revoked = OAuth2Token::revoke_for_client(&appstate.pool, client.id, token_id).await?;
+    if token_id.is_some() && revoked == 0 {
+        return Ok(ApiResponse {
+            json: json!({}),
+            status: StatusCode::NOT_FOUND,
+        });
+    }
+    info!(
+        "User {} revoked {revoked} token(s) of OpenID client {client_id} ({})",
+        session.user.username, client.name
+    );
+    Ok(ApiResponse {
+        json: json!({}),
+        status: StatusCode::OK,
+    })
+}
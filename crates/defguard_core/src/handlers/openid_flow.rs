@@ -22,7 +22,8 @@ use openidconnect::{
     EndUserGivenName, EndUserName, EndUserPhoneNumber, EndUserUsername, IdToken, IdTokenClaims,
     IdTokenFields, IssuerUrl, JsonWebKeySetUrl, LocalizedClaim, Nonce, PkceCodeChallenge,
     PkceCodeVerifier, PrivateSigningKey, RefreshToken, ResponseTypes, Scope, StandardClaims,
-    StandardErrorResponse, StandardTokenResponse, SubjectIdentifier, TokenUrl, UserInfoUrl,
+    StandardErrorResponse, StandardTokenResponse, SubjectIdentifier, TokenUrl, UserInfoClaims,
+    UserInfoUrl,
     core::{
         CoreAuthErrorResponseType, CoreClaimName, CoreErrorResponseType, CoreGenderClaim,
         CoreGrantType, CoreHmacKey, CoreJsonWebKeySet, CoreJweContentEncryptionAlgorithm,
@@ -35,7 +36,7 @@ use serde::{
     de::{Deserialize, Deserializer, Error as DeError, Unexpected, Visitor},
     ser::{Serialize, Serializer},
 };
-use serde_json::json;
+use serde_json::{Map, Value, json};
 use sqlx::PgPool;
 use time::Duration;
 
@@ -45,7 +46,7 @@ use crate::{
     auth::{SessionInfo, UserClaims},
     db::{
         OAuth2AuthorizedApp, OAuth2Token, Session, SessionState, User,
-        models::oauth2client::OAuth2Client,
+        models::{oauth2client::OAuth2Client, oauth2token::OAuth2RevocationReason},
     },
     error::WebError,
     handlers::{SIGN_IN_COOKIE_NAME, mail::send_new_device_ocid_login_email},
@@ -103,7 +104,7 @@ pub async fn discovery_keys() -> ApiResult {
     })
 }
 pub type DefguardIdTokenFields = IdTokenFields<
-    GroupClaims,
+    CustomClaims,
     EmptyExtraTokenFields,
     CoreGenderClaim,
     CoreJweContentEncryptionAlgorithm,
@@ -543,19 +544,47 @@ pub async fn authorization(
     Ok(redirect_to(url, private_cookies))
 }
 
+/// Claims emitted besides the standard ones: groups, standard claims renamed by the client
+/// configuration and static claims of the client.
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize, Default)]
-pub struct GroupClaims {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    groups: Option<Vec<String>>,
+pub struct CustomClaims {
+    #[serde(flatten)]
+    claims: Map<String, Value>,
 }
 
-impl AdditionalClaims for GroupClaims {}
+impl AdditionalClaims for CustomClaims {}
+
+/// Applies claim configuration of `client`. Renamed claims are moved out of `user_claims`.
+async fn get_custom_claims(
+    pool: &PgPool,
+    user: &User<Id>,
+    client: &OAuth2Client<Id>,
+    scope: &str,
+    user_claims: &mut UserClaims,
+) -> Result<CustomClaims, WebError> {
+    let mut claims = Map::new();
+    if scope.split_whitespace().any(|scope| scope == "groups") {
+        let mut groups = user.member_of_names(pool).await?;
+        if !client.group_filter.is_empty() {
+            groups.retain(|group| client.group_filter.contains(group));
+        }
+        claims.insert("groups".into(), json!(groups));
+    }
+    for (claim, name) in client.claim_mapping.iter() {
+        let value = if claim == "groups" {
+            claims.remove(claim)
+        } else {
+            user_claims.take(claim).map(Value::from)
+        };
+        if let Some(value) = value {
+            claims.insert(name.clone(), value);
+        }
+    }
+    for (name, value) in client.static_claims.iter() {
+        claims.insert(name.clone(), value.clone());
+    }
 
-async fn get_group_claims(pool: &PgPool, user: &User<Id>) -> Result<GroupClaims, WebError> {
-    let groups = user.member_of_names(pool).await?;
-    Ok(GroupClaims {
-        groups: Some(groups),
-    })
+    Ok(CustomClaims { claims })
 }
 
 /// Login Authorization Endpoint redirect with authorization code
@@ -695,7 +724,7 @@ impl TokenRequest {
         base_url: &Url,
         secret: T,
         rsa_key: Option<CoreRsaPrivateSigningKey>,
-        group_claims: GroupClaims,
+        custom_claims: CustomClaims,
     ) -> Result<DefguardTokenResponse, CoreErrorResponseType>
     where
         T: Into<Vec<u8>>,
@@ -733,7 +762,7 @@ impl TokenRequest {
                     expiration,
                     issue_time,
                     claims,
-                    group_claims,
+                    custom_claims,
                 )
                 .set_nonce(auth_code.nonce.clone().map(Nonce::new));
 
@@ -873,13 +902,16 @@ pub async fn token(
                                     auth_code.redirect_uri.clone(),
                                     auth_code.scope.clone(),
                                 );
-                                let group_claims = if auth_code.scope.contains("groups") {
-                                    get_group_claims(&appstate.pool, &user).await?
-                                } else {
-                                    GroupClaims { groups: None }
-                                };
+                                let mut user_claims = UserClaims::from_user(&user, &client, &token);
+                                let custom_claims = get_custom_claims(
+                                    &appstate.pool,
+                                    &user,
+                                    &client,
+                                    &auth_code.scope,
+                                    &mut user_claims,
+                                )
+                                .await?;
                                 let config = server_config();
-                                let user_claims = UserClaims::from_user(&user, &client, &token);
                                 match form.authorization_code_flow(
                                     &auth_code,
                                     &token,
@@ -887,7 +919,7 @@ pub async fn token(
                                     &config.url,
                                     client.client_secret,
                                     config.openid_key(),
-                                    group_claims,
+                                    custom_claims,
                                 ) {
                                     Ok(response) => {
                                         token.save(&appstate.pool).await?;
@@ -937,6 +969,36 @@ pub async fn token(
         "refresh_token" => {
             debug!("Starting refresh_token flow");
             if let Some(refresh_token) = form.refresh_token {
+                if let Some((authorized_app_id, reason)) =
+                    OAuth2Token::find_revoked(&appstate.pool, &refresh_token).await?
+                {
+                    // Reuse of a rotated refresh token means it might have been stolen, so revoke
+                    // tokens issued to the app in case the current one is in wrong hands too.
+                    if reason == OAuth2RevocationReason::Rotated {
+                        let revoked = OAuth2Token::revoke_for_authorized_app(
+                            &appstate.pool,
+                            authorized_app_id,
+                            OAuth2RevocationReason::Replay,
+                        )
+                        .await?;
+                        warn!(
+                            "Rotated refresh token of authorized app {authorized_app_id} has been \
+                            reused, revoked {revoked} token(s)"
+                        );
+                    } else {
+                        warn!(
+                            "Revoked refresh token of authorized app {authorized_app_id} has been \
+                            used"
+                        );
+                    }
+                    let err = CoreErrorResponseType::InvalidGrant;
+                    let response =
+                        StandardErrorResponse::<CoreErrorResponseType>::new(err, None, None);
+                    return Ok(ApiResponse {
+                        json: json!(response),
+                        status: StatusCode::BAD_REQUEST,
+                    });
+                }
                 if let Ok(Some(mut token)) =
                     OAuth2Token::find_refresh_token(&appstate.pool, &refresh_token).await
                 {
@@ -965,9 +1027,17 @@ pub async fn token(
                         });
                     }
 
-                    token.refresh_and_save(&appstate.pool).await?;
+                    if !token.refresh_and_save(&appstate.pool).await? {
+                        error!("Refresh token has already been used by a concurrent request");
+                        let err = CoreErrorResponseType::InvalidGrant;
+                        let response =
+                            StandardErrorResponse::<CoreErrorResponseType>::new(err, None, None);
+                        return Ok(ApiResponse {
+                            json: json!(response),
+                            status: StatusCode::BAD_REQUEST,
+                        });
+                    }
                     let response = TokenRequest::refresh_token_flow(&token);
-                    token.save(&appstate.pool).await?;
                     return Ok(ApiResponse {
                         json: json!(response),
                         status: StatusCode::OK,
@@ -1025,10 +1095,21 @@ pub async fn userinfo(State(appstate): State<AppState>, headers: HeaderMap) -> A
         return Err(WebError::Authorization("User not found".into()));
     };
 
-    let user_claims = UserClaims::from_user(&user, &client, &oauth2token);
+    let mut user_claims = UserClaims::from_user(&user, &client, &oauth2token);
+    let custom_claims = get_custom_claims(
+        &appstate.pool,
+        &user,
+        &client,
+        &oauth2token.scope,
+        &mut user_claims,
+    )
+    .await?;
 
     Ok(ApiResponse {
-        json: json!(StandardClaims::<CoreGenderClaim>::from(&user_claims)),
+        json: json!(UserInfoClaims::<_, CoreGenderClaim>::new(
+            (&user_claims).into(),
+            custom_claims
+        )),
         status: StatusCode::OK,
     })
 }
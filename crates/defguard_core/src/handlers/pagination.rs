@@ -5,6 +5,7 @@ use axum::{
 use reqwest::StatusCode;
 use serde::Serialize;
 
+use super::DEFAULT_API_PAGE_SIZE;
 use crate::error::WebError;
 
 /// Query params for paginated endpoints
@@ -28,6 +29,24 @@ pub struct PaginationMeta {
     pub next_page: Option<u32>,
 }
 
+/// Prepares pagination metadata that's part of the response
+pub(crate) fn get_pagination_metadata(current_page: u32, total_items: u32) -> PaginationMeta {
+    let total_pages = (total_items).div_ceil(DEFAULT_API_PAGE_SIZE);
+    let next_page = if current_page < total_pages {
+        Some(current_page + 1)
+    } else {
+        None
+    };
+
+    PaginationMeta {
+        current_page,
+        page_size: DEFAULT_API_PAGE_SIZE,
+        total_items,
+        total_pages,
+        next_page,
+    }
+}
+
 pub type PaginatedApiResult<T> = Result<PaginatedApiResponse<T>, WebError>;
 
 #[derive(Debug, Serialize)]
@@ -0,0 +1,191 @@
+//! Redaction of personal data in responses to auditors.
+//!
+//! Auditors have read-only access to activity log, statistics and configuration. Handlers of
+//! these endpoints take the [`Redaction`] extractor and apply it to the JSON they respond with.
+//! Depending on the policy configured in settings, email addresses and IP addresses found in
+//! any string of the response are replaced with [`REDACTED`]. Responses to admins and regular
+//! users aren't affected.
+
+use std::sync::LazyLock;
+
+use axum::{
+    extract::{FromRef, FromRequestParts},
+    http::request::Parts,
+};
+use defguard_common::db::models::Settings;
+use ipnetwork::IpNetwork;
+use regex::{Captures, Regex};
+use serde_json::Value;
+
+use crate::{appstate::AppState, auth::SessionInfo, error::WebError};
+
+pub(crate) const REDACTED: &str = "[redacted]";
+
+static EMAIL_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}")
+        .expect("Failed to parse email regex")
+});
+// Matches candidates only, which are redacted if they parse as an address or a network.
+static IP_ADDRESS_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?:[0-9A-Fa-f]{0,4}:){2,7}[0-9A-Fa-f]{0,4}(?:/\d{1,3})?|\d{1,3}(?:\.\d{1,3}){3}(?:/\d{1,2})?",
+    )
+    .expect("Failed to parse IP address regex")
+});
+
+fn is_word_char(char: char) -> bool {
+    char.is_alphanumeric() || char == '_'
+}
+
+/// Personal data redacted in responses to auditors.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct RedactionPolicy {
+    pub emails: bool,
+    pub ip_addresses: bool,
+}
+
+impl From<&Settings> for RedactionPolicy {
+    fn from(settings: &Settings) -> Self {
+        Self {
+            emails: settings.auditor_redact_emails,
+            ip_addresses: settings.auditor_redact_ip_addresses,
+        }
+    }
+}
+
+impl RedactionPolicy {
+    fn redact_text(self, text: &str) -> String {
+        let mut text = text.to_string();
+        if self.emails {
+            text = EMAIL_REGEX.replace_all(&text, REDACTED).into_owned();
+        }
+        if self.ip_addresses {
+            text = IP_ADDRESS_REGEX
+                .replace_all(&text, |caps: &Captures| {
+                    let candidate = caps.get_match();
+                    // skip parts of longer words, e.g. `crate::foo`
+                    let in_word = text[..candidate.start()]
+                        .chars()
+                        .next_back()
+                        .is_some_and(is_word_char)
+                        || text[candidate.end()..]
+                            .chars()
+                            .next()
+                            .is_some_and(is_word_char);
+                    if !in_word && candidate.as_str().parse::<IpNetwork>().is_ok() {
+                        REDACTED.to_string()
+                    } else {
+                        candidate.as_str().to_string()
+                    }
+                })
+                .into_owned();
+        }
+        text
+    }
+
+    /// Redacts all strings in a JSON value; object keys are kept.
+    fn redact(self, value: &mut Value) {
+        match value {
+            Value::String(text) => *text = self.redact_text(text),
+            Value::Array(values) => values.iter_mut().for_each(|value| self.redact(value)),
+            Value::Object(map) => map.values_mut().for_each(|value| self.redact(value)),
+            Value::Null | Value::Bool(_) | Value::Number(_) => {}
+        }
+    }
+}
+
+/// Redaction of responses to the user making a request. There is none unless the user is an
+/// auditor without admin privileges.
+pub(crate) struct Redaction(Option<RedactionPolicy>);
+
+impl<S> FromRequestParts<S> for Redaction
+where
+    S: Send + Sync,
+    AppState: FromRef<S>,
+{
+    type Rejection = WebError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let session_info = if let Some(cached) = parts.extensions.get::<SessionInfo>() {
+            cached.clone()
+        } else {
+            SessionInfo::from_request_parts(parts, state).await?
+        };
+        if session_info.is_admin {
+            return Ok(Self(None));
+        }
+        let appstate = AppState::from_ref(state);
+        if !session_info.user.is_auditor(&appstate.pool).await? {
+            return Ok(Self(None));
+        }
+        debug!(
+            "Redacting response to auditor {}",
+            session_info.user.username
+        );
+        Ok(Self(Some(RedactionPolicy::from(
+            &Settings::get_current_settings(),
+        ))))
+    }
+}
+
+impl Redaction {
+    pub(crate) fn apply(&self, value: &mut Value) {
+        if let Some(policy) = self.0 {
+            policy.redact(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_redaction() {
+        let response = json!({
+            "user": {"username": "hpotter", "email": "h.potter@hogwart.edu.uk"},
+            "ip": "10.1.1.10",
+            "networks": [{"address": ["10.0.0.1/24", "fd00::1/64"], "port": 51820}],
+            "description": "User hpotter logged in from 203.0.113.7 (hpotter@hogwart.edu.uk)",
+            "endpoint": "[2001:db8::7]:51820",
+            "timestamp": "2026-01-23T12:30:45",
+            "mac": "aa:bb:cc:dd:ee:ff",
+            "module": "defguard_core::handlers",
+        });
+
+        let mut value = response.clone();
+        Redaction(None).apply(&mut value);
+        assert_eq!(value, response);
+
+        let mut value = response.clone();
+        Redaction(Some(RedactionPolicy {
+            emails: true,
+            ip_addresses: true,
+        }))
+        .apply(&mut value);
+        assert_eq!(
+            value,
+            json!({
+                "user": {"username": "hpotter", "email": REDACTED},
+                "ip": REDACTED,
+                "networks": [{"address": [REDACTED, REDACTED], "port": 51820}],
+                "description": "User hpotter logged in from [redacted] ([redacted])",
+                "endpoint": "[[redacted]]:51820",
+                "timestamp": "2026-01-23T12:30:45",
+                "mac": "aa:bb:cc:dd:ee:ff",
+                "module": "defguard_core::handlers",
+            })
+        );
+
+        let mut value = response;
+        Redaction(Some(RedactionPolicy {
+            emails: true,
+            ip_addresses: false,
+        }))
+        .apply(&mut value);
+        assert_eq!(value["user"]["email"], REDACTED);
+        assert_eq!(value["ip"], "10.1.1.10");
+    }
+}
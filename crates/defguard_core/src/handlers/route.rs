@@ -0,0 +1,359 @@
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+};
+use defguard_common::db::Id;
+use ipnetwork::IpNetwork;
+use serde_json::json;
+use sqlx::PgPool;
+use utoipa::ToSchema;
+
+use super::{ApiResponse, ApiResult};
+use crate::{
+    appstate::AppState,
+    auth::{AdminRole, SessionInfo},
+    db::{Group, WireguardNetwork, models::route::Route},
+    error::WebError,
+    events::{ApiEvent, ApiEventType, ApiRequestContext},
+};
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct RouteData {
+    pub name: String,
+    pub description: Option<String>,
+    #[schema(value_type = Vec<String>)]
+    pub addresses: Vec<IpNetwork>,
+    /// Locations which route these addresses
+    #[serde(default)]
+    pub locations: Vec<Id>,
+    /// Apply only to devices of members of these groups; applies to all devices if empty
+    #[serde(default)]
+    pub groups: Vec<Id>,
+}
+
+impl RouteData {
+    /// Check route data; `id` is the ID of a route being modified.
+    async fn validate(&self, pool: &PgPool, id: Option<Id>) -> Result<(), WebError> {
+        if self.name.trim().is_empty() {
+            return Err(WebError::BadRequest("Route name can't be empty".into()));
+        }
+        if let Some(route) = Route::find_by_name(pool, &self.name).await? {
+            if Some(route.id) != id {
+                return Err(WebError::ObjectAlreadyExists(format!(
+                    "Route {} already exists",
+                    self.name
+                )));
+            }
+        }
+        if self.addresses.is_empty() {
+            return Err(WebError::BadRequest(
+                "Route must contain at least one address".into(),
+            ));
+        }
+        for id in &self.locations {
+            if WireguardNetwork::find_by_id(pool, *id).await?.is_none() {
+                return Err(WebError::BadRequest(format!("Location {id} not found")));
+            }
+        }
+        for id in &self.groups {
+            if Group::find_by_id(pool, *id).await?.is_none() {
+                return Err(WebError::BadRequest(format!("Group {id} not found")));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Route with locations and groups it's attached to.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct RouteInfo {
+    pub id: Id,
+    pub name: String,
+    pub description: Option<String>,
+    #[schema(value_type = Vec<String>)]
+    pub addresses: Vec<IpNetwork>,
+    pub locations: Vec<Id>,
+    pub groups: Vec<Id>,
+}
+
+impl RouteInfo {
+    async fn from_route(pool: &PgPool, route: Route<Id>) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            locations: route.location_ids(pool).await?,
+            groups: route.group_ids(pool).await?,
+            id: route.id,
+            name: route.name,
+            description: route.description,
+            addresses: route.addresses,
+        })
+    }
+}
+
+/// List all routes
+///
+/// # Returns
+/// - `Vec<RouteInfo>` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/route",
+    tag = "route",
+    responses(
+        (status = 200, description = "List of routes", body = Vec<RouteInfo>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn list_routes(_admin: AdminRole, State(appstate): State<AppState>) -> ApiResult {
+    let mut routes = Vec::new();
+    for route in Route::all(&appstate.pool).await? {
+        routes.push(RouteInfo::from_route(&appstate.pool, route).await?);
+    }
+
+    Ok(ApiResponse {
+        json: json!(routes),
+        status: StatusCode::OK,
+    })
+}
+
+/// Get route
+///
+/// # Returns
+/// - `RouteInfo` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/route/{id}",
+    tag = "route",
+    params(
+        ("id" = Id, Path, description = "Route ID")
+    ),
+    responses(
+        (status = 200, description = "Route", body = RouteInfo),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - route does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn get_route(
+    _admin: AdminRole,
+    Path(id): Path<Id>,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let route = Route::find_by_id(&appstate.pool, id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Route {id} not found")))?;
+
+    Ok(ApiResponse {
+        json: json!(RouteInfo::from_route(&appstate.pool, route).await?),
+        status: StatusCode::OK,
+    })
+}
+
+/// Create a route
+///
+/// Addresses are added to AllowedIPs of device configs in given locations.
+///
+/// # Returns
+/// - `RouteInfo` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    post,
+    path = "/api/v1/route",
+    tag = "route",
+    request_body = RouteData,
+    responses(
+        (status = 201, description = "Route created", body = RouteInfo),
+        (status = 400, description = "Bad request - invalid route data"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 409, description = "Conflict - route with this name already exists"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn create_route(
+    _admin: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    State(appstate): State<AppState>,
+    Json(data): Json<RouteData>,
+) -> ApiResult {
+    debug!(
+        "User {} creating route {}",
+        session.user.username, data.name
+    );
+    data.validate(&appstate.pool, None).await?;
+
+    let mut transaction = appstate.pool.begin().await?;
+    let route = Route::new(data.name, data.description, data.addresses)
+        .save(&mut *transaction)
+        .await?;
+    route
+        .set_locations(&mut transaction, &data.locations)
+        .await?;
+    route.set_groups(&mut transaction, &data.groups).await?;
+    transaction.commit().await?;
+    info!(
+        "User {} created route {}({})",
+        session.user.username, route.name, route.id
+    );
+
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::RouteAdded {
+            route: route.clone(),
+        }),
+    })?;
+
+    Ok(ApiResponse {
+        json: json!(RouteInfo::from_route(&appstate.pool, route).await?),
+        status: StatusCode::CREATED,
+    })
+}
+
+/// Modify a route
+///
+/// # Returns
+/// - `RouteInfo` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    put,
+    path = "/api/v1/route/{id}",
+    tag = "route",
+    params(
+        ("id" = Id, Path, description = "Route ID")
+    ),
+    request_body = RouteData,
+    responses(
+        (status = 200, description = "Route modified", body = RouteInfo),
+        (status = 400, description = "Bad request - invalid route data"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - route does not exist"),
+        (status = 409, description = "Conflict - route with this name already exists"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn modify_route(
+    _admin: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    Path(id): Path<Id>,
+    State(appstate): State<AppState>,
+    Json(data): Json<RouteData>,
+) -> ApiResult {
+    let mut route = Route::find_by_id(&appstate.pool, id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Route {id} not found")))?;
+    debug!(
+        "User {} modifying route {}({id})",
+        session.user.username, route.name
+    );
+    data.validate(&appstate.pool, Some(id)).await?;
+
+    let before = route.clone();
+    route.name = data.name;
+    route.description = data.description;
+    route.addresses = data.addresses;
+    let mut transaction = appstate.pool.begin().await?;
+    route.save(&mut *transaction).await?;
+    route
+        .set_locations(&mut transaction, &data.locations)
+        .await?;
+    route.set_groups(&mut transaction, &data.groups).await?;
+    transaction.commit().await?;
+    info!(
+        "User {} modified route {}({id})",
+        session.user.username, route.name
+    );
+
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::RouteModified {
+            before,
+            after: route.clone(),
+        }),
+    })?;
+
+    Ok(ApiResponse {
+        json: json!(RouteInfo::from_route(&appstate.pool, route).await?),
+        status: StatusCode::OK,
+    })
+}
+
+/// Remove a route
+///
+/// # Returns
+/// - empty JSON
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    delete,
+    path = "/api/v1/route/{id}",
+    tag = "route",
+    params(
+        ("id" = Id, Path, description = "Route ID")
+    ),
+    responses(
+        (status = 200, description = "Route removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - route does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn delete_route(
+    _admin: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    Path(id): Path<Id>,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let route = Route::find_by_id(&appstate.pool, id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Route {id} not found")))?;
+    debug!(
+        "User {} removing route {}({id})",
+        session.user.username, route.name
+    );
+
+    route.clone().delete(&appstate.pool).await?;
+    info!(
+        "User {} removed route {}({id})",
+        session.user.username, route.name
+    );
+
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::RouteRemoved { route }),
+    })?;
+
+    Ok(ApiResponse::default())
+}
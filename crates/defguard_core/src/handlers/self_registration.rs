@@ -0,0 +1,295 @@
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+};
+use axum_client_ip::InsecureClientIp;
+use axum_extra::{TypedHeader, headers::UserAgent};
+use defguard_common::db::models::Settings;
+use serde_json::json;
+use utoipa::ToSchema;
+
+use super::{
+    ApiResponse, ApiResult,
+    mail::{
+        send_self_registration_admin_notification, send_self_registration_enrollment_email,
+        send_self_registration_verification_email,
+    },
+    user::check_username,
+};
+use crate::{
+    appstate::AppState,
+    db::{
+        AppEvent, User, UserInfo,
+        models::{
+            enrollment::{ENROLLMENT_TOKEN_TYPE, Token},
+            self_registration::SelfRegistrationRequest,
+        },
+    },
+    enterprise::limits::update_counts,
+    error::WebError,
+    events::{ApiEvent, ApiEventType, ApiRequestContext},
+    is_valid_phone_number, server_config,
+};
+
+#[derive(Deserialize, ToSchema)]
+pub struct SelfRegistrationData {
+    pub username: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: String,
+    pub phone: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SelfRegistrationVerification {
+    pub token: String,
+}
+
+/// Checks if email belongs to one of allowed domains or their subdomains.
+fn is_email_domain_allowed(email: &str, domains: &[String]) -> bool {
+    let Some((_, email_domain)) = email.rsplit_once('@') else {
+        return false;
+    };
+    let email_domain = email_domain.to_lowercase();
+    domains.iter().any(|domain| {
+        let domain = domain.trim().trim_start_matches('@').to_lowercase();
+        !domain.is_empty()
+            && (email_domain == domain || email_domain.ends_with(&format!(".{domain}")))
+    })
+}
+
+fn ensure_self_registration_enabled(settings: &Settings) -> Result<(), WebError> {
+    if settings.self_registration_enabled {
+        Ok(())
+    } else {
+        Err(WebError::Forbidden("Self-registration is disabled".into()))
+    }
+}
+
+/// Request a user account
+///
+/// Public endpoint available when self-registration is enabled. Accepts emails from configured
+/// domains only and sends a verification token to given email address.
+///
+/// # Returns
+/// - empty JSON object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    post,
+    path = "/api/v1/self_registration",
+    tag = "user",
+    request_body = SelfRegistrationData,
+    responses(
+        (status = 201, description = "Verification email has been sent"),
+        (status = 400, description = "Bad request - invalid user data or username already taken"),
+        (status = 403, description = "Forbidden - self-registration disabled or email domain not allowed"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn request_self_registration(
+    State(appstate): State<AppState>,
+    Json(data): Json<SelfRegistrationData>,
+) -> ApiResult {
+    debug!(
+        "Processing self-registration request for user {} with email {}",
+        data.username, data.email
+    );
+    let settings = Settings::get_current_settings();
+    ensure_self_registration_enabled(&settings)?;
+    if !is_email_domain_allowed(&data.email, &settings.self_registration_domains) {
+        warn!(
+            "Rejected self-registration request for email {} from a domain which is not allowed",
+            data.email
+        );
+        return Err(WebError::Forbidden("Email domain is not allowed".into()));
+    }
+    check_username(&data.username)?;
+    if let Some(ref phone) = data.phone {
+        if !is_valid_phone_number(phone) {
+            return Err(WebError::BadRequest("Invalid phone number".into()));
+        }
+    }
+    if User::find_by_username(&appstate.pool, &data.username)
+        .await?
+        .is_some()
+    {
+        return Err(WebError::BadRequest(format!(
+            "Username {} is already taken",
+            data.username
+        )));
+    }
+    // Don't reveal existing accounts, just skip sending the verification email.
+    if User::find_by_email(&appstate.pool, &data.email)
+        .await?
+        .is_some()
+    {
+        warn!(
+            "Ignoring self-registration request for already registered email {}",
+            data.email
+        );
+        return Ok(ApiResponse {
+            json: json!({}),
+            status: StatusCode::CREATED,
+        });
+    }
+
+    let mut transaction = appstate.pool.begin().await?;
+    SelfRegistrationRequest::delete_stale(&mut *transaction, &data.username, &data.email).await?;
+    let request = SelfRegistrationRequest::new(
+        data.username,
+        data.first_name,
+        data.last_name,
+        data.email,
+        data.phone,
+        server_config().enrollment_token_timeout.as_secs(),
+    )
+    .save(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    send_self_registration_verification_email(&request, &appstate.mail_tx)?;
+    info!(
+        "Sent self-registration verification token for user {} to {}",
+        request.username, request.email
+    );
+
+    Ok(ApiResponse {
+        json: json!({}),
+        status: StatusCode::CREATED,
+    })
+}
+
+/// Verify email of a requested user account
+///
+/// Creates a disabled user account with an enrollment token sent to the verified email address.
+/// Admin users are notified and have to activate the account before enrollment can proceed.
+///
+/// # Returns
+/// - JSON with `username` of created user
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    post,
+    path = "/api/v1/self_registration/verify",
+    tag = "user",
+    request_body = SelfRegistrationVerification,
+    responses(
+        (status = 201, description = "User account created and awaiting activation", body = ApiResponse, example = json!({"username": "hpotter"})),
+        (status = 400, description = "Bad request - invalid or expired token"),
+        (status = 403, description = "Forbidden - self-registration disabled"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn verify_self_registration(
+    user_agent: TypedHeader<UserAgent>,
+    InsecureClientIp(insecure_ip): InsecureClientIp,
+    State(appstate): State<AppState>,
+    Json(data): Json<SelfRegistrationVerification>,
+) -> ApiResult {
+    let settings = Settings::get_current_settings();
+    ensure_self_registration_enabled(&settings)?;
+
+    let mut transaction = appstate.pool.begin().await?;
+    let Some(request) =
+        SelfRegistrationRequest::find_by_token(&mut *transaction, &data.token).await?
+    else {
+        return Err(WebError::BadRequest("Invalid verification token".into()));
+    };
+    if request.is_expired() {
+        request.delete(&mut *transaction).await?;
+        transaction.commit().await?;
+        return Err(WebError::BadRequest("Verification token expired".into()));
+    }
+    // Account might have been created in the meantime.
+    if User::find_by_username(&mut *transaction, &request.username)
+        .await?
+        .is_some()
+        || User::find_by_email(&mut *transaction, &request.email)
+            .await?
+            .is_some()
+    {
+        let username = request.username.clone();
+        request.delete(&mut *transaction).await?;
+        transaction.commit().await?;
+        return Err(WebError::BadRequest(format!(
+            "User {username} already exists"
+        )));
+    }
+
+    let mut user = User::new(
+        request.username.clone(),
+        None,
+        request.last_name.clone(),
+        request.first_name.clone(),
+        request.email.clone(),
+        request.phone.clone(),
+    );
+    user.is_active = false;
+    user.enrollment_pending = true;
+    let user = user.save(&mut *transaction).await?;
+    request.delete(&mut *transaction).await?;
+    let token = Token::new(
+        user.id,
+        None,
+        Some(user.email.clone()),
+        server_config().enrollment_token_timeout.as_secs(),
+        Some(ENROLLMENT_TOKEN_TYPE.to_string()),
+    );
+    token.save(&mut *transaction).await?;
+    update_counts(&mut *transaction).await?;
+    transaction.commit().await?;
+    info!(
+        "User {} verified email {} through self-registration and awaits activation",
+        user.username, user.email
+    );
+
+    if let Err(err) = send_self_registration_enrollment_email(&user, &token.id, &appstate.mail_tx) {
+        error!(
+            "Failed to send enrollment token to self-registered user {}: {err}",
+            user.username
+        );
+    }
+    if let Err(err) =
+        send_self_registration_admin_notification(&user, &appstate.mail_tx, &appstate.pool).await
+    {
+        error!(
+            "Failed to notify admins about self-registered user {}: {err}",
+            user.username
+        );
+    }
+
+    let user_info = UserInfo::from_user(&appstate.pool, &user).await?;
+    appstate.trigger_action(AppEvent::UserCreated(user_info));
+    appstate.emit_event(ApiEvent {
+        context: ApiRequestContext::new(
+            user.id,
+            user.username.clone(),
+            insecure_ip,
+            user_agent.to_string(),
+        ),
+        event: Box::new(ApiEventType::UserSelfRegistered { user: user.clone() }),
+    })?;
+
+    Ok(ApiResponse {
+        json: json!({"username": user.username}),
+        status: StatusCode::CREATED,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_email_domain_allowed() {
+        let domains = vec!["example.com".to_string(), "@Corp.org".to_string()];
+        assert!(is_email_domain_allowed("user@example.com", &domains));
+        assert!(is_email_domain_allowed("user@EXAMPLE.com", &domains));
+        assert!(is_email_domain_allowed("user@eu.example.com", &domains));
+        assert!(is_email_domain_allowed("user@corp.org", &domains));
+        assert!(!is_email_domain_allowed("user@badexample.com", &domains));
+        assert!(!is_email_domain_allowed("user@example.com.evil", &domains));
+        assert!(!is_email_domain_allowed("example.com", &domains));
+        assert!(!is_email_domain_allowed("user@example.com", &[]));
+    }
+}
@@ -0,0 +1,432 @@
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+};
+use defguard_common::db::Id;
+use serde_json::json;
+use utoipa::ToSchema;
+
+use super::{ApiResponse, ApiResult, user::check_username};
+use crate::{
+    appstate::AppState,
+    auth::{AdminRole, SessionInfo},
+    db::{
+        Device, User,
+        models::{
+            device::DeviceType,
+            service_account::{ServiceAccount, ServiceAccountInfo},
+        },
+    },
+    enterprise::limits::update_counts,
+    error::WebError,
+    events::{ApiEvent, ApiEventType, ApiRequestContext},
+};
+
+/// Domain of placeholder e-mail addresses of users backing service accounts.
+const SERVICE_ACCOUNT_EMAIL_DOMAIN: &str = "service-accounts.invalid";
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct NewServiceAccount {
+    /// Becomes the username of the backing user
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct EditServiceAccount {
+    pub description: Option<String>,
+    /// Disabled service accounts can't authenticate with their API tokens
+    pub is_active: bool,
+}
+
+async fn find_service_account(
+    appstate: &AppState,
+    id: Id,
+) -> Result<(ServiceAccount<Id>, ServiceAccountInfo), WebError> {
+    let info = ServiceAccountInfo::find_by_id(&appstate.pool, id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Service account {id} not found")))?;
+    let service_account = ServiceAccount::find_by_id(&appstate.pool, id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Service account {id} not found")))?;
+    Ok((service_account, info))
+}
+
+/// List all service accounts
+///
+/// # Returns
+/// - `Vec<ServiceAccountInfo>` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/service_account",
+    tag = "service_account",
+    responses(
+        (status = 200, description = "List of service accounts", body = Vec<ServiceAccountInfo>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn list_service_accounts(
+    _admin: AdminRole,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let service_accounts = ServiceAccountInfo::all(&appstate.pool).await?;
+
+    Ok(ApiResponse {
+        json: json!(service_accounts),
+        status: StatusCode::OK,
+    })
+}
+
+/// Get service account
+///
+/// # Returns
+/// - `ServiceAccountInfo` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/service_account/{id}",
+    tag = "service_account",
+    params(
+        ("id" = Id, Path, description = "Service account ID")
+    ),
+    responses(
+        (status = 200, description = "Service account", body = ServiceAccountInfo),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - service account does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn get_service_account(
+    _admin: AdminRole,
+    Path(id): Path<Id>,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let (_, info) = find_service_account(&appstate, id).await?;
+
+    Ok(ApiResponse {
+        json: json!(info),
+        status: StatusCode::OK,
+    })
+}
+
+/// Create a service account
+///
+/// Service account is backed by a user without password, which can own network devices and
+/// API tokens. To use API tokens, the service account has to be a member of an admin group.
+///
+/// # Returns
+/// - `ServiceAccountInfo` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    post,
+    path = "/api/v1/service_account",
+    tag = "service_account",
+    request_body = NewServiceAccount,
+    responses(
+        (status = 201, description = "Service account created", body = ServiceAccountInfo),
+        (status = 400, description = "Bad request - invalid name or name already taken"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn create_service_account(
+    _admin: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    State(appstate): State<AppState>,
+    Json(data): Json<NewServiceAccount>,
+) -> ApiResult {
+    let name = data.name;
+    debug!(
+        "User {} creating service account {name}",
+        session.user.username
+    );
+    if let Err(err) = check_username(&name) {
+        debug!("Service account name {name} rejected: {err}");
+        return Err(WebError::BadRequest(format!(
+            "Invalid service account name {name}"
+        )));
+    }
+    let email = format!("{name}@{SERVICE_ACCOUNT_EMAIL_DOMAIN}");
+    if User::find_by_username(&appstate.pool, &name)
+        .await?
+        .is_some()
+        || User::find_by_email(&appstate.pool, &email).await?.is_some()
+    {
+        return Err(WebError::BadRequest(format!(
+            "User or service account {name} already exists"
+        )));
+    }
+
+    let mut transaction = appstate.pool.begin().await?;
+    let user = User::new(name.clone(), None, String::new(), name.clone(), email, None)
+        .save(&mut *transaction)
+        .await?;
+    let service_account = ServiceAccount::new(user.id, data.description, session.user.id)
+        .save(&mut *transaction)
+        .await?;
+    let info = ServiceAccountInfo::find_by_id(&mut *transaction, service_account.id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Service account {name} not found")))?;
+    transaction.commit().await?;
+    info!(
+        "User {} created service account {name}",
+        session.user.username
+    );
+
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::ServiceAccountAdded {
+            service_account: info.clone(),
+        }),
+    })?;
+
+    Ok(ApiResponse {
+        json: json!(info),
+        status: StatusCode::CREATED,
+    })
+}
+
+/// Modify a service account
+///
+/// # Returns
+/// - `ServiceAccountInfo` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    put,
+    path = "/api/v1/service_account/{id}",
+    tag = "service_account",
+    params(
+        ("id" = Id, Path, description = "Service account ID")
+    ),
+    request_body = EditServiceAccount,
+    responses(
+        (status = 200, description = "Service account modified", body = ServiceAccountInfo),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - service account does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn modify_service_account(
+    _admin: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    Path(id): Path<Id>,
+    State(appstate): State<AppState>,
+    Json(data): Json<EditServiceAccount>,
+) -> ApiResult {
+    let (mut service_account, before) = find_service_account(&appstate, id).await?;
+    debug!(
+        "User {} modifying service account {}",
+        session.user.username, before.name
+    );
+
+    let mut transaction = appstate.pool.begin().await?;
+    service_account.description = data.description;
+    service_account.save(&mut *transaction).await?;
+    if data.is_active != before.is_active {
+        let mut user = User::find_by_id(&mut *transaction, service_account.user_id)
+            .await?
+            .ok_or_else(|| WebError::ObjectNotFound(format!("Service account {id} not found")))?;
+        user.is_active = data.is_active;
+        user.save(&mut *transaction).await?;
+    }
+    let after = ServiceAccountInfo::find_by_id(&mut *transaction, id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Service account {id} not found")))?;
+    transaction.commit().await?;
+    info!(
+        "User {} modified service account {}",
+        session.user.username, after.name
+    );
+
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::ServiceAccountModified {
+            before,
+            after: after.clone(),
+        }),
+    })?;
+
+    Ok(ApiResponse {
+        json: json!(after),
+        status: StatusCode::OK,
+    })
+}
+
+/// Remove a service account
+///
+/// Removes the backing user along with its API tokens. Network devices owned by the service
+/// account have to be removed or transferred to another service account first.
+///
+/// # Returns
+/// - empty JSON
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    delete,
+    path = "/api/v1/service_account/{id}",
+    tag = "service_account",
+    params(
+        ("id" = Id, Path, description = "Service account ID")
+    ),
+    responses(
+        (status = 200, description = "Service account removed"),
+        (status = 400, description = "Bad request - service account owns network devices"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - service account does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn delete_service_account(
+    _admin: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    Path(id): Path<Id>,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let (service_account, info) = find_service_account(&appstate, id).await?;
+    debug!(
+        "User {} removing service account {}",
+        session.user.username, info.name
+    );
+    if service_account.network_device_count(&appstate.pool).await? > 0 {
+        return Err(WebError::BadRequest(format!(
+            "Service account {} owns network devices, remove or transfer them first",
+            info.name
+        )));
+    }
+
+    let mut transaction = appstate.pool.begin().await?;
+    if let Some(user) = User::find_by_id(&mut *transaction, service_account.user_id).await? {
+        user.delete_and_cleanup(&mut transaction, &appstate.wireguard_tx)
+            .await?;
+    }
+    transaction.commit().await?;
+    update_counts(&appstate.pool).await?;
+    info!(
+        "User {} removed service account {}",
+        session.user.username, info.name
+    );
+
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::ServiceAccountRemoved {
+            service_account: info,
+        }),
+    })?;
+
+    Ok(ApiResponse::default())
+}
+
+/// Transfer a network device to a service account
+///
+/// # Returns
+/// - `ServiceAccountInfo` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    put,
+    path = "/api/v1/service_account/{id}/network_device/{device_id}",
+    tag = "service_account",
+    params(
+        ("id" = Id, Path, description = "Service account ID"),
+        ("device_id" = Id, Path, description = "Network device ID")
+    ),
+    responses(
+        (status = 200, description = "Network device transferred", body = ServiceAccountInfo),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - service account or network device does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn assign_network_device(
+    _admin: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    Path((id, device_id)): Path<(Id, Id)>,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let (service_account, info) = find_service_account(&appstate, id).await?;
+    let mut transaction = appstate.pool.begin().await?;
+    let mut device = Device::find_by_id(&mut *transaction, device_id)
+        .await?
+        .filter(|device| device.device_type == DeviceType::Network)
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Network device {device_id} not found")))?;
+    let location = device
+        .find_network_device_networks(&mut *transaction)
+        .await?
+        .pop()
+        .ok_or_else(|| {
+            WebError::ObjectNotFound(format!(
+                "Network device {device_id} not found in any network"
+            ))
+        })?;
+    debug!(
+        "User {} transferring network device {} to service account {}",
+        session.user.username, device.name, info.name
+    );
+
+    let before = device.clone();
+    device.user_id = service_account.user_id;
+    device.save(&mut *transaction).await?;
+    let info = ServiceAccountInfo::find_by_id(&mut *transaction, id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Service account {id} not found")))?;
+    transaction.commit().await?;
+    info!(
+        "User {} transferred network device {} to service account {}",
+        session.user.username, device.name, info.name
+    );
+
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::NetworkDeviceModified {
+            location,
+            before,
+            after: device,
+        }),
+    })?;
+
+    Ok(ApiResponse {
+        json: json!(info),
+        status: StatusCode::OK,
+    })
+}
@@ -0,0 +1,434 @@
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+};
+use chrono::{NaiveDateTime, Utc};
+use defguard_common::{
+    db::{
+        Id, NoId,
+        models::{
+            MailCategory, SmtpProfile,
+            settings::{SmtpEncryption, is_valid_email_address},
+            smtp_profile::{
+                clear_failover_smtp_profile, get_failover_smtp_profile, initialize_smtp_profiles,
+                smtp_profile_categories,
+            },
+        },
+    },
+    secret::SecretStringWrapper,
+};
+use defguard_mail::failover::{RelayHealth, smtp_health};
+use serde_json::json;
+use utoipa::ToSchema;
+
+use super::{ApiResponse, ApiResult};
+use crate::{
+    appstate::AppState,
+    auth::{AdminRole, SessionInfo},
+    error::WebError,
+};
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct SmtpProfileData {
+    pub name: String,
+    pub server: String,
+    pub port: i32,
+    #[schema(value_type = String)]
+    pub encryption: SmtpEncryption,
+    pub username: Option<String>,
+    /// Current password is kept if omitted when modifying a profile
+    #[schema(value_type = Option<String>)]
+    pub password: Option<SecretStringWrapper>,
+    pub sender: String,
+    pub sender_name: Option<String>,
+    pub reply_to: Option<String>,
+    /// Mail categories sent through this profile; categories bound to other profiles are moved
+    #[serde(default)]
+    pub categories: Vec<MailCategory>,
+    /// Use this profile in place of the SMTP server from settings while it keeps failing;
+    /// only one profile can be used for failover
+    #[serde(default)]
+    pub failover: bool,
+}
+
+/// SMTP profile without its password.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct SmtpProfileInfo {
+    pub id: Id,
+    pub name: String,
+    pub server: String,
+    pub port: i32,
+    #[schema(value_type = String)]
+    pub encryption: SmtpEncryption,
+    pub username: Option<String>,
+    pub has_password: bool,
+    pub sender: String,
+    pub sender_name: Option<String>,
+    pub reply_to: Option<String>,
+    pub categories: Vec<MailCategory>,
+    pub failover: bool,
+}
+
+impl SmtpProfileInfo {
+    fn new(profile: SmtpProfile<Id>, categories: Vec<MailCategory>) -> Self {
+        Self {
+            id: profile.id,
+            name: profile.name,
+            server: profile.server,
+            port: profile.port,
+            encryption: profile.encryption,
+            username: profile.username,
+            has_password: profile.password.is_some(),
+            sender: profile.sender,
+            sender_name: profile.sender_name,
+            reply_to: profile.reply_to,
+            categories,
+            failover: profile.failover,
+        }
+    }
+}
+
+/// Health of the SMTP server configured in settings and the failover SMTP profile.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SmtpHealthInfo {
+    #[schema(value_type = Object)]
+    pub primary: RelayHealth,
+    /// Name of the failover SMTP profile, if there is one
+    pub failover_profile: Option<String>,
+    #[schema(value_type = Option<Object>)]
+    pub secondary: Option<RelayHealth>,
+    /// Whether mails are currently sent through the failover SMTP profile
+    pub failover_active: bool,
+    pub failover_until: Option<NaiveDateTime>,
+}
+
+/// Make sure profile data is valid and its name is not used by another profile.
+async fn validate(
+    appstate: &AppState,
+    data: &SmtpProfileData,
+    id: Option<Id>,
+) -> Result<(), WebError> {
+    if data.name.trim().is_empty() {
+        return Err(WebError::BadRequest(
+            "SMTP profile name can't be empty".into(),
+        ));
+    }
+    if data.server.trim().is_empty() {
+        return Err(WebError::BadRequest("SMTP server can't be empty".into()));
+    }
+    if u16::try_from(data.port).is_err() || data.port == 0 {
+        return Err(WebError::BadRequest(format!(
+            "Invalid SMTP port {}",
+            data.port
+        )));
+    }
+    for address in [Some(&data.sender), data.reply_to.as_ref()]
+        .into_iter()
+        .flatten()
+    {
+        if !is_valid_email_address(address) {
+            return Err(WebError::BadRequest(format!(
+                "Invalid email address {address}"
+            )));
+        }
+    }
+    if let Some(existing) = SmtpProfile::find_by_name(&appstate.pool, &data.name).await? {
+        if Some(existing.id) != id {
+            return Err(WebError::BadRequest(format!(
+                "SMTP profile {} already exists",
+                data.name
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+fn non_empty(password: Option<SecretStringWrapper>) -> Option<SecretStringWrapper> {
+    password.filter(|password| !password.expose_secret().is_empty())
+}
+
+async fn find_profile(appstate: &AppState, id: Id) -> Result<SmtpProfile<Id>, WebError> {
+    SmtpProfile::find_by_id(&appstate.pool, id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("SMTP profile {id} not found")))
+}
+
+/// List SMTP profiles
+///
+/// # Returns
+/// - `Vec<SmtpProfileInfo>` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/settings/smtp_profile",
+    tag = "smtp_profile",
+    responses(
+        (status = 200, description = "List of SMTP profiles", body = Vec<SmtpProfileInfo>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn list_smtp_profiles(_admin: AdminRole, State(appstate): State<AppState>) -> ApiResult {
+    let profiles = SmtpProfile::all(&appstate.pool).await?;
+    let bindings = smtp_profile_categories(&appstate.pool).await?;
+    let profiles: Vec<SmtpProfileInfo> = profiles
+        .into_iter()
+        .map(|profile| {
+            let categories = bindings
+                .iter()
+                .filter(|(_, profile_id)| *profile_id == profile.id)
+                .map(|(category, _)| *category)
+                .collect();
+            SmtpProfileInfo::new(profile, categories)
+        })
+        .collect();
+
+    Ok(ApiResponse {
+        json: json!(profiles),
+        status: StatusCode::OK,
+    })
+}
+
+/// Create SMTP profile
+///
+/// Mails of categories bound to the profile are sent through it instead of the SMTP server
+/// configured in settings.
+///
+/// # Returns
+/// - `SmtpProfileInfo` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    post,
+    path = "/api/v1/settings/smtp_profile",
+    tag = "smtp_profile",
+    request_body = SmtpProfileData,
+    responses(
+        (status = 201, description = "SMTP profile created", body = SmtpProfileInfo),
+        (status = 400, description = "Bad request - invalid data or duplicate profile name"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn create_smtp_profile(
+    _admin: AdminRole,
+    session: SessionInfo,
+    State(appstate): State<AppState>,
+    Json(data): Json<SmtpProfileData>,
+) -> ApiResult {
+    debug!(
+        "User {} creating SMTP profile {}",
+        session.user.username, data.name
+    );
+    validate(&appstate, &data, None).await?;
+    let mut transaction = appstate.pool.begin().await?;
+    if data.failover {
+        clear_failover_smtp_profile(&mut *transaction).await?;
+    }
+    let profile = SmtpProfile {
+        id: NoId,
+        name: data.name,
+        server: data.server,
+        port: data.port,
+        encryption: data.encryption,
+        username: data.username.filter(|username| !username.is_empty()),
+        password: non_empty(data.password),
+        sender: data.sender,
+        sender_name: data.sender_name,
+        reply_to: data.reply_to,
+        failover: data.failover,
+    }
+    .save(&mut *transaction)
+    .await?;
+    profile
+        .set_categories(&mut transaction, &data.categories)
+        .await?;
+    let categories = profile.categories(&mut *transaction).await?;
+    transaction.commit().await?;
+    initialize_smtp_profiles(&appstate.pool).await?;
+    info!(
+        "User {} created SMTP profile {}",
+        session.user.username, profile.name
+    );
+
+    Ok(ApiResponse {
+        json: json!(SmtpProfileInfo::new(profile, categories)),
+        status: StatusCode::CREATED,
+    })
+}
+
+/// Modify SMTP profile
+///
+/// # Returns
+/// - `SmtpProfileInfo` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    put,
+    path = "/api/v1/settings/smtp_profile/{id}",
+    tag = "smtp_profile",
+    params(
+        ("id" = Id, Path, description = "SMTP profile ID")
+    ),
+    request_body = SmtpProfileData,
+    responses(
+        (status = 200, description = "SMTP profile modified", body = SmtpProfileInfo),
+        (status = 400, description = "Bad request - invalid data or duplicate profile name"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - SMTP profile does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn modify_smtp_profile(
+    _admin: AdminRole,
+    session: SessionInfo,
+    Path(id): Path<Id>,
+    State(appstate): State<AppState>,
+    Json(data): Json<SmtpProfileData>,
+) -> ApiResult {
+    let mut profile = find_profile(&appstate, id).await?;
+    debug!(
+        "User {} modifying SMTP profile {}",
+        session.user.username, profile.name
+    );
+    validate(&appstate, &data, Some(id)).await?;
+    profile.name = data.name;
+    profile.server = data.server;
+    profile.port = data.port;
+    profile.encryption = data.encryption;
+    profile.username = data.username.filter(|username| !username.is_empty());
+    if data.password.is_some() {
+        profile.password = non_empty(data.password);
+    }
+    profile.sender = data.sender;
+    profile.sender_name = data.sender_name;
+    profile.reply_to = data.reply_to;
+    profile.failover = data.failover;
+
+    let mut transaction = appstate.pool.begin().await?;
+    if profile.failover {
+        clear_failover_smtp_profile(&mut *transaction).await?;
+    }
+    profile.save(&mut *transaction).await?;
+    profile
+        .set_categories(&mut transaction, &data.categories)
+        .await?;
+    let categories = profile.categories(&mut *transaction).await?;
+    transaction.commit().await?;
+    initialize_smtp_profiles(&appstate.pool).await?;
+    info!(
+        "User {} modified SMTP profile {}",
+        session.user.username, profile.name
+    );
+
+    Ok(ApiResponse {
+        json: json!(SmtpProfileInfo::new(profile, categories)),
+        status: StatusCode::OK,
+    })
+}
+
+/// Remove SMTP profile
+///
+/// Mail categories bound to the profile are sent through the SMTP server configured in settings
+/// again.
+///
+/// # Returns
+/// - empty JSON
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    delete,
+    path = "/api/v1/settings/smtp_profile/{id}",
+    tag = "smtp_profile",
+    params(
+        ("id" = Id, Path, description = "SMTP profile ID")
+    ),
+    responses(
+        (status = 200, description = "SMTP profile removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+        (status = 404, description = "Not found - SMTP profile does not exist"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn delete_smtp_profile(
+    _admin: AdminRole,
+    session: SessionInfo,
+    Path(id): Path<Id>,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let profile = find_profile(&appstate, id).await?;
+    let name = profile.name.clone();
+    profile.delete(&appstate.pool).await?;
+    initialize_smtp_profiles(&appstate.pool).await?;
+    info!("User {} removed SMTP profile {name}", session.user.username);
+
+    Ok(ApiResponse::default())
+}
+
+/// Get SMTP health
+///
+/// Reports failures of the SMTP server configured in settings and of the failover SMTP profile
+/// used in its place while it keeps failing. Health is tracked since the last restart.
+///
+/// # Returns
+/// - `SmtpHealthInfo` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/mail/health",
+    tag = "smtp_profile",
+    responses(
+        (status = 200, description = "Health of SMTP servers", body = SmtpHealthInfo),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Admin role required"),
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub async fn get_smtp_health(_admin: AdminRole) -> ApiResult {
+    let health = smtp_health();
+    let failover_profile = get_failover_smtp_profile()
+        .as_ref()
+        .map(|profile| profile.name.clone());
+    let failover_active = failover_profile.is_some()
+        && health
+            .failover_until
+            .is_some_and(|until| until > Utc::now().naive_utc());
+    let info = SmtpHealthInfo {
+        primary: health.primary,
+        secondary: failover_profile.as_ref().map(|_| health.secondary),
+        failover_profile,
+        failover_active,
+        failover_until: health.failover_until,
+    };
+
+    Ok(ApiResponse {
+        json: json!(info),
+        status: StatusCode::OK,
+    })
+}
@@ -1,21 +1,32 @@
 use axum::{extract::State, http::StatusCode};
+use defguard_common::db::pool_stats;
+use serde_json::json;
 
-use super::{ApiResponse, ApiResult};
+use super::{ApiResponse, ApiResult, redaction::Redaction};
 use crate::{
     AppState,
-    auth::{AdminRole, SessionInfo},
+    auth::{AdminRole, AuditorRole, SessionInfo},
     error::WebError,
     server_config,
-    support::dump_config,
+    support::{dump_auditor_config, dump_config},
+    wireguard_stats_maintenance::stats_maintenance_report,
 };
 
+/// Dump of app configuration; also available to auditors, without credentials and keys and
+/// with personal data redacted.
 pub async fn configuration(
-    _admin: AdminRole,
+    _role: AuditorRole,
     State(appstate): State<AppState>,
     session: SessionInfo,
+    redaction: Redaction,
 ) -> ApiResult {
     debug!("User {} dumping app configuration", session.user.username);
-    let config = dump_config(&appstate.pool).await;
+    let mut config = if session.is_admin {
+        dump_config(&appstate.pool).await
+    } else {
+        dump_auditor_config(&appstate.pool).await
+    };
+    redaction.apply(&mut config);
     info!("User {} dumped app configuration", session.user.username);
     Ok(ApiResponse {
         json: config,
@@ -23,6 +34,27 @@ pub async fn configuration(
     })
 }
 
+/// Connection usage of database pools used by separate subsystems.
+pub async fn database_pools(_admin: AdminRole, session: SessionInfo) -> ApiResult {
+    debug!(
+        "User {} retrieving database pool statistics",
+        session.user.username
+    );
+    Ok(ApiResponse::new(json!(pool_stats().await), StatusCode::OK))
+}
+
+/// Counters and results of the latest maintenance of stats tables.
+pub async fn stats_maintenance(_admin: AdminRole, session: SessionInfo) -> ApiResult {
+    debug!(
+        "User {} retrieving stats maintenance report",
+        session.user.username
+    );
+    Ok(ApiResponse::new(
+        json!(stats_maintenance_report()),
+        StatusCode::OK,
+    ))
+}
+
 pub async fn logs(_admin: AdminRole, session: SessionInfo) -> Result<String, WebError> {
     debug!("User {} dumping app logs", session.user.username);
     if let Some(ref log_file) = server_config().log_file {
@@ -0,0 +1,405 @@
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    Extension,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use chrono::{NaiveDateTime, Utc};
+use defguard_common::db::Id;
+use defguard_proto::enterprise::firewall::{
+    FirewallConfig, FirewallPolicy, IpAddress, ip_address::Address,
+};
+use ipnetwork::IpNetwork;
+use serde_json::json;
+use sqlx::{PgPool, query};
+use utoipa::ToSchema;
+
+use super::{ApiResponse, ApiResult};
+use crate::{
+    appstate::AppState,
+    auth::AdminRole,
+    db::{
+        Device, User, WireguardNetwork,
+        models::{
+            device::WireguardNetworkDevice, device_approval::DeviceApprovalStatus,
+            gateway_distribution::DeviceGatewayAssignment, wireguard::WIREGUARD_MAX_HANDSHAKE,
+        },
+    },
+    enterprise::db::models::{
+        firewall_config_version::FirewallConfigVersion, quarantine::DeviceQuarantine,
+    },
+    error::WebError,
+    grpc::gateway::map::GatewayMap,
+};
+
+/// Probable cause of a device not being able to connect.
+///
+/// Variants are ordered from the most to the least likely cause, i.e. causes which prevent
+/// a device from connecting at all come before the ones which may only limit its traffic.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TroubleshootCause {
+    /// Owner of the device is disabled.
+    UserDisabled,
+    /// Device isn't set up yet, e.g. its public key wasn't provided.
+    DeviceNotConfigured,
+    /// Device doesn't belong to any location.
+    NoLocations,
+    /// Device is awaiting approval or was rejected in a location.
+    ApprovalMissing,
+    /// Service location can't be used without an enterprise license.
+    ServiceLocationUnavailable,
+    /// Device hasn't completed MFA in an MFA-protected location.
+    NotAuthorized,
+    /// None of the location gateways is connected.
+    NoGatewayConnected,
+    /// Gateway holding the device peer in a location with peer sharding isn't connected.
+    AssignedGatewayDisconnected,
+    /// Device is quarantined and can only reach remediation hosts.
+    Quarantined,
+    /// Firewall of a location doesn't allow any traffic from the device.
+    BlockedByFirewall,
+    /// Gateways never reported a handshake with the device.
+    NeverConnected,
+    /// Gateways didn't report a handshake with the device recently.
+    NoRecentHandshake,
+}
+
+/// Probable cause found by a troubleshooting check.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProbableCause {
+    pub cause: TroubleshootCause,
+    /// Location the cause applies to, if it's location-specific.
+    pub location_id: Option<Id>,
+    pub location_name: Option<String>,
+    pub details: String,
+}
+
+impl ProbableCause {
+    fn device(cause: TroubleshootCause, details: String) -> Self {
+        Self {
+            cause,
+            location_id: None,
+            location_name: None,
+            details,
+        }
+    }
+
+    fn location(
+        cause: TroubleshootCause,
+        location: &WireguardNetwork<Id>,
+        details: String,
+    ) -> Self {
+        Self {
+            cause,
+            location_id: Some(location.id),
+            location_name: Some(location.name.clone()),
+            details,
+        }
+    }
+}
+
+/// Result of device connectivity troubleshooting.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DeviceTroubleshooting {
+    pub device_id: Id,
+    pub device_name: String,
+    pub checked_at: NaiveDateTime,
+    /// Probable causes, most likely first. Empty if no problem was found.
+    pub causes: Vec<ProbableCause>,
+}
+
+/// Returns `true` if a firewall address matches a given IP address.
+fn address_contains(address: &IpAddress, ip: IpAddr) -> bool {
+    match &address.address {
+        Some(Address::Ip(addr)) => addr.parse::<IpAddr>().is_ok_and(|addr| addr == ip),
+        Some(Address::IpSubnet(subnet)) => subnet
+            .parse::<IpNetwork>()
+            .is_ok_and(|subnet| subnet.contains(ip)),
+        Some(Address::IpRange(range)) => {
+            match (range.start.parse::<IpAddr>(), range.end.parse::<IpAddr>()) {
+                (Ok(start), Ok(end)) => start <= ip && ip <= end,
+                _ => false,
+            }
+        }
+        None => false,
+    }
+}
+
+/// Returns `true` if a firewall configuration allows at least some traffic from given addresses.
+/// Rules without source addresses apply to all sources.
+fn firewall_allows(config: &FirewallConfig, ips: &[IpAddr]) -> bool {
+    config.default_policy() == FirewallPolicy::Allow
+        || config.rules.iter().any(|rule| {
+            rule.verdict() == FirewallPolicy::Allow
+                && (rule.source_addrs.is_empty()
+                    || rule
+                        .source_addrs
+                        .iter()
+                        .any(|address| ips.iter().any(|ip| address_contains(address, *ip))))
+        })
+}
+
+/// Runs checks for a device in a single location.
+async fn check_location(
+    pool: &PgPool,
+    device: &Device<Id>,
+    network_device: &WireguardNetworkDevice,
+    location: &WireguardNetwork<Id>,
+    connected_hostnames: &[String],
+    causes: &mut Vec<ProbableCause>,
+) -> Result<(), WebError> {
+    if location.should_prevent_service_location_usage() {
+        causes.push(ProbableCause::location(
+            TroubleshootCause::ServiceLocationUnavailable,
+            location,
+            "Service location requires an enterprise license".into(),
+        ));
+        return Ok(());
+    }
+    if location.mfa_enabled() && !network_device.is_authorized {
+        causes.push(ProbableCause::location(
+            TroubleshootCause::NotAuthorized,
+            location,
+            "Device hasn't completed MFA for this location".into(),
+        ));
+    }
+
+    // peers are sent to all connected gateways, or only to the assigned one with peer sharding
+    if connected_hostnames.is_empty() {
+        causes.push(ProbableCause::location(
+            TroubleshootCause::NoGatewayConnected,
+            location,
+            "No gateway of this location is connected".into(),
+        ));
+    } else if location.gateway_peer_sharding {
+        if let Some(hostname) = DeviceGatewayAssignment::hostname_for_pubkey(
+            pool,
+            location.id,
+            &device.wireguard_pubkey,
+        )
+        .await?
+        .filter(|hostname| !connected_hostnames.contains(hostname))
+        {
+            causes.push(ProbableCause::location(
+                TroubleshootCause::AssignedGatewayDisconnected,
+                location,
+                format!("Device is assigned to gateway {hostname} which isn't connected"),
+            ));
+        }
+    }
+
+    if location.acl_enabled {
+        if let Some(version) = FirewallConfigVersion::latest(pool, location.id).await? {
+            match version.decode() {
+                Ok(config) => {
+                    if !firewall_allows(&config, &network_device.wireguard_ips) {
+                        causes.push(ProbableCause::location(
+                            TroubleshootCause::BlockedByFirewall,
+                            location,
+                            format!(
+                                "Firewall configuration version {} doesn't allow any traffic \
+                                from the device",
+                                version.version
+                            ),
+                        ));
+                    }
+                }
+                Err(err) => error!(
+                    "Failed to decode firewall configuration version {} of location {location}: \
+                    {err}",
+                    version.version
+                ),
+            }
+        }
+    }
+
+    let stats = query!(
+        "SELECT gateway, latest_handshake FROM wireguard_peer_stats \
+        WHERE device_id = $1 AND network = $2 ORDER BY collected_at DESC LIMIT 1",
+        device.id,
+        location.id
+    )
+    .fetch_optional(pool)
+    .await?;
+    let active_since = (Utc::now() - WIREGUARD_MAX_HANDSHAKE).naive_utc();
+    match stats {
+        None => causes.push(ProbableCause::location(
+            TroubleshootCause::NeverConnected,
+            location,
+            "Gateways never reported a handshake with the device".into(),
+        )),
+        Some(stats) if stats.latest_handshake < active_since => {
+            causes.push(ProbableCause::location(
+                TroubleshootCause::NoRecentHandshake,
+                location,
+                format!(
+                    "Latest handshake at {} reported by gateway {}",
+                    stats.latest_handshake,
+                    stats.gateway.as_deref().unwrap_or("unknown")
+                ),
+            ));
+        }
+        Some(_) => (),
+    }
+
+    Ok(())
+}
+
+/// Runs all checks for a device and returns probable causes, most likely first.
+async fn probable_causes(
+    pool: &PgPool,
+    gateway_state: &Mutex<GatewayMap>,
+    device: &Device<Id>,
+) -> Result<Vec<ProbableCause>, WebError> {
+    let mut causes = Vec::new();
+
+    if let Some(user) = User::find_by_id(pool, device.user_id).await? {
+        if !user.is_active {
+            causes.push(ProbableCause::device(
+                TroubleshootCause::UserDisabled,
+                format!("User {} is disabled", user.username),
+            ));
+        }
+    }
+    if !device.configured {
+        causes.push(ProbableCause::device(
+            TroubleshootCause::DeviceNotConfigured,
+            "Device isn't configured, so it isn't sent to gateways".into(),
+        ));
+    }
+    if let Some(quarantine) = DeviceQuarantine::find_by_device_id(pool, device.id).await? {
+        causes.push(ProbableCause::device(
+            TroubleshootCause::Quarantined,
+            format!(
+                "Device was quarantined at {}: {}",
+                quarantine.created_at,
+                quarantine.reason.as_deref().unwrap_or("no reason given")
+            ),
+        ));
+    }
+
+    // devices which aren't approved in a location don't belong to it
+    let approvals = query!(
+        "SELECT r.location_id, n.name location_name, r.status \"status: DeviceApprovalStatus\" \
+        FROM device_approval_request r JOIN wireguard_network n ON n.id = r.location_id \
+        WHERE r.device_id = $1 AND r.status != 'approved'::device_approval_status \
+        ORDER BY r.location_id",
+        device.id
+    )
+    .fetch_all(pool)
+    .await?;
+    for approval in &approvals {
+        causes.push(ProbableCause {
+            cause: TroubleshootCause::ApprovalMissing,
+            location_id: Some(approval.location_id),
+            location_name: Some(approval.location_name.clone()),
+            details: match approval.status {
+                DeviceApprovalStatus::Pending => "Device is awaiting approval".into(),
+                _ => "Device wasn't approved in this location".into(),
+            },
+        });
+    }
+
+    let network_devices = WireguardNetworkDevice::find_by_device(pool, device.id)
+        .await?
+        .unwrap_or_default();
+    if network_devices.is_empty() && approvals.is_empty() {
+        causes.push(ProbableCause::device(
+            TroubleshootCause::NoLocations,
+            "Device doesn't belong to any location".into(),
+        ));
+    }
+    let mut locations = Vec::new();
+    for network_device in network_devices {
+        if let Some(location) =
+            WireguardNetwork::find_by_id(pool, network_device.wireguard_network_id).await?
+        {
+            locations.push((network_device, location));
+        }
+    }
+    let connected_hostnames: HashMap<Id, Vec<String>> = {
+        let gateway_state = gateway_state
+            .lock()
+            .expect("Failed to acquire gateway state lock");
+        locations
+            .iter()
+            .map(|(_, location)| (location.id, gateway_state.connected_hostnames(location.id)))
+            .collect()
+    };
+    for (network_device, location) in &locations {
+        check_location(
+            pool,
+            device,
+            network_device,
+            location,
+            &connected_hostnames[&location.id],
+            &mut causes,
+        )
+        .await?;
+    }
+
+    causes.sort_by_key(|cause| (cause.cause, cause.location_id));
+    Ok(causes)
+}
+
+/// Troubleshoot device connectivity
+///
+/// Runs server-side checks explaining why a device may not be able to connect: whether the device
+/// is configured and authorized, whether its peer is held by a connected gateway, whether gateways
+/// reported a recent handshake and whether firewall rules block its traffic.
+///
+/// # Returns
+/// - `DeviceTroubleshooting` object with a ranked list of probable causes
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/troubleshoot/device/{id}",
+    tag = "device",
+    params(
+        ("id" = i64, description = "ID of the device")
+    ),
+    responses(
+        (status = 200, description = "Probable causes of connectivity problems", body = DeviceTroubleshooting),
+        (status = 401, description = "Unauthorized to troubleshoot devices.", body = ApiResponse, example = json!({"msg": "Session is required"})),
+        (status = 403, description = "You don't have permission to troubleshoot devices.", body = ApiResponse, example = json!({"msg": "access denied"})),
+        (status = 404, description = "Device not found.", body = ApiResponse, example = json!({"msg": "Device 1 not found"})),
+        (status = 500, description = "Unable to troubleshoot the device.", body = ApiResponse, example = json!({"msg": "Internal server error"}))
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub(crate) async fn troubleshoot_device(
+    _role: AdminRole,
+    State(appstate): State<AppState>,
+    Extension(gateway_state): Extension<Arc<Mutex<GatewayMap>>>,
+    Path(id): Path<Id>,
+) -> ApiResult {
+    debug!("Troubleshooting connectivity of device {id}");
+    let device = Device::find_by_id(&appstate.pool, id)
+        .await?
+        .ok_or_else(|| WebError::ObjectNotFound(format!("Device {id} not found")))?;
+    let causes = probable_causes(&appstate.pool, &gateway_state, &device).await?;
+    debug!(
+        "Found {} probable causes of connectivity problems of device {device}",
+        causes.len()
+    );
+
+    Ok(ApiResponse {
+        json: json!(DeviceTroubleshooting {
+            device_id: device.id,
+            device_name: device.name,
+            checked_at: Utc::now().naive_utc(),
+            causes,
+        }),
+        status: StatusCode::OK,
+    })
+}
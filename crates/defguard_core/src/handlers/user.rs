@@ -1,23 +1,29 @@
 use std::collections::HashSet;
 
 use axum::{
-    extract::{Json, Path, State},
+    extract::{Json, Path, Query, State},
     http::StatusCode,
 };
-use defguard_mail::{Mail, templates};
+use defguard_common::db::{
+    Id,
+    models::{Settings, mail_context::is_valid_language_tag},
+};
+use defguard_mail::{Mail, MailCategory, templates};
 use humantime::parse_duration;
 use serde_json::json;
 
 use super::{
-    AddUserData, ApiResponse, ApiResult, PasswordChange, PasswordChangeSelf,
-    StartEnrollmentRequest, Username, mail::EMAIL_PASSWORD_RESET_START_SUBJECT,
+    AddUserData, ApiResponse, ApiResult, DEFAULT_API_PAGE_SIZE, PasswordChange, PasswordChangeSelf,
+    StartEnrollmentRequest, Username,
+    api_version::ApiVersion,
+    pagination::{PaginatedApiResponse, PaginationParams, get_pagination_metadata},
     user_for_admin_or_self,
 };
 use crate::{
     appstate::AppState,
     auth::{AdminRole, SessionInfo},
     db::{
-        AppEvent, OAuth2AuthorizedApp, User, UserDetails, UserInfo, WebAuthn,
+        AppEvent, OAuth2AuthorizedApp, User, UserDetails, UserInfo, WebAuthn, WireguardNetwork,
         models::{
             GroupDiff,
             enrollment::{PASSWORD_RESET_TOKEN_TYPE, Token},
@@ -37,6 +43,33 @@ use crate::{
     is_valid_phone_number, server_config,
 };
 
+/// Make sure locations an enrollment token is restricted to exist.
+async fn validate_token_locations(
+    appstate: &AppState,
+    location_ids: Option<&[Id]>,
+) -> Result<(), WebError> {
+    let Some(location_ids) = location_ids else {
+        return Ok(());
+    };
+    if location_ids.is_empty() {
+        return Err(WebError::BadRequest(
+            "Enrollment token has to allow at least one location".into(),
+        ));
+    }
+    for location_id in location_ids {
+        if WireguardNetwork::find_by_id(&appstate.pool, *location_id)
+            .await?
+            .is_none()
+        {
+            return Err(WebError::BadRequest(format!(
+                "Location {location_id} not found"
+            )));
+        }
+    }
+
+    Ok(())
+}
+
 /// The maximum length for the commonName (CN) attribute in LDAP schemas is commonly set to 64
 /// characters according to the X.520 standard and many LDAP implementations like Active Directory.
 pub(crate) const MAX_USERNAME_CHARS: usize = 64;
@@ -109,7 +142,7 @@ pub(crate) fn check_password_strength(password: &str) -> Result<(), WebError> {
 
 /// List of all users
 ///
-/// Retrieves list of users.
+/// Retrieves list of users. API v2 returns paginated list.
 ///
 /// # Returns
 /// - List of `UserInfo` objects.
@@ -151,14 +184,41 @@ pub(crate) fn check_password_strength(password: &str) -> Result<(), WebError> {
         ("api_token" = [])
     )
 )]
-pub async fn list_users(_role: AdminRole, State(appstate): State<AppState>) -> ApiResult {
+pub async fn list_users(
+    _role: AdminRole,
+    State(appstate): State<AppState>,
+    version: ApiVersion,
+    Query(pagination): Query<PaginationParams>,
+) -> ApiResult {
     let all_users = User::all(&appstate.pool).await?;
-    let mut users: Vec<UserInfo> = Vec::with_capacity(all_users.len());
-    for user in all_users {
-        users.push(UserInfo::from_user(&appstate.pool, &user).await?);
+    if version == ApiVersion::V1 {
+        let mut users: Vec<UserInfo> = Vec::with_capacity(all_users.len());
+        for user in all_users {
+            users.push(UserInfo::from_user(&appstate.pool, &user).await?);
+        }
+        return Ok(ApiResponse {
+            json: json!(users),
+            status: StatusCode::OK,
+        });
     }
+
+    let total_items = all_users.len() as u32;
+    let offset = pagination.page.saturating_sub(1) * DEFAULT_API_PAGE_SIZE;
+    let mut users = Vec::new();
+    for user in all_users
+        .iter()
+        .skip(offset as usize)
+        .take(DEFAULT_API_PAGE_SIZE as usize)
+    {
+        users.push(UserInfo::from_user(&appstate.pool, user).await?);
+    }
+    let response = PaginatedApiResponse {
+        data: users,
+        pagination: get_pagination_metadata(pagination.page, total_items),
+    };
+
     Ok(ApiResponse {
-        json: json!(users),
+        json: json!(response),
         status: StatusCode::OK,
     })
 }
@@ -415,6 +475,8 @@ pub async fn start_enrollment(
         ));
     }
 
+    validate_token_locations(&appstate, data.location_ids.as_deref()).await?;
+
     debug!(
         "Search for the user {} in database to get started with enrollment process.",
         username
@@ -452,6 +514,9 @@ pub async fn start_enrollment(
             appstate.mail_tx.clone(),
         )
         .await?;
+    if let Some(location_ids) = &data.location_ids {
+        Token::restrict_locations(&mut *transaction, &enrollment_token, location_ids).await?;
+    }
 
     debug!("Try to commit transaction to save the enrollment token into the database.");
     transaction.commit().await?;
@@ -527,6 +592,7 @@ pub async fn start_remote_desktop_configuration(
     );
     let user = user_for_admin_or_self(&appstate.pool, &session, &username).await?;
     debug!("Successfully fetched user data: {user:?}");
+    validate_token_locations(&appstate, data.location_ids.as_deref()).await?;
 
     // if email is None assume that email should be sent to enrolling user
     let email = match data.email {
@@ -556,6 +622,14 @@ pub async fn start_remote_desktop_configuration(
             None,
         )
         .await?;
+    if let Some(location_ids) = &data.location_ids {
+        Token::restrict_locations(
+            &mut *transaction,
+            &desktop_configuration_token,
+            location_ids,
+        )
+        .await?;
+    }
 
     debug!("Try to submit transaction to save the desktop configuration token into the databse.");
     transaction.commit().await?;
@@ -697,6 +771,17 @@ pub async fn modify_user(
         }
     }
 
+    // check mail language
+    if let Some(ref language) = user_info.mail_language {
+        if !is_valid_language_tag(language) {
+            debug!("Invalid mail language for user {username}: {language}");
+            return Ok(ApiResponse {
+                json: json!({}),
+                status: StatusCode::BAD_REQUEST,
+            });
+        }
+    }
+
     let status_changing = user_info.is_active != user.is_active;
 
     let mut transaction = appstate.pool.begin().await?;
@@ -1112,25 +1197,33 @@ pub async fn reset_password(
         Token::delete_unused_user_password_reset_tokens(&mut transaction, user.id).await?;
 
         let config = server_config();
+        let settings = Settings::get_current_settings();
         let enrollment = Token::new(
             user.id,
             Some(session.user.id),
             Some(user.email.clone()),
-            config.password_reset_token_timeout.as_secs(),
+            settings.password_reset_token_timeout().as_secs(),
             Some(PASSWORD_RESET_TOKEN_TYPE.to_string()),
         );
         enrollment.save(&mut *transaction).await?;
 
         let mail = Mail {
             to: user.email.clone(),
-            subject: EMAIL_PASSWORD_RESET_START_SUBJECT.into(),
+            subject: templates::localized_subject(
+                templates::PASSWORD_RESET_START_TEMPLATE,
+                user.mail_language.as_deref(),
+            )?,
             content: templates::email_password_reset_mail(
                 config.enrollment_url.clone(),
-                enrollment.id.clone().as_str(),
+                &enrollment.id,
+                enrollment.expires_at,
+                settings.password_reset_max_uses,
                 None,
                 None,
+                user.mail_language.as_deref(),
             )?,
             attachments: Vec::new(),
+            category: MailCategory::Security,
             result_tx: None,
         };
 
@@ -22,6 +22,13 @@ pub async fn add_webhook(
     let url = webhookdata.url.clone();
     debug!("User {} adding webhook {url}", session.user.username);
     let webhook: WebHook = webhookdata.into();
+    if let Err(err) = webhook.validate_payload_template() {
+        debug!("Invalid payload template of webhook {url}: {err}");
+        return Ok(ApiResponse {
+            json: json!({}),
+            status: StatusCode::BAD_REQUEST,
+        });
+    }
     let status = match webhook.save(&appstate.pool).await {
         Ok(webhook) => {
             info!("User {} added webhook {url}", session.user.username);
@@ -88,6 +95,15 @@ pub async fn change_webhook(
             webhook.on_user_deleted = data.on_user_deleted;
             webhook.on_user_modified = data.on_user_modified;
             webhook.on_hwkey_provision = data.on_hwkey_provision;
+            webhook.on_enrollment_completed = data.on_enrollment_completed;
+            webhook.payload_template = data.payload_template;
+            if let Err(err) = webhook.validate_payload_template() {
+                debug!("Invalid payload template of webhook {id}: {err}");
+                return Ok(ApiResponse {
+                    json: json!({}),
+                    status: StatusCode::BAD_REQUEST,
+                });
+            }
             webhook.save(&appstate.pool).await?;
             info!("User {} updated webhook {id}", session.user.username);
             appstate.emit_event(ApiEvent {
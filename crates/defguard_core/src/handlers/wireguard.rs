@@ -10,44 +10,71 @@ use axum::{
     extract::{Json, Path, Query, State},
     http::StatusCode,
 };
+use axum_extra::{TypedHeader, headers::UserAgent};
 use chrono::{DateTime, NaiveDateTime, TimeDelta, Utc};
 use defguard_common::{csv::AsCsv, db::Id};
 use defguard_mail::templates::TemplateLocation;
 use ipnetwork::IpNetwork;
+use semver::Version;
 use serde_json::{Value, json};
 use sqlx::PgPool;
 use utoipa::ToSchema;
 use uuid::Uuid;
 
-use super::{ApiResponse, ApiResult, WebError, device_for_admin_or_self, user_for_admin_or_self};
+use super::{
+    ApiResponse, ApiResult, WebError, device_for_admin_or_self, redaction::Redaction,
+    user_for_admin_or_self,
+};
 use crate::{
     appstate::AppState,
-    auth::{AdminRole, SessionInfo},
+    auth::{AdminRole, AuditorRole, SessionInfo},
     db::{
-        AddDevice, Device, GatewayEvent, WireguardNetwork,
+        AddDevice, Device, GatewayEvent, Group, WireguardNetwork,
         models::{
             device::{
                 DeviceConfig, DeviceInfo, DeviceNetworkInfo, DeviceType, ModifyDevice,
                 WireguardNetworkDevice,
             },
+            device_endpoint_change::DeviceEndpointChange,
+            gateway_distribution::{DeviceGatewayAssignment, LocationGateway},
+            gateway_journal::GatewayJournalEntry,
+            location_geofence::LocationGeofence,
+            route::Route,
             wireguard::{
-                DateTimeAggregation, LocationMfaMode, MappedDevice, ServiceLocationMode,
-                WireguardDeviceStatsRow, WireguardNetworkInfo, WireguardNetworkStats,
-                WireguardUserStatsRow, networks_stats,
+                DateTimeAggregation, DeviceNameUniqueness, GatewayDistributionPolicy,
+                IpAllocationStrategy, LocationMfaMode, MappedDevice, PeerImportReport,
+                PeerImportStatus, ServiceLocationMode, WireguardDeviceStatsRow,
+                WireguardNetworkInfo, WireguardNetworkStats, WireguardUserStatsRow,
+                get_allowed_ips_for_device, networks_stats,
             },
         },
     },
+    device_naming::{validate_device_name, validate_naming_policy},
     enterprise::{
-        db::models::{enterprise_settings::EnterpriseSettings, openid_provider::OpenIdProvider},
+        db::models::{
+            enterprise_settings::{ClientTrafficPolicy, EnterpriseSettings},
+            openid_provider::OpenIdProvider,
+        },
         handlers::CanManageDevices,
         is_business_license_active,
         limits::update_counts,
     },
     events::{ApiEvent, ApiEventType, ApiRequestContext},
-    grpc::gateway::map::GatewayMap,
-    handlers::mail::send_new_device_added_email,
+    geoip::{geoip_database, is_country_code},
+    grpc::gateway::{
+        distribution::{device_endpoint, rebalance_location},
+        journal::UpdateSummary,
+        map::GatewayMap,
+    },
+    handlers::mail::{send_device_disconnected_email, send_new_device_added_email},
+    headers::get_user_agent_os,
+    ip_conflicts::{LocationIpConflicts, location_conflicts},
     server_config,
-    wg_config::{ImportedDevice, parse_wireguard_config},
+    wg_config::{
+        ImportedDevice, parse_wireguard_config, parse_wireguard_config_with_address,
+        render_server_config,
+    },
+    wireguard_keepalive_tuning::KeepaliveRecommendation,
 };
 
 /// Parse a string with comma-separated IP addresses.
@@ -87,6 +114,43 @@ pub struct WireguardNetworkData {
     pub acl_default_allow: bool,
     pub location_mfa_mode: LocationMfaMode,
     pub service_location_mode: ServiceLocationMode,
+    /// Minimum desktop client version allowed to connect, e.g. "1.5.0"
+    #[serde(default)]
+    pub min_desktop_client_version: Option<String>,
+    /// Minimum mobile client version allowed to connect, e.g. "1.2.0"
+    #[serde(default)]
+    pub min_mobile_client_version: Option<String>,
+    /// Require admin approval of devices created through enrollment
+    #[serde(default)]
+    pub device_approval_required: bool,
+    /// Order in which addresses are assigned to new devices
+    #[serde(default)]
+    pub ip_allocation_strategy: IpAllocationStrategy,
+    /// Overrides instance-wide and group client traffic policy
+    #[serde(default)]
+    pub client_traffic_policy: Option<ClientTrafficPolicy>,
+    /// Hours after which devices need to repeat MFA; 0 means no limit
+    #[serde(default)]
+    pub mfa_session_lifetime_hours: i32,
+    /// Hours for which devices don't need to repeat MFA; 0 disables remembering devices
+    #[serde(default)]
+    pub mfa_remember_device_hours: i32,
+    /// Generate static preshared keys for peers of locations without MFA
+    #[serde(default)]
+    pub preshared_keys_enabled: bool,
+    /// Days after which static preshared keys are rotated; 0 disables rotation
+    #[serde(default)]
+    pub preshared_key_rotation_days: i32,
+    /// Regular expression names of new devices have to match
+    #[serde(default)]
+    pub device_name_pattern: Option<String>,
+    /// Required prefix of new device names; may contain `{username}`, `{first_name}`,
+    /// `{last_name}` and `{os}` placeholders
+    #[serde(default)]
+    pub device_name_prefix: Option<String>,
+    /// Whether device names have to be unique per user or in the whole location
+    #[serde(default)]
+    pub device_name_uniqueness: DeviceNameUniqueness,
 }
 
 impl WireguardNetworkData {
@@ -123,6 +187,17 @@ impl WireguardNetworkData {
         &self,
         executor: E,
     ) -> Result<(), WebError> {
+        if self.mfa_session_lifetime_hours < 0 || self.mfa_remember_device_hours < 0 {
+            return Err(WebError::BadRequest(
+                "MFA session lifetime and remember device period can't be negative".into(),
+            ));
+        }
+        if self.preshared_key_rotation_days < 0 {
+            return Err(WebError::BadRequest(
+                "Preshared key rotation period can't be negative".into(),
+            ));
+        }
+
         // if external MFA was chosen verify if enterprise features are enabled
         // and external OpenID provider is configured
         if self.location_mfa_mode == LocationMfaMode::External {
@@ -148,6 +223,43 @@ impl WireguardNetworkData {
 
         Ok(())
     }
+
+    /// Normalizes minimum client versions. Empty values are treated as no requirement.
+    pub(crate) fn parse_min_client_versions(
+        &self,
+    ) -> Result<(Option<String>, Option<String>), WebError> {
+        let parse = |version: Option<&String>| -> Result<Option<String>, WebError> {
+            match version.map(|version| version.trim()) {
+                None | Some("") => Ok(None),
+                Some(version) => Version::parse(version)
+                    .map(|version| Some(version.to_string()))
+                    .map_err(|_| {
+                        WebError::BadRequest(format!("{version} is not a valid client version"))
+                    }),
+            }
+        };
+
+        Ok((
+            parse(self.min_desktop_client_version.as_ref())?,
+            parse(self.min_mobile_client_version.as_ref())?,
+        ))
+    }
+
+    /// Normalizes and validates device naming pattern and prefix. Empty values are treated as
+    /// no requirement.
+    pub(crate) fn parse_device_naming(&self) -> Result<(Option<String>, Option<String>), WebError> {
+        let normalize = |value: Option<&String>| {
+            value
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+                .map(ToString::to_string)
+        };
+        let pattern = normalize(self.device_name_pattern.as_ref());
+        let prefix = normalize(self.device_name_prefix.as_ref());
+        validate_naming_policy(pattern.as_deref(), prefix.as_deref())?;
+
+        Ok((pattern, prefix))
+    }
 }
 
 // Used in process of importing network from WireGuard config
@@ -170,6 +282,40 @@ pub struct ImportedNetworkData {
     pub devices: Vec<ImportedDevice>,
 }
 
+#[derive(Deserialize, ToSchema)]
+pub struct MigrateNetworkData {
+    pub name: String,
+    pub endpoint: String,
+    /// wg-quick config or `wg showconf` dump
+    pub config: String,
+    pub allowed_groups: Vec<String>,
+    /// Comma-separated interface addresses, required if config has no `Address` key.
+    #[serde(default)]
+    pub address: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ExportedPeerConfig {
+    pub device_id: Id,
+    pub name: String,
+    pub wireguard_pubkey: String,
+    /// wg-quick client config with a placeholder instead of device private key
+    pub config: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct NetworkExport {
+    /// wg-quick server config
+    pub server_config: String,
+    pub peers: Vec<ExportedPeerConfig>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct NetworkMigrationReport {
+    pub network: WireguardNetwork<Id>,
+    pub peers: Vec<PeerImportReport>,
+}
+
 /// Create new network
 ///
 /// Create new network based on `WireguardNetworkData` object.
@@ -206,10 +352,39 @@ pub(crate) async fn create_network(
         session.user.username
     );
 
+    let network = create_network_from_data(&appstate, data).await?;
+
+    info!(
+        "User {} created WireGuard network {network_name}",
+        session.user.username
+    );
+
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::VpnLocationAdded {
+            location: network.clone(),
+        }),
+    })?;
+    update_counts(&appstate.pool).await?;
+
+    Ok(ApiResponse {
+        json: json!(network),
+        status: StatusCode::CREATED,
+    })
+}
+
+/// Creates a network, assigns addresses to existing allowed devices and notifies gateways.
+pub(crate) async fn create_network_from_data(
+    appstate: &AppState,
+    data: WireguardNetworkData,
+) -> Result<WireguardNetwork<Id>, WebError> {
     data.validate_location_mfa_mode(&appstate.pool).await?;
+    let (min_desktop_client_version, min_mobile_client_version) =
+        data.parse_min_client_versions()?;
+    let (device_name_pattern, device_name_prefix) = data.parse_device_naming()?;
 
     let allowed_ips = data.parse_allowed_ips();
-    let network = WireguardNetwork::new(
+    let mut network = WireguardNetwork::new(
         data.name,
         parse_address_list(&data.address),
         data.port,
@@ -223,6 +398,21 @@ pub(crate) async fn create_network(
         data.location_mfa_mode,
         data.service_location_mode,
     );
+    network.min_desktop_client_version = min_desktop_client_version;
+    network.min_mobile_client_version = min_mobile_client_version;
+    network.device_approval_required = data.device_approval_required;
+    network.ip_allocation_strategy = data.ip_allocation_strategy;
+    network.client_traffic_policy = data.client_traffic_policy;
+    network.mfa_session_lifetime_hours = data.mfa_session_lifetime_hours;
+    network.mfa_remember_device_hours = data.mfa_remember_device_hours;
+    network.preshared_keys_enabled = data.preshared_keys_enabled;
+    network.preshared_key_rotation_days = data.preshared_key_rotation_days;
+    network.device_name_pattern = device_name_pattern;
+    network.device_name_prefix = device_name_prefix;
+    network.device_name_uniqueness = data.device_name_uniqueness;
+    if data.preshared_keys_enabled {
+        network.preshared_keys_rotated_at = Some(Utc::now().naive_utc());
+    }
 
     let mut transaction = appstate.pool.begin().await?;
     let network = network.save(&mut *transaction).await?;
@@ -238,23 +428,7 @@ pub(crate) async fn create_network(
 
     transaction.commit().await?;
 
-    info!(
-        "User {} created WireGuard network {network_name}",
-        session.user.username
-    );
-
-    appstate.emit_event(ApiEvent {
-        context,
-        event: Box::new(ApiEventType::VpnLocationAdded {
-            location: network.clone(),
-        }),
-    })?;
-    update_counts(&appstate.pool).await?;
-
-    Ok(ApiResponse {
-        json: json!(network),
-        status: StatusCode::CREATED,
-    })
+    Ok(network)
 }
 
 async fn find_network(id: Id, pool: &PgPool) -> Result<WireguardNetwork<Id>, WebError> {
@@ -299,10 +473,39 @@ pub(crate) async fn modify_network(
         "User {} updating WireGuard network {network_id}",
         session.user.username
     );
+    let network = find_network(network_id, &appstate.pool).await?;
+    // store network before mods
+    let before = network.clone();
+    let network = update_network_from_data(&appstate, network, data).await?;
+
+    info!(
+        "User {} updated WireGuard network {network_id}",
+        session.user.username,
+    );
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::VpnLocationModified {
+            before,
+            after: network.clone(),
+        }),
+    })?;
+    Ok(ApiResponse {
+        json: json!(network),
+        status: StatusCode::OK,
+    })
+}
+
+/// Updates a network, synchronizes addresses of its allowed devices and notifies gateways.
+pub(crate) async fn update_network_from_data(
+    appstate: &AppState,
+    mut network: WireguardNetwork<Id>,
+    data: WireguardNetworkData,
+) -> Result<WireguardNetwork<Id>, WebError> {
     data.validate_location_mfa_mode(&appstate.pool).await?;
+    let (min_desktop_client_version, min_mobile_client_version) =
+        data.parse_min_client_versions()?;
+    let (device_name_pattern, device_name_prefix) = data.parse_device_naming()?;
 
-    let mut network = find_network(network_id, &appstate.pool).await?;
-    // store network before mods
     let before = network.clone();
     network.address = data.parse_addresses()?;
 
@@ -330,12 +533,36 @@ pub(crate) async fn modify_network(
         }
     };
     network.location_mfa_mode = data.location_mfa_mode;
+    network.min_desktop_client_version = min_desktop_client_version;
+    network.min_mobile_client_version = min_mobile_client_version;
+    network.device_approval_required = data.device_approval_required;
+    network.ip_allocation_strategy = data.ip_allocation_strategy;
+    network.client_traffic_policy = data.client_traffic_policy;
+    network.mfa_session_lifetime_hours = data.mfa_session_lifetime_hours;
+    network.mfa_remember_device_hours = data.mfa_remember_device_hours;
+    network.preshared_keys_enabled = data.preshared_keys_enabled;
+    network.preshared_key_rotation_days = data.preshared_key_rotation_days;
+    network.device_name_pattern = device_name_pattern;
+    network.device_name_prefix = device_name_prefix;
+    network.device_name_uniqueness = data.device_name_uniqueness;
+    let generate_preshared_keys =
+        network.static_preshared_keys() && !before.static_preshared_keys();
+    if generate_preshared_keys {
+        network.preshared_keys_rotated_at = Some(Utc::now().naive_utc());
+    }
 
     network.save(&mut *transaction).await?;
     network
         .set_allowed_groups(&mut transaction, data.allowed_groups)
         .await?;
     let _events = network.sync_allowed_devices(&mut transaction, None).await?;
+    if generate_preshared_keys {
+        network
+            .generate_preshared_keys(&mut transaction, true)
+            .await?;
+    } else if before.static_preshared_keys() && !network.static_preshared_keys() {
+        network.clear_preshared_keys(&mut *transaction).await?;
+    }
 
     let peers = network.get_peers(&mut *transaction).await?;
     let maybe_firewall_config = network.try_get_firewall_config(&mut transaction).await?;
@@ -349,21 +576,7 @@ pub(crate) async fn modify_network(
     // commit DB transaction
     transaction.commit().await?;
 
-    info!(
-        "User {} updated WireGuard network {network_id}",
-        session.user.username,
-    );
-    appstate.emit_event(ApiEvent {
-        context,
-        event: Box::new(ApiEventType::VpnLocationModified {
-            before,
-            after: network.clone(),
-        }),
-    })?;
-    Ok(ApiResponse {
-        json: json!(network),
-        status: StatusCode::OK,
-    })
+    Ok(network)
 }
 
 /// Delete network
@@ -560,7 +773,8 @@ pub(crate) async fn gateway_status(
 ///
 /// Returns current state of gateways as `HashMap<i64, Vec<GatewayState>>` where key is an id of `WireguardNetwork`
 pub(crate) async fn all_gateways_status(
-    _role: AdminRole,
+    _role: AuditorRole,
+    redaction: Redaction,
     Extension(gateway_state): Extension<Arc<Mutex<GatewayMap>>>,
 ) -> ApiResult {
     debug!("Displaying gateways status for all networks.");
@@ -568,8 +782,10 @@ pub(crate) async fn all_gateways_status(
         .lock()
         .expect("Failed to acquire gateway state lock");
     let flattened = (*gateway_state).as_flattened();
+    let mut json = json!(flattened);
+    redaction.apply(&mut json);
     Ok(ApiResponse {
-        json: json!(flattened),
+        json,
         status: StatusCode::OK,
     })
 }
@@ -656,6 +872,104 @@ pub(crate) async fn import_network(
     })
 }
 
+/// Migrate existing WireGuard server
+///
+/// Create new network from a wg-quick config or `wg showconf` dump. Peers are matched with
+/// existing devices by public key or with users by `# Email = ...` annotations, and keep their
+/// IP addresses. Peers which could not be matched are listed in the report and can be mapped
+/// later with `POST /api/v1/network/{network_id}/devices`.
+///
+/// # Returns
+/// - `NetworkMigrationReport` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    post,
+    path = "/api/v1/network/migrate",
+    request_body = MigrateNetworkData,
+    responses(
+        (status = 201, description = "Successfully migrated network.", body = NetworkMigrationReport),
+        (status = 401, description = "Unauthorized to create network.", body = ApiResponse, example = json!({"msg": "Session is required"})),
+        (status = 403, description = "You don't have permission to create a network.", body = ApiResponse, example = json!({"msg": "access denied"})),
+        (status = 422, description = "Invalid WireGuard config."),
+        (status = 500, description = "Unable to migrate network.", body = ApiResponse, example = json!({"msg": "Internal server error"}))
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub(crate) async fn migrate_network(
+    _role: AdminRole,
+    State(appstate): State<AppState>,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    Json(data): Json<MigrateNetworkData>,
+) -> ApiResult {
+    debug!(
+        "User {} migrating network {} from config file",
+        session.user.username, data.name
+    );
+    let (mut network, imported_devices) =
+        parse_wireguard_config_with_address(&data.config, data.address.as_deref()).map_err(
+            |error| {
+                error!("{error}");
+                WebError::Http(StatusCode::UNPROCESSABLE_ENTITY)
+            },
+        )?;
+    network.name = data.name;
+    network.endpoint = data.endpoint;
+
+    let mut transaction = appstate.pool.begin().await?;
+    let network = network.save(&mut *transaction).await?;
+    network
+        .set_allowed_groups(&mut transaction, data.allowed_groups)
+        .await?;
+
+    info!("New network {network} created");
+    appstate.send_wireguard_event(GatewayEvent::NetworkCreated(network.id, network.clone()));
+
+    let reserved_ips: Vec<IpAddr> = imported_devices
+        .iter()
+        .flat_map(|dev| dev.wireguard_ips.clone())
+        .collect();
+    let (peers, gateway_events) = network
+        .migrate_imported_devices(&mut transaction, imported_devices)
+        .await?;
+    appstate.send_multiple_wireguard_events(gateway_events);
+
+    // assign IPs for other existing devices
+    debug!("Assigning IPs in migrated network for remaining existing devices");
+    let gateway_events = network
+        .sync_allowed_devices(&mut transaction, Some(&reserved_ips))
+        .await?;
+    appstate.send_multiple_wireguard_events(gateway_events);
+
+    transaction.commit().await?;
+
+    let unmapped = peers
+        .iter()
+        .filter(|peer| peer.status == PeerImportStatus::Unmapped)
+        .count();
+    info!(
+        "User {} migrated network {network} with {} peers, {unmapped} of them need to be mapped manually",
+        session.user.username,
+        peers.len()
+    );
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::VpnLocationAdded {
+            location: network.clone(),
+        }),
+    })?;
+    update_counts(&appstate.pool).await?;
+
+    Ok(ApiResponse {
+        json: json!(NetworkMigrationReport { network, peers }),
+        status: StatusCode::CREATED,
+    })
+}
+
 // This is used exclusively for the wizard to map imported devices to users.
 pub(crate) async fn add_user_devices(
     _role: AdminRole,
@@ -766,7 +1080,7 @@ pub struct AddDeviceResult {
                 }
             }
         )),
-        (status = 400, description = "Bad request, no networks found or device with pubkey that you want to send with already exists.", body = ApiResponse, example = json!({})),
+        (status = 400, description = "Bad request, no networks found, device with pubkey that you want to send with already exists or device name violates naming policy of a location.", body = ApiResponse, example = json!({})),
         (status = 401, description = "Unauthorized to add a new device for a user.", body = ApiResponse, example = json!({"msg": "Session is required"})),
         (status = 403, description = "You don't have permission to add a new device for a user. You can't add a new device for a disabled user.", body = ApiResponse, example = json!({"msg": "requires privileged access"})),
         (status = 500, description = "Cannot add a new device for a user.", body = ApiResponse, example = json!({"msg": "Internal server error"}))
@@ -783,6 +1097,7 @@ pub(crate) async fn add_device(
     State(appstate): State<AppState>,
     // Alias, because otherwise `axum` reports conflicting routes.
     Path(username): Path<String>,
+    user_agent: Option<TypedHeader<UserAgent>>,
     Json(add_device): Json<AddDevice>,
 ) -> ApiResult {
     let device_name = add_device.name.clone();
@@ -836,8 +1151,21 @@ pub(crate) async fn add_device(
         )));
     }
 
-    // save the device
     let mut transaction = appstate.pool.begin().await?;
+    // operating system is known only if users add devices for themselves
+    let os = user_agent
+        .filter(|_| session.user == user)
+        .and_then(|TypedHeader(user_agent)| get_user_agent_os(user_agent.as_str()));
+    validate_device_name(
+        &mut transaction,
+        &add_device.name,
+        &user,
+        os.as_deref(),
+        None,
+    )
+    .await?;
+
+    // save the device
     let device = Device::new(
         add_device.name,
         add_device.wireguard_pubkey,
@@ -1120,6 +1448,76 @@ pub(crate) async fn get_device(
     })
 }
 
+#[derive(Debug, Deserialize)]
+pub struct EndpointHistoryQuery {
+    from: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DeviceEndpointHistory {
+    /// Number of distinct addresses the device connected from
+    pub distinct_ips: usize,
+    /// Address changes, the most recent first
+    pub changes: Vec<DeviceEndpointChange<Id>>,
+}
+
+/// Get device endpoint history
+///
+/// Returns changes of addresses a device connected to its locations from since `from`,
+/// including the addresses in use at that time. Defaults to the last 30 days.
+///
+/// # Returns
+/// - `DeviceEndpointHistory` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/device/{device_id}/endpoint_history",
+    params(
+        ("device_id" = i64, description = "ID of a device."),
+        ("from" = Option<String>, Query, description = "Beginning of the period as RFC 3339 timestamp, 30 days ago by default.")
+    ),
+    responses(
+        (status = 200, description = "Endpoint history of a device.", body = DeviceEndpointHistory),
+        (status = 400, description = "`from` is in the future.", body = ApiResponse, example = json!({"msg": "`from` can't be in the future"})),
+        (status = 401, description = "Unauthorized to view a device.", body = ApiResponse, example = json!({"msg": "Session is required"})),
+        (status = 404, description = "Device not found.", body = ApiResponse, example = json!({"msg": "device id <id> not found"}))
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub(crate) async fn get_device_endpoint_history(
+    session: SessionInfo,
+    Path(device_id): Path<i64>,
+    Query(query): Query<EndpointHistoryQuery>,
+    State(appstate): State<AppState>,
+) -> ApiResult {
+    let device = device_for_admin_or_self(&appstate.pool, &session, device_id).await?;
+    let now = Utc::now();
+    let from = query.from.unwrap_or(now - TimeDelta::days(30));
+    if from > now {
+        return Err(WebError::BadRequest("`from` can't be in the future".into()));
+    }
+    debug!("Retrieving endpoint history of device {device} since {from}");
+    let changes =
+        DeviceEndpointChange::for_device_since(&appstate.pool, device.id, from.naive_utc()).await?;
+    let distinct_ips = changes
+        .iter()
+        .map(|change| change.ip.ip())
+        .collect::<HashSet<_>>()
+        .len();
+
+    Ok(ApiResponse {
+        json: json!(DeviceEndpointHistory {
+            distinct_ips,
+            changes,
+        }),
+        status: StatusCode::OK,
+    })
+}
+
 /// Delete device
 ///
 /// Delete user device and trigger new update in gateway server.
@@ -1234,39 +1632,176 @@ pub(crate) async fn delete_device(
     Ok(ApiResponse::default())
 }
 
-/// List all devices
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct DisconnectDevice {
+    /// Disconnect only from this location; all device locations are used if not set.
+    #[serde(default)]
+    pub location_id: Option<Id>,
+    /// Notify device owner by email.
+    #[serde(default)]
+    pub notify_user: bool,
+}
+
+/// Disconnect device
 ///
-/// Retrieves all devices
+/// Forcibly disconnect a device from all its locations, or from a single location.
+/// The device peer is removed from gateways. In MFA-protected locations the device is also
+/// marked as not authorized, so its owner has to authenticate again to reconnect.
+/// In other locations the peer is restored with the next configuration update of the location.
 ///
 /// # Returns
-/// - List of `Device` objects
+/// - JSON with IDs of locations the device has been disconnected from
 ///
 /// - `WebError` if error occurs
 #[utoipa::path(
-    get,
-    path = "/api/v1/device",
+    post,
+    path = "/api/v1/device/{device_id}/disconnect",
+    params(
+        ("device_id" = i64, description = "ID of device to disconnect.")
+    ),
+    request_body = DisconnectDevice,
     responses(
-        (status = 200, description = "List all devices.", body = [Device], example = json!([
-            {
-                "id": 0,
-                "name": "name",
-                "wireguard_pubkey": "wireguard_pubkey",
-                "user_id": 0,
-                "created": "2024-07-10T10:25:43.231Z"
-            }
-        ])),
-        (status = 401, description = "Unauthorized to list all devices.", body = ApiResponse, example = json!({"msg": "Session is required"})),
-        (status = 403, description = "You don't have permission to list all devices.", body = ApiResponse, example = json!({"msg": "requires privileged access"})),
+        (status = 200, description = "Successfully disconnected device.", body = ApiResponse, example = json!({"locations": [1]})),
+        (status = 401, description = "Unauthorized to disconnect a device.", body = ApiResponse, example = json!({"msg": "Session is required"})),
+        (status = 403, description = "You don't have permission to disconnect a device.", body = ApiResponse, example = json!({"msg": "access denied"})),
+        (status = 404, description = "Device or location not found.", body = ApiResponse, example = json!({"msg": "device id <id> not found"})),
+        (status = 500, description = "Cannot disconnect a device.", body = ApiResponse, example = json!({"msg": "Internal server error"}))
     ),
     security(
         ("cookie" = []),
         ("api_token" = [])
     )
 )]
-pub(crate) async fn list_devices(_role: AdminRole, State(appstate): State<AppState>) -> ApiResult {
-    debug!("Listing devices");
-    let devices = Device::all(&appstate.pool).await?;
-    info!("Listed {} devices", devices.len());
+pub(crate) async fn disconnect_device(
+    _admin: AdminRole,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    Path(device_id): Path<i64>,
+    State(appstate): State<AppState>,
+    Json(data): Json<DisconnectDevice>,
+) -> ApiResult {
+    let username = &session.user.username;
+    debug!("User {username} disconnecting device {device_id}");
+    let mut transaction = appstate.pool.begin().await?;
+
+    let Some(device) = Device::find_by_id(&mut *transaction, device_id).await? else {
+        return Err(WebError::ObjectNotFound(format!(
+            "device id {device_id} not found"
+        )));
+    };
+    let network_devices = WireguardNetworkDevice::find_by_device(&mut *transaction, device.id)
+        .await?
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|network_device| {
+            data.location_id
+                .is_none_or(|location_id| network_device.wireguard_network_id == location_id)
+        })
+        .collect::<Vec<_>>();
+    if let Some(location_id) = data.location_id {
+        if network_devices.is_empty() {
+            return Err(WebError::ObjectNotFound(format!(
+                "device id {device_id} is not assigned to location {location_id}"
+            )));
+        }
+    }
+
+    let owner = device.get_owner(&mut *transaction).await?;
+    let mut network_info = Vec::new();
+    let mut locations = Vec::new();
+    for mut network_device in network_devices {
+        let Some(location) =
+            WireguardNetwork::find_by_id(&mut *transaction, network_device.wireguard_network_id)
+                .await?
+        else {
+            continue;
+        };
+        if location.mfa_enabled() && network_device.is_authorized {
+            info!("Marking device {device} as not authorized to connect to location {location}");
+            network_device.is_authorized = false;
+            network_device.preshared_key = None;
+            network_device.update(&mut *transaction).await?;
+        }
+        network_info.push(DeviceNetworkInfo {
+            network_id: location.id,
+            device_wireguard_ips: network_device.wireguard_ips,
+            preshared_key: network_device.preshared_key,
+            is_authorized: network_device.is_authorized,
+        });
+        locations.push(location);
+    }
+    transaction.commit().await?;
+
+    appstate.send_wireguard_event(GatewayEvent::DeviceDeleted(DeviceInfo {
+        device: device.clone(),
+        network_info,
+    }));
+
+    if data.notify_user && !locations.is_empty() {
+        let location_names: Vec<String> = locations
+            .iter()
+            .map(|location| location.name.clone())
+            .collect();
+        send_device_disconnected_email(
+            &device.name,
+            &location_names,
+            &owner.email,
+            &appstate.mail_tx,
+        )?;
+    }
+
+    let location_ids: Vec<Id> = locations.iter().map(|location| location.id).collect();
+    for location in locations {
+        appstate.emit_event(ApiEvent {
+            context: context.clone(),
+            event: Box::new(ApiEventType::DeviceDisconnected {
+                owner: owner.clone(),
+                device: device.clone(),
+                location,
+            }),
+        })?;
+    }
+    info!("User {username} disconnected device {device_id} from locations {location_ids:?}");
+
+    Ok(ApiResponse {
+        json: json!({ "locations": location_ids }),
+        status: StatusCode::OK,
+    })
+}
+
+/// List all devices
+///
+/// Retrieves all devices
+///
+/// # Returns
+/// - List of `Device` objects
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/device",
+    responses(
+        (status = 200, description = "List all devices.", body = [Device], example = json!([
+            {
+                "id": 0,
+                "name": "name",
+                "wireguard_pubkey": "wireguard_pubkey",
+                "user_id": 0,
+                "created": "2024-07-10T10:25:43.231Z"
+            }
+        ])),
+        (status = 401, description = "Unauthorized to list all devices.", body = ApiResponse, example = json!({"msg": "Session is required"})),
+        (status = 403, description = "You don't have permission to list all devices.", body = ApiResponse, example = json!({"msg": "requires privileged access"})),
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub(crate) async fn list_devices(_role: AdminRole, State(appstate): State<AppState>) -> ApiResult {
+    debug!("Listing devices");
+    let devices = Device::all(&appstate.pool).await?;
+    info!("Listed {} devices", devices.len());
 
     Ok(ApiResponse {
         json: json!(devices),
@@ -1349,16 +1884,22 @@ pub(crate) async fn download_config(
         ));
     }
 
-    let network = find_network(network_id, &appstate.pool).await?;
+    let mut network = find_network(network_id, &appstate.pool).await?;
     let device = device_for_admin_or_self(&appstate.pool, &session, device_id).await?;
     let wireguard_network_device =
         WireguardNetworkDevice::find(&appstate.pool, device_id, network_id).await?;
     if let Some(wireguard_network_device) = wireguard_network_device {
+        network.endpoint = device_endpoint(&appstate.pool, device_id, &network).await?;
+        let enterprise_settings =
+            EnterpriseSettings::get_for_user(&appstate.pool, device.user_id).await?;
+        let allowed_ips = network
+            .device_allowed_ips(&appstate.pool, &device, &enterprise_settings)
+            .await?;
         info!("Created config for device {}({device_id})", device.name);
         Ok(Device::create_config(
             &network,
             &wireguard_network_device,
-            &enterprise_settings,
+            &allowed_ips,
         ))
     } else {
         error!(
@@ -1372,6 +1913,80 @@ pub(crate) async fn download_config(
     }
 }
 
+/// Export network to wg-quick configuration
+///
+/// Render complete wg-quick server config, including network private key, and client configs
+/// for all peers of a network. Meant for disaster recovery, when Defguard is unavailable.
+///
+/// # Returns
+/// - `NetworkExport` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/network/{network_id}/export",
+    params(
+        ("network_id" = i64, description = "ID of network to export")
+    ),
+    responses(
+        (status = 200, description = "Network configuration.", body = NetworkExport),
+        (status = 401, description = "Unauthorized to export network.", body = ApiResponse, example = json!({"msg": "Session is required"})),
+        (status = 403, description = "You don't have permission to export network.", body = ApiResponse, example = json!({"msg": "access denied"})),
+        (status = 404, description = "Network not found.", body = ApiResponse, example = json!({"msg": "network not found"})),
+        (status = 500, description = "Unable to export network.", body = ApiResponse, example = json!({"msg": "Internal server error"}))
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub(crate) async fn export_network(
+    _role: AdminRole,
+    session: SessionInfo,
+    State(appstate): State<AppState>,
+    Path(network_id): Path<i64>,
+) -> ApiResult {
+    debug!(
+        "User {} exporting configuration of network {network_id}",
+        session.user.username
+    );
+    let network = find_network(network_id, &appstate.pool).await?;
+    let enterprise_settings = EnterpriseSettings::get(&appstate.pool).await?;
+    let peers = network.export_peers(&appstate.pool).await?;
+    // routes restricted to groups are left out, same as group client traffic policies
+    let routes = Route::addresses_for_location(&appstate.pool, network.id, None).await?;
+    let allowed_ips = get_allowed_ips_for_device(&enterprise_settings, &network, &routes);
+
+    let server_config = render_server_config(&network, &peers);
+    let peers = peers
+        .into_iter()
+        .map(|peer| {
+            let mut wireguard_network_device =
+                WireguardNetworkDevice::new(network.id, peer.device_id, peer.wireguard_ips);
+            wireguard_network_device.preshared_key = peer.preshared_key;
+            ExportedPeerConfig {
+                device_id: peer.device_id,
+                name: peer.name,
+                wireguard_pubkey: peer.wireguard_pubkey,
+                config: Device::create_config(&network, &wireguard_network_device, &allowed_ips),
+            }
+        })
+        .collect::<Vec<_>>();
+    info!(
+        "User {} exported configuration of network {network} with {} peers",
+        session.user.username,
+        peers.len()
+    );
+
+    Ok(ApiResponse {
+        json: json!(NetworkExport {
+            server_config,
+            peers
+        }),
+        status: StatusCode::OK,
+    })
+}
+
 pub(crate) async fn create_network_token(
     _role: AdminRole,
     State(appstate): State<AppState>,
@@ -1469,7 +2084,8 @@ pub(crate) async fn devices_stats(
 /// # Returns
 /// Returns an `WireguardNetworkStats` based on requested network and time period
 pub(crate) async fn network_stats(
-    _role: AdminRole,
+    _role: AuditorRole,
+    redaction: Redaction,
     State(appstate): State<AppState>,
     Path(network_id): Path<i64>,
     Query(query_from): Query<QueryFrom>,
@@ -1487,8 +2103,717 @@ pub(crate) async fn network_stats(
         .await?;
     debug!("Displayed WireGuard network stats for network {network_id}");
 
+    let mut json = json!(stats);
+    redaction.apply(&mut json);
+    Ok(ApiResponse {
+        json,
+        status: StatusCode::OK,
+    })
+}
+
+#[derive(Deserialize)]
+pub struct JournalQuery {
+    from: Option<String>,
+    until: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct GatewayJournalEntryInfo {
+    pub epoch: i64,
+    pub event: String,
+    pub created_at: NaiveDateTime,
+    // `None` for entries marking a gap in the journal
+    pub update: Option<UpdateSummary>,
+}
+
+/// Returns gateway configuration journal of a network
+///
+/// List updates pushed to gateways of a network in requested time period, one hour by default.
+///
+/// # Returns
+/// - list of `GatewayJournalEntryInfo` objects
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/network/{network_id}/journal",
+    params(
+        ("network_id" = i64, description = "ID of network"),
+        ("from" = Option<String>, Query, description = "Start of time period in RFC 3339 format"),
+        ("until" = Option<String>, Query, description = "End of time period in RFC 3339 format")
+    ),
+    responses(
+        (status = 200, description = "Gateway configuration journal.", body = [GatewayJournalEntryInfo]),
+        (status = 400, description = "Invalid time period.", body = ApiResponse, example = json!({})),
+        (status = 401, description = "Unauthorized to view journal.", body = ApiResponse, example = json!({"msg": "Session is required"})),
+        (status = 403, description = "You don't have permission to view journal.", body = ApiResponse, example = json!({"msg": "access denied"})),
+        (status = 404, description = "Network not found.", body = ApiResponse, example = json!({"msg": "network not found"})),
+        (status = 500, description = "Unable to fetch journal.", body = ApiResponse, example = json!({"msg": "Internal server error"}))
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub(crate) async fn network_journal(
+    _role: AdminRole,
+    State(appstate): State<AppState>,
+    Path(network_id): Path<i64>,
+    Query(query): Query<JournalQuery>,
+) -> ApiResult {
+    debug!("Displaying gateway configuration journal for network {network_id}");
+    let network = find_network(network_id, &appstate.pool).await?;
+    let from = QueryFrom { from: query.from }
+        .parse_timestamp()?
+        .naive_utc();
+    let until = match query.until {
+        Some(until) => DateTime::<Utc>::from_str(&until)
+            .map_err(|_| StatusCode::BAD_REQUEST)?
+            .naive_utc(),
+        None => Utc::now().naive_utc(),
+    };
+    let entries = GatewayJournalEntry::all_between(&appstate.pool, network.id, from, until)
+        .await?
+        .into_iter()
+        .map(|entry| GatewayJournalEntryInfo {
+            update: UpdateSummary::from_payload(entry.payload.as_deref()),
+            epoch: entry.epoch,
+            event: entry.event,
+            created_at: entry.created_at,
+        })
+        .collect::<Vec<_>>();
+    debug!("Displayed gateway configuration journal for network {network_id}");
+
+    Ok(ApiResponse {
+        json: json!(entries),
+        status: StatusCode::OK,
+    })
+}
+
+/// Get keepalive interval recommendation for a network
+///
+/// Recommendation is based on handshakes of network peers from the last 24 hours.
+///
+/// # Returns
+/// - `KeepaliveRecommendation` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/network/{network_id}/keepalive",
+    params(
+        ("network_id" = i64, description = "ID of network"),
+    ),
+    responses(
+        (status = 200, description = "Keepalive recommendation.", body = KeepaliveRecommendation),
+        (status = 401, description = "Unauthorized to view recommendation.", body = ApiResponse, example = json!({"msg": "Session is required"})),
+        (status = 403, description = "You don't have permission to view recommendation.", body = ApiResponse, example = json!({"msg": "access denied"})),
+        (status = 404, description = "Network not found.", body = ApiResponse, example = json!({"msg": "network not found"})),
+        (status = 500, description = "Unable to prepare recommendation.", body = ApiResponse, example = json!({"msg": "Internal server error"}))
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub(crate) async fn keepalive_recommendation(
+    _role: AdminRole,
+    State(appstate): State<AppState>,
+    Path(network_id): Path<i64>,
+) -> ApiResult {
+    debug!("Preparing keepalive recommendation for network {network_id}");
+    let network = find_network(network_id, &appstate.pool).await?;
+    let recommendation = KeepaliveRecommendation::for_location(&appstate.pool, &network).await?;
+    debug!("Prepared keepalive recommendation for network {network_id}");
+
     Ok(ApiResponse {
-        json: json!(stats),
+        json: json!(recommendation),
+        status: StatusCode::OK,
+    })
+}
+
+/// Apply keepalive interval recommendation to a network
+///
+/// Updated configuration is sent to network gateways.
+///
+/// # Returns
+/// - applied `KeepaliveRecommendation` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    post,
+    path = "/api/v1/network/{network_id}/keepalive",
+    params(
+        ("network_id" = i64, description = "ID of network"),
+    ),
+    responses(
+        (status = 200, description = "Keepalive recommendation applied.", body = KeepaliveRecommendation),
+        (status = 401, description = "Unauthorized to modify network.", body = ApiResponse, example = json!({"msg": "Session is required"})),
+        (status = 403, description = "You don't have permission to modify network.", body = ApiResponse, example = json!({"msg": "access denied"})),
+        (status = 404, description = "Network not found.", body = ApiResponse, example = json!({"msg": "network not found"})),
+        (status = 500, description = "Unable to apply recommendation.", body = ApiResponse, example = json!({"msg": "Internal server error"}))
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub(crate) async fn apply_keepalive_recommendation(
+    _role: AdminRole,
+    State(appstate): State<AppState>,
+    Path(network_id): Path<i64>,
+    session: SessionInfo,
+    context: ApiRequestContext,
+) -> ApiResult {
+    debug!(
+        "User {} applying keepalive recommendation to network {network_id}",
+        session.user.username
+    );
+    let mut network = find_network(network_id, &appstate.pool).await?;
+    let recommendation = KeepaliveRecommendation::for_location(&appstate.pool, &network).await?;
+    if recommendation.is_change() {
+        let before = network.clone();
+        network.keepalive_interval = recommendation.recommended_keepalive;
+        let mut transaction = appstate.pool.begin().await?;
+        network.save(&mut *transaction).await?;
+        let peers = network.get_peers(&mut *transaction).await?;
+        let maybe_firewall_config = network.try_get_firewall_config(&mut transaction).await?;
+        transaction.commit().await?;
+        appstate.send_wireguard_event(GatewayEvent::NetworkModified(
+            network.id,
+            network.clone(),
+            peers,
+            maybe_firewall_config,
+        ));
+        info!(
+            "User {} changed keepalive interval of network {network_id} from {} to {}",
+            session.user.username,
+            recommendation.current_keepalive,
+            recommendation.recommended_keepalive
+        );
+        appstate.emit_event(ApiEvent {
+            context,
+            event: Box::new(ApiEventType::VpnLocationModified {
+                before,
+                after: network,
+            }),
+        })?;
+    } else {
+        debug!("Keepalive interval of network {network_id} doesn't need to be changed");
+    }
+
+    Ok(ApiResponse {
+        json: json!(recommendation),
+        status: StatusCode::OK,
+    })
+}
+
+/// Verify IP addresses of devices in all networks
+///
+/// Scans addresses assigned to devices for duplicates, reserved addresses, addresses outside of
+/// network subnets and subnets in which a device has no address. Each conflict comes with
+/// a suggested remediation.
+///
+/// # Returns
+/// - List of `LocationIpConflicts` objects
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/network/ip_conflicts",
+    responses(
+        (status = 200, description = "IP address conflicts in all networks.", body = [LocationIpConflicts]),
+        (status = 401, description = "Unauthorized to verify IP addresses.", body = ApiResponse, example = json!({"msg": "Session is required"})),
+        (status = 403, description = "You don't have permission to verify IP addresses.", body = ApiResponse, example = json!({"msg": "access denied"})),
+        (status = 500, description = "Unable to verify IP addresses.", body = ApiResponse, example = json!({"msg": "Internal server error"}))
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub(crate) async fn ip_conflicts(_role: AdminRole, State(appstate): State<AppState>) -> ApiResult {
+    debug!("Verifying IP addresses of devices in all networks");
+    let mut reports = Vec::new();
+    for network in WireguardNetwork::all(&appstate.pool).await? {
+        let report = location_conflicts(&appstate.pool, &network).await?;
+        if !report.conflicts.is_empty() {
+            warn!(
+                "Found {} IP address conflicts in network {}",
+                report.conflicts.len(),
+                network.name
+            );
+        }
+        reports.push(report);
+    }
+    debug!("Verified IP addresses of devices in all networks");
+
+    Ok(ApiResponse {
+        json: json!(reports),
+        status: StatusCode::OK,
+    })
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LocationGatewayData {
+    pub hostname: String,
+    pub endpoint: Option<String>,
+    #[serde(default = "default_gateway_weight")]
+    pub weight: i32,
+    pub group_id: Option<Id>,
+}
+
+fn default_gateway_weight() -> i32 {
+    1
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct GatewayDistributionData {
+    pub policy: GatewayDistributionPolicy,
+    /// Split peers between gateways, requires hash or group policy.
+    #[serde(default)]
+    pub peer_sharding: bool,
+    pub gateways: Vec<LocationGatewayData>,
+}
+
+impl GatewayDistributionData {
+    pub(crate) async fn validate(&self, pool: &PgPool) -> Result<(), WebError> {
+        let mut hostnames = HashSet::new();
+        for gateway in &self.gateways {
+            if gateway.hostname.trim().is_empty() {
+                return Err(WebError::BadRequest("gateway hostname is required".into()));
+            }
+            if gateway.weight < 1 {
+                return Err(WebError::BadRequest(
+                    "gateway weight has to be positive".into(),
+                ));
+            }
+            if !hostnames.insert(gateway.hostname.as_str()) {
+                return Err(WebError::BadRequest(format!(
+                    "duplicate gateway {}",
+                    gateway.hostname
+                )));
+            }
+            if let Some(group_id) = gateway.group_id {
+                if Group::find_by_id(pool, group_id).await?.is_none() {
+                    return Err(WebError::BadRequest(format!("group {group_id} not found")));
+                }
+            }
+        }
+        if self.peer_sharding {
+            if !matches!(
+                self.policy,
+                GatewayDistributionPolicy::Hash | GatewayDistributionPolicy::Group
+            ) {
+                return Err(WebError::BadRequest(
+                    "peer sharding requires hash or group distribution policy".into(),
+                ));
+            }
+            if self.gateways.is_empty() {
+                return Err(WebError::BadRequest(
+                    "peer sharding requires configured gateways".into(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct GatewayDistributionGatewayInfo {
+    pub hostname: String,
+    pub endpoint: Option<String>,
+    pub weight: i32,
+    pub group_id: Option<Id>,
+    pub connected: bool,
+    pub assigned_devices: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct GatewayDistributionInfo {
+    pub policy: GatewayDistributionPolicy,
+    pub peer_sharding: bool,
+    pub gateways: Vec<GatewayDistributionGatewayInfo>,
+}
+
+impl GatewayDistributionInfo {
+    async fn fetch(
+        pool: &PgPool,
+        network: &WireguardNetwork<Id>,
+        connected: &[String],
+    ) -> Result<Self, WebError> {
+        let configured = LocationGateway::all_for_location(pool, network.id).await?;
+        let assignments = DeviceGatewayAssignment::all_for_location(pool, network.id).await?;
+        let assigned_devices = |hostname: &str| {
+            assignments
+                .iter()
+                .filter(|assignment| assignment.hostname == hostname)
+                .count()
+        };
+
+        let mut gateways: Vec<GatewayDistributionGatewayInfo> = configured
+            .into_iter()
+            .map(|gateway| GatewayDistributionGatewayInfo {
+                connected: connected.contains(&gateway.hostname),
+                assigned_devices: assigned_devices(&gateway.hostname),
+                hostname: gateway.hostname,
+                endpoint: gateway.endpoint,
+                weight: gateway.weight,
+                group_id: gateway.group_id,
+            })
+            .collect();
+        // connected gateways without configuration take part in distribution with default weight,
+        // unless peers are sharded between configured gateways
+        for hostname in connected {
+            if !network.gateway_peer_sharding
+                && !gateways.iter().any(|gateway| &gateway.hostname == hostname)
+            {
+                gateways.push(GatewayDistributionGatewayInfo {
+                    hostname: hostname.clone(),
+                    endpoint: None,
+                    weight: default_gateway_weight(),
+                    group_id: None,
+                    connected: true,
+                    assigned_devices: assigned_devices(hostname),
+                });
+            }
+        }
+        gateways.sort_by(|a, b| a.hostname.cmp(&b.hostname));
+
+        Ok(Self {
+            policy: network.gateway_distribution_policy,
+            peer_sharding: network.gateway_peer_sharding,
+            gateways,
+        })
+    }
+}
+
+/// Returns gateway distribution of a network
+///
+/// # Returns
+/// - `GatewayDistributionInfo` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/network/{network_id}/gateway_distribution",
+    params(
+        ("network_id" = i64, description = "ID of network")
+    ),
+    responses(
+        (status = 200, description = "Gateway distribution of a network.", body = GatewayDistributionInfo),
+        (status = 401, description = "Unauthorized to view gateway distribution.", body = ApiResponse, example = json!({"msg": "Session is required"})),
+        (status = 403, description = "You don't have permission to view gateway distribution.", body = ApiResponse, example = json!({"msg": "access denied"})),
+        (status = 404, description = "Network not found.", body = ApiResponse, example = json!({"msg": "network not found"})),
+        (status = 500, description = "Unable to fetch gateway distribution.", body = ApiResponse, example = json!({"msg": "Internal server error"}))
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub(crate) async fn gateway_distribution(
+    _role: AdminRole,
+    State(appstate): State<AppState>,
+    Path(network_id): Path<i64>,
+    Extension(gateway_state): Extension<Arc<Mutex<GatewayMap>>>,
+) -> ApiResult {
+    debug!("Displaying gateway distribution for network {network_id}");
+    let network = find_network(network_id, &appstate.pool).await?;
+    let connected = gateway_state
+        .lock()
+        .expect("Failed to acquire gateway state lock")
+        .connected_hostnames(network.id);
+    let info = GatewayDistributionInfo::fetch(&appstate.pool, &network, &connected).await?;
+    debug!("Displayed gateway distribution for network {network_id}");
+
+    Ok(ApiResponse {
+        json: json!(info),
+        status: StatusCode::OK,
+    })
+}
+
+/// Modify gateway distribution of a network
+///
+/// Sets distribution policy and endpoints and weights of gateways serving the network.
+/// Devices are reassigned immediately, to currently connected gateways or to all configured
+/// gateways if peers are sharded. Gateways of sharded networks receive updated configuration.
+///
+/// # Returns
+/// - `GatewayDistributionInfo` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    put,
+    path = "/api/v1/network/{network_id}/gateway_distribution",
+    params(
+        ("network_id" = i64, description = "ID of network")
+    ),
+    request_body = GatewayDistributionData,
+    responses(
+        (status = 200, description = "Successfully modified gateway distribution.", body = GatewayDistributionInfo),
+        (status = 400, description = "Invalid gateway configuration.", body = ApiResponse, example = json!({"msg": "gateway weight has to be positive"})),
+        (status = 401, description = "Unauthorized to modify gateway distribution.", body = ApiResponse, example = json!({"msg": "Session is required"})),
+        (status = 403, description = "You don't have permission to modify gateway distribution.", body = ApiResponse, example = json!({"msg": "access denied"})),
+        (status = 404, description = "Network not found.", body = ApiResponse, example = json!({"msg": "network not found"})),
+        (status = 500, description = "Unable to modify gateway distribution.", body = ApiResponse, example = json!({"msg": "Internal server error"}))
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub(crate) async fn modify_gateway_distribution(
+    _role: AdminRole,
+    State(appstate): State<AppState>,
+    Path(network_id): Path<i64>,
+    session: SessionInfo,
+    context: ApiRequestContext,
+    Extension(gateway_state): Extension<Arc<Mutex<GatewayMap>>>,
+    Json(data): Json<GatewayDistributionData>,
+) -> ApiResult {
+    debug!(
+        "User {} updating gateway distribution of network {network_id}",
+        session.user.username
+    );
+    let network = find_network(network_id, &appstate.pool).await?;
+    let before = network.clone();
+    let (network, info) =
+        set_gateway_distribution(&appstate, &gateway_state, network, data).await?;
+
+    info!(
+        "User {} updated gateway distribution of network {network_id}",
+        session.user.username
+    );
+    appstate.emit_event(ApiEvent {
+        context,
+        event: Box::new(ApiEventType::VpnLocationModified {
+            before,
+            after: network,
+        }),
+    })?;
+
+    Ok(ApiResponse {
+        json: json!(info),
+        status: StatusCode::OK,
+    })
+}
+
+/// Sets gateway distribution of a network and reassigns its devices.
+pub(crate) async fn set_gateway_distribution(
+    appstate: &AppState,
+    gateway_state: &Mutex<GatewayMap>,
+    mut network: WireguardNetwork<Id>,
+    data: GatewayDistributionData,
+) -> Result<(WireguardNetwork<Id>, GatewayDistributionInfo), WebError> {
+    data.validate(&appstate.pool).await?;
+
+    let was_sharded = network.gateway_peer_sharding;
+    network.gateway_distribution_policy = data.policy;
+    network.gateway_peer_sharding = data.peer_sharding;
+
+    let mut transaction = appstate.pool.begin().await?;
+    network.save(&mut *transaction).await?;
+    LocationGateway::delete_for_location(&mut *transaction, network.id).await?;
+    for gateway in data.gateways {
+        LocationGateway::new(
+            network.id,
+            gateway.hostname,
+            gateway
+                .endpoint
+                .filter(|endpoint| !endpoint.trim().is_empty()),
+            gateway.weight,
+            gateway.group_id,
+        )
+        .save(&mut *transaction)
+        .await?;
+    }
+    transaction.commit().await?;
+
+    let connected = gateway_state
+        .lock()
+        .expect("Failed to acquire gateway state lock")
+        .connected_hostnames(network.id);
+    rebalance_location(&appstate.pool, network.id, &connected, true).await?;
+    let info = GatewayDistributionInfo::fetch(&appstate.pool, &network, &connected).await?;
+
+    // send sharded peers to gateways
+    if was_sharded || network.gateway_peer_sharding {
+        let mut conn = appstate.pool.acquire().await?;
+        let peers = network.get_peers(&mut *conn).await?;
+        let maybe_firewall_config = network.try_get_firewall_config(&mut conn).await?;
+        appstate.send_wireguard_event(GatewayEvent::NetworkModified(
+            network.id,
+            network.clone(),
+            peers,
+            maybe_firewall_config,
+        ));
+    }
+
+    Ok((network, info))
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct LocationGeofenceData {
+    /// ISO 3166-1 alpha-2 codes of countries; all countries are allowed if empty
+    #[serde(default)]
+    pub allowed_countries: Vec<String>,
+    #[serde(default)]
+    pub blocked_countries: Vec<String>,
+}
+
+impl LocationGeofenceData {
+    /// Validates country codes and returns them uppercase, sorted and deduplicated.
+    fn normalize(self) -> Result<(Vec<String>, Vec<String>), WebError> {
+        let normalize = |codes: Vec<String>| {
+            let mut codes = codes
+                .into_iter()
+                .map(|code| {
+                    let code = code.trim().to_ascii_uppercase();
+                    if is_country_code(&code) {
+                        Ok(code)
+                    } else {
+                        Err(WebError::BadRequest(format!("Invalid country code {code}")))
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            codes.sort_unstable();
+            codes.dedup();
+            Ok::<_, WebError>(codes)
+        };
+        let allowed = normalize(self.allowed_countries)?;
+        let blocked = normalize(self.blocked_countries)?;
+        if let Some(code) = allowed.iter().find(|code| blocked.contains(code)) {
+            return Err(WebError::BadRequest(format!(
+                "Country {code} can't be both allowed and blocked"
+            )));
+        }
+
+        Ok((allowed, blocked))
+    }
+}
+
+/// Geofence of a location along with GeoIP database availability.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct LocationGeofenceInfo {
+    pub allowed_countries: Vec<String>,
+    pub blocked_countries: Vec<String>,
+    /// Without GeoIP database countries are unknown, so authorizations are denied if there are
+    /// allowed countries
+    pub geoip_available: bool,
+}
+
+impl From<LocationGeofence> for LocationGeofenceInfo {
+    fn from(geofence: LocationGeofence) -> Self {
+        Self {
+            allowed_countries: geofence.allowed_countries,
+            blocked_countries: geofence.blocked_countries,
+            geoip_available: geoip_database().is_some(),
+        }
+    }
+}
+
+/// Returns geofence of a network
+///
+/// # Returns
+/// - `LocationGeofenceInfo` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/network/{network_id}/geofence",
+    params(
+        ("network_id" = i64, description = "ID of network")
+    ),
+    responses(
+        (status = 200, description = "Geofence of a network.", body = LocationGeofenceInfo),
+        (status = 401, description = "Unauthorized to view geofence.", body = ApiResponse, example = json!({"msg": "Session is required"})),
+        (status = 403, description = "You don't have permission to view geofence.", body = ApiResponse, example = json!({"msg": "access denied"})),
+        (status = 404, description = "Network not found.", body = ApiResponse, example = json!({"msg": "network not found"})),
+        (status = 500, description = "Unable to fetch geofence.", body = ApiResponse, example = json!({"msg": "Internal server error"}))
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub(crate) async fn location_geofence(
+    _role: AdminRole,
+    State(appstate): State<AppState>,
+    Path(network_id): Path<i64>,
+) -> ApiResult {
+    let network = find_network(network_id, &appstate.pool).await?;
+    let geofence = LocationGeofence::for_location(&appstate.pool, network.id).await?;
+
+    Ok(ApiResponse {
+        json: json!(LocationGeofenceInfo::from(geofence)),
+        status: StatusCode::OK,
+    })
+}
+
+/// Modify geofence of a network
+///
+/// Desktop client MFA authorizations are allowed or denied based on the country of the public
+/// address the client connects from, looked up in GeoIP database. Denied authorizations are
+/// recorded in the activity log.
+///
+/// # Returns
+/// - `LocationGeofenceInfo` object
+///
+/// - `WebError` if error occurs
+#[utoipa::path(
+    put,
+    path = "/api/v1/network/{network_id}/geofence",
+    params(
+        ("network_id" = i64, description = "ID of network")
+    ),
+    request_body = LocationGeofenceData,
+    responses(
+        (status = 200, description = "Successfully modified geofence.", body = LocationGeofenceInfo),
+        (status = 400, description = "Invalid country codes.", body = ApiResponse, example = json!({"msg": "Invalid country code XYZ"})),
+        (status = 401, description = "Unauthorized to modify geofence.", body = ApiResponse, example = json!({"msg": "Session is required"})),
+        (status = 403, description = "You don't have permission to modify geofence.", body = ApiResponse, example = json!({"msg": "access denied"})),
+        (status = 404, description = "Network not found.", body = ApiResponse, example = json!({"msg": "network not found"})),
+        (status = 500, description = "Unable to modify geofence.", body = ApiResponse, example = json!({"msg": "Internal server error"}))
+    ),
+    security(
+        ("cookie" = []),
+        ("api_token" = [])
+    )
+)]
+pub(crate) async fn modify_location_geofence(
+    _role: AdminRole,
+    State(appstate): State<AppState>,
+    Path(network_id): Path<i64>,
+    session: SessionInfo,
+    Json(data): Json<LocationGeofenceData>,
+) -> ApiResult {
+    debug!(
+        "User {} updating geofence of network {network_id}",
+        session.user.username
+    );
+    let network = find_network(network_id, &appstate.pool).await?;
+    let (allowed_countries, blocked_countries) = data.normalize()?;
+    let geofence = LocationGeofence {
+        location_id: network.id,
+        allowed_countries,
+        blocked_countries,
+    };
+    geofence.save(&appstate.pool).await?;
+    if geofence.is_restricted() && geoip_database().is_none() {
+        warn!(
+            "Geofence of network {network} is set, but GeoIP database is not available, \
+            countries of clients can't be determined"
+        );
+    }
+    info!(
+        "User {} updated geofence of network {network_id}",
+        session.user.username
+    );
+
+    Ok(ApiResponse {
+        json: json!(LocationGeofenceInfo::from(geofence)),
         status: StatusCode::OK,
     })
 }
@@ -1498,7 +2823,8 @@ pub(crate) async fn network_stats(
 /// # Returns
 /// Returns an `WireguardNetworkStats` based on stats from all networks in requested time period
 pub(crate) async fn networks_overview_stats(
-    _role: AdminRole,
+    _role: AuditorRole,
+    redaction: Redaction,
     State(appstate): State<AppState>,
     Query(query_from): Query<QueryFrom>,
 ) -> ApiResult {
@@ -1507,8 +2833,10 @@ pub(crate) async fn networks_overview_stats(
     let aggregation = get_aggregation(from)?;
     let all_networks_stats = networks_stats(&appstate.pool, &from, &aggregation).await?;
     debug!("Finished processing networks overview stats");
+    let mut json = json!(all_networks_stats);
+    redaction.apply(&mut json);
     Ok(ApiResponse {
-        json: json!(all_networks_stats),
+        json,
         status: StatusCode::OK,
     })
 }
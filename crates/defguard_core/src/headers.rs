@@ -10,7 +10,11 @@ use sqlx::PgPool;
 use tokio::sync::mpsc::UnboundedSender;
 use uaparser::{Client, Parser, UserAgentParser};
 
-use crate::{db::User, handlers::mail::send_new_device_login_email};
+use crate::{
+    db::User,
+    geoip::{GeoIpDatabase, geoip_database},
+    handlers::mail::send_new_device_login_email,
+};
 
 pub(crate) const CONTENT_SECURITY_POLICY_HEADER_NAME: HeaderName =
     HeaderName::from_static("content-security-policy");
@@ -61,6 +65,35 @@ pub(crate) fn get_user_agent_device(user_agent_client: &Client) -> String {
     format!("{device_type}, OS: {device_os}")
 }
 
+/// Human-readable device name, e.g. "Firefox on Ubuntu" or "Mobile Safari on iOS (Apple iPhone)".
+#[must_use]
+pub(crate) fn get_user_agent_device_name(user_agent_client: &Client) -> String {
+    let mut name = format!(
+        "{} on {}",
+        user_agent_client.user_agent.family, user_agent_client.os.family
+    );
+    let device = &user_agent_client.device;
+    let hardware = match (&device.brand, &device.model) {
+        (Some(brand), Some(model)) => Some(format!("{brand} {model}")),
+        (None, Some(model)) => Some(model.to_string()),
+        _ if device.family != "Other" => Some(device.family.to_string()),
+        _ => None,
+    };
+    if let Some(hardware) = hardware {
+        name.push_str(&format!(" ({hardware})"));
+    }
+
+    name
+}
+
+/// Operating system family from a user agent, if recognized.
+#[must_use]
+pub(crate) fn get_user_agent_os(user_agent: &str) -> Option<String> {
+    let client = USER_AGENT_PARSER.parse(user_agent);
+    let family = client.os.family;
+    (family != "Other").then(|| family.to_string())
+}
+
 fn get_user_agent_device_login_data(
     user_id: Id,
     ip_address: String,
@@ -88,6 +121,17 @@ fn get_user_agent_device_login_data(
     )
 }
 
+/// Approximate location of an IP address as country code and city, if they're in the database.
+fn ip_location<'a>(
+    database: Option<&'a GeoIpDatabase>,
+    ip_address: &str,
+) -> (Option<&'a str>, Option<&'a str>) {
+    match (database, ip_address.parse()) {
+        (Some(database), Ok(ip)) => (database.country(ip), database.city(ip)),
+        _ => (None, None),
+    }
+}
+
 pub(crate) async fn check_new_device_login(
     pool: &PgPool,
     mail_tx: &UnboundedSender<Mail>,
@@ -97,8 +141,10 @@ pub(crate) async fn check_new_device_login(
     event_type: String,
     agent: Client<'_>,
 ) -> Result<(), TemplateError> {
+    let (country, city) = ip_location(geoip_database(), &ip_address);
     let device_login_event =
         get_user_agent_device_login_data(user.id, ip_address, event_type, &agent);
+    let device_name = get_user_agent_device_name(&agent);
 
     if let Ok(Some(created_device_login_event)) = device_login_event
         .check_if_device_already_logged_in(pool)
@@ -109,9 +155,37 @@ pub(crate) async fn check_new_device_login(
             mail_tx,
             session,
             created_device_login_event.created,
+            &device_name,
+            country,
+            city,
         )
         .await?;
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ip_location() {
+        let database = GeoIpDatabase::parse(include_str!("../tests/fixtures/geoip.csv"));
+
+        assert_eq!(
+            ip_location(Some(&database), "1.0.0.10"),
+            (Some("AU"), Some("South Brisbane"))
+        );
+        assert_eq!(
+            ip_location(Some(&database), "2001:200::1"),
+            (Some("JP"), Some("Tokyo"))
+        );
+        // cities are optional
+        assert_eq!(ip_location(Some(&database), "2.17.0.1"), (Some("PL"), None));
+        assert_eq!(ip_location(Some(&database), "3.0.0.1"), (None, None));
+        assert_eq!(ip_location(Some(&database), "invalid"), (None, None));
+        // nothing is known without GeoIP database
+        assert_eq!(ip_location(None, "1.0.0.10"), (None, None));
+    }
+}
@@ -0,0 +1,979 @@
+//! Export and import of a whole instance.
+//!
+//! Users (including password hashes and MFA secrets), groups, VPN locations (including private
+//! keys), devices and settings are exported to a JSON file, which can be imported into another
+//! database, e.g. to move a deployment or to merge two instances.
+//!
+//! Objects are matched by their natural keys rather than IDs: groups and VPN locations by name,
+//! users by username and devices by public key. What happens to an object which already exists is
+//! decided by [`ConflictPolicy`]. Some conflicts can't be resolved by overwriting, e.g. a user
+//! whose email is used by another user; such objects are rejected. Group memberships and allowed
+//! groups are merged, while device addresses taken by other devices are reassigned.
+//!
+//! The import runs in a single transaction, which is committed only if verification of imported
+//! objects passes. Security keys, API tokens, sessions and stats aren't exported.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt,
+    net::IpAddr,
+};
+
+use anyhow::{Context, bail};
+use chrono::{NaiveDateTime, Utc};
+use defguard_common::{
+    config::{ConflictPolicy, ExportArgs, ImportArgs},
+    db::{
+        Id, NoId,
+        models::{MFAMethod, Settings},
+    },
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+
+use crate::{
+    db::{Device, Group, User, WireguardNetwork, models::device::WireguardNetworkDevice},
+    enterprise::db::models::enterprise_settings::ClientTrafficPolicy,
+};
+
+/// Version of the export file format.
+pub const EXPORT_VERSION: u32 = 1;
+
+/// Contents of an export file.
+#[derive(Deserialize, Serialize)]
+pub struct InstanceExport {
+    pub version: u32,
+    pub exported_at: NaiveDateTime,
+    pub settings: Option<Settings>,
+    pub groups: Vec<GroupExport>,
+    pub users: Vec<UserExport>,
+    pub networks: Vec<NetworkExport>,
+    pub devices: Vec<DeviceExport>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct GroupExport {
+    pub name: String,
+    pub is_admin: bool,
+    pub client_traffic_policy: Option<ClientTrafficPolicy>,
+}
+
+impl From<Group<Id>> for GroupExport {
+    fn from(group: Group<Id>) -> Self {
+        Self {
+            name: group.name,
+            is_admin: group.is_admin,
+            client_traffic_policy: group.client_traffic_policy,
+        }
+    }
+}
+
+impl GroupExport {
+    fn apply<I>(&self, group: &mut Group<I>) {
+        group.is_admin = self.is_admin;
+        group.client_traffic_policy = self.client_traffic_policy;
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct UserExport {
+    pub username: String,
+    pub password_hash: Option<String>,
+    pub last_name: String,
+    pub first_name: String,
+    pub email: String,
+    pub phone: Option<String>,
+    pub is_active: bool,
+    pub mfa_enabled: bool,
+    pub mfa_method: MFAMethod,
+    pub totp_enabled: bool,
+    pub totp_secret: Option<Vec<u8>>,
+    pub email_mfa_enabled: bool,
+    pub email_mfa_secret: Option<Vec<u8>>,
+    pub recovery_codes: Vec<String>,
+    pub from_ldap: bool,
+    pub ldap_pass_randomized: bool,
+    pub ldap_rdn: Option<String>,
+    pub ldap_user_path: Option<String>,
+    pub openid_sub: Option<String>,
+    pub mail_language: Option<String>,
+    /// Names of groups the user is a member of
+    pub groups: Vec<String>,
+}
+
+impl UserExport {
+    fn new(user: User<Id>, mut groups: Vec<String>) -> Self {
+        groups.sort();
+        Self {
+            username: user.username,
+            password_hash: user.password_hash,
+            last_name: user.last_name,
+            first_name: user.first_name,
+            email: user.email,
+            phone: user.phone,
+            is_active: user.is_active,
+            mfa_enabled: user.mfa_enabled,
+            mfa_method: user.mfa_method,
+            totp_enabled: user.totp_enabled,
+            totp_secret: user.totp_secret,
+            email_mfa_enabled: user.email_mfa_enabled,
+            email_mfa_secret: user.email_mfa_secret,
+            recovery_codes: user.recovery_codes,
+            from_ldap: user.from_ldap,
+            ldap_pass_randomized: user.ldap_pass_randomized,
+            ldap_rdn: user.ldap_rdn,
+            ldap_user_path: user.ldap_user_path,
+            openid_sub: user.openid_sub,
+            mail_language: user.mail_language,
+            groups,
+        }
+    }
+
+    fn apply<I>(&self, user: &mut User<I>) {
+        user.password_hash.clone_from(&self.password_hash);
+        user.last_name.clone_from(&self.last_name);
+        user.first_name.clone_from(&self.first_name);
+        user.email.clone_from(&self.email);
+        user.phone.clone_from(&self.phone);
+        user.is_active = self.is_active;
+        user.mfa_enabled = self.mfa_enabled;
+        user.mfa_method = self.mfa_method.clone();
+        user.totp_enabled = self.totp_enabled;
+        user.totp_secret.clone_from(&self.totp_secret);
+        user.email_mfa_enabled = self.email_mfa_enabled;
+        user.email_mfa_secret.clone_from(&self.email_mfa_secret);
+        user.recovery_codes.clone_from(&self.recovery_codes);
+        user.from_ldap = self.from_ldap;
+        user.ldap_pass_randomized = self.ldap_pass_randomized;
+        user.ldap_rdn.clone_from(&self.ldap_rdn);
+        user.ldap_user_path.clone_from(&self.ldap_user_path);
+        user.openid_sub.clone_from(&self.openid_sub);
+        user.mail_language.clone_from(&self.mail_language);
+    }
+}
+
+#[derive(Clone, Deserialize, PartialEq, Serialize)]
+pub struct NetworkExport {
+    pub network: WireguardNetwork<NoId>,
+    /// Private key isn't serialized with the location
+    pub private_key: String,
+    /// Names of groups allowed to use the location
+    pub allowed_groups: Vec<String>,
+}
+
+impl NetworkExport {
+    fn new(network: WireguardNetwork<Id>, mut allowed_groups: Vec<String>) -> Self {
+        allowed_groups.sort();
+        let mut network = network.as_noid();
+        Self {
+            private_key: std::mem::take(&mut network.prvkey),
+            network,
+            allowed_groups,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct DeviceExport {
+    /// Owner is referenced by `owner`, `user_id` is ignored on import
+    pub device: Device<NoId>,
+    pub owner: String,
+    pub networks: Vec<DeviceNetworkExport>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct DeviceNetworkExport {
+    /// Name of the VPN location
+    pub network: String,
+    pub wireguard_ips: Vec<IpAddr>,
+    pub preshared_key: Option<String>,
+    pub is_authorized: bool,
+    pub authorized_at: Option<NaiveDateTime>,
+}
+
+/// Exports all users, groups, VPN locations, devices and settings.
+pub async fn export_instance(pool: &PgPool) -> Result<InstanceExport, anyhow::Error> {
+    let settings = Settings::get(pool).await?;
+    let groups = Group::all(pool)
+        .await?
+        .into_iter()
+        .map(GroupExport::from)
+        .collect();
+
+    let mut users = Vec::new();
+    let mut usernames = HashMap::new();
+    for user in User::all(pool).await? {
+        let groups = user.member_of_names(pool).await?;
+        usernames.insert(user.id, user.username.clone());
+        users.push(UserExport::new(user, groups));
+    }
+
+    let mut networks = Vec::new();
+    let mut network_names = HashMap::new();
+    for network in WireguardNetwork::all(pool).await? {
+        let allowed_groups = network.fetch_allowed_groups(pool).await?;
+        network_names.insert(network.id, network.name.clone());
+        networks.push(NetworkExport::new(network, allowed_groups));
+    }
+
+    let mut devices = Vec::new();
+    for device in Device::all(pool).await? {
+        let network_devices = WireguardNetworkDevice::find_by_device(pool, device.id)
+            .await?
+            .unwrap_or_default();
+        let networks = network_devices
+            .into_iter()
+            .filter_map(|network_device| {
+                Some(DeviceNetworkExport {
+                    network: network_names
+                        .get(&network_device.wireguard_network_id)?
+                        .clone(),
+                    wireguard_ips: network_device.wireguard_ips,
+                    preshared_key: network_device.preshared_key,
+                    is_authorized: network_device.is_authorized,
+                    authorized_at: network_device.authorized_at,
+                })
+            })
+            .collect();
+        let Some(owner) = usernames.get(&device.user_id) else {
+            warn!("Skipping export of device {device}, its owner doesn't exist");
+            continue;
+        };
+        devices.push(DeviceExport {
+            owner: owner.clone(),
+            device: device.as_noid(),
+            networks,
+        });
+    }
+
+    Ok(InstanceExport {
+        version: EXPORT_VERSION,
+        exported_at: Utc::now().naive_utc(),
+        settings,
+        groups,
+        users,
+        networks,
+        devices,
+    })
+}
+
+/// Handles the `export` command.
+pub async fn run_export(pool: &PgPool, args: &ExportArgs) -> Result<(), anyhow::Error> {
+    let export = export_instance(pool).await?;
+    let contents = serde_json::to_vec_pretty(&export)?;
+    tokio::fs::write(&args.output, contents)
+        .await
+        .with_context(|| format!("Failed to write {}", args.output.display()))?;
+    info!(
+        "Exported {} users, {} groups, {} locations and {} devices to {}",
+        export.users.len(),
+        export.groups.len(),
+        export.networks.len(),
+        export.devices.len(),
+        args.output.display()
+    );
+    Ok(())
+}
+
+/// Names of imported objects of one kind, by import result.
+#[derive(Debug, Default)]
+pub struct ImportedObjects {
+    pub created: Vec<String>,
+    pub updated: Vec<String>,
+    /// Already existing, kept intact
+    pub skipped: Vec<String>,
+    /// Not imported due to a conflict which can't be resolved
+    pub rejected: Vec<String>,
+}
+
+impl ImportedObjects {
+    fn is_written(&self, name: &str) -> bool {
+        self.created.iter().chain(&self.updated).any(|n| n == name)
+    }
+
+    fn is_rejected(&self, name: &str) -> bool {
+        self.rejected.iter().any(|n| n == name)
+    }
+}
+
+impl fmt::Display for ImportedObjects {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} created, {} updated, {} skipped, {} rejected",
+            self.created.len(),
+            self.updated.len(),
+            self.skipped.len(),
+            self.rejected.len()
+        )
+    }
+}
+
+/// Result of an import along with the verification report.
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub policy: ConflictPolicy,
+    pub dry_run: bool,
+    pub settings_imported: bool,
+    pub groups: ImportedObjects,
+    pub users: ImportedObjects,
+    pub networks: ImportedObjects,
+    pub devices: ImportedObjects,
+    /// Objects which already exist or can't be imported as they are
+    pub conflicts: Vec<String>,
+    /// Device addresses which were taken by other devices and have been reassigned
+    pub readdressed: Vec<String>,
+    /// Differences between the export and the database found after the import
+    pub mismatches: Vec<String>,
+}
+
+impl ImportReport {
+    fn new(policy: ConflictPolicy, dry_run: bool) -> Self {
+        Self {
+            policy,
+            dry_run,
+            ..Default::default()
+        }
+    }
+
+    /// Whether the import can be committed.
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.mismatches.is_empty()
+            && (self.policy != ConflictPolicy::Fail || self.conflicts.is_empty())
+    }
+
+    fn conflict(&mut self, conflict: String) {
+        debug!("Import conflict: {conflict}");
+        self.conflicts.push(conflict);
+    }
+
+    fn mismatch(&mut self, mismatch: String) {
+        warn!("Import verification failed: {mismatch}");
+        self.mismatches.push(mismatch);
+    }
+}
+
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Settings: {}",
+            if self.settings_imported {
+                "imported"
+            } else {
+                "kept"
+            }
+        )?;
+        writeln!(f, "Groups: {}", self.groups)?;
+        writeln!(f, "Users: {}", self.users)?;
+        writeln!(f, "Locations: {}", self.networks)?;
+        writeln!(f, "Devices: {}", self.devices)?;
+        for (title, lines) in [
+            ("Conflicts", &self.conflicts),
+            ("Reassigned device addresses", &self.readdressed),
+            ("Verification failures", &self.mismatches),
+        ] {
+            if !lines.is_empty() {
+                writeln!(f, "{title}:")?;
+                for line in lines {
+                    writeln!(f, "- {line}")?;
+                }
+            }
+        }
+        if self.dry_run {
+            writeln!(f, "Dry run, no changes have been made")
+        } else if self.is_ok() {
+            writeln!(f, "Import succeeded")
+        } else {
+            writeln!(f, "Import failed, no changes have been made")
+        }
+    }
+}
+
+/// Imports an export into the database. Changes are committed only if the import has been
+/// verified and it isn't a dry run.
+pub async fn import_instance(
+    pool: &PgPool,
+    export: &InstanceExport,
+    policy: ConflictPolicy,
+    dry_run: bool,
+) -> Result<ImportReport, anyhow::Error> {
+    if export.version != EXPORT_VERSION {
+        bail!(
+            "Unsupported export version {}, expected {EXPORT_VERSION}",
+            export.version
+        );
+    }
+    let mut report = ImportReport::new(policy, dry_run);
+    let mut transaction = pool.begin().await?;
+
+    import_settings(&mut transaction, export, &mut report).await?;
+    import_groups(&mut transaction, export, &mut report).await?;
+    import_users(&mut transaction, export, &mut report).await?;
+    import_networks(&mut transaction, export, &mut report).await?;
+    import_devices(&mut transaction, export, &mut report).await?;
+    verify(&mut transaction, export, &mut report).await?;
+
+    if dry_run || !report.is_ok() {
+        transaction.rollback().await?;
+    } else {
+        transaction.commit().await?;
+        info!("Imported instance exported at {}", export.exported_at);
+    }
+    Ok(report)
+}
+
+/// Handles the `import` command.
+pub async fn run_import(pool: &PgPool, args: &ImportArgs) -> Result<ImportReport, anyhow::Error> {
+    let contents = tokio::fs::read(&args.input)
+        .await
+        .with_context(|| format!("Failed to read {}", args.input.display()))?;
+    let export: InstanceExport = serde_json::from_slice(&contents)
+        .with_context(|| format!("Invalid export file {}", args.input.display()))?;
+    import_instance(pool, &export, args.on_conflict, args.dry_run).await
+}
+
+async fn import_settings(
+    transaction: &mut PgConnection,
+    export: &InstanceExport,
+    report: &mut ImportReport,
+) -> Result<(), anyhow::Error> {
+    let Some(settings) = &export.settings else {
+        return Ok(());
+    };
+    // settings always exist, so they're imported only if overwriting is allowed
+    if report.policy != ConflictPolicy::Overwrite {
+        report.conflict("settings already exist".into());
+        return Ok(());
+    }
+    let current = Settings::get(&mut *transaction)
+        .await?
+        .context("Settings don't exist")?;
+    let mut settings = settings.clone();
+    // instance identity isn't exported
+    settings.uuid = current.uuid;
+    settings.validate()?;
+    settings.save(&mut *transaction).await?;
+    report.settings_imported = true;
+    Ok(())
+}
+
+async fn import_groups(
+    transaction: &mut PgConnection,
+    export: &InstanceExport,
+    report: &mut ImportReport,
+) -> Result<(), anyhow::Error> {
+    for exported in &export.groups {
+        let name = exported.name.clone();
+        if let Some(mut group) = Group::find_by_name(&mut *transaction, &name).await? {
+            report.conflict(format!("group {name} already exists"));
+            if report.policy == ConflictPolicy::Overwrite {
+                exported.apply(&mut group);
+                group.save(&mut *transaction).await?;
+                report.groups.updated.push(name);
+            } else {
+                report.groups.skipped.push(name);
+            }
+        } else {
+            let mut group = Group::new(name.clone());
+            exported.apply(&mut group);
+            group.save(&mut *transaction).await?;
+            report.groups.created.push(name);
+        }
+    }
+    Ok(())
+}
+
+async fn import_users(
+    transaction: &mut PgConnection,
+    export: &InstanceExport,
+    report: &mut ImportReport,
+) -> Result<(), anyhow::Error> {
+    for exported in &export.users {
+        let username = exported.username.clone();
+        let existing = User::find_by_username(&mut *transaction, &username).await?;
+        // email has to be unique, it can't be taken over from another user
+        if let Some(other) = User::find_by_email(&mut *transaction, &exported.email).await? {
+            if existing.as_ref().is_none_or(|user| user.id != other.id) {
+                report.conflict(format!(
+                    "user {username}: email {} is used by user {}",
+                    exported.email, other.username
+                ));
+                report.users.rejected.push(username);
+                continue;
+            }
+        }
+
+        let user = if let Some(mut user) = existing {
+            report.conflict(format!("user {username} already exists"));
+            if report.policy != ConflictPolicy::Overwrite {
+                report.users.skipped.push(username);
+                continue;
+            }
+            exported.apply(&mut user);
+            user.save(&mut *transaction).await?;
+            report.users.updated.push(username);
+            user
+        } else {
+            let mut user = User::new(
+                username.clone(),
+                None,
+                exported.last_name.clone(),
+                exported.first_name.clone(),
+                exported.email.clone(),
+                exported.phone.clone(),
+            );
+            exported.apply(&mut user);
+            let user = user.save(&mut *transaction).await?;
+            report.users.created.push(username);
+            user
+        };
+
+        // memberships are merged, so users keep groups they have on this instance
+        for name in &exported.groups {
+            if let Some(group) = Group::find_by_name(&mut *transaction, name).await? {
+                user.add_to_group(&mut *transaction, &group).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Finds a VPN location by name; names aren't unique, so ambiguous names are reported.
+async fn find_network(
+    transaction: &mut PgConnection,
+    name: &str,
+) -> Result<Result<Option<WireguardNetwork<Id>>, String>, anyhow::Error> {
+    let mut networks = WireguardNetwork::find_by_name(&mut *transaction, name)
+        .await?
+        .unwrap_or_default();
+    if networks.len() > 1 {
+        return Ok(Err(format!(
+            "there are {} locations named {name}",
+            networks.len()
+        )));
+    }
+    Ok(Ok(networks.pop()))
+}
+
+async fn import_networks(
+    transaction: &mut PgConnection,
+    export: &InstanceExport,
+    report: &mut ImportReport,
+) -> Result<(), anyhow::Error> {
+    for exported in &export.networks {
+        let name = exported.network.name.clone();
+        let existing = match find_network(&mut *transaction, &name).await? {
+            Ok(existing) => existing,
+            Err(conflict) => {
+                report.conflict(format!("location {name}: {conflict}"));
+                report.networks.rejected.push(name);
+                continue;
+            }
+        };
+        let mut network = exported.network.clone();
+        network.prvkey.clone_from(&exported.private_key);
+
+        let created = existing.is_none();
+        let network = if let Some(existing) = existing {
+            report.conflict(format!("location {name} already exists"));
+            if report.policy != ConflictPolicy::Overwrite {
+                report.networks.skipped.push(name);
+                continue;
+            }
+            let mut network = network.with_id(existing.id);
+            network.save(&mut *transaction).await?;
+            report.networks.updated.push(name);
+            network
+        } else {
+            let network = network.save(&mut *transaction).await?;
+            report.networks.created.push(name);
+            network
+        };
+
+        // allowed groups are merged, so access granted on this instance isn't revoked;
+        // no allowed groups means that all groups are allowed
+        let mut allowed_groups: BTreeSet<_> = network
+            .fetch_allowed_groups(&mut *transaction)
+            .await?
+            .into_iter()
+            .collect();
+        if created || !allowed_groups.is_empty() {
+            allowed_groups.extend(exported.allowed_groups.iter().cloned());
+        }
+        network
+            .set_allowed_groups(transaction, allowed_groups.into_iter().collect())
+            .await?;
+    }
+    Ok(())
+}
+
+async fn import_devices(
+    transaction: &mut PgConnection,
+    export: &InstanceExport,
+    report: &mut ImportReport,
+) -> Result<(), anyhow::Error> {
+    for exported in &export.devices {
+        let pubkey = exported.device.wireguard_pubkey.clone();
+        let Some(owner) = User::find_by_username(&mut *transaction, &exported.owner).await? else {
+            report.conflict(format!(
+                "device {pubkey}: owner {} doesn't exist",
+                exported.owner
+            ));
+            report.devices.rejected.push(pubkey);
+            continue;
+        };
+        let mut device = exported.device.clone();
+        device.user_id = owner.id;
+
+        let device =
+            if let Some(existing) = Device::find_by_pubkey(&mut *transaction, &pubkey).await? {
+                report.conflict(format!(
+                    "device {} with public key {pubkey} already exists",
+                    existing.name
+                ));
+                if report.policy != ConflictPolicy::Overwrite {
+                    report.devices.skipped.push(pubkey);
+                    continue;
+                }
+                let mut device = device.with_id(existing.id);
+                device.save(&mut *transaction).await?;
+                report.devices.updated.push(pubkey);
+                device
+            } else {
+                let device = device.save(&mut *transaction).await?;
+                report.devices.created.push(pubkey);
+                device
+            };
+
+        for exported_network in &exported.networks {
+            let Ok(Some(network)) =
+                find_network(&mut *transaction, &exported_network.network).await?
+            else {
+                continue;
+            };
+            import_network_device(transaction, &device, &network, exported_network, report).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Adds an imported device to a VPN location, reassigning addresses taken by other devices.
+async fn import_network_device(
+    transaction: &mut PgConnection,
+    device: &Device<Id>,
+    network: &WireguardNetwork<Id>,
+    exported: &DeviceNetworkExport,
+    report: &mut ImportReport,
+) -> Result<(), anyhow::Error> {
+    let mut network_device = if network
+        .can_assign_ips(transaction, &exported.wireguard_ips, Some(device.id))
+        .await
+        .is_ok()
+    {
+        let network_device =
+            WireguardNetworkDevice::new(network.id, device.id, exported.wireguard_ips.clone());
+        network_device.insert(&mut *transaction).await?;
+        network_device
+    } else {
+        let network_device = device
+            .assign_next_network_ip(transaction, network, None, None)
+            .await?;
+        report.readdressed.push(format!(
+            "device {} in location {}: {:?} -> {:?}",
+            device.name, network.name, exported.wireguard_ips, network_device.wireguard_ips
+        ));
+        network_device
+    };
+    network_device
+        .preshared_key
+        .clone_from(&exported.preshared_key);
+    network_device.is_authorized = exported.is_authorized;
+    network_device.authorized_at = exported.authorized_at;
+    network_device.update(&mut *transaction).await?;
+    Ok(())
+}
+
+/// Checks that imported objects exist and match the export.
+async fn verify(
+    transaction: &mut PgConnection,
+    export: &InstanceExport,
+    report: &mut ImportReport,
+) -> Result<(), anyhow::Error> {
+    for exported in &export.groups {
+        let name = &exported.name;
+        match Group::find_by_name(&mut *transaction, name).await? {
+            None => report.mismatch(format!("group {name} doesn't exist")),
+            Some(group) => {
+                if report.groups.is_written(name) && GroupExport::from(group) != *exported {
+                    report.mismatch(format!("group {name} differs from the export"));
+                }
+            }
+        }
+    }
+
+    for exported in &export.users {
+        let username = &exported.username;
+        if report.users.is_rejected(username) {
+            continue;
+        }
+        let Some(user) = User::find_by_username(&mut *transaction, username).await? else {
+            report.mismatch(format!("user {username} doesn't exist"));
+            continue;
+        };
+        let groups = user.member_of_names(&mut *transaction).await?;
+        let missing: Vec<_> = exported
+            .groups
+            .iter()
+            .filter(|group| !groups.contains(group))
+            .collect();
+        if !missing.is_empty() {
+            report.mismatch(format!("user {username} isn't a member of {missing:?}"));
+        }
+        if report.users.is_written(username) {
+            let mut imported = UserExport::new(user, Vec::new());
+            imported.groups.clone_from(&exported.groups);
+            if imported != *exported {
+                report.mismatch(format!("user {username} differs from the export"));
+            }
+        }
+    }
+
+    for exported in &export.networks {
+        let name = &exported.network.name;
+        if report.networks.is_rejected(name) {
+            continue;
+        }
+        let Ok(Some(network)) = find_network(&mut *transaction, name).await? else {
+            report.mismatch(format!("location {name} doesn't exist"));
+            continue;
+        };
+        if report.networks.is_written(name) {
+            let allowed_groups = network.fetch_allowed_groups(&mut *transaction).await?;
+            if !allowed_groups.is_empty()
+                && exported
+                    .allowed_groups
+                    .iter()
+                    .any(|group| !allowed_groups.contains(group))
+            {
+                report.mismatch(format!("location {name} doesn't allow all exported groups"));
+            }
+            let mut imported = NetworkExport::new(network, Vec::new());
+            imported.allowed_groups.clone_from(&exported.allowed_groups);
+            if imported != *exported {
+                report.mismatch(format!("location {name} differs from the export"));
+            }
+        }
+    }
+
+    for exported in &export.devices {
+        let pubkey = &exported.device.wireguard_pubkey;
+        if report.devices.is_rejected(pubkey) {
+            continue;
+        }
+        let Some(device) = Device::find_by_pubkey(&mut *transaction, pubkey).await? else {
+            report.mismatch(format!("device {pubkey} doesn't exist"));
+            continue;
+        };
+        if !report.devices.is_written(pubkey) {
+            continue;
+        }
+        let owner = User::find_by_id(&mut *transaction, device.user_id).await?;
+        if owner.is_none_or(|owner| owner.username != exported.owner) {
+            report.mismatch(format!("device {pubkey} isn't owned by {}", exported.owner));
+        }
+        let device_id = device.id;
+        let mut imported = device.as_noid();
+        imported.user_id = exported.device.user_id;
+        if imported != exported.device {
+            report.mismatch(format!("device {pubkey} differs from the export"));
+        }
+        for exported_network in &exported.networks {
+            let Ok(Some(network)) =
+                find_network(&mut *transaction, &exported_network.network).await?
+            else {
+                continue;
+            };
+            let network_device =
+                WireguardNetworkDevice::find(&mut *transaction, device_id, network.id).await?;
+            if network_device.is_none_or(|network_device| {
+                network_device.is_authorized != exported_network.is_authorized
+                    || network_device.preshared_key != exported_network.preshared_key
+            }) {
+                report.mismatch(format!(
+                    "device {pubkey} in location {} differs from the export",
+                    network.name
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use defguard_common::db::setup_pool;
+    use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+    use super::*;
+    use crate::db::models::device::DeviceType;
+
+    #[sqlx::test]
+    async fn test_export_import(_: PgPoolOptions, options: PgConnectOptions) {
+        let pool = setup_pool(options).await;
+
+        let group = Group::new("staff").save(&pool).await.unwrap();
+        let user = User::new(
+            "hpotter",
+            Some("pass123"),
+            "Potter",
+            "Harry",
+            "h.potter@hogwart.edu.uk",
+            None,
+        )
+        .save(&pool)
+        .await
+        .unwrap();
+        user.add_to_group(&pool, &group).await.unwrap();
+        let mut network = WireguardNetwork::default();
+        network.name = "office".into();
+        network.address = vec!["10.1.1.1/24".parse().unwrap()];
+        network.prvkey = "private".into();
+        let network = network.save(&pool).await.unwrap();
+        let mut transaction = pool.begin().await.unwrap();
+        network
+            .set_allowed_groups(&mut transaction, vec!["staff".into()])
+            .await
+            .unwrap();
+        transaction.commit().await.unwrap();
+        let device = Device::new(
+            "laptop".into(),
+            "key".into(),
+            user.id,
+            DeviceType::User,
+            None,
+            true,
+        )
+        .save(&pool)
+        .await
+        .unwrap();
+        let ip: IpAddr = "10.1.1.2".parse().unwrap();
+        let mut network_device = WireguardNetworkDevice::new(network.id, device.id, [ip]);
+        network_device.is_authorized = true;
+        network_device.insert(&pool).await.unwrap();
+
+        let export = export_instance(&pool).await.unwrap();
+        let json = serde_json::to_string(&export).unwrap();
+        let export: InstanceExport = serde_json::from_str(&json).unwrap();
+        assert_eq!(export.networks[0].private_key, "private");
+        let exported_user = export
+            .users
+            .iter()
+            .find(|user| user.username == "hpotter")
+            .unwrap();
+        assert_eq!(exported_user.password_hash, user.password_hash);
+        assert_eq!(exported_user.groups, ["staff"]);
+
+        // everything already exists
+        let report = import_instance(&pool, &export, ConflictPolicy::Fail, false)
+            .await
+            .unwrap();
+        assert!(!report.is_ok());
+        assert!(report.mismatches.is_empty());
+        assert_eq!(report.users.skipped.len(), export.users.len());
+        let report = import_instance(&pool, &export, ConflictPolicy::Skip, false)
+            .await
+            .unwrap();
+        assert!(report.is_ok(), "{report}");
+        assert!(!report.settings_imported);
+
+        // changed objects are restored only if overwriting
+        let mut changed = User::find_by_username(&pool, "hpotter")
+            .await
+            .unwrap()
+            .unwrap();
+        changed.first_name = "Harold".into();
+        changed.save(&pool).await.unwrap();
+        import_instance(&pool, &export, ConflictPolicy::Skip, false)
+            .await
+            .unwrap();
+        let user = User::find_by_id(&pool, user.id).await.unwrap().unwrap();
+        assert_eq!(user.first_name, "Harold");
+        let report = import_instance(&pool, &export, ConflictPolicy::Overwrite, false)
+            .await
+            .unwrap();
+        assert!(report.is_ok(), "{report}");
+        assert!(report.settings_imported);
+        assert!(report.users.updated.contains(&"hpotter".to_string()));
+        let user = User::find_by_id(&pool, user.id).await.unwrap().unwrap();
+        assert_eq!(user.first_name, "Harry");
+
+        // removed objects are recreated, address taken by another device is reassigned
+        Device::find_by_id(&pool, device.id)
+            .await
+            .unwrap()
+            .unwrap()
+            .delete(&pool)
+            .await
+            .unwrap();
+        let other = Device::new(
+            "phone".into(),
+            "other key".into(),
+            user.id,
+            DeviceType::User,
+            None,
+            true,
+        )
+        .save(&pool)
+        .await
+        .unwrap();
+        WireguardNetworkDevice::new(network.id, other.id, [ip])
+            .insert(&pool)
+            .await
+            .unwrap();
+
+        let report = import_instance(&pool, &export, ConflictPolicy::Skip, true)
+            .await
+            .unwrap();
+        assert_eq!(report.devices.created, ["key"]);
+        assert!(
+            Device::find_by_pubkey(&pool, "key")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let report = import_instance(&pool, &export, ConflictPolicy::Skip, false)
+            .await
+            .unwrap();
+        assert!(report.is_ok(), "{report}");
+        assert_eq!(report.readdressed.len(), 1);
+        let device = Device::find_by_pubkey(&pool, "key").await.unwrap().unwrap();
+        assert_eq!(device.user_id, user.id);
+        let network_device = WireguardNetworkDevice::find(&pool, device.id, network.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(network_device.is_authorized);
+        assert_ne!(network_device.wireguard_ips, [ip]);
+        assert!(network.address[0].contains(network_device.wireguard_ips[0]));
+
+        // email of another user can't be taken over
+        let mut export = export;
+        let mut clone = export.users[0].clone();
+        clone.username = "clone".into();
+        export.users.push(clone);
+        let report = import_instance(&pool, &export, ConflictPolicy::Overwrite, false)
+            .await
+            .unwrap();
+        assert!(report.is_ok(), "{report}");
+        assert_eq!(report.users.rejected, ["clone"]);
+        assert!(
+            User::find_by_username(&pool, "clone")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,386 @@
+//! This module exports complete user and device inventories for compliance snapshots, as well as
+//! login histories of users. Rows are streamed from the database as CSV or NDJSON (one JSON object
+//! per line), limited to selected columns.
+
+use std::convert::Infallible;
+
+use chrono::NaiveDateTime;
+use defguard_common::db::Id;
+use serde_json::{Map, Value};
+use sqlx::{Error as SqlxError, PgPool, query_as};
+use tokio::{spawn, sync::mpsc};
+use tokio_stream::{Stream, StreamExt, wrappers::ReceiverStream};
+
+use crate::db::models::login_history::LoginRecord;
+
+// Number of rendered rows buffered before waiting for the client to receive them
+const EXPORT_BUFFER: usize = 64;
+
+/// Columns of user inventory, in default order.
+pub const USER_COLUMNS: &[&str] = &[
+    "id",
+    "username",
+    "first_name",
+    "last_name",
+    "email",
+    "phone",
+    "is_active",
+    "enrollment_pending",
+    "mfa_enabled",
+    "mfa_method",
+    "from_ldap",
+    "groups",
+    "devices",
+    "last_handshake",
+];
+
+/// Columns of device inventory, in default order.
+pub const DEVICE_COLUMNS: &[&str] = &[
+    "id",
+    "name",
+    "owner",
+    "device_type",
+    "wireguard_pubkey",
+    "description",
+    "created",
+    "configured",
+    "addresses",
+    "last_handshake",
+];
+
+/// Columns of login history, in default order.
+pub const LOGIN_HISTORY_COLUMNS: &[&str] = &[
+    "id",
+    "timestamp",
+    "source",
+    "method",
+    "success",
+    "ip",
+    "device",
+    "location",
+    "proxy",
+    "message",
+];
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Ndjson,
+}
+
+impl ExportFormat {
+    #[must_use]
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv",
+            Self::Ndjson => "application/x-ndjson",
+        }
+    }
+
+    #[must_use]
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Ndjson => "ndjson",
+        }
+    }
+}
+
+/// User with groups, number of devices and the latest handshake of any of them.
+#[derive(Debug, Serialize)]
+pub struct InventoryUser {
+    pub id: Id,
+    pub username: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: String,
+    pub phone: Option<String>,
+    pub is_active: bool,
+    pub enrollment_pending: bool,
+    pub mfa_enabled: bool,
+    pub mfa_method: String,
+    pub from_ldap: bool,
+    pub groups: Vec<String>,
+    pub devices: i64,
+    pub last_handshake: Option<NaiveDateTime>,
+}
+
+/// Device with its owner, addresses assigned in each location and the latest handshake.
+#[derive(Debug, Serialize)]
+pub struct InventoryDevice {
+    pub id: Id,
+    pub name: String,
+    pub owner: String,
+    pub device_type: String,
+    pub wireguard_pubkey: String,
+    pub description: Option<String>,
+    pub created: NaiveDateTime,
+    pub configured: bool,
+    /// Location name to device addresses in that location
+    pub addresses: Value,
+    pub last_handshake: Option<NaiveDateTime>,
+}
+
+/// Renders inventory rows in requested format, limited to selected columns.
+#[derive(Debug)]
+pub struct RowRenderer {
+    format: ExportFormat,
+    columns: Vec<&'static str>,
+}
+
+impl RowRenderer {
+    /// Select comma-separated `columns` from `available` ones, or all of them if not specified.
+    pub fn new(
+        format: ExportFormat,
+        columns: Option<&str>,
+        available: &'static [&'static str],
+    ) -> Result<Self, String> {
+        let columns = match columns {
+            Some(columns) => columns
+                .split(',')
+                .map(|column| {
+                    let column = column.trim();
+                    available
+                        .iter()
+                        .find(|available| **available == column)
+                        .copied()
+                        .ok_or_else(|| format!("Unknown column {column}"))
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            None => available.to_vec(),
+        };
+
+        Ok(Self { format, columns })
+    }
+
+    /// CSV header line, NDJSON has no header.
+    fn header(&self) -> Option<String> {
+        match self.format {
+            ExportFormat::Csv => Some(self.columns.join(",") + "\n"),
+            ExportFormat::Ndjson => None,
+        }
+    }
+
+    fn render<T: Serialize>(&self, row: &T) -> String {
+        let Ok(Value::Object(mut fields)) = serde_json::to_value(row) else {
+            return String::new();
+        };
+        match self.format {
+            ExportFormat::Csv => {
+                let line = self
+                    .columns
+                    .iter()
+                    .map(|column| csv_field(fields.get(*column).unwrap_or(&Value::Null)))
+                    .collect::<Vec<_>>()
+                    .join(",");
+                line + "\n"
+            }
+            ExportFormat::Ndjson => {
+                let selected: Map<String, Value> = self
+                    .columns
+                    .iter()
+                    .map(|column| {
+                        (
+                            (*column).to_string(),
+                            fields.remove(*column).unwrap_or_default(),
+                        )
+                    })
+                    .collect();
+                Value::Object(selected).to_string() + "\n"
+            }
+        }
+    }
+}
+
+/// Plain text representation of a value: arrays are comma-separated, objects are rendered as
+/// semicolon-separated `key=value` pairs.
+fn plain_value(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(string) => string.clone(),
+        Value::Array(items) => items.iter().map(plain_value).collect::<Vec<_>>().join(","),
+        Value::Object(fields) => fields
+            .iter()
+            .map(|(key, value)| format!("{key}={}", plain_value(value)))
+            .collect::<Vec<_>>()
+            .join(";"),
+        other => other.to_string(),
+    }
+}
+
+/// CSV field quoted if it contains separators, quotes or line breaks.
+fn csv_field(value: &Value) -> String {
+    let text = plain_value(value);
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
+
+/// Send rendered rows to the client until all are sent or the client disconnects.
+async fn send_rows<S, T>(
+    mut rows: S,
+    renderer: &RowRenderer,
+    tx: &mpsc::Sender<Result<String, Infallible>>,
+) where
+    S: Stream<Item = Result<T, SqlxError>> + Unpin,
+    T: Serialize,
+{
+    if let Some(header) = renderer.header() {
+        if tx.send(Ok(header)).await.is_err() {
+            return;
+        }
+    }
+    while let Some(row) = rows.next().await {
+        match row {
+            Ok(row) => {
+                if tx.send(Ok(renderer.render(&row))).await.is_err() {
+                    debug!("Client disconnected during inventory export");
+                    return;
+                }
+            }
+            Err(err) => {
+                error!("Failed to fetch inventory export row: {err}");
+                return;
+            }
+        }
+    }
+}
+
+/// Stream user inventory ordered by username.
+#[must_use]
+pub fn export_users(
+    pool: PgPool,
+    renderer: RowRenderer,
+) -> ReceiverStream<Result<String, Infallible>> {
+    let (tx, rx) = mpsc::channel(EXPORT_BUFFER);
+    spawn(async move {
+        let rows = query_as!(
+            InventoryUser,
+            "SELECT u.id, u.username, u.first_name, u.last_name, u.email, u.phone, u.is_active, \
+            u.enrollment_pending, u.mfa_enabled, u.mfa_method::text \"mfa_method!\", u.from_ldap, \
+            ARRAY(SELECT g.name FROM \"group\" g JOIN group_user gu ON gu.group_id = g.id \
+            WHERE gu.user_id = u.id ORDER BY g.name) \"groups!\", \
+            (SELECT count(*) FROM device d WHERE d.user_id = u.id \
+            AND d.device_type = 'user'::device_type) \"devices!\", \
+            (SELECT max(s.latest_handshake) FROM wireguard_peer_stats s \
+            JOIN device d ON d.id = s.device_id WHERE d.user_id = u.id) last_handshake \
+            FROM \"user\" u ORDER BY u.username"
+        )
+        .fetch(&pool);
+        send_rows(rows, &renderer, &tx).await;
+    });
+
+    ReceiverStream::new(rx)
+}
+
+/// Stream device inventory ordered by device ID.
+#[must_use]
+pub fn export_devices(
+    pool: PgPool,
+    renderer: RowRenderer,
+) -> ReceiverStream<Result<String, Infallible>> {
+    let (tx, rx) = mpsc::channel(EXPORT_BUFFER);
+    spawn(async move {
+        let rows = query_as!(
+            InventoryDevice,
+            "SELECT d.id, d.name, u.username owner, d.device_type::text \"device_type!\", \
+            d.wireguard_pubkey, d.description, d.created, d.configured, \
+            (SELECT COALESCE(jsonb_object_agg(n.name, wnd.allowed_ips), '{}'::jsonb) \
+            FROM wireguard_network_device wnd \
+            JOIN wireguard_network n ON n.id = wnd.wireguard_network_id \
+            WHERE wnd.device_id = d.id) \"addresses!\", \
+            (SELECT max(s.latest_handshake) FROM wireguard_peer_stats s \
+            WHERE s.device_id = d.id) last_handshake \
+            FROM device d JOIN \"user\" u ON u.id = d.user_id ORDER BY d.id"
+        )
+        .fetch(&pool);
+        send_rows(rows, &renderer, &tx).await;
+    });
+
+    ReceiverStream::new(rx)
+}
+
+/// Stream login history of a user, the most recent first.
+#[must_use]
+pub fn export_login_history(
+    pool: PgPool,
+    user_id: Id,
+    renderer: RowRenderer,
+) -> ReceiverStream<Result<String, Infallible>> {
+    let (tx, rx) = mpsc::channel(EXPORT_BUFFER);
+    spawn(async move {
+        let rows = query_as!(
+            LoginRecord::<Id>,
+            "SELECT id, user_id, timestamp, source \"source: _\", method, success, ip, device, \
+            location, proxy, message FROM login_history WHERE user_id = $1 \
+            ORDER BY timestamp DESC, id DESC",
+            user_id
+        )
+        .fetch(&pool);
+        send_rows(rows, &renderer, &tx).await;
+    });
+
+    ReceiverStream::new(rx)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Row {
+        name: String,
+        groups: Vec<String>,
+        addresses: Value,
+        last_handshake: Option<NaiveDateTime>,
+    }
+
+    fn row() -> Row {
+        Row {
+            name: "Potter, \"Harry\"".into(),
+            groups: vec!["admin".into(), "R&D".into()],
+            addresses: json!({"home": ["10.2.0.2"], "office": ["10.1.1.2", "fd00::2"]}),
+            last_handshake: None,
+        }
+    }
+
+    const COLUMNS: &[&str] = &["name", "groups", "addresses", "last_handshake"];
+
+    #[test]
+    fn test_render_csv() {
+        let renderer = RowRenderer::new(ExportFormat::Csv, None, COLUMNS).unwrap();
+        assert_eq!(
+            renderer.header().unwrap(),
+            "name,groups,addresses,last_handshake\n"
+        );
+        assert_eq!(
+            renderer.render(&row()),
+            "\"Potter, \"\"Harry\"\"\",\"admin,R&D\",\"home=10.2.0.2;office=10.1.1.2,fd00::2\",\n"
+        );
+    }
+
+    #[test]
+    fn test_render_selected_columns() {
+        let renderer =
+            RowRenderer::new(ExportFormat::Ndjson, Some("groups, name"), COLUMNS).unwrap();
+        assert!(renderer.header().is_none());
+        assert_eq!(
+            serde_json::from_str::<Value>(&renderer.render(&row())).unwrap(),
+            json!({"groups": ["admin", "R&D"], "name": "Potter, \"Harry\""})
+        );
+
+        let renderer = RowRenderer::new(ExportFormat::Csv, Some("groups,name"), COLUMNS).unwrap();
+        assert_eq!(renderer.header().unwrap(), "groups,name\n");
+
+        assert_eq!(
+            RowRenderer::new(ExportFormat::Csv, Some("name,password_hash"), COLUMNS).unwrap_err(),
+            "Unknown column password_hash"
+        );
+    }
+}
@@ -0,0 +1,465 @@
+//! This module builds IP allow-lists for third-party firewalls, mapping users and groups to VPN
+//! addresses of their devices. Allow-lists can be exported as a generic JSON document, as a
+//! PAN-OS User-ID API message registering addresses with tags used by dynamic address groups,
+//! or as an `ipset restore` script.
+//!
+//! If a push URL is configured, the allow-list is regenerated whenever device addresses change
+//! (and periodically, to pick up group membership changes) and pushed if it differs from the
+//! previously pushed one.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Write,
+    net::IpAddr,
+    time::Duration,
+};
+
+use chrono::{NaiveDateTime, Utc};
+use defguard_common::{
+    config::{IpAllowlistFormat, server_config},
+    db::Id,
+};
+use reqwest::{Client, header::CONTENT_TYPE};
+use secrecy::ExposeSecret;
+use serde_json::json;
+use sqlx::{Error as SqlxError, PgExecutor, PgPool, query};
+use tokio::{
+    sync::broadcast::{Receiver, error::RecvError},
+    time::{Instant, sleep_until},
+};
+use utoipa::ToSchema;
+
+use crate::db::GatewayEvent;
+
+// How often the allow-list is regenerated if no device changes were received
+const ALLOWLIST_REFRESH_INTERVAL: Duration = Duration::from_secs(5 * 60);
+// Delay before regenerating the allow-list, so a burst of changes is pushed once
+const ALLOWLIST_DEBOUNCE_DELAY: Duration = Duration::from_secs(5);
+// Timeout of push requests
+const ALLOWLIST_PUSH_TIMEOUT: Duration = Duration::from_secs(30);
+// Maximum length of ipset set name
+const IPSET_NAME_MAX_LENGTH: usize = 31;
+
+/// VPN addresses of user devices.
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema)]
+pub struct AllowlistUser {
+    pub username: String,
+    pub groups: Vec<String>,
+    pub addresses: Vec<IpAddr>,
+}
+
+/// VPN addresses of devices of group members.
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema)]
+pub struct AllowlistGroup {
+    pub name: String,
+    pub addresses: Vec<IpAddr>,
+}
+
+/// Mapping of users and groups to VPN addresses.
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema)]
+pub struct IpAllowlist {
+    pub users: Vec<AllowlistUser>,
+    pub groups: Vec<AllowlistGroup>,
+}
+
+impl IpAllowlist {
+    /// Build allow-list of configured devices of active users, optionally limited to a location.
+    pub async fn current<'e, E>(executor: E, location_id: Option<Id>) -> Result<Self, SqlxError>
+    where
+        E: PgExecutor<'e> + Copy,
+    {
+        let addresses = query!(
+            "SELECT u.username, wnd.wireguard_ips \"wireguard_ips: Vec<IpAddr>\" \
+            FROM wireguard_network_device wnd \
+            JOIN device d ON d.id = wnd.device_id JOIN \"user\" u ON u.id = d.user_id \
+            WHERE u.is_active AND d.configured AND d.device_type = 'user'::device_type \
+            AND ($1::bigint IS NULL OR wnd.wireguard_network_id = $1)",
+            location_id
+        )
+        .fetch_all(executor)
+        .await?;
+        let memberships = query!(
+            "SELECT u.username, g.name FROM group_user gu \
+            JOIN \"group\" g ON g.id = gu.group_id JOIN \"user\" u ON u.id = gu.user_id \
+            WHERE u.is_active"
+        )
+        .fetch_all(executor)
+        .await?;
+
+        let mut users: BTreeMap<String, BTreeSet<IpAddr>> = BTreeMap::new();
+        for row in addresses {
+            users
+                .entry(row.username)
+                .or_default()
+                .extend(row.wireguard_ips);
+        }
+        let mut user_groups: HashMap<String, BTreeSet<String>> = HashMap::new();
+        let mut groups: BTreeMap<String, BTreeSet<IpAddr>> = BTreeMap::new();
+        for row in memberships {
+            let addresses = groups.entry(row.name.clone()).or_default();
+            if let Some(user_addresses) = users.get(&row.username) {
+                addresses.extend(user_addresses);
+            }
+            user_groups
+                .entry(row.username)
+                .or_default()
+                .insert(row.name);
+        }
+
+        Ok(Self {
+            users: users
+                .into_iter()
+                .map(|(username, addresses)| AllowlistUser {
+                    groups: user_groups
+                        .remove(&username)
+                        .map(|groups| groups.into_iter().collect())
+                        .unwrap_or_default(),
+                    username,
+                    addresses: addresses.into_iter().collect(),
+                })
+                .collect(),
+            groups: groups
+                .into_iter()
+                .map(|(name, addresses)| AllowlistGroup {
+                    name,
+                    addresses: addresses.into_iter().collect(),
+                })
+                .collect(),
+        })
+    }
+
+    /// Render the allow-list in a given format.
+    #[must_use]
+    pub fn render(&self, format: IpAllowlistFormat, generated_at: NaiveDateTime) -> String {
+        match format {
+            IpAllowlistFormat::Json => self.to_json(generated_at),
+            IpAllowlistFormat::Panos => self.to_panos(None),
+            IpAllowlistFormat::Ipset => self.to_ipset(),
+        }
+    }
+
+    fn to_json(&self, generated_at: NaiveDateTime) -> String {
+        json!({
+            "generated_at": generated_at,
+            "users": self.users,
+            "groups": self.groups,
+        })
+        .to_string()
+    }
+
+    /// Tags of each address: one tag for the device owner and one for each of their groups.
+    fn address_tags(&self) -> BTreeMap<IpAddr, BTreeSet<String>> {
+        let mut tags: BTreeMap<IpAddr, BTreeSet<String>> = BTreeMap::new();
+        for user in &self.users {
+            for address in &user.addresses {
+                let address_tags = tags.entry(*address).or_default();
+                address_tags.insert(format!("defguard-user-{}", user.username));
+                for group in &user.groups {
+                    address_tags.insert(format!("defguard-group-{group}"));
+                }
+            }
+        }
+        tags
+    }
+
+    /// PAN-OS User-ID API message registering addresses with tags, which dynamic address groups
+    /// can match. Tags registered by a `previous` allow-list which are no longer valid are
+    /// unregistered.
+    fn to_panos(&self, previous: Option<&Self>) -> String {
+        let tags = self.address_tags();
+        let mut message = String::from(
+            "<uid-message><version>2.0</version><type>update</type><payload><register>",
+        );
+        for (address, address_tags) in &tags {
+            write_panos_entry(&mut message, *address, address_tags);
+        }
+        message.push_str("</register>");
+
+        if let Some(previous) = previous {
+            let stale: BTreeMap<IpAddr, BTreeSet<String>> = previous
+                .address_tags()
+                .into_iter()
+                .filter_map(|(address, previous_tags)| {
+                    let stale_tags: BTreeSet<String> = match tags.get(&address) {
+                        Some(current_tags) => {
+                            previous_tags.difference(current_tags).cloned().collect()
+                        }
+                        None => previous_tags,
+                    };
+                    (!stale_tags.is_empty()).then_some((address, stale_tags))
+                })
+                .collect();
+            if !stale.is_empty() {
+                message.push_str("<unregister>");
+                for (address, address_tags) in &stale {
+                    write_panos_entry(&mut message, *address, address_tags);
+                }
+                message.push_str("</unregister>");
+            }
+        }
+
+        message.push_str("</payload></uid-message>");
+        message
+    }
+
+    /// `ipset restore` script with a set of IPv4 addresses for each user and group. Sets of IPv6
+    /// addresses, suffixed with `-v6`, are added only if there are any IPv6 addresses.
+    fn to_ipset(&self) -> String {
+        let mut script = String::new();
+        let users = self
+            .users
+            .iter()
+            .map(|user| (format!("dg-u-{}", user.username), &user.addresses));
+        let groups = self
+            .groups
+            .iter()
+            .map(|group| (format!("dg-g-{}", group.name), &group.addresses));
+        for (name, addresses) in users.chain(groups) {
+            let (ipv4, ipv6): (Vec<&IpAddr>, Vec<&IpAddr>) =
+                addresses.iter().partition(|address| address.is_ipv4());
+            write_ipset(&mut script, &ipset_name(&name, ""), "inet", &ipv4);
+            if !ipv6.is_empty() {
+                write_ipset(&mut script, &ipset_name(&name, "-v6"), "inet6", &ipv6);
+            }
+        }
+        script
+    }
+}
+
+/// Escape text for use in XML content and attribute values.
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+fn write_panos_entry(message: &mut String, address: IpAddr, tags: &BTreeSet<String>) {
+    let _ = write!(message, "<entry ip=\"{address}\"><tag>");
+    for tag in tags {
+        let _ = write!(message, "<member>{}</member>", xml_escape(tag));
+    }
+    message.push_str("</tag></entry>");
+}
+
+/// Valid ipset name: unsupported characters are replaced and the name is truncated to fit
+/// the suffix within the length limit.
+fn ipset_name(name: &str, suffix: &str) -> String {
+    let mut name: String = name
+        .chars()
+        .map(|character| {
+            if character.is_ascii_alphanumeric() || matches!(character, '-' | '_' | '.') {
+                character
+            } else {
+                '_'
+            }
+        })
+        .take(IPSET_NAME_MAX_LENGTH - suffix.len())
+        .collect();
+    name.push_str(suffix);
+    name
+}
+
+fn write_ipset(script: &mut String, name: &str, family: &str, addresses: &[&IpAddr]) {
+    let _ = writeln!(script, "create {name} hash:ip family {family} -exist");
+    let _ = writeln!(script, "flush {name}");
+    for address in addresses {
+        let _ = writeln!(script, "add {name} {address}");
+    }
+}
+
+/// Push the allow-list to the configured URL.
+async fn push_allowlist(
+    client: &Client,
+    allowlist: &IpAllowlist,
+    previous: Option<&IpAllowlist>,
+) -> Result<(), reqwest::Error> {
+    let config = server_config();
+    let Some(url) = &config.ip_allowlist_push_url else {
+        return Ok(());
+    };
+    let token = config
+        .ip_allowlist_push_token
+        .as_ref()
+        .map(|token| token.expose_secret());
+    let request = match config.ip_allowlist_push_format {
+        IpAllowlistFormat::Panos => {
+            let request = client.post(url.clone()).form(&[
+                ("type", "user-id"),
+                ("cmd", allowlist.to_panos(previous).as_str()),
+            ]);
+            match token {
+                Some(token) => request.header("X-PAN-KEY", token),
+                None => request,
+            }
+        }
+        format => {
+            let content_type = if format == IpAllowlistFormat::Json {
+                "application/json"
+            } else {
+                "text/plain"
+            };
+            let request = client
+                .post(url.clone())
+                .header(CONTENT_TYPE, content_type)
+                .body(allowlist.render(format, Utc::now().naive_utc()));
+            match token {
+                Some(token) => request.bearer_auth(token),
+                None => request,
+            }
+        }
+    };
+    request.send().await?.error_for_status()?;
+
+    Ok(())
+}
+
+/// Regenerates the IP allow-list on device changes and pushes it to the configured URL.
+#[instrument(skip_all)]
+pub async fn run_ip_allowlist_publisher(
+    pool: PgPool,
+    mut events_rx: Receiver<GatewayEvent>,
+) -> Result<(), SqlxError> {
+    info!("Starting IP allow-list publisher");
+    let client = Client::builder()
+        .timeout(ALLOWLIST_PUSH_TIMEOUT)
+        .build()
+        .expect("Failed to build HTTP client");
+    // last successfully pushed allow-list
+    let mut pushed: Option<IpAllowlist> = None;
+    let mut deadline = Instant::now();
+
+    loop {
+        tokio::select! {
+            () = sleep_until(deadline) => {
+                let allowlist = IpAllowlist::current(&pool, None).await?;
+                if pushed.as_ref() == Some(&allowlist) {
+                    debug!("IP allow-list hasn't changed, skipping push");
+                } else {
+                    match push_allowlist(&client, &allowlist, pushed.as_ref()).await {
+                        Ok(()) => {
+                            info!(
+                                "Pushed IP allow-list with {} users and {} groups",
+                                allowlist.users.len(),
+                                allowlist.groups.len()
+                            );
+                            pushed = Some(allowlist);
+                        }
+                        // retried on next refresh
+                        Err(err) => error!("Failed to push IP allow-list: {err}"),
+                    }
+                }
+                deadline = Instant::now() + ALLOWLIST_REFRESH_INTERVAL;
+            }
+            event = events_rx.recv() => match event {
+                Ok(
+                    GatewayEvent::DeviceCreated(_)
+                    | GatewayEvent::DeviceModified(_)
+                    | GatewayEvent::DeviceDeleted(_)
+                    | GatewayEvent::NetworkModified(..)
+                    | GatewayEvent::NetworkDeleted(..),
+                )
+                | Err(RecvError::Lagged(_)) => {
+                    deadline = deadline.min(Instant::now() + ALLOWLIST_DEBOUNCE_DELAY);
+                }
+                Ok(_) => {}
+                Err(RecvError::Closed) => {
+                    info!("Gateway events channel closed, stopping IP allow-list publisher");
+                    return Ok(());
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn allowlist() -> IpAllowlist {
+        IpAllowlist {
+            users: vec![
+                AllowlistUser {
+                    username: "hpotter".into(),
+                    groups: vec!["R&D".into()],
+                    addresses: vec!["10.0.0.2".parse().unwrap(), "fd00::2".parse().unwrap()],
+                },
+                AllowlistUser {
+                    username: "rweasley".into(),
+                    groups: Vec::new(),
+                    addresses: vec!["10.0.0.3".parse().unwrap()],
+                },
+            ],
+            groups: vec![AllowlistGroup {
+                name: "R&D".into(),
+                addresses: vec!["10.0.0.2".parse().unwrap(), "fd00::2".parse().unwrap()],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_allowlist_panos() {
+        let allowlist = allowlist();
+        assert_eq!(
+            allowlist.to_panos(None),
+            "<uid-message><version>2.0</version><type>update</type><payload><register>\
+            <entry ip=\"10.0.0.2\"><tag><member>defguard-group-R&amp;D</member>\
+            <member>defguard-user-hpotter</member></tag></entry>\
+            <entry ip=\"10.0.0.3\"><tag><member>defguard-user-rweasley</member></tag></entry>\
+            <entry ip=\"fd00::2\"><tag><member>defguard-group-R&amp;D</member>\
+            <member>defguard-user-hpotter</member></tag></entry>\
+            </register></payload></uid-message>"
+        );
+
+        // user left the group and the other one has no devices anymore
+        let mut current = allowlist.clone();
+        current.users[0].groups.clear();
+        current.users.pop();
+        current.groups.clear();
+        assert_eq!(
+            current.to_panos(Some(&allowlist)),
+            "<uid-message><version>2.0</version><type>update</type><payload><register>\
+            <entry ip=\"10.0.0.2\"><tag><member>defguard-user-hpotter</member></tag></entry>\
+            <entry ip=\"fd00::2\"><tag><member>defguard-user-hpotter</member></tag></entry>\
+            </register><unregister>\
+            <entry ip=\"10.0.0.2\"><tag><member>defguard-group-R&amp;D</member></tag></entry>\
+            <entry ip=\"10.0.0.3\"><tag><member>defguard-user-rweasley</member></tag></entry>\
+            <entry ip=\"fd00::2\"><tag><member>defguard-group-R&amp;D</member></tag></entry>\
+            </unregister></payload></uid-message>"
+        );
+    }
+
+    #[test]
+    fn test_allowlist_ipset() {
+        assert_eq!(
+            allowlist().to_ipset(),
+            "create dg-u-hpotter hash:ip family inet -exist\n\
+            flush dg-u-hpotter\n\
+            add dg-u-hpotter 10.0.0.2\n\
+            create dg-u-hpotter-v6 hash:ip family inet6 -exist\n\
+            flush dg-u-hpotter-v6\n\
+            add dg-u-hpotter-v6 fd00::2\n\
+            create dg-u-rweasley hash:ip family inet -exist\n\
+            flush dg-u-rweasley\n\
+            add dg-u-rweasley 10.0.0.3\n\
+            create dg-g-R_D hash:ip family inet -exist\n\
+            flush dg-g-R_D\n\
+            add dg-g-R_D 10.0.0.2\n\
+            create dg-g-R_D-v6 hash:ip family inet6 -exist\n\
+            flush dg-g-R_D-v6\n\
+            add dg-g-R_D-v6 fd00::2\n"
+        );
+        assert_eq!(
+            ipset_name(&"a".repeat(40), "-v6").len(),
+            IPSET_NAME_MAX_LENGTH
+        );
+    }
+}
@@ -0,0 +1,372 @@
+//! Detection of IP address conflicts between devices in locations.
+//!
+//! Addresses are validated when devices are added or modified, but manual database edits or
+//! changes of location subnets may leave devices with duplicate, reserved or out-of-range
+//! addresses. This module scans address assignments of a location and suggests remediations,
+//! including a free address picked according to the location IP allocation strategy.
+
+use std::{
+    collections::{HashMap, HashSet},
+    net::IpAddr,
+};
+
+use defguard_common::db::Id;
+use ipnetwork::IpNetwork;
+use sqlx::{Error as SqlxError, PgExecutor, query_as};
+use utoipa::ToSchema;
+
+use crate::db::WireguardNetwork;
+
+/// Kind of an address conflict.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum IpConflictKind {
+    /// Address is assigned to more than one device.
+    Duplicate,
+    /// Address is not contained in any of location subnets.
+    OutOfRange,
+    /// Network, broadcast or gateway address of a location subnet.
+    Reserved,
+    /// Device has no address in one of location subnets.
+    MissingAddress,
+}
+
+/// Action resolving an address conflict.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum IpConflictRemediation {
+    /// Replace the address with a free one from the same subnet.
+    Reassign,
+    /// Remove the address, the device already has an address in every subnet.
+    Remove,
+    /// Add an address from the subnet.
+    Assign,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema)]
+pub struct IpConflict {
+    pub kind: IpConflictKind,
+    pub device_id: Id,
+    pub device_name: String,
+    /// Conflicting address, not set for missing addresses.
+    #[schema(value_type = Option<String>)]
+    pub address: Option<IpAddr>,
+    /// Other devices using the same address.
+    pub conflicting_device_ids: Vec<Id>,
+    pub remediation: IpConflictRemediation,
+    /// Free address to use when reassigning or assigning, if the subnet has one.
+    #[schema(value_type = Option<String>)]
+    pub suggested_address: Option<IpAddr>,
+}
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct LocationIpConflicts {
+    pub location_id: Id,
+    pub location_name: String,
+    #[schema(value_type = Vec<String>)]
+    pub address: Vec<IpNetwork>,
+    pub scanned_devices: usize,
+    pub conflicts: Vec<IpConflict>,
+}
+
+/// Addresses assigned to a device in a location.
+#[derive(Debug)]
+pub(crate) struct DeviceAddresses {
+    pub id: Id,
+    pub name: String,
+    pub user_id: Id,
+    pub wireguard_ips: Vec<IpAddr>,
+}
+
+impl DeviceAddresses {
+    async fn all_for_location<'e, E>(executor: E, location_id: Id) -> Result<Vec<Self>, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        query_as!(
+            Self,
+            "SELECT d.id, d.name, d.user_id, wnd.wireguard_ips \"wireguard_ips: Vec<IpAddr>\" \
+            FROM wireguard_network_device wnd JOIN device d ON d.id = wnd.device_id \
+            WHERE wnd.wireguard_network_id = $1 ORDER BY d.id",
+            location_id
+        )
+        .fetch_all(executor)
+        .await
+    }
+}
+
+/// Scans address assignments of a location for conflicts.
+pub(crate) async fn location_conflicts<'e, E>(
+    executor: E,
+    location: &WireguardNetwork<Id>,
+) -> Result<LocationIpConflicts, SqlxError>
+where
+    E: PgExecutor<'e>,
+{
+    let devices = DeviceAddresses::all_for_location(executor, location.id).await?;
+
+    Ok(LocationIpConflicts {
+        location_id: location.id,
+        location_name: location.name.clone(),
+        address: location.address.clone(),
+        scanned_devices: devices.len(),
+        conflicts: find_conflicts(location, &devices),
+    })
+}
+
+fn is_reserved(subnet: &IpNetwork, ip: IpAddr) -> bool {
+    ip == subnet.network() || ip == subnet.broadcast() || ip == subnet.ip()
+}
+
+/// Finds conflicts between addresses of devices in a location.
+///
+/// The device with the lowest ID keeps a duplicated address. Suggested addresses are unique
+/// within the report, so all remediations can be applied together.
+pub(crate) fn find_conflicts(
+    location: &WireguardNetwork<Id>,
+    devices: &[DeviceAddresses],
+) -> Vec<IpConflict> {
+    let mut owners: HashMap<IpAddr, Vec<Id>> = HashMap::new();
+    for device in devices {
+        for ip in &device.wireguard_ips {
+            owners.entry(*ip).or_default().push(device.id);
+        }
+    }
+    let mut taken: HashSet<IpAddr> = owners.keys().copied().collect();
+    let mut suggest = |subnet: &IpNetwork, user_id: Id| {
+        let ip = location
+            .ip_allocation_strategy
+            .candidate_ips(subnet, user_id)
+            .find(|ip| !is_reserved(subnet, *ip) && !taken.contains(ip))?;
+        taken.insert(ip);
+        Some(ip)
+    };
+
+    let mut conflicts = Vec::new();
+    for device in devices {
+        let conflict = |kind, address: Option<IpAddr>, remediation, suggested_address| {
+            let conflicting_device_ids = address
+                .and_then(|ip| owners.get(&ip))
+                .map(|ids| ids.iter().copied().filter(|id| *id != device.id).collect())
+                .unwrap_or_default();
+            IpConflict {
+                kind,
+                device_id: device.id,
+                device_name: device.name.clone(),
+                address,
+                conflicting_device_ids,
+                remediation,
+                suggested_address,
+            }
+        };
+        let mut out_of_range: Vec<IpAddr> = device
+            .wireguard_ips
+            .iter()
+            .copied()
+            .filter(|ip| !location.address.iter().any(|subnet| subnet.contains(*ip)))
+            .collect();
+
+        for subnet in &location.address {
+            let ips = device
+                .wireguard_ips
+                .iter()
+                .filter(|ip| subnet.contains(**ip));
+            let mut valid = false;
+            for ip in ips {
+                let kind = if is_reserved(subnet, *ip) {
+                    IpConflictKind::Reserved
+                } else if owners[ip].first() != Some(&device.id) {
+                    IpConflictKind::Duplicate
+                } else {
+                    valid = true;
+                    continue;
+                };
+                let suggested = suggest(subnet, device.user_id);
+                conflicts.push(conflict(
+                    kind,
+                    Some(*ip),
+                    IpConflictRemediation::Reassign,
+                    suggested,
+                ));
+                valid = true;
+            }
+            if valid {
+                continue;
+            }
+            // move an out-of-range address of the same family to this subnet
+            let suggested = suggest(subnet, device.user_id);
+            if let Some(index) = out_of_range
+                .iter()
+                .position(|ip| ip.is_ipv4() == subnet.is_ipv4())
+            {
+                let ip = out_of_range.remove(index);
+                conflicts.push(conflict(
+                    IpConflictKind::OutOfRange,
+                    Some(ip),
+                    IpConflictRemediation::Reassign,
+                    suggested,
+                ));
+            } else {
+                conflicts.push(conflict(
+                    IpConflictKind::MissingAddress,
+                    None,
+                    IpConflictRemediation::Assign,
+                    suggested,
+                ));
+            }
+        }
+
+        for ip in out_of_range {
+            conflicts.push(conflict(
+                IpConflictKind::OutOfRange,
+                Some(ip),
+                IpConflictRemediation::Remove,
+                None,
+            ));
+        }
+    }
+
+    conflicts
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::db::models::wireguard::IpAllocationStrategy;
+
+    fn device(id: Id, ips: &[&str]) -> DeviceAddresses {
+        DeviceAddresses {
+            id,
+            name: format!("device{id}"),
+            user_id: 1,
+            wireguard_ips: ips.iter().map(|ip| IpAddr::from_str(ip).unwrap()).collect(),
+        }
+    }
+
+    fn ip(ip: &str) -> Option<IpAddr> {
+        Some(IpAddr::from_str(ip).unwrap())
+    }
+
+    fn location(address: &str) -> WireguardNetwork<Id> {
+        WireguardNetwork {
+            address: address.split(',').map(|net| net.parse().unwrap()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_no_conflicts() {
+        let location = location("10.1.1.1/24,fc00::1/112");
+        let devices = [
+            device(1, &["10.1.1.2", "fc00::2"]),
+            device(2, &["10.1.1.3", "fc00::3"]),
+        ];
+        assert!(find_conflicts(&location, &devices).is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_and_reserved() {
+        let location = location("10.1.1.1/29");
+        let devices = [
+            device(1, &["10.1.1.2"]),
+            device(2, &["10.1.1.2"]),
+            device(3, &["10.1.1.1"]),
+            device(4, &["10.1.1.7"]),
+        ];
+        let conflicts = find_conflicts(&location, &devices);
+        assert_eq!(
+            conflicts,
+            [
+                IpConflict {
+                    kind: IpConflictKind::Duplicate,
+                    device_id: 2,
+                    device_name: "device2".into(),
+                    address: ip("10.1.1.2"),
+                    conflicting_device_ids: vec![1],
+                    remediation: IpConflictRemediation::Reassign,
+                    suggested_address: ip("10.1.1.3"),
+                },
+                IpConflict {
+                    kind: IpConflictKind::Reserved,
+                    device_id: 3,
+                    device_name: "device3".into(),
+                    address: ip("10.1.1.1"),
+                    conflicting_device_ids: Vec::new(),
+                    remediation: IpConflictRemediation::Reassign,
+                    suggested_address: ip("10.1.1.4"),
+                },
+                IpConflict {
+                    kind: IpConflictKind::Reserved,
+                    device_id: 4,
+                    device_name: "device4".into(),
+                    address: ip("10.1.1.7"),
+                    conflicting_device_ids: Vec::new(),
+                    remediation: IpConflictRemediation::Reassign,
+                    suggested_address: ip("10.1.1.5"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_out_of_range_and_missing() {
+        // subnet changed from 10.1.1.0/24 and IPv6 subnet added
+        let location = location("10.2.2.1/30,fc00::1/112");
+        let devices = [
+            device(1, &["10.1.1.2"]),
+            device(2, &["10.2.2.2", "fc00::2", "10.1.1.3"]),
+        ];
+        let conflicts = find_conflicts(&location, &devices);
+        assert_eq!(
+            conflicts
+                .iter()
+                .map(|conflict| (
+                    conflict.kind,
+                    conflict.device_id,
+                    conflict.address,
+                    conflict.remediation,
+                    conflict.suggested_address
+                ))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    IpConflictKind::OutOfRange,
+                    1,
+                    ip("10.1.1.2"),
+                    IpConflictRemediation::Reassign,
+                    None
+                ),
+                (
+                    IpConflictKind::MissingAddress,
+                    1,
+                    None,
+                    IpConflictRemediation::Assign,
+                    ip("fc00::3")
+                ),
+                (
+                    IpConflictKind::OutOfRange,
+                    2,
+                    ip("10.1.1.3"),
+                    IpConflictRemediation::Remove,
+                    None
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_suggestion_follows_strategy() {
+        let mut location = location("10.1.0.1/16");
+        location.ip_allocation_strategy = IpAllocationStrategy::StickyByUser;
+        let devices = [device(1, &["10.1.0.2"]), device(2, &["10.1.0.2"])];
+        let conflicts = find_conflicts(&location, &devices);
+        let subnet = location.address[0];
+        let expected = IpAllocationStrategy::StickyByUser
+            .candidate_ips(&subnet, 1)
+            .find(|ip| !is_reserved(&subnet, *ip) && *ip != devices[0].wireguard_ips[0]);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].suggested_address, expected);
+    }
+}
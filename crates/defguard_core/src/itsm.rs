@@ -0,0 +1,417 @@
+//! This module implements ITSM connectors, which open tickets in ServiceNow or Jira for critical
+//! events: gateways disconnected for too long, expired license and TLS certificates which are
+//! about to expire.
+//!
+//! Tickets opened for ongoing incidents are recorded in the database, so each incident is
+//! reported once. When an incident is over, its record is removed and a new ticket is opened if
+//! the incident happens again.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use defguard_common::db::Id;
+use defguard_mail::templates::SummaryCertificate;
+use reqwest::Client;
+use serde_json::{Value, json};
+use sqlx::{Error as SqlxError, PgPool};
+use thiserror::Error;
+use tokio::time::sleep;
+
+use crate::{
+    db::models::itsm::{ItsmConnector, ItsmConnectorType, ItsmTicket},
+    enterprise::license::get_cached_license,
+    grpc::gateway::map::GatewayMap,
+    security_summary::certificate_expirations,
+};
+
+// How long to sleep between loop iterations
+const ITSM_LOOP_SLEEP: Duration = Duration::from_secs(60);
+// Timeout of requests sent to ITSM systems
+const ITSM_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+// ServiceNow table used if connector doesn't specify one
+const SERVICE_NOW_DEFAULT_TABLE: &str = "incident";
+// Jira issue type used if connector doesn't specify one
+const JIRA_DEFAULT_ISSUE_TYPE: &str = "Task";
+
+#[derive(Debug, Error)]
+pub enum ItsmError {
+    #[error(transparent)]
+    DbError(#[from] SqlxError),
+    #[error("Failed to acquire lock on gateway state map")]
+    GatewayStateMutexError,
+    #[error("Failed to render ticket: {0}")]
+    TemplateError(#[from] tera::Error),
+    #[error("Request failed: {0}")]
+    RequestError(#[from] reqwest::Error),
+    #[error("Ticket {0} missing in response")]
+    MissingTicket(&'static str),
+}
+
+/// Ongoing incident for which a ticket can be opened.
+#[derive(Debug)]
+pub(crate) struct Incident {
+    /// Key identifying the incident while it lasts
+    pub key: String,
+    /// Incident data available in ticket templates
+    pub data: Value,
+}
+
+/// Gateway which is currently disconnected.
+#[derive(Debug)]
+pub(crate) struct DisconnectedGateway {
+    pub location_id: Id,
+    pub location_name: String,
+    pub hostname: String,
+    pub disconnected_at: NaiveDateTime,
+}
+
+/// Gateways which were connected since Core started and are disconnected now.
+fn disconnected_gateways(
+    gateway_state: &Mutex<GatewayMap>,
+) -> Result<Vec<DisconnectedGateway>, ItsmError> {
+    let gateway_state = gateway_state
+        .lock()
+        .map_err(|_| ItsmError::GatewayStateMutexError)?;
+    Ok(gateway_state
+        .as_flattened()
+        .into_values()
+        .flatten()
+        .filter(|gateway| !gateway.connected)
+        .filter_map(|gateway| {
+            Some(DisconnectedGateway {
+                location_id: gateway.network_id,
+                location_name: gateway.network_name,
+                hostname: gateway.hostname,
+                disconnected_at: gateway.disconnected_at?,
+            })
+        })
+        .collect())
+}
+
+/// Incidents ongoing at a given time, for which a connector opens tickets.
+pub(crate) fn connector_incidents(
+    connector: &ItsmConnector<Id>,
+    gateways: &[DisconnectedGateway],
+    license_expired_at: Option<NaiveDateTime>,
+    certificates: &[SummaryCertificate],
+    now: NaiveDateTime,
+) -> Vec<Incident> {
+    let mut incidents = Vec::new();
+
+    if connector.on_gateway_down {
+        let threshold = TimeDelta::minutes(connector.gateway_down_threshold.into());
+        for gateway in gateways {
+            let down_for = now - gateway.disconnected_at;
+            if down_for < threshold {
+                continue;
+            }
+            let (hostname, location) = (&gateway.hostname, &gateway.location_name);
+            incidents.push(Incident {
+                key: format!("gateway_down:{}:{hostname}", gateway.location_id),
+                data: json!({
+                    "event": "gateway_down",
+                    "title": format!("Gateway {hostname} in location {location} is down"),
+                    "details": format!(
+                        "Gateway {hostname} in location {location} has been disconnected since \
+                        {} UTC.",
+                        gateway.disconnected_at.format("%Y-%m-%d %H:%M:%S")
+                    ),
+                    "location": location,
+                    "location_id": gateway.location_id,
+                    "hostname": hostname,
+                    "disconnected_at": gateway.disconnected_at,
+                    "minutes": down_for.num_minutes(),
+                }),
+            });
+        }
+    }
+
+    if connector.on_license_expired {
+        if let Some(expired_at) = license_expired_at {
+            incidents.push(Incident {
+                key: "license_expired".into(),
+                data: json!({
+                    "event": "license_expired",
+                    "title": "Defguard license has expired",
+                    "details": format!(
+                        "Defguard license expired at {} UTC. Renew the license to keep enterprise \
+                        features enabled.",
+                        expired_at.format("%Y-%m-%d %H:%M:%S")
+                    ),
+                    "expired_at": expired_at,
+                }),
+            });
+        }
+    }
+
+    if connector.on_certificate_expiring {
+        let threshold = TimeDelta::days(connector.certificate_expiration_threshold.into());
+        for certificate in certificates {
+            if certificate.expires_at - now >= threshold {
+                continue;
+            }
+            let name = &certificate.name;
+            let expires_at = certificate.expires_at.format("%Y-%m-%d %H:%M:%S");
+            incidents.push(Incident {
+                key: format!("certificate_expiring:{name}"),
+                data: json!({
+                    "event": "certificate_expiring",
+                    "title": format!("Defguard {name} expires soon"),
+                    "details": format!("Defguard {name} expires at {expires_at} UTC."),
+                    "certificate": name,
+                    "expires_at": certificate.expires_at,
+                    "days": (certificate.expires_at - now).num_days(),
+                }),
+            });
+        }
+    }
+
+    incidents
+}
+
+/// Open a ticket for an incident and return its number (ServiceNow) or key (Jira).
+async fn open_ticket(
+    client: &Client,
+    connector: &ItsmConnector<Id>,
+    incident: &Incident,
+) -> Result<String, ItsmError> {
+    let ticket = connector.render(&incident.data)?;
+    let url = connector.url.trim_end_matches('/');
+    match connector.connector_type {
+        ItsmConnectorType::ServiceNow => {
+            let table = connector
+                .ticket_type
+                .as_deref()
+                .unwrap_or(SERVICE_NOW_DEFAULT_TABLE);
+            let mut record = ticket.fields;
+            if let Some(group) = &connector.project {
+                record
+                    .entry("assignment_group")
+                    .or_insert_with(|| group.clone().into());
+            }
+            record.insert("short_description".into(), ticket.summary.into());
+            record.insert("description".into(), ticket.description.into());
+            let response: Value = client
+                .post(format!("{url}/api/now/table/{table}"))
+                .basic_auth(&connector.username, Some(&connector.secret))
+                .json(&record)
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            response["result"]["number"]
+                .as_str()
+                .map(ToString::to_string)
+                .ok_or(ItsmError::MissingTicket("number"))
+        }
+        ItsmConnectorType::Jira => {
+            let issue_type = connector
+                .ticket_type
+                .as_deref()
+                .unwrap_or(JIRA_DEFAULT_ISSUE_TYPE);
+            let mut fields = ticket.fields;
+            fields.insert("project".into(), json!({ "key": connector.project }));
+            fields.insert("issuetype".into(), json!({ "name": issue_type }));
+            fields.insert("summary".into(), ticket.summary.into());
+            fields.insert("description".into(), ticket.description.into());
+            let response: Value = client
+                .post(format!("{url}/rest/api/2/issue"))
+                .basic_auth(&connector.username, Some(&connector.secret))
+                .json(&json!({ "fields": fields }))
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            response["key"]
+                .as_str()
+                .map(ToString::to_string)
+                .ok_or(ItsmError::MissingTicket("key"))
+        }
+    }
+}
+
+/// Open tickets for new incidents and forget tickets of incidents which are over.
+async fn sync_tickets(
+    pool: &PgPool,
+    client: &Client,
+    connector: &ItsmConnector<Id>,
+    incidents: Vec<Incident>,
+) -> Result<(), SqlxError> {
+    let tickets = ItsmTicket::all_for_connector(pool, connector.id).await?;
+    for ticket in &tickets {
+        if !incidents
+            .iter()
+            .any(|incident| incident.key == ticket.incident)
+        {
+            info!(
+                "Incident {} reported in ticket {} by ITSM connector {} is over",
+                ticket.incident, ticket.ticket, connector.name
+            );
+            ticket.clone().delete(pool).await?;
+        }
+    }
+
+    for incident in incidents {
+        if tickets.iter().any(|ticket| ticket.incident == incident.key) {
+            continue;
+        }
+        // failed attempts are retried in the next iteration
+        match open_ticket(client, connector, &incident).await {
+            Ok(ticket) => {
+                info!(
+                    "ITSM connector {} opened ticket {ticket} for incident {}",
+                    connector.name, incident.key
+                );
+                ItsmTicket::new(connector.id, incident.key, ticket)
+                    .save(pool)
+                    .await?;
+            }
+            Err(err) => error!(
+                "ITSM connector {} failed to open ticket for incident {}: {err}",
+                connector.name, incident.key
+            ),
+        }
+    }
+
+    Ok(())
+}
+
+/// Periodically checks for incidents and opens tickets using enabled ITSM connectors.
+#[instrument(skip_all)]
+pub async fn run_itsm_connectors(
+    pool: PgPool,
+    gateway_state: Arc<Mutex<GatewayMap>>,
+) -> Result<(), ItsmError> {
+    info!("Starting ITSM connectors");
+    let client = Client::builder().timeout(ITSM_REQUEST_TIMEOUT).build()?;
+
+    loop {
+        let connectors = ItsmConnector::all_enabled(&pool).await?;
+        if !connectors.is_empty() {
+            debug!(
+                "Checking incidents for {} ITSM connectors",
+                connectors.len()
+            );
+            let now = Utc::now().naive_utc();
+            let gateways = disconnected_gateways(&gateway_state)?;
+            let license_expired_at = get_cached_license()
+                .as_ref()
+                .filter(|license| license.is_expired())
+                .and_then(|license| license.valid_until)
+                .map(|valid_until| valid_until.naive_utc());
+            let certificates = certificate_expirations();
+            for connector in connectors {
+                let incidents = connector_incidents(
+                    &connector,
+                    &gateways,
+                    license_expired_at,
+                    &certificates,
+                    now,
+                );
+                sync_tickets(&pool, &client, &connector, incidents).await?;
+            }
+        }
+
+        // wait till next iteration
+        sleep(ITSM_LOOP_SLEEP).await;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::models::itsm::{DEFAULT_DESCRIPTION_TEMPLATE, DEFAULT_SUMMARY_TEMPLATE};
+
+    #[test]
+    fn test_connector_incidents() {
+        let now = Utc::now().naive_utc();
+        let mut connector = ItsmConnector {
+            id: 1,
+            name: "Service desk".into(),
+            connector_type: ItsmConnectorType::ServiceNow,
+            url: "https://example.service-now.com".into(),
+            username: "defguard".into(),
+            secret: "secret".into(),
+            project: None,
+            ticket_type: None,
+            enabled: true,
+            on_gateway_down: true,
+            gateway_down_threshold: 15,
+            on_license_expired: false,
+            on_certificate_expiring: true,
+            certificate_expiration_threshold: 14,
+            summary_template: DEFAULT_SUMMARY_TEMPLATE.into(),
+            description_template: DEFAULT_DESCRIPTION_TEMPLATE.into(),
+            fields: json!({}),
+        };
+        let gateways = [
+            DisconnectedGateway {
+                location_id: 1,
+                location_name: "Office".into(),
+                hostname: "gateway-1".into(),
+                disconnected_at: now - TimeDelta::minutes(20),
+            },
+            DisconnectedGateway {
+                location_id: 1,
+                location_name: "Office".into(),
+                hostname: "gateway-2".into(),
+                disconnected_at: now - TimeDelta::minutes(5),
+            },
+        ];
+        let certificates = [
+            SummaryCertificate {
+                name: "gRPC server certificate".into(),
+                expires_at: now + TimeDelta::days(7),
+            },
+            SummaryCertificate {
+                name: "Proxy CA certificate".into(),
+                expires_at: now + TimeDelta::days(90),
+            },
+        ];
+        let license_expired_at = Some(now - TimeDelta::days(1));
+
+        let incidents = connector_incidents(
+            &connector,
+            &gateways,
+            license_expired_at,
+            &certificates,
+            now,
+        );
+        let keys: Vec<&str> = incidents
+            .iter()
+            .map(|incident| incident.key.as_str())
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                "gateway_down:1:gateway-1",
+                "certificate_expiring:gRPC server certificate"
+            ]
+        );
+        assert_eq!(incidents[0].data["minutes"], 20);
+        let ticket = connector.render(&incidents[0].data).unwrap();
+        assert_eq!(
+            ticket.summary,
+            "Gateway gateway-1 in location Office is down"
+        );
+
+        connector.on_gateway_down = false;
+        connector.on_license_expired = true;
+        connector.on_certificate_expiring = false;
+        let incidents = connector_incidents(
+            &connector,
+            &gateways,
+            license_expired_at,
+            &certificates,
+            now,
+        );
+        assert_eq!(incidents.len(), 1);
+        assert_eq!(incidents[0].key, "license_expired");
+        assert!(connector_incidents(&connector, &gateways, None, &certificates, now).is_empty());
+    }
+}
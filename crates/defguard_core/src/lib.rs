@@ -10,20 +10,28 @@ use anyhow::anyhow;
 use axum::{
     Extension, Json, Router,
     http::{Request, StatusCode},
+    middleware::from_fn_with_state,
     routing::{delete, get, post, put},
     serve,
 };
+use bytes::Bytes;
 use db::models::{device::DeviceType, wireguard::LocationMfaMode};
 use defguard_common::{
     VERSION,
     auth::claims::{Claims, ClaimsType},
-    config::{DefGuardConfig, GatewayConfigArgs, InitVpnLocationArgs, server_config},
+    config::{
+        DefGuardConfig, GatewayConfigArgs, InitReportingRoleArgs, InitVpnLocationArgs,
+        server_config,
+    },
     db::init_db,
 };
 use defguard_mail::Mail;
 use defguard_version::server::DefguardVersionLayer;
 use defguard_web_ui::{index, svg, web_asset};
 use enterprise::{
+    firewall_history::handlers::{
+        get_firewall_version, list_firewall_versions, rollback_firewall_version,
+    },
     handlers::{
         acl::{
             apply_acl_aliases, apply_acl_rules, create_acl_alias, create_acl_rule,
@@ -40,16 +48,23 @@ use enterprise::{
         openid_login::{auth_callback, get_auth_info},
         openid_providers::{
             add_openid_provider, delete_openid_provider, get_current_openid_provider,
-            test_dirsync_connection,
+            run_directory_sync, test_dirsync_connection,
         },
     },
+    quarantine::handlers::{
+        create_device_quarantine, create_remediation_host, delete_device_quarantine,
+        delete_remediation_host, list_quarantined_devices, list_remediation_hosts,
+    },
     snat::handlers::{
         create_snat_binding, delete_snat_binding, list_snat_bindings, modify_snat_binding,
     },
 };
 use events::ApiEvent;
 use handlers::{
-    activity_log::get_activity_log_events,
+    activity_log::{activity_log_live_tail, get_activity_log_events},
+    api_version::{
+        API_V2_PREFIX, ApiVersion, Deprecation, compatibility_shim, deprecation_headers,
+    },
     auth::disable_user_mfa,
     group::{bulk_assign_to_groups, list_groups_info},
     network_devices::{
@@ -100,6 +115,9 @@ use self::{
     },
     grpc::{WorkerState, gateway::map::GatewayMap},
     handlers::{
+        announcement::{
+            create_announcement, delete_announcement, get_announcement, list_announcements,
+        },
         app_info::get_app_info,
         auth::{
             authenticate, email_mfa_code, email_mfa_disable, email_mfa_enable, email_mfa_init,
@@ -107,26 +125,84 @@ use self::{
             totp_disable, totp_enable, totp_secret, webauthn_end, webauthn_finish, webauthn_init,
             webauthn_start,
         },
+        dashboard::dashboard,
+        device_approval::{approve_device, list_pending_device_approvals, reject_device},
+        dns_leak::{create_dns_canary, report_dns_canary_query, verify_dns_canary},
+        enrollment_sheet::{enrollment_sheet, enrollment_sheets},
+        enrollment_token::{
+            extend_enrollment_token, list_enrollment_tokens, resend_enrollment_token,
+            revoke_enrollment_token,
+        },
         forward_auth::forward_auth,
+        gateway_setup::{create_gateway_setup_link, download_gateway_setup, gateway_deployment},
+        graphql::graphql,
         group::{
             add_group_member, create_group, delete_group, get_group, list_groups, modify_group,
             remove_group_member,
         },
+        helpdesk_password_reset::{
+            confirm_helpdesk_password_reset, list_helpdesk_password_resets,
+            request_helpdesk_password_reset,
+        },
+        inventory::{export_device_inventory, export_user_inventory},
+        ip_allowlist::export_ip_allowlist,
+        itsm::{
+            create_itsm_connector, delete_itsm_connector, get_itsm_connector, list_itsm_connectors,
+            modify_itsm_connector,
+        },
+        jobs::{cancel_job, get_job, list_jobs},
+        location_spec::apply_location,
+        login_banner::{
+            delete_login_banner, get_login_banner, list_login_banner_acknowledgments,
+            set_login_banner,
+        },
+        login_history::{export_user_login_history, list_login_history},
+        lookup::{lookup_endpoint, lookup_ip},
         mail::{send_support_data, test_mail},
+        mail_context::{
+            delete_mail_context, list_localized_templates, list_mail_context, set_mail_context,
+        },
+        mail_queue::{clear_mail_queue, delete_queued_mail, list_queued_mails},
+        mail_template::{
+            get_mail_template, list_mail_templates, preview_mail, restore_mail_template,
+            set_mail_template,
+        },
+        mail_variable::{
+            create_mail_variable, delete_mail_variable, list_mail_variables, modify_mail_variable,
+        },
+        maintenance_window::{
+            create_maintenance_window, delete_maintenance_window, list_maintenance_windows,
+        },
+        notification::{
+            list_notification_rules, list_notifications, mark_notification_read,
+            set_notification_rules,
+        },
         openid_clients::{
             add_openid_client, change_openid_client, change_openid_client_state,
-            delete_openid_client, get_openid_client, list_openid_clients,
+            delete_openid_client, get_openid_client, list_openid_client_tokens,
+            list_openid_clients, revoke_openid_client_token, revoke_openid_client_tokens,
         },
         openid_flow::{
             authorization, discovery_keys, openid_configuration, secure_authorization, token,
             userinfo,
         },
+        route::{create_route, delete_route, get_route, list_routes, modify_route},
+        self_registration::{request_self_registration, verify_self_registration},
+        service_account::{
+            assign_network_device, create_service_account, delete_service_account,
+            get_service_account, list_service_accounts, modify_service_account,
+        },
         settings::{
             get_settings, get_settings_essentials, patch_settings, set_default_branding,
             test_ldap_settings, update_settings,
         },
+        smtp_profile::{
+            create_smtp_profile, delete_smtp_profile, get_smtp_health, list_smtp_profiles,
+            modify_smtp_profile,
+        },
         ssh_authorized_keys::get_authorized_keys,
-        support::{configuration, logs},
+        support::{configuration, database_pools, logs, stats_maintenance},
+        troubleshoot::troubleshoot_device,
         updates::outdated_components,
         user::{
             add_user, change_password, change_self_password, delete_authorized_app,
@@ -138,10 +214,13 @@ use self::{
             add_webhook, change_enabled, change_webhook, delete_webhook, get_webhook, list_webhooks,
         },
         wireguard::{
-            add_device, add_user_devices, create_network, create_network_token, delete_device,
-            delete_network, devices_stats, download_config, gateway_status, get_device,
-            import_network, list_devices, list_networks, list_user_devices, modify_device,
-            modify_network, network_details, network_stats, remove_gateway,
+            add_device, add_user_devices, apply_keepalive_recommendation, create_network,
+            create_network_token, delete_device, delete_network, devices_stats, disconnect_device,
+            download_config, export_network, gateway_distribution, gateway_status, get_device,
+            get_device_endpoint_history, import_network, ip_conflicts, keepalive_recommendation,
+            list_devices, list_networks, list_user_devices, location_geofence, migrate_network,
+            modify_device, modify_gateway_distribution, modify_location_geofence, modify_network,
+            network_details, network_journal, network_stats, remove_gateway,
         },
         worker::{create_job, create_worker_token, job_status, list_workers, remove_worker},
     },
@@ -151,21 +230,45 @@ use crate::{
     version::IncompatibleComponents,
 };
 
+pub mod announcements;
 pub mod appstate;
 pub mod auth;
+pub mod background_jobs;
 pub mod db;
+pub mod device_approval;
+pub mod device_naming;
+pub mod enrollment_sheet;
 pub mod enterprise;
 mod error;
+pub mod event_outbox;
 pub mod events;
+pub mod gateway_deployment;
+pub mod geoip;
 pub mod grpc;
 pub mod handlers;
 pub mod headers;
+pub mod instance_export;
+pub mod inventory;
+pub mod ip_allowlist;
+pub mod ip_conflicts;
+pub mod itsm;
+pub mod load_test;
+pub mod location_spec;
+pub mod migration_preflight;
+pub mod notification_digest;
+pub mod notifications;
+pub mod security_summary;
 pub mod support;
 pub mod updates;
 pub mod utility_thread;
 pub mod version;
 pub mod wg_config;
+pub mod wireguard_keepalive_tuning;
 pub mod wireguard_peer_disconnect;
+pub mod wireguard_session_reconciliation;
+pub mod wireguard_stats_ingest;
+pub mod wireguard_stats_maintenance;
+pub mod wireguard_stats_partitions;
 pub mod wireguard_stats_purge;
 
 #[macro_use]
@@ -187,12 +290,45 @@ mod openapi {
         AddDevice, UserDetails, UserInfo,
         models::device::{ModifyDevice, UserDevice},
     };
+    use defguard_common::db::models::MailCategory;
     use handlers::{
         ApiResponse, EditGroupInfo, GroupInfo, PasswordChange, PasswordChangeSelf,
         SESSION_COOKIE_NAME, StartEnrollmentRequest, Username,
+        announcement::{self, AnnouncementDeliveryReport, AnnouncementDetails, NewAnnouncement},
+        device_approval,
+        dns_leak::{
+            self, DnsCanaryInfo, DnsCanaryQuery, DnsCanaryRequest, DnsLeakResult, DnsLeakStatus,
+            DnsLeakVerifyRequest,
+        },
+        enrollment_sheet::{self, EnrollmentSheetRequest, EnrollmentSheetsRequest},
+        enrollment_token::{
+            self, EnrollmentTokenInfo, EnrollmentTokenStatus, ExtendEnrollmentToken,
+        },
+        gateway_setup::{self, GatewaySetupBundle, GatewaySetupLinkInfo},
         group::{self, BulkAssignToGroupsRequest, Groups},
-        user, wireguard as device, wireguard as network,
-        wireguard::AddDeviceResult,
+        helpdesk_password_reset::{
+            self, HelpdeskPasswordResetConfirmation, HelpdeskPasswordResetData,
+            HelpdeskPasswordResetInfo,
+        },
+        inventory, ip_allowlist,
+        itsm::{self, ItsmConnectorData},
+        jobs, location_spec,
+        login_banner::{self, LoginBannerData},
+        login_history, lookup,
+        mail_context::{self, LocalizedTemplate, MailContextData, TemplateSection},
+        mail_queue::{self, QueuedMailInfo},
+        mail_template::{
+            self, MailPreview, MailPreviewRequest, MailTemplateData, MailTemplateInfo,
+        },
+        mail_variable::{self, MailVariableData},
+        maintenance_window::{self, MaintenanceWindowData},
+        notification::{self, NotificationRuleData},
+        route::{self, RouteData, RouteInfo},
+        self_registration::{self, SelfRegistrationData, SelfRegistrationVerification},
+        service_account::{self, EditServiceAccount, NewServiceAccount},
+        smtp_profile::{self, SmtpHealthInfo, SmtpProfileData, SmtpProfileInfo},
+        troubleshoot, user, wireguard as device, wireguard as network,
+        wireguard::{AddDeviceResult, DeviceEndpointHistory, DisconnectDevice},
     };
     use utoipa::{
         OpenApi,
@@ -200,7 +336,24 @@ mod openapi {
     };
 
     use super::*;
-    use crate::{enterprise::snat::handlers as snat, error::WebError};
+    use crate::{
+        db::models::{
+            device_endpoint_change::DeviceEndpointChange,
+            helpdesk_password_reset::HelpdeskPasswordResetStatus,
+            login_banner::{LoginBanner, LoginBannerAcknowledgment},
+            login_history::{LoginRecord, LoginSource},
+            maintenance_window::MaintenanceWindow,
+            notification::{
+                Notification, NotificationCategory, NotificationChannel, NotificationRule,
+            },
+        },
+        enterprise::{
+            firewall_history::handlers as firewall_history, quarantine::handlers as quarantine,
+            snat::handlers as snat,
+        },
+        error::WebError,
+        gateway_deployment::DeploymentFormat,
+    };
 
     #[derive(OpenApi)]
     #[openapi(
@@ -211,6 +364,12 @@ mod openapi {
             user::get_user,
             user::add_user,
             user::start_enrollment,
+            enrollment_sheet::enrollment_sheet,
+            enrollment_sheet::enrollment_sheets,
+            enrollment_token::list_enrollment_tokens,
+            enrollment_token::resend_enrollment_token,
+            enrollment_token::extend_enrollment_token,
+            enrollment_token::revoke_enrollment_token,
             user::start_remote_desktop_configuration,
             user::username_available,
             user::modify_user,
@@ -218,9 +377,17 @@ mod openapi {
             user::change_self_password,
             user::change_password,
             user::reset_password,
+            helpdesk_password_reset::request_helpdesk_password_reset,
+            helpdesk_password_reset::list_helpdesk_password_resets,
+            helpdesk_password_reset::confirm_helpdesk_password_reset,
             user::delete_security_key,
             user::me,
             user::delete_authorized_app,
+            login_history::list_login_history,
+            login_history::export_user_login_history,
+            // /self_registration
+            self_registration::request_self_registration,
+            self_registration::verify_self_registration,
             // /group
             group::bulk_assign_to_groups,
             group::list_groups_info,
@@ -235,24 +402,139 @@ mod openapi {
             device::add_device,
             device::modify_device,
             device::get_device,
+            device::get_device_endpoint_history,
             device::delete_device,
+            device::disconnect_device,
             device::list_devices,
             device::list_user_devices,
+            // /device_approval
+            device_approval::list_pending_device_approvals,
+            device_approval::approve_device,
+            device_approval::reject_device,
+            // /lookup
+            lookup::lookup_ip,
+            lookup::lookup_endpoint,
+            // /troubleshoot
+            troubleshoot::troubleshoot_device,
             // /network
             network::create_network,
             network::modify_network,
             network::delete_network,
             network::list_networks,
             network::network_details,
+            network::migrate_network,
+            network::export_network,
+            network::network_journal,
+            network::keepalive_recommendation,
+            network::apply_keepalive_recommendation,
+            network::ip_conflicts,
+            network::gateway_distribution,
+            network::modify_gateway_distribution,
+            network::location_geofence,
+            network::modify_location_geofence,
+            gateway_setup::create_gateway_setup_link,
+            gateway_setup::download_gateway_setup,
+            gateway_setup::gateway_deployment,
+            // /dns_leak
+            dns_leak::create_dns_canary,
+            dns_leak::report_dns_canary_query,
+            dns_leak::verify_dns_canary,
+            location_spec::apply_location,
             // /network/{location_id}/snat
 			snat::list_snat_bindings,
 			snat::create_snat_binding,
 			snat::modify_snat_binding,
 			snat::delete_snat_binding,
+            // /network/{location_id}/firewall/version
+            firewall_history::list_firewall_versions,
+            firewall_history::get_firewall_version,
+            firewall_history::rollback_firewall_version,
+            // /quarantine
+            quarantine::list_quarantined_devices,
+            quarantine::create_device_quarantine,
+            quarantine::delete_device_quarantine,
+            quarantine::list_remediation_hosts,
+            quarantine::create_remediation_host,
+            quarantine::delete_remediation_host,
+            // /announcement
+            announcement::list_announcements,
+            announcement::get_announcement,
+            announcement::create_announcement,
+            announcement::delete_announcement,
+            // /service_account
+            service_account::list_service_accounts,
+            service_account::get_service_account,
+            service_account::create_service_account,
+            service_account::modify_service_account,
+            service_account::delete_service_account,
+            service_account::assign_network_device,
+            // /itsm_connector
+            itsm::list_itsm_connectors,
+            itsm::get_itsm_connector,
+            itsm::create_itsm_connector,
+            itsm::modify_itsm_connector,
+            itsm::delete_itsm_connector,
+            // /settings/mail_variable
+            mail_variable::list_mail_variables,
+            mail_variable::create_mail_variable,
+            mail_variable::modify_mail_variable,
+            mail_variable::delete_mail_variable,
+            // /settings/mail_context
+            mail_context::list_localized_templates,
+            mail_context::list_mail_context,
+            mail_context::set_mail_context,
+            mail_context::delete_mail_context,
+            // /settings/mail_templates
+            mail_template::list_mail_templates,
+            mail_template::get_mail_template,
+            mail_template::set_mail_template,
+            mail_template::restore_mail_template,
+            // /mail/preview
+            mail_template::preview_mail,
+            // /settings/smtp_profile
+            smtp_profile::list_smtp_profiles,
+            smtp_profile::create_smtp_profile,
+            smtp_profile::modify_smtp_profile,
+            smtp_profile::delete_smtp_profile,
+            // /mail/health
+            smtp_profile::get_smtp_health,
+            // /network/{location_id}/maintenance
+            maintenance_window::list_maintenance_windows,
+            maintenance_window::create_maintenance_window,
+            maintenance_window::delete_maintenance_window,
+            // /login_banner
+            login_banner::get_login_banner,
+            login_banner::set_login_banner,
+            login_banner::delete_login_banner,
+            login_banner::list_login_banner_acknowledgments,
+            // /mail_queue
+            mail_queue::list_queued_mails,
+            mail_queue::clear_mail_queue,
+            mail_queue::delete_queued_mail,
+            // /notification
+            notification::list_notification_rules,
+            notification::set_notification_rules,
+            notification::list_notifications,
+            notification::mark_notification_read,
+            // /ip_allowlist
+            ip_allowlist::export_ip_allowlist,
+            // /inventory
+            inventory::export_user_inventory,
+            inventory::export_device_inventory,
+            // /route
+            route::list_routes,
+            route::get_route,
+            route::create_route,
+            route::modify_route,
+            route::delete_route,
+            // /jobs
+            jobs::list_jobs,
+            jobs::get_job,
+            jobs::cancel_job,
         ),
         components(
             schemas(
-                ApiResponse, UserInfo, UserDetails, UserDevice, Groups, Username, StartEnrollmentRequest, PasswordChangeSelf, PasswordChange, AddDevice, AddDeviceResult, Device, ModifyDevice, BulkAssignToGroupsRequest, GroupInfo, EditGroupInfo, WebError
+                ApiResponse, UserInfo, UserDetails, UserDevice, Groups, Username, StartEnrollmentRequest, PasswordChangeSelf, PasswordChange, HelpdeskPasswordResetData, HelpdeskPasswordResetConfirmation, HelpdeskPasswordResetInfo, HelpdeskPasswordResetStatus, AddDevice, AddDeviceResult, Device, ModifyDevice, DisconnectDevice, DeviceEndpointHistory, DeviceEndpointChange, BulkAssignToGroupsRequest, GroupInfo, EditGroupInfo, NewAnnouncement, AnnouncementDetails, AnnouncementDeliveryReport, NewServiceAccount, EditServiceAccount, ItsmConnectorData, MailVariableData, MailContextData, LocalizedTemplate, TemplateSection, MailTemplateData, MailTemplateInfo, MailPreviewRequest, MailPreview, SmtpProfileData, SmtpProfileInfo, SmtpHealthInfo, MaintenanceWindowData, MaintenanceWindow, MailCategory, QueuedMailInfo, NotificationRuleData, NotificationRule, Notification, NotificationCategory, NotificationChannel, LoginRecord, LoginSource, LoginBannerData, LoginBanner, LoginBannerAcknowledgment, GatewaySetupLinkInfo, GatewaySetupBundle, DeploymentFormat, RouteData, RouteInfo, SelfRegistrationData, SelfRegistrationVerification, EnrollmentSheetRequest, EnrollmentSheetsRequest, EnrollmentTokenInfo, EnrollmentTokenStatus, ExtendEnrollmentToken, DnsCanaryRequest, DnsCanaryInfo, DnsCanaryQuery, DnsLeakVerifyRequest, DnsLeakStatus, DnsLeakResult, WebError
             ),
         ),
         tags(
@@ -266,6 +548,7 @@ Available actions:
 - change user password.
 - start remote desktop configuratiion
 - trigger enrollment process
+- public self-registration with email verification
             "),
             (name = "group", description = "
 ### Endpoints for managing groups
@@ -282,6 +565,10 @@ Available actions:
 Available actions:
 - list all devices or user devices
 - CRUD mechanism for handling devices.
+- approve or reject devices awaiting approval in locations requiring it
+- look up a device, its owner and session by VPN address
+- look up devices connected from a public address during a time range
+- troubleshoot device connectivity
             "),
             (name = "network", description = "
 ### Endpoints that allow to control your networks.
@@ -289,6 +576,29 @@ Available actions:
 Available actions:
 - list all wireguard networks
 - CRUD mechanism for handling devices.
+            "),
+            (name = "gateway_setup", description = "
+### Endpoints for setting up gateways.
+
+Gateway setup links are one-time links to a bundle with everything needed to run a gateway for a location:
+gateway token, gRPC URL, CA certificate and ready-to-run configuration snippets. Links expire after first use.
+
+Available actions:
+- create gateway setup link
+- download gateway setup bundle
+- render gateway deployment artifact: docker compose service, systemd unit or env file
+            "),
+            (name = "dns_leak", description = "
+### Endpoints for DNS leak tests run by desktop clients.
+
+A client resolves a unique canary hostname while connected to a location. The DNS server
+authoritative for the canary domain reports resolvers which queried it, and the client checks
+if all of them belong to the location.
+
+Available actions:
+- create DNS canary
+- report DNS canary query
+- verify DNS canary
             "),
             (name = "SNAT", description = "
 ### Endpoints that allow you to control user SNAT bindings for your locations.
@@ -299,6 +609,179 @@ Available actions:
 - modify SNAT binding
 - delete SNAT binding
             "),
+            (name = "Firewall", description = "
+### Endpoints that allow you to review and roll back firewall configuration of your locations.
+
+Available actions:
+- list firewall configuration versions sent to gateways
+- view a version and its changes against another version
+- roll back to a previous version
+            "),
+            (name = "Quarantine", description = "
+### Endpoints that allow you to quarantine compromised devices.
+
+Available actions:
+- list quarantined devices
+- place a device in quarantine or release it
+- manage remediation hosts reachable by quarantined devices in a location
+            "),
+            (name = "announcement", description = "
+### Endpoints for broadcasting announcement emails to users.
+
+Available actions:
+- list announcements
+- send or schedule an announcement to a segment of users
+- view delivery report
+- cancel or remove an announcement
+            "),
+            (name = "service_account", description = "
+### Endpoints for managing service accounts.
+
+Service accounts are non-human users used by automation. They can own network devices and API tokens,
+don't count towards the licensed user limit and are skipped by directory synchronization.
+
+Available actions:
+- list service accounts
+- create, modify, disable or remove a service account
+- transfer a network device to a service account
+            "),
+            (name = "itsm_connector", description = "
+### Endpoints for managing ITSM connectors.
+
+ITSM connectors open ServiceNow or Jira tickets for critical events: gateways disconnected for too long,
+expired license and TLS certificates which are about to expire. Ticket fields are rendered from templates.
+
+Available actions:
+- list ITSM connectors
+- create, modify or remove an ITSM connector
+            "),
+            (name = "mail_variable", description = "
+### Endpoints for managing mail variables.
+
+Mail variables hold instance-level values, like company name, support URL or helpdesk phone number,
+which can be referenced in enrollment welcome templates and announcements as `{{ instance.<name> }}`.
+
+Available actions:
+- list mail variables
+- create, modify or remove a mail variable
+            "),
+            (name = "mail_context", description = "
+### Endpoints for managing mail template translations.
+
+Sections of enrollment, desktop configuration and password reset mails can be translated.
+Mails are sent in the recipient's language, falling back to the instance default and English.
+
+Available actions:
+- list localized templates with their built-in texts
+- list, set or remove a translation
+            "),
+            (name = "mail_template", description = "
+### Endpoints for managing mail template overrides.
+
+Built-in mail templates can be replaced with custom Tera templates. Overrides are rendered with
+sample data before they're saved, and built-in templates are used if an override fails to render.
+
+Available actions:
+- list mail templates
+- get, override or restore a mail template
+- preview a mail without sending it
+            "),
+            (name = "smtp_profile", description = "
+### Endpoints for managing SMTP profiles.
+
+SMTP profiles are additional SMTP configurations. Mail categories (e.g. admin alerts or enrollment
+mails) bound to a profile are sent through it instead of the SMTP server configured in settings.
+One profile can be used for failover while the SMTP server configured in settings keeps failing.
+
+Available actions:
+- list SMTP profiles with their mail categories
+- create, modify or remove an SMTP profile
+- check health of the SMTP server and the failover profile
+            "),
+            (name = "maintenance_window", description = "
+### Endpoints for scheduling maintenance windows of locations.
+
+Scheduled gateway downtime is announced to admins by mails with calendar invites. Cancelling a
+maintenance window sends cancellations of the invites.
+
+Available actions:
+- list upcoming maintenance windows of a location
+- schedule or cancel a maintenance window
+            "),
+            (name = "login_banner", description = "
+### Endpoints for managing the login banner.
+
+Login banner, e.g. a legal notice of a government system, is shown before logging in. Each version
+has to be acknowledged once by every user logging in to the web UI and, optionally, by desktop clients
+connecting to locations with MFA.
+
+Available actions:
+- get current login banner
+- set or remove the login banner
+- list users who acknowledged a version of the banner
+            "),
+            (name = "mail_queue", description = "
+### Endpoints for managing the outbound mail queue.
+
+Mails which failed to be delivered because of transient SMTP failures are queued and retried with
+exponential backoff. Mails given up on, after too many attempts or a permanent failure, stay in the
+queue until removed.
+
+Available actions:
+- list queued mails
+- remove a queued mail or clear the whole queue
+            "),
+            (name = "notification", description = "
+### Endpoints for managing admin notifications.
+
+Notification rules choose which alerts an admin receives and whether they're delivered by email,
+as in-app notifications or posted to a webhook. Admins without any rules receive all alerts by email.
+
+Available actions:
+- list or replace notification rules of the current admin
+- list in-app notifications of the current admin
+- mark an in-app notification as read
+            "),
+            (name = "ip_allowlist", description = "
+### Endpoints for exporting IP allow-lists.
+
+IP allow-list maps users and groups to VPN addresses of their devices, so third-party firewalls can match
+traffic by user identity. It can be exported as JSON, PAN-OS User-ID API message or ipset script,
+and pushed to a configured URL whenever it changes.
+
+Available actions:
+- export IP allow-list
+            "),
+            (name = "inventory", description = "
+### Endpoints for exporting user and device inventories.
+
+Inventories are streamed as CSV or NDJSON and can be limited to selected columns,
+e.g. for periodic compliance snapshots.
+
+Available actions:
+- export user inventory with groups, number of devices and the latest handshake
+- export device inventory with addresses in each location and the latest handshake
+            "),
+            (name = "route", description = "
+### Endpoints for managing named routes.
+
+Routes are named lists of networks added to AllowedIPs of device configs in locations they're attached to.
+
+Available actions:
+- list routes
+- create, modify or remove a route
+- attach a route to locations and restrict it to groups
+            "),
+            (name = "jobs", description = "
+### Endpoints for polling long-running operations.
+
+Long-running operations return a background job immediately and continue in background.
+
+Available actions:
+- list recent jobs
+- get job status, progress and result
+- cancel a running job
+            "),
         )
     )]
     pub struct ApiDoc;
@@ -336,6 +819,14 @@ async fn openapi() -> Json<utoipa::openapi::OpenApi> {
     Json(openapi::ApiDoc::openapi())
 }
 
+// Unpaginated user list, replaced by paginated list in API v2
+const USER_LIST_V1: Deprecation = Deprecation {
+    version: ApiVersion::V1,
+    since: 1_766_102_400,
+    sunset: None,
+    successor: Some("/api/v2/user"),
+};
+
 pub fn build_webapp(
     webhook_tx: UnboundedSender<AppEvent>,
     webhook_rx: UnboundedReceiver<AppEvent>,
@@ -348,6 +839,7 @@ pub fn build_webapp(
     event_tx: UnboundedSender<ApiEvent>,
     version: Version,
     incompatible_components: Arc<RwLock<IncompatibleComponents>>,
+    activity_log_messages_tx: Sender<Bytes>,
 ) -> Router {
     let webapp: Router<AppState> = Router::new()
         .route("/", get(index))
@@ -365,6 +857,18 @@ pub fn build_webapp(
             .route("/ssh_authorized_keys", get(get_authorized_keys))
             .route("/api-docs", get(openapi))
             .route("/updates", get(check_new_version))
+            // /self_registration
+            .route("/self_registration", post(request_self_registration))
+            .route("/self_registration/verify", post(verify_self_registration))
+            // /helpdesk_password_reset
+            .route(
+                "/helpdesk_password_reset/confirm",
+                post(confirm_helpdesk_password_reset),
+            )
+            // /dns_leak
+            .route("/dns_leak/canary", post(create_dns_canary))
+            .route("/dns_leak/canary/{id}/verify", post(verify_dns_canary))
+            .route("/dns_leak/query", post(report_dns_canary_query))
             // /auth
             .route("/auth", post(authenticate))
             .route("/auth/logout", post(logout))
@@ -386,9 +890,28 @@ pub fn build_webapp(
             .route("/auth/email/verify", post(email_mfa_code))
             .route("/auth/recovery", post(recovery_code))
             // /user
-            .route("/user", get(list_users).post(add_user))
+            .route(
+                "/user",
+                get(list_users)
+                    .layer(from_fn_with_state(USER_LIST_V1, deprecation_headers))
+                    .post(add_user),
+            )
             .route("/user/{username}", get(get_user))
             .route("/user/{username}/start_enrollment", post(start_enrollment))
+            .route("/user/{username}/enrollment_sheet", post(enrollment_sheet))
+            .route("/user/enrollment_sheets", post(enrollment_sheets))
+            .route(
+                "/user/{username}/enrollment_token",
+                get(list_enrollment_tokens),
+            )
+            .route(
+                "/user/{username}/enrollment_token/{token}",
+                put(extend_enrollment_token).delete(revoke_enrollment_token),
+            )
+            .route(
+                "/user/{username}/enrollment_token/{token}/resend",
+                post(resend_enrollment_token),
+            )
             .route(
                 "/user/{username}/start_desktop",
                 post(start_remote_desktop_configuration),
@@ -399,6 +922,10 @@ pub fn build_webapp(
             .route("/user/change_password", put(change_self_password))
             .route("/user/{username}/password", put(change_password))
             .route("/user/{username}/reset_password", post(reset_password))
+            .route(
+                "/user/{username}/helpdesk_password_reset",
+                get(list_helpdesk_password_resets).post(request_helpdesk_password_reset),
+            )
             // auth keys
             .route(
                 "/user/{username}/auth_key",
@@ -441,6 +968,11 @@ pub fn build_webapp(
                 delete(delete_authorized_app),
             )
             .route("/user/{username}/mfa", delete(disable_user_mfa))
+            .route("/user/{username}/login_history", get(list_login_history))
+            .route(
+                "/user/{username}/login_history/export",
+                get(export_user_login_history),
+            )
             // forward_auth
             .route("/forward_auth", get(forward_auth))
             // group
@@ -458,12 +990,110 @@ pub fn build_webapp(
             // mail
             .route("/mail/test", post(test_mail))
             .route("/mail/support", post(send_support_data))
+            .route("/mail/preview", post(preview_mail))
+            .route("/mail/health", get(get_smtp_health))
+            // announcements
+            .route(
+                "/announcement",
+                get(list_announcements).post(create_announcement),
+            )
+            .route(
+                "/announcement/{id}",
+                get(get_announcement).delete(delete_announcement),
+            )
+            // service accounts
+            .route(
+                "/service_account",
+                get(list_service_accounts).post(create_service_account),
+            )
+            .route(
+                "/service_account/{id}",
+                get(get_service_account)
+                    .put(modify_service_account)
+                    .delete(delete_service_account),
+            )
+            .route(
+                "/service_account/{id}/network_device/{device_id}",
+                put(assign_network_device),
+            )
+            // ITSM connectors
+            .route(
+                "/itsm_connector",
+                get(list_itsm_connectors).post(create_itsm_connector),
+            )
+            .route(
+                "/itsm_connector/{id}",
+                get(get_itsm_connector)
+                    .put(modify_itsm_connector)
+                    .delete(delete_itsm_connector),
+            )
+            // login banner
+            .route(
+                "/login_banner",
+                get(get_login_banner)
+                    .put(set_login_banner)
+                    .delete(delete_login_banner),
+            )
+            .route(
+                "/login_banner/{id}/acknowledgment",
+                get(list_login_banner_acknowledgments),
+            )
+            // mail queue
+            .route(
+                "/mail_queue",
+                get(list_queued_mails).delete(clear_mail_queue),
+            )
+            .route("/mail_queue/{id}", delete(delete_queued_mail))
+            // admin notifications
+            .route(
+                "/notification/rules",
+                get(list_notification_rules).put(set_notification_rules),
+            )
+            .route("/notification", get(list_notifications))
+            .route("/notification/{id}/read", post(mark_notification_read))
+            // IP allow-list
+            .route("/ip_allowlist", get(export_ip_allowlist))
+            // inventory
+            .route("/inventory/users", get(export_user_inventory))
+            .route("/inventory/devices", get(export_device_inventory))
             // settings
             .route(
                 "/settings",
                 get(get_settings).put(update_settings).patch(patch_settings),
             )
             .route("/settings/{id}", put(set_default_branding))
+            .route(
+                "/settings/mail_variable",
+                get(list_mail_variables).post(create_mail_variable),
+            )
+            .route(
+                "/settings/mail_variable/{id}",
+                put(modify_mail_variable).delete(delete_mail_variable),
+            )
+            .route(
+                "/settings/mail_context",
+                get(list_mail_context).put(set_mail_context),
+            )
+            .route(
+                "/settings/mail_context/template",
+                get(list_localized_templates),
+            )
+            .route("/settings/mail_context/{id}", delete(delete_mail_context))
+            .route("/settings/mail_templates", get(list_mail_templates))
+            .route(
+                "/settings/mail_templates/{name}",
+                get(get_mail_template)
+                    .put(set_mail_template)
+                    .delete(restore_mail_template),
+            )
+            .route(
+                "/settings/smtp_profile",
+                get(list_smtp_profiles).post(create_smtp_profile),
+            )
+            .route(
+                "/settings/smtp_profile/{id}",
+                put(modify_smtp_profile).delete(delete_smtp_profile),
+            )
             // settings for frontend
             .route("/settings_essentials", get(get_settings_essentials))
             // enterprise settings
@@ -474,6 +1104,8 @@ pub fn build_webapp(
             // support
             .route("/support/configuration", get(configuration))
             .route("/support/logs", get(logs))
+            .route("/support/database-pools", get(database_pools))
+            .route("/support/stats-maintenance", get(stats_maintenance))
             // webhooks
             .route("/webhook", post(add_webhook).get(list_webhooks))
             .route(
@@ -486,7 +1118,11 @@ pub fn build_webapp(
             // ldap
             .route("/ldap/test", get(test_ldap_settings))
             // activity log
-            .route("/activity_log", get(get_activity_log_events)),
+            .route("/activity_log", get(get_activity_log_events))
+            .route(
+                "/activity_log/live",
+                get(activity_log_live_tail).layer(Extension(activity_log_messages_tx)),
+            ),
     );
 
     // Enterprise features
@@ -506,7 +1142,8 @@ pub fn build_webapp(
         "/api/v1",
         Router::new()
             .route("/enterprise_info", get(check_enterprise_info))
-            .route("/test_directory_sync", get(test_dirsync_connection)),
+            .route("/test_directory_sync", get(test_dirsync_connection))
+            .route("/directory_sync", post(run_directory_sync)),
     );
 
     // activity log stream
@@ -536,6 +1173,14 @@ pub fn build_webapp(
                         .post(change_openid_client_state)
                         .delete(delete_openid_client),
                 )
+                .route(
+                    "/{client_id}/tokens",
+                    get(list_openid_client_tokens).delete(revoke_openid_client_tokens),
+                )
+                .route(
+                    "/{client_id}/tokens/{token_id}",
+                    delete(revoke_openid_client_token),
+                )
                 .route("/authorize", get(authorization).post(secure_authorization))
                 .route("/token", post(token))
                 .route("/userinfo", get(userinfo)),
@@ -575,8 +1220,29 @@ pub fn build_webapp(
                 "/device/{device_id}",
                 put(modify_device).get(get_device).delete(delete_device),
             )
+            .route("/device/{device_id}/disconnect", post(disconnect_device))
+            .route(
+                "/device/{device_id}/endpoint_history",
+                get(get_device_endpoint_history),
+            )
+            .route(
+                "/device/{device_id}/quarantine",
+                post(create_device_quarantine).delete(delete_device_quarantine),
+            )
+            .route("/quarantine", get(list_quarantined_devices))
             .route("/device", get(list_devices))
             .route("/device/user/{username}", get(list_user_devices))
+            // devices awaiting approval
+            .route("/device_approval", get(list_pending_device_approvals))
+            .route("/device_approval/{id}/approve", post(approve_device))
+            .route("/device_approval/{id}/reject", post(reject_device))
+            // background jobs
+            .route("/jobs", get(list_jobs))
+            .route("/jobs/{id}", get(get_job))
+            .route("/jobs/{id}/cancel", post(cancel_job))
+            .route("/lookup/ip/{addr}", get(lookup_ip))
+            .route("/lookup/endpoint/{addr}", get(lookup_endpoint))
+            .route("/troubleshoot/device/{id}", get(troubleshoot_device))
             // Network devices, as opposed to user devices
             .route(
                 "/device/network",
@@ -606,8 +1272,11 @@ pub fn build_webapp(
             )
             .route("/network", post(create_network).get(list_networks))
             .route("/network/import", post(import_network))
+            .route("/network/migrate", post(migrate_network))
+            .route("/network/apply", put(apply_location))
             .route("/network/stats", get(networks_overview_stats))
             .route("/network/gateways", get(all_gateways_status))
+            .route("/network/ip_conflicts", get(ip_conflicts))
             .route(
                 "/network/{network_id}",
                 put(modify_network)
@@ -625,8 +1294,45 @@ pub fn build_webapp(
                 get(download_config),
             )
             .route("/network/{network_id}/token", get(create_network_token))
+            .route(
+                "/network/{network_id}/gateway_setup_link",
+                post(create_gateway_setup_link),
+            )
+            .route(
+                "/network/{network_id}/gateway_deployment",
+                get(gateway_deployment),
+            )
+            .route("/gateway_setup/{token}", get(download_gateway_setup))
+            .route("/network/{network_id}/export", get(export_network))
+            .route("/network/{network_id}/journal", get(network_journal))
+            .route(
+                "/network/{network_id}/keepalive",
+                get(keepalive_recommendation).post(apply_keepalive_recommendation),
+            )
+            .route(
+                "/network/{network_id}/gateway_distribution",
+                get(gateway_distribution).put(modify_gateway_distribution),
+            )
+            .route(
+                "/network/{network_id}/geofence",
+                get(location_geofence).put(modify_location_geofence),
+            )
+            .route(
+                "/network/{location_id}/maintenance",
+                get(list_maintenance_windows).post(create_maintenance_window),
+            )
+            .route(
+                "/network/{location_id}/maintenance/{id}",
+                delete(delete_maintenance_window),
+            )
             .route("/network/{network_id}/stats/users", get(devices_stats))
             .route("/network/{network_id}/stats", get(network_stats))
+            // routes
+            .route("/route", get(list_routes).post(create_route))
+            .route(
+                "/route/{id}",
+                get(get_route).put(modify_route).delete(delete_route),
+            )
             .route(
                 "/network/{location_id}/snat",
                 get(list_snat_bindings).post(create_snat_binding),
@@ -635,10 +1341,37 @@ pub fn build_webapp(
                 "/network/{location_id}/snat/{user_id}",
                 put(modify_snat_binding).delete(delete_snat_binding),
             )
+            .route(
+                "/network/{location_id}/firewall/version",
+                get(list_firewall_versions),
+            )
+            .route(
+                "/network/{location_id}/firewall/version/{version}",
+                get(get_firewall_version),
+            )
+            .route(
+                "/network/{location_id}/firewall/version/{version}/rollback",
+                post(rollback_firewall_version),
+            )
+            .route(
+                "/network/{location_id}/quarantine",
+                get(list_remediation_hosts).post(create_remediation_host),
+            )
+            .route(
+                "/network/{location_id}/quarantine/{host_id}",
+                delete(delete_remediation_host),
+            )
             .route("/outdated", get(outdated_components))
+            .route("/dashboard", get(dashboard))
             .layer(Extension(gateway_state)),
     );
 
+    let webapp = if server_config().graphql_enabled {
+        webapp.route("/api/v1/graphql", post(graphql))
+    } else {
+        webapp
+    };
+
     let webapp = webapp.nest(
         "/api/v1/worker",
         Router::new()
@@ -649,6 +1382,21 @@ pub fn build_webapp(
             .layer(Extension(worker_state)),
     );
 
+    let appstate = AppState::new(
+        pool,
+        webhook_tx,
+        webhook_rx,
+        wireguard_tx,
+        mail_tx,
+        failed_logins,
+        event_tx,
+        incompatible_components,
+    );
+
+    // API v2 is served by v1 handlers, which check requested version for changed representations
+    let v1 = webapp.clone().with_state(appstate.clone());
+    let webapp = webapp.nest_service(API_V2_PREFIX, compatibility_shim(v1));
+
     let webapp = webapp.layer(DefguardVersionLayer::new(version)).layer(
         SetResponseHeaderLayer::if_not_present(
             headers::CONTENT_SECURITY_POLICY_HEADER_NAME,
@@ -660,16 +1408,7 @@ pub fn build_webapp(
         SwaggerUi::new("/api-docs").url("/api-docs/openapi.json", openapi::ApiDoc::openapi());
 
     webapp
-        .with_state(AppState::new(
-            pool,
-            webhook_tx,
-            webhook_rx,
-            wireguard_tx,
-            mail_tx,
-            failed_logins,
-            event_tx,
-            incompatible_components,
-        ))
+        .with_state(appstate)
         .layer(
             TraceLayer::new_for_http()
                 .make_span_with(|request: &Request<_>| {
@@ -697,6 +1436,7 @@ pub async fn run_web_server(
     failed_logins: Arc<Mutex<FailedLoginMap>>,
     event_tx: UnboundedSender<ApiEvent>,
     incompatible_components: Arc<RwLock<IncompatibleComponents>>,
+    activity_log_messages_tx: Sender<Bytes>,
 ) -> Result<(), anyhow::Error> {
     let webapp = build_webapp(
         webhook_tx,
@@ -710,6 +1450,7 @@ pub async fn run_web_server(
         event_tx,
         Version::parse(VERSION)?,
         incompatible_components,
+        activity_log_messages_tx,
     );
     info!("Started web services");
     let server_config = server_config();
@@ -743,6 +1484,7 @@ pub async fn init_dev_env(config: &DefGuardConfig) {
         &config.database_name,
         &config.database_user,
         config.database_password.expose_secret(),
+        config.database_max_connections,
     )
     .await;
 
@@ -978,6 +1720,44 @@ pub async fn gateway_config(
     Ok(config)
 }
 
+/// Create a read-only database role for BI tools, or update its password if it already exists.
+/// The role can only read views from the `reporting` schema, which is kept stable across releases.
+pub async fn init_reporting_role(
+    pool: &PgPool,
+    args: &InitReportingRoleArgs,
+) -> Result<(), anyhow::Error> {
+    let mut transaction = pool.begin().await?;
+    let exists: bool =
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM pg_roles WHERE rolname = $1)")
+            .bind(&args.name)
+            .fetch_one(&mut *transaction)
+            .await?;
+    let role_statement = if exists {
+        "ALTER ROLE %I WITH LOGIN PASSWORD %L"
+    } else {
+        "CREATE ROLE %I WITH LOGIN PASSWORD %L"
+    };
+    // identifiers and literals are quoted by Postgres, as they can't be bound as parameters
+    let statements: Vec<String> = sqlx::query_scalar(
+        "SELECT unnest(ARRAY[format($3, $1, $2), \
+        format('GRANT CONNECT ON DATABASE %I TO %I', current_database(), $1), \
+        format('GRANT USAGE ON SCHEMA reporting TO %I', $1), \
+        format('GRANT SELECT ON ALL TABLES IN SCHEMA reporting TO %I', $1), \
+        format('ALTER DEFAULT PRIVILEGES IN SCHEMA reporting GRANT SELECT ON TABLES TO %I', $1)])",
+    )
+    .bind(&args.name)
+    .bind(&args.password)
+    .bind(role_statement)
+    .fetch_all(&mut *transaction)
+    .await?;
+    for statement in statements {
+        sqlx::query(&statement).execute(&mut *transaction).await?;
+    }
+    transaction.commit().await?;
+
+    Ok(())
+}
+
 pub(crate) fn is_valid_phone_number(number: &str) -> bool {
     PHONE_NUMBER_REGEX.is_match(number)
 }
@@ -0,0 +1,459 @@
+//! Development-only load testing mode.
+//!
+//! Creates synthetic devices spread across synthetic VPN locations and streams
+//! made-up peer stats for them to a running core instance, acting as one gateway
+//! per location. This exercises the whole stats pipeline, from the gateway gRPC
+//! service down to the database, without real WireGuard peers.
+
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail};
+use chrono::Utc;
+use defguard_common::{
+    VERSION,
+    auth::claims::{Claims, ClaimsType},
+    config::LoadTestArgs,
+    db::Id,
+};
+use defguard_proto::gateway::{
+    PeerStats, StatsUpdate, gateway_service_client::GatewayServiceClient, stats_update,
+};
+use defguard_version::{Version, client::ClientVersionInterceptor};
+use ipnetwork::IpNetwork;
+use rand::{Rng, thread_rng};
+use reqwest::Url;
+use sqlx::{PgPool, query};
+use tokio::{
+    sync::mpsc,
+    task::JoinSet,
+    time::{MissedTickBehavior, interval, sleep},
+};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{
+    metadata::{Ascii, MetadataValue},
+    service::Interceptor,
+    transport::{ClientTlsConfig, Endpoint},
+};
+
+use crate::{
+    db::{
+        Device, User, WireguardNetwork,
+        models::{
+            device::DeviceType,
+            wireguard::{
+                DEFAULT_DISCONNECT_THRESHOLD, DEFAULT_KEEPALIVE_INTERVAL, LocationMfaMode,
+                ServiceLocationMode,
+            },
+        },
+    },
+    grpc::{AUTHORIZATION_HEADER, HOSTNAME_HEADER},
+};
+
+/// Owner of all synthetic devices.
+const LOAD_TEST_USERNAME: &str = "loadtest";
+/// Name prefix of synthetic locations.
+const LOCATION_PREFIX: &str = "loadtest-location-";
+/// Each location gets its own /16 network, so the number of locations is limited.
+const MAX_LOCATIONS: u32 = 256;
+const MAX_DEVICES_PER_LOCATION: u32 = 65_000;
+const TICK: Duration = Duration::from_millis(100);
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(10);
+// WireGuard performs a handshake every two minutes on an active tunnel
+const HANDSHAKE_INTERVAL: u64 = 120;
+
+/// Synthetic peer reported by a synthetic gateway.
+struct SyntheticPeer {
+    pubkey: String,
+    allowed_ips: String,
+    endpoint: String,
+    upload: u64,
+    download: u64,
+    latest_handshake: u64,
+}
+
+/// Synthetic location and its peers.
+pub struct SyntheticLocation {
+    network: WireguardNetwork<Id>,
+    peers: Vec<SyntheticPeer>,
+}
+
+impl SyntheticLocation {
+    #[must_use]
+    pub fn id(&self) -> Id {
+        self.network.id
+    }
+
+    #[must_use]
+    pub fn peer_count(&self) -> usize {
+        self.peers.len()
+    }
+}
+
+/// Remove synthetic locations and devices along with stats of those devices.
+pub async fn cleanup_synthetic_peers(pool: &PgPool) -> Result<(), sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+    let locations = query!(
+        "DELETE FROM wireguard_network WHERE name LIKE $1 || '%'",
+        LOCATION_PREFIX
+    )
+    .execute(&mut *transaction)
+    .await?
+    .rows_affected();
+    let users = query!(
+        "DELETE FROM \"user\" WHERE username = $1",
+        LOAD_TEST_USERNAME
+    )
+    .execute(&mut *transaction)
+    .await?
+    .rows_affected();
+    transaction.commit().await?;
+    info!(
+        "Removed {locations} synthetic locations{}",
+        if users > 0 {
+            " and synthetic devices"
+        } else {
+            ""
+        }
+    );
+
+    Ok(())
+}
+
+/// Create `devices` synthetic devices distributed evenly across `locations` synthetic locations.
+/// Synthetic objects left by a previous run are removed first.
+pub async fn create_synthetic_peers(
+    pool: &PgPool,
+    locations: u32,
+    devices: u32,
+) -> Result<Vec<SyntheticLocation>, anyhow::Error> {
+    if locations == 0 || locations > MAX_LOCATIONS {
+        bail!("Number of locations must be between 1 and {MAX_LOCATIONS}");
+    }
+    if devices.div_ceil(locations) > MAX_DEVICES_PER_LOCATION {
+        bail!("At most {MAX_DEVICES_PER_LOCATION} devices per location are supported");
+    }
+    cleanup_synthetic_peers(pool).await?;
+
+    info!("Creating {devices} synthetic devices in {locations} synthetic locations");
+    let mut transaction = pool.begin().await?;
+    let user = User::new(
+        LOAD_TEST_USERNAME,
+        None,
+        "Test",
+        "Load",
+        "loadtest@defguard.invalid",
+        None,
+    )
+    .save(&mut *transaction)
+    .await?;
+
+    let mut synthetic_locations = Vec::with_capacity(locations as usize);
+    for index in 0..locations {
+        let address = IpNetwork::new(IpAddr::V4(Ipv4Addr::new(10, index as u8, 0, 1)), 16)?;
+        let network = WireguardNetwork::new(
+            format!("{LOCATION_PREFIX}{index}"),
+            vec![address],
+            51820 + index as i32,
+            "loadtest.defguard.invalid".to_string(),
+            None,
+            vec![address],
+            DEFAULT_KEEPALIVE_INTERVAL,
+            DEFAULT_DISCONNECT_THRESHOLD,
+            false,
+            false,
+            LocationMfaMode::Disabled,
+            ServiceLocationMode::Disabled,
+        )
+        .save(&mut *transaction)
+        .await?;
+        synthetic_locations.push(SyntheticLocation {
+            network,
+            peers: Vec::new(),
+        });
+    }
+
+    for index in 0..devices {
+        let location = &mut synthetic_locations[(index % locations) as usize];
+        let pubkey = WireguardNetwork::genkey().public;
+        let device = Device::new(
+            format!("loadtest-device-{index}"),
+            pubkey.clone(),
+            user.id,
+            DeviceType::User,
+            None,
+            true,
+        )
+        .save(&mut *transaction)
+        .await?;
+        let network_device = device
+            .assign_next_network_ip(&mut transaction, &location.network, None, None)
+            .await?;
+        let allowed_ips = network_device
+            .wireguard_ips
+            .iter()
+            .map(|ip| format!("{ip}/32"))
+            .collect::<Vec<_>>()
+            .join(",");
+        // documentation range, so synthetic endpoints can't be mistaken for real clients
+        let endpoint = format!("198.51.100.{}:{}", index % 254 + 1, 10_000 + index % 50_000);
+        location.peers.push(SyntheticPeer {
+            pubkey,
+            allowed_ips,
+            endpoint,
+            upload: 0,
+            download: 0,
+            latest_handshake: 0,
+        });
+    }
+    transaction.commit().await?;
+
+    Ok(synthetic_locations)
+}
+
+/// Adds gateway token and hostname to requests of a synthetic gateway.
+#[derive(Clone)]
+struct SyntheticGatewayInterceptor {
+    version: ClientVersionInterceptor,
+    token: MetadataValue<Ascii>,
+    hostname: MetadataValue<Ascii>,
+}
+
+impl Interceptor for SyntheticGatewayInterceptor {
+    fn call(&mut self, request: tonic::Request<()>) -> Result<tonic::Request<()>, tonic::Status> {
+        let mut request = self.version.call(request)?;
+        let metadata = request.metadata_mut();
+        metadata.insert(AUTHORIZATION_HEADER, self.token.clone());
+        metadata.insert(HOSTNAME_HEADER, self.hostname.clone());
+        Ok(request)
+    }
+}
+
+/// Stream synthetic stats of a single location at `rate` updates per second.
+async fn run_synthetic_gateway(
+    endpoint: Endpoint,
+    mut location: SyntheticLocation,
+    rate: f64,
+    sent: Arc<AtomicU64>,
+) -> Result<(), anyhow::Error> {
+    let token = Claims::new(
+        ClaimsType::Gateway,
+        format!("DEFGUARD-NETWORK-{}", location.id()),
+        location.id().to_string(),
+        u32::MAX.into(),
+    )
+    .to_jwt()?;
+    let interceptor = SyntheticGatewayInterceptor {
+        version: ClientVersionInterceptor::new(Version::parse(VERSION)?),
+        token: token.parse()?,
+        hostname: format!("loadtest-gateway-{}", location.id()).parse()?,
+    };
+    let mut client = GatewayServiceClient::with_interceptor(endpoint.connect_lazy(), interceptor);
+
+    let (tx, rx) = mpsc::channel(1024);
+    let stream = tokio::spawn(async move { client.stats(ReceiverStream::new(rx)).await });
+
+    let mut ticker = interval(TICK);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut budget = 0.0;
+    let mut next_peer = 0;
+    let peer_count = location.peers.len();
+    let mut id = 0;
+    loop {
+        ticker.tick().await;
+        budget += rate * TICK.as_secs_f64();
+        let now = Utc::now().timestamp() as u64;
+        while budget >= 1.0 {
+            budget -= 1.0;
+            let peer = &mut location.peers[next_peer];
+            next_peer = (next_peer + 1) % peer_count;
+            {
+                let mut rng = thread_rng();
+                peer.upload += rng.gen_range(0..1_000_000);
+                peer.download += rng.gen_range(0..10_000_000);
+            }
+            if now.saturating_sub(peer.latest_handshake) >= HANDSHAKE_INTERVAL {
+                peer.latest_handshake = now;
+            }
+            id += 1;
+            let update = StatsUpdate {
+                id,
+                payload: Some(stats_update::Payload::PeerStats(PeerStats {
+                    public_key: peer.pubkey.clone(),
+                    endpoint: peer.endpoint.clone(),
+                    upload: peer.upload,
+                    download: peer.download,
+                    keepalive_interval: location.network.keepalive_interval as u32,
+                    latest_handshake: peer.latest_handshake,
+                    allowed_ips: peer.allowed_ips.clone(),
+                })),
+            };
+            if tx.send(update).await.is_err() {
+                // stream has been closed, the reason is returned by the request
+                return match stream.await? {
+                    Ok(_) => Err(anyhow!(
+                        "Core closed stats stream of location {}",
+                        location.id()
+                    )),
+                    Err(status) => Err(anyhow!(
+                        "Stats stream of location {} failed: {status}",
+                        location.id()
+                    )),
+                };
+            }
+            sent.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Run load test as configured by command line arguments.
+///
+/// Requires a running core instance reachable under `grpc_url`.
+pub async fn run_load_test(
+    pool: &PgPool,
+    args: &LoadTestArgs,
+    grpc_url: &Url,
+) -> Result<(), anyhow::Error> {
+    if args.cleanup {
+        return Ok(cleanup_synthetic_peers(pool).await?);
+    }
+    let locations = create_synthetic_peers(pool, args.locations, args.devices).await?;
+
+    let endpoint = Endpoint::from_shared(grpc_url.to_string())?;
+    let endpoint = if grpc_url.scheme() == "https" {
+        endpoint.tls_config(ClientTlsConfig::new().with_enabled_roots())?
+    } else {
+        endpoint
+    };
+    // locations may have no devices if there are fewer devices than locations
+    let active = locations
+        .iter()
+        .filter(|location| location.peer_count() > 0)
+        .count();
+    if active == 0 {
+        bail!("No synthetic devices to report stats for");
+    }
+    let rate = f64::from(args.rate) / active as f64;
+    let sent = Arc::new(AtomicU64::new(0));
+    let mut gateways = JoinSet::new();
+    for location in locations {
+        if location.peer_count() == 0 {
+            continue;
+        }
+        gateways.spawn(run_synthetic_gateway(
+            endpoint.clone(),
+            location,
+            rate,
+            Arc::clone(&sent),
+        ));
+    }
+    info!(
+        "Streaming {} peer stats updates per second from {} synthetic gateways to {grpc_url}",
+        args.rate,
+        gateways.len()
+    );
+
+    let started = Instant::now();
+    let deadline = sleep(args.duration.map_or(Duration::MAX, Duration::from_secs));
+    tokio::pin!(deadline);
+    let mut progress = interval(PROGRESS_INTERVAL);
+    progress.tick().await;
+    loop {
+        tokio::select! {
+            result = gateways.join_next() => {
+                match result {
+                    Some(Ok(Err(err))) => return Err(err),
+                    Some(Err(err)) => return Err(err.into()),
+                    _ => bail!("Synthetic gateways stopped unexpectedly"),
+                }
+            }
+            _ = progress.tick() => {
+                let sent = sent.load(Ordering::Relaxed);
+                info!(
+                    "Sent {sent} peer stats updates ({:.0}/s)",
+                    sent as f64 / started.elapsed().as_secs_f64()
+                );
+            }
+            () = &mut deadline, if args.duration.is_some() => break,
+        }
+    }
+    gateways.abort_all();
+    info!(
+        "Load test finished, sent {} peer stats updates in {}s",
+        sent.load(Ordering::Relaxed),
+        started.elapsed().as_secs()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use defguard_common::db::setup_pool;
+    use sqlx::{
+        postgres::{PgConnectOptions, PgPoolOptions},
+        query_scalar,
+    };
+
+    use super::*;
+
+    #[sqlx::test]
+    async fn test_synthetic_peers(_: PgPoolOptions, options: PgConnectOptions) {
+        let pool = setup_pool(options).await;
+
+        let locations = create_synthetic_peers(&pool, 3, 10).await.unwrap();
+        assert_eq!(locations.len(), 3);
+        let peers: Vec<_> = locations
+            .iter()
+            .map(SyntheticLocation::peer_count)
+            .collect();
+        assert_eq!(peers, [4, 3, 3]);
+        for location in &locations {
+            for peer in &location.peers {
+                let device = Device::find_by_pubkey(&pool, &peer.pubkey)
+                    .await
+                    .unwrap()
+                    .unwrap();
+                let ips = query_scalar!(
+                    "SELECT wireguard_ips \"wireguard_ips: Vec<IpAddr>\" FROM wireguard_network_device \
+                    WHERE device_id = $1 AND wireguard_network_id = $2",
+                    device.id,
+                    location.id()
+                )
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+                assert_eq!(peer.allowed_ips, format!("{}/32", ips[0]));
+            }
+        }
+
+        // previous synthetic objects are replaced
+        create_synthetic_peers(&pool, 1, 5).await.unwrap();
+        let count = query_scalar!("SELECT count(*) \"count!\" FROM device")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 5);
+
+        cleanup_synthetic_peers(&pool).await.unwrap();
+        let count = query_scalar!(
+            "SELECT count(*) \"count!\" FROM wireguard_network WHERE name LIKE 'loadtest-%'"
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(count, 0);
+        assert!(
+            User::find_by_username(&pool, LOAD_TEST_USERNAME)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,939 @@
+//! Declarative configuration of locations, meant for reconcilers such as a Kubernetes operator.
+//!
+//! A [`LocationSpec`] describes the desired state of a location: its network settings, allowed
+//! groups, gateways and ACL rules. Applying a spec is idempotent: the current state is compared
+//! with the spec and only differing resources are created, updated or deleted. The result lists
+//! these changes and Kubernetes-style status conditions.
+//!
+//! Locations are identified by name, gateways by hostname and ACL rules by name. Only ACL rules
+//! which apply exclusively to the location are managed, so rules shared between locations are
+//! never modified or removed. Gateways and ACL rules are left untouched if they're omitted from
+//! the spec.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+};
+
+use chrono::NaiveDateTime;
+use defguard_common::db::Id;
+use serde_json::Value;
+use utoipa::ToSchema;
+
+use crate::{
+    appstate::AppState,
+    db::{
+        Group, User, WireguardNetwork,
+        models::{
+            gateway_distribution::LocationGateway,
+            wireguard::{GatewayDistributionPolicy, LocationMfaMode, ServiceLocationMode},
+        },
+    },
+    enterprise::{
+        db::models::acl::{
+            AclAlias, AclRule, AliasState, Protocol, RuleState, normalize_destination,
+            normalize_ports,
+        },
+        handlers::acl::EditAclRule,
+        is_business_license_active,
+    },
+    error::WebError,
+    events::ApiEventType,
+    grpc::gateway::map::GatewayMap,
+    handlers::wireguard::{
+        GatewayDistributionData, LocationGatewayData, WireguardNetworkData,
+        create_network_from_data, set_gateway_distribution, update_network_from_data,
+    },
+};
+
+/// Gateway serving a location.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct GatewaySpec {
+    pub hostname: String,
+    /// Public address of the gateway, location endpoint is used if not set.
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    #[serde(default = "default_gateway_weight")]
+    pub weight: i32,
+    /// Name of a group whose members are assigned to this gateway by group distribution.
+    #[serde(default)]
+    pub group: Option<String>,
+}
+
+fn default_gateway_weight() -> i32 {
+    1
+}
+
+/// Gateways serving a location and distribution of devices between them.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct GatewaysSpec {
+    #[serde(default)]
+    pub policy: GatewayDistributionPolicy,
+    #[serde(default)]
+    pub peer_sharding: bool,
+    #[serde(default)]
+    pub gateways: Vec<GatewaySpec>,
+}
+
+/// ACL rule applying to a location. Users, groups and aliases are referenced by name.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct AclRuleSpec {
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub expires: Option<NaiveDateTime>,
+    #[serde(default)]
+    pub allow_all_users: bool,
+    #[serde(default)]
+    pub deny_all_users: bool,
+    #[serde(default)]
+    pub allow_all_network_devices: bool,
+    #[serde(default)]
+    pub deny_all_network_devices: bool,
+    #[serde(default)]
+    pub allowed_users: Vec<String>,
+    #[serde(default)]
+    pub denied_users: Vec<String>,
+    #[serde(default)]
+    pub allowed_groups: Vec<String>,
+    #[serde(default)]
+    pub denied_groups: Vec<String>,
+    /// Comma-separated addresses, networks and address ranges
+    #[serde(default)]
+    pub destination: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Comma-separated ports and port ranges
+    #[serde(default)]
+    pub ports: String,
+    #[serde(default)]
+    pub protocols: Vec<Protocol>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Desired state of a location.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct LocationSpec {
+    #[serde(flatten)]
+    pub network: WireguardNetworkData,
+    /// Gateways are not managed if omitted.
+    #[serde(default)]
+    pub gateways: Option<GatewaysSpec>,
+    /// ACL rules are not managed if omitted.
+    #[serde(default)]
+    pub acl_rules: Option<Vec<AclRuleSpec>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceKind {
+    Location,
+    Gateway,
+    AclRule,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeAction {
+    Create,
+    Update,
+    Delete,
+}
+
+/// Difference between the current state of a resource and the spec.
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema)]
+pub struct ResourceChange {
+    pub kind: ResourceKind,
+    pub name: String,
+    pub action: ChangeAction,
+    /// Names of changed fields of updated resources
+    pub fields: Vec<String>,
+}
+
+impl ResourceChange {
+    fn new(kind: ResourceKind, name: &str, action: ChangeAction, fields: Vec<String>) -> Self {
+        Self {
+            kind,
+            name: name.to_string(),
+            action,
+            fields,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, ToSchema)]
+pub enum ConditionStatus {
+    True,
+    False,
+}
+
+/// Status condition, following Kubernetes conventions.
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema)]
+pub struct Condition {
+    #[serde(rename = "type")]
+    pub condition_type: String,
+    pub status: ConditionStatus,
+    pub reason: String,
+    pub message: String,
+}
+
+impl Condition {
+    fn new(condition_type: &str, status: bool, reason: &str, message: String) -> Self {
+        Self {
+            condition_type: condition_type.to_string(),
+            status: if status {
+                ConditionStatus::True
+            } else {
+                ConditionStatus::False
+            },
+            reason: reason.to_string(),
+            message,
+        }
+    }
+}
+
+/// Result of applying a location spec.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LocationApplyStatus {
+    /// ID of the location, `None` if it doesn't exist yet
+    pub location_id: Option<Id>,
+    pub dry_run: bool,
+    /// Changes which have been made, or would be made in a dry run
+    pub changes: Vec<ResourceChange>,
+    pub conditions: Vec<Condition>,
+}
+
+/// Gateways of a spec with group names resolved.
+struct ResolvedGateways {
+    spec: GatewaysSpec,
+    data: GatewayDistributionData,
+}
+
+/// Spec with all referenced objects resolved and validated.
+struct ResolvedSpec {
+    network: WireguardNetworkData,
+    gateways: Option<ResolvedGateways>,
+    /// ACL rules with normalized destination and ports; networks are filled in when applying.
+    acl_rules: Option<Vec<EditAclRule>>,
+}
+
+async fn group_id(appstate: &AppState, name: &str) -> Result<Id, WebError> {
+    Group::find_by_name(&appstate.pool, name)
+        .await?
+        .map(|group| group.id)
+        .ok_or_else(|| WebError::BadRequest(format!("group {name} not found")))
+}
+
+async fn group_ids(appstate: &AppState, names: &[String]) -> Result<Vec<Id>, WebError> {
+    let mut ids = Vec::with_capacity(names.len());
+    for name in names {
+        ids.push(group_id(appstate, name).await?);
+    }
+    Ok(ids)
+}
+
+async fn user_ids(appstate: &AppState, usernames: &[String]) -> Result<Vec<Id>, WebError> {
+    let mut ids = Vec::with_capacity(usernames.len());
+    for username in usernames {
+        let user = User::find_by_username(&appstate.pool, username)
+            .await?
+            .ok_or_else(|| WebError::BadRequest(format!("user {username} not found")))?;
+        ids.push(user.id);
+    }
+    Ok(ids)
+}
+
+/// Sort related object IDs, so rules can be compared regardless of their order.
+fn sorted(mut rule: EditAclRule) -> EditAclRule {
+    rule.networks.sort_unstable();
+    rule.allowed_users.sort_unstable();
+    rule.denied_users.sort_unstable();
+    rule.allowed_groups.sort_unstable();
+    rule.denied_groups.sort_unstable();
+    rule.allowed_devices.sort_unstable();
+    rule.denied_devices.sort_unstable();
+    rule.aliases.sort_unstable();
+    rule.protocols.sort_unstable();
+    rule
+}
+
+impl LocationSpec {
+    async fn resolve(self, appstate: &AppState) -> Result<ResolvedSpec, WebError> {
+        let network = self.network;
+        if network.name.trim().is_empty() {
+            return Err(WebError::BadRequest("location name is required".into()));
+        }
+        network.validate_location_mfa_mode(&appstate.pool).await?;
+        network.parse_min_client_versions()?;
+        network.parse_addresses()?;
+        group_ids(appstate, &network.allowed_groups).await?;
+
+        let gateways = match self.gateways {
+            Some(spec) => {
+                let mut gateways = Vec::with_capacity(spec.gateways.len());
+                for gateway in &spec.gateways {
+                    let group_id = match &gateway.group {
+                        Some(group) => Some(group_id(appstate, group).await?),
+                        None => None,
+                    };
+                    gateways.push(LocationGatewayData {
+                        hostname: gateway.hostname.clone(),
+                        endpoint: gateway
+                            .endpoint
+                            .clone()
+                            .filter(|endpoint| !endpoint.trim().is_empty()),
+                        weight: gateway.weight,
+                        group_id,
+                    });
+                }
+                let data = GatewayDistributionData {
+                    policy: spec.policy,
+                    peer_sharding: spec.peer_sharding,
+                    gateways,
+                };
+                data.validate(&appstate.pool).await?;
+                Some(ResolvedGateways { spec, data })
+            }
+            None => None,
+        };
+
+        let acl_rules = match self.acl_rules {
+            Some(specs) => {
+                if !is_business_license_active() {
+                    return Err(WebError::Forbidden(
+                        "Enterprise features are disabled".into(),
+                    ));
+                }
+                let aliases: HashMap<String, Id> = AclAlias::all(&appstate.pool)
+                    .await?
+                    .into_iter()
+                    .filter(|alias| alias.state == AliasState::Applied)
+                    .map(|alias| (alias.name, alias.id))
+                    .collect();
+                let mut names = HashSet::new();
+                let mut rules = Vec::with_capacity(specs.len());
+                for spec in specs {
+                    if !names.insert(spec.name.clone()) {
+                        return Err(WebError::BadRequest(format!(
+                            "duplicate ACL rule {}",
+                            spec.name
+                        )));
+                    }
+                    let rule = EditAclRule {
+                        all_networks: false,
+                        networks: Vec::new(),
+                        expires: spec.expires,
+                        enabled: spec.enabled,
+                        allow_all_users: spec.allow_all_users,
+                        deny_all_users: spec.deny_all_users,
+                        allow_all_network_devices: spec.allow_all_network_devices,
+                        deny_all_network_devices: spec.deny_all_network_devices,
+                        allowed_users: user_ids(appstate, &spec.allowed_users).await?,
+                        denied_users: user_ids(appstate, &spec.denied_users).await?,
+                        allowed_groups: group_ids(appstate, &spec.allowed_groups).await?,
+                        denied_groups: group_ids(appstate, &spec.denied_groups).await?,
+                        allowed_devices: Vec::new(),
+                        denied_devices: Vec::new(),
+                        destination: normalize_destination(&spec.destination)?,
+                        aliases: spec
+                            .aliases
+                            .iter()
+                            .map(|name| {
+                                aliases.get(name).copied().ok_or_else(|| {
+                                    WebError::BadRequest(format!("ACL alias {name} not found"))
+                                })
+                            })
+                            .collect::<Result<_, _>>()?,
+                        ports: normalize_ports(&spec.ports)?,
+                        protocols: spec.protocols,
+                        name: spec.name,
+                    };
+                    rule.validate()?;
+                    rules.push(sorted(rule));
+                }
+                Some(rules)
+            }
+            None => None,
+        };
+
+        Ok(ResolvedSpec {
+            network,
+            gateways,
+            acl_rules,
+        })
+    }
+}
+
+/// Names of location fields which differ from the spec.
+async fn location_changes(
+    appstate: &AppState,
+    location: &WireguardNetwork<Id>,
+    spec: &ResolvedSpec,
+) -> Result<Vec<String>, WebError> {
+    let data = &spec.network;
+    let (min_desktop_client_version, min_mobile_client_version) =
+        data.parse_min_client_versions()?;
+    let (device_name_pattern, device_name_prefix) = data.parse_device_naming()?;
+    let mut allowed_groups = location.fetch_allowed_groups(&appstate.pool).await?;
+    allowed_groups.sort();
+    let mut desired_groups = data.allowed_groups.clone();
+    desired_groups.sort();
+    desired_groups.dedup();
+    // service location mode is disabled for locations with MFA
+    let service_location_mode = if data.location_mfa_mode == LocationMfaMode::Disabled {
+        data.service_location_mode.clone()
+    } else {
+        ServiceLocationMode::Disabled
+    };
+
+    let mut fields = vec![
+        ("address", location.address != data.parse_addresses()?),
+        ("endpoint", location.endpoint != data.endpoint),
+        ("port", location.port != data.port),
+        (
+            "allowed_ips",
+            location.allowed_ips != data.parse_allowed_ips(),
+        ),
+        ("dns", location.dns != data.dns),
+        ("allowed_groups", allowed_groups != desired_groups),
+        (
+            "keepalive_interval",
+            location.keepalive_interval != data.keepalive_interval,
+        ),
+        (
+            "peer_disconnect_threshold",
+            location.peer_disconnect_threshold != data.peer_disconnect_threshold,
+        ),
+        ("acl_enabled", location.acl_enabled != data.acl_enabled),
+        (
+            "acl_default_allow",
+            location.acl_default_allow != data.acl_default_allow,
+        ),
+        (
+            "location_mfa_mode",
+            location.location_mfa_mode != data.location_mfa_mode,
+        ),
+        (
+            "service_location_mode",
+            location.service_location_mode != service_location_mode,
+        ),
+        (
+            "min_desktop_client_version",
+            location.min_desktop_client_version != min_desktop_client_version,
+        ),
+        (
+            "min_mobile_client_version",
+            location.min_mobile_client_version != min_mobile_client_version,
+        ),
+        (
+            "device_approval_required",
+            location.device_approval_required != data.device_approval_required,
+        ),
+        (
+            "ip_allocation_strategy",
+            location.ip_allocation_strategy != data.ip_allocation_strategy,
+        ),
+        (
+            "client_traffic_policy",
+            location.client_traffic_policy != data.client_traffic_policy,
+        ),
+        (
+            "mfa_session_lifetime_hours",
+            location.mfa_session_lifetime_hours != data.mfa_session_lifetime_hours,
+        ),
+        (
+            "mfa_remember_device_hours",
+            location.mfa_remember_device_hours != data.mfa_remember_device_hours,
+        ),
+        (
+            "preshared_keys_enabled",
+            location.preshared_keys_enabled != data.preshared_keys_enabled,
+        ),
+        (
+            "preshared_key_rotation_days",
+            location.preshared_key_rotation_days != data.preshared_key_rotation_days,
+        ),
+        (
+            "device_name_pattern",
+            location.device_name_pattern != device_name_pattern,
+        ),
+        (
+            "device_name_prefix",
+            location.device_name_prefix != device_name_prefix,
+        ),
+        (
+            "device_name_uniqueness",
+            location.device_name_uniqueness != data.device_name_uniqueness,
+        ),
+    ];
+    if let Some(gateways) = &spec.gateways {
+        fields.push((
+            "gateway_distribution_policy",
+            location.gateway_distribution_policy != gateways.data.policy,
+        ));
+        fields.push((
+            "gateway_peer_sharding",
+            location.gateway_peer_sharding != gateways.data.peer_sharding,
+        ));
+    }
+
+    Ok(fields
+        .into_iter()
+        .filter_map(|(field, changed)| changed.then(|| field.to_string()))
+        .collect())
+}
+
+/// Changes of configured gateways, compared by hostname.
+fn gateway_changes(
+    current: &[LocationGateway<Id>],
+    desired: &[LocationGatewayData],
+) -> Vec<ResourceChange> {
+    let mut changes = Vec::new();
+    for gateway in desired {
+        match current
+            .iter()
+            .find(|current| current.hostname == gateway.hostname)
+        {
+            Some(current) => {
+                let fields: Vec<String> = [
+                    ("endpoint", current.endpoint != gateway.endpoint),
+                    ("weight", current.weight != gateway.weight),
+                    ("group", current.group_id != gateway.group_id),
+                ]
+                .into_iter()
+                .filter_map(|(field, changed)| changed.then(|| field.to_string()))
+                .collect();
+                if !fields.is_empty() {
+                    changes.push(ResourceChange::new(
+                        ResourceKind::Gateway,
+                        &gateway.hostname,
+                        ChangeAction::Update,
+                        fields,
+                    ));
+                }
+            }
+            None => changes.push(ResourceChange::new(
+                ResourceKind::Gateway,
+                &gateway.hostname,
+                ChangeAction::Create,
+                Vec::new(),
+            )),
+        }
+    }
+    for gateway in current {
+        if !desired
+            .iter()
+            .any(|desired| desired.hostname == gateway.hostname)
+        {
+            changes.push(ResourceChange::new(
+                ResourceKind::Gateway,
+                &gateway.hostname,
+                ChangeAction::Delete,
+                Vec::new(),
+            ));
+        }
+    }
+    changes
+}
+
+/// Names of ACL rule fields which differ.
+fn acl_rule_fields(current: &EditAclRule, desired: &EditAclRule) -> Vec<String> {
+    let (Ok(Value::Object(current)), Ok(Value::Object(desired))) =
+        (serde_json::to_value(current), serde_json::to_value(desired))
+    else {
+        return Vec::new();
+    };
+    desired
+        .iter()
+        .filter(|(field, value)| current.get(*field) != Some(value))
+        .map(|(field, _)| field.clone())
+        .collect()
+}
+
+/// ACL rule managed by a spec, with its current state.
+struct ManagedAclRule {
+    rule: AclRule<Id>,
+    current: EditAclRule,
+}
+
+async fn managed_acl_rules(
+    appstate: &AppState,
+    location_id: Id,
+) -> Result<Vec<ManagedAclRule>, WebError> {
+    let mut conn = appstate.pool.acquire().await?;
+    let rules = AclRule::all_for_single_location(&mut *conn, location_id).await?;
+    let mut managed = Vec::with_capacity(rules.len());
+    for rule in rules {
+        let current = sorted(rule.to_info(&mut conn).await?.into());
+        managed.push(ManagedAclRule { rule, current });
+    }
+    Ok(managed)
+}
+
+/// Changes of ACL rules, compared by name, with IDs of affected rules. Rules which haven't been
+/// applied yet are updated, so they get applied.
+fn acl_rule_changes(
+    managed: &[ManagedAclRule],
+    desired: &[EditAclRule],
+) -> Vec<(ResourceChange, Option<Id>)> {
+    let mut changes = Vec::new();
+    for rule in desired {
+        match managed
+            .iter()
+            .find(|managed| managed.rule.name == rule.name)
+        {
+            Some(managed) => {
+                // managed rules apply only to this location
+                let mut rule = rule.clone();
+                rule.networks.clone_from(&managed.current.networks);
+                let mut fields = acl_rule_fields(&managed.current, &rule);
+                if managed.rule.state == RuleState::New {
+                    fields.push("state".into());
+                }
+                if !fields.is_empty() {
+                    changes.push((
+                        ResourceChange::new(
+                            ResourceKind::AclRule,
+                            &rule.name,
+                            ChangeAction::Update,
+                            fields,
+                        ),
+                        Some(managed.rule.id),
+                    ));
+                }
+            }
+            None => changes.push((
+                ResourceChange::new(
+                    ResourceKind::AclRule,
+                    &rule.name,
+                    ChangeAction::Create,
+                    Vec::new(),
+                ),
+                None,
+            )),
+        }
+    }
+    for managed in managed {
+        if !desired.iter().any(|rule| rule.name == managed.rule.name) {
+            changes.push((
+                ResourceChange::new(
+                    ResourceKind::AclRule,
+                    &managed.rule.name,
+                    ChangeAction::Delete,
+                    Vec::new(),
+                ),
+                Some(managed.rule.id),
+            ));
+        }
+    }
+    changes
+}
+
+/// Find location by name.
+async fn find_location(
+    appstate: &AppState,
+    name: &str,
+) -> Result<Option<WireguardNetwork<Id>>, WebError> {
+    match WireguardNetwork::find_by_name(&appstate.pool, name).await? {
+        None => Ok(None),
+        Some(mut locations) => {
+            if locations.len() > 1 {
+                return Err(WebError::BadRequest(format!(
+                    "multiple locations named {name}, can't apply spec"
+                )));
+            }
+            Ok(locations.pop())
+        }
+    }
+}
+
+fn conditions(
+    location_id: Option<Id>,
+    changes: &[ResourceChange],
+    dry_run: bool,
+    gateways: Option<&GatewaysSpec>,
+    gateway_state: &Mutex<GatewayMap>,
+) -> Vec<Condition> {
+    let synced = if changes.is_empty() {
+        Condition::new(
+            "Synced",
+            true,
+            "UpToDate",
+            "Location matches the spec".into(),
+        )
+    } else if dry_run {
+        Condition::new(
+            "Synced",
+            false,
+            "ChangesPending",
+            format!("{} changes would be applied", changes.len()),
+        )
+    } else {
+        Condition::new(
+            "Synced",
+            true,
+            "Applied",
+            format!("{} changes have been applied", changes.len()),
+        )
+    };
+
+    let gateways_connected = match location_id {
+        Some(location_id) => {
+            let connected = gateway_state
+                .lock()
+                .expect("Failed to acquire gateway state lock")
+                .connected_hostnames(location_id);
+            let expected: Vec<&str> = gateways
+                .map(|gateways| {
+                    gateways
+                        .gateways
+                        .iter()
+                        .map(|gateway| gateway.hostname.as_str())
+                        .collect()
+                })
+                .unwrap_or_default();
+            if expected.is_empty() {
+                if connected.is_empty() {
+                    Condition::new(
+                        "GatewaysConnected",
+                        false,
+                        "NoGatewayConnected",
+                        "No gateway is connected".into(),
+                    )
+                } else {
+                    Condition::new(
+                        "GatewaysConnected",
+                        true,
+                        "GatewayConnected",
+                        format!("Connected gateways: {}", connected.join(", ")),
+                    )
+                }
+            } else {
+                let disconnected: Vec<&str> = expected
+                    .into_iter()
+                    .filter(|hostname| !connected.iter().any(|connected| connected == hostname))
+                    .collect();
+                if disconnected.is_empty() {
+                    Condition::new(
+                        "GatewaysConnected",
+                        true,
+                        "AllGatewaysConnected",
+                        "All gateways are connected".into(),
+                    )
+                } else {
+                    Condition::new(
+                        "GatewaysConnected",
+                        false,
+                        "GatewaysDisconnected",
+                        format!("Disconnected gateways: {}", disconnected.join(", ")),
+                    )
+                }
+            }
+        }
+        None => Condition::new(
+            "GatewaysConnected",
+            false,
+            "LocationNotCreated",
+            "Location doesn't exist yet".into(),
+        ),
+    };
+
+    let ready = synced.status == ConditionStatus::True
+        && gateways_connected.status == ConditionStatus::True;
+    let ready = if ready {
+        Condition::new(
+            "Ready",
+            true,
+            "Ready",
+            "Location is configured and served by gateways".into(),
+        )
+    } else if synced.status == ConditionStatus::True {
+        Condition::new(
+            "Ready",
+            false,
+            gateways_connected.reason.as_str(),
+            gateways_connected.message.clone(),
+        )
+    } else {
+        Condition::new(
+            "Ready",
+            false,
+            synced.reason.as_str(),
+            synced.message.clone(),
+        )
+    };
+
+    vec![synced, gateways_connected, ready]
+}
+
+/// Compare a location with the spec and apply differences, unless `dry_run` is set.
+///
+/// Returns the result and an activity log event if the location has been created or modified.
+pub(crate) async fn apply_location_spec(
+    appstate: &AppState,
+    gateway_state: &Mutex<GatewayMap>,
+    spec: LocationSpec,
+    dry_run: bool,
+) -> Result<(LocationApplyStatus, Option<ApiEventType>), WebError> {
+    let spec = spec.resolve(appstate).await?;
+    let name = spec.network.name.clone();
+    let location = find_location(appstate, &name).await?;
+
+    // compare current state with the spec
+    let mut changes = Vec::new();
+    let mut acl_changes = Vec::new();
+    let mut gateways_changed = false;
+    match &location {
+        Some(location) => {
+            let fields = location_changes(appstate, location, &spec).await?;
+            gateways_changed = fields.iter().any(|field| field.starts_with("gateway_"));
+            if !fields.is_empty() {
+                changes.push(ResourceChange::new(
+                    ResourceKind::Location,
+                    &name,
+                    ChangeAction::Update,
+                    fields,
+                ));
+            }
+            if let Some(gateways) = &spec.gateways {
+                let current =
+                    LocationGateway::all_for_location(&appstate.pool, location.id).await?;
+                let gateway_changes = gateway_changes(&current, &gateways.data.gateways);
+                gateways_changed |= !gateway_changes.is_empty();
+                changes.extend(gateway_changes);
+            }
+            if let Some(rules) = &spec.acl_rules {
+                let managed = managed_acl_rules(appstate, location.id).await?;
+                acl_changes = acl_rule_changes(&managed, rules);
+            }
+        }
+        None => {
+            changes.push(ResourceChange::new(
+                ResourceKind::Location,
+                &name,
+                ChangeAction::Create,
+                Vec::new(),
+            ));
+            if let Some(gateways) = &spec.gateways {
+                let gateway_changes = gateway_changes(&[], &gateways.data.gateways);
+                gateways_changed = true;
+                changes.extend(gateway_changes);
+            }
+            if let Some(rules) = &spec.acl_rules {
+                acl_changes = acl_rule_changes(&[], rules);
+            }
+        }
+    }
+    changes.extend(acl_changes.iter().map(|(change, _)| change.clone()));
+
+    let gateways_spec = spec.gateways.as_ref().map(|gateways| gateways.spec.clone());
+    if dry_run || changes.is_empty() {
+        let location_id = location.as_ref().map(|location| location.id);
+        let status = LocationApplyStatus {
+            location_id,
+            dry_run,
+            conditions: conditions(
+                location_id,
+                &changes,
+                dry_run,
+                gateways_spec.as_ref(),
+                gateway_state,
+            ),
+            changes,
+        };
+        return Ok((status, None));
+    }
+
+    // location
+    let location_changed = changes
+        .iter()
+        .any(|change| change.kind == ResourceKind::Location);
+    let (location, event) = match location {
+        Some(location) => {
+            if location_changed {
+                let before = location.clone();
+                let after = update_network_from_data(appstate, location, spec.network).await?;
+                (
+                    after.clone(),
+                    Some(ApiEventType::VpnLocationModified { before, after }),
+                )
+            } else {
+                (location, None)
+            }
+        }
+        None => {
+            let location = create_network_from_data(appstate, spec.network).await?;
+            (
+                location.clone(),
+                Some(ApiEventType::VpnLocationAdded { location }),
+            )
+        }
+    };
+
+    // gateways
+    let location = match spec.gateways {
+        Some(gateways) if gateways_changed => {
+            set_gateway_distribution(appstate, gateway_state, location, gateways.data)
+                .await?
+                .0
+        }
+        _ => location,
+    };
+
+    // ACL rules
+    if let Some(rules) = spec.acl_rules {
+        let mut touched = Vec::new();
+        for (change, rule_id) in &acl_changes {
+            let rule = rules.iter().find(|rule| rule.name == change.name);
+            match (change.action, rule, rule_id) {
+                (ChangeAction::Create, Some(rule), _) => {
+                    let mut rule = rule.clone();
+                    rule.networks = vec![location.id];
+                    touched.push(AclRule::create_from_api(&appstate.pool, &rule).await?.id);
+                }
+                (ChangeAction::Update, Some(rule), Some(rule_id)) => {
+                    let mut rule = rule.clone();
+                    rule.networks = vec![location.id];
+                    touched.push(*rule_id);
+                    touched.push(
+                        AclRule::update_from_api(&appstate.pool, *rule_id, &rule)
+                            .await?
+                            .id,
+                    );
+                }
+                (ChangeAction::Delete, _, Some(rule_id)) => {
+                    AclRule::delete_from_api(&appstate.pool, *rule_id).await?;
+                    touched.push(*rule_id);
+                }
+                _ => {}
+            }
+        }
+        let pending = AclRule::pending_ids(&appstate.pool, &touched).await?;
+        if !pending.is_empty() {
+            AclRule::apply_rules(&pending, appstate).await?;
+        }
+    }
+    info!(
+        "Applied {} changes to location {}",
+        changes.len(),
+        location.name
+    );
+
+    let status = LocationApplyStatus {
+        location_id: Some(location.id),
+        dry_run,
+        conditions: conditions(
+            Some(location.id),
+            &changes,
+            dry_run,
+            gateways_spec.as_ref(),
+            gateway_state,
+        ),
+        changes,
+    };
+    Ok((status, event))
+}
@@ -0,0 +1,761 @@
+//! Preflight check of database migrations.
+//!
+//! Upgrades fail with opaque errors when the database schema doesn't match what migrations
+//! expect, e.g. after a migration file was edited or a table was modified manually. This module
+//! inspects the database without applying migrations and reports:
+//! - migrations which will be applied on startup,
+//! - failed, modified and unknown migrations recorded in `_sqlx_migrations`,
+//! - schema drift: differences between the live schema and the schema produced by applied
+//!   migrations, which are replayed in a temporary database for comparison.
+//!
+//! Each problem comes with a suggested fix.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use defguard_common::{db::MIGRATOR, hex::to_lower_hex, random::gen_alphanumeric};
+use sqlx::{
+    Connection, PgConnection, PgPool,
+    migrate::Migrate,
+    migrate::Migration,
+    postgres::{PgConnectOptions, PgPoolOptions},
+    query_as, query_scalar,
+};
+
+// system schemas are not managed by migrations
+const SCHEMA_FILTER: &str = "n.nspname NOT LIKE 'pg\\_%' AND n.nspname <> 'information_schema'";
+
+/// Kind of a database object managed by migrations.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ObjectKind {
+    Table,
+    Column,
+    Index,
+    Trigger,
+    Function,
+    Type,
+}
+
+impl fmt::Display for ObjectKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            Self::Table => "table",
+            Self::Column => "column",
+            Self::Index => "index",
+            Self::Trigger => "trigger",
+            Self::Function => "function",
+            Self::Type => "type",
+        };
+        f.write_str(kind)
+    }
+}
+
+/// Definition of a table or view column.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColumnDefinition {
+    pub data_type: String,
+    pub nullable: bool,
+}
+
+impl fmt::Display for ColumnDefinition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.nullable {
+            f.write_str(&self.data_type)
+        } else {
+            write!(f, "{} NOT NULL", self.data_type)
+        }
+    }
+}
+
+/// Problem found by the preflight check.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Issue {
+    /// Migration has been started, but didn't finish successfully.
+    FailedMigration { version: i64, description: String },
+    /// Applied migration differs from the migration shipped with this version.
+    ModifiedMigration {
+        version: i64,
+        description: String,
+        checksum: Vec<u8>,
+    },
+    /// Database has been migrated by a newer version.
+    UnknownMigration { version: i64, description: String },
+    /// Object created by migrations doesn't exist.
+    MissingObject {
+        kind: ObjectKind,
+        name: String,
+        migration: Option<(i64, String)>,
+    },
+    /// Object not created by migrations exists.
+    UnexpectedObject { kind: ObjectKind, name: String },
+    /// Column definition differs from the one created by migrations.
+    ColumnMismatch {
+        table: String,
+        column: String,
+        expected: ColumnDefinition,
+        actual: ColumnDefinition,
+    },
+    /// Enum type values differ from the ones created by migrations.
+    EnumMismatch {
+        name: String,
+        expected: Vec<String>,
+        actual: Vec<String>,
+    },
+}
+
+impl Issue {
+    /// Suggested fix of the problem.
+    #[must_use]
+    pub fn fix(&self) -> String {
+        match self {
+            Self::FailedMigration { version, .. } => format!(
+                "revert partial changes of the migration manually, then remove its record with \
+                `DELETE FROM _sqlx_migrations WHERE version = {version}` so it's retried on startup"
+            ),
+            Self::ModifiedMigration {
+                version, checksum, ..
+            } => format!(
+                "make sure this version matches the release the database was migrated with; if \
+                the migration has been changed intentionally, update its checksum with \
+                `UPDATE _sqlx_migrations SET checksum = '\\x{}' WHERE version = {version}`",
+                to_lower_hex(checksum)
+            ),
+            Self::UnknownMigration { .. } => "the database has been migrated by a newer version; \
+                upgrade to that version or restore a database backup made before the upgrade"
+                .into(),
+            Self::MissingObject {
+                kind,
+                migration: Some((version, description)),
+                ..
+            } => format!(
+                "recreate the {kind} as defined in migration {version} ({description}) or restore \
+                a database backup"
+            ),
+            Self::MissingObject { kind, .. } => {
+                format!("recreate the {kind} as defined in migrations or restore a database backup")
+            }
+            Self::UnexpectedObject { kind, .. } => format!(
+                "drop the {kind} if it has been added manually, as it may conflict with future \
+                migrations"
+            ),
+            Self::ColumnMismatch {
+                table,
+                column,
+                expected,
+                actual,
+            } => {
+                let mut statements = Vec::new();
+                if expected.data_type != actual.data_type {
+                    statements.push(format!(
+                        "ALTER TABLE {} ALTER COLUMN {} TYPE {}",
+                        quote_name(table),
+                        quote_identifier(column),
+                        expected.data_type
+                    ));
+                }
+                if expected.nullable != actual.nullable {
+                    statements.push(format!(
+                        "ALTER TABLE {} ALTER COLUMN {} {} NOT NULL",
+                        quote_name(table),
+                        quote_identifier(column),
+                        if expected.nullable { "DROP" } else { "SET" }
+                    ));
+                }
+                format!("restore column definition with `{}`", statements.join("; "))
+            }
+            Self::EnumMismatch { expected, .. } => format!(
+                "restore enum values to: {}, values can be added with `ALTER TYPE ... ADD VALUE`",
+                expected.join(", ")
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FailedMigration {
+                version,
+                description,
+            } => write!(f, "migration {version} ({description}) has failed"),
+            Self::ModifiedMigration {
+                version,
+                description,
+                ..
+            } => write!(
+                f,
+                "migration {version} ({description}) has been modified after it was applied"
+            ),
+            Self::UnknownMigration {
+                version,
+                description,
+            } => write!(
+                f,
+                "applied migration {version} ({description}) is unknown to this version"
+            ),
+            Self::MissingObject { kind, name, .. } => write!(f, "{kind} {name} is missing"),
+            Self::UnexpectedObject { kind, name } => {
+                write!(f, "{kind} {name} is not created by migrations")
+            }
+            Self::ColumnMismatch {
+                table,
+                column,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "column {table}.{column} is defined as {actual}, expected {expected}"
+            ),
+            Self::EnumMismatch {
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "type {name} has values {}, expected {}",
+                actual.join(", "),
+                expected.join(", ")
+            ),
+        }
+    }
+}
+
+/// Result of the preflight check.
+#[derive(Debug, Default)]
+pub struct PreflightReport {
+    /// Versions and descriptions of migrations which will be applied on startup.
+    pub pending: Vec<(i64, String)>,
+    pub issues: Vec<Issue>,
+    /// Reason why schema drift hasn't been checked.
+    pub drift_check_skipped: Option<String>,
+}
+
+impl PreflightReport {
+    /// Whether migrations can be safely applied.
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl fmt::Display for PreflightReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.pending.is_empty() {
+            writeln!(f, "No pending migrations")?;
+        } else {
+            writeln!(f, "Pending migrations:")?;
+            for (version, description) in &self.pending {
+                writeln!(f, "  {version} {description}")?;
+            }
+        }
+        if let Some(reason) = &self.drift_check_skipped {
+            writeln!(f, "Schema drift check skipped: {reason}")?;
+        }
+        if self.issues.is_empty() {
+            writeln!(f, "No problems found")
+        } else {
+            writeln!(f, "Found {} problems:", self.issues.len())?;
+            for issue in &self.issues {
+                writeln!(f, "  - {issue}")?;
+                writeln!(f, "    fix: {}", issue.fix())?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Database schema objects, identified by schema-qualified names.
+#[derive(Debug, Default, PartialEq)]
+struct Schema {
+    tables: BTreeSet<String>,
+    columns: BTreeMap<(String, String), ColumnDefinition>,
+    // index -> table
+    indexes: BTreeMap<String, String>,
+    // (table, trigger)
+    triggers: BTreeSet<(String, String)>,
+    functions: BTreeSet<String>,
+    enums: BTreeMap<String, Vec<String>>,
+}
+
+impl Schema {
+    async fn load(conn: &mut PgConnection) -> Result<Self, sqlx::Error> {
+        let tables = query_scalar::<_, String>(&format!(
+            "SELECT n.nspname || '.' || c.relname FROM pg_class c \
+            JOIN pg_namespace n ON n.oid = c.relnamespace \
+            WHERE c.relkind IN ('r', 'p', 'v', 'm') AND {SCHEMA_FILTER}"
+        ))
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .collect();
+        let columns = query_as::<_, (String, String, String, bool)>(&format!(
+            "SELECT n.nspname || '.' || c.relname, a.attname::text, \
+            format_type(a.atttypid, a.atttypmod), NOT a.attnotnull FROM pg_attribute a \
+            JOIN pg_class c ON c.oid = a.attrelid JOIN pg_namespace n ON n.oid = c.relnamespace \
+            WHERE c.relkind IN ('r', 'p', 'v', 'm') AND a.attnum > 0 AND NOT a.attisdropped \
+            AND {SCHEMA_FILTER}"
+        ))
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .map(|(table, column, data_type, nullable)| {
+            (
+                (table, column),
+                ColumnDefinition {
+                    data_type,
+                    nullable,
+                },
+            )
+        })
+        .collect();
+        let indexes = query_as::<_, (String, String)>(&format!(
+            "SELECT n.nspname || '.' || i.relname, n.nspname || '.' || t.relname FROM pg_index x \
+            JOIN pg_class i ON i.oid = x.indexrelid JOIN pg_class t ON t.oid = x.indrelid \
+            JOIN pg_namespace n ON n.oid = t.relnamespace WHERE {SCHEMA_FILTER}"
+        ))
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .collect();
+        let triggers = query_as::<_, (String, String)>(&format!(
+            "SELECT n.nspname || '.' || c.relname, t.tgname::text FROM pg_trigger t \
+            JOIN pg_class c ON c.oid = t.tgrelid JOIN pg_namespace n ON n.oid = c.relnamespace \
+            WHERE NOT t.tgisinternal AND {SCHEMA_FILTER}"
+        ))
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .collect();
+        // skip functions installed by extensions
+        let functions = query_scalar::<_, String>(&format!(
+            "SELECT n.nspname || '.' || p.proname || '(' || \
+            pg_get_function_identity_arguments(p.oid) || ')' FROM pg_proc p \
+            JOIN pg_namespace n ON n.oid = p.pronamespace WHERE {SCHEMA_FILTER} \
+            AND NOT EXISTS (SELECT 1 FROM pg_depend d WHERE d.classid = 'pg_proc'::regclass \
+            AND d.objid = p.oid AND d.deptype = 'e')"
+        ))
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .collect();
+        let enums = query_as::<_, (String, Vec<String>)>(&format!(
+            "SELECT n.nspname || '.' || t.typname, \
+            array_agg(e.enumlabel::text ORDER BY e.enumsortorder) FROM pg_type t \
+            JOIN pg_enum e ON e.enumtypid = t.oid JOIN pg_namespace n ON n.oid = t.typnamespace \
+            WHERE {SCHEMA_FILTER} GROUP BY 1"
+        ))
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .collect();
+
+        Ok(Self {
+            tables,
+            columns,
+            indexes,
+            triggers,
+            functions,
+            enums,
+        })
+    }
+
+    /// Compare this, expected schema with the `actual` one.
+    fn drift(&self, actual: &Self, migrations: &[&Migration]) -> Vec<Issue> {
+        let mut issues = Vec::new();
+        let missing = |kind, name: String, needles: &[&str]| Issue::MissingObject {
+            kind,
+            migration: defining_migration(migrations, needles),
+            name,
+        };
+        for table in self.tables.difference(&actual.tables) {
+            issues.push(missing(
+                ObjectKind::Table,
+                table.clone(),
+                &[unqualified(table)],
+            ));
+        }
+        // objects of missing tables are not reported separately
+        for ((table, column), definition) in &self.columns {
+            if !actual.tables.contains(table) {
+                continue;
+            }
+            match actual.columns.get(&(table.clone(), column.clone())) {
+                Some(actual) if actual != definition => {
+                    issues.push(Issue::ColumnMismatch {
+                        table: table.clone(),
+                        column: column.clone(),
+                        expected: definition.clone(),
+                        actual: actual.clone(),
+                    });
+                }
+                Some(_) => {}
+                None => issues.push(missing(
+                    ObjectKind::Column,
+                    format!("{table}.{column}"),
+                    &[unqualified(table), column.as_str()],
+                )),
+            }
+        }
+        for (index, table) in &self.indexes {
+            if actual.tables.contains(table) && !actual.indexes.contains_key(index) {
+                issues.push(missing(
+                    ObjectKind::Index,
+                    index.clone(),
+                    &[unqualified(index)],
+                ));
+            }
+        }
+        for (table, trigger) in self.triggers.difference(&actual.triggers) {
+            if actual.tables.contains(table) {
+                issues.push(missing(
+                    ObjectKind::Trigger,
+                    format!("{trigger} on {table}"),
+                    &[trigger.as_str()],
+                ));
+            }
+        }
+        for function in self.functions.difference(&actual.functions) {
+            let name = unqualified(function);
+            let name = name.split('(').next().unwrap_or(name);
+            issues.push(missing(ObjectKind::Function, function.clone(), &[name]));
+        }
+        for (name, values) in &self.enums {
+            match actual.enums.get(name) {
+                Some(actual) if actual != values => issues.push(Issue::EnumMismatch {
+                    name: name.clone(),
+                    expected: values.clone(),
+                    actual: actual.clone(),
+                }),
+                Some(_) => {}
+                None => issues.push(missing(
+                    ObjectKind::Type,
+                    name.clone(),
+                    &[unqualified(name)],
+                )),
+            }
+        }
+
+        // objects not created by migrations
+        let unexpected = |kind, name| Issue::UnexpectedObject { kind, name };
+        for table in actual.tables.difference(&self.tables) {
+            issues.push(unexpected(ObjectKind::Table, table.clone()));
+        }
+        for (table, column) in actual.columns.keys() {
+            if self.tables.contains(table)
+                && !self.columns.contains_key(&(table.clone(), column.clone()))
+            {
+                issues.push(unexpected(ObjectKind::Column, format!("{table}.{column}")));
+            }
+        }
+        for (index, table) in &actual.indexes {
+            if self.tables.contains(table) && !self.indexes.contains_key(index) {
+                issues.push(unexpected(ObjectKind::Index, index.clone()));
+            }
+        }
+        for (table, trigger) in actual.triggers.difference(&self.triggers) {
+            if self.tables.contains(table) {
+                issues.push(unexpected(
+                    ObjectKind::Trigger,
+                    format!("{trigger} on {table}"),
+                ));
+            }
+        }
+        for function in actual.functions.difference(&self.functions) {
+            issues.push(unexpected(ObjectKind::Function, function.clone()));
+        }
+        for name in actual.enums.keys() {
+            if !self.enums.contains_key(name) {
+                issues.push(unexpected(ObjectKind::Type, name.clone()));
+            }
+        }
+
+        issues
+    }
+}
+
+/// Strip schema from a qualified name.
+fn unqualified(name: &str) -> &str {
+    name.split_once('.').map_or(name, |(_, name)| name)
+}
+
+fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+fn quote_name(name: &str) -> String {
+    name.split('.')
+        .map(quote_identifier)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Find the latest migration mentioning all `needles`, which most likely defines the object.
+fn defining_migration(migrations: &[&Migration], needles: &[&str]) -> Option<(i64, String)> {
+    migrations
+        .iter()
+        .rev()
+        .find(|migration| {
+            let sql = migration.sql.to_lowercase();
+            needles
+                .iter()
+                .all(|needle| sql.contains(&needle.to_lowercase()))
+        })
+        .map(|migration| (migration.version, migration.description.to_string()))
+}
+
+/// Migrations recorded in `_sqlx_migrations`: version -> (description, success, checksum).
+async fn applied_migrations(
+    pool: &PgPool,
+) -> Result<BTreeMap<i64, (String, bool, Vec<u8>)>, sqlx::Error> {
+    let exists: bool = query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+        .fetch_one(pool)
+        .await?;
+    if !exists {
+        return Ok(BTreeMap::new());
+    }
+    let rows = query_as::<_, (i64, String, bool, Vec<u8>)>(
+        "SELECT version, description, success, checksum FROM _sqlx_migrations ORDER BY version",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(version, description, success, checksum)| {
+            (version, (description, success, checksum))
+        })
+        .collect())
+}
+
+/// Replay `migrations` in a temporary database and load the resulting schema.
+async fn expected_schema(
+    pool: &PgPool,
+    options: &PgConnectOptions,
+    migrations: &[&Migration],
+) -> Result<Schema, anyhow::Error> {
+    let database = format!("defguard_preflight_{}", gen_alphanumeric(8).to_lowercase());
+    sqlx::query(&format!("CREATE DATABASE {}", quote_identifier(&database)))
+        .execute(pool)
+        .await?;
+    let result = async {
+        let mut conn = PgConnection::connect_with(&options.clone().database(&database)).await?;
+        conn.ensure_migrations_table().await?;
+        for migration in migrations {
+            conn.apply(migration).await?;
+        }
+        let schema = Schema::load(&mut conn).await?;
+        conn.close().await?;
+        Ok::<_, anyhow::Error>(schema)
+    }
+    .await;
+    if let Err(err) = sqlx::query(&format!(
+        "DROP DATABASE IF EXISTS {} WITH (FORCE)",
+        quote_identifier(&database)
+    ))
+    .execute(pool)
+    .await
+    {
+        warn!("Failed to drop temporary database {database}: {err}");
+    }
+
+    result
+}
+
+/// Check migrations and schema of the database without applying migrations.
+///
+/// Schema drift is checked by replaying applied migrations in a temporary database, which
+/// requires the `CREATEDB` privilege; otherwise the drift check is skipped.
+pub async fn check_migrations(
+    options: &PgConnectOptions,
+) -> Result<PreflightReport, anyhow::Error> {
+    info!("Checking database migrations");
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect_with(options.clone())
+        .await?;
+    let applied = applied_migrations(&pool).await?;
+    let known: BTreeMap<i64, &Migration> = MIGRATOR
+        .iter()
+        .filter(|migration| migration.migration_type.is_up_migration())
+        .map(|migration| (migration.version, migration))
+        .collect();
+
+    let mut report = PreflightReport::default();
+    for (version, migration) in &known {
+        match applied.get(version) {
+            None => report
+                .pending
+                .push((*version, migration.description.to_string())),
+            Some((description, false, _)) => report.issues.push(Issue::FailedMigration {
+                version: *version,
+                description: description.clone(),
+            }),
+            Some((description, true, checksum)) => {
+                if checksum[..] != migration.checksum[..] {
+                    report.issues.push(Issue::ModifiedMigration {
+                        version: *version,
+                        description: description.clone(),
+                        checksum: migration.checksum.to_vec(),
+                    });
+                }
+            }
+        }
+    }
+    for (version, (description, _, _)) in &applied {
+        if !known.contains_key(version) {
+            report.issues.push(Issue::UnknownMigration {
+                version: *version,
+                description: description.clone(),
+            });
+        }
+    }
+
+    // compare with schema created by successfully applied migrations
+    let replayed: Vec<&Migration> = known
+        .iter()
+        .filter(|(version, _)| matches!(applied.get(*version), Some((_, true, _))))
+        .map(|(_, migration)| *migration)
+        .collect();
+    // schema can't be reproduced if migrations are unknown, modified or have failed
+    if !report.issues.is_empty() {
+        report.drift_check_skipped =
+            Some("migrations have to be fixed before checking the schema".into());
+    } else if applied.is_empty() {
+        report.drift_check_skipped = Some("database hasn't been migrated yet".into());
+    } else {
+        match expected_schema(&pool, options, &replayed).await {
+            Ok(expected) => {
+                let mut conn = pool.acquire().await?;
+                let actual = Schema::load(&mut conn).await?;
+                report.issues.extend(expected.drift(&actual, &replayed));
+            }
+            Err(err) => {
+                warn!("Unable to check schema drift: {err}");
+                report.drift_check_skipped = Some(format!(
+                    "unable to replay migrations in a temporary database: {err}"
+                ));
+            }
+        }
+    }
+    pool.close().await;
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod test {
+    use defguard_common::db::setup_pool;
+    use sqlx::query;
+
+    use super::*;
+
+    #[sqlx::test]
+    async fn test_check_migrations(_: PgPoolOptions, options: PgConnectOptions) {
+        let pool = setup_pool(options.clone()).await;
+
+        let report = check_migrations(&options).await.unwrap();
+        assert!(report.is_ok(), "{report}");
+        assert!(report.pending.is_empty());
+        assert_eq!(report.drift_check_skipped, None);
+
+        // manual schema changes
+        for statement in [
+            "ALTER TABLE \"user\" ALTER COLUMN email DROP NOT NULL",
+            "ALTER TABLE device DROP COLUMN description",
+            "DROP INDEX activity_log_event_username_idx",
+            "CREATE TABLE manual (id bigint)",
+        ] {
+            query(statement).execute(&pool).await.unwrap();
+        }
+        let report = check_migrations(&options).await.unwrap();
+        assert!(!report.is_ok());
+        assert_eq!(report.drift_check_skipped, None);
+        let issue = Issue::ColumnMismatch {
+            table: "public.user".into(),
+            column: "email".into(),
+            expected: ColumnDefinition {
+                data_type: "text".into(),
+                nullable: false,
+            },
+            actual: ColumnDefinition {
+                data_type: "text".into(),
+                nullable: true,
+            },
+        };
+        assert!(report.issues.contains(&issue));
+        assert_eq!(
+            issue.fix(),
+            "restore column definition with \
+            `ALTER TABLE \"public\".\"user\" ALTER COLUMN \"email\" SET NOT NULL`"
+        );
+        assert!(report.issues.iter().any(|issue| matches!(
+            issue,
+            Issue::MissingObject { kind: ObjectKind::Column, name, migration: Some(_) }
+                if name == "public.device.description"
+        )));
+        assert!(report.issues.iter().any(|issue| matches!(
+            issue,
+            Issue::MissingObject { kind: ObjectKind::Index, name, migration: Some(_) }
+                if name == "public.activity_log_event_username_idx"
+        )));
+        assert!(report.issues.contains(&Issue::UnexpectedObject {
+            kind: ObjectKind::Table,
+            name: "public.manual".into(),
+        }));
+
+        // tampered migrations
+        let first = MIGRATOR
+            .iter()
+            .find(|migration| migration.migration_type.is_up_migration())
+            .unwrap();
+        let last: i64 = query_scalar("SELECT max(version) FROM _sqlx_migrations")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        for statement in [
+            format!(
+                "UPDATE _sqlx_migrations SET success = false WHERE version = {}",
+                first.version
+            ),
+            format!("UPDATE _sqlx_migrations SET checksum = '\\x00' WHERE version = {last}"),
+            "INSERT INTO _sqlx_migrations (version, description, success, checksum, \
+            execution_time) VALUES (99990101000000, 'future', true, '\\x00', 0)"
+                .into(),
+        ] {
+            query(&statement).execute(&pool).await.unwrap();
+        }
+        let report = check_migrations(&options).await.unwrap();
+        assert!(report.issues.contains(&Issue::FailedMigration {
+            version: first.version,
+            description: first.description.to_string(),
+        }));
+        assert!(report.issues.iter().any(|issue| matches!(
+            issue,
+            Issue::ModifiedMigration { version, .. } if *version == last
+        )));
+        assert!(report.issues.contains(&Issue::UnknownMigration {
+            version: 99990101000000,
+            description: "future".into(),
+        }));
+        // schema isn't compared when migrations are broken
+        assert!(report.drift_check_skipped.is_some());
+        assert!(
+            !report
+                .issues
+                .iter()
+                .any(|issue| matches!(issue, Issue::UnexpectedObject { .. }))
+        );
+
+        // pending migration
+        query("DELETE FROM _sqlx_migrations WHERE version = $1")
+            .bind(last)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let report = check_migrations(&options).await.unwrap();
+        assert_eq!(report.pending.len(), 1);
+        assert_eq!(report.pending[0].0, last);
+    }
+}
@@ -0,0 +1,238 @@
+//! Digest of connectivity notifications.
+//!
+//! Gateway and proxy disconnect/reconnect storms would otherwise send one mail per admin per
+//! event. If a digest window is configured in settings, such mails are collected per admin
+//! instead. Once the oldest collected notification is older than the window, they're sent as a
+//! single mail summarizing all of them. Other channels of notification rules aren't affected.
+
+use std::{collections::BTreeMap, sync::Mutex, time::Duration};
+
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use defguard_common::db::models::Settings;
+use defguard_mail::{
+    Mail, MailCategory,
+    templates::{self, DigestNotification, TemplateError},
+};
+use tokio::{sync::mpsc::UnboundedSender, time::sleep};
+
+use crate::{db::models::notification::NotificationCategory, notifications::AdminNotification};
+
+// How often collected notifications are checked
+const DIGEST_LOOP_SLEEP: Duration = Duration::from_secs(30);
+const DIGEST_SUBJECT: &str = "Defguard: Connectivity notifications digest";
+
+static PENDING: Mutex<PendingDigests> = Mutex::new(PendingDigests::new());
+
+/// Notification collected for a digest.
+#[derive(Clone, Debug)]
+struct PendingNotification {
+    occurred_at: NaiveDateTime,
+    notification: AdminNotification,
+}
+
+/// Notifications collected for admins, by email address.
+#[derive(Debug)]
+struct PendingDigests {
+    admins: BTreeMap<String, Vec<PendingNotification>>,
+}
+
+impl PendingDigests {
+    const fn new() -> Self {
+        Self {
+            admins: BTreeMap::new(),
+        }
+    }
+
+    fn push(&mut self, email: &str, notification: &AdminNotification, now: NaiveDateTime) {
+        self.admins
+            .entry(email.to_string())
+            .or_default()
+            .push(PendingNotification {
+                occurred_at: now,
+                notification: notification.clone(),
+            });
+    }
+
+    /// Removes and returns notifications of admins whose oldest notification is older than
+    /// `window`.
+    fn take_due(
+        &mut self,
+        window: TimeDelta,
+        now: NaiveDateTime,
+    ) -> Vec<(String, Vec<PendingNotification>)> {
+        let due: Vec<_> = self
+            .admins
+            .iter()
+            .filter(|(_, pending)| {
+                pending
+                    .first()
+                    .is_some_and(|oldest| now - oldest.occurred_at >= window)
+            })
+            .map(|(email, _)| email.clone())
+            .collect();
+        due.into_iter()
+            .filter_map(|email| {
+                let pending = self.admins.remove(&email)?;
+                Some((email, pending))
+            })
+            .collect()
+    }
+}
+
+/// Whether notifications of a category are collected for digests.
+fn is_digested(category: NotificationCategory) -> bool {
+    matches!(
+        category,
+        NotificationCategory::GatewayDisconnected
+            | NotificationCategory::GatewayReconnected
+            | NotificationCategory::ProxyConnectivity
+    )
+}
+
+/// Digest window in minutes; 0 if notifications are mailed immediately.
+fn digest_window() -> i32 {
+    Settings::get_current_settings()
+        .notification_digest_window
+        .max(0)
+}
+
+/// Collects a notification mailed to an admin for the digest. Returns `false` if the
+/// notification should be mailed immediately.
+pub(crate) fn collect(email: &str, notification: &AdminNotification) -> bool {
+    if !is_digested(notification.category) || digest_window() == 0 {
+        return false;
+    }
+    debug!(
+        "Collecting {:?} notification to {email} for the digest",
+        notification.category
+    );
+    PENDING
+        .lock()
+        .expect("Failed to acquire lock on the mutex.")
+        .push(email, notification, Utc::now().naive_utc());
+    true
+}
+
+/// Mail with notifications collected over `window` minutes; a single notification is sent as is.
+fn digest_mail(
+    to: String,
+    pending: Vec<PendingNotification>,
+    window: i32,
+) -> Result<Mail, TemplateError> {
+    let (subject, content) = if let [single] = pending.as_slice() {
+        (
+            single.notification.subject.clone(),
+            single.notification.content.clone(),
+        )
+    } else {
+        let notifications: Vec<_> = pending
+            .into_iter()
+            .map(|pending| DigestNotification {
+                occurred_at: pending.occurred_at,
+                message: pending.notification.message,
+            })
+            .collect();
+        (
+            DIGEST_SUBJECT.to_string(),
+            templates::notification_digest_mail(&notifications, window)?,
+        )
+    };
+    Ok(Mail {
+        to,
+        subject,
+        content,
+        attachments: Vec::new(),
+        category: MailCategory::Alert,
+        result_tx: None,
+    })
+}
+
+/// Periodically mail notifications collected for digests.
+#[instrument(skip_all)]
+pub async fn run_notification_digest(mail_tx: UnboundedSender<Mail>) {
+    info!("Starting notification digest");
+    loop {
+        sleep(DIGEST_LOOP_SLEEP).await;
+        // notifications collected before the digest has been disabled are sent right away
+        let window = digest_window();
+        let due = PENDING
+            .lock()
+            .expect("Failed to acquire lock on the mutex.")
+            .take_due(TimeDelta::minutes(window.into()), Utc::now().naive_utc());
+        for (email, pending) in due {
+            let count = pending.len();
+            let mail = match digest_mail(email.clone(), pending, window) {
+                Ok(mail) => mail,
+                Err(err) => {
+                    error!("Failed to render notification digest for {email}: {err}");
+                    continue;
+                }
+            };
+            match mail_tx.send(mail) {
+                Ok(()) => info!("Sent digest of {count} notifications to {email}"),
+                Err(err) => error!(
+                    "Sending digest of {count} notifications to {email} failed with error:\n{err}"
+                ),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn notification(category: NotificationCategory, message: &str) -> AdminNotification {
+        AdminNotification {
+            category,
+            subject: "Defguard: Gateway disconnected".into(),
+            content: "content".into(),
+            message: message.into(),
+        }
+    }
+
+    #[test]
+    fn test_digest() {
+        let mut digests = PendingDigests::new();
+        let now = Utc::now().naive_utc();
+        let window = TimeDelta::minutes(15);
+        let disconnected = notification(NotificationCategory::GatewayDisconnected, "disconnected");
+        let reconnected = notification(NotificationCategory::GatewayReconnected, "reconnected");
+
+        digests.push("admin@defguard", &disconnected, now);
+        digests.push("admin@defguard", &reconnected, now + TimeDelta::minutes(5));
+        digests.push(
+            "other@defguard",
+            &disconnected,
+            now + TimeDelta::minutes(10),
+        );
+        assert!(
+            digests
+                .take_due(window, now + TimeDelta::minutes(14))
+                .is_empty()
+        );
+
+        // digest is due once its oldest notification is older than the window
+        let due = digests.take_due(window, now + TimeDelta::minutes(15));
+        assert_eq!(due.len(), 1);
+        let (email, pending) = due.into_iter().next().unwrap();
+        assert_eq!(email, "admin@defguard");
+        assert_eq!(pending.len(), 2);
+        let mail = digest_mail(email, pending, 15).unwrap();
+        assert_eq!(mail.subject, DIGEST_SUBJECT);
+        assert!(mail.content.contains("disconnected"));
+        assert!(mail.content.contains("reconnected"));
+
+        // single notification is mailed as is
+        let due = digests.take_due(window, now + TimeDelta::minutes(25));
+        let (email, pending) = due.into_iter().next().unwrap();
+        let mail = digest_mail(email, pending, 15).unwrap();
+        assert_eq!(mail.to, "other@defguard");
+        assert_eq!(mail.subject, disconnected.subject);
+        assert_eq!(mail.content, disconnected.content);
+        assert!(digests.admins.is_empty());
+
+        assert!(is_digested(NotificationCategory::ProxyConnectivity));
+        assert!(!is_digested(NotificationCategory::DeviceApproval));
+    }
+}
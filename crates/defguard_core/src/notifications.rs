@@ -0,0 +1,256 @@
+//! This module dispatches alerts to admins according to their notification rules.
+//!
+//! Each admin chooses alert categories they receive and channels they're delivered through:
+//! email, in-app notifications listed in the web UI, or a webhook. Admins without any rules
+//! receive all alerts by email. Connectivity alerts may be mailed in digests, see
+//! [`crate::notification_digest`].
+//!
+//! Failover of the SMTP relay reported by the mail handler is also alerted about here.
+
+use std::time::Duration;
+
+use defguard_common::db::Id;
+use defguard_mail::{Mail, MailCategory, failover::FailoverEvent, templates};
+use reqwest::Client;
+use sqlx::{Error as SqlxError, PgPool};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+
+use crate::{
+    db::{
+        User,
+        models::notification::{
+            Notification, NotificationCategory, NotificationChannel, NotificationRule,
+        },
+    },
+    notification_digest,
+};
+
+// How long to wait for a webhook to respond
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+const SMTP_FAILED_OVER_SUBJECT: &str = "Defguard: SMTP server failed over";
+const SMTP_RECOVERED_SUBJECT: &str = "Defguard: SMTP server recovered";
+
+/// Alert sent to admins.
+#[derive(Clone, Debug)]
+pub struct AdminNotification {
+    pub category: NotificationCategory,
+    pub subject: String,
+    /// Rendered email content
+    pub content: String,
+    /// Plain text summary used for in-app and webhook channels
+    pub message: String,
+}
+
+/// Payload posted to webhook URLs of notification rules.
+#[derive(Debug, Serialize)]
+struct WebhookPayload<'a> {
+    category: NotificationCategory,
+    subject: &'a str,
+    message: &'a str,
+    username: &'a str,
+}
+
+/// Channels through which an admin with given rules receives alerts of a category, along with
+/// webhook URLs.
+fn admin_channels(
+    rules: &[NotificationRule<Id>],
+    category: NotificationCategory,
+) -> Vec<(NotificationChannel, Option<&str>)> {
+    if rules.is_empty() {
+        return vec![(NotificationChannel::Email, None)];
+    }
+    rules
+        .iter()
+        .filter(|rule| rule.category == category)
+        .map(|rule| (rule.channel, rule.webhook_url.as_deref()))
+        .collect()
+}
+
+fn send_email(admin: &User<Id>, notification: &AdminNotification, mail_tx: &UnboundedSender<Mail>) {
+    let mail = Mail {
+        to: admin.email.clone(),
+        subject: notification.subject.clone(),
+        content: notification.content.clone(),
+        attachments: Vec::new(),
+        category: MailCategory::Alert,
+        result_tx: None,
+    };
+    match mail_tx.send(mail) {
+        Ok(()) => info!(
+            "Sent {:?} notification to {}",
+            notification.category, admin.email
+        ),
+        Err(err) => error!(
+            "Sending {:?} notification to {} failed with error:\n{err}",
+            notification.category, admin.email
+        ),
+    }
+}
+
+fn post_webhook(admin: &User<Id>, notification: &AdminNotification, url: &str) {
+    let payload = serde_json::json!(WebhookPayload {
+        category: notification.category,
+        subject: &notification.subject,
+        message: &notification.message,
+        username: &admin.username,
+    });
+    let url = url.to_string();
+    let category = notification.category;
+    tokio::spawn(async move {
+        let result = Client::new()
+            .post(&url)
+            .timeout(WEBHOOK_TIMEOUT)
+            .json(&payload)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status);
+        match result {
+            Ok(_) => info!("Posted {category:?} notification to webhook {url}"),
+            Err(err) => error!("Posting {category:?} notification to webhook {url} failed: {err}"),
+        }
+    });
+}
+
+/// Deliver an alert to all admins through channels chosen in their notification rules.
+pub async fn notify_admins(
+    pool: &PgPool,
+    mail_tx: &UnboundedSender<Mail>,
+    notification: &AdminNotification,
+) -> Result<(), SqlxError> {
+    debug!(
+        "Sending {:?} notification to admin users",
+        notification.category
+    );
+    for admin in User::find_admins(pool).await? {
+        let rules = NotificationRule::all_for_user(pool, admin.id).await?;
+        for (channel, webhook_url) in admin_channels(&rules, notification.category) {
+            match (channel, webhook_url) {
+                (NotificationChannel::Email, _) => {
+                    if !notification_digest::collect(&admin.email, notification) {
+                        send_email(&admin, notification, mail_tx);
+                    }
+                }
+                (NotificationChannel::InApp, _) => {
+                    Notification::new(
+                        admin.id,
+                        notification.category,
+                        notification.subject.clone(),
+                        notification.message.clone(),
+                    )
+                    .save(pool)
+                    .await?;
+                }
+                (NotificationChannel::Webhook, Some(url)) => {
+                    post_webhook(&admin, notification, url);
+                }
+                (NotificationChannel::Webhook, None) => {
+                    warn!(
+                        "Webhook notification rule of {} has no URL, skipping",
+                        admin.username
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Alert admins about SMTP relay failover events reported by the mail handler.
+#[instrument(skip_all)]
+pub async fn run_smtp_failover_notifier(
+    pool: PgPool,
+    mail_tx: UnboundedSender<Mail>,
+    mut failover_rx: UnboundedReceiver<FailoverEvent>,
+) {
+    info!("Starting SMTP failover notifier");
+    while let Some(event) = failover_rx.recv().await {
+        let (subject, error, message) = match &event {
+            FailoverEvent::FailedOver { error } => (
+                SMTP_FAILED_OVER_SUBJECT,
+                Some(error.as_str()),
+                format!(
+                    "SMTP server keeps failing ({error}), mails are sent through the failover \
+                    SMTP profile."
+                ),
+            ),
+            FailoverEvent::Recovered => (
+                SMTP_RECOVERED_SUBJECT,
+                None,
+                "SMTP server works again.".to_string(),
+            ),
+        };
+        let content = match templates::smtp_failover_mail(error) {
+            Ok(content) => content,
+            Err(err) => {
+                error!("Failed to render SMTP failover mail: {err}");
+                continue;
+            }
+        };
+        let notification = AdminNotification {
+            category: NotificationCategory::SmtpFailover,
+            subject: subject.to_string(),
+            content,
+            message,
+        };
+        if let Err(err) = notify_admins(&pool, &mail_tx, &notification).await {
+            error!("Failed to notify admins about SMTP failover: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rule(category: NotificationCategory, channel: NotificationChannel) -> NotificationRule<Id> {
+        NotificationRule {
+            id: 1,
+            user_id: 1,
+            category,
+            channel,
+            webhook_url: (channel == NotificationChannel::Webhook)
+                .then(|| "https://hooks.example.com/alerts".into()),
+        }
+    }
+
+    #[test]
+    fn test_admin_channels() {
+        // email by default
+        assert_eq!(
+            admin_channels(&[], NotificationCategory::GatewayDisconnected),
+            [(NotificationChannel::Email, None)]
+        );
+
+        let rules = [
+            rule(
+                NotificationCategory::GatewayDisconnected,
+                NotificationChannel::InApp,
+            ),
+            rule(
+                NotificationCategory::GatewayDisconnected,
+                NotificationChannel::Webhook,
+            ),
+            rule(
+                NotificationCategory::DeviceApproval,
+                NotificationChannel::Email,
+            ),
+        ];
+        assert_eq!(
+            admin_channels(&rules, NotificationCategory::GatewayDisconnected),
+            [
+                (NotificationChannel::InApp, None),
+                (
+                    NotificationChannel::Webhook,
+                    Some("https://hooks.example.com/alerts")
+                )
+            ]
+        );
+        assert_eq!(
+            admin_channels(&rules, NotificationCategory::DeviceApproval),
+            [(NotificationChannel::Email, None)]
+        );
+        // categories without rules are muted
+        assert!(admin_channels(&rules, NotificationCategory::GatewayReconnected).is_empty());
+    }
+}
@@ -0,0 +1,322 @@
+//! This module implements the weekly security summary mailed to admin users. The summary covers
+//! devices added during the week, spikes of failed logins, gateways which repeatedly
+//! disconnected, license limits which are close to being reached and TLS certificates which are
+//! about to expire.
+//!
+//! Sent summaries are recorded in the database, so a summary is sent once a week regardless of
+//! Core restarts.
+
+use std::{fs::read, time::Duration};
+
+use chrono::{DateTime, NaiveDateTime, TimeDelta, Utc};
+use defguard_common::db::{Id, models::Settings};
+use defguard_mail::{
+    Mail, MailCategory,
+    templates::{
+        self, SecuritySummary, SummaryCertificate, SummaryDevice, SummaryFailedLogins,
+        SummaryGateway, SummaryLicenseLimit,
+    },
+};
+use sqlx::{Error as SqlxError, PgExecutor, PgPool, query, query_as, query_scalar};
+use tokio::{sync::mpsc::UnboundedSender, time::sleep};
+use x509_parser::pem::Pem;
+
+use crate::{
+    db::User,
+    enterprise::{license::get_cached_license, limits::get_counts},
+    server_config,
+};
+
+// How long to sleep between loop iterations
+const SUMMARY_LOOP_SLEEP: Duration = Duration::from_secs(60 * 60);
+// Period covered by a single summary
+const SUMMARY_PERIOD: TimeDelta = TimeDelta::weeks(1);
+// Number of failed logins of a single user within the period considered a spike
+const FAILED_LOGINS_SPIKE_THRESHOLD: i64 = 10;
+// Number of disconnects within the period after which a gateway is considered unstable
+const GATEWAY_DISCONNECTS_THRESHOLD: i64 = 3;
+// License limit usage (in percent) which is reported
+const LICENSE_LIMIT_WARNING_PERCENT: u32 = 80;
+// Certificates expiring within this time are reported
+const CERTIFICATE_EXPIRATION_WARNING: TimeDelta = TimeDelta::days(30);
+
+static SECURITY_SUMMARY_SUBJECT: &str = "Defguard: weekly security summary";
+
+/// Store gateway disconnect, used to detect unstable gateways.
+pub(crate) async fn record_gateway_disconnect<'e, E>(
+    executor: E,
+    location_id: Id,
+    hostname: &str,
+) -> Result<(), SqlxError>
+where
+    E: PgExecutor<'e>,
+{
+    query!(
+        "INSERT INTO gateway_disconnect (location_id, hostname) VALUES ($1, $2)",
+        location_id,
+        hostname
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Expiration time of the earliest expiring certificate in a PEM file.
+fn certificate_expiration(path: &str) -> Option<NaiveDateTime> {
+    let content = match read(path) {
+        Ok(content) => content,
+        Err(err) => {
+            warn!("Failed to read certificate {path}: {err}");
+            return None;
+        }
+    };
+    let timestamp = Pem::iter_from_buffer(&content)
+        .filter_map(Result::ok)
+        .filter_map(|pem| {
+            pem.parse_x509()
+                .ok()
+                .map(|certificate| certificate.validity().not_after.timestamp())
+        })
+        .min()?;
+
+    DateTime::from_timestamp(timestamp, 0).map(|expiration| expiration.naive_utc())
+}
+
+/// Expiration times of configured certificates.
+pub(crate) fn certificate_expirations() -> Vec<SummaryCertificate> {
+    let config = server_config();
+    [
+        ("gRPC server certificate", config.grpc_cert.as_deref()),
+        ("Proxy CA certificate", config.proxy_grpc_ca.as_deref()),
+    ]
+    .into_iter()
+    .filter_map(|(name, path)| {
+        Some(SummaryCertificate {
+            name: name.into(),
+            expires_at: certificate_expiration(path?)?,
+        })
+    })
+    .collect()
+}
+
+/// Configured certificates which expire before a given time.
+fn expiring_certificates(before: NaiveDateTime) -> Vec<SummaryCertificate> {
+    certificate_expirations()
+        .into_iter()
+        .filter(|certificate| certificate.expires_at < before)
+        .collect()
+}
+
+/// License limits which are close to being reached.
+fn license_limits() -> Vec<SummaryLicenseLimit> {
+    let license = get_cached_license();
+    get_counts()
+        .limit_usage(license.as_ref())
+        .into_iter()
+        .filter(|usage| usage.reached(LICENSE_LIMIT_WARNING_PERCENT))
+        .map(|usage| SummaryLicenseLimit {
+            name: usage.name.into(),
+            current: usage.current,
+            limit: usage.limit,
+        })
+        .collect()
+}
+
+/// Gather security summary of a given period.
+pub(crate) async fn collect_security_summary(
+    pool: &PgPool,
+    period_start: NaiveDateTime,
+    period_end: NaiveDateTime,
+) -> Result<SecuritySummary, SqlxError> {
+    let new_devices = query_as!(
+        SummaryDevice,
+        "SELECT u.username, d.name device_name, d.created added_at \
+        FROM device d JOIN \"user\" u ON d.user_id = u.id \
+        WHERE d.created >= $1 AND d.created < $2 ORDER BY d.created",
+        period_start,
+        period_end
+    )
+    .fetch_all(pool)
+    .await?;
+    let failed_logins = query_as!(
+        SummaryFailedLogins,
+        "SELECT username, count(*) \"attempts!\" FROM activity_log_event \
+        WHERE event IN ('user_login_failed', 'user_mfa_login_failed') \
+        AND timestamp >= $1 AND timestamp < $2 \
+        GROUP BY username HAVING count(*) >= $3 ORDER BY 2 DESC, username",
+        period_start,
+        period_end,
+        FAILED_LOGINS_SPIKE_THRESHOLD
+    )
+    .fetch_all(pool)
+    .await?;
+    let flapping_gateways = query_as!(
+        SummaryGateway,
+        "SELECT n.name location_name, g.hostname, count(*) \"disconnects!\" \
+        FROM gateway_disconnect g JOIN wireguard_network n ON g.location_id = n.id \
+        WHERE g.disconnected_at >= $1 AND g.disconnected_at < $2 \
+        GROUP BY n.id, g.hostname HAVING count(*) >= $3 ORDER BY 3 DESC, n.name, g.hostname",
+        period_start,
+        period_end,
+        GATEWAY_DISCONNECTS_THRESHOLD
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(SecuritySummary {
+        period_start,
+        period_end,
+        new_devices,
+        failed_logins,
+        flapping_gateways,
+        license_limits: license_limits(),
+        expiring_certificates: expiring_certificates(period_end + CERTIFICATE_EXPIRATION_WARNING),
+    })
+}
+
+/// Send security summary of a given period to all admin users.
+pub(crate) async fn send_security_summary(
+    pool: &PgPool,
+    mail_tx: &UnboundedSender<Mail>,
+    period_start: NaiveDateTime,
+    period_end: NaiveDateTime,
+) -> Result<(), SqlxError> {
+    let summary = collect_security_summary(pool, period_start, period_end).await?;
+    let mut recipients: i32 = 0;
+    match templates::security_summary_mail(&summary) {
+        Ok(content) => {
+            for admin in User::find_admins(pool).await? {
+                let mail = Mail {
+                    to: admin.email,
+                    subject: SECURITY_SUMMARY_SUBJECT.to_string(),
+                    content: content.clone(),
+                    attachments: Vec::new(),
+                    category: MailCategory::Security,
+                    result_tx: None,
+                };
+                let to = mail.to.clone();
+                match mail_tx.send(mail) {
+                    Ok(()) => {
+                        info!("Sent security summary to {to}");
+                        recipients += 1;
+                    }
+                    Err(err) => error!("Sending security summary to {to} failed: {err}"),
+                }
+            }
+        }
+        Err(err) => error!("Failed to render security summary: {err}"),
+    }
+
+    // record the summary even if it couldn't be sent, so it isn't retried until next period
+    query!(
+        "INSERT INTO security_summary (period_start, period_end, recipients) \
+        VALUES ($1, $2, $3)",
+        period_start,
+        period_end,
+        recipients
+    )
+    .execute(pool)
+    .await?;
+    // disconnects from previous periods are no longer needed
+    query!(
+        "DELETE FROM gateway_disconnect WHERE disconnected_at < $1",
+        period_start
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Periodically sends security summary to admin users, if enabled in settings.
+#[instrument(skip_all)]
+pub async fn run_security_summary_mailer(
+    pool: PgPool,
+    mail_tx: UnboundedSender<Mail>,
+) -> Result<(), SqlxError> {
+    info!("Starting security summary mailer");
+
+    loop {
+        if Settings::get_current_settings().security_summary_enabled {
+            debug!("Checking if security summary should be sent");
+            let now = Utc::now().naive_utc();
+            let last_period_end = query_scalar!("SELECT MAX(period_end) FROM security_summary")
+                .fetch_one(&pool)
+                .await?;
+            // summarize at most one period, even if summaries were disabled for a longer time
+            let period_start =
+                last_period_end.map_or(now - SUMMARY_PERIOD, |end| end.max(now - SUMMARY_PERIOD));
+            if last_period_end.is_none_or(|end| now - end >= SUMMARY_PERIOD) {
+                info!("Sending security summary since {period_start}");
+                send_security_summary(&pool, &mail_tx, period_start, now).await?;
+            }
+        }
+
+        // wait till next iteration
+        sleep(SUMMARY_LOOP_SLEEP).await;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use defguard_common::db::setup_pool;
+    use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+    use super::*;
+    use crate::db::{Device, WireguardNetwork, models::device::DeviceType};
+
+    #[sqlx::test]
+    async fn test_collect_security_summary(_: PgPoolOptions, options: PgConnectOptions) {
+        let pool = setup_pool(options).await;
+        let period_start = Utc::now().naive_utc() - SUMMARY_PERIOD;
+
+        let user = User::new(
+            "hpotter",
+            Some("pass123"),
+            "Potter",
+            "Harry",
+            "h.potter@hogwart.edu.uk",
+            None,
+        )
+        .save(&pool)
+        .await
+        .unwrap();
+        Device::new(
+            "Laptop".into(),
+            "key".into(),
+            user.id,
+            DeviceType::User,
+            None,
+            true,
+        )
+        .save(&pool)
+        .await
+        .unwrap();
+        let mut location = WireguardNetwork::default();
+        location.try_set_address("10.1.1.1/24").unwrap();
+        let location = location.save(&pool).await.unwrap();
+        for _ in 0..GATEWAY_DISCONNECTS_THRESHOLD {
+            record_gateway_disconnect(&pool, location.id, "gateway-1")
+                .await
+                .unwrap();
+        }
+        record_gateway_disconnect(&pool, location.id, "gateway-2")
+            .await
+            .unwrap();
+
+        let summary = collect_security_summary(&pool, period_start, Utc::now().naive_utc())
+            .await
+            .unwrap();
+        assert_eq!(summary.new_devices.len(), 1);
+        assert_eq!(summary.new_devices[0].username, "hpotter");
+        assert_eq!(summary.new_devices[0].device_name, "Laptop");
+        assert!(summary.failed_logins.is_empty());
+        assert_eq!(summary.flapping_gateways.len(), 1);
+        assert_eq!(summary.flapping_gateways[0].hostname, "gateway-1");
+        assert_eq!(
+            summary.flapping_gateways[0].disconnects,
+            GATEWAY_DISCONNECTS_THRESHOLD
+        );
+    }
+}
@@ -13,6 +13,17 @@ use crate::{
     server_config,
 };
 
+/// Fields holding credentials or keys, left out of configuration dumps for auditors.
+const AUDITOR_HIDDEN_FIELDS: &[&str] = &[
+    "smtp_password",
+    "smtp_oauth2_client_secret",
+    "mail_http_token",
+    "ldap_bind_password",
+    "license",
+    "prvkey",
+    "preshared_key",
+];
+
 /// Unwraps the result returning a JSON representation of value or error
 fn unwrap_json<S: Serialize, D: Display>(result: Result<S, D>) -> Value {
     match result {
@@ -27,6 +38,8 @@ pub async fn dump_config(db: &PgPool) -> Value {
     let settings = match Settings::get(db).await {
         Ok(Some(mut settings)) => {
             settings.smtp_password = None;
+            settings.smtp_oauth2_client_secret = None;
+            settings.mail_http_token = None;
             json!(settings)
         }
         Ok(None) => json!({"error": "Settings not found"}),
@@ -61,3 +74,25 @@ pub async fn dump_config(db: &PgPool) -> Value {
         "config": server_config(),
     })
 }
+
+/// Removes given fields from all objects in a JSON value.
+fn remove_fields(value: &mut Value, fields: &[&str]) {
+    match value {
+        Value::Array(values) => values
+            .iter_mut()
+            .for_each(|value| remove_fields(value, fields)),
+        Value::Object(map) => {
+            map.retain(|key, _| !fields.contains(&key.as_str()));
+            map.values_mut()
+                .for_each(|value| remove_fields(value, fields));
+        }
+        Value::Null | Value::Bool(_) | Value::Number(_) | Value::String(_) => {}
+    }
+}
+
+/// Dumps the same data as [`dump_config`] for auditors, without any credentials or keys.
+pub async fn dump_auditor_config(db: &PgPool) -> Value {
+    let mut config = dump_config(db).await;
+    remove_fields(&mut config, AUDITOR_HIDDEN_FIELDS);
+    config
+}
@@ -1,6 +1,7 @@
 use std::{collections::HashSet, time::Duration};
 
-use defguard_common::db::Id;
+use chrono::Utc;
+use defguard_common::{config::server_config, db::Id};
 use sqlx::{PgPool, query_as};
 use tokio::{
     sync::broadcast::Sender,
@@ -10,6 +11,7 @@ use tracing::Instrument;
 
 use crate::{
     db::{GatewayEvent, WireguardNetwork, models::wireguard::ServiceLocationMode},
+    device_approval::expire_device_approvals,
     enterprise::{
         db::models::acl::{AclRule, RuleState},
         directory_sync::{do_directory_sync, get_directory_sync_interval},
@@ -18,6 +20,7 @@ use crate::{
         limits::do_count_update,
     },
     updates::do_new_version_check,
+    wireguard_keepalive_tuning::do_keepalive_auto_tuning,
 };
 
 // Times in seconds
@@ -26,6 +29,9 @@ const COUNT_UPDATE_INTERVAL: u64 = 60 * 60;
 const UPDATES_CHECK_INTERVAL: u64 = 60 * 60 * 6;
 const EXPIRED_ACL_RULES_CHECK_INTERVAL: u64 = 60 * 5;
 const ENTERPRISE_STATUS_CHECK_INTERVAL: u64 = 60 * 5;
+const EXPIRED_DEVICE_APPROVALS_CHECK_INTERVAL: u64 = 60 * 5;
+const KEEPALIVE_AUTO_TUNING_INTERVAL: u64 = 60 * 60 * 6;
+const PRESHARED_KEY_ROTATION_CHECK_INTERVAL: u64 = 60 * 60;
 
 #[instrument(skip_all)]
 pub async fn run_utility_thread(
@@ -38,6 +44,9 @@ pub async fn run_utility_thread(
     let mut last_ldap_sync = Instant::now();
     let mut last_expired_acl_rules_check = Instant::now();
     let mut last_enterprise_status_check = Instant::now();
+    let mut last_expired_device_approvals_check = Instant::now();
+    let mut last_keepalive_auto_tuning = Instant::now();
+    let mut last_preshared_key_rotation_check = Instant::now();
 
     // helper variable which stores previous enterprise features status
     let mut enterprise_enabled = is_business_license_active();
@@ -88,11 +97,40 @@ pub async fn run_utility_thread(
         }
     };
 
+    let expired_device_approvals_task = || async {
+        if let Err(err) = expire_device_approvals(pool)
+            .instrument(info_span!("expired_device_approvals_task"))
+            .await
+        {
+            error!("Failed to expire device approval requests: {err}");
+        }
+    };
+
+    let keepalive_auto_tuning_task = || async {
+        if let Err(err) = do_keepalive_auto_tuning(pool, &wireguard_tx)
+            .instrument(info_span!("keepalive_auto_tuning_task"))
+            .await
+        {
+            error!("Failed to apply keepalive recommendations: {err}");
+        }
+    };
+
+    let preshared_key_rotation_task = || async {
+        if let Err(err) = preshared_key_rotation(pool, &wireguard_tx)
+            .instrument(info_span!("preshared_key_rotation_task"))
+            .await
+        {
+            error!("Failed to rotate preshared keys: {err}");
+        }
+    };
+
     directory_sync_task().await;
     count_update_task().await;
     updates_check_task().await;
     ldap_sync_task().await;
     expired_acl_rules_task().await;
+    expired_device_approvals_task().await;
+    preshared_key_rotation_task().await;
 
     loop {
         sleep(Duration::from_secs(UTILITY_THREAD_MAIN_SLEEP_TIME)).await;
@@ -127,6 +165,30 @@ pub async fn run_utility_thread(
             last_expired_acl_rules_check = Instant::now();
         }
 
+        // Expire unresolved device approval requests
+        if last_expired_device_approvals_check.elapsed().as_secs()
+            >= EXPIRED_DEVICE_APPROVALS_CHECK_INTERVAL
+        {
+            expired_device_approvals_task().await;
+            last_expired_device_approvals_check = Instant::now();
+        }
+
+        // Apply keepalive recommendations to locations, if enabled
+        if server_config().keepalive_auto_tuning
+            && last_keepalive_auto_tuning.elapsed().as_secs() >= KEEPALIVE_AUTO_TUNING_INTERVAL
+        {
+            keepalive_auto_tuning_task().await;
+            last_keepalive_auto_tuning = Instant::now();
+        }
+
+        // Rotate static preshared keys of locations
+        if last_preshared_key_rotation_check.elapsed().as_secs()
+            >= PRESHARED_KEY_ROTATION_CHECK_INTERVAL
+        {
+            preshared_key_rotation_task().await;
+            last_preshared_key_rotation_check = Instant::now();
+        }
+
         // Check if enterprise features got enabled or disabled
         if last_enterprise_status_check.elapsed().as_secs() >= ENTERPRISE_STATUS_CHECK_INTERVAL {
             let new_enterprise_enabled = is_business_license_active();
@@ -285,3 +347,35 @@ async fn expired_acl_rules_check(
 
     Ok(())
 }
+
+/// Rotate static preshared keys of locations for which the rotation period has passed and send
+/// new keys to gateways. Clients receive new keys with their device configuration.
+async fn preshared_key_rotation(
+    pool: &PgPool,
+    wireguard_tx: &Sender<GatewayEvent>,
+) -> Result<(), anyhow::Error> {
+    for mut location in WireguardNetwork::all(pool).await? {
+        if !location.preshared_keys_rotation_due() {
+            continue;
+        }
+        debug!("Rotating preshared keys for location {location}");
+        let mut transaction = pool.begin().await?;
+        location.preshared_keys_rotated_at = Some(Utc::now().naive_utc());
+        location.save(&mut *transaction).await?;
+        location
+            .generate_preshared_keys(&mut transaction, true)
+            .await?;
+        let peers = location.get_peers(&mut *transaction).await?;
+        let maybe_firewall_config = location.try_get_firewall_config(&mut transaction).await?;
+        transaction.commit().await?;
+        wireguard_tx.send(GatewayEvent::NetworkModified(
+            location.id,
+            location.clone(),
+            peers,
+            maybe_firewall_config,
+        ))?;
+        info!("Rotated preshared keys for location {location}");
+    }
+
+    Ok(())
+}
@@ -1,6 +1,7 @@
-use std::{array::TryFromSliceError, net::IpAddr};
+use std::{array::TryFromSliceError, fmt::Write, net::IpAddr};
 
 use base64::{DecodeError, Engine, prelude::BASE64_STANDARD};
+use defguard_common::{csv::AsCsv, db::Id};
 use ipnetwork::{IpNetwork, IpNetworkError};
 use thiserror::Error;
 use x25519_dalek::{PublicKey, StaticSecret};
@@ -22,6 +23,82 @@ pub struct ImportedDevice {
     pub name: String,
     pub wireguard_pubkey: String,
     pub wireguard_ips: Vec<IpAddr>,
+    /// User email taken from `# Email = ...` peer annotation, used to match the peer with a user.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+/// Peer of an exported network, rendered as a `[Peer]` section of wg-quick server config.
+#[derive(Clone, Debug)]
+pub(crate) struct ExportedPeer {
+    pub device_id: Id,
+    pub name: String,
+    pub wireguard_pubkey: String,
+    pub email: String,
+    pub wireguard_ips: Vec<IpAddr>,
+    pub preshared_key: Option<String>,
+}
+
+/// Metadata stored in comments of a `[Peer]` section, e.g. `# Name = laptop` or
+/// `# Email: user@example.com`. Comment lines placed directly above the section header are
+/// attributed to that peer as well.
+#[derive(Debug, Default, PartialEq)]
+struct PeerAnnotations {
+    name: Option<String>,
+    email: Option<String>,
+}
+
+impl PeerAnnotations {
+    fn parse_comment(&mut self, comment: &str) {
+        let Some((key, value)) = comment.split_once(['=', ':']) else {
+            return;
+        };
+        let value = value.trim();
+        if value.is_empty() {
+            return;
+        }
+        match key.trim().to_lowercase().as_str() {
+            "name" => self.name = Some(value.to_string()),
+            "email" => self.email = Some(value.to_string()),
+            _ => (),
+        }
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.name = self.name.take().or(other.name);
+        self.email = self.email.take().or(other.email);
+    }
+}
+
+/// Collect annotations for each `[Peer]` section, in order of appearance.
+/// The `ini` parser drops comments, so the raw config has to be scanned separately.
+fn parse_peer_annotations(config: &str) -> Vec<PeerAnnotations> {
+    let mut peers = Vec::new();
+    let mut pending = PeerAnnotations::default();
+    let mut in_peer = false;
+    for line in config.lines().map(str::trim) {
+        if let Some(comment) = line.strip_prefix(['#', ';']) {
+            pending.parse_comment(comment);
+        } else if line.starts_with('[') {
+            in_peer = line == "[Peer]";
+            if in_peer {
+                peers.push(std::mem::take(&mut pending));
+            } else {
+                pending = PeerAnnotations::default();
+            }
+        } else if !line.is_empty() {
+            // comments inside a section body belong to that section
+            let annotations = std::mem::take(&mut pending);
+            if let Some(peer) = peers.last_mut().filter(|_| in_peer) {
+                peer.merge(annotations);
+            }
+        }
+    }
+    // trailing comments belong to the last peer
+    if let Some(peer) = peers.last_mut().filter(|_| in_peer) {
+        peer.merge(pending);
+    }
+    peers
 }
 
 #[derive(Debug, Error)]
@@ -61,6 +138,17 @@ impl From<DecodeError> for WireguardConfigParseError {
 pub(crate) fn parse_wireguard_config(
     config: &str,
 ) -> Result<(WireguardNetwork, Vec<ImportedDevice>), WireguardConfigParseError> {
+    parse_wireguard_config_with_address(config, None)
+}
+
+/// Parse wg-quick config or `wg showconf` dump.
+/// The latter has no `Address` key, so interface addresses have to be provided in
+/// `default_address` (comma-separated list of networks).
+pub(crate) fn parse_wireguard_config_with_address(
+    config: &str,
+    default_address: Option<&str>,
+) -> Result<(WireguardNetwork, Vec<ImportedDevice>), WireguardConfigParseError> {
+    let annotations = parse_peer_annotations(config);
     let config = ini::Ini::load_from_str(config)?;
     // Parse WireGuardNetwork
     let interface_section = config
@@ -77,6 +165,7 @@ pub(crate) fn parse_wireguard_config(
         BASE64_STANDARD.encode(PublicKey::from(&StaticSecret::from(prvkey_bytes)).to_bytes());
     let address = interface_section
         .get("Address")
+        .or(default_address)
         .ok_or_else(|| WireguardConfigParseError::KeyNotFound("Address"))?;
     let port = interface_section
         .get("ListenPort")
@@ -121,7 +210,9 @@ pub(crate) fn parse_wireguard_config(
     let peer_sections = config.section_all(Some("Peer"));
 
     let mut devices = Vec::new();
+    let mut annotations = annotations.into_iter();
     for peer in peer_sections {
+        let annotation = annotations.next().unwrap_or_default();
         let allowed_ips = peer
             .get("AllowedIPs")
             .ok_or_else(|| WireguardConfigParseError::KeyNotFound("AllowedIPs"))?;
@@ -160,15 +251,57 @@ pub(crate) fn parse_wireguard_config(
 
         devices.push(ImportedDevice {
             user_id: None,
-            name: pubkey.to_string(),
+            name: annotation.name.unwrap_or_else(|| pubkey.to_string()),
             wireguard_pubkey: pubkey.to_string(),
             wireguard_ips: peer_addresses,
+            email: annotation.email,
         });
     }
 
     Ok((network, devices))
 }
 
+/// Render wg-quick server config for a network.
+/// Peer names and user emails are stored as annotations, so the config can be imported back.
+pub(crate) fn render_server_config(
+    network: &WireguardNetwork<Id>,
+    peers: &[ExportedPeer],
+) -> String {
+    let mut config = format!(
+        "[Interface]\n\
+        PrivateKey = {}\n\
+        Address = {}\n\
+        ListenPort = {}\n",
+        network.prvkey,
+        network.address.as_csv(),
+        network.port,
+    );
+    for peer in peers {
+        let allowed_ips = peer
+            .wireguard_ips
+            .iter()
+            .map(|ip| IpNetwork::from(*ip))
+            .collect::<Vec<_>>();
+        // writing to `String` never fails
+        let _ = write!(
+            config,
+            "\n# Name = {}\n\
+            # Email = {}\n\
+            [Peer]\n\
+            PublicKey = {}\n\
+            AllowedIPs = {}\n",
+            peer.name,
+            peer.email,
+            peer.wireguard_pubkey,
+            allowed_ips.as_csv(),
+        );
+        if let Some(preshared_key) = &peer.preshared_key {
+            let _ = writeln!(config, "PresharedKey = {preshared_key}");
+        }
+    }
+    config
+}
+
 #[cfg(test)]
 mod test {
     use defguard_common::db::NoId;
@@ -320,4 +453,101 @@ mod test {
             ]
         );
     }
+
+    #[test]
+    fn test_parse_showconf_dump_with_annotations() {
+        // `wg showconf` output has no `Address` and `DNS` keys
+        let config = "
+            [Interface]
+            ListenPort = 55055
+            PrivateKey = GAA2X3DW0WakGVx+DsGjhDpTgg50s1MlmrLf24Psrlg=
+
+            # Name = laptop
+            # Email: h.potter@hogwart.edu.uk
+            [Peer]
+            PublicKey = 2LYRr2HgSSpGCdXKDDAlcFe0Uuc6RR8TFgSquNc9VAE=
+            PresharedKey = 0r8QUu+Dd2n8PvcwjPnsZnHKWjHEYRC3GI2kmWYUNTY=
+            AllowedIPs = 10.0.0.10/32
+            Endpoint = 192.168.1.10:51820
+
+            [Peer]
+            PublicKey = OLQNaEH3FxW0hiodaChEHoETzd+7UzcqIbsLs+X8rD0=
+            # email = r.weasley@hogwart.edu.uk
+            AllowedIPs = 10.0.0.11/32
+        ";
+        assert!(matches!(
+            parse_wireguard_config(config),
+            Err(WireguardConfigParseError::KeyNotFound("Address"))
+        ));
+
+        let (network, devices) =
+            parse_wireguard_config_with_address(config, Some("10.0.0.1/24")).unwrap();
+        assert_eq!(network.address, vec!["10.0.0.1/24".parse().unwrap()]);
+        assert_eq!(network.port, 55055);
+        assert_eq!(network.dns, None);
+
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[0].name, "laptop");
+        assert_eq!(devices[0].email.as_deref(), Some("h.potter@hogwart.edu.uk"));
+        assert_eq!(
+            devices[0].wireguard_ips,
+            vec!["10.0.0.10".parse::<IpAddr>().unwrap()]
+        );
+        assert_eq!(
+            devices[1].name,
+            "OLQNaEH3FxW0hiodaChEHoETzd+7UzcqIbsLs+X8rD0="
+        );
+        assert_eq!(
+            devices[1].email.as_deref(),
+            Some("r.weasley@hogwart.edu.uk")
+        );
+    }
+
+    #[test]
+    fn test_render_server_config() {
+        let network = WireguardNetwork {
+            id: 1,
+            prvkey: "GAA2X3DW0WakGVx+DsGjhDpTgg50s1MlmrLf24Psrlg=".into(),
+            address: vec![
+                "10.0.0.1/24".parse().unwrap(),
+                "fc00::1/112".parse().unwrap(),
+            ],
+            port: 55055,
+            ..Default::default()
+        };
+        let peers = vec![
+            ExportedPeer {
+                device_id: 1,
+                name: "laptop".into(),
+                wireguard_pubkey: "2LYRr2HgSSpGCdXKDDAlcFe0Uuc6RR8TFgSquNc9VAE=".into(),
+                email: "h.potter@hogwart.edu.uk".into(),
+                wireguard_ips: vec!["10.0.0.10".parse().unwrap(), "fc00::10".parse().unwrap()],
+                preshared_key: None,
+            },
+            ExportedPeer {
+                device_id: 2,
+                name: "phone".into(),
+                wireguard_pubkey: "OLQNaEH3FxW0hiodaChEHoETzd+7UzcqIbsLs+X8rD0=".into(),
+                email: "r.weasley@hogwart.edu.uk".into(),
+                wireguard_ips: vec!["10.0.0.11".parse().unwrap()],
+                preshared_key: Some("0r8QUu+Dd2n8PvcwjPnsZnHKWjHEYRC3GI2kmWYUNTY=".into()),
+            },
+        ];
+        let config = render_server_config(&network, &peers);
+        assert!(config.contains("AllowedIPs = 10.0.0.10/32,fc00::10/128\n"));
+        assert!(config.contains("PresharedKey = 0r8QUu+Dd2n8PvcwjPnsZnHKWjHEYRC3GI2kmWYUNTY=\n"));
+
+        // exported config can be imported back
+        let (imported_network, devices) = parse_wireguard_config(&config).unwrap();
+        assert_eq!(imported_network.prvkey, network.prvkey);
+        assert_eq!(imported_network.address, network.address);
+        assert_eq!(imported_network.port, network.port);
+        assert_eq!(devices.len(), 2);
+        for (device, peer) in devices.iter().zip(&peers) {
+            assert_eq!(device.name, peer.name);
+            assert_eq!(device.email.as_ref(), Some(&peer.email));
+            assert_eq!(device.wireguard_pubkey, peer.wireguard_pubkey);
+            assert_eq!(device.wireguard_ips, peer.wireguard_ips);
+        }
+    }
 }
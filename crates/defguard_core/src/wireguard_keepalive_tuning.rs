@@ -0,0 +1,230 @@
+//! This module implements keepalive interval recommendations for VPN locations.
+//! Signals are derived from peer stats reported by gateways: WireGuard peers re-handshake
+//! every 2 minutes while the tunnel is in use, so a longer gap between consecutive handshakes
+//! of a connected peer means handshakes were lost, usually because a NAT mapping expired.
+//! Locations with frequent or irregular handshakes get a shorter keepalive interval,
+//! stable ones may get a longer one to reduce overhead.
+//!
+//! MTU is not tuned, since gateway configuration doesn't include it.
+
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use defguard_common::db::Id;
+use sqlx::{Error as SqlxError, PgExecutor, PgPool, query_as};
+use tokio::sync::broadcast::Sender;
+use utoipa::ToSchema;
+
+use crate::db::{GatewayEvent, WireguardNetwork};
+
+// Handshakes further apart than this (in seconds) are considered lost
+const HANDSHAKE_GAP_THRESHOLD: f64 = 180.0;
+// Period of peer stats taken into account
+const SIGNALS_PERIOD: TimeDelta = TimeDelta::hours(24);
+// Minimal number of observed handshakes to make a recommendation
+const MIN_HANDSHAKES: i64 = 100;
+// Share of lost handshakes above which the keepalive interval should be shortened
+const MAX_GAP_RATIO: f64 = 0.05;
+// Share of lost handshakes below which the keepalive interval may be extended
+const STABLE_GAP_RATIO: f64 = 0.01;
+// Standard deviation of handshake intervals (in seconds) considered unstable
+const MAX_JITTER: f64 = 60.0;
+const MIN_KEEPALIVE: i32 = 10;
+const STABLE_KEEPALIVE: i32 = 25;
+
+/// Handshake signals collected for a location.
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema)]
+pub struct HandshakeSignals {
+    /// Number of observed handshakes
+    pub handshakes: i64,
+    /// Number of handshakes which happened later than expected
+    pub handshake_gaps: i64,
+    /// Standard deviation of intervals between handshakes in seconds
+    pub jitter: f64,
+}
+
+impl HandshakeSignals {
+    pub async fn fetch<'e, E>(
+        executor: E,
+        location: &WireguardNetwork<Id>,
+        from: NaiveDateTime,
+    ) -> Result<Self, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        // reconnects after the peer has been disconnected are not handshake failures
+        let disconnect_threshold = f64::from(location.peer_disconnect_threshold);
+        query_as!(
+            Self,
+            "SELECT COUNT(*) FILTER (WHERE gap > 0) \"handshakes!\", \
+            COUNT(*) FILTER (WHERE gap > $3 AND gap < $4) \"handshake_gaps!\", \
+            COALESCE(stddev_pop(gap) FILTER (WHERE gap > 0 AND gap < $4), 0)::float8 \"jitter!\" \
+            FROM (SELECT EXTRACT(EPOCH FROM latest_handshake_diff)::float8 gap \
+            FROM wireguard_peer_stats_view WHERE network = $1 AND collected_at >= $2) stats",
+            location.id,
+            from,
+            HANDSHAKE_GAP_THRESHOLD,
+            disconnect_threshold,
+        )
+        .fetch_one(executor)
+        .await
+    }
+
+    fn gap_ratio(&self) -> f64 {
+        if self.handshakes == 0 {
+            0.0
+        } else {
+            self.handshake_gaps as f64 / self.handshakes as f64
+        }
+    }
+}
+
+/// Keepalive interval recommendation for a location.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct KeepaliveRecommendation {
+    pub location_id: Id,
+    pub current_keepalive: i32,
+    pub recommended_keepalive: i32,
+    pub reason: String,
+    pub signals: HandshakeSignals,
+}
+
+impl KeepaliveRecommendation {
+    pub async fn for_location<'e, E>(
+        executor: E,
+        location: &WireguardNetwork<Id>,
+    ) -> Result<Self, SqlxError>
+    where
+        E: PgExecutor<'e>,
+    {
+        let from = Utc::now().naive_utc() - SIGNALS_PERIOD;
+        let signals = HandshakeSignals::fetch(executor, location, from).await?;
+        let (recommended_keepalive, reason) =
+            recommend_keepalive(location.keepalive_interval, &signals);
+
+        Ok(Self {
+            location_id: location.id,
+            current_keepalive: location.keepalive_interval,
+            recommended_keepalive,
+            reason,
+            signals,
+        })
+    }
+
+    #[must_use]
+    pub fn is_change(&self) -> bool {
+        self.recommended_keepalive != self.current_keepalive
+    }
+}
+
+/// Suggest a keepalive interval based on handshake signals. Returns the interval and a reason.
+fn recommend_keepalive(current: i32, signals: &HandshakeSignals) -> (i32, String) {
+    if signals.handshakes < MIN_HANDSHAKES {
+        return (current, "Not enough handshakes observed".into());
+    }
+    let gap_ratio = signals.gap_ratio();
+    if gap_ratio > MAX_GAP_RATIO || signals.jitter > MAX_JITTER {
+        let recommended = if current == 0 {
+            STABLE_KEEPALIVE
+        } else {
+            (current / 2).max(MIN_KEEPALIVE)
+        };
+        if recommended < current || current == 0 {
+            return (
+                recommended,
+                format!(
+                    "{:.1}% of handshakes were late (jitter {:.0}s), peers are likely losing NAT mappings",
+                    gap_ratio * 100.0,
+                    signals.jitter
+                ),
+            );
+        }
+        return (
+            current,
+            "Handshakes are unstable, but keepalive is already at its minimum".into(),
+        );
+    }
+    if gap_ratio < STABLE_GAP_RATIO && current > 0 && current < STABLE_KEEPALIVE {
+        return (
+            STABLE_KEEPALIVE,
+            "Handshakes are stable, a longer keepalive interval reduces overhead".into(),
+        );
+    }
+
+    (current, "Current keepalive interval works well".into())
+}
+
+/// Apply recommended keepalive interval to a location and send updated configuration
+/// to its gateways.
+async fn apply_recommendation(
+    pool: &PgPool,
+    location: &mut WireguardNetwork<Id>,
+    recommendation: &KeepaliveRecommendation,
+    wireguard_tx: &Sender<GatewayEvent>,
+) -> Result<(), anyhow::Error> {
+    location.keepalive_interval = recommendation.recommended_keepalive;
+    let mut transaction = pool.begin().await?;
+    location.save(&mut *transaction).await?;
+    let peers = location.get_peers(&mut *transaction).await?;
+    let maybe_firewall_config = location.try_get_firewall_config(&mut transaction).await?;
+    transaction.commit().await?;
+    wireguard_tx.send(GatewayEvent::NetworkModified(
+        location.id,
+        location.clone(),
+        peers,
+        maybe_firewall_config,
+    ))?;
+    info!(
+        "Changed keepalive interval of location {location} from {} to {}: {}",
+        recommendation.current_keepalive,
+        recommendation.recommended_keepalive,
+        recommendation.reason
+    );
+
+    Ok(())
+}
+
+/// Apply keepalive recommendations to all locations.
+/// Used if `DEFGUARD_KEEPALIVE_AUTO_TUNING` is enabled.
+pub async fn do_keepalive_auto_tuning(
+    pool: &PgPool,
+    wireguard_tx: &Sender<GatewayEvent>,
+) -> Result<(), anyhow::Error> {
+    for mut location in WireguardNetwork::all(pool).await? {
+        let recommendation = KeepaliveRecommendation::for_location(pool, &location).await?;
+        if recommendation.is_change() {
+            apply_recommendation(pool, &mut location, &recommendation, wireguard_tx).await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn signals(handshakes: i64, handshake_gaps: i64, jitter: f64) -> HandshakeSignals {
+        HandshakeSignals {
+            handshakes,
+            handshake_gaps,
+            jitter,
+        }
+    }
+
+    #[test]
+    fn test_recommend_keepalive() {
+        // not enough data
+        assert_eq!(recommend_keepalive(25, &signals(10, 10, 100.0)).0, 25);
+        // frequent handshake gaps shorten the interval
+        assert_eq!(recommend_keepalive(25, &signals(1000, 100, 10.0)).0, 12);
+        assert_eq!(recommend_keepalive(15, &signals(1000, 100, 10.0)).0, 10);
+        assert_eq!(recommend_keepalive(10, &signals(1000, 100, 10.0)).0, 10);
+        // keepalive gets enabled if it was disabled
+        assert_eq!(recommend_keepalive(0, &signals(1000, 0, 120.0)).0, 25);
+        // stable handshakes allow a longer interval
+        assert_eq!(recommend_keepalive(10, &signals(1000, 5, 10.0)).0, 25);
+        assert_eq!(recommend_keepalive(25, &signals(1000, 5, 10.0)).0, 25);
+        assert_eq!(recommend_keepalive(60, &signals(1000, 0, 10.0)).0, 60);
+        // disabled keepalive with stable handshakes stays disabled
+        assert_eq!(recommend_keepalive(0, &signals(1000, 0, 10.0)).0, 0);
+    }
+}
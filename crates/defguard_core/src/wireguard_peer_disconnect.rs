@@ -3,6 +3,7 @@
 //! If a device does not disconnect explicitly and just becomes inactive
 //! it should be removed from gateway configuration and marked as "not allowed",
 //! which enforces an authentication requirement to connect again.
+//! The same happens to devices which exceed location MFA session lifetime, even if active.
 
 use std::{
     net::{IpAddr, Ipv4Addr},
@@ -27,7 +28,10 @@ use crate::{
         Device, GatewayEvent, WireguardNetwork,
         models::{
             device::{DeviceInfo, DeviceNetworkInfo, DeviceType, WireguardNetworkDevice},
-            wireguard::{LocationMfaMode, ServiceLocationMode, WireguardNetworkError},
+            wireguard::{
+                DeviceNameUniqueness, GatewayDistributionPolicy, IpAllocationStrategy,
+                LocationMfaMode, ServiceLocationMode, WireguardNetworkError,
+            },
         },
     },
     events::{InternalEvent, InternalEventContext},
@@ -98,7 +102,16 @@ pub async fn run_periodic_peer_disconnect(
                 id, name, address, port, pubkey, prvkey, endpoint, dns, allowed_ips, \
                 connected_at, keepalive_interval, peer_disconnect_threshold, \
                 acl_enabled, acl_default_allow, location_mfa_mode \"location_mfa_mode: LocationMfaMode\", \
-                service_location_mode \"service_location_mode: ServiceLocationMode\" \
+                service_location_mode \"service_location_mode: ServiceLocationMode\", \
+                min_desktop_client_version, min_mobile_client_version, device_approval_required, \
+            gateway_distribution_policy \"gateway_distribution_policy: GatewayDistributionPolicy\", \
+            gateway_peer_sharding, \
+            ip_allocation_strategy \"ip_allocation_strategy: IpAllocationStrategy\", \
+            client_traffic_policy \"client_traffic_policy: _\", \
+            mfa_session_lifetime_hours, mfa_remember_device_hours, \
+            preshared_keys_enabled, preshared_key_rotation_days, preshared_keys_rotated_at, \
+            device_name_pattern, device_name_prefix, \
+            device_name_uniqueness \"device_name_uniqueness: DeviceNameUniqueness\" \
             FROM wireguard_network WHERE location_mfa_mode != 'disabled'::location_mfa_mode",
         )
         .fetch_all(&pool)
@@ -106,7 +119,7 @@ pub async fn run_periodic_peer_disconnect(
 
         // loop over all locations
         for location in locations {
-            debug!("Fetching inactive devices for location {location}");
+            debug!("Fetching inactive and expired devices for location {location}");
             let devices = query_as!(
                 DeviceWithEndpoint,
                 "WITH stats AS ( \
@@ -122,10 +135,12 @@ pub async fn run_periodic_peer_disconnect(
             LEFT JOIN stats on d.id = stats.device_id \
             WHERE wnd.wireguard_network_id = $1 AND wnd.is_authorized = true \
             AND d.configured = true \
-            AND (NOW() - wnd.authorized_at) > $2 * interval '1 second' \
-            AND (NOW() - stats.latest_handshake) > $2 * interval '1 second'",
+            AND (((NOW() - wnd.authorized_at) > $2 * interval '1 second' \
+                AND (NOW() - stats.latest_handshake) > $2 * interval '1 second') \
+                OR ($3 > 0 AND (NOW() - wnd.authorized_at) > $3 * interval '1 hour'))",
                 location.id,
-                f64::from(location.peer_disconnect_threshold)
+                f64::from(location.peer_disconnect_threshold),
+                location.mfa_session_lifetime_hours
             )
             .fetch_all(&pool)
             .await?;
@@ -0,0 +1,230 @@
+//! This module implements periodic reconciliation of connected VPN clients.
+//! Inactive clients are normally disconnected while processing stats sent by a gateway.
+//! If a gateway goes away without reporting (e.g. it crashed), its clients would be
+//! considered connected forever, so they are disconnected here instead.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use defguard_common::db::Id;
+use sqlx::{Error as SqlxError, PgPool};
+use thiserror::Error;
+use tokio::{
+    sync::mpsc::{UnboundedSender, error::SendError},
+    time::sleep,
+};
+
+use crate::{
+    db::WireguardNetwork,
+    events::GrpcEvent,
+    grpc::gateway::client_state::{ClientMap, ClientMapError},
+};
+
+// How long to sleep between loop iterations
+const RECONCILIATION_LOOP_SLEEP: Duration = Duration::from_secs(60); // 1 minute
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Error)]
+pub enum SessionReconciliationError {
+    #[error(transparent)]
+    DbError(#[from] SqlxError),
+    #[error(transparent)]
+    ClientMapError(#[from] ClientMapError),
+    #[error("Failed to acquire lock on VPN client state map")]
+    ClientStateMutexError,
+    #[error("Failed to send gRPC event: {0}")]
+    GrpcEventError(#[from] SendError<GrpcEvent>),
+}
+
+/// Run periodic session reconciliation task
+///
+/// Disconnect VPN clients which have not had a handshake for longer than
+/// their location's peer disconnect threshold.
+#[instrument(skip_all)]
+pub async fn run_periodic_session_reconciliation(
+    pool: PgPool,
+    client_state: Arc<Mutex<ClientMap>>,
+    grpc_event_tx: UnboundedSender<GrpcEvent>,
+) -> Result<(), SessionReconciliationError> {
+    info!("Starting periodic reconciliation of connected VPN clients");
+    loop {
+        debug!("Starting VPN client session reconciliation");
+        reconcile_sessions(&pool, &client_state, &grpc_event_tx).await?;
+
+        // wait till next iteration
+        debug!("Sleeping until next iteration");
+        sleep(RECONCILIATION_LOOP_SLEEP).await;
+    }
+}
+
+/// Disconnect inactive VPN clients in all locations and emit disconnect events for them.
+pub(crate) async fn reconcile_sessions(
+    pool: &PgPool,
+    client_state: &Arc<Mutex<ClientMap>>,
+    grpc_event_tx: &UnboundedSender<GrpcEvent>,
+) -> Result<(), SessionReconciliationError> {
+    let location_ids: Vec<Id> = client_state
+        .lock()
+        .map_err(|_| SessionReconciliationError::ClientStateMutexError)?
+        .location_ids();
+
+    for location_id in location_ids {
+        let Some(location) = WireguardNetwork::find_by_id(pool, location_id).await? else {
+            warn!("Location {location_id} no longer exists, removing its connected VPN clients");
+            client_state
+                .lock()
+                .map_err(|_| SessionReconciliationError::ClientStateMutexError)?
+                .remove_location(location_id);
+            continue;
+        };
+
+        // perform client state operations in a dedicated block to drop mutex guard
+        let disconnected_clients = {
+            let mut client_map = client_state
+                .lock()
+                .map_err(|_| SessionReconciliationError::ClientStateMutexError)?;
+            client_map.disconnect_inactive_vpn_clients_for_location(&location)?
+        };
+
+        if !disconnected_clients.is_empty() {
+            info!(
+                "Disconnected {} inactive VPN clients in location {location}",
+                disconnected_clients.len()
+            );
+        }
+        for (device, context) in disconnected_clients {
+            grpc_event_tx.send(GrpcEvent::ClientDisconnected {
+                context,
+                location: location.clone(),
+                device,
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::SocketAddr;
+
+    use chrono::{TimeDelta, Utc};
+    use defguard_common::db::{NoId, setup_pool};
+    use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+    use tokio::sync::mpsc::unbounded_channel;
+
+    use super::*;
+    use crate::db::{
+        Device, User,
+        models::{device::DeviceType, wireguard_peer_stats::WireguardPeerStats},
+    };
+
+    #[sqlx::test]
+    async fn test_reconcile_sessions(_: PgPoolOptions, options: PgConnectOptions) {
+        let pool = setup_pool(options).await;
+        let mut location = WireguardNetwork::default();
+        location.try_set_address("10.1.1.1/24").unwrap();
+        let location = location.save(&pool).await.unwrap();
+        let user = User::new(
+            "testuser",
+            Some("hunter2"),
+            "Tester",
+            "Test",
+            "test@test.com",
+            None,
+        )
+        .save(&pool)
+        .await
+        .unwrap();
+        let endpoint: SocketAddr = "1.2.3.4:51820".parse().unwrap();
+
+        let client_state = Arc::new(Mutex::new(ClientMap::new()));
+        for (name, handshake_age) in [("active", 10), ("stale", 3600)] {
+            let device = Device::new(
+                name.into(),
+                name.into(),
+                user.id,
+                DeviceType::User,
+                None,
+                true,
+            )
+            .save(&pool)
+            .await
+            .unwrap();
+            let stats = WireguardPeerStats {
+                id: NoId,
+                device_id: device.id,
+                collected_at: Utc::now().naive_utc(),
+                network: location.id,
+                endpoint: Some(endpoint.to_string()),
+                upload: 0,
+                download: 0,
+                latest_handshake: Utc::now().naive_utc() - TimeDelta::seconds(handshake_age),
+                allowed_ips: None,
+                gateway: None,
+            };
+            client_state
+                .lock()
+                .unwrap()
+                .connect_vpn_client(
+                    location.id,
+                    "gateway",
+                    name,
+                    &device,
+                    &user,
+                    endpoint,
+                    &stats,
+                )
+                .unwrap();
+        }
+        // clients of a deleted location
+        let device = Device::find_by_pubkey(&pool, "active")
+            .await
+            .unwrap()
+            .unwrap();
+        client_state
+            .lock()
+            .unwrap()
+            .connect_vpn_client(
+                location.id + 1,
+                "gateway",
+                "orphan",
+                &device,
+                &user,
+                endpoint,
+                &WireguardPeerStats {
+                    id: NoId,
+                    device_id: 0,
+                    collected_at: Utc::now().naive_utc(),
+                    network: location.id + 1,
+                    endpoint: None,
+                    upload: 0,
+                    download: 0,
+                    latest_handshake: Utc::now().naive_utc(),
+                    allowed_ips: None,
+                    gateway: None,
+                },
+            )
+            .unwrap();
+
+        let (grpc_event_tx, mut grpc_event_rx) = unbounded_channel();
+        reconcile_sessions(&pool, &client_state, &grpc_event_tx)
+            .await
+            .unwrap();
+
+        let event = grpc_event_rx.try_recv().unwrap();
+        assert!(matches!(
+            event,
+            GrpcEvent::ClientDisconnected { location: ref event_location, ref device, .. }
+                if event_location.id == location.id && device.name == "stale"
+        ));
+        assert!(grpc_event_rx.try_recv().is_err());
+
+        let mut client_map = client_state.lock().unwrap();
+        assert_eq!(client_map.location_ids(), vec![location.id]);
+        assert!(client_map.get_vpn_client(location.id, "active").is_some());
+        assert!(client_map.get_vpn_client(location.id, "stale").is_none());
+    }
+}
@@ -0,0 +1,541 @@
+use std::{
+    collections::{HashMap, HashSet},
+    net::{IpAddr, SocketAddr},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use chrono::Utc;
+use defguard_common::db::Id;
+use sqlx::PgPool;
+use tokio::{
+    sync::mpsc::{self, Receiver, error::TrySendError},
+    task::JoinSet,
+    time::interval,
+};
+
+use crate::db::models::{
+    device_endpoint_change::DeviceEndpointChange, wireguard_peer_stats::WireguardPeerStats,
+};
+
+// Batches at least this large are written with `COPY` instead of a multi-row `INSERT`
+const COPY_THRESHOLD: usize = 100;
+
+/// Counters describing peer stats ingestion for a single shard.
+#[derive(Debug, Default)]
+pub struct StatsIngestMetrics {
+    /// Stats records accepted into the shard queue
+    pub received: AtomicU64,
+    /// Stats records dropped because the shard queue was full
+    pub dropped: AtomicU64,
+    /// Stats records written to the database
+    pub written: AtomicU64,
+    /// Stats records lost because a database write failed
+    pub failed: AtomicU64,
+    /// Number of batches written to the database
+    pub batches: AtomicU64,
+    /// Time between collection of the oldest record in the last batch and its write, in milliseconds
+    pub lag_ms: AtomicU64,
+}
+
+impl StatsIngestMetrics {
+    fn inc(counter: &AtomicU64, value: u64) {
+        counter.fetch_add(value, Ordering::Relaxed);
+    }
+}
+
+/// Queue and metrics of a single ingestion shard.
+#[derive(Clone)]
+struct StatsShard {
+    tx: mpsc::Sender<WireguardPeerStats>,
+    metrics: Arc<StatsIngestMetrics>,
+}
+
+/// Cloneable handle used to queue peer stats for ingestion.
+///
+/// Stats are routed to shards by location, so a location reporting many peers
+/// only fills up its own shard queue.
+#[derive(Clone)]
+pub struct StatsIngestHandle {
+    shards: Arc<[StatsShard]>,
+}
+
+impl StatsIngestHandle {
+    /// Queue stats record for ingestion.
+    ///
+    /// Never waits for the database. If the shard queue is full the record is dropped
+    /// and counted in [`StatsIngestMetrics::dropped`].
+    pub fn submit(&self, stats: WireguardPeerStats) {
+        let shard = &self.shards[self.shard_index(stats.network)];
+        match shard.tx.try_send(stats) {
+            Ok(()) => StatsIngestMetrics::inc(&shard.metrics.received, 1),
+            Err(TrySendError::Full(_)) => {
+                StatsIngestMetrics::inc(&shard.metrics.dropped, 1);
+                debug!("Peer stats ingestion queue is full, dropping stats update");
+            }
+            Err(TrySendError::Closed(_)) => {
+                StatsIngestMetrics::inc(&shard.metrics.dropped, 1);
+                error!("Peer stats ingestion queue is closed, dropping stats update");
+            }
+        }
+    }
+
+    /// Index of the shard handling stats for a given location.
+    #[must_use]
+    pub fn shard_index(&self, location_id: i64) -> usize {
+        location_id.unsigned_abs() as usize % self.shards.len()
+    }
+
+    /// Metrics of a given shard.
+    #[must_use]
+    pub fn metrics(&self, shard: usize) -> Arc<StatsIngestMetrics> {
+        Arc::clone(&self.shards[shard].metrics)
+    }
+
+    /// Number of records waiting in a given shard queue.
+    #[must_use]
+    pub fn queue_len(&self, shard: usize) -> usize {
+        let tx = &self.shards[shard].tx;
+        tx.max_capacity() - tx.capacity()
+    }
+}
+
+/// Pool of ingestion workers, one for each shard.
+pub struct StatsIngestor {
+    workers: Vec<StatsIngestWorker>,
+}
+
+impl StatsIngestor {
+    /// Create ingestor with `shards` workers, each with its own queue of `queue_size` records.
+    #[must_use]
+    pub fn new(
+        pool: PgPool,
+        shards: usize,
+        batch_size: usize,
+        flush_interval: Duration,
+        queue_size: usize,
+    ) -> (Self, StatsIngestHandle) {
+        let (workers, shards): (Vec<_>, Vec<_>) = (0..shards.max(1))
+            .map(|shard| {
+                let (tx, rx) = mpsc::channel(queue_size.max(1));
+                let metrics = Arc::new(StatsIngestMetrics::default());
+                let worker = StatsIngestWorker {
+                    shard,
+                    pool: pool.clone(),
+                    rx,
+                    batch_size: batch_size.max(1),
+                    flush_interval,
+                    metrics: Arc::clone(&metrics),
+                    reported_dropped: 0,
+                    endpoints: HashMap::new(),
+                };
+                (worker, StatsShard { tx, metrics })
+            })
+            .unzip();
+        let handle = StatsIngestHandle {
+            shards: shards.into(),
+        };
+        (Self { workers }, handle)
+    }
+
+    /// Run all workers until all [`StatsIngestHandle`]s are dropped.
+    pub async fn run(self) {
+        info!(
+            "Starting peer stats ingestion with {} workers",
+            self.workers.len()
+        );
+        let mut handles = JoinSet::new();
+        for worker in self.workers {
+            handles.spawn(worker.run());
+        }
+        handles.join_all().await;
+        info!("Peer stats ingestion stopped");
+    }
+}
+
+/// Buffers peer stats of a single shard and writes them to the database in batches.
+///
+/// A batch is flushed when it reaches `batch_size` records or when `flush_interval` elapses,
+/// whichever comes first. Changes of peer endpoint addresses are recorded along the way.
+struct StatsIngestWorker {
+    shard: usize,
+    pool: PgPool,
+    rx: Receiver<WireguardPeerStats>,
+    batch_size: usize,
+    flush_interval: Duration,
+    metrics: Arc<StatsIngestMetrics>,
+    reported_dropped: u64,
+    // Latest known endpoint address of peers handled by this shard, keyed by device and
+    // location IDs; `None` if a peer has no endpoint history yet
+    endpoints: HashMap<(Id, Id), Option<IpAddr>>,
+}
+
+impl StatsIngestWorker {
+    /// Run until the shard queue is closed, then flush remaining stats.
+    async fn run(mut self) {
+        debug!(
+            "Starting peer stats ingestion worker {} with batch size {} and flush interval {:?}",
+            self.shard, self.batch_size, self.flush_interval
+        );
+        let mut buffer = Vec::with_capacity(self.batch_size);
+        let mut flush_timer = interval(self.flush_interval);
+        loop {
+            let limit = self.batch_size - buffer.len();
+            tokio::select! {
+                received = self.rx.recv_many(&mut buffer, limit) => {
+                    if received == 0 {
+                        // all senders have been dropped
+                        self.flush(&mut buffer).await;
+                        break;
+                    }
+                    if buffer.len() >= self.batch_size {
+                        self.flush(&mut buffer).await;
+                        flush_timer.reset();
+                    }
+                }
+                _ = flush_timer.tick() => {
+                    self.flush(&mut buffer).await;
+                }
+            }
+        }
+        debug!("Peer stats ingestion worker {} stopped", self.shard);
+    }
+
+    /// Write buffered stats to the database and clear the buffer.
+    async fn flush(&mut self, buffer: &mut Vec<WireguardPeerStats>) {
+        self.report_dropped();
+        if buffer.is_empty() {
+            return;
+        }
+        self.record_endpoint_changes(buffer).await;
+        let count = buffer.len() as u64;
+        let oldest = buffer.iter().map(|stats| stats.collected_at).min();
+        let result = if buffer.len() >= COPY_THRESHOLD {
+            WireguardPeerStats::copy_many(&self.pool, buffer).await
+        } else {
+            WireguardPeerStats::save_many(&self.pool, buffer).await
+        };
+        buffer.clear();
+        if let Some(oldest) = oldest {
+            let lag = (Utc::now().naive_utc() - oldest).num_milliseconds().max(0);
+            self.metrics.lag_ms.store(lag as u64, Ordering::Relaxed);
+        }
+        match result {
+            Ok(written) => {
+                StatsIngestMetrics::inc(&self.metrics.written, written);
+                StatsIngestMetrics::inc(&self.metrics.batches, 1);
+                debug!(
+                    "Saved {written} WireGuard peer stats records to db in shard {}, lag {} ms",
+                    self.shard,
+                    self.metrics.lag_ms.load(Ordering::Relaxed)
+                );
+            }
+            Err(err) => {
+                StatsIngestMetrics::inc(&self.metrics.failed, count);
+                error!(
+                    "Saving {count} WireGuard peer stats records to db in shard {} failed: {err}",
+                    self.shard
+                );
+            }
+        }
+    }
+
+    /// Save endpoint address changes found in a batch of stats. Only the address is compared,
+    /// so NAT port changes don't count as roaming.
+    async fn record_endpoint_changes(&mut self, buffer: &[WireguardPeerStats]) {
+        let unknown: Vec<_> = buffer
+            .iter()
+            .filter(|stats| stats.endpoint.is_some())
+            .map(|stats| (stats.device_id, stats.network))
+            .filter(|peer| !self.endpoints.contains_key(peer))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        if !unknown.is_empty() {
+            match DeviceEndpointChange::latest_ips(&self.pool, &unknown).await {
+                Ok(latest) => {
+                    for peer in unknown {
+                        self.endpoints.insert(peer, latest.get(&peer).copied());
+                    }
+                }
+                Err(err) => {
+                    error!(
+                        "Failed to fetch latest peer endpoints in shard {}: {err}",
+                        self.shard
+                    );
+                    return;
+                }
+            }
+        }
+
+        let mut changes = Vec::new();
+        for stats in buffer {
+            let Some(ip) = stats.endpoint.as_deref().and_then(endpoint_ip) else {
+                continue;
+            };
+            if let Some(last) = self.endpoints.get_mut(&(stats.device_id, stats.network)) {
+                if *last != Some(ip) {
+                    *last = Some(ip);
+                    changes.push(DeviceEndpointChange::new(
+                        stats.device_id,
+                        stats.network,
+                        ip,
+                        stats.collected_at,
+                    ));
+                }
+            }
+        }
+        if changes.is_empty() {
+            return;
+        }
+        if let Err(err) = DeviceEndpointChange::save_many(&self.pool, &changes).await {
+            error!(
+                "Saving {} peer endpoint changes to db in shard {} failed: {err}",
+                changes.len(),
+                self.shard
+            );
+            // fetch actual state from the database next time
+            for change in changes {
+                self.endpoints
+                    .remove(&(change.device_id, change.location_id));
+            }
+        }
+    }
+
+    /// Log a summary of stats dropped since the last report.
+    fn report_dropped(&mut self) {
+        let dropped = self.metrics.dropped.load(Ordering::Relaxed);
+        if dropped > self.reported_dropped {
+            warn!(
+                "Peer stats ingestion shard {} is overloaded, dropped {} stats updates \
+                ({dropped} in total), lag {} ms",
+                self.shard,
+                dropped - self.reported_dropped,
+                self.metrics.lag_ms.load(Ordering::Relaxed)
+            );
+            self.reported_dropped = dropped;
+        }
+    }
+}
+
+/// Address part of a peer endpoint reported by a gateway, e.g. `11.22.33.44:51820`.
+fn endpoint_ip(endpoint: &str) -> Option<IpAddr> {
+    endpoint.parse::<SocketAddr>().ok().map(|addr| addr.ip())
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{TimeDelta, Utc};
+    use defguard_common::db::{Id, NoId, setup_pool};
+    use sqlx::{
+        postgres::{PgConnectOptions, PgPoolOptions},
+        query_scalar,
+    };
+
+    use super::*;
+    use crate::db::{Device, User, WireguardNetwork, models::device::DeviceType};
+
+    fn make_stats(device_id: Id, network_id: Id, i: i64) -> WireguardPeerStats {
+        WireguardPeerStats {
+            id: NoId,
+            device_id,
+            collected_at: Utc::now().naive_utc(),
+            network: network_id,
+            endpoint: (i % 2 == 0).then(|| "11.22.33.44:51820".into()),
+            upload: i,
+            download: i * 2,
+            latest_handshake: Utc::now().naive_utc(),
+            allowed_ips: Some("10.1.1.2/32,\tfd00::2/128".into()),
+            gateway: None,
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_stats_ingestion(_: PgPoolOptions, options: PgConnectOptions) {
+        let pool = setup_pool(options).await;
+        let mut network = WireguardNetwork::default();
+        network.try_set_address("10.1.1.1/24").unwrap();
+        let network = network.save(&pool).await.unwrap();
+        let user = User::new(
+            "testuser",
+            Some("hunter2"),
+            "Tester",
+            "Test",
+            "test@test.com",
+            None,
+        )
+        .save(&pool)
+        .await
+        .unwrap();
+        let device = Device::new(
+            String::new(),
+            String::new(),
+            user.id,
+            DeviceType::User,
+            None,
+            true,
+        )
+        .save(&pool)
+        .await
+        .unwrap();
+
+        // small batches are written with INSERT, large ones with COPY
+        for count in [COPY_THRESHOLD - 1, COPY_THRESHOLD * 3] {
+            let (ingestor, handle) =
+                StatsIngestor::new(pool.clone(), 1, count, Duration::from_secs(60), count);
+            for i in 0..count {
+                handle.submit(make_stats(device.id, network.id, i as i64));
+            }
+            assert_eq!(handle.queue_len(0), count);
+            // queue is full, so this update is shed
+            handle.submit(make_stats(device.id, network.id, 0));
+            let metrics = handle.metrics(0);
+            drop(handle);
+            ingestor.run().await;
+
+            assert_eq!(metrics.received.load(Ordering::Relaxed), count as u64);
+            assert_eq!(metrics.dropped.load(Ordering::Relaxed), 1);
+            assert_eq!(metrics.written.load(Ordering::Relaxed), count as u64);
+            assert_eq!(metrics.failed.load(Ordering::Relaxed), 0);
+        }
+
+        let total = query_scalar!("SELECT count(*) \"count!\" FROM wireguard_peer_stats")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(total, (COPY_THRESHOLD * 4 - 1) as i64);
+
+        // escaped values survive the round trip
+        let latest = WireguardPeerStats::fetch_latest(&pool, device.id, network.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            latest.allowed_ips.as_deref(),
+            Some("10.1.1.2/32,\tfd00::2/128")
+        );
+        let without_endpoint = query_scalar!(
+            "SELECT count(*) \"count!\" FROM wireguard_peer_stats WHERE endpoint IS NULL"
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(without_endpoint, total / 2);
+    }
+
+    #[sqlx::test]
+    async fn test_endpoint_changes(_: PgPoolOptions, options: PgConnectOptions) {
+        let pool = setup_pool(options).await;
+        let mut network = WireguardNetwork::default();
+        network.try_set_address("10.1.1.1/24").unwrap();
+        let network = network.save(&pool).await.unwrap();
+        let user = User::new(
+            "testuser",
+            Some("hunter2"),
+            "Tester",
+            "Test",
+            "test@test.com",
+            None,
+        )
+        .save(&pool)
+        .await
+        .unwrap();
+        let device = Device::new(
+            String::new(),
+            String::new(),
+            user.id,
+            DeviceType::User,
+            None,
+            true,
+        )
+        .save(&pool)
+        .await
+        .unwrap();
+
+        let start = Utc::now().naive_utc() - TimeDelta::hours(1);
+        let ingest = |endpoints: &[Option<&str>], offset: i64| {
+            let (ingestor, handle) = StatsIngestor::new(
+                pool.clone(),
+                1,
+                endpoints.len(),
+                Duration::from_secs(60),
+                endpoints.len(),
+            );
+            for (i, endpoint) in endpoints.iter().enumerate() {
+                let mut stats = make_stats(device.id, network.id, i as i64);
+                stats.collected_at = start + TimeDelta::minutes(offset + i as i64);
+                stats.endpoint = endpoint.map(ToString::to_string);
+                handle.submit(stats);
+            }
+            drop(handle);
+            ingestor.run()
+        };
+
+        // port changes and missing endpoints aren't recorded
+        ingest(
+            &[
+                Some("11.22.33.44:51820"),
+                Some("11.22.33.44:40000"),
+                None,
+                Some("55.66.77.88:51820"),
+                Some("[fd00::1]:51820"),
+            ],
+            0,
+        )
+        .await;
+        // latest endpoint is fetched from the database after restart
+        ingest(&[Some("[fd00::1]:40000"), Some("11.22.33.44:51820")], 10).await;
+
+        let changes = DeviceEndpointChange::for_device_since(&pool, device.id, start)
+            .await
+            .unwrap();
+        let ips: Vec<String> = changes
+            .iter()
+            .map(|change| change.ip.ip().to_string())
+            .collect();
+        assert_eq!(
+            ips,
+            ["11.22.33.44", "fd00::1", "55.66.77.88", "11.22.33.44"]
+        );
+        assert_eq!(changes[0].changed_at, start + TimeDelta::minutes(11));
+
+        // address in use at the beginning of a period is included
+        let changes =
+            DeviceEndpointChange::for_device_since(&pool, device.id, start + TimeDelta::minutes(5))
+                .await
+                .unwrap();
+        let ips: Vec<String> = changes
+            .iter()
+            .map(|change| change.ip.ip().to_string())
+            .collect();
+        assert_eq!(ips, ["11.22.33.44", "fd00::1"]);
+    }
+
+    #[sqlx::test]
+    async fn test_stats_ingestion_shards(_: PgPoolOptions, options: PgConnectOptions) {
+        let pool = setup_pool(options).await;
+        let (ingestor, handle) = StatsIngestor::new(pool, 2, 10, Duration::from_secs(60), 1);
+        let busy = handle.shard_index(1);
+        let idle = handle.shard_index(2);
+        assert_ne!(busy, idle);
+
+        // a busy location only fills up its own shard queue
+        handle.submit(make_stats(1, 1, 1));
+        handle.submit(make_stats(1, 1, 2));
+        handle.submit(make_stats(1, 2, 1));
+        assert_eq!(handle.queue_len(busy), 1);
+        assert_eq!(handle.queue_len(idle), 1);
+        assert_eq!(handle.metrics(busy).dropped.load(Ordering::Relaxed), 1);
+        assert_eq!(handle.metrics(idle).dropped.load(Ordering::Relaxed), 0);
+        assert_eq!(handle.metrics(idle).received.load(Ordering::Relaxed), 1);
+
+        // writes fail for nonexistent devices, which is reported per shard
+        let busy_metrics = handle.metrics(busy);
+        drop(handle);
+        ingestor.run().await;
+        assert_eq!(busy_metrics.failed.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,220 @@
+//! This module keeps high-churn stats tables in shape without manual database administration.
+//!
+//! If a maintenance window is configured, once per window expired stats partitions are dropped,
+//! old stats are purged and stats tables are vacuumed and analyzed, so queries over stats stay
+//! fast. Stats purge runs only during the window then, instead of at any time. Counters and results of the latest run are available
+//! through [`stats_maintenance_report`].
+
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use defguard_common::config::DefGuardConfig;
+use humantime::format_duration;
+use sqlx::{Error as SqlxError, PgPool, query, raw_sql};
+use tokio::time::sleep;
+use utoipa::ToSchema;
+
+use crate::{
+    db::models::wireguard_peer_stats::WireguardPeerStats,
+    wireguard_stats_partitions::drop_expired_partitions,
+};
+
+// How long to sleep between loop iterations
+const MAINTENANCE_LOOP_SLEEP: Duration = Duration::from_secs(60);
+
+/// High-churn tables vacuumed and analyzed during maintenance.
+pub const STATS_TABLES: &[&str] = &["wireguard_peer_stats"];
+
+/// Result of maintenance of a single table.
+#[derive(Clone, Debug, Default, Serialize, ToSchema)]
+pub struct TableMaintenance {
+    pub table: String,
+    pub live_rows: i64,
+    /// Dead rows before and after vacuum, as estimated by table statistics
+    pub dead_rows_before: i64,
+    pub dead_rows_after: i64,
+    /// Total size of the table with indexes before and after vacuum, in bytes
+    pub size_before: i64,
+    pub size_after: i64,
+    pub duration_ms: u64,
+    pub error: Option<String>,
+}
+
+/// Counters and results of the latest stats maintenance run.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct StatsMaintenanceReport {
+    /// Daily maintenance window in UTC; `None` if maintenance is disabled
+    #[schema(value_type = Option<String>)]
+    pub window: Option<String>,
+    pub runs: u64,
+    /// Runs which failed to purge stats or maintain any of the tables
+    pub failures: u64,
+    pub last_started_at: Option<NaiveDateTime>,
+    pub last_finished_at: Option<NaiveDateTime>,
+    /// Stats records removed by the latest purge; `None` if stats purge is disabled or failed
+    pub records_purged: Option<u64>,
+    pub tables: Vec<TableMaintenance>,
+}
+
+static REPORT: Mutex<StatsMaintenanceReport> = Mutex::new(StatsMaintenanceReport {
+    window: None,
+    runs: 0,
+    failures: 0,
+    last_started_at: None,
+    last_finished_at: None,
+    records_purged: None,
+    tables: Vec::new(),
+});
+
+/// Counters and results of the latest stats maintenance run.
+pub fn stats_maintenance_report() -> StatsMaintenanceReport {
+    REPORT
+        .lock()
+        .expect("Failed to acquire lock on stats maintenance report")
+        .clone()
+}
+
+/// Vacuum and analyze a table, collecting its statistics before and after. Statistics of
+/// partitioned tables are summed over all partitions.
+async fn maintain_table(pool: &PgPool, table: &str) -> Result<TableMaintenance, SqlxError> {
+    let started = Instant::now();
+    let before = query!(
+        "SELECT COALESCE(SUM(s.n_dead_tup), 0)::bigint \"dead!\", \
+        COALESCE(SUM(pg_total_relation_size(t.relid)), 0)::bigint \"size!\" \
+        FROM pg_partition_tree($1::text::regclass) t \
+        JOIN pg_stat_user_tables s ON s.relid = t.relid",
+        table
+    )
+    .fetch_one(pool)
+    .await?;
+    // table names come from a constant list, identifiers can't be bound as parameters
+    raw_sql(&format!("VACUUM (ANALYZE) {table}"))
+        .execute(pool)
+        .await?;
+    let after = query!(
+        "SELECT COALESCE(SUM(s.n_live_tup), 0)::bigint \"live!\", \
+        COALESCE(SUM(s.n_dead_tup), 0)::bigint \"dead!\", \
+        COALESCE(SUM(pg_total_relation_size(t.relid)), 0)::bigint \"size!\" \
+        FROM pg_partition_tree($1::text::regclass) t \
+        JOIN pg_stat_user_tables s ON s.relid = t.relid",
+        table
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(TableMaintenance {
+        table: table.into(),
+        live_rows: after.live,
+        dead_rows_before: before.dead,
+        dead_rows_after: after.dead,
+        size_before: before.size,
+        size_after: after.size,
+        duration_ms: started.elapsed().as_millis() as u64,
+        error: None,
+    })
+}
+
+/// Drop expired stats partitions and purge old stats, if enabled, and maintain all stats tables.
+pub async fn run_maintenance(pool: &PgPool, stats_purge_threshold: Option<Duration>) {
+    let started_at = Utc::now().naive_utc();
+    let mut failed = false;
+    let records_purged = match stats_purge_threshold {
+        Some(threshold) => {
+            let partitions_threshold =
+                started_at - TimeDelta::from_std(threshold).expect("Failed to parse duration");
+            if let Err(err) = drop_expired_partitions(pool, partitions_threshold).await {
+                error!("Error while dropping expired stats partitions: {err}");
+                failed = true;
+            }
+            match WireguardPeerStats::purge_old_stats(pool, threshold).await {
+                Ok(records_purged) => Some(records_purged),
+                Err(err) => {
+                    error!("Error while purging stats: {err}");
+                    failed = true;
+                    None
+                }
+            }
+        }
+        None => None,
+    };
+
+    let mut tables = Vec::with_capacity(STATS_TABLES.len());
+    for table in STATS_TABLES {
+        debug!("Vacuuming and analyzing table {table}");
+        match maintain_table(pool, table).await {
+            Ok(result) => {
+                info!(
+                    "Maintained table {table} in {} ms, dead rows: {} -> {}, size: {} -> {} bytes",
+                    result.duration_ms,
+                    result.dead_rows_before,
+                    result.dead_rows_after,
+                    result.size_before,
+                    result.size_after
+                );
+                tables.push(result);
+            }
+            Err(err) => {
+                error!("Error while maintaining table {table}: {err}");
+                failed = true;
+                tables.push(TableMaintenance {
+                    table: (*table).into(),
+                    error: Some(err.to_string()),
+                    ..Default::default()
+                });
+            }
+        }
+    }
+
+    let mut report = REPORT
+        .lock()
+        .expect("Failed to acquire lock on stats maintenance report");
+    report.runs += 1;
+    if failed {
+        report.failures += 1;
+    }
+    report.last_started_at = Some(started_at);
+    report.last_finished_at = Some(Utc::now().naive_utc());
+    report.records_purged = records_purged;
+    report.tables = tables;
+}
+
+/// Runs stats maintenance once per daily maintenance window. Should only be run if a maintenance
+/// window is configured.
+#[instrument(skip_all)]
+pub async fn run_stats_maintenance(pool: PgPool, config: DefGuardConfig) {
+    let Some(window) = config.stats_maintenance_window else {
+        debug!("No stats maintenance window configured, stats tables won't be maintained");
+        return;
+    };
+    // stats purge is done as a part of maintenance
+    let stats_purge_threshold: Option<Duration> =
+        (!config.disable_stats_purge).then(|| config.stats_purge_threshold.into());
+    match stats_purge_threshold {
+        Some(threshold) => info!(
+            "Starting stats maintenance in daily window {window} UTC, purging stats older than {}",
+            format_duration(threshold)
+        ),
+        None => info!("Starting stats maintenance in daily window {window} UTC"),
+    }
+    REPORT
+        .lock()
+        .expect("Failed to acquire lock on stats maintenance report")
+        .window = Some(window.to_string());
+
+    // runs in the same window are less than its length apart
+    let mut last_run: Option<NaiveDateTime> = None;
+    loop {
+        let now = Utc::now().naive_utc();
+        if window.contains(now.time()) && last_run.is_none_or(|last| now - last > window.length()) {
+            info!("Executing stats maintenance");
+            last_run = Some(now);
+            run_maintenance(&pool, stats_purge_threshold).await;
+        }
+
+        // wait till next iteration
+        sleep(MAINTENANCE_LOOP_SLEEP).await;
+    }
+}
@@ -0,0 +1,349 @@
+//! This module manages monthly partitions of the `wireguard_peer_stats` table.
+//!
+//! Partitions for the current and upcoming months are created ahead of time, so stats are never
+//! written to the default partition under normal operation. Partitions which only contain stats
+//! older than the purge threshold are dropped as a whole, which is much cheaper than deleting
+//! their rows. If a stats maintenance window is configured, they're dropped during stats
+//! maintenance instead, so only within the window. Rows outside of any monthly partition are still
+//! removed by the regular stats purge.
+
+use std::time::Duration;
+
+use chrono::{Datelike, Months, NaiveDate, NaiveDateTime, TimeDelta, Utc};
+use defguard_common::config::DefGuardConfig;
+use humantime::format_duration;
+use sqlx::{Error as SqlxError, PgPool, query, query_scalar, raw_sql};
+use tokio::time::sleep;
+
+// How long to sleep between loop iterations
+const PARTITION_LOOP_SLEEP: Duration = Duration::from_secs(3600); // 1 hour
+
+/// Number of months following the current one to create partitions for.
+pub const PARTITIONS_AHEAD: u32 = 2;
+
+const PARTITION_PREFIX: &str = "wireguard_peer_stats_p";
+
+/// Name of a partition holding stats collected in a given month.
+#[must_use]
+pub fn partition_name(month: NaiveDate) -> String {
+    format!("{PARTITION_PREFIX}{}", month.format("%Y%m"))
+}
+
+/// First day of the month of a partition, `None` if it isn't a monthly partition.
+fn partition_month(name: &str) -> Option<NaiveDate> {
+    let month = name.strip_prefix(PARTITION_PREFIX)?;
+    NaiveDate::parse_from_str(&format!("{month}01"), "%Y%m%d").ok()
+}
+
+fn month_start(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).expect("Failed to get first day of month")
+}
+
+fn next_month(month: NaiveDate) -> NaiveDate {
+    month + Months::new(1)
+}
+
+/// Names of all partitions of the stats table, including the default one.
+pub async fn stats_partitions(pool: &PgPool) -> Result<Vec<String>, SqlxError> {
+    query_scalar!(
+        "SELECT c.relname::text \"name!\" FROM pg_inherits i JOIN pg_class c ON c.oid = i.inhrelid \
+        WHERE i.inhparent = 'wireguard_peer_stats'::regclass ORDER BY c.relname"
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Create a partition for a month, moving its stats out of the default partition.
+async fn create_partition(pool: &PgPool, month: NaiveDate) -> Result<u64, SqlxError> {
+    let name = partition_name(month);
+    let start = month.and_hms_opt(0, 0, 0).expect("Invalid time");
+    let end = next_month(month)
+        .and_hms_opt(0, 0, 0)
+        .expect("Invalid time");
+
+    // partition names are derived from dates, identifiers can't be bound as parameters
+    let mut transaction = pool.begin().await?;
+    raw_sql(&format!("CREATE TABLE {name} (LIKE wireguard_peer_stats)"))
+        .execute(&mut *transaction)
+        .await?;
+    let moved = query(&format!(
+        "WITH moved AS (DELETE FROM wireguard_peer_stats_default \
+        WHERE collected_at >= $1 AND collected_at < $2 RETURNING *) \
+        INSERT INTO {name} SELECT * FROM moved"
+    ))
+    .bind(start)
+    .bind(end)
+    .execute(&mut *transaction)
+    .await?
+    .rows_affected();
+    raw_sql(&format!(
+        "ALTER TABLE wireguard_peer_stats ATTACH PARTITION {name} \
+        FOR VALUES FROM ('{start}') TO ('{end}')"
+    ))
+    .execute(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+
+    Ok(moved)
+}
+
+/// Drop a partition, keeping the latest stats of each device in each location if there are no
+/// newer ones. Those end up in the default partition, as the month is no longer covered.
+async fn drop_partition(pool: &PgPool, name: &str) -> Result<u64, SqlxError> {
+    let mut transaction = pool.begin().await?;
+    raw_sql(&format!(
+        "ALTER TABLE wireguard_peer_stats DETACH PARTITION {name}"
+    ))
+    .execute(&mut *transaction)
+    .await?;
+    let kept = raw_sql(&format!(
+        "INSERT INTO wireguard_peer_stats SELECT * FROM ( \
+            SELECT DISTINCT ON (device_id, network) * FROM {name} \
+            ORDER BY device_id, network, collected_at DESC) latest \
+        WHERE NOT EXISTS (SELECT 1 FROM wireguard_peer_stats s \
+            WHERE s.device_id = latest.device_id AND s.network = latest.network \
+            AND s.collected_at > latest.collected_at)"
+    ))
+    .execute(&mut *transaction)
+    .await?
+    .rows_affected();
+    raw_sql(&format!("DROP TABLE {name}"))
+        .execute(&mut *transaction)
+        .await?;
+    transaction.commit().await?;
+
+    Ok(kept)
+}
+
+/// Create missing partitions for the month of `now` and [`PARTITIONS_AHEAD`] following months.
+/// Returns names of created partitions.
+pub async fn create_future_partitions(
+    pool: &PgPool,
+    now: NaiveDateTime,
+) -> Result<Vec<String>, SqlxError> {
+    let existing = stats_partitions(pool).await?;
+    let mut created = Vec::new();
+    let mut month = month_start(now.date());
+    for _ in 0..=PARTITIONS_AHEAD {
+        let name = partition_name(month);
+        if !existing.contains(&name) {
+            let moved = create_partition(pool, month).await?;
+            info!("Created stats partition {name}, moved {moved} records from default partition");
+            created.push(name);
+        }
+        month = next_month(month);
+    }
+
+    Ok(created)
+}
+
+/// Drop partitions containing only stats collected before `threshold`.
+/// Returns names of dropped partitions.
+pub async fn drop_expired_partitions(
+    pool: &PgPool,
+    threshold: NaiveDateTime,
+) -> Result<Vec<String>, SqlxError> {
+    let mut dropped = Vec::new();
+    for name in stats_partitions(pool).await? {
+        let Some(month) = partition_month(&name) else {
+            continue;
+        };
+        if next_month(month)
+            .and_hms_opt(0, 0, 0)
+            .expect("Invalid time")
+            <= threshold
+        {
+            let kept = drop_partition(pool, &name).await?;
+            info!("Dropped expired stats partition {name}, kept {kept} latest records");
+            dropped.push(name);
+        }
+    }
+
+    Ok(dropped)
+}
+
+/// Periodically creates upcoming stats partitions and, unless stats purge is disabled or done
+/// during stats maintenance, drops partitions older than stats purge threshold.
+#[instrument(skip_all)]
+pub async fn run_stats_partition_manager(pool: PgPool, config: DefGuardConfig) {
+    let stats_purge_threshold: Option<Duration> = (!config.disable_stats_purge
+        && config.stats_maintenance_window.is_none())
+    .then(|| config.stats_purge_threshold.into());
+    match (stats_purge_threshold, &config.stats_maintenance_window) {
+        (Some(threshold), _) => info!(
+            "Starting stats partition manager, dropping partitions older than {}",
+            format_duration(threshold)
+        ),
+        (None, Some(window)) if !config.disable_stats_purge => info!(
+            "Starting stats partition manager, expired partitions are dropped during stats \
+            maintenance in daily window {window} UTC"
+        ),
+        (None, _) => info!("Starting stats partition manager, stats purge is disabled"),
+    }
+
+    loop {
+        debug!("Managing stats partitions");
+        let now = Utc::now().naive_utc();
+        if let Err(err) = create_future_partitions(&pool, now).await {
+            error!("Error while creating stats partitions: {err}");
+        }
+        if let Some(threshold) = stats_purge_threshold {
+            let threshold = now - TimeDelta::from_std(threshold).expect("Failed to parse duration");
+            if let Err(err) = drop_expired_partitions(&pool, threshold).await {
+                error!("Error while dropping expired stats partitions: {err}");
+            }
+        }
+
+        // wait till next iteration
+        debug!("Sleeping until next iteration");
+        sleep(PARTITION_LOOP_SLEEP).await;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use defguard_common::db::{Id, NoId, setup_pool};
+    use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+    use super::*;
+    use crate::db::{
+        Device, User, WireguardNetwork,
+        models::{device::DeviceType, wireguard_peer_stats::WireguardPeerStats},
+    };
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    async fn partition_of(pool: &PgPool, id: Id) -> String {
+        query_scalar::<_, String>(
+            "SELECT tableoid::regclass::text FROM wireguard_peer_stats WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[test]
+    fn test_partition_names() {
+        assert_eq!(
+            partition_name(date(2026, 1, 15)),
+            "wireguard_peer_stats_p202601"
+        );
+        assert_eq!(
+            partition_month("wireguard_peer_stats_p202612"),
+            Some(date(2026, 12, 1))
+        );
+        assert_eq!(partition_month("wireguard_peer_stats_default"), None);
+        assert_eq!(next_month(date(2026, 12, 1)), date(2027, 1, 1));
+    }
+
+    #[sqlx::test]
+    async fn test_partition_management(_: PgPoolOptions, options: PgConnectOptions) {
+        let pool = setup_pool(options).await;
+        let mut network = WireguardNetwork::default();
+        network.try_set_address("10.1.1.1/24").unwrap();
+        let network = network.save(&pool).await.unwrap();
+        let user = User::new(
+            "testuser",
+            Some("hunter2"),
+            "Tester",
+            "Test",
+            "test@test.com",
+            None,
+        )
+        .save(&pool)
+        .await
+        .unwrap();
+        let device = Device::new(
+            String::new(),
+            String::new(),
+            user.id,
+            DeviceType::User,
+            None,
+            true,
+        )
+        .save(&pool)
+        .await
+        .unwrap();
+        let save_stats = async |collected_at: NaiveDateTime| {
+            WireguardPeerStats {
+                id: NoId,
+                device_id: device.id,
+                collected_at,
+                network: network.id,
+                endpoint: None,
+                upload: 10,
+                download: 20,
+                latest_handshake: collected_at,
+                allowed_ips: None,
+                gateway: None,
+            }
+            .save(&pool)
+            .await
+            .unwrap()
+            .id
+        };
+
+        // stats from far future go to the default partition until its partition is created
+        let future = date(2040, 3, 10).and_hms_opt(12, 0, 0).unwrap();
+        let future_id = save_stats(future).await;
+        assert_eq!(
+            partition_of(&pool, future_id).await,
+            "wireguard_peer_stats_default"
+        );
+        let created = create_future_partitions(&pool, date(2040, 2, 20).into())
+            .await
+            .unwrap();
+        assert_eq!(
+            created,
+            [
+                "wireguard_peer_stats_p204002",
+                "wireguard_peer_stats_p204003",
+                "wireguard_peer_stats_p204004"
+            ]
+        );
+        assert_eq!(
+            partition_of(&pool, future_id).await,
+            "wireguard_peer_stats_p204003"
+        );
+        assert!(
+            create_future_partitions(&pool, date(2040, 2, 20).into())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        // only the latest stats of a device are kept when dropping partitions
+        let first = save_stats(date(2040, 2, 1).into()).await;
+        let second = save_stats(date(2040, 2, 2).into()).await;
+        let dropped = drop_expired_partitions(&pool, date(2040, 4, 1).into())
+            .await
+            .unwrap();
+        assert!(dropped.contains(&"wireguard_peer_stats_p204002".to_string()));
+        assert!(dropped.contains(&"wireguard_peer_stats_p204003".to_string()));
+        assert!(!dropped.contains(&"wireguard_peer_stats_p204004".to_string()));
+        let remaining = query_scalar::<_, Id>("SELECT id FROM wireguard_peer_stats")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(remaining, [future_id]);
+        assert!(
+            WireguardPeerStats::find_by_id(&pool, first)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            WireguardPeerStats::find_by_id(&pool, second)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(
+            partition_of(&pool, future_id).await,
+            "wireguard_peer_stats_default"
+        );
+    }
+}
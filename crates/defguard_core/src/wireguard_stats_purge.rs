@@ -33,7 +33,7 @@ pub async fn run_periodic_stats_purge(
             // perform purge
             info!("Executing stats purge");
             match WireguardPeerStats::purge_old_stats(&pool, stats_purge_threshold).await {
-                Ok(()) => {
+                Ok(_) => {
                     let next_purge_timestamp = (Utc::now()
                         + TimeDelta::from_std(stats_purge_frequency)
                             .expect("Failed to parse duration"))
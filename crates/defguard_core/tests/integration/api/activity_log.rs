@@ -0,0 +1,72 @@
+use bytes::Bytes;
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+use super::common::{authenticate_admin, make_test_client, setup_pool};
+
+fn serialized_event(username: &str, event: &str, location: Option<&str>) -> String {
+    json!({
+        "id": null,
+        "timestamp": "2025-01-01T12:00:00",
+        "user_id": 1,
+        "username": username,
+        "location": location,
+        "ip": "10.0.0.1/32",
+        "event": event,
+        "module": "vpn",
+        "device": "laptop",
+        "description": null,
+        "metadata": null,
+    })
+    .to_string()
+}
+
+#[sqlx::test]
+async fn test_activity_log_live_tail(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, client_state) = make_test_client(pool).await;
+
+    // admin only
+    let response = client.get("/api/v1/activity_log/live").send().await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    client.login_user("hpotter", "pass123").await;
+    let response = client.get("/api/v1/activity_log/live").send().await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    authenticate_admin(&mut client).await;
+    let response = client
+        .get(
+            "/api/v1/activity_log/live?username=hpotter&event=vpn_client_connected&location=office",
+        )
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+    let mut response = response.into_inner();
+
+    let messages = [
+        serialized_event("admin", "vpn_client_connected", Some("office")),
+        serialized_event("hpotter", "vpn_client_disconnected", Some("office")),
+        serialized_event("hpotter", "vpn_client_connected", Some("home")),
+        serialized_event("hpotter", "vpn_client_connected", Some("office")),
+    ]
+    .join("\n");
+    client_state
+        .activity_log_tx
+        .send(Bytes::from(messages + "\n"))
+        .unwrap();
+
+    // only the last event matches all filters
+    let mut stream = String::new();
+    while !stream.contains("\n\n") {
+        let chunk = response.chunk().await.unwrap().unwrap();
+        stream.push_str(std::str::from_utf8(&chunk).unwrap());
+    }
+    let (message, rest) = stream.split_once("\n\n").unwrap();
+    assert!(rest.is_empty());
+    let event: Value = serde_json::from_str(message.strip_prefix("data: ").unwrap()).unwrap();
+    assert_eq!(event["username"], "hpotter");
+    assert_eq!(event["event"], "vpn_client_connected");
+    assert_eq!(event["location"], "office");
+}
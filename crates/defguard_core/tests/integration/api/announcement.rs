@@ -0,0 +1,146 @@
+use std::time::Duration;
+
+use chrono::{TimeDelta, Utc};
+use defguard_common::db::Id;
+use defguard_core::db::models::announcement::Announcement;
+use defguard_mail::MailError;
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions},
+    query_scalar,
+};
+use tokio::time::{sleep, timeout};
+
+use super::common::{authenticate_admin, make_test_client, setup_pool};
+
+#[sqlx::test]
+async fn test_announcement(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, client_state) = make_test_client(pool).await;
+    let mut mail_rx = client_state.mail_rx;
+    authenticate_admin(&mut client).await;
+    while mail_rx.try_recv().is_ok() {}
+
+    // invalid template
+    let response = client
+        .post("/api/v1/announcement")
+        .json(&json!({
+            "subject": "Maintenance",
+            "body": "Hello {{ first_name",
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // send to all active users
+    let response = client
+        .post("/api/v1/announcement")
+        .json(&json!({
+            "subject": "Maintenance for {{ username }}",
+            "body": "Hello **{{ first_name }}**, VPN will be down tonight.",
+            "user_active": true,
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let announcement: Announcement<Id> = response.json().await;
+
+    let mut mails = Vec::new();
+    for _ in 0..2 {
+        let mail = timeout(Duration::from_secs(5), mail_rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        mails.push(mail);
+    }
+    assert!(mail_rx.try_recv().is_err());
+    let potter_mail = mails
+        .iter()
+        .find(|mail| mail.to == "h.potter@hogwart.edu.uk")
+        .unwrap();
+    assert_eq!(potter_mail.subject, "Maintenance for hpotter");
+    assert!(potter_mail.content.contains("<strong>Harry</strong>"));
+
+    // report delivery results
+    for mail in mails {
+        let result_tx = mail.result_tx.unwrap();
+        if mail.to == "h.potter@hogwart.edu.uk" {
+            result_tx.send(Err(MailError::SmtpNotConfigured)).unwrap();
+        }
+    }
+    let mut details = Value::Null;
+    for _ in 0..50 {
+        let response = client
+            .get(format!("/api/v1/announcement/{}", announcement.id))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        details = response.json().await;
+        if details["report"]["pending"] == 0 {
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    assert!(details["announcement"]["sent_at"].is_string());
+    assert_eq!(details["report"]["total"], 2);
+    assert_eq!(details["report"]["failed"], 2);
+    assert_eq!(details["deliveries"].as_array().unwrap().len(), 2);
+
+    // send only to admin group members
+    let admin_group_id: Id = query_scalar("SELECT id FROM \"group\" WHERE name = 'admin'")
+        .fetch_one(&client_state.pool)
+        .await
+        .unwrap();
+    let response = client
+        .post("/api/v1/announcement")
+        .json(&json!({
+            "subject": "Admins only",
+            "body": "Hello",
+            "group_ids": [admin_group_id],
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let mail = timeout(Duration::from_secs(5), mail_rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(mail.subject, "Admins only");
+    assert_ne!(mail.to, "h.potter@hogwart.edu.uk");
+    sleep(Duration::from_millis(100)).await;
+    assert!(mail_rx.try_recv().is_err());
+
+    // scheduled announcement isn't sent immediately
+    let response = client
+        .post("/api/v1/announcement")
+        .json(&json!({
+            "subject": "Scheduled",
+            "body": "Hello",
+            "scheduled_at": (Utc::now() + TimeDelta::days(1)).naive_utc(),
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let scheduled: Announcement<Id> = response.json().await;
+    assert!(scheduled.sent_at.is_none());
+    sleep(Duration::from_millis(100)).await;
+    assert!(mail_rx.try_recv().is_err());
+
+    let response = client.get("/api/v1/announcement").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let announcements: Vec<Announcement<Id>> = response.json().await;
+    assert_eq!(announcements.len(), 3);
+
+    // cancel scheduled announcement
+    let response = client
+        .delete(format!("/api/v1/announcement/{}", scheduled.id))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .get(format!("/api/v1/announcement/{}", scheduled.id))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,46 @@
+use reqwest::StatusCode;
+use serde_json::Value;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+use super::common::{authenticate_admin, make_test_client, setup_pool};
+
+#[sqlx::test]
+async fn test_api_v2_compatibility(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, _) = make_test_client(pool).await;
+    authenticate_admin(&mut client).await;
+
+    // v1 user list is deprecated
+    let response = client.get("/api/v1/user").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().contains_key("deprecation"));
+    assert_eq!(
+        response.headers()["link"],
+        "</api/v2/user>; rel=\"successor-version\""
+    );
+    let users: Value = response.json().await;
+    let user_count = users.as_array().unwrap().len();
+
+    // v2 user list is paginated
+    let response = client.get("/api/v2/user").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(!response.headers().contains_key("deprecation"));
+    let users: Value = response.json().await;
+    assert_eq!(users["data"].as_array().unwrap().len(), user_count);
+    assert_eq!(users["pagination"]["current_page"], 1);
+    assert_eq!(users["pagination"]["total_items"], user_count);
+    assert_eq!(users["pagination"]["next_page"], Value::Null);
+
+    let response = client.get("/api/v2/user?page=2").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let users: Value = response.json().await;
+    assert!(users["data"].as_array().unwrap().is_empty());
+
+    // unchanged endpoints are served by v1 handlers
+    let response = client.get("/api/v2/user/admin").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let user: Value = response.json().await;
+    assert_eq!(user["user"]["username"], "admin");
+    let response = client.get("/api/v2/info").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
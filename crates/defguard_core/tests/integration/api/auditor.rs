@@ -0,0 +1,182 @@
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+use super::common::{authenticate_admin, make_network, make_test_client, setup_pool};
+
+const REDACTED: &str = "[redacted]";
+
+#[sqlx::test]
+async fn test_auditor_redaction(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, client_state) = make_test_client(pool).await;
+
+    sqlx::query(
+        "INSERT INTO activity_log_event (timestamp, user_id, username, ip, event, module, device, description) \
+        SELECT NOW(), id, username, '203.0.113.7', 'user_login', 'defguard', 'Firefox', \
+        'Logged in from 203.0.113.7' FROM \"user\" WHERE username = 'admin'",
+    )
+    .execute(&client_state.pool)
+    .await
+    .unwrap();
+
+    // regular users can't access auditor endpoints and see only their own events
+    client.login_user("hpotter", "pass123").await;
+    let response = client.get("/api/v1/support/configuration").send().await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = client.get("/api/v1/network/stats").send().await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = client.get("/api/v1/activity_log").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let events: Value = response.json().await;
+    assert!(events["data"].as_array().unwrap().is_empty());
+
+    authenticate_admin(&mut client).await;
+    let data = json!({
+        "name": "auditors",
+        "members": ["hpotter"],
+        "is_admin": false,
+        "is_auditor": true
+    });
+    let response = client.post("/api/v1/group").json(&data).send().await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response = client.get("/api/v1/group/auditors").send().await;
+    let group: Value = response.json().await;
+    assert_eq!(group["is_auditor"], true);
+
+    // responses to admins aren't redacted
+    let response = client.get("/api/v1/support/configuration").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let config = response.text().await;
+    assert!(config.contains("h.potter@hogwart.edu.uk"));
+
+    // auditors have read-only access with personal data redacted
+    client.login_user("hpotter", "pass123").await;
+    let response = client.get("/api/v1/support/configuration").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let config = response.text().await;
+    assert!(!config.contains("h.potter@hogwart.edu.uk"));
+    assert!(config.contains(REDACTED));
+    let response = client.get("/api/v1/network/stats").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client.get("/api/v1/network/gateways").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client.get("/api/v1/activity_log").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let events: Value = response.json().await;
+    let event = &events["data"][0];
+    assert_eq!(event["username"], "admin");
+    assert_eq!(event["ip"], REDACTED);
+    assert_eq!(event["description"], "Logged in from [redacted]");
+
+    let response = client.get("/api/v1/settings").send().await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = client
+        .patch("/api/v1/settings")
+        .json(&json!({"auditor_redact_emails": false}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // redaction policy is configurable
+    authenticate_admin(&mut client).await;
+    let response = client
+        .patch("/api/v1/settings")
+        .json(&json!({"auditor_redact_emails": false}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    client.login_user("hpotter", "pass123").await;
+    let response = client.get("/api/v1/support/configuration").send().await;
+    let config = response.text().await;
+    assert!(config.contains("h.potter@hogwart.edu.uk"));
+    let response = client.get("/api/v1/activity_log").send().await;
+    let events: Value = response.json().await;
+    assert_eq!(events["data"][0]["ip"], REDACTED);
+}
+
+#[sqlx::test]
+async fn test_auditor_configuration_without_secrets(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, client_state) = make_test_client(pool).await;
+
+    authenticate_admin(&mut client).await;
+    let response = client
+        .post("/api/v1/network")
+        .json(&make_network())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response = client
+        .post("/api/v1/device/admin")
+        .json(&json!({
+            "name": "laptop",
+            "wireguard_pubkey": "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=",
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let data = json!({
+        "name": "auditors",
+        "members": ["hpotter"],
+        "is_admin": false,
+        "is_auditor": true
+    });
+    let response = client.post("/api/v1/group").json(&data).send().await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    sqlx::query(
+        "UPDATE settings SET smtp_password = 'smtp-secret', ldap_bind_password = 'ldap-secret', \
+        mail_http_token = 'mail-secret'",
+    )
+    .execute(&client_state.pool)
+    .await
+    .unwrap();
+    sqlx::query("UPDATE wireguard_network_device SET preshared_key = 'psk-secret'")
+        .execute(&client_state.pool)
+        .await
+        .unwrap();
+
+    // admins get preshared keys of devices
+    let response = client.get("/api/v1/support/configuration").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let config = response.text().await;
+    assert!(config.contains("psk-secret"));
+
+    // auditors get no credentials nor keys
+    client.login_user("hpotter", "pass123").await;
+    let response = client.get("/api/v1/support/configuration").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let config = response.text().await;
+    for secret in ["smtp-secret", "ldap-secret", "mail-secret", "psk-secret"] {
+        assert!(!config.contains(secret), "auditor got {secret}");
+    }
+    let config: Value = serde_json::from_str(&config).unwrap();
+    for field in [
+        "smtp_password",
+        "smtp_oauth2_client_secret",
+        "mail_http_token",
+        "ldap_bind_password",
+        "license",
+    ] {
+        assert!(
+            config["settings"].get(field).is_none(),
+            "auditor got {field}"
+        );
+    }
+    let devices = config["devices"].as_object().unwrap();
+    assert!(!devices.is_empty());
+    for device in devices
+        .values()
+        .flat_map(|devices| devices.as_array().unwrap())
+    {
+        assert!(device.get("preshared_key").is_none());
+    }
+    assert!(
+        config["networks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|network| network.get("prvkey").is_none())
+    );
+}
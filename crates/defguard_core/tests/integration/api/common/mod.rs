@@ -5,6 +5,7 @@ use std::{
     sync::{Arc, Mutex},
 };
 
+use bytes::Bytes;
 pub use defguard_common::db::setup_pool;
 use defguard_common::{
     VERSION,
@@ -29,7 +30,7 @@ use sqlx::PgPool;
 use tokio::{
     net::TcpListener,
     sync::{
-        broadcast::{self, Receiver},
+        broadcast::{self, Receiver, Sender},
         mpsc::{UnboundedReceiver, unbounded_channel},
     },
 };
@@ -49,6 +50,7 @@ pub(crate) struct ClientState {
     pub worker_state: Arc<Mutex<WorkerState>>,
     pub wireguard_rx: Receiver<GatewayEvent>,
     pub mail_rx: UnboundedReceiver<Mail>,
+    pub activity_log_tx: Sender<Bytes>,
     pub test_user: User<Id>,
     pub config: DefGuardConfig,
 }
@@ -59,6 +61,7 @@ impl ClientState {
         worker_state: Arc<Mutex<WorkerState>>,
         wireguard_rx: Receiver<GatewayEvent>,
         mail_rx: UnboundedReceiver<Mail>,
+        activity_log_tx: Sender<Bytes>,
         test_user: User<Id>,
         config: DefGuardConfig,
     ) -> Self {
@@ -67,6 +70,7 @@ impl ClientState {
             worker_state,
             wireguard_rx,
             mail_rx,
+            activity_log_tx,
             test_user,
             config,
         }
@@ -83,6 +87,7 @@ pub(crate) async fn make_base_client(
     let worker_state = Arc::new(Mutex::new(WorkerState::new(tx.clone())));
     let (wg_tx, wg_rx) = broadcast::channel::<GatewayEvent>(16);
     let (mail_tx, mail_rx) = unbounded_channel::<Mail>();
+    let (activity_log_tx, _) = broadcast::channel::<Bytes>(16);
     let gateway_state = Arc::new(Mutex::new(GatewayMap::new()));
 
     let failed_logins = FailedLoginMap::new();
@@ -105,6 +110,7 @@ pub(crate) async fn make_base_client(
         worker_state.clone(),
         wg_rx,
         mail_rx,
+        activity_log_tx.clone(),
         User::find_by_username(&pool, "hpotter")
             .await
             .unwrap()
@@ -135,6 +141,7 @@ pub(crate) async fn make_base_client(
         api_event_tx,
         Version::parse(VERSION).unwrap(),
         Default::default(),
+        activity_log_tx,
     );
 
     (
@@ -0,0 +1,82 @@
+use chrono::{Duration, Utc};
+use defguard_common::db::NoId;
+use defguard_core::db::models::wireguard_peer_stats::WireguardPeerStats;
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+use super::common::{authenticate_admin, make_network, make_test_client, setup_pool};
+
+#[sqlx::test]
+async fn test_dashboard(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, client_state) = make_test_client(pool).await;
+    let pool = client_state.pool;
+
+    let response = client.get("/api/v1/dashboard").send().await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    authenticate_admin(&mut client).await;
+    let response = client
+        .post("/api/v1/network")
+        .json(&make_network())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response = client
+        .post("/api/v1/device/admin")
+        .json(&json!({
+            "name": "device",
+            "wireguard_pubkey": "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=",
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // no traffic yet
+    let response = client.get("/api/v1/dashboard").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let dashboard: Value = response.json().await;
+    assert_eq!(dashboard["stats"]["current_active_users"], 0);
+    assert_eq!(dashboard["top_locations"], json!([]));
+    assert!(dashboard["recent_events"].is_array());
+    assert!(dashboard["license_info"].is_object());
+
+    // counters are cumulative, transfer is a sum of differences between samples
+    let now = Utc::now().naive_utc();
+    for (minutes, upload, download) in [(2, 100, 1000), (1, 150, 1100), (0, 300, 1400)] {
+        WireguardPeerStats {
+            id: NoId,
+            device_id: 1,
+            collected_at: now - Duration::minutes(minutes),
+            network: 1,
+            endpoint: Some("11.22.33.44".into()),
+            upload,
+            download,
+            latest_handshake: now,
+            allowed_ips: Some("10.1.1.0/24".into()),
+            gateway: None,
+        }
+        .save(&pool)
+        .await
+        .unwrap();
+    }
+
+    let response = client.get("/api/v1/dashboard").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let dashboard: Value = response.json().await;
+    assert_eq!(dashboard["stats"]["current_active_users"], 1);
+    assert_eq!(dashboard["stats"]["current_active_user_devices"], 1);
+    assert_eq!(dashboard["stats"]["upload"], 200);
+    assert_eq!(dashboard["stats"]["download"], 400);
+    assert_eq!(
+        dashboard["top_locations"],
+        json!([{
+            "id": 1,
+            "name": "network",
+            "active_devices": 1,
+            "upload": 200,
+            "download": 400,
+        }])
+    );
+}
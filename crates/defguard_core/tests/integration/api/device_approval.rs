@@ -0,0 +1,172 @@
+use chrono::{TimeDelta, Utc};
+use defguard_common::db::Id;
+use defguard_core::{
+    db::{
+        Device, GatewayEvent, WireguardNetwork,
+        models::{
+            device::DeviceType,
+            device_approval::{DeviceApprovalRequest, DeviceApprovalStatus},
+        },
+    },
+    device_approval::expire_device_approvals,
+};
+use matches::assert_matches;
+use reqwest::StatusCode;
+use serde_json::Value;
+use sqlx::{
+    PgPool,
+    postgres::{PgConnectOptions, PgPoolOptions},
+    query_scalar,
+};
+
+use super::common::{authenticate_admin, make_network, make_test_client, setup_pool};
+
+async fn is_in_location(pool: &PgPool, device_id: Id, location_id: Id) -> bool {
+    query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM wireguard_network_device \
+        WHERE device_id = $1 AND wireguard_network_id = $2)",
+    )
+    .bind(device_id)
+    .bind(location_id)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+#[sqlx::test]
+async fn test_device_approval(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, client_state) = make_test_client(pool).await;
+    let pool = client_state.pool;
+    let mut wg_rx = client_state.wireguard_rx;
+    authenticate_admin(&mut client).await;
+
+    // create location requiring device approval
+    let mut network = make_network();
+    network["device_approval_required"] = true.into();
+    let response = client.post("/api/v1/network").json(&network).send().await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let location: WireguardNetwork<Id> = response.json().await;
+    assert!(location.device_approval_required);
+
+    // devices awaiting approval, as created by enrollment
+    let user_id: Id = query_scalar("SELECT id FROM \"user\" WHERE username = 'hpotter'")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    let expires_at = Utc::now().naive_utc() + TimeDelta::hours(1);
+    let mut devices = Vec::new();
+    let mut requests = Vec::new();
+    for (name, pubkey) in [
+        ("approved", "2LYRr2HgSSpGCdXKDDAlcFe0Uuc6RR8TFgSquNc9VAE="),
+        ("rejected", "6xmL/jRuxmzQ3J2/kVZnKnh+6dwODcEEczmmkIKU4sM="),
+        ("expired", "A2cg4qMe+s0MSFlV6xyhz7XY6PrET6mli9GVSUshXAk="),
+    ] {
+        let device = Device::new(
+            name.into(),
+            pubkey.into(),
+            user_id,
+            DeviceType::User,
+            None,
+            true,
+        )
+        .save(&pool)
+        .await
+        .unwrap();
+        let request = DeviceApprovalRequest::new(device.id, location.id, expires_at)
+            .save(&pool)
+            .await
+            .unwrap();
+        devices.push(device);
+        requests.push(request);
+    }
+
+    // pending devices are not added to the location by a sync
+    let response = client
+        .put(format!("/api/v1/network/{}", location.id))
+        .json(&network)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    for device in &devices {
+        assert!(!is_in_location(&pool, device.id, location.id).await);
+    }
+    while wg_rx.try_recv().is_ok() {}
+
+    let response = client.get("/api/v1/device_approval").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let pending: Vec<Value> = response.json().await;
+    assert_eq!(pending.len(), 3);
+    assert_eq!(pending[0]["username"], "hpotter");
+    assert_eq!(pending[0]["location_name"], "network");
+
+    // approve device
+    let response = client
+        .post(format!(
+            "/api/v1/device_approval/{}/approve",
+            requests[0].id
+        ))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let request: DeviceApprovalRequest<Id> = response.json().await;
+    assert_eq!(request.status, DeviceApprovalStatus::Approved);
+    assert_eq!(request.resolved_by, Some(1));
+    assert!(is_in_location(&pool, devices[0].id, location.id).await);
+    let event = wg_rx.try_recv().unwrap();
+    assert_matches!(event, GatewayEvent::DeviceCreated(ref info) if info.device.id == devices[0].id);
+
+    // request can't be resolved twice
+    let response = client
+        .post(format!("/api/v1/device_approval/{}/reject", requests[0].id))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // reject device
+    let response = client
+        .post(format!("/api/v1/device_approval/{}/reject", requests[1].id))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let request: DeviceApprovalRequest<Id> = response.json().await;
+    assert_eq!(request.status, DeviceApprovalStatus::Rejected);
+    assert!(wg_rx.try_recv().is_err());
+
+    // expire remaining request
+    sqlx::query("UPDATE device_approval_request SET expires_at = $2 WHERE id = $1")
+        .bind(requests[2].id)
+        .bind(Utc::now().naive_utc() - TimeDelta::minutes(1))
+        .execute(&pool)
+        .await
+        .unwrap();
+    expire_device_approvals(&pool).await.unwrap();
+    let request = DeviceApprovalRequest::find_by_id(&pool, requests[2].id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(request.status, DeviceApprovalStatus::Expired);
+    let response = client
+        .post(format!(
+            "/api/v1/device_approval/{}/approve",
+            requests[2].id
+        ))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // rejected and expired devices stay out of the location
+    let response = client
+        .put(format!("/api/v1/network/{}", location.id))
+        .json(&network)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(is_in_location(&pool, devices[0].id, location.id).await);
+    assert!(!is_in_location(&pool, devices[1].id, location.id).await);
+    assert!(!is_in_location(&pool, devices[2].id, location.id).await);
+
+    let response = client.get("/api/v1/device_approval").send().await;
+    let pending: Vec<Value> = response.json().await;
+    assert!(pending.is_empty());
+}
@@ -0,0 +1,133 @@
+use defguard_core::db::models::polling_token::PollingToken;
+use reqwest::{StatusCode, header::AUTHORIZATION};
+use serde_json::{Value, json};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+use super::common::{authenticate_admin, make_network, make_test_client, setup_pool};
+
+#[sqlx::test]
+async fn test_dns_leak(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, _) = make_test_client(pool.clone()).await;
+
+    authenticate_admin(&mut client).await;
+    let response = client
+        .post("/api/v1/network")
+        .json(&make_network())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response = client
+        .post("/api/v1/device/admin")
+        .json(&json!({
+            "name": "laptop",
+            "wireguard_pubkey": "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=",
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let token = PollingToken::new(1).save(&pool).await.unwrap();
+
+    // invalid polling token and unknown location
+    let response = client
+        .post("/api/v1/dns_leak/canary")
+        .json(&json!({"token": "invalid", "network_id": 1}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = client
+        .post("/api/v1/dns_leak/canary")
+        .json(&json!({"token": token.token, "network_id": 2}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = client
+        .post("/api/v1/dns_leak/canary")
+        .json(&json!({"token": token.token, "network_id": 1}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let canary: Value = response.json().await;
+    let hostname = canary["hostname"].as_str().unwrap();
+    assert!(hostname.ends_with(".canary.defguard.test"));
+    let verify_url = format!("/api/v1/dns_leak/canary/{}/verify", canary["id"]);
+
+    let response = client
+        .post(&verify_url)
+        .json(&json!({"token": token.token}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let result: Value = response.json().await;
+    assert_eq!(result["status"], "pending");
+
+    // only the DNS server with a valid token can report queries
+    let query = json!({"hostname": format!("{}.", hostname.to_uppercase()), "resolver": "1.1.1.1"});
+    for token in ["invalid", "canary-toke", "canary-token2"] {
+        let response = client
+            .post("/api/v1/dns_leak/query")
+            .header(AUTHORIZATION, &format!("Bearer {token}"))
+            .json(&query)
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+    let response = client
+        .post("/api/v1/dns_leak/query")
+        .header(AUTHORIZATION, "Bearer canary-token")
+        .json(&query)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // location DNS server
+    let response = client
+        .post(&verify_url)
+        .json(&json!({"token": token.token}))
+        .send()
+        .await;
+    let result: Value = response.json().await;
+    assert_eq!(
+        result,
+        json!({"status": "no_leak", "resolvers": ["1.1.1.1"], "leaking_resolvers": []})
+    );
+
+    // resolver outside of the location
+    let response = client
+        .post("/api/v1/dns_leak/query")
+        .header(AUTHORIZATION, "Bearer canary-token")
+        .json(&json!({"hostname": hostname, "resolver": "8.8.8.8"}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .post(&verify_url)
+        .json(&json!({"token": token.token}))
+        .send()
+        .await;
+    let result: Value = response.json().await;
+    assert_eq!(
+        result,
+        json!({
+            "status": "leak",
+            "resolvers": ["1.1.1.1", "8.8.8.8"],
+            "leaking_resolvers": ["8.8.8.8"],
+        })
+    );
+
+    // unknown canary
+    let response = client
+        .post("/api/v1/dns_leak/query")
+        .header(AUTHORIZATION, "Bearer canary-token")
+        .json(&json!({"hostname": "unknown.canary.defguard.test", "resolver": "8.8.8.8"}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = client
+        .post("/api/v1/dns_leak/canary/100/verify")
+        .json(&json!({"token": token.token}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
@@ -367,3 +367,89 @@ async fn test_enrollment_pending_unset_for_desktop_client(
     assert!(!user.enrollment_pending);
     assert!(user.is_enrolled());
 }
+
+#[sqlx::test]
+async fn test_enrollment_sheet(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+
+    let (client, pool) = make_client_with_db(pool).await;
+
+    let auth = Auth::new("admin", "pass123");
+    let response = client.post("/api/v1/auth").json(&auth).send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let new_user = AddUserData {
+        username: "adumbledore".into(),
+        last_name: "Dumbledore".into(),
+        first_name: "Albus".into(),
+        email: "a.dumbledore@hogwart.edu.uk".into(),
+        phone: Some("1234".into()),
+        password: None,
+    };
+    let response = client.post("/api/v1/user").json(&new_user).send().await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // invalid expiration time
+    let response = client
+        .post("/api/v1/user/adumbledore/enrollment_sheet")
+        .json(&json!({"token_expiration_time": "invalid"}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = client
+        .post("/api/v1/user/adumbledore/enrollment_sheet")
+        .json(&json!({"token_expiration_time": "7d"}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert_eq!(response.headers()["content-type"], "application/pdf");
+    assert_eq!(
+        response.headers()["content-disposition"],
+        "attachment; filename=\"enrollment_adumbledore.pdf\""
+    );
+    let pdf = response.bytes().await;
+    assert!(pdf.starts_with(b"%PDF-"));
+
+    // token printed on the sheet is valid
+    let enrollments = Token::fetch_all(&pool).await.unwrap();
+    assert_eq!(enrollments.len(), 1);
+    let token = &enrollments[0];
+    assert!(
+        String::from_utf8_lossy(&pdf).contains(&format!("({}) Tj", token.id)),
+        "token missing from the sheet"
+    );
+    assert!(token.expires_at > chrono::Utc::now().naive_utc() + Duration::days(6));
+
+    // batch fails entirely if any user doesn't exist
+    let response = client
+        .post("/api/v1/user/enrollment_sheets")
+        .json(&json!({"usernames": ["adumbledore", "nonexistent"]}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let enrollments = Token::fetch_all(&pool).await.unwrap();
+    assert_eq!(enrollments.len(), 1);
+    assert_eq!(enrollments[0].id, token.id);
+
+    // enrolled users are rejected
+    let response = client
+        .post("/api/v1/user/enrollment_sheets")
+        .json(&json!({"usernames": ["adumbledore", "hpotter"]}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = client
+        .post("/api/v1/user/enrollment_sheets")
+        .json(&json!({"usernames": ["adumbledore"]}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert_eq!(response.headers()["content-type"], "application/zip");
+    assert!(response.bytes().await.starts_with(b"PK"));
+    // previous token has been replaced
+    let enrollments = Token::fetch_all(&pool).await.unwrap();
+    assert_eq!(enrollments.len(), 1);
+    assert_ne!(enrollments[0].id, token.id);
+}
@@ -0,0 +1,164 @@
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions},
+    query,
+};
+use tokio::time::timeout;
+
+use super::common::{authenticate_admin, make_test_client, setup_pool};
+
+#[sqlx::test]
+async fn test_enrollment_token_management(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, client_state) = make_test_client(pool.clone()).await;
+    let mut mail_rx = client_state.mail_rx;
+
+    // only admins can manage tokens
+    client.login_user("hpotter", "pass123").await;
+    let response = client
+        .get("/api/v1/user/hpotter/enrollment_token")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    authenticate_admin(&mut client).await;
+    let response = client
+        .get("/api/v1/user/nobody/enrollment_token")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = client
+        .post("/api/v1/user")
+        .json(&json!({
+            "username": "adumbledore",
+            "last_name": "Dumbledore",
+            "first_name": "Albus",
+            "email": "a.dumbledore@hogwart.edu.uk",
+            "phone": null,
+            "password": null,
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response = client
+        .post("/api/v1/user/adumbledore/start_enrollment")
+        .json(&json!({
+            "email": "albus@example.com",
+            "send_enrollment_notification": false,
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let started: Value = response.json().await;
+    let token = started["enrollment_token"].as_str().unwrap().to_string();
+    while mail_rx.try_recv().is_ok() {}
+
+    let response = client
+        .get("/api/v1/user/adumbledore/enrollment_token")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let tokens: Vec<Value> = response.json().await;
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0]["token"], token);
+    assert_eq!(tokens[0]["status"], "pending");
+    assert_eq!(tokens[0]["email"], "albus@example.com");
+    assert_eq!(tokens[0]["used_ip"], Value::Null);
+
+    // tokens are scoped to their user
+    let response = client
+        .post(format!(
+            "/api/v1/user/hpotter/enrollment_token/{token}/resend"
+        ))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // resend goes to the address the token was issued for
+    let response = client
+        .post(format!(
+            "/api/v1/user/adumbledore/enrollment_token/{token}/resend"
+        ))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let mail = timeout(Duration::from_secs(5), mail_rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(mail.to, "albus@example.com");
+    assert!(mail.content.contains(&token));
+
+    // extend
+    let response = client
+        .put(format!("/api/v1/user/adumbledore/enrollment_token/{token}"))
+        .json(&json!({"token_expiration_time": "invalid"}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = client
+        .put(format!("/api/v1/user/adumbledore/enrollment_token/{token}"))
+        .json(&json!({"token_expiration_time": "30days"}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let extended: Value = response.json().await;
+    let expires_at: NaiveDateTime = serde_json::from_value(extended["expires_at"].clone()).unwrap();
+    let previous: NaiveDateTime = serde_json::from_value(tokens[0]["expires_at"].clone()).unwrap();
+    assert!(expires_at > previous);
+
+    // used tokens show the client and can't be resent or extended
+    query(
+        "UPDATE token SET used_at = now() - interval '1 day', use_count = 1, \
+        used_ip = '10.1.1.1', used_user_agent = 'defguard-proxy' WHERE id = $1",
+    )
+    .bind(&token)
+    .execute(&pool)
+    .await
+    .unwrap();
+    let response = client
+        .get("/api/v1/user/adumbledore/enrollment_token")
+        .send()
+        .await;
+    let tokens: Vec<Value> = response.json().await;
+    assert_eq!(tokens[0]["status"], "used");
+    assert_eq!(tokens[0]["use_count"], 1);
+    assert_eq!(tokens[0]["used_ip"], "10.1.1.1");
+    assert_eq!(tokens[0]["used_user_agent"], "defguard-proxy");
+    let response = client
+        .post(format!(
+            "/api/v1/user/adumbledore/enrollment_token/{token}/resend"
+        ))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = client
+        .put(format!("/api/v1/user/adumbledore/enrollment_token/{token}"))
+        .json(&json!({"token_expiration_time": "1day"}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // revoke
+    let response = client
+        .delete(format!("/api/v1/user/adumbledore/enrollment_token/{token}"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .delete(format!("/api/v1/user/adumbledore/enrollment_token/{token}"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = client
+        .get("/api/v1/user/adumbledore/enrollment_token")
+        .send()
+        .await;
+    let tokens: Vec<Value> = response.json().await;
+    assert!(tokens.is_empty());
+}
@@ -5,7 +5,7 @@ use defguard_core::{
         db::models::enterprise_settings::{ClientTrafficPolicy, EnterpriseSettings},
         license::{get_cached_license, set_cached_license},
     },
-    handlers::{Auth, wireguard::AddDeviceResult},
+    handlers::{Auth, GroupInfo, wireguard::AddDeviceResult},
 };
 use ipnetwork::IpNetwork;
 use reqwest::StatusCode;
@@ -441,3 +441,108 @@ async fn test_override_allowed_ips(_: PgPoolOptions, options: PgConnectOptions)
         )
     }
 }
+
+#[sqlx::test]
+async fn test_client_traffic_policy_overrides(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+
+    // admin login
+    let (client, client_state) = make_test_client(pool).await;
+    let auth = Auth::new("admin", "pass123");
+    let response = client.post("/api/v1/auth").json(&auth).send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // create network
+    let response = client
+        .post("/api/v1/network")
+        .json(&make_network())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // force all traffic for members of a group
+    let group = json!({
+        "name": "contractors",
+        "members": ["hpotter"],
+        "is_admin": false,
+        "client_traffic_policy": "force_all_traffic",
+    });
+    let response = client.post("/api/v1/group").json(&group).send().await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response = client.get("/api/v1/group/contractors").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let group: GroupInfo = response.json().await;
+    assert_eq!(
+        group.client_traffic_policy,
+        Some(ClientTrafficPolicy::ForceAllTraffic)
+    );
+
+    // the strictest policy wins for members of multiple groups
+    let group = json!({
+        "name": "students",
+        "members": ["hpotter"],
+        "is_admin": false,
+        "client_traffic_policy": "disable_all_traffic",
+    });
+    let response = client.post("/api/v1/group").json(&group).send().await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let settings = EnterpriseSettings::get_for_user(&client_state.pool, 2)
+        .await
+        .unwrap();
+    assert_eq!(
+        settings.client_traffic_policy,
+        ClientTrafficPolicy::ForceAllTraffic
+    );
+    let settings = EnterpriseSettings::get_for_user(&client_state.pool, 1)
+        .await
+        .unwrap();
+    assert_eq!(settings.client_traffic_policy, ClientTrafficPolicy::None);
+
+    let all_traffic = vec![
+        IpNetwork::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0).unwrap(),
+        IpNetwork::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0).unwrap(),
+    ];
+    let location_allowed_ips = vec!["10.1.1.0/24".parse::<IpNetwork>().unwrap()];
+
+    // group member is forced to route all traffic
+    let device = json!({
+        "name": "device",
+        "wireguard_pubkey": "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=",
+    });
+    let response = client
+        .post("/api/v1/device/hpotter")
+        .json(&device)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response: AddDeviceResult = response.json().await;
+    assert_eq!(response.configs[0].allowed_ips, all_traffic);
+    let device_id = response.device.id;
+
+    // other users aren't affected
+    let device = json!({
+        "name": "admin-device",
+        "wireguard_pubkey": "hNuapt7lOxF93KUqZGUY00oKJxH8LYwwsUVB1uUa0y4=",
+    });
+    let response = client
+        .post("/api/v1/device/admin")
+        .json(&device)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response: AddDeviceResult = response.json().await;
+    assert_eq!(response.configs[0].allowed_ips, location_allowed_ips);
+
+    // location override takes precedence over groups
+    let mut network = make_network();
+    network["client_traffic_policy"] = json!("none");
+    let response = client.put("/api/v1/network/1").json(&network).send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .get(format!("/api/v1/network/1/device/{device_id}/config"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let config = response.text().await;
+    assert!(config.contains("AllowedIPs = 10.1.1.0/24"));
+}
@@ -0,0 +1,170 @@
+use defguard_core::{db::GatewayEvent, handlers::Auth};
+use defguard_proto::enterprise::firewall::{
+    FirewallConfig, FirewallPolicy, FirewallRule, IpAddress, IpVersion, ip_address::Address,
+};
+use matches::assert_matches;
+use prost::Message;
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+use super::common::{authenticate_admin, make_network, make_test_client, setup_pool};
+
+fn rule(destination: &str) -> FirewallRule {
+    FirewallRule {
+        id: 1,
+        source_addrs: vec![IpAddress {
+            address: Some(Address::Ip("10.1.1.2".into())),
+        }],
+        destination_addrs: vec![IpAddress {
+            address: Some(Address::IpSubnet(destination.into())),
+        }],
+        verdict: FirewallPolicy::Allow.into(),
+        ip_version: IpVersion::Ipv4.into(),
+        ..Default::default()
+    }
+}
+
+#[sqlx::test]
+async fn test_firewall_config_rollback(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, client_state) = make_test_client(pool).await;
+    let mut wg_rx = client_state.wireguard_rx;
+    authenticate_admin(&mut client).await;
+
+    let response = client
+        .post("/api/v1/network")
+        .json(&make_network())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    while wg_rx.try_recv().is_ok() {}
+
+    // a working configuration replaced by one with a wrong destination
+    let working = FirewallConfig {
+        default_policy: FirewallPolicy::Deny.into(),
+        rules: vec![rule("192.168.1.0/24")],
+        snat_bindings: Vec::new(),
+    };
+    let broken = FirewallConfig {
+        default_policy: FirewallPolicy::Deny.into(),
+        rules: vec![rule("192.168.2.0/24")],
+        snat_bindings: Vec::new(),
+    };
+    for (version, config) in [(1, &working), (2, &broken)] {
+        sqlx::query(
+            "INSERT INTO firewall_config_version (location_id, version, config) \
+            VALUES (1, $1, $2)",
+        )
+        .bind(version as i64)
+        .bind(config.encode_to_vec())
+        .execute(&client_state.pool)
+        .await
+        .unwrap();
+    }
+
+    let response = client
+        .get("/api/v1/network/1/firewall/version")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let versions: Value = response.json().await;
+    assert_eq!(versions.as_array().unwrap().len(), 2);
+    assert_eq!(versions[0]["version"], 2);
+    assert_eq!(versions[0]["default_policy"], "deny");
+    assert_eq!(versions[0]["rules"], 1);
+
+    // changes against the preceding version
+    let response = client
+        .get("/api/v1/network/1/firewall/version/2")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let details: Value = response.json().await;
+    assert_eq!(details["compared_to"], 1);
+    assert_eq!(
+        details["rules"],
+        json!(["allow any ipv4 from 10.1.1.2 to 192.168.2.0/24 port any"])
+    );
+    assert_eq!(
+        details["diff"]["added_rules"],
+        json!(["allow any ipv4 from 10.1.1.2 to 192.168.2.0/24 port any"])
+    );
+    assert_eq!(
+        details["diff"]["removed_rules"],
+        json!(["allow any ipv4 from 10.1.1.2 to 192.168.1.0/24 port any"])
+    );
+    assert_eq!(details["diff"]["previous_default_policy"], Value::Null);
+    let response = client
+        .get("/api/v1/network/1/firewall/version/1")
+        .send()
+        .await;
+    let details: Value = response.json().await;
+    assert_eq!(details["compared_to"], Value::Null);
+    assert_eq!(details["diff"], Value::Null);
+    let response = client
+        .get("/api/v1/network/1/firewall/version/1?compare=2")
+        .send()
+        .await;
+    let details: Value = response.json().await;
+    assert_eq!(details["compared_to"], 2);
+    assert_eq!(
+        details["diff"]["added_rules"],
+        json!(["allow any ipv4 from 10.1.1.2 to 192.168.1.0/24 port any"])
+    );
+
+    // roll back to the working configuration
+    let response = client
+        .post("/api/v1/network/1/firewall/version/1/rollback")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let latest: Value = response.json().await;
+    assert_eq!(latest["version"], 3);
+    assert_eq!(latest["rollback_of"], 1);
+    assert_eq!(
+        latest["rules"],
+        json!(["allow any ipv4 from 10.1.1.2 to 192.168.1.0/24 port any"])
+    );
+    let event = wg_rx.try_recv().unwrap();
+    assert_matches!(
+        event,
+        GatewayEvent::FirewallConfigChanged(1, config) if config == working
+    );
+
+    // rolling back to the current configuration doesn't add a version
+    let response = client
+        .post("/api/v1/network/1/firewall/version/3/rollback")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let latest: Value = response.json().await;
+    assert_eq!(latest["version"], 3);
+
+    // nonexistent versions and locations
+    let response = client
+        .get("/api/v1/network/1/firewall/version/4")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = client
+        .post("/api/v1/network/1/firewall/version/4/rollback")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = client
+        .get("/api/v1/network/2/firewall/version")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // only admins can roll back
+    let auth = Auth::new("hpotter", "pass123");
+    let response = client.post("/api/v1/auth").json(&auth).send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .post("/api/v1/network/1/firewall/version/1/rollback")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
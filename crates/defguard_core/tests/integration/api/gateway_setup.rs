@@ -0,0 +1,158 @@
+use defguard_common::auth::claims::{Claims, ClaimsType};
+use reqwest::StatusCode;
+use serde_json::Value;
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions},
+    query,
+};
+
+use super::common::{authenticate_admin, make_network, make_test_client, setup_pool};
+
+#[sqlx::test]
+async fn test_gateway_setup_link(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, client_state) = make_test_client(pool).await;
+
+    // only admins can create links
+    let response = client
+        .post("/api/v1/network/1/gateway_setup_link")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    authenticate_admin(&mut client).await;
+    let response = client
+        .post("/api/v1/network/1/gateway_setup_link")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = client
+        .post("/api/v1/network")
+        .json(&make_network())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let network: Value = response.json().await;
+    let network_id = network["id"].as_i64().unwrap();
+
+    let response = client
+        .post(format!("/api/v1/network/{network_id}/gateway_setup_link"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let link: Value = response.json().await;
+    let (_, path) = link["url"]
+        .as_str()
+        .unwrap()
+        .split_once("/api/v1/")
+        .unwrap();
+    let path = format!("/api/v1/{path}");
+
+    // links don't require authentication
+    let response = client.post("/api/v1/auth/logout").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client.get(&path).send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let bundle: Value = response.json().await;
+    assert_eq!(bundle["network_id"], network_id);
+    assert_eq!(bundle["network_name"], "network");
+    let token = bundle["token"].as_str().unwrap();
+    let claims = Claims::from_jwt(ClaimsType::Gateway, token).unwrap();
+    assert_eq!(claims.client_id, network_id.to_string());
+    assert!(
+        bundle["env"]
+            .as_str()
+            .unwrap()
+            .contains(&format!("DEFGUARD_TOKEN={token}"))
+    );
+    assert!(
+        bundle["docker_compose"]
+            .as_str()
+            .unwrap()
+            .contains(&format!("DEFGUARD_TOKEN: {token}"))
+    );
+
+    // links can be used only once
+    let response = client.get(&path).send().await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // expired links can't be used
+    authenticate_admin(&mut client).await;
+    let response = client
+        .post(format!("/api/v1/network/{network_id}/gateway_setup_link"))
+        .send()
+        .await;
+    let link: Value = response.json().await;
+    let (_, path) = link["url"]
+        .as_str()
+        .unwrap()
+        .split_once("/api/v1/")
+        .unwrap();
+    query!("UPDATE gateway_setup_link SET expires_at = NOW() - interval '1 minute'")
+        .execute(&client_state.pool)
+        .await
+        .unwrap();
+    let response = client.get(format!("/api/v1/{path}")).send().await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test]
+async fn test_gateway_deployment(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, _) = make_test_client(pool).await;
+    authenticate_admin(&mut client).await;
+
+    let response = client
+        .post("/api/v1/network")
+        .json(&make_network())
+        .send()
+        .await;
+    let network: Value = response.json().await;
+    let network_id = network["id"].as_i64().unwrap();
+
+    let response = client
+        .get(format!(
+            "/api/v1/network/{network_id}/gateway_deployment?format=docker_compose&name=gw-1&version=1.5.0"
+        ))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let compose = response.text().await;
+    assert!(compose.contains("image: ghcr.io/defguard/gateway:1.5.0\n"));
+    assert!(compose.contains("DEFGUARD_GATEWAY_NAME: gw-1\n"));
+    let (_, token) = compose.split_once("DEFGUARD_TOKEN: ").unwrap();
+    let (token, _) = token.split_once('\n').unwrap();
+    let claims = Claims::from_jwt(ClaimsType::Gateway, token).unwrap();
+    assert_eq!(claims.client_id, network_id.to_string());
+
+    let response = client
+        .get(format!(
+            "/api/v1/network/{network_id}/gateway_deployment?format=systemd"
+        ))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let unit = response.text().await;
+    assert!(unit.contains("EnvironmentFile=/etc/defguard/gateway.env\n"));
+
+    // invalid parameters
+    for query in [
+        "format=ansible",
+        "format=env&version=next",
+        "format=env&name=gw%0A1",
+    ] {
+        let response = client
+            .get(format!(
+                "/api/v1/network/{network_id}/gateway_deployment?{query}"
+            ))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{query}");
+    }
+    let response = client
+        .get("/api/v1/network/100/gateway_deployment?format=env")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
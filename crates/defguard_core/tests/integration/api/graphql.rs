@@ -0,0 +1,116 @@
+use defguard_core::handlers::Auth;
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+use super::common::{
+    authenticate_admin, client::TestClient, make_network, make_test_client, setup_pool,
+};
+
+async fn graphql(client: &TestClient, query: &str) -> Value {
+    let response = client
+        .post("/api/v1/graphql")
+        .json(&json!({ "query": query }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    response.json().await
+}
+
+#[sqlx::test]
+async fn test_graphql(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, _) = make_test_client(pool).await;
+
+    let response = client
+        .post("/api/v1/graphql")
+        .json(&json!({ "query": "{ me { username } }" }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    authenticate_admin(&mut client).await;
+    let response = client
+        .post("/api/v1/network")
+        .json(&make_network())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response = client
+        .post("/api/v1/device/hpotter")
+        .json(&json!({
+            "name": "device",
+            "wireguard_pubkey": "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=",
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // admin can fetch nested data in a single query
+    let response = graphql(
+        &client,
+        "{ users { username email devices { name locations { locationName wireguardIps } } } \
+        locations { name devices { deviceId authorized } stats { currentActiveUsers upload } } }",
+    )
+    .await;
+    assert!(response.get("errors").is_none());
+    let users = response["data"]["users"].as_array().unwrap();
+    assert_eq!(users.len(), 2);
+    let hpotter = users
+        .iter()
+        .find(|user| user["username"] == "hpotter")
+        .unwrap();
+    assert_eq!(hpotter["email"], "h.potter@hogwart.edu.uk");
+    assert_eq!(
+        hpotter["devices"],
+        json!([{
+            "name": "device",
+            "locations": [{ "locationName": "network", "wireguardIps": ["10.1.1.2"] }],
+        }])
+    );
+    assert_eq!(
+        response["data"]["locations"],
+        json!([{
+            "name": "network",
+            "devices": [{ "deviceId": 1, "authorized": false }],
+            "stats": { "currentActiveUsers": 0, "upload": 0 },
+        }])
+    );
+
+    // non-admin users can only query themselves
+    client.post("/api/v1/auth/logout").send().await;
+    let response = client
+        .post("/api/v1/auth")
+        .json(&Auth::new("hpotter", "pass123"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = graphql(&client, "{ me { username email devices { name } } }").await;
+    assert!(response.get("errors").is_none());
+    assert_eq!(
+        response["data"]["me"],
+        json!({
+            "username": "hpotter",
+            "email": "h.potter@hogwart.edu.uk",
+            "devices": [{ "name": "device" }],
+        })
+    );
+
+    let response = graphql(&client, "{ users { username } }").await;
+    assert_eq!(response["errors"][0]["message"], "Admin role required");
+
+    let response = graphql(&client, "{ user(username: \"admin\") { username } }").await;
+    assert_eq!(response["errors"][0]["message"], "Access denied");
+
+    let response = graphql(
+        &client,
+        "{ me { devices { locations { locationId authorized } } } }",
+    )
+    .await;
+    assert!(response.get("errors").is_none());
+    assert_eq!(
+        response["data"]["me"]["devices"][0]["locations"],
+        json!([{ "locationId": 1, "authorized": false }])
+    );
+}
@@ -0,0 +1,134 @@
+use defguard_core::{
+    db::models::helpdesk_password_reset::HelpdeskPasswordReset,
+    handlers::{AddUserData, Auth},
+};
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+use super::common::{authenticate_admin, get_db_user, make_test_client, setup_pool};
+
+const TEMPORARY_PASSWORD: &str = "Temporary1234!";
+
+#[sqlx::test]
+async fn test_helpdesk_password_reset(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, _) = make_test_client(pool.clone()).await;
+
+    // regular users can't initiate resets
+    client.login_user("hpotter", "pass123").await;
+    let response = client
+        .post("/api/v1/user/admin/helpdesk_password_reset")
+        .json(&json!({"password": TEMPORARY_PASSWORD}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    authenticate_admin(&mut client).await;
+    let new_user = AddUserData {
+        username: "adumbledore".into(),
+        last_name: "Dumbledore".into(),
+        first_name: "Albus".into(),
+        email: "a.dumbledore@hogwart.edu.uk".into(),
+        phone: None,
+        password: Some("Password1234543$!".into()),
+    };
+    let response = client.post("/api/v1/user").json(&new_user).send().await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let data = json!({
+        "name": "helpdesk",
+        "members": ["hpotter"],
+        "is_admin": false,
+        "is_helpdesk": true
+    });
+    let response = client.post("/api/v1/group").json(&data).send().await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response = client.get("/api/v1/group/helpdesk").send().await;
+    let group: Value = response.json().await;
+    assert_eq!(group["is_helpdesk"], true);
+    client.drain_all_events();
+
+    // helpdesk can't reset passwords of admins
+    client.login_user("hpotter", "pass123").await;
+    let response = client
+        .post("/api/v1/user/admin/helpdesk_password_reset")
+        .json(&json!({"password": TEMPORARY_PASSWORD}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = client
+        .post("/api/v1/user/adumbledore/helpdesk_password_reset")
+        .json(&json!({"password": "weak"}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = client
+        .post("/api/v1/user/adumbledore/helpdesk_password_reset")
+        .json(&json!({"password": TEMPORARY_PASSWORD}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let reset: Value = response.json().await;
+    assert_eq!(reset["status"], "pending");
+    assert_eq!(reset["requested_by"], "hpotter");
+    assert!(reset.get("code").is_none());
+
+    // temporary password isn't active until confirmed
+    let auth = Auth::new("adumbledore", TEMPORARY_PASSWORD);
+    let response = client.post("/api/v1/auth").json(&auth).send().await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let user = get_db_user(&pool, "adumbledore").await;
+    let code = HelpdeskPasswordReset::all_for_user(&pool, user.id)
+        .await
+        .unwrap()
+        .remove(0)
+        .code;
+    let response = client
+        .post("/api/v1/helpdesk_password_reset/confirm")
+        .json(&json!({
+            "username": "adumbledore",
+            "code": "invalid",
+            "password": TEMPORARY_PASSWORD
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = client
+        .post("/api/v1/helpdesk_password_reset/confirm")
+        .json(&json!({
+            "username": "adumbledore",
+            "code": code,
+            "password": TEMPORARY_PASSWORD
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // confirmed reset can't be reused
+    let response = client
+        .post("/api/v1/helpdesk_password_reset/confirm")
+        .json(&json!({
+            "username": "adumbledore",
+            "code": code,
+            "password": TEMPORARY_PASSWORD
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // audit trail
+    let response = client
+        .get("/api/v1/user/adumbledore/helpdesk_password_reset")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let resets: Vec<Value> = response.json().await;
+    assert_eq!(resets.len(), 1);
+    assert_eq!(resets[0]["status"], "confirmed");
+    assert_eq!(resets[0]["failed_attempts"], 1);
+    assert!(resets[0]["confirmed_at"].is_string());
+
+    client.drain_all_events();
+    client.login_user("adumbledore", TEMPORARY_PASSWORD).await;
+}
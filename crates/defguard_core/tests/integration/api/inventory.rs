@@ -0,0 +1,114 @@
+use reqwest::{
+    StatusCode,
+    header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+};
+use serde_json::{Value, json};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+use super::common::{authenticate_admin, make_network, make_test_client, setup_pool};
+
+#[sqlx::test]
+async fn test_inventory_export(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, _) = make_test_client(pool).await;
+
+    // admin only
+    let response = client.get("/api/v1/inventory/users").send().await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    client.login_user("hpotter", "pass123").await;
+    let response = client.get("/api/v1/inventory/users").send().await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    authenticate_admin(&mut client).await;
+
+    let response = client
+        .post("/api/v1/network")
+        .json(&make_network())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response = client
+        .post("/api/v1/device/admin")
+        .json(&json!({
+            "name": "laptop",
+            "wireguard_pubkey": "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=",
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // users as CSV with all columns
+    let response = client.get("/api/v1/inventory/users").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[CONTENT_TYPE], "text/csv");
+    assert!(
+        response.headers()[CONTENT_DISPOSITION]
+            .to_str()
+            .unwrap()
+            .ends_with(".csv\"")
+    );
+    let csv = response.text().await;
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        lines[0],
+        "id,username,first_name,last_name,email,phone,is_active,enrollment_pending,mfa_enabled,\
+        mfa_method,from_ldap,groups,devices,last_handshake"
+    );
+    assert_eq!(lines.len(), 3);
+    assert!(lines[1].starts_with("1,admin,"));
+    assert!(lines[1].ends_with(",admin,1,"));
+    assert!(lines[2].starts_with("2,hpotter,Harry,Potter,h.potter@hogwart.edu.uk,"));
+
+    // users as NDJSON with selected columns
+    let response = client
+        .get("/api/v1/inventory/users?format=ndjson&columns=username,groups,devices")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[CONTENT_TYPE], "application/x-ndjson");
+    let users: Vec<Value> = response
+        .text()
+        .await
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(
+        users,
+        [
+            json!({"username": "admin", "groups": ["admin"], "devices": 1}),
+            json!({"username": "hpotter", "groups": [], "devices": 0}),
+        ]
+    );
+
+    // devices with addresses in each location
+    let response = client
+        .get("/api/v1/inventory/devices?format=ndjson&columns=name,owner,addresses,last_handshake")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let device: Value = serde_json::from_str(response.text().await.trim_end()).unwrap();
+    assert_eq!(
+        device,
+        json!({
+            "name": "laptop",
+            "owner": "admin",
+            "addresses": {"network": ["10.1.1.2"]},
+            "last_handshake": null,
+        })
+    );
+    let response = client
+        .get("/api/v1/inventory/devices?columns=name,addresses")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.text().await,
+        "name,addresses\nlaptop,network=10.1.1.2\n"
+    );
+
+    // unknown column
+    let response = client
+        .get("/api/v1/inventory/devices?columns=name,preshared_key")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,81 @@
+use reqwest::{StatusCode, header::CONTENT_TYPE};
+use serde_json::{Value, json};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+use super::common::{authenticate_admin, make_network, make_test_client, setup_pool};
+
+#[sqlx::test]
+async fn test_ip_allowlist(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, _) = make_test_client(pool).await;
+
+    // admin only
+    let response = client.get("/api/v1/ip_allowlist").send().await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    authenticate_admin(&mut client).await;
+
+    let response = client
+        .post("/api/v1/network")
+        .json(&make_network())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    for (username, name, pubkey) in [
+        (
+            "admin",
+            "laptop",
+            "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=",
+        ),
+        (
+            "hpotter",
+            "phone",
+            "sIhx53MsX+iLk83sssybHrD7M+5m+CmpLzWL/zo8C38=",
+        ),
+    ] {
+        let response = client
+            .post(format!("/api/v1/device/{username}"))
+            .json(&json!({"name": name, "wireguard_pubkey": pubkey}))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    let response = client.get("/api/v1/ip_allowlist").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let allowlist: Value = response.json().await;
+    assert_eq!(
+        allowlist["users"],
+        json!([
+            {"username": "admin", "groups": ["admin"], "addresses": ["10.1.1.2"]},
+            {"username": "hpotter", "groups": [], "addresses": ["10.1.1.3"]},
+        ])
+    );
+    assert_eq!(
+        allowlist["groups"],
+        json!([{"name": "admin", "addresses": ["10.1.1.2"]}])
+    );
+
+    let response = client
+        .get("/api/v1/ip_allowlist?format=panos&location=1")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[CONTENT_TYPE], "application/xml");
+    let message = response.text().await;
+    assert!(message.contains(
+        "<entry ip=\"10.1.1.2\"><tag><member>defguard-group-admin</member>\
+        <member>defguard-user-admin</member></tag></entry>"
+    ));
+
+    let response = client.get("/api/v1/ip_allowlist?format=ipset").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let script = response.text().await;
+    assert!(script.contains("add dg-u-hpotter 10.1.1.3\n"));
+    assert!(script.contains("add dg-g-admin 10.1.1.2\n"));
+
+    // unknown location and format
+    let response = client.get("/api/v1/ip_allowlist?location=2").send().await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = client.get("/api/v1/ip_allowlist?format=csv").send().await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
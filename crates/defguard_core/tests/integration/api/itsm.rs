@@ -0,0 +1,86 @@
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+use super::common::{authenticate_admin, make_test_client, setup_pool};
+
+#[sqlx::test]
+async fn test_itsm_connector_lifecycle(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, _) = make_test_client(pool).await;
+    authenticate_admin(&mut client).await;
+
+    let mut connector = json!({
+        "name": "Service desk",
+        "connector_type": "jira",
+        "url": "https://example.atlassian.net",
+        "username": "ops@example.com",
+        "secret": "token",
+        "project": "OPS",
+        "enabled": true,
+        "on_gateway_down": true,
+        "gateway_down_threshold": 30,
+        "fields": {"labels": "{{ event }}"},
+    });
+
+    // invalid configurations
+    for (key, value) in [
+        ("secret", Value::Null),
+        ("project", Value::Null),
+        ("url", json!("ftp://example.com")),
+        ("gateway_down_threshold", json!(0)),
+        ("summary_template", json!("{{ title")),
+        ("fields", json!({"labels": 1})),
+    ] {
+        let mut invalid = connector.clone();
+        invalid[key] = value;
+        let response = client
+            .post("/api/v1/itsm_connector")
+            .json(&invalid)
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{key}");
+    }
+
+    let response = client
+        .post("/api/v1/itsm_connector")
+        .json(&connector)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: Value = response.json().await;
+    let id = created["id"].as_i64().unwrap();
+    assert_eq!(created["summary_template"], "{{ title }}");
+    assert_eq!(created["certificate_expiration_threshold"], 14);
+    // secret is never returned
+    assert!(created.get("secret").is_none());
+
+    let response = client.get("/api/v1/itsm_connector").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let connectors: Vec<Value> = response.json().await;
+    assert_eq!(connectors.len(), 1);
+    assert_eq!(connectors[0]["name"], "Service desk");
+
+    // modify without changing the secret
+    connector["secret"] = Value::Null;
+    connector["on_license_expired"] = json!(true);
+    let response = client
+        .put(format!("/api/v1/itsm_connector/{id}"))
+        .json(&connector)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let modified: Value = response.json().await;
+    assert_eq!(modified["on_license_expired"], true);
+
+    let response = client
+        .delete(format!("/api/v1/itsm_connector/{id}"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .get(format!("/api/v1/itsm_connector/{id}"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,46 @@
+use defguard_core::db::models::background_job::BackgroundJob;
+use reqwest::StatusCode;
+use serde_json::Value;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+use super::common::{authenticate_admin, make_test_client, setup_pool};
+
+#[sqlx::test]
+async fn test_background_jobs_api(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, client_state) = make_test_client(pool).await;
+    let pool = client_state.pool;
+
+    // jobs are only available for admins
+    let response = client.get("/api/v1/jobs").send().await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    authenticate_admin(&mut client).await;
+    let response = client.get("/api/v1/jobs").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let jobs: Vec<Value> = response.json().await;
+    assert!(jobs.is_empty());
+
+    let response = client.get("/api/v1/jobs/1").send().await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = client.post("/api/v1/jobs/1/cancel").send().await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let job = BackgroundJob::create(&pool, "test", None).await.unwrap();
+    let response = client.get("/api/v1/jobs").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let jobs: Vec<Value> = response.json().await;
+    assert_eq!(jobs.len(), 1);
+
+    let response = client.get(format!("/api/v1/jobs/{}", job.id)).send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let fetched: BackgroundJob = response.json().await;
+    assert_eq!(fetched, job);
+
+    // job not spawned by the runner can't be cancelled
+    let response = client
+        .post(format!("/api/v1/jobs/{}/cancel", job.id))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
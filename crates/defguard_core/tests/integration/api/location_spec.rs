@@ -0,0 +1,158 @@
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+use super::common::{authenticate_admin, make_network, make_test_client, setup_pool};
+
+fn make_spec() -> Value {
+    let mut spec = make_network();
+    spec["allowed_groups"] = json!(["admin"]);
+    spec["gateways"] = json!({
+        "policy": "round_robin",
+        "gateways": [
+            {"hostname": "gateway-1", "endpoint": "192.168.4.15"},
+            {"hostname": "gateway-2", "endpoint": "192.168.4.16"},
+        ]
+    });
+    spec["acl_rules"] = json!([{
+        "name": "admins to servers",
+        "allowed_groups": ["admin"],
+        "destination": "10.2.0.0/24",
+        "ports": "22, 443",
+    }]);
+    spec
+}
+
+fn condition<'a>(status: &'a Value, condition_type: &str) -> &'a Value {
+    status["conditions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|condition| condition["type"] == condition_type)
+        .unwrap()
+}
+
+#[sqlx::test]
+async fn test_apply_location_spec(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, _) = make_test_client(pool).await;
+
+    // admin only
+    let response = client
+        .put("/api/v1/network/apply")
+        .json(&make_spec())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    authenticate_admin(&mut client).await;
+
+    // dry run doesn't create anything
+    let response = client
+        .put("/api/v1/network/apply?dry_run=true")
+        .json(&make_spec())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let status: Value = response.json().await;
+    assert_eq!(status["location_id"], Value::Null);
+    assert_eq!(status["dry_run"], true);
+    let changes: Vec<(&str, &str, &str)> = status["changes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|change| {
+            (
+                change["kind"].as_str().unwrap(),
+                change["name"].as_str().unwrap(),
+                change["action"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        changes,
+        [
+            ("location", "network", "create"),
+            ("gateway", "gateway-1", "create"),
+            ("gateway", "gateway-2", "create"),
+            ("acl_rule", "admins to servers", "create"),
+        ]
+    );
+    assert_eq!(condition(&status, "Synced")["reason"], "ChangesPending");
+    let response = client.get("/api/v1/network").send().await;
+    let networks: Vec<Value> = response.json().await;
+    assert!(networks.is_empty());
+
+    // apply
+    let response = client
+        .put("/api/v1/network/apply")
+        .json(&make_spec())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let status: Value = response.json().await;
+    assert_eq!(status["location_id"], 1);
+    assert_eq!(status["changes"].as_array().unwrap().len(), 4);
+    assert_eq!(condition(&status, "Synced")["reason"], "Applied");
+    assert_eq!(condition(&status, "GatewaysConnected")["status"], "False");
+    assert_eq!(condition(&status, "Ready")["status"], "False");
+
+    let response = client.get("/api/v1/network/1").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let network: Value = response.json().await;
+    assert_eq!(network["name"], "network");
+    assert_eq!(network["allowed_groups"], json!(["admin"]));
+    assert_eq!(network["gateway_distribution_policy"], "round_robin");
+
+    let response = client.get("/api/v1/acl/rule").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let rules: Vec<Value> = response.json().await;
+    assert_eq!(rules.len(), 1);
+    assert_eq!(rules[0]["name"], "admins to servers");
+    assert_eq!(rules[0]["networks"], json!([1]));
+    assert_eq!(rules[0]["state"], "Applied");
+
+    // applying the same spec again is a no-op
+    let response = client
+        .put("/api/v1/network/apply")
+        .json(&make_spec())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let status: Value = response.json().await;
+    assert_eq!(status["changes"], json!([]));
+    assert_eq!(condition(&status, "Synced")["reason"], "UpToDate");
+
+    // modified spec
+    let mut spec = make_spec();
+    spec["port"] = json!(55556);
+    spec["dns"] = json!("8.8.8.8");
+    spec["gateways"]["gateways"] = json!([{"hostname": "gateway-1", "endpoint": "192.168.4.15"}]);
+    spec["acl_rules"] = json!([]);
+    let response = client.put("/api/v1/network/apply").json(&spec).send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let status: Value = response.json().await;
+    let changes = status["changes"].as_array().unwrap();
+    assert_eq!(changes.len(), 3);
+    assert_eq!(changes[0]["kind"], "location");
+    assert_eq!(changes[0]["action"], "update");
+    assert_eq!(changes[0]["fields"], json!(["port", "dns"]));
+    assert_eq!(changes[1]["kind"], "gateway");
+    assert_eq!(changes[1]["name"], "gateway-2");
+    assert_eq!(changes[1]["action"], "delete");
+    assert_eq!(changes[2]["kind"], "acl_rule");
+    assert_eq!(changes[2]["action"], "delete");
+
+    let response = client.get("/api/v1/network/1").send().await;
+    let network: Value = response.json().await;
+    assert_eq!(network["port"], 55556);
+    assert_eq!(network["dns"], "8.8.8.8");
+    let response = client.get("/api/v1/acl/rule").send().await;
+    let rules: Vec<Value> = response.json().await;
+    assert!(rules.is_empty());
+
+    // unknown group
+    let mut spec = make_spec();
+    spec["allowed_groups"] = json!(["admins"]);
+    let response = client.put("/api/v1/network/apply").json(&spec).send().await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,154 @@
+use defguard_core::handlers::Auth;
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+use super::common::{authenticate_admin, make_test_client, setup_pool};
+
+#[sqlx::test]
+async fn test_login_banner(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, _) = make_test_client(pool).await;
+
+    // no banner by default
+    let response = client.get("/api/v1/login_banner").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let banner: Value = response.json().await;
+    assert!(banner.is_null());
+
+    // only admins can set the banner
+    client.login_user("hpotter", "pass123").await;
+    let response = client
+        .put("/api/v1/login_banner")
+        .json(&json!({"content": "Authorized use only"}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    authenticate_admin(&mut client).await;
+    let response = client
+        .put("/api/v1/login_banner")
+        .json(&json!({"content": "  "}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = client
+        .put("/api/v1/login_banner")
+        .json(&json!({"content": "Authorized use only"}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let banner: Value = response.json().await;
+    let first_id = banner["id"].as_i64().unwrap();
+
+    // setting the same content keeps the version
+    let response = client
+        .put("/api/v1/login_banner")
+        .json(&json!({"content": "Authorized use only"}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let banner: Value = response.json().await;
+    assert_eq!(banner["id"], first_id);
+
+    // banner is available without logging in
+    let response = client.post("/api/v1/auth/logout").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client.get("/api/v1/login_banner").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let banner: Value = response.json().await;
+    assert_eq!(banner["id"], first_id);
+    assert_eq!(banner["content"], "Authorized use only");
+
+    // login requires acknowledgment of the current version
+    let response = client
+        .post("/api/v1/auth")
+        .json(&Auth::new("hpotter", "pass123"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = client
+        .post("/api/v1/auth")
+        .json(&Auth::new("hpotter", "pass123").with_banner(first_id + 1))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = client
+        .post("/api/v1/auth")
+        .json(&Auth::new("hpotter", "pass123").with_banner(first_id))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // acknowledged version doesn't have to be acknowledged again
+    let response = client.post("/api/v1/auth/logout").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .post("/api/v1/auth")
+        .json(&Auth::new("hpotter", "pass123"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // new content requires a new acknowledgment
+    let response = client
+        .post("/api/v1/auth")
+        .json(&Auth::new("admin", "pass123").with_banner(first_id))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .put("/api/v1/login_banner")
+        .json(&json!({"content": "Authorized use only. Activity is monitored."}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let banner: Value = response.json().await;
+    let second_id = banner["id"].as_i64().unwrap();
+    assert_ne!(second_id, first_id);
+    let response = client
+        .post("/api/v1/auth")
+        .json(&Auth::new("hpotter", "pass123"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // acknowledgments are listed per version
+    let response = client
+        .get(format!("/api/v1/login_banner/{first_id}/acknowledgment"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let acknowledgments: Vec<Value> = response.json().await;
+    let mut usernames: Vec<&str> = acknowledgments
+        .iter()
+        .map(|acknowledgment| acknowledgment["username"].as_str().unwrap())
+        .collect();
+    usernames.sort_unstable();
+    assert_eq!(usernames, ["admin", "hpotter"]);
+    let response = client
+        .get(format!("/api/v1/login_banner/{second_id}/acknowledgment"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let acknowledgments: Vec<Value> = response.json().await;
+    assert!(acknowledgments.is_empty());
+    let response = client
+        .get("/api/v1/login_banner/12345/acknowledgment")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // without a banner users log in as usual
+    let response = client.delete("/api/v1/login_banner").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client.get("/api/v1/login_banner").send().await;
+    let banner: Value = response.json().await;
+    assert!(banner.is_null());
+    let response = client
+        .post("/api/v1/auth")
+        .json(&Auth::new("hpotter", "pass123"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
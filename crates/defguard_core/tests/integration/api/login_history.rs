@@ -0,0 +1,138 @@
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use defguard_common::db::{Id, NoId};
+use defguard_core::db::models::login_history::{LoginRecord, LoginSource};
+use reqwest::{StatusCode, header::CONTENT_TYPE};
+use serde_json::Value;
+use sqlx::{
+    PgPool,
+    postgres::{PgConnectOptions, PgPoolOptions},
+};
+
+use super::common::{authenticate_admin, get_db_user, make_test_client, setup_pool};
+
+async fn save_record(
+    pool: &PgPool,
+    user_id: Id,
+    timestamp: NaiveDateTime,
+    source: LoginSource,
+    success: bool,
+) {
+    let client_mfa = source == LoginSource::ClientMfa;
+    LoginRecord {
+        id: NoId,
+        user_id,
+        timestamp,
+        source,
+        method: if client_mfa { "TOTP" } else { "Password" }.into(),
+        success,
+        ip: "10.0.0.1".parse().unwrap(),
+        device: if client_mfa {
+            "laptop"
+        } else {
+            "Mozilla/5.0 (X11; Linux x86_64)"
+        }
+        .into(),
+        location: client_mfa.then(|| "office".into()),
+        proxy: client_mfa.then(|| "http://proxy.example.com:50051".into()),
+        message: (!success).then(|| "Invalid code".into()),
+    }
+    .save(pool)
+    .await
+    .unwrap();
+}
+
+#[sqlx::test]
+async fn test_login_history(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, client_state) = make_test_client(pool).await;
+    let pool = client_state.pool;
+
+    let user = get_db_user(&pool, "hpotter").await;
+    let now = Utc::now().naive_utc();
+    for minutes in 0..60 {
+        save_record(
+            &pool,
+            user.id,
+            now - TimeDelta::minutes(minutes),
+            LoginSource::Web,
+            minutes % 2 == 0,
+        )
+        .await;
+    }
+    save_record(
+        &pool,
+        user.id,
+        now + TimeDelta::minutes(1),
+        LoginSource::ClientMfa,
+        false,
+    )
+    .await;
+
+    // users can view only their own history
+    let response = client
+        .get("/api/v1/user/hpotter/login_history")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    client.login_user("hpotter", "pass123").await;
+    let response = client.get("/api/v1/user/admin/login_history").send().await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = client
+        .get("/api/v1/user/admin/login_history/export")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // the most recent first
+    let response = client
+        .get("/api/v1/user/hpotter/login_history")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let page: Value = response.json().await;
+    assert_eq!(page["pagination"]["total_items"], 61);
+    assert_eq!(page["pagination"]["next_page"], 2);
+    let records = page["data"].as_array().unwrap();
+    assert_eq!(records.len(), 50);
+    assert_eq!(records[0]["source"], "client_mfa");
+    assert_eq!(records[0]["success"], false);
+    assert_eq!(records[0]["location"], "office");
+    assert_eq!(records[0]["proxy"], "http://proxy.example.com:50051");
+    assert_eq!(records[0]["message"], "Invalid code");
+    assert_eq!(records[1]["source"], "web");
+    assert_eq!(records[1]["success"], true);
+
+    authenticate_admin(&mut client).await;
+    let response = client
+        .get("/api/v1/user/hpotter/login_history?page=2")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let page: Value = response.json().await;
+    assert_eq!(page["data"].as_array().unwrap().len(), 11);
+    assert!(page["pagination"]["next_page"].is_null());
+    let response = client
+        .get("/api/v1/user/unknown/login_history")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // export
+    let response = client
+        .get("/api/v1/user/hpotter/login_history/export?columns=source,method,success,location")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[CONTENT_TYPE], "text/csv");
+    let csv = response.text().await;
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 62);
+    assert_eq!(lines[0], "source,method,success,location");
+    assert_eq!(lines[1], "client_mfa,TOTP,false,office");
+    assert_eq!(lines[2], "web,Password,true,");
+    let response = client
+        .get("/api/v1/user/hpotter/login_history/export?columns=password")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
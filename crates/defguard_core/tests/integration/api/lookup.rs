@@ -0,0 +1,268 @@
+use chrono::{Duration, SecondsFormat, Utc};
+use defguard_common::db::NoId;
+use defguard_core::{db::models::wireguard_peer_stats::WireguardPeerStats, handlers::Auth};
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+use super::common::{authenticate_admin, make_network, make_test_client, setup_pool};
+
+#[sqlx::test]
+async fn test_lookup_ip(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, client_state) = make_test_client(pool).await;
+    authenticate_admin(&mut client).await;
+
+    let response = client
+        .post("/api/v1/network")
+        .json(&make_network())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    for (username, name, pubkey) in [
+        (
+            "admin",
+            "laptop",
+            "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=",
+        ),
+        (
+            "hpotter",
+            "phone",
+            "sIhx53MsX+iLk83sssybHrD7M+5m+CmpLzWL/zo8C38=",
+        ),
+    ] {
+        let response = client
+            .post(format!("/api/v1/device/{username}"))
+            .json(&json!({"name": name, "wireguard_pubkey": pubkey}))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    // the first device connected an hour ago, the second one is connected now
+    let now = Utc::now().naive_utc();
+    for (device_id, latest_handshake) in [
+        (1, now - Duration::hours(1)),
+        (2, now - Duration::minutes(1)),
+    ] {
+        WireguardPeerStats {
+            id: NoId,
+            device_id,
+            collected_at: now,
+            network: 1,
+            endpoint: Some("11.22.33.44:51820".into()),
+            upload: 100,
+            download: 200,
+            latest_handshake,
+            allowed_ips: None,
+            gateway: Some("gateway-1".into()),
+        }
+        .save(&client_state.pool)
+        .await
+        .unwrap();
+    }
+
+    let response = client.get("/api/v1/lookup/ip/10.1.1.2").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let results: Value = response.json().await;
+    assert_eq!(results.as_array().unwrap().len(), 1);
+    assert_eq!(results[0]["device"]["name"], "laptop");
+    assert_eq!(results[0]["user"]["username"], "admin");
+    assert_eq!(results[0]["location"], json!({"id": 1, "name": "network"}));
+    assert_eq!(results[0]["session"], Value::Null);
+
+    let response = client.get("/api/v1/lookup/ip/10.1.1.3").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let results: Value = response.json().await;
+    assert_eq!(results[0]["device"]["name"], "phone");
+    assert_eq!(
+        results[0]["device"]["wireguard_pubkey"],
+        "sIhx53MsX+iLk83sssybHrD7M+5m+CmpLzWL/zo8C38="
+    );
+    assert_eq!(results[0]["user"]["username"], "hpotter");
+    assert_eq!(results[0]["session"]["gateway"], "gateway-1");
+    assert_eq!(results[0]["session"]["endpoint"], "11.22.33.44:51820");
+    assert_eq!(results[0]["session"]["upload"], 100);
+
+    // unused and invalid addresses
+    let response = client.get("/api/v1/lookup/ip/10.1.1.200").send().await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = client.get("/api/v1/lookup/ip/10.1.1").send().await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // only admins can look up devices
+    let auth = Auth::new("hpotter", "pass123");
+    let response = client.post("/api/v1/auth").json(&auth).send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client.get("/api/v1/lookup/ip/10.1.1.3").send().await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[sqlx::test]
+async fn test_lookup_endpoint(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, client_state) = make_test_client(pool).await;
+    authenticate_admin(&mut client).await;
+
+    let response = client
+        .post("/api/v1/network")
+        .json(&make_network())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    for (username, name, pubkey) in [
+        (
+            "admin",
+            "laptop",
+            "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=",
+        ),
+        (
+            "hpotter",
+            "phone",
+            "sIhx53MsX+iLk83sssybHrD7M+5m+CmpLzWL/zo8C38=",
+        ),
+    ] {
+        let response = client
+            .post(format!("/api/v1/device/{username}"))
+            .json(&json!({"name": name, "wireguard_pubkey": pubkey}))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    // the laptop connected from the address two days ago and ten minutes ago,
+    // the phone connected from a similar address, then from an IPv6 address
+    let now = Utc::now().naive_utc();
+    for (device_id, collected_at, endpoint, gateway) in [
+        (1, now - Duration::days(2), "11.22.33.44:51820", "gateway-1"),
+        (
+            1,
+            now - Duration::minutes(10),
+            "11.22.33.44:40000",
+            "gateway-2",
+        ),
+        (
+            2,
+            now - Duration::minutes(5),
+            "11.22.33.4:51820",
+            "gateway-1",
+        ),
+        (
+            2,
+            now - Duration::minutes(1),
+            "[2001:db8::1]:51820",
+            "gateway-1",
+        ),
+    ] {
+        WireguardPeerStats {
+            id: NoId,
+            device_id,
+            collected_at,
+            network: 1,
+            endpoint: Some(endpoint.into()),
+            upload: 100,
+            download: 200,
+            latest_handshake: collected_at - Duration::seconds(30),
+            allowed_ips: None,
+            gateway: Some(gateway.into()),
+        }
+        .save(&client_state.pool)
+        .await
+        .unwrap();
+    }
+    // stale stats of a disconnected peer don't count
+    WireguardPeerStats {
+        id: NoId,
+        device_id: 2,
+        collected_at: now - Duration::minutes(2),
+        network: 1,
+        endpoint: Some("11.22.33.44:51820".into()),
+        upload: 100,
+        download: 200,
+        latest_handshake: now - Duration::hours(1),
+        allowed_ips: None,
+        gateway: Some("gateway-1".into()),
+    }
+    .save(&client_state.pool)
+    .await
+    .unwrap();
+
+    // last hour by default
+    let response = client
+        .get("/api/v1/lookup/endpoint/11.22.33.44")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let results: Value = response.json().await;
+    assert_eq!(results.as_array().unwrap().len(), 1);
+    assert_eq!(results[0]["device"]["name"], "laptop");
+    assert_eq!(results[0]["user"]["username"], "admin");
+    assert_eq!(results[0]["location"], json!({"id": 1, "name": "network"}));
+    assert_eq!(results[0]["endpoints"], json!(["11.22.33.44:40000"]));
+    assert_eq!(results[0]["gateways"], json!(["gateway-2"]));
+
+    // custom time range
+    let from = (Utc::now() - Duration::days(3)).to_rfc3339_opts(SecondsFormat::Secs, true);
+    let response = client
+        .get(format!("/api/v1/lookup/endpoint/11.22.33.44?from={from}"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let results: Value = response.json().await;
+    assert_eq!(results.as_array().unwrap().len(), 1);
+    assert_eq!(
+        results[0]["endpoints"],
+        json!(["11.22.33.44:40000", "11.22.33.44:51820"])
+    );
+    assert_eq!(results[0]["gateways"], json!(["gateway-1", "gateway-2"]));
+    let until = (Utc::now() - Duration::days(1)).to_rfc3339_opts(SecondsFormat::Secs, true);
+    let response = client
+        .get(format!(
+            "/api/v1/lookup/endpoint/11.22.33.44?from={from}&until={until}"
+        ))
+        .send()
+        .await;
+    let results: Value = response.json().await;
+    assert_eq!(results[0]["endpoints"], json!(["11.22.33.44:51820"]));
+
+    // IPv6 and a prefix of another address
+    let response = client
+        .get("/api/v1/lookup/endpoint/2001:db8::1")
+        .send()
+        .await;
+    let results: Value = response.json().await;
+    assert_eq!(results.as_array().unwrap().len(), 1);
+    assert_eq!(results[0]["device"]["name"], "phone");
+    let response = client
+        .get("/api/v1/lookup/endpoint/11.22.33.4")
+        .send()
+        .await;
+    let results: Value = response.json().await;
+    assert_eq!(results.as_array().unwrap().len(), 1);
+    assert_eq!(results[0]["endpoints"], json!(["11.22.33.4:51820"]));
+
+    // no matches, invalid address and time range
+    let response = client.get("/api/v1/lookup/endpoint/1.2.3.4").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let results: Value = response.json().await;
+    assert_eq!(results, json!([]));
+    let response = client.get("/api/v1/lookup/endpoint/1.2.3").send().await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = client
+        .get(format!(
+            "/api/v1/lookup/endpoint/11.22.33.44?from={until}&until={from}"
+        ))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // only admins can look up devices
+    let auth = Auth::new("hpotter", "pass123");
+    let response = client.post("/api/v1/auth").json(&auth).send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .get("/api/v1/lookup/endpoint/11.22.33.44")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions},
+    query,
+};
+use tokio::time::timeout;
+
+use super::common::{authenticate_admin, make_test_client, setup_pool};
+
+#[sqlx::test]
+async fn test_mail_context(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, client_state) = make_test_client(pool.clone()).await;
+    let mut mail_rx = client_state.mail_rx;
+
+    // only admins can manage translations
+    client.login_user("hpotter", "pass123").await;
+    let response = client.get("/api/v1/settings/mail_context").send().await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    authenticate_admin(&mut client).await;
+    let response = client
+        .get("/api/v1/settings/mail_context/template")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let templates: Vec<Value> = response.json().await;
+    assert!(
+        templates
+            .iter()
+            .any(|template| template["template"] == "mail_password_reset_start")
+    );
+
+    // sections and language tags are validated
+    for (section, language_tag, text) in [
+        ("unknown", "pl", "Resetowanie hasła"),
+        ("title", "Polish", "Resetowanie hasła"),
+        ("title", "pl", "{% if %}"),
+    ] {
+        let response = client
+            .put("/api/v1/settings/mail_context")
+            .json(&json!({
+                "template": "mail_password_reset_start",
+                "section": section,
+                "language_tag": language_tag,
+                "text": text,
+            }))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    for (section, text) in [
+        ("subject", "Defguard: Resetowanie hasła"),
+        ("title", "Resetowanie hasła"),
+    ] {
+        let response = client
+            .put("/api/v1/settings/mail_context")
+            .json(&json!({
+                "template": "mail_password_reset_start",
+                "section": section,
+                "language_tag": "pl",
+                "text": text,
+            }))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    // setting a translation again replaces it
+    let response = client
+        .put("/api/v1/settings/mail_context")
+        .json(&json!({
+            "template": "mail_password_reset_start",
+            "section": "title",
+            "language_tag": "pl",
+            "text": "Reset hasła",
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let title: Value = response.json().await;
+    let response = client.get("/api/v1/settings/mail_context").send().await;
+    let translations: Vec<Value> = response.json().await;
+    assert_eq!(translations.len(), 2);
+
+    // mails are sent in the user's language
+    query("UPDATE \"user\" SET mail_language = 'pl' WHERE username = 'hpotter'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    while mail_rx.try_recv().is_ok() {}
+    let response = client
+        .post("/api/v1/user/hpotter/reset_password")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let mail = timeout(Duration::from_secs(5), mail_rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(mail.subject, "Defguard: Resetowanie hasła");
+    assert!(mail.content.contains("Reset hasła"));
+    // untranslated sections use built-in texts
+    assert!(mail.content.contains("Or click the button below:"));
+
+    // falling back to the instance default
+    query("UPDATE \"user\" SET mail_language = 'de' WHERE username = 'hpotter'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let response = client
+        .patch("/api/v1/settings")
+        .json(&json!({"default_mail_language": "Polish"}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = client
+        .patch("/api/v1/settings")
+        .json(&json!({"default_mail_language": "pl"}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .post("/api/v1/user/hpotter/reset_password")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let mail = timeout(Duration::from_secs(5), mail_rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(mail.subject, "Defguard: Resetowanie hasła");
+
+    // removed translations fall back to English
+    let response = client
+        .delete(format!("/api/v1/settings/mail_context/{}", title["id"]))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .delete(format!("/api/v1/settings/mail_context/{}", title["id"]))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = client
+        .post("/api/v1/user/hpotter/reset_password")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let mail = timeout(Duration::from_secs(5), mail_rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(mail.content.contains("<b>Password reset</b>"));
+}
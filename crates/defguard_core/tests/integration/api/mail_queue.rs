@@ -0,0 +1,78 @@
+use chrono::Utc;
+use defguard_common::db::models::{MailCategory, mail_queue::QueuedMail};
+use reqwest::StatusCode;
+use serde_json::Value;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+use super::common::{authenticate_admin, make_test_client, setup_pool};
+
+#[sqlx::test]
+async fn test_mail_queue(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, _) = make_test_client(pool.clone()).await;
+
+    // only admins can manage the queue
+    client.login_user("hpotter", "pass123").await;
+    let response = client.get("/api/v1/mail_queue").send().await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = client.delete("/api/v1/mail_queue").send().await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    authenticate_admin(&mut client).await;
+    let response = client.get("/api/v1/mail_queue").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let mails: Vec<Value> = response.json().await;
+    assert!(mails.is_empty());
+
+    let mut ids = Vec::new();
+    for (recipient, category) in [
+        ("hpotter@hogwart.edu.uk", MailCategory::Enrollment),
+        ("admin@defguard", MailCategory::Alert),
+    ] {
+        let mail = QueuedMail::new(
+            recipient.into(),
+            "Subject".into(),
+            "<p>Secret content</p>".into(),
+            category,
+            "Connection refused".into(),
+            Utc::now().naive_utc(),
+        )
+        .save(&pool)
+        .await
+        .unwrap();
+        ids.push(mail.id);
+    }
+
+    // the most recent mails first, without content
+    let response = client.get("/api/v1/mail_queue").send().await;
+    let mails: Vec<Value> = response.json().await;
+    assert_eq!(mails.len(), 2);
+    assert_eq!(mails[0]["id"], ids[1]);
+    assert_eq!(mails[0]["recipient"], "admin@defguard");
+    assert_eq!(mails[0]["category"], "alert");
+    assert_eq!(mails[0]["attempts"], 1);
+    assert_eq!(mails[0]["last_error"], "Connection refused");
+    assert!(mails[0]["next_attempt_at"].is_string());
+    assert!(mails[0].get("content").is_none());
+
+    let response = client
+        .delete(format!("/api/v1/mail_queue/{}", ids[0]))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .delete(format!("/api/v1/mail_queue/{}", ids[0]))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = client.get("/api/v1/mail_queue").send().await;
+    let mails: Vec<Value> = response.json().await;
+    assert_eq!(mails.len(), 1);
+    assert_eq!(mails[0]["id"], ids[1]);
+
+    let response = client.delete("/api/v1/mail_queue").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client.get("/api/v1/mail_queue").send().await;
+    let mails: Vec<Value> = response.json().await;
+    assert!(mails.is_empty());
+}
@@ -0,0 +1,159 @@
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use tokio::time::timeout;
+
+use super::common::{authenticate_admin, make_test_client, setup_pool};
+
+#[sqlx::test]
+async fn test_mail_template_override(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, client_state) = make_test_client(pool).await;
+    let mut mail_rx = client_state.mail_rx;
+
+    // only admins can manage templates
+    client.login_user("hpotter", "pass123").await;
+    let response = client.get("/api/v1/settings/mail_templates").send().await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    authenticate_admin(&mut client).await;
+    let response = client.get("/api/v1/settings/mail_templates").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let templates: Vec<Value> = response.json().await;
+    assert!(
+        templates
+            .iter()
+            .all(|template| template["overridden"] == false)
+    );
+
+    let response = client
+        .get("/api/v1/settings/mail_templates/mail_unknown")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = client
+        .put("/api/v1/settings/mail_templates/mail_unknown")
+        .json(&json!({"content": "Hello"}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // overrides have to render
+    let url = "/api/v1/settings/mail_templates/mail_password_reset_start";
+    for content in ["{% if %}", "Reset your password: {{ unknown_url }}"] {
+        let response = client
+            .put(url)
+            .json(&json!({"content": content}))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+    let response = client
+        .put(url)
+        .json(&json!({"content": "Reset your password: {{ link_url }}"}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let template: Value = response.json().await;
+    assert_eq!(template["overridden"], true);
+    assert_eq!(template["content"], "Reset your password: {{ link_url }}");
+    let response = client.get(url).send().await;
+    let template: Value = response.json().await;
+    assert_eq!(template["overridden"], true);
+
+    // mails are rendered from the override
+    while mail_rx.try_recv().is_ok() {}
+    let response = client
+        .post("/api/v1/user/hpotter/reset_password")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let mail = timeout(Duration::from_secs(5), mail_rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(mail.content.starts_with("Reset your password: http"));
+
+    // restore
+    let response = client.delete(url).send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client.delete(url).send().await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = client
+        .post("/api/v1/user/hpotter/reset_password")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let mail = timeout(Duration::from_secs(5), mail_rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(mail.content.contains("Or click the button below:"));
+}
+
+#[sqlx::test]
+async fn test_mail_preview(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, client_state) = make_test_client(pool).await;
+    let mut mail_rx = client_state.mail_rx;
+
+    client.login_user("hpotter", "pass123").await;
+    let response = client
+        .post("/api/v1/mail/preview")
+        .json(&json!({"template": "mail_test"}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    authenticate_admin(&mut client).await;
+    while mail_rx.try_recv().is_ok() {}
+    let response = client
+        .post("/api/v1/mail/preview")
+        .json(&json!({"template": "mail_unknown"}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // sample data is used without context
+    let response = client
+        .post("/api/v1/mail/preview")
+        .json(&json!({"template": "mail_password_reset_start"}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let preview: Value = response.json().await;
+    assert_eq!(preview["subject"], "Defguard: Password reset");
+    assert!(preview["content"].as_str().unwrap().contains("TOKEN"));
+
+    // supplied context has to be complete
+    let response = client
+        .post("/api/v1/mail/preview")
+        .json(&json!({
+            "template": "mail_gateway_disconnected",
+            "context": {"gateway_name": "Gateway A"},
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = client
+        .post("/api/v1/mail/preview")
+        .json(&json!({
+            "template": "mail_gateway_disconnected",
+            "context": {
+                "gateway_name": "Gateway A",
+                "gateway_ip": "10.0.0.2",
+                "network_name": "Office",
+            },
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let preview: Value = response.json().await;
+    assert_eq!(preview["subject"], Value::Null);
+    assert!(preview["content"].as_str().unwrap().contains("Gateway A"));
+
+    // previews aren't sent
+    assert!(mail_rx.try_recv().is_err());
+}
@@ -0,0 +1,97 @@
+use defguard_common::db::models::Settings;
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+use super::common::{authenticate_admin, make_test_client, setup_pool};
+
+#[sqlx::test]
+async fn test_mail_variables(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, _) = make_test_client(pool).await;
+    authenticate_admin(&mut client).await;
+
+    // default variables
+    let response = client.get("/api/v1/settings/mail_variable").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let variables: Vec<Value> = response.json().await;
+    let mut names: Vec<_> = variables
+        .iter()
+        .map(|variable| variable["name"].as_str().unwrap())
+        .collect();
+    names.sort_unstable();
+    assert_eq!(names, ["company_name", "helpdesk_phone", "support_url"]);
+    let support_url_id = variables
+        .iter()
+        .find(|variable| variable["name"] == "support_url")
+        .unwrap()["id"]
+        .as_i64()
+        .unwrap();
+
+    // invalid and duplicate names
+    for name in ["Support URL", "", "support_url"] {
+        let response = client
+            .post("/api/v1/settings/mail_variable")
+            .json(&json!({"name": name, "value": "value"}))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{name}");
+    }
+
+    let response = client
+        .put(format!("/api/v1/settings/mail_variable/{support_url_id}"))
+        .json(&json!({"name": "support_url", "value": "https://help.example.com"}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let modified: Value = response.json().await;
+    assert_eq!(modified["value"], "https://help.example.com");
+
+    // templates can only reference existing variables
+    let response = client.get("/api/v1/settings").send().await;
+    let mut settings: Settings = response.json().await;
+    settings.enrollment_welcome_message = Some("Contact {{ instance.unknown }}".into());
+    let response = client.put("/api/v1/settings").json(&settings).send().await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    settings.enrollment_welcome_message = Some("Contact {{ instance.support_url }}".into());
+    let response = client.put("/api/v1/settings").json(&settings).send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .patch("/api/v1/settings")
+        .json(&json!({"enrollment_welcome_email": "{% if instance.unknown %}{% endif %}"}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // variables used in templates can't be renamed or removed
+    let response = client
+        .put(format!("/api/v1/settings/mail_variable/{support_url_id}"))
+        .json(&json!({"name": "support_link", "value": "https://help.example.com"}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = client
+        .delete(format!("/api/v1/settings/mail_variable/{support_url_id}"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = client
+        .post("/api/v1/settings/mail_variable")
+        .json(&json!({"name": "office_hours", "value": "9-17"}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: Value = response.json().await;
+    let response = client
+        .delete(format!(
+            "/api/v1/settings/mail_variable/{}",
+            created["id"].as_i64().unwrap()
+        ))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client.get("/api/v1/settings/mail_variable").send().await;
+    let variables: Vec<Value> = response.json().await;
+    assert_eq!(variables.len(), 3);
+}
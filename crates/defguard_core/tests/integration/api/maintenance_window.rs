@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use chrono::{TimeDelta, Utc};
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use tokio::time::timeout;
+
+use super::common::{authenticate_admin, make_network, make_test_client, setup_pool};
+
+#[sqlx::test]
+async fn test_maintenance_window(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, client_state) = make_test_client(pool).await;
+    let mut mail_rx = client_state.mail_rx;
+
+    // only admins can manage maintenance windows
+    client.login_user("hpotter", "pass123").await;
+    let response = client.get("/api/v1/network/1/maintenance").send().await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    authenticate_admin(&mut client).await;
+    let response = client.get("/api/v1/network/1/maintenance").send().await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = client
+        .post("/api/v1/network")
+        .json(&make_network())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let starts_at = Utc::now().naive_utc() + TimeDelta::days(1);
+    let ends_at = starts_at + TimeDelta::hours(2);
+    for (starts_at, ends_at) in [
+        (ends_at, starts_at),
+        (starts_at - TimeDelta::days(3), ends_at - TimeDelta::days(3)),
+    ] {
+        let response = client
+            .post("/api/v1/network/1/maintenance")
+            .json(&json!({"starts_at": starts_at, "ends_at": ends_at}))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    // admins are invited
+    while mail_rx.try_recv().is_ok() {}
+    let response = client
+        .post("/api/v1/network/1/maintenance")
+        .json(&json!({
+            "starts_at": starts_at,
+            "ends_at": ends_at,
+            "description": "Gateway upgrade",
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let window: Value = response.json().await;
+    let id = window["id"].as_i64().unwrap();
+    assert_eq!(window["description"], "Gateway upgrade");
+
+    let mail = timeout(Duration::from_secs(5), mail_rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(mail.to, "admin@defguard");
+    assert!(mail.content.contains("Gateway upgrade"));
+    assert_eq!(mail.attachments.len(), 1);
+    let invite = String::from_utf8(mail.attachments[0].content.clone()).unwrap();
+    assert!(invite.contains("METHOD:REQUEST"));
+    assert!(invite.contains(&format!("UID:maintenance-window-{id}@")));
+    assert!(invite.contains("ATTENDEE;ROLE=REQ-PARTICIPANT;RSVP=FALSE:mailto:admin@defguard"));
+
+    let response = client.get("/api/v1/network/1/maintenance").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let windows: Vec<Value> = response.json().await;
+    assert_eq!(windows.len(), 1);
+    assert_eq!(windows[0]["id"], id);
+
+    // cancellation supersedes the invite
+    while mail_rx.try_recv().is_ok() {}
+    let response = client
+        .delete(format!("/api/v1/network/2/maintenance/{id}"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = client
+        .delete(format!("/api/v1/network/1/maintenance/{id}"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let mail = timeout(Duration::from_secs(5), mail_rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(mail.content.contains("has been cancelled"));
+    let invite = String::from_utf8(mail.attachments[0].content.clone()).unwrap();
+    assert!(invite.contains("METHOD:CANCEL"));
+    assert!(invite.contains("SEQUENCE:1"));
+
+    let response = client.get("/api/v1/network/1/maintenance").send().await;
+    let windows: Vec<Value> = response.json().await;
+    assert!(windows.is_empty());
+}
@@ -1,22 +1,57 @@
 mod acl;
+mod activity_log;
+mod announcement;
 mod api_tokens;
+mod api_version;
+mod auditor;
 mod auth;
 mod common;
+mod dashboard;
+mod device_approval;
+mod dns_leak;
 mod enrollment;
+mod enrollment_token;
 mod enterprise_settings;
+mod firewall_history;
 mod forward_auth;
+mod gateway_setup;
+mod graphql;
 mod group;
+mod helpdesk_password_reset;
+mod inventory;
+mod ip_allowlist;
+mod itsm;
+mod jobs;
+mod location_spec;
+mod login_banner;
+mod login_history;
+mod lookup;
+mod mail_context;
+mod mail_queue;
+mod mail_template;
+mod mail_variable;
+mod maintenance_window;
+mod notification;
 mod oauth;
 mod openid;
 mod openid_login;
+mod quarantine;
+mod route;
+mod self_registration;
+mod service_account;
 mod settings;
+mod smtp_profile;
 mod snat;
+mod support;
+mod troubleshoot;
 mod user;
 mod webhook;
 mod wireguard;
 mod wireguard_network_allowed_groups;
 mod wireguard_network_devices;
+mod wireguard_network_geofence;
 mod wireguard_network_import;
+mod wireguard_network_keepalive;
 mod wireguard_network_stats;
 mod worker;
 
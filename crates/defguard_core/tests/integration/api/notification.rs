@@ -0,0 +1,143 @@
+use defguard_core::{
+    db::models::notification::NotificationCategory,
+    notifications::{AdminNotification, notify_admins},
+};
+use defguard_mail::Mail;
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use tokio::sync::mpsc::unbounded_channel;
+
+use super::common::{authenticate_admin, make_test_client, setup_pool};
+
+fn alert(category: NotificationCategory) -> AdminNotification {
+    AdminNotification {
+        category,
+        subject: "Gateway disconnected".into(),
+        content: "<p>Gateway disconnected</p>".into(),
+        message: "Gateway gw1 in location Office disconnected".into(),
+    }
+}
+
+#[sqlx::test]
+async fn test_notification_rules(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, client_state) = make_test_client(pool).await;
+    let pool = client_state.pool;
+    let (mail_tx, mut mail_rx) = unbounded_channel::<Mail>();
+
+    // only admins can manage notifications
+    let response = client.get("/api/v1/notification/rules").send().await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    authenticate_admin(&mut client).await;
+
+    // alerts are sent by email without any rules
+    let response = client.get("/api/v1/notification/rules").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let rules: Vec<Value> = response.json().await;
+    assert!(rules.is_empty());
+    notify_admins(
+        &pool,
+        &mail_tx,
+        &alert(NotificationCategory::GatewayDisconnected),
+    )
+    .await
+    .unwrap();
+    let mail = mail_rx.try_recv().unwrap();
+    assert_eq!(mail.to, "admin@defguard");
+
+    // invalid rules
+    for rules in [
+        json!([{"category": "gateway_disconnected", "channel": "webhook"}]),
+        json!([{"category": "gateway_disconnected", "channel": "webhook", "webhook_url": "ftp://hooks.example.com"}]),
+        json!([{"category": "gateway_disconnected", "channel": "email", "webhook_url": "https://hooks.example.com"}]),
+        json!([
+            {"category": "gateway_disconnected", "channel": "in_app"},
+            {"category": "gateway_disconnected", "channel": "in_app"}
+        ]),
+    ] {
+        let response = client
+            .put("/api/v1/notification/rules")
+            .json(&rules)
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{rules}");
+    }
+
+    let response = client
+        .put("/api/v1/notification/rules")
+        .json(&json!([
+            {"category": "gateway_disconnected", "channel": "in_app"},
+            {"category": "device_approval", "channel": "email"}
+        ]))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let rules: Vec<Value> = response.json().await;
+    assert_eq!(rules.len(), 2);
+
+    // categories without rules are muted
+    notify_admins(
+        &pool,
+        &mail_tx,
+        &alert(NotificationCategory::GatewayReconnected),
+    )
+    .await
+    .unwrap();
+    assert!(mail_rx.try_recv().is_err());
+
+    // in-app notifications
+    notify_admins(
+        &pool,
+        &mail_tx,
+        &alert(NotificationCategory::GatewayDisconnected),
+    )
+    .await
+    .unwrap();
+    assert!(mail_rx.try_recv().is_err());
+    let response = client.get("/api/v1/notification?unread=true").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let notifications: Vec<Value> = response.json().await;
+    assert_eq!(notifications.len(), 1);
+    assert_eq!(notifications[0]["category"], "gateway_disconnected");
+    assert_eq!(
+        notifications[0]["message"],
+        "Gateway gw1 in location Office disconnected"
+    );
+    assert!(notifications[0]["read_at"].is_null());
+    let id = notifications[0]["id"].as_i64().unwrap();
+
+    let response = client
+        .post(format!("/api/v1/notification/{id}/read"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .post(format!("/api/v1/notification/{}/read", id + 1))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = client.get("/api/v1/notification?unread=true").send().await;
+    let notifications: Vec<Value> = response.json().await;
+    assert!(notifications.is_empty());
+    let response = client.get("/api/v1/notification").send().await;
+    let notifications: Vec<Value> = response.json().await;
+    assert_eq!(notifications.len(), 1);
+    assert!(!notifications[0]["read_at"].is_null());
+
+    // replacing rules with an empty list restores email delivery
+    let response = client
+        .put("/api/v1/notification/rules")
+        .json(&json!([]))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    notify_admins(
+        &pool,
+        &mail_tx,
+        &alert(NotificationCategory::GatewayReconnected),
+    )
+    .await
+    .unwrap();
+    assert!(mail_rx.try_recv().is_ok());
+}
@@ -33,6 +33,7 @@ async fn test_authorize(_: PgPoolOptions, options: PgConnectOptions) {
         redirect_uri: vec!["http://test.server.tnt:12345/".into()],
         scope: vec!["openid".into()],
         enabled: true,
+        ..Default::default()
     };
     let response = client
         .post("/api/v1/oauth")
@@ -176,6 +177,7 @@ async fn test_openid_app_management_access(_: PgPoolOptions, options: PgConnectO
         redirect_uri: vec!["http://test.server.tnt:12345/".into()],
         scope: vec!["openid".into()],
         enabled: true,
+        ..Default::default()
     };
     let response = client
         .post("/api/v1/oauth")
@@ -207,6 +209,7 @@ async fn test_openid_app_management_access(_: PgPoolOptions, options: PgConnectO
         redirect_uri: vec!["http://test.server.tnt:12345/".into()],
         scope: vec!["openid email".into()],
         enabled: true,
+        ..Default::default()
     };
     let response = client
         .put(format!("/api/v1/oauth/{}", test_app.client_id))
@@ -255,6 +258,7 @@ async fn test_openid_app_management_access(_: PgPoolOptions, options: PgConnectO
         redirect_uri: vec!["http://test.server.tnt:12345/".into()],
         scope: vec!["openid phone".into()],
         enabled: true,
+        ..Default::default()
     };
     let response = client
         .post("/api/v1/oauth")
@@ -290,6 +294,7 @@ async fn test_openid_app_management_access(_: PgPoolOptions, options: PgConnectO
         redirect_uri: vec!["http://test.com/redirect".into()],
         scope: vec!["openid profile".into()],
         enabled: true,
+        ..Default::default()
     };
     let response = client
         .post("/api/v1/oauth")
@@ -1,17 +1,17 @@
-use std::str::FromStr;
+use std::{collections::HashMap, str::FromStr};
 
 use axum::http::header::ToStrError;
 use claims::assert_err;
 use defguard_common::db::Id;
 use defguard_core::{
     db::{
-        User,
+        Group, User,
         models::{NewOpenIDClient, oauth2client::OAuth2Client},
     },
     handlers::Auth,
 };
 use openidconnect::{
-    AuthenticationFlow, AuthorizationCode, ClientId, ClientSecret, CsrfToken,
+    AdditionalClaims, AuthenticationFlow, AuthorizationCode, ClientId, ClientSecret, CsrfToken,
     EmptyAdditionalClaims, HttpRequest, HttpResponse, IssuerUrl, Nonce, OAuth2TokenResponse,
     PkceCodeChallenge, RedirectUrl, Scope, UserInfoClaims,
     core::{
@@ -24,7 +24,8 @@ use reqwest::{
     header::{AUTHORIZATION, CONTENT_TYPE, HeaderName, LOCATION, USER_AGENT},
 };
 use rsa::RsaPrivateKey;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
 use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
 
 use super::{
@@ -56,6 +57,7 @@ async fn test_openid_client(_: PgPoolOptions, options: PgConnectOptions) {
         redirect_uri: vec![TEST_SERVER_URL.into()],
         scope: vec!["openid".into()],
         enabled: true,
+        ..Default::default()
     };
 
     let response = client
@@ -115,6 +117,7 @@ async fn test_openid_flow(_: PgPoolOptions, options: PgConnectOptions) {
         redirect_uri: vec![TEST_SERVER_URL.into(), "http://safe.net".into()],
         scope: vec!["openid".into()],
         enabled: true,
+        ..Default::default()
     };
 
     let response = client
@@ -496,6 +499,7 @@ async fn test_openid_authorization_code(_: PgPoolOptions, options: PgConnectOpti
         redirect_uri: vec![FAKE_REDIRECT_URI.into()],
         scope: vec!["openid".into()],
         enabled: true,
+        ..Default::default()
     };
     let response = client
         .post("/api/v1/oauth")
@@ -601,6 +605,7 @@ async fn dg25_20_test_openid_disabled_client_doesnt_generate_code(
         redirect_uri: vec![FAKE_REDIRECT_URI.into()],
         scope: vec!["openid".into()],
         enabled: true,
+        ..Default::default()
     };
     let response = client
         .post("/api/v1/oauth")
@@ -658,6 +663,7 @@ async fn dg25_20_test_openid_disabled_client_doesnt_generate_code(
         redirect_uri: vec![FAKE_REDIRECT_URI.into()],
         scope: vec!["openid".into()],
         enabled: false,
+        ..Default::default()
     };
     let response = client
         .put(format!("/api/v1/oauth/{}", oauth2client.client_id))
@@ -708,6 +714,7 @@ async fn dg25_25_openid_disabled_client_userinfo_fails(
         redirect_uri: vec![FAKE_REDIRECT_URI.into()],
         scope: vec!["openid".into(), "email".into(), "profile".into()],
         enabled: true,
+        ..Default::default()
     };
     let response = client
         .post("/api/v1/oauth")
@@ -789,6 +796,7 @@ async fn dg25_25_openid_disabled_client_userinfo_fails(
         redirect_uri: vec![FAKE_REDIRECT_URI.into()],
         scope: vec!["openid".into(), "email".into(), "profile".into()],
         enabled: false,
+        ..Default::default()
     };
     let response = client
         .put(format!("/api/v1/oauth/{}", oauth2client.client_id))
@@ -839,6 +847,7 @@ async fn test_openid_authorization_code_with_pkce(_: PgPoolOptions, options: PgC
         redirect_uri: vec![FAKE_REDIRECT_URI.into()],
         scope: vec!["openid".into()],
         enabled: true,
+        ..Default::default()
     };
     let response = client
         .post("/api/v1/oauth")
@@ -917,9 +926,9 @@ async fn test_openid_authorization_code_with_pkce(_: PgPoolOptions, options: PgC
         .unwrap();
     assert!(refresh_response.refresh_token().is_some());
 
-    // userinfo
+    // userinfo, access token has been rotated together with the refresh token
     let _userinfo_claims: UserInfoClaims<EmptyAdditionalClaims, CoreGenderClaim> = core_client
-        .user_info(token_response.access_token().clone(), None)
+        .user_info(refresh_response.access_token().clone(), None)
         .expect("Missing info endpoint")
         .request_async(&|r| http_client(r, &client))
         .await
@@ -949,6 +958,7 @@ async fn dg25_23_test_openid_client_scope_change_clears_authorizations(
         redirect_uri: vec![TEST_SERVER_URL.into()],
         scope: vec!["openid".into(), "email".into()],
         enabled: true,
+        ..Default::default()
     };
 
     let response = client
@@ -996,6 +1006,7 @@ async fn dg25_23_test_openid_client_scope_change_clears_authorizations(
         redirect_uri: vec![TEST_SERVER_URL.into()],
         scope: vec!["openid".into(), "profile".into()], // Changed from email to profile
         enabled: true,
+        ..Default::default()
     };
 
     let response = client
@@ -1056,6 +1067,7 @@ async fn dg25_23_test_openid_client_scope_change_clears_authorizations(
         redirect_uri: vec![TEST_SERVER_URL.into()],
         scope: vec!["openid".into(), "profile".into()], // Same scopes
         enabled: true,
+        ..Default::default()
     };
 
     let response = client
@@ -1099,6 +1111,7 @@ async fn dg25_17_test_openid_open_redirects(_: PgPoolOptions, options: PgConnect
         redirect_uri: vec![TEST_SERVER_URL.into(), "http://safe.net/".into()],
         scope: vec!["openid".into(), "email".into()],
         enabled: true,
+        ..Default::default()
     };
 
     let response = client
@@ -1305,6 +1318,7 @@ async fn dg25_22_test_respect_openid_scope_in_userinfo(
                 redirect_uri: vec![FAKE_REDIRECT_URI.into()],
                 scope: client_scopes,
                 enabled: true,
+                ..Default::default()
             };
             let response = client
                 .post("/api/v1/oauth")
@@ -1466,6 +1480,7 @@ async fn dg25_21_test_openid_html_injection(_: PgPoolOptions, options: PgConnect
             redirect_uri: vec![TEST_SERVER_URL.into()],
             scope: vec!["openid".into()],
             enabled: true,
+            ..Default::default()
         };
         let response = client
             .post("/api/v1/oauth")
@@ -1481,6 +1496,7 @@ async fn dg25_21_test_openid_html_injection(_: PgPoolOptions, options: PgConnect
         redirect_uri: vec![TEST_SERVER_URL.into()],
         scope: vec!["openid".into()],
         enabled: true,
+        ..Default::default()
     };
     let response = client
         .post("/api/v1/oauth")
@@ -1498,6 +1514,7 @@ async fn dg25_21_test_openid_html_injection(_: PgPoolOptions, options: PgConnect
             redirect_uri: vec![TEST_SERVER_URL.into()],
             scope: vec!["openid".into()],
             enabled: true,
+            ..Default::default()
         };
         let response = client
             .put(format!("/api/v1/oauth/{}", valid_openid_client.client_id))
@@ -1529,6 +1546,7 @@ async fn test_openid_flow_new_login_mail(_: PgPoolOptions, options: PgConnectOpt
         redirect_uri: vec![TEST_SERVER_URL.into()],
         scope: vec!["openid".into()],
         enabled: true,
+        ..Default::default()
     };
 
     let response = client
@@ -1600,3 +1618,316 @@ async fn test_openid_flow_new_login_mail(_: PgPoolOptions, options: PgConnectOpt
     // No new mail recevied
     assert_err!(mail_rx.try_recv());
 }
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct TestCustomClaims {
+    uid: Option<String>,
+    roles: Option<Vec<String>>,
+    tenant: Option<String>,
+}
+
+impl AdditionalClaims for TestCustomClaims {}
+
+#[sqlx::test]
+async fn test_openid_custom_claims(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+
+    let (client, state) = make_test_client(pool).await;
+    let mut config = state.config;
+
+    let mut rng = rand::thread_rng();
+    config.openid_signing_key = RsaPrivateKey::new(&mut rng, 2048).ok();
+
+    let admin = User::find_by_username(&state.pool, "admin")
+        .await
+        .unwrap()
+        .unwrap();
+    let devs = Group::new("devs").save(&state.pool).await.unwrap();
+    admin.add_to_group(&state.pool, &devs).await.unwrap();
+
+    let auth = Auth::new("admin", "pass123");
+    let response = client.post("/api/v1/auth").json(&auth).send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // claims set by the provider can't be configured
+    let response = client
+        .post("/api/v1/oauth")
+        .json(&json!({
+            "name": "Test client",
+            "redirect_uri": [FAKE_REDIRECT_URI],
+            "scope": ["openid"],
+            "enabled": true,
+            "claim_mapping": {"sub": "uid"},
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = client
+        .post("/api/v1/oauth")
+        .json(&json!({
+            "name": "Test client",
+            "redirect_uri": [FAKE_REDIRECT_URI],
+            "scope": ["openid"],
+            "enabled": true,
+            "static_claims": {"iss": "https://example.com"},
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    // mapped names can't clash with each other
+    let response = client
+        .post("/api/v1/oauth")
+        .json(&json!({
+            "name": "Test client",
+            "redirect_uri": [FAKE_REDIRECT_URI],
+            "scope": ["openid"],
+            "enabled": true,
+            "claim_mapping": {"preferred_username": "uid"},
+            "static_claims": {"uid": "admin"},
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let oauth2client = NewOpenIDClient {
+        name: "Test client".into(),
+        redirect_uri: vec![FAKE_REDIRECT_URI.into()],
+        scope: vec!["openid".into(), "profile".into(), "groups".into()],
+        enabled: true,
+        claim_mapping: HashMap::from([
+            ("preferred_username".to_string(), "uid".to_string()),
+            ("groups".to_string(), "roles".to_string()),
+        ]),
+        group_filter: vec!["devs".into()],
+        static_claims: [("tenant".to_string(), json!("acme"))]
+            .into_iter()
+            .collect(),
+    };
+    let response = client
+        .post("/api/v1/oauth")
+        .json(&oauth2client)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let oauth2client: OAuth2Client<Id> = response.json().await;
+
+    let issuer_url = IssuerUrl::from_url(config.url.clone());
+    let provider_metadata =
+        CoreProviderMetadata::discover_async(issuer_url, &|r| http_client(r, &client))
+            .await
+            .unwrap();
+    let core_client = CoreClient::from_provider_metadata(
+        provider_metadata,
+        ClientId::new(oauth2client.client_id),
+        Some(ClientSecret::new(oauth2client.client_secret)),
+    )
+    .set_redirect_uri(RedirectUrl::new(FAKE_REDIRECT_URI.into()).unwrap());
+    let (authorize_url, _csrf_state, nonce) = core_client
+        .authorize_url(
+            AuthenticationFlow::<CoreResponseType>::AuthorizationCode,
+            CsrfToken::new_random,
+            Nonce::new_random,
+        )
+        .add_scope(Scope::new("profile".into()))
+        .add_scope(Scope::new("groups".into()))
+        .url();
+
+    let uri = format!(
+        "{}?allow=true&{}",
+        authorize_url.path(),
+        authorize_url.query().unwrap()
+    );
+    let response = client.post(uri).send().await;
+    assert_eq!(response.status(), StatusCode::FOUND);
+    let location = response
+        .headers()
+        .get("Location")
+        .unwrap()
+        .to_str()
+        .unwrap();
+    let (_, query) = location.split_once('?').unwrap();
+    let auth_response: AuthenticationResponse = serde_qs::from_str(query).unwrap();
+
+    let token_response = core_client
+        .exchange_code(AuthorizationCode::new(auth_response.code.into()))
+        .unwrap()
+        .request_async(&|r| http_client(r, &client))
+        .await
+        .unwrap();
+    let id_token_verifier = core_client.id_token_verifier();
+    token_response
+        .extra_fields()
+        .id_token()
+        .expect("Server did not return an ID token")
+        .claims(&id_token_verifier, &nonce)
+        .unwrap();
+
+    let userinfo_claims: UserInfoClaims<TestCustomClaims, CoreGenderClaim> = core_client
+        .user_info(token_response.access_token().clone(), None)
+        .expect("Missing info endpoint")
+        .request_async(&|r| http_client(r, &client))
+        .await
+        .unwrap();
+
+    // username is emitted as `uid` instead of `preferred_username`
+    assert!(userinfo_claims.preferred_username().is_none());
+    assert!(userinfo_claims.given_name().is_some());
+    let custom_claims = userinfo_claims.additional_claims();
+    assert_eq!(custom_claims.uid.as_deref(), Some("admin"));
+    // only groups from the filter are emitted
+    assert_eq!(custom_claims.roles, Some(vec!["devs".to_string()]));
+    assert_eq!(custom_claims.tenant.as_deref(), Some("acme"));
+}
+
+#[sqlx::test]
+async fn test_openid_refresh_token_rotation(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+
+    let (client, state) = make_test_client(pool).await;
+    let mut config = state.config;
+
+    let mut rng = rand::thread_rng();
+    config.openid_signing_key = RsaPrivateKey::new(&mut rng, 2048).ok();
+
+    let auth = Auth::new("admin", "pass123");
+    let response = client.post("/api/v1/auth").json(&auth).send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let oauth2client = NewOpenIDClient {
+        name: "Test client".into(),
+        redirect_uri: vec![FAKE_REDIRECT_URI.into()],
+        scope: vec!["openid".into()],
+        enabled: true,
+        ..Default::default()
+    };
+    let response = client
+        .post("/api/v1/oauth")
+        .json(&oauth2client)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let oauth2client: OAuth2Client<Id> = response.json().await;
+    let client_id = oauth2client.client_id.clone();
+
+    let issuer_url = IssuerUrl::from_url(config.url.clone());
+    let provider_metadata =
+        CoreProviderMetadata::discover_async(issuer_url, &|r| http_client(r, &client))
+            .await
+            .unwrap();
+    let core_client = CoreClient::from_provider_metadata(
+        provider_metadata,
+        ClientId::new(oauth2client.client_id),
+        Some(ClientSecret::new(oauth2client.client_secret)),
+    )
+    .set_redirect_uri(RedirectUrl::new(FAKE_REDIRECT_URI.into()).unwrap());
+
+    // authorize the client and exchange authorization code for tokens
+    let authorize = || {
+        let client = &client;
+        let core_client = &core_client;
+        async move {
+            let (authorize_url, _csrf_state, _nonce) = core_client
+                .authorize_url(
+                    AuthenticationFlow::<CoreResponseType>::AuthorizationCode,
+                    CsrfToken::new_random,
+                    Nonce::new_random,
+                )
+                .url();
+            let uri = format!(
+                "{}?allow=true&{}",
+                authorize_url.path(),
+                authorize_url.query().unwrap()
+            );
+            let response = client.post(uri).send().await;
+            assert_eq!(response.status(), StatusCode::FOUND);
+            let location = response
+                .headers()
+                .get("Location")
+                .unwrap()
+                .to_str()
+                .unwrap();
+            let (_, query) = location.split_once('?').unwrap();
+            let auth_response: AuthenticationResponse = serde_qs::from_str(query).unwrap();
+            core_client
+                .exchange_code(AuthorizationCode::new(auth_response.code.into()))
+                .unwrap()
+                .request_async(&|r| http_client(r, client))
+                .await
+                .unwrap()
+        }
+    };
+
+    // refreshing rotates both tokens
+    let token_response = authorize().await;
+    let refresh_token = token_response.refresh_token().unwrap();
+    let refresh_response = core_client
+        .exchange_refresh_token(refresh_token)
+        .unwrap()
+        .request_async(&|r| http_client(r, &client))
+        .await
+        .unwrap();
+    assert_ne!(
+        refresh_response.access_token().secret(),
+        token_response.access_token().secret()
+    );
+    let new_refresh_token = refresh_response.refresh_token().unwrap();
+    assert_ne!(new_refresh_token.secret(), refresh_token.secret());
+
+    // old access token can't be used anymore
+    let userinfo: Result<UserInfoClaims<EmptyAdditionalClaims, CoreGenderClaim>, _> = core_client
+        .user_info(token_response.access_token().clone(), None)
+        .expect("Missing info endpoint")
+        .request_async(&|r| http_client(r, &client))
+        .await;
+    assert!(userinfo.is_err());
+
+    // reuse of the rotated refresh token fails and revokes the current one
+    let replay = core_client
+        .exchange_refresh_token(refresh_token)
+        .unwrap()
+        .request_async(&|r| http_client(r, &client))
+        .await;
+    assert!(replay.is_err());
+    let refresh = core_client
+        .exchange_refresh_token(new_refresh_token)
+        .unwrap()
+        .request_async(&|r| http_client(r, &client))
+        .await;
+    assert!(refresh.is_err());
+
+    // administrators can list and revoke tokens
+    let token_response = authorize().await;
+    let response = client
+        .get(format!("/api/v1/oauth/{client_id}/tokens"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let tokens: Vec<serde_json::Value> = response.json().await;
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0]["username"], "admin");
+    let token_id = tokens[0]["id"].as_i64().unwrap();
+
+    let response = client
+        .delete(format!("/api/v1/oauth/{client_id}/tokens/{token_id}"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .delete(format!("/api/v1/oauth/{client_id}/tokens/{token_id}"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let refresh = core_client
+        .exchange_refresh_token(token_response.refresh_token().unwrap())
+        .unwrap()
+        .request_async(&|r| http_client(r, &client))
+        .await;
+    assert!(refresh.is_err());
+    let response = client
+        .get(format!("/api/v1/oauth/{client_id}/tokens"))
+        .send()
+        .await;
+    let tokens: Vec<serde_json::Value> = response.json().await;
+    assert!(tokens.is_empty());
+}
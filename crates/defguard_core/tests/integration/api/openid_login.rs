@@ -120,6 +120,7 @@ async fn test_openid_login(_: PgPoolOptions, options: PgConnectOptions) {
         redirect_uri: vec![redirect_uri],
         scope: vec!["openid".into(), "email".into(), "profile".into()],
         enabled: true,
+        ..Default::default()
     };
     let response = client
         .post("/api/v1/oauth")
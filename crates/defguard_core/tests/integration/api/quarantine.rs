@@ -0,0 +1,120 @@
+use defguard_common::db::Id;
+use defguard_core::{
+    db::GatewayEvent,
+    enterprise::{
+        db::models::quarantine::{DeviceQuarantine, QuarantineRemediationHost, QuarantineSource},
+        quarantine::handlers::{NewRemediationHost, QuarantineDevice},
+    },
+    handlers::Auth,
+};
+use matches::assert_matches;
+use reqwest::StatusCode;
+use serde_json::json;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+use super::common::{authenticate_admin, make_network, make_test_client, setup_pool};
+
+#[sqlx::test]
+async fn test_device_quarantine(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, client_state) = make_test_client(pool).await;
+    let mut wg_rx = client_state.wireguard_rx;
+    authenticate_admin(&mut client).await;
+
+    // create location and device
+    let response = client
+        .post("/api/v1/network")
+        .json(&make_network())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response = client
+        .post("/api/v1/device/admin")
+        .json(&json!({
+            "name": "device",
+            "wireguard_pubkey": "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=",
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    while wg_rx.try_recv().is_ok() {}
+
+    // add remediation host
+    let host = NewRemediationHost {
+        address: "10.1.1.0/24".parse().unwrap(),
+        description: Some("patch server".into()),
+    };
+    let response = client
+        .post("/api/v1/network/1/quarantine")
+        .json(&host)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response = client
+        .post("/api/v1/network/1/quarantine")
+        .json(&host)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let response = client.get("/api/v1/network/1/quarantine").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let hosts: Vec<QuarantineRemediationHost<Id>> = response.json().await;
+    assert_eq!(hosts.len(), 1);
+    while wg_rx.try_recv().is_ok() {}
+
+    // quarantine device
+    let response = client
+        .post("/api/v1/device/1/quarantine")
+        .json(&QuarantineDevice {
+            reason: Some("malware detected".into()),
+        })
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let quarantine: DeviceQuarantine<Id> = response.json().await;
+    assert_eq!(quarantine.device_id, 1);
+    assert_eq!(quarantine.source, QuarantineSource::Manual);
+    let event = wg_rx.try_recv().unwrap();
+    assert_matches!(event, GatewayEvent::FirewallConfigChanged(1, ref config) if config.rules.len() == 2);
+
+    // device can't be quarantined twice
+    let response = client
+        .post("/api/v1/device/1/quarantine")
+        .json(&QuarantineDevice::default())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = client.get("/api/v1/quarantine").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let quarantines: Vec<DeviceQuarantine<Id>> = response.json().await;
+    assert_eq!(quarantines.len(), 1);
+    assert_eq!(quarantines[0].id, quarantine.id);
+    assert_eq!(quarantines[0].reason.as_deref(), Some("malware detected"));
+
+    // release device
+    let response = client.delete("/api/v1/device/1/quarantine").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_matches!(wg_rx.try_recv().unwrap(), GatewayEvent::FirewallDisabled(1));
+    let response = client.delete("/api/v1/device/1/quarantine").send().await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // remove remediation host
+    let response = client.delete("/api/v1/network/1/quarantine/1").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client.delete("/api/v1/network/1/quarantine/1").send().await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // only admins can manage quarantine
+    let auth = Auth::new("hpotter", "pass123");
+    let response = client.post("/api/v1/auth").json(&auth).send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .post("/api/v1/device/1/quarantine")
+        .json(&QuarantineDevice::default())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = client.get("/api/v1/quarantine").send().await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,153 @@
+use defguard_core::handlers::{EditGroupInfo, GroupInfo, wireguard::AddDeviceResult};
+use ipnetwork::IpNetwork;
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+use super::common::{authenticate_admin, make_network, make_test_client, setup_pool};
+
+#[sqlx::test]
+async fn test_routes(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, _) = make_test_client(pool).await;
+    authenticate_admin(&mut client).await;
+
+    let response = client
+        .post("/api/v1/network")
+        .json(&make_network())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // unknown location
+    let response = client
+        .post("/api/v1/route")
+        .json(&json!({
+            "name": "office",
+            "addresses": ["192.168.10.0/24"],
+            "locations": [42],
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // route applying to all users
+    let response = client
+        .post("/api/v1/route")
+        .json(&json!({
+            "name": "office",
+            "description": "Office LAN",
+            "addresses": ["192.168.10.0/24", "10.1.1.0/24"],
+            "locations": [1],
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let office: Value = response.json().await;
+    assert_eq!(office["name"], "office");
+    assert_eq!(office["locations"], json!([1]));
+    assert_eq!(office["groups"], json!([]));
+
+    let response = client
+        .post("/api/v1/route")
+        .json(&json!({
+            "name": "office",
+            "addresses": ["192.168.11.0/24"],
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    // route restricted to a group
+    let data = EditGroupInfo::new("contractors", vec!["hpotter".into()], false);
+    let response = client.post("/api/v1/group").json(&data).send().await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response = client.get("/api/v1/group/contractors").send().await;
+    let group: GroupInfo = response.json().await;
+    let servers = json!({
+        "name": "servers",
+        "addresses": ["172.16.0.0/16"],
+        "locations": [1],
+        "groups": [group.id],
+    });
+    let response = client.post("/api/v1/route").json(&servers).send().await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let servers_route: Value = response.json().await;
+    let servers_id = servers_route["id"].as_i64().unwrap();
+
+    let response = client.get("/api/v1/route").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let routes: Vec<Value> = response.json().await;
+    assert_eq!(routes.len(), 2);
+
+    // group member gets all routes
+    let response = client
+        .post("/api/v1/device/hpotter")
+        .json(&json!({
+            "name": "device",
+            "wireguard_pubkey": "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=",
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response: AddDeviceResult = response.json().await;
+    let expected: Vec<IpNetwork> = ["10.1.1.0/24", "192.168.10.0/24", "172.16.0.0/16"]
+        .iter()
+        .map(|address| address.parse().unwrap())
+        .collect();
+    assert_eq!(response.configs[0].allowed_ips, expected);
+    assert!(
+        response.configs[0]
+            .config
+            .contains("AllowedIPs = 10.1.1.0/24,192.168.10.0/24,172.16.0.0/16")
+    );
+
+    // other users don't get group routes
+    let response = client
+        .post("/api/v1/device/admin")
+        .json(&json!({
+            "name": "admin-device",
+            "wireguard_pubkey": "hNuapt7lOxF93KUqZGUY00oKJxH8LYwwsUVB1uUa0y4=",
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response: AddDeviceResult = response.json().await;
+    assert_eq!(response.configs[0].allowed_ips, expected[..2]);
+    let admin_device_id = response.device.id;
+
+    // route no longer restricted to a group
+    let mut servers = servers;
+    servers["groups"] = json!([]);
+    let response = client
+        .put(format!("/api/v1/route/{servers_id}"))
+        .json(&servers)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .get(format!("/api/v1/network/1/device/{admin_device_id}/config"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let config = response.text().await;
+    assert!(config.contains("AllowedIPs = 10.1.1.0/24,192.168.10.0/24,172.16.0.0/16"));
+
+    // remove route
+    let response = client
+        .delete(format!("/api/v1/route/{servers_id}"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .get(format!("/api/v1/route/{servers_id}"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = client
+        .get(format!("/api/v1/network/1/device/{admin_device_id}/config"))
+        .send()
+        .await;
+    let config = response.text().await;
+    assert!(config.contains("AllowedIPs = 10.1.1.0/24,192.168.10.0/24\n"));
+}
@@ -0,0 +1,132 @@
+use defguard_common::db::models::{Settings, settings::update_current_settings};
+use defguard_core::{db::User, events::ApiEventType};
+use reqwest::StatusCode;
+use serde_json::json;
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions},
+    query_scalar,
+};
+
+use super::common::{make_test_client, setup_pool};
+
+#[sqlx::test]
+async fn test_self_registration(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, client_state) = make_test_client(pool).await;
+    let pool = client_state.pool;
+    let mut mail_rx = client_state.mail_rx;
+
+    let registration = json!({
+        "username": "nlongbottom",
+        "first_name": "Neville",
+        "last_name": "Longbottom",
+        "email": "n.longbottom@hogwart.edu.uk",
+        "phone": null,
+    });
+
+    // self-registration is disabled by default
+    let response = client
+        .post("/api/v1/self_registration")
+        .json(&registration)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let mut settings = Settings::get_current_settings();
+    settings.smtp_server = Some("smtp_server".into());
+    settings.smtp_port = Some(587);
+    settings.smtp_sender = Some("smtp@sender.pl".into());
+    settings.self_registration_enabled = true;
+    settings.self_registration_domains = vec!["hogwart.edu.uk".into()];
+    update_current_settings(&pool, settings).await.unwrap();
+
+    // email domain not allowed
+    let mut other_domain = registration.clone();
+    other_domain["email"] = "n.longbottom@durmstrang.edu".into();
+    let response = client
+        .post("/api/v1/self_registration")
+        .json(&other_domain)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // username already taken
+    let mut taken_username = registration.clone();
+    taken_username["username"] = "hpotter".into();
+    let response = client
+        .post("/api/v1/self_registration")
+        .json(&taken_username)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(mail_rx.try_recv().is_err());
+
+    // request account
+    let response = client
+        .post("/api/v1/self_registration")
+        .json(&registration)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let mail = mail_rx.try_recv().unwrap();
+    assert_eq!(mail.to, "n.longbottom@hogwart.edu.uk");
+    assert_eq!(mail.subject, "Defguard: verify your email address");
+    let token: String =
+        query_scalar("SELECT token FROM self_registration_request WHERE username = 'nlongbottom'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert!(mail.content.contains(&token));
+    assert!(
+        User::find_by_username(&pool, "nlongbottom")
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    // invalid token
+    let response = client
+        .post("/api/v1/self_registration/verify")
+        .json(&json!({"token": "invalid"}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // verify email
+    let response = client
+        .post("/api/v1/self_registration/verify")
+        .json(&json!({"token": token}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let user = User::find_by_username(&pool, "nlongbottom")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!user.is_active);
+    assert_eq!(user.email, "n.longbottom@hogwart.edu.uk");
+    let enrollment_tokens: i64 =
+        query_scalar("SELECT count(*) FROM token WHERE user_id = $1 AND token_type = 'ENROLLMENT'")
+            .bind(user.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(enrollment_tokens, 1);
+
+    // user receives enrollment token, admins are notified
+    let mail = mail_rx.try_recv().unwrap();
+    assert_eq!(mail.to, "n.longbottom@hogwart.edu.uk");
+    assert_eq!(mail.subject, "Defguard user enrollment");
+    let mail = mail_rx.try_recv().unwrap();
+    assert_eq!(mail.to, "admin@defguard");
+    assert_eq!(mail.subject, "Defguard: new user awaiting activation");
+    client.verify_api_events(&[ApiEventType::UserSelfRegistered { user }]);
+
+    // token can be used only once
+    let response = client
+        .post("/api/v1/self_registration/verify")
+        .json(&json!({"token": token}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,122 @@
+use defguard_core::db::models::service_account::ServiceAccountInfo;
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+use super::common::{authenticate_admin, make_network, make_test_client, setup_pool};
+
+#[sqlx::test]
+async fn test_service_account_lifecycle(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, _) = make_test_client(pool).await;
+    authenticate_admin(&mut client).await;
+
+    let response = client
+        .post("/api/v1/service_account")
+        .json(&json!({"name": "deploy-bot", "description": "CI pipeline"}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let service_account: ServiceAccountInfo = response.json().await;
+    assert_eq!(service_account.name, "deploy-bot");
+    assert_eq!(service_account.description.as_deref(), Some("CI pipeline"));
+    assert!(service_account.is_active);
+    assert_eq!(service_account.network_devices, 0);
+    let id = service_account.id;
+
+    // name already taken by a service account or a user
+    for name in ["deploy-bot", "admin"] {
+        let response = client
+            .post("/api/v1/service_account")
+            .json(&json!({ "name": name }))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    let response = client.get("/api/v1/service_account").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let service_accounts: Vec<ServiceAccountInfo> = response.json().await;
+    assert_eq!(service_accounts.len(), 1);
+    assert_eq!(service_accounts[0].id, id);
+
+    // transfer a network device to the service account
+    let response = client
+        .post("/api/v1/network")
+        .json(&make_network())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response = client
+        .post("/api/v1/device/network")
+        .json(&json!({
+            "name": "runner",
+            "description": null,
+            "location_id": 1,
+            "assigned_ips": ["10.1.1.10"],
+            "wireguard_pubkey": "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=",
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let json: Value = response.json().await;
+    let device_id = json["device"]["id"].as_i64().unwrap();
+    assert_eq!(json["device"]["added_by"], "admin");
+
+    let response = client
+        .put(format!("/api/v1/service_account/{id}/network_device/100"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = client
+        .put(format!(
+            "/api/v1/service_account/{id}/network_device/{device_id}"
+        ))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let service_account: ServiceAccountInfo = response.json().await;
+    assert_eq!(service_account.network_devices, 1);
+    let response = client
+        .get(format!("/api/v1/device/network/{device_id}"))
+        .send()
+        .await;
+    let json: Value = response.json().await;
+    assert_eq!(json["added_by"], "deploy-bot");
+
+    // disable
+    let response = client
+        .put(format!("/api/v1/service_account/{id}"))
+        .json(&json!({"description": "CD pipeline", "is_active": false}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let service_account: ServiceAccountInfo = response.json().await;
+    assert_eq!(service_account.description.as_deref(), Some("CD pipeline"));
+    assert!(!service_account.is_active);
+
+    // owned network devices have to be removed first
+    let response = client
+        .delete(format!("/api/v1/service_account/{id}"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = client
+        .delete(format!("/api/v1/device/network/{device_id}"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .delete(format!("/api/v1/service_account/{id}"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = client
+        .get(format!("/api/v1/service_account/{id}"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = client.get("/api/v1/user/deploy-bot").send().await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
@@ -1,6 +1,7 @@
 use defguard_common::db::models::{Settings, settings::SettingsPatch};
 use defguard_core::handlers::Auth;
 use reqwest::StatusCode;
+use serde_json::json;
 use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
 
 use super::common::{make_test_client, setup_pool};
@@ -43,4 +44,21 @@ async fn test_settings(_: PgPoolOptions, options: PgConnectOptions) {
     assert_eq!(response.status(), StatusCode::OK);
     let new_settings: Settings = response.json().await;
     assert!(new_settings.wireguard_enabled);
+
+    // notification digest window can't be negative
+    let response = client
+        .patch("/api/v1/settings")
+        .json(&json!({"notification_digest_window": -1}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = client
+        .patch("/api/v1/settings")
+        .json(&json!({"notification_digest_window": 15}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client.get("/api/v1/settings").send().await;
+    let new_settings: Settings = response.json().await;
+    assert_eq!(new_settings.notification_digest_window, 15);
 }
@@ -0,0 +1,199 @@
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+use super::common::{authenticate_admin, make_test_client, setup_pool};
+
+#[sqlx::test]
+async fn test_smtp_profiles(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, _) = make_test_client(pool).await;
+
+    // only admins can manage profiles
+    client.login_user("hpotter", "pass123").await;
+    let response = client.get("/api/v1/settings/smtp_profile").send().await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    authenticate_admin(&mut client).await;
+    let response = client.get("/api/v1/settings/smtp_profile").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let profiles: Vec<Value> = response.json().await;
+    assert!(profiles.is_empty());
+
+    let alerts = json!({
+        "name": "alerts",
+        "server": "alerts.example.com",
+        "port": 465,
+        "encryption": "ImplicitTls",
+        "username": "alerts",
+        "password": "hunter2",
+        "sender": "alerts@example.com",
+        "categories": ["alert"],
+    });
+    let response = client
+        .post("/api/v1/settings/smtp_profile")
+        .json(&alerts)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let profile: Value = response.json().await;
+    let alerts_id = profile["id"].as_i64().unwrap();
+    assert_eq!(profile["categories"], json!(["alert"]));
+    assert_eq!(profile["has_password"], true);
+    assert!(profile.get("password").is_none());
+
+    // invalid data and duplicate names
+    for (field, value) in [
+        ("name", json!("alerts")),
+        ("name", json!(" ")),
+        ("server", json!("")),
+        ("port", json!(0)),
+        ("port", json!(70000)),
+        ("sender", json!("alerts")),
+        ("reply_to", json!("not an address")),
+    ] {
+        let mut data = alerts.clone();
+        data[field] = value;
+        let response = client
+            .post("/api/v1/settings/smtp_profile")
+            .json(&data)
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{field}");
+    }
+
+    // binding a category to another profile moves it
+    let response = client
+        .post("/api/v1/settings/smtp_profile")
+        .json(&json!({
+            "name": "enrollment",
+            "server": "mail.example.com",
+            "port": 587,
+            "encryption": "StartTls",
+            "sender": "welcome@example.com",
+            "sender_name": "Example Inc.",
+            "categories": ["enrollment", "alert"],
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let profile: Value = response.json().await;
+    let enrollment_id = profile["id"].as_i64().unwrap();
+    assert_eq!(profile["categories"], json!(["alert", "enrollment"]));
+    assert_eq!(profile["has_password"], false);
+
+    let response = client.get("/api/v1/settings/smtp_profile").send().await;
+    let profiles: Vec<Value> = response.json().await;
+    assert_eq!(profiles.len(), 2);
+    let alerts_profile = profiles
+        .iter()
+        .find(|profile| profile["id"] == alerts_id)
+        .unwrap();
+    assert_eq!(alerts_profile["categories"], json!([]));
+
+    // omitted password is kept
+    let mut data = alerts.clone();
+    data["password"] = Value::Null;
+    data["port"] = json!(2465);
+    let response = client
+        .put(format!("/api/v1/settings/smtp_profile/{alerts_id}"))
+        .json(&data)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let profile: Value = response.json().await;
+    assert_eq!(profile["port"], 2465);
+    assert_eq!(profile["has_password"], true);
+    assert_eq!(profile["categories"], json!(["alert"]));
+
+    // name can't be taken from another profile
+    data["name"] = json!("enrollment");
+    let response = client
+        .put(format!("/api/v1/settings/smtp_profile/{alerts_id}"))
+        .json(&data)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // removing a profile removes its bindings
+    let response = client
+        .delete(format!("/api/v1/settings/smtp_profile/{alerts_id}"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .delete(format!("/api/v1/settings/smtp_profile/{alerts_id}"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = client.get("/api/v1/settings/smtp_profile").send().await;
+    let profiles: Vec<Value> = response.json().await;
+    assert_eq!(profiles.len(), 1);
+    assert_eq!(profiles[0]["id"], enrollment_id);
+    assert_eq!(profiles[0]["categories"], json!(["enrollment"]));
+}
+
+#[sqlx::test]
+async fn test_smtp_failover_profile(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, _) = make_test_client(pool).await;
+
+    client.login_user("hpotter", "pass123").await;
+    let response = client.get("/api/v1/mail/health").send().await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    authenticate_admin(&mut client).await;
+    let response = client.get("/api/v1/mail/health").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let health: Value = response.json().await;
+    assert!(health["primary"]["consecutive_failures"].is_u64());
+    assert!(health["failover_active"].is_boolean());
+
+    let mut ids = Vec::new();
+    for name in ["backup", "backup2"] {
+        let response = client
+            .post("/api/v1/settings/smtp_profile")
+            .json(&json!({
+                "name": name,
+                "server": "backup.example.com",
+                "port": 587,
+                "encryption": "StartTls",
+                "sender": "defguard@example.com",
+                "failover": true,
+            }))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let profile: Value = response.json().await;
+        assert_eq!(profile["failover"], true);
+        ids.push(profile["id"].as_i64().unwrap());
+    }
+
+    // only one profile is used for failover
+    let failover_ids = |profiles: Vec<Value>| -> Vec<Value> {
+        profiles
+            .into_iter()
+            .filter(|profile| profile["failover"] == true)
+            .map(|profile| profile["id"].clone())
+            .collect()
+    };
+    let response = client.get("/api/v1/settings/smtp_profile").send().await;
+    assert_eq!(failover_ids(response.json().await), [json!(ids[1])]);
+
+    // failover can be moved back to the first profile
+    let response = client
+        .put(format!("/api/v1/settings/smtp_profile/{}", ids[0]))
+        .json(&json!({
+            "name": "backup",
+            "server": "backup.example.com",
+            "port": 2587,
+            "encryption": "StartTls",
+            "sender": "defguard@example.com",
+            "failover": true,
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client.get("/api/v1/settings/smtp_profile").send().await;
+    assert_eq!(failover_ids(response.json().await), [json!(ids[0])]);
+}
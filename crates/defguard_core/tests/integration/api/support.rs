@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use chrono::NaiveDate;
+use defguard_common::db::register_pool;
+use defguard_core::{
+    wireguard_stats_maintenance::{STATS_TABLES, run_maintenance},
+    wireguard_stats_partitions::{create_future_partitions, partition_name, stats_partitions},
+};
+use reqwest::StatusCode;
+use serde_json::Value;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+use super::common::{authenticate_admin, make_test_client, setup_pool};
+
+#[sqlx::test]
+async fn test_database_pools(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, client_state) = make_test_client(pool).await;
+    register_pool("api", &client_state.pool);
+
+    let response = client.get("/api/v1/support/database-pools").send().await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    authenticate_admin(&mut client).await;
+    let response = client.get("/api/v1/support/database-pools").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let pools: Vec<Value> = response.json().await;
+    let api = pools.iter().find(|pool| pool["name"] == "api").unwrap();
+    assert!(api["max_connections"].as_u64().unwrap() > 0);
+    assert!(api["size"].as_u64().unwrap() > 0);
+    assert_eq!(
+        api["in_use"].as_u64().unwrap() + api["idle"].as_u64().unwrap(),
+        api["size"].as_u64().unwrap()
+    );
+    assert!(api["acquire_ms"].is_u64());
+}
+
+#[sqlx::test]
+async fn test_stats_maintenance(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, client_state) = make_test_client(pool).await;
+
+    let response = client.get("/api/v1/support/stats-maintenance").send().await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // expired partitions are dropped during maintenance
+    let month = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+    create_future_partitions(&client_state.pool, month.into())
+        .await
+        .unwrap();
+    run_maintenance(&client_state.pool, Some(Duration::from_secs(3600))).await;
+    let partitions = stats_partitions(&client_state.pool).await.unwrap();
+    assert!(!partitions.contains(&partition_name(month)));
+
+    authenticate_admin(&mut client).await;
+    let response = client.get("/api/v1/support/stats-maintenance").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let report: Value = response.json().await;
+    assert!(report["runs"].as_u64().unwrap() >= 1);
+    assert_eq!(report["failures"], 0);
+    assert_eq!(report["records_purged"], 0);
+    let tables = report["tables"].as_array().unwrap();
+    assert_eq!(tables.len(), STATS_TABLES.len());
+    for table in tables {
+        assert!(table["error"].is_null());
+        assert!(table["size_after"].as_i64().unwrap() > 0);
+    }
+}
@@ -0,0 +1,97 @@
+use chrono::{Duration, Utc};
+use defguard_common::db::NoId;
+use defguard_core::db::models::wireguard_peer_stats::WireguardPeerStats;
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+use super::common::{
+    authenticate_admin, fetch_user_details, make_network, make_test_client, setup_pool,
+};
+
+#[sqlx::test]
+async fn test_troubleshoot_device(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, client_state) = make_test_client(pool).await;
+    authenticate_admin(&mut client).await;
+
+    let response = client.get("/api/v1/troubleshoot/device/1").send().await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = client
+        .post("/api/v1/network")
+        .json(&make_network())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response = client
+        .post("/api/v1/device/admin")
+        .json(&json!({
+            "name": "laptop",
+            "wireguard_pubkey": "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=",
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // no gateway is connected and the device never connected
+    let response = client.get("/api/v1/troubleshoot/device/1").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let result: Value = response.json().await;
+    assert_eq!(result["device_name"], "laptop");
+    let causes = result["causes"].as_array().unwrap();
+    assert_eq!(causes.len(), 2);
+    assert_eq!(causes[0]["cause"], "no_gateway_connected");
+    assert_eq!(causes[0]["location_id"], 1);
+    assert_eq!(causes[0]["location_name"], "network");
+    assert_eq!(causes[1]["cause"], "never_connected");
+
+    // stale handshake
+    let now = Utc::now().naive_utc();
+    WireguardPeerStats {
+        id: NoId,
+        device_id: 1,
+        collected_at: now,
+        network: 1,
+        endpoint: Some("11.22.33.44:51820".into()),
+        upload: 100,
+        download: 200,
+        latest_handshake: now - Duration::hours(1),
+        allowed_ips: None,
+        gateway: Some("gateway-1".into()),
+    }
+    .save(&client_state.pool)
+    .await
+    .unwrap();
+    let response = client.get("/api/v1/troubleshoot/device/1").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let result: Value = response.json().await;
+    let causes = result["causes"].as_array().unwrap();
+    assert_eq!(causes.len(), 2);
+    assert_eq!(causes[1]["cause"], "no_recent_handshake");
+
+    // device of a disabled user
+    let response = client
+        .post("/api/v1/device/hpotter")
+        .json(&json!({
+            "name": "phone",
+            "wireguard_pubkey": "sIhx53MsX+iLk83sssybHrD7M+5m+CmpLzWL/zo8C38=",
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let mut user_details = fetch_user_details(&client, "hpotter").await;
+    user_details.user.is_active = false;
+    let response = client
+        .put("/api/v1/user/hpotter")
+        .json(&user_details.user)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client.get("/api/v1/troubleshoot/device/2").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let result: Value = response.json().await;
+    let causes = result["causes"].as_array().unwrap();
+    assert_eq!(causes[0]["cause"], "user_disabled");
+    assert_eq!(causes[0]["location_id"], Value::Null);
+}
@@ -455,6 +455,7 @@ async fn test_user_unregister_authorized_app(_: PgPoolOptions, options: PgConnec
         redirect_uri: vec![TEST_SERVER_URL.into()],
         scope: vec!["openid".into()],
         enabled: true,
+        ..Default::default()
     };
     let response = client
         .post("/api/v1/oauth")
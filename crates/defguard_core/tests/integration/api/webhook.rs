@@ -25,6 +25,8 @@ async fn test_webhooks(_: PgPoolOptions, options: PgConnectOptions) {
         on_user_deleted: false,
         on_user_modified: true,
         on_hwkey_provision: false,
+        on_enrollment_completed: true,
+        payload_template: None,
     };
 
     let response = client.post("/api/v1/webhook").json(&webhook).send().await;
@@ -54,6 +56,23 @@ async fn test_webhooks(_: PgPoolOptions, options: PgConnectOptions) {
     assert_eq!(fetched_webhook.description, webhook.description);
     assert_eq!(fetched_webhook.on_user_modified, webhook.on_user_modified);
 
+    // invalid payload template
+    webhook.payload_template = Some("{\"user\": {{ username }".into());
+    let response = client
+        .put(format!("/api/v1/webhook/{}", webhooks[0].id))
+        .json(&webhook)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    webhook.payload_template = Some("{\"user\": {{ username | json_encode() }}}".into());
+    let response = client
+        .put(format!("/api/v1/webhook/{}", webhooks[0].id))
+        .json(&webhook)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
     let response = client
         .delete(format!("/api/v1/webhook/{}", webhooks[0].id))
         .send()
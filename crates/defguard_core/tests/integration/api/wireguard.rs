@@ -7,7 +7,8 @@ use defguard_core::{
         models::{
             device::WireguardNetworkDevice,
             wireguard::{
-                DEFAULT_DISCONNECT_THRESHOLD, DEFAULT_KEEPALIVE_INTERVAL, LocationMfaMode,
+                DEFAULT_DISCONNECT_THRESHOLD, DEFAULT_KEEPALIVE_INTERVAL, DeviceNameUniqueness,
+                GatewayDistributionPolicy, IpAllocationStrategy, LocationMfaMode,
                 ServiceLocationMode,
             },
         },
@@ -23,10 +24,15 @@ use ipnetwork::IpNetwork;
 use matches::assert_matches;
 use reqwest::StatusCode;
 use serde_json::json;
-use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions},
+    query,
+};
 
 use super::common::{
-    authenticate_admin, exceed_enterprise_limits, make_network, make_test_client, setup_pool,
+    authenticate_admin,
+    client::{TestClient, TestResponse},
+    exceed_enterprise_limits, make_network, make_test_client, setup_pool,
 };
 
 #[sqlx::test]
@@ -73,6 +79,18 @@ async fn test_network(_: PgPoolOptions, options: PgConnectOptions) {
         acl_default_allow: false,
         location_mfa_mode: LocationMfaMode::Disabled,
         service_location_mode: ServiceLocationMode::Disabled,
+        min_desktop_client_version: None,
+        min_mobile_client_version: None,
+        device_approval_required: false,
+        ip_allocation_strategy: IpAllocationStrategy::Sequential,
+        client_traffic_policy: None,
+        mfa_session_lifetime_hours: 0,
+        mfa_remember_device_hours: 0,
+        preshared_keys_enabled: false,
+        preshared_key_rotation_days: 0,
+        device_name_pattern: None,
+        device_name_prefix: None,
+        device_name_uniqueness: DeviceNameUniqueness::User,
     };
     let response = client
         .put(format!("/api/v1/network/{}", network.id))
@@ -153,6 +171,18 @@ async fn test_location_mfa_mode_validation_create(_: PgPoolOptions, options: PgC
         acl_default_allow: false,
         location_mfa_mode: LocationMfaMode::External,
         service_location_mode: ServiceLocationMode::Disabled,
+        min_desktop_client_version: None,
+        min_mobile_client_version: None,
+        device_approval_required: false,
+        ip_allocation_strategy: IpAllocationStrategy::Sequential,
+        client_traffic_policy: None,
+        mfa_session_lifetime_hours: 0,
+        mfa_remember_device_hours: 0,
+        preshared_keys_enabled: false,
+        preshared_key_rotation_days: 0,
+        device_name_pattern: None,
+        device_name_prefix: None,
+        device_name_uniqueness: DeviceNameUniqueness::User,
     };
 
     // create network
@@ -234,6 +264,18 @@ async fn test_location_mfa_mode_validation_modify(_: PgPoolOptions, options: PgC
         acl_default_allow: false,
         location_mfa_mode: LocationMfaMode::Disabled,
         service_location_mode: ServiceLocationMode::Disabled,
+        min_desktop_client_version: None,
+        min_mobile_client_version: None,
+        device_approval_required: false,
+        ip_allocation_strategy: IpAllocationStrategy::Sequential,
+        client_traffic_policy: None,
+        mfa_session_lifetime_hours: 0,
+        mfa_remember_device_hours: 0,
+        preshared_keys_enabled: false,
+        preshared_key_rotation_days: 0,
+        device_name_pattern: None,
+        device_name_prefix: None,
+        device_name_uniqueness: DeviceNameUniqueness::User,
     };
 
     // create network
@@ -979,3 +1021,587 @@ async fn test_network_size_validation(_: PgPoolOptions, options: PgConnectOption
         .await;
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 }
+
+#[sqlx::test]
+async fn test_device_disconnect(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, client_state) = make_test_client(pool).await;
+    let mut wg_rx = client_state.wireguard_rx;
+    let mut mail_rx = client_state.mail_rx;
+    authenticate_admin(&mut client).await;
+
+    // create two networks, the second one protected with MFA
+    for _ in 0..2 {
+        let response = client
+            .post("/api/v1/network")
+            .json(&make_network())
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_matches!(wg_rx.try_recv().unwrap(), GatewayEvent::NetworkCreated(..));
+    }
+    let mut mfa_network = WireguardNetwork::find_by_id(&client_state.pool, 2)
+        .await
+        .unwrap()
+        .unwrap();
+    mfa_network.location_mfa_mode = LocationMfaMode::Internal;
+    mfa_network.save(&client_state.pool).await.unwrap();
+
+    // create device
+    let device = json!({
+        "name": "device",
+        "wireguard_pubkey": "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=",
+    });
+    let response = client
+        .post("/api/v1/device/admin")
+        .json(&device)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert_matches!(wg_rx.try_recv().unwrap(), GatewayEvent::DeviceCreated(..));
+    query(
+        "UPDATE wireguard_network_device SET is_authorized = true, preshared_key = 'psk' \
+        WHERE device_id = 1 AND wireguard_network_id = 2",
+    )
+    .execute(&client_state.pool)
+    .await
+    .unwrap();
+    // drain new device notifications
+    while mail_rx.try_recv().is_ok() {}
+
+    // unknown location
+    let response = client
+        .post("/api/v1/device/1/disconnect")
+        .json(&json!({"location_id": 100}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // disconnect from a single location
+    let response = client
+        .post("/api/v1/device/1/disconnect")
+        .json(&json!({"location_id": 1}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value = response.json().await;
+    assert_eq!(body["locations"], json!([1]));
+    let event = wg_rx.try_recv().unwrap();
+    assert_matches!(event, GatewayEvent::DeviceDeleted(ref info) if info.network_info.len() == 1);
+    assert!(mail_rx.try_recv().is_err());
+
+    // disconnect from all locations and notify user
+    let response = client
+        .post("/api/v1/device/1/disconnect")
+        .json(&json!({"notify_user": true}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value = response.json().await;
+    assert_eq!(body["locations"], json!([1, 2]));
+    let event = wg_rx.try_recv().unwrap();
+    assert_matches!(event, GatewayEvent::DeviceDeleted(ref info) if info.network_info.len() == 2);
+    let mail = mail_rx.try_recv().unwrap();
+    assert_eq!(mail.to, "admin@defguard");
+
+    // device has to authenticate again in MFA location
+    let mfa_network_device = WireguardNetworkDevice::find_by_device(&client_state.pool, 1)
+        .await
+        .unwrap()
+        .unwrap()
+        .into_iter()
+        .find(|network_device| network_device.wireguard_network_id == 2)
+        .unwrap();
+    assert!(!mfa_network_device.is_authorized);
+    assert!(mfa_network_device.preshared_key.is_none());
+
+    // only admins can disconnect devices
+    let auth = Auth::new("hpotter", "pass123");
+    let response = client.post("/api/v1/auth").json(&auth).send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .post("/api/v1/device/1/disconnect")
+        .json(&json!({}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[sqlx::test]
+async fn test_network_min_client_versions(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, _) = make_test_client(pool).await;
+    authenticate_admin(&mut client).await;
+
+    // invalid version
+    let mut network = make_network();
+    network["min_desktop_client_version"] = json!("1.5");
+    let response = client.post("/api/v1/network").json(&network).send().await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // valid versions, empty values mean no requirement
+    network["min_desktop_client_version"] = json!(" 1.5.0 ");
+    network["min_mobile_client_version"] = json!("");
+    let response = client.post("/api/v1/network").json(&network).send().await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let location: WireguardNetwork<Id> = response.json().await;
+    assert_eq!(
+        location.min_desktop_client_version.as_deref(),
+        Some("1.5.0")
+    );
+    assert_eq!(location.min_mobile_client_version, None);
+
+    // modify versions
+    network["min_desktop_client_version"] = json!(null);
+    network["min_mobile_client_version"] = json!("1.2.0");
+    let response = client
+        .put(format!("/api/v1/network/{}", location.id))
+        .json(&network)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let location: WireguardNetwork<Id> = response.json().await;
+    assert_eq!(location.min_desktop_client_version, None);
+    assert_eq!(location.min_mobile_client_version.as_deref(), Some("1.2.0"));
+}
+
+#[sqlx::test]
+async fn test_network_ip_allocation_strategy(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, _) = make_test_client(pool).await;
+    authenticate_admin(&mut client).await;
+
+    // sequential by default
+    let mut network = make_network();
+    let response = client.post("/api/v1/network").json(&network).send().await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let location: WireguardNetwork<Id> = response.json().await;
+    assert_eq!(
+        location.ip_allocation_strategy,
+        IpAllocationStrategy::Sequential
+    );
+
+    // unknown strategy
+    network["ip_allocation_strategy"] = json!("lowest");
+    let response = client
+        .put(format!("/api/v1/network/{}", location.id))
+        .json(&network)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    network["ip_allocation_strategy"] = json!("sticky_by_user");
+    let response = client
+        .put(format!("/api/v1/network/{}", location.id))
+        .json(&network)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let location: WireguardNetwork<Id> = response.json().await;
+    assert_eq!(
+        location.ip_allocation_strategy,
+        IpAllocationStrategy::StickyByUser
+    );
+}
+
+async fn add_device(client: &TestClient, username: &str, name: &str, pubkey: &str) -> TestResponse {
+    client
+        .post(format!("/api/v1/device/{username}"))
+        .json(&json!({"name": name, "wireguard_pubkey": pubkey}))
+        .send()
+        .await
+}
+
+#[sqlx::test]
+async fn test_device_naming_policy(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, _) = make_test_client(pool).await;
+    authenticate_admin(&mut client).await;
+
+    // invalid pattern and unknown placeholder
+    let mut network = make_network();
+    network["device_name_pattern"] = json!("[a-z");
+    let response = client.post("/api/v1/network").json(&network).send().await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    network["device_name_pattern"] = json!("[a-z0-9-]+");
+    network["device_name_prefix"] = json!("{hostname}-");
+    let response = client.post("/api/v1/network").json(&network).send().await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    network["device_name_prefix"] = json!("{username}-");
+    let response = client.post("/api/v1/network").json(&network).send().await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let location: WireguardNetwork<Id> = response.json().await;
+    assert_eq!(location.device_name_prefix.as_deref(), Some("{username}-"));
+    assert_eq!(location.device_name_uniqueness, DeviceNameUniqueness::User);
+
+    // missing prefix
+    let response = add_device(
+        &client,
+        "admin",
+        "laptop",
+        "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=",
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    // pattern mismatch
+    let response = add_device(
+        &client,
+        "admin",
+        "admin-Laptop",
+        "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=",
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = add_device(
+        &client,
+        "admin",
+        "admin-laptop",
+        "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=",
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    // duplicate name of the same user
+    let response = add_device(
+        &client,
+        "admin",
+        "admin-laptop",
+        "hRt7ntqmkhr4x5Rwvdrr/wQinN0YVy2I0zVCS+8Wk3g=",
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // names unique in the whole location
+    network["device_name_pattern"] = json!("");
+    network["device_name_prefix"] = json!(null);
+    network["device_name_uniqueness"] = json!("location");
+    let response = client
+        .put(format!("/api/v1/network/{}", location.id))
+        .json(&network)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let location: WireguardNetwork<Id> = response.json().await;
+    assert_eq!(location.device_name_pattern, None);
+    let response = add_device(
+        &client,
+        "hpotter",
+        "admin-laptop",
+        "hRt7ntqmkhr4x5Rwvdrr/wQinN0YVy2I0zVCS+8Wk3g=",
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // names unique per user
+    network["device_name_uniqueness"] = json!("user");
+    let response = client
+        .put(format!("/api/v1/network/{}", location.id))
+        .json(&network)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = add_device(
+        &client,
+        "hpotter",
+        "admin-laptop",
+        "hRt7ntqmkhr4x5Rwvdrr/wQinN0YVy2I0zVCS+8Wk3g=",
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+}
+
+#[sqlx::test]
+async fn test_network_ip_conflicts(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, client_state) = make_test_client(pool).await;
+    authenticate_admin(&mut client).await;
+
+    let response = client
+        .post("/api/v1/network")
+        .json(&make_network())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    for (name, pubkey) in [
+        ("device1", "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU="),
+        ("device2", "AJwxGkzvVVn5Q1xjpCDFo5RJSU9KOPHeoEixYaj+20M="),
+        ("device3", "sIhx53MsX+iLk83sssybHrD7M+5m+CmpLzWL/zo8C38="),
+    ] {
+        let response = client
+            .post("/api/v1/device/admin")
+            .json(&json!({"name": name, "wireguard_pubkey": pubkey}))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    // no conflicts in addresses assigned by defguard
+    let response = client.get("/api/v1/network/ip_conflicts").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let reports: serde_json::Value = response.json().await;
+    assert_eq!(reports[0]["scanned_devices"], json!(3));
+    assert_eq!(reports[0]["conflicts"], json!([]));
+
+    // break addresses manually
+    for (device_id, ip) in [(2, "10.1.1.2"), (3, "10.2.2.2")] {
+        query(
+            "UPDATE wireguard_network_device SET wireguard_ips = ARRAY[$1::inet] \
+            WHERE device_id = $2",
+        )
+        .bind(ip)
+        .bind(device_id)
+        .execute(&client_state.pool)
+        .await
+        .unwrap();
+    }
+    let response = client.get("/api/v1/network/ip_conflicts").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let reports: serde_json::Value = response.json().await;
+    assert_eq!(
+        reports,
+        json!([{
+            "location_id": 1,
+            "location_name": "network",
+            "address": ["10.1.1.1/24"],
+            "scanned_devices": 3,
+            "conflicts": [
+                {
+                    "kind": "duplicate",
+                    "device_id": 2,
+                    "device_name": "device2",
+                    "address": "10.1.1.2",
+                    "conflicting_device_ids": [1],
+                    "remediation": "reassign",
+                    "suggested_address": "10.1.1.3",
+                },
+                {
+                    "kind": "out_of_range",
+                    "device_id": 3,
+                    "device_name": "device3",
+                    "address": "10.2.2.2",
+                    "conflicting_device_ids": [],
+                    "remediation": "reassign",
+                    "suggested_address": "10.1.1.4",
+                },
+            ],
+        }])
+    );
+
+    // only admins can verify addresses
+    let response = client.post("/api/v1/auth/logout").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client.get("/api/v1/network/ip_conflicts").send().await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test]
+async fn test_gateway_distribution(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, client_state) = make_test_client(pool).await;
+    authenticate_admin(&mut client).await;
+
+    let response = client
+        .post("/api/v1/network")
+        .json(&make_network())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let device = json!({
+        "name": "device",
+        "wireguard_pubkey": "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=",
+    });
+    let response = client
+        .post("/api/v1/device/admin")
+        .json(&device)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // distribution is disabled by default
+    let response = client
+        .get("/api/v1/network/1/gateway_distribution")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let distribution: serde_json::Value = response.json().await;
+    assert_eq!(distribution["policy"], "none");
+    assert_eq!(distribution["gateways"], json!([]));
+
+    // invalid gateways
+    let response = client
+        .put("/api/v1/network/1/gateway_distribution")
+        .json(&json!({
+            "policy": "weighted",
+            "gateways": [{"hostname": "gw1", "weight": 0}]
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = client
+        .put("/api/v1/network/1/gateway_distribution")
+        .json(&json!({
+            "policy": "weighted",
+            "gateways": [{"hostname": "gw1"}, {"hostname": "gw1"}]
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = client
+        .put("/api/v1/network/1/gateway_distribution")
+        .json(&json!({
+            "policy": "weighted",
+            "gateways": [
+                {"hostname": "gw2", "endpoint": "gw2.example.com", "weight": 3},
+                {"hostname": "gw1", "endpoint": "gw1.example.com"}
+            ]
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let distribution: serde_json::Value = response.json().await;
+    assert_eq!(distribution["policy"], "weighted");
+    assert_eq!(
+        distribution["gateways"],
+        json!([
+            {"hostname": "gw1", "endpoint": "gw1.example.com", "weight": 1, "group_id": null, "connected": false, "assigned_devices": 0},
+            {"hostname": "gw2", "endpoint": "gw2.example.com", "weight": 3, "group_id": null, "connected": false, "assigned_devices": 0}
+        ])
+    );
+    let location = WireguardNetwork::find_by_id(&client_state.pool, 1)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        location.gateway_distribution_policy,
+        GatewayDistributionPolicy::Weighted
+    );
+
+    // no gateway is connected, location endpoint is used
+    let response = client.get("/api/v1/network/1/device/1/config").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(
+        response
+            .text()
+            .await
+            .contains("Endpoint = 192.168.4.14:55555")
+    );
+
+    // assigned device gets endpoint of its gateway
+    query(
+        "INSERT INTO device_gateway_assignment (device_id, location_id, hostname) \
+        VALUES (1, 1, 'gw2')",
+    )
+    .execute(&client_state.pool)
+    .await
+    .unwrap();
+    let response = client.get("/api/v1/network/1/device/1/config").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(
+        response
+            .text()
+            .await
+            .contains("Endpoint = gw2.example.com:55555")
+    );
+
+    // disabling distribution drops assignments
+    let response = client
+        .put("/api/v1/network/1/gateway_distribution")
+        .json(&json!({"policy": "none", "gateways": []}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let distribution: serde_json::Value = response.json().await;
+    assert_eq!(distribution["gateways"], json!([]));
+    let response = client.get("/api/v1/network/1/device/1/config").send().await;
+    assert!(
+        response
+            .text()
+            .await
+            .contains("Endpoint = 192.168.4.14:55555")
+    );
+}
+
+#[sqlx::test]
+async fn test_gateway_peer_sharding(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, _) = make_test_client(pool).await;
+    authenticate_admin(&mut client).await;
+
+    let response = client
+        .post("/api/v1/network")
+        .json(&make_network())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    for (name, pubkey) in [
+        ("device1", "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU="),
+        ("device2", "hNuapt7lOxF93KUqZGUY00oKJxH8LYwwsUVB1uUa0y4="),
+        ("device3", "wYOt6ImBaQ3BEMQ3Xf5P5fTnbqwOvjcqYkkSBt+1xOg="),
+    ] {
+        let response = client
+            .post("/api/v1/device/admin")
+            .json(&json!({"name": name, "wireguard_pubkey": pubkey}))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    // sharding requires a deterministic policy and configured gateways
+    let gateways = json!([
+        {"hostname": "gw1", "endpoint": "gw1.example.com"},
+        {"hostname": "gw2", "endpoint": "gw2.example.com"}
+    ]);
+    let response = client
+        .put("/api/v1/network/1/gateway_distribution")
+        .json(&json!({"policy": "round_robin", "peer_sharding": true, "gateways": gateways}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = client
+        .put("/api/v1/network/1/gateway_distribution")
+        .json(&json!({"policy": "hash", "peer_sharding": true, "gateways": []}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = client
+        .put("/api/v1/network/1/gateway_distribution")
+        .json(&json!({
+            "policy": "group",
+            "gateways": [{"hostname": "gw1", "group_id": 100}]
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // all devices are assigned to configured gateways, even if none is connected
+    let response = client
+        .put("/api/v1/network/1/gateway_distribution")
+        .json(&json!({"policy": "hash", "peer_sharding": true, "gateways": gateways}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let distribution: serde_json::Value = response.json().await;
+    assert_eq!(distribution["peer_sharding"], true);
+    let assigned: u64 = distribution["gateways"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|gateway| gateway["assigned_devices"].as_u64().unwrap())
+        .sum();
+    assert_eq!(assigned, 3);
+
+    // device configuration points to the gateway holding its peer
+    for device_id in 1..=3 {
+        let response = client
+            .get(format!("/api/v1/network/1/device/{device_id}/config"))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let config = response.text().await;
+        assert!(
+            config.contains("Endpoint = gw1.example.com:55555")
+                || config.contains("Endpoint = gw2.example.com:55555")
+        );
+    }
+}
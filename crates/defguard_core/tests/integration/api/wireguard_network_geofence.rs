@@ -0,0 +1,74 @@
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+use super::common::{authenticate_admin, make_network, make_test_client, setup_pool};
+
+#[sqlx::test]
+async fn test_location_geofence(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, _) = make_test_client(pool).await;
+
+    // only admins can manage geofences
+    client.login_user("hpotter", "pass123").await;
+    let response = client.get("/api/v1/network/1/geofence").send().await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    authenticate_admin(&mut client).await;
+    let response = client
+        .post("/api/v1/network")
+        .json(&make_network())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // no restrictions by default
+    let response = client.get("/api/v1/network/1/geofence").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let geofence: Value = response.json().await;
+    assert_eq!(geofence["allowed_countries"], json!([]));
+    assert_eq!(geofence["blocked_countries"], json!([]));
+    assert_eq!(geofence["geoip_available"], false);
+
+    // country codes are normalized
+    let response = client
+        .put("/api/v1/network/1/geofence")
+        .json(&json!({"allowed_countries": ["pl", "DE", " PL "], "blocked_countries": ["ru"]}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let geofence: Value = response.json().await;
+    assert_eq!(geofence["allowed_countries"], json!(["DE", "PL"]));
+    assert_eq!(geofence["blocked_countries"], json!(["RU"]));
+    let response = client.get("/api/v1/network/1/geofence").send().await;
+    let geofence: Value = response.json().await;
+    assert_eq!(geofence["allowed_countries"], json!(["DE", "PL"]));
+
+    // invalid codes and conflicting lists
+    for data in [
+        json!({"allowed_countries": ["POL"]}),
+        json!({"blocked_countries": ["1A"]}),
+        json!({"allowed_countries": ["PL"], "blocked_countries": ["pl"]}),
+    ] {
+        let response = client
+            .put("/api/v1/network/1/geofence")
+            .json(&data)
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{data}");
+    }
+
+    // restrictions can be removed
+    let response = client
+        .put("/api/v1/network/1/geofence")
+        .json(&json!({}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let geofence: Value = response.json().await;
+    assert_eq!(geofence["allowed_countries"], json!([]));
+    assert_eq!(geofence["blocked_countries"], json!([]));
+
+    let response = client.get("/api/v1/network/2/geofence").send().await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
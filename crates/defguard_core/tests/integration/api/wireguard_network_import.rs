@@ -7,11 +7,14 @@ use defguard_core::{
             device::{DeviceType, UserDevice},
             wireguard::{
                 DEFAULT_DISCONNECT_THRESHOLD, DEFAULT_KEEPALIVE_INTERVAL, LocationMfaMode,
-                ServiceLocationMode,
+                PeerImportStatus, ServiceLocationMode,
             },
         },
     },
-    handlers::{Auth, wireguard::ImportedNetworkData},
+    handlers::{
+        Auth,
+        wireguard::{ImportedNetworkData, NetworkExport, NetworkMigrationReport},
+    },
 };
 use matches::assert_matches;
 use reqwest::StatusCode;
@@ -410,3 +413,175 @@ async fn test_config_import_nonadmin(_: PgPoolOptions, options: PgConnectOptions
         .await;
     assert_eq!(response.status(), StatusCode::FORBIDDEN);
 }
+
+#[sqlx::test]
+async fn test_config_migrate(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+
+    // `wg showconf` dump with peer annotations
+    let wg_config = "
+        [Interface]
+        ListenPort = 55055
+        PrivateKey = GAA2X3DW0WakGVx+DsGjhDpTgg50s1MlmrLf24Psrlg=
+
+        # Name = harry laptop
+        # Email = h.potter@hogwart.edu.uk
+        [Peer]
+        PublicKey = 2LYRr2HgSSpGCdXKDDAlcFe0Uuc6RR8TFgSquNc9VAE=
+        AllowedIPs = 10.0.0.10/32
+
+        # Email = nobody@hogwart.edu.uk
+        [Peer]
+        PublicKey = OLQNaEH3FxW0hiodaChEHoETzd+7UzcqIbsLs+X8rD0=
+        AllowedIPs = 10.0.0.11/32
+
+        [Peer]
+        PublicKey = l07+qPWs4jzW3Gp1DKbHgBMRRm4Jg3q2BJxw0ZYl6c4=
+        AllowedIPs = 10.0.0.12/32
+    ";
+    let (client, client_state) = make_test_client(pool).await;
+    let pool = client_state.pool;
+
+    // existing device matched by pubkey
+    let mut transaction = pool.begin().await.unwrap();
+    let device = Device::new(
+        "test device".into(),
+        "l07+qPWs4jzW3Gp1DKbHgBMRRm4Jg3q2BJxw0ZYl6c4=".into(),
+        1,
+        DeviceType::User,
+        None,
+        true,
+    )
+    .save(&mut *transaction)
+    .await
+    .unwrap();
+    transaction.commit().await.unwrap();
+
+    let auth = Auth::new("admin", "pass123");
+    let response = &client.post("/api/v1/auth").json(&auth).send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // address is required for `wg showconf` dumps
+    let response = client
+        .post("/api/v1/network/migrate")
+        .json(&json!({"name": "network", "endpoint": "192.168.1.1", "config": wg_config, "allowed_groups": []}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = client
+        .post("/api/v1/network/migrate")
+        .json(&json!({"name": "network", "endpoint": "192.168.1.1", "config": wg_config, "allowed_groups": [], "address": "10.0.0.1/24"}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let report: NetworkMigrationReport = response.json().await;
+    assert_eq!(report.network.name, "network");
+    assert_eq!(report.network.address, vec!["10.0.0.1/24".parse().unwrap()]);
+
+    assert_eq!(report.peers.len(), 3);
+    assert_eq!(report.peers[0].name, "harry laptop");
+    assert_matches!(
+        report.peers[0].status,
+        PeerImportStatus::Created { user_id: 2, .. }
+    );
+    assert_eq!(report.peers[1].status, PeerImportStatus::Unmapped);
+    assert_eq!(
+        report.peers[2].status,
+        PeerImportStatus::Matched {
+            device_id: device.id
+        }
+    );
+
+    // created device keeps its imported IP
+    let user_info = fetch_user_details(&client, "hpotter").await;
+    assert_eq!(user_info.devices.len(), 1);
+    assert_eq!(user_info.devices[0].device.name, "harry laptop");
+    assert_eq!(
+        user_info.devices[0].networks[0].device_wireguard_ips,
+        vec!["10.0.0.10"]
+    );
+}
+
+#[sqlx::test]
+async fn test_config_export(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+
+    let wg_config = "
+        [Interface]
+        PrivateKey = GAA2X3DW0WakGVx+DsGjhDpTgg50s1MlmrLf24Psrlg=
+        Address = 10.0.0.1/24
+        ListenPort = 55055
+        DNS = 10.0.0.2
+
+        [Peer]
+        PublicKey = l07+qPWs4jzW3Gp1DKbHgBMRRm4Jg3q2BJxw0ZYl6c4=
+        AllowedIPs = 10.0.0.12/32
+    ";
+    let (client, client_state) = make_test_client(pool).await;
+    let pool = client_state.pool;
+
+    let mut transaction = pool.begin().await.unwrap();
+    let device = Device::new(
+        "test device".into(),
+        "l07+qPWs4jzW3Gp1DKbHgBMRRm4Jg3q2BJxw0ZYl6c4=".into(),
+        1,
+        DeviceType::User,
+        None,
+        true,
+    )
+    .save(&mut *transaction)
+    .await
+    .unwrap();
+    transaction.commit().await.unwrap();
+
+    let auth = Auth::new("admin", "pass123");
+    let response = &client.post("/api/v1/auth").json(&auth).send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = client
+        .post("/api/v1/network/import")
+        .json(&json!({"name": "network", "endpoint": "192.168.1.1", "config": wg_config, "allowed_groups": []}))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let network = response.json::<ImportedNetworkData>().await.network;
+
+    let response = client
+        .get(format!("/api/v1/network/{}/export", network.id))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let export: NetworkExport = response.json().await;
+    assert_eq!(
+        export.server_config,
+        "[Interface]\n\
+        PrivateKey = GAA2X3DW0WakGVx+DsGjhDpTgg50s1MlmrLf24Psrlg=\n\
+        Address = 10.0.0.1/24\n\
+        ListenPort = 55055\n\
+        \n\
+        # Name = test device\n\
+        # Email = admin@defguard\n\
+        [Peer]\n\
+        PublicKey = l07+qPWs4jzW3Gp1DKbHgBMRRm4Jg3q2BJxw0ZYl6c4=\n\
+        AllowedIPs = 10.0.0.12/32\n"
+    );
+    assert_eq!(export.peers.len(), 1);
+    assert_eq!(export.peers[0].device_id, device.id);
+    assert!(export.peers[0].config.contains("Address = 10.0.0.12\n"));
+    assert!(
+        export.peers[0]
+            .config
+            .contains("PublicKey = Y5ewP5RXstQd71gkmS/M0xL8wi0yVbbVY/ocLM4cQ1Y=\n")
+    );
+
+    // non-admin can't export network
+    let auth = Auth::new("hpotter", "pass123");
+    let response = &client.post("/api/v1/auth").json(&auth).send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .get(format!("/api/v1/network/{}/export", network.id))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
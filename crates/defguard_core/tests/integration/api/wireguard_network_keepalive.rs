@@ -0,0 +1,84 @@
+use chrono::{Duration, Utc};
+use defguard_common::db::NoId;
+use defguard_core::db::models::wireguard_peer_stats::WireguardPeerStats;
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+use super::common::{authenticate_admin, make_network, make_test_client, setup_pool};
+
+#[sqlx::test]
+async fn test_keepalive_recommendation(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (mut client, client_state) = make_test_client(pool).await;
+    let pool = client_state.pool;
+
+    authenticate_admin(&mut client).await;
+    let response = client
+        .post("/api/v1/network")
+        .json(&make_network())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response = client
+        .post("/api/v1/device/admin")
+        .json(&json!({
+            "name": "device",
+            "wireguard_pubkey": "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=",
+        }))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // no handshakes yet
+    let response = client.get("/api/v1/network/1/keepalive").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let recommendation: Value = response.json().await;
+    assert_eq!(recommendation["current_keepalive"], 25);
+    assert_eq!(recommendation["recommended_keepalive"], 25);
+    assert_eq!(recommendation["signals"]["handshakes"], 0);
+
+    // every 10th handshake is late
+    let now = Utc::now().naive_utc();
+    let mut latest_handshake = now - Duration::hours(6);
+    for sample in 0..=120 {
+        if sample % 10 == 0 {
+            latest_handshake += Duration::seconds(240);
+        } else {
+            latest_handshake += Duration::seconds(120);
+        }
+        WireguardPeerStats {
+            id: NoId,
+            device_id: 1,
+            collected_at: latest_handshake,
+            network: 1,
+            endpoint: Some("11.22.33.44".into()),
+            upload: sample,
+            download: sample,
+            latest_handshake,
+            allowed_ips: Some("10.1.1.0/24".into()),
+            gateway: None,
+        }
+        .save(&pool)
+        .await
+        .unwrap();
+    }
+
+    let response = client.get("/api/v1/network/1/keepalive").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let recommendation: Value = response.json().await;
+    assert_eq!(recommendation["signals"]["handshakes"], 120);
+    assert_eq!(recommendation["signals"]["handshake_gaps"], 12);
+    assert_eq!(recommendation["recommended_keepalive"], 12);
+
+    // network is not modified until recommendation is applied
+    let response = client.get("/api/v1/network/1").send().await;
+    let network: Value = response.json().await;
+    assert_eq!(network["keepalive_interval"], 25);
+
+    let response = client.post("/api/v1/network/1/keepalive").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client.get("/api/v1/network/1").send().await;
+    let network: Value = response.json().await;
+    assert_eq!(network["keepalive_interval"], 12);
+}
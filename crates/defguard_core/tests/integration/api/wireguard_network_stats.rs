@@ -3,6 +3,7 @@ use defguard_common::db::{Id, NoId};
 use defguard_core::{
     db::models::{
         device::Device,
+        device_endpoint_change::DeviceEndpointChange,
         wireguard::{
             WireguardDeviceStatsRow, WireguardDeviceTransferRow, WireguardNetworkStats,
             WireguardUserStatsRow,
@@ -13,10 +14,10 @@ use defguard_core::{
 };
 use reqwest::StatusCode;
 use serde::Deserialize;
-use serde_json::json;
+use serde_json::{Value, json};
 use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
 
-use super::common::{make_network, make_test_client, setup_pool};
+use super::common::{authenticate_admin, make_network, make_test_client, setup_pool};
 
 static DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:00Z";
 
@@ -107,6 +108,7 @@ async fn test_stats(_: PgPoolOptions, options: PgConnectOptions) {
                 download: (samples - i) * 20 * (d as i64 + 1),
                 latest_handshake: now - Duration::minutes(i * 10),
                 allowed_ips: Some("10.1.1.0/24".into()),
+                gateway: None,
             }
             .save(&pool)
             .await
@@ -288,3 +290,78 @@ async fn test_stats(_: PgPoolOptions, options: PgConnectOptions) {
             .sum::<i64>()
     );
 }
+
+#[sqlx::test]
+async fn test_device_endpoint_history(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+
+    let (mut client, client_state) = make_test_client(pool).await;
+    let pool = client_state.pool;
+
+    authenticate_admin(&mut client).await;
+    let response = client
+        .post("/api/v1/network")
+        .json(&make_network())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let device = json!({
+        "name": "laptop",
+        "wireguard_pubkey": "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=",
+    });
+    let response = client
+        .post("/api/v1/device/admin")
+        .json(&device)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let now = Utc::now().naive_utc();
+    for (days, ip) in [
+        (40, "11.22.33.44"),
+        (20, "55.66.77.88"),
+        (10, "11.22.33.44"),
+        (1, "fd00::1"),
+    ] {
+        DeviceEndpointChange::new(1, 1, ip.parse().unwrap(), now - Duration::days(days))
+            .save(&pool)
+            .await
+            .unwrap();
+    }
+
+    // last 30 days by default, including the address in use 30 days ago
+    let response = client.get("/api/v1/device/1/endpoint_history").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let history: Value = response.json().await;
+    assert_eq!(history["distinct_ips"], 3);
+    let changes = history["changes"].as_array().unwrap();
+    assert_eq!(changes.len(), 4);
+    assert!(changes[0]["ip"].as_str().unwrap().starts_with("fd00::1"));
+    assert!(
+        changes[3]["ip"]
+            .as_str()
+            .unwrap()
+            .starts_with("11.22.33.44")
+    );
+
+    let from = (Utc::now() - Duration::days(5)).format(DATE_FORMAT);
+    let response = client
+        .get(format!("/api/v1/device/1/endpoint_history?from={from}"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let history: Value = response.json().await;
+    assert_eq!(history["distinct_ips"], 2);
+
+    let from = (Utc::now() + Duration::days(1)).format(DATE_FORMAT);
+    let response = client
+        .get(format!("/api/v1/device/1/endpoint_history?from={from}"))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // only the owner and admins can view device history
+    client.login_user("hpotter", "pass123").await;
+    let response = client.get("/api/v1/device/1/endpoint_history").send().await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
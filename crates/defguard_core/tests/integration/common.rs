@@ -1,7 +1,7 @@
 use defguard_common::config::{DefGuardConfig, SERVER_CONFIG};
 use defguard_core::db::User;
 use reqwest::Url;
-use secrecy::ExposeSecret;
+use secrecy::{ExposeSecret, SecretString};
 use sqlx::PgPool;
 
 /// Allows overriding the default DefGuard URL for tests, as during the tests, the server has a random port, making the URL unpredictable beforehand.
@@ -10,6 +10,9 @@ pub(crate) fn init_config(custom_defguard_url: Option<&str>) -> DefGuardConfig {
     let url = custom_defguard_url.unwrap_or("http://localhost:8000");
     let mut config = DefGuardConfig::new_test_config();
     config.url = Url::parse(url).unwrap();
+    config.graphql_enabled = true;
+    config.dns_canary_domain = Some("canary.defguard.test".into());
+    config.dns_canary_token = Some(SecretString::from("canary-token"));
     let _ = SERVER_CONFIG.set(config.clone());
     config
 }
@@ -113,11 +113,16 @@ impl MockGateway {
     }
 
     pub(crate) async fn connect_to_updates_stream(&mut self) {
+        self.try_connect_to_updates_stream().await.unwrap();
+    }
+
+    pub(crate) async fn try_connect_to_updates_stream(&mut self) -> Result<(), Status> {
         let request = Request::new(());
 
-        let updates_stream = self.client.updates(request).await.unwrap().into_inner();
+        let updates_stream = self.client.updates(request).await?.into_inner();
 
         self.updates_stream = Some(updates_stream);
+        Ok(())
     }
 
     pub(crate) fn disconnect_from_updates_stream(&mut self) {
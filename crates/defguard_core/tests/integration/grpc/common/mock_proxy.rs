@@ -0,0 +1,207 @@
+use std::{sync::Mutex, time::Duration};
+
+use axum::http::Uri;
+use defguard_common::db::models::settings::initialize_current_settings;
+use defguard_core::{
+    db::{AppEvent, GatewayEvent},
+    events::BidiStreamEvent,
+    grpc::run_proxy_message_loop,
+};
+use defguard_mail::Mail;
+use defguard_proto::proxy::{
+    CoreRequest, CoreResponse, DeviceInfo, core_request, core_response,
+    proxy_client::ProxyClient,
+    proxy_server::{Proxy, ProxyServer},
+};
+use sqlx::PgPool;
+use tokio::{
+    sync::{
+        broadcast,
+        mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel},
+    },
+    task::JoinHandle,
+    time::timeout,
+};
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tonic::{Request, Response, Status, Streaming, transport::Server};
+
+use super::create_client_channel;
+use crate::common::{init_config, initialize_users};
+
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+type RequestStream = UnboundedReceiverStream<Result<CoreRequest, Status>>;
+
+// Proxy gRPC service accepting a single bi-directional stream from core.
+struct MockProxyService {
+    requests: Mutex<Option<RequestStream>>,
+    responses_tx: UnboundedSender<Streaming<CoreResponse>>,
+}
+
+#[tonic::async_trait]
+impl Proxy for MockProxyService {
+    type BidiStream = RequestStream;
+
+    async fn bidi(
+        &self,
+        request: Request<Streaming<CoreResponse>>,
+    ) -> Result<Response<Self::BidiStream>, Status> {
+        let requests = self
+            .requests
+            .lock()
+            .expect("failed to acquire lock on requests")
+            .take()
+            .ok_or_else(|| Status::already_exists("core is already connected"))?;
+        self.responses_tx
+            .send(request.into_inner())
+            .map_err(|_| Status::unavailable("mock proxy has been dropped"))?;
+
+        Ok(Response::new(requests))
+    }
+}
+
+/// Fake Defguard Proxy connected to core's message loop over an in-memory stream.
+/// Requests are sent the way a real proxy would, and core responses are returned.
+pub(crate) struct MockProxy {
+    next_id: u64,
+    requests_tx: Option<UnboundedSender<Result<CoreRequest, Status>>>,
+    responses: Streaming<CoreResponse>,
+    server_task_handle: JoinHandle<()>,
+    message_loop_handle: Option<JoinHandle<Result<(), anyhow::Error>>>,
+    pub(crate) mail_rx: UnboundedReceiver<Mail>,
+    pub(crate) bidi_event_rx: UnboundedReceiver<BidiStreamEvent>,
+    // keep receivers open, so that core can send events
+    _wireguard_rx: broadcast::Receiver<GatewayEvent>,
+    _webhook_rx: UnboundedReceiver<AppEvent>,
+}
+
+impl Drop for MockProxy {
+    fn drop(&mut self) {
+        // explicitly stop spawned tasks
+        self.server_task_handle.abort();
+        if let Some(handle) = &self.message_loop_handle {
+            handle.abort();
+        }
+    }
+}
+
+impl MockProxy {
+    #[must_use]
+    pub(crate) async fn new(pool: &PgPool) -> Self {
+        let config = init_config(None);
+        initialize_users(pool, &config).await;
+        initialize_current_settings(pool)
+            .await
+            .expect("Could not initialize settings");
+
+        // spawn proxy gRPC server
+        let (client_stream, server_stream) = tokio::io::duplex(1024);
+        let (requests_tx, requests_rx) = unbounded_channel();
+        let (responses_tx, mut responses_rx) = unbounded_channel();
+        let service = MockProxyService {
+            requests: Mutex::new(Some(UnboundedReceiverStream::new(requests_rx))),
+            responses_tx,
+        };
+        let server_task_handle = tokio::spawn(async move {
+            Server::builder()
+                .add_service(ProxyServer::new(service))
+                .serve_with_incoming(tokio_stream::once(Ok::<_, std::io::Error>(server_stream)))
+                .await
+                .map_err(|err| eprintln!("Unexpected mock proxy server error: {err}"))
+                .unwrap()
+        });
+
+        // connect core to proxy
+        let (wireguard_tx, wireguard_rx) = broadcast::channel(16);
+        let (mail_tx, mail_rx) = unbounded_channel();
+        let (webhook_tx, webhook_rx) = unbounded_channel();
+        let (bidi_event_tx, bidi_event_rx) = unbounded_channel();
+        let channel = create_client_channel(client_stream).await;
+        let (tx, rx) = unbounded_channel();
+        let mut resp_stream = ProxyClient::new(channel)
+            .bidi(UnboundedReceiverStream::new(rx))
+            .await
+            .expect("failed to connect to mock proxy")
+            .into_inner();
+        let responses = responses_rx
+            .recv()
+            .await
+            .expect("failed to receive core response stream");
+        let pool = pool.clone();
+        let message_loop_handle = tokio::spawn(async move {
+            let endpoint_uri = Uri::from_static("http://proxy.test");
+            run_proxy_message_loop(
+                pool,
+                wireguard_tx,
+                mail_tx,
+                webhook_tx,
+                bidi_event_tx,
+                tx,
+                &mut resp_stream,
+                &endpoint_uri,
+            )
+            .await
+        });
+
+        Self {
+            next_id: 0,
+            requests_tx: Some(requests_tx),
+            responses,
+            server_task_handle,
+            message_loop_handle: Some(message_loop_handle),
+            mail_rx,
+            bidi_event_rx,
+            _wireguard_rx: wireguard_rx,
+            _webhook_rx: webhook_rx,
+        }
+    }
+
+    /// Send a request to core and wait for the response, which must carry the request ID.
+    pub(crate) async fn send(
+        &mut self,
+        payload: Option<core_request::Payload>,
+    ) -> Option<core_response::Payload> {
+        self.next_id += 1;
+        let request = CoreRequest {
+            id: self.next_id,
+            device_info: Some(DeviceInfo {
+                ip_address: "127.0.0.1".into(),
+                user_agent: Some("mock-proxy".into()),
+                version: None,
+                platform: None,
+            }),
+            payload,
+        };
+        self.requests_tx
+            .as_ref()
+            .expect("mock proxy is disconnected")
+            .send(Ok(request))
+            .expect("failed to send request to core");
+
+        let response = timeout(RESPONSE_TIMEOUT, self.responses.message())
+            .await
+            .expect("timed out waiting for core response")
+            .expect("failed to receive core response")
+            .expect("core closed the stream");
+        assert_eq!(
+            response.id, self.next_id,
+            "response ID doesn't match request"
+        );
+
+        response.payload
+    }
+
+    /// Close the stream and return the result of core message loop.
+    pub(crate) async fn disconnect(&mut self) -> Result<(), anyhow::Error> {
+        self.requests_tx = None;
+        let handle = self
+            .message_loop_handle
+            .take()
+            .expect("mock proxy is already disconnected");
+
+        timeout(RESPONSE_TIMEOUT, handle)
+            .await
+            .expect("timed out waiting for core message loop to finish")
+            .expect("core message loop panicked")
+    }
+}
@@ -29,6 +29,7 @@ use tower::service_fn;
 use crate::common::{init_config, initialize_users};
 
 pub mod mock_gateway;
+pub mod mock_proxy;
 
 pub struct TestGrpcServer {
     grpc_server_task_handle: JoinHandle<()>,
@@ -164,6 +165,7 @@ pub(crate) async fn make_grpc_test_server(pool: &PgPool) -> TestGrpcServer {
         failed_logins,
         grpc_event_tx,
         Default::default(),
+        pool.clone(),
     )
     .await
     .unwrap();
@@ -8,10 +8,11 @@ use claims::{assert_err_eq, assert_matches};
 use defguard_common::db::{Id, NoId, setup_pool};
 use defguard_core::{
     db::{
-        Device, User, WireguardNetwork,
+        Device, GatewayEvent, User, WireguardNetwork,
         models::{
-            device::DeviceType,
-            wireguard::{LocationMfaMode, ServiceLocationMode},
+            device::{DeviceInfo, DeviceType},
+            gateway_distribution::LocationGateway,
+            wireguard::{GatewayDistributionPolicy, LocationMfaMode, ServiceLocationMode},
             wireguard_peer_stats::WireguardPeerStats,
         },
     },
@@ -20,9 +21,10 @@ use defguard_core::{
     grpc::MIN_GATEWAY_VERSION,
 };
 use defguard_proto::{
-    enterprise::firewall::FirewallPolicy,
-    gateway::{Configuration, PeerStats, StatsUpdate, Update, stats_update::Payload, update},
+    enterprise::firewall::{FirewallConfig, FirewallPolicy},
+    gateway::{Configuration, Peer, PeerStats, StatsUpdate, Update, stats_update::Payload, update},
 };
+use prost::Message;
 use semver::Version;
 use sqlx::{
     PgPool,
@@ -333,6 +335,7 @@ async fn test_vpn_client_disconnected(_: PgPoolOptions, options: PgConnectOption
             download: 0,
             latest_handshake: now.checked_sub_days(Days::new(1)).unwrap(),
             allowed_ips: None,
+            gateway: None,
         };
         client_map
             .connect_vpn_client(
@@ -672,3 +675,259 @@ async fn test_device_pubkey_change(_: PgPoolOptions, options: PgConnectOptions)
         } if ((location.id == test_location.id) & (device.id == test_device.id))
     );
 }
+
+#[sqlx::test]
+async fn test_gateway_updates_between_config_and_subscription(
+    _: PgPoolOptions,
+    options: PgConnectOptions,
+) {
+    let pool = setup_pool(options).await;
+    let (test_server, mut gateway, test_location, _test_user) =
+        setup_test_server(pool.clone()).await;
+
+    // updates stream requires configuration snapshot
+    let status = gateway.try_connect_to_updates_stream().await.unwrap_err();
+    assert_eq!(status.code(), Code::FailedPrecondition);
+
+    gateway.get_gateway_config().await.unwrap();
+
+    // updates made after configuration was sent, but before gateway subscribed aren't lost
+    test_server.send_wireguard_event(GatewayEvent::FirewallDisabled(test_location.id));
+    test_server.send_wireguard_event(GatewayEvent::FirewallDisabled(test_location.id));
+    sleep(Duration::from_millis(100)).await;
+    gateway.connect_to_updates_stream().await;
+    assert!(gateway.receive_next_update().await.is_some());
+    assert!(gateway.receive_next_update().await.is_some());
+    assert!(gateway.receive_next_update().await.is_none());
+
+    // updates made after subscription are sent once
+    test_server.send_wireguard_event(GatewayEvent::FirewallDisabled(test_location.id));
+    assert!(gateway.receive_next_update().await.is_some());
+    assert!(gateway.receive_next_update().await.is_none());
+
+    // reconnecting requires fetching configuration again
+    gateway.disconnect_from_updates_stream();
+    let status = gateway.try_connect_to_updates_stream().await.unwrap_err();
+    assert_eq!(status.code(), Code::FailedPrecondition);
+    gateway.get_gateway_config().await.unwrap();
+    gateway.connect_to_updates_stream().await;
+    assert!(gateway.receive_next_update().await.is_none());
+}
+
+#[sqlx::test]
+async fn test_gateway_journal_events(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (test_server, _gateway, test_location, _test_user) = setup_test_server(pool.clone()).await;
+
+    let peer = Peer {
+        pubkey: "peer".into(),
+        allowed_ips: vec!["10.1.1.2".into()],
+        preshared_key: Some("preshared-key".into()),
+        keepalive_interval: Some(25),
+    };
+    test_server.send_wireguard_event(GatewayEvent::NetworkModified(
+        test_location.id,
+        test_location.clone(),
+        vec![peer],
+        None,
+    ));
+    test_server.send_wireguard_event(GatewayEvent::FirewallDisabled(test_location.id));
+    sleep(Duration::from_millis(100)).await;
+
+    let entries: Vec<(i64, String, Option<Vec<u8>>)> = sqlx::query_as(
+        "SELECT epoch, event, payload FROM gateway_config_journal WHERE location_id = $1 \
+        ORDER BY epoch",
+    )
+    .bind(test_location.id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    let events: Vec<_> = entries
+        .iter()
+        .map(|(epoch, event, _)| (*epoch, event.as_str()))
+        .collect();
+    assert_eq!(events, [(1, "network_modified"), (2, "firewall_disabled")]);
+
+    // private and preshared keys aren't journaled
+    let update = Update::decode(entries[0].2.as_deref().unwrap()).unwrap();
+    let Some(update::Update::Network(config)) = update.update else {
+        panic!("expected network update");
+    };
+    assert!(!test_location.prvkey.is_empty());
+    assert!(config.prvkey.is_empty());
+    assert_eq!(config.peers[0].pubkey, "peer");
+    assert_eq!(config.peers[0].preshared_key, None);
+
+    // journal of deleted location is removed
+    test_server.send_wireguard_event(GatewayEvent::NetworkDeleted(
+        test_location.id,
+        test_location.name.clone(),
+    ));
+    sleep(Duration::from_millis(100)).await;
+    let count: i64 =
+        sqlx::query_scalar("SELECT count(*) FROM gateway_config_journal WHERE location_id = $1")
+            .bind(test_location.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(count, 0);
+}
+
+#[sqlx::test]
+async fn test_gateway_firewall_config_versions(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let (test_server, _gateway, test_location, _test_user) = setup_test_server(pool.clone()).await;
+
+    let allow = FirewallConfig {
+        default_policy: FirewallPolicy::Allow.into(),
+        ..Default::default()
+    };
+    let deny = FirewallConfig {
+        default_policy: FirewallPolicy::Deny.into(),
+        ..Default::default()
+    };
+    // unchanged configuration isn't recorded again
+    for config in [allow.clone(), allow, deny] {
+        test_server.send_wireguard_event(GatewayEvent::FirewallConfigChanged(
+            test_location.id,
+            config,
+        ));
+    }
+    sleep(Duration::from_millis(100)).await;
+
+    let versions: Vec<(i64, Option<i64>)> = sqlx::query_as(
+        "SELECT version, rollback_of FROM firewall_config_version WHERE location_id = $1 \
+        ORDER BY version",
+    )
+    .bind(test_location.id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(versions, vec![(1, None), (2, None)]);
+}
+
+#[sqlx::test]
+async fn test_gateway_peer_sharding(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let test_server = make_grpc_test_server(&pool).await;
+
+    // location with peers sharded between two gateways
+    let mut location = WireguardNetwork::new(
+        "sharded location".to_string(),
+        vec!["10.2.0.1/24".parse().unwrap()],
+        1000,
+        "endpoint1".to_string(),
+        None,
+        Vec::new(),
+        100,
+        100,
+        false,
+        false,
+        LocationMfaMode::Disabled,
+        ServiceLocationMode::Disabled,
+    );
+    location.gateway_distribution_policy = GatewayDistributionPolicy::Hash;
+    location.gateway_peer_sharding = true;
+    let location = location.save(&pool).await.unwrap();
+    for hostname in ["gw1", "gw2"] {
+        LocationGateway::new(location.id, hostname.into(), None, 1, None)
+            .save(&pool)
+            .await
+            .unwrap();
+    }
+
+    let test_user = User::find_by_username(&pool, "hpotter")
+        .await
+        .unwrap()
+        .unwrap();
+    let mut pubkeys = Vec::new();
+    for index in 0..8 {
+        let device = add_device(&pool, &test_user, index).await;
+        pubkeys.push(device.device.wireguard_pubkey);
+    }
+
+    let token = location.generate_gateway_token().unwrap();
+    let mut gateway_1 = MockGateway::new(
+        test_server.client_channel.clone(),
+        MIN_GATEWAY_VERSION,
+        Some(token.clone()),
+        Some("gw1".into()),
+    )
+    .await;
+    let mut gateway_2 = MockGateway::new(
+        test_server.client_channel.clone(),
+        MIN_GATEWAY_VERSION,
+        Some(token),
+        Some("gw2".into()),
+    )
+    .await;
+
+    // each gateway receives its share of peers
+    let peers_1: Vec<String> = gateway_1
+        .get_gateway_config()
+        .await
+        .unwrap()
+        .into_inner()
+        .peers
+        .into_iter()
+        .map(|peer| peer.pubkey)
+        .collect();
+    let peers_2: Vec<String> = gateway_2
+        .get_gateway_config()
+        .await
+        .unwrap()
+        .into_inner()
+        .peers
+        .into_iter()
+        .map(|peer| peer.pubkey)
+        .collect();
+    assert!(!peers_1.is_empty());
+    assert!(!peers_2.is_empty());
+    assert_eq!(peers_1.len() + peers_2.len(), pubkeys.len());
+    for pubkey in &pubkeys {
+        assert!(peers_1.contains(pubkey) != peers_2.contains(pubkey));
+    }
+
+    // new peer is sent only to its gateway
+    gateway_1.connect_to_updates_stream().await;
+    gateway_2.connect_to_updates_stream().await;
+    let device_info = add_device(&pool, &test_user, 8).await;
+    let pubkey = device_info.device.wireguard_pubkey.clone();
+    test_server.send_wireguard_event(GatewayEvent::DeviceCreated(device_info));
+    // sharded updates wait for device assignment, give them more time than a single poll
+    let mut received = Vec::new();
+    for _ in 0..10 {
+        received.extend(gateway_1.receive_next_update().await);
+        received.extend(gateway_2.receive_next_update().await);
+        if !received.is_empty() {
+            break;
+        }
+    }
+    received.extend(gateway_1.receive_next_update().await);
+    received.extend(gateway_2.receive_next_update().await);
+    assert_eq!(received.len(), 1);
+    assert_matches!(
+        &received[0].update,
+        Some(update::Update::Peer(peer)) if peer.pubkey == pubkey
+    );
+}
+
+async fn add_device(pool: &PgPool, user: &User<Id>, index: u8) -> DeviceInfo {
+    let device = Device::new(
+        format!("device {index}"),
+        format!("{index:0>43}="),
+        user.id,
+        DeviceType::User,
+        None,
+        true,
+    )
+    .save(pool)
+    .await
+    .unwrap();
+    let mut conn = pool.acquire().await.unwrap();
+    let (network_info, _) = device.add_to_all_networks(&mut conn).await.unwrap();
+    DeviceInfo {
+        device,
+        network_info,
+    }
+}
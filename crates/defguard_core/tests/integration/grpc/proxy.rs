@@ -0,0 +1,483 @@
+use std::collections::BTreeSet;
+
+use chrono::Utc;
+use defguard_common::db::{
+    Id,
+    models::{Settings, settings::update_current_settings},
+    setup_pool,
+};
+use defguard_core::{
+    db::{
+        User, WireguardNetwork,
+        models::{
+            enrollment::{ENROLLMENT_TOKEN_TYPE, Token},
+            wireguard::{LocationMfaMode, ServiceLocationMode},
+        },
+    },
+    events::{BidiStreamEventType, PasswordResetEvent},
+};
+use defguard_proto::proxy::{
+    ActivateUserRequest, AuthCallbackRequest, AuthInfoRequest, ClientMfaFinishRequest,
+    ClientMfaOidcAuthenticateRequest, ClientMfaStartRequest, ClientMfaTokenValidationRequest,
+    CodeMfaSetupFinishRequest, CodeMfaSetupStartRequest, EnrollmentStartRequest, ExistingDevice,
+    InstanceInfoRequest, MfaMethod, NewDevice, PasswordResetInitializeRequest,
+    PasswordResetRequest, PasswordResetStartRequest, RegisterMobileAuthRequest, core_request,
+    core_response,
+};
+use sqlx::{
+    PgPool,
+    postgres::{PgConnectOptions, PgPoolOptions},
+    query, query_scalar,
+};
+
+use crate::grpc::common::mock_proxy::MockProxy;
+
+// Every request variant has to be listed here, so new payloads can't be added without a test.
+fn request_kind(payload: &core_request::Payload) -> &'static str {
+    match payload {
+        core_request::Payload::EnrollmentStart(_) => "enrollment_start",
+        core_request::Payload::ActivateUser(_) => "activate_user",
+        core_request::Payload::NewDevice(_) => "new_device",
+        core_request::Payload::ExistingDevice(_) => "existing_device",
+        core_request::Payload::PasswordResetInit(_) => "password_reset_init",
+        core_request::Payload::PasswordResetStart(_) => "password_reset_start",
+        core_request::Payload::PasswordReset(_) => "password_reset",
+        core_request::Payload::ClientMfaStart(_) => "client_mfa_start",
+        core_request::Payload::ClientMfaFinish(_) => "client_mfa_finish",
+        core_request::Payload::InstanceInfo(_) => "instance_info",
+        core_request::Payload::AuthInfo(_) => "auth_info",
+        core_request::Payload::AuthCallback(_) => "auth_callback",
+        core_request::Payload::ClientMfaOidcAuthenticate(_) => "client_mfa_oidc_authenticate",
+        core_request::Payload::CodeMfaSetupStart(_) => "code_mfa_setup_start",
+        core_request::Payload::CodeMfaSetupFinish(_) => "code_mfa_setup_finish",
+        core_request::Payload::RegisterMobileAuth(_) => "register_mobile_auth",
+        core_request::Payload::ClientMfaTokenValidation(_) => "client_mfa_token_validation",
+    }
+}
+const REQUEST_KINDS: usize = 17;
+
+fn response_kind(payload: Option<&core_response::Payload>) -> &'static str {
+    match payload {
+        None => "none",
+        Some(core_response::Payload::Empty(())) => "empty",
+        Some(core_response::Payload::EnrollmentStart(_)) => "enrollment_start",
+        Some(core_response::Payload::DeviceConfig(_)) => "device_config",
+        Some(core_response::Payload::PasswordResetStart(_)) => "password_reset_start",
+        Some(core_response::Payload::ClientMfaStart(_)) => "client_mfa_start",
+        Some(core_response::Payload::ClientMfaFinish(_)) => "client_mfa_finish",
+        Some(core_response::Payload::CoreError(_)) => "core_error",
+        Some(core_response::Payload::InstanceInfo(_)) => "instance_info",
+        Some(core_response::Payload::AuthInfo(_)) => "auth_info",
+        Some(core_response::Payload::AuthCallback(_)) => "auth_callback",
+        Some(core_response::Payload::CodeMfaSetupStartResponse(_)) => {
+            "code_mfa_setup_start_response"
+        }
+        Some(core_response::Payload::CodeMfaSetupFinishResponse(_)) => {
+            "code_mfa_setup_finish_response"
+        }
+        Some(core_response::Payload::ClientMfaTokenValidation(_)) => "client_mfa_token_validation",
+    }
+}
+
+async fn password_hash(pool: &PgPool) -> Option<String> {
+    query_scalar("SELECT password_hash FROM \"user\" WHERE username = 'hpotter'")
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[sqlx::test]
+async fn test_proxy_request_conformance(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let mut proxy = MockProxy::new(&pool).await;
+
+    // invalid requests must be answered with an error, without breaking the stream
+    let requests = [
+        (
+            core_request::Payload::EnrollmentStart(EnrollmentStartRequest {
+                token: "invalid".into(),
+            }),
+            "core_error",
+        ),
+        (
+            core_request::Payload::ActivateUser(ActivateUserRequest {
+                phone_number: None,
+                password: "Passw0rd!".into(),
+                token: None,
+            }),
+            "core_error",
+        ),
+        (
+            core_request::Payload::NewDevice(NewDevice {
+                name: "laptop".into(),
+                pubkey: "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=".into(),
+                token: None,
+            }),
+            "core_error",
+        ),
+        (
+            core_request::Payload::ExistingDevice(ExistingDevice {
+                pubkey: "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=".into(),
+                token: None,
+            }),
+            "core_error",
+        ),
+        // existence of users isn't revealed
+        (
+            core_request::Payload::PasswordResetInit(PasswordResetInitializeRequest {
+                email: "nobody@example.com".into(),
+            }),
+            "empty",
+        ),
+        (
+            core_request::Payload::PasswordResetStart(PasswordResetStartRequest {
+                token: "invalid".into(),
+            }),
+            "core_error",
+        ),
+        (
+            core_request::Payload::PasswordReset(PasswordResetRequest {
+                password: "Passw0rd!".into(),
+                token: None,
+            }),
+            "core_error",
+        ),
+        (
+            core_request::Payload::ClientMfaStart(ClientMfaStartRequest {
+                location_id: 1000,
+                pubkey: "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=".into(),
+                method: MfaMethod::Totp as i32,
+            }),
+            "core_error",
+        ),
+        (
+            core_request::Payload::ClientMfaFinish(ClientMfaFinishRequest {
+                token: "invalid".into(),
+                code: Some("123456".into()),
+                auth_pub_key: None,
+            }),
+            "core_error",
+        ),
+        (
+            core_request::Payload::InstanceInfo(InstanceInfoRequest {
+                token: "invalid".into(),
+            }),
+            "core_error",
+        ),
+        (
+            core_request::Payload::AuthInfo(AuthInfoRequest {
+                redirect_url: "invalid".into(),
+                state: None,
+            }),
+            "core_error",
+        ),
+        (
+            core_request::Payload::AuthCallback(AuthCallbackRequest {
+                code: "code".into(),
+                nonce: "nonce".into(),
+                callback_url: "invalid".into(),
+            }),
+            "core_error",
+        ),
+        (
+            core_request::Payload::ClientMfaOidcAuthenticate(ClientMfaOidcAuthenticateRequest {
+                code: "code".into(),
+                state: "invalid".into(),
+                callback_url: "invalid".into(),
+                nonce: "nonce".into(),
+            }),
+            "core_error",
+        ),
+        (
+            core_request::Payload::CodeMfaSetupStart(CodeMfaSetupStartRequest {
+                method: MfaMethod::Totp as i32,
+                token: "invalid".into(),
+            }),
+            "core_error",
+        ),
+        (
+            core_request::Payload::CodeMfaSetupFinish(CodeMfaSetupFinishRequest {
+                code: "123456".into(),
+                token: "invalid".into(),
+                method: MfaMethod::Totp as i32,
+            }),
+            "core_error",
+        ),
+        (
+            core_request::Payload::RegisterMobileAuth(RegisterMobileAuthRequest {
+                token: "invalid".into(),
+                auth_pub_key: "key".into(),
+                device_pub_key: "key".into(),
+            }),
+            "core_error",
+        ),
+        (
+            core_request::Payload::ClientMfaTokenValidation(ClientMfaTokenValidationRequest {
+                token: "invalid".into(),
+            }),
+            "core_error",
+        ),
+    ];
+    let kinds: BTreeSet<_> = requests
+        .iter()
+        .map(|(payload, _)| request_kind(payload))
+        .collect();
+    assert_eq!(kinds.len(), REQUEST_KINDS);
+
+    for (payload, expected) in requests {
+        let kind = request_kind(&payload);
+        let response = proxy.send(Some(payload)).await;
+        assert_eq!(
+            response_kind(response.as_ref()),
+            expected,
+            "unexpected response to {kind}"
+        );
+    }
+
+    // request without payload is answered without payload
+    assert_eq!(response_kind(proxy.send(None).await.as_ref()), "none");
+
+    // closing the stream ends the message loop
+    proxy.disconnect().await.unwrap();
+}
+
+#[sqlx::test]
+async fn test_proxy_password_reset(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let mut proxy = MockProxy::new(&pool).await;
+    let initial_hash = password_hash(&pool).await;
+
+    let response = proxy
+        .send(Some(core_request::Payload::PasswordResetInit(
+            PasswordResetInitializeRequest {
+                email: "h.potter@hogwart.edu.uk".into(),
+            },
+        )))
+        .await;
+    assert_eq!(response_kind(response.as_ref()), "empty");
+    let mail = proxy.mail_rx.try_recv().unwrap();
+    assert_eq!(mail.to, "h.potter@hogwart.edu.uk");
+    let event = proxy.bidi_event_rx.try_recv().unwrap();
+    assert!(matches!(
+        event.event,
+        BidiStreamEventType::PasswordReset(event)
+            if matches!(*event, PasswordResetEvent::PasswordResetRequested)
+    ));
+
+    let token: String = query_scalar(
+        "SELECT id FROM token WHERE token_type = 'PASSWORD_RESET' AND user_id = \
+        (SELECT id FROM \"user\" WHERE username = 'hpotter')",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    let response = proxy
+        .send(Some(core_request::Payload::PasswordResetStart(
+            PasswordResetStartRequest {
+                token: token.clone(),
+            },
+        )))
+        .await;
+    assert_eq!(response_kind(response.as_ref()), "password_reset_start");
+
+    // weak password is rejected
+    let response = proxy
+        .send(Some(core_request::Payload::PasswordReset(
+            PasswordResetRequest {
+                password: "weak".into(),
+                token: Some(token.clone()),
+            },
+        )))
+        .await;
+    assert_eq!(response_kind(response.as_ref()), "core_error");
+    assert_eq!(password_hash(&pool).await, initial_hash);
+
+    let response = proxy
+        .send(Some(core_request::Payload::PasswordReset(
+            PasswordResetRequest {
+                password: "NewPassw0rd!".into(),
+                token: Some(token.clone()),
+            },
+        )))
+        .await;
+    assert_eq!(response_kind(response.as_ref()), "empty");
+    let new_hash = password_hash(&pool).await;
+    assert_ne!(new_hash, initial_hash);
+
+    // link is invalidated once the password has been reset
+    let response = proxy
+        .send(Some(core_request::Payload::PasswordReset(
+            PasswordResetRequest {
+                password: "OtherPassw0rd!".into(),
+                token: Some(token.clone()),
+            },
+        )))
+        .await;
+    assert_eq!(response_kind(response.as_ref()), "core_error");
+    assert_eq!(password_hash(&pool).await, new_hash);
+    let response = proxy
+        .send(Some(core_request::Payload::PasswordResetStart(
+            PasswordResetStartRequest { token },
+        )))
+        .await;
+    assert_eq!(response_kind(response.as_ref()), "core_error");
+
+    proxy.disconnect().await.unwrap();
+}
+
+#[sqlx::test]
+async fn test_proxy_password_reset_max_uses(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let mut proxy = MockProxy::new(&pool).await;
+    let mut settings = Settings::get_current_settings();
+    settings.password_reset_token_lifetime = Some(30);
+    settings.password_reset_max_uses = 2;
+    update_current_settings(&pool, settings).await.unwrap();
+
+    let response = proxy
+        .send(Some(core_request::Payload::PasswordResetInit(
+            PasswordResetInitializeRequest {
+                email: "h.potter@hogwart.edu.uk".into(),
+            },
+        )))
+        .await;
+    assert_eq!(response_kind(response.as_ref()), "empty");
+    let mail = proxy.mail_rx.try_recv().unwrap();
+    assert!(mail.content.contains("valid for 30 minutes"));
+    assert!(mail.content.contains("can be used 2 times"));
+
+    let token: String = query_scalar(
+        "SELECT id FROM token WHERE token_type = 'PASSWORD_RESET' AND user_id = \
+        (SELECT id FROM \"user\" WHERE username = 'hpotter')",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    // a new session can be started once the previous one expires
+    for _ in 0..2 {
+        let response = proxy
+            .send(Some(core_request::Payload::PasswordResetStart(
+                PasswordResetStartRequest {
+                    token: token.clone(),
+                },
+            )))
+            .await;
+        assert_eq!(response_kind(response.as_ref()), "password_reset_start");
+        query("UPDATE token SET used_at = used_at - interval '1 day' WHERE id = $1")
+            .bind(&token)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+    let response = proxy
+        .send(Some(core_request::Payload::PasswordResetStart(
+            PasswordResetStartRequest { token },
+        )))
+        .await;
+    assert_eq!(response_kind(response.as_ref()), "core_error");
+
+    proxy.disconnect().await.unwrap();
+}
+
+async fn create_location(pool: &PgPool, name: &str, address: &str) -> WireguardNetwork<Id> {
+    WireguardNetwork::new(
+        name.into(),
+        vec![address.parse().unwrap()],
+        51820,
+        "endpoint".into(),
+        None,
+        Vec::new(),
+        25,
+        300,
+        false,
+        false,
+        LocationMfaMode::Disabled,
+        ServiceLocationMode::Disabled,
+    )
+    .save(pool)
+    .await
+    .unwrap()
+}
+
+/// Create enrollment token for hpotter with already started session.
+async fn create_enrollment_session(pool: &PgPool, location_ids: Option<Vec<Id>>) -> String {
+    let user = User::find_by_username(pool, "hpotter")
+        .await
+        .unwrap()
+        .unwrap();
+    let mut token = Token::new(
+        user.id,
+        None,
+        None,
+        3600,
+        Some(ENROLLMENT_TOKEN_TYPE.into()),
+    );
+    token.used_at = Some(Utc::now().naive_utc());
+    token.location_ids = location_ids;
+    token.save(pool).await.unwrap();
+    token.id
+}
+
+fn config_location_ids(response: Option<core_response::Payload>) -> Vec<Id> {
+    let Some(core_response::Payload::DeviceConfig(response)) = response else {
+        panic!("unexpected response {:?}", response_kind(response.as_ref()));
+    };
+    let mut ids: Vec<Id> = response
+        .configs
+        .iter()
+        .map(|config| config.network_id)
+        .collect();
+    ids.sort_unstable();
+    ids
+}
+
+#[sqlx::test]
+async fn test_proxy_enrollment_token_locations(_: PgPoolOptions, options: PgConnectOptions) {
+    let pool = setup_pool(options).await;
+    let mut proxy = MockProxy::new(&pool).await;
+    let office = create_location(&pool, "office", "10.1.1.1/24").await;
+    let lab = create_location(&pool, "lab", "10.2.2.1/24").await;
+
+    // unrestricted token configures all locations
+    let token = create_enrollment_session(&pool, None).await;
+    let response = proxy
+        .send(Some(core_request::Payload::NewDevice(NewDevice {
+            name: "laptop".into(),
+            pubkey: "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=".into(),
+            token: Some(token),
+        })))
+        .await;
+    assert_eq!(config_location_ids(response), [office.id, lab.id]);
+
+    // restricted token only configures allowed locations
+    let token = create_enrollment_session(&pool, Some(vec![lab.id])).await;
+    let response = proxy
+        .send(Some(core_request::Payload::NewDevice(NewDevice {
+            name: "phone".into(),
+            pubkey: "hRt7ntqmkhr4x5Rwvdrr/wQinN0YVy2I0zVCS+8Wk3g=".into(),
+            token: Some(token.clone()),
+        })))
+        .await;
+    assert_eq!(config_location_ids(response), [lab.id]);
+    let device_locations: Vec<Id> = query_scalar(
+        "SELECT wireguard_network_id FROM wireguard_network_device \
+        WHERE device_id = (SELECT id FROM device WHERE name = 'phone')",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(device_locations, [lab.id]);
+
+    // existing device configs are limited to allowed locations as well
+    let response = proxy
+        .send(Some(core_request::Payload::ExistingDevice(
+            ExistingDevice {
+                pubkey: "LQKsT6/3HWKuJmMulH63R8iK+5sI8FyYEL6WDIi6lQU=".into(),
+                token: Some(token),
+            },
+        )))
+        .await;
+    assert_eq!(config_location_ids(response), [lab.id]);
+
+    proxy.disconnect().await.unwrap();
+}
@@ -41,12 +41,22 @@ pub fn get_defguard_event_description(event: &DefguardEvent) -> Option<String> {
         DefguardEvent::PasswordReset { user } => {
             Some(format!("Password for user {user} was reset"))
         }
+        DefguardEvent::HelpdeskPasswordResetRequested { user } => Some(format!(
+            "Helpdesk password reset for user {user} awaits confirmation"
+        )),
+        DefguardEvent::HelpdeskPasswordResetConfirmed { user } => {
+            Some(format!("User {user} confirmed helpdesk password reset"))
+        }
         DefguardEvent::MfaSecurityKeyAdded { key } => {
             Some(format!("Added MFA security key {}", key.name))
         }
         DefguardEvent::MfaSecurityKeyRemoved { key } => {
             Some(format!("Removed MFA security key {}", key.name))
         }
+        DefguardEvent::UserSelfRegistered { user } => Some(format!(
+            "User {user} registered with verified email {}, account awaits activation",
+            user.email
+        )),
         DefguardEvent::UserAdded { user } => {
             let self_enrollment_enabled = !user.is_enrolled();
             let enrollment_flag_text = if self_enrollment_enabled {
@@ -92,6 +102,28 @@ pub fn get_defguard_event_description(event: &DefguardEvent) -> Option<String> {
             before: _,
             after,
         } => Some(format!("Modified device {after} owned by user {owner}")),
+        DefguardEvent::DeviceDisconnected { owner, device } => Some(format!(
+            "Forcibly disconnected device {device} owned by user {owner}"
+        )),
+        DefguardEvent::DeviceQuarantined {
+            owner,
+            device,
+            reason,
+        } => Some(match reason {
+            Some(reason) => {
+                format!("Quarantined device {device} owned by user {owner}: {reason}")
+            }
+            None => format!("Quarantined device {device} owned by user {owner}"),
+        }),
+        DefguardEvent::DeviceReleasedFromQuarantine { owner, device } => Some(format!(
+            "Released device {device} owned by user {owner} from quarantine"
+        )),
+        DefguardEvent::DeviceApproved { owner, device } => {
+            Some(format!("Approved device {device} owned by user {owner}"))
+        }
+        DefguardEvent::DeviceRejected { owner, device } => {
+            Some(format!("Rejected device {device} owned by user {owner}"))
+        }
         DefguardEvent::NetworkDeviceAdded { device, location } => Some(format!(
             "Added network device {device} to location {location}"
         )),
@@ -126,6 +158,9 @@ pub fn get_defguard_event_description(event: &DefguardEvent) -> Option<String> {
         DefguardEvent::VpnLocationModified { before: _, after } => {
             Some(format!("VPN location {after} was modified"))
         }
+        DefguardEvent::VpnLocationFirewallRolledBack { location, version } => Some(format!(
+            "Rolled back firewall configuration of VPN location {location} to version {version}"
+        )),
         DefguardEvent::ApiTokenAdded { owner, token } => {
             Some(format!("Added API token {} for user {owner}", token.name))
         }
@@ -218,6 +253,68 @@ pub fn get_defguard_event_description(event: &DefguardEvent) -> Option<String> {
             let state = if *enabled { "Enabled" } else { "Disabled" };
             Some(format!("{} webhook with URL {}", state, webhook.url))
         }
+        DefguardEvent::AnnouncementCreated { announcement } => Some(format!(
+            "Created announcement \"{}\" scheduled at {}",
+            announcement.subject, announcement.scheduled_at
+        )),
+        DefguardEvent::AnnouncementRemoved { announcement } => {
+            Some(format!("Removed announcement \"{}\"", announcement.subject))
+        }
+        DefguardEvent::ServiceAccountAdded { service_account } => {
+            Some(format!("Added service account {}", service_account.name))
+        }
+        DefguardEvent::ServiceAccountModified { before, after } => {
+            let mut description = format!("Modified service account {}", after.name);
+            if before.is_active != after.is_active {
+                let status_change_text = if after.is_active {
+                    "enabled"
+                } else {
+                    "disabled"
+                };
+                description = format!("{description}, status changed to {status_change_text}");
+            }
+            Some(description)
+        }
+        DefguardEvent::ServiceAccountRemoved { service_account } => {
+            Some(format!("Removed service account {}", service_account.name))
+        }
+        DefguardEvent::ItsmConnectorAdded { connector } => {
+            Some(format!("Added ITSM connector {}", connector.name))
+        }
+        DefguardEvent::ItsmConnectorModified { before: _, after } => {
+            Some(format!("Modified ITSM connector {}", after.name))
+        }
+        DefguardEvent::ItsmConnectorRemoved { connector } => {
+            Some(format!("Removed ITSM connector {}", connector.name))
+        }
+        DefguardEvent::MailVariableAdded { variable } => {
+            Some(format!("Added mail variable {}", variable.name))
+        }
+        DefguardEvent::MailVariableModified { before: _, after } => {
+            Some(format!("Modified mail variable {}", after.name))
+        }
+        DefguardEvent::MailVariableRemoved { variable } => {
+            Some(format!("Removed mail variable {}", variable.name))
+        }
+        DefguardEvent::MailContextModified { translation } => Some(format!(
+            "Modified {} translation of {} section in {} mail template",
+            translation.language_tag, translation.section, translation.template
+        )),
+        DefguardEvent::MailContextRemoved { translation } => Some(format!(
+            "Removed {} translation of {} section in {} mail template",
+            translation.language_tag, translation.section, translation.template
+        )),
+        DefguardEvent::MailTemplateModified { template } => {
+            Some(format!("Overrode mail template {}", template.name))
+        }
+        DefguardEvent::MailTemplateRestored { template } => {
+            Some(format!("Restored built-in mail template {}", template.name))
+        }
+        DefguardEvent::RouteAdded { route } => Some(format!("Added route {}", route.name)),
+        DefguardEvent::RouteModified { before: _, after } => {
+            Some(format!("Modified route {}", after.name))
+        }
+        DefguardEvent::RouteRemoved { route } => Some(format!("Removed route {}", route.name)),
         DefguardEvent::AuthenticationKeyAdded { key } => Some(format!(
             "Added {} authentication key {}",
             key.key_type,
@@ -263,10 +360,19 @@ pub fn get_defguard_event_description(event: &DefguardEvent) -> Option<String> {
 #[must_use]
 pub fn get_vpn_event_description(event: &VpnEvent) -> Option<String> {
     match event {
+        VpnEvent::ConnectedToMfaLocation {
+            location,
+            device,
+            method: _,
+            remembered: true,
+        } => Some(format!(
+            "Device {device} connected to MFA location {location} as a remembered device"
+        )),
         VpnEvent::ConnectedToMfaLocation {
             location,
             device,
             method,
+            remembered: false,
         } => Some(format!(
             "Device {device} connected to MFA location {location} using {method}"
         )),
@@ -306,5 +412,14 @@ pub fn get_enrollment_event_description(event: &EnrollmentEvent) -> Option<Strin
         EnrollmentEvent::TokenAdded { user } => {
             Some(format!("Added enrollment token for user {user}"))
         }
+        EnrollmentEvent::TokenResent { user } => {
+            Some(format!("Resent enrollment token email to user {user}"))
+        }
+        EnrollmentEvent::TokenExtended { user } => {
+            Some(format!("Extended enrollment token of user {user}"))
+        }
+        EnrollmentEvent::TokenRevoked { user } => {
+            Some(format!("Revoked enrollment token of user {user}"))
+        }
     }
 }
@@ -1,20 +1,24 @@
 use bytes::Bytes;
-use defguard_common::db::NoId;
+use defguard_common::{config::server_config, db::NoId};
 use defguard_core::db::models::activity_log::{
     ActivityLogEvent, ActivityLogModule, EventType,
     metadata::{
-        ActivityLogStreamMetadata, ActivityLogStreamModifiedMetadata, ApiTokenMetadata,
-        ApiTokenRenamedMetadata, AuthenticationKeyMetadata, AuthenticationKeyRenamedMetadata,
-        ClientConfigurationTokenMetadata, DeviceMetadata, DeviceModifiedMetadata,
-        EnrollmentDeviceAddedMetadata, EnrollmentTokenMetadata, GroupAssignedMetadata,
-        GroupMembersModifiedMetadata, GroupMetadata, GroupModifiedMetadata,
-        GroupsBulkAssignedMetadata, LoginFailedMetadata, MfaLoginFailedMetadata, MfaLoginMetadata,
-        MfaSecurityKeyMetadata, NetworkDeviceMetadata, NetworkDeviceModifiedMetadata,
-        OpenIdAppMetadata, OpenIdAppModifiedMetadata, OpenIdAppStateChangedMetadata,
-        OpenIdProviderMetadata, PasswordChangedByAdminMetadata, PasswordResetMetadata,
-        SettingsUpdateMetadata, UserGroupsModifiedMetadata, UserMetadata, UserMfaDisabledMetadata,
-        UserModifiedMetadata, UserSnatBindingMetadata, UserSnatBindingModifiedMetadata,
-        VpnClientMetadata, VpnClientMfaFailedMetadata, VpnClientMfaMetadata, VpnLocationMetadata,
+        ActivityLogStreamMetadata, ActivityLogStreamModifiedMetadata, AnnouncementMetadata,
+        ApiTokenMetadata, ApiTokenRenamedMetadata, AuthenticationKeyMetadata,
+        AuthenticationKeyRenamedMetadata, ClientConfigurationTokenMetadata, DeviceMetadata,
+        DeviceModifiedMetadata, DeviceQuarantinedMetadata, EnrollmentDeviceAddedMetadata,
+        EnrollmentTokenMetadata, GroupAssignedMetadata, GroupMembersModifiedMetadata,
+        GroupMetadata, GroupModifiedMetadata, GroupsBulkAssignedMetadata, ItsmConnectorMetadata,
+        ItsmConnectorModifiedMetadata, LoginFailedMetadata, MailContextMetadata,
+        MailTemplateMetadata, MailVariableMetadata, MailVariableModifiedMetadata,
+        MfaLoginFailedMetadata, MfaLoginMetadata, MfaSecurityKeyMetadata, NetworkDeviceMetadata,
+        NetworkDeviceModifiedMetadata, OpenIdAppMetadata, OpenIdAppModifiedMetadata,
+        OpenIdAppStateChangedMetadata, OpenIdProviderMetadata, PasswordChangedByAdminMetadata,
+        PasswordResetMetadata, RouteMetadata, RouteModifiedMetadata, ServiceAccountMetadata,
+        ServiceAccountModifiedMetadata, SettingsUpdateMetadata, UserGroupsModifiedMetadata,
+        UserMetadata, UserMfaDisabledMetadata, UserModifiedMetadata, UserSnatBindingMetadata,
+        UserSnatBindingModifiedMetadata, VpnClientMetadata, VpnClientMfaFailedMetadata,
+        VpnClientMfaMetadata, VpnLocationFirewallRolledBackMetadata, VpnLocationMetadata,
         VpnLocationModifiedMetadata, WebHookMetadata, WebHookModifiedMetadata,
         WebHookStateChangedMetadata,
     },
@@ -23,16 +27,20 @@ use description::{
     get_defguard_event_description, get_enrollment_event_description, get_vpn_event_description,
 };
 use error::EventLoggerError;
+use login_history::get_login_record;
 use message::{
     DefguardEvent, EnrollmentEvent, EventContext, EventLoggerMessage, LoggerEvent, VpnEvent,
 };
+use outbox::get_domain_event;
 use sqlx::PgPool;
 use tokio::sync::mpsc::UnboundedReceiver;
 use tracing::{debug, error, info, trace};
 
 pub mod description;
 pub mod error;
+pub mod login_history;
 pub mod message;
+pub mod outbox;
 
 const MESSAGE_LIMIT: usize = 100;
 
@@ -46,6 +54,8 @@ pub async fn run_event_logger(
     activity_log_messages_tx: tokio::sync::broadcast::Sender<Bytes>,
 ) -> Result<(), EventLoggerError> {
     info!("Starting activity log event logger service");
+    let event_outbox_enabled = server_config().event_outbox_enabled();
+    let proxy_url = server_config().proxy_url.clone();
 
     // Receive messages in an infinite loop
     loop {
@@ -62,6 +72,10 @@ pub async fn run_event_logger(
 
         // Process all messages in the batch
         for message in message_buffer {
+            // Login attempts are stored in the same transaction as activity log events
+            let login_record =
+                get_login_record(&message.event, &message.context, proxy_url.as_deref());
+
             // Unpack shared event context
             let EventContext {
                 user_id,
@@ -72,6 +86,13 @@ pub async fn run_event_logger(
                 device,
             } = message.context;
 
+            // Domain events are stored in the same transaction as activity log events
+            let domain_event = if event_outbox_enabled {
+                get_domain_event(&message.event, &username)
+            } else {
+                None
+            };
+
             // Convert each message to a related activity log event
             let activity_log_event = {
                 let (module, event, description, metadata) = match message.event {
@@ -130,6 +151,51 @@ pub async fn run_event_logger(
                                 })
                                 .ok(),
                             ),
+                            DefguardEvent::DeviceDisconnected { owner, device } => (
+                                EventType::DeviceDisconnected,
+                                serde_json::to_value(DeviceMetadata {
+                                    owner: owner.into(),
+                                    device,
+                                })
+                                .ok(),
+                            ),
+                            DefguardEvent::DeviceQuarantined {
+                                owner,
+                                device,
+                                reason,
+                            } => (
+                                EventType::DeviceQuarantined,
+                                serde_json::to_value(DeviceQuarantinedMetadata {
+                                    owner: owner.into(),
+                                    device,
+                                    reason,
+                                })
+                                .ok(),
+                            ),
+                            DefguardEvent::DeviceReleasedFromQuarantine { owner, device } => (
+                                EventType::DeviceReleasedFromQuarantine,
+                                serde_json::to_value(DeviceMetadata {
+                                    owner: owner.into(),
+                                    device,
+                                })
+                                .ok(),
+                            ),
+                            DefguardEvent::DeviceApproved { owner, device } => (
+                                EventType::DeviceApproved,
+                                serde_json::to_value(DeviceMetadata {
+                                    owner: owner.into(),
+                                    device,
+                                })
+                                .ok(),
+                            ),
+                            DefguardEvent::DeviceRejected { owner, device } => (
+                                EventType::DeviceRejected,
+                                serde_json::to_value(DeviceMetadata {
+                                    owner: owner.into(),
+                                    device,
+                                })
+                                .ok(),
+                            ),
                             DefguardEvent::UserGroupsModified {
                                 user,
                                 before,
@@ -230,6 +296,10 @@ pub async fn run_event_logger(
                                 EventType::UserAdded,
                                 serde_json::to_value(UserMetadata { user: user.into() }).ok(),
                             ),
+                            DefguardEvent::UserSelfRegistered { user } => (
+                                EventType::UserSelfRegistered,
+                                serde_json::to_value(UserMetadata { user: user.into() }).ok(),
+                            ),
                             DefguardEvent::UserRemoved { user } => (
                                 EventType::UserRemoved,
                                 serde_json::to_value(UserMetadata { user: user.into() }).ok(),
@@ -278,6 +348,14 @@ pub async fn run_event_logger(
                                 serde_json::to_value(VpnLocationModifiedMetadata { before, after })
                                     .ok(),
                             ),
+                            DefguardEvent::VpnLocationFirewallRolledBack { location, version } => (
+                                EventType::VpnLocationFirewallRolledBack,
+                                serde_json::to_value(VpnLocationFirewallRolledBackMetadata {
+                                    location,
+                                    version,
+                                })
+                                .ok(),
+                            ),
                             DefguardEvent::OpenIdAppAdded { app } => (
                                 EventType::OpenIdAppAdded,
                                 serde_json::to_value(OpenIdAppMetadata { app: app.into() }).ok(),
@@ -427,11 +505,107 @@ pub async fn run_event_logger(
                                 })
                                 .ok(),
                             ),
+                            DefguardEvent::AnnouncementCreated { announcement } => (
+                                EventType::AnnouncementCreated,
+                                serde_json::to_value(AnnouncementMetadata { announcement }).ok(),
+                            ),
+                            DefguardEvent::AnnouncementRemoved { announcement } => (
+                                EventType::AnnouncementRemoved,
+                                serde_json::to_value(AnnouncementMetadata { announcement }).ok(),
+                            ),
+                            DefguardEvent::ServiceAccountAdded { service_account } => (
+                                EventType::ServiceAccountAdded,
+                                serde_json::to_value(ServiceAccountMetadata { service_account })
+                                    .ok(),
+                            ),
+                            DefguardEvent::ServiceAccountModified { before, after } => (
+                                EventType::ServiceAccountModified,
+                                serde_json::to_value(ServiceAccountModifiedMetadata {
+                                    before,
+                                    after,
+                                })
+                                .ok(),
+                            ),
+                            DefguardEvent::ServiceAccountRemoved { service_account } => (
+                                EventType::ServiceAccountRemoved,
+                                serde_json::to_value(ServiceAccountMetadata { service_account })
+                                    .ok(),
+                            ),
+                            DefguardEvent::ItsmConnectorAdded { connector } => (
+                                EventType::ItsmConnectorAdded,
+                                serde_json::to_value(ItsmConnectorMetadata { connector }).ok(),
+                            ),
+                            DefguardEvent::ItsmConnectorModified { before, after } => (
+                                EventType::ItsmConnectorModified,
+                                serde_json::to_value(ItsmConnectorModifiedMetadata {
+                                    before,
+                                    after,
+                                })
+                                .ok(),
+                            ),
+                            DefguardEvent::ItsmConnectorRemoved { connector } => (
+                                EventType::ItsmConnectorRemoved,
+                                serde_json::to_value(ItsmConnectorMetadata { connector }).ok(),
+                            ),
+                            DefguardEvent::MailVariableAdded { variable } => (
+                                EventType::MailVariableAdded,
+                                serde_json::to_value(MailVariableMetadata { variable }).ok(),
+                            ),
+                            DefguardEvent::MailVariableModified { before, after } => (
+                                EventType::MailVariableModified,
+                                serde_json::to_value(MailVariableModifiedMetadata {
+                                    before,
+                                    after,
+                                })
+                                .ok(),
+                            ),
+                            DefguardEvent::MailVariableRemoved { variable } => (
+                                EventType::MailVariableRemoved,
+                                serde_json::to_value(MailVariableMetadata { variable }).ok(),
+                            ),
+                            DefguardEvent::MailContextModified { translation } => (
+                                EventType::MailContextModified,
+                                serde_json::to_value(MailContextMetadata { translation }).ok(),
+                            ),
+                            DefguardEvent::MailContextRemoved { translation } => (
+                                EventType::MailContextRemoved,
+                                serde_json::to_value(MailContextMetadata { translation }).ok(),
+                            ),
+                            DefguardEvent::MailTemplateModified { template } => (
+                                EventType::MailTemplateModified,
+                                serde_json::to_value(MailTemplateMetadata { template }).ok(),
+                            ),
+                            DefguardEvent::MailTemplateRestored { template } => (
+                                EventType::MailTemplateRestored,
+                                serde_json::to_value(MailTemplateMetadata { template }).ok(),
+                            ),
+                            DefguardEvent::RouteAdded { route } => (
+                                EventType::RouteAdded,
+                                serde_json::to_value(RouteMetadata { route }).ok(),
+                            ),
+                            DefguardEvent::RouteModified { before, after } => (
+                                EventType::RouteModified,
+                                serde_json::to_value(RouteModifiedMetadata { before, after }).ok(),
+                            ),
+                            DefguardEvent::RouteRemoved { route } => (
+                                EventType::RouteRemoved,
+                                serde_json::to_value(RouteMetadata { route }).ok(),
+                            ),
                             DefguardEvent::PasswordReset { user } => (
                                 EventType::PasswordReset,
                                 serde_json::to_value(PasswordResetMetadata { user: user.into() })
                                     .ok(),
                             ),
+                            DefguardEvent::HelpdeskPasswordResetRequested { user } => (
+                                EventType::HelpdeskPasswordResetRequested,
+                                serde_json::to_value(PasswordResetMetadata { user: user.into() })
+                                    .ok(),
+                            ),
+                            DefguardEvent::HelpdeskPasswordResetConfirmed { user } => (
+                                EventType::HelpdeskPasswordResetConfirmed,
+                                serde_json::to_value(PasswordResetMetadata { user: user.into() })
+                                    .ok(),
+                            ),
                             DefguardEvent::ClientConfigurationTokenAdded { user } => (
                                 EventType::ClientConfigurationTokenAdded,
                                 serde_json::to_value(ClientConfigurationTokenMetadata {
@@ -495,12 +669,14 @@ pub async fn run_event_logger(
                                 location,
                                 device,
                                 method,
+                                remembered,
                             } => (
                                 EventType::VpnClientConnectedMfa,
                                 serde_json::to_value(VpnClientMfaMetadata {
                                     location,
                                     device,
                                     method,
+                                    remembered,
                                 })
                                 .ok(),
                             ),
@@ -548,6 +724,21 @@ pub async fn run_event_logger(
                                 serde_json::to_value(EnrollmentTokenMetadata { user: user.into() })
                                     .ok(),
                             ),
+                            EnrollmentEvent::TokenResent { user } => (
+                                EventType::EnrollmentTokenResent,
+                                serde_json::to_value(EnrollmentTokenMetadata { user: user.into() })
+                                    .ok(),
+                            ),
+                            EnrollmentEvent::TokenExtended { user } => (
+                                EventType::EnrollmentTokenExtended,
+                                serde_json::to_value(EnrollmentTokenMetadata { user: user.into() })
+                                    .ok(),
+                            ),
+                            EnrollmentEvent::TokenRevoked { user } => (
+                                EventType::EnrollmentTokenRevoked,
+                                serde_json::to_value(EnrollmentTokenMetadata { user: user.into() })
+                                    .ok(),
+                            ),
                         };
                         (module, event_type, description, metadata)
                     }
@@ -580,6 +771,12 @@ pub async fn run_event_logger(
             // Store activity log event in DB
             // TODO: do batch inserts
             activity_log_event.save(&mut *transaction).await?;
+            if let Some(domain_event) = domain_event {
+                domain_event.enqueue(&mut *transaction).await?;
+            }
+            if let Some(login_record) = login_record {
+                login_record.save(&mut *transaction).await?;
+            }
         }
 
         // Send serialized events
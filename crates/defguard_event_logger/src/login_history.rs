@@ -0,0 +1,97 @@
+//! Login attempts stored in login history along with activity log events.
+//!
+//! Successful and failed web logins and desktop client MFA attempts are recorded, so users can
+//! review their own activity and anomalies can be detected per user.
+
+use defguard_common::db::NoId;
+use defguard_core::db::models::login_history::{LoginRecord, LoginSource};
+
+use crate::message::{DefguardEvent, EventContext, LoggerEvent, VpnEvent};
+
+// Method of logging in to the web UI without MFA
+const PASSWORD_METHOD: &str = "Password";
+
+/// Login attempt corresponding to a logger event; `proxy` is the URL of the proxy relaying
+/// desktop client MFA.
+#[must_use]
+pub fn get_login_record(
+    event: &LoggerEvent,
+    context: &EventContext,
+    proxy: Option<&str>,
+) -> Option<LoginRecord> {
+    let (source, method, success, location, message) = match event {
+        LoggerEvent::Defguard(event) => match &**event {
+            DefguardEvent::UserLogin => (
+                LoginSource::Web,
+                PASSWORD_METHOD.to_string(),
+                true,
+                None,
+                None,
+            ),
+            DefguardEvent::UserLoginFailed { message } => (
+                LoginSource::Web,
+                PASSWORD_METHOD.to_string(),
+                false,
+                None,
+                Some(message.clone()),
+            ),
+            DefguardEvent::UserMfaLogin { mfa_method } => {
+                (LoginSource::Web, mfa_method.to_string(), true, None, None)
+            }
+            DefguardEvent::UserMfaLoginFailed {
+                mfa_method,
+                message,
+            } => (
+                LoginSource::Web,
+                mfa_method.to_string(),
+                false,
+                None,
+                Some(message.clone()),
+            ),
+            _ => return None,
+        },
+        LoggerEvent::Vpn(event) => match &**event {
+            VpnEvent::ConnectedToMfaLocation {
+                location, method, ..
+            } => (
+                LoginSource::ClientMfa,
+                method.to_string(),
+                true,
+                Some(location.name.clone()),
+                None,
+            ),
+            VpnEvent::MfaFailed {
+                location,
+                method,
+                message,
+                ..
+            } => (
+                LoginSource::ClientMfa,
+                method.to_string(),
+                false,
+                Some(location.name.clone()),
+                Some(message.clone()),
+            ),
+            _ => return None,
+        },
+        LoggerEvent::Enrollment(_) => return None,
+    };
+    let proxy = match source {
+        LoginSource::Web => None,
+        LoginSource::ClientMfa => proxy.map(ToString::to_string),
+    };
+
+    Some(LoginRecord {
+        id: NoId,
+        user_id: context.user_id,
+        timestamp: context.timestamp,
+        source,
+        method,
+        success,
+        ip: context.ip.into(),
+        device: context.device.clone(),
+        location,
+        proxy,
+        message,
+    })
+}
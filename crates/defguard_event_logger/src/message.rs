@@ -3,12 +3,15 @@ use std::net::IpAddr;
 use chrono::NaiveDateTime;
 use defguard_common::db::{
     Id,
-    models::{AuthenticationKey, MFAMethod, Settings},
+    models::{AuthenticationKey, MFAMethod, MailContext, MailTemplate, MailVariable, Settings},
 };
 use defguard_core::{
     db::{
         Device, Group, User, WebAuthn, WebHook, WireguardNetwork,
-        models::oauth2client::OAuth2Client,
+        models::{
+            announcement::Announcement, itsm::ItsmConnector, oauth2client::OAuth2Client,
+            route::Route, service_account::ServiceAccountInfo,
+        },
     },
     enterprise::db::models::{
         activity_log_stream::ActivityLogStream, api_tokens::ApiToken,
@@ -138,6 +141,12 @@ pub enum DefguardEvent {
     PasswordReset {
         user: User<Id>,
     },
+    HelpdeskPasswordResetRequested {
+        user: User<Id>,
+    },
+    HelpdeskPasswordResetConfirmed {
+        user: User<Id>,
+    },
     MfaDisabled,
     UserMfaDisabled {
         user: User<Id>,
@@ -155,6 +164,9 @@ pub enum DefguardEvent {
     UserAdded {
         user: User<Id>,
     },
+    UserSelfRegistered {
+        user: User<Id>,
+    },
     UserRemoved {
         user: User<Id>,
     },
@@ -180,6 +192,27 @@ pub enum DefguardEvent {
         before: Device<Id>,
         after: Device<Id>,
     },
+    DeviceDisconnected {
+        owner: User<Id>,
+        device: Device<Id>,
+    },
+    DeviceQuarantined {
+        owner: User<Id>,
+        device: Device<Id>,
+        reason: Option<String>,
+    },
+    DeviceReleasedFromQuarantine {
+        owner: User<Id>,
+        device: Device<Id>,
+    },
+    DeviceApproved {
+        owner: User<Id>,
+        device: Device<Id>,
+    },
+    DeviceRejected {
+        owner: User<Id>,
+        device: Device<Id>,
+    },
     NetworkDeviceAdded {
         device: Device<Id>,
         location: WireguardNetwork<Id>,
@@ -213,6 +246,10 @@ pub enum DefguardEvent {
         before: WireguardNetwork<Id>,
         after: WireguardNetwork<Id>,
     },
+    VpnLocationFirewallRolledBack {
+        location: WireguardNetwork<Id>,
+        version: i64,
+    },
     ApiTokenAdded {
         owner: User<Id>,
         token: ApiToken<Id>,
@@ -297,6 +334,64 @@ pub enum DefguardEvent {
         webhook: WebHook<Id>,
         enabled: bool,
     },
+    AnnouncementCreated {
+        announcement: Announcement<Id>,
+    },
+    AnnouncementRemoved {
+        announcement: Announcement<Id>,
+    },
+    ServiceAccountAdded {
+        service_account: ServiceAccountInfo,
+    },
+    ServiceAccountModified {
+        before: ServiceAccountInfo,
+        after: ServiceAccountInfo,
+    },
+    ServiceAccountRemoved {
+        service_account: ServiceAccountInfo,
+    },
+    ItsmConnectorAdded {
+        connector: ItsmConnector<Id>,
+    },
+    ItsmConnectorModified {
+        before: ItsmConnector<Id>,
+        after: ItsmConnector<Id>,
+    },
+    ItsmConnectorRemoved {
+        connector: ItsmConnector<Id>,
+    },
+    MailVariableAdded {
+        variable: MailVariable<Id>,
+    },
+    MailVariableModified {
+        before: MailVariable<Id>,
+        after: MailVariable<Id>,
+    },
+    MailVariableRemoved {
+        variable: MailVariable<Id>,
+    },
+    MailContextModified {
+        translation: MailContext<Id>,
+    },
+    MailContextRemoved {
+        translation: MailContext<Id>,
+    },
+    MailTemplateModified {
+        template: MailTemplate<Id>,
+    },
+    MailTemplateRestored {
+        template: MailTemplate<Id>,
+    },
+    RouteAdded {
+        route: Route<Id>,
+    },
+    RouteModified {
+        before: Route<Id>,
+        after: Route<Id>,
+    },
+    RouteRemoved {
+        route: Route<Id>,
+    },
     AuthenticationKeyAdded {
         key: AuthenticationKey<Id>,
     },
@@ -338,6 +433,7 @@ pub enum VpnEvent {
         location: WireguardNetwork<Id>,
         device: Device<Id>,
         method: ClientMFAMethod,
+        remembered: bool,
     },
     DisconnectedFromMfaLocation {
         location: WireguardNetwork<Id>,
@@ -368,4 +464,7 @@ pub enum EnrollmentEvent {
     PasswordResetStarted,
     PasswordResetCompleted,
     TokenAdded { user: User<Id> },
+    TokenResent { user: User<Id> },
+    TokenExtended { user: User<Id> },
+    TokenRevoked { user: User<Id> },
 }
@@ -0,0 +1,44 @@
+//! Domain events stored in the outbox along with activity log events.
+//!
+//! Only a subset of activity log events is relevant to external data platforms. They are
+//! converted to normalized [`DomainEvent`]s, which are published by the core outbox publisher.
+
+use defguard_core::db::models::event_outbox::DomainEvent;
+
+use crate::message::{DefguardEvent, EnrollmentEvent, LoggerEvent, VpnEvent};
+
+/// Domain event corresponding to a logger event; `username` is the user from event context.
+#[must_use]
+pub fn get_domain_event(event: &LoggerEvent, username: &str) -> Option<DomainEvent> {
+    match event {
+        LoggerEvent::Defguard(event) => match &**event {
+            DefguardEvent::UserAdded { user } | DefguardEvent::UserSelfRegistered { user } => {
+                Some(DomainEvent::user_created(user))
+            }
+            DefguardEvent::UserDeviceAdded { owner, device } => {
+                Some(DomainEvent::device_added(device, &owner.username))
+            }
+            DefguardEvent::NetworkDeviceAdded { device, .. } => {
+                Some(DomainEvent::device_added(device, username))
+            }
+            _ => None,
+        },
+        LoggerEvent::Enrollment(event) => match &**event {
+            EnrollmentEvent::EnrollmentDeviceAdded { device } => {
+                Some(DomainEvent::device_added(device, username))
+            }
+            _ => None,
+        },
+        LoggerEvent::Vpn(event) => match &**event {
+            VpnEvent::ConnectedToLocation { location, device }
+            | VpnEvent::ConnectedToMfaLocation {
+                location, device, ..
+            } => Some(DomainEvent::session_started(device, location, username)),
+            VpnEvent::DisconnectedFromLocation { location, device }
+            | VpnEvent::DisconnectedFromMfaLocation { location, device } => {
+                Some(DomainEvent::session_ended(device, location, username))
+            }
+            VpnEvent::MfaFailed { .. } => None,
+        },
+    }
+}
@@ -42,6 +42,10 @@ impl EventRouter {
                 LoggerEvent::Defguard(Box::new(DefguardEvent::UserAdded { user })),
                 None,
             ),
+            ApiEventType::UserSelfRegistered { user } => (
+                LoggerEvent::Defguard(Box::new(DefguardEvent::UserSelfRegistered { user })),
+                None,
+            ),
             ApiEventType::UserRemoved { user } => (
                 LoggerEvent::Defguard(Box::new(DefguardEvent::UserRemoved { user })),
                 None,
@@ -114,6 +118,52 @@ impl EventRouter {
                 })),
                 None,
             ),
+            ApiEventType::DeviceDisconnected {
+                owner,
+                device,
+                location,
+            } => (
+                LoggerEvent::Defguard(Box::new(DefguardEvent::DeviceDisconnected {
+                    owner,
+                    device,
+                })),
+                Some(location),
+            ),
+            ApiEventType::DeviceQuarantined {
+                owner,
+                device,
+                reason,
+            } => (
+                LoggerEvent::Defguard(Box::new(DefguardEvent::DeviceQuarantined {
+                    owner,
+                    device,
+                    reason,
+                })),
+                None,
+            ),
+            ApiEventType::DeviceReleasedFromQuarantine { owner, device } => (
+                LoggerEvent::Defguard(Box::new(DefguardEvent::DeviceReleasedFromQuarantine {
+                    owner,
+                    device,
+                })),
+                None,
+            ),
+            ApiEventType::DeviceApproved {
+                owner,
+                device,
+                location,
+            } => (
+                LoggerEvent::Defguard(Box::new(DefguardEvent::DeviceApproved { owner, device })),
+                Some(location),
+            ),
+            ApiEventType::DeviceRejected {
+                owner,
+                device,
+                location,
+            } => (
+                LoggerEvent::Defguard(Box::new(DefguardEvent::DeviceRejected { owner, device })),
+                Some(location),
+            ),
             ApiEventType::NetworkDeviceAdded { device, location } => (
                 LoggerEvent::Defguard(Box::new(DefguardEvent::NetworkDeviceAdded {
                     device,
@@ -190,6 +240,13 @@ impl EventRouter {
                 })),
                 Some(after),
             ),
+            ApiEventType::VpnLocationFirewallRolledBack { location, version } => (
+                LoggerEvent::Defguard(Box::new(DefguardEvent::VpnLocationFirewallRolledBack {
+                    location: location.clone(),
+                    version,
+                })),
+                Some(location),
+            ),
             ApiEventType::ApiTokenAdded { owner, token } => (
                 LoggerEvent::Defguard(Box::new(DefguardEvent::ApiTokenAdded { owner, token })),
                 None,
@@ -312,6 +369,95 @@ impl EventRouter {
                 })),
                 None,
             ),
+            ApiEventType::AnnouncementCreated { announcement } => (
+                LoggerEvent::Defguard(Box::new(DefguardEvent::AnnouncementCreated {
+                    announcement,
+                })),
+                None,
+            ),
+            ApiEventType::AnnouncementRemoved { announcement } => (
+                LoggerEvent::Defguard(Box::new(DefguardEvent::AnnouncementRemoved {
+                    announcement,
+                })),
+                None,
+            ),
+            ApiEventType::ServiceAccountAdded { service_account } => (
+                LoggerEvent::Defguard(Box::new(DefguardEvent::ServiceAccountAdded {
+                    service_account,
+                })),
+                None,
+            ),
+            ApiEventType::ServiceAccountModified { before, after } => (
+                LoggerEvent::Defguard(Box::new(DefguardEvent::ServiceAccountModified {
+                    before,
+                    after,
+                })),
+                None,
+            ),
+            ApiEventType::ServiceAccountRemoved { service_account } => (
+                LoggerEvent::Defguard(Box::new(DefguardEvent::ServiceAccountRemoved {
+                    service_account,
+                })),
+                None,
+            ),
+            ApiEventType::ItsmConnectorAdded { connector } => (
+                LoggerEvent::Defguard(Box::new(DefguardEvent::ItsmConnectorAdded { connector })),
+                None,
+            ),
+            ApiEventType::ItsmConnectorModified { before, after } => (
+                LoggerEvent::Defguard(Box::new(DefguardEvent::ItsmConnectorModified {
+                    before,
+                    after,
+                })),
+                None,
+            ),
+            ApiEventType::ItsmConnectorRemoved { connector } => (
+                LoggerEvent::Defguard(Box::new(DefguardEvent::ItsmConnectorRemoved { connector })),
+                None,
+            ),
+            ApiEventType::MailVariableAdded { variable } => (
+                LoggerEvent::Defguard(Box::new(DefguardEvent::MailVariableAdded { variable })),
+                None,
+            ),
+            ApiEventType::MailVariableModified { before, after } => (
+                LoggerEvent::Defguard(Box::new(DefguardEvent::MailVariableModified {
+                    before,
+                    after,
+                })),
+                None,
+            ),
+            ApiEventType::MailVariableRemoved { variable } => (
+                LoggerEvent::Defguard(Box::new(DefguardEvent::MailVariableRemoved { variable })),
+                None,
+            ),
+            ApiEventType::MailContextModified { translation } => (
+                LoggerEvent::Defguard(Box::new(DefguardEvent::MailContextModified { translation })),
+                None,
+            ),
+            ApiEventType::MailContextRemoved { translation } => (
+                LoggerEvent::Defguard(Box::new(DefguardEvent::MailContextRemoved { translation })),
+                None,
+            ),
+            ApiEventType::MailTemplateModified { template } => (
+                LoggerEvent::Defguard(Box::new(DefguardEvent::MailTemplateModified { template })),
+                None,
+            ),
+            ApiEventType::MailTemplateRestored { template } => (
+                LoggerEvent::Defguard(Box::new(DefguardEvent::MailTemplateRestored { template })),
+                None,
+            ),
+            ApiEventType::RouteAdded { route } => (
+                LoggerEvent::Defguard(Box::new(DefguardEvent::RouteAdded { route })),
+                None,
+            ),
+            ApiEventType::RouteModified { before, after } => (
+                LoggerEvent::Defguard(Box::new(DefguardEvent::RouteModified { before, after })),
+                None,
+            ),
+            ApiEventType::RouteRemoved { route } => (
+                LoggerEvent::Defguard(Box::new(DefguardEvent::RouteRemoved { route })),
+                None,
+            ),
             ApiEventType::AuthenticationKeyAdded { key } => (
                 LoggerEvent::Defguard(Box::new(DefguardEvent::AuthenticationKeyAdded { key })),
                 None,
@@ -336,6 +482,18 @@ impl EventRouter {
                 LoggerEvent::Enrollment(Box::new(EnrollmentEvent::TokenAdded { user })),
                 None,
             ),
+            ApiEventType::EnrollmentTokenResent { user } => (
+                LoggerEvent::Enrollment(Box::new(EnrollmentEvent::TokenResent { user })),
+                None,
+            ),
+            ApiEventType::EnrollmentTokenExtended { user } => (
+                LoggerEvent::Enrollment(Box::new(EnrollmentEvent::TokenExtended { user })),
+                None,
+            ),
+            ApiEventType::EnrollmentTokenRevoked { user } => (
+                LoggerEvent::Enrollment(Box::new(EnrollmentEvent::TokenRevoked { user })),
+                None,
+            ),
             ApiEventType::PasswordChanged => (
                 LoggerEvent::Defguard(Box::new(DefguardEvent::PasswordChanged)),
                 None,
@@ -348,6 +506,18 @@ impl EventRouter {
                 LoggerEvent::Defguard(Box::new(DefguardEvent::PasswordReset { user })),
                 None,
             ),
+            ApiEventType::HelpdeskPasswordResetRequested { user } => (
+                LoggerEvent::Defguard(Box::new(DefguardEvent::HelpdeskPasswordResetRequested {
+                    user,
+                })),
+                None,
+            ),
+            ApiEventType::HelpdeskPasswordResetConfirmed { user } => (
+                LoggerEvent::Defguard(Box::new(DefguardEvent::HelpdeskPasswordResetConfirmed {
+                    user,
+                })),
+                None,
+            ),
             ApiEventType::ClientConfigurationTokenAdded { user } => (
                 LoggerEvent::Defguard(Box::new(DefguardEvent::ClientConfigurationTokenAdded {
                     user,
@@ -49,11 +49,13 @@ impl EventRouter {
                     location,
                     device,
                     method,
+                    remembered,
                 } => (
                     LoggerEvent::Vpn(Box::new(VpnEvent::ConnectedToMfaLocation {
                         location: location.clone(),
                         device,
                         method,
+                        remembered,
                     })),
                     Some(location),
                 ),
@@ -0,0 +1,169 @@
+//! iCalendar (RFC 5545) events attached to mails, e.g. invites to maintenance windows.
+//!
+//! Mail clients show such attachments as invites which can be added to a calendar. Sending the
+//! same event (by UID) with a higher sequence number updates or cancels it.
+
+use chrono::{NaiveDateTime, Utc};
+
+use crate::{Attachment, AttachmentType};
+
+const PRODUCT_ID: &str = "-//defguard//defguard//EN";
+const DATETIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+// Content lines longer than this are folded.
+const MAX_LINE_LENGTH: usize = 75;
+
+/// iTIP method of a calendar object: whether an event is scheduled or cancelled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CalendarMethod {
+    Request,
+    Cancel,
+}
+
+impl CalendarMethod {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Request => "REQUEST",
+            Self::Cancel => "CANCEL",
+        }
+    }
+}
+
+/// Calendar event sent to a single attendee. Times are in UTC.
+#[derive(Clone, Debug)]
+pub struct CalendarEvent {
+    /// Globally unique identifier, the same for all updates of an event
+    pub uid: String,
+    /// Incremented with each update of an event
+    pub sequence: u32,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub summary: String,
+    pub description: String,
+    /// Organizer email address
+    pub organizer: Option<String>,
+    /// Attendee email address
+    pub attendee: String,
+}
+
+/// Escapes TEXT value.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Folds a content line into lines of at most `MAX_LINE_LENGTH` octets, continued with a space.
+fn fold(line: &str, ics: &mut String) {
+    let mut length = 0;
+    for char in line.chars() {
+        if length + char.len_utf8() > MAX_LINE_LENGTH {
+            ics.push_str("\r\n ");
+            length = 1;
+        }
+        ics.push(char);
+        length += char.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+impl CalendarEvent {
+    /// Serializes event as iCalendar object.
+    #[must_use]
+    pub fn to_ics(&self, method: CalendarMethod) -> String {
+        let status = match method {
+            CalendarMethod::Request => "CONFIRMED",
+            CalendarMethod::Cancel => "CANCELLED",
+        };
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_string(),
+            "VERSION:2.0".to_string(),
+            format!("PRODID:{PRODUCT_ID}"),
+            format!("METHOD:{}", method.as_str()),
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{}", self.uid),
+            format!("SEQUENCE:{}", self.sequence),
+            format!("DTSTAMP:{}", Utc::now().naive_utc().format(DATETIME_FORMAT)),
+            format!("DTSTART:{}", self.start.format(DATETIME_FORMAT)),
+            format!("DTEND:{}", self.end.format(DATETIME_FORMAT)),
+            format!("SUMMARY:{}", escape(&self.summary)),
+            format!("DESCRIPTION:{}", escape(&self.description)),
+            format!("STATUS:{status}"),
+        ];
+        if let Some(organizer) = &self.organizer {
+            lines.push(format!("ORGANIZER:mailto:{organizer}"));
+        }
+        lines.push(format!(
+            "ATTENDEE;ROLE=REQ-PARTICIPANT;RSVP=FALSE:mailto:{}",
+            self.attendee
+        ));
+        lines.push("END:VEVENT".to_string());
+        lines.push("END:VCALENDAR".to_string());
+
+        let mut ics = String::new();
+        for line in lines {
+            fold(&line, &mut ics);
+        }
+        ics
+    }
+
+    /// Event as mail attachment.
+    #[must_use]
+    pub fn attachment(&self, method: CalendarMethod) -> Attachment {
+        Attachment::new(
+            "invite.ics",
+            self.to_ics(method),
+            AttachmentType::Calendar(method),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    #[test]
+    fn test_calendar_event() {
+        let start = NaiveDate::from_ymd_opt(2026, 3, 1)
+            .unwrap()
+            .and_hms_opt(22, 0, 0)
+            .unwrap();
+        let event = CalendarEvent {
+            uid: "maintenance-window-1@defguard.example.com".into(),
+            sequence: 0,
+            start,
+            end: start + chrono::TimeDelta::hours(2),
+            summary: "Maintenance: Office, gateway downtime".into(),
+            description: format!("Upgrade;\n{}", "reboot ".repeat(20)),
+            organizer: Some("defguard@example.com".into()),
+            attendee: "admin@example.com".into(),
+        };
+
+        let ics = event.to_ics(CalendarMethod::Request);
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"));
+        assert!(ics.contains("\r\nMETHOD:REQUEST\r\n"));
+        assert!(ics.contains("\r\nDTSTART:20260301T220000Z\r\n"));
+        assert!(ics.contains("\r\nDTEND:20260302T000000Z\r\n"));
+        assert!(ics.contains("\r\nSUMMARY:Maintenance: Office\\, gateway downtime\r\n"));
+        assert!(ics.contains("\r\nDESCRIPTION:Upgrade\\;\\nreboot "));
+        assert!(ics.contains("\r\nSTATUS:CONFIRMED\r\n"));
+        assert!(ics.contains("\r\nORGANIZER:mailto:defguard@example.com\r\n"));
+        assert!(ics.split("\r\n").all(|line| line.len() <= MAX_LINE_LENGTH));
+
+        let ics = event.to_ics(CalendarMethod::Cancel);
+        assert!(ics.contains("\r\nMETHOD:CANCEL\r\n"));
+        assert!(ics.contains("\r\nSTATUS:CANCELLED\r\n"));
+
+        let attachment = event.attachment(CalendarMethod::Cancel);
+        assert_eq!(attachment.filename, "invite.ics");
+        assert_eq!(
+            attachment.content_type,
+            AttachmentType::Calendar(CalendarMethod::Cancel).content_type()
+        );
+    }
+}
@@ -0,0 +1,226 @@
+//! Failover from the SMTP server configured in settings to a secondary relay.
+//!
+//! The primary relay is guarded by a circuit breaker: after `FAILURE_THRESHOLD` consecutive
+//! failures which might be caused by the relay itself (see [`queue::is_transient`]), mails are
+//! sent through the failover SMTP profile for `FAILOVER_PERIOD`. Then the primary relay is tried
+//! again; it's used as long as it works, otherwise the failover period starts over.
+//! SMTP profiles bound to mail categories and the HTTP mail backend don't fail over.
+
+use std::sync::Mutex;
+
+use chrono::{NaiveDateTime, TimeDelta};
+use defguard_common::db::models::smtp_profile::get_failover_smtp_profile;
+use serde::Serialize;
+
+use crate::{MailError, queue};
+
+/// Primary relay fails over after this many consecutive failures.
+pub(crate) const FAILURE_THRESHOLD: u32 = 3;
+/// How long the secondary relay is used before the primary one is tried again.
+pub(crate) const FAILOVER_PERIOD: TimeDelta = TimeDelta::minutes(5);
+
+static SMTP_HEALTH: Mutex<SmtpHealth> = Mutex::new(SmtpHealth::new());
+
+/// SMTP relay a mail is sent through.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Relay {
+    /// SMTP server configured in settings
+    Primary,
+    /// Failover SMTP profile
+    Secondary,
+}
+
+/// Health of an SMTP relay.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct RelayHealth {
+    pub consecutive_failures: u32,
+    pub last_success: Option<NaiveDateTime>,
+    pub last_failure: Option<NaiveDateTime>,
+    pub last_error: Option<String>,
+}
+
+impl RelayHealth {
+    const fn new() -> Self {
+        Self {
+            consecutive_failures: 0,
+            last_success: None,
+            last_failure: None,
+            last_error: None,
+        }
+    }
+
+    fn record(&mut self, result: Result<(), &MailError>, now: NaiveDateTime) {
+        match result {
+            Ok(()) => {
+                self.consecutive_failures = 0;
+                self.last_success = Some(now);
+            }
+            Err(err) => {
+                self.consecutive_failures += 1;
+                self.last_failure = Some(now);
+                self.last_error = Some(err.to_string());
+            }
+        }
+    }
+}
+
+/// Health of the primary and secondary SMTP relays.
+#[derive(Clone, Debug, Serialize)]
+pub struct SmtpHealth {
+    pub primary: RelayHealth,
+    pub secondary: RelayHealth,
+    /// Mails are sent through the secondary relay until this time
+    pub failover_until: Option<NaiveDateTime>,
+}
+
+/// Change of the relay mails are sent through, which admins are alerted about.
+#[derive(Debug)]
+pub enum FailoverEvent {
+    /// Primary relay keeps failing, mails are sent through the secondary one.
+    FailedOver { error: String },
+    /// Primary relay works again.
+    Recovered,
+}
+
+impl SmtpHealth {
+    const fn new() -> Self {
+        Self {
+            primary: RelayHealth::new(),
+            secondary: RelayHealth::new(),
+            failover_until: None,
+        }
+    }
+
+    fn active_relay(&self, now: NaiveDateTime) -> Relay {
+        if self.failover_until.is_some_and(|until| now < until) {
+            Relay::Secondary
+        } else {
+            Relay::Primary
+        }
+    }
+
+    /// Records result of sending a mail through a relay. Only failures which might be caused by
+    /// the relay count; e.g. rejected recipients don't. Failover happens only if `can_fail_over`.
+    fn record(
+        &mut self,
+        relay: Relay,
+        result: Result<(), &MailError>,
+        can_fail_over: bool,
+        now: NaiveDateTime,
+    ) -> Option<FailoverEvent> {
+        if result.is_err_and(|err| !queue::is_transient(err)) {
+            return None;
+        }
+        if relay == Relay::Secondary {
+            self.secondary.record(result, now);
+            return None;
+        }
+        self.primary.record(result, now);
+        match result {
+            Ok(()) => self.failover_until.take().map(|_| FailoverEvent::Recovered),
+            Err(err) if can_fail_over && self.primary.consecutive_failures >= FAILURE_THRESHOLD => {
+                let failed_over = self.failover_until.is_none();
+                self.failover_until = Some(now + FAILOVER_PERIOD);
+                failed_over.then(|| FailoverEvent::FailedOver {
+                    error: err.to_string(),
+                })
+            }
+            Err(_) => None,
+        }
+    }
+}
+
+/// Current health of SMTP relays.
+pub fn smtp_health() -> SmtpHealth {
+    SMTP_HEALTH
+        .lock()
+        .expect("Failed to acquire lock on the mutex.")
+        .clone()
+}
+
+/// Relay mails which aren't bound to an SMTP profile are currently sent through.
+pub(crate) fn active_relay(now: NaiveDateTime) -> Relay {
+    SMTP_HEALTH
+        .lock()
+        .expect("Failed to acquire lock on the mutex.")
+        .active_relay(now)
+}
+
+/// Records result of sending a mail through a relay, returning a change admins should be
+/// alerted about.
+pub(crate) fn record(
+    relay: Relay,
+    result: Result<(), &MailError>,
+    now: NaiveDateTime,
+) -> Option<FailoverEvent> {
+    let can_fail_over = get_failover_smtp_profile().as_ref().is_some();
+    SMTP_HEALTH
+        .lock()
+        .expect("Failed to acquire lock on the mutex.")
+        .record(relay, result, can_fail_over, now)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    #[test]
+    fn test_failover() {
+        let mut health = SmtpHealth::new();
+        let now = Utc::now().naive_utc();
+        let err = MailError::OAuth2TokenError("timeout".into());
+
+        // failures which aren't caused by the relay don't count
+        assert!(
+            health
+                .record(Relay::Primary, Err(&MailError::InvalidPort(0)), true, now)
+                .is_none()
+        );
+        assert_eq!(health.primary.consecutive_failures, 0);
+
+        for _ in 1..FAILURE_THRESHOLD {
+            assert!(
+                health
+                    .record(Relay::Primary, Err(&err), true, now)
+                    .is_none()
+            );
+        }
+        assert_eq!(health.active_relay(now), Relay::Primary);
+        assert!(matches!(
+            health.record(Relay::Primary, Err(&err), true, now),
+            Some(FailoverEvent::FailedOver { .. })
+        ));
+        assert_eq!(health.active_relay(now), Relay::Secondary);
+        assert!(health.record(Relay::Secondary, Ok(()), true, now).is_none());
+        assert_eq!(health.secondary.last_success, Some(now));
+
+        // primary relay is tried again after the failover period
+        let later = now + FAILOVER_PERIOD;
+        assert_eq!(health.active_relay(later), Relay::Primary);
+        assert!(
+            health
+                .record(Relay::Primary, Err(&err), true, later)
+                .is_none()
+        );
+        assert_eq!(health.active_relay(later), Relay::Secondary);
+        let later = later + FAILOVER_PERIOD;
+        assert!(matches!(
+            health.record(Relay::Primary, Ok(()), true, later),
+            Some(FailoverEvent::Recovered)
+        ));
+        assert_eq!(health.active_relay(later), Relay::Primary);
+        assert_eq!(health.primary.consecutive_failures, 0);
+
+        // no failover without secondary relay
+        for _ in 0..FAILURE_THRESHOLD {
+            assert!(
+                health
+                    .record(Relay::Primary, Err(&err), false, later)
+                    .is_none()
+            );
+        }
+        assert_eq!(health.active_relay(later), Relay::Primary);
+    }
+}
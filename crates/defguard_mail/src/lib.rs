@@ -1,19 +1,52 @@
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
-use defguard_common::db::models::{Settings, settings::SmtpEncryption};
+use chrono::Utc;
+pub use defguard_common::db::models::MailCategory;
+use defguard_common::{
+    config::server_config,
+    db::{
+        Id,
+        models::{
+            Settings, SmtpProfile,
+            mail_queue::QueuedMail,
+            settings::{SmtpAuthMethod, SmtpEncryption},
+            smtp_profile::get_smtp_profiles,
+        },
+    },
+};
 use lettre::{
-    Address, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+    Address, Message,
     address::AddressError,
     message::{Mailbox, MultiPart, SinglePart, header::ContentType},
-    transport::smtp::{authentication::Credentials, response::Response},
 };
+use sqlx::PgPool;
 use thiserror::Error;
-use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tokio::{
+    sync::{
+        Mutex,
+        mpsc::{UnboundedReceiver, UnboundedSender},
+    },
+    time::interval,
+};
 use tracing::{debug, error, info, instrument, warn};
 
+pub mod calendar;
+pub mod failover;
+mod oauth2;
+mod queue;
+mod scheduler;
 pub mod templates;
+mod transport;
+
+use calendar::CalendarMethod;
+use failover::FailoverEvent;
+use oauth2::{OAuth2Credentials, TokenCache};
+use scheduler::{SendScheduler, recipient_domain};
+pub use transport::MailResponse;
+use transport::{MailTransport, Transport};
 
-const SMTP_TIMEOUT_SECONDS: u64 = 15;
+/// How often the mail queue is checked for mails due for another delivery attempt.
+const MAIL_QUEUE_INTERVAL: Duration = Duration::from_secs(30);
 
 #[derive(Debug, Error)]
 pub enum MailError {
@@ -32,11 +65,80 @@ pub enum MailError {
     #[error("SMTP not configured")]
     SmtpNotConfigured,
 
+    #[error("Mail HTTP API not configured")]
+    HttpNotConfigured,
+
+    #[error("Mail HTTP API request failed: {0}")]
+    HttpError(#[from] reqwest::Error),
+
     #[error("No settings record in database")]
     EmptySettings,
 
     #[error("Invalid port: {0}")]
     InvalidPort(i32),
+
+    #[error("Failed to obtain OAuth2 access token: {0}")]
+    OAuth2TokenError(String),
+}
+
+/// SMTP authentication method with its parameters
+enum SmtpAuth {
+    Password {
+        user: String,
+        password: String,
+    },
+    OAuth2 {
+        user: String,
+        credentials: OAuth2Credentials,
+    },
+}
+
+/// Sender of mails, shared by all mail backends
+struct MailSender {
+    pub address: String,
+    pub name: Option<String>,
+    pub reply_to: Option<String>,
+    pub security_address: Option<String>,
+    pub announcement_address: Option<String>,
+}
+
+impl MailSender {
+    /// Constructs `MailSender` from `Settings`. Returns `None` if sender address is not set.
+    fn from_settings(settings: &Settings) -> Option<Self> {
+        let address = settings
+            .smtp_sender
+            .clone()
+            .filter(|address| !address.is_empty())?;
+        Some(Self {
+            address,
+            name: settings
+                .smtp_sender_name
+                .clone()
+                .filter(|name| !name.is_empty()),
+            reply_to: settings
+                .smtp_reply_to
+                .clone()
+                .filter(|address| !address.is_empty()),
+            security_address: settings
+                .smtp_security_sender
+                .clone()
+                .filter(|address| !address.is_empty()),
+            announcement_address: settings
+                .smtp_announcement_sender
+                .clone()
+                .filter(|address| !address.is_empty()),
+        })
+    }
+
+    /// Sender address of a given mail category.
+    fn address(&self, category: MailCategory) -> &str {
+        let address = match category {
+            MailCategory::General | MailCategory::Alert | MailCategory::Enrollment => None,
+            MailCategory::Security => self.security_address.as_ref(),
+            MailCategory::Announcement => self.announcement_address.as_ref(),
+        };
+        address.unwrap_or(&self.address)
+    }
 }
 
 /// Subset of Settings object representing SMTP configuration
@@ -44,34 +146,99 @@ struct SmtpSettings {
     pub server: String,
     pub port: u16,
     pub encryption: SmtpEncryption,
-    pub user: String,
-    pub password: String,
-    pub sender: String,
+    pub auth: SmtpAuth,
+    pub sender: MailSender,
 }
 
 impl SmtpSettings {
-    /// Constructs `SmtpSettings` from `Settings`. Returns error if `SmtpSettings` are incomplete.
-    pub fn from_settings(settings: Settings) -> Result<SmtpSettings, MailError> {
-        if let (Some(server), Some(port), encryption, Some(user), Some(password), Some(sender)) = (
+    /// Constructs `SmtpSettings` for a given mail category: from the SMTP profile bound to the
+    /// category if there is one, otherwise from `Settings`. Returns error if `SmtpSettings` are
+    /// incomplete.
+    pub fn from_settings(
+        settings: Settings,
+        category: MailCategory,
+    ) -> Result<SmtpSettings, MailError> {
+        if let Some(profile) = get_smtp_profiles().get(&category) {
+            return Self::from_profile(profile);
+        }
+        let (Some(sender), Some(server), Some(port), encryption, Some(user)) = (
+            MailSender::from_settings(&settings),
             settings.smtp_server,
             settings.smtp_port,
             settings.smtp_encryption,
             settings.smtp_user,
-            settings.smtp_password,
-            settings.smtp_sender,
-        ) {
-            let port = port.try_into().map_err(|_| MailError::InvalidPort(port))?;
-            Ok(Self {
-                server,
-                port,
-                encryption,
-                user,
-                password: password.expose_secret().to_string(),
-                sender,
-            })
-        } else {
-            Err(MailError::SmtpNotConfigured)
-        }
+        ) else {
+            return Err(MailError::SmtpNotConfigured);
+        };
+        let auth = match settings.smtp_auth_method {
+            SmtpAuthMethod::Password => {
+                let Some(password) = settings.smtp_password else {
+                    return Err(MailError::SmtpNotConfigured);
+                };
+                SmtpAuth::Password {
+                    user,
+                    password: password.expose_secret().to_string(),
+                }
+            }
+            SmtpAuthMethod::OAuth2 => {
+                let (Some(token_url), Some(client_id), Some(client_secret)) = (
+                    settings.smtp_oauth2_token_url,
+                    settings.smtp_oauth2_client_id,
+                    settings.smtp_oauth2_client_secret,
+                ) else {
+                    return Err(MailError::SmtpNotConfigured);
+                };
+                SmtpAuth::OAuth2 {
+                    user,
+                    credentials: OAuth2Credentials {
+                        token_url,
+                        client_id,
+                        client_secret: client_secret.expose_secret().to_string(),
+                        scope: settings.smtp_oauth2_scope.filter(|scope| !scope.is_empty()),
+                    },
+                }
+            }
+        };
+        let port = port.try_into().map_err(|_| MailError::InvalidPort(port))?;
+        Ok(Self {
+            server,
+            port,
+            encryption,
+            auth,
+            sender,
+        })
+    }
+
+    /// Constructs `SmtpSettings` from an SMTP profile, which uses its sender for all mails.
+    fn from_profile(profile: &SmtpProfile<Id>) -> Result<SmtpSettings, MailError> {
+        debug!("Using SMTP profile {}", profile.name);
+        let port = profile
+            .port
+            .try_into()
+            .map_err(|_| MailError::InvalidPort(profile.port))?;
+        Ok(Self {
+            server: profile.server.clone(),
+            port,
+            encryption: profile.encryption.clone(),
+            auth: SmtpAuth::Password {
+                user: profile.username.clone().unwrap_or_default(),
+                password: profile
+                    .password
+                    .as_ref()
+                    .map(|password| password.expose_secret().to_string())
+                    .unwrap_or_default(),
+            },
+            sender: MailSender {
+                address: profile.sender.clone(),
+                name: profile.sender_name.clone().filter(|name| !name.is_empty()),
+                reply_to: profile
+                    .reply_to
+                    .clone()
+                    .filter(|address| !address.is_empty()),
+                security_address: None,
+                announcement_address: None,
+            },
+        })
     }
 }
 
@@ -81,7 +248,8 @@ pub struct Mail {
     pub subject: String,
     pub content: String,
     pub attachments: Vec<Attachment>,
-    pub result_tx: Option<UnboundedSender<Result<Response, MailError>>>,
+    pub category: MailCategory,
+    pub result_tx: Option<UnboundedSender<Result<MailResponse, MailError>>>,
 }
 
 #[derive(Debug)]
@@ -91,6 +259,52 @@ pub struct Attachment {
     pub content_type: ContentType,
 }
 
+/// Type of attachment content, determining its MIME type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttachmentType {
+    Text,
+    Json,
+    /// iCalendar object, see [`calendar`]
+    Calendar(CalendarMethod),
+    Pdf,
+    Zip,
+}
+
+impl AttachmentType {
+    fn mime_type(self) -> &'static str {
+        match self {
+            Self::Text => "text/plain; charset=utf-8",
+            Self::Json => "application/json",
+            Self::Calendar(CalendarMethod::Request) => {
+                "text/calendar; charset=utf-8; method=REQUEST"
+            }
+            Self::Calendar(CalendarMethod::Cancel) => "text/calendar; charset=utf-8; method=CANCEL",
+            Self::Pdf => "application/pdf",
+            Self::Zip => "application/zip",
+        }
+    }
+
+    #[must_use]
+    pub fn content_type(self) -> ContentType {
+        ContentType::parse(self.mime_type()).expect("Invalid attachment MIME type")
+    }
+}
+
+impl Attachment {
+    #[must_use]
+    pub fn new(
+        filename: impl Into<String>,
+        content: impl Into<Vec<u8>>,
+        attachment_type: AttachmentType,
+    ) -> Self {
+        Self {
+            filename: filename.into(),
+            content: content.into(),
+            content_type: attachment_type.content_type(),
+        }
+    }
+}
+
 impl From<Attachment> for SinglePart {
     fn from(attachment: Attachment) -> Self {
         lettre::message::Attachment::new(attachment.filename)
@@ -100,11 +314,16 @@ impl From<Attachment> for SinglePart {
 
 impl Mail {
     /// Converts Mail to lettre Message
-    fn into_message(self, from: &str) -> Result<Message, MailError> {
-        let builder = Message::builder()
-            .from(Self::mailbox(from)?)
+    fn into_message(self, sender: &MailSender) -> Result<Message, MailError> {
+        let mut from = Self::mailbox(sender.address(self.category))?;
+        from.name.clone_from(&sender.name);
+        let mut builder = Message::builder()
+            .from(from)
             .to(Self::mailbox(&self.to)?)
             .subject(self.subject.clone());
+        if let Some(reply_to) = &sender.reply_to {
+            builder = builder.reply_to(Self::mailbox(reply_to)?);
+        }
         match self.attachments {
             attachments if attachments.is_empty() => Ok(builder
                 .header(ContentType::TEXT_HTML)
@@ -132,112 +351,279 @@ impl Mail {
 
 struct MailHandler {
     rx: UnboundedReceiver<Mail>,
+    pool: PgPool,
+    scheduler: Arc<SendScheduler>,
+    token_cache: Arc<Mutex<TokenCache>>,
+    http_client: reqwest::Client,
+    failover_tx: UnboundedSender<FailoverEvent>,
 }
 
 impl MailHandler {
-    pub fn new(rx: UnboundedReceiver<Mail>) -> Self {
-        Self { rx }
+    pub fn new(
+        rx: UnboundedReceiver<Mail>,
+        pool: PgPool,
+        scheduler: SendScheduler,
+        failover_tx: UnboundedSender<FailoverEvent>,
+    ) -> Self {
+        Self {
+            rx,
+            pool,
+            scheduler: Arc::new(scheduler),
+            token_cache: Arc::default(),
+            http_client: reqwest::Client::default(),
+            failover_tx,
+        }
     }
 
     pub fn send_result(
-        tx: Option<UnboundedSender<Result<Response, MailError>>>,
-        result: Result<Response, MailError>,
+        tx: Option<UnboundedSender<Result<MailResponse, MailError>>>,
+        result: Result<MailResponse, MailError>,
     ) {
         if let Some(tx) = tx {
             if tx.send(result).is_ok() {
-                debug!("SMTP result sent back to caller");
+                debug!("Mail sending result sent back to caller");
             } else {
-                error!("Error sending SMTP result back to caller");
+                error!("Error sending mail sending result back to caller");
             }
         }
     }
 
-    /// Listens on rx channel for messages and sends them via SMTP.
+    /// Listens on rx channel for messages and schedules sending them, along with queued mails
+    /// due for another delivery attempt.
     pub async fn run(mut self) {
-        while let Some(mail) = self.rx.recv().await {
-            let (to, subject) = (mail.to.clone(), mail.subject.clone());
-            debug!("Sending mail to: {to}, subject: {subject}");
-
-            // fetch SMTP settings
-            let settings = Settings::get_current_settings();
-            let settings = match SmtpSettings::from_settings(settings) {
-                Ok(settings) => settings,
-                Err(MailError::SmtpNotConfigured) => {
-                    warn!("SMTP not configured, email sending skipped");
-                    continue;
+        let mut queue_interval = interval(MAIL_QUEUE_INTERVAL);
+        loop {
+            tokio::select! {
+                mail = self.rx.recv() => {
+                    let Some(mail) = mail else {
+                        break;
+                    };
+                    // mails without a result channel are queued if sending fails
+                    let queued = (mail.result_tx.is_none() && mail.attachments.is_empty())
+                        .then(|| {
+                            QueuedMail::new(
+                                mail.to.clone(),
+                                mail.subject.clone(),
+                                mail.content.clone(),
+                                mail.category,
+                                String::new(),
+                                Utc::now().naive_utc(),
+                            )
+                        });
+                    self.schedule(mail, queued.map(Queued::New));
                 }
-                Err(err) => {
-                    error!("Error retrieving SMTP settings: {err}");
-                    continue;
-                }
-            };
+                _ = queue_interval.tick() => self.retry_queued().await,
+            }
+        }
+    }
 
-            // Construct lettre Message
-            let result_tx = mail.result_tx.clone();
-            let message: Message = match mail.into_message(&settings.sender) {
-                Ok(message) => message,
+    /// Schedules retries of queued mails due for another delivery attempt.
+    async fn retry_queued(&self) {
+        let mails =
+            match QueuedMail::claim_due(&self.pool, queue::CLAIM_LIMIT, queue::CLAIM_LEASE).await {
+                Ok(mails) => mails,
                 Err(err) => {
-                    error!("Failed to build message to: {to}, subject: {subject}, error: {err}");
-                    continue;
+                    error!("Failed to fetch queued mails: {err}");
+                    return;
                 }
             };
-            // Build mailer and send the message
-            match Self::mailer(settings) {
-                Ok(mailer) => match mailer.send(message).await {
-                    Ok(response) => {
-                        Self::send_result(result_tx, Ok(response.clone()));
-                        info!(
-                            "Mail sent successfully to: {to}, subject: {subject}, response: {response:?}"
-                        );
-                    }
-                    Err(err) => {
-                        error!("Mail sending failed to: {to}, subject: {subject}, error: {err}");
-                        Self::send_result(result_tx, Err(MailError::SmtpError(err)));
-                    }
-                },
-                Err(MailError::SmtpNotConfigured) => {
-                    warn!("SMTP not configured, onboarding email sending skipped");
-                    Self::send_result(result_tx, Err(MailError::SmtpNotConfigured));
-                }
-                Err(err) => {
-                    error!("Error building mailer: {err}");
-                    Self::send_result(result_tx, Err(err));
-                }
-            }
+        for queued in mails {
+            debug!(
+                "Retrying delivery of queued mail to: {}, subject: {}",
+                queued.recipient, queued.subject
+            );
+            let mail = Mail {
+                to: queued.recipient.clone(),
+                subject: queued.subject.clone(),
+                content: queued.content.clone(),
+                attachments: Vec::new(),
+                category: queued.category,
+                result_tx: None,
+            };
+            self.schedule(mail, Some(Queued::Existing(queued)));
         }
     }
 
-    /// Builds mailer object with specified configuration
-    fn mailer(settings: SmtpSettings) -> Result<AsyncSmtpTransport<Tokio1Executor>, MailError> {
-        let builder = match settings.encryption {
-            SmtpEncryption::None => {
-                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(settings.server)
-            }
-            SmtpEncryption::StartTls => {
-                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&settings.server)?
+    /// Schedules sending a mail through the transport selected for its category. Mail which
+    /// failed to be delivered is queued for another attempt if `queued` is given.
+    fn schedule(&self, mail: Mail, queued: Option<Queued>) {
+        let (to, subject) = (mail.to.clone(), mail.subject.clone());
+        debug!("Scheduling mail to: {to}, subject: {subject}");
+
+        let settings = Settings::get_current_settings();
+        let transport = match Transport::for_category(
+            settings,
+            mail.category,
+            &self.token_cache,
+            &self.http_client,
+        ) {
+            Ok(transport) => transport,
+            Err(err @ (MailError::SmtpNotConfigured | MailError::HttpNotConfigured)) => {
+                warn!("{err}, email sending skipped");
+                return;
             }
-            SmtpEncryption::ImplicitTls => {
-                AsyncSmtpTransport::<Tokio1Executor>::relay(&settings.server)?
+            Err(err) => {
+                error!("Error retrieving mail transport settings: {err}");
+                return;
             }
-        }
-        .port(settings.port)
-        .timeout(Some(Duration::from_secs(SMTP_TIMEOUT_SECONDS)));
-
-        // Skip credentials if any of them is empty
-        let builder = if settings.user.is_empty() || settings.password.is_empty() {
-            debug!("SMTP credentials were not provided, skipping username/password authentication");
-            builder
-        } else {
-            builder.credentials(Credentials::new(settings.user, settings.password))
         };
 
-        Ok(builder.build())
+        let result_tx = mail.result_tx.clone();
+        let pool = self.pool.clone();
+        let scheduler = Arc::clone(&self.scheduler);
+        let failover_tx = self.failover_tx.clone();
+        tokio::spawn(async move {
+            let _permit = scheduler.acquire(&recipient_domain(&to)).await;
+            debug!("Sending mail to: {to}, subject: {subject}");
+            let result = transport.send(mail).await;
+            match &result {
+                Ok(response) => info!(
+                    "Mail sent successfully to: {to}, subject: {subject}, response: {response:?}"
+                ),
+                Err(err) => {
+                    error!("Mail sending failed to: {to}, subject: {subject}, error: {err}");
+                }
+            }
+            if let Some(relay) = transport.relay() {
+                let event =
+                    failover::record(relay, result.as_ref().map(|_| ()), Utc::now().naive_utc());
+                if let Some(event) = event {
+                    warn!("SMTP relay failover: {event:?}");
+                    if failover_tx.send(event).is_err() {
+                        error!("Failed to send SMTP failover event");
+                    }
+                }
+            }
+            match (queued, &result) {
+                (Some(Queued::New(mail)), Err(err)) if queue::is_transient(err) => {
+                    queue::enqueue(&pool, mail, err).await;
+                }
+                (Some(Queued::Existing(mail)), result) => {
+                    queue::update(&pool, mail, result.as_ref().map(|_| ())).await;
+                }
+                _ => {}
+            }
+            Self::send_result(result_tx, result);
+        });
     }
 }
 
-/// Builds MailHandler and runs it.
+/// Mail which can be queued for another delivery attempt.
+enum Queued {
+    /// Sent for the first time, queued only if sending fails
+    New(QueuedMail),
+    /// Already in the queue
+    Existing(QueuedMail<Id>),
+}
+
+/// Builds MailHandler and runs it. Changes of the SMTP relay mails are sent through are reported
+/// to `failover_tx`.
 #[instrument(skip_all)]
-pub async fn run_mail_handler(rx: UnboundedReceiver<Mail>) {
+pub async fn run_mail_handler(
+    rx: UnboundedReceiver<Mail>,
+    pool: PgPool,
+    failover_tx: UnboundedSender<FailoverEvent>,
+) {
     info!("Starting mail sending service");
-    MailHandler::new(rx).run().await;
+    let config = server_config();
+    let scheduler = SendScheduler::new(
+        config.mail_max_concurrent_sessions,
+        *config.mail_domain_send_interval,
+    );
+    MailHandler::new(rx, pool, scheduler, failover_tx)
+        .run()
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, str::FromStr};
+
+    use defguard_common::{
+        db::models::smtp_profile::set_smtp_profiles, secret::SecretStringWrapper,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_attachment_types() {
+        for attachment_type in [
+            AttachmentType::Text,
+            AttachmentType::Json,
+            AttachmentType::Calendar(CalendarMethod::Request),
+            AttachmentType::Calendar(CalendarMethod::Cancel),
+            AttachmentType::Pdf,
+            AttachmentType::Zip,
+        ] {
+            let attachment = Attachment::new("file", b"content".to_vec(), attachment_type);
+            let mut headers = lettre::message::header::Headers::new();
+            headers.set(attachment.content_type);
+            assert_eq!(
+                headers.get_raw("Content-Type"),
+                Some(attachment_type.mime_type())
+            );
+        }
+    }
+
+    #[test]
+    fn test_smtp_settings_from_profile() {
+        let profile = SmtpProfile {
+            id: 1,
+            name: "alerts".into(),
+            server: "alerts.example.com".into(),
+            port: 465,
+            encryption: SmtpEncryption::ImplicitTls,
+            username: Some("alerts".into()),
+            password: Some(SecretStringWrapper::from_str("hunter2").unwrap()),
+            sender: "alerts@example.com".into(),
+            sender_name: Some(String::new()),
+            reply_to: None,
+            failover: false,
+        };
+        set_smtp_profiles(BTreeMap::from([(MailCategory::Alert, profile)]));
+
+        let settings = Settings {
+            smtp_server: Some("smtp.example.com".into()),
+            smtp_port: Some(587),
+            smtp_user: Some("defguard".into()),
+            smtp_password: Some(SecretStringWrapper::from_str("secret").unwrap()),
+            smtp_sender: Some("defguard@example.com".into()),
+            smtp_security_sender: Some("security@example.com".into()),
+            ..Default::default()
+        };
+
+        // bound category uses the profile
+        let smtp = SmtpSettings::from_settings(settings.clone(), MailCategory::Alert).unwrap();
+        assert_eq!(smtp.server, "alerts.example.com");
+        assert_eq!(smtp.port, 465);
+        assert_eq!(smtp.encryption, SmtpEncryption::ImplicitTls);
+        assert!(matches!(
+            smtp.auth,
+            SmtpAuth::Password { ref user, ref password } if user == "alerts" && password == "hunter2"
+        ));
+        assert_eq!(
+            smtp.sender.address(MailCategory::Alert),
+            "alerts@example.com"
+        );
+        assert_eq!(smtp.sender.name, None);
+
+        // other categories use settings
+        let smtp = SmtpSettings::from_settings(settings.clone(), MailCategory::Security).unwrap();
+        assert_eq!(smtp.server, "smtp.example.com");
+        assert_eq!(
+            smtp.sender.address(MailCategory::Security),
+            "security@example.com"
+        );
+        let smtp = SmtpSettings::from_settings(settings, MailCategory::Enrollment).unwrap();
+        assert_eq!(
+            smtp.sender.address(MailCategory::Enrollment),
+            "defguard@example.com"
+        );
+
+        set_smtp_profiles(BTreeMap::new());
+        assert!(matches!(
+            SmtpSettings::from_settings(Settings::default(), MailCategory::Alert),
+            Err(MailError::SmtpNotConfigured)
+        ));
+    }
 }
@@ -0,0 +1,153 @@
+//! Access token acquisition for SMTP XOAUTH2 authentication.
+
+use std::time::Duration;
+
+use serde::Deserialize;
+use tokio::time::Instant;
+use tracing::debug;
+
+use crate::MailError;
+
+/// Token lifetime assumed when the authorization server doesn't return `expires_in`.
+const DEFAULT_TOKEN_LIFETIME: Duration = Duration::from_secs(3600);
+/// Tokens are refreshed this long before they expire.
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+/// OAuth2 client credentials grant configuration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct OAuth2Credentials {
+    pub token_url: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub scope: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+}
+
+struct CachedToken {
+    credentials: OAuth2Credentials,
+    access_token: String,
+    expires_at: Instant,
+}
+
+impl CachedToken {
+    fn is_valid_for(&self, credentials: &OAuth2Credentials) -> bool {
+        self.credentials == *credentials && self.expires_at > Instant::now() + TOKEN_REFRESH_MARGIN
+    }
+}
+
+/// Caches access token between sent mails and refreshes it when it's about to expire.
+#[derive(Default)]
+pub(crate) struct TokenCache {
+    client: reqwest::Client,
+    token: Option<CachedToken>,
+}
+
+impl TokenCache {
+    /// Returns cached access token, or obtains a new one if there is none, it's about to expire,
+    /// or it was issued for different credentials.
+    pub async fn access_token(
+        &mut self,
+        credentials: &OAuth2Credentials,
+    ) -> Result<String, MailError> {
+        if let Some(token) = &self.token {
+            if token.is_valid_for(credentials) {
+                return Ok(token.access_token.clone());
+            }
+        }
+
+        debug!(
+            "Requesting SMTP OAuth2 access token from {}",
+            credentials.token_url
+        );
+        let mut form = vec![
+            ("grant_type", "client_credentials"),
+            ("client_id", credentials.client_id.as_str()),
+            ("client_secret", credentials.client_secret.as_str()),
+        ];
+        if let Some(scope) = &credentials.scope {
+            form.push(("scope", scope.as_str()));
+        }
+        let response = self
+            .client
+            .post(&credentials.token_url)
+            .form(&form)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|err| MailError::OAuth2TokenError(err.to_string()))?
+            .json::<TokenResponse>()
+            .await
+            .map_err(|err| MailError::OAuth2TokenError(err.to_string()))?;
+        debug!("Obtained SMTP OAuth2 access token");
+
+        let lifetime = response
+            .expires_in
+            .map_or(DEFAULT_TOKEN_LIFETIME, Duration::from_secs);
+        self.token = Some(CachedToken {
+            credentials: credentials.clone(),
+            access_token: response.access_token.clone(),
+            expires_at: Instant::now() + lifetime,
+        });
+
+        Ok(response.access_token)
+    }
+
+    /// Drops cached token, e.g. after it has been rejected by the SMTP server.
+    pub fn invalidate(&mut self) {
+        self.token = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn credentials() -> OAuth2Credentials {
+        OAuth2Credentials {
+            token_url: "https://login.example.com/token".into(),
+            client_id: "client".into(),
+            client_secret: "secret".into(),
+            scope: Some("https://outlook.office365.com/.default".into()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cached_token_reused() {
+        let mut cache = TokenCache {
+            token: Some(CachedToken {
+                credentials: credentials(),
+                access_token: "token".into(),
+                expires_at: Instant::now() + DEFAULT_TOKEN_LIFETIME,
+            }),
+            ..Default::default()
+        };
+        assert_eq!(cache.access_token(&credentials()).await.unwrap(), "token");
+    }
+
+    #[test]
+    fn test_cached_token_validity() {
+        let token = CachedToken {
+            credentials: credentials(),
+            access_token: "token".into(),
+            expires_at: Instant::now() + DEFAULT_TOKEN_LIFETIME,
+        };
+        assert!(token.is_valid_for(&credentials()));
+
+        // different credentials
+        let mut other = credentials();
+        other.client_id = "other".into();
+        assert!(!token.is_valid_for(&other));
+
+        // about to expire
+        let token = CachedToken {
+            expires_at: Instant::now() + TOKEN_REFRESH_MARGIN / 2,
+            ..token
+        };
+        assert!(!token.is_valid_for(&credentials()));
+    }
+}
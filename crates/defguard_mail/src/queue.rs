@@ -0,0 +1,128 @@
+//! Persistent queue of mails retried after transient delivery failures.
+//!
+//! Mails which failed to be delivered because of a transient failure, e.g. unreachable server,
+//! 4xx SMTP response or 5xx HTTP API response, are stored in the database and retried with
+//! exponential backoff.
+//! Delivery is given up after `MAX_ATTEMPTS` or on a permanent failure; such mails stay in the
+//! queue until removed by an admin. Mails sent with a result channel are not queued, as their
+//! senders handle failures themselves.
+
+use chrono::{TimeDelta, Utc};
+use defguard_common::db::{Id, models::mail_queue::QueuedMail};
+use reqwest::StatusCode;
+use sqlx::PgPool;
+use tracing::{debug, error, info, warn};
+
+use crate::MailError;
+
+/// Delivery is given up after this many failed attempts.
+pub(crate) const MAX_ATTEMPTS: i32 = 8;
+/// Delay before the second attempt, doubled after each following failure.
+const BASE_RETRY_DELAY: TimeDelta = TimeDelta::minutes(1);
+const MAX_RETRY_DELAY: TimeDelta = TimeDelta::hours(6);
+/// Claimed mails are not picked up again for this long, in case sending them gets stuck.
+pub(crate) const CLAIM_LEASE: TimeDelta = TimeDelta::minutes(10);
+/// Maximum number of mails retried at once.
+pub(crate) const CLAIM_LIMIT: i64 = 50;
+
+/// Delay before the next attempt after a given number of failed attempts.
+pub(crate) fn retry_delay(attempts: i32) -> TimeDelta {
+    let exponent = u32::try_from(attempts.saturating_sub(1)).unwrap_or_default();
+    2_i32
+        .checked_pow(exponent)
+        .and_then(|factor| BASE_RETRY_DELAY.checked_mul(factor))
+        .map_or(MAX_RETRY_DELAY, |delay| delay.min(MAX_RETRY_DELAY))
+}
+
+/// Checks if sending might succeed when retried later.
+pub(crate) fn is_transient(err: &MailError) -> bool {
+    match err {
+        MailError::SmtpError(err) => !err.is_permanent(),
+        MailError::OAuth2TokenError(_) => true,
+        // connection failures, server errors and rate limiting
+        MailError::HttpError(err) => err.status().map_or(!err.is_builder(), |status| {
+            status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+        }),
+        _ => false,
+    }
+}
+
+/// Stores a mail which failed to be delivered for the first time.
+pub(crate) async fn enqueue(pool: &PgPool, mail: QueuedMail, err: &MailError) {
+    let next_attempt_at = Utc::now().naive_utc() + retry_delay(mail.attempts);
+    let (recipient, subject) = (mail.recipient.clone(), mail.subject.clone());
+    let mail = QueuedMail {
+        last_error: err.to_string(),
+        next_attempt_at: Some(next_attempt_at),
+        ..mail
+    };
+    match mail.save(pool).await {
+        Ok(_) => info!(
+            "Mail to: {recipient}, subject: {subject} queued for another attempt at \
+            {next_attempt_at}"
+        ),
+        Err(err) => error!("Failed to queue mail to: {recipient}, subject: {subject}: {err}"),
+    }
+}
+
+/// Updates a queued mail after another delivery attempt.
+pub(crate) async fn update(
+    pool: &PgPool,
+    mut mail: QueuedMail<Id>,
+    result: Result<(), &MailError>,
+) {
+    let result = match result {
+        Ok(()) => {
+            info!(
+                "Queued mail to: {}, subject: {} delivered after {} failed attempts",
+                mail.recipient, mail.subject, mail.attempts
+            );
+            mail.delete(pool).await
+        }
+        Err(err) => {
+            mail.attempts += 1;
+            mail.last_error = err.to_string();
+            if is_transient(err) && mail.attempts < MAX_ATTEMPTS {
+                let next_attempt_at = Utc::now().naive_utc() + retry_delay(mail.attempts);
+                debug!(
+                    "Queued mail to: {}, subject: {} will be retried at {next_attempt_at}",
+                    mail.recipient, mail.subject
+                );
+                mail.next_attempt_at = Some(next_attempt_at);
+            } else {
+                warn!(
+                    "Giving up delivery of mail to: {}, subject: {} after {} attempts",
+                    mail.recipient, mail.subject, mail.attempts
+                );
+                mail.next_attempt_at = None;
+            }
+            mail.save(pool).await
+        }
+    };
+    if let Err(err) = result {
+        error!("Failed to update mail queue: {err}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(retry_delay(1), TimeDelta::minutes(1));
+        assert_eq!(retry_delay(2), TimeDelta::minutes(2));
+        assert_eq!(retry_delay(5), TimeDelta::minutes(16));
+        assert_eq!(retry_delay(9), TimeDelta::hours(4) + TimeDelta::minutes(16));
+        assert_eq!(retry_delay(10), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(100), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(0), TimeDelta::minutes(1));
+    }
+
+    #[test]
+    fn test_is_transient() {
+        assert!(is_transient(&MailError::OAuth2TokenError("timeout".into())));
+        assert!(!is_transient(&MailError::SmtpNotConfigured));
+        assert!(!is_transient(&MailError::InvalidPort(0)));
+    }
+}
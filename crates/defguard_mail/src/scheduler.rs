@@ -0,0 +1,116 @@
+//! Send scheduling which keeps mass mailing (e.g. bulk enrollment) within SMTP provider limits.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::{
+    sync::{OwnedSemaphorePermit, Semaphore},
+    time::{Instant, sleep_until},
+};
+
+/// Limits the number of concurrent SMTP sessions and spaces out mails sent to the same
+/// recipient domain.
+pub(crate) struct SendScheduler {
+    sessions: Arc<Semaphore>,
+    domain_interval: Duration,
+    // earliest time at which next mail to a given domain can be sent
+    domain_slots: Mutex<HashMap<String, Instant>>,
+}
+
+impl SendScheduler {
+    #[must_use]
+    pub fn new(max_sessions: usize, domain_interval: Duration) -> Self {
+        Self {
+            sessions: Arc::new(Semaphore::new(max_sessions.max(1))),
+            domain_interval,
+            domain_slots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Reserves the next send slot for a domain. Slots are handed out in call order,
+    /// so mails to the same domain are sent in the order they were scheduled.
+    fn reserve_slot(&self, domain: &str) -> Instant {
+        let now = Instant::now();
+        let mut slots = self
+            .domain_slots
+            .lock()
+            .expect("Failed to lock domain slots");
+        // forget domains which are no longer throttled
+        slots.retain(|_, slot| *slot > now);
+        let slot = slots.get(domain).copied().unwrap_or(now);
+        slots.insert(domain.to_string(), slot + self.domain_interval);
+        slot
+    }
+
+    /// Waits until a mail to the given domain can be sent. Returned permit must be held
+    /// for the duration of the SMTP session.
+    pub async fn acquire(&self, domain: &str) -> OwnedSemaphorePermit {
+        sleep_until(self.reserve_slot(domain)).await;
+        Arc::clone(&self.sessions)
+            .acquire_owned()
+            .await
+            .expect("SMTP session semaphore closed")
+    }
+}
+
+/// Extracts lowercase domain part of an email address used as a throttling key.
+pub(crate) fn recipient_domain(address: &str) -> String {
+    address
+        .rsplit_once('@')
+        .map_or(address, |(_, domain)| domain)
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recipient_domain() {
+        assert_eq!(recipient_domain("user@Example.COM"), "example.com");
+        assert_eq!(recipient_domain("a@b@example.com"), "example.com");
+        assert_eq!(recipient_domain("example.com"), "example.com");
+    }
+
+    #[tokio::test]
+    async fn test_domain_throttling() {
+        let interval = Duration::from_secs(60);
+        let scheduler = SendScheduler::new(2, interval);
+
+        // consecutive mails to the same domain are spaced out
+        let first = scheduler.reserve_slot("example.com");
+        assert_eq!(scheduler.reserve_slot("example.com"), first + interval);
+        assert_eq!(scheduler.reserve_slot("example.com"), first + interval * 2);
+        // other domains are not affected
+        assert!(scheduler.reserve_slot("defguard.net") < first + interval);
+    }
+
+    #[tokio::test]
+    async fn test_domain_throttle_expiry() {
+        let interval = Duration::from_millis(10);
+        let scheduler = SendScheduler::new(2, interval);
+
+        let first = scheduler.reserve_slot("example.com");
+        scheduler.reserve_slot("defguard.net");
+        sleep_until(first + interval * 2).await;
+
+        // expired slots are not reused and get pruned
+        assert!(scheduler.reserve_slot("example.com") >= first + interval * 2);
+        assert_eq!(scheduler.domain_slots.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_session_limit() {
+        let scheduler = SendScheduler::new(2, Duration::ZERO);
+        let first = scheduler.acquire("a.com").await;
+        let _second = scheduler.acquire("b.com").await;
+        assert_eq!(scheduler.sessions.available_permits(), 0);
+
+        drop(first);
+        let _third = scheduler.acquire("c.com").await;
+        assert_eq!(scheduler.sessions.available_permits(), 0);
+    }
+}
@@ -1,13 +1,22 @@
-use std::collections::HashMap;
-
-use chrono::{Datelike, NaiveDateTime, Utc};
-use defguard_common::{VERSION, config::server_config, db::models::user::MFAMethod};
+use std::{cell::RefCell, collections::HashMap};
+
+use chrono::{Datelike, NaiveDateTime, TimeDelta, Utc};
+use defguard_common::{
+    VERSION,
+    config::{SERVER_CONFIG, server_config},
+    db::models::{
+        mail_context::{mail_languages, translated_section},
+        mail_template::mail_template_override,
+        mail_variable::{MAIL_VARIABLES_CONTEXT_KEY, get_mail_variables},
+        user::MFAMethod,
+    },
+};
 use reqwest::Url;
-use serde::Serialize;
+use serde::{Serialize, Serializer};
 use serde_json::Value;
 use tera::{Context, Function, Tera};
 use thiserror::Error;
-use tracing::debug;
+use tracing::{debug, warn};
 
 static MAIL_BASE: &str = include_str!("../templates/base.tera");
 static MAIL_MACROS: &str = include_str!("../templates/macros.tera");
@@ -22,6 +31,17 @@ static MAIL_NEW_DEVICE_ADDED: &str = include_str!("../templates/mail_new_device_
 static MAIL_GATEWAY_DISCONNECTED: &str =
     include_str!("../templates/mail_gateway_disconnected.tera");
 static MAIL_GATEWAY_RECONNECTED: &str = include_str!("../templates/mail_gateway_reconnected.tera");
+static MAIL_SMTP_FAILOVER: &str = include_str!("../templates/mail_smtp_failover.tera");
+static MAIL_PROXY_CONNECTIVITY: &str = include_str!("../templates/mail_proxy_connectivity.tera");
+static MAIL_NOTIFICATION_DIGEST: &str = include_str!("../templates/mail_notification_digest.tera");
+static MAIL_MAINTENANCE_WINDOW: &str = include_str!("../templates/mail_maintenance_window.tera");
+static MAIL_DEVICE_DISCONNECTED: &str = include_str!("../templates/mail_device_disconnected.tera");
+static MAIL_DEVICE_APPROVAL_REQUEST: &str =
+    include_str!("../templates/mail_device_approval_request.tera");
+static MAIL_SELF_REGISTRATION_VERIFICATION: &str =
+    include_str!("../templates/mail_self_registration_verification.tera");
+static MAIL_SELF_REGISTRATION_ADMIN_NOTIFICATION: &str =
+    include_str!("../templates/mail_self_registration_admin_notification.tera");
 static MAIL_MFA_CONFIGURED: &str = include_str!("../templates/mail_mfa_configured.tera");
 static MAIL_NEW_DEVICE_LOGIN: &str = include_str!("../templates/mail_new_device_login.tera");
 static MAIL_NEW_DEVICE_OCID_LOGIN: &str =
@@ -29,18 +49,183 @@ static MAIL_NEW_DEVICE_OCID_LOGIN: &str =
 static MAIL_EMAIL_MFA_ACTIVATION: &str =
     include_str!("../templates/mail_email_mfa_activation.tera");
 static MAIL_EMAIL_MFA_CODE: &str = include_str!("../templates/mail_email_mfa_code.tera");
+static MAIL_ANNOUNCEMENT: &str = include_str!("../templates/mail_announcement.tera");
 static MAIL_PASSWORD_RESET_START: &str =
     include_str!("../templates/mail_password_reset_start.tera");
 static MAIL_PASSWORD_RESET_SUCCESS: &str =
     include_str!("../templates/mail_password_reset_success.tera");
+static MAIL_HELPDESK_PASSWORD_RESET: &str =
+    include_str!("../templates/mail_helpdesk_password_reset.tera");
+static MAIL_SECURITY_SUMMARY: &str = include_str!("../templates/mail_security_summary.tera");
 static MAIL_DATETIME_FORMAT: &str = "%A, %B %d, %Y at %r";
 
+pub const ENROLLMENT_START_TEMPLATE: &str = "mail_enrollment_start";
+pub const DESKTOP_START_TEMPLATE: &str = "mail_desktop_start";
+pub const PASSWORD_RESET_START_TEMPLATE: &str = "mail_password_reset_start";
+pub const PASSWORD_RESET_SUCCESS_TEMPLATE: &str = "mail_password_reset_success";
+
+/// Name of the template context object holding texts of localized sections.
+const LOCALIZED_TEXT_CONTEXT_KEY: &str = "text";
+/// Section holding the mail subject.
+const SUBJECT_SECTION: &str = "subject";
+
+// Built-in English texts of localized template sections, which can be translated in the
+// `mail_context` table. Texts are rendered as templates with the context of the mail.
+static ENROLLMENT_START_TEXT: &[(&str, &str)] = &[
+    (SUBJECT_SECTION, "Defguard user enrollment"),
+    (
+        "intro",
+        "You're receiving this email because a new account has been created for you.",
+    ),
+    (
+        "options",
+        "In order to start the enrollment process please choose one of the following options:",
+    ),
+    ("desktop_title", "1. Enrollment by desktop client"),
+    (
+        "desktop_download",
+        "Download the official defguard desktop client for Windows, macOS or Linux:",
+    ),
+    (
+        "desktop_instance",
+        "After installation, please add a defguard instance by entering:",
+    ),
+    ("instance_url", "Instance URL:"),
+    ("enrollment_token", "Enrollment token:"),
+    (
+        "desktop_validity",
+        "Please note that: the token is only valid for 24 hours after receiving this email. \
+        When the enrollment process starts user will have 10 minutes to complete the process.",
+    ),
+    (
+        "desktop_docs",
+        "For more details go to the desktop client documentation:",
+    ),
+    ("web_title", "2. Enrollment via Web Browser"),
+    (
+        "web_description",
+        "If you choose this option, you will be able to change your account details, set \
+        a password, and <b>only</b> configure a standard WireGuard client device - but not the \
+        official defguard desktop client.\nDesktop client can still be activated later, by \
+        accessing your profile in defguard:",
+    ),
+    (
+        "web_url",
+        "If you wish to do enrollment via Web, please copy & paste the following URL in your \
+        browser:",
+    ),
+    (
+        "web_validity",
+        "Please note that: this option is only valid for 24 hours after receiving this email. \
+        When the enrollment process starts user will have 10 minutes to complete the process.",
+    ),
+    (
+        "buttons",
+        "You can also click the buttons below to start the enrollment on website or within \
+        desktop client:",
+    ),
+    ("web_button", "Start enrollment"),
+    ("desktop_button", "Enroll with desktop client"),
+];
+static DESKTOP_START_TEXT: &[(&str, &str)] = &[
+    (SUBJECT_SECTION, "Defguard desktop client configuration"),
+    (
+        "intro",
+        "You're receiving this email to configure a new desktop client.",
+    ),
+    (
+        "instructions",
+        "Please paste this URL and token in your desktop client:",
+    ),
+    ("url", "URL:"),
+    ("token", "Token:"),
+    ("link", "Or use link below"),
+    ("button", "Configure your desktop client"),
+];
+static PASSWORD_RESET_START_TEXT: &[(&str, &str)] = &[
+    (SUBJECT_SECTION, "Defguard: Password reset"),
+    ("title", "Password reset"),
+    (
+        "instructions",
+        "If you wish to reset your password, please copy & paste the following URL in your \
+        browser:",
+    ),
+    (
+        "validity",
+        "Please note that the link is valid for {{ link_valid_for }} (until {{ link_expires_at }} \
+        UTC) and can be used {% if link_max_uses == 1 %}only once{% else %}{{ link_max_uses }} \
+        times{% endif %}.",
+    ),
+    ("button_hint", "Or click the button below:"),
+    ("button", "Reset password"),
+];
+static PASSWORD_RESET_SUCCESS_TEXT: &[(&str, &str)] = &[
+    (SUBJECT_SECTION, "Defguard: Password reset success"),
+    ("title", "Password reset"),
+    ("message", "Your password has been successfully changed."),
+];
+
+/// Built-in mail templates which can be overridden by admins.
+pub static BUILTIN_TEMPLATES: &[(&str, &str)] = &[
+    ("mail_test", MAIL_TEST),
+    (ENROLLMENT_START_TEMPLATE, MAIL_ENROLLMENT_START),
+    (DESKTOP_START_TEMPLATE, MAIL_DESKTOP_START),
+    ("mail_enrollment_welcome", MAIL_ENROLLMENT_WELCOME),
+    ("mail_announcement", MAIL_ANNOUNCEMENT),
+    (
+        "mail_enrollment_admin_notification",
+        MAIL_ENROLLMENT_ADMIN_NOTIFICATION,
+    ),
+    ("mail_support_data", MAIL_SUPPORT_DATA),
+    ("mail_new_device_added", MAIL_NEW_DEVICE_ADDED),
+    ("mail_device_disconnected", MAIL_DEVICE_DISCONNECTED),
+    ("mail_device_approval_request", MAIL_DEVICE_APPROVAL_REQUEST),
+    (
+        "mail_self_registration_verification",
+        MAIL_SELF_REGISTRATION_VERIFICATION,
+    ),
+    (
+        "mail_self_registration_admin_notification",
+        MAIL_SELF_REGISTRATION_ADMIN_NOTIFICATION,
+    ),
+    ("mail_mfa_configured", MAIL_MFA_CONFIGURED),
+    ("mail_new_device_login", MAIL_NEW_DEVICE_LOGIN),
+    ("mail_new_device_ocid_login", MAIL_NEW_DEVICE_OCID_LOGIN),
+    ("mail_gateway_disconnected", MAIL_GATEWAY_DISCONNECTED),
+    ("mail_gateway_reconnected", MAIL_GATEWAY_RECONNECTED),
+    ("mail_smtp_failover", MAIL_SMTP_FAILOVER),
+    ("mail_proxy_connectivity", MAIL_PROXY_CONNECTIVITY),
+    ("mail_notification_digest", MAIL_NOTIFICATION_DIGEST),
+    ("mail_maintenance_window", MAIL_MAINTENANCE_WINDOW),
+    ("mail_email_mfa_activation", MAIL_EMAIL_MFA_ACTIVATION),
+    ("mail_email_mfa_code", MAIL_EMAIL_MFA_CODE),
+    (PASSWORD_RESET_START_TEMPLATE, MAIL_PASSWORD_RESET_START),
+    ("mail_helpdesk_password_reset", MAIL_HELPDESK_PASSWORD_RESET),
+    (PASSWORD_RESET_SUCCESS_TEMPLATE, MAIL_PASSWORD_RESET_SUCCESS),
+    ("mail_security_summary", MAIL_SECURITY_SUMMARY),
+];
+
+thread_local! {
+    // Override which is being previewed, rendered in place of the stored one.
+    static PREVIEW: RefCell<Option<(String, String)>> = const { RefCell::new(None) };
+}
+
+/// Localized templates along with built-in English texts of their sections.
+pub static LOCALIZED_TEMPLATES: &[(&str, &[(&str, &str)])] = &[
+    (ENROLLMENT_START_TEMPLATE, ENROLLMENT_START_TEXT),
+    (DESKTOP_START_TEMPLATE, DESKTOP_START_TEXT),
+    (PASSWORD_RESET_START_TEMPLATE, PASSWORD_RESET_START_TEXT),
+    (PASSWORD_RESET_SUCCESS_TEMPLATE, PASSWORD_RESET_SUCCESS_TEXT),
+];
+
 #[derive(Error, Debug)]
 pub enum TemplateError {
     #[error("Failed to generate email MFA code")]
     MfaError,
     #[error(transparent)]
     TemplateError(#[from] tera::Error),
+    #[error("Unknown mail template {0}")]
+    UnknownTemplate(String),
 }
 
 struct NoOp(&'static str);
@@ -51,6 +236,43 @@ impl Function for NoOp {
     }
 }
 
+// Mail images are loaded from here unless `DEFGUARD_MAIL_ASSET_URL` is configured.
+static DEFAULT_ASSET_URL: &str = "https://defguard.net/images";
+
+/// URL of a mail image. Images served from the configured asset URL get a hash of Defguard
+/// version appended, so caches pick up images changed by an update.
+#[must_use]
+pub fn asset_url(path: &str) -> String {
+    // configuration isn't needed to render mails otherwise, so it may not be set yet
+    let base = SERVER_CONFIG
+        .get()
+        .and_then(|config| config.mail_asset_url.as_ref());
+    match base {
+        Some(base) => {
+            let hash = sha256::digest(VERSION);
+            format!(
+                "{}/{path}?v={}",
+                base.as_str().trim_end_matches('/'),
+                &hash[..8]
+            )
+        }
+        None => format!("{DEFAULT_ASSET_URL}/{path}"),
+    }
+}
+
+/// Tera function `asset_url(path=...)` available in mail templates.
+struct AssetUrl;
+
+impl Function for AssetUrl {
+    fn call(&self, args: &HashMap<String, Value>) -> tera::Result<Value> {
+        let path = args
+            .get("path")
+            .and_then(Value::as_str)
+            .ok_or_else(|| tera::Error::msg("asset_url requires a `path` argument"))?;
+        Ok(Value::String(asset_url(path)))
+    }
+}
+
 /// Return a safe instance of Tera, as Tera is vulnerable to `get_env()` function exploit.
 /// See: https://github.com/Keats/tera/issues/677
 #[must_use]
@@ -62,6 +284,249 @@ pub fn safe_tera() -> Tera {
     tera
 }
 
+/// Add instance-level mail variables to template context, so they can be referenced
+/// as `instance.<name>`.
+pub fn insert_mail_variables(context: &mut Context) {
+    context.insert(MAIL_VARIABLES_CONTEXT_KEY, &*get_mail_variables());
+}
+
+/// Sections of a localized template along with their built-in English texts.
+#[must_use]
+pub fn template_sections(template: &str) -> Option<&'static [(&'static str, &'static str)]> {
+    LOCALIZED_TEMPLATES
+        .iter()
+        .find(|(name, _)| *name == template)
+        .map(|(_, sections)| *sections)
+}
+
+/// Render a section in the first of the recipient's mail languages it's translated to,
+/// falling back to the built-in text. Translations which fail to render are skipped.
+fn render_section(
+    template: &str,
+    section: &str,
+    default: &str,
+    languages: &[String],
+    context: &Context,
+) -> Result<String, TemplateError> {
+    let mut tera = safe_tera();
+    if let Some(translation) = translated_section(template, section, languages) {
+        match tera.render_str(&translation, context) {
+            Ok(text) => return Ok(text),
+            Err(err) => {
+                warn!("Failed to render {section} section translation of {template}: {err}");
+            }
+        }
+    }
+    Ok(tera.render_str(default, context)?)
+}
+
+/// Add texts of localized template sections to the context, so they can be referenced
+/// as `text.<section>`.
+fn insert_localized_text(
+    context: &mut Context,
+    template: &str,
+    language: Option<&str>,
+) -> Result<(), TemplateError> {
+    let languages = mail_languages(language);
+    let mut text = HashMap::new();
+    for (section, default) in template_sections(template).unwrap_or_default() {
+        text.insert(
+            *section,
+            render_section(template, section, default, &languages, context)?,
+        );
+    }
+    context.insert(LOCALIZED_TEXT_CONTEXT_KEY, &text);
+    Ok(())
+}
+
+/// Subject of a localized mail in the recipient's language. Mail variables can be referenced
+/// as `instance.<name>`.
+pub fn localized_subject(template: &str, language: Option<&str>) -> Result<String, TemplateError> {
+    let default = template_sections(template)
+        .unwrap_or_default()
+        .iter()
+        .find(|(section, _)| *section == SUBJECT_SECTION)
+        .map_or("Defguard", |(_, text)| *text);
+    let mut context = Context::new();
+    insert_mail_variables(&mut context);
+    render_section(
+        template,
+        SUBJECT_SECTION,
+        default,
+        &mail_languages(language),
+        &context,
+    )
+}
+
+/// Built-in source of a mail template.
+#[must_use]
+pub fn builtin_template(name: &str) -> Option<&'static str> {
+    BUILTIN_TEMPLATES
+        .iter()
+        .find(|(template, _)| *template == name)
+        .map(|(_, source)| *source)
+}
+
+/// Render a mail template, using the admin override if there is one. Overrides which fail to
+/// render are skipped in favour of the built-in template.
+fn render_template(
+    tera: &mut Tera,
+    name: &str,
+    builtin: &str,
+    context: &Context,
+) -> Result<String, TemplateError> {
+    let preview = PREVIEW.with_borrow(|preview| {
+        preview
+            .as_ref()
+            .filter(|(template, _)| template == name)
+            .map(|(_, source)| source.clone())
+    });
+    if let Some(source) = preview {
+        tera.add_raw_template(name, &source)?;
+        return Ok(tera.render(name, context)?);
+    }
+    if let Some(source) = mail_template_override(name) {
+        match tera
+            .add_raw_template(name, &source)
+            .and_then(|()| tera.render(name, context))
+        {
+            Ok(mail) => return Ok(mail),
+            Err(err) => {
+                warn!("Failed to render override of {name} mail template, using built-in: {err}");
+            }
+        }
+    }
+    tera.add_raw_template(name, builtin)?;
+    Ok(tera.render(name, context)?)
+}
+
+/// Render a mail template with `source` in place of the built-in one and sample data,
+/// to make sure an override works before it's activated.
+pub fn preview_template(name: &str, source: &str) -> Result<String, TemplateError> {
+    if builtin_template(name).is_none() {
+        return Err(TemplateError::UnknownTemplate(name.to_string()));
+    }
+    PREVIEW.set(Some((name.to_string(), source.to_string())));
+    let mail = sample_mail(name, None);
+    PREVIEW.set(None);
+    mail
+}
+
+/// Render a mail template with supplied context, without sending it. Base template context,
+/// mail variables and texts of localized templates are added like for sent mails.
+pub fn render_mail(
+    name: &str,
+    context: Value,
+    language: Option<&str>,
+) -> Result<String, TemplateError> {
+    let builtin =
+        builtin_template(name).ok_or_else(|| TemplateError::UnknownTemplate(name.to_string()))?;
+    let (mut tera, mut context) =
+        get_base_tera(Some(Context::from_value(context)?), None, None, None)?;
+    if template_sections(name).is_some() {
+        insert_localized_text(&mut context, name, language)?;
+    }
+    render_template(&mut tera, name, builtin, &context)
+}
+
+/// Render a mail template with sample data, in the given language if it's localized.
+pub fn sample_mail(name: &str, language: Option<&str>) -> Result<String, TemplateError> {
+    let url = server_config().enrollment_url.clone();
+    let user = UserContext {
+        first_name: "John".into(),
+        last_name: "Doe".into(),
+    };
+    let session = SessionContext {
+        ip_address: "10.0.0.1".into(),
+        device_info: Some("Firefox on Linux".into()),
+    };
+    let expires_at = Utc::now().naive_utc() + TimeDelta::hours(24);
+    let locations = ["Office".to_string()];
+    match name {
+        "mail_test" => test_mail(Some(&session)),
+        ENROLLMENT_START_TEMPLATE => enrollment_start_mail(Context::new(), url, "TOKEN", language),
+        DESKTOP_START_TEMPLATE => desktop_start_mail(Context::new(), &url, "TOKEN", language),
+        "mail_enrollment_welcome" => enrollment_welcome_mail("Welcome", None, None),
+        "mail_announcement" => announcement_mail("Announcement"),
+        "mail_enrollment_admin_notification" => {
+            enrollment_admin_notification(&user, &user, "10.0.0.1", None)
+        }
+        "mail_support_data" => support_data_mail(),
+        "mail_new_device_added" => new_device_added_mail(
+            "laptop",
+            "PUBLIC_KEY",
+            &[TemplateLocation {
+                name: "Office".into(),
+                assigned_ips: "10.1.1.2".into(),
+            }],
+            None,
+            None,
+        ),
+        "mail_device_disconnected" => device_disconnected_mail("laptop", &locations),
+        "mail_device_approval_request" => {
+            device_approval_request_mail("laptop", "PUBLIC_KEY", "jdoe", &locations, expires_at)
+        }
+        "mail_self_registration_verification" => {
+            self_registration_verification_mail("jdoe", "TOKEN")
+        }
+        "mail_self_registration_admin_notification" => {
+            self_registration_admin_notification(&user, "jdoe", "jdoe@example.com")
+        }
+        "mail_mfa_configured" => mfa_configured_mail(Some(&session), &MFAMethod::OneTimePassword),
+        "mail_new_device_login" => new_device_login_mail(
+            &session,
+            Utc::now().naive_utc(),
+            "Firefox on Linux",
+            Some("DE"),
+            Some("Berlin"),
+        ),
+        "mail_new_device_ocid_login" => new_device_ocid_login_mail(&session, "Application"),
+        "mail_gateway_disconnected" => gateway_disconnected_mail("gateway", "10.0.0.2", "Office"),
+        "mail_gateway_reconnected" => gateway_reconnected_mail("gateway", "10.0.0.2", "Office"),
+        "mail_smtp_failover" => smtp_failover_mail(Some("Connection refused")),
+        "mail_proxy_connectivity" => proxy_connectivity_mail("https://proxy.example.com", false),
+        "mail_notification_digest" => notification_digest_mail(
+            &[
+                DigestNotification {
+                    occurred_at: expires_at,
+                    message: "Gateway gateway (10.0.0.2) in location Office disconnected.".into(),
+                },
+                DigestNotification {
+                    occurred_at: expires_at,
+                    message: "Gateway gateway (10.0.0.2) in location Office reconnected.".into(),
+                },
+            ],
+            15,
+        ),
+        "mail_maintenance_window" => maintenance_window_mail(
+            "Office",
+            expires_at,
+            expires_at + TimeDelta::hours(2),
+            "Gateway upgrade",
+            false,
+        ),
+        "mail_email_mfa_activation" => email_mfa_activation_mail(&user, "123456", Some(&session)),
+        "mail_email_mfa_code" => email_mfa_code_mail(&user, "123456", Some(&session)),
+        PASSWORD_RESET_START_TEMPLATE => {
+            email_password_reset_mail(url, "TOKEN", expires_at, 1, None, None, language)
+        }
+        "mail_helpdesk_password_reset" => {
+            helpdesk_password_reset_mail("jdoe", "admin", "123456", expires_at)
+        }
+        PASSWORD_RESET_SUCCESS_TEMPLATE => email_password_reset_success_mail(None, None, language),
+        "mail_security_summary" => security_summary_mail(&SecuritySummary {
+            period_start: expires_at - TimeDelta::days(8),
+            period_end: expires_at - TimeDelta::days(1),
+            new_devices: Vec::new(),
+            failed_logins: Vec::new(),
+            flapping_gateways: Vec::new(),
+            license_limits: Vec::new(),
+            expiring_certificates: Vec::new(),
+        }),
+        _ => Err(TemplateError::UnknownTemplate(name.to_string())),
+    }
+}
+
 pub struct SessionContext {
     pub ip_address: String,
     pub device_info: Option<String>,
@@ -80,6 +545,7 @@ fn get_base_tera(
 ) -> Result<(Tera, Context), TemplateError> {
     let mut tera = safe_tera();
     let mut context = external_context.unwrap_or_default();
+    tera.register_function("asset_url", AssetUrl);
     tera.add_raw_template("base.tera", MAIL_BASE)?;
     tera.add_raw_template("macros.tera", MAIL_MACROS)?;
     // supply context required by base
@@ -88,6 +554,7 @@ fn get_base_tera(
     let current_year = format!("{:04}", now.year());
     context.insert("current_year", &current_year);
     context.insert("date_now", &now.format(MAIL_DATETIME_FORMAT).to_string());
+    insert_mail_variables(&mut context);
 
     if let Some(current_session) = session {
         let device_info = &current_session.device_info;
@@ -109,8 +576,7 @@ fn get_base_tera(
 // sends test message when requested during SMTP configuration process
 pub fn test_mail(session: Option<&SessionContext>) -> Result<String, TemplateError> {
     let (mut tera, context) = get_base_tera(None, session, None, None)?;
-    tera.add_raw_template("mail_test", MAIL_TEST)?;
-    Ok(tera.render("mail_test", &context)?)
+    render_template(&mut tera, "mail_test", MAIL_TEST, &context)
 }
 
 // mail with link to enrollment service
@@ -118,6 +584,7 @@ pub fn enrollment_start_mail(
     context: Context,
     mut enrollment_service_url: Url,
     enrollment_token: &str,
+    language: Option<&str>,
 ) -> Result<String, TemplateError> {
     debug!("Render an enrollment start mail template for the user.");
     let (mut tera, mut context) = get_base_tera(Some(context), None, None, None)?;
@@ -133,26 +600,35 @@ pub fn enrollment_start_mail(
         .append_pair("token", enrollment_token);
 
     context.insert("link_url", &enrollment_service_url.to_string());
-
-    tera.add_raw_template("mail_enrollment_start", MAIL_ENROLLMENT_START)?;
-
-    Ok(tera.render("mail_enrollment_start", &context)?)
+    insert_localized_text(&mut context, ENROLLMENT_START_TEMPLATE, language)?;
+
+    render_template(
+        &mut tera,
+        ENROLLMENT_START_TEMPLATE,
+        MAIL_ENROLLMENT_START,
+        &context,
+    )
 }
 // mail with link to enrollment service
 pub fn desktop_start_mail(
     context: Context,
     enrollment_service_url: &Url,
     enrollment_token: &str,
+    language: Option<&str>,
 ) -> Result<String, TemplateError> {
     debug!("Render a mail template for desktop activation.");
     let (mut tera, mut context) = get_base_tera(Some(context), None, None, None)?;
 
-    tera.add_raw_template("mail_desktop_start", MAIL_DESKTOP_START)?;
-
     context.insert("url", &enrollment_service_url.to_string());
     context.insert("token", enrollment_token);
-
-    Ok(tera.render("mail_desktop_start", &context)?)
+    insert_localized_text(&mut context, DESKTOP_START_TEMPLATE, language)?;
+
+    render_template(
+        &mut tera,
+        DESKTOP_START_TEMPLATE,
+        MAIL_DESKTOP_START,
+        &context,
+    )
 }
 
 // welcome message sent when activating an account through enrollment
@@ -164,7 +640,6 @@ pub fn enrollment_welcome_mail(
 ) -> Result<String, TemplateError> {
     debug!("Render a welcome mail template for user enrollment.");
     let (mut tera, mut context) = get_base_tera(None, None, ip_address, device_info)?;
-    tera.add_raw_template("mail_enrollment_welcome", MAIL_ENROLLMENT_WELCOME)?;
 
     // convert content to HTML
     let parser = pulldown_cmark::Parser::new(content);
@@ -173,7 +648,28 @@ pub fn enrollment_welcome_mail(
 
     context.insert("welcome_message_content", &html_output);
 
-    Ok(tera.render("mail_enrollment_welcome", &context)?)
+    render_template(
+        &mut tera,
+        "mail_enrollment_welcome",
+        MAIL_ENROLLMENT_WELCOME,
+        &context,
+    )
+}
+
+// announcement broadcast by an administrator
+// content is stored in markdown, so it's parsed into HTML
+pub fn announcement_mail(content: &str) -> Result<String, TemplateError> {
+    debug!("Render an announcement mail template.");
+    let (mut tera, mut context) = get_base_tera(None, None, None, None)?;
+
+    // convert content to HTML
+    let parser = pulldown_cmark::Parser::new(content);
+    let mut html_output = String::new();
+    pulldown_cmark::html::push_html(&mut html_output, parser);
+
+    context.insert("announcement_content", &html_output);
+
+    render_template(&mut tera, "mail_announcement", MAIL_ANNOUNCEMENT, &context)
 }
 
 // notification sent to admin after user completes enrollment
@@ -186,23 +682,23 @@ pub fn enrollment_admin_notification(
     debug!("Render an admin notification mail template.");
     let (mut tera, mut context) = get_base_tera(None, None, Some(ip_address), device_info)?;
 
-    tera.add_raw_template(
-        "mail_enrollment_admin_notification",
-        MAIL_ENROLLMENT_ADMIN_NOTIFICATION,
-    )?;
     context.insert("first_name", &user.first_name);
     context.insert("last_name", &user.last_name);
     context.insert("admin_first_name", &admin.first_name);
     context.insert("admin_last_name", &admin.last_name);
 
-    Ok(tera.render("mail_enrollment_admin_notification", &context)?)
+    render_template(
+        &mut tera,
+        "mail_enrollment_admin_notification",
+        MAIL_ENROLLMENT_ADMIN_NOTIFICATION,
+        &context,
+    )
 }
 
 // message with support data
 pub fn support_data_mail() -> Result<String, TemplateError> {
     let (mut tera, context) = get_base_tera(None, None, None, None)?;
-    tera.add_raw_template("mail_support_data", MAIL_SUPPORT_DATA)?;
-    Ok(tera.render("mail_support_data", &context)?)
+    render_template(&mut tera, "mail_support_data", MAIL_SUPPORT_DATA, &context)
 }
 
 #[derive(Serialize, Debug, Clone)]
@@ -224,8 +720,97 @@ pub fn new_device_added_mail(
     context.insert("public_key", public_key);
     context.insert("locations", template_locations);
 
-    tera.add_raw_template("mail_new_device_added", MAIL_NEW_DEVICE_ADDED)?;
-    Ok(tera.render("mail_new_device_added", &context)?)
+    render_template(
+        &mut tera,
+        "mail_new_device_added",
+        MAIL_NEW_DEVICE_ADDED,
+        &context,
+    )
+}
+
+pub fn device_disconnected_mail(
+    device_name: &str,
+    locations: &[String],
+) -> Result<String, TemplateError> {
+    debug!("Render a device disconnected mail template for the user.");
+    let (mut tera, mut context) = get_base_tera(None, None, None, None)?;
+    context.insert("device_name", device_name);
+    context.insert("locations", locations);
+
+    render_template(
+        &mut tera,
+        "mail_device_disconnected",
+        MAIL_DEVICE_DISCONNECTED,
+        &context,
+    )
+}
+
+pub fn device_approval_request_mail(
+    device_name: &str,
+    public_key: &str,
+    username: &str,
+    locations: &[String],
+    expires_at: NaiveDateTime,
+) -> Result<String, TemplateError> {
+    debug!("Render a device approval request mail template for admin users.");
+    let (mut tera, mut context) = get_base_tera(None, None, None, None)?;
+    context.insert("device_name", device_name);
+    context.insert("public_key", public_key);
+    context.insert("username", username);
+    context.insert("locations", locations);
+    context.insert(
+        "expires_at",
+        &expires_at.format(MAIL_DATETIME_FORMAT).to_string(),
+    );
+    context.insert("defguard_url", &server_config().url);
+
+    render_template(
+        &mut tera,
+        "mail_device_approval_request",
+        MAIL_DEVICE_APPROVAL_REQUEST,
+        &context,
+    )
+}
+
+// email verification sent after an account has been requested through self-registration
+pub fn self_registration_verification_mail(
+    username: &str,
+    token: &str,
+) -> Result<String, TemplateError> {
+    debug!("Render a self-registration verification mail template.");
+    let (mut tera, mut context) = get_base_tera(None, None, None, None)?;
+    context.insert("username", username);
+    context.insert("token", token);
+    context.insert("defguard_url", &server_config().url);
+
+    render_template(
+        &mut tera,
+        "mail_self_registration_verification",
+        MAIL_SELF_REGISTRATION_VERIFICATION,
+        &context,
+    )
+}
+
+// notification sent to admin after a self-registered user verifies their email
+pub fn self_registration_admin_notification(
+    user: &UserContext,
+    username: &str,
+    email: &str,
+) -> Result<String, TemplateError> {
+    debug!("Render a self-registration admin notification mail template.");
+    let (mut tera, mut context) = get_base_tera(None, None, None, None)?;
+    context.insert("username", username);
+    context.insert("first_name", &user.first_name);
+    context.insert("last_name", &user.last_name);
+    context.insert("email", email);
+    context.insert("defguard_url", &server_config().url);
+
+    render_template(
+        &mut tera,
+        "mail_self_registration_admin_notification",
+        MAIL_SELF_REGISTRATION_ADMIN_NOTIFICATION,
+        &context,
+    )
 }
 
 pub fn mfa_configured_mail(
@@ -235,14 +820,21 @@ pub fn mfa_configured_mail(
     let (mut tera, mut context) = get_base_tera(None, session, None, None)?;
     context.insert("mfa_method", &method);
     tera.add_raw_template("mail_base", MAIL_BASE)?;
-    tera.add_raw_template("mail_mfa_configured", MAIL_MFA_CONFIGURED)?;
 
-    Ok(tera.render("mail_mfa_configured", &context)?)
+    render_template(
+        &mut tera,
+        "mail_mfa_configured",
+        MAIL_MFA_CONFIGURED,
+        &context,
+    )
 }
 
 pub fn new_device_login_mail(
     session: &SessionContext,
     created: NaiveDateTime,
+    device_name: &str,
+    country: Option<&str>,
+    city: Option<&str>,
 ) -> Result<String, TemplateError> {
     let (mut tera, mut context) = get_base_tera(None, Some(session), None, None)?;
     tera.add_raw_template("mail_base", MAIL_BASE)?;
@@ -250,9 +842,23 @@ pub fn new_device_login_mail(
         "date_now",
         &created.format(MAIL_DATETIME_FORMAT).to_string(),
     );
+    // device name is derived from the user agent header, so it has to be escaped
+    context.insert("device_name", &tera::escape_html(device_name));
+    context.insert("profile_url", &format!("{}me", server_config().url));
+    // approximate location of login IP address, only known with GeoIP database configured
+    if let Some(country) = country {
+        context.insert("country", country);
+    }
+    if let Some(city) = city {
+        context.insert("city", &tera::escape_html(city));
+    }
 
-    tera.add_raw_template("mail_new_device_login", MAIL_NEW_DEVICE_LOGIN)?;
-    Ok(tera.render("mail_new_device_login", &context)?)
+    render_template(
+        &mut tera,
+        "mail_new_device_login",
+        MAIL_NEW_DEVICE_LOGIN,
+        &context,
+    )
 }
 
 pub fn new_device_ocid_login_mail(
@@ -267,8 +873,12 @@ pub fn new_device_ocid_login_mail(
     context.insert("oauth2client_name", &oauth2client_name);
     context.insert("profile_url", &url);
 
-    tera.add_raw_template("mail_new_device_oicd_login", MAIL_NEW_DEVICE_OCID_LOGIN)?;
-    Ok(tera.render("mail_new_device_oicd_login", &context)?)
+    render_template(
+        &mut tera,
+        "mail_new_device_ocid_login",
+        MAIL_NEW_DEVICE_OCID_LOGIN,
+        &context,
+    )
 }
 
 pub fn gateway_disconnected_mail(
@@ -280,8 +890,12 @@ pub fn gateway_disconnected_mail(
     context.insert("gateway_name", gateway_name);
     context.insert("gateway_ip", gateway_ip);
     context.insert("network_name", network_name);
-    tera.add_raw_template("mail_gateway_disconnected", MAIL_GATEWAY_DISCONNECTED)?;
-    Ok(tera.render("mail_gateway_disconnected", &context)?)
+    render_template(
+        &mut tera,
+        "mail_gateway_disconnected",
+        MAIL_GATEWAY_DISCONNECTED,
+        &context,
+    )
 }
 
 pub fn gateway_reconnected_mail(
@@ -293,8 +907,92 @@ pub fn gateway_reconnected_mail(
     context.insert("gateway_name", gateway_name);
     context.insert("gateway_ip", gateway_ip);
     context.insert("network_name", network_name);
-    tera.add_raw_template("mail_gateway_reconnected", MAIL_GATEWAY_RECONNECTED)?;
-    Ok(tera.render("mail_gateway_reconnected", &context)?)
+    render_template(
+        &mut tera,
+        "mail_gateway_reconnected",
+        MAIL_GATEWAY_RECONNECTED,
+        &context,
+    )
+}
+
+/// Alert about SMTP relay failover: `error` of the primary relay if mails are sent through the
+/// failover SMTP profile, `None` if the primary relay recovered.
+pub fn smtp_failover_mail(error: Option<&str>) -> Result<String, TemplateError> {
+    let (mut tera, mut context) = get_base_tera(None, None, None, None)?;
+    context.insert("failed_over", &error.is_some());
+    context.insert("error", error.unwrap_or_default());
+    render_template(
+        &mut tera,
+        "mail_smtp_failover",
+        MAIL_SMTP_FAILOVER,
+        &context,
+    )
+}
+
+/// Alert about lost connection to the proxy, or its restoration if `connected`.
+pub fn proxy_connectivity_mail(proxy_url: &str, connected: bool) -> Result<String, TemplateError> {
+    let (mut tera, mut context) = get_base_tera(None, None, None, None)?;
+    context.insert("proxy_url", proxy_url);
+    context.insert("connected", &connected);
+    render_template(
+        &mut tera,
+        "mail_proxy_connectivity",
+        MAIL_PROXY_CONNECTIVITY,
+        &context,
+    )
+}
+
+/// Notification summarized in a digest.
+#[derive(Serialize, Debug, Clone)]
+pub struct DigestNotification {
+    #[serde(serialize_with = "serialize_datetime")]
+    pub occurred_at: NaiveDateTime,
+    pub message: String,
+}
+
+/// Notifications collected over `window` minutes, sent as a single mail.
+pub fn notification_digest_mail(
+    notifications: &[DigestNotification],
+    window: i32,
+) -> Result<String, TemplateError> {
+    let (mut tera, mut context) = get_base_tera(None, None, None, None)?;
+    context.insert("notifications", notifications);
+    context.insert("window", &window);
+    render_template(
+        &mut tera,
+        "mail_notification_digest",
+        MAIL_NOTIFICATION_DIGEST,
+        &context,
+    )
+}
+
+/// Announcement of gateway downtime of a location, sent along with a calendar invite.
+/// Times are in UTC.
+pub fn maintenance_window_mail(
+    location_name: &str,
+    starts_at: NaiveDateTime,
+    ends_at: NaiveDateTime,
+    description: &str,
+    cancelled: bool,
+) -> Result<String, TemplateError> {
+    let (mut tera, mut context) = get_base_tera(None, None, None, None)?;
+    context.insert("location_name", location_name);
+    context.insert(
+        "starts_at",
+        &format!("{} UTC", starts_at.format(MAIL_DATETIME_FORMAT)),
+    );
+    context.insert(
+        "ends_at",
+        &format!("{} UTC", ends_at.format(MAIL_DATETIME_FORMAT)),
+    );
+    context.insert("description", description);
+    context.insert("cancelled", &cancelled);
+    render_template(
+        &mut tera,
+        "mail_maintenance_window",
+        MAIL_MAINTENANCE_WINDOW,
+        &context,
+    )
 }
 
 pub fn email_mfa_activation_mail(
@@ -308,9 +1006,13 @@ pub fn email_mfa_activation_mail(
     context.insert("code", &format!("{code:0>6}"));
     context.insert("timeout", &timeout.to_string());
     context.insert("name", &user.first_name);
-    tera.add_raw_template("mail_email_mfa_activation", MAIL_EMAIL_MFA_ACTIVATION)?;
 
-    Ok(tera.render("mail_email_mfa_activation", &context)?)
+    render_template(
+        &mut tera,
+        "mail_email_mfa_activation",
+        MAIL_EMAIL_MFA_ACTIVATION,
+        &context,
+    )
 }
 
 pub fn email_mfa_code_mail(
@@ -324,22 +1026,60 @@ pub fn email_mfa_code_mail(
     context.insert("code", &format!("{code:0>6}"));
     context.insert("timeout", &timeout.to_string());
     context.insert("name", &user.first_name);
-    tera.add_raw_template("mail_email_mfa_code", MAIL_EMAIL_MFA_CODE)?;
 
-    Ok(tera.render("mail_email_mfa_code", &context)?)
+    render_template(
+        &mut tera,
+        "mail_email_mfa_code",
+        MAIL_EMAIL_MFA_CODE,
+        &context,
+    )
+}
+
+/// Format time left until a deadline, e.g. "1 day 2 hours" or "30 minutes".
+fn format_remaining_time(deadline: NaiveDateTime) -> String {
+    // round up, so links valid for 30 minutes aren't described as valid for 29 minutes
+    let seconds = (deadline - Utc::now().naive_utc()).num_seconds();
+    let minutes = ((seconds + 59) / 60).max(1);
+    let units = [
+        (minutes / (24 * 60), "day"),
+        (minutes / 60 % 24, "hour"),
+        (minutes % 60, "minute"),
+    ];
+    units
+        .into_iter()
+        .filter(|(count, _)| *count > 0)
+        .take(2)
+        .map(|(count, unit)| {
+            if count == 1 {
+                format!("{count} {unit}")
+            } else {
+                format!("{count} {unit}s")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
 }
 
 pub fn email_password_reset_mail(
     mut service_url: Url,
     password_reset_token: &str,
+    expires_at: NaiveDateTime,
+    max_uses: i32,
     ip_address: Option<&str>,
     device_info: Option<&str>,
+    language: Option<&str>,
 ) -> Result<String, TemplateError> {
     let (mut tera, mut context) = get_base_tera(None, None, ip_address, device_info)?;
 
     context.insert("enrollment_url", &service_url.to_string());
     context.insert("defguard_url", &server_config().url);
     context.insert("token", password_reset_token);
+    context.insert(
+        "link_expires_at",
+        &expires_at.format(MAIL_DATETIME_FORMAT).to_string(),
+    );
+    context.insert("link_valid_for", &format_remaining_time(expires_at));
+    context.insert("link_max_uses", &max_uses);
 
     service_url.set_path("/password-reset");
     service_url
@@ -347,27 +1087,146 @@ pub fn email_password_reset_mail(
         .append_pair("token", password_reset_token);
 
     context.insert("link_url", &service_url.to_string());
+    insert_localized_text(&mut context, PASSWORD_RESET_START_TEMPLATE, language)?;
+
+    render_template(
+        &mut tera,
+        PASSWORD_RESET_START_TEMPLATE,
+        MAIL_PASSWORD_RESET_START,
+        &context,
+    )
+}
 
-    tera.add_raw_template("mail_passowrd_reset_start", MAIL_PASSWORD_RESET_START)?;
-
-    Ok(tera.render("mail_passowrd_reset_start", &context)?)
+// verification code of a password reset initiated by helpdesk
+pub fn helpdesk_password_reset_mail(
+    username: &str,
+    requested_by: &str,
+    code: &str,
+    expires_at: NaiveDateTime,
+) -> Result<String, TemplateError> {
+    debug!("Render a helpdesk password reset mail template.");
+    let (mut tera, mut context) = get_base_tera(None, None, None, None)?;
+    context.insert("username", username);
+    context.insert("requested_by", requested_by);
+    context.insert("code", code);
+    context.insert("defguard_url", &server_config().url);
+    context.insert(
+        "code_expires_at",
+        &expires_at.format(MAIL_DATETIME_FORMAT).to_string(),
+    );
+    context.insert("code_valid_for", &format_remaining_time(expires_at));
+
+    render_template(
+        &mut tera,
+        "mail_helpdesk_password_reset",
+        MAIL_HELPDESK_PASSWORD_RESET,
+        &context,
+    )
 }
 
 pub fn email_password_reset_success_mail(
     ip_address: Option<&str>,
     device_info: Option<&str>,
+    language: Option<&str>,
 ) -> Result<String, TemplateError> {
-    let (mut tera, context) = get_base_tera(None, None, ip_address, device_info)?;
+    let (mut tera, mut context) = get_base_tera(None, None, ip_address, device_info)?;
+    insert_localized_text(&mut context, PASSWORD_RESET_SUCCESS_TEMPLATE, language)?;
+
+    render_template(
+        &mut tera,
+        PASSWORD_RESET_SUCCESS_TEMPLATE,
+        MAIL_PASSWORD_RESET_SUCCESS,
+        &context,
+    )
+}
+
+fn serialize_datetime<S>(timestamp: &NaiveDateTime, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_str(&timestamp.format(MAIL_DATETIME_FORMAT))
+}
+
+/// Device added during the summarized period.
+#[derive(Serialize, Debug, Clone)]
+pub struct SummaryDevice {
+    pub username: String,
+    pub device_name: String,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub added_at: NaiveDateTime,
+}
+
+/// Number of failed login attempts for a given username.
+#[derive(Serialize, Debug, Clone)]
+pub struct SummaryFailedLogins {
+    pub username: String,
+    pub attempts: i64,
+}
+
+/// Gateway which repeatedly disconnected during the summarized period.
+#[derive(Serialize, Debug, Clone)]
+pub struct SummaryGateway {
+    pub location_name: String,
+    pub hostname: String,
+    pub disconnects: i64,
+}
 
-    tera.add_raw_template("mail_passowrd_reset_success", MAIL_PASSWORD_RESET_SUCCESS)?;
+/// Usage of a license limit.
+#[derive(Serialize, Debug, Clone)]
+pub struct SummaryLicenseLimit {
+    pub name: String,
+    pub current: u32,
+    pub limit: u32,
+}
 
-    Ok(tera.render("mail_passowrd_reset_success", &context)?)
+/// Certificate which is about to expire.
+#[derive(Serialize, Debug, Clone)]
+pub struct SummaryCertificate {
+    pub name: String,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub expires_at: NaiveDateTime,
+}
+
+/// Periodic security summary sent to admin users.
+#[derive(Serialize, Debug, Clone)]
+pub struct SecuritySummary {
+    #[serde(serialize_with = "serialize_datetime")]
+    pub period_start: NaiveDateTime,
+    #[serde(serialize_with = "serialize_datetime")]
+    pub period_end: NaiveDateTime,
+    pub new_devices: Vec<SummaryDevice>,
+    pub failed_logins: Vec<SummaryFailedLogins>,
+    pub flapping_gateways: Vec<SummaryGateway>,
+    pub license_limits: Vec<SummaryLicenseLimit>,
+    pub expiring_certificates: Vec<SummaryCertificate>,
+}
+
+// weekly security summary sent to admin users
+pub fn security_summary_mail(summary: &SecuritySummary) -> Result<String, TemplateError> {
+    debug!("Render a security summary mail template for admin users.");
+    let (mut tera, mut context) =
+        get_base_tera(Some(Context::from_serialize(summary)?), None, None, None)?;
+    context.insert("defguard_url", &server_config().url);
+
+    render_template(
+        &mut tera,
+        "mail_security_summary",
+        MAIL_SECURITY_SUMMARY,
+        &context,
+    )
 }
 
 #[cfg(test)]
 mod test {
+    use std::collections::BTreeMap;
+
+    use chrono::NaiveDate;
     use claims::assert_ok;
-    use defguard_common::config::{DefGuardConfig, SERVER_CONFIG};
+    use defguard_common::{
+        config::{DefGuardConfig, SERVER_CONFIG},
+        db::models::{mail_context::set_mail_context, mail_template::set_mail_templates},
+    };
+    use serde_json::json;
 
     use super::*;
 
@@ -407,13 +1266,21 @@ mod test {
         assert_ok!(test_mail(None));
     }
 
+    #[test]
+    fn test_mail_assets() {
+        let mail = test_mail(None).unwrap();
+        assert!(mail.contains(r#"src="https://defguard.net/images/png/new-logo.png""#));
+        assert!(mail.contains(r#"src="https://defguard.net/images/decap/github_black.png""#));
+    }
+
     #[test]
     fn test_enrollment_start_mail() {
         let _ = SERVER_CONFIG.set(DefGuardConfig::new_test_config());
         assert_ok!(enrollment_start_mail(
             Context::new(),
             Url::parse("http://localhost:8080").unwrap(),
-            "test_token"
+            "test_token",
+            None
         ));
     }
 
@@ -426,12 +1293,112 @@ mod test {
         ));
     }
 
+    #[test]
+    fn test_announcement_mail() {
+        let mail = announcement_mail("# Maintenance\n\nVPN will be **down** tonight.").unwrap();
+        assert!(mail.contains("<h1>Maintenance</h1>"));
+        assert!(mail.contains("<strong>down</strong>"));
+    }
+
+    #[test]
+    fn test_password_reset_mail() {
+        let _ = SERVER_CONFIG.set(DefGuardConfig::new_test_config());
+        let url = Url::parse("http://localhost:8080").unwrap();
+        let expires_at = Utc::now().naive_utc() + TimeDelta::hours(26);
+        let mail = email_password_reset_mail(url.clone(), "token", expires_at, 1, None, None, None)
+            .unwrap();
+        assert!(mail.contains("password-reset?token=token"));
+        assert!(mail.contains("valid for 1 day 2 hours"));
+        assert!(mail.contains("can be used only once"));
+
+        let expires_at = Utc::now().naive_utc() + TimeDelta::minutes(30);
+        let mail =
+            email_password_reset_mail(url, "token", expires_at, 3, None, None, None).unwrap();
+        assert!(mail.contains("valid for 30 minutes"));
+        assert!(mail.contains("can be used 3 times"));
+    }
+
     #[test]
     fn test_desktop_start_mail() {
         let external_context = get_welcome_context();
         let url = Url::parse("http://127.0.0.1:8080").unwrap();
         let token = "TestToken";
-        assert_ok!(desktop_start_mail(external_context, &url, token));
+        assert_ok!(desktop_start_mail(external_context, &url, token, None));
+    }
+
+    #[test]
+    fn test_localized_mail() {
+        set_mail_context(BTreeMap::from([(
+            (DESKTOP_START_TEMPLATE.to_string(), "pl".to_string()),
+            HashMap::from([
+                ("intro".to_string(), "Konfiguracja klienta".to_string()),
+                ("token".to_string(), "Token {{ token }}:".to_string()),
+                (
+                    "subject".to_string(),
+                    "Konfiguracja {{ missing }}".to_string(),
+                ),
+            ]),
+        )]));
+        let url = Url::parse("http://127.0.0.1:8080").unwrap();
+        let mail = desktop_start_mail(Context::new(), &url, "TestToken", Some("pl")).unwrap();
+        assert!(mail.contains("Konfiguracja klienta"));
+        assert!(mail.contains("Token TestToken:"));
+        // untranslated sections fall back to built-in texts
+        assert!(mail.contains("Configure your desktop client"));
+        assert!(!mail.contains("You're receiving this email"));
+
+        // unknown language falls back to English
+        let mail = desktop_start_mail(Context::new(), &url, "TestToken", Some("de")).unwrap();
+        assert!(mail.contains("You're receiving this email"));
+
+        // translations which fail to render are skipped
+        assert_eq!(
+            localized_subject(DESKTOP_START_TEMPLATE, Some("pl")).unwrap(),
+            "Defguard desktop client configuration"
+        );
+    }
+
+    #[test]
+    fn test_new_device_login_mail() {
+        let _ = SERVER_CONFIG.set(DefGuardConfig::new_test_config());
+        let session = SessionContext {
+            ip_address: "1.1.1.1".into(),
+            device_info: None,
+        };
+        let mail = new_device_login_mail(
+            &session,
+            Utc::now().naive_utc(),
+            "Firefox on Ubuntu <script>",
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(mail.contains("Firefox on Ubuntu &lt;script&gt;"));
+        assert!(mail.contains("/me\""));
+        // location is left out without GeoIP database
+        assert!(!mail.contains("Approximate location"));
+
+        let mail = new_device_login_mail(
+            &session,
+            Utc::now().naive_utc(),
+            "Firefox on Ubuntu",
+            Some("AU"),
+            Some("South Brisbane"),
+        )
+        .unwrap();
+        assert!(mail.contains("Approximate location:"));
+        assert!(mail.contains("South Brisbane, AU"));
+
+        let mail = new_device_login_mail(
+            &session,
+            Utc::now().naive_utc(),
+            "Firefox on Ubuntu",
+            Some("AU"),
+            None,
+        )
+        .unwrap();
+        assert!(mail.contains("Approximate location:"));
+        assert!(!mail.contains(", AU"));
     }
 
     #[test]
@@ -454,6 +1421,63 @@ mod test {
             None,
         ));
     }
+
+    #[test]
+    fn test_device_disconnected_mail() {
+        assert_ok!(device_disconnected_mail(
+            "Test device",
+            &["Location1".into(), "Location2".into()],
+        ));
+    }
+
+    #[test]
+    fn test_device_approval_request_mail() {
+        let _ = SERVER_CONFIG.set(DefGuardConfig::new_test_config());
+        let mail = device_approval_request_mail(
+            "Laptop",
+            "pubkey",
+            "hpotter",
+            &["Office".into(), "Lab".into()],
+            Utc::now().naive_utc(),
+        )
+        .unwrap();
+        assert!(mail.contains("hpotter"));
+        assert!(mail.contains("Office, Lab"));
+    }
+
+    #[test]
+    fn test_self_registration_mails() {
+        let _ = SERVER_CONFIG.set(DefGuardConfig::new_test_config());
+        let mail = self_registration_verification_mail("hpotter", "verification-token").unwrap();
+        assert!(mail.contains("hpotter"));
+        assert!(mail.contains("verification-token"));
+
+        let user = UserContext {
+            last_name: "Potter".into(),
+            first_name: "Harry".into(),
+        };
+        let mail =
+            self_registration_admin_notification(&user, "hpotter", "h.potter@hogwart.edu.uk")
+                .unwrap();
+        assert!(mail.contains("Harry Potter"));
+        assert!(mail.contains("h.potter@hogwart.edu.uk"));
+    }
+
+    #[test]
+    fn test_helpdesk_password_reset_mail() {
+        let _ = SERVER_CONFIG.set(DefGuardConfig::new_test_config());
+        let mail = helpdesk_password_reset_mail(
+            "hpotter",
+            "support",
+            "Abc123xY",
+            Utc::now().naive_utc() + TimeDelta::hours(1),
+        )
+        .unwrap();
+        assert!(mail.contains("hpotter"));
+        assert!(mail.contains("support"));
+        assert!(mail.contains("Abc123xY"));
+    }
+
     #[test]
     fn test_gateway_disconnected() {
         assert_ok!(gateway_disconnected_mail(
@@ -463,6 +1487,115 @@ mod test {
         ));
     }
 
+    #[test]
+    fn test_smtp_failover_mail() {
+        let mail = smtp_failover_mail(Some("Connection refused")).unwrap();
+        assert!(mail.contains("Connection refused"));
+        assert!(mail.contains("failover SMTP profile until"));
+        let mail = smtp_failover_mail(None).unwrap();
+        assert!(mail.contains("works again"));
+    }
+
+    #[test]
+    fn test_proxy_connectivity_mail() {
+        let mail = proxy_connectivity_mail("https://proxy.example.com", false).unwrap();
+        assert!(mail.contains("https://proxy.example.com has been lost"));
+        let mail = proxy_connectivity_mail("https://proxy.example.com", true).unwrap();
+        assert!(mail.contains("has been restored"));
+    }
+
+    #[test]
+    fn test_notification_digest_mail() {
+        let occurred_at = NaiveDate::from_ymd_opt(2026, 3, 1)
+            .unwrap()
+            .and_hms_opt(22, 0, 0)
+            .unwrap();
+        let notifications = [
+            DigestNotification {
+                occurred_at,
+                message: "Gateway A disconnected.".into(),
+            },
+            DigestNotification {
+                occurred_at: occurred_at + TimeDelta::minutes(1),
+                message: "Gateway A reconnected.".into(),
+            },
+        ];
+        let mail = notification_digest_mail(&notifications, 15).unwrap();
+        assert!(mail.contains("2 notifications have been collected over the last 15 minutes"));
+        assert!(mail.contains("Sunday, March 01, 2026 at 10:01:00 PM"));
+        assert!(mail.contains("Gateway A reconnected."));
+    }
+
+    #[test]
+    fn test_maintenance_window_mail() {
+        let starts_at = NaiveDate::from_ymd_opt(2026, 3, 1)
+            .unwrap()
+            .and_hms_opt(22, 0, 0)
+            .unwrap();
+        let ends_at = starts_at + TimeDelta::hours(2);
+        let mail = maintenance_window_mail("Office", starts_at, ends_at, "Gateway upgrade", false)
+            .unwrap();
+        assert!(mail.contains("Sunday, March 01, 2026 at 10:00:00 PM UTC"));
+        assert!(mail.contains("Gateway upgrade"));
+        let mail = maintenance_window_mail("Office", starts_at, ends_at, "", true).unwrap();
+        assert!(mail.contains("has been cancelled"));
+    }
+
+    #[test]
+    fn test_template_override() {
+        let _ = SERVER_CONFIG.set(DefGuardConfig::new_test_config());
+        let name = "mail_gateway_reconnected";
+        set_mail_templates(BTreeMap::from([(
+            name.to_string(),
+            "Gateway {{ gateway_name }} is back".to_string(),
+        )]));
+        let mail = gateway_reconnected_mail("Gateway A", "127.0.0.1", "Location1").unwrap();
+        assert_eq!(mail, "Gateway Gateway A is back");
+
+        // overrides which fail to render fall back to the built-in template
+        set_mail_templates(BTreeMap::from([(
+            name.to_string(),
+            "Gateway {{ unknown }} is back".to_string(),
+        )]));
+        let mail = gateway_reconnected_mail("Gateway A", "127.0.0.1", "Location1").unwrap();
+        assert!(mail.contains("Location1"));
+
+        // previews don't fall back
+        assert!(preview_template(name, "Gateway {{ unknown }}").is_err());
+        assert!(preview_template(name, "{% if %}").is_err());
+        assert!(preview_template("mail_unknown", "Gateway").is_err());
+        assert_eq!(
+            preview_template(name, "Gateway {{ gateway_name }}").unwrap(),
+            "Gateway gateway"
+        );
+        // built-in templates render with the sample data
+        for (name, source) in BUILTIN_TEMPLATES {
+            assert_ok!(preview_template(name, source));
+        }
+    }
+
+    #[test]
+    fn test_render_mail() {
+        let _ = SERVER_CONFIG.set(DefGuardConfig::new_test_config());
+        let context = json!({
+            "gateway_name": "Gateway A",
+            "gateway_ip": "10.0.0.2",
+            "network_name": "Office",
+        });
+        let mail = render_mail("mail_gateway_disconnected", context, None).unwrap();
+        assert!(mail.contains("Gateway A"));
+        assert!(mail.contains("Office"));
+
+        // supplied context has to be complete
+        assert!(render_mail("mail_gateway_disconnected", json!({}), None).is_err());
+        assert!(render_mail("mail_gateway_disconnected", json!("Gateway A"), None).is_err());
+        assert!(render_mail("mail_unknown", json!({}), None).is_err());
+
+        // localized texts are added to the context
+        let mail = render_mail(PASSWORD_RESET_SUCCESS_TEMPLATE, json!({}), None).unwrap();
+        assert!(mail.contains("Your password has been successfully changed."));
+    }
+
     #[test]
     fn test_enrollment_admin_notification() {
         let test_user = UserContext {
@@ -478,6 +1611,49 @@ mod test {
         ));
     }
 
+    #[test]
+    fn test_security_summary_mail() {
+        let _ = SERVER_CONFIG.set(DefGuardConfig::new_test_config());
+        let now = Utc::now().naive_utc();
+        let mut summary = SecuritySummary {
+            period_start: now,
+            period_end: now,
+            new_devices: vec![SummaryDevice {
+                username: "hpotter".into(),
+                device_name: "Laptop".into(),
+                added_at: now,
+            }],
+            failed_logins: vec![SummaryFailedLogins {
+                username: "dmalfoy".into(),
+                attempts: 42,
+            }],
+            flapping_gateways: vec![SummaryGateway {
+                location_name: "Office".into(),
+                hostname: "gateway-1".into(),
+                disconnects: 7,
+            }],
+            license_limits: vec![SummaryLicenseLimit {
+                name: "Users".into(),
+                current: 9,
+                limit: 10,
+            }],
+            expiring_certificates: vec![SummaryCertificate {
+                name: "gRPC server certificate".into(),
+                expires_at: now,
+            }],
+        };
+        let mail = security_summary_mail(&summary).unwrap();
+        assert!(mail.contains("hpotter"));
+        assert!(mail.contains("42 failed attempts"));
+        assert!(mail.contains("gateway-1 disconnected 7 times"));
+        assert!(mail.contains("9 of 10 used"));
+        assert!(mail.contains("gRPC server certificate"));
+
+        summary.new_devices.clear();
+        let mail = security_summary_mail(&summary).unwrap();
+        assert!(mail.contains("No devices have been added."));
+    }
+
     #[test]
     fn dg25_8_server_side_template_injection() {
         let mut tera = safe_tera();
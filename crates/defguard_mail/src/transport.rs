@@ -0,0 +1,353 @@
+//! Transports delivering rendered mails: SMTP server or HTTP API of a mail delivery service.
+//!
+//! HTTP transport POSTs mails as JSON to a configured URL, authenticating with a bearer token if
+//! one is set. Services which expect their own payload format can be integrated through a relay
+//! translating it.
+
+use std::{sync::Arc, time::Duration};
+
+use base64::{Engine, prelude::BASE64_STANDARD};
+use chrono::Utc;
+use defguard_common::db::models::{
+    MailCategory, Settings,
+    settings::{MailBackend, SmtpEncryption},
+    smtp_profile::{get_failover_smtp_profile, get_smtp_profiles},
+};
+use lettre::{
+    AsyncSmtpTransport, AsyncTransport, Tokio1Executor,
+    message::header::Headers,
+    transport::smtp::{
+        authentication::{Credentials, Mechanism},
+        response::Response,
+    },
+};
+use reqwest::{Client, StatusCode};
+use serde::Serialize;
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{
+    Attachment, Mail, MailError, MailSender, SmtpAuth, SmtpSettings, TokenCache,
+    failover::{self, Relay},
+};
+
+const SMTP_TIMEOUT: Duration = Duration::from_secs(15);
+const HTTP_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Response of a server which accepted a mail.
+#[derive(Debug)]
+pub enum MailResponse {
+    Smtp(Response),
+    /// Status of HTTP API response
+    Http(StatusCode),
+}
+
+#[trait_variant::make(Send)]
+pub(crate) trait MailTransport {
+    /// Delivers a mail.
+    async fn send(&self, mail: Mail) -> Result<MailResponse, MailError>;
+}
+
+/// Transport selected for a mail, dispatching to [`MailTransport`] implementations.
+pub(crate) enum Transport {
+    Smtp(SmtpTransport),
+    Http(HttpTransport),
+}
+
+impl Transport {
+    /// Selects transport for a given mail category: SMTP profile bound to the category if there
+    /// is one, otherwise mail backend selected in `Settings`. SMTP server from `Settings` is
+    /// replaced by the failover SMTP profile while it's failing, see [`failover`].
+    pub fn for_category(
+        settings: Settings,
+        category: MailCategory,
+        token_cache: &Arc<Mutex<TokenCache>>,
+        client: &Client,
+    ) -> Result<Self, MailError> {
+        if settings.mail_backend == MailBackend::Http
+            && !get_smtp_profiles().contains_key(&category)
+        {
+            return Ok(Self::Http(HttpTransport {
+                settings: HttpSettings::from_settings(&settings)?,
+                client: client.clone(),
+            }));
+        }
+
+        if get_smtp_profiles().contains_key(&category) {
+            return Ok(Self::Smtp(SmtpTransport {
+                settings: SmtpSettings::from_settings(settings, category)?,
+                token_cache: Arc::clone(token_cache),
+                relay: None,
+            }));
+        }
+        if failover::active_relay(Utc::now().naive_utc()) == Relay::Secondary {
+            // clone the profile, so the lock isn't held while building the transport
+            let failover_profile = get_failover_smtp_profile().clone();
+            if let Some(profile) = failover_profile {
+                return Ok(Self::Smtp(SmtpTransport {
+                    settings: SmtpSettings::from_profile(&profile)?,
+                    token_cache: Arc::clone(token_cache),
+                    relay: Some(Relay::Secondary),
+                }));
+            }
+        }
+
+        Ok(Self::Smtp(SmtpTransport {
+            settings: SmtpSettings::from_settings(settings, category)?,
+            token_cache: Arc::clone(token_cache),
+            relay: Some(Relay::Primary),
+        }))
+    }
+
+    /// SMTP relay guarded by the failover circuit breaker, if the mail is sent through one.
+    pub fn relay(&self) -> Option<Relay> {
+        match self {
+            Self::Smtp(transport) => transport.relay,
+            Self::Http(_) => None,
+        }
+    }
+}
+
+impl MailTransport for Transport {
+    async fn send(&self, mail: Mail) -> Result<MailResponse, MailError> {
+        match self {
+            Self::Smtp(transport) => transport.send(mail).await,
+            Self::Http(transport) => transport.send(mail).await,
+        }
+    }
+}
+
+pub(crate) struct SmtpTransport {
+    settings: SmtpSettings,
+    token_cache: Arc<Mutex<TokenCache>>,
+    relay: Option<Relay>,
+}
+
+impl SmtpTransport {
+    /// Builds mailer object with specified configuration
+    async fn mailer(&self) -> Result<AsyncSmtpTransport<Tokio1Executor>, MailError> {
+        let settings = &self.settings;
+        let builder = match settings.encryption {
+            SmtpEncryption::None => {
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&settings.server)
+            }
+            SmtpEncryption::StartTls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&settings.server)?
+            }
+            SmtpEncryption::ImplicitTls => {
+                AsyncSmtpTransport::<Tokio1Executor>::relay(&settings.server)?
+            }
+        }
+        .port(settings.port)
+        .timeout(Some(SMTP_TIMEOUT));
+
+        let builder = match &settings.auth {
+            // Skip credentials if any of them is empty
+            SmtpAuth::Password { user, password } if user.is_empty() || password.is_empty() => {
+                debug!(
+                    "SMTP credentials were not provided, skipping username/password authentication"
+                );
+                builder
+            }
+            SmtpAuth::Password { user, password } => {
+                builder.credentials(Credentials::new(user.clone(), password.clone()))
+            }
+            SmtpAuth::OAuth2 { user, credentials } => {
+                let access_token = self
+                    .token_cache
+                    .lock()
+                    .await
+                    .access_token(credentials)
+                    .await?;
+                builder
+                    .credentials(Credentials::new(user.clone(), access_token))
+                    .authentication(vec![Mechanism::Xoauth2])
+            }
+        };
+
+        Ok(builder.build())
+    }
+}
+
+impl MailTransport for SmtpTransport {
+    async fn send(&self, mail: Mail) -> Result<MailResponse, MailError> {
+        let message = mail.into_message(&self.settings.sender)?;
+        let mailer = self.mailer().await?;
+        let result = mailer.send(message).await;
+        // Token might have been revoked, so get a fresh one for the next mail.
+        if result.is_err() && matches!(self.settings.auth, SmtpAuth::OAuth2 { .. }) {
+            self.token_cache.lock().await.invalidate();
+        }
+        Ok(MailResponse::Smtp(result?))
+    }
+}
+
+/// Subset of Settings object representing HTTP mail API configuration
+struct HttpSettings {
+    url: String,
+    token: Option<String>,
+    sender: MailSender,
+}
+
+impl HttpSettings {
+    /// Constructs `HttpSettings` from `Settings`. Returns error if they are incomplete.
+    fn from_settings(settings: &Settings) -> Result<Self, MailError> {
+        let (Some(url), Some(sender)) = (
+            settings.mail_http_url.clone().filter(|url| !url.is_empty()),
+            MailSender::from_settings(settings),
+        ) else {
+            return Err(MailError::HttpNotConfigured);
+        };
+        Ok(Self {
+            url,
+            token: settings
+                .mail_http_token
+                .as_ref()
+                .map(|token| token.expose_secret().to_string())
+                .filter(|token| !token.is_empty()),
+            sender,
+        })
+    }
+}
+
+/// Mail as sent to HTTP API
+#[derive(Debug, Serialize)]
+struct HttpMail<'a> {
+    from: &'a str,
+    from_name: Option<&'a str>,
+    reply_to: Option<&'a str>,
+    to: &'a str,
+    subject: &'a str,
+    html: &'a str,
+    category: MailCategory,
+    attachments: Vec<HttpAttachment>,
+}
+
+#[derive(Debug, Serialize)]
+struct HttpAttachment {
+    filename: String,
+    content_type: String,
+    /// Base64-encoded content
+    content: String,
+}
+
+impl From<&Attachment> for HttpAttachment {
+    fn from(attachment: &Attachment) -> Self {
+        let mut headers = Headers::new();
+        headers.set(attachment.content_type.clone());
+        Self {
+            filename: attachment.filename.clone(),
+            content_type: headers
+                .get_raw("Content-Type")
+                .unwrap_or_default()
+                .to_string(),
+            content: BASE64_STANDARD.encode(&attachment.content),
+        }
+    }
+}
+
+impl<'a> HttpMail<'a> {
+    fn new(mail: &'a Mail, sender: &'a MailSender) -> Self {
+        Self {
+            from: sender.address(mail.category),
+            from_name: sender.name.as_deref(),
+            reply_to: sender.reply_to.as_deref(),
+            to: &mail.to,
+            subject: &mail.subject,
+            html: &mail.content,
+            category: mail.category,
+            attachments: mail.attachments.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+pub(crate) struct HttpTransport {
+    settings: HttpSettings,
+    client: Client,
+}
+
+impl MailTransport for HttpTransport {
+    async fn send(&self, mail: Mail) -> Result<MailResponse, MailError> {
+        let mut request = self
+            .client
+            .post(&self.settings.url)
+            .timeout(HTTP_TIMEOUT)
+            .json(&HttpMail::new(&mail, &self.settings.sender));
+        if let Some(token) = &self.settings.token {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await?.error_for_status()?;
+
+        Ok(MailResponse::Http(response.status()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lettre::message::header::ContentType;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_http_mail() {
+        let sender = MailSender {
+            address: "defguard@example.com".into(),
+            name: Some("Defguard".into()),
+            reply_to: None,
+            security_address: Some("security@example.com".into()),
+            announcement_address: None,
+        };
+        let mail = Mail {
+            to: "hpotter@hogwart.edu.uk".into(),
+            subject: "Subject".into(),
+            content: "<p>Content</p>".into(),
+            attachments: vec![Attachment {
+                filename: "logs.txt".into(),
+                content: b"logs".to_vec(),
+                content_type: ContentType::TEXT_PLAIN,
+            }],
+            category: MailCategory::Security,
+            result_tx: None,
+        };
+        assert_eq!(
+            serde_json::to_value(HttpMail::new(&mail, &sender)).unwrap(),
+            json!({
+                "from": "security@example.com",
+                "from_name": "Defguard",
+                "reply_to": null,
+                "to": "hpotter@hogwart.edu.uk",
+                "subject": "Subject",
+                "html": "<p>Content</p>",
+                "category": "security",
+                "attachments": [{
+                    "filename": "logs.txt",
+                    "content_type": "text/plain; charset=utf-8",
+                    "content": "bG9ncw==",
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn test_http_settings() {
+        let mut settings = Settings {
+            mail_backend: MailBackend::Http,
+            smtp_sender: Some("defguard@example.com".into()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            HttpSettings::from_settings(&settings),
+            Err(MailError::HttpNotConfigured)
+        ));
+
+        settings.mail_http_url = Some("https://mail.example.com/send".into());
+        let http = HttpSettings::from_settings(&settings).unwrap();
+        assert_eq!(http.url, "https://mail.example.com/send");
+        assert_eq!(http.token, None);
+        assert_eq!(
+            http.sender.address(MailCategory::General),
+            "defguard@example.com"
+        );
+    }
+}
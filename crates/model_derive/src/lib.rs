@@ -36,6 +36,20 @@ fn model_attr(f: &Field) -> Option<String> {
     model_type
 }
 
+/// Returns true if the field type is wrapped in `Option`.
+fn is_option(ty: &Type) -> bool {
+    if let Type::Path(TypePath {
+        path: Path { segments, .. },
+        ..
+    }) = ty
+    {
+        if let Some(segment) = segments.first() {
+            return segment.ident == "Option";
+        }
+    }
+    false
+}
+
 fn field_type(ty: &Type) -> Option<&Ident> {
     if let Type::Path(TypePath {
         path: Path { segments, .. },
@@ -142,13 +156,16 @@ pub fn derive(input: TokenStream) -> TokenStream {
         }
     });
 
-    // TODO: handle fields wrapped in Option
     // field arguments for queries
     let insert_args = named.iter().filter_map(|field| {
         if let Some(name) = &field.ident {
             if name != "id" {
                 if let Some(tokens) = model_attr(field) {
                     if tokens == "enum" {
+                        if is_option(&field.ty) {
+                            let field_type = &field.ty;
+                            return Some(quote! { &self.#name as &#field_type });
+                        }
                         if let Some(field_type) = field_type(&field.ty) {
                             return Some(quote! { &self.#name as &#field_type });
                         }
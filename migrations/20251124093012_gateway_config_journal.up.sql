@@ -0,0 +1,12 @@
+-- Journal of configuration updates sent to gateways, used to resume gateway sync.
+-- There is no foreign key on `location_id`, because entries are written in the background
+-- and may refer to locations created in transactions which haven't been committed yet.
+-- Entries are removed when a location is deleted.
+CREATE TABLE gateway_config_journal (
+    id bigserial PRIMARY KEY,
+    location_id bigint NOT NULL,
+    epoch bigint NOT NULL,
+    payload bytea NULL,
+    created_at timestamp without time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT location_epoch UNIQUE (location_id, epoch)
+);
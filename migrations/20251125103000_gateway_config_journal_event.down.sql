@@ -0,0 +1,2 @@
+DROP INDEX gateway_config_journal_created_at;
+ALTER TABLE gateway_config_journal DROP COLUMN event;
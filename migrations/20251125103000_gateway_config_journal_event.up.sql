@@ -0,0 +1,4 @@
+-- Journal entries are kept for forensics, so store the kind of event which caused them.
+ALTER TABLE gateway_config_journal ADD COLUMN event text NOT NULL DEFAULT 'unknown';
+ALTER TABLE gateway_config_journal ALTER COLUMN event DROP DEFAULT;
+CREATE INDEX gateway_config_journal_created_at ON gateway_config_journal (location_id, created_at);
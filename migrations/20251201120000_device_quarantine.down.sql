@@ -0,0 +1,3 @@
+DROP TABLE quarantine_remediation_host;
+DROP TABLE device_quarantine;
+DROP TYPE quarantine_source;
@@ -0,0 +1,26 @@
+-- add enum representing the subsystem which placed a device in quarantine
+CREATE TYPE quarantine_source AS ENUM (
+    'manual',
+    'posture',
+    'anomaly'
+);
+
+-- Quarantined devices keep their peers on gateways, but their traffic
+-- is restricted to remediation hosts configured for each location.
+CREATE TABLE device_quarantine (
+    id bigserial PRIMARY KEY,
+    device_id bigint NOT NULL UNIQUE,
+    source quarantine_source NOT NULL,
+    reason text NULL,
+    created_at timestamp without time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY(device_id) REFERENCES "device"(id) ON DELETE CASCADE
+);
+
+CREATE TABLE quarantine_remediation_host (
+    id bigserial PRIMARY KEY,
+    location_id bigint NOT NULL,
+    address inet NOT NULL,
+    description text NULL,
+    FOREIGN KEY(location_id) REFERENCES wireguard_network(id) ON DELETE CASCADE,
+    CONSTRAINT location_address UNIQUE (location_id, address)
+);
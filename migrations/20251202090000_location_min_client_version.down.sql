@@ -0,0 +1,3 @@
+ALTER TABLE wireguard_network
+    DROP COLUMN min_desktop_client_version,
+    DROP COLUMN min_mobile_client_version;
@@ -0,0 +1,3 @@
+ALTER TABLE wireguard_network
+    ADD COLUMN min_desktop_client_version text NULL,
+    ADD COLUMN min_mobile_client_version text NULL;
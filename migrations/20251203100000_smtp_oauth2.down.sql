@@ -0,0 +1,7 @@
+ALTER TABLE settings
+    DROP COLUMN smtp_auth_method,
+    DROP COLUMN smtp_oauth2_token_url,
+    DROP COLUMN smtp_oauth2_client_id,
+    DROP COLUMN smtp_oauth2_client_secret,
+    DROP COLUMN smtp_oauth2_scope;
+DROP TYPE smtp_auth_method;
@@ -0,0 +1,10 @@
+CREATE TYPE smtp_auth_method AS ENUM (
+    'password',
+    'oauth2'
+);
+ALTER TABLE settings
+    ADD COLUMN smtp_auth_method smtp_auth_method NOT NULL DEFAULT 'password',
+    ADD COLUMN smtp_oauth2_token_url text NULL,
+    ADD COLUMN smtp_oauth2_client_id text NULL,
+    ADD COLUMN smtp_oauth2_client_secret text NULL,
+    ADD COLUMN smtp_oauth2_scope text NULL;
@@ -0,0 +1,2 @@
+DROP TABLE announcement_delivery;
+DROP TABLE announcement;
@@ -0,0 +1,23 @@
+CREATE TABLE announcement (
+    id bigserial PRIMARY KEY,
+    subject text NOT NULL,
+    body text NOT NULL,
+    group_ids bigint[] NOT NULL DEFAULT '{}',
+    location_ids bigint[] NOT NULL DEFAULT '{}',
+    user_active boolean NULL,
+    scheduled_at timestamp without time zone NOT NULL,
+    sent_at timestamp without time zone NULL,
+    created_by bigint NULL REFERENCES "user"(id) ON DELETE SET NULL,
+    created_at timestamp without time zone NOT NULL DEFAULT now()
+);
+CREATE INDEX announcement_pending ON announcement (scheduled_at) WHERE sent_at IS NULL;
+
+CREATE TABLE announcement_delivery (
+    id bigserial PRIMARY KEY,
+    announcement_id bigint NOT NULL REFERENCES announcement(id) ON DELETE CASCADE,
+    user_id bigint NULL REFERENCES "user"(id) ON DELETE SET NULL,
+    email text NOT NULL,
+    sent_at timestamp without time zone NULL,
+    error text NULL
+);
+CREATE INDEX announcement_delivery_announcement_id ON announcement_delivery (announcement_id);
@@ -0,0 +1,26 @@
+ALTER TABLE wireguard_network ADD COLUMN device_approval_required boolean NOT NULL DEFAULT false;
+
+CREATE TYPE device_approval_status AS ENUM (
+    'pending',
+    'approved',
+    'rejected',
+    'expired'
+);
+
+-- Devices created through enrollment in locations requiring approval are not
+-- assigned to these locations until an administrator approves the request.
+CREATE TABLE device_approval_request (
+    id bigserial PRIMARY KEY,
+    device_id bigint NOT NULL,
+    location_id bigint NOT NULL,
+    status device_approval_status NOT NULL DEFAULT 'pending',
+    created_at timestamp without time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at timestamp without time zone NOT NULL,
+    resolved_at timestamp without time zone NULL,
+    resolved_by bigint NULL,
+    FOREIGN KEY(device_id) REFERENCES "device"(id) ON DELETE CASCADE,
+    FOREIGN KEY(location_id) REFERENCES wireguard_network(id) ON DELETE CASCADE,
+    FOREIGN KEY(resolved_by) REFERENCES "user"(id) ON DELETE SET NULL,
+    CONSTRAINT device_location_approval UNIQUE (device_id, location_id)
+);
+CREATE INDEX device_approval_request_pending ON device_approval_request (expires_at) WHERE status = 'pending';
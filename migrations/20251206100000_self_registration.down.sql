@@ -0,0 +1,4 @@
+DROP TABLE self_registration_request;
+ALTER TABLE settings
+    DROP COLUMN self_registration_enabled,
+    DROP COLUMN self_registration_domains;
@@ -0,0 +1,15 @@
+ALTER TABLE settings
+    ADD COLUMN self_registration_enabled boolean NOT NULL DEFAULT false,
+    ADD COLUMN self_registration_domains text[] NOT NULL DEFAULT '{}';
+
+CREATE TABLE self_registration_request (
+    id bigserial PRIMARY KEY,
+    username text NOT NULL,
+    first_name text NOT NULL,
+    last_name text NOT NULL,
+    email text NOT NULL,
+    phone text NULL,
+    token text NOT NULL UNIQUE,
+    created_at timestamp without time zone NOT NULL DEFAULT current_timestamp,
+    expires_at timestamp without time zone NOT NULL
+);
@@ -0,0 +1,18 @@
+DROP VIEW wireguard_peer_stats_view;
+ALTER TABLE wireguard_peer_stats DROP COLUMN gateway;
+CREATE VIEW wireguard_peer_stats_view AS
+    SELECT
+        device_id,
+        greatest(upload - lag(upload, 1, upload) OVER (PARTITION BY device_id, network ORDER BY collected_at), 0) upload,
+        greatest(download - lag(download, 1, download) OVER (PARTITION BY device_id, network ORDER BY collected_at), 0) download,
+        latest_handshake - (lag(latest_handshake, 1, latest_handshake) OVER (PARTITION BY device_id, network ORDER BY collected_at)) latest_handshake_diff,
+        latest_handshake,
+        collected_at,
+        network,
+        endpoint,
+        allowed_ips
+    FROM wireguard_peer_stats;
+DROP TABLE device_gateway_assignment;
+DROP TABLE location_gateway;
+ALTER TABLE wireguard_network DROP COLUMN gateway_distribution_policy;
+DROP TYPE gateway_distribution_policy;
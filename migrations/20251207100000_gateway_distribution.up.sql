@@ -0,0 +1,45 @@
+CREATE TYPE gateway_distribution_policy AS ENUM (
+    'none',
+    'round_robin',
+    'weighted'
+);
+ALTER TABLE wireguard_network ADD COLUMN gateway_distribution_policy gateway_distribution_policy NOT NULL DEFAULT 'none';
+
+-- Public endpoints and weights of gateways serving a location, identified by hostname.
+CREATE TABLE location_gateway (
+    id bigserial PRIMARY KEY,
+    location_id bigint NOT NULL,
+    hostname text NOT NULL,
+    endpoint text NULL,
+    weight integer NOT NULL DEFAULT 1 CHECK (weight > 0),
+    FOREIGN KEY(location_id) REFERENCES wireguard_network(id) ON DELETE CASCADE,
+    CONSTRAINT location_gateway_hostname UNIQUE (location_id, hostname)
+);
+
+-- Gateway which devices should connect to, maintained according to location distribution policy.
+CREATE TABLE device_gateway_assignment (
+    device_id bigint NOT NULL,
+    location_id bigint NOT NULL,
+    hostname text NOT NULL,
+    assigned_at timestamp without time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY(device_id) REFERENCES "device"(id) ON DELETE CASCADE,
+    FOREIGN KEY(location_id) REFERENCES wireguard_network(id) ON DELETE CASCADE,
+    PRIMARY KEY (device_id, location_id)
+);
+
+ALTER TABLE wireguard_peer_stats ADD COLUMN gateway text NULL;
+
+-- Transfer counters are kept by each gateway separately.
+CREATE OR REPLACE VIEW wireguard_peer_stats_view AS
+    SELECT
+        device_id,
+        greatest(upload - lag(upload, 1, upload) OVER (PARTITION BY device_id, network, gateway ORDER BY collected_at), 0) upload,
+        greatest(download - lag(download, 1, download) OVER (PARTITION BY device_id, network, gateway ORDER BY collected_at), 0) download,
+        latest_handshake - (lag(latest_handshake, 1, latest_handshake) OVER (PARTITION BY device_id, network, gateway ORDER BY collected_at)) latest_handshake_diff,
+        latest_handshake,
+        collected_at,
+        network,
+        endpoint,
+        allowed_ips,
+        gateway
+    FROM wireguard_peer_stats;
@@ -0,0 +1,17 @@
+ALTER TABLE location_gateway DROP COLUMN group_id;
+ALTER TABLE wireguard_network DROP COLUMN gateway_peer_sharding;
+
+-- enum values can't be removed, recreate the type
+UPDATE wireguard_network SET gateway_distribution_policy = 'none'
+    WHERE gateway_distribution_policy IN ('hash', 'group');
+ALTER TABLE wireguard_network ALTER COLUMN gateway_distribution_policy DROP DEFAULT;
+ALTER TYPE gateway_distribution_policy RENAME TO gateway_distribution_policy_old;
+CREATE TYPE gateway_distribution_policy AS ENUM (
+    'none',
+    'round_robin',
+    'weighted'
+);
+ALTER TABLE wireguard_network ALTER COLUMN gateway_distribution_policy
+    TYPE gateway_distribution_policy USING gateway_distribution_policy::text::gateway_distribution_policy;
+ALTER TABLE wireguard_network ALTER COLUMN gateway_distribution_policy SET DEFAULT 'none';
+DROP TYPE gateway_distribution_policy_old;
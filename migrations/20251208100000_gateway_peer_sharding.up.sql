@@ -0,0 +1,8 @@
+ALTER TYPE gateway_distribution_policy ADD VALUE 'hash';
+ALTER TYPE gateway_distribution_policy ADD VALUE 'group';
+
+-- Each gateway holds only peers of devices assigned to it.
+ALTER TABLE wireguard_network ADD COLUMN gateway_peer_sharding boolean NOT NULL DEFAULT false;
+
+-- Members of the group are assigned to the gateway by `group` distribution policy.
+ALTER TABLE location_gateway ADD COLUMN group_id bigint NULL REFERENCES "group"(id) ON DELETE SET NULL;
@@ -0,0 +1,2 @@
+ALTER TABLE wireguard_network DROP COLUMN ip_allocation_strategy;
+DROP TYPE ip_allocation_strategy;
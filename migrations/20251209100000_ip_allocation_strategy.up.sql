@@ -0,0 +1,6 @@
+CREATE TYPE ip_allocation_strategy AS ENUM (
+    'sequential',
+    'random',
+    'sticky_by_user'
+);
+ALTER TABLE wireguard_network ADD COLUMN ip_allocation_strategy ip_allocation_strategy NOT NULL DEFAULT 'sequential';
@@ -0,0 +1 @@
+DROP INDEX wireguard_network_device_wireguard_ips;
@@ -0,0 +1,2 @@
+-- Speeds up finding devices by VPN address, e.g. when correlating firewall logs with identities.
+CREATE INDEX wireguard_network_device_wireguard_ips ON wireguard_network_device USING GIN (wireguard_ips);
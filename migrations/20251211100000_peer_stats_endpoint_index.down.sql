@@ -0,0 +1 @@
+DROP INDEX peer_stats_endpoint_collected_at;
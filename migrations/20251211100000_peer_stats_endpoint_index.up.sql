@@ -0,0 +1,2 @@
+-- Speeds up finding sessions by public endpoint address, e.g. when handling abuse reports.
+CREATE INDEX peer_stats_endpoint_collected_at ON wireguard_peer_stats (endpoint text_pattern_ops, collected_at);
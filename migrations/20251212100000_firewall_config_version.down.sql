@@ -0,0 +1 @@
+DROP TABLE firewall_config_version;
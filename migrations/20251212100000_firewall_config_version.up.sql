@@ -0,0 +1,12 @@
+-- Firewall configurations sent to gateways of each location. `config` holds an encoded
+-- `FirewallConfig`, `rollback_of` is set if the configuration was restored from an older version.
+CREATE TABLE firewall_config_version (
+    id bigserial PRIMARY KEY,
+    location_id bigint NOT NULL,
+    version bigint NOT NULL,
+    config bytea NOT NULL,
+    rollback_of bigint NULL,
+    created_at timestamp without time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY(location_id) REFERENCES wireguard_network(id) ON DELETE CASCADE,
+    CONSTRAINT location_version UNIQUE (location_id, version)
+);
@@ -0,0 +1,2 @@
+ALTER TABLE wireguard_network DROP COLUMN client_traffic_policy;
+ALTER TABLE "group" DROP COLUMN client_traffic_policy;
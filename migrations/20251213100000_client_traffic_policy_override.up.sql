@@ -0,0 +1,3 @@
+-- override instance-wide client traffic policy for group members and locations
+ALTER TABLE "group" ADD COLUMN client_traffic_policy client_traffic_policy NULL;
+ALTER TABLE wireguard_network ADD COLUMN client_traffic_policy client_traffic_policy NULL;
@@ -0,0 +1,3 @@
+DROP TABLE route_group;
+DROP TABLE route_location;
+DROP TABLE route;
@@ -0,0 +1,20 @@
+-- named lists of networks routed through locations, compiled into client AllowedIPs
+CREATE TABLE route (
+    id bigserial PRIMARY KEY,
+    name text NOT NULL UNIQUE,
+    description text NULL,
+    addresses inet[] NOT NULL DEFAULT '{}'
+);
+
+CREATE TABLE route_location (
+    route_id bigint NOT NULL REFERENCES route(id) ON DELETE CASCADE,
+    location_id bigint NOT NULL REFERENCES wireguard_network(id) ON DELETE CASCADE,
+    PRIMARY KEY (route_id, location_id)
+);
+CREATE INDEX route_location_location_id ON route_location (location_id);
+
+CREATE TABLE route_group (
+    route_id bigint NOT NULL REFERENCES route(id) ON DELETE CASCADE,
+    group_id bigint NOT NULL REFERENCES "group"(id) ON DELETE CASCADE,
+    PRIMARY KEY (route_id, group_id)
+);
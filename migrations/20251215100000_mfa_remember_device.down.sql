@@ -0,0 +1,3 @@
+DROP TABLE mfa_remembered_device;
+ALTER TABLE wireguard_network DROP COLUMN mfa_remember_device_hours;
+ALTER TABLE wireguard_network DROP COLUMN mfa_session_lifetime_hours;
@@ -0,0 +1,12 @@
+-- MFA session lifetime and remembered devices per location; 0 disables
+ALTER TABLE wireguard_network ADD COLUMN mfa_session_lifetime_hours integer NOT NULL DEFAULT 0;
+ALTER TABLE wireguard_network ADD COLUMN mfa_remember_device_hours integer NOT NULL DEFAULT 0;
+
+-- last MFA completed by a device in a location, used to skip MFA for remembered devices
+CREATE TABLE mfa_remembered_device (
+    wireguard_pubkey text NOT NULL,
+    location_id bigint NOT NULL REFERENCES wireguard_network(id) ON DELETE CASCADE,
+    device_id bigint NOT NULL REFERENCES device(id) ON DELETE CASCADE,
+    remembered_at timestamp without time zone NOT NULL,
+    PRIMARY KEY (wireguard_pubkey, location_id)
+);
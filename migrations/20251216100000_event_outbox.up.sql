@@ -0,0 +1,8 @@
+-- Domain events waiting to be published to an external message broker. Events are removed once
+-- published.
+CREATE TABLE event_outbox (
+    id bigserial PRIMARY KEY,
+    event_type text NOT NULL,
+    payload jsonb NOT NULL,
+    created_at timestamp without time zone NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
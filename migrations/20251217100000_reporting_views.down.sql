@@ -0,0 +1 @@
+DROP SCHEMA reporting CASCADE;
@@ -0,0 +1,81 @@
+-- Stable, versioned views for BI tools (Grafana, Metabase etc.). Columns of a published view
+-- must not be changed or removed; breaking changes require a new view version (e.g. `users_v2`)
+-- while the old one is kept until deprecated.
+CREATE SCHEMA reporting;
+COMMENT ON SCHEMA reporting IS 'Stable reporting views for BI tools, see `defguard init-reporting-role`.';
+
+CREATE VIEW reporting.users_v1 AS
+SELECT u.id user_id, u.username, u.first_name, u.last_name, u.email, u.is_active, u.mfa_enabled,
+    u.mfa_method::text mfa_method, u.from_ldap, u.enrollment_pending,
+    COALESCE(ARRAY_AGG(g.name ORDER BY g.name) FILTER (WHERE g.name IS NOT NULL), '{}') groups
+FROM "user" u
+LEFT JOIN group_user gu ON gu.user_id = u.id
+LEFT JOIN "group" g ON g.id = gu.group_id
+GROUP BY u.id;
+COMMENT ON VIEW reporting.users_v1 IS 'Users with their group membership.';
+COMMENT ON COLUMN reporting.users_v1.mfa_method IS 'Default MFA method: none, one_time_password, webauthn, email.';
+COMMENT ON COLUMN reporting.users_v1.groups IS 'Names of groups the user belongs to.';
+
+CREATE VIEW reporting.locations_v1 AS
+SELECT id location_id, name, endpoint, port, address::text[] address,
+    location_mfa_mode::text location_mfa_mode, keepalive_interval, peer_disconnect_threshold,
+    connected_at gateway_connected_at
+FROM wireguard_network;
+COMMENT ON VIEW reporting.locations_v1 IS 'VPN locations.';
+COMMENT ON COLUMN reporting.locations_v1.location_mfa_mode IS 'Location MFA mode: disabled, internal, external.';
+COMMENT ON COLUMN reporting.locations_v1.gateway_connected_at IS 'Last time a gateway connected to the location (UTC).';
+
+CREATE VIEW reporting.devices_v1 AS
+SELECT d.id device_id, d.name, d.device_type::text device_type, d.user_id, u.username,
+    d.created created_at, d.configured
+FROM device d
+JOIN "user" u ON u.id = d.user_id;
+COMMENT ON VIEW reporting.devices_v1 IS 'User and network devices.';
+COMMENT ON COLUMN reporting.devices_v1.device_type IS 'Device type: user, network.';
+COMMENT ON COLUMN reporting.devices_v1.created_at IS 'Device creation time (UTC).';
+COMMENT ON COLUMN reporting.devices_v1.configured IS 'False for network devices which have not been configured yet.';
+
+CREATE VIEW reporting.device_locations_v1 AS
+SELECT device_id, wireguard_network_id location_id, wireguard_ips::text[] wireguard_ips,
+    is_authorized authorized, authorized_at
+FROM wireguard_network_device;
+COMMENT ON VIEW reporting.device_locations_v1 IS 'Devices assigned to VPN locations.';
+COMMENT ON COLUMN reporting.device_locations_v1.authorized IS 'Whether the device passed location MFA.';
+
+-- Sessions are reconstructed from VPN activity log events by pairing each connection
+-- with the next disconnection of the same device in the same location.
+CREATE VIEW reporting.sessions_v1 AS
+SELECT id session_id, user_id, username, device_id, device device_name, location_id,
+    location location_name, ip, timestamp started_at,
+    CASE WHEN next_event IN ('vpn_client_disconnected', 'vpn_client_disconnected_mfa')
+        THEN next_timestamp END ended_at,
+    event = 'vpn_client_connected_mfa' mfa
+FROM (
+    SELECT id, user_id, username, ip, event, timestamp, device, location,
+        (metadata->'device'->>'id')::bigint device_id,
+        (metadata->'location'->>'id')::bigint location_id,
+        LEAD(event) OVER session next_event,
+        LEAD(timestamp) OVER session next_timestamp
+    FROM activity_log_event
+    WHERE module = 'vpn' AND event IN ('vpn_client_connected', 'vpn_client_connected_mfa',
+        'vpn_client_disconnected', 'vpn_client_disconnected_mfa')
+    WINDOW session AS (PARTITION BY metadata->'device'->'id', metadata->'location'->'id'
+        ORDER BY timestamp, id)
+) events
+WHERE event IN ('vpn_client_connected', 'vpn_client_connected_mfa');
+COMMENT ON VIEW reporting.sessions_v1 IS 'VPN sessions reconstructed from the activity log.';
+COMMENT ON COLUMN reporting.sessions_v1.ip IS 'Client public IP address.';
+COMMENT ON COLUMN reporting.sessions_v1.started_at IS 'Session start time (UTC).';
+COMMENT ON COLUMN reporting.sessions_v1.ended_at IS 'Session end time (UTC), NULL if the session is active or its end was not recorded.';
+COMMENT ON COLUMN reporting.sessions_v1.mfa IS 'Whether the session was established with location MFA.';
+
+CREATE VIEW reporting.traffic_hourly_v1 AS
+SELECT date_trunc('hour', s.collected_at) AS hour, s.network location_id, s.device_id, d.user_id,
+    SUM(s.upload)::bigint upload, SUM(s.download)::bigint download
+FROM wireguard_peer_stats_view s
+JOIN device d ON d.id = s.device_id
+GROUP BY 1, 2, 3, 4;
+COMMENT ON VIEW reporting.traffic_hourly_v1 IS 'Hourly VPN traffic per device and location, retained as long as peer stats are.';
+COMMENT ON COLUMN reporting.traffic_hourly_v1.hour IS 'Start of the hour (UTC).';
+COMMENT ON COLUMN reporting.traffic_hourly_v1.upload IS 'Bytes sent by the device.';
+COMMENT ON COLUMN reporting.traffic_hourly_v1.download IS 'Bytes received by the device.';
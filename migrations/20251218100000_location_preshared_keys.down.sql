@@ -0,0 +1,3 @@
+ALTER TABLE wireguard_network DROP COLUMN preshared_keys_rotated_at;
+ALTER TABLE wireguard_network DROP COLUMN preshared_key_rotation_days;
+ALTER TABLE wireguard_network DROP COLUMN preshared_keys_enabled;
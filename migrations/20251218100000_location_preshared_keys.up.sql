@@ -0,0 +1,5 @@
+-- static preshared keys for peers of locations without MFA, rotated every given number of days;
+-- 0 disables rotation
+ALTER TABLE wireguard_network ADD COLUMN preshared_keys_enabled boolean NOT NULL DEFAULT false;
+ALTER TABLE wireguard_network ADD COLUMN preshared_key_rotation_days integer NOT NULL DEFAULT 0;
+ALTER TABLE wireguard_network ADD COLUMN preshared_keys_rotated_at timestamp without time zone NULL;
@@ -0,0 +1,2 @@
+DROP TABLE background_job;
+DROP TYPE background_job_status;
@@ -0,0 +1,24 @@
+CREATE TYPE background_job_status AS ENUM (
+    'pending',
+    'running',
+    'succeeded',
+    'failed',
+    'cancelled'
+);
+
+-- Long-running operations executed in background; clients poll them by id.
+CREATE TABLE background_job (
+    id bigserial PRIMARY KEY,
+    kind text NOT NULL,
+    status background_job_status NOT NULL DEFAULT 'pending',
+    progress integer NOT NULL DEFAULT 0,
+    total integer NULL,
+    message text NULL,
+    result jsonb NULL,
+    created_by bigint NULL,
+    created_at timestamp without time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    started_at timestamp without time zone NULL,
+    finished_at timestamp without time zone NULL,
+    FOREIGN KEY(created_by) REFERENCES "user"(id) ON DELETE SET NULL
+);
+CREATE INDEX background_job_created_at ON background_job (created_at);
@@ -0,0 +1,5 @@
+ALTER TABLE settings
+    DROP COLUMN smtp_sender_name,
+    DROP COLUMN smtp_reply_to,
+    DROP COLUMN smtp_security_sender,
+    DROP COLUMN smtp_announcement_sender;
@@ -0,0 +1,5 @@
+ALTER TABLE settings
+    ADD COLUMN smtp_sender_name text NULL,
+    ADD COLUMN smtp_reply_to text NULL,
+    ADD COLUMN smtp_security_sender text NULL,
+    ADD COLUMN smtp_announcement_sender text NULL;
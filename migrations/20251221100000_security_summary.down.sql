@@ -0,0 +1,3 @@
+DROP TABLE security_summary;
+DROP TABLE gateway_disconnect;
+ALTER TABLE settings DROP COLUMN security_summary_enabled;
@@ -0,0 +1,18 @@
+ALTER TABLE settings ADD COLUMN security_summary_enabled boolean NOT NULL DEFAULT false;
+
+-- Gateway disconnects, used to detect unstable gateways.
+CREATE TABLE gateway_disconnect (
+    id bigserial PRIMARY KEY,
+    location_id bigint NOT NULL,
+    hostname text NOT NULL,
+    disconnected_at timestamp without time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY(location_id) REFERENCES wireguard_network(id) ON DELETE CASCADE
+);
+CREATE INDEX gateway_disconnect_disconnected_at ON gateway_disconnect (disconnected_at);
+
+CREATE TABLE security_summary (
+    id bigserial PRIMARY KEY,
+    period_start timestamp without time zone NOT NULL,
+    period_end timestamp without time zone NOT NULL,
+    recipients integer NOT NULL
+);
@@ -0,0 +1,10 @@
+-- Non-human accounts used by automation. Each one is backed by a user without credentials.
+CREATE TABLE service_account (
+    id bigserial PRIMARY KEY,
+    user_id bigint NOT NULL UNIQUE,
+    description text NULL,
+    created_by bigint NULL,
+    created_at timestamp without time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY(user_id) REFERENCES "user"(id) ON DELETE CASCADE,
+    FOREIGN KEY(created_by) REFERENCES "user"(id) ON DELETE SET NULL
+);
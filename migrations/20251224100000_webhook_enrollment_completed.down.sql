@@ -0,0 +1,2 @@
+ALTER TABLE webhook DROP COLUMN payload_template;
+ALTER TABLE webhook DROP COLUMN on_enrollment_completed;
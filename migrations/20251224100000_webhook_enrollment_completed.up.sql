@@ -0,0 +1,2 @@
+ALTER TABLE webhook ADD COLUMN on_enrollment_completed boolean NOT NULL DEFAULT false;
+ALTER TABLE webhook ADD COLUMN payload_template text NULL;
@@ -0,0 +1,2 @@
+DROP TABLE itsm_ticket;
+DROP TABLE itsm_connector;
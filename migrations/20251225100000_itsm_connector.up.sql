@@ -0,0 +1,31 @@
+-- Connectors to ITSM systems (ServiceNow, Jira) which open tickets on critical events.
+CREATE TABLE itsm_connector (
+    id bigserial PRIMARY KEY,
+    name text NOT NULL,
+    connector_type text NOT NULL,
+    url text NOT NULL,
+    username text NOT NULL,
+    secret text NOT NULL,
+    project text NULL,
+    ticket_type text NULL,
+    enabled boolean NOT NULL DEFAULT true,
+    on_gateway_down boolean NOT NULL DEFAULT false,
+    gateway_down_threshold integer NOT NULL DEFAULT 15,
+    on_license_expired boolean NOT NULL DEFAULT false,
+    on_certificate_expiring boolean NOT NULL DEFAULT false,
+    certificate_expiration_threshold integer NOT NULL DEFAULT 14,
+    summary_template text NOT NULL,
+    description_template text NOT NULL,
+    fields jsonb NOT NULL DEFAULT '{}'
+);
+
+-- Tickets opened for ongoing incidents, so each incident is reported only once.
+CREATE TABLE itsm_ticket (
+    id bigserial PRIMARY KEY,
+    connector_id bigint NOT NULL,
+    incident text NOT NULL,
+    ticket text NOT NULL,
+    opened_at timestamp without time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY(connector_id) REFERENCES itsm_connector(id) ON DELETE CASCADE,
+    CONSTRAINT connector_incident UNIQUE (connector_id, incident)
+);
@@ -0,0 +1,3 @@
+DROP INDEX wireguard_network_device_network_device;
+ALTER TABLE wireguard_network_device DROP COLUMN allowed_ips;
+DROP FUNCTION inet_hosts;
@@ -0,0 +1,10 @@
+-- Peer allowed IPs in the format sent to gateways, maintained by Postgres whenever device IPs change.
+CREATE FUNCTION inet_hosts(ips inet[]) RETURNS text[] AS $$
+    SELECT ARRAY(SELECT host(ip) FROM unnest(ips) AS ip);
+$$ LANGUAGE sql IMMUTABLE STRICT;
+
+ALTER TABLE wireguard_network_device
+    ADD COLUMN allowed_ips text[] NOT NULL GENERATED ALWAYS AS (inet_hosts(wireguard_ips)) STORED;
+
+-- Peers are fetched per location when generating gateway configuration.
+CREATE INDEX wireguard_network_device_network_device ON wireguard_network_device (wireguard_network_id, device_id);
@@ -0,0 +1,11 @@
+-- Instance-level variables available to admin-editable mail templates as `instance.<name>`.
+CREATE TABLE mail_variable (
+    id bigserial PRIMARY KEY,
+    name text NOT NULL UNIQUE,
+    value text NOT NULL
+);
+
+INSERT INTO mail_variable (name, value) VALUES
+    ('company_name', ''),
+    ('support_url', ''),
+    ('helpdesk_phone', '');
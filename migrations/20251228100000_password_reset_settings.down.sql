@@ -0,0 +1,3 @@
+ALTER TABLE token DROP COLUMN use_count;
+ALTER TABLE settings DROP COLUMN password_reset_max_uses;
+ALTER TABLE settings DROP COLUMN password_reset_token_lifetime;
@@ -0,0 +1,8 @@
+-- Lifetime of password reset links in minutes, server configuration is used if not set.
+ALTER TABLE settings ADD COLUMN password_reset_token_lifetime integer NULL
+    CHECK (password_reset_token_lifetime > 0);
+-- Number of sessions which can be started with a single password reset link.
+ALTER TABLE settings ADD COLUMN password_reset_max_uses integer NOT NULL DEFAULT 1;
+
+ALTER TABLE token ADD COLUMN use_count integer NOT NULL DEFAULT 0;
+UPDATE token SET use_count = 1 WHERE used_at IS NOT NULL;
@@ -0,0 +1,11 @@
+-- One-time links for downloading gateway setup bundles. Links are removed once used.
+CREATE TABLE gateway_setup_link (
+    id bigserial PRIMARY KEY,
+    token text NOT NULL UNIQUE,
+    network_id bigint NOT NULL,
+    created_by bigint NULL,
+    created_at timestamp without time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at timestamp without time zone NOT NULL,
+    FOREIGN KEY(network_id) REFERENCES wireguard_network(id) ON DELETE CASCADE,
+    FOREIGN KEY(created_by) REFERENCES "user"(id) ON DELETE SET NULL
+);
@@ -0,0 +1,2 @@
+-- Locations devices enrolled with a token can be added to; NULL means all locations available to the user.
+ALTER TABLE token ADD COLUMN location_ids bigint[] NULL;
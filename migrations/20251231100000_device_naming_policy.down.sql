@@ -0,0 +1,4 @@
+ALTER TABLE wireguard_network DROP COLUMN device_name_uniqueness;
+ALTER TABLE wireguard_network DROP COLUMN device_name_prefix;
+ALTER TABLE wireguard_network DROP COLUMN device_name_pattern;
+DROP TYPE device_name_uniqueness;
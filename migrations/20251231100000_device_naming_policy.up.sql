@@ -0,0 +1,9 @@
+-- naming policy for devices added to a location: regex pattern device names have to match,
+-- required name prefix template (e.g. `{username}-{os}`) and scope in which names are unique
+CREATE TYPE device_name_uniqueness AS ENUM (
+    'user',
+    'location'
+);
+ALTER TABLE wireguard_network ADD COLUMN device_name_pattern text NULL;
+ALTER TABLE wireguard_network ADD COLUMN device_name_prefix text NULL;
+ALTER TABLE wireguard_network ADD COLUMN device_name_uniqueness device_name_uniqueness NOT NULL DEFAULT 'user';
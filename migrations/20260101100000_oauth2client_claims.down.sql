@@ -0,0 +1,3 @@
+ALTER TABLE oauth2client DROP COLUMN static_claims;
+ALTER TABLE oauth2client DROP COLUMN group_filter;
+ALTER TABLE oauth2client DROP COLUMN claim_mapping;
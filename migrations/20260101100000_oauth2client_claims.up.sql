@@ -0,0 +1,5 @@
+-- custom claims of the OpenID provider: names under which user claims are emitted,
+-- groups included in the groups claim (all if empty) and static claims added for every user
+ALTER TABLE oauth2client ADD COLUMN claim_mapping jsonb NOT NULL DEFAULT '{}';
+ALTER TABLE oauth2client ADD COLUMN group_filter text[] NOT NULL DEFAULT '{}';
+ALTER TABLE oauth2client ADD COLUMN static_claims jsonb NOT NULL DEFAULT '{}';
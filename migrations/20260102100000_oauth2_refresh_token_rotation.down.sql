@@ -0,0 +1,3 @@
+DROP TABLE oauth2revokedtoken;
+DROP TYPE oauth2_revocation_reason;
+ALTER TABLE oauth2token DROP COLUMN refresh_expires_in;
@@ -0,0 +1,18 @@
+-- refresh tokens are valid longer than access tokens and are rotated on each use
+ALTER TABLE oauth2token ADD COLUMN refresh_expires_in bigint NOT NULL DEFAULT 0;
+UPDATE oauth2token SET refresh_expires_in = expires_in;
+ALTER TABLE oauth2token ALTER COLUMN refresh_expires_in DROP DEFAULT;
+
+-- refresh tokens which can't be used anymore; reuse of a rotated token revokes tokens of the app
+CREATE TYPE oauth2_revocation_reason AS ENUM (
+    'rotated',
+    'admin',
+    'replay'
+);
+CREATE TABLE oauth2revokedtoken (
+    refresh_token text PRIMARY KEY,
+    oauth2authorizedapp_id bigint NOT NULL REFERENCES oauth2authorizedapp(id) ON DELETE CASCADE,
+    reason oauth2_revocation_reason NOT NULL,
+    revoked_at timestamp without time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_in bigint NOT NULL
+);
@@ -0,0 +1,13 @@
+-- Unique hostnames resolved by clients running DNS leak tests. Queries for the hostnames are
+-- reported by the DNS server authoritative for the canary domain, along with resolver address.
+CREATE TABLE dns_canary (
+    id bigserial PRIMARY KEY,
+    hostname text NOT NULL UNIQUE,
+    device_id bigint NOT NULL,
+    network_id bigint NOT NULL,
+    resolvers inet[] NOT NULL DEFAULT '{}',
+    created_at timestamp without time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at timestamp without time zone NOT NULL,
+    FOREIGN KEY(device_id) REFERENCES device(id) ON DELETE CASCADE,
+    FOREIGN KEY(network_id) REFERENCES wireguard_network(id) ON DELETE CASCADE
+);
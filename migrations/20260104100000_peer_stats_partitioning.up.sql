@@ -0,0 +1,75 @@
+-- Peer stats are partitioned by month of collection, so expired stats can be dropped with whole
+-- partitions and long-range queries only scan relevant months. Partitions are named
+-- wireguard_peer_stats_pYYYYMM; stats outside of existing partitions end up in the default one.
+DROP VIEW reporting.traffic_hourly_v1;
+DROP VIEW wireguard_peer_stats_view;
+ALTER TABLE wireguard_peer_stats RENAME TO wireguard_peer_stats_unpartitioned;
+ALTER TABLE wireguard_peer_stats_unpartitioned DROP CONSTRAINT wireguard_peer_stats_pkey,
+    DROP CONSTRAINT wireguard_peer_stats_device_id_fkey;
+
+CREATE TABLE wireguard_peer_stats (
+    id bigint NOT NULL DEFAULT nextval('wireguard_peer_stats_id_seq'),
+    device_id bigint NOT NULL,
+    collected_at timestamp without time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    network bigint NOT NULL,
+    endpoint text NULL,
+    upload bigint NOT NULL,
+    download bigint NOT NULL,
+    latest_handshake timestamp without time zone NOT NULL,
+    allowed_ips text NULL,
+    gateway text NULL,
+    FOREIGN KEY(device_id) REFERENCES "device"(id) ON DELETE CASCADE,
+    PRIMARY KEY (id, collected_at)
+) PARTITION BY RANGE (collected_at);
+ALTER SEQUENCE wireguard_peer_stats_id_seq OWNED BY wireguard_peer_stats.id;
+CREATE TABLE wireguard_peer_stats_default PARTITION OF wireguard_peer_stats DEFAULT;
+
+-- Partitions for months of existing stats and the next two months; further ones are created by Defguard.
+DO $$
+DECLARE
+    month timestamp without time zone;
+BEGIN
+    FOR month IN SELECT generate_series(
+        date_trunc('month', LEAST((SELECT MIN(collected_at) FROM wireguard_peer_stats_unpartitioned), now()::timestamp)),
+        date_trunc('month', now()::timestamp) + interval '2 months',
+        interval '1 month'
+    ) LOOP
+        EXECUTE format(
+            'CREATE TABLE %I PARTITION OF wireguard_peer_stats FOR VALUES FROM (%L) TO (%L)',
+            'wireguard_peer_stats_p' || to_char(month, 'YYYYMM'), month, month + interval '1 month'
+        );
+    END LOOP;
+END $$;
+
+INSERT INTO wireguard_peer_stats (id, device_id, collected_at, network, endpoint, upload, download, latest_handshake, allowed_ips, gateway)
+    SELECT id, device_id, collected_at, network, endpoint, upload, download, latest_handshake, allowed_ips, gateway
+    FROM wireguard_peer_stats_unpartitioned;
+DROP TABLE wireguard_peer_stats_unpartitioned;
+
+CREATE INDEX peer_stats_device_id_collected_at on wireguard_peer_stats (device_id, network, collected_at DESC, latest_handshake DESC NULLS LAST);
+CREATE INDEX peer_stats_endpoint_collected_at ON wireguard_peer_stats (endpoint text_pattern_ops, collected_at);
+
+CREATE VIEW wireguard_peer_stats_view AS
+    SELECT
+        device_id,
+        greatest(upload - lag(upload, 1, upload) OVER (PARTITION BY device_id, network, gateway ORDER BY collected_at), 0) upload,
+        greatest(download - lag(download, 1, download) OVER (PARTITION BY device_id, network, gateway ORDER BY collected_at), 0) download,
+        latest_handshake - (lag(latest_handshake, 1, latest_handshake) OVER (PARTITION BY device_id, network, gateway ORDER BY collected_at)) latest_handshake_diff,
+        latest_handshake,
+        collected_at,
+        network,
+        endpoint,
+        allowed_ips,
+        gateway
+    FROM wireguard_peer_stats;
+
+CREATE VIEW reporting.traffic_hourly_v1 AS
+SELECT date_trunc('hour', s.collected_at) AS hour, s.network location_id, s.device_id, d.user_id,
+    SUM(s.upload)::bigint upload, SUM(s.download)::bigint download
+FROM wireguard_peer_stats_view s
+JOIN device d ON d.id = s.device_id
+GROUP BY 1, 2, 3, 4;
+COMMENT ON VIEW reporting.traffic_hourly_v1 IS 'Hourly VPN traffic per device and location, retained as long as peer stats are.';
+COMMENT ON COLUMN reporting.traffic_hourly_v1.hour IS 'Start of the hour (UTC).';
+COMMENT ON COLUMN reporting.traffic_hourly_v1.upload IS 'Bytes sent by the device.';
+COMMENT ON COLUMN reporting.traffic_hourly_v1.download IS 'Bytes received by the device.';
@@ -0,0 +1,4 @@
+DROP TABLE notification;
+DROP TABLE notification_rule;
+DROP TYPE notification_channel;
+DROP TYPE notification_category;
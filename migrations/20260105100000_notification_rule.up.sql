@@ -0,0 +1,37 @@
+CREATE TYPE notification_category AS ENUM (
+    'device_approval',
+    'self_registration',
+    'gateway_disconnected',
+    'gateway_reconnected'
+);
+CREATE TYPE notification_channel AS ENUM (
+    'email',
+    'in_app',
+    'webhook'
+);
+
+-- Alert categories an admin receives and channels they're delivered through.
+-- Admins without any rules receive all alerts by email.
+CREATE TABLE notification_rule (
+    id bigserial PRIMARY KEY,
+    user_id bigint NOT NULL,
+    category notification_category NOT NULL,
+    channel notification_channel NOT NULL,
+    webhook_url text NULL,
+    FOREIGN KEY(user_id) REFERENCES "user"(id) ON DELETE CASCADE,
+    CONSTRAINT notification_rule_channel UNIQUE (user_id, category, channel),
+    CONSTRAINT notification_rule_webhook_url CHECK ((channel = 'webhook') = (webhook_url IS NOT NULL))
+);
+
+-- Alerts delivered through the in-app channel.
+CREATE TABLE notification (
+    id bigserial PRIMARY KEY,
+    user_id bigint NOT NULL,
+    category notification_category NOT NULL,
+    subject text NOT NULL,
+    message text NOT NULL,
+    created_at timestamp without time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    read_at timestamp without time zone NULL,
+    FOREIGN KEY(user_id) REFERENCES "user"(id) ON DELETE CASCADE
+);
+CREATE INDEX notification_user_id ON notification (user_id, created_at DESC);
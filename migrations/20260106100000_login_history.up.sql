@@ -0,0 +1,21 @@
+CREATE TYPE login_source AS ENUM (
+    'web',
+    'client_mfa'
+);
+
+-- Successful and failed web logins and desktop client MFA attempts.
+CREATE TABLE login_history (
+    id bigserial PRIMARY KEY,
+    user_id bigint NOT NULL,
+    timestamp timestamp without time zone NOT NULL,
+    source login_source NOT NULL,
+    method text NOT NULL,
+    success boolean NOT NULL,
+    ip inet NOT NULL,
+    device text NOT NULL,
+    location text NULL,
+    proxy text NULL,
+    message text NULL,
+    FOREIGN KEY(user_id) REFERENCES "user"(id) ON DELETE CASCADE
+);
+CREATE INDEX login_history_user_id ON login_history (user_id, timestamp DESC);
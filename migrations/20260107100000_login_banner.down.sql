@@ -0,0 +1,2 @@
+DROP TABLE login_banner_acknowledgment;
+DROP TABLE login_banner;
@@ -0,0 +1,17 @@
+-- Versions of the banner shown before logging in; the latest active one is current.
+CREATE TABLE login_banner (
+    id bigserial PRIMARY KEY,
+    content text NOT NULL,
+    active boolean NOT NULL DEFAULT true,
+    created_at timestamp without time zone NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Users who acknowledged a version of the login banner.
+CREATE TABLE login_banner_acknowledgment (
+    user_id bigint NOT NULL,
+    banner_id bigint NOT NULL,
+    acknowledged_at timestamp without time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, banner_id),
+    FOREIGN KEY(user_id) REFERENCES "user"(id) ON DELETE CASCADE,
+    FOREIGN KEY(banner_id) REFERENCES login_banner(id) ON DELETE CASCADE
+);
@@ -0,0 +1,11 @@
+-- Changes of the address peers connect from; a row is added only when the address changes.
+CREATE TABLE device_endpoint_change (
+    id bigserial PRIMARY KEY,
+    device_id bigint NOT NULL,
+    location_id bigint NOT NULL,
+    ip inet NOT NULL,
+    changed_at timestamp without time zone NOT NULL,
+    FOREIGN KEY(device_id) REFERENCES device(id) ON DELETE CASCADE,
+    FOREIGN KEY(location_id) REFERENCES wireguard_network(id) ON DELETE CASCADE
+);
+CREATE INDEX device_endpoint_change_device_id ON device_endpoint_change (device_id, location_id, changed_at DESC);
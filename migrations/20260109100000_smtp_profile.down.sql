@@ -0,0 +1,3 @@
+DROP TABLE smtp_profile_category;
+DROP TABLE smtp_profile;
+DROP TYPE mail_category;
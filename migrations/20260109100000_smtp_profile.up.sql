@@ -0,0 +1,26 @@
+CREATE TYPE mail_category AS ENUM (
+    'general',
+    'security',
+    'announcement',
+    'alert',
+    'enrollment'
+);
+-- Additional SMTP configurations; mail categories bound to a profile are sent through it
+-- instead of the SMTP server from settings.
+CREATE TABLE smtp_profile (
+    id bigserial PRIMARY KEY,
+    name text NOT NULL UNIQUE,
+    server text NOT NULL,
+    port integer NOT NULL,
+    encryption smtp_encryption NOT NULL DEFAULT 'starttls',
+    username text NULL,
+    password text NULL,
+    sender text NOT NULL,
+    sender_name text NULL,
+    reply_to text NULL
+);
+CREATE TABLE smtp_profile_category (
+    category mail_category PRIMARY KEY,
+    profile_id bigint NOT NULL,
+    FOREIGN KEY(profile_id) REFERENCES smtp_profile(id) ON DELETE CASCADE
+);
@@ -0,0 +1,8 @@
+-- Countries desktop client MFA authorizations of a location are allowed or blocked from,
+-- by ISO 3166-1 alpha-2 code of the client public address.
+CREATE TABLE location_geofence (
+    location_id bigint PRIMARY KEY,
+    allowed_countries text[] NOT NULL DEFAULT '{}',
+    blocked_countries text[] NOT NULL DEFAULT '{}',
+    FOREIGN KEY(location_id) REFERENCES wireguard_network(id) ON DELETE CASCADE
+);
@@ -0,0 +1,14 @@
+-- Mails waiting for another delivery attempt after a transient SMTP failure.
+CREATE TABLE mail_queue (
+    id bigserial PRIMARY KEY,
+    recipient text NOT NULL,
+    subject text NOT NULL,
+    content text NOT NULL,
+    category mail_category NOT NULL,
+    attempts integer NOT NULL,
+    -- NULL once delivery is given up
+    next_attempt_at timestamp without time zone NULL,
+    last_error text NOT NULL,
+    created_at timestamp without time zone NOT NULL
+);
+CREATE INDEX mail_queue_next_attempt_at ON mail_queue (next_attempt_at);
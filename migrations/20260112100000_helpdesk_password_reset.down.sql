@@ -0,0 +1,2 @@
+DROP TABLE helpdesk_password_reset;
+ALTER TABLE "group" DROP COLUMN is_helpdesk;
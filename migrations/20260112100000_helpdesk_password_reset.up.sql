@@ -0,0 +1,19 @@
+ALTER TABLE "group" ADD COLUMN is_helpdesk boolean NOT NULL DEFAULT false;
+
+-- Password resets initiated by helpdesk, kept as an audit trail.
+CREATE TABLE helpdesk_password_reset (
+    id bigserial PRIMARY KEY,
+    user_id bigint NOT NULL REFERENCES "user"(id) ON DELETE CASCADE,
+    requested_by bigint NULL REFERENCES "user"(id) ON DELETE SET NULL,
+    -- kept after the requesting user is removed
+    requested_by_username text NOT NULL,
+    -- temporary password, cleared once the reset is confirmed or invalidated
+    password_hash text NULL,
+    -- verification code sent to the user
+    code text NOT NULL,
+    failed_attempts integer NOT NULL DEFAULT 0,
+    created_at timestamp without time zone NOT NULL,
+    expires_at timestamp without time zone NOT NULL,
+    confirmed_at timestamp without time zone NULL
+);
+CREATE INDEX helpdesk_password_reset_user_id ON helpdesk_password_reset (user_id);
@@ -0,0 +1,5 @@
+ALTER TABLE settings
+    DROP COLUMN mail_backend,
+    DROP COLUMN mail_http_url,
+    DROP COLUMN mail_http_token;
+DROP TYPE mail_backend;
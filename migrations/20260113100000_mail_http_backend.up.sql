@@ -0,0 +1,8 @@
+CREATE TYPE mail_backend AS ENUM (
+    'smtp',
+    'http'
+);
+ALTER TABLE settings
+    ADD COLUMN mail_backend mail_backend NOT NULL DEFAULT 'smtp',
+    ADD COLUMN mail_http_url text NULL,
+    ADD COLUMN mail_http_token text NULL;
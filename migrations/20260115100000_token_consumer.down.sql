@@ -0,0 +1 @@
+ALTER TABLE token DROP COLUMN used_ip, DROP COLUMN used_user_agent;
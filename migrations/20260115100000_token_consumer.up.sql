@@ -0,0 +1,2 @@
+-- client which started the last session with a token
+ALTER TABLE token ADD COLUMN used_ip text NULL, ADD COLUMN used_user_agent text NULL;
@@ -0,0 +1,3 @@
+DROP TABLE mail_context;
+ALTER TABLE settings DROP COLUMN default_mail_language;
+ALTER TABLE "user" DROP COLUMN mail_language;
@@ -0,0 +1,13 @@
+-- Language of mails sent to a user, instance default is used if not set.
+ALTER TABLE "user" ADD COLUMN mail_language text NULL;
+ALTER TABLE settings ADD COLUMN default_mail_language text NOT NULL DEFAULT 'en';
+
+-- Translations of mail template sections, replacing built-in English texts.
+CREATE TABLE mail_context (
+    id bigserial PRIMARY KEY,
+    template text NOT NULL,
+    section text NOT NULL,
+    language_tag text NOT NULL,
+    text text NOT NULL,
+    UNIQUE (template, section, language_tag)
+);
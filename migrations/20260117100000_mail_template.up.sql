@@ -0,0 +1,6 @@
+-- Admin overrides of built-in mail templates.
+CREATE TABLE mail_template (
+    id bigserial PRIMARY KEY,
+    name text NOT NULL UNIQUE,
+    content text NOT NULL
+);
@@ -0,0 +1,18 @@
+DROP INDEX smtp_profile_failover;
+ALTER TABLE smtp_profile DROP COLUMN failover;
+
+-- enum values can't be removed, recreate the type
+DELETE FROM notification_rule WHERE category = 'smtp_failover';
+DELETE FROM notification WHERE category = 'smtp_failover';
+ALTER TYPE notification_category RENAME TO notification_category_old;
+CREATE TYPE notification_category AS ENUM (
+    'device_approval',
+    'self_registration',
+    'gateway_disconnected',
+    'gateway_reconnected'
+);
+ALTER TABLE notification_rule ALTER COLUMN category
+    TYPE notification_category USING category::text::notification_category;
+ALTER TABLE notification ALTER COLUMN category
+    TYPE notification_category USING category::text::notification_category;
+DROP TYPE notification_category_old;
@@ -0,0 +1,4 @@
+ALTER TYPE notification_category ADD VALUE 'smtp_failover';
+-- SMTP profile used in place of the SMTP server from settings while it keeps failing.
+ALTER TABLE smtp_profile ADD COLUMN failover boolean NOT NULL DEFAULT false;
+CREATE UNIQUE INDEX smtp_profile_failover ON smtp_profile (failover) WHERE failover;
@@ -0,0 +1,12 @@
+-- Scheduled gateway downtime of a location, announced to admins with calendar invites.
+CREATE TABLE maintenance_window (
+    id bigserial PRIMARY KEY,
+    location_id bigint NOT NULL,
+    starts_at timestamp without time zone NOT NULL,
+    ends_at timestamp without time zone NOT NULL,
+    description text NOT NULL DEFAULT '',
+    created_at timestamp without time zone NOT NULL DEFAULT current_timestamp,
+    FOREIGN KEY(location_id) REFERENCES wireguard_network(id) ON DELETE CASCADE,
+    CHECK (ends_at > starts_at)
+);
+CREATE INDEX maintenance_window_location_id ON maintenance_window(location_id);
@@ -0,0 +1,3 @@
+ALTER TYPE notification_category ADD VALUE 'proxy_connectivity';
+-- Connectivity notifications are batched into a digest mail over this many minutes; 0 disables.
+ALTER TABLE settings ADD COLUMN notification_digest_window integer NOT NULL DEFAULT 0;
@@ -0,0 +1,3 @@
+ALTER TABLE settings DROP COLUMN auditor_redact_ip_addresses;
+ALTER TABLE settings DROP COLUMN auditor_redact_emails;
+ALTER TABLE "group" DROP COLUMN is_auditor;
@@ -0,0 +1,5 @@
+ALTER TABLE "group" ADD COLUMN is_auditor boolean NOT NULL DEFAULT false;
+
+-- personal data redacted in responses to auditors
+ALTER TABLE settings ADD COLUMN auditor_redact_emails boolean NOT NULL DEFAULT true;
+ALTER TABLE settings ADD COLUMN auditor_redact_ip_addresses boolean NOT NULL DEFAULT true;
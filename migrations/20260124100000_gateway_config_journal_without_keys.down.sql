@@ -0,0 +1 @@
+-- Removed journal entries can't be restored.
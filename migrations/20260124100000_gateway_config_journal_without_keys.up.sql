@@ -0,0 +1,3 @@
+-- Journal payloads used to include private and preshared keys, drop them along with entries
+-- of already deleted locations.
+DELETE FROM gateway_config_journal;
@@ -233,6 +233,10 @@ Licensing information: [https://docs.defguard.net/enterprise/license](https://do
       submit: 'Create group',
       groupSettings: 'Group settings',
       adminGroup: 'Admin group',
+      helpdeskGroup: 'Helpdesk group (can reset passwords of non-admin users)',
+      auditorGroup: 'Auditor group (read-only access with personal data redacted)',
+      clientTrafficPolicy: 'Client traffic policy',
+      inheritTrafficPolicy: 'Same as in enterprise settings',
     },
     editGroup: {
       title: 'Edit group',
@@ -242,6 +246,10 @@ Licensing information: [https://docs.defguard.net/enterprise/license](https://do
       submit: 'Update group',
       groupSettings: 'Group settings',
       adminGroup: 'Admin group',
+      helpdeskGroup: 'Helpdesk group (can reset passwords of non-admin users)',
+      auditorGroup: 'Auditor group (read-only access with personal data redacted)',
+      clientTrafficPolicy: 'Client traffic policy',
+      inheritTrafficPolicy: 'Same as in enterprise settings',
     },
     deleteGroup: {
       title: 'Delete group {name:string}',
@@ -1016,6 +1024,7 @@ Licensing information: [https://docs.defguard.net/enterprise/license](https://do
         addGPG: 'Add GPG Key',
         delete: 'Delete account',
         startEnrollment: 'Start enrollment',
+        enrollmentSheet: 'Download enrollment sheet',
         activateDesktop: 'Configure Desktop Client',
         resetPassword: 'Reset password',
         disableMfa: 'Disable MFA',
@@ -1585,12 +1594,32 @@ Licensing information: [https://docs.defguard.net/enterprise/license](https://do
       form: {
         title: 'SMTP configuration',
         sections: {
+          delivery: 'Delivery',
           server: 'Server settings',
+          authentication: 'Authentication',
+          senders: 'Senders',
         },
         fields: {
           encryption: {
             label: 'Encryption',
           },
+          authMethod: {
+            label: 'Authentication method',
+          },
+          mailBackend: {
+            label: 'Mail backend',
+            helper:
+              'Mails can be delivered through an SMTP server or POSTed as JSON to an HTTP API of a mail delivery service. Mail categories bound to SMTP profiles are always sent through their profiles.',
+          },
+          httpUrl: {
+            label: 'HTTP API URL',
+            placeholder: 'https://api.example.com/mail/send',
+          },
+          httpToken: {
+            label: 'HTTP API token',
+            placeholder: 'Token',
+            helper: 'Sent as a bearer token in the Authorization header.',
+          },
           server: {
             label: 'Server address',
             placeholder: 'Address',
@@ -1607,6 +1636,42 @@ Licensing information: [https://docs.defguard.net/enterprise/license](https://do
             label: 'Server password',
             placeholder: 'Password',
           },
+          oauth2TokenUrl: {
+            label: 'OAuth2 token URL',
+            placeholder: 'https://login.microsoftonline.com/<tenant>/oauth2/v2.0/token',
+          },
+          oauth2ClientId: {
+            label: 'OAuth2 client ID',
+            placeholder: 'Client ID',
+          },
+          oauth2ClientSecret: {
+            label: 'OAuth2 client secret',
+            placeholder: 'Client secret',
+          },
+          oauth2Scope: {
+            label: 'OAuth2 scope',
+            placeholder: 'https://outlook.office365.com/.default',
+          },
+          senderName: {
+            label: 'Sender display name',
+            placeholder: 'defguard',
+            helper: 'Name displayed next to the sender address.',
+          },
+          replyTo: {
+            label: 'Reply-to address',
+            placeholder: 'Address',
+            helper: 'Address replies are sent to. Leave empty to reply to the sender.',
+          },
+          securitySender: {
+            label: 'Security emails sender',
+            placeholder: 'Address',
+            helper: 'Sender address of MFA codes, password resets and new login notifications. Leave empty to use the default sender.',
+          },
+          announcementSender: {
+            label: 'Announcements sender',
+            placeholder: 'Address',
+            helper: 'Sender address of announcements. Leave empty to use the default sender.',
+          },
           sender: {
             label: 'Sender email address',
             placeholder: 'Address',
@@ -1741,6 +1806,8 @@ Licensing information: [https://docs.defguard.net/enterprise/license](https://do
       header: 'Notifications',
       sections: {
         gateway: 'Gateway disconnect notifications',
+        securitySummary: 'Weekly security summary',
+        digest: 'Notification digest',
       },
       helper: 'Here you can manage email notifications.',
       form: {
@@ -1758,6 +1825,14 @@ Licensing information: [https://docs.defguard.net/enterprise/license](https://do
             label: 'Enable gateway reconnect notifications',
             help: 'Send email notification to admin users once a gateway is reconnected',
           },
+          securitySummaryEnabled: {
+            label: 'Enable weekly security summary',
+            help: 'Send a weekly email to admin users summarizing new devices, failed login spikes, unstable gateways, license limits and expiring certificates',
+          },
+          notificationDigestWindow: {
+            label: 'Notification digest window [minutes]',
+            help: 'Gateway and proxy connectivity notifications sent to admin users within this time are batched into a single summary email. Set to 0 to send each notification immediately',
+          },
         },
       },
     },
@@ -1822,6 +1897,7 @@ Licensing information: [https://docs.defguard.net/enterprise/license](https://do
           editApp: 'Edit {appName: string} app',
         },
         scopes: 'Scopes:',
+        claims: 'Claims:',
         messages: {
           clientIdCopy: 'Client ID copied.',
           clientSecretCopy: 'Client secret copied.',
@@ -1835,6 +1911,8 @@ Licensing information: [https://docs.defguard.net/enterprise/license](https://do
             urlRequired: 'URL is required.',
             validUrl: 'Must be a valid URL.',
             scopeValidation: 'Must have at least one scope.',
+            claimMapping: 'Use claim:name pairs separated by commas.',
+            staticClaims: 'Must be a JSON object.',
           },
           fields: {
             name: {
@@ -1859,6 +1937,17 @@ Licensing information: [https://docs.defguard.net/enterprise/license](https://do
             groups: {
               label: 'Groups',
             },
+            groupFilter: {
+              label: 'Groups included in groups claim',
+              placeholder: 'All groups',
+            },
+            claimMapping: {
+              label: 'Claim names',
+              placeholder: 'preferred_username:uid, groups:roles',
+            },
+            staticClaims: {
+              label: 'Static claims (JSON object)',
+            },
           },
           controls: {
             addUrl: 'Add URL',
@@ -2052,6 +2141,20 @@ Licensing information: [https://docs.defguard.net/enterprise/license](https://do
           "ACL functionality is an enterprise feature and you've exceeded the user, device or network limits to use it. In order to use this feature, purchase an enterprise license or upgrade your existing one.",
         peerDisconnectThreshold:
           'Clients authorized with MFA will be disconnected from the location once there has been no network activity detected between them and the VPN gateway for a length of time configured below.',
+        mfaSessionLifetime:
+          'Clients authorized with MFA have to authenticate again once the session lifetime configured below has passed, even if they are active. Devices can also be remembered after MFA, so they can connect again without repeating it for the configured number of hours. Use 0 to disable either option.',
+        presharedKeys:
+          'Peers of locations without MFA can additionally use preshared keys generated by Defguard. Keys are delivered to clients with their device configuration and can be rotated every configured number of days. Use 0 to disable rotation.',
+        clientVersions:
+          'Clients older than the versions configured below will be asked to update before they can connect to this location. Leave empty to allow all client versions.',
+        ipAllocation:
+          'New devices get the lowest free address by default. Random assignment makes addresses harder to guess, and assignment by user keeps devices of the same user next to each other.',
+        deviceNaming:
+          'Names of new devices can be required to match a regular expression and to start with a prefix. The prefix can contain username, first_name, last_name and os placeholders, each in curly braces. Leave empty to allow any name.',
+        clientTrafficPolicy:
+          'Overrides the client traffic policy from enterprise settings and group settings for this location. When all traffic is forced, devices route all traffic through this location.',
+        deviceApproval:
+          "Devices added by users through enrollment (e.g. in the desktop client) won't be able to connect to this location until an administrator approves them. Unapproved requests expire automatically.",
         locationMfaMode: {
           description: 'Choose how MFA is enforced when connecting to this location:',
           internal:
@@ -2082,6 +2185,21 @@ Licensing information: [https://docs.defguard.net/enterprise/license](https://do
         serviceLocation: {
           header: 'Service location',
         },
+        clientVersions: {
+          header: 'Client versions',
+        },
+        deviceApproval: {
+          header: 'Device approval',
+        },
+        ipAllocation: {
+          header: 'IP address assignment',
+        },
+        deviceNaming: {
+          header: 'Device naming',
+        },
+        clientTrafficPolicy: {
+          header: 'Client traffic policy',
+        },
       },
       messages: {
         networkModified: 'Location modified.',
@@ -2116,6 +2234,18 @@ Licensing information: [https://docs.defguard.net/enterprise/license](https://do
         peer_disconnect_threshold: {
           label: 'Client disconnect threshold [seconds]',
         },
+        mfa_session_lifetime_hours: {
+          label: 'MFA session lifetime [hours]',
+        },
+        mfa_remember_device_hours: {
+          label: 'Remember devices for [hours]',
+        },
+        preshared_keys_enabled: {
+          label: 'Use preshared keys',
+        },
+        preshared_key_rotation_days: {
+          label: 'Preshared key rotation [days]',
+        },
         acl_enabled: {
           label: 'Enable ACL for this location',
         },
@@ -2128,6 +2258,40 @@ Licensing information: [https://docs.defguard.net/enterprise/license](https://do
         service_location_mode: {
           label: 'Service location mode',
         },
+        min_desktop_client_version: {
+          label: 'Minimum desktop client version',
+        },
+        min_mobile_client_version: {
+          label: 'Minimum mobile client version',
+        },
+        device_approval_required: {
+          label: 'Require admin approval of new devices',
+        },
+        ip_allocation_strategy: {
+          label: 'Address assignment strategy',
+          options: {
+            sequential: 'Lowest free address',
+            random: 'Random address',
+            sticky_by_user: 'Grouped by user',
+          },
+        },
+        device_name_pattern: {
+          label: 'Device name pattern (regular expression)',
+        },
+        device_name_prefix: {
+          label: 'Required device name prefix',
+        },
+        device_name_uniqueness: {
+          label: 'Device names have to be unique',
+          options: {
+            user: 'Among devices of the same user',
+            location: 'Among all devices in the location',
+          },
+        },
+        client_traffic_policy: {
+          label: 'Client traffic policy',
+          inherit: 'Same as in enterprise and group settings',
+        },
       },
       controls: {
         submit: 'Save changes',
@@ -2347,6 +2511,18 @@ Licensing information: [https://docs.defguard.net/enterprise/license](https://do
           duplicateWelcome: 'Same as welcome message',
         },
       },
+      selfRegistration: {
+        title: 'Self-registration',
+        messageBox:
+          'Allow users with emails under listed domains to request an account. After verifying their email, a disabled account is created and administrators are notified to activate it. Requires SMTP configuration.',
+        controls: {
+          enabled: 'Enable self-registration',
+        },
+        domains: {
+          label: 'Allowed email domains',
+          placeholder: 'example.com, example.org',
+        },
+      },
     },
   },
   supportPage: {
@@ -2723,6 +2899,7 @@ This alias is currently in use by the following rule(s) and cannot be deleted. T
       recovery_code_used: 'Recovery code used',
       user_logout: 'User logout',
       user_added: 'User added',
+      user_self_registered: 'User self-registered',
       user_removed: 'User removed',
       user_modified: 'User modified',
       user_groups_modified: 'User groups modified',
@@ -2738,6 +2915,11 @@ This alias is currently in use by the following rule(s) and cannot be deleted. T
       device_added: 'Device added',
       device_removed: 'Device removed',
       device_modified: 'Device modified',
+      device_disconnected: 'Device disconnected',
+      device_quarantined: 'Device quarantined',
+      device_released_from_quarantine: 'Device released from quarantine',
+      device_approved: 'Device approved',
+      device_rejected: 'Device rejected',
       network_device_added: 'Network device added',
       network_device_removed: 'Network device removed',
       network_device_modified: 'Network device modified',
@@ -2750,6 +2932,9 @@ This alias is currently in use by the following rule(s) and cannot be deleted. T
       vpn_client_disconnected_mfa: 'VPN client disconnected from MFA location',
       vpn_client_mfa_failed: 'VPN client failed MFA authentication',
       enrollment_token_added: 'Enrollment token added',
+      enrollment_token_resent: 'Enrollment token email resent',
+      enrollment_token_extended: 'Enrollment token extended',
+      enrollment_token_revoked: 'Enrollment token revoked',
       enrollment_started: 'Enrollment started',
       enrollment_device_added: 'Device added',
       enrollment_completed: 'Enrollment completed',
@@ -2759,6 +2944,7 @@ This alias is currently in use by the following rule(s) and cannot be deleted. T
       vpn_location_added: 'VPN location added',
       vpn_location_removed: 'VPN location removed',
       vpn_location_modified: 'VPN location modified',
+      vpn_location_firewall_rolled_back: 'VPN location firewall rolled back',
       api_token_added: 'API token added',
       api_token_removed: 'API token removed',
       api_token_renamed: 'API token renamed',
@@ -2782,12 +2968,32 @@ This alias is currently in use by the following rule(s) and cannot be deleted. T
       web_hook_modified: 'Webhook modified',
       web_hook_removed: 'Webhook removed',
       web_hook_state_changed: 'Webhook state changed',
+      announcement_created: 'Announcement created',
+      announcement_removed: 'Announcement removed',
+      service_account_added: 'Service account added',
+      service_account_modified: 'Service account modified',
+      service_account_removed: 'Service account removed',
+      itsm_connector_added: 'ITSM connector added',
+      itsm_connector_modified: 'ITSM connector modified',
+      itsm_connector_removed: 'ITSM connector removed',
+      mail_variable_added: 'Mail variable added',
+      mail_variable_modified: 'Mail variable modified',
+      mail_variable_removed: 'Mail variable removed',
+      mail_context_modified: 'Mail translation modified',
+      mail_context_removed: 'Mail translation removed',
+      mail_template_modified: 'Mail template overridden',
+      mail_template_restored: 'Mail template restored',
+      route_added: 'Route added',
+      route_modified: 'Route modified',
+      route_removed: 'Route removed',
       authentication_key_added: 'Authentication key added',
       authentication_key_removed: 'Authentication key removed',
       authentication_key_renamed: 'Authentication key renamed',
       password_changed: 'Password changed',
       password_changed_by_admin: 'Password changed by admin',
       password_reset: 'Password reset',
+      helpdesk_password_reset_requested: 'Helpdesk password reset requested',
+      helpdesk_password_reset_confirmed: 'Helpdesk password reset confirmed',
       client_configuration_token_added: 'Client configuration token added',
       user_snat_binding_added: 'User SNAT binding added',
       user_snat_binding_modified: 'User SNAT binding modified',
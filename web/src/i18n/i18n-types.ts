@@ -590,6 +590,22 @@ type RootTranslation = {
 			 * A​d​m​i​n​ ​g​r​o​u​p
 			 */
 			adminGroup: string
+			/**
+			 * H​e​l​p​d​e​s​k​ ​g​r​o​u​p​ ​(​c​a​n​ ​r​e​s​e​t​ ​p​a​s​s​w​o​r​d​s​ ​o​f​ ​n​o​n​-​a​d​m​i​n​ ​u​s​e​r​s​)
+			 */
+			helpdeskGroup: string
+			/**
+			 * A​u​d​i​t​o​r​ ​g​r​o​u​p​ ​(​r​e​a​d​-​o​n​l​y​ ​a​c​c​e​s​s​ ​w​i​t​h​ ​p​e​r​s​o​n​a​l​ ​d​a​t​a​ ​r​e​d​a​c​t​e​d​)
+			 */
+			auditorGroup: string
+			/**
+			 * C​l​i​e​n​t​ ​t​r​a​f​f​i​c​ ​p​o​l​i​c​y
+			 */
+			clientTrafficPolicy: string
+			/**
+			 * S​a​m​e​ ​a​s​ ​i​n​ ​e​n​t​e​r​p​r​i​s​e​ ​s​e​t​t​i​n​g​s
+			 */
+			inheritTrafficPolicy: string
 		}
 		editGroup: {
 			/**
@@ -620,6 +636,22 @@ type RootTranslation = {
 			 * A​d​m​i​n​ ​g​r​o​u​p
 			 */
 			adminGroup: string
+			/**
+			 * H​e​l​p​d​e​s​k​ ​g​r​o​u​p​ ​(​c​a​n​ ​r​e​s​e​t​ ​p​a​s​s​w​o​r​d​s​ ​o​f​ ​n​o​n​-​a​d​m​i​n​ ​u​s​e​r​s​)
+			 */
+			helpdeskGroup: string
+			/**
+			 * A​u​d​i​t​o​r​ ​g​r​o​u​p​ ​(​r​e​a​d​-​o​n​l​y​ ​a​c​c​e​s​s​ ​w​i​t​h​ ​p​e​r​s​o​n​a​l​ ​d​a​t​a​ ​r​e​d​a​c​t​e​d​)
+			 */
+			auditorGroup: string
+			/**
+			 * C​l​i​e​n​t​ ​t​r​a​f​f​i​c​ ​p​o​l​i​c​y
+			 */
+			clientTrafficPolicy: string
+			/**
+			 * S​a​m​e​ ​a​s​ ​i​n​ ​e​n​t​e​r​p​r​i​s​e​ ​s​e​t​t​i​n​g​s
+			 */
+			inheritTrafficPolicy: string
 		}
 		deleteGroup: {
 			/**
@@ -2401,6 +2433,10 @@ type RootTranslation = {
 				 * S​t​a​r​t​ ​e​n​r​o​l​l​m​e​n​t
 				 */
 				startEnrollment: string
+				/**
+				 * D​o​w​n​l​o​a​d​ ​e​n​r​o​l​l​m​e​n​t​ ​s​h​e​e​t
+				 */
+				enrollmentSheet: string
 				/**
 				 * C​o​n​f​i​g​u​r​e​ ​D​e​s​k​t​o​p​ ​C​l​i​e​n​t
 				 */
@@ -3829,10 +3865,22 @@ type RootTranslation = {
 				 */
 				title: string
 				sections: {
+					/**
+					 * D​e​l​i​v​e​r​y
+					 */
+					delivery: string
 					/**
 					 * S​e​r​v​e​r​ ​s​e​t​t​i​n​g​s
 					 */
 					server: string
+					/**
+					 * A​u​t​h​e​n​t​i​c​a​t​i​o​n
+					 */
+					authentication: string
+					/**
+					 * S​e​n​d​e​r​s
+					 */
+					senders: string
 				}
 				fields: {
 					encryption: {
@@ -3841,6 +3889,46 @@ type RootTranslation = {
 						 */
 						label: string
 					}
+					authMethod: {
+						/**
+						 * A​u​t​h​e​n​t​i​c​a​t​i​o​n​ ​m​e​t​h​o​d
+						 */
+						label: string
+					}
+					mailBackend: {
+						/**
+						 * M​a​i​l​ ​b​a​c​k​e​n​d
+						 */
+						label: string
+						/**
+						 * M​a​i​l​s​ ​c​a​n​ ​b​e​ ​d​e​l​i​v​e​r​e​d​ ​t​h​r​o​u​g​h​ ​a​n​ ​S​M​T​P​ ​s​e​r​v​e​r​ ​o​r​ ​P​O​S​T​e​d​ ​a​s​ ​J​S​O​N​ ​t​o​ ​a​n​ ​H​T​T​P​ ​A​P​I​ ​o​f​ ​a​ ​m​a​i​l​ ​d​e​l​i​v​e​r​y​ ​s​e​r​v​i​c​e​.​ ​M​a​i​l​ ​c​a​t​e​g​o​r​i​e​s​ ​b​o​u​n​d​ ​t​o​ ​S​M​T​P​ ​p​r​o​f​i​l​e​s​ ​a​r​e​ ​a​l​w​a​y​s​ ​s​e​n​t​ ​t​h​r​o​u​g​h​ ​t​h​e​i​r​ ​p​r​o​f​i​l​e​s​.
+						 */
+						helper: string
+					}
+					httpUrl: {
+						/**
+						 * H​T​T​P​ ​A​P​I​ ​U​R​L
+						 */
+						label: string
+						/**
+						 * h​t​t​p​s​:​/​/​a​p​i​.​e​x​a​m​p​l​e​.​c​o​m​/​m​a​i​l​/​s​e​n​d
+						 */
+						placeholder: string
+					}
+					httpToken: {
+						/**
+						 * H​T​T​P​ ​A​P​I​ ​t​o​k​e​n
+						 */
+						label: string
+						/**
+						 * T​o​k​e​n
+						 */
+						placeholder: string
+						/**
+						 * S​e​n​t​ ​a​s​ ​a​ ​b​e​a​r​e​r​ ​t​o​k​e​n​ ​i​n​ ​t​h​e​ ​A​u​t​h​o​r​i​z​a​t​i​o​n​ ​h​e​a​d​e​r​.
+						 */
+						helper: string
+					}
 					server: {
 						/**
 						 * S​e​r​v​e​r​ ​a​d​d​r​e​s​s
@@ -3881,6 +3969,102 @@ type RootTranslation = {
 						 */
 						placeholder: string
 					}
+					oauth2TokenUrl: {
+						/**
+						 * O​A​u​t​h​2​ ​t​o​k​e​n​ ​U​R​L
+						 */
+						label: string
+						/**
+						 * h​t​t​p​s​:​/​/​l​o​g​i​n​.​m​i​c​r​o​s​o​f​t​o​n​l​i​n​e​.​c​o​m​/​<​t​e​n​a​n​t​>​/​o​a​u​t​h​2​/​v​2​.​0​/​t​o​k​e​n
+						 */
+						placeholder: string
+					}
+					oauth2ClientId: {
+						/**
+						 * O​A​u​t​h​2​ ​c​l​i​e​n​t​ ​I​D
+						 */
+						label: string
+						/**
+						 * C​l​i​e​n​t​ ​I​D
+						 */
+						placeholder: string
+					}
+					oauth2ClientSecret: {
+						/**
+						 * O​A​u​t​h​2​ ​c​l​i​e​n​t​ ​s​e​c​r​e​t
+						 */
+						label: string
+						/**
+						 * C​l​i​e​n​t​ ​s​e​c​r​e​t
+						 */
+						placeholder: string
+					}
+					oauth2Scope: {
+						/**
+						 * O​A​u​t​h​2​ ​s​c​o​p​e
+						 */
+						label: string
+						/**
+						 * h​t​t​p​s​:​/​/​o​u​t​l​o​o​k​.​o​f​f​i​c​e​3​6​5​.​c​o​m​/​.​d​e​f​a​u​l​t
+						 */
+						placeholder: string
+					}
+					senderName: {
+						/**
+						 * S​e​n​d​e​r​ ​d​i​s​p​l​a​y​ ​n​a​m​e
+						 */
+						label: string
+						/**
+						 * d​e​f​g​u​a​r​d
+						 */
+						placeholder: string
+						/**
+						 * N​a​m​e​ ​d​i​s​p​l​a​y​e​d​ ​n​e​x​t​ ​t​o​ ​t​h​e​ ​s​e​n​d​e​r​ ​a​d​d​r​e​s​s​.
+						 */
+						helper: string
+					}
+					replyTo: {
+						/**
+						 * R​e​p​l​y​-​t​o​ ​a​d​d​r​e​s​s
+						 */
+						label: string
+						/**
+						 * A​d​d​r​e​s​s
+						 */
+						placeholder: string
+						/**
+						 * A​d​d​r​e​s​s​ ​r​e​p​l​i​e​s​ ​a​r​e​ ​s​e​n​t​ ​t​o​.​ ​L​e​a​v​e​ ​e​m​p​t​y​ ​t​o​ ​r​e​p​l​y​ ​t​o​ ​t​h​e​ ​s​e​n​d​e​r​.
+						 */
+						helper: string
+					}
+					securitySender: {
+						/**
+						 * S​e​c​u​r​i​t​y​ ​e​m​a​i​l​s​ ​s​e​n​d​e​r
+						 */
+						label: string
+						/**
+						 * A​d​d​r​e​s​s
+						 */
+						placeholder: string
+						/**
+						 * S​e​n​d​e​r​ ​a​d​d​r​e​s​s​ ​o​f​ ​M​F​A​ ​c​o​d​e​s​,​ ​p​a​s​s​w​o​r​d​ ​r​e​s​e​t​s​ ​a​n​d​ ​n​e​w​ ​l​o​g​i​n​ ​n​o​t​i​f​i​c​a​t​i​o​n​s​.​ ​L​e​a​v​e​ ​e​m​p​t​y​ ​t​o​ ​u​s​e​ ​t​h​e​ ​d​e​f​a​u​l​t​ ​s​e​n​d​e​r​.
+						 */
+						helper: string
+					}
+					announcementSender: {
+						/**
+						 * A​n​n​o​u​n​c​e​m​e​n​t​s​ ​s​e​n​d​e​r
+						 */
+						label: string
+						/**
+						 * A​d​d​r​e​s​s
+						 */
+						placeholder: string
+						/**
+						 * S​e​n​d​e​r​ ​a​d​d​r​e​s​s​ ​o​f​ ​a​n​n​o​u​n​c​e​m​e​n​t​s​.​ ​L​e​a​v​e​ ​e​m​p​t​y​ ​t​o​ ​u​s​e​ ​t​h​e​ ​d​e​f​a​u​l​t​ ​s​e​n​d​e​r​.
+						 */
+						helper: string
+					}
 					sender: {
 						/**
 						 * S​e​n​d​e​r​ ​e​m​a​i​l​ ​a​d​d​r​e​s​s
@@ -4150,6 +4334,14 @@ type RootTranslation = {
 				 * G​a​t​e​w​a​y​ ​d​i​s​c​o​n​n​e​c​t​ ​n​o​t​i​f​i​c​a​t​i​o​n​s
 				 */
 				gateway: string
+				/**
+				 * W​e​e​k​l​y​ ​s​e​c​u​r​i​t​y​ ​s​u​m​m​a​r​y
+				 */
+				securitySummary: string
+				/**
+				 * N​o​t​i​f​i​c​a​t​i​o​n​ ​d​i​g​e​s​t
+				 */
+				digest: string
 			}
 			/**
 			 * H​e​r​e​ ​y​o​u​ ​c​a​n​ ​m​a​n​a​g​e​ ​e​m​a​i​l​ ​n​o​t​i​f​i​c​a​t​i​o​n​s​.
@@ -4191,6 +4383,26 @@ type RootTranslation = {
 						 */
 						help: string
 					}
+					securitySummaryEnabled: {
+						/**
+						 * E​n​a​b​l​e​ ​w​e​e​k​l​y​ ​s​e​c​u​r​i​t​y​ ​s​u​m​m​a​r​y
+						 */
+						label: string
+						/**
+						 * S​e​n​d​ ​a​ ​w​e​e​k​l​y​ ​e​m​a​i​l​ ​t​o​ ​a​d​m​i​n​ ​u​s​e​r​s​ ​s​u​m​m​a​r​i​z​i​n​g​ ​n​e​w​ ​d​e​v​i​c​e​s​,​ ​f​a​i​l​e​d​ ​l​o​g​i​n​ ​s​p​i​k​e​s​,​ ​u​n​s​t​a​b​l​e​ ​g​a​t​e​w​a​y​s​,​ ​l​i​c​e​n​s​e​ ​l​i​m​i​t​s​ ​a​n​d​ ​e​x​p​i​r​i​n​g​ ​c​e​r​t​i​f​i​c​a​t​e​s
+						 */
+						help: string
+					}
+					notificationDigestWindow: {
+						/**
+						 * N​o​t​i​f​i​c​a​t​i​o​n​ ​d​i​g​e​s​t​ ​w​i​n​d​o​w​ ​[​m​i​n​u​t​e​s​]
+						 */
+						label: string
+						/**
+						 * G​a​t​e​w​a​y​ ​a​n​d​ ​p​r​o​x​y​ ​c​o​n​n​e​c​t​i​v​i​t​y​ ​n​o​t​i​f​i​c​a​t​i​o​n​s​ ​s​e​n​t​ ​t​o​ ​a​d​m​i​n​ ​u​s​e​r​s​ ​w​i​t​h​i​n​ ​t​h​i​s​ ​t​i​m​e​ ​a​r​e​ ​b​a​t​c​h​e​d​ ​i​n​t​o​ ​a​ ​s​i​n​g​l​e​ ​s​u​m​m​a​r​y​ ​e​m​a​i​l​.​ ​S​e​t​ ​t​o​ ​0​ ​t​o​ ​s​e​n​d​ ​e​a​c​h​ ​n​o​t​i​f​i​c​a​t​i​o​n​ ​i​m​m​e​d​i​a​t​e​l​y
+						 */
+						help: string
+					}
 				}
 			}
 		}
@@ -4344,6 +4556,10 @@ type RootTranslation = {
 				 * S​c​o​p​e​s​:
 				 */
 				scopes: string
+				/**
+				 * C​l​a​i​m​s​:
+				 */
+				claims: string
 				messages: {
 					/**
 					 * C​l​i​e​n​t​ ​I​D​ ​c​o​p​i​e​d​.
@@ -4378,6 +4594,14 @@ type RootTranslation = {
 						 * M​u​s​t​ ​h​a​v​e​ ​a​t​ ​l​e​a​s​t​ ​o​n​e​ ​s​c​o​p​e​.
 						 */
 						scopeValidation: string
+						/**
+						 * U​s​e​ ​c​l​a​i​m​:​n​a​m​e​ ​p​a​i​r​s​ ​s​e​p​a​r​a​t​e​d​ ​b​y​ ​c​o​m​m​a​s​.
+						 */
+						claimMapping: string
+						/**
+						 * M​u​s​t​ ​b​e​ ​a​ ​J​S​O​N​ ​o​b​j​e​c​t​.
+						 */
+						staticClaims: string
 					}
 					fields: {
 						name: {
@@ -4427,6 +4651,32 @@ type RootTranslation = {
 							 */
 							label: string
 						}
+						groupFilter: {
+							/**
+							 * G​r​o​u​p​s​ ​i​n​c​l​u​d​e​d​ ​i​n​ ​g​r​o​u​p​s​ ​c​l​a​i​m
+							 */
+							label: string
+							/**
+							 * A​l​l​ ​g​r​o​u​p​s
+							 */
+							placeholder: string
+						}
+						claimMapping: {
+							/**
+							 * C​l​a​i​m​ ​n​a​m​e​s
+							 */
+							label: string
+							/**
+							 * p​r​e​f​e​r​r​e​d​_​u​s​e​r​n​a​m​e​:​u​i​d​,​ ​g​r​o​u​p​s​:​r​o​l​e​s
+							 */
+							placeholder: string
+						}
+						staticClaims: {
+							/**
+							 * S​t​a​t​i​c​ ​c​l​a​i​m​s​ ​(​J​S​O​N​ ​o​b​j​e​c​t​)
+							 */
+							label: string
+						}
 					}
 					controls: {
 						/**
@@ -4920,6 +5170,34 @@ type RootTranslation = {
 				 * C​l​i​e​n​t​s​ ​a​u​t​h​o​r​i​z​e​d​ ​w​i​t​h​ ​M​F​A​ ​w​i​l​l​ ​b​e​ ​d​i​s​c​o​n​n​e​c​t​e​d​ ​f​r​o​m​ ​t​h​e​ ​l​o​c​a​t​i​o​n​ ​o​n​c​e​ ​t​h​e​r​e​ ​h​a​s​ ​b​e​e​n​ ​n​o​ ​n​e​t​w​o​r​k​ ​a​c​t​i​v​i​t​y​ ​d​e​t​e​c​t​e​d​ ​b​e​t​w​e​e​n​ ​t​h​e​m​ ​a​n​d​ ​t​h​e​ ​V​P​N​ ​g​a​t​e​w​a​y​ ​f​o​r​ ​a​ ​l​e​n​g​t​h​ ​o​f​ ​t​i​m​e​ ​c​o​n​f​i​g​u​r​e​d​ ​b​e​l​o​w​.
 				 */
 				peerDisconnectThreshold: string
+				/**
+				 * C​l​i​e​n​t​s​ ​a​u​t​h​o​r​i​z​e​d​ ​w​i​t​h​ ​M​F​A​ ​h​a​v​e​ ​t​o​ ​a​u​t​h​e​n​t​i​c​a​t​e​ ​a​g​a​i​n​ ​o​n​c​e​ ​t​h​e​ ​s​e​s​s​i​o​n​ ​l​i​f​e​t​i​m​e​ ​c​o​n​f​i​g​u​r​e​d​ ​b​e​l​o​w​ ​h​a​s​ ​p​a​s​s​e​d​,​ ​e​v​e​n​ ​i​f​ ​t​h​e​y​ ​a​r​e​ ​a​c​t​i​v​e​.​ ​D​e​v​i​c​e​s​ ​c​a​n​ ​a​l​s​o​ ​b​e​ ​r​e​m​e​m​b​e​r​e​d​ ​a​f​t​e​r​ ​M​F​A​,​ ​s​o​ ​t​h​e​y​ ​c​a​n​ ​c​o​n​n​e​c​t​ ​a​g​a​i​n​ ​w​i​t​h​o​u​t​ ​r​e​p​e​a​t​i​n​g​ ​i​t​ ​f​o​r​ ​t​h​e​ ​c​o​n​f​i​g​u​r​e​d​ ​n​u​m​b​e​r​ ​o​f​ ​h​o​u​r​s​.​ ​U​s​e​ ​0​ ​t​o​ ​d​i​s​a​b​l​e​ ​e​i​t​h​e​r​ ​o​p​t​i​o​n​.
+				 */
+				mfaSessionLifetime: string
+				/**
+				 * P​e​e​r​s​ ​o​f​ ​l​o​c​a​t​i​o​n​s​ ​w​i​t​h​o​u​t​ ​M​F​A​ ​c​a​n​ ​a​d​d​i​t​i​o​n​a​l​l​y​ ​u​s​e​ ​p​r​e​s​h​a​r​e​d​ ​k​e​y​s​ ​g​e​n​e​r​a​t​e​d​ ​b​y​ ​D​e​f​g​u​a​r​d​.​ ​K​e​y​s​ ​a​r​e​ ​d​e​l​i​v​e​r​e​d​ ​t​o​ ​c​l​i​e​n​t​s​ ​w​i​t​h​ ​t​h​e​i​r​ ​d​e​v​i​c​e​ ​c​o​n​f​i​g​u​r​a​t​i​o​n​ ​a​n​d​ ​c​a​n​ ​b​e​ ​r​o​t​a​t​e​d​ ​e​v​e​r​y​ ​c​o​n​f​i​g​u​r​e​d​ ​n​u​m​b​e​r​ ​o​f​ ​d​a​y​s​.​ ​U​s​e​ ​0​ ​t​o​ ​d​i​s​a​b​l​e​ ​r​o​t​a​t​i​o​n​.
+				 */
+				presharedKeys: string
+				/**
+				 * C​l​i​e​n​t​s​ ​o​l​d​e​r​ ​t​h​a​n​ ​t​h​e​ ​v​e​r​s​i​o​n​s​ ​c​o​n​f​i​g​u​r​e​d​ ​b​e​l​o​w​ ​w​i​l​l​ ​b​e​ ​a​s​k​e​d​ ​t​o​ ​u​p​d​a​t​e​ ​b​e​f​o​r​e​ ​t​h​e​y​ ​c​a​n​ ​c​o​n​n​e​c​t​ ​t​o​ ​t​h​i​s​ ​l​o​c​a​t​i​o​n​.​ ​L​e​a​v​e​ ​e​m​p​t​y​ ​t​o​ ​a​l​l​o​w​ ​a​l​l​ ​c​l​i​e​n​t​ ​v​e​r​s​i​o​n​s​.
+				 */
+				clientVersions: string
+				/**
+				 * N​e​w​ ​d​e​v​i​c​e​s​ ​g​e​t​ ​t​h​e​ ​l​o​w​e​s​t​ ​f​r​e​e​ ​a​d​d​r​e​s​s​ ​b​y​ ​d​e​f​a​u​l​t​.​ ​R​a​n​d​o​m​ ​a​s​s​i​g​n​m​e​n​t​ ​m​a​k​e​s​ ​a​d​d​r​e​s​s​e​s​ ​h​a​r​d​e​r​ ​t​o​ ​g​u​e​s​s​,​ ​a​n​d​ ​a​s​s​i​g​n​m​e​n​t​ ​b​y​ ​u​s​e​r​ ​k​e​e​p​s​ ​d​e​v​i​c​e​s​ ​o​f​ ​t​h​e​ ​s​a​m​e​ ​u​s​e​r​ ​n​e​x​t​ ​t​o​ ​e​a​c​h​ ​o​t​h​e​r​.
+				 */
+				ipAllocation: string
+				/**
+				 * N​a​m​e​s​ ​o​f​ ​n​e​w​ ​d​e​v​i​c​e​s​ ​c​a​n​ ​b​e​ ​r​e​q​u​i​r​e​d​ ​t​o​ ​m​a​t​c​h​ ​a​ ​r​e​g​u​l​a​r​ ​e​x​p​r​e​s​s​i​o​n​ ​a​n​d​ ​t​o​ ​s​t​a​r​t​ ​w​i​t​h​ ​a​ ​p​r​e​f​i​x​.​ ​T​h​e​ ​p​r​e​f​i​x​ ​c​a​n​ ​c​o​n​t​a​i​n​ ​u​s​e​r​n​a​m​e​,​ ​f​i​r​s​t​_​n​a​m​e​,​ ​l​a​s​t​_​n​a​m​e​ ​a​n​d​ ​o​s​ ​p​l​a​c​e​h​o​l​d​e​r​s​,​ ​e​a​c​h​ ​i​n​ ​c​u​r​l​y​ ​b​r​a​c​e​s​.​ ​L​e​a​v​e​ ​e​m​p​t​y​ ​t​o​ ​a​l​l​o​w​ ​a​n​y​ ​n​a​m​e​.
+				 */
+				deviceNaming: string
+				/**
+				 * O​v​e​r​r​i​d​e​s​ ​t​h​e​ ​c​l​i​e​n​t​ ​t​r​a​f​f​i​c​ ​p​o​l​i​c​y​ ​f​r​o​m​ ​e​n​t​e​r​p​r​i​s​e​ ​s​e​t​t​i​n​g​s​ ​a​n​d​ ​g​r​o​u​p​ ​s​e​t​t​i​n​g​s​ ​f​o​r​ ​t​h​i​s​ ​l​o​c​a​t​i​o​n​.​ ​W​h​e​n​ ​a​l​l​ ​t​r​a​f​f​i​c​ ​i​s​ ​f​o​r​c​e​d​,​ ​d​e​v​i​c​e​s​ ​r​o​u​t​e​ ​a​l​l​ ​t​r​a​f​f​i​c​ ​t​h​r​o​u​g​h​ ​t​h​i​s​ ​l​o​c​a​t​i​o​n​.
+				 */
+				clientTrafficPolicy: string
+				/**
+				 * D​e​v​i​c​e​s​ ​a​d​d​e​d​ ​b​y​ ​u​s​e​r​s​ ​t​h​r​o​u​g​h​ ​e​n​r​o​l​l​m​e​n​t​ ​(​e​.​g​.​ ​i​n​ ​t​h​e​ ​d​e​s​k​t​o​p​ ​c​l​i​e​n​t​)​ ​w​o​n​'​t​ ​b​e​ ​a​b​l​e​ ​t​o​ ​c​o​n​n​e​c​t​ ​t​o​ ​t​h​i​s​ ​l​o​c​a​t​i​o​n​ ​u​n​t​i​l​ ​a​n​ ​a​d​m​i​n​i​s​t​r​a​t​o​r​ ​a​p​p​r​o​v​e​s​ ​t​h​e​m​.​ ​U​n​a​p​p​r​o​v​e​d​ ​r​e​q​u​e​s​t​s​ ​e​x​p​i​r​e​ ​a​u​t​o​m​a​t​i​c​a​l​l​y​.
+				 */
+				deviceApproval: string
 				locationMfaMode: {
 					/**
 					 * C​h​o​o​s​e​ ​h​o​w​ ​M​F​A​ ​i​s​ ​e​n​f​o​r​c​e​d​ ​w​h​e​n​ ​c​o​n​n​e​c​t​i​n​g​ ​t​o​ ​t​h​i​s​ ​l​o​c​a​t​i​o​n​:
@@ -4980,6 +5258,36 @@ type RootTranslation = {
 					 */
 					header: string
 				}
+				clientVersions: {
+					/**
+					 * C​l​i​e​n​t​ ​v​e​r​s​i​o​n​s
+					 */
+					header: string
+				}
+				deviceApproval: {
+					/**
+					 * D​e​v​i​c​e​ ​a​p​p​r​o​v​a​l
+					 */
+					header: string
+				}
+				ipAllocation: {
+					/**
+					 * I​P​ ​a​d​d​r​e​s​s​ ​a​s​s​i​g​n​m​e​n​t
+					 */
+					header: string
+				}
+				deviceNaming: {
+					/**
+					 * D​e​v​i​c​e​ ​n​a​m​i​n​g
+					 */
+					header: string
+				}
+				clientTrafficPolicy: {
+					/**
+					 * C​l​i​e​n​t​ ​t​r​a​f​f​i​c​ ​p​o​l​i​c​y
+					 */
+					header: string
+				}
 			}
 			messages: {
 				/**
@@ -5050,6 +5358,30 @@ type RootTranslation = {
 					 */
 					label: string
 				}
+				mfa_session_lifetime_hours: {
+					/**
+					 * M​F​A​ ​s​e​s​s​i​o​n​ ​l​i​f​e​t​i​m​e​ ​[​h​o​u​r​s​]
+					 */
+					label: string
+				}
+				mfa_remember_device_hours: {
+					/**
+					 * R​e​m​e​m​b​e​r​ ​d​e​v​i​c​e​s​ ​f​o​r​ ​[​h​o​u​r​s​]
+					 */
+					label: string
+				}
+				preshared_keys_enabled: {
+					/**
+					 * U​s​e​ ​p​r​e​s​h​a​r​e​d​ ​k​e​y​s
+					 */
+					label: string
+				}
+				preshared_key_rotation_days: {
+					/**
+					 * P​r​e​s​h​a​r​e​d​ ​k​e​y​ ​r​o​t​a​t​i​o​n​ ​[​d​a​y​s​]
+					 */
+					label: string
+				}
 				acl_enabled: {
 					/**
 					 * E​n​a​b​l​e​ ​A​C​L​ ​f​o​r​ ​t​h​i​s​ ​l​o​c​a​t​i​o​n
@@ -5074,44 +5406,120 @@ type RootTranslation = {
 					 */
 					label: string
 				}
-			}
-			controls: {
-				/**
-				 * S​a​v​e​ ​c​h​a​n​g​e​s
-				 */
-				submit: string
-				/**
-				 * B​a​c​k​ ​t​o​ ​O​v​e​r​v​i​e​w
-				 */
-				cancel: string
-				/**
-				 * R​e​m​o​v​e​ ​l​o​c​a​t​i​o​n
-				 */
-				'delete': string
-			}
-		}
-	}
-	gatewaySetup: {
-		header: {
-			/**
-			 * G​a​t​e​w​a​y​ ​s​e​r​v​e​r​ ​s​e​t​u​p
-			 */
-			main: string
-			/**
-			 * D​o​c​k​e​r​ ​B​a​s​e​d​ ​G​a​t​e​w​a​y​ ​S​e​t​u​p
-			 */
-			dockerBasedGatewaySetup: string
-			/**
-			 * F​r​o​m​ ​P​a​c​k​a​g​e
-			 */
-			fromPackage: string
-			/**
-			 * O​n​e​ ​L​i​n​e​ ​I​n​s​t​a​l​l
-			 */
-			oneLineInstall: string
-		}
-		card: {
-			/**
+				min_desktop_client_version: {
+					/**
+					 * M​i​n​i​m​u​m​ ​d​e​s​k​t​o​p​ ​c​l​i​e​n​t​ ​v​e​r​s​i​o​n
+					 */
+					label: string
+				}
+				min_mobile_client_version: {
+					/**
+					 * M​i​n​i​m​u​m​ ​m​o​b​i​l​e​ ​c​l​i​e​n​t​ ​v​e​r​s​i​o​n
+					 */
+					label: string
+				}
+				device_approval_required: {
+					/**
+					 * R​e​q​u​i​r​e​ ​a​d​m​i​n​ ​a​p​p​r​o​v​a​l​ ​o​f​ ​n​e​w​ ​d​e​v​i​c​e​s
+					 */
+					label: string
+				}
+				ip_allocation_strategy: {
+					/**
+					 * A​d​d​r​e​s​s​ ​a​s​s​i​g​n​m​e​n​t​ ​s​t​r​a​t​e​g​y
+					 */
+					label: string
+					options: {
+						/**
+						 * L​o​w​e​s​t​ ​f​r​e​e​ ​a​d​d​r​e​s​s
+						 */
+						sequential: string
+						/**
+						 * R​a​n​d​o​m​ ​a​d​d​r​e​s​s
+						 */
+						random: string
+						/**
+						 * G​r​o​u​p​e​d​ ​b​y​ ​u​s​e​r
+						 */
+						sticky_by_user: string
+					}
+				}
+				device_name_pattern: {
+					/**
+					 * D​e​v​i​c​e​ ​n​a​m​e​ ​p​a​t​t​e​r​n​ ​(​r​e​g​u​l​a​r​ ​e​x​p​r​e​s​s​i​o​n​)
+					 */
+					label: string
+				}
+				device_name_prefix: {
+					/**
+					 * R​e​q​u​i​r​e​d​ ​d​e​v​i​c​e​ ​n​a​m​e​ ​p​r​e​f​i​x
+					 */
+					label: string
+				}
+				device_name_uniqueness: {
+					/**
+					 * D​e​v​i​c​e​ ​n​a​m​e​s​ ​h​a​v​e​ ​t​o​ ​b​e​ ​u​n​i​q​u​e
+					 */
+					label: string
+					options: {
+						/**
+						 * A​m​o​n​g​ ​d​e​v​i​c​e​s​ ​o​f​ ​t​h​e​ ​s​a​m​e​ ​u​s​e​r
+						 */
+						user: string
+						/**
+						 * A​m​o​n​g​ ​a​l​l​ ​d​e​v​i​c​e​s​ ​i​n​ ​t​h​e​ ​l​o​c​a​t​i​o​n
+						 */
+						location: string
+					}
+				}
+				client_traffic_policy: {
+					/**
+					 * C​l​i​e​n​t​ ​t​r​a​f​f​i​c​ ​p​o​l​i​c​y
+					 */
+					label: string
+					/**
+					 * S​a​m​e​ ​a​s​ ​i​n​ ​e​n​t​e​r​p​r​i​s​e​ ​a​n​d​ ​g​r​o​u​p​ ​s​e​t​t​i​n​g​s
+					 */
+					inherit: string
+				}
+			}
+			controls: {
+				/**
+				 * S​a​v​e​ ​c​h​a​n​g​e​s
+				 */
+				submit: string
+				/**
+				 * B​a​c​k​ ​t​o​ ​O​v​e​r​v​i​e​w
+				 */
+				cancel: string
+				/**
+				 * R​e​m​o​v​e​ ​l​o​c​a​t​i​o​n
+				 */
+				'delete': string
+			}
+		}
+	}
+	gatewaySetup: {
+		header: {
+			/**
+			 * G​a​t​e​w​a​y​ ​s​e​r​v​e​r​ ​s​e​t​u​p
+			 */
+			main: string
+			/**
+			 * D​o​c​k​e​r​ ​B​a​s​e​d​ ​G​a​t​e​w​a​y​ ​S​e​t​u​p
+			 */
+			dockerBasedGatewaySetup: string
+			/**
+			 * F​r​o​m​ ​P​a​c​k​a​g​e
+			 */
+			fromPackage: string
+			/**
+			 * O​n​e​ ​L​i​n​e​ ​I​n​s​t​a​l​l
+			 */
+			oneLineInstall: string
+		}
+		card: {
+			/**
 			 * D​o​c​k​e​r​ ​b​a​s​e​d​ ​g​a​t​e​w​a​y​ ​s​e​t​u​p
 			 */
 			title: string
@@ -5568,6 +5976,32 @@ type RootTranslation = {
 					duplicateWelcome: string
 				}
 			}
+			selfRegistration: {
+				/**
+				 * S​e​l​f​-​r​e​g​i​s​t​r​a​t​i​o​n
+				 */
+				title: string
+				/**
+				 * A​l​l​o​w​ ​u​s​e​r​s​ ​w​i​t​h​ ​e​m​a​i​l​s​ ​u​n​d​e​r​ ​l​i​s​t​e​d​ ​d​o​m​a​i​n​s​ ​t​o​ ​r​e​q​u​e​s​t​ ​a​n​ ​a​c​c​o​u​n​t​.​ ​A​f​t​e​r​ ​v​e​r​i​f​y​i​n​g​ ​t​h​e​i​r​ ​e​m​a​i​l​,​ ​a​ ​d​i​s​a​b​l​e​d​ ​a​c​c​o​u​n​t​ ​i​s​ ​c​r​e​a​t​e​d​ ​a​n​d​ ​a​d​m​i​n​i​s​t​r​a​t​o​r​s​ ​a​r​e​ ​n​o​t​i​f​i​e​d​ ​t​o​ ​a​c​t​i​v​a​t​e​ ​i​t​.​ ​R​e​q​u​i​r​e​s​ ​S​M​T​P​ ​c​o​n​f​i​g​u​r​a​t​i​o​n​.
+				 */
+				messageBox: string
+				controls: {
+					/**
+					 * E​n​a​b​l​e​ ​s​e​l​f​-​r​e​g​i​s​t​r​a​t​i​o​n
+					 */
+					enabled: string
+				}
+				domains: {
+					/**
+					 * A​l​l​o​w​e​d​ ​e​m​a​i​l​ ​d​o​m​a​i​n​s
+					 */
+					label: string
+					/**
+					 * e​x​a​m​p​l​e​.​c​o​m​,​ ​e​x​a​m​p​l​e​.​o​r​g
+					 */
+					placeholder: string
+				}
+			}
 		}
 	}
 	supportPage: {
@@ -6523,6 +6957,10 @@ type RootTranslation = {
 			 * U​s​e​r​ ​a​d​d​e​d
 			 */
 			user_added: string
+			/**
+			 * U​s​e​r​ ​s​e​l​f​-​r​e​g​i​s​t​e​r​e​d
+			 */
+			user_self_registered: string
 			/**
 			 * U​s​e​r​ ​r​e​m​o​v​e​d
 			 */
@@ -6583,6 +7021,26 @@ type RootTranslation = {
 			 * D​e​v​i​c​e​ ​m​o​d​i​f​i​e​d
 			 */
 			device_modified: string
+			/**
+			 * D​e​v​i​c​e​ ​d​i​s​c​o​n​n​e​c​t​e​d
+			 */
+			device_disconnected: string
+			/**
+			 * D​e​v​i​c​e​ ​q​u​a​r​a​n​t​i​n​e​d
+			 */
+			device_quarantined: string
+			/**
+			 * D​e​v​i​c​e​ ​r​e​l​e​a​s​e​d​ ​f​r​o​m​ ​q​u​a​r​a​n​t​i​n​e
+			 */
+			device_released_from_quarantine: string
+			/**
+			 * D​e​v​i​c​e​ ​a​p​p​r​o​v​e​d
+			 */
+			device_approved: string
+			/**
+			 * D​e​v​i​c​e​ ​r​e​j​e​c​t​e​d
+			 */
+			device_rejected: string
 			/**
 			 * N​e​t​w​o​r​k​ ​d​e​v​i​c​e​ ​a​d​d​e​d
 			 */
@@ -6631,6 +7089,18 @@ type RootTranslation = {
 			 * E​n​r​o​l​l​m​e​n​t​ ​t​o​k​e​n​ ​a​d​d​e​d
 			 */
 			enrollment_token_added: string
+			/**
+			 * E​n​r​o​l​l​m​e​n​t​ ​t​o​k​e​n​ ​e​m​a​i​l​ ​r​e​s​e​n​t
+			 */
+			enrollment_token_resent: string
+			/**
+			 * E​n​r​o​l​l​m​e​n​t​ ​t​o​k​e​n​ ​e​x​t​e​n​d​e​d
+			 */
+			enrollment_token_extended: string
+			/**
+			 * E​n​r​o​l​l​m​e​n​t​ ​t​o​k​e​n​ ​r​e​v​o​k​e​d
+			 */
+			enrollment_token_revoked: string
 			/**
 			 * E​n​r​o​l​l​m​e​n​t​ ​s​t​a​r​t​e​d
 			 */
@@ -6667,6 +7137,10 @@ type RootTranslation = {
 			 * V​P​N​ ​l​o​c​a​t​i​o​n​ ​m​o​d​i​f​i​e​d
 			 */
 			vpn_location_modified: string
+			/**
+			 * V​P​N​ ​l​o​c​a​t​i​o​n​ ​f​i​r​e​w​a​l​l​ ​r​o​l​l​e​d​ ​b​a​c​k
+			 */
+			vpn_location_firewall_rolled_back: string
 			/**
 			 * A​P​I​ ​t​o​k​e​n​ ​a​d​d​e​d
 			 */
@@ -6759,6 +7233,78 @@ type RootTranslation = {
 			 * W​e​b​h​o​o​k​ ​s​t​a​t​e​ ​c​h​a​n​g​e​d
 			 */
 			web_hook_state_changed: string
+			/**
+			 * A​n​n​o​u​n​c​e​m​e​n​t​ ​c​r​e​a​t​e​d
+			 */
+			announcement_created: string
+			/**
+			 * A​n​n​o​u​n​c​e​m​e​n​t​ ​r​e​m​o​v​e​d
+			 */
+			announcement_removed: string
+			/**
+			 * S​e​r​v​i​c​e​ ​a​c​c​o​u​n​t​ ​a​d​d​e​d
+			 */
+			service_account_added: string
+			/**
+			 * S​e​r​v​i​c​e​ ​a​c​c​o​u​n​t​ ​m​o​d​i​f​i​e​d
+			 */
+			service_account_modified: string
+			/**
+			 * S​e​r​v​i​c​e​ ​a​c​c​o​u​n​t​ ​r​e​m​o​v​e​d
+			 */
+			service_account_removed: string
+			/**
+			 * I​T​S​M​ ​c​o​n​n​e​c​t​o​r​ ​a​d​d​e​d
+			 */
+			itsm_connector_added: string
+			/**
+			 * I​T​S​M​ ​c​o​n​n​e​c​t​o​r​ ​m​o​d​i​f​i​e​d
+			 */
+			itsm_connector_modified: string
+			/**
+			 * I​T​S​M​ ​c​o​n​n​e​c​t​o​r​ ​r​e​m​o​v​e​d
+			 */
+			itsm_connector_removed: string
+			/**
+			 * M​a​i​l​ ​v​a​r​i​a​b​l​e​ ​a​d​d​e​d
+			 */
+			mail_variable_added: string
+			/**
+			 * M​a​i​l​ ​v​a​r​i​a​b​l​e​ ​m​o​d​i​f​i​e​d
+			 */
+			mail_variable_modified: string
+			/**
+			 * M​a​i​l​ ​v​a​r​i​a​b​l​e​ ​r​e​m​o​v​e​d
+			 */
+			mail_variable_removed: string
+			/**
+			 * M​a​i​l​ ​t​r​a​n​s​l​a​t​i​o​n​ ​m​o​d​i​f​i​e​d
+			 */
+			mail_context_modified: string
+			/**
+			 * M​a​i​l​ ​t​r​a​n​s​l​a​t​i​o​n​ ​r​e​m​o​v​e​d
+			 */
+			mail_context_removed: string
+			/**
+			 * M​a​i​l​ ​t​e​m​p​l​a​t​e​ ​o​v​e​r​r​i​d​d​e​n
+			 */
+			mail_template_modified: string
+			/**
+			 * M​a​i​l​ ​t​e​m​p​l​a​t​e​ ​r​e​s​t​o​r​e​d
+			 */
+			mail_template_restored: string
+			/**
+			 * R​o​u​t​e​ ​a​d​d​e​d
+			 */
+			route_added: string
+			/**
+			 * R​o​u​t​e​ ​m​o​d​i​f​i​e​d
+			 */
+			route_modified: string
+			/**
+			 * R​o​u​t​e​ ​r​e​m​o​v​e​d
+			 */
+			route_removed: string
 			/**
 			 * A​u​t​h​e​n​t​i​c​a​t​i​o​n​ ​k​e​y​ ​a​d​d​e​d
 			 */
@@ -6783,6 +7329,14 @@ type RootTranslation = {
 			 * P​a​s​s​w​o​r​d​ ​r​e​s​e​t
 			 */
 			password_reset: string
+			/**
+			 * H​e​l​p​d​e​s​k​ ​p​a​s​s​w​o​r​d​ ​r​e​s​e​t​ ​r​e​q​u​e​s​t​e​d
+			 */
+			helpdesk_password_reset_requested: string
+			/**
+			 * H​e​l​p​d​e​s​k​ ​p​a​s​s​w​o​r​d​ ​r​e​s​e​t​ ​c​o​n​f​i​r​m​e​d
+			 */
+			helpdesk_password_reset_confirmed: string
 			/**
 			 * C​l​i​e​n​t​ ​c​o​n​f​i​g​u​r​a​t​i​o​n​ ​t​o​k​e​n​ ​a​d​d​e​d
 			 */
@@ -7394,6 +7948,22 @@ export type TranslationFunctions = {
 			 * Admin group
 			 */
 			adminGroup: () => LocalizedString
+			/**
+			 * Helpdesk group (can reset passwords of non-admin users)
+			 */
+			helpdeskGroup: () => LocalizedString
+			/**
+			 * Auditor group (read-only access with personal data redacted)
+			 */
+			auditorGroup: () => LocalizedString
+			/**
+			 * Client traffic policy
+			 */
+			clientTrafficPolicy: () => LocalizedString
+			/**
+			 * Same as in enterprise settings
+			 */
+			inheritTrafficPolicy: () => LocalizedString
 		}
 		editGroup: {
 			/**
@@ -7424,6 +7994,22 @@ export type TranslationFunctions = {
 			 * Admin group
 			 */
 			adminGroup: () => LocalizedString
+			/**
+			 * Helpdesk group (can reset passwords of non-admin users)
+			 */
+			helpdeskGroup: () => LocalizedString
+			/**
+			 * Auditor group (read-only access with personal data redacted)
+			 */
+			auditorGroup: () => LocalizedString
+			/**
+			 * Client traffic policy
+			 */
+			clientTrafficPolicy: () => LocalizedString
+			/**
+			 * Same as in enterprise settings
+			 */
+			inheritTrafficPolicy: () => LocalizedString
 		}
 		deleteGroup: {
 			/**
@@ -9183,6 +9769,10 @@ export type TranslationFunctions = {
 				 * Start enrollment
 				 */
 				startEnrollment: () => LocalizedString
+				/**
+				 * Download enrollment sheet
+				 */
+				enrollmentSheet: () => LocalizedString
 				/**
 				 * Configure Desktop Client
 				 */
@@ -10597,10 +11187,22 @@ export type TranslationFunctions = {
 				 */
 				title: () => LocalizedString
 				sections: {
+					/**
+					 * Delivery
+					 */
+					delivery: () => LocalizedString
 					/**
 					 * Server settings
 					 */
 					server: () => LocalizedString
+					/**
+					 * Authentication
+					 */
+					authentication: () => LocalizedString
+					/**
+					 * Senders
+					 */
+					senders: () => LocalizedString
 				}
 				fields: {
 					encryption: {
@@ -10609,6 +11211,46 @@ export type TranslationFunctions = {
 						 */
 						label: () => LocalizedString
 					}
+					authMethod: {
+						/**
+						 * Authentication method
+						 */
+						label: () => LocalizedString
+					}
+					mailBackend: {
+						/**
+						 * Mail backend
+						 */
+						label: () => LocalizedString
+						/**
+						 * Mails can be delivered through an SMTP server or POSTed as JSON to an HTTP API of a mail delivery service. Mail categories bound to SMTP profiles are always sent through their profiles.
+						 */
+						helper: () => LocalizedString
+					}
+					httpUrl: {
+						/**
+						 * HTTP API URL
+						 */
+						label: () => LocalizedString
+						/**
+						 * https://api.example.com/mail/send
+						 */
+						placeholder: () => LocalizedString
+					}
+					httpToken: {
+						/**
+						 * HTTP API token
+						 */
+						label: () => LocalizedString
+						/**
+						 * Token
+						 */
+						placeholder: () => LocalizedString
+						/**
+						 * Sent as a bearer token in the Authorization header.
+						 */
+						helper: () => LocalizedString
+					}
 					server: {
 						/**
 						 * Server address
@@ -10649,6 +11291,102 @@ export type TranslationFunctions = {
 						 */
 						placeholder: () => LocalizedString
 					}
+					oauth2TokenUrl: {
+						/**
+						 * OAuth2 token URL
+						 */
+						label: () => LocalizedString
+						/**
+						 * https://login.microsoftonline.com/<tenant>/oauth2/v2.0/token
+						 */
+						placeholder: () => LocalizedString
+					}
+					oauth2ClientId: {
+						/**
+						 * OAuth2 client ID
+						 */
+						label: () => LocalizedString
+						/**
+						 * Client ID
+						 */
+						placeholder: () => LocalizedString
+					}
+					oauth2ClientSecret: {
+						/**
+						 * OAuth2 client secret
+						 */
+						label: () => LocalizedString
+						/**
+						 * Client secret
+						 */
+						placeholder: () => LocalizedString
+					}
+					oauth2Scope: {
+						/**
+						 * OAuth2 scope
+						 */
+						label: () => LocalizedString
+						/**
+						 * https://outlook.office365.com/.default
+						 */
+						placeholder: () => LocalizedString
+					}
+					senderName: {
+						/**
+						 * Sender display name
+						 */
+						label: () => LocalizedString
+						/**
+						 * defguard
+						 */
+						placeholder: () => LocalizedString
+						/**
+						 * Name displayed next to the sender address.
+						 */
+						helper: () => LocalizedString
+					}
+					replyTo: {
+						/**
+						 * Reply-to address
+						 */
+						label: () => LocalizedString
+						/**
+						 * Address
+						 */
+						placeholder: () => LocalizedString
+						/**
+						 * Address replies are sent to. Leave empty to reply to the sender.
+						 */
+						helper: () => LocalizedString
+					}
+					securitySender: {
+						/**
+						 * Security emails sender
+						 */
+						label: () => LocalizedString
+						/**
+						 * Address
+						 */
+						placeholder: () => LocalizedString
+						/**
+						 * Sender address of MFA codes, password resets and new login notifications. Leave empty to use the default sender.
+						 */
+						helper: () => LocalizedString
+					}
+					announcementSender: {
+						/**
+						 * Announcements sender
+						 */
+						label: () => LocalizedString
+						/**
+						 * Address
+						 */
+						placeholder: () => LocalizedString
+						/**
+						 * Sender address of announcements. Leave empty to use the default sender.
+						 */
+						helper: () => LocalizedString
+					}
 					sender: {
 						/**
 						 * Sender email address
@@ -10917,6 +11655,14 @@ export type TranslationFunctions = {
 				 * Gateway disconnect notifications
 				 */
 				gateway: () => LocalizedString
+				/**
+				 * Weekly security summary
+				 */
+				securitySummary: () => LocalizedString
+				/**
+				 * Notification digest
+				 */
+				digest: () => LocalizedString
 			}
 			/**
 			 * Here you can manage email notifications.
@@ -10958,6 +11704,26 @@ export type TranslationFunctions = {
 						 */
 						help: () => LocalizedString
 					}
+					securitySummaryEnabled: {
+						/**
+						 * Enable weekly security summary
+						 */
+						label: () => LocalizedString
+						/**
+						 * Send a weekly email to admin users summarizing new devices, failed login spikes, unstable gateways, license limits and expiring certificates
+						 */
+						help: () => LocalizedString
+					}
+					notificationDigestWindow: {
+						/**
+						 * Notification digest window [minutes]
+						 */
+						label: () => LocalizedString
+						/**
+						 * Gateway and proxy connectivity notifications sent to admin users within this time are batched into a single summary email. Set to 0 to send each notification immediately
+						 */
+						help: () => LocalizedString
+					}
 				}
 			}
 		}
@@ -11109,6 +11875,10 @@ export type TranslationFunctions = {
 				 * Scopes:
 				 */
 				scopes: () => LocalizedString
+				/**
+				 * Claims:
+				 */
+				claims: () => LocalizedString
 				messages: {
 					/**
 					 * Client ID copied.
@@ -11143,6 +11913,14 @@ export type TranslationFunctions = {
 						 * Must have at least one scope.
 						 */
 						scopeValidation: () => LocalizedString
+						/**
+						 * Use claim:name pairs separated by commas.
+						 */
+						claimMapping: () => LocalizedString
+						/**
+						 * Must be a JSON object.
+						 */
+						staticClaims: () => LocalizedString
 					}
 					fields: {
 						name: {
@@ -11191,6 +11969,32 @@ export type TranslationFunctions = {
 							 */
 							label: () => LocalizedString
 						}
+						groupFilter: {
+							/**
+							 * Groups included in groups claim
+							 */
+							label: () => LocalizedString
+							/**
+							 * All groups
+							 */
+							placeholder: () => LocalizedString
+						}
+						claimMapping: {
+							/**
+							 * Claim names
+							 */
+							label: () => LocalizedString
+							/**
+							 * preferred_username:uid, groups:roles
+							 */
+							placeholder: () => LocalizedString
+						}
+						staticClaims: {
+							/**
+							 * Static claims (JSON object)
+							 */
+							label: () => LocalizedString
+						}
 					}
 					controls: {
 						/**
@@ -11675,6 +12479,34 @@ export type TranslationFunctions = {
 				 * Clients authorized with MFA will be disconnected from the location once there has been no network activity detected between them and the VPN gateway for a length of time configured below.
 				 */
 				peerDisconnectThreshold: () => LocalizedString
+				/**
+				 * Clients authorized with MFA have to authenticate again once the session lifetime configured below has passed, even if they are active. Devices can also be remembered after MFA, so they can connect again without repeating it for the configured number of hours. Use 0 to disable either option.
+				 */
+				mfaSessionLifetime: () => LocalizedString
+				/**
+				 * Peers of locations without MFA can additionally use preshared keys generated by Defguard. Keys are delivered to clients with their device configuration and can be rotated every configured number of days. Use 0 to disable rotation.
+				 */
+				presharedKeys: () => LocalizedString
+				/**
+				 * Clients older than the versions configured below will be asked to update before they can connect to this location. Leave empty to allow all client versions.
+				 */
+				clientVersions: () => LocalizedString
+				/**
+				 * New devices get the lowest free address by default. Random assignment makes addresses harder to guess, and assignment by user keeps devices of the same user next to each other.
+				 */
+				ipAllocation: () => LocalizedString
+				/**
+				 * Names of new devices can be required to match a regular expression and to start with a prefix. The prefix can contain username, first_name, last_name and os placeholders, each in curly braces. Leave empty to allow any name.
+				 */
+				deviceNaming: () => LocalizedString
+				/**
+				 * Overrides the client traffic policy from enterprise settings and group settings for this location. When all traffic is forced, devices route all traffic through this location.
+				 */
+				clientTrafficPolicy: () => LocalizedString
+				/**
+				 * Devices added by users through enrollment (e.g. in the desktop client) won't be able to connect to this location until an administrator approves them. Unapproved requests expire automatically.
+				 */
+				deviceApproval: () => LocalizedString
 				locationMfaMode: {
 					/**
 					 * Choose how MFA is enforced when connecting to this location:
@@ -11735,6 +12567,36 @@ export type TranslationFunctions = {
 					 */
 					header: () => LocalizedString
 				}
+				clientVersions: {
+					/**
+					 * Client versions
+					 */
+					header: () => LocalizedString
+				}
+				deviceApproval: {
+					/**
+					 * Device approval
+					 */
+					header: () => LocalizedString
+				}
+				ipAllocation: {
+					/**
+					 * IP address assignment
+					 */
+					header: () => LocalizedString
+				}
+				deviceNaming: {
+					/**
+					 * Device naming
+					 */
+					header: () => LocalizedString
+				}
+				clientTrafficPolicy: {
+					/**
+					 * Client traffic policy
+					 */
+					header: () => LocalizedString
+				}
 			}
 			messages: {
 				/**
@@ -11805,6 +12667,30 @@ export type TranslationFunctions = {
 					 */
 					label: () => LocalizedString
 				}
+				mfa_session_lifetime_hours: {
+					/**
+					 * MFA session lifetime [hours]
+					 */
+					label: () => LocalizedString
+				}
+				mfa_remember_device_hours: {
+					/**
+					 * Remember devices for [hours]
+					 */
+					label: () => LocalizedString
+				}
+				preshared_keys_enabled: {
+					/**
+					 * Use preshared keys
+					 */
+					label: () => LocalizedString
+				}
+				preshared_key_rotation_days: {
+					/**
+					 * Preshared key rotation [days]
+					 */
+					label: () => LocalizedString
+				}
 				acl_enabled: {
 					/**
 					 * Enable ACL for this location
@@ -11829,6 +12715,82 @@ export type TranslationFunctions = {
 					 */
 					label: () => LocalizedString
 				}
+				min_desktop_client_version: {
+					/**
+					 * Minimum desktop client version
+					 */
+					label: () => LocalizedString
+				}
+				min_mobile_client_version: {
+					/**
+					 * Minimum mobile client version
+					 */
+					label: () => LocalizedString
+				}
+				device_approval_required: {
+					/**
+					 * Require admin approval of new devices
+					 */
+					label: () => LocalizedString
+				}
+				ip_allocation_strategy: {
+					/**
+					 * Address assignment strategy
+					 */
+					label: () => LocalizedString
+					options: {
+						/**
+						 * Lowest free address
+						 */
+						sequential: () => LocalizedString
+						/**
+						 * Random address
+						 */
+						random: () => LocalizedString
+						/**
+						 * Grouped by user
+						 */
+						sticky_by_user: () => LocalizedString
+					}
+				}
+				device_name_pattern: {
+					/**
+					 * Device name pattern (regular expression)
+					 */
+					label: () => LocalizedString
+				}
+				device_name_prefix: {
+					/**
+					 * Required device name prefix
+					 */
+					label: () => LocalizedString
+				}
+				device_name_uniqueness: {
+					/**
+					 * Device names have to be unique
+					 */
+					label: () => LocalizedString
+					options: {
+						/**
+						 * Among devices of the same user
+						 */
+						user: () => LocalizedString
+						/**
+						 * Among all devices in the location
+						 */
+						location: () => LocalizedString
+					}
+				}
+				client_traffic_policy: {
+					/**
+					 * Client traffic policy
+					 */
+					label: () => LocalizedString
+					/**
+					 * Same as in enterprise and group settings
+					 */
+					inherit: () => LocalizedString
+				}
 			}
 			controls: {
 				/**
@@ -12319,6 +13281,32 @@ export type TranslationFunctions = {
 					duplicateWelcome: () => LocalizedString
 				}
 			}
+			selfRegistration: {
+				/**
+				 * Self-registration
+				 */
+				title: () => LocalizedString
+				/**
+				 * Allow users with emails under listed domains to request an account. After verifying their email, a disabled account is created and administrators are notified to activate it. Requires SMTP configuration.
+				 */
+				messageBox: () => LocalizedString
+				controls: {
+					/**
+					 * Enable self-registration
+					 */
+					enabled: () => LocalizedString
+				}
+				domains: {
+					/**
+					 * Allowed email domains
+					 */
+					label: () => LocalizedString
+					/**
+					 * example.com, example.org
+					 */
+					placeholder: () => LocalizedString
+				}
+			}
 		}
 	}
 	supportPage: {
@@ -13266,6 +14254,10 @@ export type TranslationFunctions = {
 			 * User added
 			 */
 			user_added: () => LocalizedString
+			/**
+			 * User self-registered
+			 */
+			user_self_registered: () => LocalizedString
 			/**
 			 * User removed
 			 */
@@ -13326,6 +14318,26 @@ export type TranslationFunctions = {
 			 * Device modified
 			 */
 			device_modified: () => LocalizedString
+			/**
+			 * Device disconnected
+			 */
+			device_disconnected: () => LocalizedString
+			/**
+			 * Device quarantined
+			 */
+			device_quarantined: () => LocalizedString
+			/**
+			 * Device released from quarantine
+			 */
+			device_released_from_quarantine: () => LocalizedString
+			/**
+			 * Device approved
+			 */
+			device_approved: () => LocalizedString
+			/**
+			 * Device rejected
+			 */
+			device_rejected: () => LocalizedString
 			/**
 			 * Network device added
 			 */
@@ -13374,6 +14386,18 @@ export type TranslationFunctions = {
 			 * Enrollment token added
 			 */
 			enrollment_token_added: () => LocalizedString
+			/**
+			 * Enrollment token email resent
+			 */
+			enrollment_token_resent: () => LocalizedString
+			/**
+			 * Enrollment token extended
+			 */
+			enrollment_token_extended: () => LocalizedString
+			/**
+			 * Enrollment token revoked
+			 */
+			enrollment_token_revoked: () => LocalizedString
 			/**
 			 * Enrollment started
 			 */
@@ -13410,6 +14434,10 @@ export type TranslationFunctions = {
 			 * VPN location modified
 			 */
 			vpn_location_modified: () => LocalizedString
+			/**
+			 * VPN location firewall rolled back
+			 */
+			vpn_location_firewall_rolled_back: () => LocalizedString
 			/**
 			 * API token added
 			 */
@@ -13502,6 +14530,78 @@ export type TranslationFunctions = {
 			 * Webhook state changed
 			 */
 			web_hook_state_changed: () => LocalizedString
+			/**
+			 * Announcement created
+			 */
+			announcement_created: () => LocalizedString
+			/**
+			 * Announcement removed
+			 */
+			announcement_removed: () => LocalizedString
+			/**
+			 * Service account added
+			 */
+			service_account_added: () => LocalizedString
+			/**
+			 * Service account modified
+			 */
+			service_account_modified: () => LocalizedString
+			/**
+			 * Service account removed
+			 */
+			service_account_removed: () => LocalizedString
+			/**
+			 * ITSM connector added
+			 */
+			itsm_connector_added: () => LocalizedString
+			/**
+			 * ITSM connector modified
+			 */
+			itsm_connector_modified: () => LocalizedString
+			/**
+			 * ITSM connector removed
+			 */
+			itsm_connector_removed: () => LocalizedString
+			/**
+			 * Mail variable added
+			 */
+			mail_variable_added: () => LocalizedString
+			/**
+			 * Mail variable modified
+			 */
+			mail_variable_modified: () => LocalizedString
+			/**
+			 * Mail variable removed
+			 */
+			mail_variable_removed: () => LocalizedString
+			/**
+			 * Mail translation modified
+			 */
+			mail_context_modified: () => LocalizedString
+			/**
+			 * Mail translation removed
+			 */
+			mail_context_removed: () => LocalizedString
+			/**
+			 * Mail template overridden
+			 */
+			mail_template_modified: () => LocalizedString
+			/**
+			 * Mail template restored
+			 */
+			mail_template_restored: () => LocalizedString
+			/**
+			 * Route added
+			 */
+			route_added: () => LocalizedString
+			/**
+			 * Route modified
+			 */
+			route_modified: () => LocalizedString
+			/**
+			 * Route removed
+			 */
+			route_removed: () => LocalizedString
 			/**
 			 * Authentication key added
 			 */
@@ -13526,6 +14626,14 @@ export type TranslationFunctions = {
 			 * Password reset
 			 */
 			password_reset: () => LocalizedString
+			/**
+			 * Helpdesk password reset requested
+			 */
+			helpdesk_password_reset_requested: () => LocalizedString
+			/**
+			 * Helpdesk password reset confirmed
+			 */
+			helpdesk_password_reset_confirmed: () => LocalizedString
 			/**
 			 * Client configuration token added
 			 */
@@ -15,6 +15,7 @@ export type ActivityLogEventType =
   | 'recovery_code_used'
   | 'user_logout'
   | 'user_added'
+  | 'user_self_registered'
   | 'user_modified'
   | 'user_removed'
   | 'user_groups_modified'
@@ -29,6 +30,11 @@ export type ActivityLogEventType =
   | 'device_added'
   | 'device_modified'
   | 'device_removed'
+  | 'device_disconnected'
+  | 'device_quarantined'
+  | 'device_released_from_quarantine'
+  | 'device_approved'
+  | 'device_rejected'
   | 'network_device_added'
   | 'network_device_modified'
   | 'network_device_removed'
@@ -41,6 +47,9 @@ export type ActivityLogEventType =
   | 'vpn_client_disconnected_mfa'
   | 'vpn_client_mfa_failed'
   | 'enrollment_token_added'
+  | 'enrollment_token_resent'
+  | 'enrollment_token_extended'
+  | 'enrollment_token_revoked'
   | 'enrollment_started'
   | 'enrollment_device_added'
   | 'enrollment_completed'
@@ -50,6 +59,7 @@ export type ActivityLogEventType =
   | 'vpn_location_added'
   | 'vpn_location_removed'
   | 'vpn_location_modified'
+  | 'vpn_location_firewall_rolled_back'
   | 'api_token_added'
   | 'api_token_removed'
   | 'api_token_renamed'
@@ -73,12 +83,32 @@ export type ActivityLogEventType =
   | 'web_hook_modified'
   | 'web_hook_removed'
   | 'web_hook_state_changed'
+  | 'announcement_created'
+  | 'announcement_removed'
+  | 'service_account_added'
+  | 'service_account_modified'
+  | 'service_account_removed'
+  | 'itsm_connector_added'
+  | 'itsm_connector_modified'
+  | 'itsm_connector_removed'
+  | 'mail_variable_added'
+  | 'mail_variable_modified'
+  | 'mail_variable_removed'
+  | 'mail_context_modified'
+  | 'mail_context_removed'
+  | 'mail_template_modified'
+  | 'mail_template_restored'
+  | 'route_added'
+  | 'route_modified'
+  | 'route_removed'
   | 'authentication_key_added'
   | 'authentication_key_removed'
   | 'authentication_key_renamed'
   | 'password_changed'
   | 'password_changed_by_admin'
   | 'password_reset'
+  | 'helpdesk_password_reset_requested'
+  | 'helpdesk_password_reset_confirmed'
   | 'client_configuration_token_added'
   | 'user_snat_binding_added'
   | 'user_snat_binding_modified'
@@ -93,6 +123,7 @@ export const activityLogEventTypeValues: ActivityLogEventType[] = [
   'recovery_code_used',
   'user_logout',
   'user_added',
+  'user_self_registered',
   'user_modified',
   'user_removed',
   'mfa_disabled',
@@ -106,6 +137,11 @@ export const activityLogEventTypeValues: ActivityLogEventType[] = [
   'device_added',
   'device_modified',
   'device_removed',
+  'device_disconnected',
+  'device_quarantined',
+  'device_released_from_quarantine',
+  'device_approved',
+  'device_rejected',
   'network_device_added',
   'network_device_modified',
   'network_device_removed',
@@ -118,6 +154,9 @@ export const activityLogEventTypeValues: ActivityLogEventType[] = [
   'vpn_client_disconnected_mfa',
   'vpn_client_mfa_failed',
   'enrollment_token_added',
+  'enrollment_token_resent',
+  'enrollment_token_extended',
+  'enrollment_token_revoked',
   'enrollment_started',
   'enrollment_device_added',
   'enrollment_completed',
@@ -127,6 +166,7 @@ export const activityLogEventTypeValues: ActivityLogEventType[] = [
   'vpn_location_added',
   'vpn_location_removed',
   'vpn_location_modified',
+  'vpn_location_firewall_rolled_back',
   'api_token_added',
   'api_token_removed',
   'api_token_renamed',
@@ -150,12 +190,32 @@ export const activityLogEventTypeValues: ActivityLogEventType[] = [
   'web_hook_modified',
   'web_hook_removed',
   'web_hook_state_changed',
+  'announcement_created',
+  'announcement_removed',
+  'service_account_added',
+  'service_account_modified',
+  'service_account_removed',
+  'itsm_connector_added',
+  'itsm_connector_modified',
+  'itsm_connector_removed',
+  'mail_variable_added',
+  'mail_variable_modified',
+  'mail_variable_removed',
+  'mail_context_modified',
+  'mail_context_removed',
+  'mail_template_modified',
+  'mail_template_restored',
+  'route_added',
+  'route_modified',
+  'route_removed',
   'authentication_key_added',
   'authentication_key_removed',
   'authentication_key_renamed',
   'password_changed',
   'password_changed_by_admin',
   'password_reset',
+  'helpdesk_password_reset_requested',
+  'helpdesk_password_reset_confirmed',
   'client_configuration_token_added',
   'user_snat_binding_added',
   'user_snat_binding_modified',
@@ -10,6 +10,7 @@ import { MessageBoxType } from '../../shared/defguard-ui/components/Layout/Messa
 import useApi from '../../shared/hooks/useApi';
 import { QueryKeys } from '../../shared/queries';
 import { EnrollmentEmail } from './components/EnrollmentEmail/EnrollmentEmail';
+import { EnrollmentSelfRegistration } from './components/EnrollmentSelfRegistration/EnrollmentSelfRegistration';
 import { EnrollmentVPN } from './components/EnrollmentVPN/EnrollmentVPN';
 import { EnrollmentWelcomeMessage } from './components/EnrollmentWelcomeMessage/EnrollmentWelcomeMessage';
 import { useEnrollmentStore } from './hooks/useEnrollmentStore';
@@ -48,6 +49,7 @@ export const EnrollmentPage = () => {
         <div className="settings">
           <div className="left">
             <EnrollmentVPN />
+            <EnrollmentSelfRegistration />
             <EnrollmentWelcomeMessage />
           </div>
           <div className="right">
@@ -0,0 +1,110 @@
+import './style.scss';
+
+import { useMutation, useQueryClient } from '@tanstack/react-query';
+import { isUndefined } from 'lodash-es';
+import { useEffect, useState } from 'react';
+
+import { useI18nContext } from '../../../../i18n/i18n-react';
+import SvgIconCheckmark from '../../../../shared/components/svg/IconCheckmark';
+import { Button } from '../../../../shared/defguard-ui/components/Layout/Button/Button';
+import {
+  ButtonSize,
+  ButtonStyleVariant,
+} from '../../../../shared/defguard-ui/components/Layout/Button/types';
+import { Card } from '../../../../shared/defguard-ui/components/Layout/Card/Card';
+import { CheckBox } from '../../../../shared/defguard-ui/components/Layout/Checkbox/CheckBox';
+import { Input } from '../../../../shared/defguard-ui/components/Layout/Input/Input';
+import { MessageBox } from '../../../../shared/defguard-ui/components/Layout/MessageBox/MessageBox';
+import { MessageBoxType } from '../../../../shared/defguard-ui/components/Layout/MessageBox/types';
+import useApi from '../../../../shared/hooks/useApi';
+import { useToaster } from '../../../../shared/hooks/useToaster';
+import { QueryKeys } from '../../../../shared/queries';
+import { useEnrollmentStore } from '../../hooks/useEnrollmentStore';
+
+export const EnrollmentSelfRegistration = () => {
+  const {
+    settings: { editSettings },
+  } = useApi();
+  const queryClient = useQueryClient();
+  const { LL } = useI18nContext();
+  const settings = useEnrollmentStore((state) => state.settings);
+  const [enabled, setEnabled] = useState(settings?.self_registration_enabled ?? false);
+  const [domains, setDomains] = useState(
+    settings?.self_registration_domains.join(', ') ?? '',
+  );
+  const componentLL = LL.enrollmentPage.settings.selfRegistration;
+  const toaster = useToaster();
+
+  const { isPending: isLoading, mutate } = useMutation({
+    mutationFn: editSettings,
+    onSuccess: () => {
+      void queryClient.invalidateQueries({
+        queryKey: [QueryKeys.FETCH_SETTINGS],
+      });
+      toaster.success(LL.enrollmentPage.messages.edit.success());
+    },
+    onError: (e) => {
+      toaster.error(LL.enrollmentPage.messages.edit.error());
+      console.error(e);
+    },
+  });
+
+  const handleSave = () => {
+    if (!isLoading && settings) {
+      mutate({
+        ...settings,
+        self_registration_enabled: enabled,
+        self_registration_domains: domains
+          .split(',')
+          .map((domain) => domain.trim())
+          .filter((domain) => domain.length > 0),
+      });
+    }
+  };
+
+  useEffect(() => {
+    if (settings) {
+      setEnabled(settings.self_registration_enabled);
+      setDomains(settings.self_registration_domains.join(', '));
+    }
+  }, [settings]);
+
+  return (
+    <div id="enrollment-self-registration">
+      <header>
+        <h3>{componentLL.title()}</h3>
+      </header>
+      <MessageBox type={MessageBoxType.INFO} message={componentLL.messageBox()} />
+      <Card shaded hideMobile>
+        <div className="controls">
+          <div className="checkbox-wrap">
+            <CheckBox
+              value={enabled}
+              onChange={() => setEnabled((state) => !state)}
+              disabled={isLoading}
+            />
+            <span onClick={() => setEnabled((state) => !state)}>
+              {componentLL.controls.enabled()}
+            </span>
+          </div>
+          <Button
+            text={LL.enrollmentPage.controls.save()}
+            styleVariant={ButtonStyleVariant.SAVE}
+            size={ButtonSize.SMALL}
+            icon={<SvgIconCheckmark />}
+            onClick={() => handleSave()}
+            loading={isLoading}
+            disabled={isUndefined(settings)}
+          />
+        </div>
+        <Input
+          label={componentLL.domains.label()}
+          placeholder={componentLL.domains.placeholder()}
+          value={domains}
+          onChange={(e) => setDomains(e.target.value)}
+          disabled={isLoading || isUndefined(settings)}
+        />
+      </Card>
+    </div>
+  );
+};
@@ -10,6 +10,7 @@ import { z } from 'zod';
 import { useI18nContext } from '../../../../../i18n/i18n-react';
 import { FormCheckBox } from '../../../../../shared/defguard-ui/components/Form/FormCheckBox/FormCheckBox';
 import { FormInput } from '../../../../../shared/defguard-ui/components/Form/FormInput/FormInput';
+import { FormSelect } from '../../../../../shared/defguard-ui/components/Form/FormSelect/FormSelect';
 import { Button } from '../../../../../shared/defguard-ui/components/Layout/Button/Button';
 import {
   ButtonSize,
@@ -18,10 +19,11 @@ import {
 import { Divider } from '../../../../../shared/defguard-ui/components/Layout/Divider/Divider';
 import { ModalWithTitle } from '../../../../../shared/defguard-ui/components/Layout/modals/ModalWithTitle/ModalWithTitle';
 import { Search } from '../../../../../shared/defguard-ui/components/Layout/Search/Search';
+import type { SelectOption } from '../../../../../shared/defguard-ui/components/Layout/Select/types';
 import useApi from '../../../../../shared/hooks/useApi';
 import { useToaster } from '../../../../../shared/hooks/useToaster';
 import { QueryKeys } from '../../../../../shared/queries';
-import type { ModifyGroupsRequest } from '../../../../../shared/types';
+import { ClientTrafficPolicy, type ModifyGroupsRequest } from '../../../../../shared/types';
 import { invalidateMultipleQueries } from '../../../../../shared/utils/invalidateMultipleQueries';
 import { GroupFormSelectAll } from './components/GroupFormSelectAll/GroupFormSelectAll';
 import { UserSelect } from './components/UserSelect/UserSelect';
@@ -47,10 +49,16 @@ export const AddGroupModal = () => {
 
 const toInvalidate = [QueryKeys.FETCH_GROUPS, QueryKeys.FETCH_GROUPS_INFO];
 
+// group doesn't override client traffic policy
+const inheritTrafficPolicy = 'inherit';
+
 export type ModifyGroupFormFields = {
   name: string;
   members: string[];
   is_admin: boolean;
+  is_helpdesk: boolean;
+  is_auditor: boolean;
+  client_traffic_policy: ClientTrafficPolicy | typeof inheritTrafficPolicy;
 };
 
 const ModalContent = () => {
@@ -126,22 +134,62 @@ const ModalContent = () => {
           }, LL.form.error.invalid()),
         members: z.array(z.string()),
         is_admin: z.boolean(),
+        is_helpdesk: z.boolean(),
+        is_auditor: z.boolean(),
+        client_traffic_policy: z.union([
+          z.nativeEnum(ClientTrafficPolicy),
+          z.literal(inheritTrafficPolicy),
+        ]),
       }),
     [LL.form.error, groupInfo, groups],
   );
 
+  const clientTrafficPolicyOptions = useMemo(
+    (): SelectOption<ClientTrafficPolicy | typeof inheritTrafficPolicy>[] => [
+      {
+        key: inheritTrafficPolicy,
+        value: inheritTrafficPolicy,
+        label: localLL.inheritTrafficPolicy(),
+      },
+      {
+        key: ClientTrafficPolicy.NONE,
+        value: ClientTrafficPolicy.NONE,
+        label: LL.settingsPage.enterprise.fields.clientTrafficPolicy.none.label(),
+      },
+      {
+        key: ClientTrafficPolicy.DISABLE_ALL_TRAFFIC,
+        value: ClientTrafficPolicy.DISABLE_ALL_TRAFFIC,
+        label:
+          LL.settingsPage.enterprise.fields.clientTrafficPolicy.disableAllTraffic.label(),
+      },
+      {
+        key: ClientTrafficPolicy.FORCE_ALL_TRAFFIC,
+        value: ClientTrafficPolicy.FORCE_ALL_TRAFFIC,
+        label:
+          LL.settingsPage.enterprise.fields.clientTrafficPolicy.forceAllTraffic.label(),
+      },
+    ],
+    [localLL, LL.settingsPage.enterprise.fields.clientTrafficPolicy],
+  );
+
   const defaults = useMemo((): ModifyGroupFormFields => {
     if (groupInfo) {
       return {
         name: groupInfo.name,
         members: groupInfo.members ?? [],
         is_admin: groupInfo.is_admin,
+        is_helpdesk: groupInfo.is_helpdesk,
+        is_auditor: groupInfo.is_auditor,
+        client_traffic_policy: groupInfo.client_traffic_policy ?? inheritTrafficPolicy,
       };
     }
     return {
       name: '',
       members: [],
       is_admin: false,
+      is_helpdesk: false,
+      is_auditor: false,
+      client_traffic_policy: inheritTrafficPolicy,
     };
   }, [groupInfo]);
 
@@ -160,6 +208,12 @@ const ModalContent = () => {
       name: values.name,
       members: values.members,
       is_admin: values.is_admin,
+      is_helpdesk: values.is_helpdesk,
+      is_auditor: values.is_auditor,
+      client_traffic_policy:
+        values.client_traffic_policy === inheritTrafficPolicy
+          ? null
+          : values.client_traffic_policy,
     };
     if (groupInfo) {
       editGroupMutation({ ...sendValues, originalName: groupInfo.name });
@@ -178,6 +232,27 @@ const ModalContent = () => {
           label={localLL.adminGroup()}
           labelPlacement="right"
         />
+        <FormCheckBox
+          controller={{ control, name: 'is_helpdesk' }}
+          label={localLL.helpdeskGroup()}
+          labelPlacement="right"
+        />
+        <FormCheckBox
+          controller={{ control, name: 'is_auditor' }}
+          label={localLL.auditorGroup()}
+          labelPlacement="right"
+        />
+        <FormSelect
+          controller={{ control, name: 'client_traffic_policy' }}
+          label={localLL.clientTrafficPolicy()}
+          options={clientTrafficPolicyOptions}
+          renderSelected={(val) => ({
+            key: val,
+            displayValue:
+              clientTrafficPolicyOptions.find((option) => option.value === val)?.label ??
+              val,
+          })}
+        />
       </div>
       <Divider />
       {users && <GroupFormSelectAll users={users} control={control} />}
@@ -25,6 +25,9 @@ import useApi from '../../../shared/hooks/useApi';
 import { useToaster } from '../../../shared/hooks/useToaster';
 import { QueryKeys } from '../../../shared/queries';
 import {
+  ClientTrafficPolicy,
+  DeviceNameUniqueness,
+  IpAllocationStrategy,
   LicenseTier,
   LocationMfaMode,
   type Network,
@@ -36,6 +39,9 @@ import { Validate } from '../../../shared/validators';
 import { useNetworkPageStore } from '../hooks/useNetworkPageStore';
 import { DividerHeader } from './components/DividerHeader.tsx';
 
+// location doesn't override client traffic policy
+const inheritTrafficPolicy = 'inherit';
+
 export const NetworkEditForm = () => {
   const toaster = useToaster();
   const {
@@ -124,6 +130,58 @@ export const NetworkEditForm = () => {
     }
   }, [groupsData]);
 
+  const ipAllocationOptions = useMemo(
+    (): SelectOption<IpAllocationStrategy>[] =>
+      Object.values(IpAllocationStrategy).map((strategy) => ({
+        key: strategy,
+        value: strategy,
+        label: LL.networkConfiguration.form.fields.ip_allocation_strategy.options[strategy](),
+      })),
+    [LL.networkConfiguration.form.fields.ip_allocation_strategy.options],
+  );
+
+  const deviceNameUniquenessOptions = useMemo(
+    (): SelectOption<DeviceNameUniqueness>[] =>
+      Object.values(DeviceNameUniqueness).map((uniqueness) => ({
+        key: uniqueness,
+        value: uniqueness,
+        label:
+          LL.networkConfiguration.form.fields.device_name_uniqueness.options[uniqueness](),
+      })),
+    [LL.networkConfiguration.form.fields.device_name_uniqueness.options],
+  );
+
+  const clientTrafficPolicyOptions = useMemo(
+    (): SelectOption<ClientTrafficPolicy | typeof inheritTrafficPolicy>[] => [
+      {
+        key: inheritTrafficPolicy,
+        value: inheritTrafficPolicy,
+        label: LL.networkConfiguration.form.fields.client_traffic_policy.inherit(),
+      },
+      {
+        key: ClientTrafficPolicy.NONE,
+        value: ClientTrafficPolicy.NONE,
+        label: LL.settingsPage.enterprise.fields.clientTrafficPolicy.none.label(),
+      },
+      {
+        key: ClientTrafficPolicy.DISABLE_ALL_TRAFFIC,
+        value: ClientTrafficPolicy.DISABLE_ALL_TRAFFIC,
+        label:
+          LL.settingsPage.enterprise.fields.clientTrafficPolicy.disableAllTraffic.label(),
+      },
+      {
+        key: ClientTrafficPolicy.FORCE_ALL_TRAFFIC,
+        value: ClientTrafficPolicy.FORCE_ALL_TRAFFIC,
+        label:
+          LL.settingsPage.enterprise.fields.clientTrafficPolicy.forceAllTraffic.label(),
+      },
+    ],
+    [
+      LL.networkConfiguration.form.fields.client_traffic_policy,
+      LL.settingsPage.enterprise.fields.clientTrafficPolicy,
+    ],
+  );
+
   const zodSchema = useMemo(
     () =>
       z.object({
@@ -192,10 +250,40 @@ export const NetworkEditForm = () => {
             invalid_type_error: LL.form.error.required(),
           })
           .min(120, LL.form.error.invalid()),
+        mfa_session_lifetime_hours: z
+          .number({
+            invalid_type_error: LL.form.error.required(),
+          })
+          .int()
+          .nonnegative(),
+        mfa_remember_device_hours: z
+          .number({
+            invalid_type_error: LL.form.error.required(),
+          })
+          .int()
+          .nonnegative(),
+        preshared_keys_enabled: z.boolean(),
+        preshared_key_rotation_days: z
+          .number({
+            invalid_type_error: LL.form.error.required(),
+          })
+          .int()
+          .nonnegative(),
         acl_enabled: z.boolean(),
         acl_default_allow: z.boolean(),
         location_mfa_mode: z.nativeEnum(LocationMfaMode),
         service_location_mode: z.nativeEnum(ServiceLocationMode),
+        min_desktop_client_version: z.string().trim(),
+        min_mobile_client_version: z.string().trim(),
+        device_approval_required: z.boolean(),
+        ip_allocation_strategy: z.nativeEnum(IpAllocationStrategy),
+        device_name_pattern: z.string().trim(),
+        device_name_prefix: z.string().trim(),
+        device_name_uniqueness: z.nativeEnum(DeviceNameUniqueness),
+        client_traffic_policy: z.union([
+          z.nativeEnum(ClientTrafficPolicy),
+          z.literal(inheritTrafficPolicy),
+        ]),
       }),
     [LL.form.error],
   );
@@ -213,10 +301,22 @@ export const NetworkEditForm = () => {
       dns: '',
       keepalive_interval: 25,
       peer_disconnect_threshold: 300,
+      mfa_session_lifetime_hours: 0,
+      mfa_remember_device_hours: 0,
+      preshared_keys_enabled: false,
+      preshared_key_rotation_days: 0,
       acl_enabled: false,
       acl_default_allow: false,
       location_mfa_mode: LocationMfaMode.DISABLED,
       service_location_mode: ServiceLocationMode.DISABLED,
+      min_desktop_client_version: '',
+      min_mobile_client_version: '',
+      device_approval_required: false,
+      ip_allocation_strategy: IpAllocationStrategy.SEQUENTIAL,
+      device_name_pattern: '',
+      device_name_prefix: '',
+      device_name_uniqueness: DeviceNameUniqueness.USER,
+      client_traffic_policy: inheritTrafficPolicy,
     }),
     [],
   );
@@ -321,6 +421,10 @@ export const NetworkEditForm = () => {
         id: selectedNetworkId,
         network: {
           ...values,
+          client_traffic_policy:
+            values.client_traffic_policy === inheritTrafficPolicy
+              ? null
+              : values.client_traffic_policy,
         },
       });
     }
@@ -462,6 +566,36 @@ export const NetworkEditForm = () => {
           type="number"
           disabled={mfaDisabled}
         />
+        <MessageBox>
+          <p>{LL.networkConfiguration.form.helpers.mfaSessionLifetime()}</p>
+        </MessageBox>
+        <FormInput
+          controller={{ control, name: 'mfa_session_lifetime_hours' }}
+          label={LL.networkConfiguration.form.fields.mfa_session_lifetime_hours.label()}
+          type="number"
+          disabled={mfaDisabled}
+        />
+        <FormInput
+          controller={{ control, name: 'mfa_remember_device_hours' }}
+          label={LL.networkConfiguration.form.fields.mfa_remember_device_hours.label()}
+          type="number"
+          disabled={mfaDisabled}
+        />
+        <MessageBox>
+          <p>{LL.networkConfiguration.form.helpers.presharedKeys()}</p>
+        </MessageBox>
+        <FormCheckBox
+          controller={{ control, name: 'preshared_keys_enabled' }}
+          label={LL.networkConfiguration.form.fields.preshared_keys_enabled.label()}
+          labelPlacement="right"
+          disabled={!mfaDisabled}
+        />
+        <FormInput
+          controller={{ control, name: 'preshared_key_rotation_days' }}
+          label={LL.networkConfiguration.form.fields.preshared_key_rotation_days.label()}
+          type="number"
+          disabled={!mfaDisabled}
+        />
         <DividerHeader
           text={LL.networkConfiguration.form.sections.serviceLocation.header()}
         />
@@ -495,6 +629,89 @@ export const NetworkEditForm = () => {
           controller={{ control, name: 'service_location_mode' }}
           disabled={!enterpriseLicenseEnabled || !mfaDisabled}
         />
+        <DividerHeader
+          text={LL.networkConfiguration.form.sections.clientVersions.header()}
+        />
+        <MessageBox>
+          <p>{LL.networkConfiguration.form.helpers.clientVersions()}</p>
+        </MessageBox>
+        <FormInput
+          controller={{ control, name: 'min_desktop_client_version' }}
+          label={LL.networkConfiguration.form.fields.min_desktop_client_version.label()}
+        />
+        <FormInput
+          controller={{ control, name: 'min_mobile_client_version' }}
+          label={LL.networkConfiguration.form.fields.min_mobile_client_version.label()}
+        />
+        <DividerHeader
+          text={LL.networkConfiguration.form.sections.deviceApproval.header()}
+        />
+        <MessageBox>
+          <p>{LL.networkConfiguration.form.helpers.deviceApproval()}</p>
+        </MessageBox>
+        <FormCheckBox
+          controller={{ control, name: 'device_approval_required' }}
+          label={LL.networkConfiguration.form.fields.device_approval_required.label()}
+          labelPlacement="right"
+        />
+        <DividerHeader
+          text={LL.networkConfiguration.form.sections.ipAllocation.header()}
+        />
+        <MessageBox>
+          <p>{LL.networkConfiguration.form.helpers.ipAllocation()}</p>
+        </MessageBox>
+        <FormSelect
+          controller={{ control, name: 'ip_allocation_strategy' }}
+          label={LL.networkConfiguration.form.fields.ip_allocation_strategy.label()}
+          options={ipAllocationOptions}
+          renderSelected={(val) => ({
+            key: val,
+            displayValue:
+              ipAllocationOptions.find((option) => option.value === val)?.label ?? val,
+          })}
+        />
+        <DividerHeader
+          text={LL.networkConfiguration.form.sections.deviceNaming.header()}
+        />
+        <MessageBox>
+          <p>{LL.networkConfiguration.form.helpers.deviceNaming()}</p>
+        </MessageBox>
+        <FormInput
+          controller={{ control, name: 'device_name_pattern' }}
+          label={LL.networkConfiguration.form.fields.device_name_pattern.label()}
+        />
+        <FormInput
+          controller={{ control, name: 'device_name_prefix' }}
+          label={LL.networkConfiguration.form.fields.device_name_prefix.label()}
+        />
+        <FormSelect
+          controller={{ control, name: 'device_name_uniqueness' }}
+          label={LL.networkConfiguration.form.fields.device_name_uniqueness.label()}
+          options={deviceNameUniquenessOptions}
+          renderSelected={(val) => ({
+            key: val,
+            displayValue:
+              deviceNameUniquenessOptions.find((option) => option.value === val)?.label ??
+              val,
+          })}
+        />
+        <DividerHeader
+          text={LL.networkConfiguration.form.sections.clientTrafficPolicy.header()}
+        />
+        <MessageBox>
+          <p>{LL.networkConfiguration.form.helpers.clientTrafficPolicy()}</p>
+        </MessageBox>
+        <FormSelect
+          controller={{ control, name: 'client_traffic_policy' }}
+          label={LL.networkConfiguration.form.fields.client_traffic_policy.label()}
+          options={clientTrafficPolicyOptions}
+          renderSelected={(val) => ({
+            key: val,
+            displayValue:
+              clientTrafficPolicyOptions.find((option) => option.value === val)?.label ??
+              val,
+          })}
+        />
         <button type="submit" className="hidden" ref={submitRef}></button>
       </form>
     </section>
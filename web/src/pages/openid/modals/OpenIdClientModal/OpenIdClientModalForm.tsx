@@ -29,6 +29,43 @@ const defaultValuesEmptyForm: OpenIdClientFormFields = {
   name: '',
   redirect_uri: [{ url: '' }],
   scope: [],
+  group_filter: '',
+  claim_mapping: '',
+  static_claims: '',
+};
+
+const splitList = (value: string): string[] =>
+  value
+    .split(',')
+    .map((item) => item.trim())
+    .filter((item) => item.length > 0);
+
+// "claim:name" pairs separated by commas
+const parseClaimMapping = (value: string): Record<string, string> =>
+  Object.fromEntries(
+    splitList(value).map((pair) => {
+      const [claim, name] = pair.split(':');
+      return [claim.trim(), name.trim()];
+    }),
+  );
+
+const parseStaticClaims = (value: string): Record<string, unknown> =>
+  value.trim().length ? (JSON.parse(value) as Record<string, unknown>) : {};
+
+const isClaimMapping = (value: string): boolean =>
+  splitList(value).every((pair) => {
+    const parts = pair.split(':');
+    return parts.length === 2 && parts.every((part) => part.trim().length > 0);
+  });
+
+const isStaticClaims = (value: string): boolean => {
+  if (!value.trim().length) return true;
+  try {
+    const parsed: unknown = JSON.parse(value);
+    return typeof parsed === 'object' && parsed !== null && !Array.isArray(parsed);
+  } catch {
+    return false;
+  }
 };
 
 export const OpenIdClientModalForm = () => {
@@ -48,6 +85,13 @@ export const OpenIdClientModalForm = () => {
         name: modalState.client.name,
         redirect_uri: urls,
         scope: modalState.client.scope as OpenIdClientScope[],
+        group_filter: modalState.client.group_filter.join(', '),
+        claim_mapping: Object.entries(modalState.client.claim_mapping)
+          .map(([claim, name]) => `${claim}:${name}`)
+          .join(', '),
+        static_claims: Object.keys(modalState.client.static_claims).length
+          ? JSON.stringify(modalState.client.static_claims)
+          : '',
       };
     }
     return defaultValuesEmptyForm;
@@ -111,6 +155,19 @@ export const OpenIdClientModalForm = () => {
           }),
         ),
         scope: z.array(z.nativeEnum(OpenIdClientScope)),
+        group_filter: z.string(),
+        claim_mapping: z
+          .string()
+          .refine(
+            isClaimMapping,
+            LL.openidOverview.modals.openidClientModal.form.error.claimMapping(),
+          ),
+        static_claims: z
+          .string()
+          .refine(
+            isStaticClaims,
+            LL.openidOverview.modals.openidClientModal.form.error.staticClaims(),
+          ),
       }),
     [LL.form.error, LL.openidOverview.modals.openidClientModal.form.error],
   );
@@ -137,10 +194,16 @@ export const OpenIdClientModalForm = () => {
       return;
     }
     const urls = values.redirect_uri.map((u) => u.url);
+    const claims = {
+      group_filter: splitList(values.group_filter),
+      claim_mapping: parseClaimMapping(values.claim_mapping),
+      static_claims: parseStaticClaims(values.static_claims),
+    };
     if (modalState.client) {
       editMutation({
         ...modalState.client,
         ...values,
+        ...claims,
         redirect_uri: urls,
       });
     } else {
@@ -149,6 +212,7 @@ export const OpenIdClientModalForm = () => {
         scope: values.scope,
         redirect_uri: urls,
         enabled: true,
+        ...claims,
       });
     }
   };
@@ -196,6 +260,24 @@ export const OpenIdClientModalForm = () => {
       </div>
       <h3>{LL.openidOverview.modals.openidClientModal.scopes()}</h3>
       <OpenIdClientModalFormScopes control={control} disabled={modalState.viewMode} />
+      <h3>{LL.openidOverview.modals.openidClientModal.claims()}</h3>
+      <FormInput
+        controller={{ control, name: 'group_filter' }}
+        label={LL.openidOverview.modals.openidClientModal.form.fields.groupFilter.label()}
+        placeholder={LL.openidOverview.modals.openidClientModal.form.fields.groupFilter.placeholder()}
+        disabled={modalState.viewMode}
+      />
+      <FormInput
+        controller={{ control, name: 'claim_mapping' }}
+        label={LL.openidOverview.modals.openidClientModal.form.fields.claimMapping.label()}
+        placeholder={LL.openidOverview.modals.openidClientModal.form.fields.claimMapping.placeholder()}
+        disabled={modalState.viewMode}
+      />
+      <FormInput
+        controller={{ control, name: 'static_claims' }}
+        label={LL.openidOverview.modals.openidClientModal.form.fields.staticClaims.label()}
+        disabled={modalState.viewMode}
+      />
       {modalState.viewMode && !isUndefined(modalState.client) && (
         <div className="client-info">
           <ExpandableCard
@@ -4,6 +4,9 @@ export type OpenIdClientFormFields = {
     url: string;
   }[];
   scope: OpenIdClientScope[];
+  group_filter: string;
+  claim_mapping: string;
+  static_claims: string;
 };
 
 export enum OpenIdClientScope {
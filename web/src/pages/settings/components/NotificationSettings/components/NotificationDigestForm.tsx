@@ -0,0 +1,39 @@
+import parse from 'html-react-parser';
+import type { Control } from 'react-hook-form';
+
+import { useI18nContext } from '../../../../../i18n/i18n-react';
+import { FormInput } from '../../../../../shared/defguard-ui/components/Form/FormInput/FormInput';
+import { Helper } from '../../../../../shared/defguard-ui/components/Layout/Helper/Helper';
+import { useAppStore } from '../../../../../shared/hooks/store/useAppStore';
+import type { FormFields } from './NotificationSettingsForm';
+
+export const NotificationDigestForm = ({
+  control,
+  isLoading,
+}: {
+  control: Control<FormFields>;
+  isLoading: boolean;
+}) => {
+  const { LL } = useI18nContext();
+  const localLL = LL.settingsPage.gatewayNotifications;
+  const smtpConfigured = useAppStore((s) => Boolean(s.appInfo?.smtp_enabled));
+
+  return (
+    <div>
+      <h3 className="subsection-header">{localLL.sections.digest()}</h3>
+      <FormInput
+        type="number"
+        controller={{
+          control,
+          name: 'notification_digest_window',
+        }}
+        label={localLL.form.fields.notificationDigestWindow.label()}
+        labelExtras={
+          <Helper>{parse(localLL.form.fields.notificationDigestWindow.help())}</Helper>
+        }
+        disabled={isLoading || !smtpConfigured}
+        required
+      />
+    </div>
+  );
+};
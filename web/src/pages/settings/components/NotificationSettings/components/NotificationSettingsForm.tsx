@@ -22,11 +22,15 @@ import type { ApiError } from '../../../../../shared/types';
 import { invalidateMultipleQueries } from '../../../../../shared/utils/invalidateMultipleQueries';
 import { useSettingsPage } from '../../../hooks/useSettingsPage';
 import { GatewayNotificationsForm } from './GatewayNotificationsForm';
+import { NotificationDigestForm } from './NotificationDigestForm';
+import { SecuritySummaryForm } from './SecuritySummaryForm';
 
 export type FormFields = {
   gateway_disconnect_notifications_enabled: boolean;
   gateway_disconnect_notifications_inactivity_threshold: number;
   gateway_disconnect_notifications_reconnect_notification_enabled: boolean;
+  security_summary_enabled: boolean;
+  notification_digest_window: number;
 };
 
 export const NotificationsForm = () => {
@@ -67,6 +71,10 @@ export const NotificationsForm = () => {
           .number()
           .min(0, LL.form.error.minimumValue({ value: 0 })),
         gateway_disconnect_notifications_reconnect_notification_enabled: z.boolean(),
+        security_summary_enabled: z.boolean(),
+        notification_digest_window: z
+          .number()
+          .min(0, LL.form.error.minimumValue({ value: 0 })),
       }),
     [LL.form],
   );
@@ -80,6 +88,8 @@ export const NotificationsForm = () => {
       gateway_disconnect_notifications_reconnect_notification_enabled:
         settings?.gateway_disconnect_notifications_reconnect_notification_enabled ??
         false,
+      security_summary_enabled: settings?.security_summary_enabled ?? false,
+      notification_digest_window: settings?.notification_digest_window ?? 0,
     };
     return res;
   }, [settings]);
@@ -124,6 +134,8 @@ export const NotificationsForm = () => {
               message={parse(LL.settingsPage.gatewayNotifications.smtpWarning())}
             />
             <GatewayNotificationsForm control={control} isLoading={isLoading} />
+            <SecuritySummaryForm control={control} isLoading={isLoading} />
+            <NotificationDigestForm control={control} isLoading={isLoading} />
           </div>
         </div>
       </form>
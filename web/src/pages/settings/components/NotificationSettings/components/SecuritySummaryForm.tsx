@@ -0,0 +1,40 @@
+import parse from 'html-react-parser';
+import type { Control } from 'react-hook-form';
+
+import { useI18nContext } from '../../../../../i18n/i18n-react';
+import { FormCheckBox } from '../../../../../shared/defguard-ui/components/Form/FormCheckBox/FormCheckBox';
+import { Helper } from '../../../../../shared/defguard-ui/components/Layout/Helper/Helper';
+import { useAppStore } from '../../../../../shared/hooks/store/useAppStore';
+import type { FormFields } from './NotificationSettingsForm';
+
+export const SecuritySummaryForm = ({
+  control,
+  isLoading,
+}: {
+  control: Control<FormFields>;
+  isLoading: boolean;
+}) => {
+  const { LL } = useI18nContext();
+  const localLL = LL.settingsPage.gatewayNotifications;
+  const smtpConfigured = useAppStore((s) => Boolean(s.appInfo?.smtp_enabled));
+
+  return (
+    <div>
+      <h3 className="subsection-header">{localLL.sections.securitySummary()}</h3>
+      <div className="checkbox-column">
+        <div className="helper-row">
+          <FormCheckBox
+            disabled={isLoading || !smtpConfigured}
+            label={localLL.form.fields.securitySummaryEnabled.label()}
+            controller={{
+              control,
+              name: 'security_summary_enabled',
+            }}
+            labelPlacement="right"
+          />
+          <Helper>{parse(localLL.form.fields.securitySummaryEnabled.help())}</Helper>
+        </div>
+      </div>
+    </div>
+  );
+};
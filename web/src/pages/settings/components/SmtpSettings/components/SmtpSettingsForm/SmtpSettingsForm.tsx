@@ -4,7 +4,7 @@ import { zodResolver } from '@hookform/resolvers/zod';
 import { useMutation, useQueryClient } from '@tanstack/react-query';
 import parse from 'html-react-parser';
 import { useCallback, useMemo } from 'react';
-import { type SubmitHandler, useForm } from 'react-hook-form';
+import { type SubmitHandler, useForm, useWatch } from 'react-hook-form';
 import { z } from 'zod';
 
 import { useI18nContext } from '../../../../../../i18n/i18n-react';
@@ -25,7 +25,11 @@ import useApi from '../../../../../../shared/hooks/useApi';
 import { useToaster } from '../../../../../../shared/hooks/useToaster';
 import { patternValidEmail } from '../../../../../../shared/patterns';
 import { QueryKeys } from '../../../../../../shared/queries';
-import type { SettingsSMTP } from '../../../../../../shared/types';
+import type {
+  MailBackend,
+  SettingsSMTP,
+  SmtpAuthMethod,
+} from '../../../../../../shared/types';
 import { invalidateMultipleQueries } from '../../../../../../shared/utils/invalidateMultipleQueries';
 import { Validate } from '../../../../../../shared/validators';
 import { useSettingsPage } from '../../../../hooks/useSettingsPage';
@@ -39,6 +43,18 @@ type FormFields = {
   smtp_password: string;
   smtp_sender: string;
   smtp_encryption: string;
+  smtp_auth_method: SmtpAuthMethod;
+  smtp_oauth2_token_url: string;
+  smtp_oauth2_client_id: string;
+  smtp_oauth2_client_secret: string;
+  smtp_oauth2_scope: string;
+  smtp_sender_name: string;
+  smtp_reply_to: string;
+  smtp_security_sender: string;
+  smtp_announcement_sender: string;
+  mail_backend: MailBackend;
+  mail_http_url: string;
+  mail_http_token: string;
 };
 
 export const SmtpSettingsForm = () => {
@@ -92,6 +108,62 @@ export const SmtpSettingsForm = () => {
     [],
   );
 
+  const authMethodOptions = useMemo(
+    (): SelectOption<SmtpAuthMethod>[] => [
+      {
+        key: 1,
+        value: 'password',
+        label: 'Password',
+      },
+      {
+        key: 2,
+        value: 'oauth2',
+        label: 'OAuth2 (XOAUTH2)',
+      },
+    ],
+    [],
+  );
+
+  const mailBackendOptions = useMemo(
+    (): SelectOption<MailBackend>[] => [
+      {
+        key: 1,
+        value: 'smtp',
+        label: 'SMTP',
+      },
+      {
+        key: 2,
+        value: 'http',
+        label: 'HTTP API',
+      },
+    ],
+    [],
+  );
+
+  const renderSelectedMailBackend = useCallback(
+    (selected: MailBackend): SelectSelectedValue => {
+      const option = mailBackendOptions.find((o) => o.value === selected);
+      if (!option) throw Error("Selected value doesn't exist");
+      return {
+        key: option.key,
+        displayValue: option.label,
+      };
+    },
+    [mailBackendOptions],
+  );
+
+  const renderSelectedAuthMethod = useCallback(
+    (selected: SmtpAuthMethod): SelectSelectedValue => {
+      const option = authMethodOptions.find((o) => o.value === selected);
+      if (!option) throw Error("Selected value doesn't exist");
+      return {
+        key: option.key,
+        displayValue: option.label,
+      };
+    },
+    [authMethodOptions],
+  );
+
   const renderSelectedEncryption = useCallback(
     (selected: string): SelectSelectedValue => {
       const option = encryptionOptions.find((o) => o.value === selected);
@@ -110,7 +182,6 @@ export const SmtpSettingsForm = () => {
         smtp_server: z
           .string()
           .trim()
-          .min(1, LL.form.error.required())
           .refine(
             (val) =>
               Validate.any(val, [
@@ -134,6 +205,59 @@ export const SmtpSettingsForm = () => {
           .min(1, LL.form.error.required())
           .regex(patternValidEmail, LL.form.error.invalid()),
         smtp_encryption: z.string().trim().min(1, LL.form.error.required()),
+        smtp_auth_method: z.enum(['password', 'oauth2']),
+        smtp_oauth2_token_url: z.string().trim(),
+        smtp_oauth2_client_id: z.string().trim(),
+        smtp_oauth2_client_secret: z.string().trim(),
+        smtp_oauth2_scope: z.string().trim(),
+        smtp_sender_name: z.string().trim(),
+        smtp_reply_to: z
+          .string()
+          .trim()
+          .regex(patternValidEmail, LL.form.error.invalid())
+          .or(z.literal('')),
+        smtp_security_sender: z
+          .string()
+          .trim()
+          .regex(patternValidEmail, LL.form.error.invalid())
+          .or(z.literal('')),
+        smtp_announcement_sender: z
+          .string()
+          .trim()
+          .regex(patternValidEmail, LL.form.error.invalid())
+          .or(z.literal('')),
+        mail_backend: z.enum(['smtp', 'http']),
+        mail_http_url: z
+          .string()
+          .trim()
+          .url(LL.form.error.invalid())
+          .or(z.literal('')),
+        mail_http_token: z.string().trim(),
+      })
+      .superRefine((val, ctx) => {
+        const required: (keyof FormFields)[] = [];
+        if (val.mail_backend === 'http') {
+          required.push('mail_http_url');
+        } else {
+          required.push('smtp_server');
+          if (val.smtp_auth_method === 'oauth2') {
+            required.push(
+              'smtp_user',
+              'smtp_oauth2_token_url',
+              'smtp_oauth2_client_id',
+              'smtp_oauth2_client_secret',
+            );
+          }
+        }
+        for (const field of required) {
+          if (String(val[field]).length === 0) {
+            ctx.addIssue({
+              code: 'custom',
+              path: [field],
+              message: LL.form.error.required(),
+            });
+          }
+        }
       }),
     [LL.form],
   );
@@ -146,6 +270,18 @@ export const SmtpSettingsForm = () => {
       smtp_sender: settings?.smtp_sender ?? '',
       smtp_user: settings?.smtp_user ?? '',
       smtp_encryption: settings?.smtp_encryption ?? encryptionOptions[1].value,
+      smtp_auth_method: settings?.smtp_auth_method ?? 'password',
+      smtp_oauth2_token_url: settings?.smtp_oauth2_token_url ?? '',
+      smtp_oauth2_client_id: settings?.smtp_oauth2_client_id ?? '',
+      smtp_oauth2_client_secret: settings?.smtp_oauth2_client_secret ?? '',
+      smtp_oauth2_scope: settings?.smtp_oauth2_scope ?? '',
+      smtp_sender_name: settings?.smtp_sender_name ?? '',
+      smtp_reply_to: settings?.smtp_reply_to ?? '',
+      smtp_security_sender: settings?.smtp_security_sender ?? '',
+      smtp_announcement_sender: settings?.smtp_announcement_sender ?? '',
+      mail_backend: settings?.mail_backend ?? 'smtp',
+      mail_http_url: settings?.mail_http_url ?? '',
+      mail_http_token: settings?.mail_http_token ?? '',
     };
     return res;
   }, [settings, encryptionOptions]);
@@ -158,6 +294,18 @@ export const SmtpSettingsForm = () => {
       smtp_sender: '',
       smtp_user: '',
       smtp_encryption: encryptionOptions[1].value,
+      smtp_auth_method: 'password',
+      smtp_oauth2_token_url: '',
+      smtp_oauth2_client_id: '',
+      smtp_oauth2_client_secret: '',
+      smtp_oauth2_scope: '',
+      smtp_sender_name: '',
+      smtp_reply_to: '',
+      smtp_security_sender: '',
+      smtp_announcement_sender: '',
+      mail_backend: 'smtp',
+      mail_http_url: '',
+      mail_http_token: '',
     }),
     [encryptionOptions],
   );
@@ -168,6 +316,9 @@ export const SmtpSettingsForm = () => {
     resolver: zodResolver(zodSchema),
   });
 
+  const authMethod = useWatch({ control, name: 'smtp_auth_method' });
+  const mailBackend = useWatch({ control, name: 'mail_backend' });
+
   const onSubmit: SubmitHandler<FormFields> = (data) => {
     mutate(data);
   };
@@ -219,6 +370,43 @@ export const SmtpSettingsForm = () => {
       </header>
       <form id="smtp-form" onSubmit={handleSubmit(onSubmit)} className="column-layout">
         <div className="left">
+          <div>
+            <div className="subsection-header helper-row">
+              <h3>{localLL.form.sections.delivery()}</h3>
+            </div>
+            <FormSelect
+              data-testid="mail-backend-select"
+              labelExtras={<Helper>{localLL.form.fields.mailBackend.helper()}</Helper>}
+              label={localLL.form.fields.mailBackend.label()}
+              renderSelected={renderSelectedMailBackend}
+              options={mailBackendOptions}
+              controller={{ control, name: 'mail_backend' }}
+            />
+            <FormInput
+              labelExtras={<Helper>{parse(localLL.form.fields.sender.helper())}</Helper>}
+              label={localLL.form.fields.sender.label()}
+              controller={{ control, name: 'smtp_sender' }}
+              placeholder={localLL.form.fields.sender.placeholder()}
+              required
+            />
+            {mailBackend === 'http' && (
+              <>
+                <FormInput
+                  label={localLL.form.fields.httpUrl.label()}
+                  controller={{ control, name: 'mail_http_url' }}
+                  placeholder={localLL.form.fields.httpUrl.placeholder()}
+                  required
+                />
+                <FormInput
+                  labelExtras={<Helper>{localLL.form.fields.httpToken.helper()}</Helper>}
+                  label={localLL.form.fields.httpToken.label()}
+                  controller={{ control, name: 'mail_http_token' }}
+                  placeholder={localLL.form.fields.httpToken.placeholder()}
+                  type="password"
+                />
+              </>
+            )}
+          </div>
           <div>
             <div className="subsection-header helper-row">
               <h3>{localLL.form.sections.server()}</h3>
@@ -227,39 +415,103 @@ export const SmtpSettingsForm = () => {
               label={localLL.form.fields.server.label()}
               controller={{ control, name: 'smtp_server' }}
               placeholder={localLL.form.fields.server.placeholder()}
-              required
+              required={mailBackend === 'smtp'}
             />
             <FormInput
               label={localLL.form.fields.port.label()}
               controller={{ control, name: 'smtp_port' }}
               placeholder={localLL.form.fields.port.placeholder()}
               type="number"
-              required
+              required={mailBackend === 'smtp'}
+            />
+            <FormSelect
+              data-testid="smtp-encryption-select"
+              label={localLL.form.fields.encryption.label()}
+              renderSelected={renderSelectedEncryption}
+              options={encryptionOptions}
+              controller={{ control, name: 'smtp_encryption' }}
+            />
+          </div>
+          <div>
+            <div className="subsection-header helper-row">
+              <h3>{localLL.form.sections.authentication()}</h3>
+            </div>
+            <FormSelect
+              data-testid="smtp-auth-method-select"
+              label={localLL.form.fields.authMethod.label()}
+              renderSelected={renderSelectedAuthMethod}
+              options={authMethodOptions}
+              controller={{ control, name: 'smtp_auth_method' }}
             />
             <FormInput
               label={localLL.form.fields.user.label()}
               controller={{ control, name: 'smtp_user' }}
               placeholder={localLL.form.fields.user.placeholder()}
+              required={authMethod === 'oauth2'}
             />
+            {authMethod === 'oauth2' ? (
+              <>
+                <FormInput
+                  label={localLL.form.fields.oauth2TokenUrl.label()}
+                  controller={{ control, name: 'smtp_oauth2_token_url' }}
+                  placeholder={localLL.form.fields.oauth2TokenUrl.placeholder()}
+                  required
+                />
+                <FormInput
+                  label={localLL.form.fields.oauth2ClientId.label()}
+                  controller={{ control, name: 'smtp_oauth2_client_id' }}
+                  placeholder={localLL.form.fields.oauth2ClientId.placeholder()}
+                  required
+                />
+                <FormInput
+                  label={localLL.form.fields.oauth2ClientSecret.label()}
+                  controller={{ control, name: 'smtp_oauth2_client_secret' }}
+                  placeholder={localLL.form.fields.oauth2ClientSecret.placeholder()}
+                  type="password"
+                  required
+                />
+                <FormInput
+                  label={localLL.form.fields.oauth2Scope.label()}
+                  controller={{ control, name: 'smtp_oauth2_scope' }}
+                  placeholder={localLL.form.fields.oauth2Scope.placeholder()}
+                />
+              </>
+            ) : (
+              <FormInput
+                label={localLL.form.fields.password.label()}
+                controller={{ control, name: 'smtp_password' }}
+                placeholder={localLL.form.fields.password.placeholder()}
+                type="password"
+              />
+            )}
+          </div>
+          <div>
+            <div className="subsection-header helper-row">
+              <h3>{localLL.form.sections.senders()}</h3>
+            </div>
             <FormInput
-              label={localLL.form.fields.password.label()}
-              controller={{ control, name: 'smtp_password' }}
-              placeholder={localLL.form.fields.password.placeholder()}
-              type="password"
+              labelExtras={<Helper>{localLL.form.fields.senderName.helper()}</Helper>}
+              label={localLL.form.fields.senderName.label()}
+              controller={{ control, name: 'smtp_sender_name' }}
+              placeholder={localLL.form.fields.senderName.placeholder()}
             />
             <FormInput
-              labelExtras={<Helper>{parse(localLL.form.fields.sender.helper())}</Helper>}
-              label={localLL.form.fields.sender.label()}
-              controller={{ control, name: 'smtp_sender' }}
-              placeholder={localLL.form.fields.sender.placeholder()}
-              required
+              labelExtras={<Helper>{localLL.form.fields.replyTo.helper()}</Helper>}
+              label={localLL.form.fields.replyTo.label()}
+              controller={{ control, name: 'smtp_reply_to' }}
+              placeholder={localLL.form.fields.replyTo.placeholder()}
             />
-            <FormSelect
-              data-testid="smtp-encryption-select"
-              label={localLL.form.fields.encryption.label()}
-              renderSelected={renderSelectedEncryption}
-              options={encryptionOptions}
-              controller={{ control, name: 'smtp_encryption' }}
+            <FormInput
+              labelExtras={<Helper>{localLL.form.fields.securitySender.helper()}</Helper>}
+              label={localLL.form.fields.securitySender.label()}
+              controller={{ control, name: 'smtp_security_sender' }}
+              placeholder={localLL.form.fields.securitySender.placeholder()}
+            />
+            <FormInput
+              labelExtras={<Helper>{localLL.form.fields.announcementSender.helper()}</Helper>}
+              label={localLL.form.fields.announcementSender.label()}
+              controller={{ control, name: 'smtp_announcement_sender' }}
+              placeholder={localLL.form.fields.announcementSender.placeholder()}
             />
           </div>
         </div>
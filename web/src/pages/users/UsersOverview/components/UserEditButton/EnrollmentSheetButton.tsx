@@ -0,0 +1,42 @@
+import { useMutation } from '@tanstack/react-query';
+import saveAs from 'file-saver';
+
+import { useI18nContext } from '../../../../../i18n/i18n-react';
+import { EditButtonOption } from '../../../../../shared/defguard-ui/components/Layout/EditButton/EditButtonOption';
+import useApi from '../../../../../shared/hooks/useApi';
+import { useToaster } from '../../../../../shared/hooks/useToaster';
+import { MutationKeys } from '../../../../../shared/mutations';
+import type { User } from '../../../../../shared/types';
+
+type Props = {
+  user: User;
+};
+
+export const EnrollmentSheetButton = ({ user }: Props) => {
+  const { LL } = useI18nContext();
+  const toaster = useToaster();
+
+  const {
+    user: { downloadEnrollmentSheet },
+  } = useApi();
+
+  const { mutate } = useMutation({
+    mutationFn: downloadEnrollmentSheet,
+    mutationKey: [MutationKeys.DOWNLOAD_ENROLLMENT_SHEET],
+    onSuccess: (blob) => {
+      saveAs(blob, `enrollment_${user.username}.pdf`);
+    },
+    onError: (e) => {
+      toaster.error(LL.messages.error());
+      console.error(e);
+    },
+  });
+
+  return (
+    <EditButtonOption
+      key="enrollment-sheet"
+      text={LL.usersOverview.list.editButton.enrollmentSheet()}
+      onClick={() => mutate(user.username)}
+    />
+  );
+};
@@ -12,6 +12,7 @@ import type { User } from '../../../../../shared/types';
 import { useAddAuthorizationKeyModal } from '../../../shared/modals/AddAuthenticationKeyModal/useAddAuthorizationKeyModal';
 import { useDisableMfaModal } from '../../../shared/modals/DisableMfaModal/store';
 import { useAddUserModal } from '../../modals/AddUserModal/hooks/useAddUserModal';
+import { EnrollmentSheetButton } from './EnrollmentSheetButton';
 import { ResetPasswordButton } from './ResetPasswordButton';
 
 type Props = {
@@ -118,6 +119,7 @@ export const UserEditButton = ({ user }: Props) => {
           }
         />
       )}
+      {!user.enrolled && user.is_active && <EnrollmentSheetButton user={user} />}
       {user.username !== currentUser?.username && (
         <EditButtonOption
           key="toggle-user"
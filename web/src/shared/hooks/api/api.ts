@@ -10,6 +10,7 @@ import type {
   ChangeOpenidClientStateRequest,
   ChangePasswordRequest,
   changeWebhookStateRequest,
+  DashboardSummary,
   Device,
   EditOpenidClientRequest,
   EmptyApiResponse,
@@ -46,6 +47,9 @@ const unpackRequest = <T>(res: AxiosResponse<T>): T => res.data;
 export const buildApi = (client: Axios): Api => {
   const getOutdatedInfo = () => client.get(`/outdated`).then(unpackRequest);
 
+  const getDashboard: Api['getDashboard'] = () =>
+    client.get<DashboardSummary>(`/dashboard`).then(unpackRequest);
+
   const addUser = (data: AddUserRequest) =>
     client.post<User>(`/user`, data).then(unpackRequest);
 
@@ -148,6 +152,11 @@ export const buildApi = (client: Axios): Api => {
       .post<StartEnrollmentResponse>(`/user/${username}/start_enrollment`, rest)
       .then((response) => response.data);
 
+  const downloadEnrollmentSheet: Api['user']['downloadEnrollmentSheet'] = (username) =>
+    client
+      .post<Blob>(`/user/${username}/enrollment_sheet`, {}, { responseType: 'blob' })
+      .then((response) => response.data);
+
   const getGroups = () => client.get<GroupsResponse>('/group').then(unpackRequest);
 
   const addToGroup = ({ group, ...rest }: UserGroupRequest) =>
@@ -547,6 +556,7 @@ export const buildApi = (client: Axios): Api => {
 
   return {
     getOutdatedInfo,
+    getDashboard,
     getAppInfo,
     getNewVersion,
     changePasswordSelf,
@@ -614,6 +624,7 @@ export const buildApi = (client: Axios): Api => {
       addToGroup,
       removeFromGroup,
       startEnrollment,
+      downloadEnrollmentSheet,
       startDesktopActivation,
       getAuthenticationKeysInfo,
       addAuthenticationKey,
@@ -9,6 +9,7 @@ export const MutationKeys = {
   CREATE_WORKER_JOB: 'CREATE_WORKER_JOB',
   CHANGE_PASSWORD: 'CHANGE_PASSWORD',
   RESET_PASSWORD: 'RESET_PASSWORD',
+  DOWNLOAD_ENROLLMENT_SHEET: 'DOWNLOAD_ENROLLMENT_SHEET',
   ADD_USER_TO_GROUP: 'ADD_USER_TO_GROUP',
   ADD_DEVICE: 'ADD_DEVICE',
   REMOVE_USER_FROM_GROUP: 'REMOVE_USER_FROM_GROUP',
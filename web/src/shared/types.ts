@@ -69,6 +69,7 @@ export type User = {
   enrolled: boolean;
   is_admin: boolean;
   ldap_pass_requires_change: boolean;
+  mail_language?: string;
 };
 
 export type UserProfile = {
@@ -145,6 +146,17 @@ export enum ServiceLocationMode {
   ALWAYSON = 'alwayson',
 }
 
+export enum IpAllocationStrategy {
+  SEQUENTIAL = 'sequential',
+  RANDOM = 'random',
+  STICKY_BY_USER = 'sticky_by_user',
+}
+
+export enum DeviceNameUniqueness {
+  USER = 'user',
+  LOCATION = 'location',
+}
+
 export interface Network {
   id: number;
   name: string;
@@ -163,6 +175,18 @@ export interface Network {
   acl_default_allow: boolean;
   location_mfa_mode: LocationMfaMode;
   service_location_mode: ServiceLocationMode;
+  min_desktop_client_version?: string;
+  min_mobile_client_version?: string;
+  device_approval_required?: boolean;
+  ip_allocation_strategy?: IpAllocationStrategy;
+  client_traffic_policy?: ClientTrafficPolicy | null;
+  mfa_session_lifetime_hours?: number;
+  mfa_remember_device_hours?: number;
+  preshared_keys_enabled?: boolean;
+  preshared_key_rotation_days?: number;
+  device_name_pattern?: string;
+  device_name_prefix?: string;
+  device_name_uniqueness?: DeviceNameUniqueness;
 }
 
 export type ModifyNetworkRequest = {
@@ -282,6 +306,7 @@ export interface StartEnrollmentRequest {
   username: string;
   send_enrollment_notification: boolean;
   email?: string;
+  location_ids?: number[];
 }
 
 export interface StartEnrollmentResponse {
@@ -460,6 +485,9 @@ export type ModifyGroupsRequest = {
   // array of usernames
   members?: string[];
   is_admin: boolean;
+  is_helpdesk: boolean;
+  is_auditor: boolean;
+  client_traffic_policy?: ClientTrafficPolicy | null;
 };
 
 export type AddUsersToGroupsRequest = {
@@ -563,6 +591,24 @@ export type PaginatedResponse<T> = {
 
 export type AllGateWaysResponse = Record<string, Array<GatewayStatus>>;
 
+export type LocationTrafficStats = {
+  id: number;
+  name: string;
+  active_devices: number;
+  upload: number;
+  download: number;
+};
+
+export type DashboardSummary = {
+  // last 24 hours
+  stats: WireguardNetworkStats;
+  top_locations: LocationTrafficStats[];
+  gateways: AllGateWaysResponse;
+  incompatible_components: OutdatedComponents;
+  recent_events: ActivityLogEvent[];
+  license_info?: EnterpriseInfo;
+};
+
 export type ActivityLogFilters = {
   // Naive UTC datetime in string
   from?: string;
@@ -633,6 +679,7 @@ export type ActivityLogStreamCreateRequest = Omit<ActivityLogStreamModifyRequest
 
 export type Api = {
   getOutdatedInfo: () => Promise<OutdatedComponents>;
+  getDashboard: () => Promise<DashboardSummary>;
   getAppInfo: () => Promise<AppInfo>;
   getNewVersion: () => Promise<UpdateInfo | null>;
   changePasswordSelf: (data: ChangePasswordSelfRequest) => Promise<EmptyApiResponse>;
@@ -685,6 +732,7 @@ export type Api = {
     getMe: () => Promise<User>;
     addUser: (data: AddUserRequest) => Promise<User>;
     startEnrollment: (data: StartEnrollmentRequest) => Promise<StartEnrollmentResponse>;
+    downloadEnrollmentSheet: (username: string) => Promise<Blob>;
     getUser: (username: string) => Promise<UserProfile>;
     getUsers: () => Promise<User[]>;
     editUser: (data: UserEditRequest) => Promise<User>;
@@ -1059,6 +1107,12 @@ export type SettingsEnrollment = {
   enrollment_welcome_email: string;
   enrollment_welcome_email_subject: string;
   enrollment_use_welcome_message_as_email: boolean;
+  self_registration_enabled: boolean;
+  self_registration_domains: string[];
+  password_reset_token_lifetime?: number;
+  password_reset_max_uses: number;
+  auditor_redact_emails: boolean;
+  auditor_redact_ip_addresses: boolean;
 };
 
 export type SettingsSMTP = {
@@ -1068,8 +1122,25 @@ export type SettingsSMTP = {
   smtp_user?: string;
   smtp_password?: string;
   smtp_sender?: string;
+  smtp_auth_method: SmtpAuthMethod;
+  smtp_oauth2_token_url?: string;
+  smtp_oauth2_client_id?: string;
+  smtp_oauth2_client_secret?: string;
+  smtp_oauth2_scope?: string;
+  smtp_sender_name?: string;
+  smtp_reply_to?: string;
+  smtp_security_sender?: string;
+  smtp_announcement_sender?: string;
+  mail_backend: MailBackend;
+  mail_http_url?: string;
+  mail_http_token?: string;
+  default_mail_language: string;
 };
 
+export type SmtpAuthMethod = 'password' | 'oauth2';
+
+export type MailBackend = 'smtp' | 'http';
+
 export type SettingsModules = {
   openid_enabled: boolean;
   wireguard_enabled: boolean;
@@ -1119,6 +1190,8 @@ export type SettingsGatewayNotifications = {
   gateway_disconnect_notifications_enabled: boolean;
   gateway_disconnect_notifications_inactivity_threshold: number;
   gateway_disconnect_notifications_reconnect_notification_enabled: boolean;
+  security_summary_enabled: boolean;
+  notification_digest_window: number;
 };
 
 export enum ClientTrafficPolicy {
@@ -1171,6 +1244,9 @@ export interface OpenidClient {
   redirect_uri: string[];
   scope: string[];
   enabled: boolean;
+  claim_mapping: Record<string, string>;
+  group_filter: string[];
+  static_claims: Record<string, unknown>;
 }
 
 export interface OpenIdInfo {
@@ -1226,6 +1302,9 @@ export interface AddOpenidClientRequest {
   redirect_uri: string[];
   enabled: boolean;
   scope: string[];
+  claim_mapping: Record<string, string>;
+  group_filter: string[];
+  static_claims: Record<string, unknown>;
 }
 
 export interface changeWebhookStateRequest {
@@ -1345,6 +1424,9 @@ export type GroupInfo = {
   members: string[];
   vpn_locations: string[];
   is_admin: boolean;
+  is_helpdesk: boolean;
+  is_auditor: boolean;
+  client_traffic_policy?: ClientTrafficPolicy | null;
 };
 
 export type DirsyncTestResponse = {